@@ -132,7 +132,8 @@ private data class PairingPayload(
     val port: Int,
     val addresses: List<String>,
     val wsPath: String,
-    val relayUrl: String?
+    val relayUrl: String?,
+    val e2eSecret: String?
 )
 
 private data class SessionSummary(
@@ -211,6 +212,10 @@ private class MobileGatewayStore(private val context: Context) {
     private val mainHandler = Handler(Looper.getMainLooper())
     private val okHttp = OkHttpClient()
     private var webSocket: WebSocket? = null
+    @Volatile
+    private var relayCipher: RelayCipher? = null
+    /** Secret of [relayCipher]; the cipher is reused on reconnect to keep its replay counters. */
+    private var relayCipherSecret: String? = null
 
     var isPaired by mutableStateOf(PairingPrefs.isPaired(context))
     var pairingPayload by mutableStateOf(PairingPrefs.getPayload(context))
@@ -260,7 +265,23 @@ private class MobileGatewayStore(private val context: Context) {
             isPaired = false
             return
         }
+        val previousCipher = relayCipher.takeIf { relayCipherSecret == parsed.e2eSecret }
+        relayCipher = null
+        relayCipherSecret = null
         if (!parsed.relayUrl.isNullOrBlank()) {
+            if (!parsed.e2eSecret.isNullOrBlank()) {
+                if (!RelayCipher.isSupported) {
+                    connectionStatus = "Unsupported"
+                    errorMessage = "End-to-end encryption requires Android 9 or later"
+                    return
+                }
+                relayCipher = previousCipher ?: RelayCipher.fromSecret(parsed.e2eSecret) ?: run {
+                    connectionStatus = "Invalid payload"
+                    errorMessage = "Invalid end-to-end encryption key"
+                    return
+                }
+                relayCipherSecret = parsed.e2eSecret
+            }
             val url = ensureClientParam(parsed.relayUrl, "mobile")
             connectionStatus = "Connecting"
             val request = Request.Builder()
@@ -333,7 +354,7 @@ private class MobileGatewayStore(private val context: Context) {
         val payload = JSONObject()
             .put("type", "command")
             .put("data", JSONObject().put("task", trimmed).put("session_id", sessionId))
-        webSocket?.send(payload.toString())
+        sendJson(payload)
     }
 
     fun requestSessionCreate(title: String? = null) {
@@ -364,7 +385,7 @@ private class MobileGatewayStore(private val context: Context) {
         val payload = JSONObject()
             .put("type", "list_files")
             .put("data", data)
-        webSocket?.send(payload.toString())
+        sendJson(payload)
     }
 
     fun requestFileContent(path: String) {
@@ -373,14 +394,14 @@ private class MobileGatewayStore(private val context: Context) {
         val payload = JSONObject()
             .put("type", "read_file")
             .put("data", JSONObject().put("path", path))
-        webSocket?.send(payload.toString())
+        sendJson(payload)
     }
 
     private fun sendPair(token: String) {
         val payload = JSONObject()
             .put("type", "pair")
             .put("data", JSONObject().put("token", token).put("device_name", "Android"))
-        webSocket?.send(payload.toString())
+        sendJson(payload)
     }
 
     private fun sendSessionCreate(title: String?) {
@@ -391,7 +412,7 @@ private class MobileGatewayStore(private val context: Context) {
         val payload = JSONObject()
             .put("type", "session_create")
             .put("data", data)
-        webSocket?.send(payload.toString())
+        sendJson(payload)
     }
 
     private fun ensureConnected(): Boolean {
@@ -409,9 +430,46 @@ private class MobileGatewayStore(private val context: Context) {
         return false
     }
 
+    private fun sendJson(payload: JSONObject) {
+        val cipher = relayCipher
+        val text = if (cipher != null) {
+            try {
+                cipher.seal(payload.toString())
+            } catch (e: Exception) {
+                postError("Failed to encrypt message: ${e.message}")
+                return
+            }
+        } else {
+            payload.toString()
+        }
+        webSocket?.send(text)
+    }
+
+    /** Decrypts relayed frames; unencrypted ones may only be relay notices. */
+    private fun openRelayFrame(json: JSONObject, cipher: RelayCipher): JSONObject? {
+        val type = json.optString("type")
+        if (type != "e2e") {
+            return if (type == "paired" || type == "error") json else null
+        }
+        return try {
+            val inner = JSONObject(cipher.open(json.optJSONObject("data") ?: JSONObject()))
+            if (inner.optString("type") == "e2e") null else inner
+        } catch (e: RelayCipher.KeyMismatchException) {
+            errorMessage = e.message
+            null
+        } catch (_: RelayCipher.ReplayedException) {
+            null
+        } catch (_: Exception) {
+            errorMessage = "Received an encrypted message that could not be decrypted"
+            null
+        }
+    }
+
     private fun handleIncoming(text: String) {
         try {
-            val json = JSONObject(text)
+            val raw = JSONObject(text)
+            val cipher = relayCipher
+            val json = if (cipher != null) openRelayFrame(raw, cipher) ?: return else raw
             val type = json.optString("type")
             if (type == "agent_event") {
                 val data = json.optJSONObject("data") ?: return
@@ -524,7 +582,8 @@ private class MobileGatewayStore(private val context: Context) {
             }
             val wsPath = json.optString("ws_path", "/ws")
             val relayUrl = json.optString("relay_url").ifBlank { null }
-            PairingPayload(token, port, addresses, wsPath, relayUrl)
+            val e2eSecret = json.optString("e2e").ifBlank { null }
+            PairingPayload(token, port, addresses, wsPath, relayUrl, e2eSecret)
         } catch (_: Exception) {
             null
         }
@@ -637,7 +696,7 @@ private class MobileGatewayStore(private val context: Context) {
         val payload = JSONObject()
             .put("type", "select_workspace")
             .put("data", JSONObject().put("workspace_id", id))
-        webSocket?.send(payload.toString())
+        sendJson(payload)
     }
 
     fun selectAgentProfile(id: String) {
@@ -645,7 +704,7 @@ private class MobileGatewayStore(private val context: Context) {
         val payload = JSONObject()
             .put("type", "select_agent_profile")
             .put("data", JSONObject().put("profile_id", id))
-        webSocket?.send(payload.toString())
+        sendJson(payload)
     }
 
     private fun postStatus(status: String) {
@@ -0,0 +1,120 @@
+package com.luminanote.mobile
+
+import android.os.Build
+import android.util.Base64
+import org.json.JSONObject
+import java.security.SecureRandom
+import javax.crypto.Cipher
+import javax.crypto.Mac
+import javax.crypto.spec.IvParameterSpec
+import javax.crypto.spec.SecretKeySpec
+
+/**
+ * End-to-end encryption of cloud relay frames, for the mobile side.
+ * Must stay in sync with src-tauri/src/relay_e2e.rs.
+ */
+internal class RelayCipher private constructor(
+    private val sealKey: ByteArray,
+    private val openKey: ByteArray,
+    private val keyId: String
+) {
+    class KeyMismatchException :
+        Exception("End-to-end encryption keys do not match; scan the pairing code again")
+
+    class ReplayedException :
+        Exception("Dropped a replayed or out-of-order encrypted message")
+
+    /** Random id of the frames this cipher seals. */
+    private val streamId = ByteArray(STREAM_ID_LENGTH).also { random.nextBytes(it) }.toHex()
+    private var sentSeq = 0L
+    private var peerStreamId: String? = null
+    private var peerSeq = 0L
+    private val retiredPeerStreams = mutableSetOf<String>()
+
+    /** Wraps a JSON message in an `e2e` envelope. */
+    @Synchronized
+    fun seal(text: String): String {
+        val seq = ++sentSeq
+        val nonce = ByteArray(NONCE_LENGTH).also { random.nextBytes(it) }
+        val cipher = Cipher.getInstance(TRANSFORMATION)
+        cipher.init(Cipher.ENCRYPT_MODE, SecretKeySpec(sealKey, "ChaCha20"), IvParameterSpec(nonce))
+        cipher.updateAAD(aad(ROLE, streamId, seq))
+        val sealed = cipher.doFinal(text.toByteArray(Charsets.UTF_8))
+        val data = JSONObject()
+            .put("kid", keyId)
+            .put("sid", streamId)
+            .put("seq", seq)
+            .put("nonce", Base64.encodeToString(nonce, Base64.NO_WRAP))
+            .put("ciphertext", Base64.encodeToString(sealed, Base64.NO_WRAP))
+        return JSONObject().put("type", "e2e").put("data", data).toString()
+    }
+
+    /** Decrypts the `data` object of an `e2e` envelope, refusing replayed frames. */
+    @Synchronized
+    fun open(data: JSONObject): String {
+        if (data.optString("kid") != keyId) throw KeyMismatchException()
+        val sid = data.getString("sid")
+        val seq = data.getLong("seq")
+        val nonce = Base64.decode(data.getString("nonce"), Base64.DEFAULT)
+        val sealed = Base64.decode(data.getString("ciphertext"), Base64.DEFAULT)
+        val cipher = Cipher.getInstance(TRANSFORMATION)
+        cipher.init(Cipher.DECRYPT_MODE, SecretKeySpec(openKey, "ChaCha20"), IvParameterSpec(nonce))
+        cipher.updateAAD(aad(PEER_ROLE, sid, seq))
+        val text = String(cipher.doFinal(sealed), Charsets.UTF_8)
+        if (sid == peerStreamId) {
+            if (seq <= peerSeq) throw ReplayedException()
+        } else {
+            if (sid in retiredPeerStreams) throw ReplayedException()
+            peerStreamId?.let { retiredPeerStreams.add(it) }
+            peerStreamId = sid
+        }
+        peerSeq = seq
+        return text
+    }
+
+    companion object {
+        private const val TRANSFORMATION = "ChaCha20/Poly1305/NoPadding"
+        private const val NONCE_LENGTH = 12
+        private const val STREAM_ID_LENGTH = 8
+        private const val ROLE = "mobile"
+        private const val PEER_ROLE = "desktop"
+        private val SALT = "lumina-relay-e2e".toByteArray(Charsets.UTF_8)
+        private val KEY_ID_INFO = "v2 key id".toByteArray(Charsets.UTF_8)
+        private const val AAD_PREFIX = "lumina-relay-e2e-v2"
+        private val random = SecureRandom()
+
+        /** HKDF info of the key that frames sent by [role] are sealed with. */
+        private fun keyInfo(role: String) = "v2 $role chacha20poly1305 key".toByteArray(Charsets.UTF_8)
+
+        /** Binds the direction, stream and counter of a frame sent by [sender]. */
+        private fun aad(sender: String, streamId: String, seq: Long) =
+            "$AAD_PREFIX|$sender|$streamId|$seq".toByteArray(Charsets.UTF_8)
+
+        private fun ByteArray.toHex() = joinToString("") { "%02x".format(it.toInt() and 0xff) }
+
+        /** ChaCha20-Poly1305 is only available from Android 9. */
+        val isSupported: Boolean
+            get() = Build.VERSION.SDK_INT >= Build.VERSION_CODES.P
+
+        fun fromSecret(secret: String): RelayCipher? {
+            val ikm = try {
+                Base64.decode(secret.trim(), Base64.DEFAULT)
+            } catch (_: IllegalArgumentException) {
+                return null
+            }
+            if (ikm.size != 32) return null
+            // HKDF-SHA256; each output fits in the first expand block
+            val prk = hmac(SALT, ikm)
+            val sealKey = hmac(prk, keyInfo(ROLE) + 1.toByte()).copyOf(32)
+            val openKey = hmac(prk, keyInfo(PEER_ROLE) + 1.toByte()).copyOf(32)
+            val keyId = hmac(prk, KEY_ID_INFO + 1.toByte()).copyOf(8).toHex()
+            return RelayCipher(sealKey, openKey, keyId)
+        }
+
+        private fun hmac(key: ByteArray, data: ByteArray): ByteArray {
+            val mac = Mac.getInstance("HmacSHA256")
+            mac.init(SecretKeySpec(key, "HmacSHA256"))
+            return mac.doFinal(data)
+        }
+    }
+}
@@ -7,6 +7,8 @@ struct PairingPayload: Codable {
     let addresses: [String]
     let ws_path: String
     let relay_url: String?
+    /// End-to-end encryption secret of a cloud relay pairing
+    let e2e: String?
 
     private enum CodingKeys: String, CodingKey {
         case v
@@ -15,15 +17,17 @@ struct PairingPayload: Codable {
         case addresses
         case ws_path
         case relay_url
+        case e2e
     }
 
-    init(v: Int?, token: String, port: Int, addresses: [String], ws_path: String, relay_url: String?) {
+    init(v: Int?, token: String, port: Int, addresses: [String], ws_path: String, relay_url: String?, e2e: String? = nil) {
         self.v = v
         self.token = token
         self.port = port
         self.addresses = addresses
         self.ws_path = ws_path
         self.relay_url = relay_url
+        self.e2e = e2e
     }
 
     init(from decoder: Decoder) throws {
@@ -34,6 +38,7 @@ struct PairingPayload: Codable {
         addresses = (try? container.decode([String].self, forKey: .addresses)) ?? []
         ws_path = (try? container.decode(String.self, forKey: .ws_path)) ?? "/ws"
         relay_url = try? container.decode(String.self, forKey: .relay_url)
+        e2e = try? container.decode(String.self, forKey: .e2e)
     }
 }
 
@@ -55,6 +60,9 @@ final class MobileGatewayStore: ObservableObject {
     @Published var isLoadingContent: Bool = false
 
     private var webSocketTask: URLSessionWebSocketTask?
+    private var relayCipher: RelayCipher?
+    /// Secret of `relayCipher`; the cipher is reused on reconnect to keep its replay counters
+    private var relayCipherSecret: String?
     private var lastSessionId: String?
     private var pendingSessionCreateTitle: String?
 
@@ -91,7 +99,19 @@ final class MobileGatewayStore: ObservableObject {
             isPaired = false
             return
         }
+        let previousCipher = relayCipherSecret == payload.e2e ? relayCipher : nil
+        relayCipher = nil
+        relayCipherSecret = nil
         if let relayUrl = payload.relay_url, !relayUrl.isEmpty {
+            if let secret = payload.e2e, !secret.isEmpty {
+                guard let cipher = previousCipher ?? RelayCipher(secret: secret) else {
+                    connectionStatus = "Invalid payload"
+                    errorMessage = "Invalid end-to-end encryption key"
+                    return
+                }
+                relayCipher = cipher
+                relayCipherSecret = secret
+            }
             let urlString = ensureClientParam(relayUrl, client: "mobile")
             guard let url = URL(string: urlString) else {
                 connectionStatus = "Invalid URL"
@@ -239,7 +259,14 @@ final class MobileGatewayStore: ObservableObject {
 
     private func sendJSON(_ payload: [String: Any]) {
         guard let data = try? JSONSerialization.data(withJSONObject: payload, options: []),
-              let text = String(data: data, encoding: .utf8) else { return }
+              var text = String(data: data, encoding: .utf8) else { return }
+        if let relayCipher {
+            guard let sealed = relayCipher.seal(text) else {
+                errorMessage = "Failed to encrypt message"
+                return
+            }
+            text = sealed
+        }
         webSocketTask?.send(.string(text)) { error in
             if let error {
                 DispatchQueue.main.async {
@@ -278,10 +305,38 @@ final class MobileGatewayStore: ObservableObject {
         }
     }
 
+    /// Decrypts relayed frames; unencrypted ones may only be relay notices.
+    private func openRelayFrame(_ json: [String: Any], cipher: RelayCipher) -> [String: Any]? {
+        let type = json["type"] as? String
+        guard type == "e2e" else {
+            return type == "paired" || type == "error" ? json : nil
+        }
+        do {
+            let plain = try cipher.open(json["data"] as? [String: Any] ?? [:])
+            guard let inner = try? JSONSerialization.jsonObject(with: Data(plain.utf8)) as? [String: Any],
+                  inner["type"] as? String != "e2e" else {
+                return nil
+            }
+            return inner
+        } catch RelayCipher.OpenError.keyMismatch {
+            errorMessage = "End-to-end encryption keys do not match; scan the pairing code again"
+        } catch RelayCipher.OpenError.replayed {
+            return nil
+        } catch {
+            errorMessage = "Received an encrypted message that could not be decrypted"
+        }
+        return nil
+    }
+
     private func handleIncoming(_ text: String) {
-        guard let json = try? JSONSerialization.jsonObject(with: Data(text.utf8)) as? [String: Any] else {
+        guard let raw = try? JSONSerialization.jsonObject(with: Data(text.utf8)) as? [String: Any] else {
             return
         }
+        var json = raw
+        if let relayCipher {
+            guard let opened = openRelayFrame(raw, cipher: relayCipher) else { return }
+            json = opened
+        }
         guard let type = json["type"] as? String else { return }
 
         if type == "agent_event", let data = json["data"] as? [String: Any] {
@@ -0,0 +1,119 @@
+import CryptoKit
+import Foundation
+
+/// End-to-end encryption of cloud relay frames, for the mobile side.
+/// Must stay in sync with `src-tauri/src/relay_e2e.rs`.
+final class RelayCipher {
+    enum OpenError: Error {
+        case keyMismatch
+        case corrupt
+        case replayed
+    }
+
+    private static let role = "mobile"
+    private static let peerRole = "desktop"
+    private static let salt = Data("lumina-relay-e2e".utf8)
+    private static let keyIdInfo = Data("v2 key id".utf8)
+    private static let aadPrefix = "lumina-relay-e2e-v2"
+    private static let tagLength = 16
+
+    private let sealKey: SymmetricKey
+    private let openKey: SymmetricKey
+    private let keyId: String
+    /// Random id of the frames this cipher seals
+    private let streamId: String
+    private var sentSeq: UInt64 = 0
+    private var peerStreamId: String?
+    private var peerSeq: UInt64 = 0
+    /// Peer streams that were replaced; their frames are never accepted again
+    private var retiredPeerStreams = Set<String>()
+
+    init?(secret: String) {
+        guard let ikm = Data(base64Encoded: secret.trimmingCharacters(in: .whitespacesAndNewlines)),
+              ikm.count == 32 else { return nil }
+        let material = SymmetricKey(data: ikm)
+        sealKey = Self.deriveKey(material, info: Self.keyInfo(Self.role), length: 32)
+        openKey = Self.deriveKey(material, info: Self.keyInfo(Self.peerRole), length: 32)
+        keyId = Self.hex(Self.deriveKey(material, info: Self.keyIdInfo, length: 8).withUnsafeBytes { Data($0) })
+        streamId = Self.hex(Data((0..<8).map { _ in UInt8.random(in: .min ... .max) }))
+    }
+
+    /// HKDF info of the key that frames sent by `role` are sealed with
+    private static func keyInfo(_ role: String) -> Data {
+        Data("v2 \(role) chacha20poly1305 key".utf8)
+    }
+
+    private static func deriveKey(_ material: SymmetricKey, info: Data, length: Int) -> SymmetricKey {
+        HKDF<SHA256>.deriveKey(inputKeyMaterial: material, salt: salt, info: info, outputByteCount: length)
+    }
+
+    /// Binds the direction, stream and counter of a frame sent by `sender`
+    private static func aad(sender: String, streamId: String, seq: UInt64) -> Data {
+        Data("\(aadPrefix)|\(sender)|\(streamId)|\(seq)".utf8)
+    }
+
+    private static func hex(_ data: Data) -> String {
+        data.map { String(format: "%02x", $0) }.joined()
+    }
+
+    /// Wraps a JSON message in an `e2e` envelope
+    func seal(_ text: String) -> String? {
+        sentSeq += 1
+        let aad = Self.aad(sender: Self.role, streamId: streamId, seq: sentSeq)
+        guard let box = try? ChaChaPoly.seal(Data(text.utf8), using: sealKey, authenticating: aad) else {
+            return nil
+        }
+        let envelope: [String: Any] = [
+            "type": "e2e",
+            "data": [
+                "kid": keyId,
+                "sid": streamId,
+                "seq": sentSeq,
+                "nonce": Data(box.nonce).base64EncodedString(),
+                "ciphertext": (box.ciphertext + box.tag).base64EncodedString()
+            ]
+        ]
+        guard let data = try? JSONSerialization.data(withJSONObject: envelope, options: []) else {
+            return nil
+        }
+        return String(data: data, encoding: .utf8)
+    }
+
+    /// Decrypts the `data` object of an `e2e` envelope, refusing replayed frames
+    func open(_ data: [String: Any]) throws -> String {
+        guard let kid = data["kid"] as? String,
+              let sid = data["sid"] as? String,
+              let seqNumber = data["seq"] as? NSNumber,
+              let nonceText = data["nonce"] as? String,
+              let cipherText = data["ciphertext"] as? String else {
+            throw OpenError.corrupt
+        }
+        guard kid == keyId else { throw OpenError.keyMismatch }
+        let seq = seqNumber.uint64Value
+        let aad = Self.aad(sender: Self.peerRole, streamId: sid, seq: seq)
+        guard let nonceData = Data(base64Encoded: nonceText),
+              let combined = Data(base64Encoded: cipherText),
+              combined.count >= Self.tagLength,
+              let nonce = try? ChaChaPoly.Nonce(data: nonceData),
+              let box = try? ChaChaPoly.SealedBox(
+                  nonce: nonce,
+                  ciphertext: combined.prefix(combined.count - Self.tagLength),
+                  tag: combined.suffix(Self.tagLength)
+              ),
+              let plain = try? ChaChaPoly.open(box, using: openKey, authenticating: aad),
+              let text = String(data: plain, encoding: .utf8) else {
+            throw OpenError.corrupt
+        }
+        if sid == peerStreamId {
+            guard seq > peerSeq else { throw OpenError.replayed }
+        } else {
+            guard !retiredPeerStreams.contains(sid) else { throw OpenError.replayed }
+            if let previous = peerStreamId {
+                retiredPeerStreams.insert(previous)
+            }
+            peerStreamId = sid
+        }
+        peerSeq = seq
+        return text
+    }
+}
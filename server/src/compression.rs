@@ -0,0 +1,281 @@
+//! Response compression for DAV reads
+//!
+//! Markdown and PROPFIND XML shrink several times under gzip, which matters
+//! most over the mobile relay. Only textual bodies between
+//! [`MIN_COMPRESS_BYTES`] and [`MAX_COMPRESS_BYTES`] are compressed; images,
+//! archives and other binary attachments are sent as-is.
+
+use std::io::Write;
+
+use axum::body::Body;
+use axum::http::header::{CONTENT_ENCODING, CONTENT_LENGTH, CONTENT_TYPE, ETAG, VARY};
+use axum::http::{HeaderMap, HeaderValue, Response};
+use flate2::write::{GzEncoder, ZlibEncoder};
+use flate2::Compression;
+use hyper::body::HttpBody;
+
+use crate::error::AppError;
+
+/// Below this the headers cost more than compression saves
+pub const MIN_COMPRESS_BYTES: u64 = 1024;
+/// Bodies are buffered to compress them, so large files are streamed as-is
+pub const MAX_COMPRESS_BYTES: u64 = 16 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Gzip,
+    Deflate,
+}
+
+impl Encoding {
+    fn as_str(self) -> &'static str {
+        match self {
+            Encoding::Gzip => "gzip",
+            Encoding::Deflate => "deflate",
+        }
+    }
+}
+
+/// Picks an encoding from `Accept-Encoding`, preferring gzip on equal weight
+pub fn negotiate(headers: &HeaderMap) -> Option<Encoding> {
+    let mut best: Option<(Encoding, f32)> = None;
+    let mut wildcard = None;
+    let mut refused = Vec::new();
+    for value in headers.get_all("accept-encoding") {
+        let Ok(value) = value.to_str() else {
+            continue;
+        };
+        for item in value.split(',') {
+            let mut parts = item.split(';');
+            let coding = parts.next().unwrap_or("").trim().to_ascii_lowercase();
+            let quality = parts
+                .filter_map(|param| param.trim().strip_prefix("q="))
+                .find_map(|q| q.trim().parse::<f32>().ok())
+                .unwrap_or(1.0);
+            let encoding = match coding.as_str() {
+                "gzip" | "x-gzip" => Encoding::Gzip,
+                "deflate" => Encoding::Deflate,
+                "*" => {
+                    wildcard = Some(quality);
+                    continue;
+                }
+                _ => continue,
+            };
+            if quality <= 0.0 {
+                refused.push(encoding);
+            } else if best.is_none_or(|(_, q)| quality > q) {
+                best = Some((encoding, quality));
+            }
+        }
+    }
+    best.map(|(encoding, _)| encoding).or_else(|| {
+        wildcard.filter(|q| *q > 0.0).and_then(|_| {
+            [Encoding::Gzip, Encoding::Deflate]
+                .into_iter()
+                .find(|encoding| !refused.contains(encoding))
+        })
+    })
+}
+
+/// Textual content types worth compressing
+pub fn is_compressible(content_type: &str) -> bool {
+    let essence = content_type
+        .split(';')
+        .next()
+        .unwrap_or("")
+        .trim()
+        .to_ascii_lowercase();
+    essence.starts_with("text/")
+        || essence.ends_with("+xml")
+        || essence.ends_with("+json")
+        || matches!(
+            essence.as_str(),
+            "application/xml"
+                | "application/json"
+                | "application/javascript"
+                | "application/x-javascript"
+                | "application/toml"
+                | "application/yaml"
+                | "application/x-yaml"
+        )
+}
+
+/// Compresses a successful response body when the client accepts it and the
+/// content is textual and within the size limits
+pub async fn compress_response(
+    response: Response<Body>,
+    encoding: Option<Encoding>,
+) -> Result<Response<Body>, AppError> {
+    if !response.status().is_success() || response.headers().contains_key(CONTENT_ENCODING) {
+        return Ok(response);
+    }
+    let compressible = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(is_compressible);
+    if !compressible {
+        return Ok(response);
+    }
+
+    let (mut parts, body) = response.into_parts();
+    // The representation now depends on the request, so caches must key on it
+    parts
+        .headers
+        .append(VARY, HeaderValue::from_static("accept-encoding"));
+
+    let length = parts
+        .headers
+        .get(CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<u64>().ok())
+        .or_else(|| body.size_hint().exact());
+    let (Some(encoding), Some(length)) = (encoding, length) else {
+        return Ok(Response::from_parts(parts, body));
+    };
+    if !(MIN_COMPRESS_BYTES..=MAX_COMPRESS_BYTES).contains(&length) {
+        return Ok(Response::from_parts(parts, body));
+    }
+
+    let bytes = hyper::body::to_bytes(body)
+        .await
+        .map_err(|e| AppError::Internal(format!("read response body: {}", e)))?;
+    let compressed = tokio::task::spawn_blocking(move || encode(&bytes, encoding))
+        .await
+        .map_err(|e| AppError::Internal(format!("compress task: {}", e)))?
+        .map_err(|e| AppError::Internal(format!("compress body: {}", e)))?;
+
+    parts.headers.insert(
+        CONTENT_ENCODING,
+        HeaderValue::from_static(encoding.as_str()),
+    );
+    parts
+        .headers
+        .insert(CONTENT_LENGTH, HeaderValue::from(compressed.len()));
+    // A strong ETag identifies the exact bytes, which compression changes
+    if let Some(etag) = parts.headers.get(ETAG).and_then(|v| v.to_str().ok()) {
+        if !etag.starts_with("W/") {
+            if let Ok(weak) = HeaderValue::from_str(&format!("W/{}", etag)) {
+                parts.headers.insert(ETAG, weak);
+            }
+        }
+    }
+    Ok(Response::from_parts(parts, Body::from(compressed)))
+}
+
+fn encode(bytes: &[u8], encoding: Encoding) -> std::io::Result<Vec<u8>> {
+    match encoding {
+        Encoding::Gzip => {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(bytes)?;
+            encoder.finish()
+        }
+        Encoding::Deflate => {
+            // HTTP "deflate" is the zlib format, not raw deflate
+            let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(bytes)?;
+            encoder.finish()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::{GzDecoder, ZlibDecoder};
+    use std::io::Read;
+
+    fn accept(value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert("accept-encoding", HeaderValue::from_str(value).unwrap());
+        headers
+    }
+
+    fn text_response(body: String, content_type: &str) -> Response<Body> {
+        Response::builder()
+            .header(CONTENT_TYPE, content_type)
+            .header(CONTENT_LENGTH, body.len())
+            .header(ETAG, "\"12-34\"")
+            .body(Body::from(body))
+            .unwrap()
+    }
+
+    #[test]
+    fn negotiates_by_quality() {
+        assert_eq!(
+            negotiate(&accept("gzip, deflate, br")),
+            Some(Encoding::Gzip)
+        );
+        assert_eq!(
+            negotiate(&accept("gzip;q=0.5, deflate")),
+            Some(Encoding::Deflate)
+        );
+        assert_eq!(negotiate(&accept("gzip;q=0, *")), Some(Encoding::Deflate));
+        assert_eq!(negotiate(&accept("br, identity")), None);
+        assert_eq!(negotiate(&HeaderMap::new()), None);
+    }
+
+    #[tokio::test]
+    async fn compresses_large_text_bodies() {
+        let text = "# Note\n\nSome markdown line.\n".repeat(200);
+        let response = compress_response(
+            text_response(text.clone(), "text/markdown"),
+            Some(Encoding::Gzip),
+        )
+        .await
+        .unwrap();
+
+        let headers = response.headers().clone();
+        assert_eq!(headers[CONTENT_ENCODING], "gzip");
+        assert_eq!(headers[VARY], "accept-encoding");
+        assert_eq!(headers[ETAG], "W/\"12-34\"");
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        assert_eq!(headers[CONTENT_LENGTH], body.len().to_string().as_str());
+        let mut decoded = String::new();
+        GzDecoder::new(&body[..])
+            .read_to_string(&mut decoded)
+            .unwrap();
+        assert_eq!(decoded, text);
+    }
+
+    #[tokio::test]
+    async fn deflate_uses_the_zlib_format() {
+        let xml = "<D:response></D:response>".repeat(100);
+        let response = Response::builder()
+            .header(CONTENT_TYPE, "application/xml; charset=utf-8")
+            .body(Body::from(xml.clone()))
+            .unwrap();
+        let response = compress_response(response, Some(Encoding::Deflate))
+            .await
+            .unwrap();
+
+        assert_eq!(response.headers()[CONTENT_ENCODING], "deflate");
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let mut decoded = String::new();
+        ZlibDecoder::new(&body[..])
+            .read_to_string(&mut decoded)
+            .unwrap();
+        assert_eq!(decoded, xml);
+    }
+
+    #[tokio::test]
+    async fn skips_small_and_binary_bodies() {
+        let small = compress_response(
+            text_response("short".to_string(), "text/markdown"),
+            Some(Encoding::Gzip),
+        )
+        .await
+        .unwrap();
+        assert!(!small.headers().contains_key(CONTENT_ENCODING));
+        assert_eq!(small.headers()[ETAG], "\"12-34\"");
+
+        let image = compress_response(
+            text_response("x".repeat(4096), "image/png"),
+            Some(Encoding::Gzip),
+        )
+        .await
+        .unwrap();
+        assert!(!image.headers().contains_key(CONTENT_ENCODING));
+        assert!(!image.headers().contains_key(VARY));
+    }
+}
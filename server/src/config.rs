@@ -1,4 +1,5 @@
 use std::env;
+use std::time::Duration;
 
 #[derive(Clone, Debug)]
 pub struct Config {
@@ -6,6 +7,16 @@ pub struct Config {
     pub db_url: String,
     pub data_dir: String,
     pub jwt_secret: String,
+    /// SQLite pool size; WAL lets these read concurrently, writes still
+    /// take turns
+    pub db_max_connections: u32,
+    /// How long a connection waits for the write lock before failing with
+    /// "database is locked"
+    pub db_busy_timeout: Duration,
+    /// Largest file accepted by a DAV PUT
+    pub max_upload_bytes: u64,
+    /// Bearer token required by `/metrics`; the endpoint is disabled without it
+    pub metrics_token: Option<String>,
 }
 
 impl Config {
@@ -16,12 +27,35 @@ impl Config {
         let data_dir = env::var("LUMINA_DATA_DIR").unwrap_or_else(|_| "data".to_string());
         let jwt_secret =
             env::var("LUMINA_JWT_SECRET").unwrap_or_else(|_| "dev-secret-change-me".to_string());
+        let db_max_connections = env::var("LUMINA_DB_MAX_CONNECTIONS")
+            .ok()
+            .and_then(|value| value.parse::<u32>().ok())
+            .filter(|value| *value > 0)
+            .unwrap_or(8);
+        let db_busy_timeout = env::var("LUMINA_DB_BUSY_TIMEOUT_MS")
+            .ok()
+            .and_then(|value| value.parse::<u64>().ok())
+            .map(Duration::from_millis)
+            .unwrap_or(Duration::from_secs(5));
+        let max_upload_bytes = env::var("LUMINA_MAX_UPLOAD_BYTES")
+            .ok()
+            .and_then(|value| value.parse::<u64>().ok())
+            .filter(|value| *value > 0)
+            .unwrap_or(200 * 1024 * 1024);
+        let metrics_token = env::var("LUMINA_METRICS_TOKEN")
+            .ok()
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty());
 
         Self {
             bind,
             db_url,
             data_dir,
             jwt_secret,
+            db_max_connections,
+            db_busy_timeout,
+            max_upload_bytes,
+            metrics_token,
         }
     }
 }
@@ -1,5 +1,5 @@
 use std::path::{Component, Path, PathBuf};
-use std::time::SystemTime;
+use std::time::{Duration, SystemTime};
 
 use axum::body::Body;
 use axum::extract::{Path as AxumPath, State};
@@ -14,11 +14,26 @@ use urlencoding::encode;
 use uuid::Uuid;
 
 use crate::auth::{decode_token, verify_password};
+use crate::compression;
 use crate::db;
 use crate::error::AppError;
-use crate::state::{AppState, ServerMetrics};
+use crate::state::{AppState, DavLock, DavLocks, MetricsWorkspace, ServerMetrics};
 
-const MAX_DAV_UPLOAD_BYTES: u64 = 200 * 1024 * 1024;
+const MAX_PROPFIND_BODY_BYTES: usize = 64 * 1024;
+const DAV_NAMESPACE: &str = "DAV:";
+const DEFAULT_LOCK_TIMEOUT: Duration = Duration::from_secs(600);
+const MAX_LOCK_TIMEOUT: Duration = Duration::from_secs(3600);
+
+/// Live properties reported for every resource, in response order
+const LIVE_PROPS: [&str; 7] = [
+    "resourcetype",
+    "displayname",
+    "getcontentlength",
+    "getcontenttype",
+    "getlastmodified",
+    "creationdate",
+    "getetag",
+];
 
 pub async fn handle_dav_root(
     State(state): State<AppState>,
@@ -69,6 +84,9 @@ async fn handle_dav_request(
     if !has_access {
         return Err(AppError::Forbidden);
     }
+    if let Some(metrics_workspace) = req.extensions().get::<MetricsWorkspace>() {
+        metrics_workspace.set(&workspace_id);
+    }
 
     let workspace_root = workspace_root(&state, &workspace_id);
     tokio::fs::create_dir_all(&workspace_root)
@@ -77,15 +95,49 @@ async fn handle_dav_request(
 
     let relative = sanitize_path(&path)?;
     let absolute = workspace_root.join(&relative);
+    let lock_key = lock_key(&workspace_id, &relative);
+    let locks = &state.dav_locks;
+    let encoding = compression::negotiate(req.headers());
 
     let result = match req.method().as_str() {
         "OPTIONS" => respond_options(),
-        "PROPFIND" => respond_propfind(&workspace_id, &relative, &absolute, req.headers()).await,
-        "GET" => respond_get(&absolute, &state.metrics).await,
+        "PROPFIND" => match respond_propfind(&workspace_id, &relative, &absolute, req).await {
+            Ok(response) => compression::compress_response(response, encoding).await,
+            Err(err) => Err(err),
+        },
+        "GET" => match respond_get(&absolute, &state.metrics).await {
+            Ok(response) => compression::compress_response(response, encoding).await,
+            Err(err) => Err(err),
+        },
         "HEAD" => respond_head(&absolute).await,
-        "PUT" => respond_put(&absolute, req, &state.metrics).await,
-        "MKCOL" => respond_mkcol(&absolute).await,
-        "DELETE" => respond_delete(&absolute).await,
+        "LOCK" => respond_lock(locks, &lock_key, &workspace_id, &relative, &absolute, req).await,
+        "UNLOCK" => respond_unlock(locks, &lock_key, req.headers()),
+        "PUT" => match ensure_unlocked(locks, &lock_key, false, req.headers()) {
+            Ok(()) => {
+                respond_put(
+                    &absolute,
+                    req,
+                    &state.metrics,
+                    state.config.max_upload_bytes,
+                )
+                .await
+            }
+            Err(err) => Err(err),
+        },
+        "MKCOL" => match ensure_unlocked(locks, &lock_key, false, req.headers()) {
+            Ok(()) => respond_mkcol(&absolute).await,
+            Err(err) => Err(err),
+        },
+        "DELETE" => match ensure_unlocked(locks, &lock_key, true, req.headers()) {
+            Ok(()) => {
+                let response = respond_delete(&absolute).await;
+                if response.is_ok() {
+                    locks.remove_under(&lock_key);
+                }
+                response
+            }
+            Err(err) => Err(err),
+        },
         _ => Ok(Response::builder()
             .status(StatusCode::METHOD_NOT_ALLOWED)
             .body(Body::empty())
@@ -111,53 +163,275 @@ async fn handle_dav_request(
 fn respond_options() -> Result<Response<Body>, AppError> {
     Response::builder()
         .status(StatusCode::NO_CONTENT)
-        .header("Allow", "OPTIONS, PROPFIND, GET, HEAD, PUT, MKCOL, DELETE")
+        .header(
+            "Allow",
+            "OPTIONS, PROPFIND, GET, HEAD, PUT, MKCOL, DELETE, LOCK, UNLOCK",
+        )
+        .header("DAV", "1, 2")
         .body(Body::empty())
         .map_err(|e| AppError::Internal(format!("build response: {}", e)))
 }
 
-async fn respond_propfind(
+/// Lock table key of a workspace-relative path
+fn lock_key(workspace_id: &str, relative: &Path) -> String {
+    let path = encode_path(relative);
+    if path.is_empty() {
+        workspace_id.to_string()
+    } else {
+        format!("{}/{}", workspace_id, path)
+    }
+}
+
+/// Lock tokens submitted in an `If:` header, e.g. `(<opaquelocktoken:...>)`
+fn submitted_lock_tokens(headers: &HeaderMap) -> Vec<String> {
+    let Some(value) = headers.get("If").and_then(|v| v.to_str().ok()) else {
+        return Vec::new();
+    };
+    value
+        .split('<')
+        .skip(1)
+        .filter_map(|part| part.split_once('>').map(|(token, _)| token.to_string()))
+        .collect()
+}
+
+/// Fails with 423 Locked when another client holds a lock covering `key`
+fn ensure_unlocked(
+    locks: &DavLocks,
+    key: &str,
+    include_descendants: bool,
+    headers: &HeaderMap,
+) -> Result<(), AppError> {
+    let tokens = submitted_lock_tokens(headers);
+    match locks.conflicting(key, include_descendants, &tokens) {
+        Some(_) => Err(AppError::Locked),
+        None => Ok(()),
+    }
+}
+
+/// `Timeout: Second-600` / `Infinite`, capped at [`MAX_LOCK_TIMEOUT`]
+fn parse_lock_timeout(headers: &HeaderMap) -> Duration {
+    let Some(value) = headers.get("Timeout").and_then(|v| v.to_str().ok()) else {
+        return DEFAULT_LOCK_TIMEOUT;
+    };
+    value
+        .split(',')
+        .find_map(|option| {
+            let option = option.trim();
+            if option.eq_ignore_ascii_case("infinite") {
+                return Some(MAX_LOCK_TIMEOUT);
+            }
+            let secs = option.strip_prefix("Second-")?.parse::<u64>().ok()?;
+            Some(Duration::from_secs(secs).min(MAX_LOCK_TIMEOUT))
+        })
+        .unwrap_or(DEFAULT_LOCK_TIMEOUT)
+}
+
+/// Raw inner XML of the first `<local>` element, e.g. the lock owner
+fn element_inner<'a>(body: &'a str, local: &str) -> Option<&'a str> {
+    let mut offset = 0;
+    while let Some(start) = body[offset..].find('<') {
+        let tag_start = offset + start + 1;
+        let tag_end = tag_start + body[tag_start..].find('>')?;
+        let tag = &body[tag_start..tag_end];
+        let name = tag.split(|c: char| c.is_whitespace() || c == '/').next()?;
+        offset = tag_end + 1;
+        if name.rsplit(':').next() == Some(local) && !tag.starts_with('/') {
+            if tag.ends_with('/') {
+                return Some("");
+            }
+            let close = body[offset..].find(&format!("</{}>", name))?;
+            return Some(body[offset..offset + close].trim());
+        }
+    }
+    None
+}
+
+async fn respond_lock(
+    locks: &DavLocks,
+    key: &str,
     workspace_id: &str,
     relative: &Path,
     absolute: &Path,
+    req: Request<Body>,
+) -> Result<Response<Body>, AppError> {
+    let timeout = parse_lock_timeout(req.headers());
+    let depth_infinity = !matches!(
+        req.headers().get("Depth").and_then(|v| v.to_str().ok()),
+        Some("0")
+    );
+    let tokens = submitted_lock_tokens(req.headers());
+    let body = read_xml_body(req.into_body()).await?;
+
+    // An empty body with a submitted token refreshes an existing lock
+    if body.trim().is_empty() {
+        let lock = tokens
+            .iter()
+            .find_map(|token| locks.refresh(key, token, timeout))
+            .ok_or_else(|| AppError::BadRequest("no lock to refresh".to_string()))?;
+        let is_dir = tokio::fs::metadata(absolute)
+            .await
+            .map(|m| m.is_dir())
+            .unwrap_or(false);
+        return lock_response(
+            StatusCode::OK,
+            &lock,
+            &href_for(workspace_id, relative, is_dir),
+        );
+    }
+
+    if scan_tags(&body)?
+        .iter()
+        .any(|tag| tag.name.rsplit(':').next() == Some("shared"))
+    {
+        return Err(AppError::BadRequest(
+            "only exclusive locks are supported".to_string(),
+        ));
+    }
+    let owner = element_inner(&body, "owner").map(str::to_string);
+    let lock = locks
+        .acquire(key, depth_infinity, owner, timeout)
+        .map_err(|_| AppError::Locked)?;
+
+    // Locking an unmapped URL creates an empty resource
+    let status = match tokio::fs::metadata(absolute).await {
+        Ok(_) => StatusCode::OK,
+        Err(_) => {
+            let created = tokio::fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(absolute)
+                .await;
+            if let Err(err) = created {
+                locks.release(key, &lock.token);
+                return Err(AppError::Conflict(format!(
+                    "cannot create resource: {}",
+                    err
+                )));
+            }
+            StatusCode::CREATED
+        }
+    };
+    let is_dir = tokio::fs::metadata(absolute)
+        .await
+        .map(|m| m.is_dir())
+        .unwrap_or(false);
+    lock_response(status, &lock, &href_for(workspace_id, relative, is_dir))
+}
+
+fn lock_response(
+    status: StatusCode,
+    lock: &DavLock,
+    root: &str,
+) -> Result<Response<Body>, AppError> {
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+    xml.push_str("<D:prop xmlns:D=\"DAV:\">\n");
+    xml.push_str("  <D:lockdiscovery>\n");
+    xml.push_str("    <D:activelock>\n");
+    xml.push_str("      <D:locktype><D:write/></D:locktype>\n");
+    xml.push_str("      <D:lockscope><D:exclusive/></D:lockscope>\n");
+    xml.push_str(&format!(
+        "      <D:depth>{}</D:depth>\n",
+        if lock.depth_infinity { "infinity" } else { "0" }
+    ));
+    if let Some(owner) = &lock.owner {
+        xml.push_str(&format!("      <D:owner>{}</D:owner>\n", owner));
+    }
+    xml.push_str(&format!(
+        "      <D:timeout>Second-{}</D:timeout>\n",
+        lock.timeout.as_secs()
+    ));
+    xml.push_str(&format!(
+        "      <D:locktoken><D:href>{}</D:href></D:locktoken>\n",
+        xml_escape(&lock.token)
+    ));
+    xml.push_str(&format!(
+        "      <D:lockroot><D:href>{}</D:href></D:lockroot>\n",
+        xml_escape(root)
+    ));
+    xml.push_str("    </D:activelock>\n");
+    xml.push_str("  </D:lockdiscovery>\n");
+    xml.push_str("</D:prop>\n");
+
+    Response::builder()
+        .status(status)
+        .header("Content-Type", "application/xml; charset=utf-8")
+        .header("Lock-Token", format!("<{}>", lock.token))
+        .body(Body::from(xml))
+        .map_err(|e| AppError::Internal(format!("build response: {}", e)))
+}
+
+fn respond_unlock(
+    locks: &DavLocks,
+    key: &str,
     headers: &HeaderMap,
 ) -> Result<Response<Body>, AppError> {
-    let depth = headers
-        .get("Depth")
+    let token = headers
+        .get("Lock-Token")
         .and_then(|v| v.to_str().ok())
-        .unwrap_or("0");
-    let depth = match depth {
-        "1" => 1,
-        _ => 0,
-    };
+        .map(|v| {
+            v.trim()
+                .trim_start_matches('<')
+                .trim_end_matches('>')
+                .to_string()
+        })
+        .ok_or_else(|| AppError::BadRequest("missing Lock-Token header".to_string()))?;
+    if !locks.release(key, &token) {
+        return Err(AppError::Conflict("lock token does not match".to_string()));
+    }
+    Response::builder()
+        .status(StatusCode::NO_CONTENT)
+        .body(Body::empty())
+        .map_err(|e| AppError::Internal(format!("build response: {}", e)))
+}
+
+async fn respond_propfind(
+    workspace_id: &str,
+    relative: &Path,
+    absolute: &Path,
+    req: Request<Body>,
+) -> Result<Response<Body>, AppError> {
+    let depth = parse_depth(req.headers())?;
+    let body = read_xml_body(req.into_body()).await?;
+    let mode = parse_propfind_body(&body)?;
 
     let metadata = tokio::fs::metadata(absolute)
         .await
         .map_err(|_| AppError::NotFound)?;
-    let mut entries = Vec::new();
-    entries.push(build_prop_entry(workspace_id, relative, &metadata).await?);
+    let mut entries = vec![build_prop_entry(workspace_id, relative, &metadata)];
 
-    if depth == 1 && metadata.is_dir() {
-        let mut dir = tokio::fs::read_dir(absolute)
-            .await
-            .map_err(|e| AppError::Internal(format!("read dir: {}", e)))?;
-        while let Some(entry) = dir
-            .next_entry()
-            .await
-            .map_err(|e| AppError::Internal(format!("read dir: {}", e)))?
-        {
-            let name = entry.file_name();
-            let name = name.to_string_lossy().to_string();
-            let child_relative = relative.join(name);
-            let child_metadata = entry
-                .metadata()
+    if depth != Depth::Zero && metadata.is_dir() {
+        // Directories still to list, with the depth of their children
+        let mut pending = vec![(absolute.to_path_buf(), relative.to_path_buf())];
+        while let Some((dir_path, dir_relative)) = pending.pop() {
+            let mut dir = tokio::fs::read_dir(&dir_path)
+                .await
+                .map_err(|e| AppError::Internal(format!("read dir: {}", e)))?;
+            let mut children = Vec::new();
+            while let Some(entry) = dir
+                .next_entry()
                 .await
-                .map_err(|e| AppError::Internal(format!("read metadata: {}", e)))?;
-            entries.push(build_prop_entry(workspace_id, &child_relative, &child_metadata).await?);
+                .map_err(|e| AppError::Internal(format!("read dir: {}", e)))?
+            {
+                let child_relative = dir_relative.join(entry.file_name());
+                let child_metadata = entry
+                    .metadata()
+                    .await
+                    .map_err(|e| AppError::Internal(format!("read metadata: {}", e)))?;
+                if depth == Depth::Infinity && child_metadata.is_dir() {
+                    pending.push((entry.path(), child_relative.clone()));
+                }
+                children.push(build_prop_entry(
+                    workspace_id,
+                    &child_relative,
+                    &child_metadata,
+                ));
+            }
+            children.sort_by(|a, b| a.href.cmp(&b.href));
+            entries.extend(children);
         }
     }
 
-    let body = build_propfind_xml(&entries);
+    let body = build_propfind_xml(&entries, &mode);
     Response::builder()
         .status(StatusCode::MULTI_STATUS)
         .header("Content-Type", "application/xml; charset=utf-8")
@@ -165,12 +439,189 @@ async fn respond_propfind(
         .map_err(|e| AppError::Internal(format!("build response: {}", e)))
 }
 
-async fn build_prop_entry(
+/// `Depth` header of a PROPFIND; RFC 4918 treats a missing header as infinity
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Depth {
+    Zero,
+    One,
+    Infinity,
+}
+
+fn parse_depth(headers: &HeaderMap) -> Result<Depth, AppError> {
+    let Some(value) = headers.get("Depth") else {
+        return Ok(Depth::Infinity);
+    };
+    let value = value
+        .to_str()
+        .map_err(|_| AppError::BadRequest("invalid Depth header".to_string()))?;
+    match value.trim().to_ascii_lowercase().as_str() {
+        "0" => Ok(Depth::Zero),
+        "1" => Ok(Depth::One),
+        "infinity" => Ok(Depth::Infinity),
+        _ => Err(AppError::BadRequest(format!(
+            "invalid Depth header: {}",
+            value
+        ))),
+    }
+}
+
+/// Reads a small XML request body (PROPFIND / LOCK)
+async fn read_xml_body(mut body: Body) -> Result<String, AppError> {
+    let mut bytes = Vec::new();
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk.map_err(|e| AppError::Internal(format!("read body: {}", e)))?;
+        if bytes.len() + chunk.len() > MAX_PROPFIND_BODY_BYTES {
+            return Err(AppError::BadRequest("PROPFIND body too large".to_string()));
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+    String::from_utf8(bytes).map_err(|_| AppError::BadRequest("invalid PROPFIND body".to_string()))
+}
+
+/// Property requested by name, e.g. `DAV:` + `getetag`
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct PropName {
+    namespace: String,
+    name: String,
+}
+
+/// What a PROPFIND asks for
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum PropfindMode {
+    /// `<allprop/>` or an empty body: all live properties with values
+    AllProp,
+    /// `<propname/>`: the names of all live properties
+    PropName,
+    /// `<prop>...</prop>`: the listed properties; unknown ones get a 404 propstat
+    Prop(Vec<PropName>),
+}
+
+/// Parses the PROPFIND request body
+///
+/// Only element names and namespace declarations matter here, so a small
+/// tag scanner is enough; namespace prefixes are resolved against every
+/// `xmlns` declaration in the document.
+fn parse_propfind_body(body: &str) -> Result<PropfindMode, AppError> {
+    if body.trim().is_empty() {
+        return Ok(PropfindMode::AllProp);
+    }
+    let tags = scan_tags(body)?;
+    let mut namespaces = std::collections::HashMap::new();
+    for tag in &tags {
+        for (key, value) in &tag.attributes {
+            if key == "xmlns" {
+                namespaces.insert(String::new(), value.clone());
+            } else if let Some(prefix) = key.strip_prefix("xmlns:") {
+                namespaces.insert(prefix.to_string(), value.clone());
+            }
+        }
+    }
+
+    let mut props = None;
+    let mut in_prop = false;
+    for tag in &tags {
+        let (prefix, local) = match tag.name.split_once(':') {
+            Some((prefix, local)) => (prefix, local),
+            None => ("", tag.name.as_str()),
+        };
+        if tag.closing {
+            if local == "prop" && in_prop {
+                in_prop = false;
+            }
+            continue;
+        }
+        if in_prop {
+            props.get_or_insert_with(Vec::new).push(PropName {
+                namespace: namespaces.get(prefix).cloned().unwrap_or_default(),
+                name: local.to_string(),
+            });
+            continue;
+        }
+        match local {
+            "allprop" => return Ok(PropfindMode::AllProp),
+            "propname" => return Ok(PropfindMode::PropName),
+            "prop" if !tag.self_closing => {
+                in_prop = true;
+                props.get_or_insert_with(Vec::new);
+            }
+            _ => {}
+        }
+    }
+    Ok(props.map_or(PropfindMode::AllProp, PropfindMode::Prop))
+}
+
+#[derive(Debug)]
+struct XmlTag {
+    name: String,
+    attributes: Vec<(String, String)>,
+    closing: bool,
+    self_closing: bool,
+}
+
+fn scan_tags(body: &str) -> Result<Vec<XmlTag>, AppError> {
+    let invalid = || AppError::BadRequest("invalid PROPFIND body".to_string());
+    let mut tags = Vec::new();
+    let mut rest = body;
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start + 1..];
+        if let Some(comment) = rest.strip_prefix("!--") {
+            let end = comment.find("-->").ok_or_else(invalid)?;
+            rest = &comment[end + 3..];
+            continue;
+        }
+        let end = rest.find('>').ok_or_else(invalid)?;
+        let inner = rest[..end].trim();
+        rest = &rest[end + 1..];
+        if inner.starts_with('?') || inner.starts_with('!') {
+            continue;
+        }
+        let (closing, inner) = match inner.strip_prefix('/') {
+            Some(inner) => (true, inner.trim()),
+            None => (false, inner),
+        };
+        let (self_closing, inner) = match inner.strip_suffix('/') {
+            Some(inner) => (true, inner.trim()),
+            None => (false, inner),
+        };
+        let name_end = inner.find(char::is_whitespace).unwrap_or(inner.len());
+        let name = inner[..name_end].to_string();
+        if name.is_empty() {
+            return Err(invalid());
+        }
+        tags.push(XmlTag {
+            name,
+            attributes: scan_attributes(&inner[name_end..]),
+            closing,
+            self_closing,
+        });
+    }
+    Ok(tags)
+}
+
+fn scan_attributes(mut rest: &str) -> Vec<(String, String)> {
+    let mut attributes = Vec::new();
+    while let Some(eq) = rest.find('=') {
+        let key = rest[..eq].trim().to_string();
+        let value = rest[eq + 1..].trim_start();
+        let Some(quote) = value.chars().next().filter(|c| *c == '"' || *c == '\'') else {
+            break;
+        };
+        let Some(close) = value[1..].find(quote) else {
+            break;
+        };
+        attributes.push((key, value[1..close + 1].to_string()));
+        rest = &value[close + 2..];
+    }
+    attributes
+}
+
+fn build_prop_entry(
     workspace_id: &str,
     relative: &Path,
     metadata: &std::fs::Metadata,
-) -> Result<PropEntry, AppError> {
+) -> PropEntry {
     let modified = metadata.modified().unwrap_or(SystemTime::now());
+    let created = metadata.created().unwrap_or(modified);
     let size = if metadata.is_file() {
         metadata.len()
     } else {
@@ -189,15 +640,21 @@ async fn build_prop_entry(
     } else {
         "httpd/unix-directory".to_string()
     };
+    let display_name = relative
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
 
-    Ok(PropEntry {
+    PropEntry {
         href: href_for(workspace_id, relative, metadata.is_dir()),
+        display_name,
         is_dir: metadata.is_dir(),
         size,
         modified,
+        created,
         etag,
         content_type,
-    })
+    }
 }
 
 async fn respond_get(absolute: &Path, metrics: &ServerMetrics) -> Result<Response<Body>, AppError> {
@@ -269,10 +726,13 @@ async fn respond_head(absolute: &Path) -> Result<Response<Body>, AppError> {
         .map_err(|e| AppError::Internal(format!("build response: {}", e)))
 }
 
+/// Streams the body to a temporary file next to `absolute` and renames it
+/// into place, so an oversized or aborted upload never truncates the old file
 async fn respond_put(
     absolute: &Path,
     req: Request<Body>,
     metrics: &ServerMetrics,
+    max_bytes: u64,
 ) -> Result<Response<Body>, AppError> {
     if let Some(len) = req
         .headers()
@@ -280,30 +740,41 @@ async fn respond_put(
         .and_then(|value| value.to_str().ok())
         .and_then(|value| value.parse::<u64>().ok())
     {
-        if len > MAX_DAV_UPLOAD_BYTES {
-            return Err(AppError::BadRequest("payload too large".to_string()));
+        if len > max_bytes {
+            return Err(AppError::PayloadTooLarge);
         }
     }
-    if let Some(parent) = absolute.parent() {
-        tokio::fs::create_dir_all(parent)
-            .await
-            .map_err(|e| AppError::Internal(format!("create dir: {}", e)))?;
-    }
-    let mut body = req.into_body();
-    let mut file = tokio::fs::File::create(absolute)
+    let parent = absolute
+        .parent()
+        .ok_or_else(|| AppError::BadRequest("cannot PUT workspace root".to_string()))?;
+    let file_name = absolute
+        .file_name()
+        .ok_or_else(|| AppError::BadRequest("cannot PUT workspace root".to_string()))?;
+    tokio::fs::create_dir_all(parent)
         .await
-        .map_err(|e| AppError::Internal(format!("create file: {}", e)))?;
-    let mut written: u64 = 0;
-    while let Some(chunk) = body.data().await {
-        let chunk = chunk.map_err(|e| AppError::Internal(format!("read body: {}", e)))?;
-        written = written.saturating_add(chunk.len() as u64);
-        if written > MAX_DAV_UPLOAD_BYTES {
-            return Err(AppError::BadRequest("payload too large".to_string()));
-        }
-        file.write_all(&chunk)
+        .map_err(|e| AppError::Internal(format!("create dir: {}", e)))?;
+
+    let temp_path = parent.join(format!(
+        ".{}.upload-{}",
+        file_name.to_string_lossy(),
+        Uuid::new_v4()
+    ));
+    let result = write_upload(&temp_path, req.into_body(), max_bytes).await;
+    let result = match result {
+        Ok(written) => tokio::fs::rename(&temp_path, absolute)
             .await
-            .map_err(|e| AppError::Internal(format!("write file: {}", e)))?;
-    }
+            .map(|_| written)
+            .map_err(|e| AppError::Internal(format!("replace file: {}", e))),
+        Err(err) => Err(err),
+    };
+    let written = match result {
+        Ok(written) => written,
+        Err(err) => {
+            let _ = tokio::fs::remove_file(&temp_path).await;
+            return Err(err);
+        }
+    };
+
     metrics.add_dav_bytes_in(written);
     tracing::info!(
         target: "metrics",
@@ -317,6 +788,28 @@ async fn respond_put(
         .map_err(|e| AppError::Internal(format!("build response: {}", e)))
 }
 
+/// Copies `body` into a new file, failing as soon as it exceeds `max_bytes`
+async fn write_upload(path: &Path, mut body: Body, max_bytes: u64) -> Result<u64, AppError> {
+    let mut file = tokio::fs::File::create(path)
+        .await
+        .map_err(|e| AppError::Internal(format!("create file: {}", e)))?;
+    let mut written: u64 = 0;
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk.map_err(|e| AppError::Internal(format!("read body: {}", e)))?;
+        written = written.saturating_add(chunk.len() as u64);
+        if written > max_bytes {
+            return Err(AppError::PayloadTooLarge);
+        }
+        file.write_all(&chunk)
+            .await
+            .map_err(|e| AppError::Internal(format!("write file: {}", e)))?;
+    }
+    file.flush()
+        .await
+        .map_err(|e| AppError::Internal(format!("write file: {}", e)))?;
+    Ok(written)
+}
+
 async fn respond_mkcol(absolute: &Path) -> Result<Response<Body>, AppError> {
     tokio::fs::create_dir_all(absolute)
         .await
@@ -401,46 +894,94 @@ fn sanitize_path(path: &str) -> Result<PathBuf, AppError> {
 #[derive(Debug)]
 struct PropEntry {
     href: String,
+    display_name: String,
     is_dir: bool,
     size: u64,
     modified: SystemTime,
+    created: SystemTime,
     etag: String,
     content_type: String,
 }
 
-fn build_propfind_xml(entries: &[PropEntry]) -> String {
+impl PropEntry {
+    /// XML content of a live `DAV:` property, or None if it is not defined
+    fn live_prop(&self, name: &str) -> Option<String> {
+        match name {
+            "resourcetype" if self.is_dir => Some("<D:collection/>".to_string()),
+            "resourcetype" => Some(String::new()),
+            "displayname" => Some(xml_escape(&self.display_name)),
+            "getcontentlength" => Some(self.size.to_string()),
+            "getcontenttype" => Some(xml_escape(&self.content_type)),
+            "getlastmodified" => Some(fmt_http_date(self.modified)),
+            "creationdate" => Some(
+                chrono::DateTime::<chrono::Utc>::from(self.created)
+                    .format("%Y-%m-%dT%H:%M:%SZ")
+                    .to_string(),
+            ),
+            "getetag" => Some(xml_escape(&self.etag)),
+            _ => None,
+        }
+    }
+}
+
+fn build_propfind_xml(entries: &[PropEntry], mode: &PropfindMode) -> String {
     let mut xml = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
     xml.push_str("<D:multistatus xmlns:D=\"DAV:\">\n");
 
     for entry in entries {
-        let href = xml_escape(&entry.href);
-        let etag = xml_escape(&entry.etag);
-        let content_type = xml_escape(&entry.content_type);
         xml.push_str("  <D:response>\n");
-        xml.push_str(&format!("    <D:href>{}</D:href>\n", href));
-        xml.push_str("    <D:propstat>\n");
-        xml.push_str("      <D:prop>\n");
-        xml.push_str("        <D:resourcetype>");
-        if entry.is_dir {
-            xml.push_str("<D:collection/>");
-        }
-        xml.push_str("</D:resourcetype>\n");
-        xml.push_str(&format!(
-            "        <D:getcontentlength>{}</D:getcontentlength>\n",
-            entry.size
-        ));
         xml.push_str(&format!(
-            "        <D:getlastmodified>{}</D:getlastmodified>\n",
-            fmt_http_date(entry.modified)
+            "    <D:href>{}</D:href>\n",
+            xml_escape(&entry.href)
         ));
-        xml.push_str(&format!("        <D:getetag>{}</D:getetag>\n", etag));
-        xml.push_str(&format!(
-            "        <D:getcontenttype>{}</D:getcontenttype>\n",
-            content_type
-        ));
-        xml.push_str("      </D:prop>\n");
-        xml.push_str("      <D:status>HTTP/1.1 200 OK</D:status>\n");
-        xml.push_str("    </D:propstat>\n");
+        match mode {
+            PropfindMode::AllProp => {
+                let found: Vec<String> = LIVE_PROPS
+                    .iter()
+                    .filter_map(|name| {
+                        let value = entry.live_prop(name)?;
+                        Some(format!("<D:{name}>{value}</D:{name}>"))
+                    })
+                    .collect();
+                push_propstat(&mut xml, &found, "200 OK");
+            }
+            PropfindMode::PropName => {
+                let names: Vec<String> = LIVE_PROPS
+                    .iter()
+                    .map(|name| format!("<D:{}/>", name))
+                    .collect();
+                push_propstat(&mut xml, &names, "200 OK");
+            }
+            PropfindMode::Prop(props) => {
+                let mut found = Vec::new();
+                let mut missing = Vec::new();
+                for prop in props {
+                    let value = (prop.namespace == DAV_NAMESPACE)
+                        .then(|| entry.live_prop(&prop.name))
+                        .flatten();
+                    match value {
+                        Some(value) => found.push(format!("<D:{0}>{1}</D:{0}>", prop.name, value)),
+                        None if prop.namespace == DAV_NAMESPACE => {
+                            missing.push(format!("<D:{}/>", prop.name))
+                        }
+                        None if prop.namespace.is_empty() => {
+                            missing.push(format!("<{} xmlns=\"\"/>", prop.name))
+                        }
+                        None => missing.push(format!(
+                            "<X:{} xmlns:X=\"{}\"/>",
+                            prop.name,
+                            xml_escape(&prop.namespace)
+                        )),
+                    }
+                }
+                if !found.is_empty() || missing.is_empty() {
+                    push_propstat(&mut xml, &found, "200 OK");
+                }
+                if !missing.is_empty() {
+                    push_propstat(&mut xml, &missing, "404 Not Found");
+                }
+            }
+        }
         xml.push_str("  </D:response>\n");
     }
 
@@ -448,6 +989,17 @@ fn build_propfind_xml(entries: &[PropEntry]) -> String {
     xml
 }
 
+fn push_propstat(xml: &mut String, props: &[String], status: &str) {
+    xml.push_str("    <D:propstat>\n");
+    xml.push_str("      <D:prop>\n");
+    for prop in props {
+        xml.push_str(&format!("        {}\n", prop));
+    }
+    xml.push_str("      </D:prop>\n");
+    xml.push_str(&format!("      <D:status>HTTP/1.1 {}</D:status>\n", status));
+    xml.push_str("    </D:propstat>\n");
+}
+
 fn href_for(workspace_id: &str, relative: &Path, is_dir: bool) -> String {
     let mut href = format!("/dav/{}", workspace_id);
     if !relative.as_os_str().is_empty() {
@@ -484,3 +1036,351 @@ fn xml_escape(value: &str) -> String {
     }
     escaped
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hyper::body::to_bytes;
+
+    async fn propfind(root: &Path, depth: Option<&str>, body: &str) -> String {
+        let mut builder = Request::builder().method("PROPFIND");
+        if let Some(depth) = depth {
+            builder = builder.header("Depth", depth);
+        }
+        let req = builder.body(Body::from(body.to_string())).unwrap();
+        let response = respond_propfind("ws", Path::new(""), root, req)
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::MULTI_STATUS);
+        let bytes = to_bytes(response.into_body()).await.unwrap();
+        String::from_utf8(bytes.to_vec()).unwrap()
+    }
+
+    fn hrefs(xml: &str) -> Vec<&str> {
+        xml.split("<D:href>")
+            .skip(1)
+            .filter_map(|part| part.split("</D:href>").next())
+            .collect()
+    }
+
+    fn workspace() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("notes/deep")).unwrap();
+        std::fs::write(dir.path().join("a.md"), "a").unwrap();
+        std::fs::write(dir.path().join("notes/b.md"), "bb").unwrap();
+        std::fs::write(dir.path().join("notes/deep/c.md"), "ccc").unwrap();
+        dir
+    }
+
+    #[tokio::test]
+    async fn depth_one_lists_immediate_children_only() {
+        let dir = workspace();
+        let xml = propfind(dir.path(), Some("1"), "").await;
+        assert_eq!(
+            hrefs(&xml),
+            vec!["/dav/ws/", "/dav/ws/a.md", "/dav/ws/notes/"]
+        );
+
+        let xml = propfind(dir.path(), Some("0"), "").await;
+        assert_eq!(hrefs(&xml), vec!["/dav/ws/"]);
+    }
+
+    #[tokio::test]
+    async fn depth_infinity_lists_all_descendants() {
+        let dir = workspace();
+        let xml = propfind(dir.path(), Some("infinity"), "").await;
+        let mut listed = hrefs(&xml);
+        listed.sort();
+        assert_eq!(
+            listed,
+            vec![
+                "/dav/ws/",
+                "/dav/ws/a.md",
+                "/dav/ws/notes/",
+                "/dav/ws/notes/b.md",
+                "/dav/ws/notes/deep/",
+                "/dav/ws/notes/deep/c.md",
+            ]
+        );
+        // RFC 4918: a missing Depth header means infinity
+        assert_eq!(hrefs(&propfind(dir.path(), None, "").await).len(), 6);
+    }
+
+    #[tokio::test]
+    async fn allprop_and_propname_report_live_properties() {
+        let dir = workspace();
+        let allprop = propfind(
+            dir.path(),
+            Some("0"),
+            r#"<?xml version="1.0"?><D:propfind xmlns:D="DAV:"><D:allprop/></D:propfind>"#,
+        )
+        .await;
+        for name in LIVE_PROPS {
+            assert!(allprop.contains(&format!("<D:{}>", name)), "{}", name);
+        }
+        assert!(allprop.contains("<D:resourcetype><D:collection/></D:resourcetype>"));
+
+        let propname = propfind(
+            dir.path(),
+            Some("0"),
+            r#"<propfind xmlns="DAV:"><propname/></propfind>"#,
+        )
+        .await;
+        assert!(propname.contains("<D:getetag/>"));
+        assert!(!propname.contains("<D:getetag>"));
+    }
+
+    #[tokio::test]
+    async fn prop_request_returns_found_and_missing_propstats() {
+        let dir = workspace();
+        let xml = propfind(
+            dir.path(),
+            Some("1"),
+            r#"<D:propfind xmlns:D="DAV:" xmlns:A="http://apple.com/ns">
+                 <D:prop><D:getcontentlength/><A:quota/></D:prop>
+               </D:propfind>"#,
+        )
+        .await;
+        assert!(xml.contains("<D:getcontentlength>1</D:getcontentlength>"));
+        assert!(xml.contains(r#"<X:quota xmlns:X="http://apple.com/ns"/>"#));
+        assert!(xml.contains("HTTP/1.1 404 Not Found"));
+        assert!(!xml.contains("<D:getetag>"));
+    }
+
+    async fn dav(
+        state: &AppState,
+        workspace_id: &str,
+        method: &str,
+        path: &str,
+        headers: &[(&str, &str)],
+        body: &str,
+    ) -> (StatusCode, HeaderMap) {
+        let mut builder = Request::builder().method(method);
+        for (name, value) in headers {
+            builder = builder.header(*name, *value);
+        }
+        let req = builder.body(Body::from(body.to_string())).unwrap();
+        match handle_dav_request(
+            state.clone(),
+            workspace_id.to_string(),
+            path.to_string(),
+            req,
+        )
+        .await
+        {
+            Ok(response) => (response.status(), response.headers().clone()),
+            Err(err) => (
+                axum::response::IntoResponse::into_response(err).status(),
+                HeaderMap::new(),
+            ),
+        }
+    }
+
+    async fn put_stream(state: &AppState, workspace_id: &str, req: Request<Body>) -> StatusCode {
+        let response = tokio::time::timeout(
+            std::time::Duration::from_secs(5),
+            handle_dav_request(
+                state.clone(),
+                workspace_id.to_string(),
+                "big.bin".to_string(),
+                req,
+            ),
+        )
+        .await
+        .expect("PUT should not wait for the rest of the body");
+        match response {
+            Ok(response) => response.status(),
+            Err(err) => axum::response::IntoResponse::into_response(err).status(),
+        }
+    }
+
+    #[tokio::test]
+    async fn oversized_put_is_rejected_without_reading_the_body() {
+        let state = crate::routes::tests::test_state().await;
+        let user_id = db::create_user(&state.pool, "dev@example.com", "hash")
+            .await
+            .unwrap();
+        let workspace_id = db::create_workspace(&state.pool, &user_id, "Notes")
+            .await
+            .unwrap();
+        let token = crate::auth::create_token(&user_id, &state.config).unwrap();
+        let auth = format!("Bearer {}", token);
+        let limit = state.config.max_upload_bytes as usize;
+
+        let put = dav(
+            &state,
+            &workspace_id,
+            "PUT",
+            "big.bin",
+            &[("Authorization", &auth)],
+            "original",
+        )
+        .await;
+        assert_eq!(put.0, StatusCode::CREATED);
+
+        // Declared length over the limit: the body sender is never drained
+        let (_declared_tx, body) = Body::channel();
+        let req = Request::builder()
+            .method("PUT")
+            .header("Authorization", &auth)
+            .header("Content-Length", (limit + 1).to_string())
+            .body(body)
+            .unwrap();
+        assert_eq!(
+            put_stream(&state, &workspace_id, req).await,
+            StatusCode::PAYLOAD_TOO_LARGE
+        );
+
+        // Chunked body that keeps going past the limit
+        let (mut chunked_tx, body) = Body::channel();
+        chunked_tx
+            .send_data(vec![b'x'; limit + 1].into())
+            .await
+            .unwrap();
+        let req = Request::builder()
+            .method("PUT")
+            .header("Authorization", &auth)
+            .body(body)
+            .unwrap();
+        assert_eq!(
+            put_stream(&state, &workspace_id, req).await,
+            StatusCode::PAYLOAD_TOO_LARGE
+        );
+
+        // The rejected uploads left the existing file and no temp files behind
+        let root = workspace_root(&state, &workspace_id);
+        assert_eq!(std::fs::read(root.join("big.bin")).unwrap(), b"original");
+        assert_eq!(std::fs::read_dir(&root).unwrap().count(), 1);
+    }
+
+    #[tokio::test]
+    async fn put_on_locked_path_requires_the_lock_token() {
+        let state = crate::routes::tests::test_state().await;
+        let user_id = db::create_user(&state.pool, "dev@example.com", "hash")
+            .await
+            .unwrap();
+        let workspace_id = db::create_workspace(&state.pool, &user_id, "Notes")
+            .await
+            .unwrap();
+        let token = crate::auth::create_token(&user_id, &state.config).unwrap();
+        let auth = format!("Bearer {}", token);
+
+        let locked = dav(
+            &state,
+            &workspace_id,
+            "LOCK",
+            "a.md",
+            &[("Authorization", &auth), ("Timeout", "Second-60")],
+            r#"<D:lockinfo xmlns:D="DAV:"><D:lockscope><D:exclusive/></D:lockscope>
+                 <D:locktype><D:write/></D:locktype><D:owner>client-a</D:owner></D:lockinfo>"#,
+        )
+        .await;
+        assert_eq!(locked.0, StatusCode::CREATED);
+        let lock_token = locked.1["Lock-Token"].to_str().unwrap().to_string();
+
+        // A second client without the token is rejected
+        let put = dav(
+            &state,
+            &workspace_id,
+            "PUT",
+            "a.md",
+            &[("Authorization", &auth)],
+            "b",
+        )
+        .await;
+        assert_eq!(put.0, StatusCode::LOCKED);
+        let relock = dav(
+            &state,
+            &workspace_id,
+            "LOCK",
+            "a.md",
+            &[("Authorization", &auth)],
+            "<D:lockinfo xmlns:D=\"DAV:\"/>",
+        )
+        .await;
+        assert_eq!(relock.0, StatusCode::LOCKED);
+
+        let if_header = format!("({})", lock_token);
+        let put = dav(
+            &state,
+            &workspace_id,
+            "PUT",
+            "a.md",
+            &[("Authorization", &auth), ("If", &if_header)],
+            "a",
+        )
+        .await;
+        assert_eq!(put.0, StatusCode::CREATED);
+
+        let unlock = dav(
+            &state,
+            &workspace_id,
+            "UNLOCK",
+            "a.md",
+            &[("Authorization", &auth), ("Lock-Token", &lock_token)],
+            "",
+        )
+        .await;
+        assert_eq!(unlock.0, StatusCode::NO_CONTENT);
+        let put = dav(
+            &state,
+            &workspace_id,
+            "PUT",
+            "a.md",
+            &[("Authorization", &auth)],
+            "b",
+        )
+        .await;
+        assert_eq!(put.0, StatusCode::CREATED);
+    }
+
+    #[test]
+    fn collection_locks_cover_descendants() {
+        let locks = DavLocks::new();
+        let lock = locks
+            .acquire("ws/notes", true, None, DEFAULT_LOCK_TIMEOUT)
+            .unwrap();
+        assert!(locks.conflicting("ws/notes/a.md", false, &[]).is_some());
+        assert!(locks
+            .conflicting("ws/notes/a.md", false, std::slice::from_ref(&lock.token))
+            .is_none());
+        assert!(locks.conflicting("ws/notes2/a.md", false, &[]).is_none());
+        // Deleting the parent collection is blocked by a lock below it
+        assert!(locks.conflicting("ws", true, &[]).is_some());
+        assert!(locks.conflicting("ws", false, &[]).is_none());
+    }
+
+    #[test]
+    fn concurrent_lock_requests_grant_one_lock() {
+        let locks = std::sync::Arc::new(DavLocks::new());
+        let barrier = std::sync::Arc::new(std::sync::Barrier::new(8));
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let locks = locks.clone();
+                let barrier = barrier.clone();
+                std::thread::spawn(move || {
+                    barrier.wait();
+                    locks
+                        .acquire("ws/a.md", false, None, DEFAULT_LOCK_TIMEOUT)
+                        .is_ok()
+                })
+            })
+            .collect();
+        let granted = handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .filter(|granted| *granted)
+            .count();
+        assert_eq!(granted, 1);
+    }
+
+    #[test]
+    fn rejects_invalid_depth() {
+        let mut headers = HeaderMap::new();
+        headers.insert("Depth", "2".parse().unwrap());
+        assert!(matches!(
+            parse_depth(&headers),
+            Err(AppError::BadRequest(_))
+        ));
+    }
+}
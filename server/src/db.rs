@@ -1,8 +1,35 @@
+use crate::config::Config;
 use crate::error::AppError;
 use chrono::Utc;
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions, SqliteSynchronous};
 use sqlx::{Row, SqlitePool};
+use std::str::FromStr;
 use uuid::Uuid;
 
+/// Opens the SQLite pool with settings suited to concurrent sync clients
+///
+/// - WAL journal: readers no longer block the writer and vice versa, at the
+///   cost of `-wal`/`-shm` files next to the database (keep them together
+///   when backing up, and do not put the database on a network filesystem).
+/// - `busy_timeout`: a writer waits for the lock instead of failing at once
+///   with "database is locked".
+/// - `synchronous = NORMAL`: safe against corruption in WAL mode; a power
+///   loss may drop the last few commits, which clients simply sync again.
+/// - Foreign keys are enforced on every connection.
+pub async fn connect(config: &Config) -> Result<SqlitePool, AppError> {
+    let options = SqliteConnectOptions::from_str(&config.db_url)
+        .map_err(|e| AppError::Internal(format!("invalid database url: {}", e)))?
+        .journal_mode(SqliteJournalMode::Wal)
+        .busy_timeout(config.db_busy_timeout)
+        .synchronous(SqliteSynchronous::Normal)
+        .foreign_keys(true);
+    SqlitePoolOptions::new()
+        .max_connections(config.db_max_connections)
+        .connect_with(options)
+        .await
+        .map_err(|e| AppError::Internal(format!("connect database: {}", e)))
+}
+
 pub async fn init_db(pool: &SqlitePool) -> Result<(), AppError> {
     sqlx::query(
         r#"
@@ -206,3 +233,43 @@ pub async fn user_has_workspace(
 
     Ok(row.is_some())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn connect_enables_wal_and_busy_timeout() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = Config {
+            bind: "127.0.0.1:0".to_string(),
+            db_url: format!("sqlite://{}?mode=rwc", dir.path().join("t.db").display()),
+            data_dir: dir.path().display().to_string(),
+            jwt_secret: "test-secret".to_string(),
+            db_max_connections: 2,
+            db_busy_timeout: Duration::from_millis(1500),
+            max_upload_bytes: 1024,
+            metrics_token: None,
+        };
+        let pool = connect(&config).await.unwrap();
+
+        let pragma = |name: &'static str| {
+            let pool = pool.clone();
+            async move {
+                sqlx::query(&format!("PRAGMA {}", name))
+                    .fetch_one(&pool)
+                    .await
+                    .unwrap()
+            }
+        };
+        let journal: String = pragma("journal_mode").await.get(0);
+        let busy: i64 = pragma("busy_timeout").await.get(0);
+        let foreign_keys: i64 = pragma("foreign_keys").await.get(0);
+        let synchronous: i64 = pragma("synchronous").await.get(0);
+        assert_eq!(journal, "wal");
+        assert_eq!(busy, 1500);
+        assert_eq!(foreign_keys, 1);
+        assert_eq!(synchronous, 1);
+    }
+}
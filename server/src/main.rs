@@ -1,4 +1,5 @@
 mod auth;
+mod compression;
 mod config;
 mod dav;
 mod db;
@@ -8,18 +9,26 @@ mod relay;
 mod routes;
 mod state;
 
+use axum::extract::DefaultBodyLimit;
 use axum::http::{HeaderName, Request};
+use axum::middleware;
 use axum::routing::{any, get, post};
 use axum::Router;
 use config::Config;
-use sqlx::sqlite::SqlitePoolOptions;
 use state::AppState;
 use std::sync::Arc;
+use std::time::Duration;
 use tower_http::request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer};
 use tower_http::trace::TraceLayer;
 use tracing_subscriber::EnvFilter;
 
 const REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+/// In-flight requests get this long to finish after a shutdown signal; with
+/// the relay drain this stays under Docker's default 10s stop grace period
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+const RELAY_DRAIN_TIMEOUT: Duration = Duration::from_secs(3);
+/// Auth and workspace requests are small JSON documents
+const API_BODY_LIMIT_BYTES: usize = 64 * 1024;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -41,10 +50,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     std::fs::create_dir_all(&config.data_dir)?;
 
-    let pool = SqlitePoolOptions::new()
-        .max_connections(5)
-        .connect(&config.db_url)
-        .await?;
+    let pool = db::connect(&config).await?;
     db::init_db(&pool).await?;
 
     let bind_addr = config.bind.parse().map_err(|_| "invalid LUMINA_BIND")?;
@@ -54,8 +60,12 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         config,
         relay: state::RelayHub::new(),
         metrics: Arc::new(state::ServerMetrics::new()),
+        dav_locks: state::DavLocks::new(),
     };
 
+    let relay = state.relay.clone();
+    let pool = state.pool.clone();
+
     let trace_layer = TraceLayer::new_for_http().make_span_with(|req: &Request<_>| {
         let request_id = req
             .headers()
@@ -70,9 +80,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         )
     });
 
-    let app = Router::new()
-        .route("/health", get(routes::health))
-        .route("/metrics", get(routes::metrics))
+    // JSON endpoints only take small bodies; DAV PUT streams with its own limit
+    let api = Router::new()
         .route("/auth/register", post(routes::register))
         .route("/auth/login", post(routes::login))
         .route("/auth/refresh", post(routes::refresh))
@@ -80,17 +89,81 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             "/workspaces",
             get(routes::list_workspaces).post(routes::create_workspace),
         )
+        .layer(DefaultBodyLimit::max(API_BODY_LIMIT_BYTES));
+    let app = Router::new()
+        .route("/health", get(routes::health))
+        .route("/metrics", get(routes::metrics))
+        .merge(api)
         .route("/relay", get(relay::relay_handler))
         .route("/dav/:workspace_id", any(dav::handle_dav_root))
         .route("/dav/:workspace_id/*path", any(dav::handle_dav_path))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            routes::track_http_metrics,
+        ))
         .with_state(state)
         .layer(PropagateRequestIdLayer::new(REQUEST_ID_HEADER))
         .layer(SetRequestIdLayer::new(REQUEST_ID_HEADER, MakeRequestUuid))
         .layer(trace_layer);
     tracing::info!("Lumina Sync Server listening on {}", bind_addr);
-    axum::Server::bind(&bind_addr)
+    let server = axum::Server::bind(&bind_addr)
         .serve(app.into_make_service())
-        .await?;
+        .with_graceful_shutdown(shutdown_signal(relay.clone()));
+    tokio::pin!(server);
+
+    let mut shutdown = relay.subscribe_shutdown();
+    let deadline = async move {
+        let _ = shutdown.wait_for(|shutdown| *shutdown).await;
+        tokio::time::sleep(SHUTDOWN_TIMEOUT).await;
+    };
+    tokio::select! {
+        result = &mut server => result?,
+        _ = deadline => {
+            tracing::warn!("Graceful shutdown timed out; dropping remaining connections");
+        }
+    }
+
+    // Upgraded relay sockets are not tracked by hyper, wait for them separately
+    if tokio::time::timeout(RELAY_DRAIN_TIMEOUT, relay.closed())
+        .await
+        .is_err()
+    {
+        tracing::warn!("Relay sockets did not close in time");
+    }
+    pool.close().await;
+    tracing::info!("Lumina Sync Server stopped");
 
     Ok(())
 }
+
+/// Resolves on Ctrl-C or SIGTERM and asks relay sockets to close
+async fn shutdown_signal(relay: state::RelayHub) {
+    let ctrl_c = async {
+        if let Err(err) = tokio::signal::ctrl_c().await {
+            tracing::error!("Failed to listen for Ctrl-C: {}", err);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(err) => {
+                tracing::error!("Failed to listen for SIGTERM: {}", err);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+    tracing::info!("Shutdown signal received; draining connections");
+    relay.begin_shutdown();
+}
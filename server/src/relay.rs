@@ -1,22 +1,70 @@
-use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::ws::{close_code, CloseFrame, Message, WebSocket, WebSocketUpgrade};
 use axum::extract::{Query, State};
 use axum::http::HeaderMap;
 use axum::response::Response;
 use base64::Engine;
 use futures_util::{SinkExt, StreamExt};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use serde_json::json;
-use tokio::sync::mpsc;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, RwLock};
 use uuid::Uuid;
 
 use crate::auth::{decode_token, verify_password};
 use crate::db;
 use crate::error::AppError;
-use crate::state::{AppState, RelayPeer};
+/// How long a socket may take to flush its close frame on shutdown
+const CLOSE_FLUSH_TIMEOUT: Duration = Duration::from_secs(2);
+
+use crate::state::{AppState, RelayFrame, RelayKey, RelayPeer, DEFAULT_RELAY_CHANNEL};
 
 #[derive(Debug, Deserialize)]
 pub struct RelayQuery {
     pub client: String,
+    /// Carry several channels (one per workspace) over this socket
+    #[serde(default)]
+    pub mux: bool,
+    /// Channel of a non-multiplexed connection
+    #[serde(default)]
+    pub channel: Option<String>,
+}
+
+/// Envelope of every text frame on a multiplexed socket
+///
+/// `open` subscribes the socket to a channel, `close` leaves it, and `data`
+/// carries one relayed message. Messages of non-multiplexed peers are sent
+/// without the envelope on their single channel.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "op", rename_all = "snake_case")]
+enum MuxFrame {
+    Open {
+        channel: String,
+    },
+    Close {
+        channel: String,
+    },
+    Data {
+        channel: String,
+        message: serde_json::Value,
+    },
+}
+
+/// Whether this socket is a desktop or a mobile
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Side {
+    Desktop,
+    Mobile,
+}
+
+impl Side {
+    fn as_str(self) -> &'static str {
+        match self {
+            Side::Desktop => "desktop",
+            Side::Mobile => "mobile",
+        }
+    }
 }
 
 pub async fn relay_handler(
@@ -25,12 +73,15 @@ pub async fn relay_handler(
     Query(query): Query<RelayQuery>,
     headers: HeaderMap,
 ) -> Result<Response, AppError> {
-    let client = query.client.to_lowercase();
-    if client != "mobile" && client != "desktop" {
-        return Err(AppError::BadRequest(
-            "client must be mobile or desktop".to_string(),
-        ));
-    }
+    let side = match query.client.to_lowercase().as_str() {
+        "desktop" => Side::Desktop,
+        "mobile" => Side::Mobile,
+        _ => {
+            return Err(AppError::BadRequest(
+                "client must be mobile or desktop".to_string(),
+            ))
+        }
+    };
 
     let user_id = authorize_request(&state, &headers).await?;
     let connections = state.metrics.inc_relay_connections();
@@ -39,49 +90,168 @@ pub async fn relay_handler(
         event = "relay_connection",
         connections,
         user_id = %user_id,
-        client = %client
+        client = side.as_str(),
+        mux = query.mux
     );
 
     Ok(ws.on_upgrade(move |socket| async move {
-        handle_socket(state, socket, user_id, client).await;
+        handle_socket(state, socket, user_id, side, query).await;
     }))
 }
 
-async fn handle_socket(state: AppState, socket: WebSocket, user_id: String, client: String) {
-    let (mut ws_tx, mut ws_rx) = socket.split();
-    let (tx, mut rx) = mpsc::unbounded_channel::<Message>();
+/// One relay socket and the channels it has joined
+struct Connection {
+    state: AppState,
+    user_id: String,
+    side: Side,
+    peer: RelayPeer,
+    channels: HashSet<String>,
+}
 
-    let peer_id = Uuid::new_v4().to_string();
-    let peer = RelayPeer {
-        id: peer_id.clone(),
-        sender: tx.clone(),
-        connected_at: std::time::Instant::now(),
-    };
+impl Connection {
+    fn own_map(&self) -> &Arc<RwLock<HashMap<RelayKey, RelayPeer>>> {
+        match self.side {
+            Side::Desktop => &self.state.relay.desktops,
+            Side::Mobile => &self.state.relay.mobiles,
+        }
+    }
 
-    if client == "desktop" {
-        state
-            .relay
-            .desktops
-            .write()
-            .await
-            .insert(user_id.clone(), peer);
-        if let Some(mobile) = state.relay.mobiles.read().await.get(&user_id) {
-            let _ = mobile.sender.send(Message::Text(
-                json!({ "type": "paired", "data": { "session_id": "" } }).to_string(),
-            ));
-        }
-    } else {
-        state
-            .relay
-            .mobiles
+    fn other_map(&self) -> &Arc<RwLock<HashMap<RelayKey, RelayPeer>>> {
+        match self.side {
+            Side::Desktop => &self.state.relay.mobiles,
+            Side::Mobile => &self.state.relay.desktops,
+        }
+    }
+
+    fn key(&self, channel: &str) -> RelayKey {
+        (self.user_id.clone(), channel.to_string())
+    }
+
+    async fn join(&mut self, channel: String) {
+        let key = self.key(&channel);
+        self.own_map()
             .write()
             .await
-            .insert(user_id.clone(), peer);
-        if state.relay.desktops.read().await.get(&user_id).is_some() {
-            let _ = tx.send(Message::Text(
-                json!({ "type": "paired", "data": { "session_id": "" } }).to_string(),
-            ));
+            .insert(key.clone(), self.peer.clone());
+        let paired = json!({ "type": "paired", "data": { "session_id": "" } }).to_string();
+        if let Some(other) = self.other_map().read().await.get(&key) {
+            match self.side {
+                Side::Desktop => other.send(&channel, Message::Text(paired)),
+                Side::Mobile => self.peer.send(&channel, Message::Text(paired)),
+            };
+        }
+        self.channels.insert(channel);
+    }
+
+    async fn leave(&mut self, channel: &str) {
+        if !self.channels.remove(channel) {
+            return;
+        }
+        let key = self.key(channel);
+        let mut own = self.own_map().write().await;
+        if own.get(&key).is_some_and(|p| p.id == self.peer.id) {
+            own.remove(&key);
+        }
+    }
+
+    /// Forwards a message to the other side of `channel`
+    async fn forward(&self, channel: &str, text: String) {
+        if !self.channels.contains(channel) {
+            self.send_error(channel, "Channel not open");
+            return;
+        }
+        let delivered = match self.other_map().read().await.get(&self.key(channel)) {
+            Some(other) => other.send(channel, Message::Text(text)),
+            None if self.side == Side::Mobile => {
+                self.send_error(channel, "Desktop offline");
+                return;
+            }
+            None => return,
+        };
+        if !delivered {
+            let failures = self.state.metrics.inc_relay_failures();
+            tracing::warn!(
+                target: "metrics",
+                event = "relay_channel_congested",
+                failures,
+                user_id = %self.user_id,
+                client = self.side.as_str(),
+                channel = %channel
+            );
+            self.send_error(channel, "Channel congested");
+        }
+    }
+
+    fn send_error(&self, channel: &str, message: &str) {
+        self.peer.send(
+            channel,
+            Message::Text(json!({ "type": "error", "data": { "message": message } }).to_string()),
+        );
+    }
+
+    async fn handle_text(&mut self, text: String) {
+        if !self.peer.multiplexed {
+            let channel = self.channels.iter().next().cloned().unwrap_or_default();
+            self.forward(&channel, text).await;
+            return;
+        }
+        match serde_json::from_str::<MuxFrame>(&text) {
+            Ok(MuxFrame::Open { channel }) => self.join(channel).await,
+            Ok(MuxFrame::Close { channel }) => self.leave(&channel).await,
+            Ok(MuxFrame::Data { channel, message }) => {
+                let text = match message {
+                    serde_json::Value::String(text) => text,
+                    other => other.to_string(),
+                };
+                self.forward(&channel, text).await;
+            }
+            Err(_) => self.send_error(DEFAULT_RELAY_CHANNEL, "Invalid relay frame"),
+        }
+    }
+}
+
+/// Wire form of a queued frame for a multiplexed socket
+fn wrap_frame(frame: RelayFrame) -> Message {
+    match frame.message {
+        Message::Text(text) => {
+            let message = serde_json::from_str(&text).unwrap_or(serde_json::Value::String(text));
+            let envelope = MuxFrame::Data {
+                channel: frame.channel,
+                message,
+            };
+            Message::Text(serde_json::to_string(&envelope).unwrap_or_default())
         }
+        other => other,
+    }
+}
+
+async fn handle_socket(
+    state: AppState,
+    socket: WebSocket,
+    user_id: String,
+    side: Side,
+    query: RelayQuery,
+) {
+    let (mut ws_tx, mut ws_rx) = socket.split();
+    let (tx, mut rx) = mpsc::unbounded_channel::<RelayFrame>();
+    let peer = RelayPeer::new(Uuid::new_v4().to_string(), tx, query.mux);
+    let mut shutdown = state.relay.subscribe_shutdown();
+
+    let mut connection = Connection {
+        state: state.clone(),
+        user_id: user_id.clone(),
+        side,
+        peer: peer.clone(),
+        channels: HashSet::new(),
+    };
+    if !query.mux {
+        connection
+            .join(
+                query
+                    .channel
+                    .unwrap_or_else(|| DEFAULT_RELAY_CHANNEL.to_string()),
+            )
+            .await;
     }
 
     let active = state.metrics.inc_relay_active();
@@ -90,18 +260,46 @@ async fn handle_socket(state: AppState, socket: WebSocket, user_id: String, clie
         event = "relay_connected",
         active,
         user_id = %user_id,
-        client = %client
+        client = side.as_str()
     );
 
-    let send_task = tokio::spawn(async move {
-        while let Some(msg) = rx.recv().await {
-            if ws_tx.send(msg).await.is_err() {
+    let writer_peer = peer.clone();
+    let mut send_task = tokio::spawn(async move {
+        while let Some(frame) = rx.recv().await {
+            let channel = frame.channel.clone();
+            let closing = matches!(frame.message, Message::Close(_));
+            let message = if writer_peer.multiplexed {
+                wrap_frame(frame)
+            } else {
+                frame.message
+            };
+            let result = ws_tx.send(message).await;
+            writer_peer.sent(&channel);
+            if result.is_err() || closing {
                 break;
             }
         }
     });
 
-    while let Some(message) = ws_rx.next().await {
+    let mut shutting_down = false;
+    loop {
+        let message = tokio::select! {
+            message = ws_rx.next() => message,
+            _ = shutdown.wait_for(|shutdown| *shutdown) => {
+                shutting_down = true;
+                peer.send(
+                    DEFAULT_RELAY_CHANNEL,
+                    Message::Close(Some(CloseFrame {
+                        code: close_code::AWAY,
+                        reason: "server shutting down".into(),
+                    })),
+                );
+                break;
+            }
+        };
+        let Some(message) = message else {
+            break;
+        };
         let msg = match message {
             Ok(msg) => msg,
             Err(err) => {
@@ -111,55 +309,30 @@ async fn handle_socket(state: AppState, socket: WebSocket, user_id: String, clie
                     event = "relay_ws_error",
                     failures,
                     user_id = %user_id,
-                    client = %client,
+                    client = side.as_str(),
                     error = %err
                 );
                 break;
             }
         };
         match msg {
-            Message::Text(text) => {
-                if client == "mobile" {
-                    if let Some(peer) = state.relay.desktops.read().await.get(&user_id) {
-                        let _ = peer.sender.send(Message::Text(text));
-                    } else {
-                        let _ = tx.send(Message::Text(
-                            json!({ "type": "error", "data": { "message": "Desktop offline" } })
-                                .to_string(),
-                        ));
-                    }
-                } else if let Some(peer) = state.relay.mobiles.read().await.get(&user_id) {
-                    let _ = peer.sender.send(Message::Text(text));
-                }
-            }
+            Message::Text(text) => connection.handle_text(text).await,
             Message::Close(_) => break,
             Message::Ping(payload) => {
-                let _ = tx.send(Message::Pong(payload));
+                peer.send(DEFAULT_RELAY_CHANNEL, Message::Pong(payload));
             }
             _ => {}
         }
     }
 
+    if shutting_down {
+        let _ = tokio::time::timeout(CLOSE_FLUSH_TIMEOUT, &mut send_task).await;
+    }
     send_task.abort();
 
-    if client == "desktop" {
-        let mut desktops = state.relay.desktops.write().await;
-        if desktops
-            .get(&user_id)
-            .map(|p| p.id == peer_id)
-            .unwrap_or(false)
-        {
-            desktops.remove(&user_id);
-        }
-    } else {
-        let mut mobiles = state.relay.mobiles.write().await;
-        if mobiles
-            .get(&user_id)
-            .map(|p| p.id == peer_id)
-            .unwrap_or(false)
-        {
-            mobiles.remove(&user_id);
-        }
+    let channels: Vec<String> = connection.channels.iter().cloned().collect();
+    for channel in channels {
+        connection.leave(&channel).await;
     }
 
     let active = state.metrics.dec_relay_active();
@@ -168,7 +341,7 @@ async fn handle_socket(state: AppState, socket: WebSocket, user_id: String, clie
         event = "relay_disconnected",
         active,
         user_id = %user_id,
-        client = %client
+        client = side.as_str()
     );
 }
 
@@ -204,3 +377,116 @@ async fn authorize_request(state: &AppState, headers: &HeaderMap) -> Result<Stri
 
     Err(AppError::Unauthorized)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::RELAY_CHANNEL_QUEUE_LIMIT;
+
+    #[test]
+    fn slow_channel_does_not_block_others() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let peer = RelayPeer::new("p".to_string(), tx, true);
+
+        for _ in 0..RELAY_CHANNEL_QUEUE_LIMIT {
+            assert!(peer.send("vault-a", Message::Text("{}".to_string())));
+        }
+        assert!(!peer.send("vault-a", Message::Text("{}".to_string())));
+        assert!(peer.send("vault-b", Message::Text("{}".to_string())));
+
+        let frame = rx.try_recv().unwrap();
+        peer.sent(&frame.channel);
+        assert!(peer.send("vault-a", Message::Text("{}".to_string())));
+    }
+
+    #[test]
+    fn wraps_frames_in_channel_envelopes() {
+        let wrapped = wrap_frame(RelayFrame {
+            channel: "vault-a".to_string(),
+            message: Message::Text(r#"{"type":"paired"}"#.to_string()),
+        });
+        let Message::Text(text) = wrapped else {
+            panic!("expected a text frame");
+        };
+        assert_eq!(
+            serde_json::from_str::<MuxFrame>(&text).unwrap(),
+            MuxFrame::Data {
+                channel: "vault-a".to_string(),
+                message: json!({ "type": "paired" }),
+            }
+        );
+
+        let open: MuxFrame = serde_json::from_str(r#"{"op":"open","channel":"vault-b"}"#).unwrap();
+        assert_eq!(
+            open,
+            MuxFrame::Open {
+                channel: "vault-b".to_string()
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn shutdown_waits_for_sockets_to_exit() {
+        let hub = crate::state::RelayHub::new();
+        let mut socket = hub.subscribe_shutdown();
+        let closed = hub.clone();
+        let closed = tokio::spawn(async move { closed.closed().await });
+
+        hub.begin_shutdown();
+        socket.wait_for(|shutdown| *shutdown).await.unwrap();
+        assert!(!closed.is_finished());
+
+        drop(socket);
+        tokio::time::timeout(Duration::from_secs(1), closed)
+            .await
+            .unwrap()
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn routes_by_channel() {
+        let state = crate::routes::tests::test_state().await;
+        let (desktop_tx, mut desktop_rx) = mpsc::unbounded_channel();
+        let (mobile_tx, mut mobile_rx) = mpsc::unbounded_channel();
+        let mut desktop = Connection {
+            state: state.clone(),
+            user_id: "u".to_string(),
+            side: Side::Desktop,
+            peer: RelayPeer::new("d".to_string(), desktop_tx, true),
+            channels: HashSet::new(),
+        };
+        let mut mobile = Connection {
+            state,
+            user_id: "u".to_string(),
+            side: Side::Mobile,
+            peer: RelayPeer::new("m".to_string(), mobile_tx, false),
+            channels: HashSet::new(),
+        };
+
+        desktop
+            .handle_text(r#"{"op":"open","channel":"vault-a"}"#.to_string())
+            .await;
+        desktop
+            .handle_text(r#"{"op":"open","channel":"vault-b"}"#.to_string())
+            .await;
+        mobile.join("vault-b".to_string()).await;
+        let paired = mobile_rx.try_recv().unwrap();
+        assert_eq!(paired.channel, "vault-b");
+
+        mobile.handle_text(r#"{"type":"ping"}"#.to_string()).await;
+        let frame = desktop_rx.try_recv().unwrap();
+        assert_eq!(frame.channel, "vault-b");
+
+        desktop
+            .handle_text(r#"{"op":"data","channel":"vault-a","message":{"type":"x"}}"#.to_string())
+            .await;
+        assert!(mobile_rx.try_recv().is_err());
+        desktop
+            .handle_text(r#"{"op":"data","channel":"vault-b","message":{"type":"x"}}"#.to_string())
+            .await;
+        let Message::Text(text) = mobile_rx.try_recv().unwrap().message else {
+            panic!("expected a text frame");
+        };
+        assert_eq!(text, r#"{"type":"x"}"#);
+    }
+}
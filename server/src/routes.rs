@@ -1,6 +1,8 @@
-use axum::extract::{Json, State};
-use axum::http::{HeaderMap, StatusCode};
-use axum::response::IntoResponse;
+use axum::extract::{Json, Query, State};
+use axum::http::{header::CONTENT_TYPE, HeaderMap, Request, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use serde::Deserialize;
 use serde_json::json;
 
 use crate::auth::{create_token, decode_token, hash_password, verify_password};
@@ -10,14 +12,92 @@ use crate::models::{
     AuthResponse, CreateWorkspaceRequest, LoginRequest, RegisterRequest, TokenResponse,
     UserSummary, WorkspaceSummary,
 };
-use crate::state::AppState;
+use crate::state::{AppState, HttpRequestLabels, MetricsWorkspace, UNKNOWN_WORKSPACE_LABEL};
+
+/// Methods reported as-is in metrics; anything else is counted as `OTHER`
+const METRIC_METHODS: [&str; 14] = [
+    "GET",
+    "HEAD",
+    "POST",
+    "PUT",
+    "PATCH",
+    "DELETE",
+    "OPTIONS",
+    "PROPFIND",
+    "PROPPATCH",
+    "MKCOL",
+    "COPY",
+    "MOVE",
+    "LOCK",
+    "UNLOCK",
+];
+
+#[derive(Debug, Default, Deserialize)]
+pub struct MetricsQuery {
+    /// `prometheus` for the text exposition format, JSON otherwise
+    pub format: Option<String>,
+}
 
 pub async fn health() -> impl IntoResponse {
     (StatusCode::OK, Json(json!({ "status": "ok" })))
 }
 
-pub async fn metrics(State(state): State<AppState>) -> impl IntoResponse {
-    (StatusCode::OK, Json(state.metrics.snapshot()))
+/// Server metrics; only served with the `LUMINA_METRICS_TOKEN` bearer token
+pub async fn metrics(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<MetricsQuery>,
+) -> Response {
+    let Some(expected) = state.config.metrics_token.as_deref() else {
+        return AppError::NotFound.into_response();
+    };
+    if extract_bearer(&headers).as_deref() != Some(expected) {
+        return AppError::Unauthorized.into_response();
+    }
+    if query.format.as_deref() == Some("prometheus") {
+        return (
+            StatusCode::OK,
+            [(CONTENT_TYPE, "text/plain; version=0.0.4; charset=utf-8")],
+            state.metrics.render_prometheus(),
+        )
+            .into_response();
+    }
+    (StatusCode::OK, Json(state.metrics.snapshot())).into_response()
+}
+
+/// Counts every request by method, response status and DAV workspace
+///
+/// The workspace label is only set by the DAV handler after the caller is
+/// authorized for it; other DAV requests are counted as `unknown`.
+pub async fn track_http_metrics<B>(
+    State(state): State<AppState>,
+    mut req: Request<B>,
+    next: Next<B>,
+) -> Response {
+    let method = req.method().as_str();
+    let method = METRIC_METHODS
+        .iter()
+        .find(|known| **known == method)
+        .unwrap_or(&"OTHER")
+        .to_string();
+    let is_dav = req.uri().path() == "/dav" || req.uri().path().starts_with("/dav/");
+    let authorized = MetricsWorkspace::default();
+    req.extensions_mut().insert(authorized.clone());
+    let response = next.run(req).await;
+    state.metrics.record_http_request(HttpRequestLabels {
+        method,
+        status: response.status().as_u16(),
+        workspace: workspace_label(is_dav, &authorized),
+    });
+    response
+}
+
+fn workspace_label(is_dav: bool, authorized: &MetricsWorkspace) -> String {
+    match authorized.get() {
+        Some(workspace_id) => workspace_id.to_string(),
+        None if is_dav => UNKNOWN_WORKSPACE_LABEL.to_string(),
+        None => String::new(),
+    }
 }
 
 pub async fn register(
@@ -144,15 +224,15 @@ fn extract_bearer(headers: &HeaderMap) -> Option<String> {
 }
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::*;
     use crate::config::Config;
     use crate::db;
-    use crate::state::{RelayHub, ServerMetrics};
+    use crate::state::{DavLocks, RelayHub, ServerMetrics};
     use axum::http::{header::AUTHORIZATION, HeaderValue};
     use sqlx::sqlite::SqlitePoolOptions;
     use std::sync::Arc;
-    async fn test_state() -> AppState {
+    pub(crate) async fn test_state() -> AppState {
         let data_dir = std::env::temp_dir().join(format!("lumina-test-{}", uuid::Uuid::new_v4()));
         std::fs::create_dir_all(&data_dir).unwrap();
 
@@ -170,9 +250,14 @@ mod tests {
                 db_url: "sqlite::memory:".to_string(),
                 data_dir: data_dir.display().to_string(),
                 jwt_secret: "test-secret".to_string(),
+                db_max_connections: 1,
+                db_busy_timeout: std::time::Duration::from_secs(5),
+                max_upload_bytes: 1024 * 1024,
+                metrics_token: Some("metrics-secret".to_string()),
             },
             relay: RelayHub::new(),
             metrics: Arc::new(ServerMetrics::new()),
+            dav_locks: DavLocks::new(),
         }
     }
 
@@ -185,6 +270,85 @@ mod tests {
         headers
     }
 
+    #[tokio::test]
+    async fn metrics_render_in_prometheus_format() {
+        let state = test_state().await;
+        let workspace = uuid::Uuid::new_v4().to_string();
+        for status in [207, 207, 404] {
+            state.metrics.record_http_request(HttpRequestLabels {
+                method: "PROPFIND".to_string(),
+                status,
+                workspace: workspace.clone(),
+            });
+        }
+        state.metrics.add_dav_bytes_in(42);
+        state.metrics.inc_relay_active();
+
+        let response = metrics(
+            State(state),
+            auth_headers("metrics-secret"),
+            Query(MetricsQuery {
+                format: Some("prometheus".to_string()),
+            }),
+        )
+        .await;
+        assert!(response.headers()[CONTENT_TYPE]
+            .to_str()
+            .unwrap()
+            .starts_with("text/plain"));
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+
+        assert!(body.contains(&format!(
+            "lumina_http_requests_total{{method=\"PROPFIND\",status=\"207\",workspace=\"{}\"}} 2",
+            workspace
+        )));
+        assert!(body.contains("# TYPE lumina_active_relay_connections gauge"));
+        assert!(body.contains("lumina_active_relay_connections 1"));
+        assert!(body.contains("lumina_dav_bytes_transferred_total{direction=\"in\"} 42"));
+    }
+
+    #[tokio::test]
+    async fn metrics_require_the_configured_token() {
+        let mut state = test_state().await;
+        let query = || Query(MetricsQuery { format: None });
+
+        let response = metrics(State(state.clone()), HeaderMap::new(), query()).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let response = metrics(State(state.clone()), auth_headers("wrong"), query()).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        state.config.metrics_token = None;
+        let response = metrics(State(state), auth_headers("metrics-secret"), query()).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[test]
+    fn only_authorized_workspaces_become_labels() {
+        let authorized = MetricsWorkspace::default();
+        assert_eq!(workspace_label(true, &authorized), "unknown");
+        assert_eq!(workspace_label(false, &authorized), "");
+
+        let id = uuid::Uuid::new_v4().to_string();
+        authorized.set(&id);
+        assert_eq!(workspace_label(true, &authorized), id);
+    }
+
+    #[test]
+    fn workspace_labels_are_capped() {
+        let metrics = ServerMetrics::new();
+        for _ in 0..=crate::state::MAX_WORKSPACE_LABELS {
+            metrics.record_http_request(HttpRequestLabels {
+                method: "GET".to_string(),
+                status: 200,
+                workspace: uuid::Uuid::new_v4().to_string(),
+            });
+        }
+
+        let body = metrics.render_prometheus();
+        assert!(body.contains("workspace=\"other\"} 1"));
+    }
+
     #[tokio::test]
     async fn register_returns_user_and_default_workspace() {
         let state = test_state().await;
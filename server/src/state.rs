@@ -1,27 +1,92 @@
 use std::collections::HashMap;
+use std::fmt::Write as _;
 use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::Arc;
-use std::time::Instant;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
 
 use axum::extract::ws::Message;
 use serde::Serialize;
 use sqlx::SqlitePool;
-use tokio::sync::{mpsc, RwLock};
+use tokio::sync::{mpsc, watch, RwLock};
 
 use crate::config::Config;
 
+/// Channel used by connections that do not multiplex
+pub const DEFAULT_RELAY_CHANNEL: &str = "";
+/// Frames one channel may have queued on a socket; further frames for that
+/// channel are dropped so a slow channel cannot stall the others
+pub const RELAY_CHANNEL_QUEUE_LIMIT: usize = 64;
+
+/// A message queued for a relay socket, tagged with its channel
+#[derive(Debug)]
+pub struct RelayFrame {
+    pub channel: String,
+    pub message: Message,
+}
+
 #[derive(Clone)]
 pub struct RelayPeer {
     pub id: String,
-    pub sender: mpsc::UnboundedSender<Message>,
+    pub sender: mpsc::UnboundedSender<RelayFrame>,
+    /// Frames are wrapped in channel envelopes on the wire
+    pub multiplexed: bool,
+    /// Frames queued but not yet written, per channel
+    queued: Arc<Mutex<HashMap<String, usize>>>,
     #[allow(dead_code)]
     pub connected_at: Instant,
 }
 
+impl RelayPeer {
+    pub fn new(id: String, sender: mpsc::UnboundedSender<RelayFrame>, multiplexed: bool) -> Self {
+        Self {
+            id,
+            sender,
+            multiplexed,
+            queued: Arc::new(Mutex::new(HashMap::new())),
+            connected_at: Instant::now(),
+        }
+    }
+
+    /// Queues `message` on `channel`; returns false when the channel is over
+    /// its limit or the socket is gone
+    pub fn send(&self, channel: &str, message: Message) -> bool {
+        let mut queued = self.queued.lock().unwrap_or_else(|err| err.into_inner());
+        let count = queued.entry(channel.to_string()).or_default();
+        if *count >= RELAY_CHANNEL_QUEUE_LIMIT {
+            return false;
+        }
+        let frame = RelayFrame {
+            channel: channel.to_string(),
+            message,
+        };
+        if self.sender.send(frame).is_err() {
+            return false;
+        }
+        *count += 1;
+        true
+    }
+
+    /// Called by the socket writer once a frame of `channel` is written
+    pub fn sent(&self, channel: &str) {
+        let mut queued = self.queued.lock().unwrap_or_else(|err| err.into_inner());
+        if let Some(count) = queued.get_mut(channel) {
+            *count = count.saturating_sub(1);
+            if *count == 0 {
+                queued.remove(channel);
+            }
+        }
+    }
+}
+
+/// `(user_id, channel)`
+pub type RelayKey = (String, String);
+
 #[derive(Clone)]
 pub struct RelayHub {
-    pub desktops: Arc<RwLock<HashMap<String, RelayPeer>>>,
-    pub mobiles: Arc<RwLock<HashMap<String, RelayPeer>>>,
+    pub desktops: Arc<RwLock<HashMap<RelayKey, RelayPeer>>>,
+    pub mobiles: Arc<RwLock<HashMap<RelayKey, RelayPeer>>>,
+    /// Flipped to true on server shutdown; every open socket holds a receiver
+    shutdown: Arc<watch::Sender<bool>>,
 }
 
 impl RelayHub {
@@ -29,8 +94,24 @@ impl RelayHub {
         Self {
             desktops: Arc::new(RwLock::new(HashMap::new())),
             mobiles: Arc::new(RwLock::new(HashMap::new())),
+            shutdown: Arc::new(watch::channel(false).0),
         }
     }
+
+    /// Held by a socket for its lifetime; resolves `wait_for` on shutdown
+    pub fn subscribe_shutdown(&self) -> watch::Receiver<bool> {
+        self.shutdown.subscribe()
+    }
+
+    /// Asks every relay socket to send a close frame and exit
+    pub fn begin_shutdown(&self) {
+        self.shutdown.send_replace(true);
+    }
+
+    /// Resolves once every relay socket has exited
+    pub async fn closed(&self) {
+        self.shutdown.closed().await;
+    }
 }
 
 #[derive(Clone)]
@@ -39,10 +120,164 @@ pub struct AppState {
     pub config: Config,
     pub relay: RelayHub,
     pub metrics: Arc<ServerMetrics>,
+    pub dav_locks: DavLocks,
+}
+
+/// An exclusive DAV write lock
+#[derive(Debug, Clone)]
+pub struct DavLock {
+    pub token: String,
+    /// Raw `<owner>` XML sent by the client, echoed back in lock discovery
+    pub owner: Option<String>,
+    /// Depth-infinity locks also cover everything below a collection
+    pub depth_infinity: bool,
+    pub timeout: Duration,
+    pub expires_at: Instant,
+}
+
+/// In-memory lock table keyed by `<workspace_id>/<relative path>`
+///
+/// Locks are lost on restart; clients re-lock when their token is rejected.
+#[derive(Clone, Default)]
+pub struct DavLocks {
+    locks: Arc<Mutex<HashMap<String, DavLock>>>,
+}
+
+impl DavLocks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn active(&self) -> std::sync::MutexGuard<'_, HashMap<String, DavLock>> {
+        let mut locks = self.locks.lock().unwrap_or_else(|err| err.into_inner());
+        let now = Instant::now();
+        locks.retain(|_, lock| lock.expires_at > now);
+        locks
+    }
+
+    /// A lock not held via `tokens` that covers `key`: on `key` itself, a
+    /// depth-infinity lock on an ancestor, or with `include_descendants` any
+    /// lock below `key`
+    pub fn conflicting(
+        &self,
+        key: &str,
+        include_descendants: bool,
+        tokens: &[String],
+    ) -> Option<DavLock> {
+        find_conflict(&self.active(), key, include_descendants, tokens)
+    }
+
+    /// Lock `key`, or return the lock that prevents it
+    ///
+    /// The conflict check and the insert happen under one guard, so of two
+    /// concurrent LOCK requests only one can win.
+    pub fn acquire(
+        &self,
+        key: &str,
+        depth_infinity: bool,
+        owner: Option<String>,
+        timeout: Duration,
+    ) -> Result<DavLock, DavLock> {
+        let mut locks = self.active();
+        if let Some(existing) = find_conflict(&locks, key, depth_infinity, &[]) {
+            return Err(existing);
+        }
+        let lock = DavLock {
+            token: format!("opaquelocktoken:{}", uuid::Uuid::new_v4()),
+            owner,
+            depth_infinity,
+            timeout,
+            expires_at: Instant::now() + timeout,
+        };
+        locks.insert(key.to_string(), lock.clone());
+        Ok(lock)
+    }
+
+    /// Extend the lock on `key` held via `token`
+    pub fn refresh(&self, key: &str, token: &str, timeout: Duration) -> Option<DavLock> {
+        let mut locks = self.active();
+        let lock = locks.get_mut(key).filter(|lock| lock.token == token)?;
+        lock.timeout = timeout;
+        lock.expires_at = Instant::now() + timeout;
+        Some(lock.clone())
+    }
+
+    /// Release the lock on `key` if `token` matches
+    pub fn release(&self, key: &str, token: &str) -> bool {
+        let mut locks = self.active();
+        if locks.get(key).is_some_and(|lock| lock.token == token) {
+            locks.remove(key);
+            return true;
+        }
+        false
+    }
+
+    /// Drop all locks on `key` and below, e.g. after the resource was deleted
+    pub fn remove_under(&self, key: &str) {
+        self.active()
+            .retain(|lock_key, _| lock_key != key && !is_below(lock_key, key));
+    }
+}
+
+fn find_conflict(
+    locks: &HashMap<String, DavLock>,
+    key: &str,
+    include_descendants: bool,
+    tokens: &[String],
+) -> Option<DavLock> {
+    locks
+        .iter()
+        .find(|(lock_key, lock)| {
+            let covers = lock_key.as_str() == key
+                || (lock.depth_infinity && is_below(key, lock_key))
+                || (include_descendants && is_below(lock_key, key));
+            covers && !tokens.contains(&lock.token)
+        })
+        .map(|(_, lock)| lock.clone())
+}
+
+fn is_below(key: &str, ancestor: &str) -> bool {
+    key.strip_prefix(ancestor)
+        .is_some_and(|rest| rest.starts_with('/'))
+}
+
+/// Workspace label of DAV requests whose caller was not authorized for it
+pub const UNKNOWN_WORKSPACE_LABEL: &str = "unknown";
+/// Workspaces beyond this many are counted under one label
+pub const MAX_WORKSPACE_LABELS: usize = 1000;
+/// Label for workspaces beyond [`MAX_WORKSPACE_LABELS`]
+pub const OTHER_WORKSPACE_LABEL: &str = "other";
+
+/// Labels of `lumina_http_requests_total`
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct HttpRequestLabels {
+    pub method: String,
+    pub status: u16,
+    /// Workspace id for authorized `/dav/<id>/...` requests, `unknown` for
+    /// other DAV requests, empty otherwise
+    pub workspace: String,
+}
+
+/// Request extension the DAV handler fills in once the caller is authorized
+/// for the workspace, so clients cannot mint metric labels before auth
+#[derive(Debug, Clone, Default)]
+pub struct MetricsWorkspace(Arc<OnceLock<String>>);
+
+impl MetricsWorkspace {
+    pub fn set(&self, workspace_id: &str) {
+        let _ = self.0.set(workspace_id.to_string());
+    }
+
+    pub fn get(&self) -> Option<&str> {
+        self.0.get().map(String::as_str)
+    }
 }
 
 #[derive(Debug, Default)]
 pub struct ServerMetrics {
+    /// Counter per label set; the write lock is only taken the first time a
+    /// label set is seen
+    pub http_requests: std::sync::RwLock<HashMap<HttpRequestLabels, AtomicU64>>,
     pub dav_requests: AtomicU64,
     pub dav_failures: AtomicU64,
     pub dav_bytes_in: AtomicU64,
@@ -80,6 +315,126 @@ impl ServerMetrics {
         }
     }
 
+    pub fn record_http_request(&self, mut labels: HttpRequestLabels) {
+        if let Some(counter) = self
+            .http_requests
+            .read()
+            .unwrap_or_else(|err| err.into_inner())
+            .get(&labels)
+        {
+            counter.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+        let mut requests = self
+            .http_requests
+            .write()
+            .unwrap_or_else(|err| err.into_inner());
+        // Cap the number of workspace label values
+        if is_workspace_id(&labels.workspace)
+            && !requests
+                .keys()
+                .any(|known| known.workspace == labels.workspace)
+        {
+            let workspaces: std::collections::HashSet<&str> = requests
+                .keys()
+                .map(|known| known.workspace.as_str())
+                .filter(|workspace| is_workspace_id(workspace))
+                .collect();
+            if workspaces.len() >= MAX_WORKSPACE_LABELS {
+                labels.workspace = OTHER_WORKSPACE_LABEL.to_string();
+            }
+        }
+        requests
+            .entry(labels)
+            .or_default()
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// All metrics in the Prometheus text exposition format
+    pub fn render_prometheus(&self) -> String {
+        let mut out = String::new();
+
+        write_header(
+            &mut out,
+            "lumina_http_requests_total",
+            "counter",
+            "HTTP requests by method, status and workspace.",
+        );
+        let mut requests: Vec<(HttpRequestLabels, u64)> = self
+            .http_requests
+            .read()
+            .unwrap_or_else(|err| err.into_inner())
+            .iter()
+            .map(|(labels, count)| (labels.clone(), count.load(Ordering::Relaxed)))
+            .collect();
+        requests.sort();
+        for (labels, count) in requests {
+            let _ = writeln!(
+                out,
+                "lumina_http_requests_total{{method=\"{}\",status=\"{}\",workspace=\"{}\"}} {}",
+                escape_label(&labels.method),
+                labels.status,
+                escape_label(&labels.workspace),
+                count
+            );
+        }
+
+        let snapshot = self.snapshot();
+        for (name, kind, help, value) in [
+            (
+                "lumina_dav_requests_total",
+                "counter",
+                "DAV requests received.",
+                snapshot.dav_requests,
+            ),
+            (
+                "lumina_dav_failures_total",
+                "counter",
+                "DAV requests that failed.",
+                snapshot.dav_failures,
+            ),
+            (
+                "lumina_relay_connections_total",
+                "counter",
+                "Relay connections accepted.",
+                snapshot.relay_connections,
+            ),
+            (
+                "lumina_active_relay_connections",
+                "gauge",
+                "Relay connections currently open.",
+                snapshot.relay_active,
+            ),
+            (
+                "lumina_relay_failures_total",
+                "counter",
+                "Relay connections that failed.",
+                snapshot.relay_failures,
+            ),
+        ] {
+            write_header(&mut out, name, kind, help);
+            let _ = writeln!(out, "{} {}", name, value);
+        }
+
+        write_header(
+            &mut out,
+            "lumina_dav_bytes_transferred_total",
+            "counter",
+            "DAV payload bytes by direction.",
+        );
+        let _ = writeln!(
+            out,
+            "lumina_dav_bytes_transferred_total{{direction=\"in\"}} {}",
+            snapshot.dav_bytes_in
+        );
+        let _ = writeln!(
+            out,
+            "lumina_dav_bytes_transferred_total{{direction=\"out\"}} {}",
+            snapshot.dav_bytes_out
+        );
+        out
+    }
+
     pub fn inc_dav_requests(&self) -> u64 {
         self.dav_requests.fetch_add(1, Ordering::Relaxed) + 1
     }
@@ -114,3 +469,20 @@ impl ServerMetrics {
             .saturating_sub(1)
     }
 }
+
+fn write_header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+/// A workspace label that names a workspace rather than a fixed bucket
+fn is_workspace_id(label: &str) -> bool {
+    !label.is_empty() && label != UNKNOWN_WORKSPACE_LABEL && label != OTHER_WORKSPACE_LABEL
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
@@ -4,19 +4,37 @@
 //!
 //! 使用 Forge LoopNode 构建和执行 Agent 循环
 
+use crate::agent::deep_research::progress::{
+    emit_research_event, ProgressTracker, ResearchProgress,
+};
 use crate::agent::deep_research::{
     build_deep_research_graph, DeepResearchConfig, DeepResearchContext, DeepResearchEvent,
     DeepResearchRequest, DeepResearchState, ResearchPhase,
 };
+use crate::agent::event_schema::{event_schema, EventSchema};
 use crate::agent::forge_loop::{
-    build_runtime_with_client, run_forge_loop, ForgeRunResult, ForgeRuntime, TauriEventSink,
+    build_runtime_with_client, build_tool_definitions, run_forge_loop, ForgeRunResult,
+    ForgeRuntime, TauriEventSink,
+};
+use crate::agent::llm_client::config_supports_fc;
+use crate::agent::session_store::{self, SessionSummary, SessionTranscript};
+use crate::agent::skills::{
+    auto_activated_skills, list_skills, load_skills, read_skill, select_skills, skill_context,
+    SkillDetail, SkillInfo,
 };
-use crate::agent::skills::{list_skills, read_skill, SkillDetail, SkillInfo};
 use crate::agent::types::*;
+use crate::agent::xml_tool_calls::build_xml_tool_prompt;
+use crate::forge_runtime::audit::{load_audit_entries, AuditEntry};
+use crate::forge_runtime::journal::{self, UndoReport};
 use crate::forge_runtime::permissions::{
-    default_ruleset, PermissionRule, PermissionSession as LocalPermissionSession,
+    clear_persisted_rules, default_ruleset, load_persisted_rules, readonly_rules, PermissionRule,
+    PermissionSession as LocalPermissionSession, PersistedPermissionRule,
 };
+use crate::forge_runtime::tools::ask_user::{question_from_interrupt, ASK_USER_TOOL};
+use crate::forge_runtime::trace::{load_trace, TraceEntry};
+use crate::llm_pricing::{session_cost, CostSummary};
 use crate::mobile_gateway::{emit_agent_event, MobileGatewayState};
+use crate::vector_db::rerank::{apply_ranking, rerank_documents, RerankConfig};
 use forge::runtime::cancel::CancellationToken;
 use forge::runtime::error::{Interrupt, ResumeCommand};
 use forge::runtime::event::{Event, EventSink, PermissionReply};
@@ -26,7 +44,7 @@ use forge::runtime::session_state::RunStatus;
 use std::collections::VecDeque;
 use std::sync::Arc;
 use std::{fs, path::Path};
-use tauri::{AppHandle, Emitter, Manager, State};
+use tauri::{AppHandle, Manager, State};
 use tokio::sync::Mutex;
 use uuid::Uuid;
 
@@ -54,6 +72,9 @@ struct QueuedTaskRequest {
     task: String,
     context: TaskContext,
     enqueued_at: u64,
+    priority: TaskPriority,
+    /// 被更高优先级任务抢先运行的次数（用于老化）
+    passed_over: usize,
 }
 
 struct PromptStackSnapshot {
@@ -140,15 +161,20 @@ async fn build_queue_snapshot(state: &AgentState) -> AgentQueueSnapshot {
     };
     let queued = {
         let queue = state.queue.lock().await;
-        queue
-            .iter()
+        // 按实际运行顺序列出（老化可能让低优先级任务提前）
+        run_order(&queue)
+            .into_iter()
             .enumerate()
-            .map(|(index, item)| QueuedTaskSummary {
-                id: item.id.clone(),
-                task: task_preview(&item.task, 80),
-                workspace_path: item.context.workspace_path.clone(),
-                enqueued_at: item.enqueued_at,
-                position: index + 1,
+            .map(|(run_index, index)| {
+                let item = &queue[index];
+                QueuedTaskSummary {
+                    id: item.id.clone(),
+                    task: task_preview(&item.task, 80),
+                    workspace_path: item.context.workspace_path.clone(),
+                    enqueued_at: item.enqueued_at,
+                    position: run_index + 1,
+                    priority: item.priority,
+                }
             })
             .collect::<Vec<_>>()
     };
@@ -160,6 +186,139 @@ async fn build_queue_snapshot(state: &AgentState) -> AgentQueueSnapshot {
     }
 }
 
+/// 每被更高优先级任务抢先这么多次，有效优先级提升一级，避免低优先级任务饿死
+const QUEUE_AGING_STEP: usize = 3;
+
+/// 按优先级入队：排在同优先级任务之后、低优先级任务之前，使队列顺序即运行顺序
+fn enqueue_task(queue: &mut VecDeque<QueuedTaskRequest>, item: QueuedTaskRequest) {
+    let index = queue
+        .iter()
+        .position(|queued| queued.priority < item.priority)
+        .unwrap_or(queue.len());
+    queue.insert(index, item);
+}
+
+/// 在 `(priority, passed_over)` 序列中选出下一个运行的下标：有效优先级最高者，相同时取靠前的
+fn pick_next(items: impl Iterator<Item = (TaskPriority, usize)>) -> Option<usize> {
+    items
+        .enumerate()
+        .max_by_key(|(index, (priority, passed_over))| {
+            (
+                priority.rank() + passed_over / QUEUE_AGING_STEP,
+                std::cmp::Reverse(*index),
+            )
+        })
+        .map(|(index, _)| index)
+}
+
+/// 取出下一个要运行的任务：有效优先级最高者，相同时取队列中靠前的
+///
+/// 被抢先的低优先级任务累计 `passed_over`，持续的高优先级任务流也无法让它永远等待。
+fn take_next_queued(queue: &mut VecDeque<QueuedTaskRequest>) -> Option<QueuedTaskRequest> {
+    let index = pick_next(queue.iter().map(|item| (item.priority, item.passed_over)))?;
+    let next = queue.remove(index)?;
+    for waiting in queue.iter_mut() {
+        if waiting.priority < next.priority {
+            waiting.passed_over += 1;
+        }
+    }
+    Some(next)
+}
+
+/// 模拟 `take_next_queued` 依次取空队列，返回按运行顺序排列的队列下标
+fn run_order(queue: &VecDeque<QueuedTaskRequest>) -> Vec<usize> {
+    let mut waiting: Vec<(usize, TaskPriority, usize)> = queue
+        .iter()
+        .enumerate()
+        .map(|(index, item)| (index, item.priority, item.passed_over))
+        .collect();
+    let mut order = Vec::with_capacity(waiting.len());
+    while let Some(next) = pick_next(
+        waiting
+            .iter()
+            .map(|(_, priority, passed_over)| (*priority, *passed_over)),
+    ) {
+        let (index, priority, _) = waiting.remove(next);
+        for (_, waiting_priority, passed_over) in waiting.iter_mut() {
+            if *waiting_priority < priority {
+                *passed_over += 1;
+            }
+        }
+        order.push(index);
+    }
+    order
+}
+
+/// 取消排队中的任务
+///
+/// 查找与移除在同一次加锁内完成：任务若已被 `drain_queued_tasks` 取出开始运行，
+/// 这里找不到它并返回错误，而不会误删其他任务。
+fn cancel_queued_task(
+    queue: &mut VecDeque<QueuedTaskRequest>,
+    id: &str,
+) -> Result<QueuedTaskRequest, String> {
+    let index = queue
+        .iter()
+        .position(|item| item.id == id)
+        .ok_or_else(|| not_queued_error(id))?;
+    Ok(queue.remove(index).expect("index from position"))
+}
+
+/// 把排队中的任务移动到运行顺序中的 `new_position`（从 1 开始，与 `QueuedTaskSummary::position` 一致）
+///
+/// 只在同优先级任务之间移动：优先级决定运行顺序，跨优先级的位置会夹到本优先级的两端。
+/// 返回移动后的实际运行位置。
+fn reorder_queued_task(
+    queue: &mut VecDeque<QueuedTaskRequest>,
+    id: &str,
+    new_position: usize,
+) -> Result<usize, String> {
+    let mut item = cancel_queued_task(queue, id)?;
+    // 队列按优先级排序，同优先级任务是连续的一段
+    let group_start = queue
+        .iter()
+        .position(|queued| queued.priority <= item.priority)
+        .unwrap_or(queue.len());
+    let group_end = queue
+        .iter()
+        .position(|queued| queued.priority < item.priority)
+        .unwrap_or(queue.len());
+    // 目标位置之前运行的同优先级任务数
+    let ahead = run_order(queue)
+        .into_iter()
+        .take(new_position.saturating_sub(1))
+        .filter(|index| (group_start..group_end).contains(index))
+        .count();
+    let index = group_start + ahead;
+
+    // 同优先级内 passed_over 保持不增，老化才不会把手动顺序换回去
+    let floor = queue
+        .range(index..group_end)
+        .map(|queued| queued.passed_over)
+        .max()
+        .unwrap_or(0);
+    let ceiling = queue
+        .range(group_start..index)
+        .map(|queued| queued.passed_over)
+        .min()
+        .unwrap_or(usize::MAX);
+    item.passed_over = item.passed_over.max(floor).min(ceiling);
+    queue.insert(index, item);
+
+    let position = run_order(queue)
+        .iter()
+        .position(|queued| *queued == index)
+        .expect("inserted task is queued");
+    Ok(position + 1)
+}
+
+fn not_queued_error(id: &str) -> String {
+    format!(
+        "Task {} is not queued (it may already be running; use agent_abort to stop it)",
+        id
+    )
+}
+
 async fn emit_queue_updated(app: &AppHandle, state: &AgentState) {
     let snapshot = build_queue_snapshot(state).await;
     emit_agent_event(
@@ -172,13 +331,60 @@ async fn emit_queue_updated(app: &AppHandle, state: &AgentState) {
     );
 }
 
+/// 用交叉编码器重排 RAG 结果；失败或配置不完整时按原顺序保留前 top_n 条
+async fn rerank_rag_results(
+    task: &str,
+    mut results: Vec<RagResult>,
+    config: &RerankConfig,
+) -> Vec<RagResult> {
+    if config.is_active() {
+        let documents: Vec<&str> = results.iter().map(|r| r.content.as_str()).collect();
+        match rerank_documents(task, &documents, config).await {
+            Ok(ranking) if !ranking.is_empty() => {
+                return apply_ranking(results, &ranking, |result, score| result.score = score);
+            }
+            Ok(_) => {}
+            Err(err) => {
+                eprintln!("[Agent] RAG rerank failed, keeping vector order: {}", err);
+            }
+        }
+    }
+    // 前端为重排多取了候选，不能全部放进提示词
+    results.truncate(config.top_n.max(1));
+    results
+}
+
 async fn execute_task_inner(
     app: AppHandle,
     state: &AgentState,
     config: AgentConfig,
     task: String,
-    context: TaskContext,
+    mut context: TaskContext,
 ) -> Result<bool, String> {
+    // 用户已选择 skills 时追加 triggers / globs 命中的 skills（未选择时由 build_initial_messages 自动选择）
+    if !context.skills.is_empty() {
+        let selected: Vec<String> = context.skills.iter().map(|s| s.name.clone()).collect();
+        let activated = auto_activated_skills(
+            &app,
+            Some(&context.workspace_path),
+            &task,
+            context.active_note_path.as_deref(),
+            &selected,
+        );
+        context
+            .skills
+            .extend(activated.into_iter().map(skill_context));
+    }
+
+    if config.rerank.enabled && !context.rag_results.is_empty() {
+        context.rag_results = rerank_rag_results(
+            &task,
+            std::mem::take(&mut context.rag_results),
+            &config.rerank,
+        )
+        .await;
+    }
+
     if let Some(mobile_state) = app.try_state::<MobileGatewayState>() {
         mobile_state
             .set_current_session_id(context.mobile_session_id.clone())
@@ -192,7 +398,18 @@ async fn execute_task_inner(
         dbg::log_skills(&context.skills);
     }
 
-    let (messages, prompt_stack) = build_initial_messages(&app, &task, &context, &config.provider);
+    let (messages, prompt_stack) = build_initial_messages(&app, &task, &context, &config);
+    // 先构建消息再开始录制，避免本次任务被当作历史恢复
+    match context.session_id.as_deref() {
+        Some(id) => {
+            if let Err(err) =
+                session_store::start_recording(Path::new(&context.workspace_path), id, &task)
+            {
+                eprintln!("[Agent] Failed to record session {}: {}", id, err);
+            }
+        }
+        None => session_store::stop_recording(),
+    }
     emit_agent_event(
         &app,
         AgentEvent::PromptStack {
@@ -204,7 +421,7 @@ async fn execute_task_inner(
             skills_index: prompt_stack.skills_index.clone(),
         },
     );
-    let initial_state = GraphState {
+    let mut initial_state = GraphState {
         messages,
         user_task: task.clone(),
         workspace_path: context.workspace_path,
@@ -223,6 +440,7 @@ async fn execute_task_inner(
         auto_approve: config.auto_approve,
         status: AgentStatus::Running,
         error: None,
+        tokens_used: 0,
     };
 
     {
@@ -231,20 +449,62 @@ async fn execute_task_inner(
     }
     emit_queue_updated(&app, state).await;
 
-    let permissions = build_permission_session(config.auto_approve);
+    let permissions = build_permission_session(config.auto_approve, &initial_state.workspace_path);
     let proxy_client = app.state::<crate::proxy::ProxyState>().client().await;
+    let cancel = CancellationToken::new();
     let runtime = build_runtime_with_client(
         &initial_state.workspace_path,
         permissions,
         Some(proxy_client),
+        cancel.clone(),
+        config.dry_run,
+        config
+            .embedding
+            .is_active()
+            .then(|| config.embedding.clone()),
     );
+    if !config_supports_fc(&config) {
+        // 工具定义不会随请求发送，需要在提示词中说明 XML 工具协议
+        let tool_defs = build_tool_definitions(&runtime.registry);
+        initial_state.messages.insert(
+            1,
+            Message {
+                role: MessageRole::System,
+                content: build_xml_tool_prompt(&tool_defs),
+                name: None,
+                tool_call_id: None,
+            },
+        );
+    }
+    if crate::fs::is_workspace_readonly() {
+        initial_state.messages.insert(
+            1,
+            Message {
+                role: MessageRole::System,
+                content: READ_ONLY_PROMPT.to_string(),
+                name: None,
+                tool_call_id: None,
+            },
+        );
+    }
+    if config.dry_run {
+        initial_state.messages.insert(
+            1,
+            Message {
+                role: MessageRole::System,
+                content: DRY_RUN_PROMPT.to_string(),
+                name: None,
+                tool_call_id: None,
+            },
+        );
+    }
     let runtime_state = ForgeRuntimeState {
         config: config.clone(),
         runtime,
         session_id: Uuid::new_v4().to_string(),
         message_id: Uuid::new_v4().to_string(),
         run_id: Uuid::new_v4().to_string(),
-        cancel: CancellationToken::new(),
+        cancel,
     };
 
     {
@@ -288,7 +548,7 @@ async fn drain_queued_tasks(app: AppHandle, state: &AgentState) {
                 None
             } else {
                 let mut queue = state.queue.lock().await;
-                let next = queue.pop_front();
+                let next = take_next_queued(&mut queue);
                 if next.is_some() {
                     *is_running = true;
                 }
@@ -322,7 +582,7 @@ async fn drain_queued_tasks(app: AppHandle, state: &AgentState) {
     }
 }
 
-/// 启动 Agent 任务
+/// 启动 Agent 任务（已有任务运行时按 `priority` 入队，默认 normal）
 #[tauri::command]
 pub async fn agent_start_task(
     app: AppHandle,
@@ -330,6 +590,7 @@ pub async fn agent_start_task(
     config: AgentConfig,
     task: String,
     context: TaskContext,
+    priority: Option<TaskPriority>,
 ) -> Result<(), String> {
     let should_enqueue = {
         let mut is_running = state.is_running.lock().await;
@@ -344,13 +605,18 @@ pub async fn agent_start_task(
     if should_enqueue {
         {
             let mut queue = state.queue.lock().await;
-            queue.push_back(QueuedTaskRequest {
-                id: Uuid::new_v4().to_string(),
-                config,
-                task,
-                context,
-                enqueued_at: now_unix_millis(),
-            });
+            enqueue_task(
+                &mut queue,
+                QueuedTaskRequest {
+                    id: Uuid::new_v4().to_string(),
+                    config,
+                    task,
+                    context,
+                    enqueued_at: now_unix_millis(),
+                    priority: priority.unwrap_or_default(),
+                    passed_over: 0,
+                },
+            );
         }
         emit_queue_updated(&app, &state).await;
         return Ok(());
@@ -421,10 +687,11 @@ pub async fn agent_approve_tool(
     state: State<'_, AgentState>,
     request_id: String,
     approved: bool,
+    always: Option<bool>,
 ) -> Result<(), String> {
     println!(
-        "[Agent] 收到审批响应: request_id={}, approved={}",
-        request_id, approved
+        "[Agent] 收到审批响应: request_id={}, approved={}, always={:?}",
+        request_id, approved, always
     );
 
     let runtime_state = {
@@ -456,7 +723,9 @@ pub async fn agent_approve_tool(
         .cloned()
         .unwrap_or_else(|| request.permission.clone());
 
-    let reply = if approved {
+    let reply = if approved && always.unwrap_or(false) {
+        PermissionReply::Always
+    } else if approved {
         PermissionReply::Once
     } else {
         PermissionReply::Reject
@@ -558,7 +827,68 @@ pub async fn agent_get_queue_status(
     Ok(build_queue_snapshot(&state).await)
 }
 
+/// 取消排队中（尚未运行）的任务；正在运行的任务请使用 `agent_abort`
+#[tauri::command]
+pub async fn agent_cancel_queued(
+    app: AppHandle,
+    state: State<'_, AgentState>,
+    id: String,
+) -> Result<(), String> {
+    {
+        let mut queue = state.queue.lock().await;
+        cancel_queued_task(&mut queue, &id)?;
+    }
+    emit_queue_updated(&app, &state).await;
+    Ok(())
+}
+
+/// 在同优先级任务之间调整顺序（`new_position` 从 1 开始），返回任务实际的运行位置
+#[tauri::command]
+pub async fn agent_reorder_queue(
+    app: AppHandle,
+    state: State<'_, AgentState>,
+    id: String,
+    new_position: usize,
+) -> Result<usize, String> {
+    let position = {
+        let mut queue = state.queue.lock().await;
+        reorder_queued_task(&mut queue, &id, new_position)?
+    };
+    emit_queue_updated(&app, &state).await;
+    Ok(position)
+}
+
+/// 注入用户对 `ask_user` 的回答
+///
+/// 有待处理的 `ask_user` 调用时只作为该调用的工具结果，紧跟在发起调用的
+/// assistant 消息之后；再追加用户消息会让模型把同一回答看成两次输入。
+/// 没有待处理的调用时才作为用户消息。
+fn push_answer(messages: &mut Vec<Message>, pending_calls: &mut Vec<ToolCall>, answer: String) {
+    if pending_calls
+        .first()
+        .is_some_and(|call| call.name == ASK_USER_TOOL)
+    {
+        let asked = pending_calls.remove(0);
+        messages.push(Message {
+            role: MessageRole::Tool,
+            content: format!("The user answered: {}", answer),
+            name: Some(asked.name),
+            tool_call_id: Some(asked.id),
+        });
+    } else {
+        messages.push(Message {
+            role: MessageRole::User,
+            content: answer,
+            name: None,
+            tool_call_id: None,
+        });
+    }
+}
+
 /// 继续任务（用户回答问题后）
+///
+/// `ask_user` 工具会以 question 中断暂停运行并保存检查点；这里把回答注入
+/// 检查点状态（见 `push_answer`），然后从检查点恢复执行。
 #[tauri::command]
 pub async fn agent_continue_with_answer(
     app: AppHandle,
@@ -573,35 +903,55 @@ pub async fn agent_continue_with_answer(
             .clone()
             .ok_or("No active Forge runtime")?
     };
-
-    let resumed_state = {
-        let mut current_state = state.current_state.lock().await;
-        let graph = current_state
-            .as_mut()
-            .ok_or("No active graph state to continue")?;
-        graph.messages.push(Message {
-            role: MessageRole::User,
-            content: answer,
-            name: None,
-            tool_call_id: None,
+    let checkpoint = {
+        let mut checkpoint_lock = state.checkpoint.lock().await;
+        let has_question = checkpoint_lock.as_ref().is_some_and(|checkpoint| {
+            checkpoint
+                .interrupts
+                .iter()
+                .any(|interrupt| question_from_interrupt(&interrupt.value).is_some())
         });
-        graph.status = AgentStatus::Running;
-        graph.clone()
+        if !has_question {
+            return Err("No pending question to answer".to_string());
+        }
+        checkpoint_lock
+            .take()
+            .ok_or("No pending question to answer")?
     };
 
+    let mut resumed_state = checkpoint.state;
+    let mut pending_calls = checkpoint.pending_tool_calls;
+    push_answer(&mut resumed_state.messages, &mut pending_calls, answer);
+    resumed_state.status = AgentStatus::Running;
+
     {
         let mut is_running = state.is_running.lock().await;
         *is_running = true;
     }
+    {
+        let mut current_state = state.current_state.lock().await;
+        if let Some(ref mut current) = *current_state {
+            current.status = AgentStatus::Running;
+        }
+    }
     emit_queue_updated(&app, &state).await;
 
+    let sink = TauriEventSink::new(app.clone());
+    emit_agent_event_safe(
+        &sink,
+        Event::RunResumed {
+            run_id: runtime_state.run_id.clone(),
+            checkpoint_id: checkpoint.checkpoint_id.clone(),
+        },
+    );
+
     let http_client = build_llm_http_client(&app).await?;
     let result = run_forge_loop(
         app.clone(),
         runtime_state.config.clone(),
         resumed_state,
         runtime_state.runtime.clone(),
-        Vec::new(),
+        pending_calls,
         runtime_state.session_id.clone(),
         runtime_state.message_id.clone(),
         runtime_state.cancel.clone(),
@@ -609,10 +959,19 @@ pub async fn agent_continue_with_answer(
     )
     .await;
 
-    let handled = handle_forge_result(app.clone(), &state, runtime_state, result).await?;
-    if handled {
-        drain_queued_tasks(app, &state).await;
+    let handled = handle_forge_result(app.clone(), &state, runtime_state, result).await;
+    match handled {
+        Ok(finished) => {
+            if finished {
+                drain_queued_tasks(app, &state).await;
+            }
+        }
+        Err(err) => {
+            drain_queued_tasks(app.clone(), &state).await;
+            return Err(err);
+        }
     }
+
     Ok(())
 }
 
@@ -637,15 +996,196 @@ pub async fn agent_read_skill(
     read_skill(&app, workspace_path.as_deref(), &name)
 }
 
-fn build_permission_session(auto_approve: bool) -> Arc<LocalPermissionSession> {
-    if auto_approve {
+// ============ 权限规则命令 ============
+
+/// 列出工作区持久化的“始终允许”权限规则
+#[tauri::command]
+pub async fn agent_list_permission_rules(
+    workspace_path: String,
+) -> Result<Vec<PersistedPermissionRule>, String> {
+    load_persisted_rules(Path::new(&workspace_path))
+}
+
+/// 清除工作区持久化的权限规则
+#[tauri::command]
+pub async fn agent_clear_permission_rules(workspace_path: String) -> Result<(), String> {
+    clear_persisted_rules(Path::new(&workspace_path))
+}
+
+// ============ 审计日志命令 ============
+
+/// 获取 Agent 文件操作审计记录（默认为当前会话）
+///
+/// 记录来自工作区的 `.lumina/agent-audit.jsonl`；没有会话时返回空列表。
+#[tauri::command]
+pub async fn agent_get_audit_log(
+    state: State<'_, AgentState>,
+    workspace_path: String,
+    session_id: Option<String>,
+) -> Result<Vec<AuditEntry>, String> {
+    let session_id = match session_id {
+        Some(id) => id,
+        None => match state.runtime.lock().await.as_ref() {
+            Some(runtime) => runtime.session_id.clone(),
+            None => return Ok(Vec::new()),
+        },
+    };
+    load_audit_entries(Path::new(&workspace_path), Some(&session_id))
+}
+
+/// 导出会话的工具调用轨迹（按调用顺序，参数已脱敏），用于提交可复现的问题报告
+///
+/// 轨迹来自工作区的 `.lumina/agent-traces/<session>.jsonl`。
+#[tauri::command]
+pub async fn agent_export_trace(
+    workspace_path: String,
+    session_id: String,
+) -> Result<Vec<TraceEntry>, String> {
+    load_trace(Path::new(&workspace_path), &session_id)
+}
+
+// ============ 撤销命令 ============
+
+/// 撤销会话（默认为当前会话）中 Agent 最近一次文件修改
+///
+/// 若相关文件在 Agent 修改后又被改动，则不做任何修改并在 `conflicts` 中列出这些文件。
+#[tauri::command]
+pub async fn agent_undo_last(
+    state: State<'_, AgentState>,
+    workspace_path: String,
+    session_id: Option<String>,
+) -> Result<UndoReport, String> {
+    let session_id = undo_session_id(&state, session_id).await?;
+    journal::undo_last(Path::new(&workspace_path), &session_id).await
+}
+
+/// 按倒序撤销会话（默认为当前会话）中 Agent 的全部文件修改
+///
+/// 有冲突时整体拒绝，不会只撤销一部分。
+#[tauri::command]
+pub async fn agent_undo_run(
+    state: State<'_, AgentState>,
+    workspace_path: String,
+    session_id: Option<String>,
+) -> Result<UndoReport, String> {
+    let session_id = undo_session_id(&state, session_id).await?;
+    journal::undo_run(Path::new(&workspace_path), &session_id).await
+}
+
+/// 重做最近一次被撤销的文件修改
+#[tauri::command]
+pub async fn agent_redo_last(
+    state: State<'_, AgentState>,
+    workspace_path: String,
+    session_id: Option<String>,
+) -> Result<UndoReport, String> {
+    let session_id = undo_session_id(&state, session_id).await?;
+    journal::redo_last(Path::new(&workspace_path), &session_id).await
+}
+
+/// 撤销/重做的目标会话；Agent 运行中或工作区只读时拒绝
+async fn undo_session_id(state: &AgentState, session_id: Option<String>) -> Result<String, String> {
+    if crate::fs::is_workspace_readonly() {
+        return Err("The workspace is read-only".to_string());
+    }
+    if matches!(
+        state
+            .current_state
+            .lock()
+            .await
+            .as_ref()
+            .map(|current| &current.status),
+        Some(AgentStatus::Running)
+    ) {
+        return Err("Cannot undo while the agent is running".to_string());
+    }
+    match session_id {
+        Some(id) => Ok(id),
+        None => state
+            .runtime
+            .lock()
+            .await
+            .as_ref()
+            .map(|runtime| runtime.session_id.clone())
+            .ok_or_else(|| "No agent session to undo".to_string()),
+    }
+}
+
+// ============ 费用统计命令 ============
+
+/// 获取 Agent 会话的累计 token 与费用（默认为当前会话，无运行中会话时为最近一次）
+#[tauri::command]
+pub async fn agent_get_cost(
+    state: State<'_, AgentState>,
+    session_id: Option<String>,
+) -> Result<Option<CostSummary>, String> {
+    let session_id = match session_id {
+        Some(id) => Some(id),
+        None => state
+            .runtime
+            .lock()
+            .await
+            .as_ref()
+            .map(|runtime| runtime.session_id.clone()),
+    };
+    Ok(session_cost(session_id.as_deref()))
+}
+
+// ============ 会话持久化命令 ============
+
+/// 返回 agent-event / deep-research-event 的事件协议（带版本号），供前端校验
+#[tauri::command]
+pub async fn agent_event_schema() -> Result<EventSchema, String> {
+    Ok(event_schema())
+}
+
+/// 列出工作区内持久化的 Agent 会话（`.lumina/sessions`）
+#[tauri::command]
+pub async fn agent_list_sessions(workspace_path: String) -> Result<Vec<SessionSummary>, String> {
+    session_store::list_sessions(Path::new(&workspace_path))
+}
+
+/// 读取会话的完整事件记录与可用于继续对话的 history
+#[tauri::command]
+pub async fn agent_load_session(
+    workspace_path: String,
+    id: String,
+) -> Result<SessionTranscript, String> {
+    session_store::load_session(Path::new(&workspace_path), &id)
+}
+
+/// 删除持久化的会话
+#[tauri::command]
+pub async fn agent_delete_session(workspace_path: String, id: String) -> Result<(), String> {
+    session_store::delete_session(Path::new(&workspace_path), &id)
+}
+
+fn build_permission_session(
+    auto_approve: bool,
+    workspace_path: &str,
+) -> Arc<LocalPermissionSession> {
+    if crate::fs::is_workspace_readonly() {
+        // 只读模式：不加载持久化的“始终允许”，避免覆盖拒绝规则
+        let mut rules = if auto_approve {
+            vec![PermissionRule::new("*", "*", PermissionDecision::Allow)]
+        } else {
+            default_ruleset()
+        };
+        rules.extend(readonly_rules());
+        Arc::new(LocalPermissionSession::new(rules))
+    } else if auto_approve {
         Arc::new(LocalPermissionSession::new(vec![PermissionRule::new(
             "*",
             "*",
             PermissionDecision::Allow,
         )]))
-    } else {
+    } else if workspace_path.trim().is_empty() {
         Arc::new(LocalPermissionSession::new(default_ruleset()))
+    } else {
+        Arc::new(LocalPermissionSession::with_workspace_persistence(
+            default_ruleset(),
+            workspace_path,
+        ))
     }
 }
 
@@ -653,14 +1193,29 @@ fn build_initial_messages(
     app: &AppHandle,
     task: &str,
     context: &TaskContext,
-    provider: &str,
+    config: &AgentConfig,
 ) -> (Vec<Message>, PromptStackSnapshot) {
+    let provider = config.provider.as_str();
     let base_system = base_system_prompt(provider).to_string();
     let system_prompt = build_system_prompt(context, provider);
     let built_in_agent = load_builtin_agent_instructions(app);
     let workspace_agent = load_workspace_agent_instructions(&context.workspace_path)
         .unwrap_or_else(|| WORKSPACE_AGENT_TEMPLATE.to_string());
-    let skills_index = build_skills_index_content(&context.skills);
+    // 调用方未指定 skills 时，按任务与 RAG / 提及笔记自动选择
+    let auto_skills;
+    let skills = if context.skills.is_empty() && config.skill_top_k > 0 {
+        auto_skills = select_skills(
+            task,
+            context,
+            &load_skills(app, Some(&context.workspace_path)),
+            config.skill_top_k,
+            config.skill_min_score,
+        );
+        &auto_skills
+    } else {
+        &context.skills
+    };
+    let skills_index = build_skills_index_content(skills);
 
     let mut messages = Vec::new();
     messages.push(Message {
@@ -689,7 +1244,17 @@ fn build_initial_messages(
             tool_call_id: None,
         });
     }
-    messages.extend(context.history.clone());
+    if context.history.is_empty() {
+        // 继续已持久化的会话（如应用重启后）
+        if let Some(id) = context.session_id.as_deref() {
+            messages.extend(session_store::load_history(
+                Path::new(&context.workspace_path),
+                id,
+            ));
+        }
+    } else {
+        messages.extend(context.history.clone());
+    }
     messages.push(Message {
         role: MessageRole::User,
         content: task.to_string(),
@@ -736,6 +1301,8 @@ Skills:\n",
     Some(content)
 }
 
+const READ_ONLY_PROMPT: &str = "The workspace is read-only: write, edit and bash are denied. You can still read and search files. Do not attempt to modify anything; answer from what you can read and describe any changes the user would need to make themselves.";
+const DRY_RUN_PROMPT: &str = "Dry run mode: write, edit and bash do not change anything and only report what they would have done. Read tools work normally. Proceed as if each simulated change succeeded, then summarize the planned changes in your final answer.";
 const PROMPT_DEFAULT: &str =
     "You are Lumina, a note assistant. Use the provided tools to read or edit files when needed. Be concise and accurate. Stop calling tools once the task is complete and provide a final answer. If repeated tool calls do not produce new information, ask a clarification question and stop.";
 const PROMPT_OPENAI: &str =
@@ -872,6 +1439,22 @@ fn build_system_prompt(context: &TaskContext, provider: &str) -> String {
         prompt.push_str("\nFile tree:\n");
         prompt.push_str(tree);
     }
+    if !context.rag_results.is_empty() {
+        prompt.push_str("\nRelevant notes (most relevant first):");
+        for result in &context.rag_results {
+            prompt.push_str(&format!(
+                "\n--- {}{} (score {:.2})\n{}",
+                result.file_path,
+                result
+                    .heading
+                    .as_deref()
+                    .map(|heading| format!(" > {}", heading))
+                    .unwrap_or_default(),
+                result.score,
+                result.content.trim()
+            ));
+        }
+    }
     prompt
 }
 
@@ -939,6 +1522,14 @@ async fn handle_forge_result(
             if let Some(pending) = run.pending {
                 final_state.status = AgentStatus::WaitingApproval;
                 let checkpoint_id = Uuid::new_v4().to_string();
+                let questions = pending
+                    .interrupts
+                    .iter()
+                    .filter_map(|interrupt| {
+                        question_from_interrupt(&interrupt.value)
+                            .map(|(question, options)| (interrupt.id.clone(), question, options))
+                    })
+                    .collect::<Vec<_>>();
                 {
                     let mut checkpoint_lock = state.checkpoint.lock().await;
                     *checkpoint_lock = Some(ForgeCheckpoint {
@@ -959,6 +1550,16 @@ async fn handle_forge_result(
                         checkpoint_id,
                     },
                 );
+                for (request_id, question, options) in questions {
+                    emit_agent_event(
+                        &app,
+                        AgentEvent::QuestionAsked {
+                            question,
+                            options,
+                            request_id,
+                        },
+                    );
+                }
                 emit_queue_updated(&app, state).await;
                 return Ok(false);
             }
@@ -1030,6 +1631,8 @@ pub struct DeepResearchStateManager {
     checkpoint: Arc<Mutex<Option<Checkpoint<DeepResearchState>>>>,
     /// 保存当前配置（用于恢复时重建图）
     current_config: Arc<Mutex<Option<DeepResearchConfig>>>,
+    /// 当前研究的进度跟踪（随事件推送给前端）
+    progress: Arc<std::sync::Mutex<ProgressTracker>>,
 }
 
 impl DeepResearchStateManager {
@@ -1038,8 +1641,18 @@ impl DeepResearchStateManager {
             is_running: Arc::new(Mutex::new(false)),
             checkpoint: Arc::new(Mutex::new(None)),
             current_config: Arc::new(Mutex::new(None)),
+            progress: Arc::new(std::sync::Mutex::new(ProgressTracker::default())),
         }
     }
+
+    /// 根据事件更新并返回当前研究进度
+    pub fn observe_progress(&self, event: &DeepResearchEvent) -> ResearchProgress {
+        let mut tracker = self
+            .progress
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        tracker.observe(event, std::time::Instant::now())
+    }
 }
 
 impl Default for DeepResearchStateManager {
@@ -1090,24 +1703,29 @@ pub async fn deep_research_start(
         outline: None,
         report: None,
         report_chunks: vec![],
+        citation_sources: vec![],
         goto: String::new(),
         error: None,
-        clarification: None, // 澄清字段，interrupt 恢复后填充
+        clarifications: vec![], // 澄清历史，每次 interrupt 恢复后追加
     };
 
-    // 发送开始事件
-    let _ = app.emit(
-        "deep-research-event",
-        DeepResearchEvent::PhaseChange {
-            phase: ResearchPhase::Init,
-            message: "开始深度研究...".to_string(),
-        },
-    );
-
     // 创建配置，合并请求中的选项
     let mut final_config = config;
     final_config.report_style = request.report_style;
     final_config.include_citations = request.include_citations;
+    if let Some(preset) = request.report_preset {
+        final_config.report_preset = preset;
+    }
+
+    // 发送开始事件（附带篇幅预设，供界面展示）
+    emit_research_event(
+        &app,
+        &DeepResearchEvent::PhaseChange {
+            phase: ResearchPhase::Init,
+            message: format!("开始深度研究（{}）...", final_config.report_preset.label()),
+            preset: Some(final_config.report_preset),
+        },
+    );
 
     // 保存配置（用于 resume 时重建图）
     {
@@ -1154,10 +1772,7 @@ pub async fn deep_research_start(
                 }
             }
             Err(e) => {
-                let _ = app_clone.emit(
-                    "deep-research-event",
-                    DeepResearchEvent::Error { message: e },
-                );
+                emit_research_event(&app_clone, &DeepResearchEvent::Error { message: e });
                 let mut is_running = state_is_running.lock().await;
                 *is_running = false;
             }
@@ -1220,17 +1835,25 @@ pub async fn deep_research_resume(
         *is_running = true;
     }
 
-    // 更新状态，添加用户澄清
+    // 更新状态，追加本轮用户澄清（保留之前的澄清历史）
     let mut resumed_checkpoint = checkpoint;
-    resumed_checkpoint.state.clarification = Some(clarification.clone());
+    resumed_checkpoint
+        .state
+        .clarifications
+        .push(clarification.clone());
     resumed_checkpoint.state.phase = ResearchPhase::AnalyzingTopic; // 重新进入分析阶段
 
     // 发送恢复事件
-    let _ = app.emit(
-        "deep-research-event",
-        DeepResearchEvent::PhaseChange {
+    emit_research_event(
+        &app,
+        &DeepResearchEvent::PhaseChange {
             phase: ResearchPhase::AnalyzingTopic,
-            message: format!("收到用户澄清，继续研究: {}", clarification),
+            message: format!(
+                "收到第 {} 轮澄清，继续研究: {}",
+                resumed_checkpoint.state.clarifications.len(),
+                clarification
+            ),
+            preset: None,
         },
     );
 
@@ -1248,9 +1871,9 @@ pub async fn deep_research_resume(
         let graph = match build_deep_research_graph(ctx) {
             Ok(g) => g.with_max_iterations(20).with_debug(false),
             Err(e) => {
-                let _ = app_clone.emit(
-                    "deep-research-event",
-                    DeepResearchEvent::Error {
+                emit_research_event(
+                    &app_clone,
+                    &DeepResearchEvent::Error {
                         message: format!("Failed to rebuild graph: {}", e),
                     },
                 );
@@ -1290,16 +1913,18 @@ pub async fn deep_research_resume(
                         checkpoint,
                         interrupts: _,
                     } => {
-                        // 又一次中断（可能需要更多澄清）
+                        // 又一次中断（需要更多澄清）：保存检查点并暂停，等待下一次 resume
                         let mut cp_lock = state_checkpoint.lock().await;
                         *cp_lock = Some(checkpoint);
+                        let mut is_running = state_is_running.lock().await;
+                        *is_running = false;
                     }
                 }
             }
             Err(e) => {
-                let _ = app_clone.emit(
-                    "deep-research-event",
-                    DeepResearchEvent::Error {
+                emit_research_event(
+                    &app_clone,
+                    &DeepResearchEvent::Error {
                         message: format!("Resume error: {}", e),
                     },
                 );
@@ -1333,9 +1958,9 @@ pub async fn deep_research_abort(
     }
 
     // 发送中止事件
-    let _ = app.emit(
-        "deep-research-event",
-        DeepResearchEvent::Error {
+    emit_research_event(
+        &app,
+        &DeepResearchEvent::Error {
             message: "研究已被中止".to_string(),
         },
     );
@@ -1387,3 +2012,181 @@ pub fn agent_get_debug_log_path() -> Option<String> {
 
     debug_log::get_debug_file_path().map(|p| p.to_string_lossy().to_string())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn queued(id: &str, priority: TaskPriority) -> QueuedTaskRequest {
+        QueuedTaskRequest {
+            id: id.to_string(),
+            config: AgentConfig::default(),
+            task: format!("task {}", id),
+            context: serde_json::from_value(serde_json::json!({ "workspace_path": "/vault" }))
+                .expect("task context"),
+            enqueued_at: 0,
+            priority,
+            passed_over: 0,
+        }
+    }
+
+    fn queue_of(ids: &[&str]) -> VecDeque<QueuedTaskRequest> {
+        ids.iter()
+            .map(|id| queued(id, TaskPriority::Normal))
+            .collect()
+    }
+
+    fn ids(queue: &VecDeque<QueuedTaskRequest>) -> Vec<&str> {
+        queue.iter().map(|item| item.id.as_str()).collect()
+    }
+
+    #[test]
+    fn answers_resume_as_the_ask_user_tool_result() {
+        let call = |id: &str, name: &str| ToolCall {
+            id: id.to_string(),
+            name: name.to_string(),
+            params: Default::default(),
+        };
+        let mut messages = Vec::new();
+        let mut pending = vec![call("ask-1", ASK_USER_TOOL), call("read-1", "read")];
+        push_answer(&mut messages, &mut pending, "Rust".to_string());
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].role, MessageRole::Tool);
+        assert_eq!(messages[0].content, "The user answered: Rust");
+        assert_eq!(messages[0].tool_call_id.as_deref(), Some("ask-1"));
+        // 其余待执行的调用在恢复后继续执行
+        assert_eq!(
+            pending
+                .iter()
+                .map(|call| call.id.as_str())
+                .collect::<Vec<_>>(),
+            vec!["read-1"]
+        );
+
+        // 没有待处理的 ask_user 调用时作为用户消息
+        let mut messages = Vec::new();
+        push_answer(&mut messages, &mut pending, "Go".to_string());
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].role, MessageRole::User);
+        assert_eq!(messages[0].content, "Go");
+        assert_eq!(pending.len(), 1);
+    }
+
+    #[test]
+    fn cancel_removes_only_the_queued_task() {
+        let mut queue = queue_of(&["a", "b", "c"]);
+        let removed = cancel_queued_task(&mut queue, "b").expect("cancel b");
+        assert_eq!(removed.task, "task b");
+        assert_eq!(ids(&queue), vec!["a", "c"]);
+
+        // 已被取出运行（不在队列中）的任务不能通过队列取消
+        match cancel_queued_task(&mut queue, "b") {
+            Ok(_) => panic!("b was already removed"),
+            Err(error) => assert!(error.contains("agent_abort")),
+        }
+        assert_eq!(ids(&queue), vec!["a", "c"]);
+    }
+
+    #[test]
+    fn reorder_moves_task_and_clamps_position() {
+        let mut queue = queue_of(&["a", "b", "c"]);
+        reorder_queued_task(&mut queue, "c", 1).expect("move c");
+        assert_eq!(ids(&queue), vec!["c", "a", "b"]);
+        reorder_queued_task(&mut queue, "c", 99).expect("move c last");
+        assert_eq!(ids(&queue), vec!["a", "b", "c"]);
+        reorder_queued_task(&mut queue, "b", 0).expect("move b first");
+        assert_eq!(ids(&queue), vec!["b", "a", "c"]);
+        assert!(reorder_queued_task(&mut queue, "missing", 1).is_err());
+        assert_eq!(ids(&queue), vec!["b", "a", "c"]);
+    }
+
+    #[test]
+    fn reorder_stays_within_priority_and_reports_run_position() {
+        let mut queue = VecDeque::new();
+        enqueue_task(&mut queue, queued("high", TaskPriority::High));
+        enqueue_task(&mut queue, queued("normal", TaskPriority::Normal));
+        enqueue_task(&mut queue, queued("low-a", TaskPriority::Low));
+        enqueue_task(&mut queue, queued("low-b", TaskPriority::Low));
+        // 两个低优先级任务已老化到与 High 相同
+        queue[2].passed_over = 2 * QUEUE_AGING_STEP;
+        queue[3].passed_over = 2 * QUEUE_AGING_STEP;
+        let run_ids = |queue: &VecDeque<QueuedTaskRequest>| -> Vec<String> {
+            run_order(queue)
+                .into_iter()
+                .map(|index| queue[index].id.clone())
+                .collect()
+        };
+        assert_eq!(run_ids(&queue), vec!["high", "low-a", "low-b", "normal"]);
+
+        // 跨优先级的移动不改变运行顺序，返回真实位置
+        assert_eq!(reorder_queued_task(&mut queue, "normal", 1), Ok(4));
+        assert_eq!(reorder_queued_task(&mut queue, "low-b", 1), Ok(2));
+        assert_eq!(run_ids(&queue), vec!["high", "low-b", "low-a", "normal"]);
+
+        // 新入队的同优先级任务移到最前后，老化不会把它换回去
+        enqueue_task(&mut queue, queued("low-c", TaskPriority::Low));
+        assert_eq!(reorder_queued_task(&mut queue, "low-c", 2), Ok(2));
+        let order: Vec<String> = std::iter::from_fn(|| take_next_queued(&mut queue))
+            .map(|item| item.id)
+            .collect();
+        assert_eq!(order, vec!["high", "low-c", "low-b", "low-a", "normal"]);
+    }
+
+    #[test]
+    fn higher_priority_runs_first_and_fifo_within_priority() {
+        let mut queue = VecDeque::new();
+        enqueue_task(&mut queue, queued("normal-1", TaskPriority::Normal));
+        enqueue_task(&mut queue, queued("low", TaskPriority::Low));
+        enqueue_task(&mut queue, queued("high", TaskPriority::High));
+        enqueue_task(&mut queue, queued("normal-2", TaskPriority::Normal));
+        assert_eq!(ids(&queue), vec!["high", "normal-1", "normal-2", "low"]);
+
+        let order: Vec<String> = std::iter::from_fn(|| take_next_queued(&mut queue))
+            .map(|item| item.id)
+            .collect();
+        assert_eq!(order, vec!["high", "normal-1", "normal-2", "low"]);
+    }
+
+    #[test]
+    fn aging_prevents_starvation_by_high_priority_stream() {
+        let mut queue = VecDeque::new();
+        enqueue_task(&mut queue, queued("low", TaskPriority::Low));
+        let mut order = Vec::new();
+        for round in 0..10 {
+            enqueue_task(
+                &mut queue,
+                queued(&format!("high-{}", round), TaskPriority::High),
+            );
+            order.push(take_next_queued(&mut queue).expect("task").id);
+        }
+        let low_at = order
+            .iter()
+            .position(|id| id == "low")
+            .expect("low-priority task eventually runs");
+        // 需要严格超过 High 才能越过排在它前面的新任务
+        assert_eq!(low_at, 3 * QUEUE_AGING_STEP);
+    }
+
+    #[tokio::test]
+    async fn rerank_fallback_keeps_only_top_n() {
+        let results: Vec<RagResult> = (0..20)
+            .map(|i| RagResult {
+                file_path: format!("/vault/{}.md", i),
+                content: format!("chunk {}", i),
+                score: 1.0 - i as f32 / 20.0,
+                heading: None,
+            })
+            .collect();
+        // 模型为空：不发请求，直接走回退
+        let config = RerankConfig {
+            enabled: true,
+            model: String::new(),
+            top_n: 3,
+            ..RerankConfig::default()
+        };
+
+        let kept = rerank_rag_results("task", results, &config).await;
+        let paths: Vec<&str> = kept.iter().map(|r| r.file_path.as_str()).collect();
+        assert_eq!(paths, ["/vault/0.md", "/vault/1.md", "/vault/2.md"]);
+    }
+}
@@ -0,0 +1,352 @@
+//! 上下文压缩
+//!
+//! 长时间运行的 Agent 会不断累积消息与工具结果，最终超出模型上下文窗口。
+//! 估算的 prompt token 超过阈值时，把较早的消息总结为一条 system 摘要，
+//! 最近几条消息保持原样。切分点不会落在工具结果上，tool call 与其结果总是一起保留或一起被总结。
+
+use crate::agent::llm_backend::LlmBackend;
+use crate::agent::types::{AgentConfig, Message, MessageRole};
+use crate::llm_pricing::estimate_tokens;
+use serde_json::Value;
+use std::ops::Range;
+
+const TOOL_CALLS_MESSAGE_NAME: &str = "__lumina_tool_calls__";
+
+/// 压缩后保持原样的最近消息数（不计工具结果）
+const COMPACT_KEEP_MESSAGES: usize = 6;
+/// 未配置上下文窗口（context_window 为 0）时使用的保守默认值
+const FALLBACK_CONTEXT_WINDOW: usize = 32_000;
+const SUMMARY_MAX_CHARS_PER_MESSAGE: usize = 4000;
+const SUMMARY_MAX_TOTAL_CHARS: usize = 120_000;
+
+/// 摘要消息的前缀；再次压缩时旧摘要会一并被总结
+pub const COMPACTION_SUMMARY_PREFIX: &str = "[对话摘要]";
+
+/// 一次压缩的结果
+#[derive(Debug, Clone, PartialEq)]
+pub struct Compaction {
+    pub removed_messages: usize,
+    pub tokens_before: usize,
+    pub tokens_after: usize,
+    /// 总结调用消耗的 tokens
+    pub summary_tokens: usize,
+}
+
+/// 估算请求消息的 token 数（每条消息额外计入少量格式开销）
+pub fn estimate_prompt_tokens(messages: &[Message]) -> usize {
+    messages
+        .iter()
+        .map(|message| estimate_tokens(&message.content) + 4)
+        .sum()
+}
+
+/// 可用于 prompt 的上下文：上下文窗口扣除输出预留（max_tokens）
+pub fn usable_context_window(config: &AgentConfig) -> usize {
+    let window = if config.context_window > 0 {
+        config.context_window
+    } else {
+        FALLBACK_CONTEXT_WINDOW
+    };
+    window.saturating_sub(config.max_tokens)
+}
+
+/// 触发压缩的 prompt token 阈值：可用上下文按比例计算；compact_threshold 为 0 时不压缩
+pub fn compaction_threshold(config: &AgentConfig) -> Option<usize> {
+    if config.compact_threshold <= 0.0 {
+        return None;
+    }
+    let usable = usable_context_window(config);
+    Some((usable as f64 * config.compact_threshold.min(1.0) as f64) as usize)
+}
+
+fn is_summary(message: &Message) -> bool {
+    message.role == MessageRole::System && message.content.starts_with(COMPACTION_SUMMARY_PREFIX)
+}
+
+/// 可压缩的消息范围
+///
+/// 开头的 system 消息（系统提示、AGENTS.md、skills 索引）始终保留；
+/// 末尾保留最近 `keep` 条非工具结果消息及其后的全部消息。
+/// 范围两端都不是工具结果，因此不会拆开 tool call 与其结果。
+pub fn compaction_range(messages: &[Message], keep: usize) -> Option<Range<usize>> {
+    let start = messages
+        .iter()
+        .position(|message| message.role != MessageRole::System || is_summary(message))?;
+    let end = messages
+        .iter()
+        .enumerate()
+        .skip(start)
+        .rev()
+        .filter(|(_, message)| message.role != MessageRole::Tool)
+        .nth(keep.saturating_sub(1))
+        .map(|(index, _)| index)?;
+    // 只剩上次的摘要可压缩时不再重复总结
+    let compactable = messages[start..end]
+        .iter()
+        .any(|message| !is_summary(message));
+    (end > start && compactable).then_some(start..end)
+}
+
+fn truncate_chars(text: &str, max_chars: usize) -> String {
+    match text.char_indices().nth(max_chars) {
+        Some((index, _)) => format!("{}...", &text[..index]),
+        None => text.to_string(),
+    }
+}
+
+/// 把工具调用消息渲染为 `name(参数)` 列表
+fn render_tool_calls(content: &str) -> String {
+    let Ok(payload) = serde_json::from_str::<Value>(content) else {
+        return content.to_string();
+    };
+    let calls = payload
+        .get("tool_calls")
+        .and_then(Value::as_array)
+        .map(|calls| {
+            calls
+                .iter()
+                .map(|call| {
+                    let name = call.get("name").and_then(Value::as_str).unwrap_or("?");
+                    let params = call.get("params").cloned().unwrap_or(Value::Null);
+                    format!("{}({})", name, params)
+                })
+                .collect::<Vec<_>>()
+                .join("; ")
+        })
+        .unwrap_or_default();
+    let text = payload.get("content").and_then(Value::as_str).unwrap_or("");
+    if text.trim().is_empty() {
+        format!("调用工具：{}", calls)
+    } else {
+        format!("{}\n调用工具：{}", text, calls)
+    }
+}
+
+/// 把待压缩的消息渲染为总结用的对话记录
+fn render_transcript(messages: &[Message]) -> String {
+    let mut transcript = String::new();
+    for message in messages {
+        let (label, content) = match message.role {
+            MessageRole::System => ("系统", message.content.clone()),
+            MessageRole::User => ("用户", message.content.clone()),
+            MessageRole::Assistant if message.name.as_deref() == Some(TOOL_CALLS_MESSAGE_NAME) => {
+                ("助手", render_tool_calls(&message.content))
+            }
+            MessageRole::Assistant => ("助手", message.content.clone()),
+            MessageRole::Tool => (
+                "工具结果",
+                format!(
+                    "[{}] {}",
+                    message.name.as_deref().unwrap_or("tool"),
+                    message.content
+                ),
+            ),
+        };
+        let entry = format!(
+            "{}：{}\n\n",
+            label,
+            truncate_chars(&content, SUMMARY_MAX_CHARS_PER_MESSAGE)
+        );
+        if transcript.chars().count() + entry.chars().count() > SUMMARY_MAX_TOTAL_CHARS {
+            transcript.push_str("...（其余记录已省略）\n");
+            break;
+        }
+        transcript.push_str(&entry);
+    }
+    transcript
+}
+
+fn build_summary_prompt(transcript: &str) -> String {
+    format!(
+        r#"以下是一个 Agent 任务中较早的对话与工具调用记录。请把它压缩为一份简洁的摘要，供 Agent 继续完成任务时参考。
+
+摘要需要保留：
+- 用户的原始目标与所有明确要求
+- 已完成的步骤，以及读取或修改过的文件路径
+- 工具结果中后续仍需要的关键信息（数据、结论、错误原因）
+- 尚未完成的工作
+
+只输出摘要本身，不要添加额外说明。
+
+对话记录：
+{}"#,
+        transcript
+    )
+}
+
+/// 估算 token 超过阈值时压缩 `messages`，并同步移除被总结的工具结果对应的 `observations`
+///
+/// 未达到阈值或没有可压缩的消息时返回 `Ok(None)`；总结失败时返回错误且不修改消息。
+pub async fn compact_if_needed<L: LlmBackend>(
+    llm: &L,
+    config: &AgentConfig,
+    messages: &mut Vec<Message>,
+    observations: &mut Vec<String>,
+) -> Result<Option<Compaction>, String> {
+    let Some(threshold) = compaction_threshold(config) else {
+        return Ok(None);
+    };
+    let tokens_before = estimate_prompt_tokens(messages);
+    if tokens_before < threshold {
+        return Ok(None);
+    }
+    let Some(range) = compaction_range(messages, COMPACT_KEEP_MESSAGES) else {
+        return Ok(None);
+    };
+
+    let prompt = build_summary_prompt(&render_transcript(&messages[range.clone()]));
+    let response = llm.call_simple_with_usage(&prompt).await?;
+    let summary = response.content.trim();
+    if summary.is_empty() {
+        return Err("上下文压缩失败：模型返回了空摘要".to_string());
+    }
+    let summary_tokens = if response.total_tokens > 0 {
+        response.total_tokens
+    } else {
+        estimate_tokens(&prompt) + estimate_tokens(summary)
+    };
+
+    // 每个工具结果消息对应一条 observation，按顺序移除被总结的部分
+    let summarized_results = messages[range.clone()]
+        .iter()
+        .filter(|message| message.role == MessageRole::Tool)
+        .count();
+    let summary = format!("{}\n{}", COMPACTION_SUMMARY_PREFIX, summary);
+    let removed_messages = range.len();
+    messages.splice(
+        range,
+        [Message {
+            role: MessageRole::System,
+            content: summary.clone(),
+            name: None,
+            tool_call_id: None,
+        }],
+    );
+    if observations
+        .first()
+        .is_some_and(|observation| observation.starts_with(COMPACTION_SUMMARY_PREFIX))
+    {
+        observations.remove(0);
+    }
+    observations.drain(..summarized_results.min(observations.len()));
+    observations.insert(0, summary);
+
+    Ok(Some(Compaction {
+        removed_messages,
+        tokens_before,
+        tokens_after: estimate_prompt_tokens(messages),
+        summary_tokens,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::mock_llm::MockLlmClient;
+
+    fn message(role: MessageRole, content: &str) -> Message {
+        Message {
+            role,
+            content: content.to_string(),
+            name: None,
+            tool_call_id: None,
+        }
+    }
+
+    fn tool_round(messages: &mut Vec<Message>, id: &str, result: &str) {
+        messages.push(Message {
+            name: Some(TOOL_CALLS_MESSAGE_NAME.to_string()),
+            ..message(
+                MessageRole::Assistant,
+                &format!(
+                    r#"{{"tool_calls":[{{"id":"{}","name":"read","params":{{"path":"a.md"}}}}],"content":""}}"#,
+                    id
+                ),
+            )
+        });
+        messages.push(Message {
+            name: Some("read".to_string()),
+            tool_call_id: Some(id.to_string()),
+            ..message(MessageRole::Tool, result)
+        });
+    }
+
+    fn long_run() -> Vec<Message> {
+        let mut messages = vec![
+            message(MessageRole::System, "system prompt"),
+            message(MessageRole::System, "agents.md"),
+            message(MessageRole::User, "整理 a.md"),
+        ];
+        for index in 0..8 {
+            tool_round(&mut messages, &format!("call-{}", index), &"x".repeat(5500));
+        }
+        messages
+    }
+
+    #[test]
+    fn range_keeps_system_prefix_and_tool_pairs() {
+        let messages = long_run();
+        let range = compaction_range(&messages, 3).unwrap();
+        assert_eq!(range.start, 2);
+        // 末尾保留 3 条非工具消息：最后 3 个工具调用及其结果
+        assert_eq!(range.end, messages.len() - 6);
+        assert_eq!(
+            messages[range.end].name.as_deref(),
+            Some(TOOL_CALLS_MESSAGE_NAME)
+        );
+        assert_eq!(messages[range.end - 1].role, MessageRole::Tool);
+
+        let short = &messages[..5];
+        assert!(compaction_range(short, 6).is_none());
+    }
+
+    #[tokio::test]
+    async fn compacts_older_messages_into_summary() {
+        let config = AgentConfig {
+            context_window: 16_000,
+            ..AgentConfig::default()
+        };
+        let mut messages = long_run();
+        let mut observations: Vec<String> = (0..8).map(|i| format!("[read] {}", i)).collect();
+        let llm = MockLlmClient::new().fallback("已读取 a.md 两次");
+
+        let compaction = compact_if_needed(&llm, &config, &mut messages, &mut observations)
+            .await
+            .unwrap()
+            .expect("compacted");
+        assert!(compaction.tokens_after < compaction.tokens_before);
+        assert!(llm.prompts()[0].contains("调用工具：read"));
+
+        assert_eq!(messages[0].content, "system prompt");
+        assert!(is_summary(&messages[2]));
+        assert!(messages[2].content.contains("已读取 a.md 两次"));
+        // 保留的部分以工具调用开头，每个工具结果前都有其调用
+        assert_eq!(messages.len(), 3 + 2 * COMPACT_KEEP_MESSAGES);
+        assert_eq!(messages[3].name.as_deref(), Some(TOOL_CALLS_MESSAGE_NAME));
+        assert_eq!(observations.len(), 1 + COMPACT_KEEP_MESSAGES);
+        assert_eq!(observations[1], "[read] 2");
+
+        // 低于阈值时不再压缩
+        let again = compact_if_needed(&llm, &config, &mut messages, &mut observations)
+            .await
+            .unwrap();
+        assert!(again.is_none());
+        assert_eq!(llm.prompts().len(), 1);
+    }
+
+    #[test]
+    fn threshold_follows_context_window() {
+        let config = AgentConfig {
+            context_window: 128_000,
+            max_tokens: 8_000,
+            compact_threshold: 0.5,
+            ..AgentConfig::default()
+        };
+        assert_eq!(compaction_threshold(&config), Some(60_000));
+        let disabled = AgentConfig {
+            compact_threshold: 0.0,
+            ..config
+        };
+        assert_eq!(compaction_threshold(&disabled), None);
+    }
+}
@@ -4,6 +4,7 @@
 
 #![allow(dead_code)]
 
+use crate::forge_runtime::trace::{TraceEntry, TraceOutcome};
 use chrono::Local;
 use std::fs::{File, OpenOptions};
 use std::io::Write;
@@ -269,6 +270,27 @@ pub fn log_tool_result(tool_name: &str, success: bool, content: &str, error: Opt
     }
 }
 
+/// 记录一次工具调用轨迹（参数已脱敏）
+pub fn log_tool_trace(entry: &TraceEntry) {
+    if !is_debug_enabled() {
+        return;
+    }
+    log_subsection(&format!("工具调用 #{}: `{}`", entry.seq, entry.tool));
+    log_kv("耗时", &format!("{} ms", entry.duration_ms));
+    log_json("参数", &entry.args);
+    let content = match &entry.result {
+        Some(serde_json::Value::String(text)) => text.clone(),
+        Some(value) => serde_json::to_string_pretty(value).unwrap_or_else(|_| value.to_string()),
+        None => String::new(),
+    };
+    log_tool_result(
+        &entry.tool,
+        entry.outcome == TraceOutcome::Success,
+        &content,
+        entry.error.as_deref(),
+    );
+}
+
 /// 记录最终结果
 pub fn log_final_result(result: &str) {
     log_separator("最终结果");
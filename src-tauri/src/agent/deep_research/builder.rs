@@ -3,10 +3,13 @@
 use std::sync::Arc;
 use tauri::AppHandle;
 
+use crate::agent::deep_research::crawl_policy::CrawlPolicy;
 use crate::agent::deep_research::crawler::JinaClient;
 use crate::agent::deep_research::nodes::*;
-use crate::agent::deep_research::tavily::TavilyClient;
 use crate::agent::deep_research::types::*;
+use crate::agent::deep_research::web_search::{
+    create_web_search_provider, WebSearchFilter, WebSearchProvider,
+};
 use crate::agent::llm_client::LlmClient;
 use forge::runtime::constants::END;
 use forge::runtime::error::{GraphError, GraphResult, Interrupt};
@@ -19,8 +22,9 @@ pub struct DeepResearchContext {
     pub app: AppHandle,
     pub llm: Arc<LlmClient>,
     pub config: DeepResearchConfig,
-    pub tavily: Option<Arc<TavilyClient>>,
+    pub web_search: Option<Arc<dyn WebSearchProvider>>,
     pub jina: Option<Arc<JinaClient>>,
+    pub crawl_policy: Option<Arc<CrawlPolicy>>,
 }
 
 impl DeepResearchContext {
@@ -32,24 +36,29 @@ impl DeepResearchContext {
             api_key: config.api_key.clone(),
             base_url: config.base_url.clone(),
             temperature: config.temperature,
+            cache: config.cache,
             ..Default::default()
         };
         let llm = Arc::new(LlmClient::new(agent_config, client.clone()));
 
-        // 创建 Tavily 客户端（如果启用且有 API Key）
-        let tavily = if config.enable_web_search {
-            config
-                .tavily_api_key
-                .as_ref()
-                .map(|key| Arc::new(TavilyClient::new(key.clone(), client.clone())))
-        } else {
-            None
-        };
+        // 创建网络搜索提供方（如果启用且配置完整）
+        let web_search = create_web_search_provider(&config, client.clone());
 
         // 创建 Jina 客户端（如果启用网络搜索）
         // Jina Reader 免费版不需要 API Key，但有速率限制
         let jina = if config.enable_web_search {
-            Some(Arc::new(JinaClient::new(None, client)))
+            Some(Arc::new(JinaClient::new(None, client.clone())))
+        } else {
+            None
+        };
+
+        // 爬取策略（robots.txt 与按主机限速），在整个研究过程中共享
+        let crawl_policy = if config.enable_web_search {
+            Some(Arc::new(CrawlPolicy::new(
+                client,
+                config.respect_robots_txt,
+                std::time::Duration::from_millis(config.crawl_host_interval_ms),
+            )))
         } else {
             None
         };
@@ -58,8 +67,9 @@ impl DeepResearchContext {
             app,
             llm,
             config,
-            tavily,
+            web_search,
             jina,
+            crawl_policy,
         }
     }
 }
@@ -109,15 +119,21 @@ pub fn build_deep_research_graph(
         let ctx = ctx_search.clone();
         let max_results = ctx.config.max_search_results;
         let max_web_results = ctx.config.max_web_search_results;
-        let tavily = ctx.tavily.clone();
+        let web_search = ctx.web_search.clone();
+        let web_filter = WebSearchFilter {
+            include_domains: ctx.config.include_domains.clone(),
+            exclude_domains: ctx.config.exclude_domains.clone(),
+            max_results_per_domain: ctx.config.max_results_per_domain,
+        };
         async move {
             let result = search_notes_node(
                 &ctx.app,
                 &ctx.llm,
                 state,
                 max_results,
-                tavily.as_ref(),
+                web_search.as_ref(),
                 max_web_results,
+                &web_filter,
             )
             .await
             .map_err(|e| GraphError::ExecutionError {
@@ -135,14 +151,23 @@ pub fn build_deep_research_graph(
     graph.add_node("crawl_web", move |state: DeepResearchState| {
         let ctx = ctx_crawl.clone();
         let jina = ctx.jina.clone();
-        let max_pages = ctx.config.max_web_search_results.min(10); // 最多爬取 10 个网页
+        let crawl_policy = ctx.crawl_policy.clone();
+        let max_pages = ctx.config.crawl_max_pages;
+        let max_depth = ctx.config.crawl_max_depth;
         async move {
-            let result = crawl_web_node(&ctx.app, state, jina.as_ref(), max_pages)
-                .await
-                .map_err(|e| GraphError::ExecutionError {
-                    node: "crawl_web".to_string(),
-                    message: e,
-                })?;
+            let result = crawl_web_node(
+                &ctx.app,
+                state,
+                jina.as_ref(),
+                crawl_policy.as_ref(),
+                max_pages,
+                max_depth,
+            )
+            .await
+            .map_err(|e| GraphError::ExecutionError {
+                node: "crawl_web".to_string(),
+                message: e,
+            })?;
             let mut state = result.state;
             state.goto = result.next_node.unwrap_or_default();
             Ok(state)
@@ -171,8 +196,9 @@ pub fn build_deep_research_graph(
     let ctx_outline = ctx.clone();
     graph.add_node("generate_outline", move |state: DeepResearchState| {
         let ctx = ctx_outline.clone();
+        let preset = ctx.config.report_preset;
         async move {
-            let result = generate_outline_node(&ctx.app, &ctx.llm, state)
+            let result = generate_outline_node(&ctx.app, &ctx.llm, state, preset)
                 .await
                 .map_err(|e| GraphError::ExecutionError {
                     node: "generate_outline".to_string(),
@@ -189,8 +215,9 @@ pub fn build_deep_research_graph(
     graph.add_node("write_report", move |state: DeepResearchState| {
         let ctx = ctx_write.clone();
         let include_citations = ctx.config.include_citations;
+        let preset = ctx.config.report_preset;
         async move {
-            let result = write_report_node(&ctx.app, &ctx.llm, state, include_citations)
+            let result = write_report_node(&ctx.app, &ctx.llm, state, include_citations, preset)
                 .await
                 .map_err(|e| GraphError::ExecutionError {
                     node: "write_report".to_string(),
@@ -207,8 +234,9 @@ pub fn build_deep_research_graph(
     graph.add_node("review_report", move |state: DeepResearchState| {
         let ctx = ctx_review.clone();
         let include_citations = ctx.config.include_citations;
+        let preset = ctx.config.report_preset;
         async move {
-            let result = review_report_node(&ctx.app, &ctx.llm, state, include_citations)
+            let result = review_report_node(&ctx.app, &ctx.llm, state, include_citations, preset)
                 .await
                 .map_err(|e| GraphError::ExecutionError {
                     node: "review_report".to_string(),
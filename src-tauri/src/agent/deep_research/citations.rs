@@ -0,0 +1,485 @@
+//! 报告引用编号
+//!
+//! 为撰写报告准备编号来源列表，并在定稿时按首次出现顺序重排 `[n]` 标记、
+//! 追加只包含实际引用来源的参考来源章节。
+
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use super::types::{CitationKind, CitationSource, DeepResearchState, ReportOutline};
+use super::web_search::normalize_url;
+
+/// 参考来源章节标题
+pub const REFERENCES_HEADING: &str = "参考来源";
+
+/// 识别模型自行编写的参考文献章节
+const REFERENCE_HEADINGS: &[&str] = &["参考来源", "参考文献", "参考资料", "references", "sources"];
+
+/// 收集报告可引用的来源：已读笔记与网页（与撰写提示词中的资料一致），相同来源只编号一次
+pub fn collect_citation_sources(state: &DeepResearchState) -> Vec<CitationSource> {
+    let mut sources: Vec<CitationSource> = Vec::new();
+    let mut seen = HashSet::new();
+
+    for note in &state.read_notes {
+        let location = relative_note_path(&state.workspace_path, &note.path);
+        if seen.insert(format!("note:{}", location.to_lowercase())) {
+            sources.push(CitationSource {
+                number: sources.len() + 1,
+                kind: CitationKind::Note,
+                title: note.title.trim_end_matches(".md").to_string(),
+                location,
+                found_via: None,
+            });
+        }
+    }
+
+    let pages: Vec<(&str, &str, Option<&String>)> = if state.crawled_pages.is_empty() {
+        state
+            .web_search_results
+            .iter()
+            .map(|r| (r.title.as_str(), r.url.as_str(), None))
+            .collect()
+    } else {
+        state
+            .crawled_pages
+            .iter()
+            .map(|p| (p.title.as_str(), p.url.as_str(), p.found_via.as_ref()))
+            .collect()
+    };
+    for (title, url, found_via) in pages {
+        if seen.insert(format!("web:{}", normalize_url(url))) {
+            sources.push(CitationSource {
+                number: sources.len() + 1,
+                kind: CitationKind::Web,
+                title: title.to_string(),
+                location: url.to_string(),
+                found_via: found_via.cloned(),
+            });
+        }
+    }
+
+    sources
+}
+
+/// 提示词中的来源列表
+pub fn format_source_catalog(sources: &[CitationSource]) -> String {
+    sources
+        .iter()
+        .map(|source| match source.kind {
+            CitationKind::Note => format!(
+                "[{}] 笔记：{}（{}）",
+                source.number, source.title, source.location
+            ),
+            CitationKind::Web => match &source.found_via {
+                Some(via) => format!(
+                    "[{}] 网页：{}（{}，由 {} 中的链接跟进）",
+                    source.number, source.title, source.location, via
+                ),
+                None => format!(
+                    "[{}] 网页：{}（{}）",
+                    source.number, source.title, source.location
+                ),
+            },
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// 将大纲各章节的 `related_notes` 映射到来源编号
+pub fn format_section_sources(outline: &ReportOutline, sources: &[CitationSource]) -> String {
+    outline
+        .sections
+        .iter()
+        .filter_map(|section| {
+            let mut numbers: Vec<usize> = Vec::new();
+            for number in section
+                .related_notes
+                .iter()
+                .filter_map(|reference| match_source(reference, sources))
+            {
+                if !numbers.contains(&number) {
+                    numbers.push(number);
+                }
+            }
+            if numbers.is_empty() {
+                return None;
+            }
+            let markers: String = numbers.iter().map(|n| format!("[{}]", n)).collect();
+            Some(format!("- {}：{}", section.heading, markers))
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// 按标题、路径、文件名或 URL 匹配来源编号
+pub fn match_source(reference: &str, sources: &[CitationSource]) -> Option<usize> {
+    let wanted = normalize_reference(reference);
+    if wanted.is_empty() {
+        return None;
+    }
+    if let Some(number) = wanted
+        .parse::<usize>()
+        .ok()
+        .filter(|n| *n >= 1 && *n <= sources.len())
+    {
+        return Some(number);
+    }
+
+    sources
+        .iter()
+        .find(|source| match source.kind {
+            CitationKind::Note => {
+                let path = normalize_reference(&source.location);
+                let stem = path.rsplit('/').next().unwrap_or(&path).to_string();
+                wanted == normalize_reference(&source.title) || wanted == path || wanted == stem
+            }
+            CitationKind::Web => {
+                (reference.contains("://")
+                    && normalize_url(reference) == normalize_url(&source.location))
+                    || wanted == normalize_reference(&source.title)
+            }
+        })
+        .map(|source| source.number)
+}
+
+/// 统计报告中指向有效来源的引用编号数
+pub fn count_citation_markers(report: &str, source_count: usize) -> usize {
+    let mut count = 0;
+    for_each_marker(report, source_count, |numbers| {
+        count += numbers.len();
+        None
+    });
+    count
+}
+
+/// 定稿引用：按首次出现顺序重新编号，并追加参考来源章节
+///
+/// 不指向任何来源的方括号编号（如 `[2024]`）保持原样。
+/// 模型自行编写的参考文献章节会被替换。没有任何有效引用时不追加章节。
+/// 返回定稿报告与重新编号后的实际引用来源。
+pub fn finalize_citations(
+    report: &str,
+    sources: &[CitationSource],
+) -> (String, Vec<CitationSource>) {
+    let body = strip_references_section(report);
+
+    let mut renumbered: HashMap<usize, usize> = HashMap::new();
+    let mut used: Vec<&CitationSource> = Vec::new();
+    let rewritten = for_each_marker(body, sources.len(), |numbers| {
+        let mut new_numbers: Vec<usize> = Vec::new();
+        for number in numbers {
+            let source = &sources[number - 1];
+            let new_number = *renumbered.entry(*number).or_insert_with(|| {
+                used.push(source);
+                used.len()
+            });
+            if !new_numbers.contains(&new_number) {
+                new_numbers.push(new_number);
+            }
+        }
+        Some(
+            new_numbers
+                .iter()
+                .map(|n| format!("[{}]", n))
+                .collect::<String>(),
+        )
+    });
+
+    let used: Vec<CitationSource> = used
+        .into_iter()
+        .enumerate()
+        .map(|(index, source)| CitationSource {
+            number: index + 1,
+            ..source.clone()
+        })
+        .collect();
+
+    let mut output = rewritten.trim_end().to_string();
+    if used.is_empty() {
+        return (output, used);
+    }
+
+    output.push_str(&format!("\n\n## {}\n\n", REFERENCES_HEADING));
+    for source in &used {
+        let entry = match source.kind {
+            CitationKind::Note => format!("[[{}]]", source.location.trim_end_matches(".md")),
+            CitationKind::Web => {
+                let link = format!(
+                    "[{}]({})",
+                    source.title.replace(['[', ']'], ""),
+                    source.location
+                );
+                match &source.found_via {
+                    Some(via) => format!("{}（跟进自 <{}>）", link, via),
+                    None => link,
+                }
+            }
+        };
+        output.push_str(&format!("- [{}] {}\n", source.number, entry));
+    }
+    (output, used)
+}
+
+fn relative_note_path(workspace: &str, path: &str) -> String {
+    let relative = Path::new(path)
+        .strip_prefix(workspace)
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_else(|_| path.to_string());
+    relative
+        .replace('\\', "/")
+        .trim_start_matches('/')
+        .to_string()
+}
+
+fn normalize_reference(reference: &str) -> String {
+    reference
+        .trim()
+        .trim_start_matches("[[")
+        .trim_end_matches("]]")
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .trim()
+        .trim_end_matches(".md")
+        .replace('\\', "/")
+        .to_lowercase()
+}
+
+/// 去掉报告末尾模型自行编写的参考文献章节
+fn strip_references_section(report: &str) -> &str {
+    let mut offset = 0;
+    let mut cut = None;
+    for line in report.split_inclusive('\n') {
+        let trimmed = line.trim();
+        if trimmed.starts_with('#') {
+            let heading = trimmed
+                .trim_start_matches('#')
+                .trim()
+                .trim_end_matches(['：', ':']);
+            if REFERENCE_HEADINGS.contains(&heading.to_lowercase().as_str()) {
+                cut = Some(offset);
+            }
+        }
+        offset += line.len();
+    }
+    cut.map(|cut| &report[..cut]).unwrap_or(report)
+}
+
+/// 遍历代码块与行内代码之外的 `[1]`、`[1, 2]` 引用标记；回调返回 Some 时替换该标记
+///
+/// 只匹配编号全部落在 `1..=source_count` 内的标记，不会匹配 `[2024]` 这类编号、
+/// Markdown 链接 `[1](url)`、双链 `[[1]]` 与脚注 `[^1]`。
+fn for_each_marker<F>(report: &str, source_count: usize, mut visit: F) -> String
+where
+    F: FnMut(&[usize]) -> Option<String>,
+{
+    let mut output = String::with_capacity(report.len());
+    let mut in_fence = false;
+
+    for line in report.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+        }
+        if in_fence || trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            output.push_str(line);
+            continue;
+        }
+
+        let mut rest = line;
+        while let Some(start) = rest.find(['[', '`']) {
+            let (before, from_bracket) = rest.split_at(start);
+            output.push_str(before);
+            if from_bracket.starts_with('`') {
+                // 行内代码原样保留；未闭合的反引号按普通文本处理
+                let ticks = from_bracket.len() - from_bracket.trim_start_matches('`').len();
+                let len = from_bracket[ticks..]
+                    .find(&from_bracket[..ticks])
+                    .map(|end| ticks + end + ticks)
+                    .unwrap_or(ticks);
+                output.push_str(&from_bracket[..len]);
+                rest = &from_bracket[len..];
+                continue;
+            }
+            let marker =
+                parse_marker(from_bracket, output.ends_with('[')).filter(|(numbers, _)| {
+                    numbers
+                        .iter()
+                        .all(|number| (1..=source_count).contains(number))
+                });
+            match marker {
+                Some((numbers, len)) => {
+                    match visit(&numbers) {
+                        Some(replacement) => output.push_str(&replacement),
+                        None => output.push_str(&from_bracket[..len]),
+                    }
+                    rest = &from_bracket[len..];
+                }
+                None => {
+                    output.push('[');
+                    rest = &from_bracket[1..];
+                }
+            }
+        }
+        output.push_str(rest);
+    }
+    output
+}
+
+/// 解析以 `[` 开头的引用标记，返回编号与标记字节长度
+fn parse_marker(text: &str, after_bracket: bool) -> Option<(Vec<usize>, usize)> {
+    if after_bracket {
+        return None;
+    }
+    let end = text.find(']')?;
+    let inner = &text[1..end];
+    if inner.trim().is_empty() {
+        return None;
+    }
+    let numbers = inner
+        .split([',', '，', '、'])
+        .map(|part| part.trim().parse::<usize>().ok())
+        .collect::<Option<Vec<_>>>()?;
+    let next = text[end + 1..].chars().next();
+    if matches!(next, Some('(') | Some(']')) {
+        return None;
+    }
+    Some((numbers, end + 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::deep_research::types::{
+        CrawledPageContent, NoteContent, OutlineSection, WebSearchResult,
+    };
+
+    fn note(path: &str, title: &str) -> NoteContent {
+        NoteContent {
+            path: path.to_string(),
+            title: title.to_string(),
+            content: String::new(),
+            summary: None,
+        }
+    }
+
+    fn sample_state() -> DeepResearchState {
+        DeepResearchState {
+            workspace_path: "/vault".to_string(),
+            read_notes: vec![
+                note("/vault/Projects/Alpha.md", "Alpha"),
+                note("Daily/2024-01-15.md", "2024-01-15"),
+                note("Projects/Alpha.md", "Alpha"),
+            ],
+            web_search_results: vec![WebSearchResult {
+                title: "Ignored when crawled".to_string(),
+                url: "https://example.com/a".to_string(),
+                content: String::new(),
+                score: 0.5,
+            }],
+            crawled_pages: vec![
+                CrawledPageContent {
+                    url: "https://example.com/guide/".to_string(),
+                    title: "Guide".to_string(),
+                    content: String::new(),
+                    depth: 0,
+                    found_via: None,
+                },
+                CrawledPageContent {
+                    url: "http://www.example.com/guide".to_string(),
+                    title: "Guide (copy)".to_string(),
+                    content: String::new(),
+                    depth: 1,
+                    found_via: Some("https://example.com/".to_string()),
+                },
+                CrawledPageContent {
+                    url: "https://example.com/guide/setup".to_string(),
+                    title: "Setup".to_string(),
+                    content: String::new(),
+                    depth: 1,
+                    found_via: Some("https://example.com/guide/".to_string()),
+                },
+            ],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn collects_deduplicated_numbered_sources() {
+        let sources = collect_citation_sources(&sample_state());
+        let summary: Vec<(usize, &str)> = sources
+            .iter()
+            .map(|s| (s.number, s.location.as_str()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (1, "Projects/Alpha.md"),
+                (2, "Daily/2024-01-15.md"),
+                (3, "https://example.com/guide/"),
+                (4, "https://example.com/guide/setup"),
+            ]
+        );
+        assert_eq!(
+            sources[3].found_via.as_deref(),
+            Some("https://example.com/guide/")
+        );
+
+        let outline = ReportOutline {
+            title: "T".to_string(),
+            sections: vec![
+                OutlineSection {
+                    heading: "背景".to_string(),
+                    points: vec![],
+                    related_notes: vec![
+                        "[[Alpha]]".to_string(),
+                        "http://example.com/guide".to_string(),
+                        "Unknown".to_string(),
+                    ],
+                },
+                OutlineSection {
+                    heading: "空".to_string(),
+                    points: vec![],
+                    related_notes: vec![],
+                },
+            ],
+        };
+        assert_eq!(format_section_sources(&outline, &sources), "- 背景：[1][3]");
+    }
+
+    #[test]
+    fn finalize_renumbers_by_first_use_and_lists_used_sources() {
+        let sources = collect_citation_sources(&sample_state());
+        let report = "# 报告\n\n网页指出 X [3]。笔记记录 Y [1, 3]，另见 [9]，步骤见 [4]。\n\n```\nlet a = v[2];\n```\n[链接](https://a.b) [[Alpha]] [^1]\n\n## 参考文献\n\n1. 旧列表\n";
+
+        assert_eq!(count_citation_markers(report, sources.len()), 4);
+
+        let (finalized, used) = finalize_citations(report, &sources);
+        assert_eq!(
+            finalized,
+            "# 报告\n\n网页指出 X [1]。笔记记录 Y [2][1]，另见 [9]，步骤见 [3]。\n\n```\nlet a = v[2];\n```\n[链接](https://a.b) [[Alpha]] [^1]\n\n## 参考来源\n\n- [1] [Guide](https://example.com/guide/)\n- [2] [[Projects/Alpha]]\n- [3] [Setup](https://example.com/guide/setup)（跟进自 <https://example.com/guide/>）\n"
+        );
+        assert_eq!(used[0].title, "Guide");
+        // 用重新编号的来源再次定稿保持不变
+        assert_eq!(finalize_citations(&finalized, &used).0, finalized);
+    }
+
+    #[test]
+    fn finalize_skips_inline_code_and_out_of_range_numbers() {
+        let sources = collect_citation_sources(&sample_state());
+        let report = "2024 年报告 [2024] 提到 `v[3]` 与 ``a[2]`` 及 [3]，未闭合 ` [1]。\n";
+
+        assert_eq!(count_citation_markers(report, sources.len()), 2);
+        let (finalized, used) = finalize_citations(report, &sources);
+        assert!(finalized
+            .starts_with("2024 年报告 [2024] 提到 `v[3]` 与 ``a[2]`` 及 [1]，未闭合 ` [2]。"));
+        assert_eq!(used.len(), 2);
+    }
+
+    #[test]
+    fn finalize_without_markers_keeps_plain_prose() {
+        let sources = collect_citation_sources(&sample_state());
+        let (report, used) = finalize_citations("纯文本报告。\n", &sources);
+        assert_eq!(report, "纯文本报告。");
+        assert!(used.is_empty());
+    }
+}
@@ -0,0 +1,514 @@
+//! 受控网页爬取策略
+//!
+//! - robots.txt：按主机缓存规则，拒绝访问被禁止的路径
+//! - nofollow：页面级 `<meta name="robots" content="nofollow">` 与链接级 `rel="nofollow"`
+//! - 限速：同一主机的请求之间至少间隔 `host_interval`
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use tokio::time::Instant;
+
+use super::web_search::url_host;
+
+/// robots.txt 中匹配的 User-Agent 标识
+pub const CRAWLER_USER_AGENT: &str = "LuminaNote";
+
+static ANCHOR_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?is)<a\s[^>]*>").expect("valid anchor regex"));
+static META_ROBOTS_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?is)<meta\s[^>]*name\s*=\s*["']?robots["']?[^>]*>"#)
+        .expect("valid meta robots regex")
+});
+static HREF_RE: Lazy<Regex> = Lazy::new(|| attribute_regex("href"));
+static REL_RE: Lazy<Regex> = Lazy::new(|| attribute_regex("rel"));
+static CONTENT_RE: Lazy<Regex> = Lazy::new(|| attribute_regex("content"));
+
+/// 不会跟进的资源扩展名
+const SKIPPED_EXTENSIONS: &[&str] = &[
+    "png", "jpg", "jpeg", "gif", "svg", "webp", "ico", "css", "js", "zip", "gz", "tar", "mp3",
+    "mp4", "mov", "woff", "woff2",
+];
+
+fn attribute_regex(name: &str) -> Regex {
+    Regex::new(&format!(
+        r#"(?i)\b{}\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s"'>]+))"#,
+        name
+    ))
+    .expect("valid attribute regex")
+}
+
+fn attribute(tag: &str, re: &Regex) -> Option<String> {
+    let caps = re.captures(tag)?;
+    caps.get(1)
+        .or_else(|| caps.get(2))
+        .or_else(|| caps.get(3))
+        .map(|m| m.as_str().trim().to_string())
+}
+
+/// robots.txt 分组：(User-Agent 列表, 规则列表)
+type RobotsGroup = (Vec<String>, Vec<(bool, String)>);
+
+/// 单个主机的 robots.txt 规则
+#[derive(Debug, Clone, Default)]
+pub struct RobotsRules {
+    /// (是否允许, 路径模式)
+    rules: Vec<(bool, String)>,
+}
+
+impl RobotsRules {
+    /// 解析 robots.txt，取与 `user_agent` 匹配的分组，没有时取 `*` 分组
+    pub fn parse(content: &str, user_agent: &str) -> Self {
+        let user_agent = user_agent.to_lowercase();
+        let mut groups: Vec<RobotsGroup> = Vec::new();
+        let mut last_was_agent = false;
+
+        for line in content.lines() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let key = key.trim().to_lowercase();
+            let value = value.trim();
+            match key.as_str() {
+                "user-agent" => {
+                    if !last_was_agent || groups.is_empty() {
+                        groups.push((Vec::new(), Vec::new()));
+                    }
+                    if let Some(group) = groups.last_mut() {
+                        group.0.push(value.to_lowercase());
+                    }
+                    last_was_agent = true;
+                }
+                "allow" | "disallow" => {
+                    // 空的 Disallow 表示允许全部
+                    if let Some(group) = groups.last_mut().filter(|_| !value.is_empty()) {
+                        group.1.push((key == "allow", value.to_string()));
+                    }
+                    last_was_agent = false;
+                }
+                _ => last_was_agent = false,
+            }
+        }
+
+        let specific: Vec<_> = groups
+            .iter()
+            .filter(|(agents, _)| {
+                agents
+                    .iter()
+                    .any(|agent| agent != "*" && !agent.is_empty() && user_agent.contains(agent))
+            })
+            .collect();
+        let selected = if specific.is_empty() {
+            groups
+                .iter()
+                .filter(|(agents, _)| agents.iter().any(|agent| agent == "*"))
+                .collect()
+        } else {
+            specific
+        };
+
+        Self {
+            rules: selected
+                .into_iter()
+                .flat_map(|(_, rules)| rules.iter().cloned())
+                .collect(),
+        }
+    }
+
+    /// 检查路径（含查询串）是否允许访问：最长匹配的规则生效，长度相同时 Allow 优先
+    pub fn is_allowed(&self, path: &str) -> bool {
+        self.rules
+            .iter()
+            .filter(|(_, pattern)| pattern_matches(pattern, path))
+            .max_by_key(|(allow, pattern)| (pattern.len(), *allow))
+            .map(|(allow, _)| *allow)
+            .unwrap_or(true)
+    }
+}
+
+/// robots.txt 路径模式匹配，支持 `*` 通配与 `$` 结尾锚定
+fn pattern_matches(pattern: &str, path: &str) -> bool {
+    let (pattern, anchored) = match pattern.strip_suffix('$') {
+        Some(pattern) => (pattern, true),
+        None => (pattern, false),
+    };
+    let parts: Vec<&str> = pattern.split('*').collect();
+    let Some(rest) = path.strip_prefix(parts[0]) else {
+        return false;
+    };
+    if parts.len() == 1 {
+        return !anchored || rest.is_empty();
+    }
+
+    let mut rest = rest;
+    for (index, part) in parts.iter().enumerate().skip(1) {
+        let is_last = index == parts.len() - 1;
+        if is_last && anchored {
+            return rest.ends_with(part);
+        }
+        match rest.find(part) {
+            Some(found) => rest = &rest[found + part.len()..],
+            None => return false,
+        }
+    }
+    true
+}
+
+/// 协议与主机部分，如 `https://example.com:8080`
+fn url_origin(url: &str) -> Option<&str> {
+    let scheme_end = url.find("://")?;
+    let after = &url[scheme_end + 3..];
+    let authority_len = after.find(['/', '?', '#']).unwrap_or(after.len());
+    Some(&url[..scheme_end + 3 + authority_len])
+}
+
+/// 路径与查询串部分，如 `/docs/a?x=1`
+fn url_path(url: &str) -> &str {
+    let origin_len = url_origin(url).map(str::len).unwrap_or(0);
+    let path = url[origin_len..].split('#').next().unwrap_or_default();
+    if path.is_empty() {
+        "/"
+    } else {
+        path
+    }
+}
+
+/// 将链接解析为绝对 http(s) URL（去掉片段），无法跟进的链接返回 None
+pub fn resolve_link(base: &str, href: &str) -> Option<String> {
+    let href = href.trim();
+    let href = href.split('#').next().unwrap_or_default();
+    if href.is_empty() {
+        return None;
+    }
+    let lower = href.to_lowercase();
+    let resolved = if lower.starts_with("http://") || lower.starts_with("https://") {
+        href.to_string()
+    } else if lower.contains(':') && !lower.starts_with('/') && !lower.starts_with('.') {
+        // mailto:、javascript: 等其他协议
+        return None;
+    } else if let Some(rest) = href.strip_prefix("//") {
+        let scheme = base.split("://").next()?;
+        format!("{}://{}", scheme, rest)
+    } else {
+        let origin = url_origin(base)?;
+        let base_path = url_path(base).split('?').next().unwrap_or("/");
+        let joined = if href.starts_with('/') {
+            href.to_string()
+        } else if href.starts_with('?') {
+            format!("{}{}", base_path, href)
+        } else {
+            let dir = &base_path[..base_path.rfind('/').map(|i| i + 1).unwrap_or(0)];
+            format!("{}{}", dir, href)
+        };
+        format!("{}{}", origin, normalize_dot_segments(&joined))
+    };
+
+    let path = url_path(&resolved).split('?').next().unwrap_or_default();
+    let extension = path
+        .rsplit('/')
+        .next()
+        .and_then(|name| name.rsplit_once('.'));
+    if let Some((_, extension)) = extension {
+        if SKIPPED_EXTENSIONS.contains(&extension.to_lowercase().as_str()) {
+            return None;
+        }
+    }
+    Some(resolved)
+}
+
+fn normalize_dot_segments(path: &str) -> String {
+    let (path, query) = match path.split_once('?') {
+        Some((path, query)) => (path, Some(query)),
+        None => (path, None),
+    };
+    let mut segments: Vec<&str> = Vec::new();
+    for segment in path.split('/').skip(1) {
+        match segment {
+            "." => {}
+            ".." => {
+                segments.pop();
+            }
+            _ => segments.push(segment),
+        }
+    }
+    let trailing = path.ends_with("/.") || path.ends_with("/..");
+    let mut normalized = format!("/{}", segments.join("/"));
+    if trailing && !normalized.ends_with('/') {
+        normalized.push('/');
+    }
+    if let Some(query) = query {
+        normalized.push('?');
+        normalized.push_str(query);
+    }
+    normalized
+}
+
+/// 提取 HTML 中可跟进的同站链接（遵守 nofollow），按出现顺序去重
+pub fn extract_followable_links(html: &str, base_url: &str) -> Vec<String> {
+    let page_nofollow = META_ROBOTS_RE.find_iter(html).any(|tag| {
+        attribute(tag.as_str(), &CONTENT_RE)
+            .map(|content| {
+                let content = content.to_lowercase();
+                content.contains("nofollow") || content.contains("none")
+            })
+            .unwrap_or(false)
+    });
+    if page_nofollow {
+        return Vec::new();
+    }
+
+    let base_host = url_host(base_url);
+    let mut links: Vec<String> = Vec::new();
+    for tag in ANCHOR_RE.find_iter(html) {
+        let tag = tag.as_str();
+        let nofollow = attribute(tag, &REL_RE)
+            .map(|rel| {
+                rel.to_lowercase()
+                    .split_whitespace()
+                    .any(|value| matches!(value, "nofollow" | "ugc" | "sponsored"))
+            })
+            .unwrap_or(false);
+        if nofollow {
+            continue;
+        }
+        let Some(link) = attribute(tag, &HREF_RE).and_then(|href| resolve_link(base_url, &href))
+        else {
+            continue;
+        };
+        if url_host(&link) != base_host
+            || link.trim_end_matches('/') == base_url.trim_end_matches('/')
+        {
+            continue;
+        }
+        if !links.contains(&link) {
+            links.push(link);
+        }
+    }
+    links
+}
+
+/// 为主机预约下一次请求时间，返回本次可以发出请求的时刻
+fn reserve_slot(
+    slots: &mut HashMap<String, Instant>,
+    host: &str,
+    now: Instant,
+    interval: Duration,
+) -> Instant {
+    let slot = slots.get(host).copied().unwrap_or(now).max(now);
+    slots.insert(host.to_string(), slot + interval);
+    slot
+}
+
+/// 爬取策略：robots.txt 缓存与按主机限速，在一次研究中共享
+pub struct CrawlPolicy {
+    client: reqwest::Client,
+    respect_robots: bool,
+    host_interval: Duration,
+    robots: Mutex<HashMap<String, Arc<RobotsRules>>>,
+    slots: Mutex<HashMap<String, Instant>>,
+}
+
+impl CrawlPolicy {
+    pub fn new(client: reqwest::Client, respect_robots: bool, host_interval: Duration) -> Self {
+        Self {
+            client,
+            respect_robots,
+            host_interval,
+            robots: Mutex::new(HashMap::new()),
+            slots: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 等待直到可以向该 URL 所在主机发出下一次请求
+    pub async fn wait_turn(&self, url: &str) {
+        let Some(host) = url_host(url) else {
+            return;
+        };
+        let slot = {
+            let mut slots = self.slots.lock().unwrap_or_else(|err| err.into_inner());
+            reserve_slot(&mut slots, &host, Instant::now(), self.host_interval)
+        };
+        tokio::time::sleep_until(slot).await;
+    }
+
+    /// robots.txt 是否允许抓取该 URL；robots.txt 无法获取时视为允许
+    pub async fn is_allowed(&self, url: &str) -> bool {
+        if !self.respect_robots {
+            return true;
+        }
+        let Some(origin) = url_origin(url).map(str::to_lowercase) else {
+            return false;
+        };
+
+        let cached = self
+            .robots
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .get(&origin)
+            .cloned();
+        let rules = match cached {
+            Some(rules) => rules,
+            None => {
+                let rules = Arc::new(self.fetch_robots(&origin).await);
+                self.robots
+                    .lock()
+                    .unwrap_or_else(|err| err.into_inner())
+                    .insert(origin, rules.clone());
+                rules
+            }
+        };
+        rules.is_allowed(url_path(url))
+    }
+
+    async fn fetch_robots(&self, origin: &str) -> RobotsRules {
+        let robots_url = format!("{}/robots.txt", origin);
+        self.wait_turn(&robots_url).await;
+        let response = match self
+            .client
+            .get(&robots_url)
+            .timeout(Duration::from_secs(10))
+            .send()
+            .await
+        {
+            Ok(response) if response.status().is_success() => response,
+            _ => return RobotsRules::default(),
+        };
+        match response.text().await {
+            Ok(content) => RobotsRules::parse(&content, CRAWLER_USER_AGENT),
+            Err(_) => RobotsRules::default(),
+        }
+    }
+
+    /// 获取页面 HTML 并返回可跟进、且 robots.txt 允许的同站链接（最多 `limit` 个）
+    pub async fn discover_links(&self, url: &str, limit: usize) -> Result<Vec<String>, String> {
+        self.wait_turn(url).await;
+        let response = self
+            .client
+            .get(url)
+            .header("User-Agent", CRAWLER_USER_AGENT)
+            .timeout(Duration::from_secs(15))
+            .send()
+            .await
+            .map_err(|e| format!("获取页面链接失败: {}", e))?;
+
+        if !response.status().is_success() {
+            return Err(format!("获取页面链接失败: {}", response.status()));
+        }
+        // 遵守 X-Robots-Tag: nofollow
+        let header_nofollow = response
+            .headers()
+            .get("x-robots-tag")
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.to_lowercase().contains("nofollow"))
+            .unwrap_or(false);
+        if header_nofollow {
+            return Ok(Vec::new());
+        }
+
+        let html = response
+            .text()
+            .await
+            .map_err(|e| format!("读取页面内容失败: {}", e))?;
+        let mut links = Vec::new();
+        for link in extract_followable_links(&html, url) {
+            if links.len() >= limit {
+                break;
+            }
+            if self.is_allowed(&link).await {
+                links.push(link);
+            }
+        }
+        Ok(links)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_robots_rules() {
+        let robots = "User-agent: *\nDisallow: /private/\nAllow: /private/public-*\nDisallow: /*.pdf$\n\nUser-agent: OtherBot\nDisallow: /\n";
+        let rules = RobotsRules::parse(robots, CRAWLER_USER_AGENT);
+        assert!(rules.is_allowed("/docs/intro"));
+        assert!(!rules.is_allowed("/private/notes"));
+        assert!(rules.is_allowed("/private/public-page"));
+        assert!(!rules.is_allowed("/files/a.pdf"));
+        assert!(rules.is_allowed("/files/a.pdf?download=1"));
+
+        let specific = "User-agent: *\nDisallow:\n\nUser-agent: luminanote\nUser-agent: Other\nDisallow: /drafts\n";
+        let rules = RobotsRules::parse(specific, CRAWLER_USER_AGENT);
+        assert!(!rules.is_allowed("/drafts/x"));
+        assert!(rules.is_allowed("/docs"));
+
+        assert!(RobotsRules::parse("", CRAWLER_USER_AGENT).is_allowed("/anything"));
+    }
+
+    #[test]
+    fn test_resolve_link() {
+        let base = "https://docs.example.com/guide/intro.html?x=1";
+        assert_eq!(
+            resolve_link(base, "setup.html#top").as_deref(),
+            Some("https://docs.example.com/guide/setup.html")
+        );
+        assert_eq!(
+            resolve_link(base, "../api/").as_deref(),
+            Some("https://docs.example.com/api/")
+        );
+        assert_eq!(
+            resolve_link(base, "/faq?lang=en").as_deref(),
+            Some("https://docs.example.com/faq?lang=en")
+        );
+        assert_eq!(
+            resolve_link(base, "//cdn.example.com/page").as_deref(),
+            Some("https://cdn.example.com/page")
+        );
+        assert_eq!(resolve_link(base, "mailto:a@b.c"), None);
+        assert_eq!(resolve_link(base, "#section"), None);
+        assert_eq!(resolve_link(base, "logo.PNG"), None);
+    }
+
+    #[test]
+    fn test_extract_followable_links() {
+        let base = "https://docs.example.com/guide/";
+        let html = r#"<html><head></head><body>
+            <a href="setup">Setup</a>
+            <a class="x" href='/api' rel="noopener">API</a>
+            <a href="/ads" rel="nofollow sponsored">Ad</a>
+            <a href="https://other.com/page">Other</a>
+            <a href="setup#again">Again</a>
+            <a href="./">Self</a>
+        </body></html>"#;
+        assert_eq!(
+            extract_followable_links(html, base),
+            vec![
+                "https://docs.example.com/guide/setup".to_string(),
+                "https://docs.example.com/api".to_string(),
+            ]
+        );
+
+        let nofollow_page = r#"<meta name="robots" content="index, nofollow"><a href="/a">A</a>"#;
+        assert!(extract_followable_links(nofollow_page, base).is_empty());
+    }
+
+    #[test]
+    fn test_reserve_slot_spaces_requests_per_host() {
+        let mut slots = HashMap::new();
+        let now = Instant::now();
+        let interval = Duration::from_millis(500);
+
+        assert_eq!(reserve_slot(&mut slots, "a.com", now, interval), now);
+        assert_eq!(
+            reserve_slot(&mut slots, "a.com", now, interval),
+            now + interval
+        );
+        assert_eq!(reserve_slot(&mut slots, "b.com", now, interval), now);
+
+        let later = now + Duration::from_secs(5);
+        assert_eq!(reserve_slot(&mut slots, "a.com", later, interval), later);
+    }
+}
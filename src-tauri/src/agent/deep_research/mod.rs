@@ -3,10 +3,15 @@
 //! 针对笔记库进行深度研究，生成综合报告
 
 pub mod builder;
+pub mod citations;
+pub mod crawl_policy;
 pub mod crawler;
 pub mod nodes;
+pub mod progress;
+pub mod searxng;
 pub mod tavily;
 pub mod types;
+pub mod web_search;
 
 pub use builder::{build_deep_research_graph, DeepResearchContext};
 pub use types::*;
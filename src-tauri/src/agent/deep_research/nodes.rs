@@ -2,16 +2,26 @@
 
 use std::path::Path;
 use std::sync::Arc;
-use tauri::{AppHandle, Emitter};
+use tauri::{AppHandle, Runtime};
 use walkdir::WalkDir;
 
+use crate::agent::deep_research::citations::{
+    collect_citation_sources, count_citation_markers, finalize_citations, format_section_sources,
+    format_source_catalog,
+};
+use crate::agent::deep_research::crawl_policy::CrawlPolicy;
 use crate::agent::deep_research::crawler::JinaClient;
-use crate::agent::deep_research::tavily::TavilyClient;
+use crate::agent::deep_research::progress::emit_research_event;
 use crate::agent::deep_research::types::*;
-use crate::agent::llm_client::LlmClient;
+use crate::agent::deep_research::web_search::{normalize_url, WebSearchFilter, WebSearchProvider};
+use crate::agent::llm_backend::LlmBackend;
+use crate::agent::llm_provider::ResponseSchema;
+use crate::forge_runtime::frontmatter::{frontmatter_string_list, parse_frontmatter};
 use forge::runtime::error::Interrupt;
 use regex::Regex;
-use std::collections::{HashMap, HashSet};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet, VecDeque};
 
 /// 节点执行结果
 pub struct NodeResult {
@@ -20,13 +30,13 @@ pub struct NodeResult {
 }
 
 /// 发送事件到前端
-fn emit_event(app: &AppHandle, event: DeepResearchEvent) {
-    let _ = app.emit("deep-research-event", &event);
+fn emit_event<R: Runtime>(app: &AppHandle<R>, event: DeepResearchEvent) {
+    emit_research_event(app, &event);
 }
 
 /// 发送 Token 使用量事件
-fn emit_token_usage(
-    app: &AppHandle,
+fn emit_token_usage<R: Runtime>(
+    app: &AppHandle<R>,
     prompt_tokens: usize,
     completion_tokens: usize,
     total_tokens: usize,
@@ -79,64 +89,40 @@ fn extract_json(text: &str) -> Result<String, String> {
     }
 
     // 3. 尝试找到 JSON 对象的边界 { ... }
-    if let Some(start_idx) = text.find('{') {
-        // 找到匹配的结束括号
-        let mut depth = 0;
-        let mut end_idx = start_idx;
-        let chars: Vec<char> = text.chars().collect();
-
-        for (i, &ch) in chars.iter().enumerate().skip(start_idx) {
-            match ch {
-                '{' => depth += 1,
-                '}' => {
-                    depth -= 1;
-                    if depth == 0 {
-                        end_idx = i;
-                        break;
-                    }
-                }
-                _ => {}
-            }
-        }
-
-        if depth == 0 && end_idx > start_idx {
-            let json_str: String = chars[start_idx..=end_idx].iter().collect();
-            return Ok(json_str);
-        }
+    if let Some(json_str) = find_balanced(text, '{', '}') {
+        return Ok(json_str.to_string());
     }
 
     // 4. 尝试找到 JSON 数组的边界 [ ... ]
-    if let Some(start_idx) = text.find('[') {
-        let mut depth = 0;
-        let mut end_idx = start_idx;
-        let chars: Vec<char> = text.chars().collect();
-
-        for (i, &ch) in chars.iter().enumerate().skip(start_idx) {
-            match ch {
-                '[' => depth += 1,
-                ']' => {
-                    depth -= 1;
-                    if depth == 0 {
-                        end_idx = i;
-                        break;
-                    }
-                }
-                _ => {}
-            }
-        }
-
-        if depth == 0 && end_idx > start_idx {
-            let json_str: String = chars[start_idx..=end_idx].iter().collect();
-            return Ok(json_str);
-        }
+    if let Some(json_str) = find_balanced(text, '[', ']') {
+        return Ok(json_str.to_string());
     }
 
     // 5. 如果都找不到，返回原文（让 JSON 解析器报告具体错误）
     Ok(text.to_string())
 }
 
+/// 从第一个 `open` 开始找到配对的 `close`，返回包含两端的切片
+///
+/// 按字节下标切片，前缀中含多字节字符（中文说明文字）时也能定位。
+fn find_balanced(text: &str, open: char, close: char) -> Option<&str> {
+    let start = text.find(open)?;
+    let mut depth = 0usize;
+    for (offset, ch) in text[start..].char_indices() {
+        if ch == open {
+            depth += 1;
+        } else if ch == close {
+            depth -= 1;
+            if depth == 0 {
+                return Some(&text[start..start + offset + ch.len_utf8()]);
+            }
+        }
+    }
+    None
+}
+
 /// 解析 JSON 并提供更好的错误信息
-fn parse_json<T: serde::de::DeserializeOwned>(text: &str, context: &str) -> Result<T, String> {
+fn parse_json<T: DeserializeOwned>(text: &str, context: &str) -> Result<T, String> {
     let json_str = extract_json(text)?;
 
     serde_json::from_str(&json_str).map_err(|e| {
@@ -146,6 +132,140 @@ fn parse_json<T: serde::de::DeserializeOwned>(text: &str, context: &str) -> Resu
     })
 }
 
+/// 严格解析：只容忍整体包裹的 markdown 代码块，不从夹杂文字中提取
+fn parse_json_strict<T: DeserializeOwned>(text: &str) -> Result<T, String> {
+    let text = text.trim();
+    let unfenced = text
+        .strip_prefix("```json")
+        .or_else(|| text.strip_prefix("```"))
+        .and_then(|rest| rest.strip_suffix("```"))
+        .unwrap_or(text);
+    serde_json::from_str(unfenced.trim()).map_err(|e| e.to_string())
+}
+
+/// 修复提示中保留的上次输出长度
+const JSON_REPAIR_PREVIEW_CHARS: usize = 2000;
+
+/// 请求结构化 JSON 输出
+///
+/// 1. 按 JSON Schema 请求（provider 支持时由服务端约束输出）
+/// 2. 严格解析失败时，把解析错误反馈给模型修正一次
+/// 3. 仍然失败时用宽松解析（`extract_json`）兜底
+async fn request_json<T, R, L>(
+    app: &AppHandle<R>,
+    llm: &Arc<L>,
+    prompt: &str,
+    schema: &ResponseSchema,
+    context: &str,
+) -> Result<T, String>
+where
+    T: DeserializeOwned,
+    R: Runtime,
+    L: LlmBackend,
+{
+    let response = llm.call_json_with_usage(prompt, schema).await?;
+    emit_token_usage(
+        app,
+        response.prompt_tokens,
+        response.completion_tokens,
+        response.total_tokens,
+    );
+    let parse_error = match parse_json_strict(&response.content) {
+        Ok(value) => return Ok(value),
+        Err(e) => e,
+    };
+
+    let previous: String = response
+        .content
+        .chars()
+        .take(JSON_REPAIR_PREVIEW_CHARS)
+        .collect();
+    let repair_prompt = format!(
+        r#"你上一次的输出不是符合要求的 JSON，解析错误：{}
+
+原始任务：
+{}
+
+你上一次的输出：
+{}
+
+请修正后重新输出。只返回 JSON 本身，不要代码块或其他文字。"#,
+        parse_error, prompt, previous
+    );
+    match llm.call_json_with_usage(&repair_prompt, schema).await {
+        Ok(repaired) => {
+            emit_token_usage(
+                app,
+                repaired.prompt_tokens,
+                repaired.completion_tokens,
+                repaired.total_tokens,
+            );
+            if let Ok(value) = parse_json_strict(&repaired.content)
+                .or_else(|_| parse_json(&repaired.content, context))
+            {
+                return Ok(value);
+            }
+        }
+        Err(e) => println!("[DeepResearch] JSON 修复请求失败: {}", e),
+    }
+
+    parse_json(&response.content, context)
+}
+
+/// 意图判断结果
+#[derive(Debug, Deserialize)]
+struct TopicIntent {
+    intent: String,
+    #[serde(default)]
+    clarify_question: Option<String>,
+    #[serde(default)]
+    clarify_suggestions: Option<Vec<String>>,
+}
+
+fn intent_schema() -> ResponseSchema {
+    ResponseSchema {
+        name: "research_intent".to_string(),
+        schema: serde_json::json!({
+            "type": "object",
+            "properties": {
+                "intent": { "type": "string", "enum": ["RESEARCH", "CHAT", "CLARIFY"] },
+                "reason": { "type": "string" },
+                "clarify_question": { "type": "string" },
+                "clarify_suggestions": { "type": "array", "items": { "type": "string" } },
+            },
+            "required": ["intent", "reason", "clarify_question", "clarify_suggestions"],
+            "additionalProperties": false,
+        }),
+    }
+}
+
+fn outline_schema() -> ResponseSchema {
+    ResponseSchema {
+        name: "report_outline".to_string(),
+        schema: serde_json::json!({
+            "type": "object",
+            "properties": {
+                "title": { "type": "string" },
+                "sections": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "heading": { "type": "string" },
+                            "points": { "type": "array", "items": { "type": "string" } },
+                            "related_notes": { "type": "array", "items": { "type": "string" } },
+                        },
+                        "required": ["heading", "points", "related_notes"],
+                        "additionalProperties": false,
+                    },
+                },
+            },
+            "required": ["title", "sections"],
+            "additionalProperties": false,
+        }),
+    }
+}
+
 fn normalize_note_key(input: &str) -> String {
     input.trim().trim_end_matches(".md").to_lowercase()
 }
@@ -164,22 +284,6 @@ fn dedupe_notes_keep_best(notes: Vec<NoteReference>) -> Vec<NoteReference> {
     by_path.into_values().collect()
 }
 
-fn normalize_url(url: &str) -> String {
-    url.trim().trim_end_matches('/').to_lowercase()
-}
-
-fn dedupe_web_results(results: Vec<WebSearchResult>) -> Vec<WebSearchResult> {
-    let mut seen = HashSet::new();
-    let mut deduped = Vec::new();
-    for result in results {
-        let key = normalize_url(&result.url);
-        if seen.insert(key) {
-            deduped.push(result);
-        }
-    }
-    deduped
-}
-
 fn collect_known_note_references(state: &DeepResearchState) -> HashSet<String> {
     let mut known = HashSet::new();
     for note in &state.read_notes {
@@ -224,15 +328,18 @@ fn count_markdown_headings(report: &str) -> usize {
 
 // ============ 节点实现 ============
 
+/// 最多向用户请求澄清的轮数
+const MAX_CLARIFICATION_ROUNDS: usize = 3;
+
 /// 分析主题节点
 ///
 /// 分析用户输入的研究主题，提取关键词用于搜索
 /// - 如果是简单问候/闲聊，直接回复
-/// - 如果主题不够明确，触发 interrupt 请求用户澄清
-/// - 如果有 clarification，使用它来增强研究主题
-pub async fn analyze_topic_node(
-    app: &AppHandle,
-    llm: &Arc<LlmClient>,
+/// - 如果主题不够明确，触发 interrupt 请求用户澄清（最多 MAX_CLARIFICATION_ROUNDS 轮）
+/// - 使用全部澄清历史来增强研究主题
+pub async fn analyze_topic_node<R: Runtime, L: LlmBackend>(
+    app: &AppHandle<R>,
+    llm: &Arc<L>,
     mut state: DeepResearchState,
 ) -> Result<NodeResult, String> {
     state.phase = ResearchPhase::AnalyzingTopic;
@@ -241,15 +348,12 @@ pub async fn analyze_topic_node(
         DeepResearchEvent::PhaseChange {
             phase: state.phase.clone(),
             message: "正在分析研究主题...".to_string(),
+            preset: None,
         },
     );
 
-    // 如果有用户澄清，将其合并到主题中
-    let effective_topic = if let Some(ref clarification) = state.clarification {
-        format!("{}\n\n用户补充说明：{}", state.topic, clarification)
-    } else {
-        state.topic.clone()
-    };
+    // 合并全部澄清历史，避免多轮澄清时丢失上下文
+    let effective_topic = state.effective_topic();
 
     // 第一步：让 LLM 判断意图和是否需要澄清
     let intent_prompt = format!(
@@ -274,21 +378,20 @@ pub async fn analyze_topic_node(
         effective_topic
     );
 
-    let intent_response = llm
-        .call_simple(&intent_prompt)
-        .await
-        .unwrap_or_else(|_| r#"{"intent": "RESEARCH"}"#.to_string());
-
-    // 解析意图（使用健壮的 JSON 提取）
-    let intent_json: serde_json::Value = extract_json(&intent_response)
-        .ok()
-        .and_then(|s| serde_json::from_str(&s).ok())
-        .unwrap_or_else(|| serde_json::json!({"intent": "RESEARCH"}));
+    // 解析意图（结构化输出 + 修复，仍失败时按研究请求处理）
+    let topic_intent: TopicIntent =
+        request_json(app, llm, &intent_prompt, &intent_schema(), "解析意图失败")
+            .await
+            .unwrap_or_else(|e| {
+                println!("[DeepResearch] {}，按研究请求处理", e);
+                TopicIntent {
+                    intent: "RESEARCH".to_string(),
+                    clarify_question: None,
+                    clarify_suggestions: None,
+                }
+            });
 
-    let intent = intent_json["intent"]
-        .as_str()
-        .unwrap_or("RESEARCH")
-        .to_uppercase();
+    let intent = topic_intent.intent.trim().to_uppercase();
 
     // 如果是闲聊，直接回复
     if intent == "CHAT" {
@@ -354,20 +457,16 @@ pub async fn analyze_topic_node(
         });
     }
 
-    // 如果需要澄清且还没有收到澄清
-    if intent == "CLARIFY" && state.clarification.is_none() {
-        let question = intent_json["clarify_question"]
-            .as_str()
-            .unwrap_or("请问您具体想研究什么内容？")
-            .to_string();
-
-        let suggestions: Vec<String> = intent_json["clarify_suggestions"]
-            .as_array()
-            .map(|arr| {
-                arr.iter()
-                    .filter_map(|v| v.as_str().map(String::from))
-                    .collect()
-            })
+    // 如果仍需要澄清且未超过轮数上限（超过后按现有信息继续研究）
+    if intent == "CLARIFY" && state.clarifications.len() < MAX_CLARIFICATION_ROUNDS {
+        let question = topic_intent
+            .clarify_question
+            .filter(|q| !q.trim().is_empty())
+            .unwrap_or_else(|| "请问您具体想研究什么内容？".to_string());
+
+        let suggestions: Vec<String> = topic_intent
+            .clarify_suggestions
+            .filter(|s| !s.is_empty())
             .unwrap_or_else(|| {
                 vec![
                     "可以说明具体想了解的方面".to_string(),
@@ -383,6 +482,7 @@ pub async fn analyze_topic_node(
                 "question": question,
                 "suggestions": suggestions,
                 "original_topic": state.topic,
+                "round": state.clarifications.len() + 1,
             }),
             "analyze_topic",
         );
@@ -465,14 +565,15 @@ React
 /// - Keyword: 使用关键词文件搜索
 /// - Hybrid: 合并两者结果
 ///
-/// 同时可选支持网络搜索（Tavily）
-pub async fn search_notes_node(
-    app: &AppHandle,
-    _llm: &Arc<LlmClient>,
+/// 同时可选支持网络搜索（Tavily / SearXNG）
+pub async fn search_notes_node<R: Runtime, L: LlmBackend>(
+    app: &AppHandle<R>,
+    _llm: &Arc<L>,
     mut state: DeepResearchState,
     max_results: usize,
-    tavily: Option<&Arc<TavilyClient>>,
+    web_search: Option<&Arc<dyn WebSearchProvider>>,
     max_web_results: usize,
+    web_filter: &WebSearchFilter,
 ) -> Result<NodeResult, String> {
     state.phase = ResearchPhase::SearchingNotes;
 
@@ -482,7 +583,7 @@ pub async fn search_notes_node(
         SearchMode::Hybrid => "混合搜索",
     };
 
-    let web_search_msg = if tavily.is_some() {
+    let web_search_msg = if web_search.is_some() {
         " + 网络搜索"
     } else {
         ""
@@ -498,6 +599,7 @@ pub async fn search_notes_node(
                 web_search_msg,
                 state.keywords.join(", ")
             ),
+            preset: None,
         },
     );
 
@@ -575,7 +677,7 @@ pub async fn search_notes_node(
     );
 
     // ============ 网络搜索 ============
-    if let Some(tavily_client) = tavily {
+    if let Some(provider) = web_search {
         // 切换到网络搜索阶段
         state.phase = ResearchPhase::SearchingWeb;
         emit_event(
@@ -583,18 +685,18 @@ pub async fn search_notes_node(
             DeepResearchEvent::PhaseChange {
                 phase: state.phase.clone(),
                 message: "正在搜索网络获取相关内容...".to_string(),
+                preset: None,
             },
         );
 
         #[cfg(debug_assertions)]
-        println!("[DeepResearch] 执行网络搜索...");
+        println!("[DeepResearch] 执行网络搜索（{}）...", provider.name());
 
         // 使用主题作为搜索查询
         let query = format!("{} {}", state.topic, state.keywords.join(" "));
 
-        match tavily_client.search(&query, max_web_results).await {
-            Ok(web_results) => {
-                let deduped_results = dedupe_web_results(web_results);
+        match provider.search(&query, max_web_results, web_filter).await {
+            Ok(deduped_results) => {
                 #[cfg(debug_assertions)]
                 println!(
                     "[DeepResearch] 网络搜索找到 {} 个结果（去重后）",
@@ -640,17 +742,20 @@ pub async fn search_notes_node(
 
 /// 爬取网页节点
 ///
-/// 分批爬取：每次爬取 BATCH_SIZE 个，直到达到 max_pages 或内容总长度达到限制
-/// 这样可以更快开始生成报告，同时控制 prompt 长度
-pub async fn crawl_web_node(
-    app: &AppHandle,
+/// 从搜索结果出发按广度优先爬取：深度小于 max_depth 的页面会跟进其中的同站链接，
+/// 直到达到 max_pages 或内容总长度限制。启用爬取策略时遵守 robots.txt、nofollow
+/// 并对同一主机限速。
+pub async fn crawl_web_node<R: Runtime>(
+    app: &AppHandle<R>,
     mut state: DeepResearchState,
     jina: Option<&Arc<JinaClient>>,
+    policy: Option<&Arc<CrawlPolicy>>,
     max_pages: usize,
+    max_depth: usize,
 ) -> Result<NodeResult, String> {
-    const BATCH_SIZE: usize = 2; // 每批爬取 2 个
     const MAX_TOTAL_CONTENT_CHARS: usize = 15000; // 总内容限制 15000 字符
     const MAX_PER_PAGE_CHARS: usize = 3000; // 每页内容限制 3000 字符
+    const MAX_LINKS_PER_PAGE: usize = 5; // 每页最多跟进 5 个链接
 
     // 如果没有网络搜索结果，直接跳到下一步
     if state.web_search_results.is_empty() {
@@ -674,23 +779,36 @@ pub async fn crawl_web_node(
     };
 
     state.phase = ResearchPhase::CrawlingWeb;
-    let total_available = state.web_search_results.len().min(max_pages);
+    let total_available = if max_depth == 0 {
+        state.web_search_results.len().min(max_pages)
+    } else {
+        max_pages
+    };
 
     emit_event(
         app,
         DeepResearchEvent::PhaseChange {
             phase: state.phase.clone(),
             message: format!("正在爬取网页内容（最多 {} 个）...", total_available),
+            preset: None,
         },
     );
 
+    // 待爬取队列：(URL, 标题, 深度, 来源页面)
+    let mut queue: VecDeque<(String, String, usize, Option<String>)> = state
+        .web_search_results
+        .iter()
+        .map(|r| (r.url.clone(), r.title.clone(), 0, None))
+        .collect();
+    let mut visited: HashSet<String> = HashSet::new();
+    let mut attempted = 0usize;
     let mut total_content_chars = 0usize;
-    #[cfg(debug_assertions)]
-    let mut crawled_count = 0usize;
 
-    // 分批爬取
-    for (index, web_result) in state.web_search_results.iter().take(max_pages).enumerate() {
-        // 检查是否达到内容限制
+    while let Some((url, title, depth, found_via)) = queue.pop_front() {
+        // 检查是否达到页面数或内容限制
+        if attempted >= max_pages {
+            break;
+        }
         if total_content_chars >= MAX_TOTAL_CONTENT_CHARS {
             #[cfg(debug_assertions)]
             println!(
@@ -699,26 +817,38 @@ pub async fn crawl_web_node(
             );
             break;
         }
+        if !visited.insert(normalize_url(&url)) {
+            continue;
+        }
+        if let Some(policy) = policy {
+            if !policy.is_allowed(&url).await {
+                #[cfg(debug_assertions)]
+                println!("[DeepResearch] robots.txt 禁止爬取，跳过: {}", url);
+                continue;
+            }
+        }
+        attempted += 1;
 
         emit_event(
             app,
             DeepResearchEvent::CrawlingPage {
-                url: web_result.url.clone(),
-                title: web_result.title.clone(),
-                index: index + 1,
+                url: url.clone(),
+                title: title.clone(),
+                index: attempted,
                 total: total_available,
             },
         );
 
         #[cfg(debug_assertions)]
         println!(
-            "[DeepResearch] 爬取网页 {}/{}: {}",
-            index + 1,
-            total_available,
-            web_result.url
+            "[DeepResearch] 爬取网页 {}/{}（深度 {}）: {}",
+            attempted, total_available, depth, url
         );
 
-        match jina_client.crawl(&web_result.url).await {
+        if let Some(policy) = policy {
+            policy.wait_turn(&url).await;
+        }
+        match jina_client.crawl(&url).await {
             Ok(crawled) => {
                 // 截断单页内容
                 let truncated_content: String =
@@ -740,33 +870,49 @@ pub async fn crawl_web_node(
                     url: crawled.url,
                     title: crawled.title,
                     content: truncated_content,
+                    depth,
+                    found_via,
                 });
 
                 total_content_chars += content_len;
-                #[cfg(debug_assertions)]
-                {
-                    crawled_count += 1;
-                }
             }
             Err(err) => {
                 #[cfg(debug_assertions)]
-                eprintln!("[DeepResearch] 爬取网页失败: {} - {}", web_result.url, err);
+                eprintln!("[DeepResearch] 爬取网页失败: {} - {}", url, err);
                 #[cfg(not(debug_assertions))]
                 let _ = err;
                 // 爬取失败不影响整体流程，继续处理下一个
+                continue;
             }
         }
 
-        // 每爬完一批，短暂暂停避免请求过快
-        if (index + 1) % BATCH_SIZE == 0 && index + 1 < total_available {
-            tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        // 跟进页面中的链接（需要原始 HTML 才能识别 nofollow）
+        if depth < max_depth {
+            if let Some(policy) = policy {
+                match policy.discover_links(&url, MAX_LINKS_PER_PAGE).await {
+                    Ok(links) => {
+                        for link in links {
+                            if !visited.contains(&normalize_url(&link)) {
+                                queue.push_back((link.clone(), link, depth + 1, Some(url.clone())));
+                            }
+                        }
+                    }
+                    Err(err) => {
+                        #[cfg(debug_assertions)]
+                        eprintln!("[DeepResearch] 获取页面链接失败: {} - {}", url, err);
+                        #[cfg(not(debug_assertions))]
+                        let _ = err;
+                    }
+                }
+            }
         }
     }
 
     #[cfg(debug_assertions)]
     println!(
         "[DeepResearch] 成功爬取 {} 个网页，总内容 {} 字符",
-        crawled_count, total_content_chars
+        state.crawled_pages.len(),
+        total_content_chars
     );
 
     Ok(NodeResult {
@@ -882,20 +1028,14 @@ fn keyword_search(
 /// 从笔记内容中提取标题
 fn extract_title(content: &str, path: &Path) -> String {
     // 尝试从 frontmatter 中提取 title
-    if content.starts_with("---") {
-        if let Some(end) = content[3..].find("\n---") {
-            let frontmatter = &content[3..3 + end];
-            for line in frontmatter.lines() {
-                let line = line.trim();
-                if line.starts_with("title:") {
-                    return line[6..]
-                        .trim()
-                        .trim_matches('"')
-                        .trim_matches('\'')
-                        .to_string();
-                }
-            }
-        }
+    if let Some(title) = parse_frontmatter(content)
+        .as_ref()
+        .and_then(|fm| fm.get("title"))
+        .and_then(|value| value.as_str())
+        .map(str::trim)
+        .filter(|title| !title.is_empty())
+    {
+        return title.to_string();
     }
 
     // 尝试从第一个 # 标题提取
@@ -969,27 +1109,12 @@ fn get_random_tags(workspace_path: &str, count: usize) -> Vec<String> {
         }
 
         if let Ok(content) = std::fs::read_to_string(path) {
-            // 从 frontmatter 提取 tags
-            if content.starts_with("---") {
-                if let Some(end) = content[3..].find("\n---") {
-                    let frontmatter = &content[3..3 + end];
-                    for line in frontmatter.lines() {
-                        let line = line.trim();
-                        if line.starts_with("tags:") {
-                            // 支持 tags: [tag1, tag2] 或 tags: tag1, tag2
-                            let tags_str = line[5..].trim();
-                            let tags_str = tags_str.trim_start_matches('[').trim_end_matches(']');
-                            for tag in tags_str.split(',') {
-                                let tag = tag
-                                    .trim()
-                                    .trim_matches('"')
-                                    .trim_matches('\'')
-                                    .trim_matches('#');
-                                if !tag.is_empty() && tag.len() < 20 {
-                                    all_tags.insert(tag.to_string());
-                                }
-                            }
-                        }
+            // 从 frontmatter 提取 tags（支持 YAML 列表或逗号分隔字符串）
+            if let Some(tags) = parse_frontmatter(&content).and_then(|fm| fm.get("tags").cloned()) {
+                for tag in frontmatter_string_list(&tags) {
+                    let tag = tag.trim_matches('#');
+                    if !tag.is_empty() && tag.len() < 20 {
+                        all_tags.insert(tag.to_string());
                     }
                 }
             }
@@ -1018,9 +1143,9 @@ fn get_random_tags(workspace_path: &str, count: usize) -> Vec<String> {
 ///
 /// 批量读取找到的笔记内容
 /// 如果没有本地笔记但有网络结果，跳过此阶段直接生成大纲
-pub async fn read_notes_node(
-    app: &AppHandle,
-    llm: &Arc<LlmClient>,
+pub async fn read_notes_node<R: Runtime, L: LlmBackend>(
+    app: &AppHandle<R>,
+    llm: &Arc<L>,
     mut state: DeepResearchState,
     max_notes: usize,
 ) -> Result<NodeResult, String> {
@@ -1039,6 +1164,7 @@ pub async fn read_notes_node(
                 DeepResearchEvent::PhaseChange {
                     phase: ResearchPhase::ReadingNotes,
                     message: "本地无相关笔记，将基于网络搜索结果生成报告...".to_string(),
+                    preset: None,
                 },
             );
 
@@ -1066,6 +1192,7 @@ pub async fn read_notes_node(
                 "正在阅读 {} 篇相关笔记...",
                 state.found_notes.len().min(max_notes)
             ),
+            preset: None,
         },
     );
 
@@ -1152,17 +1279,20 @@ pub async fn read_notes_node(
 /// 生成大纲节点
 ///
 /// 基于阅读的笔记内容和/或网络搜索结果，生成报告大纲
-pub async fn generate_outline_node(
-    app: &AppHandle,
-    llm: &Arc<LlmClient>,
+pub async fn generate_outline_node<R: Runtime, L: LlmBackend>(
+    app: &AppHandle<R>,
+    llm: &Arc<L>,
     mut state: DeepResearchState,
+    preset: ReportPreset,
 ) -> Result<NodeResult, String> {
+    let spec = preset.spec();
     state.phase = ResearchPhase::GeneratingOutline;
     emit_event(
         app,
         DeepResearchEvent::PhaseChange {
             phase: state.phase.clone(),
-            message: "正在生成报告大纲...".to_string(),
+            message: format!("正在生成报告大纲（{}）...", preset.label()),
+            preset: None,
         },
     );
 
@@ -1232,7 +1362,8 @@ pub async fn generate_outline_node(
 相关笔记：
 {}{}
 
-请生成一个 JSON 格式的报告大纲，包含标题和 3-5 个章节，每个章节有要点和相关引用来源。
+报告类型：{}，全文约 {} 字。
+请生成一个 JSON 格式的报告大纲，包含标题和 {}-{} 个章节，每个章节有 {} 个要点和相关引用来源。
 格式：
 {{
   "title": "报告标题",
@@ -1246,19 +1377,18 @@ pub async fn generate_outline_node(
 }}
 
 请直接返回 JSON，不要其他内容："#,
-        state.topic, notes_summary, web_summary
-    );
-
-    let response = llm.call_simple_with_usage(&prompt).await?;
-    emit_token_usage(
-        app,
-        response.prompt_tokens,
-        response.completion_tokens,
-        response.total_tokens,
+        state.topic,
+        notes_summary,
+        web_summary,
+        preset.label(),
+        spec.target_words,
+        spec.min_sections,
+        spec.max_sections,
+        spec.points_per_section
     );
 
-    // 解析 JSON（使用健壮的 JSON 提取）
-    let outline: ReportOutline = parse_json(&response.content, "解析大纲失败")?;
+    let outline: ReportOutline =
+        request_json(app, llm, &prompt, &outline_schema(), "解析大纲失败").await?;
 
     emit_event(
         app,
@@ -1278,21 +1408,31 @@ pub async fn generate_outline_node(
 /// 撰写报告节点
 ///
 /// 基于大纲和笔记内容/网络搜索结果，生成完整报告
-pub async fn write_report_node(
-    app: &AppHandle,
-    llm: &Arc<LlmClient>,
+pub async fn write_report_node<R: Runtime, L: LlmBackend>(
+    app: &AppHandle<R>,
+    llm: &Arc<L>,
     mut state: DeepResearchState,
     include_citations: bool,
+    preset: ReportPreset,
 ) -> Result<NodeResult, String> {
+    let spec = preset.spec();
     state.phase = ResearchPhase::WritingReport;
     emit_event(
         app,
         DeepResearchEvent::PhaseChange {
             phase: state.phase.clone(),
             message: "正在撰写研究报告...".to_string(),
+            preset: None,
         },
     );
 
+    // 启用引用时为笔记与网页来源统一编号，相同来源只编号一次
+    state.citation_sources = if include_citations {
+        collect_citation_sources(&state)
+    } else {
+        Vec::new()
+    };
+
     let outline = state.outline.as_ref().ok_or("缺少报告大纲")?;
 
     // 构建笔记内容参考（移除 .md 后缀以便 LLM 正确生成双链）
@@ -1337,19 +1477,25 @@ pub async fn write_report_node(
         String::new()
     };
 
-    let has_web_content = !state.crawled_pages.is_empty() || !state.web_search_results.is_empty();
-    let citation_instruction = if include_citations {
-        let web_note = if has_web_content {
-            " 引用网络来源时，请使用 Markdown 链接格式 [标题](URL)。"
+    let (citation_instruction, sources_block) = if include_citations
+        && !state.citation_sources.is_empty()
+    {
+        let section_sources = format_section_sources(outline, &state.citation_sources);
+        let section_hint = if section_sources.is_empty() {
+            String::new()
         } else {
-            ""
+            format!("\n\n各章节建议引用：\n{}", section_sources)
         };
-        format!(
-            "在引用笔记内容时，请使用 [[笔记名]] 格式标注来源（不要包含 .md 后缀，例如 [[我的笔记]] 而不是 [[我的笔记.md]]）。{}",
-            web_note
+        (
+            "每个事实性陈述后用 [n] 标注所依据的来源编号，多个来源写作 [1][2]，只能使用“可引用来源”中的编号。提到笔记时可使用 [[笔记名]] 双链（不要包含 .md 后缀）。不要自行编写参考文献列表，系统会自动生成。".to_string(),
+            format!(
+                "\n可引用来源：\n{}{}\n",
+                format_source_catalog(&state.citation_sources),
+                section_hint
+            ),
         )
     } else {
-        "不需要标注来源。".to_string()
+        ("不需要标注来源。".to_string(), String::new())
     };
 
     let prompt = format!(
@@ -1363,13 +1509,13 @@ pub async fn write_report_node(
 参考笔记内容：
 {}
 {}
-
+{}
 要求：
 1. 使用 Markdown 格式
-2. 结构清晰，层次分明
-3. 内容详实，有理有据
+2. 报告类型：{}
+3. 内容有理有据，每个章节约 {} 字
 4. {}
-5. 报告长度适中，不少于 500 字
+5. 全文约 {} 字，不少于 {} 字
 6. 优先使用笔记库中的内容，网络搜索结果作为补充
 
 请直接输出报告内容："#,
@@ -1377,7 +1523,12 @@ pub async fn write_report_node(
         serde_json::to_string_pretty(&outline).unwrap_or_default(),
         notes_content,
         web_content,
-        citation_instruction
+        sources_block,
+        preset.writing_guidance(),
+        spec.words_per_section,
+        citation_instruction,
+        spec.target_words,
+        spec.min_words
     );
 
     // 使用流式输出
@@ -1402,18 +1553,21 @@ pub async fn write_report_node(
 /// 审校报告节点
 ///
 /// 对报告进行结构、引用和完整性审查，必要时自动修订。
-pub async fn review_report_node(
-    app: &AppHandle,
-    llm: &Arc<LlmClient>,
+pub async fn review_report_node<R: Runtime, L: LlmBackend>(
+    app: &AppHandle<R>,
+    llm: &Arc<L>,
     mut state: DeepResearchState,
     include_citations: bool,
+    preset: ReportPreset,
 ) -> Result<NodeResult, String> {
+    let spec = preset.spec();
     state.phase = ResearchPhase::ReviewingReport;
     emit_event(
         app,
         DeepResearchEvent::PhaseChange {
             phase: state.phase.clone(),
             message: "正在审校报告质量与引用...".to_string(),
+            preset: None,
         },
     );
 
@@ -1426,16 +1580,16 @@ pub async fn review_report_node(
         .unwrap_or(0);
     let known_note_refs = collect_known_note_references(&state);
     let unknown_wikilinks = collect_unknown_wikilinks(&report, &known_note_refs);
-    let has_wikilinks = report.contains("[[");
-    let has_web_links = report.contains("http://") || report.contains("https://");
+    let source_count = state.citation_sources.len();
+    let valid_citations = count_citation_markers(&report, source_count);
 
     let citation_ok = if include_citations {
-        unknown_wikilinks.is_empty() && (has_wikilinks || has_web_links)
+        unknown_wikilinks.is_empty() && (valid_citations > 0 || source_count == 0)
     } else {
         true
     };
     let structure_ok = heading_count >= outline_sections.saturating_sub(1).max(1);
-    let length_ok = report.chars().count() >= 500;
+    let length_ok = report.chars().count() >= spec.min_words;
 
     let needs_revision = !structure_ok
         || !length_ok
@@ -1443,18 +1597,42 @@ pub async fn review_report_node(
 
     let mut final_report = report;
     if needs_revision {
-        let notes_catalog = state
-            .read_notes
-            .iter()
-            .map(|note| format!("- {} ({})", note.title.trim_end_matches(".md"), note.path))
-            .collect::<Vec<_>>()
-            .join("\n");
-        let web_catalog = state
-            .crawled_pages
-            .iter()
-            .map(|page| format!("- {} ({})", page.title, page.url))
-            .collect::<Vec<_>>()
-            .join("\n");
+        let sources_catalog = if source_count > 0 {
+            format_source_catalog(&state.citation_sources)
+        } else {
+            let notes_catalog = state
+                .read_notes
+                .iter()
+                .map(|note| format!("- {} ({})", note.title.trim_end_matches(".md"), note.path))
+                .collect::<Vec<_>>()
+                .join("\n");
+            let web_catalog = state
+                .crawled_pages
+                .iter()
+                .map(|page| format!("- {} ({})", page.title, page.url))
+                .collect::<Vec<_>>()
+                .join("\n");
+            format!(
+                "笔记：\n{}\n\n网络：\n{}",
+                if notes_catalog.is_empty() {
+                    "（无）"
+                } else {
+                    &notes_catalog
+                },
+                if web_catalog.is_empty() {
+                    "（无）"
+                } else {
+                    &web_catalog
+                }
+            )
+        };
+        let citation_goal = if source_count > 0 {
+            "保留并增强可追溯引用：事实性陈述用 [n] 标注来源编号，只能使用可用来源中的编号，不要编写参考文献列表。"
+        } else if include_citations {
+            "保留并增强可追溯引用：笔记引用用 [[笔记名]]，网络引用用 [标题](URL)。"
+        } else {
+            "不需要标注来源，保持正文为连贯的段落。"
+        };
         let issue_hint = if unknown_wikilinks.is_empty() {
             "无未知笔记引用".to_string()
         } else {
@@ -1467,9 +1645,9 @@ pub async fn review_report_node(
 目标：
 1. 严格对齐既有大纲章节，不减少关键章节。
 2. 不引入新事实，只能重写表达、补全结构、修复引用。
-3. 保留并增强可追溯引用：笔记引用用 [[笔记名]]，网络引用用 [标题](URL)。
+3. {}
 4. 如果存在未知引用，请替换为可用来源。
-5. 最终中文报告不少于 500 字。
+5. 保持{}的篇幅与结构，最终中文报告不少于 {} 字。
 
 审校发现：
 - 结构通过: {}
@@ -1477,10 +1655,7 @@ pub async fn review_report_node(
 - 引用通过: {}
 - {}
 
-可用笔记来源：
-{}
-
-可用网络来源：
+可用来源：
 {}
 
 目标大纲：
@@ -1490,20 +1665,14 @@ pub async fn review_report_node(
 {}
 
 请直接输出修订后的完整 Markdown 报告，不要解释。"#,
+            citation_goal,
+            preset.label(),
+            spec.min_words,
             structure_ok,
             length_ok,
             citation_ok,
             issue_hint,
-            if notes_catalog.is_empty() {
-                "（无）"
-            } else {
-                &notes_catalog
-            },
-            if web_catalog.is_empty() {
-                "（无）"
-            } else {
-                &web_catalog
-            },
+            sources_catalog,
             serde_json::to_string_pretty(&state.outline).unwrap_or_default(),
             final_report
         );
@@ -1522,6 +1691,13 @@ pub async fn review_report_node(
         }
     }
 
+    // 重排引用编号并追加参考来源章节
+    if source_count > 0 {
+        let (finalized, used_sources) = finalize_citations(&final_report, &state.citation_sources);
+        final_report = finalized;
+        state.citation_sources = used_sources;
+    }
+
     state.report = Some(final_report.clone());
     state.phase = ResearchPhase::Completed;
     emit_event(
@@ -1536,3 +1712,233 @@ pub async fn review_report_node(
         next_node: None,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::mock_llm::MockLlmClient;
+
+    /// 默认的 ".tmp" 前缀会被关键词搜索当作隐藏目录跳过
+    fn workspace() -> tempfile::TempDir {
+        tempfile::Builder::new()
+            .prefix("deep-research")
+            .tempdir()
+            .expect("tempdir")
+    }
+
+    fn research_state(workspace: &Path, topic: &str) -> DeepResearchState {
+        DeepResearchState {
+            topic: topic.to_string(),
+            workspace_path: workspace.to_string_lossy().to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn malformed_intent_json_falls_back_to_research() {
+        let app = tauri::test::mock_app();
+        let workspace = workspace();
+        let llm = Arc::new(
+            MockLlmClient::new()
+                .on_call(0, r#"{"intent": "CHAT", "reason": "#)
+                .on_call(1, "抱歉，我无法判断。")
+                .on_call(2, "Rust\n所有权\n"),
+        );
+
+        let result = analyze_topic_node(
+            app.handle(),
+            &llm,
+            research_state(workspace.path(), "Rust 所有权"),
+        )
+        .await
+        .expect("analyze topic");
+
+        assert_eq!(result.next_node.as_deref(), Some("search_notes"));
+        assert_eq!(result.state.keywords, vec!["Rust", "所有权"]);
+        assert_eq!(llm.prompts().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn invalid_intent_json_is_repaired_with_parse_error() {
+        let app = tauri::test::mock_app();
+        let workspace = workspace();
+        let llm = Arc::new(
+            MockLlmClient::new()
+                .on_call(0, "{intent: CHAT}")
+                .on_call(1, r#"{"intent": "CHAT", "reason": "问候"}"#)
+                .on_call(2, "你好！"),
+        );
+
+        let result =
+            analyze_topic_node(app.handle(), &llm, research_state(workspace.path(), "你好"))
+                .await
+                .expect("analyze topic");
+
+        assert_eq!(result.state.report.as_deref(), Some("你好！"));
+        let prompts = llm.prompts();
+        assert!(prompts[1].contains("解析错误"));
+        assert!(prompts[1].contains("{intent: CHAT}"));
+        assert!(prompts[1].contains("分析用户的研究请求"));
+    }
+
+    #[test]
+    fn strict_parser_only_accepts_whole_json() {
+        let fenced: serde_json::Value =
+            parse_json_strict("```json\n{\"a\": 1}\n```").expect("fenced json");
+        assert_eq!(fenced["a"], 1);
+        assert!(parse_json_strict::<serde_json::Value>("结果如下：{\"a\": 1}").is_err());
+        assert!(parse_json_strict::<ReportOutline>(r#"{"title": "t"}"#).is_err());
+    }
+
+    #[tokio::test]
+    async fn lenient_parser_is_last_resort_for_outline() {
+        let app = tauri::test::mock_app();
+        let workspace = workspace();
+        let outline = r#"大纲如下：
+{"title": "T", "sections": [{"heading": "H", "points": ["p"], "related_notes": []}]}
+希望有帮助。"#;
+        let llm = Arc::new(
+            MockLlmClient::new()
+                .on_call(0, outline)
+                .on_call(1, "{\"title\": \"T\""),
+        );
+
+        let result = generate_outline_node(
+            app.handle(),
+            &llm,
+            research_state(workspace.path(), "主题"),
+            ReportPreset::Brief,
+        )
+        .await
+        .expect("outline");
+
+        let parsed = result.state.outline.expect("outline set");
+        assert_eq!(parsed.title, "T");
+        assert_eq!(parsed.sections[0].heading, "H");
+        assert_eq!(llm.prompts().len(), 2);
+
+        let broken = Arc::new(MockLlmClient::new().fallback("not json at all"));
+        let error = generate_outline_node(
+            app.handle(),
+            &broken,
+            research_state(workspace.path(), "主题"),
+            ReportPreset::Brief,
+        )
+        .await
+        .err()
+        .expect("unparseable outline fails");
+        assert!(error.contains("解析大纲失败"));
+    }
+
+    #[tokio::test]
+    async fn fenced_chat_intent_replies_directly() {
+        let app = tauri::test::mock_app();
+        let workspace = workspace();
+        let llm = Arc::new(
+            MockLlmClient::new()
+                .on_call(0, "```json\n{\"intent\": \"chat\"}\n```")
+                .on_call(1, "你好！想研究什么主题？"),
+        );
+
+        let result =
+            analyze_topic_node(app.handle(), &llm, research_state(workspace.path(), "你好"))
+                .await
+                .expect("analyze topic");
+
+        assert_eq!(result.next_node, None);
+        assert_eq!(result.state.phase, ResearchPhase::Completed);
+        assert_eq!(
+            result.state.report.as_deref(),
+            Some("你好！想研究什么主题？")
+        );
+    }
+
+    #[tokio::test]
+    async fn research_runs_from_topic_to_reviewed_report() {
+        let app = tauri::test::mock_app();
+        let workspace = workspace();
+        std::fs::write(
+            workspace.path().join("rust-ownership.md"),
+            "# Rust 所有权\n\n## 借用\n\n每个值都有唯一的所有者。",
+        )
+        .expect("write note");
+
+        let outline = r#"```json
+{
+  "title": "Rust 所有权",
+  "sections": [
+    { "heading": "所有权规则", "points": ["唯一所有者"], "related_notes": ["Rust 所有权"] },
+    { "heading": "借用", "points": ["引用"], "related_notes": ["Rust 所有权"] }
+  ]
+}
+```"#;
+        let revised =
+            "# Rust 所有权\n\n## 所有权规则\n\n修订后的正文。\n\n## 借用\n\n修订后的正文。";
+        let llm = Arc::new(
+            MockLlmClient::new()
+                .on_prompt("分析用户的研究请求", r#"{"intent": "RESEARCH"}"#)
+                .on_prompt("提取 3-5 个关键词", "Rust\n所有权")
+                .on_prompt("生成一个报告大纲", outline)
+                .on_prompt(
+                    "专业的研究报告撰写者",
+                    "# Rust 所有权\n\n## 所有权规则\n\n初稿。\n",
+                )
+                .on_prompt("资深研究编辑", revised),
+        );
+
+        let preset = ReportPreset::Brief;
+        let handle = app.handle();
+        let mut state = research_state(workspace.path(), "Rust 所有权");
+        let mut next = Some("analyze_topic".to_string());
+        let mut visited = Vec::new();
+        while let Some(node) = next {
+            let result = match node.as_str() {
+                "analyze_topic" => analyze_topic_node(handle, &llm, state).await,
+                "search_notes" => {
+                    search_notes_node(
+                        handle,
+                        &llm,
+                        state,
+                        10,
+                        None,
+                        0,
+                        &WebSearchFilter::default(),
+                    )
+                    .await
+                }
+                "crawl_web" => crawl_web_node(handle, state, None, None, 0, 0).await,
+                "read_notes" => read_notes_node(handle, &llm, state, 5).await,
+                "generate_outline" => generate_outline_node(handle, &llm, state, preset).await,
+                "write_report" => write_report_node(handle, &llm, state, false, preset).await,
+                "review_report" => review_report_node(handle, &llm, state, false, preset).await,
+                other => panic!("unexpected node {}", other),
+            }
+            .expect("node succeeds");
+            visited.push(node);
+            state = result.state;
+            next = result.next_node;
+        }
+
+        assert_eq!(
+            visited,
+            vec![
+                "analyze_topic",
+                "search_notes",
+                "crawl_web",
+                "read_notes",
+                "generate_outline",
+                "write_report",
+                "review_report",
+            ]
+        );
+        assert_eq!(state.phase, ResearchPhase::Completed);
+        assert_eq!(state.read_notes.len(), 1);
+        assert_eq!(state.outline.as_ref().map(|o| o.sections.len()), Some(2));
+        assert_eq!(
+            state.report_chunks.concat(),
+            "# Rust 所有权\n\n## 所有权规则\n\n初稿。\n"
+        );
+        // 初稿篇幅不足，审校节点使用修订结果
+        assert_eq!(state.report.as_deref(), Some(revised));
+    }
+}
@@ -0,0 +1,291 @@
+//! Deep Research 进度与剩余时间估算
+//!
+//! 按固定的阶段顺序与权重计算整体进度（0.0–1.0）：爬取网页、阅读笔记按
+//! index/total 推进，撰写报告按已生成字数相对预设目标字数推进。跳过的阶段
+//! （如未启用网络搜索）直接越过，进度只增不减。
+//!
+//! 等待用户澄清时进度保持不变、不给出 ETA，暂停时间也不计入耗时；收到澄清
+//! 恢复后继续计时。
+
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+
+use super::types::{DeepResearchEvent, ResearchPhase};
+use crate::agent::commands::DeepResearchStateManager;
+
+/// 各阶段在整体进度中的权重（按执行顺序，合计为 1）
+const PHASE_WEIGHTS: &[(ResearchPhase, f32)] = &[
+    (ResearchPhase::AnalyzingTopic, 0.05),
+    (ResearchPhase::SearchingNotes, 0.05),
+    (ResearchPhase::SearchingWeb, 0.05),
+    (ResearchPhase::CrawlingWeb, 0.15),
+    (ResearchPhase::ReadingNotes, 0.25),
+    (ResearchPhase::GeneratingOutline, 0.10),
+    (ResearchPhase::WritingReport, 0.25),
+    (ResearchPhase::ReviewingReport, 0.10),
+];
+
+/// 进度低于该值时样本太少，不估算 ETA
+const MIN_PROGRESS_FOR_ETA: f32 = 0.05;
+/// ETA 取整粒度（秒）
+const ETA_GRANULARITY_SECS: u64 = 5;
+/// 未知预设时撰写阶段的预期字数
+const DEFAULT_REPORT_CHARS: usize = 1200;
+
+/// 随事件发送的进度信息
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct ResearchProgress {
+    /// 整体进度 0.0–1.0
+    pub fraction: f32,
+    /// 预计剩余秒数（粗略，等待澄清或样本不足时为 None）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub eta_seconds: Option<u64>,
+    /// 是否因等待用户澄清而暂停
+    pub paused: bool,
+}
+
+/// 发送给前端的事件：原事件字段之外附带 `progress`
+#[derive(Serialize)]
+struct ProgressEventPayload<'a> {
+    #[serde(flatten)]
+    event: &'a DeepResearchEvent,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    progress: Option<ResearchProgress>,
+}
+
+/// 发送 Deep Research 事件并附带整体进度
+pub fn emit_research_event<R: Runtime>(app: &AppHandle<R>, event: &DeepResearchEvent) {
+    let progress = app
+        .try_state::<DeepResearchStateManager>()
+        .map(|manager| manager.observe_progress(event));
+    let _ = app.emit(
+        crate::agent::event_schema::DEEP_RESEARCH_EVENT_CHANNEL,
+        ProgressEventPayload { event, progress },
+    );
+}
+
+/// 单次研究的进度跟踪器
+#[derive(Debug)]
+pub struct ProgressTracker {
+    started_at: Instant,
+    paused_at: Option<Instant>,
+    paused_total: Duration,
+    phase: ResearchPhase,
+    fraction: f32,
+    finished: bool,
+    expected_report_chars: usize,
+    report_chars: usize,
+    crawl: (usize, usize),
+}
+
+impl Default for ProgressTracker {
+    fn default() -> Self {
+        Self::new(Instant::now(), DEFAULT_REPORT_CHARS)
+    }
+}
+
+impl ProgressTracker {
+    pub fn new(started_at: Instant, expected_report_chars: usize) -> Self {
+        Self {
+            started_at,
+            paused_at: None,
+            paused_total: Duration::ZERO,
+            phase: ResearchPhase::Init,
+            fraction: 0.0,
+            finished: false,
+            expected_report_chars: expected_report_chars.max(1),
+            report_chars: 0,
+            crawl: (0, 0),
+        }
+    }
+
+    /// 根据事件更新进度
+    pub fn observe(&mut self, event: &DeepResearchEvent, now: Instant) -> ResearchProgress {
+        match event {
+            DeepResearchEvent::PhaseChange { phase, preset, .. } => {
+                if *phase == ResearchPhase::Init {
+                    let expected = preset
+                        .map(|preset| preset.spec().target_words)
+                        .unwrap_or(DEFAULT_REPORT_CHARS);
+                    *self = Self::new(now, expected);
+                } else {
+                    self.resume(now);
+                    self.enter(phase.clone());
+                }
+            }
+            DeepResearchEvent::NeedsClarification { .. } => {
+                self.phase = ResearchPhase::WaitingForClarification;
+                self.paused_at.get_or_insert(now);
+            }
+            DeepResearchEvent::CrawlingPage { index, total, .. } => {
+                self.enter(ResearchPhase::CrawlingWeb);
+                self.crawl = (*index, *total);
+                self.advance(index.saturating_sub(1) as f32 / (*total).max(1) as f32);
+            }
+            DeepResearchEvent::PageCrawled { .. } => {
+                let (index, total) = self.crawl;
+                self.advance(index as f32 / total.max(1) as f32);
+            }
+            DeepResearchEvent::ReadingNote { index, total, .. } => {
+                self.enter(ResearchPhase::ReadingNotes);
+                self.advance(index.saturating_sub(1) as f32 / (*total).max(1) as f32);
+            }
+            DeepResearchEvent::ReportChunk { content } => {
+                self.enter(ResearchPhase::WritingReport);
+                self.report_chars += content.chars().count();
+                let written = self.report_chars as f32 / self.expected_report_chars as f32;
+                self.advance(written.min(0.95));
+            }
+            DeepResearchEvent::Complete { .. } => {
+                self.resume(now);
+                self.phase = ResearchPhase::Completed;
+                self.fraction = 1.0;
+                self.finished = true;
+            }
+            DeepResearchEvent::Error { .. } => {
+                self.phase = ResearchPhase::Error;
+                self.finished = true;
+            }
+            _ => {}
+        }
+        self.snapshot(now)
+    }
+
+    fn resume(&mut self, now: Instant) {
+        if let Some(paused_at) = self.paused_at.take() {
+            self.paused_total += now.saturating_duration_since(paused_at);
+        }
+    }
+
+    /// 进入阶段：进度至少推进到该阶段的起点
+    fn enter(&mut self, phase: ResearchPhase) {
+        if self.phase != phase {
+            self.phase = phase;
+            self.advance(0.0);
+        }
+    }
+
+    /// 按当前阶段内的完成比例推进整体进度（不回退）
+    fn advance(&mut self, within_phase: f32) {
+        let mut start = 0.0;
+        for (phase, weight) in PHASE_WEIGHTS {
+            if *phase == self.phase {
+                let value = start + weight * within_phase.clamp(0.0, 1.0);
+                self.fraction = self.fraction.max(value).min(1.0);
+                return;
+            }
+            start += weight;
+        }
+    }
+
+    fn snapshot(&self, now: Instant) -> ResearchProgress {
+        let paused = self.paused_at.is_some();
+        let eta_seconds = if self.phase == ResearchPhase::Completed {
+            Some(0)
+        } else if paused || self.finished || self.fraction < MIN_PROGRESS_FOR_ETA {
+            None
+        } else {
+            let elapsed = now
+                .saturating_duration_since(self.started_at)
+                .saturating_sub(self.paused_total)
+                .as_secs_f32();
+            let remaining = elapsed * (1.0 - self.fraction) / self.fraction;
+            let rounded = (remaining / ETA_GRANULARITY_SECS as f32).ceil() as u64;
+            Some(rounded * ETA_GRANULARITY_SECS)
+        };
+        ResearchProgress {
+            fraction: self.fraction,
+            eta_seconds,
+            paused,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::deep_research::types::ReportPreset;
+
+    fn phase(phase: ResearchPhase) -> DeepResearchEvent {
+        DeepResearchEvent::PhaseChange {
+            phase,
+            message: String::new(),
+            preset: None,
+        }
+    }
+
+    #[test]
+    fn progress_follows_phases_and_work_counts() {
+        let start = Instant::now();
+        let mut tracker = ProgressTracker::default();
+        let init = DeepResearchEvent::PhaseChange {
+            phase: ResearchPhase::Init,
+            message: String::new(),
+            preset: Some(ReportPreset::Brief),
+        };
+        assert_eq!(tracker.observe(&init, start).fraction, 0.0);
+
+        tracker.observe(&phase(ResearchPhase::AnalyzingTopic), start);
+        // 未启用网络搜索时直接从搜索笔记跳到阅读笔记
+        tracker.observe(&phase(ResearchPhase::SearchingNotes), start);
+        let reading = DeepResearchEvent::ReadingNote {
+            path: "a.md".into(),
+            title: "a".into(),
+            index: 3,
+            total: 5,
+        };
+        let progress = tracker.observe(&reading, start + Duration::from_secs(30));
+        assert!((progress.fraction - (0.30 + 0.25 * 0.4)).abs() < 1e-6);
+        // 已用 30 秒完成 40%，剩余约 45 秒
+        assert_eq!(progress.eta_seconds, Some(45));
+
+        // 进度不回退
+        let back = tracker.observe(&phase(ResearchPhase::SearchingNotes), start);
+        assert!((back.fraction - 0.40).abs() < 1e-6);
+
+        let chunk = DeepResearchEvent::ReportChunk {
+            content: "字".repeat(150),
+        };
+        let writing = tracker.observe(&chunk, start + Duration::from_secs(60));
+        assert!((writing.fraction - (0.65 + 0.25 * 0.5)).abs() < 1e-6);
+
+        let done = tracker.observe(
+            &DeepResearchEvent::Complete {
+                report: String::new(),
+            },
+            start + Duration::from_secs(90),
+        );
+        assert_eq!(done.fraction, 1.0);
+        assert_eq!(done.eta_seconds, Some(0));
+    }
+
+    #[test]
+    fn clarification_pauses_progress_and_clock() {
+        let start = Instant::now();
+        let mut tracker = ProgressTracker::new(start, 1000);
+        tracker.observe(&phase(ResearchPhase::AnalyzingTopic), start);
+        tracker.observe(&phase(ResearchPhase::SearchingNotes), start);
+
+        let waiting = tracker.observe(
+            &DeepResearchEvent::NeedsClarification {
+                question: "?".into(),
+                suggestions: vec![],
+                interrupt_id: "i".into(),
+            },
+            start + Duration::from_secs(10),
+        );
+        assert!(waiting.paused);
+        assert_eq!(waiting.eta_seconds, None);
+        assert!((waiting.fraction - 0.05).abs() < 1e-6);
+
+        // 等待 10 分钟后恢复，暂停时间不计入耗时
+        let resumed = tracker.observe(
+            &phase(ResearchPhase::AnalyzingTopic),
+            start + Duration::from_secs(610),
+        );
+        assert!(!resumed.paused);
+        assert_eq!(resumed.eta_seconds, Some(190));
+    }
+}
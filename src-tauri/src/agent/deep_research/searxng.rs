@@ -0,0 +1,124 @@
+//! SearXNG 网络搜索客户端
+//!
+//! 调用自建 SearXNG 实例的 JSON 接口（需在实例设置中启用 `json` 格式），
+//! 作为 Tavily 的替代。SearXNG 不支持域名过滤参数，域名过滤在本地完成。
+
+use futures_util::future::BoxFuture;
+use serde::Deserialize;
+
+use super::types::WebSearchResult;
+use super::web_search::{refine_results, WebSearchFilter, WebSearchProvider};
+
+/// SearXNG 搜索响应
+#[derive(Debug, Deserialize)]
+struct SearxngSearchResponse {
+    #[serde(default)]
+    results: Vec<SearxngResult>,
+}
+
+/// SearXNG 单个搜索结果
+#[derive(Debug, Deserialize)]
+struct SearxngResult {
+    #[serde(default)]
+    title: String,
+    url: String,
+    #[serde(default)]
+    content: String,
+    #[serde(default)]
+    score: f32,
+}
+
+/// SearXNG 客户端
+pub struct SearxngClient {
+    base_url: String,
+    client: reqwest::Client,
+}
+
+impl SearxngClient {
+    /// 创建新的 SearXNG 客户端，`base_url` 为实例地址（如 `http://localhost:8888`）
+    pub fn new(base_url: String, client: reqwest::Client) -> Self {
+        Self { base_url, client }
+    }
+
+    fn search_url(&self, query: &str) -> String {
+        format!(
+            "{}/search?q={}&format=json",
+            self.base_url.trim().trim_end_matches('/'),
+            urlencoding::encode(query)
+        )
+    }
+
+    /// 执行搜索，返回去重、过滤并截断到 `max_results` 的结果
+    pub async fn search_filtered(
+        &self,
+        query: &str,
+        max_results: usize,
+        filter: &WebSearchFilter,
+    ) -> Result<Vec<WebSearchResult>, String> {
+        let response = self
+            .client
+            .get(self.search_url(query))
+            .header("Accept", "application/json")
+            .send()
+            .await
+            .map_err(|e| format!("SearXNG request failed: {}", e))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(format!("SearXNG error {}: {}", status, body));
+        }
+
+        let searxng_response: SearxngSearchResponse = response
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse SearXNG response: {}", e))?;
+
+        let results = searxng_response
+            .results
+            .into_iter()
+            .map(|r| WebSearchResult {
+                title: r.title,
+                url: r.url,
+                content: r.content,
+                score: r.score,
+            })
+            .collect();
+
+        let mut results = refine_results(results, filter);
+        results.truncate(max_results);
+        Ok(results)
+    }
+}
+
+impl WebSearchProvider for SearxngClient {
+    fn name(&self) -> &'static str {
+        "searxng"
+    }
+
+    fn search<'a>(
+        &'a self,
+        query: &'a str,
+        max_results: usize,
+        filter: &'a WebSearchFilter,
+    ) -> BoxFuture<'a, Result<Vec<WebSearchResult>, String>> {
+        Box::pin(self.search_filtered(query, max_results, filter))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_search_url() {
+        let client = SearxngClient::new(
+            "http://localhost:8888/".to_string(),
+            crate::proxy::ProxyState::new().client().await,
+        );
+        assert_eq!(
+            client.search_url("rust 所有权"),
+            "http://localhost:8888/search?q=rust%20%E6%89%80%E6%9C%89%E6%9D%83&format=json"
+        );
+    }
+}
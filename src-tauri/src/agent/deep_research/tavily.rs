@@ -2,9 +2,11 @@
 //!
 //! 封装 Tavily API 调用，用于 Deep Research 的网络搜索功能
 
+use futures_util::future::BoxFuture;
 use serde::{Deserialize, Serialize};
 
 use super::types::WebSearchResult;
+use super::web_search::{normalize_domains, refine_results, WebSearchFilter, WebSearchProvider};
 
 /// Tavily 搜索请求
 #[derive(Debug, Serialize)]
@@ -19,6 +21,10 @@ struct TavilySearchRequest {
     include_answer: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
     include_raw_content: Option<bool>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    include_domains: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    exclude_domains: Vec<String>,
 }
 
 /// Tavily 搜索响应
@@ -59,6 +65,17 @@ impl TavilyClient {
         &self,
         query: &str,
         max_results: usize,
+    ) -> Result<Vec<WebSearchResult>, String> {
+        self.search_filtered(query, max_results, &WebSearchFilter::default())
+            .await
+    }
+
+    /// 执行搜索，域名过滤交给 Tavily，返回去重并按域名限额后的结果
+    pub async fn search_filtered(
+        &self,
+        query: &str,
+        max_results: usize,
+        filter: &WebSearchFilter,
     ) -> Result<Vec<WebSearchResult>, String> {
         let request = TavilySearchRequest {
             api_key: self.api_key.clone(),
@@ -67,6 +84,8 @@ impl TavilyClient {
             max_results: Some(max_results),
             include_answer: Some(false),
             include_raw_content: Some(false),
+            include_domains: normalize_domains(&filter.include_domains),
+            exclude_domains: normalize_domains(&filter.exclude_domains),
         };
 
         let response = self
@@ -100,7 +119,22 @@ impl TavilyClient {
             })
             .collect();
 
-        Ok(results)
+        Ok(refine_results(results, filter))
+    }
+}
+
+impl WebSearchProvider for TavilyClient {
+    fn name(&self) -> &'static str {
+        "tavily"
+    }
+
+    fn search<'a>(
+        &'a self,
+        query: &'a str,
+        max_results: usize,
+        filter: &'a WebSearchFilter,
+    ) -> BoxFuture<'a, Result<Vec<WebSearchResult>, String>> {
+        Box::pin(self.search_filtered(query, max_results, filter))
     }
 }
 
@@ -112,7 +146,7 @@ mod tests {
     #[ignore] // 需要 API key
     async fn test_tavily_search() {
         let api_key = std::env::var("TAVILY_API_KEY").expect("TAVILY_API_KEY not set");
-        let client = TavilyClient::new(api_key, reqwest::Client::new());
+        let client = TavilyClient::new(api_key, crate::proxy::ProxyState::new().client().await);
         let results = client.search("Rust programming language", 3).await.unwrap();
         assert!(!results.is_empty());
     }
@@ -57,6 +57,86 @@ impl Default for ReportStyle {
     }
 }
 
+/// 报告篇幅预设
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportPreset {
+    /// 简报（约 300 字，要点列表，适合较慢或较便宜的模型）
+    Brief,
+    /// 标准报告
+    #[default]
+    Standard,
+    /// 深度报告（分章节，含分析）
+    Comprehensive,
+}
+
+/// 预设对应的大纲与篇幅参数
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReportPresetSpec {
+    /// 大纲章节数下限
+    pub min_sections: usize,
+    /// 大纲章节数上限
+    pub max_sections: usize,
+    /// 每个章节的要点数
+    pub points_per_section: usize,
+    /// 每个章节的目标字数
+    pub words_per_section: usize,
+    /// 全文目标字数
+    pub target_words: usize,
+    /// 审校时的最低字数
+    pub min_words: usize,
+}
+
+impl ReportPreset {
+    /// 预设参数
+    pub fn spec(self) -> ReportPresetSpec {
+        match self {
+            Self::Brief => ReportPresetSpec {
+                min_sections: 2,
+                max_sections: 3,
+                points_per_section: 2,
+                words_per_section: 100,
+                target_words: 300,
+                min_words: 150,
+            },
+            Self::Standard => ReportPresetSpec {
+                min_sections: 3,
+                max_sections: 5,
+                points_per_section: 3,
+                words_per_section: 300,
+                target_words: 1200,
+                min_words: 500,
+            },
+            Self::Comprehensive => ReportPresetSpec {
+                min_sections: 5,
+                max_sections: 7,
+                points_per_section: 4,
+                words_per_section: 600,
+                target_words: 3500,
+                min_words: 1500,
+            },
+        }
+    }
+
+    /// 预设名称（用于进度提示）
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Brief => "简报",
+            Self::Standard => "标准报告",
+            Self::Comprehensive => "深度报告",
+        }
+    }
+
+    /// 撰写报告时的结构与文风要求
+    pub fn writing_guidance(self) -> &'static str {
+        match self {
+            Self::Brief => "以要点列表为主的简报：开头用一两句话给出结论，每个章节用 2-3 条要点概括，不展开论证",
+            Self::Standard => "结构清晰的报告：每个章节用 1-3 段阐述要点，重点内容可使用列表",
+            Self::Comprehensive => "分章节的深度报告：每个章节依次包含现状、分析（对比、原因与影响）和小结，必要时使用表格，最后给出总体结论与建议",
+        }
+    }
+}
+
 /// 笔记引用
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NoteReference {
@@ -108,6 +188,38 @@ pub struct CrawledPageContent {
     pub title: String,
     /// 提取的内容（Markdown 格式）
     pub content: String,
+    /// 爬取深度：0 为搜索结果，1 及以上为跟进的链接
+    #[serde(default)]
+    pub depth: usize,
+    /// 跟进链接时所在的页面 URL（搜索结果为 None）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub found_via: Option<String>,
+}
+
+/// 引用来源类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CitationKind {
+    /// 本地笔记
+    Note,
+    /// 网页
+    Web,
+}
+
+/// 报告中可引用的来源（编号从 1 开始）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CitationSource {
+    /// 引用编号
+    pub number: usize,
+    /// 来源类型
+    pub kind: CitationKind,
+    /// 标题
+    pub title: String,
+    /// 笔记为工作区相对路径，网页为 URL
+    pub location: String,
+    /// 网页经链接跟进得到时，所在的页面 URL
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub found_via: Option<String>,
 }
 
 /// 报告大纲
@@ -145,6 +257,9 @@ pub struct DeepResearchConfig {
     /// 温度
     #[serde(default = "default_temperature")]
     pub temperature: f32,
+    /// LLM 响应缓存模式（仅对 temperature 为 0 的请求生效）
+    #[serde(default)]
+    pub cache: crate::llm_cache::CacheMode,
     /// 最大搜索结果数
     #[serde(default = "default_max_search_results")]
     pub max_search_results: usize,
@@ -154,6 +269,9 @@ pub struct DeepResearchConfig {
     /// 报告风格
     #[serde(default)]
     pub report_style: ReportStyle,
+    /// 报告篇幅预设（决定大纲章节数与目标字数）
+    #[serde(default)]
+    pub report_preset: ReportPreset,
     /// 是否包含引用来源
     #[serde(default = "default_true")]
     pub include_citations: bool,
@@ -165,12 +283,53 @@ pub struct DeepResearchConfig {
     /// 是否启用网络搜索
     #[serde(default)]
     pub enable_web_search: bool,
+    /// 网络搜索提供方：tavily（默认）或 searxng
+    #[serde(default = "default_web_search_provider")]
+    pub web_search_provider: String,
     /// Tavily API Key（用于网络搜索）
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tavily_api_key: Option<String>,
+    /// SearXNG 实例地址（provider 为 searxng 时使用）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub searxng_url: Option<String>,
     /// 网络搜索最大结果数
     #[serde(default = "default_web_search_results")]
     pub max_web_search_results: usize,
+    /// 仅搜索这些域名（如文档站），为空时不限制
+    #[serde(default)]
+    pub include_domains: Vec<String>,
+    /// 排除这些域名（如内容农场）
+    #[serde(default)]
+    pub exclude_domains: Vec<String>,
+    /// 每个域名最多保留的网络结果数，避免单一站点占满结果
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_results_per_domain: Option<usize>,
+
+    // ============ 网页爬取配置 ============
+    /// 从搜索结果跟进链接的最大深度（0 表示只爬取搜索结果本身）
+    #[serde(default)]
+    pub crawl_max_depth: usize,
+    /// 最多爬取的网页数（含跟进的链接）
+    #[serde(default = "default_crawl_max_pages")]
+    pub crawl_max_pages: usize,
+    /// 是否遵守 robots.txt
+    #[serde(default = "default_true")]
+    pub respect_robots_txt: bool,
+    /// 同一主机两次请求的最小间隔（毫秒）
+    #[serde(default = "default_crawl_host_interval_ms")]
+    pub crawl_host_interval_ms: u64,
+}
+
+fn default_crawl_max_pages() -> usize {
+    10
+}
+
+fn default_crawl_host_interval_ms() -> u64 {
+    1000
+}
+
+fn default_web_search_provider() -> String {
+    "tavily".to_string()
 }
 
 fn default_web_search_results() -> usize {
@@ -201,14 +360,25 @@ impl Default for DeepResearchConfig {
             api_key: String::new(),
             base_url: None,
             temperature: default_temperature(),
+            cache: Default::default(),
             max_search_results: default_max_search_results(),
             max_notes_to_read: default_max_notes_to_read(),
             report_style: ReportStyle::default(),
+            report_preset: ReportPreset::default(),
             include_citations: true,
             locale: default_locale(),
             enable_web_search: false,
+            web_search_provider: default_web_search_provider(),
             tavily_api_key: None,
+            searxng_url: None,
             max_web_search_results: default_web_search_results(),
+            include_domains: Vec::new(),
+            exclude_domains: Vec::new(),
+            max_results_per_domain: None,
+            crawl_max_depth: 0,
+            crawl_max_pages: default_crawl_max_pages(),
+            respect_robots_txt: true,
+            crawl_host_interval_ms: default_crawl_host_interval_ms(),
         }
     }
 }
@@ -256,15 +426,40 @@ pub struct DeepResearchState {
     /// 报告流式内容（逐步生成）
     #[serde(default)]
     pub report_chunks: Vec<String>,
+    /// 撰写报告时提供给模型的编号来源（启用引用时）
+    #[serde(default)]
+    pub citation_sources: Vec<CitationSource>,
     /// 下一个节点
     #[serde(default)]
     pub goto: String,
     /// 错误信息
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,
-    /// 用户澄清的输入（interrupt 恢复后填充）
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub clarification: Option<String>,
+    /// 用户每轮澄清的回答（interrupt 恢复后追加，按轮次顺序）
+    #[serde(default)]
+    pub clarifications: Vec<String>,
+}
+
+impl DeepResearchState {
+    /// 合并全部澄清历史后的研究主题
+    pub fn effective_topic(&self) -> String {
+        match self.clarifications.as_slice() {
+            [] => self.topic.clone(),
+            [only] => format!("{}\n\n用户补充说明：{}", self.topic, only),
+            all => {
+                let history = all
+                    .iter()
+                    .enumerate()
+                    .map(|(index, answer)| format!("{}. {}", index + 1, answer))
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                format!(
+                    "{}\n\n用户补充说明（按轮次顺序，后面的说明更准确）：\n{}",
+                    self.topic, history
+                )
+            }
+        }
+    }
 }
 
 impl ForgeGraphState for DeepResearchState {
@@ -298,6 +493,9 @@ pub enum DeepResearchEvent {
     PhaseChange {
         phase: ResearchPhase,
         message: String,
+        /// 本次研究使用的篇幅预设（仅在开始时发送）
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        preset: Option<ReportPreset>,
     },
     /// 关键词提取完成
     KeywordsExtracted { keywords: Vec<String> },
@@ -366,6 +564,9 @@ pub struct DeepResearchRequest {
     /// 报告风格
     #[serde(default)]
     pub report_style: ReportStyle,
+    /// 报告篇幅预设（不传时使用配置中的预设）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub report_preset: Option<ReportPreset>,
     /// 是否包含引用
     #[serde(default = "default_true")]
     pub include_citations: bool,
@@ -392,3 +593,24 @@ impl Default for SearchMode {
         Self::Keyword
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn effective_topic_keeps_every_clarification_round() {
+        let mut state = DeepResearchState {
+            topic: "Rust".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(state.effective_topic(), "Rust");
+
+        state.clarifications.push("异步编程".to_string());
+        assert_eq!(state.effective_topic(), "Rust\n\n用户补充说明：异步编程");
+
+        state.clarifications.push("重点是 tokio 的调度器".to_string());
+        let topic = state.effective_topic();
+        assert!(topic.contains("1. 异步编程\n2. 重点是 tokio 的调度器"));
+    }
+}
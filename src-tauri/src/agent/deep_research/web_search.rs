@@ -0,0 +1,366 @@
+//! 网络搜索提供方抽象
+//!
+//! Deep Research 通过 `WebSearchProvider` 调用网络搜索，
+//! 目前支持 Tavily 与 SearXNG，由 `DeepResearchConfig.web_search_provider` 选择。
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use futures_util::future::BoxFuture;
+
+use super::searxng::SearxngClient;
+use super::tavily::TavilyClient;
+use super::types::{DeepResearchConfig, WebSearchResult};
+use crate::text::is_cjk;
+
+/// 网络搜索提供方
+pub trait WebSearchProvider: Send + Sync {
+    /// 提供方名称（用于日志与进度提示）
+    fn name(&self) -> &'static str;
+
+    /// 搜索并返回按相关性排序、已去重与过滤的结果
+    fn search<'a>(
+        &'a self,
+        query: &'a str,
+        max_results: usize,
+        filter: &'a WebSearchFilter,
+    ) -> BoxFuture<'a, Result<Vec<WebSearchResult>, String>>;
+}
+
+/// 根据配置创建网络搜索提供方
+///
+/// 未启用网络搜索或缺少必需配置时返回 None
+pub fn create_web_search_provider(
+    config: &DeepResearchConfig,
+    client: reqwest::Client,
+) -> Option<Arc<dyn WebSearchProvider>> {
+    if !config.enable_web_search {
+        return None;
+    }
+
+    match config.web_search_provider.trim().to_lowercase().as_str() {
+        "" | "tavily" => config
+            .tavily_api_key
+            .as_ref()
+            .filter(|key| !key.trim().is_empty())
+            .map(|key| {
+                Arc::new(TavilyClient::new(key.clone(), client)) as Arc<dyn WebSearchProvider>
+            }),
+        "searxng" => config
+            .searxng_url
+            .as_ref()
+            .filter(|url| !url.trim().is_empty())
+            .map(|url| {
+                Arc::new(SearxngClient::new(url.clone(), client)) as Arc<dyn WebSearchProvider>
+            }),
+        other => {
+            eprintln!("[DeepResearch] 未知的网络搜索提供方: {}", other);
+            None
+        }
+    }
+}
+
+/// 网络搜索结果的来源控制
+#[derive(Debug, Clone, Default)]
+pub struct WebSearchFilter {
+    /// 仅保留这些域名（含子域名）的结果，为空时不限制
+    pub include_domains: Vec<String>,
+    /// 排除这些域名（含子域名）的结果
+    pub exclude_domains: Vec<String>,
+    /// 每个域名最多保留的结果数，None 表示不限制
+    pub max_results_per_domain: Option<usize>,
+}
+
+/// 去重并应用域名过滤与每域名限额（保持原有排序）
+///
+/// 按规范化 URL 去重，并丢弃标题相同或摘要几乎相同的结果。
+/// Tavily 偶尔会返回过滤范围之外的结果，这里再在本地校验一次。
+pub fn refine_results(
+    results: Vec<WebSearchResult>,
+    filter: &WebSearchFilter,
+) -> Vec<WebSearchResult> {
+    let include = normalize_domains(&filter.include_domains);
+    let exclude = normalize_domains(&filter.exclude_domains);
+
+    let mut seen_urls = HashSet::new();
+    let mut seen_titles = HashSet::new();
+    let mut kept_snippets: Vec<HashSet<String>> = Vec::new();
+    let mut per_domain: HashMap<String, usize> = HashMap::new();
+    let mut refined = Vec::new();
+
+    for result in results {
+        let host = url_host(&result.url).unwrap_or_default();
+        if !include.is_empty() && !include.iter().any(|d| domain_matches(&host, d)) {
+            continue;
+        }
+        if exclude.iter().any(|d| domain_matches(&host, d)) {
+            continue;
+        }
+        if !seen_urls.insert(normalize_url(&result.url)) {
+            continue;
+        }
+
+        let title = text_tokens(&result.title).into_iter().collect::<Vec<_>>();
+        let mut title_key = title.clone();
+        title_key.sort();
+        if !title_key.is_empty() && !seen_titles.insert(title_key.join(" ")) {
+            continue;
+        }
+        let snippet: HashSet<String> = text_tokens(&result.content).into_iter().collect();
+        if kept_snippets
+            .iter()
+            .any(|kept| jaccard(kept, &snippet) >= NEAR_DUPLICATE_SIMILARITY)
+        {
+            continue;
+        }
+
+        if let Some(cap) = filter.max_results_per_domain {
+            let count = per_domain.entry(host).or_insert(0);
+            if *count >= cap {
+                continue;
+            }
+            *count += 1;
+        }
+        kept_snippets.push(snippet);
+        refined.push(result);
+    }
+    refined
+}
+
+/// 摘要词集合的 Jaccard 相似度达到该值即视为重复
+const NEAR_DUPLICATE_SIMILARITY: f64 = 0.9;
+
+/// 跟踪参数，不影响页面内容
+const TRACKING_PARAMS: &[&str] = &["fbclid", "gclid", "ref", "ref_src", "spm"];
+
+/// 规范化 URL：忽略协议、www.、片段、跟踪参数与末尾斜杠
+pub fn normalize_url(url: &str) -> String {
+    let url = url.trim();
+    let without_scheme = url.split_once("://").map(|(_, rest)| rest).unwrap_or(url);
+    let without_fragment = without_scheme.split('#').next().unwrap_or_default();
+    let (location, query) = without_fragment
+        .split_once('?')
+        .unwrap_or((without_fragment, ""));
+    let (host, path) = location.split_once('/').unwrap_or((location, ""));
+    let host = host.to_lowercase();
+    let host = host.strip_prefix("www.").unwrap_or(&host);
+
+    let mut params: Vec<&str> = query
+        .split('&')
+        .filter(|param| {
+            let name = param.split('=').next().unwrap_or_default().to_lowercase();
+            !name.is_empty()
+                && !name.starts_with("utm_")
+                && !TRACKING_PARAMS.contains(&name.as_str())
+        })
+        .collect();
+    params.sort_unstable();
+
+    let mut normalized = format!("{}/{}", host, path.trim_end_matches('/'));
+    if normalized.ends_with('/') {
+        normalized.pop();
+    }
+    if !params.is_empty() {
+        normalized.push('?');
+        normalized.push_str(&params.join("&"));
+    }
+    normalized
+}
+
+/// 提取 URL 的主机名（小写，去掉 www. 与端口）
+pub(super) fn url_host(url: &str) -> Option<String> {
+    let rest = url.trim().split_once("://")?.1;
+    let authority = rest.split(['/', '?', '#']).next()?;
+    let host = authority
+        .rsplit('@')
+        .next()?
+        .split(':')
+        .next()?
+        .to_lowercase();
+    if host.is_empty() {
+        return None;
+    }
+    Some(host.strip_prefix("www.").unwrap_or(&host).to_string())
+}
+
+/// 将用户填写的域名（可能带协议或路径）规范化为主机名
+pub(super) fn normalize_domains(domains: &[String]) -> Vec<String> {
+    let mut normalized: Vec<String> = Vec::new();
+    for domain in domains {
+        let domain = domain.trim();
+        if domain.is_empty() {
+            continue;
+        }
+        let host = if domain.contains("://") {
+            url_host(domain)
+        } else {
+            url_host(&format!("https://{}", domain))
+        };
+        if let Some(host) = host.map(|h| h.trim_start_matches("*.").to_string()) {
+            if !host.is_empty() && !normalized.contains(&host) {
+                normalized.push(host);
+            }
+        }
+    }
+    normalized
+}
+
+fn domain_matches(host: &str, domain: &str) -> bool {
+    host == domain
+        || host
+            .strip_suffix(domain)
+            .is_some_and(|prefix| prefix.ends_with('.'))
+}
+
+/// 切分为小写词元；中日韩字符逐字成词
+fn text_tokens(text: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    for c in text.chars() {
+        if is_cjk(c) {
+            if !current.is_empty() {
+                tokens.push(std::mem::take(&mut current));
+            }
+            tokens.push(c.to_string());
+        } else if c.is_alphanumeric() {
+            current.extend(c.to_lowercase());
+        } else if !current.is_empty() {
+            tokens.push(std::mem::take(&mut current));
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    tokens
+}
+
+fn jaccard(a: &HashSet<String>, b: &HashSet<String>) -> f64 {
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    let shared = a.intersection(b).count();
+    shared as f64 / (a.len() + b.len() - shared) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(title: &str, url: &str, content: &str) -> WebSearchResult {
+        WebSearchResult {
+            title: title.to_string(),
+            url: url.to_string(),
+            content: content.to_string(),
+            score: 0.5,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_create_web_search_provider() {
+        let client = crate::proxy::ProxyState::new().client().await;
+        let mut config = DeepResearchConfig {
+            enable_web_search: true,
+            tavily_api_key: Some("key".to_string()),
+            ..Default::default()
+        };
+        let provider = create_web_search_provider(&config, client.clone());
+        assert_eq!(provider.map(|p| p.name()), Some("tavily"));
+
+        config.web_search_provider = "SearXNG".to_string();
+        assert!(create_web_search_provider(&config, client.clone()).is_none());
+        config.searxng_url = Some("http://localhost:8888".to_string());
+        let provider = create_web_search_provider(&config, client.clone());
+        assert_eq!(provider.map(|p| p.name()), Some("searxng"));
+
+        config.enable_web_search = false;
+        assert!(create_web_search_provider(&config, client).is_none());
+    }
+
+    #[test]
+    fn test_normalize_url() {
+        assert_eq!(
+            normalize_url("https://www.Example.com/Guide/?utm_source=x&b=2&a=1#intro"),
+            "example.com/Guide?a=1&b=2"
+        );
+        assert_eq!(
+            normalize_url("http://example.com/Guide"),
+            normalize_url("https://example.com/Guide/?fbclid=abc")
+        );
+        assert_eq!(normalize_url("https://example.com/"), "example.com");
+    }
+
+    #[test]
+    fn test_refine_results_dedupes_urls_titles_and_snippets() {
+        let results = vec![
+            result(
+                "Rust Book",
+                "https://doc.rust-lang.org/book/",
+                "The Rust programming language book",
+            ),
+            result(
+                "Rust Book mirror",
+                "http://www.doc.rust-lang.org/book",
+                "Different text entirely",
+            ),
+            result(
+                "rust  book!",
+                "https://mirror.example/rust-book",
+                "Another copy",
+            ),
+            result(
+                "Ownership",
+                "https://a.example/ownership",
+                "Ownership rules in Rust explained simply",
+            ),
+            result(
+                "Ownership guide",
+                "https://b.example/o",
+                "Ownership rules in Rust explained simply.",
+            ),
+            result(
+                "Borrowing",
+                "https://c.example/b",
+                "Borrowing and references",
+            ),
+        ];
+        let refined = refine_results(results, &WebSearchFilter::default());
+        let titles: Vec<&str> = refined.iter().map(|r| r.title.as_str()).collect();
+        assert_eq!(titles, vec!["Rust Book", "Ownership", "Borrowing"]);
+    }
+
+    #[test]
+    fn test_refine_results_applies_domain_filters_and_cap() {
+        let results = vec![
+            result("A", "https://docs.python.org/3/a", "alpha"),
+            result("B", "https://docs.python.org/3/b", "beta"),
+            result("C", "https://docs.python.org/3/c", "gamma"),
+            result("D", "https://farm.contentmill.com/d", "delta"),
+            result("E", "https://developer.mozilla.org/e", "epsilon"),
+            result("F", "https://notdocs.python.org.evil.com/f", "zeta"),
+        ];
+
+        let excluded = refine_results(
+            results.clone(),
+            &WebSearchFilter {
+                exclude_domains: vec!["contentmill.com".to_string()],
+                max_results_per_domain: Some(2),
+                ..Default::default()
+            },
+        );
+        let titles: Vec<&str> = excluded.iter().map(|r| r.title.as_str()).collect();
+        assert_eq!(titles, vec!["A", "B", "E", "F"]);
+
+        let included = refine_results(
+            results,
+            &WebSearchFilter {
+                include_domains: vec![
+                    "https://python.org/".to_string(),
+                    "*.mozilla.org".to_string(),
+                ],
+                ..Default::default()
+            },
+        );
+        let titles: Vec<&str> = included.iter().map(|r| r.title.as_str()).collect();
+        assert_eq!(titles, vec!["A", "B", "C", "E"]);
+    }
+}
@@ -0,0 +1,466 @@
+//! 事件协议描述
+//!
+//! `agent-event` 与 `deep-research-event` 的载荷由 Rust 枚举序列化为 `{ type, data }`。
+//! 这里集中维护事件标签名与字段描述，并带版本号，前端通过 `agent_event_schema` 命令获取后校验。
+//! 修改事件变体时需同步更新本文件并递增 EVENT_SCHEMA_VERSION；
+//! `tag()` 的穷尽匹配会在新增变体时编译报错作为提醒。
+//! forge 运行时事件（run_started、text_delta 等）由 forge 定义，不在此描述。
+
+use crate::agent::deep_research::DeepResearchEvent;
+use crate::agent::types::AgentEvent;
+use serde::Serialize;
+
+/// 事件协议版本，任一事件的标签或字段变化时递增
+pub const EVENT_SCHEMA_VERSION: u32 = 1;
+
+pub const AGENT_EVENT_CHANNEL: &str = "agent-event";
+pub const DEEP_RESEARCH_EVENT_CHANNEL: &str = "deep-research-event";
+
+/// 事件字段；`type` 为前端对应的 TypeScript 类型名
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct EventField {
+    pub name: &'static str,
+    #[serde(rename = "type")]
+    pub ty: &'static str,
+    pub optional: bool,
+}
+
+const fn field(name: &'static str, ty: &'static str) -> EventField {
+    EventField {
+        name,
+        ty,
+        optional: false,
+    }
+}
+
+const fn optional(name: &'static str, ty: &'static str) -> EventField {
+    EventField {
+        name,
+        ty,
+        optional: true,
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct EventVariant {
+    pub tag: &'static str,
+    pub fields: &'static [EventField],
+}
+
+/// 单个事件通道的协议
+#[derive(Debug, Clone, Serialize)]
+pub struct EventChannelSchema {
+    pub channel: &'static str,
+    pub tag_field: &'static str,
+    pub content_field: &'static str,
+    pub variants: &'static [EventVariant],
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EventSchema {
+    pub version: u32,
+    pub agent: EventChannelSchema,
+    pub deep_research: EventChannelSchema,
+}
+
+const LLM_RETRY_FIELDS: &[EventField] = &[
+    field("request_id", "string"),
+    field("attempt", "number"),
+    field("max_retries", "number"),
+    field("delay_ms", "number"),
+    field("reason", "string"),
+    field("next_retry_at", "number"),
+];
+
+const TOKEN_USAGE_FIELDS: &[EventField] = &[
+    field("prompt_tokens", "number"),
+    field("completion_tokens", "number"),
+    field("total_tokens", "number"),
+];
+
+pub const AGENT_EVENTS: &[EventVariant] = &[
+    EventVariant {
+        tag: "status_change",
+        fields: &[field("status", "AgentStatus")],
+    },
+    EventVariant {
+        tag: "message_chunk",
+        fields: &[field("content", "string"), field("agent", "AgentType")],
+    },
+    EventVariant {
+        tag: "reasoning_delta",
+        fields: &[field("content", "string"), field("agent", "AgentType")],
+    },
+    EventVariant {
+        tag: "reasoning_done",
+        fields: &[field("request_id", "string")],
+    },
+    EventVariant {
+        tag: "intent_analysis",
+        fields: &[
+            field("intent", "string"),
+            field("route", "string"),
+            field("message", "string"),
+        ],
+    },
+    EventVariant {
+        tag: "tool_call",
+        fields: &[field("tool", "ToolCall")],
+    },
+    EventVariant {
+        tag: "tool_result",
+        fields: &[field("result", "ToolResult")],
+    },
+    EventVariant {
+        tag: "plan_updated",
+        fields: &[field("plan", "Plan")],
+    },
+    EventVariant {
+        tag: "token_usage",
+        fields: TOKEN_USAGE_FIELDS,
+    },
+    EventVariant {
+        tag: "context_compacted",
+        fields: &[
+            field("removed_messages", "number"),
+            field("tokens_before", "number"),
+            field("tokens_after", "number"),
+        ],
+    },
+    EventVariant {
+        tag: "token_budget_exceeded",
+        fields: &[field("used_tokens", "number"), field("budget", "number")],
+    },
+    EventVariant {
+        tag: "cost_update",
+        fields: &[
+            field("step_cost", "number"),
+            field("total_cost", "number"),
+            field("prompt_tokens", "number"),
+            field("completion_tokens", "number"),
+            field("estimated", "boolean"),
+            field("unpriced", "boolean"),
+            field("budget", "number"),
+            field("near_budget", "boolean"),
+        ],
+    },
+    EventVariant {
+        tag: "dry_run_change",
+        fields: &[field("change", "SimulatedChange")],
+    },
+    EventVariant {
+        tag: "complete",
+        fields: &[field("result", "string")],
+    },
+    EventVariant {
+        tag: "error",
+        fields: &[field("message", "string")],
+    },
+    EventVariant {
+        tag: "waiting_approval",
+        fields: &[field("tool", "ToolCall"), field("request_id", "string")],
+    },
+    EventVariant {
+        tag: "question_asked",
+        fields: &[
+            field("question", "string"),
+            field("options", "string[]"),
+            field("request_id", "string"),
+        ],
+    },
+    EventVariant {
+        tag: "llm_request_start",
+        fields: &[field("request_id", "string"), field("timestamp", "number")],
+    },
+    EventVariant {
+        tag: "llm_request_end",
+        fields: &[field("request_id", "string")],
+    },
+    EventVariant {
+        tag: "llm_retry_scheduled",
+        fields: LLM_RETRY_FIELDS,
+    },
+    EventVariant {
+        tag: "rate_limited",
+        fields: LLM_RETRY_FIELDS,
+    },
+    EventVariant {
+        tag: "heartbeat",
+        fields: &[field("timestamp", "number")],
+    },
+    EventVariant {
+        tag: "queue_updated",
+        fields: &[
+            field("running", "boolean"),
+            optional("active_task", "string"),
+            field("queued", "QueuedTaskSummary[]"),
+        ],
+    },
+    EventVariant {
+        tag: "prompt_stack",
+        fields: &[
+            field("provider", "string"),
+            field("base_system", "string"),
+            field("system_prompt", "string"),
+            field("built_in_agent", "string"),
+            field("workspace_agent", "string"),
+            optional("skills_index", "string"),
+        ],
+    },
+];
+
+pub const DEEP_RESEARCH_EVENTS: &[EventVariant] = &[
+    EventVariant {
+        tag: "phase_change",
+        fields: &[
+            field("phase", "ResearchPhase"),
+            field("message", "string"),
+            optional("preset", "ReportPreset"),
+        ],
+    },
+    EventVariant {
+        tag: "keywords_extracted",
+        fields: &[field("keywords", "string[]")],
+    },
+    EventVariant {
+        tag: "notes_found",
+        fields: &[field("notes", "NoteReference[]")],
+    },
+    EventVariant {
+        tag: "web_search_complete",
+        fields: &[field("results", "WebSearchResult[]")],
+    },
+    EventVariant {
+        tag: "crawling_page",
+        fields: &[
+            field("url", "string"),
+            field("title", "string"),
+            field("index", "number"),
+            field("total", "number"),
+        ],
+    },
+    EventVariant {
+        tag: "page_crawled",
+        fields: &[
+            field("url", "string"),
+            field("title", "string"),
+            field("content_preview", "string"),
+        ],
+    },
+    EventVariant {
+        tag: "reading_note",
+        fields: &[
+            field("path", "string"),
+            field("title", "string"),
+            field("index", "number"),
+            field("total", "number"),
+        ],
+    },
+    EventVariant {
+        tag: "note_read",
+        fields: &[
+            field("path", "string"),
+            field("title", "string"),
+            field("summary", "string | null"),
+        ],
+    },
+    EventVariant {
+        tag: "outline_generated",
+        fields: &[field("outline", "ReportOutline")],
+    },
+    EventVariant {
+        tag: "report_chunk",
+        fields: &[field("content", "string")],
+    },
+    EventVariant {
+        tag: "token_usage",
+        fields: TOKEN_USAGE_FIELDS,
+    },
+    EventVariant {
+        tag: "needs_clarification",
+        fields: &[
+            field("question", "string"),
+            field("suggestions", "string[]"),
+            field("interrupt_id", "string"),
+        ],
+    },
+    EventVariant {
+        tag: "complete",
+        fields: &[field("report", "string")],
+    },
+    EventVariant {
+        tag: "error",
+        fields: &[field("message", "string")],
+    },
+];
+
+/// 完整的事件协议（`agent_event_schema` 命令返回值）
+pub fn event_schema() -> EventSchema {
+    EventSchema {
+        version: EVENT_SCHEMA_VERSION,
+        agent: EventChannelSchema {
+            channel: AGENT_EVENT_CHANNEL,
+            tag_field: "type",
+            content_field: "data",
+            variants: AGENT_EVENTS,
+        },
+        deep_research: EventChannelSchema {
+            channel: DEEP_RESEARCH_EVENT_CHANNEL,
+            tag_field: "type",
+            content_field: "data",
+            variants: DEEP_RESEARCH_EVENTS,
+        },
+    }
+}
+
+impl AgentEvent {
+    /// 序列化后的 `type` 标签
+    pub fn tag(&self) -> &'static str {
+        match self {
+            AgentEvent::StatusChange { .. } => "status_change",
+            AgentEvent::MessageChunk { .. } => "message_chunk",
+            AgentEvent::ReasoningDelta { .. } => "reasoning_delta",
+            AgentEvent::ReasoningDone { .. } => "reasoning_done",
+            AgentEvent::IntentAnalysis { .. } => "intent_analysis",
+            AgentEvent::ToolCall { .. } => "tool_call",
+            AgentEvent::ToolResult { .. } => "tool_result",
+            AgentEvent::PlanUpdated { .. } => "plan_updated",
+            AgentEvent::TokenUsage { .. } => "token_usage",
+            AgentEvent::ContextCompacted { .. } => "context_compacted",
+            AgentEvent::TokenBudgetExceeded { .. } => "token_budget_exceeded",
+            AgentEvent::CostUpdate { .. } => "cost_update",
+            AgentEvent::DryRunChange { .. } => "dry_run_change",
+            AgentEvent::Complete { .. } => "complete",
+            AgentEvent::Error { .. } => "error",
+            AgentEvent::WaitingApproval { .. } => "waiting_approval",
+            AgentEvent::QuestionAsked { .. } => "question_asked",
+            AgentEvent::LlmRequestStart { .. } => "llm_request_start",
+            AgentEvent::LlmRequestEnd { .. } => "llm_request_end",
+            AgentEvent::LlmRetryScheduled { .. } => "llm_retry_scheduled",
+            AgentEvent::RateLimited { .. } => "rate_limited",
+            AgentEvent::Heartbeat { .. } => "heartbeat",
+            AgentEvent::QueueUpdated { .. } => "queue_updated",
+            AgentEvent::PromptStack { .. } => "prompt_stack",
+        }
+    }
+}
+
+impl DeepResearchEvent {
+    /// 序列化后的 `type` 标签
+    pub fn tag(&self) -> &'static str {
+        match self {
+            DeepResearchEvent::PhaseChange { .. } => "phase_change",
+            DeepResearchEvent::KeywordsExtracted { .. } => "keywords_extracted",
+            DeepResearchEvent::NotesFound { .. } => "notes_found",
+            DeepResearchEvent::WebSearchComplete { .. } => "web_search_complete",
+            DeepResearchEvent::CrawlingPage { .. } => "crawling_page",
+            DeepResearchEvent::PageCrawled { .. } => "page_crawled",
+            DeepResearchEvent::ReadingNote { .. } => "reading_note",
+            DeepResearchEvent::NoteRead { .. } => "note_read",
+            DeepResearchEvent::OutlineGenerated { .. } => "outline_generated",
+            DeepResearchEvent::ReportChunk { .. } => "report_chunk",
+            DeepResearchEvent::TokenUsage { .. } => "token_usage",
+            DeepResearchEvent::NeedsClarification { .. } => "needs_clarification",
+            DeepResearchEvent::Complete { .. } => "complete",
+            DeepResearchEvent::Error { .. } => "error",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+    use std::collections::HashSet;
+
+    /// 序列化结果与描述一致：标签存在，必填字段齐全，没有未描述的字段
+    fn assert_matches(variants: &[EventVariant], tag: &str, payload: Value) {
+        assert_eq!(payload["type"], tag);
+        let variant = variants
+            .iter()
+            .find(|variant| variant.tag == tag)
+            .unwrap_or_else(|| panic!("{} is not described", tag));
+        let data = payload["data"].as_object().expect("data object");
+        for field in variant.fields {
+            assert!(
+                field.optional || data.contains_key(field.name),
+                "{}.{} missing",
+                tag,
+                field.name
+            );
+        }
+        for key in data.keys() {
+            assert!(
+                variant.fields.iter().any(|field| field.name == key),
+                "{}.{} is not described",
+                tag,
+                key
+            );
+        }
+    }
+
+    #[test]
+    fn tags_are_unique() {
+        for variants in [AGENT_EVENTS, DEEP_RESEARCH_EVENTS] {
+            let tags: HashSet<_> = variants.iter().map(|variant| variant.tag).collect();
+            assert_eq!(tags.len(), variants.len());
+        }
+    }
+
+    #[test]
+    fn descriptors_match_serialized_events() {
+        let events = [
+            AgentEvent::ReasoningDone {
+                request_id: "r1".to_string(),
+            },
+            AgentEvent::ContextCompacted {
+                removed_messages: 4,
+                tokens_before: 9000,
+                tokens_after: 3000,
+            },
+            AgentEvent::QuestionAsked {
+                question: "继续吗？".to_string(),
+                options: vec!["是".to_string()],
+                request_id: "q1".to_string(),
+            },
+            AgentEvent::RateLimited {
+                request_id: "r1".to_string(),
+                attempt: 1,
+                max_retries: 3,
+                delay_ms: 1000,
+                reason: "429".to_string(),
+                next_retry_at: 0,
+            },
+            AgentEvent::QueueUpdated {
+                running: false,
+                active_task: None,
+                queued: Vec::new(),
+            },
+        ];
+        for event in events {
+            let tag = event.tag();
+            assert_matches(AGENT_EVENTS, tag, serde_json::to_value(&event).unwrap());
+        }
+
+        let research = [
+            DeepResearchEvent::NoteRead {
+                path: "a.md".to_string(),
+                title: "A".to_string(),
+                summary: None,
+            },
+            DeepResearchEvent::NeedsClarification {
+                question: "范围？".to_string(),
+                suggestions: Vec::new(),
+                interrupt_id: "i1".to_string(),
+            },
+        ];
+        for event in research {
+            let tag = event.tag();
+            assert_matches(
+                DEEP_RESEARCH_EVENTS,
+                tag,
+                serde_json::to_value(&event).unwrap(),
+            );
+        }
+    }
+}
@@ -1,8 +1,21 @@
-use crate::agent::llm_client::LlmClient;
-use crate::agent::types::{AgentConfig, GraphState, Message, MessageRole, ToolCall};
+use crate::agent::compaction::{compact_if_needed, estimate_prompt_tokens};
+use crate::agent::debug_log;
+use crate::agent::llm_backend::LlmBackend;
+use crate::agent::llm_client::{LlmClient, LlmResponse};
+use crate::agent::tool_budget::ToolOutputBudget;
+use crate::agent::types::{
+    AgentConfig, AgentEvent, GraphState, Message, MessageRole, ReasoningMode, ToolCall,
+};
+use crate::agent::xml_tool_calls::parse_tool_calls;
+use crate::forge_runtime::audit::AuditLog;
+use crate::forge_runtime::dry_run::DryRunLog;
+use crate::forge_runtime::journal::MutationJournal;
 use crate::forge_runtime::permissions::PermissionSession as LocalPermissionSession;
 use crate::forge_runtime::tools::{build_registry, ToolEnvironment};
-use crate::mobile_gateway::emit_agent_event_payload;
+use crate::forge_runtime::trace::ToolTrace;
+use crate::llm_pricing::{estimate_tokens, near_cost_budget, record_session_usage};
+use crate::mobile_gateway::{emit_agent_event, emit_agent_event_payload};
+use crate::vector_db::embedding::EmbeddingConfig;
 use forge::runtime::cancel::CancellationToken;
 use forge::runtime::error::{GraphError, GraphResult, Interrupt};
 use forge::runtime::event::{Event, EventSink, TokenUsage};
@@ -12,18 +25,22 @@ use forge::runtime::session_state::SessionState;
 use forge::runtime::tool::{ToolCall as ForgeToolCall, ToolOutput, ToolRegistry};
 use serde_json::{json, Value};
 use std::collections::HashSet;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
+use std::time::Instant;
 use tauri::AppHandle;
 use uuid::Uuid;
 
 const DOOM_LOOP_THRESHOLD: usize = 3;
 const TOOL_CALLS_MESSAGE_NAME: &str = "__lumina_tool_calls__";
+const TOKEN_BUDGET_WRAP_UP_PROMPT: &str = "Token budget exceeded. Stop calling tools and wrap up now: reply with your final answer, summarizing what was done and what remains unfinished.";
 
 #[derive(Clone)]
 pub struct ForgeRuntime {
     pub registry: Arc<ToolRegistry>,
     pub permissions: Arc<LocalPermissionSession>,
+    /// 试运行模式下写入类工具记录的模拟变更
+    pub dry_run: Option<Arc<DryRunLog>>,
 }
 
 pub struct ForgePending {
@@ -59,22 +76,43 @@ pub fn build_runtime(
     workspace_root: impl Into<PathBuf>,
     permissions: Arc<LocalPermissionSession>,
 ) -> ForgeRuntime {
-    build_runtime_with_client(workspace_root, permissions, None)
+    build_runtime_with_client(
+        workspace_root,
+        permissions,
+        None,
+        CancellationToken::new(),
+        false,
+        None,
+    )
 }
 
+/// `cancel` 为本次运行的取消令牌：中止时正在执行的工具（搜索、fetch、bash）会尽快停止；
+/// `dry_run` 为 true 时写入类工具只描述变更，不修改磁盘；
+/// `embedding` 为查询向量化配置，find_note 用它做语义检索
 pub fn build_runtime_with_client(
     workspace_root: impl Into<PathBuf>,
     permissions: Arc<LocalPermissionSession>,
     http_client: Option<reqwest::Client>,
+    cancel: CancellationToken,
+    dry_run: bool,
+    embedding: Option<EmbeddingConfig>,
 ) -> ForgeRuntime {
-    let mut env = ToolEnvironment::new(workspace_root, permissions.clone());
+    let mut env = ToolEnvironment::new(workspace_root, permissions.clone()).with_cancel(cancel);
     if let Some(client) = http_client {
         env = env.with_http_client(client);
     }
+    if let Some(config) = embedding {
+        env = env.with_embedding(config);
+    }
+    let dry_run = dry_run.then(|| Arc::new(DryRunLog::default()));
+    if let Some(log) = &dry_run {
+        env = env.with_dry_run(log.clone());
+    }
     let registry = Arc::new(build_registry(env));
     ForgeRuntime {
         registry,
         permissions,
+        dry_run,
     }
 }
 
@@ -114,7 +152,7 @@ pub async fn run_forge_loop(
 ) -> Result<ForgeRunResult, String> {
     let tool_defs = Arc::new(build_tool_definitions(&runtime.registry));
     let available_tools = Arc::new(collect_available_tools(&tool_defs));
-    let llm = Arc::new(LlmClient::new(config.clone(), http_client));
+    let llm = Arc::new(LlmClient::new(config.clone(), http_client).with_cancel(cancel.clone()));
     let pending = Arc::new(Mutex::new(None::<ForgePending>));
     let pending_calls = Arc::new(Mutex::new(pending_tool_calls));
     let sink: Arc<dyn EventSink> = Arc::new(TauriEventSink::new(app.clone()));
@@ -123,6 +161,23 @@ pub async fn run_forge_loop(
         message_id.clone(),
     )));
 
+    // 记录本会话中 Agent 读写过的文件（.lumina/agent-audit.jsonl）
+    let audit = Arc::new(AuditLog::new(&state.workspace_path, session_id.clone()));
+    let dry_run = runtime.dry_run.clone();
+    // 记录每次文件变更前后的内容，供撤销/重做（.lumina/agent-journal/<session>.json）
+    let journal = Arc::new(MutationJournal::new(
+        &state.workspace_path,
+        session_id.clone(),
+    ));
+    // 按顺序记录每次工具调用的参数、耗时与结果（.lumina/agent-traces/<session>.jsonl）
+    let trace = Arc::new(ToolTrace::new(&state.workspace_path, session_id.clone()));
+    // 超出预算的工具结果截断后再放入消息，完整内容落盘供模型分页读取
+    let budget = Arc::new(ToolOutputBudget::new(
+        &config,
+        Path::new(&state.workspace_path),
+        &session_id,
+    ));
+
     let gate = Arc::new(PermissionSession::new(PermissionPolicy::default()));
     let node = LoopNode::with_tools_and_gate("agent_loop", runtime.registry.clone(), gate, {
         let pending = pending.clone();
@@ -134,6 +189,11 @@ pub async fn run_forge_loop(
         let message_id = message_id.clone();
         let cancel = cancel.clone();
         let app = app.clone();
+        let audit = audit.clone();
+        let dry_run = dry_run.clone();
+        let journal = journal.clone();
+        let trace = trace.clone();
+        let budget = budget.clone();
         move |mut state: GraphState, ctx| {
             let pending = pending.clone();
             let pending_calls = pending_calls.clone();
@@ -144,6 +204,11 @@ pub async fn run_forge_loop(
             let message_id = message_id.clone();
             let cancel = cancel.clone();
             let app = app.clone();
+            let audit = audit.clone();
+            let dry_run = dry_run.clone();
+            let journal = journal.clone();
+            let trace = trace.clone();
+            let budget = budget.clone();
             async move {
                 let mut queued_calls = {
                     let mut locked = pending_calls.lock().unwrap();
@@ -152,6 +217,7 @@ pub async fn run_forge_loop(
                 let mut iteration = 0usize;
                 let max_iterations = config.max_steps;
                 let mut recent_tool_batches: Vec<String> = Vec::new();
+                let mut token_budget = TokenBudget::new(config.token_budget, state.tokens_used);
 
                 loop {
                     if cancel.is_cancelled() {
@@ -166,48 +232,83 @@ pub async fn run_forge_loop(
                             return Err(GraphError::MaxIterationsExceeded);
                         }
 
+                        // 上下文接近模型窗口时总结较早的消息；失败时按原消息继续
+                        match compact_if_needed(
+                            llm.as_ref(),
+                            &config,
+                            &mut state.messages,
+                            &mut state.observations,
+                        )
+                        .await
+                        {
+                            Ok(Some(compaction)) => {
+                                state.tokens_used += compaction.summary_tokens;
+                                emit_agent_event(
+                                    &app,
+                                    AgentEvent::ContextCompacted {
+                                        removed_messages: compaction.removed_messages,
+                                        tokens_before: compaction.tokens_before,
+                                        tokens_after: compaction.tokens_after,
+                                    },
+                                );
+                            }
+                            Ok(None) => {}
+                            Err(_) if cancel.is_cancelled() => {
+                                return Err(GraphError::Aborted {
+                                    reason: cancel.abort_reason(),
+                                });
+                            }
+                            Err(err) => eprintln!("[Agent] Context compaction failed: {}", err),
+                        }
+
                         ctx.emit(Event::StepStart {
                             session_id: session_id.clone(),
                         })?;
 
-                        let tools = if llm.supports_fc() {
-                            Some(tool_defs.as_ref().as_slice())
-                        } else {
-                            None
-                        };
-
                         let request_id = Uuid::new_v4().to_string();
                         let ctx_for_delta = ctx.clone();
                         let delta_session_id = session_id.clone();
                         let delta_message_id = message_id.clone();
                         let delta_emit_error = Arc::new(Mutex::new(None::<String>));
-                        let response = llm
-                            .call_stream_with_delta(
-                                Some(app.clone()),
-                                &request_id,
-                                &state.messages,
-                                tools,
-                                {
-                                    let delta_emit_error = delta_emit_error.clone();
-                                    move |delta| {
-                                        if let Err(err) = ctx_for_delta.emit(Event::TextDelta {
-                                            session_id: delta_session_id.clone(),
-                                            message_id: delta_message_id.clone(),
-                                            delta: delta.to_string(),
-                                        }) {
-                                            let mut locked = delta_emit_error.lock().unwrap();
-                                            if locked.is_none() {
-                                                *locked = Some(err.to_string());
-                                            }
+                        let response = request_turn(
+                            llm.as_ref(),
+                            &mut token_budget,
+                            Some(app.clone()),
+                            &request_id,
+                            &mut state.messages,
+                            tool_defs.as_slice(),
+                            {
+                                let delta_emit_error = delta_emit_error.clone();
+                                move |delta| {
+                                    if let Err(err) = ctx_for_delta.emit(Event::TextDelta {
+                                        session_id: delta_session_id.clone(),
+                                        message_id: delta_message_id.clone(),
+                                        delta: delta.to_string(),
+                                    }) {
+                                        let mut locked = delta_emit_error.lock().unwrap();
+                                        if locked.is_none() {
+                                            *locked = Some(err.to_string());
                                         }
                                     }
-                                },
-                            )
-                            .await
-                            .map_err(|err| GraphError::ExecutionError {
-                                node: "llm".to_string(),
-                                message: err,
-                            })?;
+                                }
+                            },
+                        )
+                        .await;
+                        let response = match response {
+                            Ok(response) => response,
+                            // 重试等待期间被中止
+                            Err(_) if cancel.is_cancelled() => {
+                                return Err(GraphError::Aborted {
+                                    reason: cancel.abort_reason(),
+                                });
+                            }
+                            Err(err) => {
+                                return Err(GraphError::ExecutionError {
+                                    node: "llm".to_string(),
+                                    message: err,
+                                });
+                            }
+                        };
                         if let Some(err) = delta_emit_error.lock().unwrap().take() {
                             return Err(GraphError::ExecutionError {
                                 node: "event_sink:text_delta".to_string(),
@@ -215,20 +316,92 @@ pub async fn run_forge_loop(
                             });
                         }
 
+                        // 部分 provider 的流式模式不返回 usage，按字符数估算；
+                        // 命中缓存的响应没有实际调用，不计费
+                        let estimated = !response.cached && response.total_tokens == 0;
+                        let (prompt_tokens, completion_tokens) = if response.cached {
+                            (0, 0)
+                        } else if estimated {
+                            (
+                                estimate_prompt_tokens(&state.messages),
+                                estimate_completion_tokens(&response),
+                            )
+                        } else {
+                            (response.prompt_tokens, response.completion_tokens)
+                        };
+                        let (step_cost, summary) = record_session_usage(
+                            &session_id,
+                            &config.provider,
+                            &config.model,
+                            prompt_tokens,
+                            completion_tokens,
+                            estimated,
+                        );
+                        emit_agent_event(
+                            &app,
+                            AgentEvent::CostUpdate {
+                                step_cost,
+                                total_cost: summary.total_cost,
+                                prompt_tokens: summary.prompt_tokens,
+                                completion_tokens: summary.completion_tokens,
+                                estimated: summary.estimated,
+                                unpriced: summary.unpriced,
+                                budget: config.cost_budget,
+                                near_budget: near_cost_budget(summary.total_cost, config.cost_budget),
+                            },
+                        );
+
                         ctx.emit(Event::StepFinish {
                             session_id: session_id.clone(),
                             tokens: TokenUsage {
-                                input: response.prompt_tokens as u64,
-                                output: response.completion_tokens as u64,
+                                input: prompt_tokens as u64,
+                                output: completion_tokens as u64,
                                 reasoning: 0,
                                 cache_read: 0,
                                 cache_write: 0,
                             },
-                            cost: 0.0,
+                            cost: step_cost,
                         })?;
 
+                        state.tokens_used += prompt_tokens + completion_tokens;
+                        if token_budget.is_final_turn() {
+                            // 收尾轮次：忽略任何工具调用，直接结束
+                            let final_text = if response.content.trim().is_empty() {
+                                "已达到 token 预算上限，任务已停止。".to_string()
+                            } else {
+                                response.content.clone()
+                            };
+                            state.messages.push(Message {
+                                role: MessageRole::Assistant,
+                                content: final_text.clone(),
+                                name: None,
+                                tool_call_id: None,
+                            });
+                            ctx.emit(Event::TextFinal {
+                                session_id: session_id.clone(),
+                                message_id: message_id.clone(),
+                                text: display_final_text(
+                                    config.reasoning_mode,
+                                    response.reasoning.as_deref(),
+                                    &final_text,
+                                ),
+                            })?;
+                            state.final_result = Some(final_text);
+                            break;
+                        }
+                        if let Some(event) = token_budget.record(state.tokens_used) {
+                            emit_agent_event(&app, event);
+                        }
+
                         let finish_reason = response.finish_reason.clone();
-                        let raw_tool_calls = response.tool_calls.unwrap_or_default();
+                        // 不支持 FC 的 provider 以 XML 文本返回工具调用
+                        let raw_tool_calls = match response.tool_calls {
+                            Some(calls) => calls,
+                            None if !llm.supports_fc() => {
+                                parse_tool_calls(&response.content, &tool_defs)
+                            }
+                            None => Vec::new(),
+                        };
                         let (tool_calls, invalid_calls) =
                             repair_tool_calls(raw_tool_calls, &available_tools);
                         if !invalid_calls.is_empty() {
@@ -245,6 +418,7 @@ pub async fn run_forge_loop(
                             break;
                         }
                         if tool_calls.is_empty() {
+                            // 最终结果只保存回答，推理内容按配置决定是否展示
                             let content = response.content;
                             let final_text = if matches!(finish_reason.as_deref(), Some("tool_calls")) {
                                 "模型返回了 tool_calls 结束原因，但没有提供可执行的工具调用，已停止以避免循环。".to_string()
@@ -266,7 +440,11 @@ pub async fn run_forge_loop(
                             ctx.emit(Event::TextFinal {
                                 session_id: session_id.clone(),
                                 message_id: message_id.clone(),
-                                text: final_text.clone(),
+                                text: display_final_text(
+                                    config.reasoning_mode,
+                                    response.reasoning.as_deref(),
+                                    &final_text,
+                                ),
                             })?;
                             state.final_result = Some(final_text);
                             break;
@@ -294,12 +472,10 @@ pub async fn run_forge_loop(
                             break;
                         }
 
-                        let (reasoning_content, assistant_content) =
-                            split_reasoning_block(&response.content);
                         let tool_calls_payload = serde_json::to_string(&json!({
                             "tool_calls": &tool_calls,
-                            "content": assistant_content,
-                            "reasoning_content": reasoning_content,
+                            "content": response.content,
+                            "reasoning_content": response.reasoning,
                         }))
                         .unwrap_or_else(|_| "[]".to_string());
                         state.messages.push(Message {
@@ -318,11 +494,60 @@ pub async fn run_forge_loop(
                                 .map(|(key, value)| (key.clone(), value.clone()))
                                 .collect(),
                         );
+                        // 试运行不修改文件，不记录审计
+                        let (audit_entry, journal_entry) = match dry_run {
+                            Some(_) => (None, None),
+                            None => (
+                                audit.begin(&call.name, &input).await,
+                                journal.begin(&call.id, &call.name, &input).await,
+                            ),
+                        };
+                        let trace_input = input.clone();
                         let forge_call =
                             ForgeToolCall::new(call.name.clone(), call.id.clone(), input);
-                        match ctx.run_tool(forge_call).await {
+                        let started = Instant::now();
+                        let result = ctx.run_tool(forge_call).await;
+                        let elapsed = started.elapsed();
+                        if let Some(log) = &dry_run {
+                            for change in log.take() {
+                                emit_agent_event(&app, AgentEvent::DryRunChange { change });
+                            }
+                        }
+                        if let Some(entry) = audit_entry {
+                            // 等待授权的调用会在恢复后重新执行，届时再记录
+                            match &result {
+                                Ok(_) => audit.finish(entry, Ok(())).await,
+                                Err(GraphError::Interrupted(_) | GraphError::Aborted { .. }) => {}
+                                Err(err) => audit.finish(entry, Err(err.to_string())).await,
+                            }
+                        }
+                        if let Some(entry) = journal_entry {
+                            // 失败的调用若未改动文件则不会记录
+                            if !matches!(
+                                result,
+                                Err(GraphError::Interrupted(_) | GraphError::Aborted { .. })
+                            ) {
+                                journal.finish(entry).await;
+                            }
+                        }
+                        let traced = match &result {
+                            Ok(output) => Some(Ok(&output.content)),
+                            Err(GraphError::Interrupted(_) | GraphError::Aborted { .. }) => None,
+                            Err(err) => Some(Err(err.to_string())),
+                        };
+                        if let Some(outcome) = traced {
+                            let entry = trace.record(
+                                &call.id,
+                                &call.name,
+                                &trace_input,
+                                elapsed,
+                                outcome.as_ref().copied().map_err(String::as_str),
+                            );
+                            debug_log::log_tool_trace(&entry);
+                        }
+                        match result {
                             Ok(output) => {
-                                handle_tool_success(&mut state, &call, output);
+                                handle_tool_success(&mut state, &call, output, &budget);
                             }
                             Err(GraphError::Interrupted(interrupts)) => {
                                 let mut pending_calls = vec![call];
@@ -362,6 +587,95 @@ pub async fn run_forge_loop(
     })
 }
 
+fn token_budget_exceeded(token_budget: usize, tokens_used: usize) -> bool {
+    token_budget > 0 && tokens_used >= token_budget
+}
+
+/// token 预算的收尾流程：超出预算后注入一次收尾提示，在不提供工具的情况下
+/// 再调用一次 LLM，随后结束运行
+struct TokenBudget {
+    budget: usize,
+    /// 已超出预算
+    wrap_up: bool,
+    /// 收尾提示已注入，当前请求即最后一轮
+    wrap_up_injected: bool,
+}
+
+impl TokenBudget {
+    fn new(budget: usize, tokens_used: usize) -> Self {
+        Self {
+            budget,
+            wrap_up: token_budget_exceeded(budget, tokens_used),
+            wrap_up_injected: false,
+        }
+    }
+
+    /// 请求前调用：需要收尾时注入一次收尾提示
+    fn prepare(&mut self, messages: &mut Vec<Message>) {
+        if self.wrap_up && !self.wrap_up_injected {
+            messages.push(Message {
+                role: MessageRole::System,
+                content: TOKEN_BUDGET_WRAP_UP_PROMPT.to_string(),
+                name: None,
+                tool_call_id: None,
+            });
+            self.wrap_up_injected = true;
+        }
+    }
+
+    fn allows_tools(&self) -> bool {
+        !self.wrap_up
+    }
+
+    /// 当前请求是收尾轮：收到响应后直接结束，忽略工具调用
+    fn is_final_turn(&self) -> bool {
+        self.wrap_up_injected
+    }
+
+    /// 记录累计用量；首次超出预算时返回要发送的事件
+    fn record(&mut self, tokens_used: usize) -> Option<AgentEvent> {
+        if self.wrap_up || !token_budget_exceeded(self.budget, tokens_used) {
+            return None;
+        }
+        self.wrap_up = true;
+        Some(AgentEvent::TokenBudgetExceeded {
+            used_tokens: tokens_used,
+            budget: self.budget,
+        })
+    }
+}
+
+/// 发起一轮请求：超出 token 预算后先注入收尾提示，且不再提供工具
+async fn request_turn<L, F>(
+    llm: &L,
+    token_budget: &mut TokenBudget,
+    app: Option<AppHandle>,
+    request_id: &str,
+    messages: &mut Vec<Message>,
+    tool_defs: &[Value],
+    on_delta: F,
+) -> Result<LlmResponse, String>
+where
+    L: LlmBackend,
+    F: FnMut(&str) + Send,
+{
+    token_budget.prepare(messages);
+    let tools = (llm.supports_fc() && token_budget.allows_tools()).then_some(tool_defs);
+    llm.call_stream_with_delta(app, request_id, messages, tools, on_delta)
+        .await
+}
+
+fn estimate_completion_tokens(response: &LlmResponse) -> usize {
+    let calls = response
+        .tool_calls
+        .as_ref()
+        .map(|calls| serde_json::to_string(calls).unwrap_or_default())
+        .unwrap_or_default();
+    estimate_tokens(&response.content)
+        + estimate_tokens(response.reasoning.as_deref().unwrap_or_default())
+        + estimate_tokens(&calls)
+}
+
 fn pop_next_call(queue: &mut Vec<ToolCall>) -> Option<ToolCall> {
     if queue.is_empty() {
         None
@@ -370,8 +684,13 @@ fn pop_next_call(queue: &mut Vec<ToolCall>) -> Option<ToolCall> {
     }
 }
 
-fn handle_tool_success(state: &mut GraphState, call: &ToolCall, output: ToolOutput) {
-    let content = tool_output_text(&output);
+fn handle_tool_success(
+    state: &mut GraphState,
+    call: &ToolCall,
+    output: ToolOutput,
+    budget: &ToolOutputBudget,
+) {
+    let content = budget.apply(&call.name, &call.id, tool_output_text(&output));
     state
         .observations
         .push(format!("[{}] {}", call.name, content));
@@ -457,26 +776,16 @@ fn repair_tool_calls(
     (repaired, invalid)
 }
 
-fn split_reasoning_block(content: &str) -> (Option<String>, String) {
-    let trimmed = content.trim();
-    if !trimmed.starts_with("<thinking>") {
-        return (None, content.to_string());
+/// 生成展示给用户的最终消息：Surface 模式下以 `<thinking>` 块保留推理内容
+fn display_final_text(mode: ReasoningMode, reasoning: Option<&str>, answer: &str) -> String {
+    let reasoning = reasoning.map(str::trim).unwrap_or_default();
+    if mode != ReasoningMode::Surface || reasoning.is_empty() {
+        return answer.to_string();
     }
-    let Some(end_idx) = trimmed.find("</thinking>") else {
-        return (None, content.to_string());
-    };
-    let reasoning_start = "<thinking>".len();
-    let reasoning = trimmed[reasoning_start..end_idx].trim().to_string();
-    let remaining = trimmed[end_idx + "</thinking>".len()..]
-        .trim_start_matches('\n')
-        .trim_start()
-        .to_string();
-    let reasoning_opt = if reasoning.is_empty() {
-        None
-    } else {
-        Some(reasoning)
-    };
-    (reasoning_opt, remaining)
+    if answer.trim().is_empty() {
+        return format!("<thinking>\n{}\n</thinking>", reasoning);
+    }
+    format!("<thinking>\n{}\n</thinking>\n\n{}", reasoning, answer)
 }
 
 fn wrap_event(event: Event) -> Value {
@@ -511,3 +820,96 @@ fn to_snake_case(value: &str) -> String {
     }
     output
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::mock_llm::MockLlmClient;
+    use std::collections::HashMap;
+
+    /// 请求一次工具调用，消耗 300 token
+    fn tool_turn() -> LlmResponse {
+        LlmResponse {
+            content: String::new(),
+            reasoning: None,
+            tool_calls: Some(vec![ToolCall {
+                id: "call-1".to_string(),
+                name: "read".to_string(),
+                params: HashMap::new(),
+            }]),
+            finish_reason: Some("tool_calls".to_string()),
+            prompt_tokens: 250,
+            completion_tokens: 50,
+            total_tokens: 300,
+            cached: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn token_budget_wraps_up_once_without_tools_then_stops() {
+        // 模型一直请求工具；只有收尾轮给出回答
+        let llm = MockLlmClient::new()
+            .on_call_response(0, tool_turn())
+            .on_call_response(1, tool_turn())
+            .on_prompt(
+                TOKEN_BUDGET_WRAP_UP_PROMPT,
+                "Done: read the notes, summary pending",
+            );
+        let tool_defs = vec![json!({ "type": "function", "function": { "name": "read" } })];
+        let mut messages = vec![Message {
+            role: MessageRole::User,
+            content: "Summarize my notes".to_string(),
+            name: None,
+            tool_call_id: None,
+        }];
+
+        // 与 run_forge_loop 相同的步骤：请求、累计用量、收尾轮结束、否则执行工具
+        let mut token_budget = TokenBudget::new(500, 0);
+        let mut tokens_used = 0;
+        let mut events = Vec::new();
+        let final_text = loop {
+            let response = request_turn(
+                &llm,
+                &mut token_budget,
+                None,
+                "request",
+                &mut messages,
+                &tool_defs,
+                |_| {},
+            )
+            .await
+            .unwrap();
+            tokens_used += response.total_tokens;
+            if token_budget.is_final_turn() {
+                break response.content;
+            }
+            events.extend(token_budget.record(tokens_used));
+            for call in response.tool_calls.unwrap_or_default() {
+                messages.push(Message {
+                    role: MessageRole::Tool,
+                    content: "note contents".to_string(),
+                    name: Some(call.name),
+                    tool_call_id: Some(call.id),
+                });
+            }
+        };
+
+        assert_eq!(final_text, "Done: read the notes, summary pending");
+        assert_eq!(llm.tools_offered(), vec![true, true, false]);
+        assert_eq!(events.len(), 1);
+        assert!(matches!(
+            events[0],
+            AgentEvent::TokenBudgetExceeded {
+                used_tokens: 600,
+                budget: 500
+            }
+        ));
+        assert_eq!(
+            messages
+                .iter()
+                .filter(|message| message.content == TOKEN_BUDGET_WRAP_UP_PROMPT)
+                .count(),
+            1
+        );
+    }
+}
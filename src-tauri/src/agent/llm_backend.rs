@@ -0,0 +1,110 @@
+//! LLM 调用抽象
+//!
+//! Deep Research 节点和 Agent 主循环的单轮请求只依赖该 trait 调用 LLM：运行时使用
+//! `LlmClient`，测试中替换为脚本化的 `MockLlmClient`，无需网络即可覆盖节点路由与解析逻辑。
+
+use crate::agent::llm_client::{LlmClient, LlmResponse};
+use crate::agent::llm_provider::ResponseSchema;
+use crate::agent::types::Message;
+use serde_json::Value;
+use std::future::Future;
+use tauri::AppHandle;
+use tokio::sync::mpsc::Receiver;
+
+/// 节点可用的 LLM 调用能力
+pub trait LlmBackend: Send + Sync {
+    /// 单轮非流式调用（返回完整响应，包含 token 统计）
+    fn call_simple_with_usage(
+        &self,
+        prompt: &str,
+    ) -> impl Future<Output = Result<LlmResponse, String>> + Send;
+
+    /// 单轮非流式调用，只返回文本
+    fn call_simple(&self, prompt: &str) -> impl Future<Output = Result<String, String>> + Send {
+        async move {
+            self.call_simple_with_usage(prompt)
+                .await
+                .map(|response| response.content)
+        }
+    }
+
+    /// 单轮结构化输出调用：要求按 JSON Schema 返回
+    ///
+    /// 默认按普通调用处理，返回内容不保证合法，调用方需自行校验。
+    fn call_json_with_usage(
+        &self,
+        prompt: &str,
+        _schema: &ResponseSchema,
+    ) -> impl Future<Output = Result<LlmResponse, String>> + Send {
+        self.call_simple_with_usage(prompt)
+    }
+
+    /// 单轮流式调用，通过 channel 逐块返回文本
+    fn call_stream_simple(
+        &self,
+        prompt: &str,
+    ) -> impl Future<Output = Result<Receiver<String>, String>> + Send;
+
+    /// 是否支持原生 function calling（不支持时工具调用以 XML 文本返回）
+    fn supports_fc(&self) -> bool {
+        true
+    }
+
+    /// 多轮消息的流式调用（Agent 主循环），`tools` 为 None 时不提供工具
+    fn call_stream_with_delta<F>(
+        &self,
+        app: Option<AppHandle>,
+        request_id: &str,
+        messages: &[Message],
+        tools: Option<&[Value]>,
+        on_delta: F,
+    ) -> impl Future<Output = Result<LlmResponse, String>> + Send
+    where
+        F: FnMut(&str) + Send;
+}
+
+impl LlmBackend for LlmClient {
+    fn call_simple_with_usage(
+        &self,
+        prompt: &str,
+    ) -> impl Future<Output = Result<LlmResponse, String>> + Send {
+        LlmClient::call_simple_with_usage(self, prompt)
+    }
+
+    fn call_simple(&self, prompt: &str) -> impl Future<Output = Result<String, String>> + Send {
+        LlmClient::call_simple(self, prompt)
+    }
+
+    fn call_json_with_usage(
+        &self,
+        prompt: &str,
+        schema: &ResponseSchema,
+    ) -> impl Future<Output = Result<LlmResponse, String>> + Send {
+        LlmClient::call_json_with_usage(self, prompt, schema)
+    }
+
+    fn call_stream_simple(
+        &self,
+        prompt: &str,
+    ) -> impl Future<Output = Result<Receiver<String>, String>> + Send {
+        LlmClient::call_stream_simple(self, prompt)
+    }
+
+    fn supports_fc(&self) -> bool {
+        LlmClient::supports_fc(self)
+    }
+
+    fn call_stream_with_delta<F>(
+        &self,
+        app: Option<AppHandle>,
+        request_id: &str,
+        messages: &[Message],
+        tools: Option<&[Value]>,
+        on_delta: F,
+    ) -> impl Future<Output = Result<LlmResponse, String>> + Send
+    where
+        F: FnMut(&str) + Send,
+    {
+        LlmClient::call_stream_with_delta(self, app, request_id, messages, tools, on_delta)
+    }
+}
@@ -4,11 +4,18 @@
 //!
 //! ## SSE 稳定性增强
 //! - 心跳机制：定期发送心跳事件，检测连接状态
-//! - 指数退避重试：网络错误时自动重试
+//! - 指数退避重试：网络错误、429 / 5xx 时自动重试，遵循 Retry-After，可随任务中止取消
 //! - 超时检测：检测流式响应假死
 
+use crate::agent::llm_provider::{
+    provider_for, split_think_tags, stream_payload, LlmProvider, ProviderDelta, ProviderRequest,
+    ProviderStreamState, ResponseSchema, ThinkTagSplitter,
+};
 use crate::agent::types::*;
+use crate::agent::xml_tool_calls::format_tool_call;
+use crate::llm_cache::{self, CacheMode};
 use crate::mobile_gateway::emit_agent_event;
+use forge::runtime::cancel::CancellationToken;
 use futures_util::StreamExt;
 use reqwest::header::HeaderMap;
 use reqwest::StatusCode;
@@ -23,6 +30,8 @@ const STREAM_MAX_RETRIES: u32 = 3;
 const STREAM_RETRY_BASE_DELAY_MS: u64 = 1_000;
 const STREAM_RETRY_MAX_DELAY_MS: u64 = 30_000;
 const STREAM_RETRY_JITTER_MS: u64 = 500;
+/// 退避等待期间检查中止信号的间隔
+const RETRY_CANCEL_POLL_MS: u64 = 200;
 const TOOL_CALLS_MESSAGE_NAME: &str = "__lumina_tool_calls__";
 
 /// OpenAI 格式的请求
@@ -100,14 +109,21 @@ struct FunctionDefinition {
 }
 
 /// LLM 响应（包含 token 使用量）
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LlmResponse {
+    /// 最终回答（不含推理内容）
     pub content: String,
+    /// 推理内容（reasoning 字段或 `<think>` 标签），与回答分开保存
+    #[serde(default)]
+    pub reasoning: Option<String>,
     pub tool_calls: Option<Vec<ToolCall>>, // FC 模式下直接返回解析后的工具调用
     pub finish_reason: Option<String>,
     pub prompt_tokens: usize,
     pub completion_tokens: usize,
     pub total_tokens: usize,
+    /// 来自响应缓存：没有实际调用，不计费
+    #[serde(skip)]
+    pub cached: bool,
 }
 
 #[derive(Default)]
@@ -121,6 +137,8 @@ struct StreamToolCall {
 struct StreamRequestError {
     message: String,
     retryable: bool,
+    /// 是否为限流错误（429 / rate_limit）
+    rate_limited: bool,
     retry_after_ms: Option<u64>,
 }
 
@@ -129,6 +147,16 @@ impl StreamRequestError {
         Self {
             message: message.into(),
             retryable: true,
+            rate_limited: false,
+            retry_after_ms,
+        }
+    }
+
+    fn rate_limited(message: impl Into<String>, retry_after_ms: Option<u64>) -> Self {
+        Self {
+            message: message.into(),
+            retryable: true,
+            rate_limited: true,
             retry_after_ms,
         }
     }
@@ -137,37 +165,84 @@ impl StreamRequestError {
         Self {
             message: message.into(),
             retryable: false,
+            rate_limited: false,
             retry_after_ms: None,
         }
     }
 }
 
+/// 判断是否使用原生 Function Calling（不支持时使用 XML 工具协议）
+///
+/// Auto 模式按 provider 默认能力决定，tool_call_mode 可按 provider 配置覆盖
+pub fn config_supports_fc(config: &AgentConfig) -> bool {
+    match config.tool_call_mode {
+        ToolCallMode::Native => true,
+        ToolCallMode::Xml => false,
+        ToolCallMode::Auto => {
+            provider_for(&config.provider, config.base_url.as_deref()).native_tool_calls()
+        }
+    }
+}
+
 /// LLM 客户端
 pub struct LlmClient {
     config: AgentConfig,
     client: reqwest::Client,
+    provider: Box<dyn LlmProvider>,
+    /// 任务中止信号：重试等待期间收到中止会立即返回
+    cancel: Option<CancellationToken>,
 }
 
 impl LlmClient {
     pub fn new(config: AgentConfig, client: reqwest::Client) -> Self {
-        Self { config, client }
+        let provider = provider_for(&config.provider, config.base_url.as_deref());
+        Self {
+            config,
+            client,
+            provider,
+            cancel: None,
+        }
+    }
+
+    /// 绑定任务中止信号
+    pub fn with_cancel(mut self, cancel: CancellationToken) -> Self {
+        self.cancel = Some(cancel);
+        self
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancel
+            .as_ref()
+            .map(|cancel| cancel.is_cancelled())
+            .unwrap_or(false)
+    }
+
+    /// 退避等待；期间任务被中止则返回错误
+    async fn wait_before_retry(&self, delay_ms: u64) -> Result<(), String> {
+        let deadline = Instant::now() + Duration::from_millis(delay_ms);
+        loop {
+            if self.is_cancelled() {
+                return Err("LLM request aborted".to_string());
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return Ok(());
+            }
+            let remaining = deadline - now;
+            tokio::time::sleep(remaining.min(Duration::from_millis(RETRY_CANCEL_POLL_MS))).await;
+        }
     }
 
     /// 获取 API URL
-    fn get_api_url(&self) -> String {
+    fn get_api_url(&self, stream: bool) -> String {
         let base = self
             .config
             .base_url
             .clone()
             .unwrap_or_else(|| self.get_default_base_url());
 
-        // 移除尾部斜杠
-        let base = base.trim_end_matches('/');
-
-        match self.config.provider.as_str() {
-            "anthropic" => format!("{}/messages", base),
-            _ => format!("{}/chat/completions", base),
-        }
+        self.provider
+            .endpoint(&base, &self.resolved_model(), stream)
     }
 
     fn get_default_base_url(&self) -> String {
@@ -178,18 +253,15 @@ impl LlmClient {
             "moonshot" => "https://api.moonshot.cn/v1".to_string(),
             "zai" => "https://open.bigmodel.cn/api/paas/v4".to_string(),
             "groq" => "https://api.groq.com/openai/v1".to_string(),
+            "gemini" => "https://generativelanguage.googleapis.com/v1beta".to_string(),
+            "ollama" => "http://localhost:11434/v1".to_string(),
             _ => "https://api.openai.com/v1".to_string(),
         }
     }
 
     /// 判断当前 provider 是否支持 Function Calling
     pub fn supports_fc(&self) -> bool {
-        match self.config.provider.as_str() {
-            "openai" | "anthropic" | "deepseek" | "moonshot" | "zai" | "gemini" | "groq"
-            | "openrouter" | "custom" => true,
-            "ollama" => false, // 本地模型 FC 支持不稳定，使用 XML 模式
-            _ => false,        // 未知 provider 默认不支持
-        }
+        config_supports_fc(&self.config)
     }
 
     fn model_matches(model: &str, target: &str) -> bool {
@@ -375,23 +447,35 @@ impl LlmClient {
 
     /// 构建请求头
     fn build_headers(&self) -> HashMap<String, String> {
-        let mut headers = HashMap::new();
-        headers.insert("Content-Type".to_string(), "application/json".to_string());
+        self.provider.headers(&self.config.api_key)
+    }
 
-        match self.config.provider.as_str() {
-            "anthropic" => {
-                headers.insert("x-api-key".to_string(), self.config.api_key.clone());
-                headers.insert("anthropic-version".to_string(), "2023-06-01".to_string());
-            }
-            _ => {
-                headers.insert(
-                    "Authorization".to_string(),
-                    format!("Bearer {}", self.config.api_key),
-                );
-            }
-        }
+    /// 构建请求体：先归一化为 OpenAI 格式，再交给 provider 转换为原生格式
+    fn build_request_body(
+        &self,
+        messages: &[Message],
+        tools: Option<&[Value]>,
+        stream: bool,
+    ) -> Value {
+        let resolved_model = self.resolved_model();
+        let chat_messages =
+            self.normalize_chat_messages(self.convert_messages(messages), &resolved_model);
+        let request = ProviderRequest {
+            model: &resolved_model,
+            messages: chat_messages
+                .iter()
+                .filter_map(|m| serde_json::to_value(m).ok())
+                .collect(),
+            temperature: Some(self.resolved_temperature()),
+            max_tokens: self.config.max_tokens,
+            stream,
+            tools: self.resolve_tools_payload(messages, tools),
+        };
 
-        headers
+        let mut body = self.provider.build_body(&request);
+        self.apply_thinking_controls(&mut body, &resolved_model);
+        self.apply_moonshot_k25_constraints(&mut body, &resolved_model);
+        body
     }
 
     /// 转换消息格式
@@ -598,203 +682,203 @@ impl LlmClient {
         normalized
     }
 
+    /// 响应缓存键：以非流式请求地址与请求体计算，流式与非流式调用共享同一条缓存
+    fn response_cache_key(
+        &self,
+        kind: &str,
+        messages: &[Message],
+        tools: Option<&[Value]>,
+    ) -> Option<String> {
+        if self.config.cache == CacheMode::Off {
+            return None;
+        }
+        let body = self.build_request_body(messages, tools, false);
+        llm_cache::cache_key_for_body(kind, &self.get_api_url(false), &body)
+    }
+
+    /// 按实际请求体计算缓存键（请求体在构建后还会被修改时使用）
+    fn body_cache_key(&self, kind: &str, body: &Value) -> Option<String> {
+        if self.config.cache == CacheMode::Off {
+            return None;
+        }
+        llm_cache::cache_key_for_body(kind, &self.get_api_url(false), body)
+    }
+
+    fn cached_response(&self, key: Option<&str>) -> Option<LlmResponse> {
+        llm_cache::lookup(self.config.cache, key)
+            .and_then(|payload| serde_json::from_value::<LlmResponse>(payload).ok())
+            .map(|response| LlmResponse {
+                cached: true,
+                ..response
+            })
+    }
+
+    fn cache_response(&self, key: Option<&str>, response: &LlmResponse) {
+        if let Ok(payload) = serde_json::to_value(response) {
+            llm_cache::store(self.config.cache, key, payload);
+        }
+    }
+
     /// 非流式调用（带重试机制）
     pub async fn call(
         &self,
         messages: &[Message],
         tools: Option<&[Value]>,
     ) -> Result<LlmResponse, String> {
-        let url = self.get_api_url();
-        let headers = self.build_headers();
-
-        let resolved_model = self.resolved_model();
-        let chat_messages =
-            self.normalize_chat_messages(self.convert_messages(messages), &resolved_model);
-        let temperature = self.resolved_temperature();
-
-        let mut body = json!({
-            "model": resolved_model,
-            "messages": chat_messages,
-            "temperature": temperature,
-            "max_tokens": self.config.max_tokens,
-            "stream": false,
-        });
-        self.apply_thinking_controls(&mut body, &resolved_model);
-        self.apply_moonshot_k25_constraints(&mut body, &resolved_model);
+        self.call_with_schema(messages, tools, None).await
+    }
 
-        let tools_payload = self.resolve_tools_payload(messages, tools);
-        let has_tools = tools_payload.is_some();
-        if let Some(tools) = tools_payload {
-            body["tools"] = tools;
+    /// 非流式调用，可要求 provider 按 JSON Schema 输出
+    ///
+    /// provider 不支持结构化输出时按普通请求发送，返回内容仍需调用方校验。
+    async fn call_with_schema(
+        &self,
+        messages: &[Message],
+        tools: Option<&[Value]>,
+        response_schema: Option<&ResponseSchema>,
+    ) -> Result<LlmResponse, String> {
+        let mut body = self.build_request_body(messages, tools, false);
+        if let Some(schema) = response_schema {
+            if !self.provider.apply_response_schema(&mut body, schema) {
+                println!(
+                    "[LlmClient] provider {} 不支持结构化输出，按普通请求发送",
+                    self.config.provider
+                );
+            }
+        }
+        let cache_key = self.body_cache_key("response", &body);
+        if let Some(response) = self.cached_response(cache_key.as_deref()) {
+            return Ok(response);
         }
 
+        let url = self.get_api_url(false);
+        let headers = self.build_headers();
+
         println!("[LlmClient] 📤 发送请求到: {}", url);
         println!(
             "[LlmClient] 📤 模型: {}, 消息数: {}, 工具: {}",
-            resolved_model,
-            chat_messages.len(),
-            has_tools
+            self.resolved_model(),
+            messages.len(),
+            body.get("tools").is_some()
         );
 
-        // 重试机制
-        let max_retries = 2;
-        let mut last_error = String::new();
+        let mut last_error = StreamRequestError::fatal("unknown request error");
 
-        for attempt in 0..=max_retries {
-            if attempt > 0 {
-                // 重试前等待，指数退避
-                let wait_secs = 1u64 << (attempt - 1); // 1s, 2s
-                println!(
-                    "[LlmClient] ⏳ 重试 {} (等待 {}s)，上次错误: {}",
-                    attempt, wait_secs, last_error
-                );
-                tokio::time::sleep(std::time::Duration::from_secs(wait_secs)).await;
+        for attempt in 0..=STREAM_MAX_RETRIES {
+            if self.is_cancelled() {
+                return Err("LLM request aborted".to_string());
             }
 
             let start_time = std::time::Instant::now();
-
-            // 每次重试都重新构建请求（避免连接复用问题）
-            let mut req = self.client.post(&url);
-            for (key, value) in &headers {
-                req = req.header(key, value);
-            }
-            req = req.json(&body);
-
-            match req.send().await {
-                Ok(response) => {
+            match self.send_request(&url, &headers, &body).await {
+                Ok(json) => {
                     println!("[LlmClient] ✅ 收到响应，耗时: {:?}", start_time.elapsed());
-
-                    if !response.status().is_success() {
-                        let status = response.status();
-                        let text = response.text().await.unwrap_or_default();
-                        last_error = format!("HTTP {}: {}", status, text);
-
-                        // 5xx 错误可以重试，4xx 错误不重试
-                        if status.is_server_error() {
-                            continue;
-                        }
-                        return Err(last_error);
-                    }
-
-                    let json: Value = match response.json().await {
-                        Ok(j) => j,
-                        Err(e) => {
-                            last_error = format!("Failed to parse response: {}", e);
-                            continue;
-                        }
-                    };
-
                     // 成功，解析响应
-                    return self.parse_llm_response(json);
+                    let response = self.parse_llm_response(json)?;
+                    self.cache_response(cache_key.as_deref(), &response);
+                    return Ok(response);
                 }
                 Err(e) => {
-                    println!("[LlmClient] ❌ 请求失败: {}", e);
-                    last_error = format!("Request failed: {}", e);
-                    // 继续重试
+                    println!("[LlmClient] ❌ 请求失败: {}", e.message);
+                    if !e.retryable || attempt >= STREAM_MAX_RETRIES {
+                        return Err(e.message);
+                    }
+                    // 重试前等待：指数退避，限流时遵循 Retry-After
+                    let delay_ms = Self::retry_delay_ms(attempt + 1, e.retry_after_ms);
+                    println!(
+                        "[LlmClient] ⏳ 重试 {}/{} (等待 {}ms)",
+                        attempt + 1,
+                        STREAM_MAX_RETRIES,
+                        delay_ms
+                    );
+                    last_error = e;
+                    self.wait_before_retry(delay_ms).await?;
                 }
             }
         }
 
         // 所有重试都失败
-        Err(last_error)
+        Err(last_error.message)
+    }
+
+    /// 发送一次非流式请求
+    async fn send_request(
+        &self,
+        url: &str,
+        headers: &HashMap<String, String>,
+        body: &Value,
+    ) -> Result<Value, StreamRequestError> {
+        // 每次重试都重新构建请求（避免连接复用问题）
+        let mut req = self.client.post(url);
+        for (key, value) in headers {
+            req = req.header(key, value);
+        }
+        let response = req
+            .json(body)
+            .send()
+            .await
+            .map_err(|e| Self::to_reqwest_error("Request failed", e))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let headers = response.headers().clone();
+            let text = response.text().await.unwrap_or_default();
+            return Err(Self::to_http_error(status, &text, &headers));
+        }
+
+        let json: Value = response.json().await.map_err(|e| {
+            StreamRequestError::retryable(format!("Failed to parse response: {}", e), None)
+        })?;
+        if let Some(error) = json.get("error") {
+            let error = Self::to_api_error(error);
+            if error.retryable {
+                return Err(error);
+            }
+        }
+        Ok(json)
     }
 
     /// 解析 LLM 响应
     fn parse_llm_response(&self, json: Value) -> Result<LlmResponse, String> {
-        // 提取 token 使用量
-        let usage = &json["usage"];
-        let prompt_tokens = usage["prompt_tokens"].as_u64().unwrap_or(0) as usize;
-        let completion_tokens = usage["completion_tokens"].as_u64().unwrap_or(0) as usize;
-        let total_tokens = usage["total_tokens"]
-            .as_u64()
-            .map(|t| t as usize)
-            .unwrap_or(prompt_tokens + completion_tokens);
-
-        let message = &json["choices"][0]["message"];
-        let finish_reason = json["choices"][0]["finish_reason"]
-            .as_str()
-            .map(|s| s.to_string());
-
-        let reasoning = Self::extract_reasoning_content(message);
-
-        // 检查是否有 tool_calls（Function Call）
-        if let Some(fc_tool_calls) = message.get("tool_calls").and_then(|v| v.as_array()) {
-            if self.supports_fc() {
-                // FC 模式：直接解析 JSON 返回结构化工具调用
-                let mut parsed_calls = Vec::new();
-                for (idx, tc) in fc_tool_calls.iter().enumerate() {
-                    let name = tc["function"]["name"].as_str().unwrap_or("").to_string();
-                    let args_str = tc["function"]["arguments"].as_str().unwrap_or("{}");
-
-                    // 解析参数 JSON
-                    let params: HashMap<String, Value> =
-                        serde_json::from_str(args_str).unwrap_or_default();
-
-                    parsed_calls.push(ToolCall {
-                        id: tc["id"]
-                            .as_str()
-                            .map(|s| s.to_string())
-                            .unwrap_or_else(|| format!("call_{}", idx)),
-                        name,
-                        params,
-                    });
-                }
+        if let Some(error) = json.get("error") {
+            return Err(format!("API error: {}", error));
+        }
+        let parsed = self.provider.parse_response(&json);
+        let (tag_reasoning, answer) = split_think_tags(&parsed.content);
+        let reasoning = Self::join_reasoning(parsed.reasoning.as_deref(), &tag_reasoning);
 
-                // 文本内容（如果有）
-                let content = message["content"].as_str().unwrap_or("").to_string();
-                let content = Self::attach_reasoning_block(reasoning.as_deref(), &content);
-
-                return Ok(LlmResponse {
-                    content,
-                    tool_calls: Some(parsed_calls),
-                    finish_reason,
-                    prompt_tokens,
-                    completion_tokens,
-                    total_tokens,
-                });
-            } else {
-                // XML 模式（Ollama 等不支持 FC 的 provider）：转换为 XML 格式
-                let mut xml_output = String::new();
-                for tc in fc_tool_calls {
-                    let name = tc["function"]["name"].as_str().unwrap_or("");
-                    let args_str = tc["function"]["arguments"].as_str().unwrap_or("{}");
-
-                    if let Ok(args) = serde_json::from_str::<Value>(args_str) {
-                        xml_output.push_str(&format!("<{}>\n", name));
-                        if let Some(obj) = args.as_object() {
-                            for (key, value) in obj {
-                                let val_str = match value {
-                                    Value::String(s) => s.clone(),
-                                    _ => value.to_string(),
-                                };
-                                xml_output.push_str(&format!("<{}>{}</{}>\n", key, val_str, key));
-                            }
-                        }
-                        xml_output.push_str(&format!("</{}>\n", name));
-                    }
-                }
-                return Ok(LlmResponse {
-                    content: xml_output,
-                    tool_calls: None,
-                    finish_reason,
-                    prompt_tokens,
-                    completion_tokens,
-                    total_tokens,
-                });
+        if !parsed.tool_calls.is_empty() && !self.supports_fc() {
+            // XML 模式（Ollama 等未启用原生 FC 的 provider）：按转义约定转换为 XML 格式
+            let mut xml_output = String::new();
+            for call in &parsed.tool_calls {
+                let args = call.params.clone().into_iter().collect();
+                xml_output.push_str(&format_tool_call(&call.name, &args));
             }
+            return Ok(LlmResponse {
+                content: xml_output,
+                reasoning,
+                tool_calls: None,
+                finish_reason: parsed.finish_reason,
+                prompt_tokens: parsed.prompt_tokens,
+                completion_tokens: parsed.completion_tokens,
+                total_tokens: parsed.total_tokens,
+                cached: false,
+            });
         }
 
-        // 提取文本内容
-        let content = message["content"].as_str().unwrap_or("").to_string();
-        let content = Self::attach_reasoning_block(reasoning.as_deref(), &content);
-
         Ok(LlmResponse {
-            content,
-            tool_calls: None,
-            finish_reason,
-            prompt_tokens,
-            completion_tokens,
-            total_tokens,
+            content: answer,
+            reasoning,
+            tool_calls: if parsed.tool_calls.is_empty() {
+                None
+            } else {
+                Some(parsed.tool_calls)
+            },
+            finish_reason: parsed.finish_reason,
+            prompt_tokens: parsed.prompt_tokens,
+            completion_tokens: parsed.completion_tokens,
+            total_tokens: parsed.total_tokens,
+            cached: false,
         })
     }
 
@@ -810,6 +894,15 @@ impl LlmClient {
     where
         F: FnMut(&str) + Send,
     {
+        // 缓存只保存拼装完成的最终结果，命中时一次性回放
+        let cache_key = self.response_cache_key("response", messages, tools);
+        if let Some(response) = self.cached_response(cache_key.as_deref()) {
+            if !response.content.is_empty() {
+                on_delta(&response.content);
+            }
+            return Ok(response);
+        }
+
         if let Some(app) = &app {
             let start_timestamp = SystemTime::now()
                 .duration_since(UNIX_EPOCH)
@@ -827,6 +920,10 @@ impl LlmClient {
         let result = self
             .call_stream_with_delta_retry(app.clone(), request_id, messages, tools, &mut on_delta)
             .await;
+        // 流中途断开时 finish_reason 为空，不完整的响应不写入缓存
+        if let Some(response) = result.as_ref().ok().filter(|r| r.finish_reason.is_some()) {
+            self.cache_response(cache_key.as_deref(), response);
+        }
 
         if let Some(app) = &app {
             emit_agent_event(
@@ -854,14 +951,33 @@ impl LlmClient {
         let mut last_error = StreamRequestError::fatal("unknown stream error");
 
         for attempt in 0..=STREAM_MAX_RETRIES {
-            match self
-                .call_stream_inner_with_delta(app.clone(), request_id, messages, tools, on_delta)
+            if self.is_cancelled() {
+                return Err("LLM request aborted".to_string());
+            }
+
+            // 记录本次尝试是否已向调用方输出过内容
+            let mut emitted = false;
+            let result = {
+                let mut tracked_delta = |delta: &str| {
+                    emitted = true;
+                    on_delta(delta);
+                };
+                self.call_stream_inner_with_delta(
+                    app.clone(),
+                    request_id,
+                    messages,
+                    tools,
+                    &mut tracked_delta,
+                )
                 .await
-            {
+            };
+
+            match result {
                 Ok(response) => return Ok(response),
                 Err(e) => {
                     last_error = e.clone();
-                    if !e.retryable || attempt >= STREAM_MAX_RETRIES {
+                    // 已输出部分内容时不再重试，避免重复输出
+                    if !e.retryable || emitted || attempt >= STREAM_MAX_RETRIES {
                         return Err(e.message);
                     }
                     let retry_attempt = attempt + 1;
@@ -874,20 +990,30 @@ impl LlmClient {
                     );
 
                     if let Some(app) = &app {
-                        emit_agent_event(
-                            app,
+                        let reason = Self::retry_reason(&e.message);
+                        let event = if e.rate_limited {
+                            AgentEvent::RateLimited {
+                                request_id: request_id.to_string(),
+                                attempt: retry_attempt,
+                                max_retries: STREAM_MAX_RETRIES,
+                                delay_ms,
+                                reason,
+                                next_retry_at,
+                            }
+                        } else {
                             AgentEvent::LlmRetryScheduled {
                                 request_id: request_id.to_string(),
                                 attempt: retry_attempt,
                                 max_retries: STREAM_MAX_RETRIES,
                                 delay_ms,
-                                reason: Self::retry_reason(&e.message),
+                                reason,
                                 next_retry_at,
-                            },
-                        );
+                            }
+                        };
+                        emit_agent_event(app, event);
                     }
 
-                    tokio::time::sleep(Duration::from_millis(delay_ms)).await;
+                    self.wait_before_retry(delay_ms).await?;
                 }
             }
         }
@@ -909,27 +1035,9 @@ impl LlmClient {
     where
         F: FnMut(&str) + Send,
     {
-        let url = self.get_api_url();
+        let url = self.get_api_url(true);
         let headers = self.build_headers();
-
-        let resolved_model = self.resolved_model();
-        let chat_messages =
-            self.normalize_chat_messages(self.convert_messages(messages), &resolved_model);
-        let temperature = self.resolved_temperature();
-
-        let mut body = json!({
-            "model": resolved_model,
-            "messages": chat_messages,
-            "temperature": temperature,
-            "max_tokens": self.config.max_tokens,
-            "stream": true,
-        });
-        self.apply_thinking_controls(&mut body, &resolved_model);
-        self.apply_moonshot_k25_constraints(&mut body, &resolved_model);
-
-        if let Some(tools) = self.resolve_tools_payload(messages, tools) {
-            body["tools"] = tools;
-        }
+        let body = self.build_request_body(messages, tools, true);
 
         let mut req = self.client.post(&url);
         for (key, value) in headers {
@@ -958,6 +1066,8 @@ impl LlmClient {
         let mut completion_tokens = 0usize;
         let mut total_tokens = 0usize;
         let mut finish_reason: Option<String> = None;
+        let mut stream_state = ProviderStreamState::default();
+        let mut think_splitter = ThinkTagSplitter::default();
 
         let heartbeat_interval = Duration::from_secs(15);
         let stream_timeout = Duration::from_secs(60);
@@ -981,11 +1091,11 @@ impl LlmClient {
                                     continue;
                                 }
 
-                                if line.starts_with("data: ") {
-                                    let data = &line[6..];
-
+                                if let Some(data) = stream_payload(&line) {
                                     if data == "[DONE]" {
-                                        if !reasoning_content.trim().is_empty() {
+                                        let tail = think_splitter.finish();
+                                        self.apply_split_deltas(&app, tail, &mut reasoning_content, &mut full_content, on_delta);
+                                        if self.config.reasoning_mode.streams() && !reasoning_content.trim().is_empty() {
                                             if let Some(app) = &app {
                                                 emit_agent_event(
                                                     app,
@@ -1011,56 +1121,53 @@ impl LlmClient {
                                             return Err(Self::to_api_error(error));
                                         }
 
-                                        if let Some(usage) = json.get("usage") {
-                                            prompt_tokens = usage["prompt_tokens"].as_u64().unwrap_or(prompt_tokens as u64) as usize;
-                                            completion_tokens = usage["completion_tokens"].as_u64().unwrap_or(completion_tokens as u64) as usize;
-                                            total_tokens = usage["total_tokens"].as_u64().unwrap_or(total_tokens as u64) as usize;
-                                        }
-
-                                        let delta = &json["choices"][0]["delta"];
-                                        if let Some(reason) = json["choices"][0]["finish_reason"].as_str() {
-                                            finish_reason = Some(reason.to_string());
-                                        }
-
-                                        if let Some(reasoning) = Self::extract_reasoning_content(delta) {
-                                            if let Some(app) = &app {
-                                                emit_agent_event(
-                                                    app,
-                                                    AgentEvent::ReasoningDelta {
-                                                        content: reasoning.clone(),
-                                                        agent: AgentType::Coordinator,
-                                                    },
-                                                );
-                                            }
-                                            reasoning_content.push_str(reasoning.as_str());
-                                        }
-
-                                        if let Some(tc_array) = delta.get("tool_calls").and_then(|v| v.as_array()) {
-                                            for tc in tc_array {
-                                                let idx = tc["index"].as_u64().unwrap_or(0) as usize;
-                                                while tool_calls.len() <= idx {
-                                                    tool_calls.push(StreamToolCall::default());
+                                        let deltas = self
+                                            .provider
+                                            .parse_stream_event(&json, &mut stream_state)
+                                            .into_iter()
+                                            .flat_map(|delta| match delta {
+                                                // 正文中的 <think> 标签拆为推理增量
+                                                ProviderDelta::Text(text) => think_splitter.push(&text),
+                                                other => vec![other],
+                                            })
+                                            .collect::<Vec<_>>();
+                                        for delta in deltas {
+                                            match delta {
+                                                ProviderDelta::Usage {
+                                                    prompt_tokens: prompt,
+                                                    completion_tokens: completion,
+                                                    total_tokens: total,
+                                                } => {
+                                                    prompt_tokens = prompt.unwrap_or(prompt_tokens);
+                                                    completion_tokens = completion.unwrap_or(completion_tokens);
+                                                    total_tokens = total.unwrap_or(total_tokens);
                                                 }
-
-                                                if let Some(id) = tc["id"].as_str() {
-                                                    tool_calls[idx].id = Some(id.to_string());
+                                                ProviderDelta::Finish(reason) => {
+                                                    finish_reason = Some(reason);
                                                 }
-                                                if let Some(name) = tc["function"]["name"].as_str() {
-                                                    tool_calls[idx].name.push_str(name);
+                                                ProviderDelta::Reasoning(reasoning) => {
+                                                    self.emit_reasoning_delta(&app, &reasoning);
+                                                    reasoning_content.push_str(reasoning.as_str());
                                                 }
-                                                if let Some(args) = tc["function"]["arguments"].as_str() {
-                                                    tool_calls[idx].args.push_str(args);
+                                                ProviderDelta::ToolCall { index, id, name, arguments } => {
+                                                    while tool_calls.len() <= index {
+                                                        tool_calls.push(StreamToolCall::default());
+                                                    }
+                                                    if id.is_some() {
+                                                        tool_calls[index].id = id;
+                                                    }
+                                                    tool_calls[index].name.push_str(&name);
+                                                    tool_calls[index].args.push_str(&arguments);
+                                                }
+                                                ProviderDelta::Text(content) => {
+                                                    if content.is_empty() {
+                                                        continue;
+                                                    }
+                                                    full_content.push_str(&content);
+                                                    on_delta(&content);
                                                 }
                                             }
                                         }
-
-                                        if let Some(content) = delta["content"].as_str() {
-                                            if content.is_empty() {
-                                                continue;
-                                            }
-                                            full_content.push_str(content);
-                                            on_delta(content);
-                                        }
                                     }
                                 }
                             }
@@ -1069,7 +1176,9 @@ impl LlmClient {
                             return Err(Self::to_reqwest_error("Stream error", e));
                         }
                         None => {
-                            if !reasoning_content.trim().is_empty() {
+                            let tail = think_splitter.finish();
+                            self.apply_split_deltas(&app, tail, &mut reasoning_content, &mut full_content, on_delta);
+                            if self.config.reasoning_mode.streams() && !reasoning_content.trim().is_empty() {
                                 if let Some(app) = &app {
                                     emit_agent_event(
                                         app,
@@ -1194,6 +1303,9 @@ impl LlmClient {
 
     fn to_http_error(status: StatusCode, body: &str, headers: &HeaderMap) -> StreamRequestError {
         let message = format!("HTTP {}: {}", status, body);
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            return StreamRequestError::rate_limited(message, Self::parse_retry_after_ms(headers));
+        }
         if Self::is_retryable_http_status(status) {
             return StreamRequestError::retryable(message, Self::parse_retry_after_ms(headers));
         }
@@ -1203,6 +1315,9 @@ impl LlmClient {
     fn to_reqwest_error(prefix: &str, error: reqwest::Error) -> StreamRequestError {
         let message = format!("{}: {}", prefix, error);
         if let Some(status) = error.status() {
+            if status == StatusCode::TOO_MANY_REQUESTS {
+                return StreamRequestError::rate_limited(message, None);
+            }
             if Self::is_retryable_http_status(status) {
                 return StreamRequestError::retryable(message, None);
             }
@@ -1218,10 +1333,13 @@ impl LlmClient {
     fn to_api_error(error: &Value) -> StreamRequestError {
         let message = format!("API error: {}", error);
         let normalized = error.to_string().to_lowercase();
-        let retryable = normalized.contains("too_many_requests")
+        let rate_limited = normalized.contains("too_many_requests")
             || normalized.contains("rate_limit")
-            || normalized.contains("overloaded")
-            || normalized.contains("resource_exhausted")
+            || normalized.contains("resource_exhausted");
+        if rate_limited {
+            return StreamRequestError::rate_limited(message, None);
+        }
+        let retryable = normalized.contains("overloaded")
             || normalized.contains("temporar")
             || normalized.contains("unavailable");
         if retryable {
@@ -1247,7 +1365,8 @@ impl LlmClient {
         };
 
         LlmResponse {
-            content: Self::attach_reasoning_block(Some(reasoning_content.as_str()), &full_content),
+            content: full_content,
+            reasoning: Self::join_reasoning(None, &reasoning_content),
             tool_calls: if parsed_calls.is_empty() {
                 None
             } else {
@@ -1257,6 +1376,7 @@ impl LlmClient {
             prompt_tokens,
             completion_tokens,
             total_tokens,
+            cached: false,
         }
     }
 
@@ -1280,32 +1400,73 @@ impl LlmClient {
             .collect()
     }
 
-    /// 统一提取 reasoning 字段（不同 provider 字段名不同）。
-    fn extract_reasoning_content(message: &Value) -> Option<String> {
-        message
-            .get("reasoning_content")
-            .and_then(|v| v.as_str())
-            .or_else(|| message.get("reasoning").and_then(|v| v.as_str()))
-            .map(|v| v.trim().to_string())
-            .filter(|v| !v.is_empty())
+    /// 合并 provider 推理字段与正文 `<think>` 标签中的推理内容
+    fn join_reasoning(field: Option<&str>, tagged: &str) -> Option<String> {
+        let parts: Vec<&str> = [field.unwrap_or_default(), tagged]
+            .into_iter()
+            .map(str::trim)
+            .filter(|part| !part.is_empty())
+            .collect();
+        if parts.is_empty() {
+            None
+        } else {
+            Some(parts.join("\n\n"))
+        }
     }
 
-    /// 把 reasoning 作为 <thinking> 块附加到正文前，供前端折叠展示。
-    fn attach_reasoning_block(reasoning: Option<&str>, content: &str) -> String {
-        let Some(reasoning) = reasoning else {
-            return content.to_string();
-        };
-        let trimmed_reasoning = reasoning.trim();
-        if trimmed_reasoning.is_empty() {
-            return content.to_string();
+    /// 按推理展示方式转发推理增量
+    fn emit_reasoning_delta(&self, app: &Option<AppHandle>, reasoning: &str) {
+        if !self.config.reasoning_mode.streams() {
+            return;
         }
-        if content.trim().is_empty() {
-            return format!("<thinking>\n{}\n</thinking>", trimmed_reasoning);
+        if let Some(app) = app {
+            emit_agent_event(
+                app,
+                AgentEvent::ReasoningDelta {
+                    content: reasoning.to_string(),
+                    agent: AgentType::Coordinator,
+                },
+            );
+        }
+    }
+
+    /// 流结束时取出拆分器中暂存的正文
+    fn think_splitter_tail(splitter: &mut ThinkTagSplitter) -> Option<String> {
+        let tail: String = splitter
+            .finish()
+            .into_iter()
+            .filter_map(|delta| match delta {
+                ProviderDelta::Text(text) => Some(text),
+                _ => None,
+            })
+            .collect();
+        (!tail.is_empty()).then_some(tail)
+    }
+
+    /// 处理 `<think>` 拆分器输出的正文 / 推理增量
+    fn apply_split_deltas<F>(
+        &self,
+        app: &Option<AppHandle>,
+        deltas: Vec<ProviderDelta>,
+        reasoning_content: &mut String,
+        full_content: &mut String,
+        on_delta: &mut F,
+    ) where
+        F: FnMut(&str) + Send,
+    {
+        for delta in deltas {
+            match delta {
+                ProviderDelta::Reasoning(reasoning) => {
+                    self.emit_reasoning_delta(app, &reasoning);
+                    reasoning_content.push_str(&reasoning);
+                }
+                ProviderDelta::Text(text) => {
+                    full_content.push_str(&text);
+                    on_delta(&text);
+                }
+                _ => {}
+            }
         }
-        format!(
-            "<thinking>\n{}\n</thinking>\n\n{}",
-            trimmed_reasoning, content
-        )
     }
 
     /// 流式调用（带心跳和超时检测）
@@ -1421,27 +1582,9 @@ impl LlmClient {
         tools: Option<&[Value]>,
         current_agent: AgentType,
     ) -> Result<String, String> {
-        let url = self.get_api_url();
+        let url = self.get_api_url(true);
         let headers = self.build_headers();
-
-        let resolved_model = self.resolved_model();
-        let chat_messages =
-            self.normalize_chat_messages(self.convert_messages(messages), &resolved_model);
-        let temperature = self.resolved_temperature();
-
-        let mut body = json!({
-            "model": resolved_model,
-            "messages": chat_messages,
-            "temperature": temperature,
-            "max_tokens": self.config.max_tokens,
-            "stream": true,
-        });
-        self.apply_thinking_controls(&mut body, &resolved_model);
-        self.apply_moonshot_k25_constraints(&mut body, &resolved_model);
-
-        if let Some(tools) = self.resolve_tools_payload(messages, tools) {
-            body["tools"] = tools;
-        }
+        let body = self.build_request_body(messages, tools, true);
 
         let mut req = self.client.post(&url);
         for (key, value) in headers {
@@ -1467,6 +1610,7 @@ impl LlmClient {
 
         // 用于累积 tool_calls
         let mut tool_calls: Vec<(String, String)> = Vec::new(); // (name, arguments)
+        let mut stream_state = ProviderStreamState::default();
 
         // 心跳和超时配置
         let heartbeat_interval = Duration::from_secs(15);
@@ -1493,56 +1637,44 @@ impl LlmClient {
                                     continue;
                                 }
 
-                                if line.starts_with("data: ") {
-                                    let data = &line[6..];
-
+                                if let Some(data) = stream_payload(&line) {
                                     if data == "[DONE]" {
                                         // 流正常结束
                                         return self.finalize_stream_result(full_content, tool_calls);
                                     }
 
                                     if let Ok(json) = serde_json::from_str::<Value>(data) {
-                                        let delta = &json["choices"][0]["delta"];
-
-                                        // 处理 tool_calls（Function Call 流式响应）
-                                        if let Some(tc_array) = delta.get("tool_calls").and_then(|v| v.as_array()) {
-                                            for tc in tc_array {
-                                                let idx = tc["index"].as_u64().unwrap_or(0) as usize;
-
-                                                // 确保 tool_calls 数组足够大
-                                                while tool_calls.len() <= idx {
-                                                    tool_calls.push((String::new(), String::new()));
+                                        for delta in self.provider.parse_stream_event(&json, &mut stream_state) {
+                                            match delta {
+                                                // 处理 tool_calls（Function Call 流式响应）
+                                                ProviderDelta::ToolCall { index, name, arguments, .. } => {
+                                                    // 确保 tool_calls 数组足够大
+                                                    while tool_calls.len() <= index {
+                                                        tool_calls.push((String::new(), String::new()));
+                                                    }
+                                                    tool_calls[index].0.push_str(&name);
+                                                    tool_calls[index].1.push_str(&arguments);
                                                 }
-
-                                                // 累积函数名
-                                                if let Some(name) = tc["function"]["name"].as_str() {
-                                                    tool_calls[idx].0.push_str(name);
+                                                // 处理普通文本内容
+                                                ProviderDelta::Text(content) => {
+                                                    // 跳过空内容
+                                                    if content.is_empty() {
+                                                        continue;
+                                                    }
+
+                                                    full_content.push_str(&content);
+
+                                                    // 发送事件到前端
+                                                    emit_agent_event(
+                                                        app,
+                                                        AgentEvent::MessageChunk {
+                                                            content,
+                                                            agent: current_agent.clone(),
+                                                        },
+                                                    );
                                                 }
-
-                                                // 累积参数
-                                                if let Some(args) = tc["function"]["arguments"].as_str() {
-                                                    tool_calls[idx].1.push_str(args);
-                                                }
-                                            }
-                                        }
-
-                                        // 处理普通文本内容
-                                        if let Some(content) = delta["content"].as_str() {
-                                            // 跳过空内容
-                                            if content.is_empty() {
-                                                continue;
+                                                _ => {}
                                             }
-
-                                            full_content.push_str(content);
-
-                                            // 发送事件到前端
-                                            emit_agent_event(
-                                                app,
-                                                AgentEvent::MessageChunk {
-                                                    content: content.to_string(),
-                                                    agent: current_agent.clone(),
-                                                },
-                                            );
                                         }
                                     }
                                 }
@@ -1645,6 +1777,22 @@ impl LlmClient {
         self.call(&messages, None).await
     }
 
+    /// 单轮结构化输出调用：支持的 provider 会按 JSON Schema 约束输出
+    pub async fn call_json_with_usage(
+        &self,
+        prompt: &str,
+        schema: &ResponseSchema,
+    ) -> Result<LlmResponse, String> {
+        let messages = vec![Message {
+            role: MessageRole::User,
+            content: prompt.to_string(),
+            name: None,
+            tool_call_id: None,
+        }];
+
+        self.call_with_schema(&messages, None, Some(schema)).await
+    }
+
     /// 简单的流式调用（只传入 prompt，通过 channel 返回）
     ///
     /// 包含超时处理和错误日志
@@ -1652,7 +1800,7 @@ impl LlmClient {
         &self,
         prompt: &str,
     ) -> Result<tokio::sync::mpsc::Receiver<String>, String> {
-        let url = self.get_api_url();
+        let url = self.get_api_url(true);
         let headers = self.build_headers();
 
         // 限制 prompt 长度，避免超过模型限制
@@ -1664,19 +1812,25 @@ impl LlmClient {
             prompt_chars.chars().count()
         );
 
-        let resolved_model = self.resolved_model();
-        let mut body = json!({
-            "model": resolved_model,
-            "messages": [{
-                "role": "user",
-                "content": prompt_chars
-            }],
-            "temperature": self.resolved_temperature(),
-            "max_tokens": self.config.max_tokens,
-            "stream": true,
-        });
-        self.apply_thinking_controls(&mut body, &resolved_model);
-        self.apply_moonshot_k25_constraints(&mut body, &resolved_model);
+        let messages = vec![Message {
+            role: MessageRole::User,
+            content: prompt_chars,
+            name: None,
+            tool_call_id: None,
+        }];
+
+        // 命中缓存时直接把完整文本写入 channel
+        let cache_mode = self.config.cache;
+        let cache_key = self.response_cache_key("text", &messages, None);
+        if let Some(text) = llm_cache::lookup(cache_mode, cache_key.as_deref())
+            .and_then(|payload| payload.as_str().map(|s| s.to_string()))
+        {
+            let (tx, rx) = tokio::sync::mpsc::channel::<String>(1);
+            let _ = tx.send(text).await;
+            return Ok(rx);
+        }
+
+        let body = self.build_request_body(&messages, None, true);
 
         let mut req = self.client.post(&url);
         for (key, value) in headers {
@@ -1707,8 +1861,15 @@ impl LlmClient {
 
         // 在后台任务中处理流
         let mut stream = response.bytes_stream();
+        let provider = provider_for(&self.config.provider, self.config.base_url.as_deref());
         tokio::spawn(async move {
             let mut buffer = String::new();
+            let mut stream_state = ProviderStreamState::default();
+            // 只输出最终回答，丢弃 <think> 推理内容
+            let mut think_splitter = ThinkTagSplitter::default();
+            // 拼装完整文本，收到结束标记后才写入缓存；连接中途断开的响应不缓存
+            let mut assembled = String::new();
+            let mut finished = false;
             #[cfg(debug_assertions)]
             let mut chunk_count = 0usize;
             #[cfg(debug_assertions)]
@@ -1740,6 +1901,13 @@ impl LlmClient {
                             total_chars,
                             start_time.elapsed()
                         );
+                        if let Some(tail) = Self::think_splitter_tail(&mut think_splitter) {
+                            assembled.push_str(&tail);
+                            let _ = tx.send(tail).await;
+                        }
+                        if finished {
+                            llm_cache::store(cache_mode, cache_key.as_deref(), assembled.into());
+                        }
                         break;
                     }
                     Err(_) => {
@@ -1762,12 +1930,15 @@ impl LlmClient {
                         continue;
                     }
 
-                    if line.starts_with("data: ") {
-                        let data = &line[6..];
-
+                    if let Some(data) = stream_payload(&line) {
                         if data == "[DONE]" {
                             #[cfg(debug_assertions)]
                             println!("[LLM] 收到 [DONE] 信号");
+                            if let Some(tail) = Self::think_splitter_tail(&mut think_splitter) {
+                                assembled.push_str(&tail);
+                                let _ = tx.send(tail).await;
+                            }
+                            llm_cache::store(cache_mode, cache_key.as_deref(), assembled.into());
                             return; // 使用 return 而不是 break，确保退出整个 spawn
                         }
 
@@ -1781,13 +1952,32 @@ impl LlmClient {
                                 return;
                             }
 
-                            if let Some(content) = json["choices"][0]["delta"]["content"].as_str() {
+                            let texts = provider
+                                .parse_stream_event(&json, &mut stream_state)
+                                .into_iter()
+                                .flat_map(|delta| match delta {
+                                    ProviderDelta::Text(content) => think_splitter.push(&content),
+                                    ProviderDelta::Finish(_) => {
+                                        finished = true;
+                                        Vec::new()
+                                    }
+                                    _ => Vec::new(),
+                                })
+                                .filter_map(|delta| match delta {
+                                    ProviderDelta::Text(content) => Some(content),
+                                    _ => None,
+                                })
+                                .collect::<Vec<_>>();
+                            for content in texts {
                                 #[cfg(debug_assertions)]
                                 {
                                     chunk_count += 1;
                                     total_chars += content.chars().count();
                                 }
-                                if tx.send(content.to_string()).await.is_err() {
+                                if cache_mode.writes() {
+                                    assembled.push_str(&content);
+                                }
+                                if tx.send(content).await.is_err() {
                                     // 接收端已关闭
                                     #[cfg(debug_assertions)]
                                     println!("[LLM] 接收端已关闭，停止发送");
@@ -1858,6 +2048,24 @@ mod tests {
         assert!(!fatal.retryable);
     }
 
+    #[test]
+    fn rate_limit_errors_carry_retry_after() {
+        let mut headers = HeaderMap::new();
+        headers.insert("retry-after", HeaderValue::from_static("7"));
+        let limited =
+            LlmClient::to_http_error(StatusCode::TOO_MANY_REQUESTS, "slow down", &headers);
+        assert!(limited.retryable && limited.rate_limited);
+        assert_eq!(limited.retry_after_ms, Some(7000));
+        assert_eq!(LlmClient::retry_delay_ms(1, limited.retry_after_ms), 7000);
+
+        let overloaded =
+            LlmClient::to_http_error(StatusCode::SERVICE_UNAVAILABLE, "busy", &HeaderMap::new());
+        assert!(overloaded.retryable && !overloaded.rate_limited);
+
+        let api_error = LlmClient::to_api_error(&json!({ "type": "rate_limit_error" }));
+        assert!(api_error.rate_limited);
+    }
+
     #[test]
     fn retry_reason_truncates_long_error() {
         let long_message = "x".repeat(300);
@@ -0,0 +1,1384 @@
+//! LLM Provider 抽象
+//!
+//! 以 OpenAI Chat Completions 的消息/工具结构作为统一中间格式：
+//! - 请求：各 provider 把统一格式转换为原生的 URL、请求头与请求体
+//! - 响应：把原生响应与流式事件归一化为 `ProviderResponse` / `ProviderDelta`
+//! - 工具调用：各 provider 映射自己的原生 function calling 格式
+//!
+//! `native_tool_calls` 决定 Auto 模式下是否使用原生 FC，否则回退到 XML 工具协议。
+
+use crate::agent::types::ToolCall;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+
+/// 统一请求（消息与工具定义均为 OpenAI 格式）
+pub struct ProviderRequest<'a> {
+    pub model: &'a str,
+    pub messages: Vec<Value>,
+    /// 为 None 时不发送，使用 provider 的默认值
+    pub temperature: Option<f32>,
+    pub max_tokens: usize,
+    pub stream: bool,
+    pub tools: Option<Value>,
+}
+
+/// 结构化输出要求：模型需按 JSON Schema 返回
+#[derive(Debug, Clone)]
+pub struct ResponseSchema {
+    /// schema 名称（OpenAI 要求 `^[a-zA-Z0-9_-]+$`）
+    pub name: String,
+    pub schema: Value,
+}
+
+/// 归一化后的非流式响应
+#[derive(Debug, Default, Serialize)]
+pub struct ProviderResponse {
+    pub content: String,
+    pub reasoning: Option<String>,
+    pub tool_calls: Vec<ToolCall>,
+    /// 统一为 OpenAI 取值：stop / length / tool_calls
+    pub finish_reason: Option<String>,
+    pub prompt_tokens: usize,
+    pub completion_tokens: usize,
+    pub total_tokens: usize,
+}
+
+/// 归一化后的流式增量
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+pub enum ProviderDelta {
+    Text(String),
+    Reasoning(String),
+    /// 工具调用片段：同一 index 的 name / arguments 需要累积拼接
+    ToolCall {
+        index: usize,
+        id: Option<String>,
+        name: String,
+        arguments: String,
+    },
+    Finish(String),
+    Usage {
+        prompt_tokens: Option<usize>,
+        completion_tokens: Option<usize>,
+        total_tokens: Option<usize>,
+    },
+}
+
+/// 流式解析状态（部分 provider 需要跨事件计数）
+#[derive(Debug, Default)]
+pub struct ProviderStreamState {
+    tool_calls_seen: usize,
+}
+
+impl ProviderStreamState {
+    fn next_tool_index(&mut self) -> usize {
+        let index = self.tool_calls_seen;
+        self.tool_calls_seen += 1;
+        index
+    }
+}
+
+/// LLM Provider：负责原生协议与统一格式之间的转换
+pub trait LlmProvider: Send + Sync {
+    /// 请求地址
+    fn endpoint(&self, base_url: &str, model: &str, stream: bool) -> String;
+    /// 请求头
+    fn headers(&self, api_key: &str) -> HashMap<String, String>;
+    /// 构建原生请求体
+    fn build_body(&self, request: &ProviderRequest) -> Value;
+    /// 解析非流式响应
+    fn parse_response(&self, json: &Value) -> ProviderResponse;
+    /// 解析单个流式事件（SSE data 或 NDJSON 行）
+    fn parse_stream_event(
+        &self,
+        event: &Value,
+        state: &mut ProviderStreamState,
+    ) -> Vec<ProviderDelta>;
+    /// Auto 模式下是否使用原生 Function Calling
+    fn native_tool_calls(&self) -> bool {
+        true
+    }
+    /// 在请求体中声明结构化输出；协议不支持时返回 false，由调用方自行校验与修复
+    fn apply_response_schema(&self, _body: &mut Value, _schema: &ResponseSchema) -> bool {
+        false
+    }
+}
+
+/// 根据 provider 名称选择协议实现
+pub fn provider_for(provider: &str, base_url: Option<&str>) -> Box<dyn LlmProvider> {
+    let base_url = base_url.unwrap_or_default().trim_end_matches('/');
+    match provider {
+        "anthropic" => Box::new(AnthropicProvider),
+        // 配置了 Gemini 的 OpenAI 兼容端点时沿用 Chat Completions 协议
+        "gemini" if !base_url.ends_with("/openai") => Box::new(GeminiProvider),
+        "ollama" => Box::new(OllamaProvider),
+        _ => Box::new(OpenAiProvider {
+            native_tools: matches!(
+                provider,
+                "openai"
+                    | "deepseek"
+                    | "moonshot"
+                    | "zai"
+                    | "gemini"
+                    | "groq"
+                    | "openrouter"
+                    | "custom"
+            ),
+            // 兼容端点多数只支持 json_object，json_schema 仅对确认支持的 provider 启用
+            json_schema: matches!(provider, "openai" | "gemini"),
+        }),
+    }
+}
+
+/// 从一行流式数据中取出 JSON 负载（兼容 SSE 与 NDJSON）
+pub fn stream_payload(line: &str) -> Option<&str> {
+    let line = line.trim();
+    if let Some(data) = line.strip_prefix("data:") {
+        return Some(data.trim_start());
+    }
+    if line.starts_with('{') {
+        return Some(line);
+    }
+    None
+}
+
+/// 工具参数：兼容 JSON 字符串与对象两种形式
+fn arguments_to_params(arguments: &Value) -> HashMap<String, Value> {
+    match arguments {
+        Value::String(raw) => serde_json::from_str(raw).unwrap_or_default(),
+        Value::Object(map) => map.clone().into_iter().collect(),
+        _ => HashMap::new(),
+    }
+}
+
+fn arguments_to_object(arguments: &Value) -> Value {
+    Value::Object(arguments_to_params(arguments).into_iter().collect())
+}
+
+/// 消息正文片段（OpenAI 多模态数组中的文本与 data URL 图片）
+enum ContentPart {
+    Text(String),
+    Image { media_type: String, data: String },
+}
+
+fn content_parts(content: &Value) -> Vec<ContentPart> {
+    match content {
+        Value::String(text) => vec![ContentPart::Text(text.clone())],
+        Value::Array(items) => items
+            .iter()
+            .filter_map(|item| match item["type"].as_str() {
+                Some("text") => Some(ContentPart::Text(
+                    item["text"].as_str().unwrap_or("").to_string(),
+                )),
+                Some("image_url") => {
+                    let url = item["image_url"]["url"].as_str()?;
+                    let (header, data) = url.strip_prefix("data:")?.split_once(";base64,")?;
+                    Some(ContentPart::Image {
+                        media_type: header.to_string(),
+                        data: data.to_string(),
+                    })
+                }
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// 消息的纯文本内容（多模态消息只取文本片段）
+fn content_text(content: &Value) -> String {
+    content_parts(content)
+        .into_iter()
+        .filter_map(|part| match part {
+            ContentPart::Text(text) => Some(text),
+            ContentPart::Image { .. } => None,
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn usage_value(value: &Value) -> Option<usize> {
+    value.as_u64().map(|v| v as usize)
+}
+
+/// 统一提取 reasoning 字段（不同 provider 字段名不同）
+fn extract_reasoning(message: &Value) -> Option<String> {
+    message
+        .get("reasoning_content")
+        .and_then(|v| v.as_str())
+        .or_else(|| message.get("reasoning").and_then(|v| v.as_str()))
+        .or_else(|| message.get("thinking").and_then(|v| v.as_str()))
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+/// 遍历 OpenAI 格式的工具定义，取出 (name, description, parameters)
+fn function_definitions(tools: &Value) -> Vec<(&str, &str, &Value)> {
+    tools
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|tool| {
+            let function = tool.get("function")?;
+            let name = function.get("name")?.as_str()?;
+            let description = function
+                .get("description")
+                .and_then(|v| v.as_str())
+                .unwrap_or_default();
+            Some((name, description, &function["parameters"]))
+        })
+        .collect()
+}
+
+/// 合并相邻同角色消息（Anthropic / Gemini 要求角色交替）
+fn push_merged(messages: &mut Vec<Value>, role: &str, key: &str, mut parts: Vec<Value>) {
+    if parts.is_empty() {
+        return;
+    }
+    if let Some(last) = messages.last_mut() {
+        if last["role"] == role {
+            if let Some(existing) = last[key].as_array_mut() {
+                existing.append(&mut parts);
+                return;
+            }
+        }
+    }
+    messages.push(json!({ "role": role, key: parts }));
+}
+
+// ============ OpenAI 兼容 ============
+
+/// OpenAI Chat Completions 协议（OpenAI、DeepSeek、Moonshot、Groq 等）
+pub struct OpenAiProvider {
+    native_tools: bool,
+    json_schema: bool,
+}
+
+impl LlmProvider for OpenAiProvider {
+    fn endpoint(&self, base_url: &str, _model: &str, _stream: bool) -> String {
+        format!("{}/chat/completions", base_url.trim_end_matches('/'))
+    }
+
+    fn headers(&self, api_key: &str) -> HashMap<String, String> {
+        let mut headers = HashMap::new();
+        headers.insert("Content-Type".to_string(), "application/json".to_string());
+        // 本地兼容端点可能不需要密钥
+        if !api_key.trim().is_empty() {
+            headers.insert("Authorization".to_string(), format!("Bearer {}", api_key));
+        }
+        headers
+    }
+
+    fn build_body(&self, request: &ProviderRequest) -> Value {
+        let mut body = json!({
+            "model": request.model,
+            "messages": request.messages,
+            "max_tokens": request.max_tokens,
+            "stream": request.stream,
+        });
+        if let Some(temperature) = request.temperature {
+            body["temperature"] = json!(temperature);
+        }
+        if let Some(tools) = &request.tools {
+            body["tools"] = tools.clone();
+        }
+        body
+    }
+
+    fn parse_response(&self, json: &Value) -> ProviderResponse {
+        let usage = &json["usage"];
+        let prompt_tokens = usage_value(&usage["prompt_tokens"]).unwrap_or(0);
+        let completion_tokens = usage_value(&usage["completion_tokens"]).unwrap_or(0);
+        let message = &json["choices"][0]["message"];
+
+        let tool_calls = message["tool_calls"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .enumerate()
+            .map(|(idx, tc)| ToolCall {
+                id: tc["id"]
+                    .as_str()
+                    .map(|s| s.to_string())
+                    .unwrap_or_else(|| format!("call_{}", idx)),
+                name: tc["function"]["name"].as_str().unwrap_or("").to_string(),
+                params: arguments_to_params(&tc["function"]["arguments"]),
+            })
+            .collect();
+
+        ProviderResponse {
+            content: message["content"].as_str().unwrap_or("").to_string(),
+            reasoning: extract_reasoning(message),
+            tool_calls,
+            finish_reason: json["choices"][0]["finish_reason"]
+                .as_str()
+                .map(|s| s.to_string()),
+            prompt_tokens,
+            completion_tokens,
+            total_tokens: usage_value(&usage["total_tokens"])
+                .unwrap_or(prompt_tokens + completion_tokens),
+        }
+    }
+
+    fn parse_stream_event(
+        &self,
+        event: &Value,
+        _state: &mut ProviderStreamState,
+    ) -> Vec<ProviderDelta> {
+        let mut deltas = Vec::new();
+        if let Some(usage) = event.get("usage").filter(|u| u.is_object()) {
+            deltas.push(ProviderDelta::Usage {
+                prompt_tokens: usage_value(&usage["prompt_tokens"]),
+                completion_tokens: usage_value(&usage["completion_tokens"]),
+                total_tokens: usage_value(&usage["total_tokens"]),
+            });
+        }
+
+        let choice = &event["choices"][0];
+        let delta = &choice["delta"];
+        if let Some(reasoning) = extract_reasoning(delta) {
+            deltas.push(ProviderDelta::Reasoning(reasoning));
+        }
+        for tc in delta["tool_calls"].as_array().into_iter().flatten() {
+            deltas.push(ProviderDelta::ToolCall {
+                index: tc["index"].as_u64().unwrap_or(0) as usize,
+                id: tc["id"].as_str().map(|s| s.to_string()),
+                name: tc["function"]["name"].as_str().unwrap_or("").to_string(),
+                arguments: tc["function"]["arguments"]
+                    .as_str()
+                    .unwrap_or("")
+                    .to_string(),
+            });
+        }
+        if let Some(content) = delta["content"].as_str().filter(|c| !c.is_empty()) {
+            deltas.push(ProviderDelta::Text(content.to_string()));
+        }
+        if let Some(reason) = choice["finish_reason"].as_str() {
+            deltas.push(ProviderDelta::Finish(reason.to_string()));
+        }
+        deltas
+    }
+
+    fn native_tool_calls(&self) -> bool {
+        self.native_tools
+    }
+
+    fn apply_response_schema(&self, body: &mut Value, schema: &ResponseSchema) -> bool {
+        if !self.json_schema {
+            return false;
+        }
+        body["response_format"] = json!({
+            "type": "json_schema",
+            "json_schema": {
+                "name": schema.name,
+                "schema": schema.schema,
+                "strict": true,
+            }
+        });
+        true
+    }
+}
+
+// ============ Anthropic ============
+
+/// Anthropic Messages 协议（tool_use / tool_result 内容块）
+pub struct AnthropicProvider;
+
+impl AnthropicProvider {
+    fn finish_reason(reason: &str) -> String {
+        match reason {
+            "tool_use" => "tool_calls",
+            "max_tokens" => "length",
+            "end_turn" | "stop_sequence" => "stop",
+            other => other,
+        }
+        .to_string()
+    }
+}
+
+impl LlmProvider for AnthropicProvider {
+    fn endpoint(&self, base_url: &str, _model: &str, _stream: bool) -> String {
+        // 兼容不带版本路径的 base_url（如 https://api.anthropic.com）
+        let base_url = base_url.trim_end_matches('/');
+        if base_url.ends_with("/v1") {
+            format!("{}/messages", base_url)
+        } else {
+            format!("{}/v1/messages", base_url)
+        }
+    }
+
+    fn headers(&self, api_key: &str) -> HashMap<String, String> {
+        let mut headers = HashMap::new();
+        headers.insert("Content-Type".to_string(), "application/json".to_string());
+        headers.insert("x-api-key".to_string(), api_key.to_string());
+        headers.insert("anthropic-version".to_string(), "2023-06-01".to_string());
+        headers
+    }
+
+    fn build_body(&self, request: &ProviderRequest) -> Value {
+        let mut system = Vec::new();
+        let mut messages = Vec::new();
+
+        for msg in &request.messages {
+            let text = content_text(&msg["content"]);
+            let text_block =
+                (!text.trim().is_empty()).then(|| json!({ "type": "text", "text": text }));
+            match msg["role"].as_str().unwrap_or("user") {
+                "system" => {
+                    if !text.trim().is_empty() {
+                        system.push(text);
+                    }
+                }
+                "assistant" => {
+                    let mut blocks: Vec<Value> = text_block.into_iter().collect();
+                    for call in msg["tool_calls"].as_array().into_iter().flatten() {
+                        blocks.push(json!({
+                            "type": "tool_use",
+                            "id": call["id"],
+                            "name": call["function"]["name"],
+                            "input": arguments_to_object(&call["function"]["arguments"]),
+                        }));
+                    }
+                    push_merged(&mut messages, "assistant", "content", blocks);
+                }
+                "tool" => {
+                    let block = json!({
+                        "type": "tool_result",
+                        "tool_use_id": msg["tool_call_id"],
+                        "content": text,
+                    });
+                    push_merged(&mut messages, "user", "content", vec![block]);
+                }
+                _ => {
+                    let blocks = content_parts(&msg["content"])
+                        .into_iter()
+                        .filter_map(|part| match part {
+                            ContentPart::Text(text) => (!text.trim().is_empty())
+                                .then(|| json!({ "type": "text", "text": text })),
+                            ContentPart::Image { media_type, data } => Some(json!({
+                                "type": "image",
+                                "source": {
+                                    "type": "base64",
+                                    "media_type": media_type,
+                                    "data": data,
+                                }
+                            })),
+                        })
+                        .collect();
+                    push_merged(&mut messages, "user", "content", blocks);
+                }
+            }
+        }
+
+        let mut body = json!({
+            "model": request.model,
+            "messages": messages,
+            "max_tokens": request.max_tokens,
+            "stream": request.stream,
+        });
+        if let Some(temperature) = request.temperature {
+            body["temperature"] = json!(temperature.clamp(0.0, 1.0));
+        }
+        if !system.is_empty() {
+            body["system"] = json!(system.join("\n\n"));
+        }
+        if let Some(tools) = &request.tools {
+            let tools = function_definitions(tools)
+                .into_iter()
+                .map(|(name, description, parameters)| {
+                    json!({
+                        "name": name,
+                        "description": description,
+                        "input_schema": parameters,
+                    })
+                })
+                .collect::<Vec<_>>();
+            if !tools.is_empty() {
+                body["tools"] = json!(tools);
+            }
+        }
+        body
+    }
+
+    fn parse_response(&self, json: &Value) -> ProviderResponse {
+        let mut response = ProviderResponse::default();
+        let mut reasoning = String::new();
+        for block in json["content"].as_array().into_iter().flatten() {
+            match block["type"].as_str() {
+                Some("text") => response
+                    .content
+                    .push_str(block["text"].as_str().unwrap_or("")),
+                Some("thinking") => reasoning.push_str(block["thinking"].as_str().unwrap_or("")),
+                Some("tool_use") => response.tool_calls.push(ToolCall {
+                    id: block["id"].as_str().unwrap_or("").to_string(),
+                    name: block["name"].as_str().unwrap_or("").to_string(),
+                    params: arguments_to_params(&block["input"]),
+                }),
+                _ => {}
+            }
+        }
+        response.reasoning = Some(reasoning.trim().to_string()).filter(|r| !r.is_empty());
+        response.finish_reason = json["stop_reason"].as_str().map(Self::finish_reason);
+        response.prompt_tokens = usage_value(&json["usage"]["input_tokens"]).unwrap_or(0);
+        response.completion_tokens = usage_value(&json["usage"]["output_tokens"]).unwrap_or(0);
+        response.total_tokens = response.prompt_tokens + response.completion_tokens;
+        response
+    }
+
+    fn parse_stream_event(
+        &self,
+        event: &Value,
+        _state: &mut ProviderStreamState,
+    ) -> Vec<ProviderDelta> {
+        let index = event["index"].as_u64().unwrap_or(0) as usize;
+        match event["type"].as_str() {
+            Some("message_start") => vec![ProviderDelta::Usage {
+                prompt_tokens: usage_value(&event["message"]["usage"]["input_tokens"]),
+                completion_tokens: None,
+                total_tokens: None,
+            }],
+            Some("content_block_start") => {
+                let block = &event["content_block"];
+                match block["type"].as_str() {
+                    Some("tool_use") => vec![ProviderDelta::ToolCall {
+                        index,
+                        id: block["id"].as_str().map(|s| s.to_string()),
+                        name: block["name"].as_str().unwrap_or("").to_string(),
+                        arguments: String::new(),
+                    }],
+                    Some("text") => block["text"]
+                        .as_str()
+                        .filter(|t| !t.is_empty())
+                        .map(|t| vec![ProviderDelta::Text(t.to_string())])
+                        .unwrap_or_default(),
+                    _ => Vec::new(),
+                }
+            }
+            Some("content_block_delta") => {
+                let delta = &event["delta"];
+                match delta["type"].as_str() {
+                    Some("text_delta") => vec![ProviderDelta::Text(
+                        delta["text"].as_str().unwrap_or("").to_string(),
+                    )],
+                    Some("thinking_delta") => vec![ProviderDelta::Reasoning(
+                        delta["thinking"].as_str().unwrap_or("").to_string(),
+                    )],
+                    Some("input_json_delta") => vec![ProviderDelta::ToolCall {
+                        index,
+                        id: None,
+                        name: String::new(),
+                        arguments: delta["partial_json"].as_str().unwrap_or("").to_string(),
+                    }],
+                    _ => Vec::new(),
+                }
+            }
+            Some("message_delta") => {
+                let mut deltas = Vec::new();
+                if let Some(reason) = event["delta"]["stop_reason"].as_str() {
+                    deltas.push(ProviderDelta::Finish(Self::finish_reason(reason)));
+                }
+                if let Some(output) = usage_value(&event["usage"]["output_tokens"]) {
+                    deltas.push(ProviderDelta::Usage {
+                        prompt_tokens: None,
+                        completion_tokens: Some(output),
+                        total_tokens: None,
+                    });
+                }
+                deltas
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
+// ============ Gemini ============
+
+/// Gemini generateContent 协议（functionCall / functionResponse parts）
+pub struct GeminiProvider;
+
+impl GeminiProvider {
+    /// Gemini 的 Schema 不接受部分 JSON Schema 关键字
+    fn sanitize_schema(schema: &Value) -> Value {
+        match schema {
+            Value::Object(map) => Value::Object(
+                map.iter()
+                    .filter(|(key, _)| {
+                        key.as_str() != "additionalProperties" && key.as_str() != "$schema"
+                    })
+                    .map(|(key, value)| (key.clone(), Self::sanitize_schema(value)))
+                    .collect::<Map<String, Value>>(),
+            ),
+            Value::Array(items) => Value::Array(items.iter().map(Self::sanitize_schema).collect()),
+            other => other.clone(),
+        }
+    }
+
+    fn finish_reason(reason: &str, has_tool_calls: bool) -> String {
+        if has_tool_calls {
+            return "tool_calls".to_string();
+        }
+        match reason {
+            "STOP" => "stop".to_string(),
+            "MAX_TOKENS" => "length".to_string(),
+            other => other.to_ascii_lowercase(),
+        }
+    }
+
+    fn usage_delta(event: &Value) -> Option<ProviderDelta> {
+        let usage = event.get("usageMetadata")?;
+        Some(ProviderDelta::Usage {
+            prompt_tokens: usage_value(&usage["promptTokenCount"]),
+            completion_tokens: usage_value(&usage["candidatesTokenCount"]),
+            total_tokens: usage_value(&usage["totalTokenCount"]),
+        })
+    }
+}
+
+impl LlmProvider for GeminiProvider {
+    fn endpoint(&self, base_url: &str, model: &str, stream: bool) -> String {
+        let model = model.trim_start_matches("models/");
+        let base_url = base_url.trim_end_matches('/');
+        if stream {
+            format!(
+                "{}/models/{}:streamGenerateContent?alt=sse",
+                base_url, model
+            )
+        } else {
+            format!("{}/models/{}:generateContent", base_url, model)
+        }
+    }
+
+    fn headers(&self, api_key: &str) -> HashMap<String, String> {
+        let mut headers = HashMap::new();
+        headers.insert("Content-Type".to_string(), "application/json".to_string());
+        headers.insert("x-goog-api-key".to_string(), api_key.to_string());
+        headers
+    }
+
+    fn build_body(&self, request: &ProviderRequest) -> Value {
+        let mut system = Vec::new();
+        let mut contents = Vec::new();
+        // functionResponse 需要函数名，按 tool_call_id 反查
+        let mut call_names: HashMap<String, String> = HashMap::new();
+
+        for msg in &request.messages {
+            let text = content_text(&msg["content"]);
+            let text_part = (!text.trim().is_empty()).then(|| json!({ "text": text }));
+            match msg["role"].as_str().unwrap_or("user") {
+                "system" => {
+                    if !text.trim().is_empty() {
+                        system.push(json!({ "text": text }));
+                    }
+                }
+                "assistant" => {
+                    let mut parts: Vec<Value> = text_part.into_iter().collect();
+                    for call in msg["tool_calls"].as_array().into_iter().flatten() {
+                        let name = call["function"]["name"].as_str().unwrap_or("");
+                        if let Some(id) = call["id"].as_str() {
+                            call_names.insert(id.to_string(), name.to_string());
+                        }
+                        parts.push(json!({
+                            "functionCall": {
+                                "name": name,
+                                "args": arguments_to_object(&call["function"]["arguments"]),
+                            }
+                        }));
+                    }
+                    push_merged(&mut contents, "model", "parts", parts);
+                }
+                "tool" => {
+                    let name = msg["tool_call_id"]
+                        .as_str()
+                        .and_then(|id| call_names.get(id))
+                        .cloned()
+                        .unwrap_or_default();
+                    let part = json!({
+                        "functionResponse": {
+                            "name": name,
+                            "response": { "content": text },
+                        }
+                    });
+                    push_merged(&mut contents, "user", "parts", vec![part]);
+                }
+                _ => {
+                    let parts = content_parts(&msg["content"])
+                        .into_iter()
+                        .filter_map(|part| match part {
+                            ContentPart::Text(text) => {
+                                (!text.trim().is_empty()).then(|| json!({ "text": text }))
+                            }
+                            ContentPart::Image { media_type, data } => Some(json!({
+                                "inlineData": { "mimeType": media_type, "data": data }
+                            })),
+                        })
+                        .collect();
+                    push_merged(&mut contents, "user", "parts", parts);
+                }
+            }
+        }
+        // 对话必须以 user 开头
+        if contents.first().is_some_and(|c| c["role"] == "model") {
+            contents.insert(
+                0,
+                json!({ "role": "user", "parts": [{ "text": "Continue." }] }),
+            );
+        }
+
+        let mut body = json!({
+            "contents": contents,
+            "generationConfig": { "maxOutputTokens": request.max_tokens },
+        });
+        if let Some(temperature) = request.temperature {
+            body["generationConfig"]["temperature"] = json!(temperature);
+        }
+        if !system.is_empty() {
+            body["systemInstruction"] = json!({ "parts": system });
+        }
+        if let Some(tools) = &request.tools {
+            let declarations = function_definitions(tools)
+                .into_iter()
+                .map(|(name, description, parameters)| {
+                    let mut declaration = json!({ "name": name, "description": description });
+                    // 空参数对象会被拒绝，直接省略 parameters
+                    let has_properties = parameters["properties"]
+                        .as_object()
+                        .is_some_and(|p| !p.is_empty());
+                    if has_properties {
+                        declaration["parameters"] = Self::sanitize_schema(parameters);
+                    }
+                    declaration
+                })
+                .collect::<Vec<_>>();
+            if !declarations.is_empty() {
+                body["tools"] = json!([{ "functionDeclarations": declarations }]);
+            }
+        }
+        body
+    }
+
+    fn parse_response(&self, json: &Value) -> ProviderResponse {
+        let mut response = ProviderResponse::default();
+        let mut reasoning = String::new();
+        let candidate = &json["candidates"][0];
+        for part in candidate["content"]["parts"]
+            .as_array()
+            .into_iter()
+            .flatten()
+        {
+            if let Some(call) = part.get("functionCall") {
+                response.tool_calls.push(ToolCall {
+                    id: format!("call_{}", response.tool_calls.len()),
+                    name: call["name"].as_str().unwrap_or("").to_string(),
+                    params: arguments_to_params(&call["args"]),
+                });
+            } else if let Some(text) = part["text"].as_str() {
+                if part["thought"].as_bool() == Some(true) {
+                    reasoning.push_str(text);
+                } else {
+                    response.content.push_str(text);
+                }
+            }
+        }
+        response.reasoning = Some(reasoning.trim().to_string()).filter(|r| !r.is_empty());
+        response.finish_reason = candidate["finishReason"]
+            .as_str()
+            .map(|reason| Self::finish_reason(reason, !response.tool_calls.is_empty()));
+        let usage = &json["usageMetadata"];
+        response.prompt_tokens = usage_value(&usage["promptTokenCount"]).unwrap_or(0);
+        response.completion_tokens = usage_value(&usage["candidatesTokenCount"]).unwrap_or(0);
+        response.total_tokens = usage_value(&usage["totalTokenCount"])
+            .unwrap_or(response.prompt_tokens + response.completion_tokens);
+        response
+    }
+
+    fn parse_stream_event(
+        &self,
+        event: &Value,
+        state: &mut ProviderStreamState,
+    ) -> Vec<ProviderDelta> {
+        let mut deltas = Vec::new();
+        let candidate = &event["candidates"][0];
+        for part in candidate["content"]["parts"]
+            .as_array()
+            .into_iter()
+            .flatten()
+        {
+            if let Some(call) = part.get("functionCall") {
+                // Gemini 每个事件都携带完整的函数调用
+                deltas.push(ProviderDelta::ToolCall {
+                    index: state.next_tool_index(),
+                    id: None,
+                    name: call["name"].as_str().unwrap_or("").to_string(),
+                    arguments: arguments_to_object(&call["args"]).to_string(),
+                });
+            } else if let Some(text) = part["text"].as_str().filter(|t| !t.is_empty()) {
+                if part["thought"].as_bool() == Some(true) {
+                    deltas.push(ProviderDelta::Reasoning(text.to_string()));
+                } else {
+                    deltas.push(ProviderDelta::Text(text.to_string()));
+                }
+            }
+        }
+        if let Some(reason) = candidate["finishReason"].as_str() {
+            deltas.push(ProviderDelta::Finish(Self::finish_reason(
+                reason,
+                state.tool_calls_seen > 0,
+            )));
+        }
+        deltas.extend(Self::usage_delta(event));
+        deltas
+    }
+
+    fn apply_response_schema(&self, body: &mut Value, schema: &ResponseSchema) -> bool {
+        body["generationConfig"]["responseMimeType"] = json!("application/json");
+        body["generationConfig"]["responseSchema"] = Self::sanitize_schema(&schema.schema);
+        true
+    }
+}
+
+// ============ Ollama ============
+
+/// Ollama 原生 /api/chat 协议（NDJSON 流，参数为 JSON 对象）
+pub struct OllamaProvider;
+
+impl LlmProvider for OllamaProvider {
+    fn endpoint(&self, base_url: &str, _model: &str, _stream: bool) -> String {
+        // 兼容配置为 OpenAI 兼容端点（.../v1）的 base_url
+        let base_url = base_url.trim_end_matches('/');
+        let base_url = base_url.strip_suffix("/v1").unwrap_or(base_url);
+        format!("{}/api/chat", base_url)
+    }
+
+    fn headers(&self, api_key: &str) -> HashMap<String, String> {
+        let mut headers = HashMap::new();
+        headers.insert("Content-Type".to_string(), "application/json".to_string());
+        if !api_key.trim().is_empty() {
+            headers.insert("Authorization".to_string(), format!("Bearer {}", api_key));
+        }
+        headers
+    }
+
+    fn build_body(&self, request: &ProviderRequest) -> Value {
+        let messages = request
+            .messages
+            .iter()
+            .map(|msg| {
+                let mut converted = json!({
+                    "role": msg["role"],
+                    "content": content_text(&msg["content"]),
+                });
+                let images = content_parts(&msg["content"])
+                    .into_iter()
+                    .filter_map(|part| match part {
+                        ContentPart::Image { data, .. } => Some(Value::String(data)),
+                        ContentPart::Text(_) => None,
+                    })
+                    .collect::<Vec<_>>();
+                if !images.is_empty() {
+                    converted["images"] = Value::Array(images);
+                }
+                if let Some(calls) = msg["tool_calls"].as_array() {
+                    converted["tool_calls"] = calls
+                        .iter()
+                        .map(|call| {
+                            json!({
+                                "function": {
+                                    "name": call["function"]["name"],
+                                    "arguments": arguments_to_object(&call["function"]["arguments"]),
+                                }
+                            })
+                        })
+                        .collect();
+                }
+                converted
+            })
+            .collect::<Vec<_>>();
+
+        let mut body = json!({
+            "model": request.model,
+            "messages": messages,
+            "stream": request.stream,
+            "options": { "num_predict": request.max_tokens },
+        });
+        if let Some(temperature) = request.temperature {
+            body["options"]["temperature"] = json!(temperature);
+        }
+        if let Some(tools) = &request.tools {
+            body["tools"] = tools.clone();
+        }
+        body
+    }
+
+    fn parse_response(&self, json: &Value) -> ProviderResponse {
+        let message = &json["message"];
+        let tool_calls: Vec<ToolCall> = message["tool_calls"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .enumerate()
+            .map(|(idx, tc)| ToolCall {
+                id: format!("call_{}", idx),
+                name: tc["function"]["name"].as_str().unwrap_or("").to_string(),
+                params: arguments_to_params(&tc["function"]["arguments"]),
+            })
+            .collect();
+        let prompt_tokens = usage_value(&json["prompt_eval_count"]).unwrap_or(0);
+        let completion_tokens = usage_value(&json["eval_count"]).unwrap_or(0);
+        let finish_reason = if tool_calls.is_empty() {
+            json["done_reason"].as_str().map(|s| s.to_string())
+        } else {
+            Some("tool_calls".to_string())
+        };
+
+        ProviderResponse {
+            content: message["content"].as_str().unwrap_or("").to_string(),
+            reasoning: extract_reasoning(message),
+            tool_calls,
+            finish_reason,
+            prompt_tokens,
+            completion_tokens,
+            total_tokens: prompt_tokens + completion_tokens,
+        }
+    }
+
+    fn parse_stream_event(
+        &self,
+        event: &Value,
+        state: &mut ProviderStreamState,
+    ) -> Vec<ProviderDelta> {
+        let mut deltas = Vec::new();
+        let message = &event["message"];
+        if let Some(thinking) = message["thinking"].as_str().filter(|t| !t.is_empty()) {
+            deltas.push(ProviderDelta::Reasoning(thinking.to_string()));
+        }
+        for tc in message["tool_calls"].as_array().into_iter().flatten() {
+            deltas.push(ProviderDelta::ToolCall {
+                index: state.next_tool_index(),
+                id: None,
+                name: tc["function"]["name"].as_str().unwrap_or("").to_string(),
+                arguments: arguments_to_object(&tc["function"]["arguments"]).to_string(),
+            });
+        }
+        if let Some(content) = message["content"].as_str().filter(|c| !c.is_empty()) {
+            deltas.push(ProviderDelta::Text(content.to_string()));
+        }
+        if event["done"].as_bool() == Some(true) {
+            let reason = if state.tool_calls_seen > 0 {
+                "tool_calls"
+            } else {
+                event["done_reason"].as_str().unwrap_or("stop")
+            };
+            deltas.push(ProviderDelta::Finish(reason.to_string()));
+            deltas.push(ProviderDelta::Usage {
+                prompt_tokens: usage_value(&event["prompt_eval_count"]),
+                completion_tokens: usage_value(&event["eval_count"]),
+                total_tokens: None,
+            });
+        }
+        deltas
+    }
+
+    /// 本地模型 FC 支持不稳定，默认使用 XML 工具协议
+    fn native_tool_calls(&self) -> bool {
+        false
+    }
+
+    /// Ollama 的 `format` 字段直接接受 JSON Schema
+    fn apply_response_schema(&self, body: &mut Value, schema: &ResponseSchema) -> bool {
+        body["format"] = schema.schema.clone();
+        true
+    }
+}
+
+/// 部分模型（如 DeepSeek-R1 / QwQ）把推理内容以 `<think>` 标签写在正文里
+const THINK_TAGS: &[(&str, &str)] = &[("<think>", "</think>"), ("<thinking>", "</thinking>")];
+
+/// 从正文流中拆出 `<think>` / `<thinking>` 标签包裹的推理内容
+///
+/// 标签可能被拆分到多个数据块中，未能确定的尾部会暂存到下一次 `push`。
+#[derive(Default)]
+pub struct ThinkTagSplitter {
+    buffer: String,
+    /// 当前所在推理块的结束标签
+    close_tag: Option<&'static str>,
+    /// 推理块结束后跳过正文开头的空白
+    trim_next_text: bool,
+}
+
+impl ThinkTagSplitter {
+    /// 输入一段正文，返回拆分后的 Text / Reasoning 增量
+    pub fn push(&mut self, text: &str) -> Vec<ProviderDelta> {
+        self.buffer.push_str(text);
+        let mut deltas = Vec::new();
+        loop {
+            if let Some(close_tag) = self.close_tag {
+                if let Some(end) = self.buffer.find(close_tag) {
+                    push_reasoning(&mut deltas, &self.buffer[..end]);
+                    self.buffer.drain(..end + close_tag.len());
+                    self.close_tag = None;
+                    self.trim_next_text = true;
+                    continue;
+                }
+                // 保留可能是结束标签前缀的尾部
+                let mut keep_from = self.buffer.len().saturating_sub(close_tag.len() - 1);
+                while !self.buffer.is_char_boundary(keep_from) {
+                    keep_from -= 1;
+                }
+                push_reasoning(&mut deltas, &self.buffer[..keep_from]);
+                self.buffer.drain(..keep_from);
+                return deltas;
+            }
+
+            if self.trim_next_text {
+                let trimmed = self.buffer.trim_start().len();
+                self.buffer.drain(..self.buffer.len() - trimmed);
+                if self.buffer.is_empty() {
+                    return deltas;
+                }
+                self.trim_next_text = false;
+            }
+
+            let Some(start) = self.buffer.find('<') else {
+                push_text(&mut deltas, &self.buffer);
+                self.buffer.clear();
+                return deltas;
+            };
+            let rest = &self.buffer[start..];
+            if let Some((open_tag, close_tag)) =
+                THINK_TAGS.iter().find(|(open, _)| rest.starts_with(open))
+            {
+                push_text(&mut deltas, &self.buffer[..start]);
+                self.buffer.drain(..start + open_tag.len());
+                self.close_tag = Some(close_tag);
+                continue;
+            }
+            if THINK_TAGS.iter().any(|(open, _)| open.starts_with(rest)) {
+                // 可能是被截断的开始标签，等待后续数据
+                push_text(&mut deltas, &self.buffer[..start]);
+                self.buffer.drain(..start);
+                return deltas;
+            }
+            push_text(&mut deltas, &self.buffer[..start + 1]);
+            self.buffer.drain(..start + 1);
+        }
+    }
+
+    /// 流结束：输出暂存的剩余内容（未闭合的推理块按推理处理）
+    pub fn finish(&mut self) -> Vec<ProviderDelta> {
+        let rest = std::mem::take(&mut self.buffer);
+        let mut deltas = Vec::new();
+        if self.close_tag.take().is_some() {
+            push_reasoning(&mut deltas, &rest);
+        } else {
+            push_text(&mut deltas, &rest);
+        }
+        deltas
+    }
+}
+
+fn push_text(deltas: &mut Vec<ProviderDelta>, text: &str) {
+    if !text.is_empty() {
+        deltas.push(ProviderDelta::Text(text.to_string()));
+    }
+}
+
+fn push_reasoning(deltas: &mut Vec<ProviderDelta>, text: &str) {
+    if !text.is_empty() {
+        deltas.push(ProviderDelta::Reasoning(text.to_string()));
+    }
+}
+
+/// 拆分完整正文中的推理标签，返回 (推理内容, 最终回答)
+pub fn split_think_tags(content: &str) -> (String, String) {
+    let mut splitter = ThinkTagSplitter::default();
+    let mut deltas = splitter.push(content);
+    deltas.extend(splitter.finish());
+    let mut reasoning = String::new();
+    let mut answer = String::new();
+    for delta in deltas {
+        match delta {
+            ProviderDelta::Reasoning(text) => reasoning.push_str(&text),
+            ProviderDelta::Text(text) => answer.push_str(&text),
+            _ => {}
+        }
+    }
+    (reasoning, answer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(messages: Vec<Value>, tools: Option<Value>) -> ProviderRequest<'static> {
+        ProviderRequest {
+            model: "test-model",
+            messages,
+            temperature: Some(0.7),
+            max_tokens: 1024,
+            stream: true,
+            tools,
+        }
+    }
+
+    fn tool_conversation() -> Vec<Value> {
+        vec![
+            json!({ "role": "system", "content": "be helpful" }),
+            json!({ "role": "user", "content": "read a.md" }),
+            json!({
+                "role": "assistant",
+                "content": "",
+                "tool_calls": [{
+                    "id": "call_1",
+                    "type": "function",
+                    "function": { "name": "read", "arguments": "{\"path\":\"a.md\"}" }
+                }]
+            }),
+            json!({ "role": "tool", "content": "# A", "tool_call_id": "call_1" }),
+        ]
+    }
+
+    fn read_tool() -> Value {
+        json!([{
+            "type": "function",
+            "function": {
+                "name": "read",
+                "description": "Read a file",
+                "parameters": {
+                    "type": "object",
+                    "properties": { "path": { "type": "string" } },
+                    "additionalProperties": false
+                }
+            }
+        }])
+    }
+
+    #[test]
+    fn anthropic_maps_tool_calls_to_content_blocks() {
+        let body = AnthropicProvider.build_body(&request(tool_conversation(), Some(read_tool())));
+
+        assert_eq!(body["system"], "be helpful");
+        let messages = body["messages"].as_array().unwrap();
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[1]["content"][0]["type"], "tool_use");
+        assert_eq!(messages[1]["content"][0]["input"]["path"], "a.md");
+        assert_eq!(messages[2]["role"], "user");
+        assert_eq!(messages[2]["content"][0]["tool_use_id"], "call_1");
+        assert_eq!(
+            body["tools"][0]["input_schema"]["properties"]["path"]["type"],
+            "string"
+        );
+    }
+
+    #[test]
+    fn anthropic_stream_accumulates_tool_use_deltas() {
+        let provider = AnthropicProvider;
+        let mut state = ProviderStreamState::default();
+        let events = [
+            json!({ "type": "message_start", "message": { "usage": { "input_tokens": 12 } } }),
+            json!({ "type": "content_block_start", "index": 1, "content_block": { "type": "tool_use", "id": "toolu_1", "name": "read" } }),
+            json!({ "type": "content_block_delta", "index": 1, "delta": { "type": "input_json_delta", "partial_json": "{\"path\":" } }),
+            json!({ "type": "message_delta", "delta": { "stop_reason": "tool_use" }, "usage": { "output_tokens": 5 } }),
+        ];
+        let deltas: Vec<ProviderDelta> = events
+            .iter()
+            .flat_map(|event| provider.parse_stream_event(event, &mut state))
+            .collect();
+
+        assert!(deltas.contains(&ProviderDelta::ToolCall {
+            index: 1,
+            id: Some("toolu_1".to_string()),
+            name: "read".to_string(),
+            arguments: String::new(),
+        }));
+        assert!(deltas.contains(&ProviderDelta::ToolCall {
+            index: 1,
+            id: None,
+            name: String::new(),
+            arguments: "{\"path\":".to_string(),
+        }));
+        assert!(deltas.contains(&ProviderDelta::Finish("tool_calls".to_string())));
+    }
+
+    #[test]
+    fn gemini_maps_function_calls_and_responses() {
+        let body = GeminiProvider.build_body(&request(tool_conversation(), Some(read_tool())));
+
+        assert_eq!(body["systemInstruction"]["parts"][0]["text"], "be helpful");
+        let contents = body["contents"].as_array().unwrap();
+        assert_eq!(contents[1]["role"], "model");
+        assert_eq!(
+            contents[1]["parts"][0]["functionCall"]["args"]["path"],
+            "a.md"
+        );
+        assert_eq!(contents[2]["parts"][0]["functionResponse"]["name"], "read");
+        let parameters = &body["tools"][0]["functionDeclarations"][0]["parameters"];
+        assert!(parameters.get("additionalProperties").is_none());
+
+        let response = GeminiProvider.parse_response(&json!({
+            "candidates": [{
+                "content": { "parts": [{ "functionCall": { "name": "read", "args": { "path": "b.md" } } }] },
+                "finishReason": "STOP"
+            }],
+            "usageMetadata": { "promptTokenCount": 3, "candidatesTokenCount": 2, "totalTokenCount": 5 }
+        }));
+        assert_eq!(response.tool_calls[0].params["path"], "b.md");
+        assert_eq!(response.finish_reason.as_deref(), Some("tool_calls"));
+        assert_eq!(response.total_tokens, 5);
+    }
+
+    #[test]
+    fn ollama_uses_native_chat_endpoint_with_object_arguments() {
+        assert_eq!(
+            OllamaProvider.endpoint("http://localhost:11434/v1/", "llama3", true),
+            "http://localhost:11434/api/chat"
+        );
+        let body = OllamaProvider.build_body(&request(tool_conversation(), None));
+        assert_eq!(
+            body["messages"][2]["tool_calls"][0]["function"]["arguments"]["path"],
+            "a.md"
+        );
+        assert!(!OllamaProvider.native_tool_calls());
+    }
+
+    #[test]
+    fn provider_for_keeps_gemini_openai_compat_endpoint() {
+        let compat = provider_for(
+            "gemini",
+            Some("https://generativelanguage.googleapis.com/v1beta/openai/"),
+        );
+        assert_eq!(
+            compat.endpoint(
+                "https://generativelanguage.googleapis.com/v1beta/openai",
+                "m",
+                false
+            ),
+            "https://generativelanguage.googleapis.com/v1beta/openai/chat/completions"
+        );
+        assert!(!provider_for("unknown", None).native_tool_calls());
+        assert_eq!(stream_payload("data: {\"a\":1}"), Some("{\"a\":1}"));
+        assert_eq!(stream_payload("event: message_start"), None);
+    }
+
+    #[test]
+    fn multimodal_messages_map_images_per_protocol() {
+        let messages = vec![json!({
+            "role": "user",
+            "content": [
+                { "type": "text", "text": "describe" },
+                { "type": "image_url", "image_url": { "url": "data:image/png;base64,AAAA" } }
+            ]
+        })];
+        let mut request = request(messages, None);
+        request.temperature = None;
+
+        let body = AnthropicProvider.build_body(&request);
+        assert_eq!(body["messages"][0]["content"][0]["text"], "describe");
+        assert_eq!(
+            body["messages"][0]["content"][1]["source"]["media_type"],
+            "image/png"
+        );
+        assert!(body.get("temperature").is_none());
+        assert_eq!(
+            AnthropicProvider.endpoint("https://api.anthropic.com/", "m", false),
+            "https://api.anthropic.com/v1/messages"
+        );
+        assert_eq!(
+            AnthropicProvider.endpoint("https://api.anthropic.com/v1", "m", false),
+            "https://api.anthropic.com/v1/messages"
+        );
+
+        let body = GeminiProvider.build_body(&request);
+        assert_eq!(
+            body["contents"][0]["parts"][1]["inlineData"]["mimeType"],
+            "image/png"
+        );
+        assert!(body["generationConfig"].get("temperature").is_none());
+
+        let body = OllamaProvider.build_body(&request);
+        assert_eq!(body["messages"][0]["content"], "describe");
+        assert_eq!(body["messages"][0]["images"][0], "AAAA");
+
+        assert_eq!(
+            serde_json::to_value(ProviderDelta::Text("hi".to_string())).unwrap(),
+            json!({ "type": "text", "data": "hi" })
+        );
+    }
+
+    #[test]
+    fn response_schema_maps_to_each_protocol() {
+        let schema = ResponseSchema {
+            name: "intent".to_string(),
+            schema: json!({
+                "type": "object",
+                "properties": { "intent": { "type": "string" } },
+                "required": ["intent"],
+                "additionalProperties": false,
+            }),
+        };
+        let mut body = json!({});
+        assert!(provider_for("openai", None).apply_response_schema(&mut body, &schema));
+        assert_eq!(body["response_format"]["json_schema"]["name"], "intent");
+        assert_eq!(body["response_format"]["json_schema"]["strict"], true);
+
+        let mut body = json!({});
+        assert!(!provider_for("deepseek", None).apply_response_schema(&mut body, &schema));
+        assert!(!AnthropicProvider.apply_response_schema(&mut body, &schema));
+        assert!(body.get("response_format").is_none());
+
+        let mut body = GeminiProvider.build_body(&request(tool_conversation(), None));
+        assert!(GeminiProvider.apply_response_schema(&mut body, &schema));
+        assert_eq!(
+            body["generationConfig"]["responseMimeType"],
+            "application/json"
+        );
+        assert!(body["generationConfig"]["responseSchema"]
+            .get("additionalProperties")
+            .is_none());
+
+        let mut body = json!({});
+        assert!(OllamaProvider.apply_response_schema(&mut body, &schema));
+        assert_eq!(body["format"]["required"][0], "intent");
+    }
+
+    #[test]
+    fn think_tags_split_across_chunks() {
+        let mut splitter = ThinkTagSplitter::default();
+        let mut deltas = Vec::new();
+        for chunk in [
+            "<th",
+            "ink>先分析",
+            "问题</thi",
+            "nk>\n\n答案 a<b",
+            "</think>",
+        ] {
+            deltas.extend(splitter.push(chunk));
+        }
+        deltas.extend(splitter.finish());
+        let reasoning: String = deltas
+            .iter()
+            .filter_map(|d| match d {
+                ProviderDelta::Reasoning(text) => Some(text.as_str()),
+                _ => None,
+            })
+            .collect();
+        let text: String = deltas
+            .iter()
+            .filter_map(|d| match d {
+                ProviderDelta::Text(text) => Some(text.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(reasoning, "先分析问题");
+        assert_eq!(text, "答案 a<b</think>");
+
+        assert_eq!(
+            split_think_tags("<thinking>plan</thinking>\nanswer"),
+            ("plan".to_string(), "answer".to_string())
+        );
+        assert_eq!(
+            split_think_tags("no tags <b>here</b>"),
+            (String::new(), "no tags <b>here</b>".to_string())
+        );
+    }
+}
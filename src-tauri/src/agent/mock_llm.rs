@@ -0,0 +1,204 @@
+//! 测试用的脚本化 LLM
+//!
+//! 按调用序号或 prompt 中的关键字返回预设响应，并记录每次调用的 prompt，
+//! 用于在无网络的情况下测试节点路由与解析逻辑。多轮消息调用把全部消息内容
+//! 拼接为 prompt 匹配，并记录每次是否提供了工具。
+
+use crate::agent::llm_backend::LlmBackend;
+use crate::agent::llm_client::LlmResponse;
+use crate::agent::types::Message;
+use serde_json::Value;
+use std::future::Future;
+use std::sync::Mutex;
+use tauri::AppHandle;
+use tokio::sync::mpsc::{self, Receiver};
+
+/// 响应匹配规则
+enum Matcher {
+    /// 第 n 次调用（从 0 开始）
+    CallIndex(usize),
+    /// prompt 包含指定文本
+    PromptContains(String),
+}
+
+struct Script {
+    matcher: Matcher,
+    response: LlmResponse,
+}
+
+/// 只含文本、没有工具调用和用量的响应
+fn text_response(content: String) -> LlmResponse {
+    LlmResponse {
+        content,
+        reasoning: None,
+        tool_calls: None,
+        finish_reason: Some("stop".to_string()),
+        prompt_tokens: 0,
+        completion_tokens: 0,
+        total_tokens: 0,
+        cached: false,
+    }
+}
+
+/// 脚本化的 LLM：按规则声明顺序匹配，都不匹配时使用兜底响应
+#[derive(Default)]
+pub struct MockLlmClient {
+    scripts: Vec<Script>,
+    fallback: Option<LlmResponse>,
+    prompts: Mutex<Vec<String>>,
+    tools_offered: Mutex<Vec<bool>>,
+}
+
+impl MockLlmClient {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 第 `index` 次调用返回 `response`
+    pub fn on_call(self, index: usize, response: impl Into<String>) -> Self {
+        self.on_call_response(index, text_response(response.into()))
+    }
+
+    /// 第 `index` 次调用返回完整响应（工具调用、token 用量等）
+    pub fn on_call_response(mut self, index: usize, response: LlmResponse) -> Self {
+        self.scripts.push(Script {
+            matcher: Matcher::CallIndex(index),
+            response,
+        });
+        self
+    }
+
+    /// prompt 包含 `needle` 时返回 `response`
+    pub fn on_prompt(mut self, needle: impl Into<String>, response: impl Into<String>) -> Self {
+        self.scripts.push(Script {
+            matcher: Matcher::PromptContains(needle.into()),
+            response: text_response(response.into()),
+        });
+        self
+    }
+
+    /// 没有规则匹配时的响应（未设置则返回错误）
+    pub fn fallback(mut self, response: impl Into<String>) -> Self {
+        self.fallback = Some(text_response(response.into()));
+        self
+    }
+
+    /// 已收到的全部 prompt（按调用顺序）
+    pub fn prompts(&self) -> Vec<String> {
+        self.prompts.lock().unwrap().clone()
+    }
+
+    /// 每次多轮消息调用是否提供了工具（按调用顺序）
+    pub fn tools_offered(&self) -> Vec<bool> {
+        self.tools_offered.lock().unwrap().clone()
+    }
+
+    fn respond(&self, prompt: &str) -> Result<LlmResponse, String> {
+        let index = {
+            let mut prompts = self.prompts.lock().unwrap();
+            prompts.push(prompt.to_string());
+            prompts.len() - 1
+        };
+        self.scripts
+            .iter()
+            .find(|script| match &script.matcher {
+                Matcher::CallIndex(expected) => *expected == index,
+                Matcher::PromptContains(needle) => prompt.contains(needle.as_str()),
+            })
+            .map(|script| script.response.clone())
+            .or_else(|| self.fallback.clone())
+            .ok_or_else(|| format!("MockLlmClient: no scripted response for call #{}", index))
+    }
+}
+
+impl LlmBackend for MockLlmClient {
+    fn call_simple_with_usage(
+        &self,
+        prompt: &str,
+    ) -> impl Future<Output = Result<LlmResponse, String>> + Send {
+        let result = self.respond(prompt);
+        async move { result }
+    }
+
+    /// 按行拆分为多个数据块发送，模拟流式输出
+    fn call_stream_simple(
+        &self,
+        prompt: &str,
+    ) -> impl Future<Output = Result<Receiver<String>, String>> + Send {
+        let result = self.respond(prompt).map(|response| {
+            let chunks: Vec<String> = response
+                .content
+                .split_inclusive('\n')
+                .map(String::from)
+                .collect();
+            let (tx, rx) = mpsc::channel(chunks.len().max(1));
+            for chunk in chunks {
+                let _ = tx.try_send(chunk);
+            }
+            rx
+        });
+        async move { result }
+    }
+
+    /// 回答内容作为一个增量回放
+    fn call_stream_with_delta<F>(
+        &self,
+        _app: Option<AppHandle>,
+        _request_id: &str,
+        messages: &[Message],
+        tools: Option<&[Value]>,
+        mut on_delta: F,
+    ) -> impl Future<Output = Result<LlmResponse, String>> + Send
+    where
+        F: FnMut(&str) + Send,
+    {
+        self.tools_offered.lock().unwrap().push(tools.is_some());
+        let prompt = messages
+            .iter()
+            .map(|message| message.content.as_str())
+            .collect::<Vec<_>>()
+            .join("\n");
+        let result = self.respond(&prompt);
+        if let Ok(response) = &result {
+            if !response.content.is_empty() {
+                on_delta(&response.content);
+            }
+        }
+        async move { result }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn scripts_match_by_index_then_prompt_then_fallback() {
+        let llm = MockLlmClient::new()
+            .on_call(0, "first")
+            .on_prompt("outline", "{\"title\": \"t\"}")
+            .fallback("default");
+
+        assert_eq!(llm.call_simple("anything").await.unwrap(), "first");
+        assert_eq!(
+            llm.call_simple("make an outline").await.unwrap(),
+            "{\"title\": \"t\"}"
+        );
+        assert_eq!(llm.call_simple("other").await.unwrap(), "default");
+        assert_eq!(llm.prompts().len(), 3);
+
+        let strict = MockLlmClient::new();
+        assert!(strict.call_simple("unscripted").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn stream_replays_response_line_by_line() {
+        let llm = MockLlmClient::new().fallback("# Title\nbody\n");
+        let mut rx = llm.call_stream_simple("write").await.unwrap();
+        let mut chunks = Vec::new();
+        while let Some(chunk) = rx.recv().await {
+            chunks.push(chunk);
+        }
+        assert_eq!(chunks, vec!["# Title\n", "body\n"]);
+    }
+}
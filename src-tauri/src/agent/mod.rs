@@ -3,12 +3,21 @@
 //! 统一基于 Forge loop 的 Agent 运行时
 
 pub mod commands;
+pub mod compaction;
 pub mod debug_log;
 pub mod deep_research;
+pub mod event_schema;
 pub mod forge_loop;
+pub mod llm_backend;
 pub mod llm_client;
+pub mod llm_provider;
+#[cfg(test)]
+pub mod mock_llm;
+pub mod session_store;
 pub mod skills;
+pub mod tool_budget;
 pub mod types;
+pub mod xml_tool_calls;
 
 #[allow(unused_imports)]
 pub use commands::*;
@@ -0,0 +1,462 @@
+//! Agent 会话持久化
+//!
+//! 每个会话的用户任务与 Agent 事件按顺序追加到工作区的
+//! `.lumina/sessions/<id>.jsonl`，应用重启后可列出、回放历史会话，
+//! 并从中恢复多轮对话的 history。
+
+use crate::agent::types::{Message, MessageRole};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Mutex};
+
+/// 会话目录（相对工作区）
+const SESSIONS_DIR: &str = ".lumina/sessions";
+
+/// 会话标题最多保留的字符数
+const TITLE_MAX_CHARS: usize = 60;
+
+/// 不属于对话内容、不写入会话记录的事件类型
+const SKIPPED_EVENT_TYPES: [&str; 3] = ["heartbeat", "queue_updated", "prompt_stack"];
+
+/// 流式增量事件（`text_delta`、`reasoning_delta` 等）不写入：
+/// 完整内容会随之后的最终事件一起记录
+fn is_recorded_event(event_type: &str) -> bool {
+    !SKIPPED_EVENT_TYPES.contains(&event_type) && !event_type.ends_with("_delta")
+}
+
+/// 会话文件中的一行记录
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SessionRecord {
+    /// 用户提交的任务
+    Task { timestamp: i64, task: String },
+    /// Agent 事件（与 "agent-event" 的 payload 相同）
+    Event { timestamp: i64, event: Value },
+}
+
+impl SessionRecord {
+    fn timestamp(&self) -> i64 {
+        match self {
+            SessionRecord::Task { timestamp, .. } | SessionRecord::Event { timestamp, .. } => {
+                *timestamp
+            }
+        }
+    }
+}
+
+/// 会话摘要（用于会话列表）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionSummary {
+    pub id: String,
+    /// 首个任务的前若干字符
+    pub title: String,
+    /// Unix 毫秒
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub task_count: usize,
+}
+
+/// 完整会话：摘要 + 全部记录 + 可直接作为 `TaskContext.history` 的消息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionTranscript {
+    pub summary: SessionSummary,
+    pub records: Vec<SessionRecord>,
+    pub history: Vec<Message>,
+}
+
+fn now_millis() -> i64 {
+    chrono::Utc::now().timestamp_millis()
+}
+
+/// 会话 id 只允许字母、数字、`-`、`_`，避免路径穿越
+fn validate_session_id(id: &str) -> Result<(), String> {
+    if id.is_empty()
+        || id.len() > 128
+        || !id
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || ch == '-' || ch == '_')
+    {
+        return Err(format!("Invalid session id: {}", id));
+    }
+    Ok(())
+}
+
+fn sessions_dir(workspace: &Path) -> PathBuf {
+    workspace.join(SESSIONS_DIR)
+}
+
+fn session_path(workspace: &Path, id: &str) -> Result<PathBuf, String> {
+    validate_session_id(id)?;
+    Ok(sessions_dir(workspace).join(format!("{}.jsonl", id)))
+}
+
+/// 追加一条记录
+pub fn append_record(workspace: &Path, id: &str, record: &SessionRecord) -> Result<(), String> {
+    let path = session_path(workspace, id)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create sessions dir: {}", e))?;
+    }
+    let line = serde_json::to_string(record).map_err(|e| e.to_string())?;
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| format!("Failed to open session file: {}", e))?;
+    writeln!(file, "{}", line).map_err(|e| format!("Failed to write session file: {}", e))
+}
+
+/// 读取会话记录；损坏的行（如写入中断）直接跳过
+fn read_records(path: &Path) -> Result<Vec<SessionRecord>, String> {
+    let content =
+        fs::read_to_string(path).map_err(|e| format!("Failed to read session file: {}", e))?;
+    Ok(content
+        .lines()
+        .filter_map(|line| serde_json::from_str::<SessionRecord>(line).ok())
+        .collect())
+}
+
+fn summarize(id: &str, records: &[SessionRecord]) -> Option<SessionSummary> {
+    let created_at = records.first()?.timestamp();
+    let updated_at = records.last()?.timestamp();
+    let mut tasks = records.iter().filter_map(|record| match record {
+        SessionRecord::Task { task, .. } => Some(task.as_str()),
+        SessionRecord::Event { .. } => None,
+    });
+    let title = tasks
+        .next()
+        .map(|task| task.trim().chars().take(TITLE_MAX_CHARS).collect())
+        .unwrap_or_default();
+    Some(SessionSummary {
+        id: id.to_string(),
+        title,
+        created_at,
+        updated_at,
+        task_count: records
+            .iter()
+            .filter(|record| matches!(record, SessionRecord::Task { .. }))
+            .count(),
+    })
+}
+
+/// 列出工作区内的会话（最近更新的在前）
+pub fn list_sessions(workspace: &Path) -> Result<Vec<SessionSummary>, String> {
+    flush_recording();
+    let dir = sessions_dir(workspace);
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let entries = fs::read_dir(&dir).map_err(|e| format!("Failed to read sessions dir: {}", e))?;
+    let mut sessions: Vec<SessionSummary> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let path = entry.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("jsonl") {
+                return None;
+            }
+            let id = path.file_stem()?.to_str()?.to_string();
+            validate_session_id(&id).ok()?;
+            summarize(&id, &read_records(&path).ok()?)
+        })
+        .collect();
+    sessions.sort_by_key(|session| std::cmp::Reverse(session.updated_at));
+    Ok(sessions)
+}
+
+/// 读取完整会话
+pub fn load_session(workspace: &Path, id: &str) -> Result<SessionTranscript, String> {
+    let path = session_path(workspace, id)?;
+    flush_recording();
+    if !path.exists() {
+        return Err(format!("Session not found: {}", id));
+    }
+    let records = read_records(&path)?;
+    let summary = summarize(id, &records).ok_or_else(|| format!("Session is empty: {}", id))?;
+    let history = history_messages(&records);
+    Ok(SessionTranscript {
+        summary,
+        records,
+        history,
+    })
+}
+
+/// 删除会话文件
+pub fn delete_session(workspace: &Path, id: &str) -> Result<(), String> {
+    let path = session_path(workspace, id)?;
+    // 先写完排队中的记录，避免删除后又被重新创建
+    flush_recording();
+    if !path.exists() {
+        return Err(format!("Session not found: {}", id));
+    }
+    fs::remove_file(&path).map_err(|e| format!("Failed to delete session: {}", e))?;
+    // 截断工具结果时保存的完整输出随会话一起删除
+    let _ = fs::remove_dir_all(crate::agent::tool_budget::tool_output_dir(workspace, id));
+    Ok(())
+}
+
+/// 读取会话的对话历史；会话不存在或无法读取时返回空
+pub fn load_history(workspace: &Path, id: &str) -> Vec<Message> {
+    flush_recording();
+    match session_path(workspace, id) {
+        Ok(path) if path.exists() => read_records(&path)
+            .map(|records| history_messages(&records))
+            .unwrap_or_default(),
+        _ => Vec::new(),
+    }
+}
+
+/// 事件中携带的最终回答文本（`text_final` / `complete`）
+fn final_text(event: &Value) -> Option<&str> {
+    let data = event.get("data")?;
+    match event.get("type")?.as_str()? {
+        "text_final" => data.get("text")?.as_str(),
+        "complete" => data.get("result")?.as_str(),
+        _ => None,
+    }
+}
+
+/// 由会话记录重建多轮对话：每个任务一条 user 消息，
+/// 其后最后一次最终回答作为 assistant 消息（任务被中止时没有回答）
+pub fn history_messages(records: &[SessionRecord]) -> Vec<Message> {
+    fn message(role: MessageRole, content: String) -> Message {
+        Message {
+            role,
+            content,
+            name: None,
+            tool_call_id: None,
+        }
+    }
+
+    let mut messages = Vec::new();
+    let mut answer: Option<String> = None;
+    for record in records {
+        match record {
+            SessionRecord::Task { task, .. } => {
+                if let Some(text) = answer.take() {
+                    messages.push(message(MessageRole::Assistant, text));
+                }
+                messages.push(message(MessageRole::User, task.clone()));
+            }
+            SessionRecord::Event { event, .. } => {
+                if let Some(text) = final_text(event).filter(|text| !text.trim().is_empty()) {
+                    answer = Some(text.to_string());
+                }
+            }
+        }
+    }
+    if let Some(text) = answer {
+        messages.push(message(MessageRole::Assistant, text));
+    }
+    messages
+}
+
+// ============ 录制当前任务 ============
+
+struct ActiveSession {
+    workspace: PathBuf,
+    id: String,
+}
+
+/// 正在录制的会话：保持到下一个任务开始，
+/// 等待审批 / 回答后恢复运行的事件仍写入同一会话
+static ACTIVE_SESSION: Lazy<Mutex<Option<ActiveSession>>> = Lazy::new(|| Mutex::new(None));
+
+enum WriteJob {
+    Append {
+        workspace: PathBuf,
+        id: String,
+        record: SessionRecord,
+    },
+    Flush(mpsc::Sender<()>),
+}
+
+/// 录制写入队列：事件在 emit 路径上产生，文件 I/O 交给独立线程按顺序完成，
+/// 不阻塞 async 执行器
+static WRITER: Lazy<Mutex<mpsc::Sender<WriteJob>>> = Lazy::new(|| {
+    let (tx, rx) = mpsc::channel::<WriteJob>();
+    std::thread::spawn(move || {
+        for job in rx {
+            match job {
+                WriteJob::Append {
+                    workspace,
+                    id,
+                    record,
+                } => {
+                    if let Err(err) = append_record(&workspace, &id, &record) {
+                        eprintln!("[Agent] Failed to record session event: {}", err);
+                    }
+                }
+                WriteJob::Flush(done) => {
+                    let _ = done.send(());
+                }
+            }
+        }
+    });
+    Mutex::new(tx)
+});
+
+fn enqueue(job: WriteJob) {
+    let _ = WRITER.lock().unwrap_or_else(|e| e.into_inner()).send(job);
+}
+
+/// 等待队列中已有的记录写入完成
+fn flush_recording() {
+    let (done_tx, done_rx) = mpsc::channel();
+    enqueue(WriteJob::Flush(done_tx));
+    let _ = done_rx.recv();
+}
+
+/// 记录用户任务，并开始把后续 Agent 事件写入该会话
+pub fn start_recording(workspace: &Path, id: &str, task: &str) -> Result<(), String> {
+    stop_recording();
+    validate_session_id(id)?;
+    let mut active = ACTIVE_SESSION.lock().unwrap_or_else(|e| e.into_inner());
+    // 任务也走写入队列，保证与之前排队的事件保持顺序
+    enqueue(WriteJob::Append {
+        workspace: workspace.to_path_buf(),
+        id: id.to_string(),
+        record: SessionRecord::Task {
+            timestamp: now_millis(),
+            task: task.to_string(),
+        },
+    });
+    *active = Some(ActiveSession {
+        workspace: workspace.to_path_buf(),
+        id: id.to_string(),
+    });
+    Ok(())
+}
+
+/// 停止录制（任务未指定会话时调用）
+pub fn stop_recording() {
+    *ACTIVE_SESSION.lock().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+/// 把 Agent 事件追加到正在录制的会话（没有时忽略）
+pub fn record_event(payload: &Value) {
+    let event_type = payload.get("type").and_then(Value::as_str).unwrap_or("");
+    if !is_recorded_event(event_type) {
+        return;
+    }
+    let active = ACTIVE_SESSION.lock().unwrap_or_else(|e| e.into_inner());
+    let Some(session) = active.as_ref() else {
+        return;
+    };
+    enqueue(WriteJob::Append {
+        workspace: session.workspace.clone(),
+        id: session.id.clone(),
+        record: SessionRecord::Event {
+            timestamp: now_millis(),
+            event: payload.clone(),
+        },
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn event(timestamp: i64, event: Value) -> SessionRecord {
+        SessionRecord::Event { timestamp, event }
+    }
+
+    #[test]
+    fn sessions_round_trip_and_rebuild_history() {
+        let workspace = tempfile::tempdir().unwrap();
+        let root = workspace.path();
+        let records = [
+            SessionRecord::Task {
+                timestamp: 1,
+                task: "总结这篇笔记".to_string(),
+            },
+            event(
+                2,
+                json!({ "type": "text_delta", "data": { "delta": "草稿" } }),
+            ),
+            event(
+                3,
+                json!({ "type": "text_final", "data": { "text": "这是总结" } }),
+            ),
+            SessionRecord::Task {
+                timestamp: 4,
+                task: "再短一点".to_string(),
+            },
+            event(
+                5,
+                json!({ "type": "complete", "data": { "result": "更短" } }),
+            ),
+        ];
+        for record in &records {
+            append_record(root, "chat-1", record).unwrap();
+        }
+        append_record(
+            root,
+            "chat-2",
+            &SessionRecord::Task {
+                timestamp: 10,
+                task: "aborted".to_string(),
+            },
+        )
+        .unwrap();
+        fs::write(sessions_dir(root).join("notes.txt"), "ignored").unwrap();
+
+        let sessions = list_sessions(root).unwrap();
+        let ids: Vec<&str> = sessions.iter().map(|s| s.id.as_str()).collect();
+        assert_eq!(ids, vec!["chat-2", "chat-1"]);
+        assert_eq!(sessions[1].title, "总结这篇笔记");
+        assert_eq!(sessions[1].task_count, 2);
+
+        let transcript = load_session(root, "chat-1").unwrap();
+        assert_eq!(transcript.records, records);
+        let history: Vec<(MessageRole, &str)> = transcript
+            .history
+            .iter()
+            .map(|m| (m.role.clone(), m.content.as_str()))
+            .collect();
+        assert_eq!(
+            history,
+            vec![
+                (MessageRole::User, "总结这篇笔记"),
+                (MessageRole::Assistant, "这是总结"),
+                (MessageRole::User, "再短一点"),
+                (MessageRole::Assistant, "更短"),
+            ]
+        );
+        assert_eq!(load_history(root, "chat-2").len(), 1);
+        assert!(load_history(root, "missing").is_empty());
+
+        delete_session(root, "chat-2").unwrap();
+        assert_eq!(list_sessions(root).unwrap().len(), 1);
+        assert!(delete_session(root, "chat-2").is_err());
+    }
+
+    #[test]
+    fn rejects_ids_outside_sessions_dir() {
+        let workspace = tempfile::tempdir().unwrap();
+        assert!(load_session(workspace.path(), "../secrets").is_err());
+        assert!(delete_session(workspace.path(), "a/b").is_err());
+        assert!(load_history(workspace.path(), "..").is_empty());
+    }
+
+    #[test]
+    fn records_events_only_while_recording() {
+        let workspace = tempfile::tempdir().unwrap();
+        let root = workspace.path();
+        start_recording(root, "live", "hello").unwrap();
+        record_event(&json!({ "type": "heartbeat", "data": { "timestamp": 1 } }));
+        record_event(&json!({ "type": "text_delta", "data": { "delta": "h" } }));
+        record_event(&json!({ "type": "reasoning_delta", "data": { "delta": "…" } }));
+        record_event(&json!({ "type": "text_final", "data": { "text": "hi" } }));
+        stop_recording();
+        record_event(&json!({ "type": "text_final", "data": { "text": "late" } }));
+
+        let transcript = load_session(root, "live").unwrap();
+        assert_eq!(transcript.records.len(), 2);
+        assert_eq!(transcript.history.len(), 2);
+        assert_eq!(transcript.history[1].content, "hi");
+    }
+}
@@ -1,7 +1,16 @@
 //! Skills registry (text-only for now)
+//!
+//! Skill metadata comes from `skill.json` and/or the YAML frontmatter of
+//! `SKILL.md` (`skill.json` wins when both set a field). Both follow the
+//! `SkillManifest` schema; problems are reported per skill in
+//! `SkillInfo::errors` instead of silently dropping the metadata.
 
+use crate::agent::types::{SkillContext, TaskContext};
+use crate::forge_runtime::frontmatter::split_frontmatter;
+use crate::text::is_cjk;
+use globset::{Glob, GlobSetBuilder};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
 use tauri::{AppHandle, Manager};
@@ -18,6 +27,30 @@ pub struct SkillManifest {
     pub runtime: Option<String>,
     pub entrypoint: Option<String>,
     pub permissions: Option<serde_json::Value>,
+    /// Keywords that auto-activate the skill when they appear in the task.
+    pub triggers: Option<Vec<String>>,
+    /// Globs (relative to the workspace) that auto-activate the skill for the active note.
+    pub globs: Option<Vec<String>>,
+}
+
+impl SkillManifest {
+    /// Fill fields missing from `self` with the ones from `fallback`.
+    fn or(self, fallback: SkillManifest) -> SkillManifest {
+        SkillManifest {
+            name: self.name.or(fallback.name),
+            title: self.title.or(fallback.title),
+            description: self.description.or(fallback.description),
+            version: self.version.or(fallback.version),
+            tags: self.tags.or(fallback.tags),
+            prompt: self.prompt.or(fallback.prompt),
+            input_schema: self.input_schema.or(fallback.input_schema),
+            runtime: self.runtime.or(fallback.runtime),
+            entrypoint: self.entrypoint.or(fallback.entrypoint),
+            permissions: self.permissions.or(fallback.permissions),
+            triggers: self.triggers.or(fallback.triggers),
+            globs: self.globs.or(fallback.globs),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -28,6 +61,13 @@ pub struct SkillInfo {
     pub version: Option<String>,
     pub tags: Option<Vec<String>>,
     pub source: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub triggers: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub globs: Option<Vec<String>>,
+    /// Metadata validation errors; empty when the skill is well-formed.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -84,15 +124,137 @@ pub fn builtin_skills_root(app: &AppHandle) -> Option<PathBuf> {
     None
 }
 
-fn read_manifest(dir: &Path) -> Option<SkillManifest> {
-    let manifest_path = dir.join("skill.json");
-    let content = fs::read_to_string(manifest_path).ok()?;
-    serde_json::from_str(&content).ok()
+/// Raw skill files plus the problems found while parsing them.
+struct SkillFiles {
+    manifest: Option<SkillManifest>,
+    markdown: Option<String>,
+    errors: Vec<String>,
+}
+
+fn read_skill_files(dir: &Path) -> SkillFiles {
+    let mut errors = Vec::new();
+    let json_manifest = fs::read_to_string(dir.join("skill.json"))
+        .ok()
+        .and_then(
+            |content| match serde_json::from_str::<SkillManifest>(&content) {
+                Ok(manifest) => Some(manifest),
+                Err(err) => {
+                    errors.push(format!("skill.json: {}", err));
+                    None
+                }
+            },
+        );
+    let markdown = fs::read_to_string(dir.join("SKILL.md")).ok();
+    let frontmatter = match markdown.as_deref().map(parse_skill_frontmatter) {
+        Some(Ok(frontmatter)) => frontmatter,
+        Some(Err(err)) => {
+            errors.push(format!("SKILL.md frontmatter: {}", err));
+            None
+        }
+        None => None,
+    };
+
+    let manifest = match (json_manifest, frontmatter) {
+        (Some(json), Some(frontmatter)) => Some(json.or(frontmatter)),
+        (json, frontmatter) => json.or(frontmatter),
+    };
+    errors.extend(validate_manifest(manifest.as_ref()));
+
+    SkillFiles {
+        manifest,
+        markdown,
+        errors,
+    }
+}
+
+/// Parse the YAML frontmatter of `SKILL.md`; `Ok(None)` when there is none.
+fn parse_skill_frontmatter(markdown: &str) -> Result<Option<SkillManifest>, String> {
+    let Some((yaml, _)) = split_frontmatter(markdown) else {
+        let opened = markdown
+            .trim_start_matches('\u{feff}')
+            .lines()
+            .next()
+            .is_some_and(|line| line.trim_end() == "---");
+        if opened {
+            return Err("missing closing `---`".to_string());
+        }
+        return Ok(None);
+    };
+    if yaml.trim().is_empty() {
+        return Ok(Some(SkillManifest::default()));
+    }
+    serde_yaml::from_str(yaml)
+        .map(Some)
+        .map_err(|err| err.to_string())
+}
+
+/// `SKILL.md` without its frontmatter block.
+fn markdown_body(markdown: &str) -> &str {
+    split_frontmatter(markdown)
+        .map(|(_, body)| body)
+        .unwrap_or(markdown)
+}
+
+fn is_valid_skill_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= 64
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
 }
 
-fn read_markdown(dir: &Path) -> Option<String> {
-    let markdown_path = dir.join("SKILL.md");
-    fs::read_to_string(markdown_path).ok()
+fn is_semver(version: &str) -> bool {
+    let core = version.split(['-', '+']).next().unwrap_or_default();
+    let parts: Vec<&str> = core.split('.').collect();
+    parts.len() == 3
+        && parts
+            .iter()
+            .all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// Check the manifest against the skill schema: `name` and `description` are
+/// required, `version`, `triggers` and `globs` are validated when present.
+fn validate_manifest(manifest: Option<&SkillManifest>) -> Vec<String> {
+    let Some(manifest) = manifest else {
+        return vec!["missing skill.json or SKILL.md frontmatter".to_string()];
+    };
+    let mut errors = Vec::new();
+
+    match manifest.name.as_deref() {
+        None => errors.push("missing required field `name`".to_string()),
+        Some(name) if !is_valid_skill_name(name) => errors.push(format!(
+            "`name` must be 1-64 lowercase letters, digits or hyphens: {}",
+            name
+        )),
+        Some(_) => {}
+    }
+    if manifest
+        .description
+        .as_deref()
+        .is_none_or(|description| description.trim().is_empty())
+    {
+        errors.push("missing required field `description`".to_string());
+    }
+    if let Some(version) = manifest.version.as_deref() {
+        if !is_semver(version) {
+            errors.push(format!(
+                "`version` must be a semantic version such as 1.0.0: {}",
+                version
+            ));
+        }
+    }
+    if let Some(triggers) = &manifest.triggers {
+        if triggers.iter().any(|trigger| trigger.trim().is_empty()) {
+            errors.push("`triggers` must not contain empty entries".to_string());
+        }
+    }
+    for pattern in manifest.globs.iter().flatten() {
+        if let Err(err) = Glob::new(pattern) {
+            errors.push(format!("invalid glob `{}`: {}", pattern, err));
+        }
+    }
+
+    errors
 }
 
 fn extract_title_description(markdown: &str) -> (Option<String>, Option<String>) {
@@ -119,12 +281,9 @@ fn extract_title_description(markdown: &str) -> (Option<String>, Option<String>)
     (title, description)
 }
 
-fn build_info(
-    dir_name: &str,
-    source: &str,
-    manifest: Option<SkillManifest>,
-    markdown: Option<&str>,
-) -> SkillInfo {
+fn build_info(dir_name: &str, source: &str, files: &SkillFiles) -> SkillInfo {
+    let manifest = files.manifest.clone();
+    let markdown = files.markdown.as_deref().map(markdown_body);
     let mut info = SkillInfo {
         name: dir_name.to_string(),
         title: dir_name.to_string(),
@@ -132,22 +291,31 @@ fn build_info(
         version: None,
         tags: None,
         source: source.to_string(),
+        triggers: None,
+        globs: None,
+        errors: files.errors.clone(),
     };
 
     if let Some(manifest) = manifest {
         if let Some(name) = manifest.name {
             info.name = name;
         }
+        let has_title = manifest.title.is_some();
         if let Some(title) = manifest.title {
             info.title = title;
         }
         info.description = manifest.description;
         info.version = manifest.version;
         info.tags = manifest.tags;
-        if info.description.is_none() {
+        info.triggers = manifest.triggers;
+        info.globs = manifest.globs;
+        if !has_title || info.description.is_none() {
             if let Some(markdown) = markdown {
-                let (_title, desc) = extract_title_description(markdown);
-                if desc.is_some() {
+                let (title, desc) = extract_title_description(markdown);
+                if let (false, Some(title)) = (has_title, title) {
+                    info.title = title;
+                }
+                if info.description.is_none() {
                     info.description = desc;
                 }
             }
@@ -179,10 +347,8 @@ fn list_skills_in_root(root: &Path, source: &str) -> Vec<SkillInfo> {
             Some(name) => name.to_string(),
             None => continue,
         };
-        let manifest = read_manifest(&path);
-        let markdown = read_markdown(&path);
-        let info = build_info(&dir_name, source, manifest, markdown.as_deref());
-        results.push(info);
+        let files = read_skill_files(&path);
+        results.push(build_info(&dir_name, source, &files));
     }
 
     results
@@ -206,6 +372,26 @@ pub fn list_skills(app: &AppHandle, workspace_path: Option<&str>) -> Vec<SkillIn
     ordered
 }
 
+fn skill_detail(name: &str, source: &str, files: SkillFiles) -> SkillDetail {
+    let info = build_info(name, source, &files);
+    let prompt = files
+        .manifest
+        .and_then(|m| m.prompt)
+        .or_else(|| {
+            files
+                .markdown
+                .as_deref()
+                .map(|md| markdown_body(md).to_string())
+        })
+        .unwrap_or_default();
+
+    SkillDetail {
+        info,
+        prompt,
+        markdown: files.markdown,
+    }
+}
+
 pub fn read_skill(
     app: &AppHandle,
     workspace_path: Option<&str>,
@@ -215,19 +401,7 @@ pub fn read_skill(
     for (source, root) in roots {
         let dir = root.join(name);
         if dir.exists() {
-            let manifest = read_manifest(&dir);
-            let markdown = read_markdown(&dir);
-            let info = build_info(name, &source, manifest.clone(), markdown.as_deref());
-            let prompt = manifest
-                .and_then(|m| m.prompt)
-                .or_else(|| markdown.clone())
-                .unwrap_or_default();
-
-            return Ok(SkillDetail {
-                info,
-                prompt,
-                markdown,
-            });
+            return Ok(skill_detail(name, &source, read_skill_files(&dir)));
         }
 
         // Fallback: scan directories to find a manifest with matching name
@@ -237,29 +411,422 @@ pub fn read_skill(
                 if !path.is_dir() {
                     continue;
                 }
-                let manifest = read_manifest(&path);
-                if let Some(ref manifest) = manifest {
-                    if manifest.name.as_deref() != Some(name) {
-                        continue;
-                    }
-                } else {
+                let files = read_skill_files(&path);
+                let matches = files
+                    .manifest
+                    .as_ref()
+                    .is_some_and(|manifest| manifest.name.as_deref() == Some(name));
+                if !matches {
                     continue;
                 }
-                let markdown = read_markdown(&path);
-                let info = build_info(name, &source, manifest.clone(), markdown.as_deref());
-                let prompt = manifest
-                    .and_then(|m| m.prompt)
-                    .or_else(|| markdown.clone())
-                    .unwrap_or_default();
-
-                return Ok(SkillDetail {
-                    info,
-                    prompt,
-                    markdown,
-                });
+                return Ok(skill_detail(name, &source, files));
             }
         }
     }
 
     Err(format!("Skill not found: {}", name))
 }
+
+/// Path of the active note relative to the workspace, with `/` separators.
+fn workspace_relative_path(workspace_path: Option<&str>, note_path: &str) -> String {
+    let path = Path::new(note_path);
+    let relative = workspace_path
+        .and_then(|workspace| path.strip_prefix(workspace).ok())
+        .unwrap_or(path);
+    relative.to_string_lossy().replace('\\', "/")
+}
+
+/// Whether a skill's `triggers` match the task text (case-insensitive) or its
+/// `globs` match the active note path.
+pub fn skill_matches(info: &SkillInfo, task: &str, note_path: Option<&str>) -> bool {
+    let task = task.to_lowercase();
+    let triggered = info.triggers.iter().flatten().any(|trigger| {
+        let trigger = trigger.trim().to_lowercase();
+        !trigger.is_empty() && task.contains(&trigger)
+    });
+    if triggered {
+        return true;
+    }
+
+    let (Some(note_path), Some(globs)) = (note_path, info.globs.as_ref()) else {
+        return false;
+    };
+    let mut builder = GlobSetBuilder::new();
+    for pattern in globs {
+        if let Ok(glob) = Glob::new(pattern) {
+            builder.add(glob);
+        }
+    }
+    builder
+        .build()
+        .map(|set| set.is_match(note_path))
+        .unwrap_or(false)
+}
+
+/// Skills that auto-activate for this task: valid skills (not already
+/// selected) whose triggers or globs match. Skills without triggers or globs
+/// are only used when selected explicitly.
+pub fn auto_activated_skills(
+    app: &AppHandle,
+    workspace_path: Option<&str>,
+    task: &str,
+    active_note_path: Option<&str>,
+    selected: &[String],
+) -> Vec<SkillDetail> {
+    let note_path = active_note_path.map(|path| workspace_relative_path(workspace_path, path));
+    list_skills(app, workspace_path)
+        .into_iter()
+        .filter(|info| info.errors.is_empty() && !selected.contains(&info.name))
+        .filter(|info| skill_matches(info, task, note_path.as_deref()))
+        .filter_map(|info| read_skill(app, workspace_path, &info.name).ok())
+        .collect()
+}
+
+/// Every available skill with its prompt, in `list_skills` order.
+pub fn load_skills(app: &AppHandle, workspace_path: Option<&str>) -> Vec<SkillDetail> {
+    list_skills(app, workspace_path)
+        .into_iter()
+        .filter_map(|info| read_skill(app, workspace_path, &info.name).ok())
+        .collect()
+}
+
+pub fn skill_context(detail: SkillDetail) -> SkillContext {
+    SkillContext {
+        name: detail.info.name,
+        title: Some(detail.info.title),
+        description: detail.info.description,
+        prompt: detail.prompt,
+        source: Some(detail.info.source),
+    }
+}
+
+/// Characters of each RAG chunk / mentioned note used for scoring.
+const CONTEXT_SNIPPET_CHARS: usize = 500;
+/// Matching this many skill terms in the task gives the full lexical score.
+const FULL_MATCH_TERMS: usize = 5;
+
+const STOP_WORDS: &[&str] = &[
+    "about", "and", "are", "can", "content", "for", "from", "into", "provided", "should", "such",
+    "that", "the", "this", "use", "user", "using", "when", "will", "with", "you", "your",
+];
+
+/// Collects scoring terms: lowercased ASCII words of 3+ chars (with naive
+/// plural folding) and character bigrams for CJK runs.
+#[derive(Default)]
+struct TermCollector {
+    terms: HashSet<String>,
+    word: String,
+    cjk_run: Vec<char>,
+}
+
+impl TermCollector {
+    fn flush_word(&mut self) {
+        let word = std::mem::take(&mut self.word);
+        if word.len() < 3 || STOP_WORDS.contains(&word.as_str()) {
+            return;
+        }
+        let folded = match word.strip_suffix('s') {
+            Some(stem) if stem.len() >= 3 && !stem.ends_with('s') => stem,
+            _ => word.as_str(),
+        };
+        self.terms.insert(folded.to_string());
+    }
+
+    fn flush_cjk(&mut self) {
+        let run = std::mem::take(&mut self.cjk_run);
+        if run.len() == 1 {
+            self.terms.insert(run[0].to_string());
+        }
+        for pair in run.windows(2) {
+            self.terms.insert(pair.iter().collect());
+        }
+    }
+}
+
+fn terms(text: &str) -> HashSet<String> {
+    let mut collector = TermCollector::default();
+    for ch in text.chars() {
+        if ch.is_ascii_alphanumeric() {
+            collector.flush_cjk();
+            collector.word.push(ch.to_ascii_lowercase());
+        } else if is_cjk(ch) {
+            collector.flush_word();
+            collector.cjk_run.push(ch);
+        } else {
+            collector.flush_word();
+            collector.flush_cjk();
+        }
+    }
+    collector.flush_word();
+    collector.flush_cjk();
+    collector.terms
+}
+
+/// Text of the RAG results and mentioned notes used as secondary evidence.
+fn context_text(context: &TaskContext) -> String {
+    let rag = context.rag_results.iter().map(|result| {
+        let snippet: String = result.content.chars().take(CONTEXT_SNIPPET_CHARS).collect();
+        format!(
+            "{} {} {}",
+            result.file_path,
+            result.heading.as_deref().unwrap_or_default(),
+            snippet
+        )
+    });
+    let links = context.resolved_links.iter().map(|link| {
+        let snippet: String = link.content.chars().take(CONTEXT_SNIPPET_CHARS).collect();
+        format!("{} {}", link.link_name, snippet)
+    });
+    rag.chain(links).collect::<Vec<_>>().join("\n")
+}
+
+/// Relevance of a skill to the task, from 0.0 to 1.0.
+///
+/// Matching triggers/globs score 1.0. Otherwise the skill's name, title,
+/// description and tags are compared with the task (full weight) and the
+/// RAG / mentioned-note context (half weight).
+pub fn score_skill(
+    info: &SkillInfo,
+    task: &str,
+    task_terms: &HashSet<String>,
+    context_terms: &HashSet<String>,
+    note_path: Option<&str>,
+) -> f32 {
+    if skill_matches(info, task, note_path) {
+        return 1.0;
+    }
+    let metadata = format!(
+        "{} {} {} {}",
+        info.name.replace('-', " "),
+        info.title,
+        info.description.as_deref().unwrap_or_default(),
+        info.tags
+            .iter()
+            .flatten()
+            .cloned()
+            .collect::<Vec<_>>()
+            .join(" ")
+    );
+    let skill_terms = terms(&metadata);
+    if skill_terms.is_empty() {
+        return 0.0;
+    }
+    let task_hits = skill_terms.intersection(task_terms).count() as f32;
+    let context_hits = skill_terms
+        .iter()
+        .filter(|term| !task_terms.contains(*term) && context_terms.contains(*term))
+        .count() as f32;
+    let denominator = skill_terms.len().min(FULL_MATCH_TERMS) as f32;
+    ((task_hits + 0.5 * context_hits) / denominator).min(1.0)
+}
+
+/// Pick up to `top_k` valid skills scoring at least `min_score` for the
+/// task, best first.
+pub fn select_skills(
+    task: &str,
+    context: &TaskContext,
+    skills: &[SkillDetail],
+    top_k: usize,
+    min_score: f32,
+) -> Vec<SkillContext> {
+    if top_k == 0 {
+        return Vec::new();
+    }
+    let task_terms = terms(task);
+    let context_terms = terms(&context_text(context));
+    let note_path = context
+        .active_note_path
+        .as_deref()
+        .map(|path| workspace_relative_path(Some(&context.workspace_path), path));
+
+    let mut scored: Vec<(f32, &SkillDetail)> = skills
+        .iter()
+        .filter(|detail| detail.info.errors.is_empty())
+        .map(|detail| {
+            let score = score_skill(
+                &detail.info,
+                task,
+                &task_terms,
+                &context_terms,
+                note_path.as_deref(),
+            );
+            (score, detail)
+        })
+        .filter(|(score, _)| *score > 0.0 && *score >= min_score)
+        .collect();
+    scored.sort_by(|a, b| {
+        b.0.total_cmp(&a.0)
+            .then_with(|| a.1.info.name.cmp(&b.1.info.name))
+    });
+    scored
+        .into_iter()
+        .take(top_k)
+        .map(|(_, detail)| skill_context(detail.clone()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_skill(root: &Path, dir: &str, files: &[(&str, &str)]) -> PathBuf {
+        let path = root.join(dir);
+        fs::create_dir_all(&path).expect("create skill dir");
+        for (name, content) in files {
+            fs::write(path.join(name), content).expect("write skill file");
+        }
+        path
+    }
+
+    #[test]
+    fn frontmatter_supplies_metadata_and_is_stripped_from_prompt() {
+        let root = tempfile::tempdir().expect("tempdir");
+        let dir = write_skill(
+            root.path(),
+            "meeting-notes",
+            &[(
+                "SKILL.md",
+                "---\nname: meeting-notes\ndescription: Tidy meeting notes\nversion: 1.2.0\ntriggers: [meeting, minutes]\nglobs: [\"Meetings/**/*.md\"]\n---\n# Meeting Notes\n\nUse action items.\n",
+            )],
+        );
+
+        let detail = skill_detail("meeting-notes", "workspace", read_skill_files(&dir));
+        assert!(detail.info.errors.is_empty(), "{:?}", detail.info.errors);
+        assert_eq!(detail.info.title, "Meeting Notes");
+        assert_eq!(
+            detail.info.description.as_deref(),
+            Some("Tidy meeting notes")
+        );
+        assert_eq!(detail.info.version.as_deref(), Some("1.2.0"));
+        assert!(detail.prompt.starts_with("# Meeting Notes"));
+    }
+
+    #[test]
+    fn malformed_skills_report_errors_instead_of_dropping_them() {
+        let root = tempfile::tempdir().expect("tempdir");
+        write_skill(root.path(), "broken-json", &[("skill.json", "{\"name\": ")]);
+        write_skill(
+            root.path(),
+            "bad-fields",
+            &[(
+                "SKILL.md",
+                "---\nname: Bad Name\nversion: v1\nglobs: [\"[\"]\n---\nBody\n",
+            )],
+        );
+        write_skill(root.path(), "unclosed", &[("SKILL.md", "---\nname: x\n")]);
+
+        let mut skills = list_skills_in_root(root.path(), "workspace");
+        skills.sort_by(|a, b| a.name.cmp(&b.name));
+        let errors: HashMap<&str, &Vec<String>> = skills
+            .iter()
+            .map(|info| (info.name.as_str(), &info.errors))
+            .collect();
+
+        assert!(errors["broken-json"][0].starts_with("skill.json:"));
+        let bad = errors["Bad Name"].join("\n");
+        assert!(bad.contains("`name`"));
+        assert!(bad.contains("`description`"));
+        assert!(bad.contains("`version`"));
+        assert!(bad.contains("invalid glob"));
+        assert!(errors["unclosed"][0].contains("closing `---`"));
+    }
+
+    #[test]
+    fn triggers_and_globs_control_auto_activation() {
+        let info = SkillInfo {
+            name: "pdf".to_string(),
+            title: "PDF".to_string(),
+            description: None,
+            version: None,
+            tags: None,
+            source: "builtin".to_string(),
+            triggers: Some(vec!["PDF".to_string()]),
+            globs: Some(vec!["**/*.pdf".to_string()]),
+            errors: Vec::new(),
+        };
+
+        assert!(skill_matches(&info, "Merge these pdf files", None));
+        assert!(skill_matches(&info, "summarize", Some("papers/a.pdf")));
+        assert!(!skill_matches(&info, "summarize", Some("notes/a.md")));
+        assert_eq!(
+            workspace_relative_path(Some("/vault"), "/vault/papers/a.pdf"),
+            "papers/a.pdf"
+        );
+
+        let manual = SkillInfo {
+            triggers: None,
+            globs: None,
+            ..info
+        };
+        assert!(!skill_matches(
+            &manual,
+            "Merge these pdf files",
+            Some("a.pdf")
+        ));
+    }
+
+    fn detail(name: &str, description: &str, tags: &[&str]) -> SkillDetail {
+        SkillDetail {
+            info: SkillInfo {
+                name: name.to_string(),
+                title: name.to_string(),
+                description: Some(description.to_string()),
+                version: None,
+                tags: Some(tags.iter().map(|tag| tag.to_string()).collect()),
+                source: "builtin".to_string(),
+                triggers: None,
+                globs: None,
+                errors: Vec::new(),
+            },
+            prompt: format!("{} prompt", name),
+            markdown: None,
+        }
+    }
+
+    fn task_context(rag_content: &str) -> TaskContext {
+        serde_json::from_value(serde_json::json!({
+            "workspace_path": "/vault",
+            "rag_results": [{ "file_path": "a.md", "content": rag_content, "score": 0.9 }],
+        }))
+        .expect("task context")
+    }
+
+    #[test]
+    fn select_skills_ranks_by_task_and_context_relevance() {
+        let skills = vec![
+            detail(
+                "summarize-note",
+                "Summarize the provided content into clear bullet points.",
+                &["summary", "notes"],
+            ),
+            detail(
+                "extract-todos",
+                "Extract action items and TODOs from notes.",
+                &["todo", "tasks"],
+            ),
+            detail("polish-writing", "润色文章，改进表达。", &["写作"]),
+        ];
+
+        let picked = select_skills(
+            "Please summarize this note as bullet points",
+            &task_context(""),
+            &skills,
+            2,
+            0.4,
+        );
+        assert_eq!(picked.len(), 1);
+        assert_eq!(picked[0].name, "summarize-note");
+        assert_eq!(picked[0].prompt, "summarize-note prompt");
+
+        let picked = select_skills("帮我润色这篇文章", &task_context(""), &skills, 2, 0.4);
+        assert_eq!(picked[0].name, "polish-writing");
+
+        // The task alone is vague; the RAG context supplies the evidence
+        let context = task_context("TODO: extract action items for the launch");
+        let picked = select_skills("go through my notes", &context, &skills, 1, 0.4);
+        assert_eq!(picked[0].name, "extract-todos");
+
+        assert!(select_skills("hello", &task_context(""), &skills, 2, 0.4).is_empty());
+        assert!(select_skills("summarize", &task_context(""), &skills, 0, 0.0).is_empty());
+    }
+}
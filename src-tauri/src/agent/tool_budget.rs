@@ -0,0 +1,258 @@
+//! 工具输出预算
+//!
+//! 目录列表、grep 结果等工具输出可能非常大，原样放进消息会挤占上下文窗口。
+//! 单个工具结果的上限按可用上下文的比例计算（可按工具名覆盖），
+//! 超出时按行保留开头与结尾，中间替换为 `[truncated, N more lines]` 标记。
+//! 完整输出写入 `.lumina/tool-outputs/<session>/<call>.txt`，
+//! 标记中告诉模型用 read 工具按 offset/limit 分页读取被省略的行。
+
+use crate::agent::compaction::usable_context_window;
+use crate::agent::types::AgentConfig;
+use crate::llm_pricing::estimate_tokens;
+use std::path::{Path, PathBuf};
+
+/// 完整工具输出的保存目录（相对工作区）
+const TOOL_OUTPUT_DIR: &str = ".lumina/tool-outputs";
+/// 预算下限，避免小窗口模型连一屏结果都看不到
+const MIN_OUTPUT_TOKENS: usize = 500;
+/// 截断时开头所占的比例，其余留给结尾（错误、汇总行通常在末尾）
+const HEAD_RATIO: f64 = 0.7;
+
+/// 一次按行截断的结果
+#[derive(Debug, Clone, PartialEq)]
+pub struct Truncation {
+    pub head: String,
+    pub tail: String,
+    /// 开头完整保留的行数，即被省略部分在原输出中的起始偏移（0 起）
+    pub head_lines: usize,
+    pub omitted_lines: usize,
+}
+
+/// 本次运行的工具输出预算
+pub struct ToolOutputBudget {
+    ratio_tokens: Option<usize>,
+    overrides: Vec<(String, usize)>,
+    spill_dir: PathBuf,
+}
+
+pub fn tool_output_dir(workspace: &Path, session_id: &str) -> PathBuf {
+    let name: String = session_id
+        .chars()
+        .map(|ch| {
+            if ch.is_ascii_alphanumeric() || ch == '-' || ch == '_' {
+                ch
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    workspace.join(TOOL_OUTPUT_DIR).join(name)
+}
+
+impl ToolOutputBudget {
+    pub fn new(config: &AgentConfig, workspace: &Path, session_id: &str) -> Self {
+        let ratio_tokens = (config.tool_output_ratio > 0.0).then(|| {
+            let usable = usable_context_window(config) as f64;
+            (usable * config.tool_output_ratio.min(1.0) as f64) as usize
+        });
+        Self {
+            ratio_tokens,
+            overrides: config
+                .tool_output_limits
+                .iter()
+                .map(|(tool, limit)| (tool.clone(), *limit))
+                .collect(),
+            spill_dir: tool_output_dir(workspace, session_id),
+        }
+    }
+
+    /// 工具输出的 token 上限；None 表示不限制
+    pub fn limit_for(&self, tool: &str) -> Option<usize> {
+        let tokens = match self.overrides.iter().find(|(name, _)| name == tool) {
+            Some((_, limit)) => Some(*limit).filter(|limit| *limit > 0),
+            None => self.ratio_tokens,
+        }?;
+        Some(tokens.max(MIN_OUTPUT_TOKENS))
+    }
+
+    /// 超出预算时截断输出，并保存完整内容供模型分页读取
+    pub fn apply(&self, tool: &str, call_id: &str, content: String) -> String {
+        let Some(limit) = self.limit_for(tool) else {
+            return content;
+        };
+        let Some(truncation) = truncate_lines(&content, limit) else {
+            return content;
+        };
+        let follow_up = match self.spill(call_id, &content) {
+            Ok(relative) => format!(
+                " Full output saved to `{}`; call read with {{\"filePath\": \"{}\", \"offset\": {}, \"limit\": {}}} to page through the omitted lines.",
+                relative,
+                relative,
+                truncation.head_lines,
+                truncation.head_lines.max(50)
+            ),
+            Err(err) => {
+                eprintln!("[Agent] Failed to save full tool output: {}", err);
+                String::new()
+            }
+        };
+        format!(
+            "{}\n[truncated, {} more lines]{}\n{}",
+            truncation.head, truncation.omitted_lines, follow_up, truncation.tail
+        )
+        .trim_end()
+        .to_string()
+    }
+
+    fn spill(&self, call_id: &str, content: &str) -> Result<String, String> {
+        std::fs::create_dir_all(&self.spill_dir)
+            .map_err(|e| format!("Failed to create tool output dir: {}", e))?;
+        let name: String = call_id
+            .chars()
+            .map(|ch| if ch.is_ascii_alphanumeric() { ch } else { '_' })
+            .collect();
+        let path = self.spill_dir.join(format!("{}.txt", name));
+        std::fs::write(&path, content)
+            .map_err(|e| format!("Failed to write tool output: {}", e))?;
+        let session_dir = self
+            .spill_dir
+            .file_name()
+            .map(|dir| dir.to_string_lossy().to_string())
+            .unwrap_or_default();
+        Ok(format!("{}/{}/{}.txt", TOOL_OUTPUT_DIR, session_dir, name))
+    }
+}
+
+/// 按行截断到约 `max_tokens`：保留开头与结尾的整行，省略中间
+///
+/// 单行就超出开头预算（如压缩后的 JSON）时按字符截断该行。未超出预算返回 None。
+pub fn truncate_lines(text: &str, max_tokens: usize) -> Option<Truncation> {
+    if estimate_tokens(text) <= max_tokens {
+        return None;
+    }
+    let lines: Vec<&str> = text.lines().collect();
+    let head_budget = (max_tokens as f64 * HEAD_RATIO) as usize;
+    let tail_budget = max_tokens - head_budget;
+
+    let mut used = 0;
+    let head_lines = lines
+        .iter()
+        .take_while(|line| {
+            used += estimate_tokens(line) + 1;
+            used <= head_budget
+        })
+        .count();
+    let mut used = 0;
+    let tail_lines = lines[head_lines..]
+        .iter()
+        .rev()
+        .take_while(|line| {
+            used += estimate_tokens(line) + 1;
+            used <= tail_budget
+        })
+        .count()
+        // 至少省略一行，标记才有意义
+        .min(lines.len().saturating_sub(head_lines + 1));
+
+    let mut head = lines[..head_lines].join("\n");
+    if head_lines == 0 {
+        if let Some(first) = lines.first() {
+            head = truncate_chars(first, head_budget);
+        }
+    }
+    Some(Truncation {
+        head,
+        tail: lines[lines.len() - tail_lines..].join("\n"),
+        head_lines,
+        omitted_lines: lines.len() - head_lines - tail_lines,
+    })
+}
+
+/// 取不超过 `max_tokens` 的字符前缀
+fn truncate_chars(line: &str, max_tokens: usize) -> String {
+    // 估算值随前缀变长单调不减，二分查找最长的前缀，避免超长行逐字重算
+    let ends: Vec<usize> = line
+        .char_indices()
+        .map(|(index, ch)| index + ch.len_utf8())
+        .collect();
+    let fits = ends.partition_point(|end| estimate_tokens(&line[..*end]) <= max_tokens);
+    let end = fits.checked_sub(1).map(|index| ends[index]).unwrap_or(0);
+    line[..end].to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn numbered(count: usize) -> String {
+        (1..=count)
+            .map(|n| format!("line {:04}", n))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn keeps_head_and_tail_on_line_boundaries() {
+        let text = numbered(1000);
+        let truncation = truncate_lines(&text, 500).unwrap();
+
+        assert!(truncation.head.starts_with("line 0001\n"));
+        assert!(truncation.tail.ends_with("line 1000"));
+        assert!(truncation.head.lines().all(|line| line.len() == 9));
+        assert!(truncation.tail.lines().all(|line| line.len() == 9));
+        let kept = truncation.head.lines().count() + truncation.tail.lines().count();
+        assert_eq!(kept + truncation.omitted_lines, 1000);
+        assert!(estimate_tokens(&truncation.head) + estimate_tokens(&truncation.tail) <= 500);
+        assert!(truncation.head.lines().count() > truncation.tail.lines().count());
+
+        assert!(truncate_lines(&numbered(10), 500).is_none());
+    }
+
+    #[test]
+    fn cuts_a_single_long_line_by_chars() {
+        let text = "中".repeat(2000);
+        let truncation = truncate_lines(&text, 100).unwrap();
+        assert_eq!(truncation.head, "中".repeat(70));
+        assert_eq!((truncation.head_lines, truncation.omitted_lines), (0, 1));
+        assert!(truncation.tail.is_empty());
+    }
+
+    #[test]
+    fn budget_scales_with_context_window_and_saves_full_output() {
+        let workspace = tempfile::tempdir().unwrap();
+        let config = AgentConfig {
+            context_window: 128_000,
+            max_tokens: 8_000,
+            tool_output_ratio: 0.1,
+            tool_output_limits: [("grep".to_string(), 600), ("read".to_string(), 0)]
+                .into_iter()
+                .collect(),
+            ..AgentConfig::default()
+        };
+        let budget = ToolOutputBudget::new(&config, workspace.path(), "s1");
+        assert_eq!(budget.limit_for("list"), Some(12_000));
+        assert_eq!(budget.limit_for("grep"), Some(600));
+        assert_eq!(budget.limit_for("read"), None);
+
+        let small = ToolOutputBudget::new(
+            &AgentConfig {
+                context_window: 8_000,
+                max_tokens: 4_000,
+                ..config.clone()
+            },
+            workspace.path(),
+            "s1",
+        );
+        assert_eq!(small.limit_for("list"), Some(MIN_OUTPUT_TOKENS));
+
+        let text = numbered(1000);
+        let output = budget.apply("grep", "call-1", text.clone());
+        assert!(output
+            .contains("more lines] Full output saved to `.lumina/tool-outputs/s1/call_1.txt`"));
+        assert!(output.ends_with("line 1000"));
+        let saved = workspace.path().join(".lumina/tool-outputs/s1/call_1.txt");
+        assert_eq!(std::fs::read_to_string(saved).unwrap(), text);
+
+        assert_eq!(budget.apply("read", "call-2", text.clone()), text);
+    }
+}
@@ -202,6 +202,9 @@ pub struct GraphState {
     /// 错误信息
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,
+    /// 本次任务累计消耗的 tokens（跨审批恢复累计）
+    #[serde(default)]
+    pub tokens_used: usize,
 }
 
 /// Agent 配置
@@ -222,6 +225,15 @@ pub struct AgentConfig {
     /// 思考模式
     #[serde(default = "default_thinking_mode")]
     pub thinking_mode: ThinkingMode,
+    /// 工具调用模式（原生 Function Calling / XML 工具协议）
+    #[serde(default)]
+    pub tool_call_mode: ToolCallMode,
+    /// 推理内容的展示方式
+    #[serde(default)]
+    pub reasoning_mode: ReasoningMode,
+    /// 响应缓存模式（仅对 temperature 为 0 的请求生效）
+    #[serde(default)]
+    pub cache: crate::llm_cache::CacheMode,
     /// 最大 tokens
     #[serde(default = "default_max_tokens")]
     pub max_tokens: usize,
@@ -231,9 +243,42 @@ pub struct AgentConfig {
     /// 最大步骤数（0 表示无限制）
     #[serde(default = "default_max_steps")]
     pub max_steps: usize,
+    /// 单次任务 token 预算（0 表示无限制），超出后要求模型立即收尾
+    #[serde(default = "default_token_budget")]
+    pub token_budget: usize,
+    /// 单次任务费用预算（美元，0 表示不限制），接近时提醒
+    #[serde(default)]
+    pub cost_budget: f64,
+    /// 模型上下文窗口（tokens，0 表示未知，按保守默认值处理）
+    #[serde(default)]
+    pub context_window: usize,
+    /// prompt 估算 token 超过上下文窗口（扣除 max_tokens）的该比例时压缩较早的消息（0 表示不压缩）
+    #[serde(default = "default_compact_threshold")]
+    pub compact_threshold: f32,
+    /// 单个工具结果最多占可用上下文的比例，超出时截断（0 表示不限制）
+    #[serde(default = "default_tool_output_ratio")]
+    pub tool_output_ratio: f32,
+    /// 按工具名覆盖的输出上限（tokens，0 表示该工具不限制）
+    #[serde(default)]
+    pub tool_output_limits: HashMap<String, usize>,
+    /// 未显式选择 skills 时自动注入的最大数量（0 表示不自动选择）
+    #[serde(default = "default_skill_top_k")]
+    pub skill_top_k: usize,
+    /// 自动选择 skill 的最低相关度（0-1）
+    #[serde(default = "default_skill_min_score")]
+    pub skill_min_score: f32,
     /// 是否自动审批
     #[serde(default)]
     pub auto_approve: bool,
+    /// 试运行：写入类工具（write / edit / bash）只返回模拟结果并发出 dry_run_change 事件，不修改磁盘
+    #[serde(default)]
+    pub dry_run: bool,
+    /// RAG 结果的交叉编码器重排序（未启用时保持向量检索顺序）
+    #[serde(default)]
+    pub rerank: crate::vector_db::rerank::RerankConfig,
+    /// 查询向量化配置，供 find_note 等工具做语义检索（未启用时只按名称与标题匹配）
+    #[serde(default)]
+    pub embedding: crate::vector_db::embedding::EmbeddingConfig,
     /// 语言
     #[serde(default = "default_locale")]
     pub locale: String,
@@ -255,6 +300,21 @@ fn default_max_plan_iterations() -> usize {
 fn default_max_steps() -> usize {
     0
 }
+fn default_token_budget() -> usize {
+    0
+}
+fn default_compact_threshold() -> f32 {
+    0.8
+}
+fn default_tool_output_ratio() -> f32 {
+    0.15
+}
+fn default_skill_top_k() -> usize {
+    2
+}
+fn default_skill_min_score() -> f32 {
+    0.4
+}
 fn default_locale() -> String {
     "zh-CN".to_string()
 }
@@ -273,6 +333,43 @@ impl Default for ThinkingMode {
     }
 }
 
+/// 工具调用模式
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ToolCallMode {
+    /// 按 provider 默认能力选择
+    Auto,
+    /// 强制使用原生 Function Calling
+    Native,
+    /// 强制使用 XML 工具协议
+    Xml,
+}
+
+impl Default for ToolCallMode {
+    fn default() -> Self {
+        Self::Auto
+    }
+}
+
+/// 推理内容（reasoning / `<think>` 块）的展示方式；最终结果始终只保存回答
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReasoningMode {
+    /// 流式展示，并以 `<thinking>` 块保留在最终消息中
+    Surface,
+    /// 仅在生成过程中流式展示，最终消息不保留
+    #[default]
+    Collapse,
+    /// 不展示也不保留
+    Discard,
+}
+
+impl ReasoningMode {
+    pub fn streams(self) -> bool {
+        self != Self::Discard
+    }
+}
+
 impl Default for AgentConfig {
     fn default() -> Self {
         Self {
@@ -282,10 +379,24 @@ impl Default for AgentConfig {
             base_url: None,
             temperature: default_temperature(),
             thinking_mode: default_thinking_mode(),
+            tool_call_mode: ToolCallMode::default(),
+            reasoning_mode: ReasoningMode::default(),
+            cache: Default::default(),
             max_tokens: default_max_tokens(),
             max_plan_iterations: default_max_plan_iterations(),
             max_steps: default_max_steps(),
+            token_budget: default_token_budget(),
+            cost_budget: 0.0,
+            context_window: 0,
+            compact_threshold: default_compact_threshold(),
+            tool_output_ratio: default_tool_output_ratio(),
+            tool_output_limits: HashMap::new(),
+            skill_top_k: default_skill_top_k(),
+            skill_min_score: default_skill_min_score(),
             auto_approve: false,
+            dry_run: false,
+            rerank: Default::default(),
+            embedding: Default::default(),
             locale: default_locale(),
         }
     }
@@ -322,12 +433,41 @@ pub enum AgentEvent {
         completion_tokens: usize,
         total_tokens: usize,
     },
+    /// 上下文接近模型窗口，较早的消息已被总结压缩
+    ContextCompacted {
+        removed_messages: usize,
+        tokens_before: usize,
+        tokens_after: usize,
+    },
+    /// Token 预算耗尽，Agent 将被要求立即收尾
+    TokenBudgetExceeded { used_tokens: usize, budget: usize },
+    /// 费用更新（美元）
+    CostUpdate {
+        step_cost: f64,
+        total_cost: f64,
+        prompt_tokens: usize,
+        completion_tokens: usize,
+        estimated: bool,
+        unpriced: bool,
+        budget: f64,
+        near_budget: bool,
+    },
+    /// 试运行中写入类工具的模拟变更（供前端预览 diff）
+    DryRunChange {
+        change: crate::forge_runtime::dry_run::SimulatedChange,
+    },
     /// 任务完成
     Complete { result: String },
     /// 错误
     Error { message: String },
     /// 等待工具审批
     WaitingApproval { tool: ToolCall, request_id: String },
+    /// Agent 通过 ask_user 提问，等待用户回答（agent_continue_with_answer）
+    QuestionAsked {
+        question: String,
+        options: Vec<String>,
+        request_id: String,
+    },
     /// LLM 请求开始（用于超时检测）
     LlmRequestStart { request_id: String, timestamp: u64 },
     /// LLM 请求结束
@@ -341,6 +481,15 @@ pub enum AgentEvent {
         reason: String,
         next_retry_at: u64,
     },
+    /// 触发 provider 限流（429），等待后重试
+    RateLimited {
+        request_id: String,
+        attempt: u32,
+        max_retries: u32,
+        delay_ms: u64,
+        reason: String,
+        next_retry_at: u64,
+    },
     /// 心跳（用于连接状态监控）
     Heartbeat { timestamp: u64 },
     /// 队列状态变化
@@ -370,6 +519,28 @@ pub struct QueuedTaskSummary {
     pub workspace_path: String,
     pub enqueued_at: u64,
     pub position: usize,
+    #[serde(default)]
+    pub priority: TaskPriority,
+}
+
+/// 排队任务的优先级：高优先级先运行，同优先级按队列顺序（FIFO）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskPriority {
+    Low,
+    #[default]
+    Normal,
+    High,
+}
+
+impl TaskPriority {
+    pub fn rank(self) -> usize {
+        match self {
+            Self::Low => 0,
+            Self::Normal => 1,
+            Self::High => 2,
+        }
+    }
 }
 
 /// Agent 队列快照
@@ -416,6 +587,10 @@ pub struct TaskContext {
     pub skills: Vec<SkillContext>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub mobile_session_id: Option<String>,
+    /// 持久化会话 id：事件写入 `.lumina/sessions/<id>.jsonl`；
+    /// history 为空时从该会话恢复多轮对话
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
 }
 
 // ============ 实现 Forge GraphState trait ============
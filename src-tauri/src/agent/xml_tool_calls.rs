@@ -0,0 +1,762 @@
+//! XML 工具调用解析
+//!
+//! 不支持 Function Calling 的 provider（如 Ollama）以 XML 文本返回工具调用：
+//!
+//! ```text
+//! <edit>
+//! <filePath>notes/a.md</filePath>
+//! <oldString>foo</oldString>
+//! <newString><![CDATA[<div>bar</div>]]></newString>
+//! </edit>
+//! ```
+//!
+//! 解析器按元素结构逐层读取，而不是简单查找字符串：
+//! - 只有位于顶层、名称属于可用工具的元素才被视为工具调用
+//! - 参数名不做限制；也支持属性形式（`<read filePath="a.md"/>`）和
+//!   `<parameter name="...">` 形式
+//! - 参数值支持 CDATA 与 `&lt;`/`&gt;`/`&amp;` 等实体转义，值中出现的工具名标签不会误触发
+//! - 参数值按工具 schema 声明的类型还原（`"007"` 声明为 string 时不会变成数字）；
+//!   schema 未声明类型时才按 JSON 字面量推断
+//!
+//! ## 转义约定
+//!
+//! 参数值含有 `<`、`>` 或 `&` 时（例如包含 HTML 片段的笔记），整段包裹在
+//! `<![CDATA[ ... ]]>` 中原样传递；值本身含有 `]]>` 时拆分为相邻的两个 CDATA 段。
+//! 以换行开头/结尾或会被推断为非字符串（如 `007`、`true`）的值同样包裹在 CDATA 中，
+//! 保证 [`escape_value`] 与解析器互为逆操作。
+//! 也接受 `&lt;`/`&gt;`/`&amp;` 实体形式。schema 未声明类型时，CDATA 中的内容按字符串处理。
+//! [`format_tool_call`] 与 [`build_xml_tool_prompt`] 生成的文本均遵循该约定。
+
+use crate::agent::types::ToolCall;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+const CDATA_START: &str = "<![CDATA[";
+const CDATA_END: &str = "]]>";
+const COMMENT_START: &str = "<!--";
+const COMMENT_END: &str = "-->";
+/// 以 `name` 属性声明参数名的通用参数元素
+const NAMED_PARAM_TAGS: [&str; 3] = ["parameter", "param", "arg"];
+/// 顶层跳过的非工具块（推理内容中可能出现示例工具调用）
+const SKIPPED_BLOCKS: [&str; 2] = ["thinking", "think"];
+
+struct OpenTag {
+    name: String,
+    attrs: Vec<(String, String)>,
+    self_closing: bool,
+    /// 标签结束位置（`>` 之后）
+    end: usize,
+}
+
+/// 可用工具名 → 参数 schema（`parameters.properties`）
+type ToolSchemas<'a> = HashMap<&'a str, Option<&'a Map<String, Value>>>;
+
+fn collect_tool_schemas(tool_defs: &[Value]) -> ToolSchemas<'_> {
+    tool_defs
+        .iter()
+        .filter_map(|def| {
+            let function = &def["function"];
+            let name = function["name"].as_str()?;
+            Some((name, function["parameters"]["properties"].as_object()))
+        })
+        .collect()
+}
+
+/// 从模型输出中解析 XML 工具调用
+///
+/// `tool_defs` 为 Function Calling 格式的工具定义，用于识别工具名与还原参数类型
+pub fn parse_tool_calls(content: &str, tool_defs: &[Value]) -> Vec<ToolCall> {
+    let schemas = collect_tool_schemas(tool_defs);
+    let mut calls = Vec::new();
+    let mut pos = 0usize;
+
+    while let Some(rel) = content[pos..].find('<') {
+        let start = pos + rel;
+        let rest = &content[start..];
+
+        if rest.starts_with(CDATA_START) {
+            pos = skip_past(content, start, CDATA_END);
+            continue;
+        }
+        if rest.starts_with(COMMENT_START) {
+            pos = skip_past(content, start, COMMENT_END);
+            continue;
+        }
+
+        if let Some(tag) = parse_open_tag(content, start) {
+            if SKIPPED_BLOCKS.contains(&tag.name.as_str()) && !tag.self_closing {
+                pos = find_element_end(content, tag.end, &tag.name)
+                    .map(|(_, after)| after)
+                    .unwrap_or(content.len());
+                continue;
+            }
+            if let Some(tool_name) = resolve_tool_name(&tag.name, &schemas) {
+                let properties = schemas.get(tool_name.as_str()).copied().flatten();
+                if let Some((params, end)) = parse_tool_block(content, &tag, properties) {
+                    calls.push(ToolCall {
+                        id: format!("call_{}", calls.len()),
+                        name: tool_name,
+                        params,
+                    });
+                    pos = end;
+                    continue;
+                }
+            }
+        }
+
+        pos = start + 1;
+    }
+
+    calls
+}
+
+fn resolve_tool_name(name: &str, schemas: &ToolSchemas) -> Option<String> {
+    if schemas.contains_key(name) {
+        return Some(name.to_string());
+    }
+    let lower = name.to_lowercase();
+    if schemas.contains_key(lower.as_str()) {
+        return Some(lower);
+    }
+    None
+}
+
+fn skip_past(content: &str, from: usize, terminator: &str) -> usize {
+    content[from..]
+        .find(terminator)
+        .map(|idx| from + idx + terminator.len())
+        .unwrap_or(content.len())
+}
+
+fn is_name_start(ch: char) -> bool {
+    ch.is_ascii_alphabetic() || ch == '_'
+}
+
+fn is_name_char(ch: char) -> bool {
+    ch.is_ascii_alphanumeric() || matches!(ch, '_' | '-' | '.' | ':')
+}
+
+fn skip_whitespace(content: &str, mut pos: usize) -> usize {
+    while let Some(ch) = content[pos..].chars().next() {
+        if !ch.is_whitespace() {
+            break;
+        }
+        pos += ch.len_utf8();
+    }
+    pos
+}
+
+fn read_name(content: &str, start: usize) -> Option<(String, usize)> {
+    let mut chars = content[start..].char_indices();
+    let (_, first) = chars.next()?;
+    if !is_name_start(first) {
+        return None;
+    }
+    let mut end = start + first.len_utf8();
+    for (idx, ch) in chars {
+        if !is_name_char(ch) {
+            break;
+        }
+        end = start + idx + ch.len_utf8();
+    }
+    Some((content[start..end].to_string(), end))
+}
+
+/// 解析 `start` 处的开始标签（含属性与自闭合）；不是合法开始标签时返回 None
+fn parse_open_tag(content: &str, start: usize) -> Option<OpenTag> {
+    if !content[start..].starts_with('<') {
+        return None;
+    }
+    let (name, mut pos) = read_name(content, start + 1)?;
+    let mut attrs = Vec::new();
+
+    loop {
+        pos = skip_whitespace(content, pos);
+        let rest = &content[pos..];
+        if rest.starts_with("/>") {
+            return Some(OpenTag {
+                name,
+                attrs,
+                self_closing: true,
+                end: pos + 2,
+            });
+        }
+        if rest.starts_with('>') {
+            return Some(OpenTag {
+                name,
+                attrs,
+                self_closing: false,
+                end: pos + 1,
+            });
+        }
+
+        let (attr_name, after_name) = read_name(content, pos)?;
+        pos = skip_whitespace(content, after_name);
+        if !content[pos..].starts_with('=') {
+            attrs.push((attr_name, String::new()));
+            continue;
+        }
+        pos = skip_whitespace(content, pos + 1);
+        let quote = content[pos..].chars().next()?;
+        let (raw_value, after_value) = if quote == '"' || quote == '\'' {
+            let value_start = pos + 1;
+            let len = content[value_start..].find(quote)?;
+            (
+                &content[value_start..value_start + len],
+                value_start + len + 1,
+            )
+        } else {
+            let len = content[pos..]
+                .find(|ch: char| ch.is_whitespace() || ch == '>' || ch == '/')
+                .unwrap_or(content.len() - pos);
+            if len == 0 {
+                return None;
+            }
+            (&content[pos..pos + len], pos + len)
+        };
+        attrs.push((attr_name, decode_entities(raw_value)));
+        pos = after_value;
+    }
+}
+
+/// 判断 `pos` 处是否为 `</name>`，是则返回其结束位置
+fn match_close_tag(content: &str, pos: usize, name: &str) -> Option<usize> {
+    let rest = &content[pos..];
+    if !rest.starts_with("</") {
+        return None;
+    }
+    let (close_name, after) = read_name(content, pos + 2)?;
+    if close_name != name {
+        return None;
+    }
+    let after = skip_whitespace(content, after);
+    content[after..].starts_with('>').then_some(after + 1)
+}
+
+/// 查找与已打开元素 `name` 匹配的结束标签，正确处理同名嵌套与 CDATA。
+/// 返回（内容结束位置，结束标签之后的位置）
+fn find_element_end(content: &str, from: usize, name: &str) -> Option<(usize, usize)> {
+    let mut depth = 1usize;
+    let mut pos = from;
+
+    while let Some(rel) = content[pos..].find('<') {
+        let idx = pos + rel;
+        let rest = &content[idx..];
+
+        if rest.starts_with(CDATA_START) {
+            let end = content[idx..].find(CDATA_END)?;
+            pos = idx + end + CDATA_END.len();
+            continue;
+        }
+        if let Some(after) = match_close_tag(content, idx, name) {
+            depth -= 1;
+            if depth == 0 {
+                return Some((idx, after));
+            }
+            pos = after;
+            continue;
+        }
+        if let Some(tag) = parse_open_tag(content, idx) {
+            if tag.name == name && !tag.self_closing {
+                depth += 1;
+            }
+            pos = tag.end;
+            continue;
+        }
+        pos = idx + 1;
+    }
+
+    None
+}
+
+/// 解析工具元素内部的参数，返回（参数，工具块结束位置）
+fn parse_tool_block(
+    content: &str,
+    tag: &OpenTag,
+    properties: Option<&Map<String, Value>>,
+) -> Option<(HashMap<String, Value>, usize)> {
+    let declared = |key: &str| properties.and_then(|props| props.get(key));
+    let mut params: HashMap<String, Value> = tag
+        .attrs
+        .iter()
+        .map(|(key, value)| (key.clone(), typed_value(value, declared(key), false)))
+        .collect();
+
+    if tag.self_closing {
+        return Some((params, tag.end));
+    }
+
+    let mut pos = tag.end;
+    loop {
+        pos = skip_whitespace(content, pos);
+        if pos >= content.len() {
+            // 模型遗漏结束标签时，仅在已经解析到参数的情况下接受
+            return (!params.is_empty()).then_some((params, content.len()));
+        }
+        if let Some(after) = match_close_tag(content, pos, &tag.name) {
+            return Some((params, after));
+        }
+
+        let Some(param_tag) = parse_open_tag(content, pos) else {
+            // 参数之间的说明文字，跳到下一个标签
+            match content[pos..].find('<') {
+                Some(rel) if rel > 0 => {
+                    pos += rel;
+                    continue;
+                }
+                _ => {
+                    let next = content[pos..]
+                        .chars()
+                        .next()
+                        .map(char::len_utf8)
+                        .unwrap_or(1);
+                    pos += next;
+                    continue;
+                }
+            }
+        };
+
+        let key = if NAMED_PARAM_TAGS.contains(&param_tag.name.as_str()) {
+            param_tag
+                .attrs
+                .iter()
+                .find(|(attr, _)| attr == "name")
+                .map(|(_, value)| value.clone())
+                .unwrap_or_else(|| param_tag.name.clone())
+        } else {
+            param_tag.name.clone()
+        };
+
+        if param_tag.self_closing {
+            let value = param_tag
+                .attrs
+                .iter()
+                .find(|(attr, _)| attr == "value")
+                .map(|(_, value)| typed_value(value, declared(&key), false))
+                .unwrap_or_else(|| Value::String(String::new()));
+            params.insert(key, value);
+            pos = param_tag.end;
+            continue;
+        }
+
+        let (content_end, after) = find_element_end(content, param_tag.end, &param_tag.name)?;
+        let value = decode_value(&content[param_tag.end..content_end], declared(&key));
+        params.insert(key, value);
+        pos = after;
+    }
+}
+
+/// 解码参数原始文本：CDATA 原样保留，其余部分解码实体
+fn decode_value(raw: &str, schema: Option<&Value>) -> Value {
+    let mut segments: Vec<(bool, &str)> = Vec::new();
+    let mut pos = 0usize;
+    while let Some(rel) = raw[pos..].find(CDATA_START) {
+        let start = pos + rel;
+        let inner_start = start + CDATA_START.len();
+        let Some(len) = raw[inner_start..].find(CDATA_END) else {
+            break;
+        };
+        segments.push((false, &raw[pos..start]));
+        segments.push((true, &raw[inner_start..inner_start + len]));
+        pos = inner_start + len + CDATA_END.len();
+    }
+    segments.push((false, &raw[pos..]));
+
+    let has_cdata = segments.iter().any(|(is_cdata, _)| *is_cdata);
+    let mut value = String::new();
+    for (is_cdata, segment) in segments {
+        if is_cdata {
+            value.push_str(segment);
+        } else if !(has_cdata && segment.trim().is_empty()) {
+            value.push_str(&decode_entities(segment));
+        }
+    }
+
+    if !has_cdata {
+        // `<content>\n...\n</content>` 形式中标签旁的换行不属于值本身
+        let trimmed = value.strip_prefix('\n').unwrap_or(&value);
+        let trimmed = trimmed.strip_suffix('\n').unwrap_or(trimmed);
+        value = trimmed.to_string();
+    }
+
+    typed_value(&value, schema, has_cdata)
+}
+
+/// 按参数 schema 声明的类型还原参数值
+///
+/// 声明了 `type` 时只接受与之匹配的 JSON 字面量，其余一律保留为字符串（交给工具校验）；
+/// 未声明类型时才推断：非字符串 JSON（数字、布尔、数组、对象）按原类型还原，
+/// CDATA 视为字符串
+fn typed_value(value: &str, schema: Option<&Value>, is_cdata: bool) -> Value {
+    let declared: Vec<&str> = match schema.map(|schema| &schema["type"]) {
+        Some(Value::String(ty)) => vec![ty.as_str()],
+        Some(Value::Array(types)) => types.iter().filter_map(Value::as_str).collect(),
+        _ => Vec::new(),
+    };
+    if declared.is_empty() && is_cdata {
+        return Value::String(value.to_string());
+    }
+    if declared.len() == 1 && declared[0] == "string" {
+        return Value::String(value.to_string());
+    }
+
+    if let Ok(parsed) = serde_json::from_str::<Value>(value.trim()) {
+        let accepted = if declared.is_empty() {
+            matches!(
+                parsed,
+                Value::Number(_) | Value::Bool(_) | Value::Array(_) | Value::Object(_)
+            )
+        } else {
+            declared.iter().any(|ty| matches_type(&parsed, ty))
+        };
+        if accepted {
+            return parsed;
+        }
+    }
+    Value::String(value.to_string())
+}
+
+fn matches_type(value: &Value, ty: &str) -> bool {
+    match ty {
+        "integer" => value.is_i64() || value.is_u64(),
+        "number" => value.is_number(),
+        "boolean" => value.is_boolean(),
+        "array" => value.is_array(),
+        "object" => value.is_object(),
+        "null" => value.is_null(),
+        _ => false,
+    }
+}
+
+fn decode_entities(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_string();
+    }
+
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(idx) = rest.find('&') {
+        output.push_str(&rest[..idx]);
+        let candidate = &rest[idx..];
+        let decoded = candidate.find(';').and_then(|end| {
+            let entity = &candidate[1..end];
+            let ch = match entity {
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "amp" => Some('&'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                _ => entity
+                    .strip_prefix("#x")
+                    .or_else(|| entity.strip_prefix("#X"))
+                    .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                    .or_else(|| {
+                        entity
+                            .strip_prefix('#')
+                            .and_then(|dec| dec.parse::<u32>().ok())
+                    })
+                    .and_then(char::from_u32),
+            };
+            ch.map(|ch| (ch, end + 1))
+        });
+        match decoded {
+            Some((ch, consumed)) => {
+                output.push(ch);
+                rest = &candidate[consumed..];
+            }
+            None => {
+                output.push('&');
+                rest = &candidate[1..];
+            }
+        }
+    }
+    output.push_str(rest);
+    output
+}
+
+/// 按转义约定把工具调用序列化为 XML
+pub fn format_tool_call(name: &str, params: &serde_json::Map<String, Value>) -> String {
+    let mut output = format!("<{}>\n", name);
+    for (key, value) in params {
+        let text = match value {
+            Value::String(text) => escape_value(text),
+            _ => value.to_string(),
+        };
+        output.push_str(&format!("<{}>{}</{}>\n", key, text, key));
+    }
+    output.push_str(&format!("</{}>\n", name));
+    output
+}
+
+/// 需要时用 CDATA 包裹参数值；`]]>` 会被拆到两个 CDATA 段之间
+///
+/// 只有原样解析能得到同一字符串时才输出纯文本，因此即使没有 schema 也能无损还原
+pub fn escape_value(value: &str) -> String {
+    if !value.contains(['<', '>', '&']) && decode_value(value, None).as_str() == Some(value) {
+        return value.to_string();
+    }
+    format!(
+        "{}{}{}",
+        CDATA_START,
+        value.replace(CDATA_END, "]]]]><![CDATA[>"),
+        CDATA_END
+    )
+}
+
+/// 为不支持 Function Calling 的 provider 生成 XML 工具协议说明（含可用工具列表）
+pub fn build_xml_tool_prompt(tool_defs: &[Value]) -> String {
+    let mut prompt = String::from(
+        "Tool protocol (XML):\n\
+- Call a tool by writing an XML element named after the tool, with one child element per parameter.\n\
+- Parameter values that contain <, > or & (HTML, XML, generics, comparisons) MUST be wrapped in <![CDATA[ ... ]]> so they are passed through verbatim.\n\
+- Inside CDATA nothing is escaped. If a value itself contains ]]>, split it as ]]]]><![CDATA[>.\n\
+- Values are converted to the parameter's declared type; numbers and booleans may be written directly.\n\
+- Write tool calls at the top level of your reply, never inside <thinking>.\n\n\
+Example (editing a note that contains HTML):\n\
+<edit>\n\
+<filePath>notes/page.md</filePath>\n\
+<oldString><![CDATA[<div class=\"note\">Draft</div>]]></oldString>\n\
+<newString><![CDATA[<div class=\"note\">Final & reviewed</div>]]></newString>\n\
+</edit>\n",
+    );
+
+    if tool_defs.is_empty() {
+        return prompt;
+    }
+    prompt.push_str("\nAvailable tools:\n");
+    for def in tool_defs {
+        let function = &def["function"];
+        let Some(name) = function["name"].as_str() else {
+            continue;
+        };
+        let mut params = function["parameters"]["properties"]
+            .as_object()
+            .map(|props| props.keys().cloned().collect::<Vec<_>>())
+            .unwrap_or_default();
+        let required = function["parameters"]["required"]
+            .as_array()
+            .map(|items| {
+                items
+                    .iter()
+                    .filter_map(|item| item.as_str())
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        params.sort_by_key(|param| !required.contains(&param.as_str()));
+        let params = params
+            .iter()
+            .map(|param| {
+                if required.contains(&param.as_str()) {
+                    param.clone()
+                } else {
+                    format!("{}?", param)
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        let summary = function["description"]
+            .as_str()
+            .and_then(|desc| desc.lines().next())
+            .unwrap_or("")
+            .trim();
+        prompt.push_str(&format!("- {}({}): {}\n", name, params, summary));
+    }
+    prompt
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn tools(names: &[&str]) -> Vec<Value> {
+        names
+            .iter()
+            .map(|name| json!({ "type": "function", "function": { "name": name } }))
+            .collect()
+    }
+
+    #[test]
+    fn parses_arbitrary_params_and_types() {
+        let content = "I'll read it.\n<read>\n<filePath>notes/a.md</filePath>\n<offset>10</offset>\n<extra_flag>true</extra_flag>\n</read>";
+        let calls = parse_tool_calls(content, &tools(&["read"]));
+
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].name, "read");
+        assert_eq!(calls[0].params["filePath"], json!("notes/a.md"));
+        assert_eq!(calls[0].params["offset"], json!(10));
+        assert_eq!(calls[0].params["extra_flag"], json!(true));
+    }
+
+    #[test]
+    fn params_follow_declared_schema_types() {
+        let defs = vec![json!({
+            "type": "function",
+            "function": {
+                "name": "write",
+                "parameters": {
+                    "type": "object",
+                    "properties": {
+                        "content": { "type": "string" },
+                        "title": { "type": ["string", "null"] },
+                        "limit": { "type": "integer" },
+                        "append": { "type": "boolean" },
+                        "tags": { "type": "array" }
+                    }
+                }
+            }
+        })];
+        let content = "<write>\n<content>007</content>\n<title>true</title>\n<limit><![CDATA[5]]></limit>\n<append>yes</append>\n<tags>[\"a\"]</tags>\n<extra>42</extra>\n</write>";
+        let calls = parse_tool_calls(content, &defs);
+
+        assert_eq!(calls[0].params["content"], json!("007"));
+        assert_eq!(calls[0].params["title"], json!("true"));
+        assert_eq!(calls[0].params["limit"], json!(5));
+        assert_eq!(calls[0].params["append"], json!("yes"));
+        assert_eq!(calls[0].params["tags"], json!(["a"]));
+        assert_eq!(calls[0].params["extra"], json!(42));
+
+        let calls = parse_tool_calls(r#"<write content="12.5" limit="3"/>"#, &defs);
+        assert_eq!(calls[0].params["content"], json!("12.5"));
+        assert_eq!(calls[0].params["limit"], json!(3));
+    }
+
+    #[test]
+    fn tool_tags_inside_param_values_do_not_misfire() {
+        let content = "<edit_note>\n<path>a.md</path>\n<old_string>old</old_string>\n<new_string>Use <read_note><path>b.md</path></read_note> to read.</new_string>\n</edit_note>";
+        let calls = parse_tool_calls(content, &tools(&["edit_note", "read_note"]));
+
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].name, "edit_note");
+        assert_eq!(
+            calls[0].params["new_string"],
+            json!("Use <read_note><path>b.md</path></read_note> to read.")
+        );
+    }
+
+    #[test]
+    fn nested_same_name_tags_stay_in_value() {
+        let content =
+            "<edit_note><new_string>a <new_string>b</new_string> c</new_string></edit_note>";
+        let calls = parse_tool_calls(content, &tools(&["edit_note"]));
+
+        assert_eq!(
+            calls[0].params["new_string"],
+            json!("a <new_string>b</new_string> c")
+        );
+    }
+
+    #[test]
+    fn cdata_and_entities_are_decoded() {
+        let content = "<edit_note>\n<old_string>&lt;div&gt;a &amp; b&lt;/div&gt;</old_string>\n<new_string>\n<![CDATA[</new_string> <read_note> & 42]]>\n</new_string>\n</edit_note>";
+        let calls = parse_tool_calls(content, &tools(&["edit_note", "read_note"]));
+
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].params["old_string"], json!("<div>a & b</div>"));
+        assert_eq!(
+            calls[0].params["new_string"],
+            json!("</new_string> <read_note> & 42")
+        );
+    }
+
+    #[test]
+    fn attribute_and_named_parameter_styles() {
+        let content = r#"<read filePath="notes/a &amp; b.md" limit="5"/>
+<edit><parameter name="filePath">c.md</parameter><param name="replaceAll">false</param></edit>"#;
+        let calls = parse_tool_calls(content, &tools(&["read", "edit"]));
+
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0].params["filePath"], json!("notes/a & b.md"));
+        assert_eq!(calls[0].params["limit"], json!(5));
+        assert_eq!(calls[1].name, "edit");
+        assert_eq!(calls[1].params["filePath"], json!("c.md"));
+        assert_eq!(calls[1].params["replaceAll"], json!(false));
+        assert_eq!(calls[1].id, "call_1");
+    }
+
+    #[test]
+    fn ignores_unknown_tags_and_thinking_blocks() {
+        let content = "<thinking>maybe <read><filePath>x.md</filePath></read></thinking>\n<b>bold</b> <grep><pattern>TODO</pattern></grep>";
+        let calls = parse_tool_calls(content, &tools(&["read", "grep"]));
+
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].name, "grep");
+        assert_eq!(calls[0].params["pattern"], json!("TODO"));
+    }
+
+    #[test]
+    fn markdown_with_html_round_trips() {
+        let markdown = "# Title\n\n<div align=\"center\">\n  <img src=\"a.png\" alt=\"a & b\"/>\n</div>\n\nSee <read> and ]]> literally.\n";
+        let mut params = serde_json::Map::new();
+        params.insert("filePath".to_string(), json!("notes/page.md"));
+        params.insert("oldString".to_string(), json!("<div align=\"center\">"));
+        params.insert("newString".to_string(), json!(markdown));
+        params.insert("replaceAll".to_string(), json!(false));
+
+        let xml = format_tool_call("edit", &params);
+        let calls = parse_tool_calls(&xml, &tools(&["edit", "read"]));
+
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].params["filePath"], json!("notes/page.md"));
+        assert_eq!(
+            calls[0].params["oldString"],
+            json!("<div align=\"center\">")
+        );
+        assert_eq!(calls[0].params["newString"], json!(markdown));
+        assert_eq!(calls[0].params["replaceAll"], json!(false));
+    }
+
+    #[test]
+    fn escape_value_round_trips_through_the_parser() {
+        let values = [
+            "",
+            "plain text",
+            "007",
+            "true",
+            "null",
+            "[1, 2]",
+            "\n",
+            "\nleading",
+            "trailing\n",
+            "\n\nboth\n\n",
+            "  spaced  ",
+            "a & b",
+            "&amp; stays literal",
+            "<a>",
+            "]]>",
+            "x]]>]]>y",
+            "<![CDATA[inner]]>",
+        ];
+        for value in values {
+            let escaped = escape_value(value);
+            assert_eq!(decode_value(&escaped, None), json!(value), "{:?}", escaped);
+
+            let mut params = serde_json::Map::new();
+            params.insert("value".to_string(), json!(value));
+            let calls = parse_tool_calls(&format_tool_call("write", &params), &tools(&["write"]));
+            assert_eq!(calls[0].params["value"], json!(value));
+        }
+        assert_eq!(escape_value("notes/a.md"), "notes/a.md");
+    }
+
+    #[test]
+    fn xml_tool_prompt_lists_tools_with_optional_params() {
+        let defs = vec![json!({
+            "type": "function",
+            "function": {
+                "name": "read",
+                "description": "Reads a file.\nMore details.",
+                "parameters": {
+                    "type": "object",
+                    "properties": { "offset": {}, "filePath": {} },
+                    "required": ["filePath"]
+                }
+            }
+        })];
+
+        let prompt = build_xml_tool_prompt(&defs);
+
+        assert!(prompt.contains("<![CDATA["));
+        assert!(prompt.contains("- read(filePath, offset?): Reads a file.\n"));
+    }
+}
@@ -1,6 +1,7 @@
 use crate::mobile_gateway::{
     handle_mobile_message, MobileClientMessage, MobileGatewayState, MobileServerMessage,
 };
+use crate::relay_e2e::{self, E2eError, RelayCipher, RelayRole};
 use futures_util::{SinkExt, StreamExt};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
@@ -19,12 +20,16 @@ pub struct CloudRelayConfig {
     pub relay_url: String,
     pub email: String,
     pub password: String,
+    /// Encrypt traffic end to end so the relay server only sees ciphertext
+    #[serde(default)]
+    pub e2e: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Default)]
 pub struct CloudRelayStatus {
     pub running: bool,
     pub connected: bool,
+    pub e2e: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub relay_url: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -38,6 +43,8 @@ pub struct CloudRelayState {
     status: Mutex<CloudRelayStatus>,
     shutdown: broadcast::Sender<()>,
     starting: Mutex<bool>,
+    /// E2E cipher and the secret it was derived from
+    cipher: Mutex<Option<(String, Arc<RelayCipher>)>>,
 }
 
 impl CloudRelayState {
@@ -48,8 +55,23 @@ impl CloudRelayState {
             status: Mutex::new(CloudRelayStatus::default()),
             shutdown,
             starting: Mutex::new(false),
+            cipher: Mutex::new(None),
         }
     }
+
+    /// Cipher for `secret`, kept across relay restarts so frames the phone
+    /// sent before the restart are still recognized as replays
+    async fn cipher_for(&self, secret: &str) -> Result<Arc<RelayCipher>, String> {
+        let mut cached = self.cipher.lock().await;
+        if let Some((known, cipher)) = cached.as_ref() {
+            if known == secret {
+                return Ok(cipher.clone());
+            }
+        }
+        let cipher = Arc::new(RelayCipher::from_secret(secret, RelayRole::Desktop)?);
+        *cached = Some((secret.to_string(), cipher.clone()));
+        Ok(cipher)
+    }
 }
 
 #[tauri::command]
@@ -170,6 +192,15 @@ async fn run_relay(
 ) -> Result<(), String> {
     let token = login_for_token(&http_client, &config).await?;
     let relay_url = ensure_client_query(&config.relay_url, "desktop")?;
+    let e2e_secret = if config.e2e {
+        Some(load_or_create_e2e_secret(&app)?)
+    } else {
+        None
+    };
+    let cipher = match e2e_secret.as_deref() {
+        Some(secret) => Some(app.state::<CloudRelayState>().cipher_for(secret).await?),
+        None => None,
+    };
 
     let mut request = relay_url
         .into_client_request()
@@ -186,18 +217,39 @@ async fn run_relay(
         let state = app.state::<CloudRelayState>();
         let mut status = state.status.lock().await;
         status.connected = true;
-        status.pairing_payload = Some(build_pairing_payload(&config.relay_url, &token));
+        status.e2e = cipher.is_some();
+        status.pairing_payload = Some(build_pairing_payload(
+            &config.relay_url,
+            &token,
+            e2e_secret.as_deref(),
+        ));
         status.error = None;
     }
 
     let (mut ws_sink, mut ws_stream) = ws_stream.split();
     let (out_tx, mut out_rx) = mpsc::unbounded_channel::<MobileServerMessage>();
+    // Frames that must stay readable without the key (E2E errors)
+    let (plain_tx, mut plain_rx) = mpsc::unbounded_channel::<String>();
 
+    let writer_cipher = cipher.clone();
     let writer = tokio::spawn(async move {
-        while let Some(message) = out_rx.recv().await {
-            let payload = match serde_json::to_string(&message) {
-                Ok(text) => text,
-                Err(_) => continue,
+        loop {
+            let payload = tokio::select! {
+                Some(message) = out_rx.recv() => {
+                    let text = match serde_json::to_string(&message) {
+                        Ok(text) => text,
+                        Err(_) => continue,
+                    };
+                    match &writer_cipher {
+                        Some(cipher) => match cipher.seal(&text) {
+                            Ok(sealed) => sealed,
+                            Err(_) => continue,
+                        },
+                        None => text,
+                    }
+                }
+                Some(text) = plain_rx.recv() => text,
+                else => break,
             };
             if ws_sink.send(Message::Text(payload)).await.is_err() {
                 break;
@@ -265,6 +317,18 @@ async fn run_relay(
                     None => break,
                 };
                 if let Message::Text(text) = message {
+                    let text = match &cipher {
+                        Some(cipher) => match cipher.open(&text) {
+                            Ok(plain) => plain,
+                            // Replays come from the relay, not the phone
+                            Err(E2eError::Replayed) => continue,
+                            Err(err) => {
+                                let _ = plain_tx.send(relay_e2e::error_frame(err));
+                                continue;
+                            }
+                        },
+                        None => text,
+                    };
                     let parsed = serde_json::from_str::<MobileClientMessage>(&text);
                     match parsed {
                         Ok(msg) => {
@@ -301,13 +365,18 @@ async fn run_relay(
     Ok(())
 }
 
-fn build_pairing_payload(relay_url: &str, token: &str) -> String {
-    json!({
+fn build_pairing_payload(relay_url: &str, token: &str, e2e_secret: Option<&str>) -> String {
+    let mut payload = json!({
         "v": 1,
         "token": token,
         "relay_url": ensure_client_query(relay_url, "mobile").unwrap_or_else(|_| relay_url.to_string()),
-    })
-    .to_string()
+    });
+    // The payload travels out of band (QR code), so the relay never sees the secret
+    if let Some(secret) = e2e_secret {
+        payload["v"] = json!(2);
+        payload["e2e"] = json!(secret);
+    }
+    payload.to_string()
 }
 
 fn ensure_client_query(relay_url: &str, client: &str) -> Result<String, String> {
@@ -386,6 +455,23 @@ fn settings_path(app: &AppHandle) -> Result<PathBuf, String> {
     Ok(app_dir.join("cloud").join("relay.json"))
 }
 
+/// The pairing secret is kept across restarts so paired phones stay valid
+fn load_or_create_e2e_secret(app: &AppHandle) -> Result<String, String> {
+    let path = settings_path(app)?.with_file_name("relay_e2e.key");
+    if let Ok(secret) = fs::read_to_string(&path) {
+        if RelayCipher::from_secret(&secret, RelayRole::Desktop).is_ok() {
+            return Ok(secret.trim().to_string());
+        }
+    }
+    let secret = RelayCipher::generate_secret();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create cloud settings dir: {}", e))?;
+    }
+    fs::write(&path, &secret).map_err(|e| format!("Failed to write E2E key: {}", e))?;
+    Ok(secret)
+}
+
 fn load_config(app: &AppHandle) -> Option<CloudRelayConfig> {
     let path = settings_path(app).ok()?;
     let content = fs::read_to_string(path).ok()?;
@@ -415,5 +501,6 @@ fn redact_password_if_needed(config: &CloudRelayConfig) -> CloudRelayConfig {
         relay_url: config.relay_url.clone(),
         email: config.email.clone(),
         password: String::new(),
+        e2e: config.e2e,
     }
 }
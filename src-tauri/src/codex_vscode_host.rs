@@ -86,7 +86,7 @@ fn host_script_path(app: &AppHandle) -> Result<std::path::PathBuf, AppError> {
     let dir = app
         .path()
         .app_data_dir()
-        .map_err(|e| AppError::InvalidPath(format!("Failed to get app_data_dir: {}", e)))?
+        .map_err(|e| AppError::Internal(format!("Failed to get app_data_dir: {}", e)))?
         .join("codex-vscode-host");
     std::fs::create_dir_all(&dir)?;
     let script_path = dir.join("host.mjs");
@@ -149,12 +149,12 @@ pub async fn codex_vscode_host_start(
     let app_data_dir = app
         .path()
         .app_data_dir()
-        .map_err(|e| AppError::InvalidPath(format!("Failed to get app_data_dir: {}", e)))?;
+        .map_err(|e| AppError::Internal(format!("Failed to get app_data_dir: {}", e)))?;
     let platform = current_platform();
     let node_path =
         ensure_node_runtime_with_env_proxy(resource_dir.as_deref(), &app_data_dir, platform)
             .await
-            .map_err(AppError::InvalidPath)?;
+            .map_err(AppError::ExternalTool)?;
 
     let mut cmd = Command::new(node_path);
     apply_no_window_flag(&mut cmd);
@@ -186,7 +186,7 @@ pub async fn codex_vscode_host_start(
 
     let mut child = cmd.spawn().map_err(|err| {
         if err.kind() == std::io::ErrorKind::NotFound {
-            AppError::InvalidPath(
+            AppError::ExternalTool(
                 "Node runtime not found. Bundle node with the app or set LUMINA_NODE_PATH.".into(),
             )
         } else {
@@ -197,11 +197,11 @@ pub async fn codex_vscode_host_start(
     let stdout = child
         .stdout
         .take()
-        .ok_or_else(|| AppError::InvalidPath("Failed to capture codex host stdout".into()))?;
+        .ok_or_else(|| AppError::ExternalTool("Failed to capture codex host stdout".into()))?;
     let stderr = child
         .stderr
         .take()
-        .ok_or_else(|| AppError::InvalidPath("Failed to capture codex host stderr".into()))?;
+        .ok_or_else(|| AppError::ExternalTool("Failed to capture codex host stderr".into()))?;
 
     let mut stdout_lines = BufReader::new(stdout).lines();
 
@@ -218,7 +218,7 @@ pub async fn codex_vscode_host_start(
         }
     })
     .await
-    .map_err(|_| AppError::InvalidPath("Timed out waiting for codex host READY".into()))?
+    .map_err(|_| AppError::ExternalTool("Timed out waiting for codex host READY".into()))?
     .map_err(AppError::from)?;
 
     let origin = ready.origin.clone();
@@ -306,7 +306,7 @@ pub async fn create_codex_webview(
     let windows = app.windows();
     let main_window = windows
         .get("main")
-        .ok_or_else(|| AppError::InvalidPath("Main window not found".into()))?;
+        .ok_or_else(|| AppError::Webview("Main window not found".into()))?;
 
     if let Some(webview) = app.get_webview(CODEX_WEBVIEW_ID) {
         let _ = webview.close();
@@ -314,7 +314,7 @@ pub async fn create_codex_webview(
 
     let parsed_url: tauri::Url = url
         .parse()
-        .map_err(|_| AppError::InvalidPath("Invalid URL".into()))?;
+        .map_err(|_| AppError::Parse("Invalid URL".into()))?;
 
     let app_for_open = app.clone();
     let webview_builder = WebviewBuilder::new(CODEX_WEBVIEW_ID, WebviewUrl::External(parsed_url))
@@ -333,7 +333,7 @@ pub async fn create_codex_webview(
             Position::Logical(LogicalPosition::new(x, y)),
             Size::Logical(LogicalSize::new(width, height)),
         )
-        .map_err(|e| AppError::InvalidPath(e.to_string()))?;
+        .map_err(|e| AppError::Webview(e.to_string()))?;
 
     {
         let mut inner = state.0.lock().await;
@@ -360,10 +360,10 @@ pub async fn update_codex_webview_bounds(
     if let Some(webview) = app.get_webview(CODEX_WEBVIEW_ID) {
         webview
             .set_position(Position::Logical(LogicalPosition::new(x, y)))
-            .map_err(|e| AppError::InvalidPath(e.to_string()))?;
+            .map_err(|e| AppError::Webview(e.to_string()))?;
         webview
             .set_size(Size::Logical(LogicalSize::new(width, height)))
-            .map_err(|e| AppError::InvalidPath(e.to_string()))?;
+            .map_err(|e| AppError::Webview(e.to_string()))?;
         let mut inner = state.0.lock().await;
         inner.webview_bounds = Some(CodexWebviewBounds {
             x,
@@ -390,15 +390,15 @@ pub async fn set_codex_webview_visible(
             if let Some(bounds) = bounds {
                 webview
                     .set_position(Position::Logical(LogicalPosition::new(bounds.x, bounds.y)))
-                    .map_err(|e| AppError::InvalidPath(e.to_string()))?;
+                    .map_err(|e| AppError::Webview(e.to_string()))?;
                 webview
                     .set_size(Size::Logical(LogicalSize::new(bounds.width, bounds.height)))
-                    .map_err(|e| AppError::InvalidPath(e.to_string()))?;
+                    .map_err(|e| AppError::Webview(e.to_string()))?;
             }
         } else {
             webview
                 .set_position(Position::Logical(LogicalPosition::new(-10000.0, -10000.0)))
-                .map_err(|e| AppError::InvalidPath(e.to_string()))?;
+                .map_err(|e| AppError::Webview(e.to_string()))?;
         }
     }
     Ok(())
@@ -409,10 +409,10 @@ pub async fn navigate_codex_webview(app: AppHandle, url: String) -> Result<(), A
     if let Some(webview) = app.get_webview(CODEX_WEBVIEW_ID) {
         let parsed_url: tauri::Url = url
             .parse()
-            .map_err(|_| AppError::InvalidPath("Invalid URL".into()))?;
+            .map_err(|_| AppError::Parse("Invalid URL".into()))?;
         webview
             .navigate(parsed_url)
-            .map_err(|e| AppError::InvalidPath(e.to_string()))?;
+            .map_err(|e| AppError::Webview(e.to_string()))?;
     }
     Ok(())
 }
@@ -422,7 +422,7 @@ pub async fn close_codex_webview(app: AppHandle) -> Result<(), AppError> {
     if let Some(webview) = app.get_webview(CODEX_WEBVIEW_ID) {
         webview
             .close()
-            .map_err(|e| AppError::InvalidPath(e.to_string()))?;
+            .map_err(|e| AppError::Webview(e.to_string()))?;
     }
     Ok(())
 }
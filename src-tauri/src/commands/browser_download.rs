@@ -0,0 +1,308 @@
+//! 浏览器标签页的下载
+//!
+//! 通过 `on_download` 拦截网页下载，统一保存到用户在工作区内选择的下载目录：
+//! 目标路径经过 `fs::ensure_allowed_path` 校验，网页无法写到允许范围之外；
+//! 重名文件追加 ` (1)`、` (2)` 等序号。
+//! WebView 不提供下载进度回调，下载期间按间隔读取目标文件大小作为进度上报。
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use tauri::webview::DownloadEvent;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::error::AppError;
+use crate::fs;
+
+/// 下载进度事件名
+pub const BROWSER_DOWNLOAD_PROGRESS_EVENT: &str = "browser:download-progress";
+
+/// 下载完成（成功或失败）事件名
+pub const BROWSER_DOWNLOAD_COMPLETE_EVENT: &str = "browser:download-complete";
+
+const PROGRESS_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+const DEFAULT_FILE_NAME: &str = "download";
+
+const MAX_FILE_NAME_LEN: usize = 200;
+
+/// 下载进度
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct BrowserDownloadProgress {
+    pub tab_id: String,
+    pub url: String,
+    pub file_name: String,
+    pub path: String,
+    pub received_bytes: u64,
+}
+
+/// 下载结果
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct BrowserDownloadComplete {
+    pub tab_id: String,
+    pub url: String,
+    pub file_name: String,
+    pub path: String,
+    pub bytes: u64,
+    pub success: bool,
+    pub error: Option<String>,
+}
+
+/// 清理网页建议的文件名：去掉路径部分与非法字符，避免借文件名跳出下载目录
+pub(crate) fn sanitize_file_name(name: &str) -> String {
+    let base = name.rsplit(['/', '\\']).next().unwrap_or_default();
+    let cleaned: String = base
+        .chars()
+        .map(|ch| match ch {
+            '<' | '>' | ':' | '"' | '|' | '?' | '*' => '_',
+            ch if ch.is_control() => '_',
+            ch => ch,
+        })
+        .collect();
+    let cleaned = cleaned.trim().trim_matches('.').trim();
+    if cleaned.is_empty() {
+        return DEFAULT_FILE_NAME.to_string();
+    }
+    let mut end = cleaned.len().min(MAX_FILE_NAME_LEN);
+    while !cleaned.is_char_boundary(end) {
+        end -= 1;
+    }
+    cleaned[..end].to_string()
+}
+
+/// 从 WebView 建议的保存路径或 URL 中取文件名
+pub(crate) fn suggested_file_name(suggested: &Path, url: &str) -> String {
+    let from_path = suggested
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .filter(|name| !name.trim().is_empty());
+    let name = from_path.unwrap_or_else(|| {
+        let path = url.split(['?', '#']).next().unwrap_or_default();
+        let last = path
+            .trim_end_matches('/')
+            .rsplit('/')
+            .next()
+            .unwrap_or_default();
+        urlencoding::decode(last)
+            .map(|decoded| decoded.to_string())
+            .unwrap_or_else(|_| last.to_string())
+    });
+    sanitize_file_name(&name)
+}
+
+/// 在目录中为文件名找一个不冲突的路径：`a.pdf` → `a (1).pdf` → `a (2).pdf`
+pub(crate) fn unique_destination(dir: &Path, file_name: &str) -> PathBuf {
+    let candidate = dir.join(file_name);
+    if !candidate.exists() {
+        return candidate;
+    }
+    let (stem, extension) = match file_name.rfind('.') {
+        Some(index) if index > 0 => (&file_name[..index], &file_name[index..]),
+        _ => (file_name, ""),
+    };
+    (1..)
+        .map(|counter| dir.join(format!("{} ({}){}", stem, counter, extension)))
+        .find(|candidate| !candidate.exists())
+        .expect("unbounded counter always finds a free name")
+}
+
+/// 计算下载的保存路径，并确认目录与文件都在允许的范围内
+pub(crate) fn resolve_destination(
+    dir: &Path,
+    suggested: &Path,
+    url: &str,
+) -> Result<PathBuf, AppError> {
+    fs::ensure_allowed_path(dir, false)?;
+    std::fs::create_dir_all(dir)?;
+    let destination = unique_destination(dir, &suggested_file_name(suggested, url));
+    fs::ensure_allowed_path(&destination, false)?;
+    Ok(destination)
+}
+
+/// 下载目录与进行中的下载
+#[derive(Default)]
+pub struct BrowserDownloadState {
+    dir: Mutex<Option<PathBuf>>,
+    /// key 为 `tab_id` + URL，值为保存路径
+    active: Mutex<HashMap<String, PathBuf>>,
+}
+
+impl BrowserDownloadState {
+    pub(crate) fn set_dir(&self, dir: PathBuf) {
+        if let Ok(mut current) = self.dir.lock() {
+            *current = Some(dir);
+        }
+    }
+
+    fn dir(&self) -> Option<PathBuf> {
+        self.dir.lock().ok().and_then(|dir| dir.clone())
+    }
+
+    fn start(&self, key: &str, path: PathBuf) {
+        if let Ok(mut active) = self.active.lock() {
+            active.insert(key.to_string(), path);
+        }
+    }
+
+    fn is_active(&self, key: &str) -> bool {
+        self.active
+            .lock()
+            .map(|active| active.contains_key(key))
+            .unwrap_or(false)
+    }
+
+    fn finish(&self, key: &str) -> Option<PathBuf> {
+        self.active
+            .lock()
+            .ok()
+            .and_then(|mut active| active.remove(key))
+    }
+}
+
+fn download_key(tab_id: &str, url: &str) -> String {
+    format!("{}\n{}", tab_id, url)
+}
+
+fn file_name_of(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// `on_download` 回调：返回 `false` 取消下载
+pub(crate) fn handle_download(app: &AppHandle, tab_id: &str, event: DownloadEvent<'_>) -> bool {
+    let state = app.state::<BrowserDownloadState>();
+    match event {
+        DownloadEvent::Requested { url, destination } => {
+            let url = url.to_string();
+            let resolved = state
+                .dir()
+                .ok_or_else(|| AppError::InvalidPath("No download folder selected".to_string()))
+                .and_then(|dir| resolve_destination(&dir, destination, &url));
+            let path = match resolved {
+                Ok(path) => path,
+                Err(err) => {
+                    let _ = app.emit(
+                        BROWSER_DOWNLOAD_COMPLETE_EVENT,
+                        BrowserDownloadComplete {
+                            tab_id: tab_id.to_string(),
+                            url: url.clone(),
+                            file_name: suggested_file_name(destination, &url),
+                            path: String::new(),
+                            bytes: 0,
+                            success: false,
+                            error: Some(err.to_string()),
+                        },
+                    );
+                    return false;
+                }
+            };
+            *destination = path.clone();
+
+            let key = download_key(tab_id, &url);
+            state.start(&key, path.clone());
+            let progress = BrowserDownloadProgress {
+                tab_id: tab_id.to_string(),
+                url,
+                file_name: file_name_of(&path),
+                path: path.to_string_lossy().to_string(),
+                received_bytes: 0,
+            };
+            let _ = app.emit(BROWSER_DOWNLOAD_PROGRESS_EVENT, progress.clone());
+            spawn_progress_poller(app.clone(), key, path, progress);
+            true
+        }
+        DownloadEvent::Finished { url, path, success } => {
+            let url = url.to_string();
+            let tracked = state.finish(&download_key(tab_id, &url));
+            let path = path.or(tracked).unwrap_or_default();
+            let bytes = std::fs::metadata(&path).map(|meta| meta.len()).unwrap_or(0);
+            let _ = app.emit(
+                BROWSER_DOWNLOAD_COMPLETE_EVENT,
+                BrowserDownloadComplete {
+                    tab_id: tab_id.to_string(),
+                    url,
+                    file_name: file_name_of(&path),
+                    path: path.to_string_lossy().to_string(),
+                    bytes,
+                    success,
+                    error: (!success).then(|| "Download failed".to_string()),
+                },
+            );
+            true
+        }
+        _ => true,
+    }
+}
+
+/// 下载期间定期上报已写入的字节数
+fn spawn_progress_poller(
+    app: AppHandle,
+    key: String,
+    path: PathBuf,
+    mut progress: BrowserDownloadProgress,
+) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(PROGRESS_POLL_INTERVAL).await;
+            if !app.state::<BrowserDownloadState>().is_active(&key) {
+                break;
+            }
+            let Ok(meta) = tokio::fs::metadata(&path).await else {
+                continue;
+            };
+            if meta.len() != progress.received_bytes {
+                progress.received_bytes = meta.len();
+                let _ = app.emit(BROWSER_DOWNLOAD_PROGRESS_EVENT, progress.clone());
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sanitizes_suggested_names() {
+        assert_eq!(sanitize_file_name("../../etc/passwd"), "passwd");
+        assert_eq!(sanitize_file_name("..\\evil.exe"), "evil.exe");
+        assert_eq!(sanitize_file_name("a:b?.pdf"), "a_b_.pdf");
+        assert_eq!(sanitize_file_name(" .. "), DEFAULT_FILE_NAME);
+        assert_eq!(
+            suggested_file_name(Path::new(""), "https://x.example/files/My%20Paper.pdf?dl=1"),
+            "My Paper.pdf"
+        );
+        assert_eq!(
+            suggested_file_name(Path::new("/tmp/report.csv"), "https://x.example/export"),
+            "report.csv"
+        );
+        assert_eq!(
+            suggested_file_name(Path::new(""), "https://x.example/"),
+            "x.example"
+        );
+    }
+
+    #[test]
+    fn appends_counter_on_collision() {
+        let dir = tempfile::tempdir().unwrap();
+        let first = unique_destination(dir.path(), "paper.pdf");
+        assert_eq!(first, dir.path().join("paper.pdf"));
+        std::fs::write(&first, b"1").unwrap();
+        let second = unique_destination(dir.path(), "paper.pdf");
+        assert_eq!(second, dir.path().join("paper (1).pdf"));
+        std::fs::write(&second, b"2").unwrap();
+        assert_eq!(
+            unique_destination(dir.path(), "paper.pdf"),
+            dir.path().join("paper (2).pdf")
+        );
+
+        std::fs::write(dir.path().join(".env"), b"").unwrap();
+        assert_eq!(
+            unique_destination(dir.path(), ".env"),
+            dir.path().join(".env (1)")
+        );
+    }
+}
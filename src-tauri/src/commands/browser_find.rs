@@ -0,0 +1,266 @@
+//! 网页内查找（Ctrl-F）
+//!
+//! `window.find` 在各平台 WebView 中表现不一致，这里改为注入脚本：
+//! 遍历文本节点，把匹配项包进 `<mark>` 并高亮当前项，滚动到可见区域后循环切换。
+//! 页面调用 `report_find_result` 命令把匹配数量发回 Rust，校验后再以 `browser:find-result`
+//! 转发给前端。tab_id 取自调用方 WebView 的标识，页面无法冒充其他标签页。
+//! 浏览器标签页与视频 WebView 共用同一套脚本。
+
+/// 页面上报查找结果的命令名
+pub const FIND_REPORT_COMMAND: &str = "report_find_result";
+
+/// 转发给前端的查找结果事件名
+pub const BROWSER_FIND_RESULT_EVENT: &str = "browser:find-result";
+
+/// 视频 WebView 使用的 tab_id
+pub const VIDEO_FIND_TAB_ID: &str = "video";
+
+const MAX_QUERY_LEN: usize = 256;
+
+/// 页面内的查找实现（首次调用时安装，之后复用）
+const FIND_HELPER_SCRIPT: &str = r#"
+(function () {
+    if (window.__luminaFind) return;
+    const MARK_ATTR = 'data-lumina-find';
+    const SKIP_TAGS = new Set(['SCRIPT', 'STYLE', 'NOSCRIPT', 'TEXTAREA', 'INPUT', 'SELECT', 'IFRAME', 'SVG', 'CANVAS']);
+    const state = { query: '', matchCase: false, marks: [], index: -1 };
+
+    const report = () => {
+        const tauri = window.__TAURI__;
+        if (!tauri || !tauri.core || typeof tauri.core.invoke !== 'function') return;
+        tauri.core.invoke('report_find_result', {
+            report: {
+                query: state.query,
+                total: state.marks.length,
+                current: state.index + 1,
+            },
+        }).catch(() => {});
+    };
+
+    const clear = () => {
+        const parents = new Set();
+        document.querySelectorAll('mark[' + MARK_ATTR + ']').forEach((mark) => {
+            const parent = mark.parentNode;
+            if (!parent) return;
+            parent.replaceChild(document.createTextNode(mark.textContent || ''), mark);
+            parents.add(parent);
+        });
+        parents.forEach((parent) => parent.normalize());
+        state.query = '';
+        state.marks = [];
+        state.index = -1;
+    };
+
+    const collectTextNodes = () => {
+        const nodes = [];
+        if (!document.body) return nodes;
+        const walker = document.createTreeWalker(document.body, NodeFilter.SHOW_TEXT, {
+            acceptNode(node) {
+                if (!node.nodeValue || !node.nodeValue.trim()) return NodeFilter.FILTER_REJECT;
+                for (let el = node.parentElement; el; el = el.parentElement) {
+                    if (SKIP_TAGS.has(el.tagName.toUpperCase())) return NodeFilter.FILTER_REJECT;
+                    if (el.isContentEditable) return NodeFilter.FILTER_REJECT;
+                }
+                const style = node.parentElement && window.getComputedStyle(node.parentElement);
+                if (style && (style.display === 'none' || style.visibility === 'hidden')) {
+                    return NodeFilter.FILTER_REJECT;
+                }
+                return NodeFilter.FILTER_ACCEPT;
+            },
+        });
+        while (walker.nextNode()) nodes.push(walker.currentNode);
+        return nodes;
+    };
+
+    const highlight = (query, matchCase) => {
+        const needle = matchCase ? query : query.toLowerCase();
+        const marks = [];
+        collectTextNodes().forEach((node) => {
+            const text = node.nodeValue;
+            const haystack = matchCase ? text : text.toLowerCase();
+            // 大小写转换改变长度时无法按下标切分，跳过该节点
+            if (haystack.length !== text.length) return;
+            let from = 0;
+            let at = haystack.indexOf(needle, from);
+            if (at < 0) return;
+            const fragment = document.createDocumentFragment();
+            while (at >= 0) {
+                fragment.appendChild(document.createTextNode(text.slice(from, at)));
+                const mark = document.createElement('mark');
+                mark.setAttribute(MARK_ATTR, '');
+                mark.style.backgroundColor = '#fde68a';
+                mark.style.color = 'inherit';
+                mark.textContent = text.slice(at, at + needle.length);
+                fragment.appendChild(mark);
+                marks.push(mark);
+                from = at + needle.length;
+                at = haystack.indexOf(needle, from);
+            }
+            fragment.appendChild(document.createTextNode(text.slice(from)));
+            node.parentNode.replaceChild(fragment, node);
+        });
+        return marks;
+    };
+
+    const focus = (index) => {
+        const previous = state.marks[state.index];
+        if (previous) previous.style.backgroundColor = '#fde68a';
+        state.index = index;
+        const current = state.marks[index];
+        if (!current) return;
+        current.style.backgroundColor = '#f97316';
+        current.scrollIntoView({ block: 'center', inline: 'nearest' });
+    };
+
+    window.__luminaFind = {
+        find(query, forward, matchCase) {
+            if (query !== state.query || matchCase !== state.matchCase || !state.marks.every((m) => m.isConnected)) {
+                clear();
+                state.query = query;
+                state.matchCase = matchCase;
+                state.marks = highlight(query, matchCase);
+                if (state.marks.length) focus(forward ? 0 : state.marks.length - 1);
+            } else if (state.marks.length) {
+                const count = state.marks.length;
+                focus(((state.index + (forward ? 1 : -1)) % count + count) % count);
+            }
+            report();
+        },
+        clear() {
+            clear();
+            report();
+        },
+    };
+})();
+"#;
+
+/// 查找命令对应的 WebView 标识
+pub(crate) fn webview_label(tab_id: &str) -> String {
+    if tab_id == VIDEO_FIND_TAB_ID {
+        "video-webview".to_string()
+    } else {
+        format!("browser-{}", tab_id)
+    }
+}
+
+/// 上报查找结果的 WebView 对应的 tab_id（`webview_label` 的逆运算）
+pub(crate) fn tab_id_for_label(label: &str) -> Option<&str> {
+    if label == "video-webview" {
+        Some(VIDEO_FIND_TAB_ID)
+    } else {
+        super::browser_navigation::tab_id_for_label(label)
+    }
+}
+
+/// 生成查找脚本（空查询等同于清除）
+pub(crate) fn find_script(query: &str, forward: bool, match_case: bool) -> Result<String, String> {
+    if query.chars().count() > MAX_QUERY_LEN {
+        return Err(format!(
+            "Search text is too long (max {} characters)",
+            MAX_QUERY_LEN
+        ));
+    }
+    if query.is_empty() {
+        return Ok(clear_script());
+    }
+    let query = serde_json::to_string(query).map_err(|e| e.to_string())?;
+    Ok(format!(
+        "{}\nwindow.__luminaFind.find({}, {}, {});",
+        FIND_HELPER_SCRIPT, query, forward, match_case
+    ))
+}
+
+/// 生成清除高亮的脚本
+pub(crate) fn clear_script() -> String {
+    format!("{}\nwindow.__luminaFind.clear();", FIND_HELPER_SCRIPT)
+}
+
+/// 页面上报的查找结果
+#[derive(Debug, Clone, PartialEq, serde::Deserialize)]
+pub struct FindReport {
+    query: String,
+    total: u32,
+    current: u32,
+}
+
+/// 查找结果
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BrowserFindResult {
+    pub tab_id: String,
+    pub query: String,
+    /// 匹配总数
+    pub total: u32,
+    /// 当前高亮项（从 1 开始，没有匹配时为 0）
+    pub current: u32,
+}
+
+/// 校验页面上报的查找结果，tab_id 由调用方 WebView 的标识决定
+pub(crate) fn find_result(label: &str, report: FindReport) -> Option<BrowserFindResult> {
+    let tab_id = tab_id_for_label(label)?;
+    if report.current > report.total {
+        return None;
+    }
+    Some(BrowserFindResult {
+        tab_id: tab_id.to_string(),
+        query: report.query,
+        total: report.total,
+        current: report.current,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_find_scripts_for_browser_and_video() {
+        assert_eq!(webview_label("abc"), "browser-abc");
+        assert_eq!(webview_label(VIDEO_FIND_TAB_ID), "video-webview");
+        assert_eq!(tab_id_for_label("browser-abc"), Some("abc"));
+        assert_eq!(tab_id_for_label("video-webview"), Some(VIDEO_FIND_TAB_ID));
+        assert_eq!(tab_id_for_label("browser-"), None);
+        assert_eq!(tab_id_for_label("main"), None);
+
+        let script = find_script("it's \"quoted\"", false, true).unwrap();
+        assert!(script.ends_with(r#"window.__luminaFind.find("it's \"quoted\"", false, true);"#));
+        assert!(script.contains(&format!("'{}'", FIND_REPORT_COMMAND)));
+        assert!(!script.contains("event.emit"));
+
+        assert!(find_script("", true, false)
+            .unwrap()
+            .ends_with("window.__luminaFind.clear();"));
+        assert_eq!(find_script("", true, false).unwrap(), clear_script());
+        assert!(find_script(&"x".repeat(MAX_QUERY_LEN + 1), true, false).is_err());
+    }
+
+    #[test]
+    fn validates_find_reports() {
+        let report = |payload: &str| serde_json::from_str::<FindReport>(payload).unwrap();
+        // 页面自带的 tabId 被忽略，以调用方 WebView 为准
+        assert_eq!(
+            find_result(
+                "browser-t1",
+                report(r#"{"tabId":"t2","query":"rust","total":3,"current":2}"#)
+            ),
+            Some(BrowserFindResult {
+                tab_id: "t1".to_string(),
+                query: "rust".to_string(),
+                total: 3,
+                current: 2,
+            })
+        );
+        assert_eq!(
+            find_result(
+                "browser-t1",
+                report(r#"{"query":"rust","total":1,"current":2}"#)
+            ),
+            None
+        );
+        assert_eq!(
+            find_result("main", report(r#"{"query":"rust","total":1,"current":1}"#)),
+            None
+        );
+        assert!(serde_json::from_str::<FindReport>(r#"{"query":"rust"}"#).is_err());
+    }
+}
@@ -0,0 +1,215 @@
+//! 浏览器标签页的导航状态
+//!
+//! 加载状态来自原生 `on_page_load` 回调，当前 URL 优先读取 `Webview::url()`；
+//! Tauri 没有暴露原生的历史记录接口，标题与能否后退 / 前进由注入脚本调用
+//! `report_browser_state` 命令上报：优先使用 Navigation API（`navigation.canGoBack`），
+//! 不支持时按 `history.length` 估算。
+//!
+//! 标签页加载的是任意网站，上报的 tab_id 取自调用方 WebView 的标识（`browser-<id>`），
+//! 不信任页面内容，避免一个标签页冒充另一个。
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// 页面上报导航状态的命令名
+pub const BROWSER_STATE_COMMAND: &str = "report_browser_state";
+
+/// 页面加载时注入的上报脚本
+pub(crate) const REPORTER_SCRIPT: &str = r#"
+(function () {
+    if (window.__luminaBrowserStateReporter) return;
+    window.__luminaBrowserStateReporter = true;
+
+    const report = () => {
+        const tauri = window.__TAURI__;
+        if (!tauri || !tauri.core || typeof tauri.core.invoke !== 'function') return;
+        const nav = window.navigation;
+        const hasNavigationApi = !!nav && typeof nav.canGoBack === 'boolean';
+        tauri.core.invoke('report_browser_state', {
+            report: {
+                url: location.href,
+                title: document.title || '',
+                historyLength: history.length,
+                canGoBack: hasNavigationApi ? nav.canGoBack : null,
+                canGoForward: hasNavigationApi ? nav.canGoForward : null,
+            },
+        }).catch(() => {});
+    };
+
+    // 同页导航（pushState / replaceState）不会触发页面加载
+    ['pushState', 'replaceState'].forEach((name) => {
+        const original = history[name];
+        history[name] = function () {
+            const result = original.apply(this, arguments);
+            setTimeout(report, 0);
+            return result;
+        };
+    });
+    window.addEventListener('popstate', report);
+    window.addEventListener('hashchange', report);
+    window.addEventListener('pageshow', report);
+    document.addEventListener('DOMContentLoaded', () => {
+        report();
+        const head = document.querySelector('head');
+        if (head) {
+            new MutationObserver(report).observe(head, { subtree: true, childList: true, characterData: true });
+        }
+    });
+    report();
+})();
+"#;
+
+/// 上报导航状态的 WebView 对应的标签页 ID
+pub(crate) fn tab_id_for_label(label: &str) -> Option<&str> {
+    label.strip_prefix("browser-").filter(|id| !id.is_empty())
+}
+
+/// 返回给前端的导航状态
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct BrowserWebviewState {
+    pub url: String,
+    pub title: String,
+    pub can_go_back: bool,
+    pub can_go_forward: bool,
+    pub loading: bool,
+}
+
+/// 页面上报的原始数据
+#[derive(Debug, Clone, PartialEq, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PageStateReport {
+    url: String,
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    history_length: u32,
+    #[serde(default)]
+    can_go_back: Option<bool>,
+    #[serde(default)]
+    can_go_forward: Option<bool>,
+}
+
+#[derive(Debug, Default, Clone)]
+struct TabNavigation {
+    loading: bool,
+    report: Option<PageStateReport>,
+}
+
+/// 各标签页最近的导航状态
+#[derive(Default)]
+pub struct BrowserNavigationState(Mutex<HashMap<String, TabNavigation>>);
+
+impl BrowserNavigationState {
+    /// 原生页面加载事件
+    pub(crate) fn set_loading(&self, tab_id: &str, loading: bool) {
+        if let Ok(mut tabs) = self.0.lock() {
+            tabs.entry(tab_id.to_string()).or_default().loading = loading;
+        }
+    }
+
+    pub(crate) fn record(&self, tab_id: &str, report: PageStateReport) {
+        if let Ok(mut tabs) = self.0.lock() {
+            tabs.entry(tab_id.to_string()).or_default().report = Some(report);
+        }
+    }
+
+    pub(crate) fn forget(&self, tab_id: &str) {
+        if let Ok(mut tabs) = self.0.lock() {
+            tabs.remove(tab_id);
+        }
+    }
+
+    /// 合并原生 URL 与页面上报，生成导航状态
+    pub(crate) fn snapshot(&self, tab_id: &str, native_url: Option<String>) -> BrowserWebviewState {
+        let tab = self
+            .0
+            .lock()
+            .ok()
+            .and_then(|tabs| tabs.get(tab_id).cloned())
+            .unwrap_or_default();
+        let report = tab.report.as_ref();
+        BrowserWebviewState {
+            url: native_url
+                .or_else(|| report.map(|r| r.url.clone()))
+                .unwrap_or_default(),
+            title: report.map(|r| r.title.clone()).unwrap_or_default(),
+            // 没有 Navigation API 时只能按历史长度估算，无法判断能否前进
+            can_go_back: report
+                .map(|r| r.can_go_back.unwrap_or(r.history_length > 1))
+                .unwrap_or(false),
+            can_go_forward: report.and_then(|r| r.can_go_forward).unwrap_or(false),
+            loading: tab.loading,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(payload: &str) -> PageStateReport {
+        serde_json::from_str(payload).unwrap()
+    }
+
+    #[test]
+    fn merges_native_and_reported_state() {
+        let state = BrowserNavigationState::default();
+        assert_eq!(
+            state.snapshot("t1", None),
+            BrowserWebviewState {
+                url: String::new(),
+                title: String::new(),
+                can_go_back: false,
+                can_go_forward: false,
+                loading: false,
+            }
+        );
+
+        state.set_loading("t1", true);
+        state.record(
+            "t1",
+            report(
+                r#"{"url":"https://a.example/#x","title":"A","historyLength":3,"canGoBack":true,"canGoForward":true}"#,
+            ),
+        );
+        let snapshot = state.snapshot("t1", Some("https://a.example/".to_string()));
+        assert_eq!(snapshot.url, "https://a.example/");
+        assert_eq!(snapshot.title, "A");
+        assert!(snapshot.can_go_back && snapshot.can_go_forward && snapshot.loading);
+
+        // 没有 Navigation API 时按 history.length 估算
+        state.set_loading("t1", false);
+        state.record(
+            "t1",
+            report(
+                r#"{"url":"https://b.example/","historyLength":2,"canGoBack":null,"canGoForward":null}"#,
+            ),
+        );
+        let snapshot = state.snapshot("t1", None);
+        assert_eq!(snapshot.url, "https://b.example/");
+        assert!(snapshot.can_go_back);
+        assert!(!snapshot.can_go_forward && !snapshot.loading);
+
+        state.forget("t1");
+        assert_eq!(state.snapshot("t1", None).url, "");
+    }
+
+    #[test]
+    fn tab_id_comes_from_the_calling_webview() {
+        assert_eq!(tab_id_for_label("browser-t1"), Some("t1"));
+        assert_eq!(tab_id_for_label("browser-"), None);
+        assert_eq!(tab_id_for_label("video-webview"), None);
+
+        // 页面自带的 tabId 不参与路由
+        let state = BrowserNavigationState::default();
+        state.record(
+            tab_id_for_label("browser-t1").unwrap(),
+            report(r#"{"tabId":"t2","url":"https://evil.example/"}"#),
+        );
+        assert_eq!(state.snapshot("t2", None).url, "");
+        assert_eq!(state.snapshot("t1", None).url, "https://evil.example/");
+
+        assert!(REPORTER_SCRIPT.contains(&format!("'{}'", BROWSER_STATE_COMMAND)));
+        assert!(!REPORTER_SCRIPT.contains("event.emit"));
+    }
+}
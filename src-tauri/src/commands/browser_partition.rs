@@ -0,0 +1,119 @@
+//! 浏览器标签页的存储分区
+//!
+//! 类似 Firefox 容器：同名分区的标签页共享 Cookie / 存储并持久化到磁盘，
+//! 不同分区之间互相隔离；`incognito` 分区不落盘，关闭即清空。
+//! 未指定分区时沿用应用默认的 WebView 存储。
+
+use std::path::{Path, PathBuf};
+
+/// 无痕分区名
+pub const INCOGNITO_PARTITION: &str = "incognito";
+
+const MAX_PARTITION_NAME_LEN: usize = 64;
+
+/// 标签页使用的存储分区
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum BrowserPartition {
+    /// 应用默认存储
+    Default,
+    /// 不持久化的临时存储
+    Incognito,
+    /// 持久化的命名分区
+    Persistent(String),
+}
+
+impl BrowserPartition {
+    /// 解析前端传入的分区名（只允许字母、数字、`-` 与 `_`）
+    pub(crate) fn parse(partition: Option<&str>) -> Result<Self, String> {
+        let Some(name) = partition.map(str::trim).filter(|name| !name.is_empty()) else {
+            return Ok(Self::Default);
+        };
+        if name.eq_ignore_ascii_case(INCOGNITO_PARTITION) {
+            return Ok(Self::Incognito);
+        }
+        let valid = name.len() <= MAX_PARTITION_NAME_LEN
+            && name
+                .chars()
+                .all(|ch| ch.is_ascii_alphanumeric() || ch == '-' || ch == '_');
+        if !valid {
+            return Err(format!(
+                "Invalid browser partition '{}': use up to {} letters, digits, '-' or '_'",
+                name, MAX_PARTITION_NAME_LEN
+            ));
+        }
+        Ok(Self::Persistent(name.to_ascii_lowercase()))
+    }
+
+    /// 持久化分区的数据目录（Windows / Linux 使用）
+    pub(crate) fn data_directory(&self, app_data_dir: &Path) -> Option<PathBuf> {
+        match self {
+            Self::Persistent(name) => Some(app_data_dir.join("browser-partitions").join(name)),
+            _ => None,
+        }
+    }
+
+    /// 持久化分区的数据存储标识（macOS 14+ 使用，按分区名稳定生成）
+    #[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+    pub(crate) fn data_store_identifier(&self) -> Option<[u8; 16]> {
+        let Self::Persistent(name) = self else {
+            return None;
+        };
+        let mut id = [0u8; 16];
+        id[..8].copy_from_slice(&fnv1a(name.as_bytes(), 0xcbf2_9ce4_8422_2325).to_be_bytes());
+        id[8..].copy_from_slice(&fnv1a(name.as_bytes(), 0x6c62_272e_07bb_0142).to_be_bytes());
+        Some(id)
+    }
+}
+
+/// FNV-1a 64 位哈希（跨版本稳定，不能用 `DefaultHasher`）
+fn fnv1a(bytes: &[u8], seed: u64) -> u64 {
+    bytes.iter().fold(seed, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_partitions_and_derives_stable_storage() {
+        assert_eq!(BrowserPartition::parse(None), Ok(BrowserPartition::Default));
+        assert_eq!(
+            BrowserPartition::parse(Some("  ")),
+            Ok(BrowserPartition::Default)
+        );
+        assert_eq!(
+            BrowserPartition::parse(Some("Incognito")),
+            Ok(BrowserPartition::Incognito)
+        );
+        assert!(BrowserPartition::parse(Some("../escape")).is_err());
+        assert!(BrowserPartition::parse(Some(&"a".repeat(65))).is_err());
+
+        let research = BrowserPartition::parse(Some("Research")).unwrap();
+        assert_eq!(
+            research,
+            BrowserPartition::Persistent("research".to_string())
+        );
+        assert_eq!(
+            research.data_directory(Path::new("/data")),
+            Some(PathBuf::from("/data/browser-partitions/research"))
+        );
+        assert_eq!(
+            research.data_store_identifier(),
+            BrowserPartition::parse(Some("research"))
+                .unwrap()
+                .data_store_identifier()
+        );
+        assert_ne!(
+            research.data_store_identifier(),
+            BrowserPartition::parse(Some("work"))
+                .unwrap()
+                .data_store_identifier()
+        );
+        assert_eq!(
+            BrowserPartition::Incognito.data_directory(Path::new("/data")),
+            None
+        );
+    }
+}
@@ -0,0 +1,503 @@
+//! B站视频分P与弹幕获取
+//!
+//! 多P视频的每个分P有独立的 CID，弹幕按 CID 获取。
+//!
+//! 优先使用 protobuf 分段接口（`dm/web/seg.so`，每段 6 分钟），
+//! 失败或无结果时回退到旧的 XML 接口（`dm/list.so`）。
+
+use std::io::Read;
+
+use futures_util::stream::{self, StreamExt, TryStreamExt};
+
+use super::DanmakuItem;
+
+/// 每个分段覆盖的时长（秒）
+pub(crate) const SEGMENT_SECONDS: u64 = 360;
+/// 分段数上限（12 小时），防止分段数未知时无限请求
+const MAX_SEGMENTS: u64 = 120;
+/// 同时请求的分段数
+const SEGMENT_CONCURRENCY: usize = 4;
+
+/// 解析后的单条弹幕
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct DanmakuElem {
+    /// 出现时间（毫秒）
+    pub progress_ms: i64,
+    /// 弹幕类型：1-3 滚动，4 底部，5 顶部，6 逆向，7 高级，8 代码，9 BAS
+    pub mode: i32,
+    pub font_size: i32,
+    /// RGB 颜色（十进制，如 16777215 为白色）
+    pub color: u32,
+    pub content: String,
+    /// 发送时间（Unix 秒）
+    pub ctime: u64,
+}
+
+impl DanmakuElem {
+    fn into_item(self) -> DanmakuItem {
+        DanmakuItem {
+            time: self.progress_ms as f64 / 1000.0,
+            content: self.content,
+            timestamp: self.ctime,
+        }
+    }
+}
+
+/// 视频分P信息
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct BilibiliPage {
+    /// 分P序号（从 1 开始）
+    pub page: u32,
+    pub cid: u64,
+    /// 分P标题
+    pub part: String,
+    /// 时长（秒）
+    pub duration: u64,
+}
+
+/// 从 `x/web-interface/view` 的 `data` 中读取分P列表
+///
+/// 单P或缺少 `pages` 的旧数据回退到 `data.cid`
+pub(crate) fn parse_pages(data: &serde_json::Value) -> Vec<BilibiliPage> {
+    let pages: Vec<BilibiliPage> = data["pages"]
+        .as_array()
+        .map(|pages| {
+            pages
+                .iter()
+                .enumerate()
+                .filter_map(|(index, page)| {
+                    Some(BilibiliPage {
+                        page: page["page"]
+                            .as_u64()
+                            .map(|p| p as u32)
+                            .unwrap_or(index as u32 + 1),
+                        cid: page["cid"].as_u64()?,
+                        part: page["part"].as_str().unwrap_or_default().to_string(),
+                        duration: page["duration"].as_u64().unwrap_or(0),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    if !pages.is_empty() {
+        return pages;
+    }
+
+    data["cid"]
+        .as_u64()
+        .map(|cid| BilibiliPage {
+            page: 1,
+            cid,
+            part: data["title"].as_str().unwrap_or_default().to_string(),
+            duration: data["duration"].as_u64().unwrap_or(0),
+        })
+        .into_iter()
+        .collect()
+}
+
+/// 选取指定分P（从 1 开始，None 表示第 1 P）
+pub(crate) fn select_page(
+    pages: &[BilibiliPage],
+    page: Option<u32>,
+) -> Result<&BilibiliPage, String> {
+    let page = page.unwrap_or(1);
+    pages.iter().find(|p| p.page == page).ok_or_else(|| {
+        format!(
+            "Bilibili page {} out of range (video has {} page(s))",
+            page,
+            pages.len()
+        )
+    })
+}
+
+/// 获取视频全部弹幕（按时间排序）
+pub(crate) async fn fetch_danmaku(
+    client: &reqwest::Client,
+    cid: u64,
+) -> Result<Vec<DanmakuItem>, String> {
+    let elems = match fetch_protobuf_danmaku(client, cid).await {
+        Ok(elems) if !elems.is_empty() => elems,
+        Ok(_) => fetch_xml_danmaku(client, cid).await?,
+        Err(err) => {
+            println!("[Danmaku] protobuf 接口失败，回退到 XML: {}", err);
+            fetch_xml_danmaku(client, cid).await?
+        }
+    };
+
+    let mut danmakus: Vec<DanmakuItem> = elems.into_iter().map(DanmakuElem::into_item).collect();
+    danmakus.sort_by(|a, b| {
+        a.time
+            .partial_cmp(&b.time)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    Ok(danmakus)
+}
+
+async fn fetch_bytes(client: &reqwest::Client, url: &str) -> Result<Vec<u8>, String> {
+    let resp = client
+        .get(url)
+        .header("User-Agent", "Mozilla/5.0")
+        .header("Referer", "https://www.bilibili.com/")
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !resp.status().is_success() {
+        return Err(format!("HTTP {} from {}", resp.status(), url));
+    }
+    resp.bytes()
+        .await
+        .map(|bytes| bytes.to_vec())
+        .map_err(|e| e.to_string())
+}
+
+/// 按分段获取 protobuf 弹幕
+///
+/// 先通过 `dm/web/view` 获取分段总数；拿不到时逐段请求，直到遇到空分段
+async fn fetch_protobuf_danmaku(
+    client: &reqwest::Client,
+    cid: u64,
+) -> Result<Vec<DanmakuElem>, String> {
+    let view_url = format!(
+        "https://api.bilibili.com/x/v2/dm/web/view?type=1&oid={}",
+        cid
+    );
+    let total = match fetch_bytes(client, &view_url).await {
+        Ok(bytes) => parse_view_segment_total(&bytes)?,
+        Err(_) => None,
+    };
+
+    let segment_url = |index: u64| {
+        format!(
+            "https://api.bilibili.com/x/v2/dm/web/seg.so?type=1&oid={}&segment_index={}",
+            cid, index
+        )
+    };
+
+    let mut elems = Vec::new();
+    match total {
+        Some(total) => {
+            let segments: Vec<Vec<DanmakuElem>> = stream::iter(1..=total.min(MAX_SEGMENTS))
+                .map(|index| {
+                    let url = segment_url(index);
+                    async move { parse_segment(&fetch_bytes(client, &url).await?) }
+                })
+                .buffered(SEGMENT_CONCURRENCY)
+                .try_collect()
+                .await?;
+            elems.extend(segments.into_iter().flatten());
+        }
+        None => {
+            for index in 1..=MAX_SEGMENTS {
+                let segment = parse_segment(&fetch_bytes(client, &segment_url(index)).await?)?;
+                if segment.is_empty() {
+                    break;
+                }
+                elems.extend(segment);
+            }
+        }
+    }
+
+    println!("[Danmaku] protobuf 解析到 {} 条弹幕", elems.len());
+    Ok(elems)
+}
+
+/// 旧版 XML 接口（只返回弹幕池中的一部分）
+async fn fetch_xml_danmaku(client: &reqwest::Client, cid: u64) -> Result<Vec<DanmakuElem>, String> {
+    let url = format!("https://api.bilibili.com/x/v1/dm/list.so?oid={}", cid);
+    let bytes = fetch_bytes(client, &url).await?;
+
+    // 尝试解压 deflate
+    let text = match flate2::read::DeflateDecoder::new(&bytes[..])
+        .bytes()
+        .collect::<Result<Vec<u8>, _>>()
+    {
+        Ok(decompressed) => String::from_utf8_lossy(&decompressed).to_string(),
+        Err(_) => String::from_utf8_lossy(&bytes).to_string(),
+    };
+
+    let elems = parse_xml(&text);
+    println!("[Danmaku] XML 解析到 {} 条弹幕", elems.len());
+    Ok(elems)
+}
+
+/// 解析 XML 中的 `<d p="time,mode,size,color,ctime,...">content</d>`
+pub(crate) fn parse_xml(text: &str) -> Vec<DanmakuElem> {
+    let mut danmakus = Vec::new();
+
+    let mut pos = 0;
+    while let Some(start) = text[pos..].find("<d p=\"") {
+        let abs_start = pos + start;
+
+        // 找到 p 属性的结束引号
+        if let Some(attr_end) = text[abs_start + 6..].find('"') {
+            let attr = &text[abs_start + 6..abs_start + 6 + attr_end];
+            let parts: Vec<&str> = attr.split(',').collect();
+
+            // 找到 > 和 </d>
+            let content_start = abs_start + 6 + attr_end + 2; // 跳过 ">
+            if let Some(content_end) = text[content_start..].find("</d>") {
+                let content = &text[content_start..content_start + content_end];
+
+                if parts.len() >= 5 {
+                    let seconds: f64 = parts[0].parse().unwrap_or(0.0);
+                    danmakus.push(DanmakuElem {
+                        progress_ms: (seconds * 1000.0).round() as i64,
+                        mode: parts[1].parse().unwrap_or(1),
+                        font_size: parts[2].parse().unwrap_or(25),
+                        color: parts[3].parse().unwrap_or(0xFFFFFF),
+                        content: content.to_string(),
+                        ctime: parts[4].parse().unwrap_or(0),
+                    });
+                }
+
+                pos = content_start + content_end + 4; // 跳过 </d>
+            } else {
+                pos = abs_start + 1;
+            }
+        } else {
+            pos = abs_start + 1;
+        }
+    }
+
+    danmakus
+}
+
+// ============ protobuf 解码 ============
+//
+// 只需读取少数字段，手写 wire format 解码，避免引入 prost 与代码生成。
+
+/// protobuf 字段值
+enum WireValue<'a> {
+    Varint(u64),
+    Fixed64,
+    Bytes(&'a [u8]),
+    Fixed32,
+}
+
+struct ProtoReader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> ProtoReader<'a> {
+    fn new(buf: &'a [u8]) -> Self {
+        Self { buf, pos: 0 }
+    }
+
+    fn read_varint(&mut self) -> Result<u64, String> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = *self.buf.get(self.pos).ok_or("protobuf: truncated varint")?;
+            self.pos += 1;
+            value |= u64::from(byte & 0x7F) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err("protobuf: varint too long".to_string())
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|end| *end <= self.buf.len())
+            .ok_or("protobuf: truncated field")?;
+        let bytes = &self.buf[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    /// 读取下一个字段，返回 (字段号, 值)
+    fn next_field(&mut self) -> Result<Option<(u64, WireValue<'a>)>, String> {
+        if self.pos >= self.buf.len() {
+            return Ok(None);
+        }
+        let key = self.read_varint()?;
+        let value = match key & 0x7 {
+            0 => WireValue::Varint(self.read_varint()?),
+            1 => {
+                self.take(8)?;
+                WireValue::Fixed64
+            }
+            2 => {
+                let len = usize::try_from(self.read_varint()?)
+                    .map_err(|_| "protobuf: length overflow".to_string())?;
+                WireValue::Bytes(self.take(len)?)
+            }
+            5 => {
+                self.take(4)?;
+                WireValue::Fixed32
+            }
+            other => return Err(format!("protobuf: unsupported wire type {}", other)),
+        };
+        Ok(Some((key >> 3, value)))
+    }
+}
+
+/// 解析 `DmSegMobileReply`：字段 1 为重复的 `DanmakuElem`
+pub(crate) fn parse_segment(bytes: &[u8]) -> Result<Vec<DanmakuElem>, String> {
+    let mut reader = ProtoReader::new(bytes);
+    let mut elems = Vec::new();
+    while let Some((field, value)) = reader.next_field()? {
+        if let (1, WireValue::Bytes(elem)) = (field, value) {
+            elems.push(parse_elem(elem)?);
+        }
+    }
+    Ok(elems)
+}
+
+/// 解析 `DanmakuElem`：2 progress(ms)、3 mode、4 fontsize、5 color、7 content、8 ctime
+fn parse_elem(bytes: &[u8]) -> Result<DanmakuElem, String> {
+    let mut elem = DanmakuElem {
+        progress_ms: 0,
+        mode: 1,
+        font_size: 25,
+        color: 0xFFFFFF,
+        content: String::new(),
+        ctime: 0,
+    };
+    let mut reader = ProtoReader::new(bytes);
+    while let Some((field, value)) = reader.next_field()? {
+        match (field, value) {
+            // int32 负数按 64 位补码编码，截断即可还原
+            (2, WireValue::Varint(v)) => elem.progress_ms = i64::from(v as i32),
+            (3, WireValue::Varint(v)) => elem.mode = v as i32,
+            (4, WireValue::Varint(v)) => elem.font_size = v as i32,
+            (5, WireValue::Varint(v)) => elem.color = v as u32,
+            (7, WireValue::Bytes(b)) => elem.content = String::from_utf8_lossy(b).into_owned(),
+            (8, WireValue::Varint(v)) => elem.ctime = v,
+            _ => {}
+        }
+    }
+    Ok(elem)
+}
+
+/// 从 `DmWebViewReply` 中读取分段总数（字段 4 `dm_sge` 的字段 2 `total`）
+pub(crate) fn parse_view_segment_total(bytes: &[u8]) -> Result<Option<u64>, String> {
+    let mut reader = ProtoReader::new(bytes);
+    while let Some((field, value)) = reader.next_field()? {
+        if let (4, WireValue::Bytes(config)) = (field, value) {
+            let mut config = ProtoReader::new(config);
+            while let Some((field, value)) = config.next_field()? {
+                if let (2, WireValue::Varint(total)) = (field, value) {
+                    return Ok((total > 0).then_some(total));
+                }
+            }
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn varint(mut value: u64, out: &mut Vec<u8>) {
+        while value >= 0x80 {
+            out.push((value as u8 & 0x7F) | 0x80);
+            value >>= 7;
+        }
+        out.push(value as u8);
+    }
+
+    fn varint_field(field: u64, value: u64, out: &mut Vec<u8>) {
+        varint(field << 3, out);
+        varint(value, out);
+    }
+
+    fn bytes_field(field: u64, bytes: &[u8], out: &mut Vec<u8>) {
+        varint((field << 3) | 2, out);
+        varint(bytes.len() as u64, out);
+        out.extend_from_slice(bytes);
+    }
+
+    #[test]
+    fn parses_protobuf_segment_fields() {
+        let mut elem = Vec::new();
+        varint_field(1, 123456789, &mut elem); // id
+        varint_field(2, 83_250, &mut elem);
+        varint_field(3, 5, &mut elem);
+        varint_field(4, 25, &mut elem);
+        varint_field(5, 0xFE0302, &mut elem);
+        bytes_field(6, b"a1b2c3", &mut elem);
+        bytes_field(7, "前方高能".as_bytes(), &mut elem);
+        varint_field(8, 1_700_000_000, &mut elem);
+        // 未知的 fixed32 / fixed64 字段应被跳过
+        elem.extend_from_slice(&[(14 << 3) | 5, 1, 2, 3, 4]);
+        elem.extend_from_slice(&[(15 << 3) | 1, 1, 2, 3, 4, 5, 6, 7, 8]);
+
+        let mut reply = Vec::new();
+        bytes_field(1, &elem, &mut reply);
+        bytes_field(1, &[], &mut reply);
+        varint_field(2, 0, &mut reply);
+
+        let elems = parse_segment(&reply).unwrap();
+        assert_eq!(elems.len(), 2);
+        assert_eq!(
+            elems[0],
+            DanmakuElem {
+                progress_ms: 83_250,
+                mode: 5,
+                font_size: 25,
+                color: 0xFE0302,
+                content: "前方高能".to_string(),
+                ctime: 1_700_000_000,
+            }
+        );
+        assert_eq!(elems[1].mode, 1);
+        assert_eq!(elems[1].color, 0xFFFFFF);
+
+        let item = elems[0].clone().into_item();
+        assert_eq!(item.time, 83.25);
+        assert_eq!(item.timestamp, 1_700_000_000);
+
+        assert!(parse_segment(&reply[..reply.len() - 3]).is_err());
+    }
+
+    #[test]
+    fn reads_segment_total_from_view_reply() {
+        let mut config = Vec::new();
+        varint_field(1, SEGMENT_SECONDS * 1000, &mut config);
+        varint_field(2, 4, &mut config);
+        let mut reply = Vec::new();
+        varint_field(1, 0, &mut reply);
+        bytes_field(2, b"text", &mut reply);
+        bytes_field(4, &config, &mut reply);
+        assert_eq!(parse_view_segment_total(&reply).unwrap(), Some(4));
+        assert_eq!(parse_view_segment_total(&[]).unwrap(), None);
+    }
+
+    #[test]
+    fn resolves_multi_part_pages() {
+        let data = serde_json::json!({
+            "cid": 100,
+            "title": "合集",
+            "pages": [
+                { "cid": 100, "page": 1, "part": "第一集", "duration": 600 },
+                { "cid": 200, "page": 2, "part": "第二集", "duration": 1200 }
+            ]
+        });
+        let pages = parse_pages(&data);
+        assert_eq!(pages.len(), 2);
+        assert_eq!(select_page(&pages, None).unwrap().cid, 100);
+        assert_eq!(select_page(&pages, Some(2)).unwrap().part, "第二集");
+        let err = select_page(&pages, Some(3)).unwrap_err();
+        assert!(err.contains("out of range"), "{err}");
+
+        let single = parse_pages(&serde_json::json!({ "cid": 42, "title": "单P" }));
+        assert_eq!(select_page(&single, None).unwrap().cid, 42);
+    }
+
+    #[test]
+    fn parses_legacy_xml() {
+        let xml = r#"<i><d p="12.5,4,25,16711680,1600000000,0,abc,1">底部红字</d><d p="3.0,1,25,16777215,1600000001,0,def,2">hi</d></i>"#;
+        let elems = parse_xml(xml);
+        assert_eq!(elems.len(), 2);
+        assert_eq!(elems[0].progress_ms, 12_500);
+        assert_eq!(elems[0].mode, 4);
+        assert_eq!(elems[0].color, 0xFF0000);
+        assert_eq!(elems[0].content, "底部红字");
+        assert_eq!(elems[1].ctime, 1_600_000_001);
+    }
+}
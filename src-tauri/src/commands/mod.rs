@@ -1,20 +1,40 @@
+mod browser_download;
+mod browser_find;
+mod browser_navigation;
+mod browser_partition;
+mod danmaku;
+mod video_frame;
+mod video_time;
+
+pub use browser_download::{
+    BrowserDownloadComplete, BrowserDownloadProgress, BrowserDownloadState,
+};
+pub use browser_find::{BrowserFindResult, FindReport};
+pub use browser_navigation::{BrowserNavigationState, BrowserWebviewState, PageStateReport};
+use browser_partition::BrowserPartition;
+pub use danmaku::BilibiliPage;
+pub use video_frame::{FrameReport, VideoFrame, VideoFrameRequests};
+pub use video_time::{VideoTimeInfo, VideoTimeReport, VideoTimeState};
+
 use crate::doc_tools;
 use crate::error::AppError;
 use crate::fs::{self, watcher, FileEntry};
 use crate::typesetting::{
-    layout_text_paragraph, shape_mixed_text, write_empty_pdf, FontManager, Glyph, PageBox,
-    PageMargins, PageSize, PageStyle, ParagraphAlign, TextLayoutOptions,
+    build_preview_pages_for_style, find_installed_fonts, layout_text_paragraph, parse_markdown,
+    preview_page_metrics, render_docx_builtin, shape_mixed_text, typeset_markdown_to_pdf,
+    write_empty_pdf, FontManager, Glyph, MarkdownFonts, NamedFont, PageBox, PageMargins, PageSize,
+    PageStyle, ParagraphAlign, PositionedLine, PreviewViewport, ShapingError, TextLayoutOptions,
+    MONOSPACE_FONT_CANDIDATES,
 };
 use std::collections::HashMap;
-use std::io::Read;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Mutex;
-use tauri::webview::NewWindowResponse;
+use tauri::webview::{NewWindowResponse, PageLoadEvent};
 use tauri::Emitter;
 use tauri::WebviewUrl;
 use tauri::{
-    AppHandle, LogicalPosition, LogicalSize, Manager, Position, Size, State, WebviewBuilder,
-    WebviewWindowBuilder,
+    AppHandle, LogicalPosition, LogicalSize, Manager, Position, Size, State, Webview,
+    WebviewBuilder, WebviewWindowBuilder,
 };
 use uuid::Uuid;
 
@@ -100,6 +120,94 @@ pub enum AlignInput {
     Justify,
 }
 
+/// Page geometry overrides (mm); unset fields use the default A4 layout.
+#[derive(serde::Deserialize, Clone, Copy, Debug, Default)]
+pub struct TypesettingPageStyleInput {
+    pub width_mm: Option<f32>,
+    pub height_mm: Option<f32>,
+    pub margin_top_mm: Option<f32>,
+    pub margin_right_mm: Option<f32>,
+    pub margin_bottom_mm: Option<f32>,
+    pub margin_left_mm: Option<f32>,
+    pub header_height_mm: Option<f32>,
+    pub footer_height_mm: Option<f32>,
+}
+
+impl TypesettingPageStyleInput {
+    fn to_page_style(self) -> PageStyle {
+        let base = default_typesetting_page_style();
+        let size = match (self.width_mm, self.height_mm) {
+            (Some(width_mm), Some(height_mm)) => PageSize::Custom {
+                width_mm,
+                height_mm,
+            },
+            _ => base.size,
+        };
+        PageStyle {
+            size,
+            margins: PageMargins {
+                top_mm: self.margin_top_mm.unwrap_or(base.margins.top_mm),
+                right_mm: self.margin_right_mm.unwrap_or(base.margins.right_mm),
+                bottom_mm: self.margin_bottom_mm.unwrap_or(base.margins.bottom_mm),
+                left_mm: self.margin_left_mm.unwrap_or(base.margins.left_mm),
+            },
+            header_height_mm: self.header_height_mm.unwrap_or(base.header_height_mm),
+            footer_height_mm: self.footer_height_mm.unwrap_or(base.footer_height_mm),
+        }
+    }
+}
+
+/// Preview layout options. Sizes are in px at `dpi` (default 96).
+#[derive(serde::Deserialize, Clone, Copy, Debug, Default)]
+pub struct TypesettingPreviewOptions {
+    pub font_size: Option<i32>,
+    pub line_height: Option<i32>,
+    pub align: Option<AlignInput>,
+    pub first_line_indent: Option<i32>,
+    pub space_before: Option<i32>,
+    pub space_after: Option<i32>,
+    pub dpi: Option<f32>,
+    pub zoom: Option<f32>,
+    pub page_gap_px: Option<i32>,
+}
+
+const DEFAULT_PREVIEW_FONT_SIZE_PX: i32 = 16;
+const DEFAULT_PREVIEW_LINE_HEIGHT_PX: i32 = 20;
+const DEFAULT_PREVIEW_DPI: f32 = 96.0;
+const DEFAULT_PREVIEW_PAGE_GAP_PX: i32 = 24;
+
+#[derive(serde::Serialize, Clone, Copy, Debug)]
+pub struct TypesettingPreviewLine {
+    pub line_index: usize,
+    pub start_byte: usize,
+    pub end_byte: usize,
+    pub x_mm: f32,
+    pub y_mm: f32,
+    pub width_mm: f32,
+    pub height_mm: f32,
+}
+
+#[derive(serde::Serialize, Clone, Debug)]
+pub struct TypesettingPreviewPage {
+    pub page_index: usize,
+    /// Offset of the page in the scroll area (px, zoom and gaps applied).
+    pub top_px: i32,
+    pub used_height_mm: f32,
+    pub lines: Vec<TypesettingPreviewLine>,
+}
+
+#[derive(serde::Serialize, Clone, Debug)]
+pub struct TypesettingPreview {
+    pub page: TypesettingPreviewPageMm,
+    /// Scaled page size (px).
+    pub page_width_px: i32,
+    pub page_height_px: i32,
+    pub page_count: usize,
+    /// Height of the whole scroll area (px).
+    pub total_height_px: i32,
+    pub pages: Vec<TypesettingPreviewPage>,
+}
+
 fn page_box_to_mm(box_mm: PageBox) -> PreviewBoxMm {
     PreviewBoxMm {
         x_mm: box_mm.x_mm,
@@ -231,12 +339,12 @@ pub async fn typesetting_export_pdf_base64() -> Result<String, AppError> {
     use base64::{engine::general_purpose::STANDARD, Engine as _};
 
     let pdf = write_empty_pdf(default_typesetting_page_style())
-        .map_err(|err| AppError::InvalidPath(format!("Typesetting PDF export failed: {err}")))?;
+        .map_err(|err| AppError::Typesetting(format!("PDF export failed: {err}")))?;
 
     Ok(STANDARD.encode(pdf))
 }
 
-fn find_rendered_pdf(out_dir: &PathBuf, docx_path: &PathBuf) -> Result<PathBuf, AppError> {
+fn find_rendered_pdf(out_dir: &Path, docx_path: &Path) -> Result<PathBuf, AppError> {
     if let Some(stem) = docx_path.file_stem().and_then(|s| s.to_str()) {
         let expected = out_dir.join(format!("{stem}.pdf"));
         if expected.exists() {
@@ -255,17 +363,44 @@ fn find_rendered_pdf(out_dir: &PathBuf, docx_path: &PathBuf) -> Result<PathBuf,
             return Ok(path);
         }
     }
-    Err(AppError::InvalidPath(
+    Err(AppError::ExternalTool(
         "OpenOffice render failed to produce a PDF".into(),
     ))
 }
 
-/// Render docx to PDF via OpenOffice/LibreOffice (soffice). Returns base64 PDF bytes.
+/// Which docx renderer to use. `Auto` tries the built-in renderer first and
+/// falls back to soffice for documents it cannot handle.
+#[derive(serde::Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DocxRendererInput {
+    #[default]
+    Auto,
+    Builtin,
+    Soffice,
+}
+
+#[derive(serde::Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DocxRenderer {
+    Builtin,
+    Soffice,
+}
+
+#[derive(serde::Serialize, Clone, Debug)]
+pub struct TypesettingDocxPdf {
+    pub pdf_base64: String,
+    pub renderer: DocxRenderer,
+    /// Why the built-in renderer was skipped when `auto` fell back to soffice.
+    pub fallback_reason: Option<String>,
+}
+
+/// Render docx to PDF. Returns base64 PDF bytes and the renderer that produced them.
 #[tauri::command]
 pub async fn typesetting_render_docx_pdf_base64(
     app: AppHandle,
     docx_path: String,
-) -> Result<String, AppError> {
+    renderer: Option<DocxRendererInput>,
+) -> Result<TypesettingDocxPdf, AppError> {
     use base64::{engine::general_purpose::STANDARD, Engine as _};
 
     let docx_path = PathBuf::from(docx_path);
@@ -275,13 +410,49 @@ pub async fn typesetting_render_docx_pdf_base64(
         ));
     }
 
-    let status = doc_tools::doc_tools_get_status(app).await?;
-    let soffice_path = status
-        .tools
-        .get("soffice")
-        .and_then(|tool| tool.path.as_ref())
-        .cloned()
-        .ok_or_else(|| AppError::InvalidPath("soffice not available".into()))?;
+    let preference = renderer.unwrap_or_default();
+    let mut fallback_reason = None;
+    if preference != DocxRendererInput::Soffice {
+        let bytes = tokio::fs::read(&docx_path).await?;
+        let forced = preference == DocxRendererInput::Builtin;
+        let rendered = tokio::task::spawn_blocking(move || render_docx_builtin(&bytes, forced))
+            .await
+            .map_err(|err| AppError::Internal(format!("Docx render task failed: {err}")))?;
+        match rendered {
+            Ok(pdf) => {
+                return Ok(TypesettingDocxPdf {
+                    pdf_base64: STANDARD.encode(pdf),
+                    renderer: DocxRenderer::Builtin,
+                    fallback_reason: None,
+                })
+            }
+            Err(err) if forced => {
+                return Err(AppError::Typesetting(format!(
+                    "built-in docx render failed: {err}"
+                )))
+            }
+            Err(err) => fallback_reason = Some(err.to_string()),
+        }
+    }
+
+    let pdf =
+        render_docx_with_soffice(app, &docx_path)
+            .await
+            .map_err(|err| match &fallback_reason {
+                Some(reason) => AppError::ExternalTool(format!("{reason}; {err}")),
+                None => err,
+            })?;
+
+    Ok(TypesettingDocxPdf {
+        pdf_base64: STANDARD.encode(pdf),
+        renderer: DocxRenderer::Soffice,
+        fallback_reason,
+    })
+}
+
+/// Render docx to PDF via OpenOffice/LibreOffice (soffice).
+async fn render_docx_with_soffice(app: AppHandle, docx_path: &Path) -> Result<Vec<u8>, AppError> {
+    let soffice_path = doc_tools::require_tool(app, "soffice").await?;
 
     let out_dir = std::env::temp_dir()
         .join("lumina-typesetting")
@@ -295,20 +466,20 @@ pub async fn typesetting_render_docx_pdf_base64(
         .arg("pdf")
         .arg("--outdir")
         .arg(&out_dir)
-        .arg(&docx_path)
+        .arg(docx_path)
         .status()
         .await?;
     if !status.success() {
-        return Err(AppError::InvalidPath(format!(
+        return Err(AppError::ExternalTool(format!(
             "OpenOffice render failed: {status}"
         )));
     }
 
-    let pdf_path = find_rendered_pdf(&out_dir, &docx_path)?;
+    let pdf_path = find_rendered_pdf(&out_dir, docx_path)?;
     let bytes = tokio::fs::read(&pdf_path).await?;
     let _ = tokio::fs::remove_dir_all(&out_dir).await;
 
-    Ok(STANDARD.encode(bytes))
+    Ok(bytes)
 }
 
 /// Typesetting text layout (placeholder). Returns line metrics for a single paragraph.
@@ -325,18 +496,18 @@ pub async fn typesetting_layout_text(
     space_after: Option<i32>,
 ) -> Result<TypesettingTextLayout, AppError> {
     if max_width <= 0 {
-        return Err(AppError::InvalidPath(
+        return Err(AppError::InvalidInput(
             "Typesetting layout requires a positive max_width".into(),
         ));
     }
     if line_height <= 0 {
-        return Err(AppError::InvalidPath(
+        return Err(AppError::InvalidInput(
             "Typesetting layout requires a positive line_height".into(),
         ));
     }
     if let Some(font_size) = font_size {
         if font_size <= 0 {
-            return Err(AppError::InvalidPath(
+            return Err(AppError::InvalidInput(
                 "Typesetting layout requires a positive font_size".into(),
             ));
         }
@@ -344,7 +515,7 @@ pub async fn typesetting_layout_text(
     let mut manager = FontManager::new();
     let font = manager
         .load_from_path(&font_path)
-        .map_err(|err| AppError::InvalidPath(format!("Typesetting font load failed: {err}")))?;
+        .map_err(|err| AppError::Typesetting(format!("font load failed: {err}")))?;
     let options = TextLayoutOptions {
         max_width,
         line_height,
@@ -355,12 +526,12 @@ pub async fn typesetting_layout_text(
         space_after: space_after.unwrap_or(0),
     };
     let lines = layout_text_paragraph(&font, &text, options)
-        .map_err(|err| AppError::InvalidPath(format!("Typesetting layout failed: {err}")))?;
+        .map_err(|err| AppError::Typesetting(format!("layout failed: {err}")))?;
     let byte_ranges = if lines.is_empty() {
         Vec::new()
     } else {
         let glyph_run = shape_mixed_text(&font, &text)
-            .map_err(|err| AppError::InvalidPath(format!("Typesetting layout failed: {err}")))?;
+            .map_err(|err| AppError::Typesetting(format!("layout failed: {err}")))?;
         line_byte_ranges(&text, &glyph_run.glyphs, &lines)
     };
     let lines = lines
@@ -383,16 +554,228 @@ pub async fn typesetting_layout_text(
     Ok(TypesettingTextLayout { lines })
 }
 
+/// Multi-page preview: lays out `text` (one paragraph per line) and paginates it
+/// into the body box of `style`. Line positions are in mm from the page origin.
+#[tauri::command]
+pub async fn typesetting_build_preview(
+    text: String,
+    font_path: String,
+    style: Option<TypesettingPageStyleInput>,
+    options: Option<TypesettingPreviewOptions>,
+) -> Result<TypesettingPreview, AppError> {
+    let options = options.unwrap_or_default();
+    let font_size = options.font_size.unwrap_or(DEFAULT_PREVIEW_FONT_SIZE_PX);
+    let line_height = options
+        .line_height
+        .unwrap_or(DEFAULT_PREVIEW_LINE_HEIGHT_PX);
+    if font_size <= 0 {
+        return Err(AppError::InvalidInput(
+            "Typesetting preview requires a positive font_size".into(),
+        ));
+    }
+    if line_height <= 0 {
+        return Err(AppError::InvalidInput(
+            "Typesetting preview requires a positive line_height".into(),
+        ));
+    }
+    let page_style = style.unwrap_or_default().to_page_style();
+    let dpi = options.dpi.unwrap_or(DEFAULT_PREVIEW_DPI);
+    let metrics = preview_page_metrics(page_style, dpi);
+    if metrics.body_width_px <= 0 || metrics.body_height_px <= 0 {
+        return Err(AppError::InvalidInput(
+            "Typesetting preview page has no body area".into(),
+        ));
+    }
+
+    let mut manager = FontManager::new();
+    let font = manager
+        .load_from_path(&font_path)
+        .map_err(|err| AppError::Typesetting(format!("font load failed: {err}")))?;
+    let layout_options = TextLayoutOptions {
+        max_width: metrics.body_width_px,
+        line_height,
+        font_size: Some(font_size),
+        align: align_input_to_paragraph(options.align.unwrap_or(AlignInput::Left)),
+        first_line_indent: options.first_line_indent.unwrap_or(0),
+        space_before: options.space_before.unwrap_or(0).max(0),
+        space_after: options.space_after.unwrap_or(0).max(0),
+    };
+    let layout_error = |err: ShapingError| AppError::Typesetting(format!("layout failed: {err}"));
+
+    // Lay out paragraphs one after another; byte ranges are relative to `text`
+    let mut lines: Vec<PositionedLine> = Vec::new();
+    let mut byte_ranges: Vec<(usize, usize)> = Vec::new();
+    let mut paragraph_top = 0i32;
+    let mut paragraph_start = 0usize;
+    for paragraph in text.split('\n') {
+        let mut placed =
+            layout_text_paragraph(&font, paragraph, layout_options).map_err(layout_error)?;
+        let ranges = if placed.is_empty() {
+            // Keep blank lines so they take up vertical space
+            placed.push(PositionedLine {
+                start: 0,
+                end: 0,
+                width: 0,
+                x_offset: 0,
+                y_offset: layout_options.space_before,
+                justify_gap: 0,
+                justify_remainder: 0,
+                space_before: layout_options.space_before,
+                space_after: layout_options.space_after,
+            });
+            vec![(0, 0)]
+        } else {
+            let glyph_run = shape_mixed_text(&font, paragraph).map_err(layout_error)?;
+            line_byte_ranges(paragraph, &glyph_run.glyphs, &placed)
+        };
+        let paragraph_height = placed
+            .iter()
+            .map(|line| line_height + line.space_before + line.space_after)
+            .sum::<i32>();
+        for (mut line, (start_byte, end_byte)) in placed.into_iter().zip(ranges) {
+            line.y_offset = line.y_offset.saturating_add(paragraph_top);
+            lines.push(line);
+            byte_ranges.push((paragraph_start + start_byte, paragraph_start + end_byte));
+        }
+        paragraph_top = paragraph_top.saturating_add(paragraph_height);
+        paragraph_start += paragraph.len() + 1;
+    }
+
+    let (metrics, pages) = build_preview_pages_for_style(&lines, line_height, page_style, dpi);
+    let viewport = PreviewViewport {
+        zoom: options.zoom.unwrap_or(1.0),
+        page_gap_px: options.page_gap_px.unwrap_or(DEFAULT_PREVIEW_PAGE_GAP_PX),
+    };
+    let body = page_style.body_box();
+    let px_to_mm = |px: i32| px as f32 * 25.4 / dpi;
+    let page_count = pages.len();
+    let pages = pages
+        .into_iter()
+        .map(|page| TypesettingPreviewPage {
+            page_index: page.page_index,
+            top_px: viewport.page_top_y(page.page_index, metrics.page_size_px),
+            used_height_mm: px_to_mm(page.used_height),
+            lines: page
+                .lines
+                .iter()
+                .map(|preview_line| {
+                    let line = &lines[preview_line.line_index];
+                    let (start_byte, end_byte) = byte_ranges[preview_line.line_index];
+                    TypesettingPreviewLine {
+                        line_index: preview_line.line_index,
+                        start_byte,
+                        end_byte,
+                        x_mm: body.x_mm + px_to_mm(line.x_offset),
+                        y_mm: body.y_mm + px_to_mm(preview_line.y_offset),
+                        width_mm: px_to_mm(line.width),
+                        height_mm: px_to_mm(line_height),
+                    }
+                })
+                .collect(),
+        })
+        .collect();
+    let scaled = viewport.scaled_page_size(metrics.page_size_px);
+
+    Ok(TypesettingPreview {
+        page: TypesettingPreviewPageMm {
+            page: page_box_to_mm(page_style.page_box()),
+            body: page_box_to_mm(body),
+            header: page_box_to_mm(page_style.header_box()),
+            footer: page_box_to_mm(page_style.footer_box()),
+        },
+        page_width_px: scaled.width_px,
+        page_height_px: scaled.height_px,
+        page_count,
+        total_height_px: viewport.total_height(metrics.page_size_px, page_count),
+        pages,
+    })
+}
+
+/// Typeset Markdown into a paginated PDF. Returns base64-encoded PDF bytes.
+/// Code uses the first installed monospace font that covers it, falling back
+/// to `font_path`.
+#[tauri::command]
+pub async fn typeset_markdown_to_pdf_base64(
+    markdown: String,
+    font_path: String,
+    style: Option<TypesettingPageStyleInput>,
+) -> Result<String, AppError> {
+    use base64::{engine::general_purpose::STANDARD, Engine as _};
+
+    let page_style = style.unwrap_or_default().to_page_style();
+    let pdf = tokio::task::spawn_blocking(move || {
+        let code_chain = find_installed_fonts(&MONOSPACE_FONT_CANDIDATES);
+        typeset_markdown_pdf(&markdown, Path::new(&font_path), &code_chain, page_style)
+    })
+    .await
+    .map_err(|err| AppError::Internal(format!("Typesetting task failed: {err}")))??;
+
+    Ok(STANDARD.encode(pdf))
+}
+
+fn typeset_markdown_pdf(
+    markdown: &str,
+    font_path: &Path,
+    code_chain: &[PathBuf],
+    page_style: PageStyle,
+) -> Result<Vec<u8>, AppError> {
+    let mut manager = FontManager::new();
+    let body = manager
+        .load_from_path(font_path)
+        .map_err(|err| AppError::Typesetting(format!("font load failed: {err}")))?;
+
+    let code_text: String = parse_markdown(markdown)
+        .iter()
+        .flat_map(|block| block.spans.iter())
+        .filter(|span| span.style.code)
+        .map(|span| span.text.as_str())
+        .collect();
+    let mut chain = code_chain.to_vec();
+    chain.push(font_path.to_path_buf());
+    let code = if code_text.is_empty() {
+        None
+    } else {
+        manager
+            .load_fallback(&chain, &code_text)
+            .filter(|(path, _)| path != font_path)
+    };
+
+    let font_name = |path: &Path| {
+        path.file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_else(|| "Font".to_string())
+    };
+    let body_name = font_name(font_path);
+    let code_name = code.as_ref().map(|(path, _)| font_name(path));
+    let fonts = MarkdownFonts {
+        body: NamedFont {
+            name: &body_name,
+            font: &body,
+        },
+        code: code
+            .as_ref()
+            .zip(code_name.as_deref())
+            .map(|((_, font), name)| NamedFont { name, font }),
+    };
+    typeset_markdown_to_pdf(markdown, fonts, page_style)
+        .map_err(|err| AppError::Typesetting(format!("export failed: {err}")))
+}
+
 /// Read file content
 #[tauri::command]
 pub async fn read_file(path: String) -> Result<String, AppError> {
     fs::read_file_content(&path)
 }
 
-/// Save file content
+/// Save file content (atomic; `backup` keeps the previous version as `.bak`)
 #[tauri::command]
-pub async fn save_file(path: String, content: String) -> Result<(), AppError> {
-    fs::write_file_content(&path, &content)
+pub async fn save_file(
+    path: String,
+    content: String,
+    backup: Option<bool>,
+) -> Result<(), AppError> {
+    let _lock = fs::lock_path(std::path::Path::new(&path)).await;
+    fs::write_file_content_with_backup(&path, &content, backup.unwrap_or(false))
 }
 
 /// Check whether a file or directory exists.
@@ -406,6 +789,7 @@ pub async fn path_exists(path: String) -> Result<bool, AppError> {
 pub async fn write_binary_file(path: String, data: Vec<u8>) -> Result<(), AppError> {
     let path = std::path::Path::new(&path);
     fs::ensure_allowed_path(path, false)?;
+    fs::ensure_writable("write files")?;
     if let Some(parent) = path.parent() {
         std::fs::create_dir_all(parent)?;
     }
@@ -422,18 +806,66 @@ pub async fn read_binary_file_base64(path: String) -> Result<String, AppError> {
     Ok(STANDARD.encode(&data))
 }
 
+/// Read a downscaled preview of an image, fitting within `max_dim` pixels
+///
+/// Previews are cached under the app cache dir; use `read_binary_file_base64`
+/// for the full-resolution image.
+#[tauri::command]
+pub async fn read_image_thumbnail_base64(
+    app: AppHandle,
+    path: String,
+    max_dim: u32,
+) -> Result<fs::ImageThumbnail, AppError> {
+    let path = PathBuf::from(path);
+    fs::ensure_allowed_path(&path, true)?;
+    let cache_dir = app
+        .path()
+        .app_cache_dir()
+        .map_err(|err| AppError::Internal(format!("get app_cache_dir failed: {err}")))?
+        .join("thumbnails");
+    tokio::task::spawn_blocking(move || fs::image_thumbnail(&path, max_dim, &cache_dir))
+        .await
+        .map_err(|err| AppError::Internal(format!("thumbnail task failed: {}", err)))?
+}
+
 /// List directory with file tree
 #[tauri::command]
 pub async fn list_directory(path: String) -> Result<Vec<FileEntry>, AppError> {
     fs::list_dir_recursive(&path)
 }
 
+/// Start a streaming directory scan; entries arrive as `fs:entry-batch` events
+#[tauri::command]
+pub async fn start_directory_scan(app: AppHandle, path: String) -> Result<String, AppError> {
+    fs::start_scan(app, &path, Uuid::new_v4().to_string())
+}
+
+/// Cancel a running directory scan
+#[tauri::command]
+pub async fn cancel_directory_scan(scan_id: String) -> Result<bool, AppError> {
+    Ok(fs::cancel_scan(&scan_id))
+}
+
 /// Update runtime allowed filesystem roots (workspace-scoped).
 #[tauri::command]
 pub async fn fs_set_allowed_roots(roots: Vec<String>) -> Result<(), AppError> {
     fs::set_runtime_allowed_roots(roots)
 }
 
+/// Switch the read-only workspace mode (writes, deletes, moves, agent edits
+/// and WebDAV sync are refused while it is on).
+#[tauri::command]
+pub async fn set_workspace_readonly(readonly: bool) -> Result<(), AppError> {
+    fs::set_workspace_readonly(readonly);
+    Ok(())
+}
+
+/// Whether the workspace is currently read-only.
+#[tauri::command]
+pub async fn get_workspace_readonly() -> Result<bool, AppError> {
+    Ok(fs::is_workspace_readonly())
+}
+
 /// List directory tree as formatted string (for Agent context)
 #[tauri::command]
 pub async fn list_directory_tree(
@@ -501,9 +933,29 @@ pub async fn create_dir(path: String) -> Result<(), AppError> {
 }
 
 /// Delete a file or directory
+///
+/// 默认移到回收站（失败时移到工作区的 `.lumina/trash/`），并广播 `fs:trashed` 供前端撤销；
+/// `permanent` 为 true 时直接删除。
+#[tauri::command]
+pub async fn delete_file(
+    app: AppHandle,
+    path: String,
+    permanent: Option<bool>,
+) -> Result<Option<fs::TrashedEntry>, AppError> {
+    let trashed = fs::delete_entry(&path, permanent.unwrap_or(false))?;
+    if let Some(entry) = &trashed {
+        let _ = app.emit("fs:trashed", entry);
+    }
+    Ok(trashed)
+}
+
+/// Restore a trashed file or directory to its original path
 #[tauri::command]
-pub async fn delete_file(path: String) -> Result<(), AppError> {
-    fs::delete_entry(&path)
+pub async fn restore_from_trash(
+    original_path: String,
+    trashed_path: Option<String>,
+) -> Result<String, AppError> {
+    fs::restore_entry(&original_path, trashed_path.as_deref())
 }
 
 /// Rename/move a file
@@ -580,7 +1032,7 @@ pub async fn create_embedded_webview(
     let windows = app.windows();
     let main_window = windows
         .get("main")
-        .ok_or_else(|| AppError::InvalidPath("Main window not found".into()))?;
+        .ok_or_else(|| AppError::Webview("Main window not found".into()))?;
 
     // 如果已存在内嵌 webview，先关闭
     if let Some(webview) = app.get_webview("video-webview") {
@@ -592,9 +1044,10 @@ pub async fn create_embedded_webview(
         "video-webview",
         WebviewUrl::External(
             url.parse()
-                .map_err(|_| AppError::InvalidPath("Invalid URL".into()))?,
+                .map_err(|_| AppError::Parse("Invalid URL".into()))?,
         ),
-    );
+    )
+    .initialization_script(video_time::VIDEO_TIME_REPORTER_SCRIPT);
 
     // 创建内嵌 WebView
     let _webview = main_window
@@ -603,7 +1056,7 @@ pub async fn create_embedded_webview(
             Position::Logical(LogicalPosition::new(x, y)),
             Size::Logical(LogicalSize::new(width, height)),
         )
-        .map_err(|e| AppError::InvalidPath(e.to_string()))?;
+        .map_err(|e| AppError::Webview(e.to_string()))?;
     bounds_state.remember("video-webview", bounds);
 
     println!(
@@ -636,10 +1089,10 @@ pub async fn update_webview_bounds(
         );
         webview
             .set_position(Position::Logical(LogicalPosition::new(x, y)))
-            .map_err(|e| AppError::InvalidPath(e.to_string()))?;
+            .map_err(|e| AppError::Webview(e.to_string()))?;
         webview
             .set_size(Size::Logical(LogicalSize::new(width, height)))
-            .map_err(|e| AppError::InvalidPath(e.to_string()))?;
+            .map_err(|e| AppError::Webview(e.to_string()))?;
     }
     Ok(())
 }
@@ -649,13 +1102,15 @@ pub async fn update_webview_bounds(
 pub async fn close_embedded_webview(
     app: AppHandle,
     bounds_state: State<'_, ChildWebviewBoundsState>,
+    video_time: State<'_, VideoTimeState>,
 ) -> Result<(), AppError> {
     if let Some(webview) = app.get_webview("video-webview") {
         webview
             .close()
-            .map_err(|e| AppError::InvalidPath(e.to_string()))?;
+            .map_err(|e| AppError::Webview(e.to_string()))?;
     }
     bounds_state.forget("video-webview");
+    video_time.clear();
     Ok(())
 }
 
@@ -677,17 +1132,16 @@ pub async fn open_new_window(app: AppHandle) -> Result<(), AppError> {
         .resizable(true)
         .center()
         .build()
-        .map_err(|e| AppError::InvalidPath(e.to_string()))?;
+        .map_err(|e| AppError::Webview(e.to_string()))?;
 
     Ok(())
 }
 
-/// 获取 B站视频 CID
-#[tauri::command]
-pub async fn get_bilibili_cid(
-    proxy_state: tauri::State<'_, crate::proxy::ProxyState>,
-    bvid: String,
-) -> Result<Option<u64>, AppError> {
+/// 获取 B站视频详情中的分P列表
+async fn fetch_bilibili_pages(
+    proxy_state: &crate::proxy::ProxyState,
+    bvid: &str,
+) -> Result<Option<Vec<BilibiliPage>>, AppError> {
     let url = format!(
         "https://api.bilibili.com/x/web-interface/view?bvid={}",
         bvid
@@ -699,93 +1153,62 @@ pub async fn get_bilibili_cid(
         .header("User-Agent", "Mozilla/5.0")
         .send()
         .await
-        .map_err(|e| AppError::InvalidPath(e.to_string()))?;
+        .map_err(AppError::from)?;
 
     let json: serde_json::Value = resp
         .json()
         .await
-        .map_err(|e| AppError::InvalidPath(e.to_string()))?;
+        .map_err(|e| AppError::Parse(e.to_string()))?;
 
-    if json["code"].as_i64() == Some(0) {
-        if let Some(cid) = json["data"]["cid"].as_u64() {
-            return Ok(Some(cid));
-        }
+    if json["code"].as_i64() != Some(0) {
+        return Ok(None);
     }
+    Ok(Some(danmaku::parse_pages(&json["data"])))
+}
 
-    Ok(None)
+/// 获取 B站视频 CID
+///
+/// `page` 为分P序号（从 1 开始），默认第 1 P；超出范围时返回错误
+#[tauri::command]
+pub async fn get_bilibili_cid(
+    proxy_state: tauri::State<'_, crate::proxy::ProxyState>,
+    bvid: String,
+    page: Option<u32>,
+) -> Result<Option<u64>, AppError> {
+    let Some(pages) = fetch_bilibili_pages(&proxy_state, &bvid).await? else {
+        return Ok(None);
+    };
+    if pages.is_empty() {
+        return Ok(None);
+    }
+    danmaku::select_page(&pages, page)
+        .map(|p| Some(p.cid))
+        .map_err(AppError::InvalidInput)
+}
+
+/// 获取 B站视频的全部分P（CID 与标题）
+#[tauri::command]
+pub async fn get_bilibili_pages(
+    proxy_state: tauri::State<'_, crate::proxy::ProxyState>,
+    bvid: String,
+) -> Result<Vec<BilibiliPage>, AppError> {
+    Ok(fetch_bilibili_pages(&proxy_state, &bvid)
+        .await?
+        .unwrap_or_default())
 }
 
 /// 获取 B站弹幕列表
+///
+/// 优先分段获取 protobuf 弹幕，失败时回退到 XML 接口
 #[tauri::command]
 pub async fn get_bilibili_danmaku(
     proxy_state: tauri::State<'_, crate::proxy::ProxyState>,
     cid: u64,
 ) -> Result<Vec<DanmakuItem>, AppError> {
-    let url = format!("https://api.bilibili.com/x/v1/dm/list.so?oid={}", cid);
-
     let client = proxy_state.client().await;
-    let resp = client
-        .get(&url)
-        .header("User-Agent", "Mozilla/5.0")
-        .send()
+    let danmakus = danmaku::fetch_danmaku(&client, cid)
         .await
-        .map_err(|e| AppError::InvalidPath(e.to_string()))?;
-
-    let bytes = resp
-        .bytes()
-        .await
-        .map_err(|e| AppError::InvalidPath(e.to_string()))?;
-
-    // 尝试解压 deflate
-    let text = match flate2::read::DeflateDecoder::new(&bytes[..])
-        .bytes()
-        .collect::<Result<Vec<u8>, _>>()
-    {
-        Ok(decompressed) => String::from_utf8_lossy(&decompressed).to_string(),
-        Err(_) => String::from_utf8_lossy(&bytes).to_string(),
-    };
-
-    // 使用正则解析 XML 中的 <d> 标签
-    let mut danmakus = Vec::new();
-
-    // 查找所有 <d p="...">...</d> 模式
-    let mut pos = 0;
-    while let Some(start) = text[pos..].find("<d p=\"") {
-        let abs_start = pos + start;
-
-        // 找到 p 属性的结束引号
-        if let Some(attr_end) = text[abs_start + 6..].find("\"") {
-            let attr = &text[abs_start + 6..abs_start + 6 + attr_end];
-            let parts: Vec<&str> = attr.split(',').collect();
-
-            // 找到 > 和 </d>
-            let content_start = abs_start + 6 + attr_end + 2; // 跳过 ">
-            if let Some(content_end) = text[content_start..].find("</d>") {
-                let content = &text[content_start..content_start + content_end];
-
-                if parts.len() >= 5 {
-                    danmakus.push(DanmakuItem {
-                        time: parts[0].parse().unwrap_or(0.0),
-                        content: content.to_string(),
-                        timestamp: parts[4].parse().unwrap_or(0),
-                    });
-                }
-
-                pos = content_start + content_end + 4; // 跳过 </d>
-            } else {
-                pos = abs_start + 1;
-            }
-        } else {
-            pos = abs_start + 1;
-        }
-    }
-
-    // 按时间排序
-    danmakus.sort_by(|a, b| {
-        a.time
-            .partial_cmp(&b.time)
-            .unwrap_or(std::cmp::Ordering::Equal)
-    });
+        .map_err(AppError::Network)?;
 
     println!("[Danmaku] 解析到 {} 条弹幕", danmakus.len());
 
@@ -818,7 +1241,7 @@ pub async fn seek_video_time(app: AppHandle, seconds: f64) -> Result<(), AppErro
         );
         webview
             .eval(&js)
-            .map_err(|e| AppError::InvalidPath(e.to_string()))?;
+            .map_err(|e| AppError::Webview(e.to_string()))?;
     }
     Ok(())
 }
@@ -862,7 +1285,7 @@ pub async fn fill_danmaku_prefix(app: AppHandle, prefix: String) -> Result<(), A
         );
         webview
             .eval(&js)
-            .map_err(|e| AppError::InvalidPath(e.to_string()))?;
+            .map_err(|e| AppError::Webview(e.to_string()))?;
     }
     Ok(())
 }
@@ -921,7 +1344,7 @@ pub async fn setup_danmaku_autofill(app: AppHandle, prefix: String) -> Result<()
         );
         webview
             .eval(&js)
-            .map_err(|e| AppError::InvalidPath(e.to_string()))?;
+            .map_err(|e| AppError::Webview(e.to_string()))?;
     }
     Ok(())
 }
@@ -940,15 +1363,16 @@ pub async fn open_video_window(app: AppHandle, url: String) -> Result<(), AppErr
         "video-player",
         WebviewUrl::External(
             url.parse()
-                .map_err(|_| AppError::InvalidPath("Invalid URL".into()))?,
+                .map_err(|_| AppError::Parse("Invalid URL".into()))?,
         ),
     )
+    .initialization_script(video_time::VIDEO_TIME_REPORTER_SCRIPT)
     .title("视频播放器 - Lumina Note")
     .inner_size(960.0, 640.0)
     .min_inner_size(640.0, 480.0)
     .center()
     .build()
-    .map_err(|e| AppError::InvalidPath(e.to_string()))?;
+    .map_err(|e| AppError::Webview(e.to_string()))?;
 
     println!("[VideoWindow] 窗口已创建: {}", url);
 
@@ -957,88 +1381,124 @@ pub async fn open_video_window(app: AppHandle, url: String) -> Result<(), AppErr
 
 /// 关闭视频播放窗口
 #[tauri::command]
-pub async fn close_video_window(app: AppHandle) -> Result<(), AppError> {
+pub async fn close_video_window(
+    app: AppHandle,
+    video_time: State<'_, VideoTimeState>,
+) -> Result<(), AppError> {
     if let Some(window) = app.get_webview_window("video-player") {
         window
             .close()
-            .map_err(|e| AppError::InvalidPath(e.to_string()))?;
+            .map_err(|e| AppError::Webview(e.to_string()))?;
     }
+    video_time.clear();
     Ok(())
 }
 
-/// 获取视频当前时间（轮询方式）
+/// 获取视频当前时间
 /// 返回 JSON 字符串: {"currentTime": 123.45, "duration": 600.0, "paused": false} 或 null
 #[tauri::command]
-pub async fn get_video_time(app: AppHandle) -> Result<Option<String>, AppError> {
-    if let Some(window) = app.get_webview_window("video-player") {
-        // 使用 eval 执行 JS 并获取返回值
-        let script = r#"
-            (function() {
-                const video = document.querySelector('video');
-                if (video) {
-                    return JSON.stringify({
-                        currentTime: video.currentTime,
-                        duration: video.duration || 0,
-                        paused: video.paused
-                    });
-                }
-                return null;
-            })();
-        "#;
-
-        match window.eval(script) {
-            Ok(_) => {
-                // eval 不直接返回值，需要用其他方式
-                // 使用 webview 的 evaluate_script 或轮询 title 等
-                // 暂时返回 None，让前端用其他方式处理
-                Ok(None)
-            }
-            Err(e) => Err(AppError::InvalidPath(e.to_string())),
-        }
-    } else {
-        Ok(None)
+pub async fn get_video_time(
+    video_time: State<'_, VideoTimeState>,
+) -> Result<Option<String>, AppError> {
+    Ok(video_time.latest(std::time::Instant::now()).map(|info| {
+        serde_json::json!({
+            "currentTime": info.current_time,
+            "duration": info.duration,
+            "paused": info.paused,
+        })
+        .to_string()
+    }))
+}
+
+/// 读取视频时间（视频页面注入脚本通过 `report_video_time` 定期上报）
+#[tauri::command]
+pub async fn sync_video_time(
+    video_time: State<'_, VideoTimeState>,
+) -> Result<Option<VideoTimeInfo>, AppError> {
+    Ok(video_time.latest(std::time::Instant::now()))
+}
+
+/// 视频页面注入脚本上报播放进度（只接受视频 WebView 的调用）
+#[tauri::command]
+pub fn report_video_time(
+    webview: Webview,
+    video_time: State<'_, VideoTimeState>,
+    report: VideoTimeReport,
+) -> Result<(), AppError> {
+    if !video_time::is_video_webview(webview.label()) {
+        return Err(AppError::Webview(
+            "Video time can only be reported by the video player".into(),
+        ));
+    }
+    if let Some(info) = video_time::validate_report(report) {
+        video_time.record(info, std::time::Instant::now());
     }
+    Ok(())
 }
 
-/// 读取视频时间（从窗口标题获取，由 initialization_script 更新）
+/// 截图脚本返回结果（只接受视频 WebView 的调用）
 #[tauri::command]
-pub async fn sync_video_time(app: AppHandle) -> Result<Option<VideoTimeInfo>, AppError> {
-    if let Some(window) = app.get_webview_window("video-player") {
-        // 直接读取标题（由 initialization_script 定期更新）
-        if let Ok(title) = window.title() {
-            if title.starts_with("MX:") {
-                let parts: Vec<&str> = title.trim_start_matches("MX:").split(':').collect();
-                if parts.len() >= 3 {
-                    // 时间以毫秒存储，转回秒
-                    let current_time = parts[0].parse::<f64>().unwrap_or(0.0) / 1000.0;
-                    let duration = parts[1].parse::<f64>().unwrap_or(0.0) / 1000.0;
-                    let paused = parts[2] == "1";
-
-                    return Ok(Some(VideoTimeInfo {
-                        current_time,
-                        duration,
-                        paused,
-                    }));
-                }
-            }
-        }
+pub fn report_video_frame(
+    webview: Webview,
+    requests: State<'_, VideoFrameRequests>,
+    report: FrameReport,
+) -> Result<(), AppError> {
+    if !video_time::is_video_webview(webview.label()) {
+        return Err(AppError::Webview(
+            "Video frames can only be reported by the video player".into(),
+        ));
     }
-    Ok(None)
+    let (request_id, result) = video_frame::frame_result(report);
+    requests.resolve(&request_id, result);
+    Ok(())
 }
 
-#[derive(serde::Serialize)]
-pub struct VideoTimeInfo {
-    pub current_time: f64,
-    pub duration: f64,
-    pub paused: bool,
+/// 截取内嵌视频当前帧（PNG base64），附带截图时的播放时间
+///
+/// 跨域视频会污染 canvas，此时返回错误而不是空白图片
+#[tauri::command]
+pub async fn capture_video_frame(
+    app: AppHandle,
+    requests: State<'_, VideoFrameRequests>,
+    max_width: Option<u32>,
+) -> Result<VideoFrame, AppError> {
+    let webview = app
+        .get_webview("video-webview")
+        .ok_or_else(|| AppError::Webview("Video webview not found".into()))?;
+
+    let request_id = Uuid::new_v4().to_string();
+    let receiver = requests.register(&request_id);
+    let script = video_frame::capture_script(
+        &request_id,
+        max_width.unwrap_or(video_frame::DEFAULT_MAX_WIDTH),
+    );
+    if let Err(e) = webview.eval(&script) {
+        requests.cancel(&request_id);
+        return Err(AppError::Webview(e.to_string()));
+    }
+
+    match tokio::time::timeout(video_frame::CAPTURE_TIMEOUT, receiver).await {
+        Ok(Ok(result)) => result.map_err(AppError::Webview),
+        _ => {
+            requests.cancel(&request_id);
+            Err(AppError::Webview(
+                "Timed out waiting for the video frame".into(),
+            ))
+        }
+    }
 }
 
 /// Start file system watcher
-/// Emits "fs:change" events when files are created, modified, or deleted
+/// Emits debounced "fs:change" events (and one "fs:changes" batch per window)
+/// when files are created, modified, deleted or renamed
 #[tauri::command]
-pub async fn start_file_watcher(app: AppHandle, watch_path: String) -> Result<(), AppError> {
+pub async fn start_file_watcher(
+    app: AppHandle,
+    watch_path: String,
+    options: Option<watcher::WatcherOptions>,
+) -> Result<(), AppError> {
     fs::ensure_allowed_path(std::path::Path::new(&watch_path), true)?;
-    watcher::start_watcher(app, watch_path).map_err(|e| AppError::InvalidPath(e))
+    watcher::start_watcher(app, watch_path, options.unwrap_or_default()).map_err(AppError::Internal)
 }
 
 #[derive(serde::Serialize, Clone)]
@@ -1060,6 +1520,7 @@ pub async fn create_browser_webview(
     y: f64,
     width: f64,
     height: f64,
+    partition: Option<String>,
 ) -> Result<(), AppError> {
     let bounds = ChildWebviewBounds {
         x,
@@ -1070,13 +1531,15 @@ pub async fn create_browser_webview(
     let windows = app.windows();
     let main_window = windows
         .get("main")
-        .ok_or_else(|| AppError::InvalidPath("Main window not found".into()))?;
+        .ok_or_else(|| AppError::Webview("Main window not found".into()))?;
+    let partition =
+        BrowserPartition::parse(partition.as_deref()).map_err(AppError::InvalidInput)?;
 
     browser_debug_log(
         &app,
         format!(
-            "create_browser_webview: tab_id={} url={} rect=({}, {}) {}x{}",
-            tab_id, url, x, y, width, height
+            "create_browser_webview: tab_id={} url={} rect=({}, {}) {}x{} partition={:?}",
+            tab_id, url, x, y, width, height, partition
         ),
     );
 
@@ -1091,11 +1554,15 @@ pub async fn create_browser_webview(
     // 解析 URL
     let parsed_url: tauri::Url = url
         .parse()
-        .map_err(|_| AppError::InvalidPath("Invalid URL".into()))?;
+        .map_err(|_| AppError::Parse("Invalid URL".into()))?;
 
     // 拦截 window.open / 新窗口请求，通知前端创建新的网页标签页
     let app_handle = app.clone();
     let parent_tab_id = tab_id.clone();
+    let nav_app = app.clone();
+    let nav_tab_id = tab_id.clone();
+    let download_app = app.clone();
+    let download_tab_id = tab_id.clone();
 
     let webview_builder = WebviewBuilder::new(&webview_id, WebviewUrl::External(parsed_url))
         .on_new_window(move |new_url, _features| {
@@ -1117,7 +1584,44 @@ pub async fn create_browser_webview(
             } else {
                 NewWindowResponse::Allow
             }
-        });
+        })
+        // 原生加载状态；标题与历史记录由注入脚本上报
+        .on_page_load(move |_webview, payload| {
+            let loading = matches!(payload.event(), PageLoadEvent::Started);
+            nav_app
+                .state::<BrowserNavigationState>()
+                .set_loading(&nav_tab_id, loading);
+        })
+        // 下载保存到工作区内的下载目录
+        .on_download(move |_webview, event| {
+            browser_download::handle_download(&download_app, &download_tab_id, event)
+        })
+        .initialization_script(browser_navigation::REPORTER_SCRIPT);
+
+    // 存储分区：无痕标签页不落盘，命名分区使用独立的持久化目录
+    let webview_builder = match &partition {
+        BrowserPartition::Default => webview_builder,
+        BrowserPartition::Incognito => webview_builder.incognito(true),
+        BrowserPartition::Persistent(_) => {
+            let app_data_dir = app
+                .path()
+                .app_data_dir()
+                .map_err(|e| AppError::Internal(e.to_string()))?;
+            let builder = match partition.data_directory(&app_data_dir) {
+                Some(dir) => {
+                    std::fs::create_dir_all(&dir)?;
+                    webview_builder.data_directory(dir)
+                }
+                None => webview_builder,
+            };
+            #[cfg(target_os = "macos")]
+            let builder = match partition.data_store_identifier() {
+                Some(id) => builder.data_store_identifier(id),
+                None => builder,
+            };
+            builder
+        }
+    };
 
     let _webview = main_window
         .add_child(
@@ -1125,7 +1629,7 @@ pub async fn create_browser_webview(
             Position::Logical(LogicalPosition::new(x, y)),
             Size::Logical(LogicalSize::new(width, height)),
         )
-        .map_err(|e| AppError::InvalidPath(e.to_string()))?;
+        .map_err(|e| AppError::Webview(e.to_string()))?;
     bounds_state.remember(&webview_id, bounds);
 
     println!(
@@ -1167,10 +1671,10 @@ pub async fn update_browser_webview_bounds(
         );
         webview
             .set_position(Position::Logical(LogicalPosition::new(x, y)))
-            .map_err(|e| AppError::InvalidPath(e.to_string()))?;
+            .map_err(|e| AppError::Webview(e.to_string()))?;
         webview
             .set_size(Size::Logical(LogicalSize::new(width, height)))
-            .map_err(|e| AppError::InvalidPath(e.to_string()))?;
+            .map_err(|e| AppError::Webview(e.to_string()))?;
     }
     Ok(())
 }
@@ -1186,11 +1690,12 @@ pub async fn close_browser_webview(
     if let Some(webview) = app.get_webview(&webview_id) {
         webview
             .close()
-            .map_err(|e| AppError::InvalidPath(e.to_string()))?;
+            .map_err(|e| AppError::Webview(e.to_string()))?;
         println!("[Browser] WebView 已关闭: {}", webview_id);
         browser_debug_log(&app, format!("close_browser_webview: tab_id={}", tab_id));
     }
     bounds_state.forget(&webview_id);
+    app.state::<BrowserNavigationState>().forget(&tab_id);
     Ok(())
 }
 
@@ -1209,10 +1714,10 @@ pub async fn navigate_browser_webview(
         );
         let parsed_url: tauri::Url = url
             .parse()
-            .map_err(|_| AppError::InvalidPath("Invalid URL".into()))?;
+            .map_err(|_| AppError::Parse("Invalid URL".into()))?;
         webview
             .navigate(parsed_url)
-            .map_err(|e| AppError::InvalidPath(e.to_string()))?;
+            .map_err(|e| AppError::Webview(e.to_string()))?;
         println!("[Browser] 导航到: {}", url);
     }
     Ok(())
@@ -1227,7 +1732,7 @@ pub async fn browser_webview_go_back(app: AppHandle, tab_id: String) -> Result<(
         // 通过 JS 执行后退
         webview
             .eval("history.back()")
-            .map_err(|e| AppError::InvalidPath(e.to_string()))?;
+            .map_err(|e| AppError::Webview(e.to_string()))?;
     }
     Ok(())
 }
@@ -1243,11 +1748,99 @@ pub async fn browser_webview_go_forward(app: AppHandle, tab_id: String) -> Resul
         );
         webview
             .eval("history.forward()")
-            .map_err(|e| AppError::InvalidPath(e.to_string()))?;
+            .map_err(|e| AppError::Webview(e.to_string()))?;
+    }
+    Ok(())
+}
+
+/// 获取浏览器 WebView 的导航状态（URL、标题、能否后退 / 前进、是否加载中）
+#[tauri::command]
+pub async fn browser_webview_get_state(
+    app: AppHandle,
+    nav_state: State<'_, BrowserNavigationState>,
+    tab_id: String,
+) -> Result<Option<BrowserWebviewState>, AppError> {
+    let webview_id = format!("browser-{}", tab_id);
+    let Some(webview) = app.get_webview(&webview_id) else {
+        return Ok(None);
+    };
+    let native_url = webview.url().ok().map(|url| url.to_string());
+    Ok(Some(nav_state.snapshot(&tab_id, native_url)))
+}
+
+/// 标签页注入脚本上报导航状态，tab_id 取自调用方 WebView 的标识
+#[tauri::command]
+pub fn report_browser_state(
+    webview: Webview,
+    nav_state: State<'_, BrowserNavigationState>,
+    report: PageStateReport,
+) -> Result<(), AppError> {
+    let tab_id = browser_navigation::tab_id_for_label(webview.label()).ok_or_else(|| {
+        AppError::Webview("Navigation state can only be reported by browser tabs".into())
+    })?;
+    nav_state.record(tab_id, report);
+    Ok(())
+}
+
+/// 网页内查找：高亮全部匹配并滚动到下一项 / 上一项，结果通过 `browser:find-result` 事件返回
+///
+/// `tab_id` 为 `"video"` 时在视频 WebView 中查找；空查询等同于清除高亮。
+#[tauri::command]
+pub async fn browser_webview_find(
+    app: AppHandle,
+    tab_id: String,
+    query: String,
+    forward: Option<bool>,
+    match_case: Option<bool>,
+) -> Result<(), AppError> {
+    let webview = app
+        .get_webview(&browser_find::webview_label(&tab_id))
+        .ok_or_else(|| AppError::Webview(format!("Webview not found: {}", tab_id)))?;
+    let script =
+        browser_find::find_script(&query, forward.unwrap_or(true), match_case.unwrap_or(false))
+            .map_err(AppError::InvalidInput)?;
+    webview
+        .eval(&script)
+        .map_err(|e| AppError::Webview(e.to_string()))
+}
+
+/// 查找脚本上报匹配数量，校验后以 `browser:find-result` 转发给前端
+#[tauri::command]
+pub fn report_find_result(
+    app: AppHandle,
+    webview: Webview,
+    report: FindReport,
+) -> Result<(), AppError> {
+    let result = browser_find::find_result(webview.label(), report).ok_or_else(|| {
+        AppError::Webview("Find results can only be reported by browser tabs".into())
+    })?;
+    let _ = app.emit(browser_find::BROWSER_FIND_RESULT_EVENT, result);
+    Ok(())
+}
+
+/// 清除网页内查找的高亮
+#[tauri::command]
+pub async fn browser_webview_find_clear(app: AppHandle, tab_id: String) -> Result<(), AppError> {
+    if let Some(webview) = app.get_webview(&browser_find::webview_label(&tab_id)) {
+        webview
+            .eval(browser_find::clear_script())
+            .map_err(|e| AppError::Webview(e.to_string()))?;
     }
     Ok(())
 }
 
+/// 设置浏览器下载目录（必须位于允许访问的工作区内）
+#[tauri::command]
+pub async fn set_browser_download_dir(
+    download_state: State<'_, BrowserDownloadState>,
+    dir: String,
+) -> Result<(), AppError> {
+    let dir = PathBuf::from(dir);
+    fs::ensure_allowed_path(&dir, false)?;
+    download_state.set_dir(dir);
+    Ok(())
+}
+
 /// 浏览器 WebView 刷新
 #[tauri::command]
 pub async fn browser_webview_reload(app: AppHandle, tab_id: String) -> Result<(), AppError> {
@@ -1256,7 +1849,7 @@ pub async fn browser_webview_reload(app: AppHandle, tab_id: String) -> Result<()
         browser_debug_log(&app, format!("browser_webview_reload: tab_id={}", tab_id));
         webview
             .eval("location.reload()")
-            .map_err(|e| AppError::InvalidPath(e.to_string()))?;
+            .map_err(|e| AppError::Webview(e.to_string()))?;
     }
     Ok(())
 }
@@ -1279,107 +1872,164 @@ pub async fn set_browser_webview_visible(
             ),
         );
         if visible {
+            // 冻结时可能被原生隐藏
+            webview
+                .show()
+                .map_err(|e| AppError::Webview(e.to_string()))?;
             if let Some(bounds) = bounds_state.get(&webview_id) {
                 webview
                     .set_position(Position::Logical(LogicalPosition::new(bounds.x, bounds.y)))
-                    .map_err(|e| AppError::InvalidPath(e.to_string()))?;
+                    .map_err(|e| AppError::Webview(e.to_string()))?;
                 webview
                     .set_size(Size::Logical(LogicalSize::new(bounds.width, bounds.height)))
-                    .map_err(|e| AppError::InvalidPath(e.to_string()))?;
+                    .map_err(|e| AppError::Webview(e.to_string()))?;
             }
         } else {
             // 移到屏幕外隐藏
             webview
                 .set_position(Position::Logical(LogicalPosition::new(-10000.0, -10000.0)))
-                .map_err(|e| AppError::InvalidPath(e.to_string()))?;
+                .map_err(|e| AppError::Webview(e.to_string()))?;
         }
     }
     Ok(())
 }
 
-/// 冻结浏览器 WebView（暂停 JS 执行，降低资源占用）
+/// 冻结时注入的脚本：暂停媒体与动画，并把页面标记为后台（页面据此自行节流）
+const BROWSER_FREEZE_JS: &str = r#"
+    (function() {
+        if (window.__lumina_frozen) return;
+        window.__lumina_frozen = true;
+
+        // 暂停所有媒体
+        document.querySelectorAll('video, audio').forEach(el => {
+            if (!el.paused) {
+                el.__lumina_was_playing = true;
+                el.pause();
+            }
+        });
+
+        // 暂停所有动画
+        document.getAnimations().forEach(anim => anim.pause());
+
+        // 覆盖可见性（原生隐藏失败、退回屏幕外定位时引擎不会更新它）
+        const define = (name, value) => {
+            try {
+                Object.defineProperty(document, name, { configurable: true, get: () => value });
+            } catch (e) {}
+        };
+        define('visibilityState', 'hidden');
+        define('hidden', true);
+        document.dispatchEvent(new Event('visibilitychange'));
+        document.dispatchEvent(new Event('freeze'));
+
+        console.log('[Lumina] Page frozen');
+    })();
+"#;
+
+/// 解冻时注入的脚本：撤销可见性覆盖并恢复媒体与动画
+const BROWSER_UNFREEZE_JS: &str = r#"
+    (function() {
+        if (!window.__lumina_frozen) return;
+        window.__lumina_frozen = false;
+
+        // 删除实例上的覆盖，回到引擎提供的真实值
+        delete document.visibilityState;
+        delete document.hidden;
+        document.dispatchEvent(new Event('resume'));
+        document.dispatchEvent(new Event('visibilitychange'));
+
+        // 恢复媒体播放
+        document.querySelectorAll('video, audio').forEach(el => {
+            if (el.__lumina_was_playing) {
+                el.play().catch(() => {});
+                delete el.__lumina_was_playing;
+            }
+        });
+
+        // 恢复动画
+        document.getAnimations().forEach(anim => anim.play());
+
+        console.log('[Lumina] Page unfrozen');
+    })();
+"#;
+
+/// 浏览器 WebView 冻结结果
+#[derive(serde::Serialize)]
+pub struct BrowserFreezeResult {
+    /// 是否通过原生隐藏挂起页面：引擎停止渲染、暂停 rAF 并按后台页节流定时器
+    pub suspended: bool,
+    /// 实际采用的方式："native-hide" 或 "offscreen"
+    pub method: &'static str,
+}
+
+/// 冻结浏览器 WebView（降低后台标签页的资源占用）
+///
+/// 优先原生隐藏 WebView，由引擎按后台页挂起渲染并节流定时器；
+/// 原生隐藏不可用时退回屏幕外定位，此时只有注入脚本的暂停效果
 #[tauri::command]
-pub async fn browser_webview_freeze(app: AppHandle, tab_id: String) -> Result<(), AppError> {
+pub async fn browser_webview_freeze(
+    app: AppHandle,
+    tab_id: String,
+) -> Result<Option<BrowserFreezeResult>, AppError> {
     let webview_id = format!("browser-{}", tab_id);
-    if let Some(webview) = app.get_webview(&webview_id) {
-        browser_debug_log(&app, format!("browser_webview_freeze: tab_id={}", tab_id));
-
-        // 注入 JS 暂停页面活动
-        // 1. 暂停所有定时器
-        // 2. 暂停所有动画
-        // 3. 暂停媒体播放
-        let freeze_js = r#"
-            (function() {
-                // 保存原始函数
-                if (!window.__lumina_frozen) {
-                    window.__lumina_frozen = true;
-                    window.__lumina_original_setInterval = window.setInterval;
-                    window.__lumina_original_setTimeout = window.setTimeout;
-                    window.__lumina_interval_ids = [];
-                    window.__lumina_timeout_ids = [];
-                    
-                    // 暂停所有媒体
-                    document.querySelectorAll('video, audio').forEach(el => {
-                        if (!el.paused) {
-                            el.__lumina_was_playing = true;
-                            el.pause();
-                        }
-                    });
-                    
-                    // 暂停所有动画
-                    document.getAnimations().forEach(anim => anim.pause());
-                    
-                    console.log('[Lumina] Page frozen');
-                }
-            })();
-        "#;
+    let Some(webview) = app.get_webview(&webview_id) else {
+        return Ok(None);
+    };
+    browser_debug_log(&app, format!("browser_webview_freeze: tab_id={}", tab_id));
 
-        webview
-            .eval(freeze_js)
-            .map_err(|e| AppError::InvalidPath(e.to_string()))?;
+    webview
+        .eval(BROWSER_FREEZE_JS)
+        .map_err(|e| AppError::Webview(e.to_string()))?;
 
-        // 移到屏幕外
-        webview
-            .set_position(Position::Logical(LogicalPosition::new(-10000.0, -10000.0)))
-            .map_err(|e| AppError::InvalidPath(e.to_string()))?;
+    let result = match webview.hide() {
+        Ok(()) => BrowserFreezeResult {
+            suspended: true,
+            method: "native-hide",
+        },
+        Err(err) => {
+            browser_debug_log(
+                &app,
+                format!(
+                    "browser_webview_freeze: native hide failed for tab_id={}: {}",
+                    tab_id, err
+                ),
+            );
+            webview
+                .set_position(Position::Logical(LogicalPosition::new(-10000.0, -10000.0)))
+                .map_err(|e| AppError::Webview(e.to_string()))?;
+            BrowserFreezeResult {
+                suspended: false,
+                method: "offscreen",
+            }
+        }
+    };
 
-        println!("[Browser] WebView 已冻结: {}", webview_id);
-    }
-    Ok(())
+    println!(
+        "[Browser] WebView 已冻结: {} ({})",
+        webview_id, result.method
+    );
+    Ok(Some(result))
 }
 
 /// 解冻浏览器 WebView（恢复 JS 执行）
+///
+/// 解冻后标签页回到后台状态（仍在屏幕外），切换到该标签页时再恢复位置
 #[tauri::command]
 pub async fn browser_webview_unfreeze(app: AppHandle, tab_id: String) -> Result<(), AppError> {
     let webview_id = format!("browser-{}", tab_id);
     if let Some(webview) = app.get_webview(&webview_id) {
         browser_debug_log(&app, format!("browser_webview_unfreeze: tab_id={}", tab_id));
 
-        // 注入 JS 恢复页面活动
-        let unfreeze_js = r#"
-            (function() {
-                if (window.__lumina_frozen) {
-                    window.__lumina_frozen = false;
-                    
-                    // 恢复媒体播放
-                    document.querySelectorAll('video, audio').forEach(el => {
-                        if (el.__lumina_was_playing) {
-                            el.play().catch(() => {});
-                            delete el.__lumina_was_playing;
-                        }
-                    });
-                    
-                    // 恢复动画
-                    document.getAnimations().forEach(anim => anim.play());
-                    
-                    console.log('[Lumina] Page unfrozen');
-                }
-            })();
-        "#;
-
+        // 先移到屏幕外再取消原生隐藏，避免解冻的后台页闪现
+        webview
+            .set_position(Position::Logical(LogicalPosition::new(-10000.0, -10000.0)))
+            .map_err(|e| AppError::Webview(e.to_string()))?;
         webview
-            .eval(unfreeze_js)
-            .map_err(|e| AppError::InvalidPath(e.to_string()))?;
+            .show()
+            .map_err(|e| AppError::Webview(e.to_string()))?;
+        webview
+            .eval(BROWSER_UNFREEZE_JS)
+            .map_err(|e| AppError::Webview(e.to_string()))?;
 
         println!("[Browser] WebView 已解冻: {}", webview_id);
     }
@@ -1597,4 +2247,58 @@ mod tests {
         assert!(layout.lines[0].x_offset > 10);
         assert_eq!(layout.lines[0].y_offset, 12);
     }
+
+    #[tokio::test]
+    async fn typeset_markdown_to_pdf_base64_returns_pdf() {
+        use base64::{engine::general_purpose::STANDARD, Engine as _};
+
+        let payload = typeset_markdown_to_pdf_base64(
+            "# Title\n\nBody with `code`.\n\n- item".to_string(),
+            fixture_font_path(),
+            None,
+        )
+        .await
+        .expect("typeset markdown");
+        let decoded = STANDARD.decode(payload).expect("decode base64");
+
+        assert!(decoded.starts_with(b"%PDF-"));
+        assert!(String::from_utf8_lossy(&decoded).contains("/Count 1 >>"));
+    }
+
+    #[tokio::test]
+    async fn typesetting_build_preview_paginates_paragraphs() {
+        let text = ["Hello world"; 60].join("\n");
+        let preview = typesetting_build_preview(
+            text.clone(),
+            fixture_font_path(),
+            None,
+            Some(TypesettingPreviewOptions {
+                page_gap_px: Some(10),
+                ..TypesettingPreviewOptions::default()
+            }),
+        )
+        .await
+        .expect("preview should succeed");
+
+        assert!(preview.page_count > 1);
+        assert_eq!(preview.pages.len(), preview.page_count);
+        let count = preview.page_count as i32;
+        assert_eq!(
+            preview.total_height_px,
+            preview.page_height_px * count + 10 * (count - 1)
+        );
+        assert_eq!(preview.pages[1].top_px, preview.page_height_px + 10);
+
+        let first = &preview.pages[0].lines[0];
+        approx_eq(first.x_mm, 25.0);
+        approx_eq(first.y_mm, 37.0);
+        let second_page_line = &preview.pages[1].lines[0];
+        approx_eq(second_page_line.y_mm, 37.0);
+        assert_eq!(
+            &text[second_page_line.start_byte..second_page_line.end_byte],
+            "Hello world"
+        );
+        let line_count: usize = preview.pages.iter().map(|page| page.lines.len()).sum();
+        assert_eq!(line_count, 60);
+    }
 }
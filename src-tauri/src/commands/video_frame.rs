@@ -0,0 +1,223 @@
+//! 视频帧截图
+//!
+//! 在 `video-webview` 中执行脚本，把当前帧绘制到 canvas 并导出 PNG。
+//! `eval` 拿不到返回值，结果和视频播放进度一样由页面调用 `report_video_frame` 命令发回 Rust，
+//! 按请求 ID 交给等待中的 `capture_video_frame` 命令。
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use tokio::sync::oneshot;
+
+/// 页面返回截图结果的命令名
+pub const VIDEO_FRAME_COMMAND: &str = "report_video_frame";
+
+/// 等待页面返回截图的超时时间
+pub(crate) const CAPTURE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 默认缩略图最大宽度（像素）
+pub(crate) const DEFAULT_MAX_WIDTH: u32 = 1280;
+
+const PNG_DATA_URL_PREFIX: &str = "data:image/png;base64,";
+
+const CAPTURE_SCRIPT_TEMPLATE: &str = r#"
+(function () {
+    const requestId = __REQUEST_ID__;
+    const maxWidth = __MAX_WIDTH__;
+    const send = (payload) => {
+        const tauri = window.__TAURI__;
+        if (!tauri || !tauri.core || typeof tauri.core.invoke !== 'function') return;
+        tauri.core.invoke('report_video_frame', {
+            report: Object.assign({ requestId }, payload),
+        }).catch(() => {});
+    };
+
+    const videos = Array.from(document.querySelectorAll('video'));
+    const video = videos.find((v) => !v.paused && !v.ended) || videos.sort(
+        (a, b) => b.clientWidth * b.clientHeight - a.clientWidth * a.clientHeight
+    )[0];
+    if (!video) {
+        send({ error: 'no-video', currentTime: 0 });
+        return;
+    }
+    if (video.readyState < 2 || !video.videoWidth) {
+        send({ error: 'not-ready', currentTime: video.currentTime });
+        return;
+    }
+
+    const scale = Math.min(1, maxWidth / video.videoWidth);
+    const canvas = document.createElement('canvas');
+    canvas.width = Math.max(1, Math.round(video.videoWidth * scale));
+    canvas.height = Math.max(1, Math.round(video.videoHeight * scale));
+    try {
+        canvas.getContext('2d').drawImage(video, 0, 0, canvas.width, canvas.height);
+        // 跨域视频会污染 canvas，toDataURL 抛出 SecurityError
+        send({
+            dataUrl: canvas.toDataURL('image/png'),
+            width: canvas.width,
+            height: canvas.height,
+            currentTime: video.currentTime,
+        });
+    } catch (e) {
+        send({
+            error: e && e.name === 'SecurityError' ? 'tainted' : String((e && e.message) || e),
+            currentTime: video.currentTime,
+        });
+    }
+})();
+"#;
+
+/// 截取到的视频帧
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct VideoFrame {
+    /// PNG 图片的 base64（不含 `data:` 前缀）
+    pub png_base64: String,
+    pub width: u32,
+    pub height: u32,
+    /// 截图时的播放时间（秒）
+    pub current_time: f64,
+}
+
+/// 页面返回的截图结果
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FrameReport {
+    request_id: String,
+    #[serde(default)]
+    data_url: Option<String>,
+    #[serde(default)]
+    width: u32,
+    #[serde(default)]
+    height: u32,
+    #[serde(default)]
+    current_time: f64,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+/// 生成截图脚本
+pub(crate) fn capture_script(request_id: &str, max_width: u32) -> String {
+    let request_id = serde_json::to_string(request_id).unwrap_or_else(|_| "\"\"".to_string());
+    CAPTURE_SCRIPT_TEMPLATE
+        .replace("__REQUEST_ID__", &request_id)
+        .replace("__MAX_WIDTH__", &max_width.max(1).to_string())
+}
+
+/// 校验页面返回的截图结果，返回 (请求 ID, 结果)
+pub(crate) fn frame_result(report: FrameReport) -> (String, Result<VideoFrame, String>) {
+    let result = match (report.error.as_deref(), report.data_url) {
+        (Some("tainted"), _) => Err(
+            "The video is served cross-origin, so the browser blocks reading its pixels (tainted canvas)"
+                .to_string(),
+        ),
+        (Some("no-video"), _) => Err("No video element found on the page".to_string()),
+        (Some("not-ready"), _) => Err("The video frame has not loaded yet".to_string()),
+        (Some(other), _) => Err(format!("Failed to capture video frame: {}", other)),
+        (None, Some(data_url)) => match data_url.strip_prefix(PNG_DATA_URL_PREFIX) {
+            // 空白 canvas 导出的 data URL 只有前缀
+            Some(base64) if !base64.is_empty() && report.width > 0 && report.height > 0 => {
+                Ok(VideoFrame {
+                    png_base64: base64.to_string(),
+                    width: report.width,
+                    height: report.height,
+                    current_time: report.current_time.max(0.0),
+                })
+            }
+            _ => Err("The page returned an empty or non-PNG frame".to_string()),
+        },
+        (None, None) => Err("The page returned no frame data".to_string()),
+    };
+    (report.request_id, result)
+}
+
+type FrameSender = oneshot::Sender<Result<VideoFrame, String>>;
+
+/// 等待页面返回的截图请求
+#[derive(Default)]
+pub struct VideoFrameRequests(Mutex<HashMap<String, FrameSender>>);
+
+impl VideoFrameRequests {
+    pub(crate) fn register(
+        &self,
+        request_id: &str,
+    ) -> oneshot::Receiver<Result<VideoFrame, String>> {
+        let (sender, receiver) = oneshot::channel();
+        if let Ok(mut pending) = self.0.lock() {
+            pending.insert(request_id.to_string(), sender);
+        }
+        receiver
+    }
+
+    /// 请求超时或失败时移除
+    pub(crate) fn cancel(&self, request_id: &str) {
+        if let Ok(mut pending) = self.0.lock() {
+            pending.remove(request_id);
+        }
+    }
+
+    pub(crate) fn resolve(&self, request_id: &str, result: Result<VideoFrame, String>) {
+        let sender = self
+            .0
+            .lock()
+            .ok()
+            .and_then(|mut pending| pending.remove(request_id));
+        if let Some(sender) = sender {
+            let _ = sender.send(result);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_frame_report(payload: &str) -> Option<(String, Result<VideoFrame, String>)> {
+        serde_json::from_str(payload).ok().map(frame_result)
+    }
+
+    #[test]
+    fn parses_frames_and_capture_errors() {
+        let (id, frame) = parse_frame_report(
+            r#"{"requestId":"r1","dataUrl":"data:image/png;base64,iVBORw0K","width":640,"height":360,"currentTime":754.2}"#,
+        )
+        .unwrap();
+        assert_eq!(id, "r1");
+        assert_eq!(
+            frame.unwrap(),
+            VideoFrame {
+                png_base64: "iVBORw0K".to_string(),
+                width: 640,
+                height: 360,
+                current_time: 754.2,
+            }
+        );
+
+        let (_, tainted) =
+            parse_frame_report(r#"{"requestId":"r2","error":"tainted","currentTime":1}"#).unwrap();
+        assert!(tainted.unwrap_err().contains("tainted canvas"));
+
+        let (_, blank) = parse_frame_report(
+            r#"{"requestId":"r3","dataUrl":"data:,","width":0,"height":0,"currentTime":1}"#,
+        )
+        .unwrap();
+        assert!(blank.is_err());
+
+        assert!(parse_frame_report("{}").is_none());
+    }
+
+    #[test]
+    fn routes_results_to_pending_requests() {
+        let requests = VideoFrameRequests::default();
+        let mut receiver = requests.register("a");
+        requests.resolve("b", Err("ignored".to_string()));
+        assert!(receiver.try_recv().is_err());
+        requests.resolve("a", Err("no video".to_string()));
+        assert_eq!(receiver.try_recv().unwrap(), Err("no video".to_string()));
+
+        let script = capture_script("x\"y", 0);
+        assert!(script.contains(r#"const requestId = "x\"y";"#));
+        assert!(script.contains("const maxWidth = 1;"));
+        assert!(script.contains(&format!("'{}'", VIDEO_FRAME_COMMAND)));
+    }
+}
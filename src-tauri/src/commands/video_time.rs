@@ -0,0 +1,174 @@
+//! 视频播放进度上报
+//!
+//! 视频页面加载时注入脚本，定期读取页面中的 `<video>` 元素，
+//! 通过 `report_video_time` 命令把 `{currentTime, duration, paused}` 发回 Rust，
+//! 由 `VideoTimeState` 保存最近一次上报，`sync_video_time` 直接读取。
+//! 不依赖站点的播放器结构或窗口标题，内嵌 `video-webview` 与独立播放窗口通用。
+//!
+//! 视频页面是任意远程站点，不授予通用的事件发送权限：上报命令只接受播放进度，
+//! 并且只处理来自视频 WebView 的调用。
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// 视频页面上报播放进度的命令名
+pub const VIDEO_TIME_COMMAND: &str = "report_video_time";
+
+/// 内嵌播放器与独立播放窗口的 WebView 标识
+const VIDEO_WEBVIEW_LABELS: [&str; 2] = ["video-webview", "video-player"];
+
+/// 超过该时长没有上报则视为没有可用的视频（页面已关闭或已离开视频页）
+const MAX_REPORT_AGE: Duration = Duration::from_secs(5);
+
+/// 注入视频页面的上报脚本（每次页面加载时执行）
+pub(crate) const VIDEO_TIME_REPORTER_SCRIPT: &str = r#"
+(function () {
+    if (window.__luminaVideoTimeReporter) return;
+    window.__luminaVideoTimeReporter = true;
+
+    // 优先正在播放的视频，否则取页面上面积最大的视频
+    const findVideo = () => {
+        const videos = Array.from(document.querySelectorAll('video'));
+        const playing = videos.find((v) => !v.paused && !v.ended);
+        if (playing) return playing;
+        return videos.sort(
+            (a, b) => b.clientWidth * b.clientHeight - a.clientWidth * a.clientHeight
+        )[0] || null;
+    };
+
+    let lastSent = 0;
+    const report = (force) => {
+        const tauri = window.__TAURI__;
+        if (!tauri || !tauri.core || typeof tauri.core.invoke !== 'function') return;
+        const now = Date.now();
+        if (!force && now - lastSent < 250) return;
+        const video = findVideo();
+        if (!video) return;
+        lastSent = now;
+        tauri.core.invoke('report_video_time', {
+            report: {
+                currentTime: video.currentTime,
+                duration: Number.isFinite(video.duration) ? video.duration : 0,
+                paused: video.paused,
+            },
+        }).catch(() => {});
+    };
+
+    // 媒体事件不冒泡，在捕获阶段监听
+    ['play', 'pause', 'seeked', 'loadedmetadata'].forEach((type) =>
+        document.addEventListener(type, () => report(true), true)
+    );
+    document.addEventListener('timeupdate', () => report(false), true);
+    setInterval(() => report(true), 1000);
+})();
+"#;
+
+/// 视频播放状态
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize)]
+pub struct VideoTimeInfo {
+    pub current_time: f64,
+    pub duration: f64,
+    pub paused: bool,
+}
+
+/// 页面上报的原始数据
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VideoTimeReport {
+    current_time: f64,
+    #[serde(default)]
+    duration: Option<f64>,
+    #[serde(default)]
+    paused: bool,
+}
+
+/// 是否为视频播放 WebView（只接受它们的上报）
+pub(crate) fn is_video_webview(label: &str) -> bool {
+    VIDEO_WEBVIEW_LABELS.contains(&label)
+}
+
+/// 校验上报数据（页面可能是任意站点，不信任其内容）
+pub(crate) fn validate_report(report: VideoTimeReport) -> Option<VideoTimeInfo> {
+    if !report.current_time.is_finite() || report.current_time < 0.0 {
+        return None;
+    }
+    let duration = report
+        .duration
+        .filter(|d| d.is_finite() && *d >= 0.0)
+        .unwrap_or(0.0);
+    Some(VideoTimeInfo {
+        current_time: report.current_time,
+        duration,
+        paused: report.paused,
+    })
+}
+
+/// 最近一次上报的播放状态
+#[derive(Default)]
+pub struct VideoTimeState(Mutex<Option<(VideoTimeInfo, Instant)>>);
+
+impl VideoTimeState {
+    pub(crate) fn record(&self, info: VideoTimeInfo, at: Instant) {
+        if let Ok(mut latest) = self.0.lock() {
+            *latest = Some((info, at));
+        }
+    }
+
+    /// 读取仍然有效的最近一次上报
+    pub(crate) fn latest(&self, now: Instant) -> Option<VideoTimeInfo> {
+        self.0.lock().ok().and_then(|latest| {
+            latest
+                .filter(|(_, at)| now.saturating_duration_since(*at) <= MAX_REPORT_AGE)
+                .map(|(info, _)| info)
+        })
+    }
+
+    pub(crate) fn clear(&self) {
+        if let Ok(mut latest) = self.0.lock() {
+            *latest = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_report(payload: &str) -> Option<VideoTimeInfo> {
+        validate_report(serde_json::from_str(payload).ok()?)
+    }
+
+    #[test]
+    fn validates_reports_and_expires_stale_values() {
+        assert_eq!(
+            parse_report(r#"{"currentTime":12.5,"duration":null,"paused":true}"#),
+            Some(VideoTimeInfo {
+                current_time: 12.5,
+                duration: 0.0,
+                paused: true,
+            })
+        );
+        assert_eq!(parse_report(r#"{"currentTime":-1,"duration":10}"#), None);
+        assert_eq!(parse_report(r#"{"duration":10}"#), None);
+        assert_eq!(parse_report("not json"), None);
+
+        let state = VideoTimeState::default();
+        let start = Instant::now();
+        let info = parse_report(r#"{"currentTime":3,"duration":60,"paused":false}"#).unwrap();
+        state.record(info, start);
+        assert_eq!(state.latest(start + Duration::from_secs(1)), Some(info));
+        assert_eq!(state.latest(start + Duration::from_secs(10)), None);
+
+        state.record(info, start);
+        state.clear();
+        assert_eq!(state.latest(start), None);
+    }
+
+    #[test]
+    fn reporter_script_invokes_the_report_command() {
+        assert!(VIDEO_TIME_REPORTER_SCRIPT.contains(&format!("'{}'", VIDEO_TIME_COMMAND)));
+        assert!(!VIDEO_TIME_REPORTER_SCRIPT.contains("event.emit"));
+        assert!(is_video_webview("video-webview") && is_video_webview("video-player"));
+        assert!(!is_video_webview("browser-video-webview") && !is_video_webview("main"));
+    }
+}
@@ -3,13 +3,17 @@ use crate::node_runtime::{
     arch_tag, current_arch, current_platform, platform_tag, NodeArch, NodePlatform,
 };
 use futures_util::StreamExt;
+use once_cell::sync::Lazy;
 use serde::Deserialize;
 use serde::Serialize;
 use sha2::{Digest, Sha256};
 use std::collections::HashMap;
 use std::env;
 use std::path::{Path, PathBuf};
-use tauri::{AppHandle, Manager};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+use tauri::{AppHandle, Emitter, Manager};
 use tokio::io::AsyncWriteExt;
 
 #[derive(Debug, Serialize)]
@@ -29,6 +33,46 @@ pub struct ToolStatus {
     pub available: bool,
     pub path: Option<String>,
     pub source: Option<String>,
+    /// First line of `<tool> --version`
+    pub version: Option<String>,
+    /// SHA-256 of the binary (pack tools only)
+    pub checksum: Option<String>,
+    pub integrity: ToolIntegrity,
+}
+
+/// Whether a tool binary still matches the checksum recorded at install time
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ToolIntegrity {
+    Verified,
+    Mismatch,
+    /// System tools, or packs installed before checksums were recorded
+    Unverified,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InstallPhase {
+    Downloading,
+    Verifying,
+    Extracting,
+    Done,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DocToolsInstallProgress {
+    pub phase: InstallPhase,
+    pub version: String,
+    pub downloaded_bytes: u64,
+    pub total_bytes: Option<u64>,
+}
+
+/// Checksums of the pack binaries, written next to the pack after install
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct IntegrityManifest {
+    tools: HashMap<String, String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -42,6 +86,20 @@ const DOC_TOOLS_ENV_URL: &str = "LUMINA_DOC_TOOLS_URL";
 const DOC_TOOLS_ENV_MANIFEST_URL: &str = "LUMINA_DOC_TOOLS_MANIFEST_URL";
 const DEFAULT_DOC_TOOLS_MANIFEST_URL: &str =
     "https://github.com/blueberrycongee/Lumina-Note/releases/latest/download/doc-tools-manifest.json";
+const DOC_TOOLS_INSTALL_PROGRESS_EVENT: &str = "doc-tools:install-progress";
+const INTEGRITY_FILE_NAME: &str = ".lumina-integrity.json";
+const PROGRESS_EMIT_INTERVAL: Duration = Duration::from_millis(150);
+const VERSION_PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Cancellation flag of the running install (at most one at a time)
+static INSTALL_CANCEL: Mutex<Option<Arc<AtomicBool>>> = Mutex::new(None);
+
+/// Probed version and checksum per binary, keyed by path and invalidated when
+/// the file's size or mtime changes
+type ToolFingerprint = (Option<SystemTime>, u64);
+type ToolInspection = (ToolFingerprint, Option<String>, Option<String>);
+static TOOL_INSPECTION_CACHE: Lazy<Mutex<HashMap<PathBuf, ToolInspection>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
 
 #[derive(Debug, Deserialize)]
 struct DocToolsManifest {
@@ -130,6 +188,8 @@ fn tool_candidates() -> Vec<(&'static str, Vec<&'static str>)> {
         ("pandoc", vec!["pandoc"]),
         ("soffice", vec!["soffice", "soffice.bin"]),
         ("pdftoppm", vec!["pdftoppm"]),
+        ("pdftotext", vec!["pdftotext"]),
+        ("tesseract", vec!["tesseract"]),
     ]
 }
 
@@ -296,6 +356,9 @@ fn tool_status_for(
                 available: true,
                 path: Some(p.to_string_lossy().to_string()),
                 source: Some("pack".to_string()),
+                version: None,
+                checksum: None,
+                integrity: ToolIntegrity::Unverified,
             };
         }
     }
@@ -305,6 +368,9 @@ fn tool_status_for(
             available: true,
             path: Some(p.to_string_lossy().to_string()),
             source: Some("system".to_string()),
+            version: None,
+            checksum: None,
+            integrity: ToolIntegrity::Unverified,
         };
     }
 
@@ -312,7 +378,124 @@ fn tool_status_for(
         available: false,
         path: None,
         source: None,
+        version: None,
+        checksum: None,
+        integrity: ToolIntegrity::Unverified,
+    }
+}
+
+fn integrity_manifest_path(root: &Path) -> PathBuf {
+    root.join(INTEGRITY_FILE_NAME)
+}
+
+fn read_integrity_manifest(root: &Path) -> Option<IntegrityManifest> {
+    let data = std::fs::read_to_string(integrity_manifest_path(root)).ok()?;
+    serde_json::from_str(&data).ok()
+}
+
+/// Record the checksums of the pack binaries right after a verified install
+async fn write_integrity_manifest(
+    root: &Path,
+    bin_dir: &Path,
+    platform: NodePlatform,
+) -> Result<(), AppError> {
+    let mut manifest = IntegrityManifest::default();
+    for (name, aliases) in tool_candidates() {
+        if let Some(path) = resolve_tool_in_dir(bin_dir, &aliases, platform) {
+            manifest
+                .tools
+                .insert(name.to_string(), sha256_file(&path).await?);
+        }
     }
+    tokio::fs::write(
+        integrity_manifest_path(root),
+        serde_json::to_string_pretty(&manifest).unwrap_or_else(|_| "{}".into()),
+    )
+    .await?;
+    Ok(())
+}
+
+fn version_args(name: &str) -> &'static [&'static str] {
+    match name {
+        "pdftoppm" => &["-v"],
+        _ => &["--version"],
+    }
+}
+
+/// First non-empty output line of the tool's version flag (some tools print
+/// it on stderr)
+async fn probe_version(name: &str, path: &Path) -> Option<String> {
+    let output = tokio::time::timeout(
+        VERSION_PROBE_TIMEOUT,
+        tokio::process::Command::new(path)
+            .args(version_args(name))
+            .stdin(std::process::Stdio::null())
+            .kill_on_drop(true)
+            .output(),
+    )
+    .await
+    .ok()?
+    .ok()?;
+    [output.stdout, output.stderr]
+        .iter()
+        .flat_map(|bytes| {
+            String::from_utf8_lossy(bytes)
+                .lines()
+                .map(|line| line.trim().to_string())
+                .collect::<Vec<_>>()
+        })
+        .find(|line| !line.is_empty())
+}
+
+/// Fill in version and integrity for an available tool
+async fn inspect_tool(name: &str, status: &mut ToolStatus, expected: Option<&String>) {
+    let Some(path) = status.path.as_ref().map(PathBuf::from) else {
+        return;
+    };
+    let is_pack = status.source.as_deref() == Some("pack");
+    let fingerprint: ToolFingerprint = match tokio::fs::metadata(&path).await {
+        Ok(meta) => (meta.modified().ok(), meta.len()),
+        Err(_) => {
+            status.available = false;
+            return;
+        }
+    };
+
+    let cached = TOOL_INSPECTION_CACHE
+        .lock()
+        .ok()
+        .and_then(|cache| cache.get(&path).cloned())
+        .filter(|(cached_fingerprint, _, checksum)| {
+            *cached_fingerprint == fingerprint && (!is_pack || checksum.is_some())
+        });
+    let (version, checksum) = match cached {
+        Some((_, version, checksum)) => (version, checksum),
+        None => {
+            let checksum = if is_pack {
+                sha256_file(&path).await.ok()
+            } else {
+                None
+            };
+            let version = probe_version(name, &path).await;
+            if let Ok(mut cache) = TOOL_INSPECTION_CACHE.lock() {
+                cache.insert(
+                    path.clone(),
+                    (fingerprint, version.clone(), checksum.clone()),
+                );
+            }
+            (version, checksum)
+        }
+    };
+
+    status.integrity = match (expected, checksum.as_ref()) {
+        (Some(expected), Some(actual)) if expected.eq_ignore_ascii_case(actual) => {
+            ToolIntegrity::Verified
+        }
+        (Some(_), _) => ToolIntegrity::Mismatch,
+        (None, _) => ToolIntegrity::Unverified,
+    };
+    status.version = version;
+    status.checksum = checksum;
 }
 
 pub fn ensure_doc_tools_env(app: &AppHandle) {
@@ -366,8 +549,15 @@ pub async fn doc_tools_get_status(app: AppHandle) -> Result<DocToolsStatus, AppE
     let mut tools = HashMap::new();
     let mut missing = Vec::new();
 
+    let integrity = root_dir.as_deref().and_then(read_integrity_manifest);
+
     for (name, aliases) in tool_candidates() {
-        let status = tool_status_for(name, &aliases, platform, bin_dir.as_deref());
+        let mut status = tool_status_for(name, &aliases, platform, bin_dir.as_deref());
+        let expected = integrity
+            .as_ref()
+            .filter(|_| status.source.as_deref() == Some("pack"))
+            .and_then(|manifest| manifest.tools.get(name));
+        inspect_tool(name, &mut status, expected).await;
         if !status.available {
             missing.push(name.to_string());
         }
@@ -394,8 +584,108 @@ pub async fn doc_tools_get_status(app: AppHandle) -> Result<DocToolsStatus, AppE
     })
 }
 
+/// Resolve a doc tool for use, failing early with an actionable message when
+/// it is missing or no longer matches its install-time checksum
+pub async fn require_tool(app: AppHandle, name: &str) -> Result<PathBuf, AppError> {
+    let status = doc_tools_get_status(app).await?;
+    let tool = status
+        .tools
+        .get(name)
+        .filter(|tool| tool.available)
+        .ok_or_else(|| {
+            AppError::FileNotFound(format!(
+                "{name} is not installed. Install the Doc Tools Pack in Settings or add {name} to PATH."
+            ))
+        })?;
+    if tool.integrity == ToolIntegrity::Mismatch {
+        return Err(AppError::InvalidPath(format!(
+            "{name} appears to be corrupt (checksum mismatch). Reinstall the Doc Tools Pack in Settings."
+        )));
+    }
+    tool.path
+        .as_ref()
+        .map(PathBuf::from)
+        .ok_or_else(|| AppError::FileNotFound(format!("{name} path is unknown")))
+}
+
+fn emit_install_progress(
+    app: &AppHandle,
+    phase: InstallPhase,
+    version: &str,
+    downloaded_bytes: u64,
+    total_bytes: Option<u64>,
+) {
+    let _ = app.emit(
+        DOC_TOOLS_INSTALL_PROGRESS_EVENT,
+        DocToolsInstallProgress {
+            phase,
+            version: version.to_string(),
+            downloaded_bytes,
+            total_bytes,
+        },
+    );
+}
+
+/// Registers the running install; clears the slot when dropped
+struct InstallGuard {
+    cancel: Arc<AtomicBool>,
+}
+
+impl InstallGuard {
+    fn acquire() -> Result<Self, AppError> {
+        let mut slot = INSTALL_CANCEL
+            .lock()
+            .map_err(|_| AppError::InvalidPath("Doc tools install lock poisoned".into()))?;
+        if slot.is_some() {
+            return Err(AppError::InvalidPath(
+                "Doc tools install already in progress".into(),
+            ));
+        }
+        let cancel = Arc::new(AtomicBool::new(false));
+        *slot = Some(cancel.clone());
+        Ok(Self { cancel })
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancel.load(Ordering::Relaxed)
+    }
+}
+
+impl Drop for InstallGuard {
+    fn drop(&mut self) {
+        if let Ok(mut slot) = INSTALL_CANCEL.lock() {
+            *slot = None;
+        }
+    }
+}
+
+fn install_cancelled(app: &AppHandle, version: &str) -> AppError {
+    emit_install_progress(app, InstallPhase::Cancelled, version, 0, None);
+    AppError::Network("Doc tools install cancelled".into())
+}
+
+/// Cancel the running install; returns false when none is running
+#[tauri::command]
+pub async fn doc_tools_cancel_install() -> Result<bool, AppError> {
+    let slot = INSTALL_CANCEL
+        .lock()
+        .map_err(|_| AppError::InvalidPath("Doc tools install lock poisoned".into()))?;
+    Ok(match slot.as_ref() {
+        Some(cancel) => {
+            cancel.store(true, Ordering::Relaxed);
+            true
+        }
+        None => false,
+    })
+}
+
+/// Install the latest doc tools pack
+///
+/// Emits "doc-tools:install-progress" events (downloading / verifying /
+/// extracting / done) and can be aborted with `doc_tools_cancel_install`.
 #[tauri::command]
 pub async fn doc_tools_install_latest(app: AppHandle) -> Result<DocToolsStatus, AppError> {
+    let guard = InstallGuard::acquire()?;
     let app_data_dir = app
         .path()
         .app_data_dir()
@@ -412,17 +702,25 @@ pub async fn doc_tools_install_latest(app: AppHandle) -> Result<DocToolsStatus,
     } else {
         None
     };
-    let (version, url, expected_sha, bin_dir_hint) = if let Some(manifest) = &manifest {
-        let asset = select_asset(manifest, platform, arch)?;
-        (
-            manifest.version.clone(),
-            asset.url.clone(),
-            asset.sha256.clone(),
-            asset.bin_dir.clone(),
-        )
-    } else {
-        (version_fallback.clone(), direct_url.unwrap(), None, None)
-    };
+    let (version, url, expected_sha, expected_size, bin_dir_hint) =
+        if let Some(manifest) = &manifest {
+            let asset = select_asset(manifest, platform, arch)?;
+            (
+                manifest.version.clone(),
+                asset.url.clone(),
+                asset.sha256.clone(),
+                asset.size,
+                asset.bin_dir.clone(),
+            )
+        } else {
+            (
+                version_fallback.clone(),
+                direct_url.unwrap(),
+                None,
+                None,
+                None,
+            )
+        };
 
     let base = doc_tools_base_dir(&app_data_dir);
     tokio::fs::create_dir_all(&base).await?;
@@ -439,19 +737,37 @@ pub async fn doc_tools_install_latest(app: AppHandle) -> Result<DocToolsStatus,
             response.status()
         )));
     }
+    let total = response.content_length().or(expected_size);
+    let mut downloaded = 0u64;
+    let mut last_emit = Instant::now();
+    emit_install_progress(&app, InstallPhase::Downloading, &version, 0, total);
+
     let mut file = tokio::fs::File::create(&archive_path).await?;
     let mut stream = response.bytes_stream();
     while let Some(chunk) = stream.next().await {
+        if guard.is_cancelled() {
+            drop(file);
+            let _ = tokio::fs::remove_file(&archive_path).await;
+            return Err(install_cancelled(&app, &version));
+        }
         let chunk =
             chunk.map_err(|e| AppError::Network(format!("Doc tools stream failed: {e}")))?;
         file.write_all(&chunk).await?;
+        downloaded += chunk.len() as u64;
+        if last_emit.elapsed() >= PROGRESS_EMIT_INTERVAL {
+            emit_install_progress(&app, InstallPhase::Downloading, &version, downloaded, total);
+            last_emit = Instant::now();
+        }
     }
     file.flush().await?;
+    drop(file);
+    emit_install_progress(&app, InstallPhase::Downloading, &version, downloaded, total);
 
     let out_dir = version_dir(&base, &version);
     tokio::fs::create_dir_all(&out_dir).await?;
 
     if let Some(expected) = expected_sha.as_deref() {
+        emit_install_progress(&app, InstallPhase::Verifying, &version, downloaded, total);
         let actual = sha256_file(&archive_path).await?;
         if !actual.eq_ignore_ascii_case(expected) {
             return Err(AppError::InvalidPath(format!(
@@ -459,8 +775,12 @@ pub async fn doc_tools_install_latest(app: AppHandle) -> Result<DocToolsStatus,
             )));
         }
     }
+    if guard.is_cancelled() {
+        return Err(install_cancelled(&app, &version));
+    }
 
-    let status = if platform == NodePlatform::Windows {
+    emit_install_progress(&app, InstallPhase::Extracting, &version, downloaded, total);
+    let mut child = if platform == NodePlatform::Windows {
         let cmd = format!(
             "Expand-Archive -LiteralPath '{}' -DestinationPath '{}' -Force",
             archive_path.display(),
@@ -470,16 +790,28 @@ pub async fn doc_tools_install_latest(app: AppHandle) -> Result<DocToolsStatus,
             .arg("-NoProfile")
             .arg("-Command")
             .arg(cmd)
-            .status()
-            .await?
+            .kill_on_drop(true)
+            .spawn()?
     } else {
         tokio::process::Command::new("tar")
             .arg("-xf")
             .arg(&archive_path)
             .arg("-C")
             .arg(&out_dir)
-            .status()
-            .await?
+            .kill_on_drop(true)
+            .spawn()?
+    };
+    let status = loop {
+        tokio::select! {
+            status = child.wait() => break status?,
+            _ = tokio::time::sleep(PROGRESS_EMIT_INTERVAL) => {
+                if guard.is_cancelled() {
+                    let _ = child.kill().await;
+                    let _ = tokio::fs::remove_dir_all(&out_dir).await;
+                    return Err(install_cancelled(&app, &version));
+                }
+            }
+        }
     };
     if !status.success() {
         return Err(AppError::InvalidPath(format!(
@@ -487,14 +819,21 @@ pub async fn doc_tools_install_latest(app: AppHandle) -> Result<DocToolsStatus,
         )));
     }
 
+    let root = find_pack_root(&out_dir).unwrap_or(out_dir.clone());
+    let bin_dir = bin_dir_hint
+        .map(|hint| root.join(hint))
+        .filter(|hinted| hinted.is_dir())
+        .or_else(|| find_pack_bin_dir(&root));
+    if let Some(bin_dir) = &bin_dir {
+        write_integrity_manifest(&root, bin_dir, platform).await?;
+    }
+
     write_current_version(&base, &version)?;
-    if let Some(bin_hint) = bin_dir_hint {
-        let root = find_pack_root(&out_dir).unwrap_or(out_dir.clone());
-        let hinted = root.join(bin_hint);
-        if hinted.is_dir() {
-            env::set_var(DOC_TOOLS_ENV_BIN, hinted);
-            env::set_var(DOC_TOOLS_ENV_DIR, root);
-        }
+    if let Some(bin_dir) = bin_dir {
+        env::set_var(DOC_TOOLS_ENV_BIN, bin_dir);
+        env::set_var(DOC_TOOLS_ENV_DIR, &root);
     }
+    emit_install_progress(&app, InstallPhase::Done, &version, downloaded, total);
+    drop(guard);
     doc_tools_get_status(app).await
 }
@@ -41,6 +41,56 @@ pub enum AppError {
 
     #[error("Update state error: {0}")]
     UpdateState(String),
+
+    #[error("Invalid input: {0}")]
+    InvalidInput(String),
+
+    #[error("Parse error: {0}")]
+    Parse(String),
+
+    #[error("Webview error: {0}")]
+    Webview(String),
+
+    #[error("External tool error: {0}")]
+    ExternalTool(String),
+
+    #[error("Typesetting error: {0}")]
+    Typesetting(String),
+
+    #[error("Workspace is read-only: {0}")]
+    ReadOnly(String),
+
+    /// Catch-all for failures that fit no other variant
+    #[error("Internal error: {0}")]
+    Internal(String),
+}
+
+impl AppError {
+    /// Stable error class sent to the frontend alongside the message
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::Io(_) => "io",
+            AppError::FileNotFound(_) => "file_not_found",
+            AppError::InvalidPath(_) => "invalid_path",
+            AppError::FileExists(_) => "file_exists",
+            AppError::Database(_) => "database",
+            AppError::Trash(_) => "trash",
+            AppError::WebDAV(_) => "webdav",
+            AppError::Network(_) => "network",
+            AppError::Update(_) => "update",
+            AppError::UpdateNetwork(_) => "update_network",
+            AppError::UpdateIntegrity(_) => "update_integrity",
+            AppError::UpdateInstall(_) => "update_install",
+            AppError::UpdateState(_) => "update_state",
+            AppError::InvalidInput(_) => "invalid_input",
+            AppError::Parse(_) => "parse",
+            AppError::Webview(_) => "webview",
+            AppError::ExternalTool(_) => "external_tool",
+            AppError::Typesetting(_) => "typesetting",
+            AppError::ReadOnly(_) => "read_only",
+            AppError::Internal(_) => "internal",
+        }
+    }
 }
 
 impl From<reqwest::Error> for AppError {
@@ -49,11 +99,37 @@ impl From<reqwest::Error> for AppError {
     }
 }
 
+/// Serialized as `{ "code": "...", "message": "..." }`
 impl Serialize for AppError {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: serde::Serializer,
     {
-        serializer.serialize_str(&self.to_string())
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("AppError", 2)?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serializes_code_and_message() {
+        let value =
+            serde_json::to_value(AppError::Webview("Main window not found".into())).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "code": "webview",
+                "message": "Webview error: Main window not found",
+            })
+        );
+        let io = AppError::from(std::io::Error::other("disk full"));
+        assert_eq!(serde_json::to_value(io).unwrap()["code"], "io");
     }
 }
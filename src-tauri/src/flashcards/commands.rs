@@ -0,0 +1,26 @@
+//! Tauri commands for flashcard review
+
+use super::{due_cards, review_card, DueCard, Sm2State};
+use crate::error::AppError;
+use std::path::Path;
+
+/// Grade the flashcard note at `path` (0 again, 1 hard, 2 good, 3 easy) and
+/// write the next review date to its frontmatter
+#[tauri::command]
+pub async fn flashcard_review(path: String, grade: u8) -> Result<Sm2State, AppError> {
+    crate::fs::ensure_writable("review flashcards")?;
+    review_card(Path::new(&path), grade, chrono::Local::now().date_naive()).await
+}
+
+/// Flashcards due today, optionally from one deck, most overdue first
+#[tauri::command]
+pub async fn flashcard_due(
+    workspace: String,
+    deck: Option<String>,
+) -> Result<Vec<DueCard>, AppError> {
+    due_cards(
+        Path::new(&workspace),
+        deck.as_deref(),
+        chrono::Local::now().date_naive(),
+    )
+}
@@ -0,0 +1,323 @@
+//! Spaced-repetition scheduling for flashcard notes
+//!
+//! Flashcards are notes with `db: "flashcards"` whose frontmatter carries the
+//! SM-2 state (`ease`, `interval`, `repetitions`, `due`, `lastReview`). The
+//! scheduler matches the frontend one (`src/services/flashcard/sm2.ts`):
+//! grades run from 0 (again) to 3 (easy) and a grade below 2 resets the card.
+
+pub mod commands;
+
+pub use commands::*;
+
+use crate::error::AppError;
+use crate::forge_runtime::frontmatter::{parse_frontmatter, set_frontmatter_fields};
+use crate::forge_runtime::tools::query_database::{collect_rows, parse_iso_date};
+use chrono::{Duration, NaiveDate};
+use forge::runtime::cancel::CancellationToken;
+use serde::{Serialize, Serializer};
+use serde_json::{json, Map, Value};
+use std::path::Path;
+
+/// Frontmatter `db` of flashcard notes
+pub const FLASHCARD_DB: &str = "flashcards";
+pub const DEFAULT_EASE: f64 = 2.5;
+pub const MIN_EASE: f64 = 1.3;
+/// Highest grade (easy)
+pub const MAX_GRADE: u8 = 3;
+
+/// SM-2 scheduling state of a card
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Sm2State {
+    pub ease: f64,
+    /// Days until the next review
+    pub interval: u32,
+    /// Consecutive successful reviews
+    pub repetitions: u32,
+    #[serde(serialize_with = "serialize_date")]
+    pub due: NaiveDate,
+    #[serde(serialize_with = "serialize_optional_date")]
+    pub last_review: Option<NaiveDate>,
+}
+
+/// Dates are written as `YYYY-MM-DD`, like the frontmatter
+fn format_date(date: NaiveDate) -> String {
+    date.format("%Y-%m-%d").to_string()
+}
+
+fn serialize_date<S: Serializer>(date: &NaiveDate, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&format_date(*date))
+}
+
+fn serialize_optional_date<S: Serializer>(
+    date: &Option<NaiveDate>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    date.map(format_date).serialize(serializer)
+}
+
+impl Sm2State {
+    /// A card that has never been reviewed, due on `today`
+    pub fn new(today: NaiveDate) -> Self {
+        Self {
+            ease: DEFAULT_EASE,
+            interval: 0,
+            repetitions: 0,
+            due: today,
+            last_review: None,
+        }
+    }
+
+    /// Read the state from frontmatter; missing or malformed fields fall back
+    /// to those of a new card
+    pub fn from_frontmatter(fields: &Map<String, Value>, today: NaiveDate) -> Self {
+        let number = |key: &str| {
+            fields.get(key).and_then(|value| match value {
+                Value::Number(number) => number.as_f64(),
+                Value::String(text) => text.trim().parse().ok(),
+                _ => None,
+            })
+        };
+        let date = |key: &str| {
+            fields
+                .get(key)
+                .and_then(Value::as_str)
+                .and_then(parse_iso_date)
+                .map(|datetime| datetime.date())
+        };
+        let new = Self::new(today);
+        Self {
+            ease: number("ease").unwrap_or(new.ease),
+            interval: number("interval").map_or(new.interval, |value| value.max(0.0) as u32),
+            repetitions: number("repetitions")
+                .map_or(new.repetitions, |value| value.max(0.0) as u32),
+            due: date("due").unwrap_or(new.due),
+            last_review: date("lastReview"),
+        }
+    }
+
+    /// Whether the card should be reviewed on `today`
+    pub fn is_due(&self, today: NaiveDate) -> bool {
+        self.due <= today
+    }
+}
+
+/// Schedule the next review after grading a card on `today`
+pub fn review(state: &Sm2State, grade: u8, today: NaiveDate) -> Sm2State {
+    let grade = grade.min(MAX_GRADE);
+    // A grade below 2 means the card was forgotten: start over tomorrow
+    if grade < 2 {
+        return Sm2State {
+            ease: round_ease(state.ease - 0.2),
+            interval: 1,
+            repetitions: 0,
+            due: today + Duration::days(1),
+            last_review: Some(today),
+        };
+    }
+
+    let mut interval = match state.repetitions {
+        0 => 1,
+        1 => 6,
+        _ => (state.interval as f64 * state.ease).round() as u32,
+    };
+    // Easy: +0.1, good: unchanged
+    let miss = (MAX_GRADE - grade) as f64;
+    let ease = round_ease(state.ease + 0.1 - miss * (0.08 + miss * 0.02));
+    if grade == MAX_GRADE {
+        interval = (interval as f64 * 1.3).round() as u32;
+    }
+    Sm2State {
+        ease,
+        interval,
+        repetitions: state.repetitions + 1,
+        due: today + Duration::days(interval as i64),
+        last_review: Some(today),
+    }
+}
+
+/// Ease is kept to two decimals so the frontmatter stays readable
+fn round_ease(ease: f64) -> f64 {
+    (ease.max(MIN_EASE) * 100.0).round() / 100.0
+}
+
+/// Grade the card at `path` and write the new schedule to its frontmatter
+pub async fn review_card(path: &Path, grade: u8, today: NaiveDate) -> Result<Sm2State, AppError> {
+    if grade > MAX_GRADE {
+        return Err(AppError::InvalidInput(format!(
+            "Grade must be between 0 and {}, got {}",
+            MAX_GRADE, grade
+        )));
+    }
+    crate::fs::ensure_allowed_path(path, true)?;
+    let _lock = crate::fs::lock_path(path).await;
+    let content = tokio::fs::read_to_string(path)
+        .await
+        .map_err(|err| match err.kind() {
+            std::io::ErrorKind::NotFound => AppError::FileNotFound(path.display().to_string()),
+            _ => AppError::Io(err),
+        })?;
+    let fields = parse_frontmatter(&content)
+        .filter(|fields| fields.get("db").and_then(Value::as_str) == Some(FLASHCARD_DB))
+        .ok_or_else(|| {
+            AppError::InvalidInput(format!("Not a flashcard note: {}", path.display()))
+        })?;
+
+    let next = review(&Sm2State::from_frontmatter(&fields, today), grade, today);
+    let date = |date: NaiveDate| json!(format_date(date));
+    let mut updates = vec![
+        ("ease", json!(next.ease)),
+        ("interval", json!(next.interval)),
+        ("repetitions", json!(next.repetitions)),
+        ("due", date(next.due)),
+    ];
+    if let Some(last_review) = next.last_review {
+        updates.push(("lastReview", date(last_review)));
+    }
+    let updated = set_frontmatter_fields(&content, &updates)
+        .ok_or_else(|| AppError::Parse("Flashcard frontmatter disappeared".into()))?;
+    crate::fs::write_file_content(&path.to_string_lossy(), &updated)?;
+    Ok(next)
+}
+
+/// A card due for review
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DueCard {
+    /// Workspace-relative path
+    pub path: String,
+    pub deck: String,
+    pub state: Sm2State,
+    /// All frontmatter fields, for rendering the card
+    pub fields: Map<String, Value>,
+}
+
+/// Cards due on `today`, optionally limited to one deck, most overdue first
+pub fn due_cards(
+    workspace: &Path,
+    deck: Option<&str>,
+    today: NaiveDate,
+) -> Result<Vec<DueCard>, AppError> {
+    let rows = collect_rows(workspace, Some(FLASHCARD_DB), &CancellationToken::new())
+        .map_err(|err| AppError::Internal(err.to_string()))?;
+    let mut cards: Vec<DueCard> = rows
+        .into_iter()
+        .filter_map(|row| {
+            let card_deck = row
+                .fields
+                .get("deck")
+                .and_then(Value::as_str)
+                .unwrap_or("Default")
+                .to_string();
+            if deck.is_some_and(|deck| deck != card_deck) {
+                return None;
+            }
+            let state = Sm2State::from_frontmatter(&row.fields, today);
+            state.is_due(today).then_some(DueCard {
+                path: row.path,
+                deck: card_deck,
+                state,
+                fields: row.fields,
+            })
+        })
+        .collect();
+    cards.sort_by(|a, b| a.state.due.cmp(&b.state.due).then(a.path.cmp(&b.path)));
+    Ok(cards)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(text: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(text, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn successful_reviews_grow_the_interval() {
+        let today = day("2024-01-10");
+        let first = review(&Sm2State::new(today), 2, today);
+        assert_eq!((first.interval, first.repetitions, first.ease), (1, 1, 2.5));
+        assert_eq!(first.due, day("2024-01-11"));
+
+        let second = review(&first, 2, first.due);
+        assert_eq!((second.interval, second.repetitions), (6, 2));
+        assert_eq!(second.due, day("2024-01-17"));
+
+        // Third success multiplies by ease; easy adds 0.1 ease and 30%
+        let third = review(&second, 3, second.due);
+        assert_eq!(
+            (third.interval, third.repetitions, third.ease),
+            (20, 3, 2.6)
+        );
+        assert_eq!(third.due, day("2024-02-06"));
+        assert_eq!(third.last_review, Some(day("2024-01-17")));
+    }
+
+    #[test]
+    fn failed_reviews_reset_and_lower_ease_to_the_floor() {
+        let today = day("2024-03-01");
+        let learned = Sm2State {
+            ease: 1.4,
+            interval: 30,
+            repetitions: 5,
+            due: today,
+            last_review: None,
+        };
+        let hard = review(&learned, 1, today);
+        assert_eq!((hard.interval, hard.repetitions, hard.ease), (1, 0, 1.3));
+        assert_eq!(hard.due, day("2024-03-02"));
+
+        let again = review(&hard, 0, hard.due);
+        assert_eq!(again.ease, MIN_EASE);
+        // Out-of-range grades count as easy
+        assert_eq!(review(&learned, 9, today), review(&learned, 3, today));
+    }
+
+    #[tokio::test]
+    async fn reviews_update_frontmatter_and_due_cards_follow() {
+        // Reviews only write inside allowed roots, which include the working directory
+        let workspace = tempfile::tempdir_in(std::env::current_dir().unwrap()).unwrap();
+        let dir = workspace.path().join("Flashcards");
+        std::fs::create_dir_all(&dir).unwrap();
+        let card = dir.join("rust.md");
+        std::fs::write(
+            &card,
+            "---\ndb: \"flashcards\"\ntype: \"basic\"\ndeck: \"Rust\"\nease: 2.5\ninterval: 0\nrepetitions: 0\ndue: \"2024-01-10\"\nfront: \"Borrow?\"\n---\n\nBorrow?\n\n---\n\nA reference\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("later.md"),
+            "---\ndb: flashcards\ndeck: Rust\ndue: 2024-02-01\n---\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join("other.md"), "---\ndb: flashcards\n---\n").unwrap();
+
+        let today = day("2024-01-10");
+        let due = due_cards(workspace.path(), Some("Rust"), today).unwrap();
+        assert_eq!(
+            due.iter()
+                .map(|card| card.path.as_str())
+                .collect::<Vec<_>>(),
+            ["Flashcards/rust.md"]
+        );
+        assert_eq!(due_cards(workspace.path(), None, today).unwrap().len(), 2);
+
+        let next = review_card(&card, 2, today).await.unwrap();
+        assert_eq!(next.due, day("2024-01-11"));
+        let content = std::fs::read_to_string(&card).unwrap();
+        assert!(content.ends_with("\nBorrow?\n\n---\n\nA reference\n"));
+        let fields = parse_frontmatter(&content).unwrap();
+        assert_eq!(fields["repetitions"], json!(1));
+        assert_eq!(fields["due"], json!("2024-01-11"));
+        assert_eq!(fields["lastReview"], json!("2024-01-10"));
+        assert_eq!(fields["front"], json!("Borrow?"));
+        assert!(due_cards(workspace.path(), Some("Rust"), today)
+            .unwrap()
+            .is_empty());
+
+        assert!(matches!(
+            review_card(&card, 4, today).await,
+            Err(AppError::InvalidInput(_))
+        ));
+    }
+}
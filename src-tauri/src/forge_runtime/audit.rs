@@ -0,0 +1,370 @@
+//! Audit log of agent file operations.
+//!
+//! Every tool call that reads or writes files (`read`, `read_many`, `write`,
+//! `edit`, `create_from_template` and `bash`) is appended to the workspace's
+//! `.lumina/agent-audit.jsonl` with its outcome, the number of bytes written
+//! and content hashes before and after the change, so a run can be reviewed
+//! afterwards. `read_many` logs one entry per file; `bash` logs its command
+//! and working directory, since the files it touches are not known up front.
+
+use crate::forge_runtime::tools::shared::resolve_path;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Workspace-relative location of the audit log.
+const AUDIT_LOG_PATH: &str = ".lumina/agent-audit.jsonl";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditOutcome {
+    Success,
+    Error,
+}
+
+/// One audited tool call.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// Unix milliseconds.
+    pub timestamp: i64,
+    pub session_id: String,
+    pub tool: String,
+    /// `read`, `edit`, `command` (`bash`), or the write mode
+    /// (`create`, `overwrite`, `append`).
+    pub operation: String,
+    pub path: String,
+    pub outcome: AuditOutcome,
+    #[serde(default)]
+    pub error: Option<String>,
+    /// Bytes written (`write`) or the resulting file size (other file tools).
+    #[serde(default)]
+    pub bytes: Option<u64>,
+    /// SHA-256 of the file before a write or edit (`None` if it did not exist).
+    #[serde(default)]
+    pub before_hash: Option<String>,
+    /// SHA-256 of the file after a successful write or edit.
+    #[serde(default)]
+    pub after_hash: Option<String>,
+    /// Shell command run by `bash`; `path` is its working directory.
+    #[serde(default)]
+    pub command: Option<String>,
+}
+
+/// A file tool call whose outcome is still pending.
+pub struct PendingAudit {
+    tool: String,
+    operation: String,
+    /// Each target with its hash before the call (hashed only when `modifies`).
+    targets: Vec<(PathBuf, Option<String>)>,
+    modifies: bool,
+    bytes: Option<u64>,
+    command: Option<String>,
+}
+
+/// Appends audit entries for one agent session.
+pub struct AuditLog {
+    workspace_root: PathBuf,
+    session_id: String,
+    lock: Mutex<()>,
+}
+
+pub fn audit_log_path(workspace_root: &Path) -> PathBuf {
+    workspace_root.join(AUDIT_LOG_PATH)
+}
+
+impl AuditLog {
+    pub fn new(workspace_root: impl Into<PathBuf>, session_id: impl Into<String>) -> Self {
+        Self {
+            workspace_root: workspace_root.into(),
+            session_id: session_id.into(),
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// Captures the targets of a file tool call before it runs.
+    /// Returns `None` for tools that are not audited.
+    pub async fn begin(&self, tool: &str, input: &Value) -> Option<PendingAudit> {
+        let path_of = |key: &str| {
+            input
+                .get(key)
+                .and_then(Value::as_str)
+                .map(|path| resolve_path(&self.workspace_root, path))
+        };
+        let (operation, paths, modifies) = match tool {
+            "read" => ("read", vec![path_of("filePath")?], false),
+            "edit" => ("edit", vec![path_of("filePath")?], true),
+            "write" => (
+                input
+                    .get("mode")
+                    .and_then(Value::as_str)
+                    .unwrap_or("overwrite"),
+                vec![path_of("filePath")?],
+                true,
+            ),
+            "read_many" => (
+                "read",
+                input
+                    .get("paths")?
+                    .as_array()?
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .map(|path| resolve_path(&self.workspace_root, path))
+                    .collect(),
+                false,
+            ),
+            "create_from_template" => {
+                // Mirrors the tool, which adds `.md` to extension-less targets
+                let mut target = path_of("targetPath")?;
+                if target.extension().is_none() {
+                    target.set_extension("md");
+                }
+                ("create", vec![target], true)
+            }
+            "bash" => (
+                "command",
+                vec![path_of("workdir").unwrap_or_else(|| self.workspace_root.clone())],
+                false,
+            ),
+            _ => return None,
+        };
+
+        let mut targets = Vec::with_capacity(paths.len());
+        for target in paths {
+            let before_hash = if modifies {
+                hash_file(&target).await
+            } else {
+                None
+            };
+            targets.push((target, before_hash));
+        }
+        let bytes = if tool == "write" {
+            input
+                .get("content")
+                .and_then(Value::as_str)
+                .map(|content| content.len() as u64)
+        } else {
+            None
+        };
+        let command = if tool == "bash" {
+            input
+                .get("command")
+                .and_then(Value::as_str)
+                .map(str::to_string)
+        } else {
+            None
+        };
+        Some(PendingAudit {
+            tool: tool.to_string(),
+            operation: operation.to_string(),
+            targets,
+            modifies,
+            bytes,
+            command,
+        })
+    }
+
+    /// Records the outcome of a call started with [`AuditLog::begin`],
+    /// one entry per target.
+    pub async fn finish(&self, pending: PendingAudit, result: Result<(), String>) {
+        let succeeded = result.is_ok();
+        let error = result.err();
+        for (target, before_hash) in pending.targets {
+            let after_hash = if succeeded && pending.modifies {
+                hash_file(&target).await
+            } else {
+                None
+            };
+            let bytes = match pending.bytes {
+                Some(bytes) => Some(bytes),
+                None if succeeded => tokio::fs::metadata(&target)
+                    .await
+                    .ok()
+                    .filter(|meta| meta.is_file())
+                    .map(|meta| meta.len()),
+                None => None,
+            };
+            let entry = AuditEntry {
+                timestamp: chrono::Utc::now().timestamp_millis(),
+                session_id: self.session_id.clone(),
+                tool: pending.tool.clone(),
+                operation: pending.operation.clone(),
+                path: target.display().to_string(),
+                outcome: if succeeded {
+                    AuditOutcome::Success
+                } else {
+                    AuditOutcome::Error
+                },
+                error: error.clone(),
+                bytes,
+                before_hash,
+                after_hash,
+                command: pending.command.clone(),
+            };
+            if let Err(err) = self.append(&entry) {
+                eprintln!("[Audit] Failed to write audit log: {}", err);
+            }
+        }
+    }
+
+    fn append(&self, entry: &AuditEntry) -> io::Result<()> {
+        let _guard = self.lock.lock().unwrap_or_else(|err| err.into_inner());
+        let path = audit_log_path(&self.workspace_root);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let line = serde_json::to_string(entry)?;
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?;
+        writeln!(file, "{}", line)
+    }
+}
+
+/// Loads audit entries for a workspace, optionally filtered to one session.
+/// A missing log yields no entries; malformed lines are skipped.
+pub fn load_audit_entries(
+    workspace_root: &Path,
+    session_id: Option<&str>,
+) -> Result<Vec<AuditEntry>, String> {
+    let path = audit_log_path(workspace_root);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content =
+        fs::read_to_string(&path).map_err(|e| format!("Failed to read audit log: {}", e))?;
+    Ok(content
+        .lines()
+        .filter_map(|line| serde_json::from_str::<AuditEntry>(line).ok())
+        .filter(|entry| session_id.is_none_or(|id| entry.session_id == id))
+        .collect())
+}
+
+async fn hash_file(path: &Path) -> Option<String> {
+    let bytes = tokio::fs::read(path).await.ok()?;
+    Some(hex::encode(Sha256::digest(&bytes)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn records_edits_per_session() {
+        let workspace = tempfile::tempdir().unwrap();
+        let note = workspace.path().join("a.md");
+        fs::write(&note, "old").unwrap();
+
+        let log = AuditLog::new(workspace.path(), "s1");
+        let pending = log
+            .begin("edit", &json!({ "filePath": "a.md", "oldString": "old" }))
+            .await
+            .unwrap();
+        fs::write(&note, "new!").unwrap();
+        log.finish(pending, Ok(())).await;
+
+        let pending = log
+            .begin(
+                "write",
+                &json!({ "filePath": "b.md", "content": "abc", "mode": "create" }),
+            )
+            .await
+            .unwrap();
+        log.finish(pending, Err("denied".to_string())).await;
+        assert!(log
+            .begin("glob", &json!({ "pattern": "*" }))
+            .await
+            .is_none());
+
+        let other = AuditLog::new(workspace.path(), "s2");
+        let pending = other
+            .begin("read", &json!({ "filePath": "a.md" }))
+            .await
+            .unwrap();
+        other.finish(pending, Ok(())).await;
+
+        let entries = load_audit_entries(workspace.path(), Some("s1")).unwrap();
+        assert_eq!(entries.len(), 2);
+        let edit = &entries[0];
+        assert_eq!(
+            (edit.operation.as_str(), edit.outcome, edit.bytes),
+            ("edit", AuditOutcome::Success, Some(4))
+        );
+        assert_eq!(
+            edit.before_hash.as_deref(),
+            Some(hex::encode(Sha256::digest(b"old")).as_str())
+        );
+        assert_eq!(
+            edit.after_hash.as_deref(),
+            Some(hex::encode(Sha256::digest(b"new!")).as_str())
+        );
+        let write = &entries[1];
+        assert_eq!(
+            (write.operation.as_str(), write.outcome, write.bytes),
+            ("create", AuditOutcome::Error, Some(3))
+        );
+        assert_eq!(write.error.as_deref(), Some("denied"));
+        assert_eq!(
+            (write.before_hash.clone(), write.after_hash.clone()),
+            (None, None)
+        );
+
+        assert_eq!(load_audit_entries(workspace.path(), None).unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn records_batch_reads_templates_and_commands() {
+        let workspace = tempfile::tempdir().unwrap();
+        fs::write(workspace.path().join("a.md"), "aa").unwrap();
+        fs::write(workspace.path().join("b.md"), "bbb").unwrap();
+
+        let log = AuditLog::new(workspace.path(), "s1");
+        let pending = log
+            .begin("read_many", &json!({ "paths": ["a.md", "b.md"] }))
+            .await
+            .unwrap();
+        log.finish(pending, Ok(())).await;
+
+        let pending = log
+            .begin(
+                "create_from_template",
+                &json!({ "template": "daily", "targetPath": "journal/today" }),
+            )
+            .await
+            .unwrap();
+        fs::create_dir_all(workspace.path().join("journal")).unwrap();
+        fs::write(workspace.path().join("journal/today.md"), "# Today").unwrap();
+        log.finish(pending, Ok(())).await;
+
+        let pending = log
+            .begin("bash", &json!({ "command": "ls", "description": "list" }))
+            .await
+            .unwrap();
+        log.finish(pending, Ok(())).await;
+
+        let entries = load_audit_entries(workspace.path(), Some("s1")).unwrap();
+        let summary: Vec<_> = entries
+            .iter()
+            .map(|entry| (entry.tool.as_str(), entry.operation.as_str(), entry.bytes))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("read_many", "read", Some(2)),
+                ("read_many", "read", Some(3)),
+                ("create_from_template", "create", Some(7)),
+                ("bash", "command", None),
+            ]
+        );
+        assert!(entries[2].path.ends_with("today.md"));
+        assert_eq!(entries[2].before_hash, None);
+        assert!(entries[2].after_hash.is_some());
+        assert_eq!(entries[3].command.as_deref(), Some("ls"));
+        assert_eq!(entries[3].path, workspace.path().display().to_string());
+    }
+}
@@ -0,0 +1,115 @@
+//! Dry-run mode for agent tools.
+//!
+//! When a run is started with `dry_run`, the write-type tools (`write`,
+//! `edit`, `bash`) describe the change they would make instead of touching
+//! disk, while read tools run normally. Every simulated change is recorded in
+//! a [`DryRunLog`] so the agent loop can forward it to the UI as a preview.
+
+use forge::runtime::tool::ToolOutput;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::path::Path;
+use std::sync::Mutex;
+
+/// A change a write-type tool would have made.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SimulatedChange {
+    pub tool: String,
+    /// Absolute path of the affected file (`None` for `bash`).
+    pub path: Option<String>,
+    /// File contents before the change (`None` if the file would be created).
+    pub original: Option<String>,
+    /// File contents after the change (`None` for `bash`).
+    pub modified: Option<String>,
+    pub description: String,
+}
+
+impl SimulatedChange {
+    /// A change to a single file.
+    pub fn file(
+        tool: &str,
+        path: &Path,
+        original: Option<String>,
+        modified: String,
+        description: String,
+    ) -> Self {
+        Self {
+            tool: tool.to_string(),
+            path: Some(path.display().to_string()),
+            original,
+            modified: Some(modified),
+            description,
+        }
+    }
+
+    /// A command that would have been run.
+    pub fn command(tool: &str, description: String) -> Self {
+        Self {
+            tool: tool.to_string(),
+            path: None,
+            original: None,
+            modified: None,
+            description,
+        }
+    }
+}
+
+/// Simulated changes of one run, drained by the agent loop after each tool call.
+#[derive(Debug, Default)]
+pub struct DryRunLog {
+    changes: Mutex<Vec<SimulatedChange>>,
+}
+
+impl DryRunLog {
+    pub fn record(&self, change: SimulatedChange) {
+        self.changes
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .push(change);
+    }
+
+    pub fn take(&self) -> Vec<SimulatedChange> {
+        std::mem::take(&mut *self.changes.lock().unwrap_or_else(|err| err.into_inner()))
+    }
+}
+
+/// Records `change` and returns the simulated success reported to the model.
+pub fn simulate(log: &DryRunLog, change: SimulatedChange) -> ToolOutput {
+    let output = ToolOutput::text(format!(
+        "[dry run] {} No changes were made.",
+        change.description
+    ))
+    .with_mime_type("text/plain")
+    .with_schema("tool.dry_run.v1")
+    .with_attribute("dry_run", json!(true))
+    .with_attribute("filepath", json!(change.path));
+    log.record(change);
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn log_hands_out_each_change_once() {
+        let log = DryRunLog::default();
+        log.record(SimulatedChange::file(
+            "write",
+            Path::new("/vault/a.md"),
+            None,
+            "hello".to_string(),
+            "Would create /vault/a.md (5 bytes).".to_string(),
+        ));
+        log.record(SimulatedChange::command(
+            "bash",
+            "Would run `ls`.".to_string(),
+        ));
+
+        let changes = log.take();
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0].path.as_deref(), Some("/vault/a.md"));
+        assert_eq!(changes[1].modified, None);
+        assert!(log.take().is_empty());
+    }
+}
@@ -0,0 +1,195 @@
+//! YAML frontmatter parsing for markdown notes.
+//!
+//! Values keep their YAML types (numbers, booleans, arrays, nested maps) and
+//! are returned as a `serde_json::Map` so callers can work with plain JSON.
+
+use serde_json::{Map, Value};
+
+/// Split a note into its raw frontmatter block and the remaining body.
+///
+/// The frontmatter must start on the first line with `---` and end with a
+/// line containing only `---` (or `...`). Returns `None` when there is no
+/// frontmatter block.
+pub fn split_frontmatter(content: &str) -> Option<(&str, &str)> {
+    let content = content.strip_prefix('\u{feff}').unwrap_or(content);
+    let rest = content
+        .strip_prefix("---\r\n")
+        .or_else(|| content.strip_prefix("---\n"))?;
+
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        let trimmed = line.trim_end_matches(['\r', '\n']);
+        if trimmed == "---" || trimmed == "..." {
+            let yaml = &rest[..offset];
+            let body = &rest[offset + line.len()..];
+            return Some((yaml, body));
+        }
+        offset += line.len();
+    }
+    None
+}
+
+/// Parse the frontmatter of a note into a JSON object.
+///
+/// Returns `None` when the note has no frontmatter, the YAML is invalid, or
+/// the top-level value is not a mapping. An empty block yields an empty map.
+pub fn parse_frontmatter(content: &str) -> Option<Map<String, Value>> {
+    let (yaml, _) = split_frontmatter(content)?;
+    if yaml.trim().is_empty() {
+        return Some(Map::new());
+    }
+    match serde_yaml::from_str::<Value>(yaml).ok()? {
+        Value::Object(map) => Some(map),
+        Value::Null => Some(Map::new()),
+        _ => None,
+    }
+}
+
+/// Read a frontmatter value as a list of strings.
+///
+/// Accepts YAML sequences as well as comma-separated scalars such as
+/// `tags: a, b`, which older notes commonly use.
+pub fn frontmatter_string_list(value: &Value) -> Vec<String> {
+    match value {
+        Value::Array(items) => items
+            .iter()
+            .filter_map(|item| match item {
+                Value::String(text) => Some(text.trim().to_string()),
+                Value::Number(number) => Some(number.to_string()),
+                Value::Bool(flag) => Some(flag.to_string()),
+                _ => None,
+            })
+            .filter(|item| !item.is_empty())
+            .collect(),
+        Value::String(text) => text
+            .split(',')
+            .map(|item| item.trim().to_string())
+            .filter(|item| !item.is_empty())
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Set top-level frontmatter fields, keeping every other line and the body.
+///
+/// A field's existing entry (including an indented block or list below it) is
+/// replaced in place; new fields are appended at the end of the block. Values
+/// are written as JSON, which is valid YAML. Returns `None` when the note has
+/// no frontmatter block.
+pub fn set_frontmatter_fields(content: &str, fields: &[(&str, Value)]) -> Option<String> {
+    let (yaml, _) = split_frontmatter(content)?;
+    let start = yaml.as_ptr() as usize - content.as_ptr() as usize;
+    let end = start + yaml.len();
+    let newline = if yaml.contains("\r\n") || content[..start].contains("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    };
+    let entry = |key: &str, value: &Value| format!("{}: {}", key, value);
+
+    let mut lines = Vec::new();
+    let mut written = vec![false; fields.len()];
+    // Inside the block of a replaced field: drop its continuation lines
+    let mut replacing = false;
+    for line in yaml.lines() {
+        if replacing && (line.starts_with([' ', '\t']) || line.starts_with('-')) {
+            continue;
+        }
+        replacing = false;
+        let key = line.split_once(':').map(|(key, _)| key);
+        match fields.iter().position(|(name, _)| Some(*name) == key) {
+            Some(index) => {
+                if !written[index] {
+                    lines.push(entry(fields[index].0, &fields[index].1));
+                    written[index] = true;
+                }
+                replacing = true;
+            }
+            None => lines.push(line.to_string()),
+        }
+    }
+    for (index, (key, value)) in fields.iter().enumerate() {
+        if !written[index] {
+            lines.push(entry(key, value));
+        }
+    }
+
+    let mut block = lines.join(newline);
+    if !block.is_empty() {
+        block.push_str(newline);
+    }
+    Some(format!("{}{}{}", &content[..start], block, &content[end..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn parses_typed_values_and_arrays() {
+        let note = "---\ndb: \"cards\"\ntags: [rust, \"async io\"]\noptions:\n  - A\n  - B\ninterval: 3\nease: 2.5\nsuspended: false\ndue: 2024-01-15\n---\n# Card\n";
+        let fm = parse_frontmatter(note).expect("frontmatter");
+
+        assert_eq!(fm["db"], json!("cards"));
+        assert_eq!(fm["tags"], json!(["rust", "async io"]));
+        assert_eq!(fm["options"], json!(["A", "B"]));
+        assert_eq!(fm["interval"], json!(3));
+        assert_eq!(fm["ease"], json!(2.5));
+        assert_eq!(fm["suspended"], json!(false));
+        assert_eq!(fm["due"], json!("2024-01-15"));
+    }
+
+    #[test]
+    fn parses_nested_maps_and_multiline_values() {
+        let note = "---\r\nreview:\r\n  count: 2\r\n  last: \"2024-01-10\"\r\nback: |\r\n  line one\r\n  line two\r\n---\r\nbody";
+        let fm = parse_frontmatter(note).expect("frontmatter");
+
+        assert_eq!(fm["review"], json!({ "count": 2, "last": "2024-01-10" }));
+        assert_eq!(fm["back"], json!("line one\nline two\n"));
+        assert_eq!(split_frontmatter(note).map(|(_, body)| body), Some("body"));
+    }
+
+    #[test]
+    fn rejects_missing_or_invalid_frontmatter() {
+        assert!(parse_frontmatter("# Title\n---\nfoo: bar\n---\n").is_none());
+        assert!(parse_frontmatter("---\nfoo: bar\n").is_none());
+        assert!(parse_frontmatter("---\n- a\n- b\n---\n").is_none());
+        assert_eq!(parse_frontmatter("---\n---\nbody"), Some(Map::new()));
+    }
+
+    #[test]
+    fn sets_fields_in_place_and_keeps_the_rest() {
+        let note = "---\ndb: \"flashcards\"\nease: 2.5\ntags:\n- a\n- b\nfront: Q\n---\nbody: not frontmatter\n";
+        let updated = set_frontmatter_fields(
+            note,
+            &[
+                ("ease", json!(2.36)),
+                ("tags", json!(["c"])),
+                ("due", json!("2024-01-16")),
+            ],
+        )
+        .unwrap();
+        assert_eq!(
+            updated,
+            "---\ndb: \"flashcards\"\nease: 2.36\ntags: [\"c\"]\nfront: Q\ndue: \"2024-01-16\"\n---\nbody: not frontmatter\n"
+        );
+        let fm = parse_frontmatter(&updated).unwrap();
+        assert_eq!(fm["tags"], json!(["c"]));
+
+        let crlf =
+            set_frontmatter_fields("---\r\nease: 2.5\r\n---\r\nbody", &[("ease", json!(1.3))]);
+        assert_eq!(crlf.as_deref(), Some("---\r\nease: 1.3\r\n---\r\nbody"));
+        assert!(set_frontmatter_fields("no frontmatter", &[("a", json!(1))]).is_none());
+    }
+
+    #[test]
+    fn reads_string_lists_from_arrays_and_scalars() {
+        assert_eq!(
+            frontmatter_string_list(&json!(["a", " b ", 3])),
+            vec!["a", "b", "3"]
+        );
+        assert_eq!(frontmatter_string_list(&json!("a, b,")), vec!["a", "b"]);
+        assert!(frontmatter_string_list(&json!({ "a": 1 })).is_empty());
+    }
+}
@@ -0,0 +1,444 @@
+//! Undo journal of agent file mutations.
+//!
+//! Every file-changing tool call of a run (`write`, `edit`,
+//! `create_from_template`) is stored in `.lumina/agent-journal/<session>.json`
+//! with the full contents of each touched file before and after the call, so
+//! the run's changes can be reverted in reverse order and re-applied.
+//!
+//! Undo only proceeds when every file is still in the state the agent left it
+//! in; a file edited since (by the user or another program) is reported as a
+//! conflict and nothing is changed.
+
+use crate::forge_runtime::tools::shared::resolve_path;
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Workspace-relative directory holding one journal per session.
+const JOURNAL_DIR: &str = ".lumina/agent-journal";
+
+/// Serializes journal updates. Not a file lock, so undo can still take the
+/// locks of the files it restores (see [`crate::fs::lock_paths`]).
+static JOURNAL_LOCK: Lazy<tokio::sync::Mutex<()>> = Lazy::new(|| tokio::sync::Mutex::new(()));
+
+/// State of one file before and after a journaled call.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileChange {
+    pub path: String,
+    /// Base64 contents before the call (`None` if the file did not exist).
+    pub before: Option<String>,
+    /// Base64 contents after the call (`None` if the call deleted the file).
+    pub after: Option<String>,
+}
+
+/// One journaled tool call.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JournalEntry {
+    /// Position of the call within the session, starting at 1.
+    pub seq: u64,
+    /// Unix milliseconds.
+    pub timestamp: i64,
+    pub call_id: String,
+    pub tool: String,
+    pub files: Vec<FileChange>,
+    /// Reverted by an undo and not yet redone.
+    #[serde(default)]
+    pub undone: bool,
+}
+
+/// Result of an undo or redo.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct UndoReport {
+    /// Sequence numbers of the entries reverted (or re-applied).
+    pub entries: Vec<u64>,
+    /// Files written or removed.
+    pub files: Vec<String>,
+    /// Files changed outside the agent since the journaled call; when non-empty
+    /// nothing was changed.
+    pub conflicts: Vec<String>,
+}
+
+/// Contents of the files a tool call is about to change.
+pub struct PendingChange {
+    call_id: String,
+    tool: String,
+    before: Vec<(PathBuf, Option<Vec<u8>>)>,
+}
+
+/// Records file mutations for one agent session.
+pub struct MutationJournal {
+    workspace_root: PathBuf,
+    session_id: String,
+}
+
+pub fn journal_path(workspace_root: &Path, session_id: &str) -> PathBuf {
+    workspace_root
+        .join(JOURNAL_DIR)
+        .join(format!("{}.json", session_id))
+}
+
+impl MutationJournal {
+    pub fn new(workspace_root: impl Into<PathBuf>, session_id: impl Into<String>) -> Self {
+        Self {
+            workspace_root: workspace_root.into(),
+            session_id: session_id.into(),
+        }
+    }
+
+    /// Captures the files a tool call may change before it runs.
+    /// Returns `None` for tools that do not modify files.
+    pub async fn begin(&self, call_id: &str, tool: &str, input: &Value) -> Option<PendingChange> {
+        let targets = mutation_targets(&self.workspace_root, tool, input);
+        if targets.is_empty() {
+            return None;
+        }
+        let mut before = Vec::with_capacity(targets.len());
+        for path in targets {
+            let contents = tokio::fs::read(&path).await.ok();
+            before.push((path, contents));
+        }
+        Some(PendingChange {
+            call_id: call_id.to_string(),
+            tool: tool.to_string(),
+            before,
+        })
+    }
+
+    /// Stores the call started with [`MutationJournal::begin`] if it changed
+    /// any file. A new change discards the entries that could be redone.
+    pub async fn finish(&self, pending: PendingChange) {
+        let mut files = Vec::new();
+        for (path, before) in pending.before {
+            let after = tokio::fs::read(&path).await.ok();
+            if after != before {
+                files.push(FileChange {
+                    path: path.display().to_string(),
+                    before: before.map(|bytes| STANDARD.encode(bytes)),
+                    after: after.map(|bytes| STANDARD.encode(bytes)),
+                });
+            }
+        }
+        if files.is_empty() {
+            return;
+        }
+
+        let path = journal_path(&self.workspace_root, &self.session_id);
+        let _journal = JOURNAL_LOCK.lock().await;
+        let result =
+            load_journal(&self.workspace_root, &self.session_id).and_then(|mut entries| {
+                entries.retain(|entry| !entry.undone);
+                let seq = entries.last().map_or(1, |entry| entry.seq + 1);
+                entries.push(JournalEntry {
+                    seq,
+                    timestamp: chrono::Utc::now().timestamp_millis(),
+                    call_id: pending.call_id,
+                    tool: pending.tool,
+                    files,
+                    undone: false,
+                });
+                save_journal(&path, &entries)
+            });
+        if let Err(err) = result {
+            eprintln!("[Journal] Failed to record change: {}", err);
+        }
+    }
+}
+
+/// Files a tool call writes, resolved the same way the tool resolves them.
+fn mutation_targets(workspace_root: &Path, tool: &str, input: &Value) -> Vec<PathBuf> {
+    let path_of = |key: &str| {
+        input
+            .get(key)
+            .and_then(Value::as_str)
+            .map(|path| resolve_path(workspace_root, path))
+    };
+    let target = match tool {
+        "write" | "edit" => path_of("filePath"),
+        "create_from_template" => path_of("targetPath").map(|mut path| {
+            if path.extension().is_none() {
+                path.set_extension("md");
+            }
+            path
+        }),
+        _ => None,
+    };
+    target.into_iter().collect()
+}
+
+/// Loads a session's journal; a missing journal has no entries.
+pub fn load_journal(workspace_root: &Path, session_id: &str) -> Result<Vec<JournalEntry>, String> {
+    let path = journal_path(workspace_root, session_id);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content =
+        fs::read_to_string(&path).map_err(|e| format!("Failed to read journal: {}", e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Invalid journal: {}", e))
+}
+
+fn save_journal(path: &Path, entries: &[JournalEntry]) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create journal dir: {}", e))?;
+    }
+    let content = serde_json::to_string(entries).map_err(|e| e.to_string())?;
+    fs::write(path, content).map_err(|e| format!("Failed to write journal: {}", e))
+}
+
+/// Reverts the most recent change of the session that has not been undone.
+pub async fn undo_last(workspace_root: &Path, session_id: &str) -> Result<UndoReport, String> {
+    revert(workspace_root, session_id, Some(1)).await
+}
+
+/// Reverts every change of the session, newest first.
+pub async fn undo_run(workspace_root: &Path, session_id: &str) -> Result<UndoReport, String> {
+    revert(workspace_root, session_id, None).await
+}
+
+async fn revert(
+    workspace_root: &Path,
+    session_id: &str,
+    limit: Option<usize>,
+) -> Result<UndoReport, String> {
+    let path = journal_path(workspace_root, session_id);
+    let _journal = JOURNAL_LOCK.lock().await;
+    let mut entries = load_journal(workspace_root, session_id)?;
+    let selected: Vec<usize> = entries
+        .iter()
+        .enumerate()
+        .rev()
+        .filter(|(_, entry)| !entry.undone)
+        .map(|(index, _)| index)
+        .take(limit.unwrap_or(usize::MAX))
+        .collect();
+    if selected.is_empty() {
+        return Ok(UndoReport::default());
+    }
+
+    // Newest first: each file must match its latest journaled state and is
+    // restored to its oldest one
+    let mut expected = BTreeMap::new();
+    let mut restore = BTreeMap::new();
+    for &index in &selected {
+        for file in &entries[index].files {
+            expected
+                .entry(file.path.clone())
+                .or_insert_with(|| file.after.clone());
+            restore.insert(file.path.clone(), file.before.clone());
+        }
+    }
+    let report = apply(expected, restore).await?;
+    if report.conflicts.is_empty() {
+        for &index in &selected {
+            entries[index].undone = true;
+        }
+        save_journal(&path, &entries)?;
+    }
+    Ok(UndoReport {
+        entries: selected.iter().map(|&index| entries[index].seq).collect(),
+        ..report
+    })
+}
+
+/// Re-applies the most recently undone change.
+pub async fn redo_last(workspace_root: &Path, session_id: &str) -> Result<UndoReport, String> {
+    let path = journal_path(workspace_root, session_id);
+    let _journal = JOURNAL_LOCK.lock().await;
+    let mut entries = load_journal(workspace_root, session_id)?;
+    // Undo works from the end, so the last undone entry is the earliest one
+    let Some(index) = entries.iter().position(|entry| entry.undone) else {
+        return Ok(UndoReport::default());
+    };
+    let files = &entries[index].files;
+    let expected = files
+        .iter()
+        .map(|file| (file.path.clone(), file.before.clone()))
+        .collect();
+    let restore = files
+        .iter()
+        .map(|file| (file.path.clone(), file.after.clone()))
+        .collect();
+    let report = apply(expected, restore).await?;
+    if report.conflicts.is_empty() {
+        entries[index].undone = false;
+        save_journal(&path, &entries)?;
+    }
+    Ok(UndoReport {
+        entries: vec![entries[index].seq],
+        ..report
+    })
+}
+
+/// Writes `restore` if every file currently matches `expected`; otherwise
+/// reports the files that differ and changes nothing.
+async fn apply(
+    expected: BTreeMap<String, Option<String>>,
+    restore: BTreeMap<String, Option<String>>,
+) -> Result<UndoReport, String> {
+    crate::fs::ensure_writable("undo agent changes").map_err(|e| e.to_string())?;
+    for path in expected.keys().chain(restore.keys()) {
+        crate::fs::ensure_allowed_path(Path::new(path), false).map_err(|e| e.to_string())?;
+    }
+    let _locks = crate::fs::lock_paths(&restore.keys().collect::<Vec<_>>()).await;
+    let mut report = UndoReport::default();
+    for (path, state) in &expected {
+        let current = tokio::fs::read(path).await.ok();
+        if hash(current.as_deref()) != hash(decode(state)?.as_deref()) {
+            report.conflicts.push(path.clone());
+        }
+    }
+    if !report.conflicts.is_empty() {
+        return Ok(report);
+    }
+    for (path, state) in restore {
+        match decode(&state)? {
+            Some(bytes) => {
+                if let Some(parent) = Path::new(&path).parent() {
+                    tokio::fs::create_dir_all(parent)
+                        .await
+                        .map_err(|e| format!("Failed to restore {}: {}", path, e))?;
+                }
+                tokio::fs::write(&path, bytes)
+                    .await
+                    .map_err(|e| format!("Failed to restore {}: {}", path, e))?;
+            }
+            None => match tokio::fs::remove_file(&path).await {
+                Err(err) if err.kind() != std::io::ErrorKind::NotFound => {
+                    return Err(format!("Failed to remove {}: {}", path, err));
+                }
+                _ => {}
+            },
+        }
+        report.files.push(path);
+    }
+    Ok(report)
+}
+
+fn decode(state: &Option<String>) -> Result<Option<Vec<u8>>, String> {
+    state
+        .as_ref()
+        .map(|encoded| {
+            STANDARD
+                .decode(encoded)
+                .map_err(|e| format!("Invalid journal contents: {}", e))
+        })
+        .transpose()
+}
+
+fn hash(contents: Option<&[u8]>) -> Option<String> {
+    contents.map(|bytes| hex::encode(Sha256::digest(bytes)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    async fn journaled_write(journal: &MutationJournal, path: &Path, contents: &str) {
+        let input = json!({ "filePath": path.display().to_string() });
+        let pending = journal.begin("call", "write", &input).await.unwrap();
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+        journal.finish(pending).await;
+    }
+
+    /// Undo only restores files inside allowed roots, which include the
+    /// working directory
+    fn workspace() -> tempfile::TempDir {
+        tempfile::tempdir_in(std::env::current_dir().unwrap()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn undoes_and_redoes_changes_in_order() {
+        let workspace = workspace();
+        let note = workspace.path().join("a.md");
+        let created = workspace.path().join("new/b.md");
+        fs::write(&note, "v1").unwrap();
+
+        let journal = MutationJournal::new(workspace.path(), "s1");
+        journaled_write(&journal, &note, "v2").await;
+        journaled_write(&journal, &created, "fresh").await;
+        journaled_write(&journal, &note, "v3").await;
+        assert!(journal
+            .begin("call", "read", &json!({ "filePath": "a.md" }))
+            .await
+            .is_none());
+
+        let report = undo_last(workspace.path(), "s1").await.unwrap();
+        assert_eq!(report.entries, vec![3]);
+        assert_eq!(fs::read_to_string(&note).unwrap(), "v2");
+
+        let report = undo_run(workspace.path(), "s1").await.unwrap();
+        assert_eq!(report.entries, vec![2, 1]);
+        assert_eq!(fs::read_to_string(&note).unwrap(), "v1");
+        assert!(!created.exists());
+
+        let report = redo_last(workspace.path(), "s1").await.unwrap();
+        assert_eq!(report.entries, vec![1]);
+        assert_eq!(fs::read_to_string(&note).unwrap(), "v2");
+
+        // A new change drops the entries left to redo
+        journaled_write(&journal, &note, "v4").await;
+        let entries = load_journal(workspace.path(), "s1").unwrap();
+        assert_eq!(
+            entries.iter().map(|entry| entry.seq).collect::<Vec<_>>(),
+            vec![1, 2]
+        );
+        assert!(redo_last(workspace.path(), "s1")
+            .await
+            .unwrap()
+            .entries
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn refuses_to_undo_files_edited_since() {
+        let workspace = workspace();
+        let note = workspace.path().join("a.md");
+        let other = workspace.path().join("b.md");
+        fs::write(&note, "original").unwrap();
+
+        let journal = MutationJournal::new(workspace.path(), "s1");
+        journaled_write(&journal, &note, "agent").await;
+        journaled_write(&journal, &other, "agent").await;
+        fs::write(&note, "user edit").unwrap();
+
+        let report = undo_run(workspace.path(), "s1").await.unwrap();
+        assert_eq!(report.conflicts, vec![note.display().to_string()]);
+        assert!(report.files.is_empty());
+        assert_eq!(fs::read_to_string(&note).unwrap(), "user edit");
+        assert!(other.exists());
+        assert!(load_journal(workspace.path(), "s1")
+            .unwrap()
+            .iter()
+            .all(|entry| !entry.undone));
+
+        // The newest change does not touch the edited file
+        let report = undo_last(workspace.path(), "s1").await.unwrap();
+        assert!(report.conflicts.is_empty());
+        assert!(!other.exists());
+    }
+
+    #[tokio::test]
+    async fn refuses_to_undo_in_read_only_mode() {
+        let workspace = workspace();
+        let note = workspace.path().join("a.md");
+        fs::write(&note, "original").unwrap();
+        let journal = MutationJournal::new(workspace.path(), "s1");
+        journaled_write(&journal, &note, "agent").await;
+
+        crate::fs::set_thread_readonly(true);
+        let undo = undo_last(workspace.path(), "s1").await;
+        crate::fs::set_thread_readonly(false);
+        assert!(undo.unwrap_err().contains("read-only"));
+        assert_eq!(fs::read_to_string(&note).unwrap(), "agent");
+        assert!(load_journal(workspace.path(), "s1")
+            .unwrap()
+            .iter()
+            .all(|entry| !entry.undone));
+    }
+}
@@ -1,2 +1,7 @@
-﻿pub mod permissions;
+﻿pub mod audit;
+pub mod dry_run;
+pub mod frontmatter;
+pub mod journal;
+pub mod permissions;
 pub mod tools;
+pub mod trace;
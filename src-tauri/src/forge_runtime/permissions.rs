@@ -3,10 +3,16 @@ use forge::runtime::event::PermissionReply;
 use forge::runtime::permission::{PermissionDecision, PermissionRequest};
 use forge::runtime::tool::ToolContext;
 use regex::Regex;
+use serde::{Deserialize, Serialize};
 use serde_json::Map;
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::sync::Mutex;
 use uuid::Uuid;
 
+/// Workspace-relative location of persisted "always allow" approvals.
+const PERMISSIONS_FILE_PATH: &str = ".lumina/permissions.json";
+
 #[derive(Clone, Debug)]
 pub struct PermissionRule {
     pub permission: String,
@@ -30,6 +36,62 @@ impl PermissionRule {
 
 pub type PermissionRuleset = Vec<PermissionRule>;
 
+/// An "always allow" approval that survives across agent sessions.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PersistedPermissionRule {
+    pub permission: String,
+    pub pattern: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct PersistedPermissions {
+    #[serde(default)]
+    always: Vec<PersistedPermissionRule>,
+}
+
+pub fn permissions_file_path(workspace_root: &Path) -> PathBuf {
+    workspace_root.join(PERMISSIONS_FILE_PATH)
+}
+
+/// Loads the persisted rules for a workspace. A missing file yields no rules.
+pub fn load_persisted_rules(workspace_root: &Path) -> Result<Vec<PersistedPermissionRule>, String> {
+    let path = permissions_file_path(workspace_root);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content =
+        fs::read_to_string(&path).map_err(|e| format!("Failed to read permission rules: {}", e))?;
+    let persisted: PersistedPermissions = serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse permission rules: {}", e))?;
+    Ok(persisted.always)
+}
+
+fn save_persisted_rules(
+    workspace_root: &Path,
+    rules: &[PersistedPermissionRule],
+) -> Result<(), String> {
+    let path = permissions_file_path(workspace_root);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create permission rules directory: {}", e))?;
+    }
+    let content = serde_json::to_string_pretty(&PersistedPermissions {
+        always: rules.to_vec(),
+    })
+    .map_err(|e| format!("Failed to serialize permission rules: {}", e))?;
+    fs::write(&path, content).map_err(|e| format!("Failed to write permission rules: {}", e))
+}
+
+/// Removes every persisted rule for the workspace.
+pub fn clear_persisted_rules(workspace_root: &Path) -> Result<(), String> {
+    let path = permissions_file_path(workspace_root);
+    match fs::remove_file(&path) {
+        Ok(()) => Ok(()),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(err) => Err(format!("Failed to clear permission rules: {}", err)),
+    }
+}
+
 pub fn default_ruleset() -> PermissionRuleset {
     vec![
         PermissionRule::new("*", "*", PermissionDecision::Allow),
@@ -42,6 +104,15 @@ pub fn default_ruleset() -> PermissionRuleset {
     ]
 }
 
+/// Rules appended in read-only workspace mode: file edits and shell commands
+/// are denied, reads and searches stay allowed.
+pub fn readonly_rules() -> PermissionRuleset {
+    vec![
+        PermissionRule::new("edit", "*", PermissionDecision::Deny),
+        PermissionRule::new("bash", "*", PermissionDecision::Deny),
+    ]
+}
+
 #[derive(Default)]
 struct PermissionOverrides {
     once: Vec<PermissionRule>,
@@ -89,6 +160,7 @@ impl PermissionOverrides {
 pub struct PermissionSession {
     base: PermissionRuleset,
     overrides: Mutex<PermissionOverrides>,
+    workspace_root: Option<PathBuf>,
 }
 
 impl PermissionSession {
@@ -96,6 +168,37 @@ impl PermissionSession {
         Self {
             base,
             overrides: Mutex::new(PermissionOverrides::default()),
+            workspace_root: None,
+        }
+    }
+
+    /// Builds a session whose "always" approvals are loaded from and saved to
+    /// the workspace's `.lumina/permissions.json`.
+    pub fn with_workspace_persistence(
+        base: PermissionRuleset,
+        workspace_root: impl Into<PathBuf>,
+    ) -> Self {
+        let workspace_root = workspace_root.into();
+        let mut overrides = PermissionOverrides::default();
+        match load_persisted_rules(&workspace_root) {
+            Ok(rules) => {
+                overrides.always = rules
+                    .into_iter()
+                    .map(|rule| {
+                        PermissionRule::new(
+                            rule.permission,
+                            rule.pattern,
+                            PermissionDecision::Allow,
+                        )
+                    })
+                    .collect();
+            }
+            Err(err) => eprintln!("[Permissions] {}", err),
+        }
+        Self {
+            base,
+            overrides: Mutex::new(overrides),
+            workspace_root: Some(workspace_root),
         }
     }
 
@@ -111,8 +214,31 @@ impl PermissionSession {
     }
 
     pub fn apply_reply(&self, permission: &str, pattern: &str, reply: PermissionReply) {
+        let persist = matches!(reply, PermissionReply::Always);
         if let Ok(mut overrides) = self.overrides.lock() {
             overrides.apply_reply(permission, pattern, reply);
+            if persist {
+                self.persist_always(&overrides.always);
+            }
+        }
+    }
+
+    fn persist_always(&self, always: &[PermissionRule]) {
+        let Some(workspace_root) = self.workspace_root.as_deref() else {
+            return;
+        };
+        let mut rules: Vec<PersistedPermissionRule> = Vec::with_capacity(always.len());
+        for rule in always {
+            let persisted = PersistedPermissionRule {
+                permission: rule.permission.clone(),
+                pattern: rule.pattern.clone(),
+            };
+            if !rules.contains(&persisted) {
+                rules.push(persisted);
+            }
+        }
+        if let Err(err) = save_persisted_rules(workspace_root, &rules) {
+            eprintln!("[Permissions] {}", err);
         }
     }
 }
@@ -202,3 +328,73 @@ fn wildcard_match(value: &str, pattern: &str) -> bool {
         Err(_) => false,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn readonly_rules_deny_writes_but_not_reads() {
+        let mut rules = default_ruleset();
+        rules.extend(readonly_rules());
+        let session = PermissionSession::new(rules);
+        assert!(matches!(
+            session.decide("edit", "notes/a.md"),
+            PermissionDecision::Deny
+        ));
+        assert!(matches!(
+            session.decide("bash", "ls"),
+            PermissionDecision::Deny
+        ));
+        assert!(matches!(
+            session.decide("read", "notes/a.md"),
+            PermissionDecision::Allow
+        ));
+    }
+
+    #[test]
+    fn always_replies_persist_per_workspace() {
+        let workspace = tempfile::tempdir().unwrap();
+        let other = tempfile::tempdir().unwrap();
+        let base = vec![PermissionRule::new("edit", "*", PermissionDecision::Ask)];
+
+        let session = PermissionSession::with_workspace_persistence(base.clone(), workspace.path());
+        assert!(matches!(
+            session.decide("edit", "notes/a.md"),
+            PermissionDecision::Ask
+        ));
+        session.apply_reply("edit", "notes/a.md", PermissionReply::Always);
+        session.apply_reply("edit", "notes/b.md", PermissionReply::Once);
+
+        let restored =
+            PermissionSession::with_workspace_persistence(base.clone(), workspace.path());
+        assert!(matches!(
+            restored.decide("edit", "notes/a.md"),
+            PermissionDecision::Allow
+        ));
+        assert!(matches!(
+            restored.decide("edit", "notes/b.md"),
+            PermissionDecision::Ask
+        ));
+        assert_eq!(
+            load_persisted_rules(workspace.path()).unwrap(),
+            vec![PersistedPermissionRule {
+                permission: "edit".to_string(),
+                pattern: "notes/a.md".to_string(),
+            }]
+        );
+
+        let unrelated = PermissionSession::with_workspace_persistence(base.clone(), other.path());
+        assert!(matches!(
+            unrelated.decide("edit", "notes/a.md"),
+            PermissionDecision::Ask
+        ));
+
+        clear_persisted_rules(workspace.path()).unwrap();
+        let cleared = PermissionSession::with_workspace_persistence(base, workspace.path());
+        assert!(matches!(
+            cleared.decide("edit", "notes/a.md"),
+            PermissionDecision::Ask
+        ));
+    }
+}
@@ -0,0 +1,77 @@
+use crate::forge_runtime::tools::shared::parse_tool_input;
+use crate::forge_runtime::tools::ToolEnvironment;
+use forge::runtime::error::{GraphError, GraphResult, Interrupt};
+use forge::runtime::tool::{ToolCall, ToolContext, ToolDefinition, ToolOutput, ToolRegistry};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use uuid::Uuid;
+
+pub const ASK_USER_TOOL: &str = "ask_user";
+const QUESTION_INTERRUPT_TYPE: &str = "question";
+
+#[derive(Deserialize)]
+struct AskUserInput {
+    question: String,
+    #[serde(default)]
+    options: Vec<String>,
+}
+
+pub fn register(registry: &mut ToolRegistry, _env: ToolEnvironment) {
+    let description = include_str!("descriptions/ask_user.txt").to_string();
+    let definition = ToolDefinition::new(ASK_USER_TOOL, description).with_input_schema(json!({
+        "type": "object",
+        "properties": {
+            "question": { "type": "string" },
+            "options": { "type": "array", "items": { "type": "string" } }
+        },
+        "required": ["question"]
+    }));
+
+    registry.register_with_definition(
+        definition,
+        Arc::new(move |call, ctx| Box::pin(async move { handle(call, ctx).await })),
+    );
+}
+
+/// Pauses the run with a question interrupt; the answer is injected on resume.
+async fn handle(call: ToolCall, _ctx: ToolContext) -> GraphResult<ToolOutput> {
+    let input: AskUserInput = parse_tool_input(&call)?;
+    let question = input.question.trim();
+    if question.is_empty() {
+        return Err(GraphError::ExecutionError {
+            node: format!("tool:{}", call.tool),
+            message: "question must not be empty".to_string(),
+        });
+    }
+
+    let request_id = Uuid::new_v4().to_string();
+    Err(GraphError::Interrupted(vec![Interrupt::with_id(
+        json!({
+            "type": QUESTION_INTERRUPT_TYPE,
+            "question": question,
+            "options": input.options,
+        }),
+        format!("question:{}", ASK_USER_TOOL),
+        request_id,
+    )]))
+}
+
+/// Returns the question and options when the interrupt payload came from `ask_user`.
+pub fn question_from_interrupt(value: &Value) -> Option<(String, Vec<String>)> {
+    if value.get("type").and_then(Value::as_str) != Some(QUESTION_INTERRUPT_TYPE) {
+        return None;
+    }
+    let question = value.get("question")?.as_str()?.to_string();
+    let options = value
+        .get("options")
+        .and_then(Value::as_array)
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|item| item.as_str().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default();
+    Some((question, options))
+}
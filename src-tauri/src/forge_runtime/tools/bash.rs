@@ -1,6 +1,8 @@
+use crate::forge_runtime::dry_run::{simulate, SimulatedChange};
 use crate::forge_runtime::permissions::request_permission;
 use crate::forge_runtime::tools::shared::{
-    ensure_external_directory_permission, parse_tool_input, resolve_path, truncate_text,
+    cancelled, ensure_external_directory_permission, parse_tool_input, resolve_path, truncate_text,
+    wait_cancelled,
 };
 use crate::forge_runtime::tools::ToolEnvironment;
 use forge::runtime::error::{GraphError, GraphResult};
@@ -170,6 +172,11 @@ async fn handle(call: ToolCall, ctx: ToolContext, env: ToolEnvironment) -> Graph
         },
     )?;
 
+    if let Some(log) = &env.dry_run {
+        let description = format!("Would run `{}` in {}.", input.command, workdir.display());
+        return Ok(simulate(log, SimulatedChange::command("bash", description)));
+    }
+
     let shell_env = ShellEnv::current();
     let shell = select_shell_command(&shell_env);
     let mut cmd = Command::new(&shell.program);
@@ -226,6 +233,11 @@ async fn handle(call: ToolCall, ctx: ToolContext, env: ToolEnvironment) -> Graph
             let _ = child.wait().await;
             Ok(None)
         }
+        _ = wait_cancelled(&env.cancel) => {
+            let _ = child.kill().await;
+            let _ = child.wait().await;
+            return Err(cancelled(&env.cancel));
+        }
     };
 
     let status = match status_result {
@@ -0,0 +1,181 @@
+use crate::forge_runtime::dry_run::{simulate, SimulatedChange};
+use crate::forge_runtime::permissions::request_permission;
+use crate::forge_runtime::tools::shared::{
+    ensure_external_directory_permission, parse_tool_input, permission_path, resolve_path,
+};
+use crate::forge_runtime::tools::ToolEnvironment;
+use crate::note_templates::{list_templates, load_template, render_template};
+use forge::runtime::error::{GraphError, GraphResult};
+use forge::runtime::tool::{ToolCall, ToolContext, ToolDefinition, ToolOutput, ToolRegistry};
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use std::io;
+use std::sync::Arc;
+use tokio::io::AsyncWriteExt;
+
+#[derive(Deserialize)]
+struct CreateFromTemplateInput {
+    template: String,
+    #[serde(rename = "targetPath")]
+    target_path: String,
+    #[serde(default)]
+    vars: Map<String, Value>,
+}
+
+pub fn register(registry: &mut ToolRegistry, env: ToolEnvironment) {
+    let description = include_str!("descriptions/create_from_template.txt").to_string();
+    let definition =
+        ToolDefinition::new("create_from_template", description).with_input_schema(json!({
+            "type": "object",
+            "properties": {
+                "template": { "type": "string" },
+                "targetPath": { "type": "string" },
+                "vars": { "type": "object" }
+            },
+            "required": ["template", "targetPath"]
+        }));
+
+    registry.register_with_definition(
+        definition,
+        Arc::new(move |call, ctx| {
+            let env = env.clone();
+            Box::pin(async move { handle(call, ctx, env).await })
+        }),
+    );
+}
+
+async fn handle(call: ToolCall, ctx: ToolContext, env: ToolEnvironment) -> GraphResult<ToolOutput> {
+    if crate::fs::is_workspace_readonly() {
+        return Err(GraphError::ExecutionError {
+            node: format!("tool:{}", call.tool),
+            message: "The workspace is read-only; files cannot be modified.".to_string(),
+        });
+    }
+    let input: CreateFromTemplateInput = parse_tool_input(&call)?;
+    let mut target = resolve_path(&env.workspace_root, &input.target_path);
+    if target.extension().is_none() {
+        target.set_extension("md");
+    }
+
+    // Resolve the template before asking for permission so a typo fails fast
+    let template = load_template(&env.workspace_root, &input.template).map_err(|err| {
+        let available = list_templates(&env.workspace_root)
+            .map(|templates| {
+                templates
+                    .iter()
+                    .map(|template| template.name.clone())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            })
+            .unwrap_or_default();
+        GraphError::ExecutionError {
+            node: format!("tool:{}", call.tool),
+            message: format!("{}. Available templates: {}", err, available),
+        }
+    })?;
+    let title = target
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let rendered = render_template(
+        &template,
+        &input.vars,
+        &title,
+        chrono::Local::now().fixed_offset(),
+    )
+    .map_err(|err| GraphError::ExecutionError {
+        node: format!("tool:{}", call.tool),
+        message: err.to_string(),
+    })?;
+
+    ensure_external_directory_permission(
+        &ctx,
+        &env.permissions,
+        &env.workspace_root,
+        &target,
+        "file",
+    )?;
+
+    let pattern = permission_path(&env.workspace_root, &target);
+    let mut metadata = Map::new();
+    metadata.insert("filepath".to_string(), json!(target.display().to_string()));
+    metadata.insert("template".to_string(), json!(input.template));
+
+    request_permission(
+        &ctx,
+        &env.permissions,
+        "edit",
+        &pattern,
+        metadata,
+        vec!["*".to_string()],
+    )?;
+
+    let _lock = crate::fs::lock_path(&target).await;
+    if tokio::fs::try_exists(&target).await.unwrap_or(false) {
+        return Err(GraphError::ExecutionError {
+            node: format!("tool:{}", call.tool),
+            message: format!(
+                "File already exists: {}. Choose another targetPath.",
+                target.display()
+            ),
+        });
+    }
+
+    if let Some(log) = &env.dry_run {
+        let description = format!(
+            "Would create {} from template {}.",
+            target.display(),
+            input.template
+        );
+        return Ok(simulate(
+            log,
+            SimulatedChange::file(
+                "create_from_template",
+                &target,
+                None,
+                rendered.content,
+                description,
+            ),
+        ));
+    }
+
+    if let Some(parent) = target.parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(|err| GraphError::ExecutionError {
+                node: format!("tool:{}", call.tool),
+                message: format!("Failed to create directory: {}", err),
+            })?;
+    }
+    let write_error = |err: io::Error| GraphError::ExecutionError {
+        node: format!("tool:{}", call.tool),
+        message: format!("Failed to write file: {}", err),
+    };
+    let mut file = tokio::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&target)
+        .await
+        .map_err(write_error)?;
+    file.write_all(rendered.content.as_bytes())
+        .await
+        .map_err(write_error)?;
+
+    let mut message = format!(
+        "Created {} from template {}.",
+        target.display(),
+        input.template
+    );
+    if let Some((line, column)) = rendered.cursor {
+        message.push_str(&format!(
+            " The template marks line {}, column {} as the place to continue writing.",
+            line, column
+        ));
+    }
+    Ok(ToolOutput::text(message)
+        .with_mime_type("text/plain")
+        .with_schema("tool.create_from_template.v1")
+        .with_attribute("filepath", json!(target.display().to_string()))
+        .with_attribute("template", json!(input.template))
+        .with_attribute("cursor", json!(rendered.cursor)))
+}
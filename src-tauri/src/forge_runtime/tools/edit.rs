@@ -1,4 +1,6 @@
+use crate::forge_runtime::dry_run::{simulate, SimulatedChange};
 use crate::forge_runtime::permissions::request_permission;
+use crate::forge_runtime::tools::fuzzy_match::{find_fuzzy, line_span, FuzzyKind, FuzzyMatch};
 use crate::forge_runtime::tools::shared::{
     ensure_external_directory_permission, parse_tool_input, permission_path, resolve_path,
 };
@@ -14,11 +16,18 @@ struct EditInput {
     #[serde(rename = "filePath")]
     file_path: String,
     #[serde(rename = "oldString")]
-    old_string: String,
+    old_string: Option<String>,
     #[serde(rename = "newString")]
     new_string: String,
     #[serde(rename = "replaceAll")]
     replace_all: Option<bool>,
+    /// Fall back to an approximate match when `oldString` is not found verbatim
+    fuzzy: Option<bool>,
+    /// 1-based inclusive line range from `read` output; replaces string matching
+    #[serde(rename = "startLine")]
+    start_line: Option<usize>,
+    #[serde(rename = "endLine")]
+    end_line: Option<usize>,
 }
 
 pub fn register(registry: &mut ToolRegistry, env: ToolEnvironment) {
@@ -29,9 +38,12 @@ pub fn register(registry: &mut ToolRegistry, env: ToolEnvironment) {
             "filePath": { "type": "string" },
             "oldString": { "type": "string" },
             "newString": { "type": "string" },
-            "replaceAll": { "type": "boolean" }
+            "replaceAll": { "type": "boolean" },
+            "fuzzy": { "type": "boolean" },
+            "startLine": { "type": "number" },
+            "endLine": { "type": "number" }
         },
-        "required": ["filePath", "oldString", "newString"]
+        "required": ["filePath", "newString"]
     }));
 
     registry.register_with_definition(
@@ -44,13 +56,40 @@ pub fn register(registry: &mut ToolRegistry, env: ToolEnvironment) {
 }
 
 async fn handle(call: ToolCall, ctx: ToolContext, env: ToolEnvironment) -> GraphResult<ToolOutput> {
-    let input: EditInput = parse_tool_input(&call)?;
-    if input.old_string == input.new_string {
+    if crate::fs::is_workspace_readonly() {
         return Err(GraphError::ExecutionError {
             node: format!("tool:{}", call.tool),
-            message: "oldString and newString must be different".to_string(),
+            message: "The workspace is read-only; files cannot be modified.".to_string(),
         });
     }
+    let input: EditInput = parse_tool_input(&call)?;
+    let line_range = match (input.start_line, input.end_line) {
+        (Some(start), end) => Some((start, end.unwrap_or(start))),
+        (None, Some(_)) => {
+            return Err(GraphError::ExecutionError {
+                node: format!("tool:{}", call.tool),
+                message: "endLine requires startLine".to_string(),
+            });
+        }
+        (None, None) => None,
+    };
+    if line_range.is_none() {
+        match &input.old_string {
+            None => {
+                return Err(GraphError::ExecutionError {
+                    node: format!("tool:{}", call.tool),
+                    message: "Provide either oldString or startLine/endLine".to_string(),
+                });
+            }
+            Some(old_string) if *old_string == input.new_string => {
+                return Err(GraphError::ExecutionError {
+                    node: format!("tool:{}", call.tool),
+                    message: "oldString and newString must be different".to_string(),
+                });
+            }
+            Some(_) => {}
+        }
+    }
 
     let target = resolve_path(&env.workspace_root, &input.file_path);
     ensure_external_directory_permission(
@@ -74,6 +113,16 @@ async fn handle(call: ToolCall, ctx: ToolContext, env: ToolEnvironment) -> Graph
         vec!["*".to_string()],
     )?;
 
+    // Hold the file lock across read-modify-write so sync or the editor cannot interleave
+    let _lock = crate::fs::lock_path(&target).await;
+    if line_range.is_some() {
+        env.file_times
+            .ensure_unchanged(&target)
+            .map_err(|message| GraphError::ExecutionError {
+                node: format!("tool:{}", call.tool),
+                message,
+            })?;
+    }
     let content =
         tokio::fs::read_to_string(&target)
             .await
@@ -83,31 +132,99 @@ async fn handle(call: ToolCall, ctx: ToolContext, env: ToolEnvironment) -> Graph
             })?;
 
     let replace_all = input.replace_all.unwrap_or(false);
-    let (new_content, replaced) = if input.old_string.is_empty() {
+    let mut fuzzy_match: Option<(FuzzyKind, String, (usize, usize))> = None;
+    let old_string = input.old_string.clone().unwrap_or_default();
+    let (new_content, replaced) = if let Some((start, end)) = line_range {
+        let updated =
+            replace_lines(&content, start, end, &input.new_string).map_err(|message| {
+                GraphError::ExecutionError {
+                    node: format!("tool:{}", call.tool),
+                    message,
+                }
+            })?;
+        (updated, end - start + 1)
+    } else if old_string.is_empty() {
         (input.new_string.clone(), 0usize)
     } else {
-        let count = content.matches(&input.old_string).count();
-        if count == 0 {
+        let count = content.matches(&old_string).count();
+        if count == 0 && input.fuzzy.unwrap_or(false) && !replace_all {
+            match find_fuzzy(&content, &old_string) {
+                FuzzyMatch::Found { range, kind } => {
+                    let lines = line_span(&content, &range);
+                    let mut updated = content.clone();
+                    updated.replace_range(range.clone(), &input.new_string);
+                    fuzzy_match = Some((kind, content[range].to_string(), lines));
+                    (updated, 1)
+                }
+                FuzzyMatch::Ambiguous { candidates } => {
+                    return Err(GraphError::ExecutionError {
+                        node: format!("tool:{}", call.tool),
+                        message: format!(
+                            "oldString not found exactly and the fuzzy match is ambiguous ({} candidate regions). Provide more context.",
+                            candidates
+                        ),
+                    });
+                }
+                FuzzyMatch::NotFound => {
+                    return Err(GraphError::ExecutionError {
+                        node: format!("tool:{}", call.tool),
+                        message: "oldString not found in content, even with fuzzy matching"
+                            .to_string(),
+                    });
+                }
+            }
+        } else if count == 0 {
             return Err(GraphError::ExecutionError {
                 node: format!("tool:{}", call.tool),
                 message: "oldString not found in content".to_string(),
             });
-        }
-        if !replace_all && count > 1 {
-            return Err(GraphError::ExecutionError {
-                node: format!("tool:{}", call.tool),
-                message:
-                    "Found multiple matches for oldString. Provide more context or set replaceAll."
+        } else {
+            if !replace_all && count > 1 {
+                return Err(GraphError::ExecutionError {
+                    node: format!("tool:{}", call.tool),
+                    message: "Found multiple matches for oldString. Provide more context or set replaceAll."
                         .to_string(),
-            });
+                });
+            }
+            let updated = if replace_all {
+                content.replace(&old_string, &input.new_string)
+            } else {
+                content.replacen(&old_string, &input.new_string, 1)
+            };
+            (updated, count)
         }
-        let updated = if replace_all {
-            content.replace(&input.old_string, &input.new_string)
+    };
+
+    if let Some(log) = &env.dry_run {
+        let description = if let Some((kind, _, (first, last))) = &fuzzy_match {
+            format!(
+                "Would edit {} via {} fuzzy match (lines {}-{}).",
+                target.display(),
+                kind.label(),
+                first,
+                last
+            )
+        } else if let Some((start, end)) = line_range {
+            format!(
+                "Would replace lines {}-{} of {}.",
+                start,
+                end,
+                target.display()
+            )
+        } else if replaced == 0 {
+            format!("Would replace the contents of {}.", target.display())
         } else {
-            content.replacen(&input.old_string, &input.new_string, 1)
+            format!(
+                "Would edit {} ({} replacement(s)).",
+                target.display(),
+                replaced
+            )
         };
-        (updated, count)
-    };
+        return Ok(simulate(
+            log,
+            SimulatedChange::file("edit", &target, Some(content), new_content, description),
+        ));
+    }
 
     tokio::fs::write(&target, new_content)
         .await
@@ -115,10 +232,103 @@ async fn handle(call: ToolCall, ctx: ToolContext, env: ToolEnvironment) -> Graph
             node: format!("tool:{}", call.tool),
             message: format!("Failed to write file: {}", err),
         })?;
+    // Our own edit does not invalidate line numbers the model can account for
+    env.file_times.record(&target);
 
-    Ok(ToolOutput::text("Edit applied successfully.")
+    let output = match &fuzzy_match {
+        Some((kind, matched, (first, last))) => ToolOutput::text(format!(
+            "Edit applied via {} fuzzy match (lines {}-{}). oldString was not found verbatim; the replaced text was:\n{}",
+            kind.label(),
+            first,
+            last,
+            matched
+        ))
+        .with_attribute("fuzzy", json!(kind.label()))
+        .with_attribute("fuzzyLines", json!([first, last]))
+        .with_attribute(
+            "fuzzyDistance",
+            json!(match kind {
+                FuzzyKind::EditDistance { distance } => *distance,
+                FuzzyKind::Whitespace => 0,
+            }),
+        ),
+        None => match line_range {
+            Some((start, end)) => ToolOutput::text(format!(
+                "Edit applied successfully. Replaced lines {}-{}.",
+                start, end
+            ))
+            .with_attribute("lines", json!([start, end])),
+            None => ToolOutput::text("Edit applied successfully."),
+        },
+    };
+    Ok(output
         .with_mime_type("text/plain")
         .with_schema("tool.edit.v1")
         .with_attribute("filepath", json!(target.display().to_string()))
         .with_attribute("replaced", json!(replaced)))
 }
+
+/// Replaces the 1-based inclusive line range `start..=end` with `replacement`.
+/// An empty replacement deletes the lines. Lines are counted like the `read`
+/// tool counts them, and CRLF endings of the replaced lines are kept.
+fn replace_lines(
+    content: &str,
+    start: usize,
+    end: usize,
+    replacement: &str,
+) -> Result<String, String> {
+    let lines: Vec<&str> = content.split('\n').collect();
+    if start == 0 || end < start || end > lines.len() {
+        return Err(format!(
+            "Invalid line range {}-{}: the file has {} lines. Read it again to get current line numbers.",
+            start,
+            end,
+            lines.len()
+        ));
+    }
+    let crlf = lines[end - 1].ends_with('\r');
+    let mut updated: Vec<String> = lines[..start - 1]
+        .iter()
+        .map(|line| line.to_string())
+        .collect();
+    if !replacement.is_empty() {
+        updated.extend(replacement.split('\n').map(|line| {
+            let line = line.trim_end_matches('\r');
+            if crlf {
+                format!("{}\r", line)
+            } else {
+                line.to_string()
+            }
+        }));
+    }
+    updated.extend(lines[end..].iter().map(|line| line.to_string()));
+    Ok(updated.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replaces_and_deletes_line_ranges() {
+        let content = "# Title\none\ntwo\nthree\n";
+        assert_eq!(
+            replace_lines(content, 2, 3, "uno\ndos\ndos y medio").unwrap(),
+            "# Title\nuno\ndos\ndos y medio\nthree\n"
+        );
+        assert_eq!(
+            replace_lines(content, 2, 2, "").unwrap(),
+            "# Title\ntwo\nthree\n"
+        );
+        assert_eq!(
+            replace_lines("a\r\nb\r\nc", 2, 2, "x\ny").unwrap(),
+            "a\r\nx\r\ny\r\nc"
+        );
+
+        for (start, end) in [(0, 1), (3, 2), (2, 6)] {
+            assert!(replace_lines(content, start, end, "x")
+                .unwrap_err()
+                .contains("the file has 5 lines"));
+        }
+    }
+}
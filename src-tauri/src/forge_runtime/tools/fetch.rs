@@ -1,11 +1,15 @@
 use crate::forge_runtime::permissions::request_permission;
+use crate::forge_runtime::tools::shared::run_cancellable;
 use crate::forge_runtime::tools::ToolEnvironment;
+use crate::http_fetch::{is_textual, read_body_limited};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
 use forge::runtime::error::{GraphError, GraphResult};
 use forge::runtime::tool::{ToolCall, ToolContext, ToolDefinition, ToolOutput, ToolRegistry};
 use html2md::parse_html;
 use reqwest::header::CONTENT_TYPE;
+use reqwest::redirect::Policy;
 use reqwest::StatusCode;
-use scraper::Html;
+use scraper::{ElementRef, Html, Selector};
 use serde::Deserialize;
 use serde_json::{json, Map};
 use std::sync::Arc;
@@ -14,12 +18,44 @@ use std::time::Duration;
 const DEFAULT_TIMEOUT_SECS: u64 = 30;
 const MAX_TIMEOUT_SECS: u64 = 120;
 const MAX_BYTES: usize = 5 * 1024 * 1024;
+const DEFAULT_MAX_REDIRECTS: usize = 10;
+const MAX_REDIRECTS: usize = 20;
+const FORMATS: [&str; 4] = ["text", "markdown", "html", "readable"];
+/// Subtrees that never contribute to the readable article body.
+const BOILERPLATE_TAGS: [&str; 14] = [
+    "script", "style", "noscript", "template", "svg", "canvas", "iframe", "nav", "header",
+    "footer", "aside", "form", "button", "select",
+];
+const BLOCK_TAGS: [&str; 16] = [
+    "p",
+    "div",
+    "section",
+    "article",
+    "main",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "li",
+    "pre",
+    "blockquote",
+    "tr",
+    "figcaption",
+];
+/// Minimum amount of text a candidate container must hold to be treated as the main content.
+const MIN_ARTICLE_CHARS: usize = 200;
 
 #[derive(Deserialize)]
 struct FetchInput {
     url: String,
     format: String,
     timeout: Option<u64>,
+    #[serde(rename = "maxBytes")]
+    max_bytes: Option<usize>,
+    #[serde(rename = "maxRedirects")]
+    max_redirects: Option<usize>,
 }
 
 pub fn register(registry: &mut ToolRegistry, env: ToolEnvironment) {
@@ -28,8 +64,10 @@ pub fn register(registry: &mut ToolRegistry, env: ToolEnvironment) {
         "type": "object",
         "properties": {
             "url": { "type": "string" },
-            "format": { "type": "string", "enum": ["text", "markdown", "html"] },
-            "timeout": { "type": "number" }
+            "format": { "type": "string", "enum": ["text", "markdown", "html", "readable"] },
+            "timeout": { "type": "number" },
+            "maxBytes": { "type": "number" },
+            "maxRedirects": { "type": "number" }
         },
         "required": ["url", "format"]
     }));
@@ -59,8 +97,10 @@ async fn handle(call: ToolCall, ctx: ToolContext, env: ToolEnvironment) -> Graph
     }
 
     let format = input.format.to_ascii_lowercase();
-    if format != "text" && format != "markdown" && format != "html" {
-        return Ok(tool_error("Format must be one of: text, markdown, html"));
+    if !FORMATS.contains(&format.as_str()) {
+        return Ok(tool_error(
+            "Format must be one of: text, markdown, html, readable",
+        ));
     }
 
     if !input.url.starts_with("http://") && !input.url.starts_with("https://") {
@@ -71,16 +111,23 @@ async fn handle(call: ToolCall, ctx: ToolContext, env: ToolEnvironment) -> Graph
         .timeout
         .unwrap_or(DEFAULT_TIMEOUT_SECS)
         .min(MAX_TIMEOUT_SECS);
-    let client = if let Some(ref c) = env.http_client {
-        c.clone()
-    } else {
-        reqwest::Client::builder()
+    let max_bytes = input.max_bytes.unwrap_or(MAX_BYTES).clamp(1, MAX_BYTES);
+    // The shared client keeps reqwest's default redirect policy (10 hops), so only
+    // build a dedicated client when the caller asks for a different limit.
+    let client = match (&env.http_client, input.max_redirects) {
+        (Some(c), None) => c.clone(),
+        (_, max_redirects) => reqwest::Client::builder()
             .timeout(Duration::from_secs(timeout))
+            .redirect(redirect_policy(
+                max_redirects
+                    .unwrap_or(DEFAULT_MAX_REDIRECTS)
+                    .min(MAX_REDIRECTS),
+            ))
             .build()
             .map_err(|err| GraphError::ExecutionError {
                 node: format!("tool:{}", call.tool),
                 message: format!("Failed to create request client: {}", err),
-            })?
+            })?,
     };
 
     let mut metadata = Map::new();
@@ -89,6 +136,9 @@ async fn handle(call: ToolCall, ctx: ToolContext, env: ToolEnvironment) -> Graph
     if let Some(timeout) = input.timeout {
         metadata.insert("timeout".to_string(), json!(timeout));
     }
+    if let Some(max_redirects) = input.max_redirects {
+        metadata.insert("maxRedirects".to_string(), json!(max_redirects));
+    }
 
     request_permission(
         &ctx,
@@ -107,12 +157,15 @@ async fn handle(call: ToolCall, ctx: ToolContext, env: ToolEnvironment) -> Graph
             }
         })?;
 
-    let mut resp = client
-        .execute(request)
-        .await
+    let mut resp = run_cancellable(&env.cancel, client.execute(request))
+        .await?
         .map_err(|err| GraphError::ExecutionError {
             node: format!("tool:{}", call.tool),
-            message: format!("Failed to fetch URL: {}", err),
+            message: if err.is_redirect() {
+                format!("Too many redirects while fetching URL: {}", err)
+            } else {
+                format!("Failed to fetch URL: {}", err)
+            },
         })?;
 
     if resp.status() != StatusCode::OK {
@@ -129,12 +182,37 @@ async fn handle(call: ToolCall, ctx: ToolContext, env: ToolEnvironment) -> Graph
         .unwrap_or("")
         .to_string();
 
-    let body = match read_body_limited(&mut resp).await {
-        Ok(body) => body,
-        Err(err) => return Ok(tool_error(format!("Failed to read response body: {}", err))),
-    };
+    let final_url = resp.url().to_string();
+    let (body, truncated) =
+        match run_cancellable(&env.cancel, read_body_limited(&mut resp, max_bytes)).await? {
+            Ok(body) => body,
+            Err(err) => return Ok(tool_error(format!("Failed to read response body: {}", err))),
+        };
+
+    // Responses without a Content-Type are treated as text.
+    if !content_type.is_empty() && !is_textual(Some(content_type.as_str())) {
+        let mime_type = content_type
+            .split(';')
+            .next()
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .unwrap_or("application/octet-stream")
+            .to_string();
+        return Ok(ToolOutput::text(STANDARD.encode(&body))
+            .with_mime_type(mime_type.as_str())
+            .with_schema("tool.fetch.v1")
+            .with_attribute("url", json!(input.url))
+            .with_attribute("final_url", json!(final_url))
+            .with_attribute("format", json!(format))
+            .with_attribute("content_type", json!(content_type))
+            .with_attribute("encoding", json!("base64"))
+            .with_attribute("bytes", json!(body.len()))
+            .with_attribute("truncated", json!(truncated)));
+    }
 
     let content = String::from_utf8_lossy(&body).to_string();
+    let is_html = content_type.to_ascii_lowercase().contains("text/html");
+    let mut title = None;
 
     let output = match format.as_str() {
         "text" => {
@@ -167,16 +245,35 @@ async fn handle(call: ToolCall, ctx: ToolContext, env: ToolEnvironment) -> Graph
                 format!("```\n{}\n```", content)
             }
         }
+        "readable" => {
+            if is_html {
+                let article = extract_readable_from_html(&content);
+                let output = match article.title.as_deref() {
+                    Some(title) => format!("# {}\n\n{}", title, article.text),
+                    None => article.text,
+                };
+                title = article.title;
+                output
+            } else {
+                content
+            }
+        }
         "html" => content,
         _ => content,
     };
 
-    Ok(ToolOutput::text(output)
+    let mut output_value = ToolOutput::text(output)
         .with_mime_type("text/plain")
         .with_schema("tool.fetch.v1")
         .with_attribute("url", json!(input.url))
+        .with_attribute("final_url", json!(final_url))
         .with_attribute("format", json!(format))
-        .with_attribute("content_type", json!(content_type)))
+        .with_attribute("content_type", json!(content_type))
+        .with_attribute("truncated", json!(truncated));
+    if let Some(title) = title {
+        output_value = output_value.with_attribute("title", json!(title));
+    }
+    Ok(output_value)
 }
 
 fn tool_error(message: impl Into<String>) -> ToolOutput {
@@ -198,20 +295,12 @@ fn build_request(
         .build()
 }
 
-async fn read_body_limited(resp: &mut reqwest::Response) -> Result<Vec<u8>, reqwest::Error> {
-    let mut body = Vec::new();
-    while let Some(chunk) = resp.chunk().await? {
-        if body.len() >= MAX_BYTES {
-            break;
-        }
-        let remaining = MAX_BYTES.saturating_sub(body.len());
-        let end = remaining.min(chunk.len());
-        body.extend_from_slice(&chunk[..end]);
-        if end < chunk.len() {
-            break;
-        }
+fn redirect_policy(max_redirects: usize) -> Policy {
+    if max_redirects == 0 {
+        Policy::none()
+    } else {
+        Policy::limited(max_redirects)
     }
-    Ok(body)
 }
 
 fn extract_text_from_html(html: &str) -> Result<String, String> {
@@ -225,6 +314,145 @@ fn convert_html_to_markdown(html: &str) -> Result<String, String> {
     Ok(parse_html(html))
 }
 
+struct ReadableArticle {
+    title: Option<String>,
+    text: String,
+}
+
+/// Readability-style extraction: pick the container that holds the article body,
+/// drop navigation/boilerplate subtrees and return paragraph-separated text.
+fn extract_readable_from_html(html: &str) -> ReadableArticle {
+    let document = Html::parse_document(html);
+    let title = extract_title(&document);
+
+    let text = find_main_container(&document)
+        .map(collect_readable_text)
+        .filter(|text| !text.is_empty())
+        .or_else(|| {
+            select_first(&document, "body")
+                .map(collect_readable_text)
+                .filter(|text| !text.is_empty())
+        })
+        .unwrap_or_default();
+
+    ReadableArticle { title, text }
+}
+
+fn select_first<'a>(document: &'a Html, selector: &str) -> Option<ElementRef<'a>> {
+    let selector = Selector::parse(selector).ok()?;
+    document.select(&selector).next()
+}
+
+fn normalize_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn extract_title(document: &Html) -> Option<String> {
+    let meta_title = Selector::parse(r#"meta[property="og:title"]"#)
+        .ok()
+        .and_then(|selector| {
+            document
+                .select(&selector)
+                .next()
+                .and_then(|element| element.value().attr("content"))
+                .map(normalize_whitespace)
+        });
+    meta_title
+        .into_iter()
+        .chain(
+            ["title", "h1"]
+                .iter()
+                .filter_map(|name| select_first(document, name))
+                .map(|element| normalize_whitespace(&element.text().collect::<String>())),
+        )
+        .find(|title| !title.is_empty())
+}
+
+fn find_main_container(document: &Html) -> Option<ElementRef<'_>> {
+    for candidate in ["article", "main", r#"[role="main"]"#] {
+        if let Some(element) = select_first(document, candidate) {
+            if paragraph_chars(element) >= MIN_ARTICLE_CHARS {
+                return Some(element);
+            }
+        }
+    }
+
+    // Fall back to the container whose direct paragraphs carry the most text.
+    let selector = Selector::parse("div, section, td").ok()?;
+    document
+        .select(&selector)
+        .map(|element| (direct_paragraph_chars(element), element))
+        .filter(|(score, _)| *score >= MIN_ARTICLE_CHARS)
+        .max_by_key(|(score, _)| *score)
+        .map(|(_, element)| element)
+}
+
+fn paragraph_chars(element: ElementRef<'_>) -> usize {
+    let Ok(selector) = Selector::parse("p") else {
+        return 0;
+    };
+    element
+        .select(&selector)
+        .map(|p| normalize_whitespace(&p.text().collect::<String>()).len())
+        .sum()
+}
+
+fn direct_paragraph_chars(element: ElementRef<'_>) -> usize {
+    element
+        .children()
+        .filter_map(ElementRef::wrap)
+        .filter(|child| child.value().name() == "p")
+        .map(|p| normalize_whitespace(&p.text().collect::<String>()).len())
+        .sum()
+}
+
+fn collect_readable_text(element: ElementRef<'_>) -> String {
+    let mut blocks = Vec::new();
+    let mut current = String::new();
+    collect_blocks(element, &mut blocks, &mut current);
+    flush_block(&mut blocks, &mut current);
+    blocks.join("\n\n")
+}
+
+fn collect_blocks(element: ElementRef<'_>, blocks: &mut Vec<String>, current: &mut String) {
+    let name = element.value().name();
+    if BOILERPLATE_TAGS.contains(&name) {
+        return;
+    }
+    let is_block = BLOCK_TAGS.contains(&name);
+    if is_block {
+        flush_block(blocks, current);
+    }
+    for child in element.children() {
+        if let Some(child_element) = ElementRef::wrap(child) {
+            if child_element.value().name() == "br" {
+                current.push('\n');
+            } else {
+                collect_blocks(child_element, blocks, current);
+            }
+        } else if let Some(text) = child.value().as_text() {
+            current.push_str(text);
+            current.push(' ');
+        }
+    }
+    if is_block {
+        flush_block(blocks, current);
+    }
+}
+
+fn flush_block(blocks: &mut Vec<String>, current: &mut String) {
+    let block = current
+        .lines()
+        .map(normalize_whitespace)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n");
+    if !block.is_empty() {
+        blocks.push(block);
+    }
+    current.clear();
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -241,4 +469,39 @@ mod tests {
 
         assert_eq!(request.timeout(), Some(&Duration::from_secs(5)));
     }
+
+    #[test]
+    fn readable_extraction_drops_boilerplate() {
+        let body = "Lorem ipsum dolor sit amet, consectetur adipiscing elit. ".repeat(5);
+        let html = format!(
+            r#"<html><head><title>Page title</title><script>var x = 1;</script></head>
+            <body><nav><a href="/">Home</a><a href="/about">About</a></nav>
+            <article><h1>Heading</h1><p>{body}</p><p>Second paragraph.</p></article>
+            <footer>Copyright</footer></body></html>"#
+        );
+
+        let article = extract_readable_from_html(&html);
+
+        assert_eq!(article.title.as_deref(), Some("Page title"));
+        assert!(article.text.starts_with("Heading\n\nLorem ipsum"));
+        assert!(article.text.ends_with("Second paragraph."));
+        assert!(!article.text.contains("About"));
+        assert!(!article.text.contains("Copyright"));
+        assert!(!article.text.contains("var x"));
+    }
+
+    #[test]
+    fn readable_extraction_picks_densest_container() {
+        let body = "Sed ut perspiciatis unde omnis iste natus error sit voluptatem. ".repeat(5);
+        let html = format!(
+            r#"<html><body><div class="sidebar"><p>Short link list</p></div>
+            <div class="content"><p>{body}</p><p>{body}</p></div></body></html>"#
+        );
+
+        let article = extract_readable_from_html(&html);
+
+        assert!(article.title.is_none());
+        assert!(article.text.starts_with("Sed ut perspiciatis"));
+        assert!(!article.text.contains("Short link list"));
+    }
 }
@@ -0,0 +1,84 @@
+//! Modification times of files as the agent last saw them.
+//!
+//! `read` records a file's mtime; line-anchored `edit` calls refuse to run
+//! when the file has changed since, because the line numbers the model is
+//! using would no longer point at the text it read.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+#[derive(Debug, Default)]
+pub struct FileTimes {
+    seen: Mutex<HashMap<PathBuf, SystemTime>>,
+}
+
+impl FileTimes {
+    /// Remembers the current mtime of `path`; missing files are ignored.
+    pub fn record(&self, path: &Path) {
+        let Ok(modified) = std::fs::metadata(path).and_then(|meta| meta.modified()) else {
+            return;
+        };
+        self.seen
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .insert(path.to_path_buf(), modified);
+    }
+
+    /// Errors unless `path` was read and has not been modified since.
+    pub fn ensure_unchanged(&self, path: &Path) -> Result<(), String> {
+        let seen = self
+            .seen
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .get(path)
+            .copied();
+        let Some(seen) = seen else {
+            return Err(format!(
+                "{} has not been read in this session. Read it before editing by line numbers.",
+                path.display()
+            ));
+        };
+        let current = std::fs::metadata(path)
+            .and_then(|meta| meta.modified())
+            .map_err(|err| format!("Failed to read file metadata: {}", err))?;
+        if current != seen {
+            return Err(format!(
+                "{} has been modified since it was last read (mtime mismatch). Read it again before editing by line numbers.",
+                path.display()
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn detects_files_changed_after_reading() {
+        let workspace = tempfile::tempdir().unwrap();
+        let path = workspace.path().join("note.md");
+        std::fs::write(&path, "a\nb\n").unwrap();
+
+        let times = FileTimes::default();
+        assert!(times
+            .ensure_unchanged(&path)
+            .unwrap_err()
+            .contains("has not been read"));
+
+        times.record(&path);
+        assert_eq!(times.ensure_unchanged(&path), Ok(()));
+
+        let file = std::fs::File::options().write(true).open(&path).unwrap();
+        file.set_modified(SystemTime::now() + Duration::from_secs(5))
+            .unwrap();
+        assert!(times
+            .ensure_unchanged(&path)
+            .unwrap_err()
+            .contains("mtime mismatch"));
+    }
+}
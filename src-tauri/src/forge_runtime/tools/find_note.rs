@@ -0,0 +1,418 @@
+use crate::forge_runtime::permissions::request_permission;
+use crate::forge_runtime::tools::shared::{parse_tool_input, run_cancellable};
+use crate::forge_runtime::tools::ToolEnvironment;
+use crate::note_map::parser::{note_stem, ParsedNote};
+use crate::note_map::scan_workspace;
+use crate::vector_db::embedding::embed_query;
+use crate::vector_db::{ensure_index_model, search_vectors};
+use forge::runtime::error::{GraphError, GraphResult};
+use forge::runtime::tool::{ToolCall, ToolContext, ToolDefinition, ToolOutput, ToolRegistry};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+const DEFAULT_LIMIT: usize = 5;
+const MAX_LIMIT: usize = 20;
+/// Candidates below this are too far off to suggest.
+const MIN_CONFIDENCE: f32 = 0.4;
+/// Chunks fetched from the vector index per query.
+const SEMANTIC_CHUNKS: usize = 40;
+/// Cosine similarity treated as no semantic evidence / as a certain match.
+const SEMANTIC_FLOOR: f32 = 0.3;
+const SEMANTIC_CEILING: f32 = 0.8;
+/// Weights of the title and semantic signals relative to the file name.
+const TITLE_WEIGHT: f32 = 0.95;
+const SEMANTIC_WEIGHT: f32 = 0.85;
+/// Query words that say nothing about which note is meant.
+const STOPWORDS: &[&str] = &[
+    "a", "an", "the", "of", "from", "in", "on", "at", "for", "to", "my", "our", "about", "with",
+    "and", "that", "this",
+];
+
+#[derive(Deserialize)]
+struct FindNoteInput {
+    query: String,
+    limit: Option<usize>,
+}
+
+/// A note that may be the one the query refers to.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NoteCandidate {
+    /// Workspace-relative path
+    pub path: String,
+    pub title: String,
+    /// Combined score in 0..=1
+    pub confidence: f32,
+    pub name_score: f32,
+    pub title_score: f32,
+    pub semantic_score: f32,
+}
+
+pub fn register(registry: &mut ToolRegistry, env: ToolEnvironment) {
+    let description = include_str!("descriptions/find_note.txt").to_string();
+    let definition = ToolDefinition::new("find_note", description).with_input_schema(json!({
+        "type": "object",
+        "properties": {
+            "query": { "type": "string" },
+            "limit": { "type": "number" }
+        },
+        "required": ["query"]
+    }));
+
+    registry.register_with_definition(
+        definition,
+        Arc::new(move |call, ctx| {
+            let env = env.clone();
+            Box::pin(async move { handle(call, ctx, env).await })
+        }),
+    );
+}
+
+async fn handle(call: ToolCall, ctx: ToolContext, env: ToolEnvironment) -> GraphResult<ToolOutput> {
+    let input: FindNoteInput = parse_tool_input(&call)?;
+    let query = input.query.trim().to_string();
+    if query.is_empty() {
+        return Err(GraphError::ExecutionError {
+            node: format!("tool:{}", call.tool),
+            message: "query must not be empty".to_string(),
+        });
+    }
+
+    let mut metadata = Map::new();
+    metadata.insert("query".to_string(), json!(query));
+
+    request_permission(
+        &ctx,
+        &env.permissions,
+        "find_note",
+        "*",
+        metadata,
+        vec!["*".to_string()],
+    )?;
+
+    let workspace_root = env.workspace_root.clone();
+    let node = format!("tool:{}", call.tool);
+    // An aborted run stops waiting; the blocking scan finishes in the background.
+    let task = tokio::task::spawn_blocking(move || scan_workspace(&workspace_root));
+    let notes = run_cancellable(&env.cancel, task)
+        .await?
+        .map_err(|err| GraphError::ExecutionError {
+            node: node.clone(),
+            message: format!("note scan task failed: {}", err),
+        })?
+        .map_err(|err| GraphError::ExecutionError {
+            node,
+            message: err.to_string(),
+        })?;
+
+    let semantic = run_cancellable(&env.cancel, semantic_scores(&env, &query)).await?;
+    let limit = input.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    let candidates = rank_notes(&query, &notes, &semantic, limit);
+
+    let output = if candidates.is_empty() {
+        format!(
+            "No notes closely match \"{}\". Ask the user which note they mean.",
+            query
+        )
+    } else {
+        let mut lines = vec![format!(
+            "{} candidate note(s) for \"{}\", most likely first:",
+            candidates.len(),
+            query
+        )];
+        for (index, candidate) in candidates.iter().enumerate() {
+            lines.push(format!(
+                "{}. {} (title: {}) confidence {:.2} [name {:.2}, title {:.2}, semantic {:.2}]",
+                index + 1,
+                candidate.path,
+                candidate.title,
+                candidate.confidence,
+                candidate.name_score,
+                candidate.title_score,
+                candidate.semantic_score
+            ));
+        }
+        lines.join("\n")
+    };
+
+    Ok(ToolOutput::text(output)
+        .with_mime_type("text/plain")
+        .with_schema("tool.find_note.v1")
+        .with_attribute("candidates", json!(candidates))
+        .with_attribute("semantic", json!(!semantic.is_empty())))
+}
+
+/// Best chunk similarity per note (workspace-relative path). Empty when no
+/// embedding model is configured or the lookup fails; the name and title
+/// signals still work without it.
+async fn semantic_scores(env: &ToolEnvironment, query: &str) -> HashMap<String, f32> {
+    let Some(config) = env.embedding.as_ref().filter(|config| config.is_active()) else {
+        return HashMap::new();
+    };
+    let embedding = match embed_query(query, config).await {
+        Ok(embedding) => embedding,
+        Err(err) => {
+            eprintln!("[find_note] Query embedding failed: {}", err);
+            return HashMap::new();
+        }
+    };
+    // A fallback model's vectors mean nothing against this index
+    if let Err(err) = ensure_index_model(&embedding.model, embedding.dimensions) {
+        eprintln!("[find_note] Skipping semantic scores: {}", err);
+        return HashMap::new();
+    }
+    let Some(vector) = embedding.vectors.into_iter().next() else {
+        return HashMap::new();
+    };
+    let results = match search_vectors(vector, SEMANTIC_CHUNKS, SEMANTIC_FLOOR, None) {
+        Ok(results) => results,
+        Err(err) => {
+            eprintln!("[find_note] Vector lookup failed: {}", err);
+            return HashMap::new();
+        }
+    };
+
+    let mut scores = HashMap::new();
+    for result in results {
+        let path = relative_note_path(&env.workspace_root, &result.file_path);
+        let score =
+            ((result.score - SEMANTIC_FLOOR) / (SEMANTIC_CEILING - SEMANTIC_FLOOR)).clamp(0.0, 1.0);
+        let best = scores.entry(path).or_insert(0.0f32);
+        *best = best.max(score);
+    }
+    scores
+}
+
+/// Indexed chunks store absolute paths; notes are keyed by relative ones.
+fn relative_note_path(workspace_root: &Path, file_path: &str) -> String {
+    Path::new(file_path)
+        .strip_prefix(workspace_root)
+        .map(|relative| relative.to_string_lossy().to_string())
+        .unwrap_or_else(|_| file_path.to_string())
+        .replace('\\', "/")
+}
+
+/// Rank notes by file name, title and semantic similarity to `query`,
+/// best first. Notes below [`MIN_CONFIDENCE`] are left out, so an unknown
+/// note yields an empty list.
+pub fn rank_notes(
+    query: &str,
+    notes: &[ParsedNote],
+    semantic: &HashMap<String, f32>,
+    limit: usize,
+) -> Vec<NoteCandidate> {
+    let mut candidates: Vec<NoteCandidate> = notes
+        .iter()
+        .map(|note| {
+            let stem = note_stem(&note.path);
+            let name_score = text_similarity(query, stem);
+            // A title that only repeats the file name adds no evidence
+            let title_score = if note.title.trim().eq_ignore_ascii_case(stem.trim()) {
+                0.0
+            } else {
+                text_similarity(query, &note.title)
+            };
+            let semantic_score = semantic.get(&note.path).copied().unwrap_or(0.0);
+            // Independent signals: each one closes part of the remaining gap
+            let confidence = 1.0
+                - (1.0 - name_score)
+                    * (1.0 - title_score * TITLE_WEIGHT)
+                    * (1.0 - semantic_score * SEMANTIC_WEIGHT);
+            NoteCandidate {
+                path: note.path.clone(),
+                title: note.title.clone(),
+                confidence: round2(confidence),
+                name_score: round2(name_score),
+                title_score: round2(title_score),
+                semantic_score: round2(semantic_score),
+            }
+        })
+        .filter(|candidate| candidate.confidence >= MIN_CONFIDENCE)
+        .collect();
+    candidates.sort_by(|a, b| {
+        b.confidence
+            .total_cmp(&a.confidence)
+            .then_with(|| a.path.cmp(&b.path))
+    });
+    candidates.truncate(limit);
+    candidates
+}
+
+fn round2(value: f32) -> f32 {
+    (value * 100.0).round() / 100.0
+}
+
+fn tokens(text: &str) -> Vec<String> {
+    let words: Vec<String> = text
+        .to_lowercase()
+        .split(|ch: char| !ch.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_string)
+        .collect();
+    let meaningful: Vec<String> = words
+        .iter()
+        .filter(|word| !STOPWORDS.contains(&word.as_str()))
+        .cloned()
+        .collect();
+    if meaningful.is_empty() {
+        words
+    } else {
+        meaningful
+    }
+}
+
+/// How well `query` describes `target`, in 0..=1. Mostly how many query
+/// words appear in the target (allowing typos and partial words), with a
+/// smaller share for how much of the target the query covers.
+fn text_similarity(query: &str, target: &str) -> f32 {
+    let query_tokens = tokens(query);
+    let target_tokens = tokens(target);
+    if query_tokens.is_empty() || target_tokens.is_empty() {
+        return 0.0;
+    }
+    if query_tokens == target_tokens {
+        return 1.0;
+    }
+    let coverage = |from: &[String], to: &[String]| {
+        from.iter()
+            .map(|word| {
+                to.iter()
+                    .map(|other| word_similarity(word, other))
+                    .fold(0.0f32, f32::max)
+            })
+            .sum::<f32>()
+            / from.len() as f32
+    };
+    let recall = coverage(&query_tokens, &target_tokens);
+    let precision = coverage(&target_tokens, &query_tokens);
+    (0.8 * recall + 0.2 * precision).min(0.99)
+}
+
+fn word_similarity(a: &str, b: &str) -> f32 {
+    if a == b {
+        return 1.0;
+    }
+    let (short, long) = if a.chars().count() <= b.chars().count() {
+        (a, b)
+    } else {
+        (b, a)
+    };
+    let short_len = short.chars().count();
+    let long_len = long.chars().count();
+    // Partial words ("meet" / "meeting") and CJK titles without spaces
+    let min_part = if short.chars().any(|ch| ch.is_ascii()) {
+        3
+    } else {
+        2
+    };
+    if short_len >= min_part && long.contains(short) {
+        return 0.5 + 0.5 * short_len as f32 / long_len as f32;
+    }
+    if short_len < 4 {
+        return 0.0;
+    }
+    // Typos
+    let ratio = 1.0 - edit_distance(a, b) as f32 / long_len as f32;
+    if ratio >= 0.75 {
+        ratio * 0.9
+    } else {
+        0.0
+    }
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+    for (i, left) in a.chars().enumerate() {
+        current[0] = i + 1;
+        for (j, right) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(left != *right);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::note_map::parser::parse_note;
+
+    fn notes() -> Vec<ParsedNote> {
+        [
+            (
+                "Meetings/2024-06-11 Team Meeting.md",
+                "# Weekly sync\n\nAgenda",
+            ),
+            (
+                "Projects/roadmap.md",
+                "---\ntitle: Product Roadmap 2025\n---\nGoals",
+            ),
+            ("Journal/Tuesday.md", "Went for a run"),
+            ("Ideas/garden.md", "# 花园设计\n\n种植计划"),
+            ("Ideas/random.md", "misc"),
+        ]
+        .iter()
+        .map(|(path, content)| parse_note(path, content))
+        .collect()
+    }
+
+    fn paths(candidates: &[NoteCandidate]) -> Vec<&str> {
+        candidates
+            .iter()
+            .map(|candidate| candidate.path.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn ranks_by_file_name_and_title() {
+        let notes = notes();
+        let none = HashMap::new();
+
+        let found = rank_notes("the team meetng notes", &notes, &none, 5);
+        assert_eq!(found[0].path, "Meetings/2024-06-11 Team Meeting.md");
+        assert!(found[0].name_score > 0.5);
+
+        let found = rank_notes("product roadmap", &notes, &none, 5);
+        assert_eq!(paths(&found), ["Projects/roadmap.md"]);
+        assert!(found[0].title_score > found[0].name_score);
+
+        let found = rank_notes("weekly sync", &notes, &none, 5);
+        assert_eq!(found[0].path, "Meetings/2024-06-11 Team Meeting.md");
+
+        let found = rank_notes("花园", &notes, &none, 5);
+        assert_eq!(paths(&found), ["Ideas/garden.md"]);
+    }
+
+    #[test]
+    fn returns_nothing_when_no_note_is_close() {
+        assert!(rank_notes("quantum physics lecture", &notes(), &HashMap::new(), 5).is_empty());
+    }
+
+    #[test]
+    fn semantic_matches_add_candidates_and_confidence() {
+        let notes = notes();
+        let semantic: HashMap<String, f32> = [
+            ("Ideas/random.md".to_string(), 0.9),
+            ("Projects/roadmap.md".to_string(), 0.5),
+        ]
+        .into_iter()
+        .collect();
+
+        let found = rank_notes("where we plan next year", &notes, &semantic, 5);
+        assert_eq!(paths(&found), ["Ideas/random.md", "Projects/roadmap.md"]);
+
+        let lexical = rank_notes("product roadmap", &notes, &HashMap::new(), 5);
+        let combined = rank_notes("product roadmap", &notes, &semantic, 5);
+        assert!(combined[0].confidence > lexical[0].confidence);
+
+        assert_eq!(
+            relative_note_path(Path::new("/vault"), "/vault/Ideas/random.md"),
+            "Ideas/random.md"
+        );
+    }
+}
@@ -0,0 +1,243 @@
+//! Approximate matching of `oldString` for the edit tool.
+//!
+//! Used only when the caller opts in and the exact match failed. Two passes,
+//! in order:
+//! 1. whitespace-insensitive: the same words separated by any whitespace, so
+//!    re-indented or re-wrapped quotes still match;
+//! 2. edit distance: whole-line windows of the same height whose trimmed text
+//!    is within a few character edits of `oldString` (a stray or dropped
+//!    character).
+//!
+//! A pass only succeeds when exactly one region qualifies; several candidates
+//! are reported as ambiguous rather than guessed between.
+
+use std::ops::Range;
+
+/// Shorter needles match too much by accident to be corrected.
+const MIN_FUZZY_CHARS: usize = 8;
+/// Needles longer than this skip the edit-distance pass to bound its cost.
+const MAX_DISTANCE_CHARS: usize = 4000;
+/// Allowed edits per character of `oldString` (5%), at least one.
+const MAX_EDIT_RATIO: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FuzzyKind {
+    Whitespace,
+    EditDistance { distance: usize },
+}
+
+impl FuzzyKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            FuzzyKind::Whitespace => "whitespace",
+            FuzzyKind::EditDistance { .. } => "edit_distance",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FuzzyMatch {
+    /// Byte range in the content to replace.
+    Found {
+        range: Range<usize>,
+        kind: FuzzyKind,
+    },
+    /// Several regions qualified in the pass that matched first.
+    Ambiguous {
+        candidates: usize,
+    },
+    NotFound,
+}
+
+pub fn find_fuzzy(content: &str, needle: &str) -> FuzzyMatch {
+    if needle.trim().chars().count() < MIN_FUZZY_CHARS {
+        return FuzzyMatch::NotFound;
+    }
+    match whitespace_match(content, needle) {
+        FuzzyMatch::NotFound => distance_match(content, needle),
+        other => other,
+    }
+}
+
+fn whitespace_match(content: &str, needle: &str) -> FuzzyMatch {
+    let mut ranges = whitespace_ranges(content, needle);
+    match ranges.len() {
+        0 => FuzzyMatch::NotFound,
+        1 => FuzzyMatch::Found {
+            range: ranges.remove(0),
+            kind: FuzzyKind::Whitespace,
+        },
+        candidates => FuzzyMatch::Ambiguous { candidates },
+    }
+}
+
+/// Byte ranges of whitespace-separated words in `text`.
+fn words(text: &str) -> Vec<Range<usize>> {
+    let mut words = Vec::new();
+    let mut start = None;
+    for (index, ch) in text.char_indices() {
+        match (ch.is_whitespace(), start) {
+            (true, Some(begin)) => {
+                words.push(begin..index);
+                start = None;
+            }
+            (false, None) => start = Some(index),
+            _ => {}
+        }
+    }
+    if let Some(begin) = start {
+        words.push(begin..text.len());
+    }
+    words
+}
+
+fn whitespace_ranges(content: &str, needle: &str) -> Vec<Range<usize>> {
+    let needle_words: Vec<&str> = needle.split_whitespace().collect();
+    let content_words = words(content);
+    if needle_words.is_empty() || content_words.len() < needle_words.len() {
+        return Vec::new();
+    }
+    content_words
+        .windows(needle_words.len())
+        .filter(|window| {
+            window
+                .iter()
+                .zip(&needle_words)
+                .all(|(range, word)| &content[range.clone()] == *word)
+        })
+        .map(|window| window[0].start..window[window.len() - 1].end)
+        .collect()
+}
+
+fn distance_match(content: &str, needle: &str) -> FuzzyMatch {
+    let needle = needle.trim();
+    let needle_chars: Vec<char> = needle.chars().collect();
+    if needle_chars.len() > MAX_DISTANCE_CHARS {
+        return FuzzyMatch::NotFound;
+    }
+    let max_edits = (needle_chars.len() / MAX_EDIT_RATIO).max(1);
+    let height = needle.lines().count().max(1);
+
+    // Byte range of each line, without its line break
+    let mut lines = Vec::new();
+    let mut offset = 0;
+    for line in content.split_inclusive('\n') {
+        let text = line.trim_end_matches(['\n', '\r']);
+        lines.push(offset..offset + text.len());
+        offset += line.len();
+    }
+    if lines.len() < height {
+        return FuzzyMatch::NotFound;
+    }
+
+    let mut candidates: Vec<(Range<usize>, usize)> = Vec::new();
+    for window in lines.windows(height) {
+        let span = window[0].start..window[height - 1].end;
+        let text = &content[span.clone()];
+        let trimmed = text.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        let Some(distance) = bounded_distance(&needle_chars, trimmed, max_edits) else {
+            continue;
+        };
+        let start = span.start + (text.len() - text.trim_start().len());
+        candidates.push((start..start + trimmed.len(), distance));
+    }
+    match candidates.len() {
+        0 => FuzzyMatch::NotFound,
+        1 => {
+            let (range, distance) = candidates.remove(0);
+            FuzzyMatch::Found {
+                range,
+                kind: FuzzyKind::EditDistance { distance },
+            }
+        }
+        candidates => FuzzyMatch::Ambiguous { candidates },
+    }
+}
+
+/// Levenshtein distance between `a` and `b` if it is at most `max`.
+/// Gives up as soon as every cell of a row exceeds `max`.
+fn bounded_distance(a: &[char], b: &str, max: usize) -> Option<usize> {
+    let b: Vec<char> = b.chars().collect();
+    if a.len().abs_diff(b.len()) > max {
+        return None;
+    }
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+    for (i, left) in a.iter().enumerate() {
+        current[0] = i + 1;
+        let mut row_min = current[0];
+        for (j, right) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(left != right);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+            row_min = row_min.min(current[j + 1]);
+        }
+        if row_min > max {
+            return None;
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    Some(previous[b.len()]).filter(|distance| *distance <= max)
+}
+
+/// 1-based line numbers spanned by `range`, for reporting.
+pub fn line_span(content: &str, range: &Range<usize>) -> (usize, usize) {
+    let first = content[..range.start].matches('\n').count() + 1;
+    let last = first + content[range.clone()].matches('\n').count();
+    (first, last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOTE: &str = "# Plan\n\n- [ ] write the outline\n    - gather   sources\n      for chapter two\n- [ ] review drafts\n";
+
+    #[test]
+    fn matches_despite_whitespace_differences() {
+        let result = find_fuzzy(NOTE, "- gather sources for chapter two");
+        let FuzzyMatch::Found { range, kind } = result else {
+            panic!("expected a match, got {:?}", result);
+        };
+        assert_eq!(kind, FuzzyKind::Whitespace);
+        assert_eq!(
+            &NOTE[range.clone()],
+            "- gather   sources\n      for chapter two"
+        );
+        assert_eq!(line_span(NOTE, &range), (4, 5));
+    }
+
+    #[test]
+    fn matches_a_stray_character_on_whole_lines() {
+        let result = find_fuzzy(NOTE, "- [ ] reviews drafts");
+        let FuzzyMatch::Found { range, kind } = result else {
+            panic!("expected a match, got {:?}", result);
+        };
+        assert_eq!(kind, FuzzyKind::EditDistance { distance: 1 });
+        assert_eq!(&NOTE[range], "- [ ] review drafts");
+
+        assert_eq!(
+            find_fuzzy(NOTE, "- [ ] publish the book"),
+            FuzzyMatch::NotFound
+        );
+        assert_eq!(find_fuzzy(NOTE, "outlin"), FuzzyMatch::NotFound);
+    }
+
+    #[test]
+    fn refuses_ambiguous_matches() {
+        let content = "status:  done\nstatus: done\n";
+        assert_eq!(
+            find_fuzzy(content, "status:\tdone"),
+            FuzzyMatch::Ambiguous { candidates: 2 }
+        );
+
+        let content = "- [ ] follow up with the editor about chapter 1\n\
+                       - [ ] follow up with the editor about chapter 2\n";
+        assert_eq!(
+            find_fuzzy(content, "- [ ] follow up with the editr about chapter 3"),
+            FuzzyMatch::Ambiguous { candidates: 2 }
+        );
+    }
+}
@@ -0,0 +1,120 @@
+//! Minimal `.gitignore` support for the search tools.
+//!
+//! Rules are loaded per directory while walking, so each file's patterns only
+//! apply below the directory that holds it. Supported syntax: comments, `!`
+//! negation, trailing `/` for directories only, patterns anchored by a `/`,
+//! and the usual `*`, `?`, `[...]` and `**` wildcards. Later rules win.
+
+use globset::{GlobBuilder, GlobMatcher};
+use std::path::{Path, PathBuf};
+
+struct Rule {
+    base: PathBuf,
+    matcher: GlobMatcher,
+    negated: bool,
+    dir_only: bool,
+}
+
+#[derive(Default)]
+pub struct GitIgnore {
+    rules: Vec<Rule>,
+}
+
+impl GitIgnore {
+    /// Loads `dir/.gitignore` if there is one.
+    pub fn add_dir(&mut self, dir: &Path) {
+        if let Ok(content) = std::fs::read_to_string(dir.join(".gitignore")) {
+            self.add_rules(dir, &content);
+        }
+    }
+
+    /// Loads the `.gitignore` files of `root` and every directory between it
+    /// and `dir`, for searches that start below the workspace root.
+    pub fn add_ancestors(&mut self, root: &Path, dir: &Path) {
+        let Ok(relative) = dir.strip_prefix(root) else {
+            return;
+        };
+        let mut current = root.to_path_buf();
+        self.add_dir(&current);
+        for component in relative.components() {
+            current.push(component);
+            if current != dir {
+                self.add_dir(&current);
+            }
+        }
+    }
+
+    pub fn add_rules(&mut self, base: &Path, content: &str) {
+        for line in content.lines() {
+            let line = line.trim_end();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (negated, pattern) = match line.strip_prefix('!') {
+                Some(rest) => (true, rest),
+                None => (false, line.strip_prefix('\\').unwrap_or(line)),
+            };
+            let (dir_only, pattern) = match pattern.strip_suffix('/') {
+                Some(rest) => (true, rest),
+                None => (false, pattern),
+            };
+            // A slash anywhere but the end anchors the pattern to `base`
+            let glob = match pattern.strip_prefix('/') {
+                Some(anchored) => anchored.to_string(),
+                None if pattern.contains('/') => pattern.to_string(),
+                None => format!("**/{}", pattern),
+            };
+            let Ok(glob) = GlobBuilder::new(&glob).literal_separator(true).build() else {
+                continue;
+            };
+            self.rules.push(Rule {
+                base: base.to_path_buf(),
+                matcher: glob.compile_matcher(),
+                negated,
+                dir_only,
+            });
+        }
+    }
+
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        self.rules
+            .iter()
+            .rev()
+            .filter(|rule| is_dir || !rule.dir_only)
+            .find(|rule| {
+                path.strip_prefix(&rule.base)
+                    .map(|relative| rule.matcher.is_match(relative))
+                    .unwrap_or(false)
+            })
+            .map(|rule| !rule.negated)
+            .unwrap_or(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn applies_rules_relative_to_their_directory() {
+        let root = Path::new("/vault");
+        let mut ignore = GitIgnore::default();
+        ignore.add_rules(
+            root,
+            "# build output\n*.log\n!keep.log\nbuild/\n/drafts\nassets/*.png\n",
+        );
+        ignore.add_rules(&root.join("Projects"), "private.md\n");
+
+        assert!(ignore.is_ignored(&root.join("debug.log"), false));
+        assert!(ignore.is_ignored(&root.join("a/b/trace.log"), false));
+        assert!(!ignore.is_ignored(&root.join("a/keep.log"), false));
+        assert!(ignore.is_ignored(&root.join("site/build"), true));
+        assert!(!ignore.is_ignored(&root.join("site/build"), false));
+        assert!(ignore.is_ignored(&root.join("drafts"), true));
+        assert!(!ignore.is_ignored(&root.join("notes/drafts"), true));
+        assert!(ignore.is_ignored(&root.join("assets/cover.png"), false));
+        assert!(!ignore.is_ignored(&root.join("assets/img/cover.png"), false));
+        assert!(ignore.is_ignored(&root.join("Projects/x/private.md"), false));
+        assert!(!ignore.is_ignored(&root.join("private.md"), false));
+    }
+}
@@ -1,32 +1,58 @@
 use crate::forge_runtime::permissions::request_permission;
+use crate::forge_runtime::tools::gitignore::GitIgnore;
 use crate::forge_runtime::tools::shared::{
-    ensure_external_directory_permission, parse_tool_input, resolve_path,
+    ensure_external_directory_permission, ensure_not_cancelled, parse_tool_input, resolve_path,
 };
 use crate::forge_runtime::tools::ToolEnvironment;
+use forge::runtime::cancel::CancellationToken;
 use forge::runtime::error::{GraphError, GraphResult};
 use forge::runtime::tool::{ToolCall, ToolContext, ToolDefinition, ToolOutput, ToolRegistry};
-use globset::{Glob, GlobSetBuilder};
+use globset::{Glob, GlobSet, GlobSetBuilder};
 use regex::Regex;
 use serde::Deserialize;
 use serde_json::{json, Map};
+use std::path::Path;
 use std::sync::Arc;
 
 const MAX_LINE_LENGTH: usize = 2000;
+/// Matching lines shown across all files; further matches are only counted.
 const LIMIT: usize = 100;
+/// Directories skipped unless `skipDirs` says otherwise.
+const DEFAULT_SKIP_DIRS: &[&str] = &[".git", ".obsidian", ".lumina"];
 
 #[derive(Deserialize)]
 struct GrepInput {
     pattern: String,
     path: Option<String>,
     include: Option<String>,
+    exclude: Option<String>,
+    #[serde(rename = "skipDirs")]
+    skip_dirs: Option<Vec<String>>,
+    #[serde(rename = "respectGitignore")]
+    respect_gitignore: Option<bool>,
 }
 
-#[derive(Clone)]
-struct MatchLine {
+struct SearchOptions {
+    include: Option<GlobSet>,
+    exclude: Option<GlobSet>,
+    skip_dirs: Vec<String>,
+    respect_gitignore: bool,
+}
+
+/// Matches found in one file.
+struct FileMatches {
     path: String,
     mod_time: u64,
-    line_num: usize,
-    line_text: String,
+    /// Every matching line in the file, including those not shown.
+    count: usize,
+    /// Shown lines as (line number, text).
+    lines: Vec<(usize, String)>,
+}
+
+struct SearchResult {
+    files: Vec<FileMatches>,
+    /// Whether the walk stopped early after reaching [`LIMIT`] shown lines.
+    truncated: bool,
 }
 
 pub fn register(registry: &mut ToolRegistry, env: ToolEnvironment) {
@@ -36,7 +62,10 @@ pub fn register(registry: &mut ToolRegistry, env: ToolEnvironment) {
         "properties": {
             "pattern": { "type": "string" },
             "path": { "type": "string" },
-            "include": { "type": "string" }
+            "include": { "type": "string" },
+            "exclude": { "type": "string" },
+            "skipDirs": { "type": "array", "items": { "type": "string" } },
+            "respectGitignore": { "type": "boolean" }
         },
         "required": ["pattern"]
     }));
@@ -70,6 +99,7 @@ async fn handle(call: ToolCall, ctx: ToolContext, env: ToolEnvironment) -> Graph
     metadata.insert("pattern".to_string(), json!(input.pattern));
     metadata.insert("path".to_string(), json!(search_root.display().to_string()));
     metadata.insert("include".to_string(), json!(input.include));
+    metadata.insert("exclude".to_string(), json!(input.exclude));
 
     let pattern = metadata
         .get("pattern")
@@ -91,43 +121,123 @@ async fn handle(call: ToolCall, ctx: ToolContext, env: ToolEnvironment) -> Graph
         message: format!("Invalid regex pattern: {}", err),
     })?;
 
-    let include_matcher = if let Some(pattern) = input.include {
-        let mut builder = GlobSetBuilder::new();
-        builder.add(
-            Glob::new(&pattern).map_err(|err| GraphError::ExecutionError {
-                node: format!("tool:{}", call.tool),
-                message: format!("Invalid include pattern: {}", err),
-            })?,
-        );
-        Some(builder.build().map_err(|err| GraphError::ExecutionError {
+    let glob_set = |pattern: Option<&String>, label: &str| -> GraphResult<Option<GlobSet>> {
+        let Some(pattern) = pattern else {
+            return Ok(None);
+        };
+        let invalid = |err: globset::Error| GraphError::ExecutionError {
             node: format!("tool:{}", call.tool),
-            message: format!("Invalid include pattern: {}", err),
-        })?)
-    } else {
-        None
+            message: format!("Invalid {} pattern: {}", label, err),
+        };
+        let mut builder = GlobSetBuilder::new();
+        builder.add(Glob::new(pattern).map_err(invalid)?);
+        builder.build().map(Some).map_err(invalid)
+    };
+    let options = SearchOptions {
+        include: glob_set(input.include.as_ref(), "include")?,
+        exclude: glob_set(input.exclude.as_ref(), "exclude")?,
+        skip_dirs: input.skip_dirs.unwrap_or_else(|| {
+            DEFAULT_SKIP_DIRS
+                .iter()
+                .map(|dir| dir.to_string())
+                .collect()
+        }),
+        respect_gitignore: input.respect_gitignore.unwrap_or(true),
     };
 
-    let mut matches = Vec::new();
-    let mut truncated = false;
+    let result = search(
+        &env.workspace_root,
+        &search_root,
+        &regex,
+        &options,
+        &env.cancel,
+    )
+    .await?;
+    let total: usize = result.files.iter().map(|file| file.count).sum();
+    let shown: usize = result.files.iter().map(|file| file.lines.len()).sum();
+
+    if result.files.is_empty() {
+        return Ok(ToolOutput::text("No files found")
+            .with_mime_type("text/plain")
+            .with_schema("tool.grep.v1")
+            .with_attribute("matches", json!(0))
+            .with_attribute("truncated", json!(false)));
+    }
 
-    'walker: for entry in walkdir::WalkDir::new(&search_root)
+    Ok(ToolOutput::text(render(&result, total))
+        .with_mime_type("text/plain")
+        .with_schema("tool.grep.v1")
+        .with_attribute("matches", json!(total))
+        .with_attribute("shown", json!(shown))
+        .with_attribute("files", json!(result.files.len()))
+        .with_attribute("truncated", json!(result.truncated)))
+}
+
+/// Walks `search_root` and collects matching lines per file. Once [`LIMIT`]
+/// lines are shown the current file is still counted to the end, then the
+/// walk stops.
+async fn search(
+    workspace_root: &Path,
+    search_root: &Path,
+    regex: &Regex,
+    options: &SearchOptions,
+    cancel: &CancellationToken,
+) -> GraphResult<SearchResult> {
+    let mut ignore = GitIgnore::default();
+    if options.respect_gitignore {
+        ignore.add_ancestors(workspace_root, search_root);
+    }
+
+    let mut files = Vec::new();
+    let mut shown = 0;
+    let mut truncated = false;
+    let mut walker = walkdir::WalkDir::new(search_root)
         .follow_links(true)
-        .into_iter()
-        .filter_map(Result::ok)
-    {
+        .sort_by_file_name()
+        .into_iter();
+    while let Some(entry) = walker.next() {
+        ensure_not_cancelled(cancel)?;
+        let Ok(entry) = entry else {
+            continue;
+        };
+        let is_dir = entry.file_type().is_dir();
+        if entry.depth() > 0 {
+            let skipped = is_dir
+                && options
+                    .skip_dirs
+                    .iter()
+                    .any(|dir| entry.file_name().to_string_lossy() == dir.as_str());
+            if skipped || (options.respect_gitignore && ignore.is_ignored(entry.path(), is_dir)) {
+                if is_dir {
+                    walker.skip_current_dir();
+                }
+                continue;
+            }
+        }
+        if is_dir {
+            if options.respect_gitignore {
+                ignore.add_dir(entry.path());
+            }
+            continue;
+        }
         if !entry.file_type().is_file() {
             continue;
         }
 
-        if let Some(ref matcher) = include_matcher {
-            let rel = entry
-                .path()
-                .strip_prefix(&search_root)
-                .unwrap_or(entry.path());
+        let rel = entry
+            .path()
+            .strip_prefix(search_root)
+            .unwrap_or(entry.path());
+        if let Some(ref matcher) = options.include {
             if !matcher.is_match(rel) {
                 continue;
             }
         }
+        if let Some(ref matcher) = options.exclude {
+            if matcher.is_match(rel) {
+                continue;
+            }
+        }
 
         let content = match tokio::fs::read_to_string(entry.path()).await {
             Ok(content) => content,
@@ -145,69 +255,197 @@ async fn handle(call: ToolCall, ctx: ToolContext, env: ToolEnvironment) -> Graph
             .map(|duration| duration.as_secs())
             .unwrap_or(0);
 
+        let mut file = FileMatches {
+            path: entry.path().display().to_string(),
+            mod_time,
+            count: 0,
+            lines: Vec::new(),
+        };
         for (idx, line) in content.lines().enumerate() {
             if !regex.is_match(line) {
                 continue;
             }
+            file.count += 1;
+            if shown >= LIMIT {
+                truncated = true;
+                continue;
+            }
 
             let mut line_text = line.to_string();
             if line_text.len() > MAX_LINE_LENGTH {
-                line_text.truncate(MAX_LINE_LENGTH);
+                let mut cut = MAX_LINE_LENGTH;
+                while !line_text.is_char_boundary(cut) {
+                    cut -= 1;
+                }
+                line_text.truncate(cut);
                 line_text.push_str("...");
             }
-            matches.push(MatchLine {
-                path: entry.path().display().to_string(),
-                mod_time,
-                line_num: idx + 1,
-                line_text,
-            });
-            if matches.len() >= LIMIT {
-                truncated = true;
-                break 'walker;
-            }
+            file.lines.push((idx + 1, line_text));
+            shown += 1;
+        }
+        if file.count > 0 {
+            files.push(file);
+        }
+        if shown >= LIMIT {
+            // Whether more files would have matched is unknown; say so
+            truncated = true;
+            break;
         }
     }
 
-    if matches.is_empty() {
-        return Ok(ToolOutput::text("No files found")
-            .with_mime_type("text/plain")
-            .with_schema("tool.grep.v1")
-            .with_attribute("matches", json!(0))
-            .with_attribute("truncated", json!(false)));
-    }
-
-    matches.sort_by(|a, b| {
+    files.sort_by(|a, b| {
         b.mod_time
             .cmp(&a.mod_time)
             .then_with(|| a.path.cmp(&b.path))
-            .then_with(|| a.line_num.cmp(&b.line_num))
     });
+    Ok(SearchResult { files, truncated })
+}
 
+fn render(result: &SearchResult, total: usize) -> String {
     let mut output = Vec::new();
-    output.push(format!("Found {} matches", matches.len()));
+    output.push(format!(
+        "Found {} matches in {} files",
+        total,
+        result.files.len()
+    ));
 
-    let mut current_path = String::new();
-    for item in &matches {
-        if item.path != current_path {
-            if !current_path.is_empty() {
-                output.push(String::new());
-            }
-            current_path = item.path.clone();
-            output.push(format!("{}:", current_path));
+    for file in &result.files {
+        output.push(String::new());
+        output.push(format!("{} ({} matches):", file.path, file.count));
+        for (line_num, line_text) in &file.lines {
+            output.push(format!("  Line {}: {}", line_num, line_text));
+        }
+        let hidden = file.count - file.lines.len();
+        if hidden > 0 {
+            output.push(format!("  ({} more matches not shown)", hidden));
         }
-        output.push(format!("  Line {}: {}", item.line_num, item.line_text));
     }
 
-    if truncated {
+    if result.truncated {
         output.push(String::new());
         output.push(
             "(Results are truncated. Consider using a more specific path or pattern.)".to_string(),
         );
     }
+    output.join("\n")
+}
 
-    Ok(ToolOutput::text(output.join("\n"))
-        .with_mime_type("text/plain")
-        .with_schema("tool.grep.v1")
-        .with_attribute("matches", json!(matches.len()))
-        .with_attribute("truncated", json!(truncated)))
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options() -> SearchOptions {
+        SearchOptions {
+            include: None,
+            exclude: None,
+            skip_dirs: DEFAULT_SKIP_DIRS
+                .iter()
+                .map(|dir| dir.to_string())
+                .collect(),
+            respect_gitignore: true,
+        }
+    }
+
+    fn glob(pattern: &str) -> Option<GlobSet> {
+        let mut builder = GlobSetBuilder::new();
+        builder.add(Glob::new(pattern).unwrap());
+        Some(builder.build().unwrap())
+    }
+
+    fn paths(result: &SearchResult, root: &Path) -> Vec<String> {
+        let mut paths: Vec<String> = result
+            .files
+            .iter()
+            .map(|file| {
+                Path::new(&file.path)
+                    .strip_prefix(root)
+                    .unwrap()
+                    .to_string_lossy()
+                    .replace('\\', "/")
+            })
+            .collect();
+        paths.sort();
+        paths
+    }
+
+    #[tokio::test]
+    async fn honours_skip_dirs_gitignore_and_globs() {
+        let workspace = tempfile::tempdir().unwrap();
+        let root = workspace.path();
+        for (path, content) in [
+            ("Projects/a/plan.md", "todo one\ntodo two\n"),
+            ("Projects/a/data.csv", "todo"),
+            ("Projects/b/tmp.md", "todo"),
+            ("Inbox.md", "todo"),
+            (".obsidian/workspace.json", "todo"),
+            ("dist/out.md", "todo"),
+            (".gitignore", "dist/\n"),
+            ("Projects/.gitignore", "tmp.md\n"),
+        ] {
+            let path = root.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        }
+        let regex = Regex::new("todo").unwrap();
+        let cancel = CancellationToken::new();
+
+        let result = search(root, root, &regex, &options(), &cancel)
+            .await
+            .unwrap();
+        assert_eq!(
+            paths(&result, root),
+            ["Inbox.md", "Projects/a/data.csv", "Projects/a/plan.md"]
+        );
+
+        let scoped = SearchOptions {
+            include: glob("Projects/**/*.md"),
+            respect_gitignore: false,
+            skip_dirs: Vec::new(),
+            ..options()
+        };
+        let result = search(root, root, &regex, &scoped, &cancel).await.unwrap();
+        assert_eq!(
+            paths(&result, root),
+            ["Projects/a/plan.md", "Projects/b/tmp.md"]
+        );
+
+        let excluded = SearchOptions {
+            exclude: glob("**/*.csv"),
+            ..options()
+        };
+        let result = search(root, &root.join("Projects"), &regex, &excluded, &cancel)
+            .await
+            .unwrap();
+        assert_eq!(paths(&result, root), ["Projects/a/plan.md"]);
+    }
+
+    #[tokio::test]
+    async fn counts_matches_beyond_the_shown_limit() {
+        let workspace = tempfile::tempdir().unwrap();
+        let lines: String = (0..LIMIT + 30).map(|n| format!("hit {}\n", n)).collect();
+        std::fs::write(workspace.path().join("big.md"), lines).unwrap();
+        std::fs::write(workspace.path().join("small.md"), "hit").unwrap();
+
+        let regex = Regex::new("hit").unwrap();
+        let result = search(
+            workspace.path(),
+            workspace.path(),
+            &regex,
+            &options(),
+            &CancellationToken::new(),
+        )
+        .await
+        .unwrap();
+
+        assert!(result.truncated);
+        let big = result
+            .files
+            .iter()
+            .find(|file| file.path.ends_with("big.md"))
+            .unwrap();
+        assert_eq!((big.count, big.lines.len()), (LIMIT + 30, LIMIT));
+        let output = render(&result, big.count);
+        assert!(output.contains("big.md (130 matches):"));
+        assert!(output.contains("  (30 more matches not shown)"));
+    }
 }
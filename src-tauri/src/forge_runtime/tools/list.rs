@@ -1,6 +1,6 @@
 use crate::forge_runtime::permissions::request_permission;
 use crate::forge_runtime::tools::shared::{
-    ensure_external_directory_permission, parse_tool_input, resolve_path,
+    ensure_external_directory_permission, ensure_not_cancelled, parse_tool_input, resolve_path,
 };
 use crate::forge_runtime::tools::ToolEnvironment;
 use forge::runtime::error::{GraphError, GraphResult};
@@ -110,6 +110,7 @@ async fn handle(call: ToolCall, ctx: ToolContext, env: ToolEnvironment) -> Graph
         .into_iter()
         .filter_map(Result::ok)
     {
+        ensure_not_cancelled(&env.cancel)?;
         if !entry.file_type().is_file() {
             continue;
         }
@@ -1,14 +1,27 @@
+pub mod ask_user;
 pub mod bash;
+pub mod create_from_template;
 pub mod edit;
 pub mod fetch;
+pub(crate) mod file_times;
+pub mod find_note;
+pub(crate) mod fuzzy_match;
+pub(crate) mod gitignore;
 pub mod glob;
 pub mod grep;
 pub mod list;
+pub mod note_map;
+pub mod query_database;
 pub mod read;
-mod shared;
+pub mod read_many;
+pub(crate) mod shared;
 pub mod write;
 
+use crate::forge_runtime::dry_run::DryRunLog;
 use crate::forge_runtime::permissions::PermissionSession;
+use crate::vector_db::embedding::EmbeddingConfig;
+use file_times::FileTimes;
+use forge::runtime::cancel::CancellationToken;
 use forge::runtime::tool::ToolRegistry;
 use std::path::PathBuf;
 use std::sync::Arc;
@@ -18,6 +31,15 @@ pub struct ToolEnvironment {
     pub workspace_root: PathBuf,
     pub permissions: Arc<PermissionSession>,
     pub http_client: Option<reqwest::Client>,
+    /// Cancelled by `agent_abort`; long-running tools stop when it fires.
+    pub cancel: CancellationToken,
+    /// Set in dry-run mode: write-type tools record the change here instead
+    /// of applying it.
+    pub dry_run: Option<Arc<DryRunLog>>,
+    /// Files read in this run, checked by line-anchored edits.
+    pub file_times: Arc<FileTimes>,
+    /// Query embedding for semantic lookups; `None` disables them.
+    pub embedding: Option<EmbeddingConfig>,
 }
 
 impl ToolEnvironment {
@@ -26,6 +48,10 @@ impl ToolEnvironment {
             workspace_root: workspace_root.into(),
             permissions,
             http_client: None,
+            cancel: CancellationToken::new(),
+            dry_run: None,
+            file_times: Arc::new(FileTimes::default()),
+            embedding: None,
         }
     }
 
@@ -33,18 +59,39 @@ impl ToolEnvironment {
         self.http_client = Some(client);
         self
     }
+
+    pub fn with_cancel(mut self, cancel: CancellationToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    pub fn with_embedding(mut self, config: EmbeddingConfig) -> Self {
+        self.embedding = Some(config);
+        self
+    }
+
+    pub fn with_dry_run(mut self, log: Arc<DryRunLog>) -> Self {
+        self.dry_run = Some(log);
+        self
+    }
 }
 
 pub fn build_registry(env: ToolEnvironment) -> ToolRegistry {
     let mut registry = ToolRegistry::new();
 
     read::register(&mut registry, env.clone());
+    read_many::register(&mut registry, env.clone());
     write::register(&mut registry, env.clone());
+    create_from_template::register(&mut registry, env.clone());
     edit::register(&mut registry, env.clone());
     fetch::register(&mut registry, env.clone());
     glob::register(&mut registry, env.clone());
     grep::register(&mut registry, env.clone());
     list::register(&mut registry, env.clone());
+    note_map::register(&mut registry, env.clone());
+    find_note::register(&mut registry, env.clone());
+    query_database::register(&mut registry, env.clone());
+    ask_user::register(&mut registry, env.clone());
     bash::register(&mut registry, env);
 
     registry
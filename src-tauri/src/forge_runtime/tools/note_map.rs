@@ -0,0 +1,79 @@
+use crate::forge_runtime::permissions::request_permission;
+use crate::forge_runtime::tools::shared::{parse_tool_input, run_cancellable};
+use crate::forge_runtime::tools::ToolEnvironment;
+use crate::note_map::{generate_note_map, NoteMapConfig};
+use forge::runtime::error::{GraphError, GraphResult};
+use forge::runtime::tool::{ToolCall, ToolContext, ToolDefinition, ToolOutput, ToolRegistry};
+use serde::Deserialize;
+use serde_json::{json, Map};
+use std::sync::Arc;
+
+#[derive(Deserialize)]
+struct NoteMapInput {
+    #[serde(default)]
+    seeds: Vec<String>,
+    #[serde(rename = "maxNotes")]
+    max_notes: Option<usize>,
+}
+
+pub fn register(registry: &mut ToolRegistry, env: ToolEnvironment) {
+    let description = include_str!("descriptions/note_map.txt").to_string();
+    let definition = ToolDefinition::new("note_map", description).with_input_schema(json!({
+        "type": "object",
+        "properties": {
+            "seeds": { "type": "array", "items": { "type": "string" } },
+            "maxNotes": { "type": "number" }
+        }
+    }));
+
+    registry.register_with_definition(
+        definition,
+        Arc::new(move |call, ctx| {
+            let env = env.clone();
+            Box::pin(async move { handle(call, ctx, env).await })
+        }),
+    );
+}
+
+async fn handle(call: ToolCall, ctx: ToolContext, env: ToolEnvironment) -> GraphResult<ToolOutput> {
+    let input: NoteMapInput = parse_tool_input(&call)?;
+
+    let mut metadata = Map::new();
+    metadata.insert("seeds".to_string(), json!(input.seeds));
+
+    request_permission(
+        &ctx,
+        &env.permissions,
+        "note_map",
+        "*",
+        metadata,
+        vec!["*".to_string()],
+    )?;
+
+    let mut config = NoteMapConfig {
+        seed_notes: input.seeds,
+        ..NoteMapConfig::default()
+    };
+    if let Some(max_notes) = input.max_notes {
+        config.max_notes = max_notes.clamp(1, 200);
+    }
+
+    let workspace_root = env.workspace_root.clone();
+    let node = format!("tool:{}", call.tool);
+    // An aborted run stops waiting; the blocking scan finishes in the background.
+    let task = tokio::task::spawn_blocking(move || generate_note_map(&workspace_root, &config));
+    let output = run_cancellable(&env.cancel, task)
+        .await?
+        .map_err(|err| GraphError::ExecutionError {
+            node: node.clone(),
+            message: format!("note map task failed: {}", err),
+        })?
+        .map_err(|err| GraphError::ExecutionError {
+            node,
+            message: err.to_string(),
+        })?;
+
+    Ok(ToolOutput::text(output)
+        .with_mime_type("text/plain")
+        .with_schema("tool.note_map.v1"))
+}
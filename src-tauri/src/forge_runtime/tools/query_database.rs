@@ -0,0 +1,1133 @@
+use crate::forge_runtime::frontmatter::parse_frontmatter;
+use crate::forge_runtime::permissions::request_permission;
+use crate::forge_runtime::tools::shared::{ensure_not_cancelled, parse_tool_input};
+use crate::forge_runtime::tools::ToolEnvironment;
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use forge::runtime::cancel::CancellationToken;
+use forge::runtime::error::{GraphError, GraphResult};
+use forge::runtime::tool::{ToolCall, ToolContext, ToolDefinition, ToolOutput, ToolRegistry};
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use std::cmp::Ordering;
+use std::path::Path;
+use std::sync::Arc;
+
+const DEFAULT_LIMIT: usize = 50;
+const MAX_LIMIT: usize = 500;
+const SKIPPED_DIRS: &[&str] = &["node_modules", "target", "dist", "build"];
+const SUPPORTED_OPERATORS: &str = "$eq, $ne, $lt, $lte, $gt, $gte, $in";
+
+#[derive(Deserialize)]
+struct QueryDatabaseInput {
+    #[serde(rename = "dbId")]
+    db_id: String,
+    #[serde(default)]
+    filter: Map<String, Value>,
+    columns: Option<Vec<String>>,
+    #[serde(rename = "sortBy")]
+    sort_by: Option<String>,
+    #[serde(default)]
+    order: SortOrder,
+    offset: Option<usize>,
+    limit: Option<usize>,
+    #[serde(rename = "groupBy")]
+    group_by: Option<String>,
+    aggregates: Option<Vec<Aggregate>>,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum AggregateFn {
+    Count,
+    Sum,
+    Avg,
+    Min,
+    Max,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+struct Aggregate {
+    #[serde(rename = "fn")]
+    function: AggregateFn,
+    column: Option<String>,
+}
+
+impl Aggregate {
+    /// Column header, e.g. `count` or `avg(interval)`.
+    fn label(&self) -> String {
+        let name = match self.function {
+            AggregateFn::Count => "count",
+            AggregateFn::Sum => "sum",
+            AggregateFn::Avg => "avg",
+            AggregateFn::Min => "min",
+            AggregateFn::Max => "max",
+        };
+        match &self.column {
+            Some(column) => format!("{}({})", name, column),
+            None => name.to_string(),
+        }
+    }
+}
+
+/// One group of a grouped query with its aggregate values.
+struct GroupRow {
+    /// `None` for rows without a value in the group-by column.
+    key: Option<Value>,
+    values: Vec<Option<f64>>,
+}
+
+struct Grouped {
+    groups: Vec<GroupRow>,
+    /// Non-numeric values skipped by each aggregate.
+    skipped: Vec<usize>,
+}
+
+#[derive(Deserialize, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FilterOp {
+    Eq,
+    Ne,
+    Lt,
+    Lte,
+    Gt,
+    Gte,
+    In,
+}
+
+impl FilterOp {
+    fn parse(raw: &str) -> Option<Self> {
+        match raw {
+            "$eq" => Some(Self::Eq),
+            "$ne" => Some(Self::Ne),
+            "$lt" => Some(Self::Lt),
+            "$lte" => Some(Self::Lte),
+            "$gt" => Some(Self::Gt),
+            "$gte" => Some(Self::Gte),
+            "$in" => Some(Self::In),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Condition {
+    field: String,
+    op: FilterOp,
+    value: Value,
+}
+
+/// A database row: a note whose frontmatter `db` matches the database id.
+pub(crate) struct Row {
+    /// Workspace-relative path with `/` separators.
+    pub(crate) path: String,
+    pub(crate) fields: Map<String, Value>,
+}
+
+pub fn register(registry: &mut ToolRegistry, env: ToolEnvironment) {
+    let description = include_str!("descriptions/query_database.txt").to_string();
+    let definition = ToolDefinition::new("query_database", description).with_input_schema(json!({
+        "type": "object",
+        "properties": {
+            "dbId": { "type": "string" },
+            "filter": { "type": "object" },
+            "columns": { "type": "array", "items": { "type": "string" } },
+            "sortBy": { "type": "string" },
+            "order": { "type": "string", "enum": ["asc", "desc"] },
+            "offset": { "type": "number" },
+            "limit": { "type": "number" },
+            "groupBy": { "type": "string" },
+            "aggregates": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "fn": { "type": "string", "enum": ["count", "sum", "avg", "min", "max"] },
+                        "column": { "type": "string" }
+                    },
+                    "required": ["fn"]
+                }
+            }
+        },
+        "required": ["dbId"]
+    }));
+
+    registry.register_with_definition(
+        definition,
+        Arc::new(move |call, ctx| {
+            let env = env.clone();
+            Box::pin(async move { handle(call, ctx, env).await })
+        }),
+    );
+}
+
+async fn handle(call: ToolCall, ctx: ToolContext, env: ToolEnvironment) -> GraphResult<ToolOutput> {
+    let input: QueryDatabaseInput = parse_tool_input(&call)?;
+    let db_id = input.db_id.trim().to_string();
+    if db_id.is_empty() {
+        return Err(GraphError::ExecutionError {
+            node: format!("tool:{}", call.tool),
+            message: "dbId must not be empty".to_string(),
+        });
+    }
+
+    let conditions = parse_filter(&input.filter).map_err(|message| GraphError::ExecutionError {
+        node: format!("tool:{}", call.tool),
+        message,
+    })?;
+    let group_by = input
+        .group_by
+        .as_deref()
+        .map(str::trim)
+        .filter(|column| !column.is_empty());
+    let aggregates = match (&input.aggregates, group_by) {
+        (Some(aggregates), _) if !aggregates.is_empty() => Some(aggregates.clone()),
+        (_, Some(_)) => Some(vec![Aggregate {
+            function: AggregateFn::Count,
+            column: None,
+        }]),
+        _ => None,
+    };
+    if let Some(aggregates) = &aggregates {
+        validate_aggregates(aggregates).map_err(|message| GraphError::ExecutionError {
+            node: format!("tool:{}", call.tool),
+            message,
+        })?;
+    }
+
+    let mut metadata = Map::new();
+    metadata.insert("dbId".to_string(), json!(db_id));
+    metadata.insert("filter".to_string(), Value::Object(input.filter.clone()));
+
+    request_permission(
+        &ctx,
+        &env.permissions,
+        "query_database",
+        &db_id,
+        metadata,
+        vec!["*".to_string()],
+    )?;
+
+    let rows = collect_rows(&env.workspace_root, Some(&db_id), &env.cancel)?;
+    let mut matched: Vec<Row> = rows
+        .into_iter()
+        .filter(|row| row_matches(&row.fields, &conditions))
+        .collect();
+    let limit = input.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    let offset = input.offset.unwrap_or(0);
+
+    if let Some(aggregates) = aggregates {
+        let mut grouped = group_rows(&matched, group_by, &aggregates);
+        sort_groups(
+            &mut grouped.groups,
+            &aggregates,
+            input.sort_by.as_deref(),
+            input.order,
+        );
+        let total = grouped.groups.len();
+        let start = offset.min(total);
+        let end = (start + limit).min(total);
+        let output = if matched.is_empty() {
+            format!("No rows found in database '{}'", db_id)
+        } else if start == end {
+            format!("No groups at offset {} ({} groups in total)", offset, total)
+        } else {
+            format!(
+                "{}\n\n{}",
+                render_groups(&grouped.groups[start..end], group_by, &aggregates),
+                groups_footer(&grouped, &aggregates, start, end, matched.len())
+            )
+        };
+        let skipped: Map<String, Value> = aggregates
+            .iter()
+            .zip(&grouped.skipped)
+            .filter(|(_, skipped)| **skipped > 0)
+            .map(|(aggregate, skipped)| (aggregate.label(), json!(skipped)))
+            .collect();
+        return Ok(ToolOutput::text(output)
+            .with_mime_type("text/markdown")
+            .with_schema("tool.query_database.v1")
+            .with_attribute("db_id", json!(db_id))
+            .with_attribute("groups", json!(total))
+            .with_attribute("total", json!(matched.len()))
+            .with_attribute("offset", json!(start))
+            .with_attribute("truncated", json!(end < total))
+            .with_attribute("skipped", Value::Object(skipped)));
+    }
+
+    sort_rows(&mut matched, input.sort_by.as_deref(), input.order);
+    let total = matched.len();
+    let start = offset.min(total);
+    let end = (start + limit).min(total);
+    let visible = &matched[start..end];
+    let truncated = end < total;
+
+    let output = if total == 0 {
+        format!("No rows found in database '{}'", db_id)
+    } else if visible.is_empty() {
+        format!(
+            "No rows at offset {} ({} matching rows in total)",
+            offset, total
+        )
+    } else {
+        let columns = input
+            .columns
+            .clone()
+            .unwrap_or_else(|| infer_columns(visible));
+        format!(
+            "{}\n\n{}",
+            render_table(visible, &columns),
+            page_footer(start, end, total)
+        )
+    };
+
+    Ok(ToolOutput::text(output)
+        .with_mime_type("text/markdown")
+        .with_schema("tool.query_database.v1")
+        .with_attribute("db_id", json!(db_id))
+        .with_attribute("count", json!(visible.len()))
+        .with_attribute("total", json!(total))
+        .with_attribute("offset", json!(start))
+        .with_attribute("truncated", json!(truncated)))
+}
+
+fn parse_filter(filter: &Map<String, Value>) -> Result<Vec<Condition>, String> {
+    let mut conditions = Vec::new();
+    for (field, spec) in filter {
+        let operators = match spec {
+            Value::Object(map) if map.keys().any(|key| key.starts_with('$')) => map,
+            _ => {
+                conditions.push(Condition {
+                    field: field.clone(),
+                    op: FilterOp::Eq,
+                    value: spec.clone(),
+                });
+                continue;
+            }
+        };
+        for (raw_op, value) in operators {
+            let op = FilterOp::parse(raw_op).ok_or_else(|| {
+                format!(
+                    "Unknown filter operator '{}' for field '{}'. Supported operators: {}",
+                    raw_op, field, SUPPORTED_OPERATORS
+                )
+            })?;
+            if op == FilterOp::In && !value.is_array() {
+                return Err(format!(
+                    "Filter operator '$in' for field '{}' expects an array",
+                    field
+                ));
+            }
+            conditions.push(Condition {
+                field: field.clone(),
+                op,
+                value: value.clone(),
+            });
+        }
+    }
+    Ok(conditions)
+}
+
+fn row_matches(fields: &Map<String, Value>, conditions: &[Condition]) -> bool {
+    conditions
+        .iter()
+        .all(|condition| condition_matches(fields.get(&condition.field), condition))
+}
+
+fn condition_matches(actual: Option<&Value>, condition: &Condition) -> bool {
+    let actual = match actual {
+        Some(Value::Null) | None => {
+            return match condition.op {
+                FilterOp::Eq => condition.value.is_null(),
+                FilterOp::Ne => !condition.value.is_null(),
+                FilterOp::In => condition
+                    .value
+                    .as_array()
+                    .is_some_and(|items| items.iter().any(Value::is_null)),
+                _ => false,
+            };
+        }
+        Some(value) => value,
+    };
+
+    // List fields (tags, options, ...) match when any element matches, and
+    // `$ne` only holds when no element equals the value.
+    if let Value::Array(items) = actual {
+        if !condition.value.is_array() || condition.op == FilterOp::In {
+            return match condition.op {
+                FilterOp::Ne => !items
+                    .iter()
+                    .any(|item| values_equal(item, &condition.value)),
+                _ => items.iter().any(|item| scalar_matches(item, condition)),
+            };
+        }
+    }
+    scalar_matches(actual, condition)
+}
+
+fn scalar_matches(actual: &Value, condition: &Condition) -> bool {
+    let target = &condition.value;
+    match condition.op {
+        FilterOp::Eq => values_equal(actual, target),
+        FilterOp::Ne => !values_equal(actual, target),
+        FilterOp::In => target
+            .as_array()
+            .is_some_and(|items| items.iter().any(|item| values_equal(actual, item))),
+        FilterOp::Lt => compare_values(actual, target) == Some(Ordering::Less),
+        FilterOp::Lte => matches!(
+            compare_values(actual, target),
+            Some(Ordering::Less | Ordering::Equal)
+        ),
+        FilterOp::Gt => compare_values(actual, target) == Some(Ordering::Greater),
+        FilterOp::Gte => matches!(
+            compare_values(actual, target),
+            Some(Ordering::Greater | Ordering::Equal)
+        ),
+    }
+}
+
+fn values_equal(left: &Value, right: &Value) -> bool {
+    left == right || compare_values(left, right) == Some(Ordering::Equal)
+}
+
+/// Compare two frontmatter values. Numbers compare numerically (including
+/// numeric strings), ISO dates compare chronologically, and other strings
+/// compare lexically. Returns `None` for values that cannot be ordered.
+fn compare_values(left: &Value, right: &Value) -> Option<Ordering> {
+    match (left, right) {
+        (Value::Bool(a), Value::Bool(b)) => Some(a.cmp(b)),
+        (Value::String(a), Value::String(b)) => {
+            if let (Some(a), Some(b)) = (parse_iso_date(a), parse_iso_date(b)) {
+                return Some(a.cmp(&b));
+            }
+            if let (Ok(a), Ok(b)) = (a.trim().parse::<f64>(), b.trim().parse::<f64>()) {
+                return a.partial_cmp(&b);
+            }
+            Some(a.cmp(b))
+        }
+        _ => {
+            let a = as_number(left)?;
+            let b = as_number(right)?;
+            a.partial_cmp(&b)
+        }
+    }
+}
+
+fn as_number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(number) => number.as_f64(),
+        Value::String(text) => text.trim().parse::<f64>().ok(),
+        _ => None,
+    }
+}
+
+/// Parse an ISO date or datetime as used in frontmatter (`2024-01-15`,
+/// `2024-01-15T08:30:00Z`, ...).
+pub(crate) fn parse_iso_date(value: &str) -> Option<NaiveDateTime> {
+    let value = value.trim();
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(value) {
+        return Some(datetime.naive_utc());
+    }
+    for format in [
+        "%Y-%m-%dT%H:%M:%S%.f",
+        "%Y-%m-%d %H:%M:%S%.f",
+        "%Y-%m-%dT%H:%M",
+    ] {
+        if let Ok(datetime) = NaiveDateTime::parse_from_str(value, format) {
+            return Some(datetime);
+        }
+    }
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+}
+
+/// Collect the rows of database `db_id` from every note in the workspace;
+/// `None` collects the rows of every database.
+pub(crate) fn collect_rows(
+    workspace_root: &Path,
+    db_id: Option<&str>,
+    cancel: &CancellationToken,
+) -> GraphResult<Vec<Row>> {
+    let mut rows = Vec::new();
+    // Symlinks are not followed: they can point outside the vault or loop.
+    let walker = walkdir::WalkDir::new(workspace_root)
+        .follow_links(false)
+        .into_iter()
+        .filter_entry(|entry| {
+            if entry.depth() == 0 || !entry.file_type().is_dir() {
+                return true;
+            }
+            let name = entry.file_name().to_string_lossy();
+            !name.starts_with('.') && !SKIPPED_DIRS.contains(&name.as_ref())
+        });
+
+    for entry in walker.filter_map(Result::ok) {
+        ensure_not_cancelled(cancel)?;
+        let path = entry.path();
+        if !entry.file_type().is_file()
+            || path.extension().and_then(|ext| ext.to_str()) != Some("md")
+        {
+            continue;
+        }
+        let Ok(content) = std::fs::read_to_string(path) else {
+            continue;
+        };
+        let Some(fields) = parse_frontmatter(&content) else {
+            continue;
+        };
+        let Some(db) = fields.get("db").and_then(Value::as_str) else {
+            continue;
+        };
+        if db_id.is_some_and(|db_id| db_id != db) {
+            continue;
+        }
+        let rel = path
+            .strip_prefix(workspace_root)
+            .unwrap_or(path)
+            .to_string_lossy()
+            .replace('\\', "/");
+        rows.push(Row { path: rel, fields });
+    }
+    Ok(rows)
+}
+
+/// Sort rows by a column (or `path`). Rows without a value always sort
+/// last; ties and unsorted queries fall back to path order so results are
+/// deterministic.
+fn sort_rows(rows: &mut [Row], sort_by: Option<&str>, order: SortOrder) {
+    rows.sort_by(|a, b| {
+        let by_column = match sort_by {
+            Some("path") => a.path.cmp(&b.path),
+            Some(column) => {
+                let left = a.fields.get(column).filter(|value| !value.is_null());
+                let right = b.fields.get(column).filter(|value| !value.is_null());
+                match (left, right) {
+                    (Some(left), Some(right)) => {
+                        let ordering = SortKey::new(left).compare(&SortKey::new(right));
+                        match order {
+                            SortOrder::Asc => ordering,
+                            SortOrder::Desc => ordering.reverse(),
+                        }
+                    }
+                    (Some(_), None) => Ordering::Less,
+                    (None, Some(_)) => Ordering::Greater,
+                    (None, None) => Ordering::Equal,
+                }
+            }
+            None => Ordering::Equal,
+        };
+        let by_column = match (sort_by, order) {
+            (Some("path"), SortOrder::Desc) => by_column.reverse(),
+            _ => by_column,
+        };
+        by_column.then_with(|| a.path.cmp(&b.path))
+    });
+}
+
+/// Sort key of a non-null cell. Keys rank by type first (numbers, including
+/// numeric strings, then dates, text, booleans, and lists/objects last) and
+/// only compare within a type, so mixed columns still sort in a total order.
+enum SortKey<'a> {
+    Number(f64),
+    Date(NaiveDateTime),
+    Text(&'a str),
+    Bool(bool),
+    Other(String),
+}
+
+impl<'a> SortKey<'a> {
+    fn new(value: &'a Value) -> Self {
+        match value {
+            Value::Number(number) => Self::Number(number.as_f64().unwrap_or_default()),
+            Value::String(text) => {
+                if let Ok(number) = text.trim().parse::<f64>() {
+                    Self::Number(number)
+                } else if let Some(date) = parse_iso_date(text) {
+                    Self::Date(date)
+                } else {
+                    Self::Text(text)
+                }
+            }
+            Value::Bool(flag) => Self::Bool(*flag),
+            other => Self::Other(render_cell(Some(other))),
+        }
+    }
+
+    fn rank(&self) -> u8 {
+        match self {
+            Self::Number(_) => 0,
+            Self::Date(_) => 1,
+            Self::Text(_) => 2,
+            Self::Bool(_) => 3,
+            Self::Other(_) => 4,
+        }
+    }
+
+    fn compare(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Self::Number(a), Self::Number(b)) => a.total_cmp(b),
+            (Self::Date(a), Self::Date(b)) => a.cmp(b),
+            (Self::Text(a), Self::Text(b)) => a.cmp(b),
+            (Self::Bool(a), Self::Bool(b)) => a.cmp(b),
+            (Self::Other(a), Self::Other(b)) => a.cmp(b),
+            _ => self.rank().cmp(&other.rank()),
+        }
+    }
+}
+
+fn validate_aggregates(aggregates: &[Aggregate]) -> Result<(), String> {
+    for aggregate in aggregates {
+        let has_column = aggregate
+            .column
+            .as_deref()
+            .is_some_and(|column| !column.trim().is_empty());
+        if aggregate.function != AggregateFn::Count && !has_column {
+            return Err(format!("Aggregate '{}' needs a column", aggregate.label()));
+        }
+    }
+    Ok(())
+}
+
+/// Group rows by the value of `group_by` (all rows form one group without it)
+/// and compute each aggregate per group. A row whose group-by value is a list
+/// counts towards the group of every element.
+fn group_rows(rows: &[Row], group_by: Option<&str>, aggregates: &[Aggregate]) -> Grouped {
+    let mut keys: Vec<Option<Value>> = Vec::new();
+    let mut members: Vec<Vec<&Row>> = Vec::new();
+    for row in rows {
+        let row_keys = match group_by.map(|column| row.fields.get(column)) {
+            None => vec![None],
+            Some(None | Some(Value::Null)) => vec![None],
+            Some(Some(Value::Array(items))) if !items.is_empty() => {
+                items.iter().cloned().map(Some).collect()
+            }
+            Some(Some(Value::Array(_))) => vec![None],
+            Some(Some(value)) => vec![Some(value.clone())],
+        };
+        for key in row_keys {
+            let index = match keys.iter().position(|existing| match (existing, &key) {
+                (Some(existing), Some(key)) => values_equal(existing, key),
+                (None, None) => true,
+                _ => false,
+            }) {
+                Some(index) => index,
+                None => {
+                    keys.push(key);
+                    members.push(Vec::new());
+                    keys.len() - 1
+                }
+            };
+            members[index].push(row);
+        }
+    }
+
+    let mut skipped = vec![0; aggregates.len()];
+    let groups = keys
+        .into_iter()
+        .zip(members)
+        .map(|(key, rows)| GroupRow {
+            key,
+            values: aggregates
+                .iter()
+                .zip(skipped.iter_mut())
+                .map(|(aggregate, skipped)| {
+                    let (value, skipped_here) = aggregate_rows(&rows, aggregate);
+                    *skipped += skipped_here;
+                    value
+                })
+                .collect(),
+        })
+        .collect();
+    Grouped { groups, skipped }
+}
+
+/// Compute one aggregate, returning the value and how many non-numeric values
+/// were skipped. Missing and empty values are ignored.
+fn aggregate_rows(rows: &[&Row], aggregate: &Aggregate) -> (Option<f64>, usize) {
+    let values = || {
+        rows.iter().filter_map(|row| {
+            let value = row.fields.get(aggregate.column.as_deref()?)?;
+            let empty =
+                value.is_null() || value.as_str().is_some_and(|text| text.trim().is_empty());
+            (!empty).then_some(value)
+        })
+    };
+    if aggregate.function == AggregateFn::Count {
+        let count = match aggregate.column {
+            Some(_) => values().count(),
+            None => rows.len(),
+        };
+        return (Some(count as f64), 0);
+    }
+
+    let numbers: Vec<f64> = values().filter_map(as_number).collect();
+    let skipped = values().count() - numbers.len();
+    let value = match aggregate.function {
+        AggregateFn::Sum => Some(numbers.iter().sum()),
+        AggregateFn::Avg if numbers.is_empty() => None,
+        AggregateFn::Avg => Some(numbers.iter().sum::<f64>() / numbers.len() as f64),
+        AggregateFn::Min => numbers.iter().copied().reduce(f64::min),
+        AggregateFn::Max => numbers.iter().copied().reduce(f64::max),
+        AggregateFn::Count => unreachable!("handled above"),
+    };
+    (value, skipped)
+}
+
+/// Sort groups by their key, or by an aggregate when `sort_by` is its label
+/// (e.g. `count`). The group of rows without a value always comes last.
+fn sort_groups(
+    groups: &mut [GroupRow],
+    aggregates: &[Aggregate],
+    sort_by: Option<&str>,
+    order: SortOrder,
+) {
+    let aggregate_index = sort_by.and_then(|label| {
+        aggregates
+            .iter()
+            .position(|aggregate| aggregate.label() == label)
+    });
+    groups.sort_by(|a, b| {
+        let ordering = match (aggregate_index, &a.key, &b.key) {
+            (_, Some(_), None) => return Ordering::Less,
+            (_, None, Some(_)) => return Ordering::Greater,
+            (Some(index), _, _) => {
+                let left = a.values[index].unwrap_or(f64::NEG_INFINITY);
+                let right = b.values[index].unwrap_or(f64::NEG_INFINITY);
+                left.partial_cmp(&right).unwrap_or(Ordering::Equal)
+            }
+            (None, Some(left), Some(right)) => compare_values(left, right)
+                .unwrap_or_else(|| render_cell(Some(left)).cmp(&render_cell(Some(right)))),
+            (None, _, _) => Ordering::Equal,
+        };
+        match order {
+            SortOrder::Asc => ordering,
+            SortOrder::Desc => ordering.reverse(),
+        }
+    });
+}
+
+fn render_groups(groups: &[GroupRow], group_by: Option<&str>, aggregates: &[Aggregate]) -> String {
+    let header: Vec<String> = group_by
+        .map(escape_cell)
+        .into_iter()
+        .chain(
+            aggregates
+                .iter()
+                .map(|aggregate| escape_cell(&aggregate.label())),
+        )
+        .collect();
+    let mut lines = Vec::with_capacity(groups.len() + 2);
+    lines.push(format!("| {} |", header.join(" | ")));
+    lines.push(format!("|{}", " --- |".repeat(header.len())));
+    for group in groups {
+        let key = match &group.key {
+            Some(key) => escape_cell(&render_cell(Some(key))),
+            None => "(empty)".to_string(),
+        };
+        let cells: Vec<String> = group_by
+            .map(|_| key)
+            .into_iter()
+            .chain(group.values.iter().map(|value| match value {
+                Some(value) => format_number(*value),
+                None => String::new(),
+            }))
+            .collect();
+        lines.push(format!("| {} |", cells.join(" | ")));
+    }
+    lines.join("\n")
+}
+
+fn groups_footer(
+    grouped: &Grouped,
+    aggregates: &[Aggregate],
+    start: usize,
+    end: usize,
+    rows: usize,
+) -> String {
+    let total = grouped.groups.len();
+    let mut footer = format!(
+        "(Showing groups {}-{} of {} from {} matching rows.",
+        start + 1,
+        end,
+        total,
+        rows
+    );
+    if end < total {
+        footer.push_str(&format!(" Use offset {} to see more.", end));
+    }
+    for (aggregate, skipped) in aggregates.iter().zip(&grouped.skipped) {
+        if *skipped > 0 {
+            footer.push_str(&format!(
+                " {} skipped {} non-numeric value{}.",
+                aggregate.label(),
+                skipped,
+                if *skipped == 1 { "" } else { "s" }
+            ));
+        }
+    }
+    footer.push(')');
+    footer
+}
+
+/// Whole numbers without a fraction, others rounded to two decimals.
+fn format_number(value: f64) -> String {
+    if value.fract() == 0.0 && value.abs() < 1e15 {
+        format!("{}", value as i64)
+    } else {
+        let text = format!("{:.2}", value);
+        text.trim_end_matches('0').trim_end_matches('.').to_string()
+    }
+}
+
+fn page_footer(start: usize, end: usize, total: usize) -> String {
+    let mut footer = format!(
+        "(Showing rows {}-{} of {} matching rows.",
+        start + 1,
+        end,
+        total
+    );
+    if end < total {
+        footer.push_str(&format!(" Use offset {} to see more.", end));
+    }
+    footer.push(')');
+    footer
+}
+
+fn infer_columns(rows: &[Row]) -> Vec<String> {
+    let mut columns: Vec<String> = Vec::new();
+    for row in rows {
+        for key in row.fields.keys() {
+            if key != "db" && !columns.contains(key) {
+                columns.push(key.clone());
+            }
+        }
+    }
+    columns
+}
+
+fn render_table(rows: &[Row], columns: &[String]) -> String {
+    let mut lines = Vec::with_capacity(rows.len() + 2);
+    let header: Vec<String> = std::iter::once("path".to_string())
+        .chain(columns.iter().map(|column| escape_cell(column)))
+        .collect();
+    lines.push(format!("| {} |", header.join(" | ")));
+    lines.push(format!("|{}", " --- |".repeat(header.len())));
+    for row in rows {
+        let cells: Vec<String> = std::iter::once(escape_cell(&row.path))
+            .chain(
+                columns
+                    .iter()
+                    .map(|column| escape_cell(&render_cell(row.fields.get(column)))),
+            )
+            .collect();
+        lines.push(format!("| {} |", cells.join(" | ")));
+    }
+    lines.join("\n")
+}
+
+fn render_cell(value: Option<&Value>) -> String {
+    match value {
+        None | Some(Value::Null) => String::new(),
+        Some(Value::String(text)) => text.clone(),
+        Some(Value::Array(items)) => items
+            .iter()
+            .map(|item| render_cell(Some(item)))
+            .collect::<Vec<_>>()
+            .join(", "),
+        Some(other) => other.to_string(),
+    }
+}
+
+fn escape_cell(text: &str) -> String {
+    text.replace('|', "\\|").replace(['\r', '\n'], " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields(value: Value) -> Map<String, Value> {
+        value.as_object().cloned().expect("object")
+    }
+
+    fn filter(value: Value) -> Vec<Condition> {
+        parse_filter(value.as_object().expect("object")).expect("valid filter")
+    }
+
+    #[test]
+    fn bare_values_mean_equality_and_match_list_fields() {
+        let row = fields(
+            json!({ "db": "cards", "deck": "rust", "tags": ["async", "io"], "interval": 3 }),
+        );
+
+        assert!(row_matches(&row, &filter(json!({ "deck": "rust" }))));
+        assert!(row_matches(&row, &filter(json!({ "tags": "io" }))));
+        assert!(row_matches(&row, &filter(json!({ "interval": "3" }))));
+        assert!(!row_matches(&row, &filter(json!({ "deck": "go" }))));
+        assert!(!row_matches(&row, &filter(json!({ "missing": "x" }))));
+    }
+
+    #[test]
+    fn comparison_operators_are_date_and_number_aware() {
+        let row = fields(
+            json!({ "due": "2024-01-15", "interval": 10, "updatedAt": "2024-01-15T08:30:00Z" }),
+        );
+
+        assert!(row_matches(
+            &row,
+            &filter(json!({ "due": { "$lte": "2024-01-15" } }))
+        ));
+        assert!(!row_matches(
+            &row,
+            &filter(json!({ "due": { "$lt": "2024-01-15" } }))
+        ));
+        assert!(row_matches(
+            &row,
+            &filter(json!({ "updatedAt": { "$gt": "2024-01-15", "$lt": "2024-01-16" } }))
+        ));
+        // 10 > 9 numerically even though "10" < "9" lexically.
+        assert!(row_matches(
+            &row,
+            &filter(json!({ "interval": { "$gte": 9 } }))
+        ));
+        assert!(!row_matches(
+            &row,
+            &filter(json!({ "interval": { "$gt": "9", "$lt": 10 } }))
+        ));
+    }
+
+    #[test]
+    fn ne_and_in_operators() {
+        let row = fields(json!({ "status": "learning", "tags": ["a", "b"] }));
+
+        assert!(row_matches(
+            &row,
+            &filter(json!({ "status": { "$in": ["new", "learning"] } }))
+        ));
+        assert!(!row_matches(
+            &row,
+            &filter(json!({ "status": { "$ne": "learning" } }))
+        ));
+        assert!(row_matches(
+            &row,
+            &filter(json!({ "tags": { "$in": ["b", "z"] } }))
+        ));
+        assert!(!row_matches(
+            &row,
+            &filter(json!({ "tags": { "$ne": "a" } }))
+        ));
+        assert!(row_matches(
+            &row,
+            &filter(json!({ "suspended": { "$ne": true } }))
+        ));
+    }
+
+    #[test]
+    fn rejects_unknown_operators_and_bad_in_values() {
+        let err = parse_filter(
+            json!({ "due": { "$before": "2024-01-01" } })
+                .as_object()
+                .unwrap(),
+        )
+        .unwrap_err();
+        assert!(err.contains("$before"));
+        assert!(err.contains("$lte"));
+
+        let err =
+            parse_filter(json!({ "status": { "$in": "new" } }).as_object().unwrap()).unwrap_err();
+        assert!(err.contains("expects an array"));
+    }
+
+    fn row(path: &str, value: Value) -> Row {
+        Row {
+            path: path.to_string(),
+            fields: fields(value),
+        }
+    }
+
+    fn paths(rows: &[Row]) -> Vec<&str> {
+        rows.iter().map(|row| row.path.as_str()).collect()
+    }
+
+    #[test]
+    fn sorts_by_type_aware_column_values() {
+        let mut rows = vec![
+            row("a.md", json!({ "interval": 10, "due": "2024-02-01" })),
+            row(
+                "b.md",
+                json!({ "interval": 9, "due": "2024-01-15T10:00:00Z" }),
+            ),
+            row("c.md", json!({ "title": "no schedule" })),
+            row("d.md", json!({ "interval": "11", "due": "2023-12-31" })),
+        ];
+
+        sort_rows(&mut rows, Some("interval"), SortOrder::Asc);
+        assert_eq!(paths(&rows), vec!["b.md", "a.md", "d.md", "c.md"]);
+
+        sort_rows(&mut rows, Some("due"), SortOrder::Desc);
+        assert_eq!(paths(&rows), vec!["a.md", "b.md", "d.md", "c.md"]);
+
+        sort_rows(&mut rows, None, SortOrder::Asc);
+        assert_eq!(paths(&rows), vec!["a.md", "b.md", "c.md", "d.md"]);
+
+        sort_rows(&mut rows, Some("path"), SortOrder::Desc);
+        assert_eq!(paths(&rows), vec!["d.md", "c.md", "b.md", "a.md"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn collect_rows_does_not_follow_symlinks() {
+        let vault = tempfile::tempdir().expect("vault");
+        let outside = tempfile::tempdir().expect("outside");
+        std::fs::write(vault.path().join("a.md"), "---\ndb: tasks\n---\n").expect("a.md");
+        std::fs::write(outside.path().join("b.md"), "---\ndb: tasks\n---\n").expect("b.md");
+        std::os::unix::fs::symlink(outside.path(), vault.path().join("linked")).expect("link");
+        std::os::unix::fs::symlink(vault.path(), vault.path().join("loop")).expect("loop");
+
+        let rows =
+            collect_rows(vault.path(), Some("tasks"), &CancellationToken::new()).expect("rows");
+        assert_eq!(paths(&rows), vec!["a.md"]);
+    }
+
+    #[test]
+    fn sorts_mixed_type_columns_in_a_total_order() {
+        let values = [
+            json!("beta"),
+            json!(true),
+            json!(3),
+            json!("NaN"),
+            json!(["x"]),
+            json!("2024-01-01"),
+            Value::Null,
+            json!("2"),
+            json!("alpha"),
+            json!(false),
+            json!({ "k": 1 }),
+            json!(-1.5),
+        ];
+        let mut rows: Vec<Row> = (0..40)
+            .map(|index| {
+                let value = values[index % values.len()].clone();
+                row(&format!("{:02}.md", index), json!({ "mixed": value }))
+            })
+            .collect();
+
+        sort_rows(&mut rows, Some("mixed"), SortOrder::Asc);
+        let mut distinct: Vec<Value> = rows.iter().map(|row| row.fields["mixed"].clone()).collect();
+        distinct.dedup();
+        assert_eq!(
+            distinct,
+            vec![
+                json!(-1.5),
+                json!("2"),
+                json!(3),
+                json!("NaN"),
+                json!("2024-01-01"),
+                json!("alpha"),
+                json!("beta"),
+                json!(false),
+                json!(true),
+                json!(["x"]),
+                json!({ "k": 1 }),
+                Value::Null,
+            ]
+        );
+    }
+
+    fn parse_aggregates(value: Value) -> Vec<Aggregate> {
+        serde_json::from_value(value).expect("aggregates")
+    }
+
+    #[test]
+    fn groups_rows_and_skips_non_numeric_values() {
+        let rows = vec![
+            row(
+                "a.md",
+                json!({ "deck": "rust", "interval": 4, "tags": ["x", "y"] }),
+            ),
+            row("b.md", json!({ "deck": "rust", "interval": "6" })),
+            row(
+                "c.md",
+                json!({ "deck": "go", "interval": "soon", "tags": ["x"] }),
+            ),
+            row("d.md", json!({ "interval": 1 })),
+        ];
+        let aggregates = parse_aggregates(json!([
+            { "fn": "count" },
+            { "fn": "sum", "column": "interval" },
+            { "fn": "avg", "column": "interval" },
+            { "fn": "max", "column": "interval" }
+        ]));
+        let mut grouped = group_rows(&rows, Some("deck"), &aggregates);
+        sort_groups(&mut grouped.groups, &aggregates, None, SortOrder::Asc);
+        assert_eq!(grouped.skipped, vec![0, 1, 1, 1]);
+        assert_eq!(
+            render_groups(&grouped.groups, Some("deck"), &aggregates),
+            "| deck | count | sum(interval) | avg(interval) | max(interval) |\n\
+             | --- | --- | --- | --- | --- |\n\
+             | go | 1 | 0 |  |  |\n\
+             | rust | 2 | 10 | 5 | 6 |\n\
+             | (empty) | 1 | 1 | 1 | 1 |"
+        );
+        assert!(groups_footer(&grouped, &aggregates, 0, 3, 4)
+            .contains("sum(interval) skipped 1 non-numeric value."));
+
+        // List values count towards each element; sort by an aggregate
+        let count = parse_aggregates(json!([{ "fn": "count" }]));
+        let mut by_tag = group_rows(&rows, Some("tags"), &count);
+        sort_groups(&mut by_tag.groups, &count, Some("count"), SortOrder::Desc);
+        assert_eq!(
+            render_groups(&by_tag.groups, Some("tags"), &count),
+            "| tags | count |\n| --- | --- |\n| x | 2 |\n| y | 1 |\n| (empty) | 2 |"
+        );
+
+        // Without groupBy all rows form one group
+        let all = group_rows(&rows, None, &aggregates);
+        assert_eq!(all.groups.len(), 1);
+        assert_eq!(all.groups[0].values[1], Some(11.0));
+        assert_eq!(format_number(11.0 / 3.0), "3.67");
+    }
+
+    #[test]
+    fn aggregates_other_than_count_need_a_column() {
+        assert!(validate_aggregates(&parse_aggregates(json!([{ "fn": "count" }]))).is_ok());
+        assert!(validate_aggregates(&parse_aggregates(json!([{ "fn": "avg" }]))).is_err());
+        assert!(serde_json::from_value::<Vec<Aggregate>>(json!([{ "fn": "median" }])).is_err());
+    }
+
+    #[test]
+    fn page_footer_reports_total_and_next_offset() {
+        assert_eq!(
+            page_footer(0, 10, 25),
+            "(Showing rows 1-10 of 25 matching rows. Use offset 10 to see more.)"
+        );
+        assert_eq!(
+            page_footer(20, 25, 25),
+            "(Showing rows 21-25 of 25 matching rows.)"
+        );
+    }
+
+    #[test]
+    fn renders_markdown_table_with_escaped_cells() {
+        let rows = vec![Row {
+            path: "Databases/cards/a.md".to_string(),
+            fields: fields(json!({ "db": "cards", "title": "a|b", "tags": ["x", "y"] })),
+        }];
+        let mut inferred = infer_columns(&rows);
+        inferred.sort();
+        assert_eq!(inferred, vec!["tags", "title"]);
+
+        let columns = vec!["title".to_string(), "tags".to_string()];
+        assert_eq!(
+            render_table(&rows, &columns),
+            "| path | title | tags |\n| --- | --- | --- |\n| Databases/cards/a.md | a\\|b | x, y |"
+        );
+    }
+}
@@ -99,12 +99,41 @@ async fn handle(call: ToolCall, ctx: ToolContext, env: ToolEnvironment) -> Graph
         message: format!("Cannot read binary file: {}", target.display()),
     })?;
 
-    let lines: Vec<&str> = content.split('\n').collect();
-    let total_lines = lines.len();
+    env.file_times.record(&target);
     let offset = input.offset.unwrap_or(0);
     let limit = input.limit.unwrap_or(DEFAULT_READ_LIMIT);
+    let window = render_window(&content, offset, limit);
+
+    Ok(ToolOutput::text(window.output)
+        .with_mime_type("text/plain")
+        .with_schema("tool.read.v1")
+        .with_attribute("preview", json!(window.preview))
+        .with_attribute("truncated", json!(window.truncated))
+        .with_attribute("total_lines", json!(window.total_lines))
+        .with_attribute("last_line", json!(window.last_line))
+        .with_attribute(
+            "remaining_lines",
+            json!(window.total_lines - window.last_line),
+        )
+        .with_attribute("offset", json!(offset))
+        .with_attribute("limit", json!(limit)))
+}
+
+struct ReadWindow {
+    output: String,
+    preview: String,
+    truncated: bool,
+    total_lines: usize,
+    last_line: usize,
+}
+
+/// Renders `limit` lines starting at the 0-based line `offset`, numbered from `offset + 1`.
+/// An offset past the end of the file yields an empty window that still reports the total.
+fn render_window(content: &str, offset: usize, limit: usize) -> ReadWindow {
+    let lines: Vec<&str> = content.split('\n').collect();
+    let total_lines = lines.len();
     let start = offset.min(total_lines);
-    let end = (start + limit).min(total_lines);
+    let end = start.saturating_add(limit).min(total_lines);
 
     let mut raw_lines = Vec::new();
     let mut bytes = 0usize;
@@ -113,7 +142,11 @@ async fn handle(call: ToolCall, ctx: ToolContext, env: ToolEnvironment) -> Graph
     for line in lines.iter().take(end).skip(start) {
         let mut text = (*line).to_string();
         if text.len() > MAX_LINE_LENGTH {
-            text.truncate(MAX_LINE_LENGTH);
+            let mut cut = MAX_LINE_LENGTH;
+            while !text.is_char_boundary(cut) {
+                cut -= 1;
+            }
+            text.truncate(cut);
             text.push_str("...");
         }
 
@@ -132,22 +165,34 @@ async fn handle(call: ToolCall, ctx: ToolContext, env: ToolEnvironment) -> Graph
         .map(|(idx, line)| format!("{:0>5}| {}", idx + start + 1, line))
         .collect();
 
-    let last_read_line = start + raw_lines.len();
-    let has_more_lines = total_lines > last_read_line;
+    let last_line = start + raw_lines.len();
+    let has_more_lines = total_lines > last_line;
     let truncated = has_more_lines || truncated_by_bytes;
 
     let mut output = String::from("<file>\n");
     output.push_str(&numbered.join("\n"));
 
-    if truncated_by_bytes {
+    if offset >= total_lines {
+        output.push_str(&format!(
+            "\n\n(Offset {} is beyond the end of the file - total {} lines)",
+            offset, total_lines
+        ));
+    } else if truncated_by_bytes {
         output.push_str(&format!(
-            "\n\n(Output truncated at {} bytes. Use 'offset' parameter to read beyond line {})",
-            MAX_BYTES, last_read_line
+            "\n\n(Output truncated at {} bytes. Showing lines {}-{} of {}. Use 'offset' parameter to read beyond line {})",
+            MAX_BYTES,
+            start + 1,
+            last_line,
+            total_lines,
+            last_line
         ));
     } else if has_more_lines {
         output.push_str(&format!(
-            "\n\n(File has more lines. Use 'offset' parameter to read beyond line {})",
-            last_read_line
+            "\n\n(Showing lines {}-{} of {}. Use 'offset' parameter to read beyond line {})",
+            start + 1,
+            last_line,
+            total_lines,
+            last_line
         ));
     } else {
         output.push_str(&format!("\n\n(End of file - total {} lines)", total_lines));
@@ -161,14 +206,13 @@ async fn handle(call: ToolCall, ctx: ToolContext, env: ToolEnvironment) -> Graph
         .collect::<Vec<_>>()
         .join("\n");
 
-    Ok(ToolOutput::text(output)
-        .with_mime_type("text/plain")
-        .with_schema("tool.read.v1")
-        .with_attribute("preview", json!(preview))
-        .with_attribute("truncated", json!(truncated))
-        .with_attribute("total_lines", json!(total_lines))
-        .with_attribute("offset", json!(offset))
-        .with_attribute("limit", json!(limit)))
+    ReadWindow {
+        output,
+        preview,
+        truncated,
+        total_lines,
+        last_line,
+    }
 }
 
 async fn missing_file_message(path: &std::path::Path) -> String {
@@ -204,3 +248,46 @@ async fn missing_file_message(path: &std::path::Path) -> String {
 
     format!("File not found: {}", path.display())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn window_numbers_lines_from_offset() {
+        let window = render_window("a\nb\nc\nd\ne", 1, 2);
+
+        assert_eq!(
+            window.output,
+            "<file>\n00002| b\n00003| c\n\n(Showing lines 2-3 of 5. Use 'offset' parameter to read beyond line 3)\n</file>"
+        );
+        assert!(window.truncated);
+        assert_eq!(window.total_lines, 5);
+        assert_eq!(window.last_line, 3);
+    }
+
+    #[test]
+    fn window_reaching_end_reports_total() {
+        let window = render_window("a\nb\nc", 1, 10);
+
+        assert!(window
+            .output
+            .ends_with("(End of file - total 3 lines)\n</file>"));
+        assert!(!window.truncated);
+        assert_eq!(window.last_line, 3);
+    }
+
+    #[test]
+    fn offset_beyond_eof_is_empty_not_error() {
+        let window = render_window("a\nb\nc", 10, 5);
+
+        assert_eq!(
+            window.output,
+            "<file>\n\n\n(Offset 10 is beyond the end of the file - total 3 lines)\n</file>"
+        );
+        assert!(window.preview.is_empty());
+        assert!(!window.truncated);
+        assert_eq!(window.total_lines, 3);
+        assert_eq!(window.last_line, 3);
+    }
+}
@@ -0,0 +1,294 @@
+use crate::forge_runtime::permissions::request_permission;
+use crate::forge_runtime::tools::shared::{
+    is_within_workspace, parse_tool_input, permission_path, resolve_path, run_cancellable,
+};
+use crate::forge_runtime::tools::ToolEnvironment;
+use forge::runtime::error::{GraphError, GraphResult};
+use forge::runtime::tool::{ToolCall, ToolContext, ToolDefinition, ToolOutput, ToolRegistry};
+use futures_util::future::join_all;
+use serde::Deserialize;
+use serde_json::{json, Map};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+const MAX_FILES: usize = 20;
+const DEFAULT_MAX_BYTES_EACH: usize = 16 * 1024;
+/// Cap on the combined output, whatever `maxBytesEach` asks for.
+const MAX_TOTAL_BYTES: usize = 100 * 1024;
+const MAX_LINE_LENGTH: usize = 2000;
+
+#[derive(Deserialize)]
+struct ReadManyInput {
+    paths: Vec<String>,
+    #[serde(rename = "maxBytesEach")]
+    max_bytes_each: Option<usize>,
+}
+
+pub fn register(registry: &mut ToolRegistry, env: ToolEnvironment) {
+    let description = include_str!("descriptions/read_many.txt").to_string();
+    let definition = ToolDefinition::new("read_many", description).with_input_schema(json!({
+        "type": "object",
+        "properties": {
+            "paths": {
+                "type": "array",
+                "items": { "type": "string" },
+                "maxItems": MAX_FILES
+            },
+            "maxBytesEach": { "type": "number" }
+        },
+        "required": ["paths"]
+    }));
+
+    registry.register_with_definition(
+        definition,
+        Arc::new(move |call, ctx| {
+            let env = env.clone();
+            Box::pin(async move { handle(call, ctx, env).await })
+        }),
+    );
+}
+
+async fn handle(call: ToolCall, ctx: ToolContext, env: ToolEnvironment) -> GraphResult<ToolOutput> {
+    let input: ReadManyInput = parse_tool_input(&call)?;
+    if input.paths.is_empty() || input.paths.len() > MAX_FILES {
+        return Err(GraphError::ExecutionError {
+            node: format!("tool:{}", call.tool),
+            message: format!("paths must list between 1 and {} files", MAX_FILES),
+        });
+    }
+
+    // Ask for every file up front so an approval prompt does not leave the
+    // batch half read; denied files are reported instead of failing the call.
+    let mut denied = Vec::new();
+    for path in &input.paths {
+        let target = resolve_path(&env.workspace_root, path);
+        if !is_within_workspace(&env.workspace_root, &target) {
+            continue;
+        }
+        let pattern = permission_path(&env.workspace_root, &target);
+        let mut metadata = Map::new();
+        metadata.insert("path".to_string(), json!(target.display().to_string()));
+        match request_permission(
+            &ctx,
+            &env.permissions,
+            "read",
+            &pattern,
+            metadata,
+            vec!["*".to_string()],
+        ) {
+            Ok(()) => {}
+            Err(GraphError::PermissionDenied { .. }) => denied.push(path.clone()),
+            Err(err) => return Err(err),
+        }
+    }
+
+    let max_bytes_each = input
+        .max_bytes_each
+        .unwrap_or(DEFAULT_MAX_BYTES_EACH)
+        .clamp(1, MAX_TOTAL_BYTES);
+    let batch = run_cancellable(
+        &env.cancel,
+        read_files(&env.workspace_root, &input.paths, &denied, max_bytes_each),
+    )
+    .await?;
+    for path in &batch.read_paths {
+        env.file_times.record(path);
+    }
+
+    Ok(ToolOutput::text(batch.output)
+        .with_mime_type("text/plain")
+        .with_schema("tool.read_many.v1")
+        .with_attribute("read", json!(batch.read))
+        .with_attribute("failed", json!(batch.failed))
+        .with_attribute("truncated", json!(batch.truncated)))
+}
+
+struct ReadBatch {
+    output: String,
+    read: usize,
+    failed: usize,
+    truncated: bool,
+    /// Files whose contents were included, for line-anchored edits.
+    read_paths: Vec<PathBuf>,
+}
+
+/// Reads `paths` in parallel and renders each one under a header, in the
+/// requested order. Failures are reported inline and do not stop the batch.
+async fn read_files(
+    workspace_root: &Path,
+    paths: &[String],
+    denied: &[String],
+    max_bytes_each: usize,
+) -> ReadBatch {
+    let reads = paths.iter().map(|path| async move {
+        let target = resolve_path(workspace_root, path);
+        let result = if !is_within_workspace(workspace_root, &target) {
+            Err("outside the workspace".to_string())
+        } else if denied.contains(path) {
+            Err("permission denied".to_string())
+        } else {
+            read_text(&target).await
+        };
+        (target, result)
+    });
+    let results = join_all(reads).await;
+
+    let mut batch = ReadBatch {
+        output: String::new(),
+        read: 0,
+        failed: 0,
+        truncated: false,
+        read_paths: Vec::new(),
+    };
+    for (target, result) in results {
+        let display = permission_path(workspace_root, &target);
+        if !batch.output.is_empty() {
+            batch.output.push_str("\n\n");
+        }
+        let content = match result {
+            Ok(content) => content,
+            Err(err) => {
+                batch.failed += 1;
+                batch
+                    .output
+                    .push_str(&format!("==> {} <==\n(failed: {})", display, err));
+                continue;
+            }
+        };
+        let remaining = MAX_TOTAL_BYTES.saturating_sub(batch.output.len());
+        if remaining == 0 {
+            batch.truncated = true;
+            batch.output.push_str(&format!(
+                "==> {} <==\n(skipped: total output limit of {} bytes reached)",
+                display, MAX_TOTAL_BYTES
+            ));
+            continue;
+        }
+        let (body, total_lines, shown_lines) =
+            number_lines(&content, max_bytes_each.min(remaining));
+        batch.read += 1;
+        batch.read_paths.push(target);
+        batch.output.push_str(&format!(
+            "==> {} ({} lines) <==\n{}",
+            display, total_lines, body
+        ));
+        if shown_lines < total_lines {
+            batch.truncated = true;
+            batch.output.push_str(&format!(
+                "\n(Showing lines 1-{} of {}. Use the read tool with offset={} to see the rest)",
+                shown_lines, total_lines, shown_lines
+            ));
+        }
+    }
+    batch
+}
+
+async fn read_text(path: &Path) -> Result<String, String> {
+    let bytes = match tokio::fs::read(path).await {
+        Ok(bytes) => bytes,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {
+            return Err("file not found".to_string())
+        }
+        Err(_) if path.is_dir() => return Err("is a directory".to_string()),
+        Err(err) => return Err(err.to_string()),
+    };
+    String::from_utf8(bytes).map_err(|_| "binary file".to_string())
+}
+
+/// Numbers lines like the read tool until `max_bytes` of output.
+/// Returns the rendered text, the file's line count and the lines shown.
+fn number_lines(content: &str, max_bytes: usize) -> (String, usize, usize) {
+    let lines: Vec<&str> = content.split('\n').collect();
+    let mut output = String::new();
+    let mut shown = 0;
+    for (index, line) in lines.iter().enumerate() {
+        let mut text = (*line).to_string();
+        if text.len() > MAX_LINE_LENGTH {
+            let mut cut = MAX_LINE_LENGTH;
+            while !text.is_char_boundary(cut) {
+                cut -= 1;
+            }
+            text.truncate(cut);
+            text.push_str("...");
+        }
+        let rendered = format!("{:0>5}| {}", index + 1, text);
+        let size = rendered.len() + usize::from(!output.is_empty());
+        if output.len() + size > max_bytes {
+            break;
+        }
+        if !output.is_empty() {
+            output.push('\n');
+        }
+        output.push_str(&rendered);
+        shown += 1;
+    }
+    (output, lines.len(), shown)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn reads_files_in_order_and_reports_failures() {
+        let workspace = tempfile::tempdir().unwrap();
+        std::fs::write(workspace.path().join("a.md"), "alpha\nbeta").unwrap();
+        std::fs::create_dir(workspace.path().join("notes")).unwrap();
+        std::fs::write(workspace.path().join("notes/b.md"), "gamma").unwrap();
+        std::fs::write(workspace.path().join(".env"), "TOKEN=1").unwrap();
+        std::fs::write(workspace.path().join("img.bin"), [0xff, 0xfe, 0x00]).unwrap();
+
+        let paths = [
+            "notes/b.md",
+            "a.md",
+            "missing.md",
+            "../outside.md",
+            ".env",
+            "img.bin",
+        ]
+        .map(String::from);
+        let batch = read_files(workspace.path(), &paths, &[".env".to_string()], 4096).await;
+
+        assert_eq!((batch.read, batch.failed, batch.truncated), (2, 4, false));
+        let b = batch
+            .output
+            .find("==> notes/b.md (1 lines) <==\n00001| gamma");
+        let a = batch
+            .output
+            .find("==> a.md (2 lines) <==\n00001| alpha\n00002| beta");
+        assert!(b.unwrap() < a.unwrap());
+        assert!(batch
+            .output
+            .contains("==> missing.md <==\n(failed: file not found)"));
+        assert!(batch.output.contains("(failed: outside the workspace)"));
+        assert!(batch
+            .output
+            .contains("==> .env <==\n(failed: permission denied)"));
+        assert!(batch.output.contains("(failed: binary file)"));
+    }
+
+    #[tokio::test]
+    async fn caps_each_file_and_the_whole_batch() {
+        let workspace = tempfile::tempdir().unwrap();
+        let long: String = (0..10_000).map(|n| format!("line {}\n", n)).collect();
+        let paths: Vec<String> = (0..4).map(|n| format!("{}.md", n)).collect();
+        for path in &paths {
+            std::fs::write(workspace.path().join(path), &long).unwrap();
+        }
+
+        let batch = read_files(workspace.path(), &paths[..1], &[], 100).await;
+        assert!(batch.truncated);
+        assert!(batch.output.contains("00001| line 0\n"));
+        assert!(batch
+            .output
+            .contains("(Showing lines 1-7 of 10001. Use the read tool with offset=7"));
+
+        let batch = read_files(workspace.path(), &paths, &[], MAX_TOTAL_BYTES).await;
+        assert_eq!(batch.read, 1);
+        assert!(batch
+            .output
+            .contains("==> 1.md <==\n(skipped: total output limit of 102400 bytes reached)"));
+        assert!(batch.output.len() < MAX_TOTAL_BYTES + 1024);
+    }
+}
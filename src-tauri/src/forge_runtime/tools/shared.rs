@@ -1,9 +1,15 @@
 use crate::forge_runtime::permissions::{request_permission, PermissionSession};
+use forge::runtime::cancel::CancellationToken;
 use forge::runtime::error::{GraphError, GraphResult};
 use forge::runtime::tool::{ToolCall, ToolContext};
 use serde::de::DeserializeOwned;
 use serde_json::Map;
+use std::future::Future;
 use std::path::{Component, Path, PathBuf};
+use std::time::Duration;
+
+/// How often a running tool checks whether the agent run was aborted.
+const CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(50);
 
 pub fn parse_tool_input<T: DeserializeOwned>(call: &ToolCall) -> GraphResult<T> {
     serde_json::from_value(call.input.clone()).map_err(|err| GraphError::ExecutionError {
@@ -12,6 +18,42 @@ pub fn parse_tool_input<T: DeserializeOwned>(call: &ToolCall) -> GraphResult<T>
     })
 }
 
+/// The result of a tool interrupted by `agent_abort`. The agent loop stops on
+/// it like on any other abort and does not record a tool result.
+pub fn cancelled(cancel: &CancellationToken) -> GraphError {
+    GraphError::Aborted {
+        reason: cancel.abort_reason(),
+    }
+}
+
+/// Returns the cancelled result once the run has been aborted; long walks call
+/// this between entries.
+pub fn ensure_not_cancelled(cancel: &CancellationToken) -> GraphResult<()> {
+    if cancel.is_cancelled() {
+        return Err(cancelled(cancel));
+    }
+    Ok(())
+}
+
+/// Resolves once the run is aborted.
+pub async fn wait_cancelled(cancel: &CancellationToken) {
+    while !cancel.is_cancelled() {
+        tokio::time::sleep(CANCEL_POLL_INTERVAL).await;
+    }
+}
+
+/// Drives `future` to completion unless the run is aborted first, in which
+/// case the future is dropped (closing its connection or file handles).
+pub async fn run_cancellable<F: Future>(
+    cancel: &CancellationToken,
+    future: F,
+) -> GraphResult<F::Output> {
+    tokio::select! {
+        output = future => Ok(output),
+        _ = wait_cancelled(cancel) => Err(cancelled(cancel)),
+    }
+}
+
 pub fn normalize_path(path: &Path) -> PathBuf {
     let mut result = PathBuf::new();
     for component in path.components() {
@@ -38,10 +80,28 @@ pub fn resolve_path(workspace_root: &Path, input: &str) -> PathBuf {
     normalize_path(&resolved)
 }
 
+/// Whether `target` is inside the workspace both lexically and on disk. A
+/// symlink inside the vault may point anywhere, so the existing part of each
+/// path is canonicalized (as `fs::resolve_for_write` does) before comparing.
 pub fn is_within_workspace(workspace_root: &Path, target: &Path) -> bool {
     let normalized_root = normalize_path(workspace_root);
     let normalized_target = normalize_path(target);
-    normalized_target.starts_with(&normalized_root)
+    if !normalized_target.starts_with(&normalized_root) {
+        return false;
+    }
+    match (
+        crate::fs::resolve_for_write(&normalized_root),
+        crate::fs::resolve_for_write(&normalized_target),
+    ) {
+        (Ok(root), Ok(target)) => target.starts_with(root),
+        _ => false,
+    }
+}
+
+/// A path that looks like it is in the workspace but resolves outside it.
+fn escapes_through_symlink(workspace_root: &Path, target: &Path) -> bool {
+    normalize_path(target).starts_with(normalize_path(workspace_root))
+        && !is_within_workspace(workspace_root, target)
 }
 
 pub fn permission_path(workspace_root: &Path, target: &Path) -> String {
@@ -66,6 +126,13 @@ pub fn ensure_external_directory_permission(
     if is_within_workspace(workspace_root, target) {
         return Ok(());
     }
+    // Asking would show a workspace path while the tool touches another place
+    if escapes_through_symlink(workspace_root, target) {
+        return Err(GraphError::PermissionDenied {
+            permission: format!("external_directory {}", target.display()),
+            message: "path resolves outside the workspace through a symbolic link".to_string(),
+        });
+    }
 
     let parent = if kind == "directory" {
         target
@@ -117,3 +184,63 @@ pub fn truncate_text(text: &str, max_lines: usize, max_bytes: usize) -> (String,
     }
     (output, truncated)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    #[tokio::test]
+    async fn abort_interrupts_slow_tool_promptly() {
+        let cancel = CancellationToken::new();
+        let aborter = cancel.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            aborter.cancel("user aborted");
+        });
+
+        let started = Instant::now();
+        let result = run_cancellable(&cancel, tokio::time::sleep(Duration::from_secs(30))).await;
+        assert!(matches!(result, Err(GraphError::Aborted { .. })));
+        assert!(started.elapsed() < Duration::from_secs(2));
+        assert!(ensure_not_cancelled(&cancel).is_err());
+    }
+
+    #[tokio::test]
+    async fn finished_tool_is_not_cancelled() {
+        let cancel = CancellationToken::new();
+        let output = run_cancellable(&cancel, async { 42 }).await;
+        assert!(matches!(output, Ok(42)));
+        assert!(ensure_not_cancelled(&cancel).is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_out_of_the_workspace_are_outside_it() {
+        let workspace = tempfile::tempdir().unwrap();
+        let outside = tempfile::tempdir().unwrap();
+        std::fs::write(outside.path().join("secret.md"), "secret").unwrap();
+        std::os::unix::fs::symlink(outside.path(), workspace.path().join("link")).unwrap();
+        std::os::unix::fs::symlink(
+            outside.path().join("new.md"),
+            workspace.path().join("dangling.md"),
+        )
+        .unwrap();
+        let root = workspace.path();
+
+        assert!(is_within_workspace(
+            root,
+            &resolve_path(root, "notes/new.md")
+        ));
+        assert!(!is_within_workspace(root, &resolve_path(root, "../x.md")));
+        for escaping in ["link/secret.md", "link/new.md", "dangling.md"] {
+            let target = resolve_path(root, escaping);
+            assert!(!is_within_workspace(root, &target), "{}", escaping);
+            assert!(escapes_through_symlink(root, &target), "{}", escaping);
+        }
+        assert!(!escapes_through_symlink(
+            root,
+            &resolve_path(root, "../x.md")
+        ));
+    }
+}
@@ -1,3 +1,4 @@
+use crate::forge_runtime::dry_run::{simulate, SimulatedChange};
 use crate::forge_runtime::permissions::request_permission;
 use crate::forge_runtime::tools::shared::{
     ensure_external_directory_permission, parse_tool_input, permission_path, resolve_path,
@@ -7,13 +8,40 @@ use forge::runtime::error::{GraphError, GraphResult};
 use forge::runtime::tool::{ToolCall, ToolContext, ToolDefinition, ToolOutput, ToolRegistry};
 use serde::Deserialize;
 use serde_json::{json, Map};
+use std::io;
+use std::path::Path;
 use std::sync::Arc;
+use tokio::io::AsyncWriteExt;
+
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum WriteMode {
+    /// Create a new file, failing if it already exists.
+    Create,
+    /// Replace the file contents, creating the file if needed.
+    #[default]
+    Overwrite,
+    /// Append to the end of the file, creating it if needed.
+    Append,
+}
+
+impl WriteMode {
+    fn as_str(self) -> &'static str {
+        match self {
+            WriteMode::Create => "create",
+            WriteMode::Overwrite => "overwrite",
+            WriteMode::Append => "append",
+        }
+    }
+}
 
 #[derive(Deserialize)]
 struct WriteInput {
     #[serde(rename = "filePath")]
     file_path: String,
     content: String,
+    #[serde(default)]
+    mode: WriteMode,
 }
 
 pub fn register(registry: &mut ToolRegistry, env: ToolEnvironment) {
@@ -22,7 +50,8 @@ pub fn register(registry: &mut ToolRegistry, env: ToolEnvironment) {
         "type": "object",
         "properties": {
             "filePath": { "type": "string" },
-            "content": { "type": "string" }
+            "content": { "type": "string" },
+            "mode": { "type": "string", "enum": ["create", "overwrite", "append"] }
         },
         "required": ["filePath", "content"]
     }));
@@ -37,6 +66,12 @@ pub fn register(registry: &mut ToolRegistry, env: ToolEnvironment) {
 }
 
 async fn handle(call: ToolCall, ctx: ToolContext, env: ToolEnvironment) -> GraphResult<ToolOutput> {
+    if crate::fs::is_workspace_readonly() {
+        return Err(GraphError::ExecutionError {
+            node: format!("tool:{}", call.tool),
+            message: "The workspace is read-only; files cannot be modified.".to_string(),
+        });
+    }
     let input: WriteInput = parse_tool_input(&call)?;
     let target = resolve_path(&env.workspace_root, &input.file_path);
 
@@ -51,6 +86,7 @@ async fn handle(call: ToolCall, ctx: ToolContext, env: ToolEnvironment) -> Graph
     let pattern = permission_path(&env.workspace_root, &target);
     let mut metadata = Map::new();
     metadata.insert("filepath".to_string(), json!(target.display().to_string()));
+    metadata.insert("mode".to_string(), json!(input.mode.as_str()));
 
     request_permission(
         &ctx,
@@ -61,25 +97,211 @@ async fn handle(call: ToolCall, ctx: ToolContext, env: ToolEnvironment) -> Graph
         vec!["*".to_string()],
     )?;
 
-    let exists = tokio::fs::metadata(&target).await.is_ok();
-    if let Some(parent) = target.parent() {
-        tokio::fs::create_dir_all(parent)
-            .await
-            .map_err(|err| GraphError::ExecutionError {
+    let _lock = crate::fs::lock_path(&target).await;
+    let existing = match tokio::fs::metadata(&target).await {
+        Ok(meta) => Some(meta.len()),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => None,
+        Err(err) => {
+            return Err(GraphError::ExecutionError {
                 node: format!("tool:{}", call.tool),
-                message: format!("Failed to create directory: {}", err),
-            })?;
+                message: format!("Failed to inspect file: {}", err),
+            });
+        }
+    };
+    check_create(input.mode, &target, existing.is_some()).map_err(|message| {
+        GraphError::ExecutionError {
+            node: format!("tool:{}", call.tool),
+            message,
+        }
+    })?;
+
+    if let Some(log) = &env.dry_run {
+        let original = match existing {
+            Some(_) => tokio::fs::read_to_string(&target).await.ok(),
+            None => None,
+        };
+        let modified = planned_content(input.mode, original.as_deref(), &input.content);
+        let description = match (input.mode, &original) {
+            (WriteMode::Append, _) => format!(
+                "Would append {} bytes to {}.",
+                input.content.len(),
+                target.display()
+            ),
+            (_, Some(_)) => format!(
+                "Would overwrite {} ({} bytes).",
+                target.display(),
+                input.content.len()
+            ),
+            (_, None) => format!(
+                "Would create {} ({} bytes).",
+                target.display(),
+                input.content.len()
+            ),
+        };
+        return Ok(simulate(
+            log,
+            SimulatedChange::file("write", &target, original, modified, description),
+        ));
     }
-    tokio::fs::write(&target, input.content)
+
+    let bytes = write_content(&target, input.mode, &input.content, existing)
         .await
-        .map_err(|err| GraphError::ExecutionError {
+        .map_err(|message| GraphError::ExecutionError {
             node: format!("tool:{}", call.tool),
-            message: format!("Failed to write file: {}", err),
+            message,
         })?;
 
-    Ok(ToolOutput::text("Wrote file successfully.")
+    let message = match input.mode {
+        WriteMode::Append => format!("Appended to file successfully ({} bytes total).", bytes),
+        _ => format!("Wrote file successfully ({} bytes).", bytes),
+    };
+
+    Ok(ToolOutput::text(message)
         .with_mime_type("text/plain")
         .with_schema("tool.write.v1")
         .with_attribute("filepath", json!(target.display().to_string()))
-        .with_attribute("exists", json!(exists)))
+        .with_attribute("exists", json!(existing.is_some()))
+        .with_attribute("mode", json!(input.mode.as_str()))
+        .with_attribute("bytes", json!(bytes)))
+}
+
+/// Refuses `create` when the file already exists.
+fn check_create(mode: WriteMode, target: &Path, exists: bool) -> Result<(), String> {
+    if mode == WriteMode::Create && exists {
+        return Err(format!(
+            "File already exists: {}. Use mode \"overwrite\" or \"append\" to modify it.",
+            target.display()
+        ));
+    }
+    Ok(())
+}
+
+/// Writes `content` to `target` (whose size is `existing`, if present),
+/// creating parent directories. Returns the resulting file size in bytes.
+async fn write_content(
+    target: &Path,
+    mode: WriteMode,
+    content: &str,
+    existing: Option<u64>,
+) -> Result<u64, String> {
+    if let Some(parent) = target.parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(|err| format!("Failed to create directory: {}", err))?;
+    }
+    match mode {
+        WriteMode::Create | WriteMode::Overwrite => tokio::fs::write(target, content)
+            .await
+            .map(|_| content.len() as u64),
+        WriteMode::Append => append_content(target, content, existing.unwrap_or(0)).await,
+    }
+    .map_err(|err| format!("Failed to write file: {}", err))
+}
+
+/// The file contents a write would produce, mirroring `append_content` for appends.
+fn planned_content(mode: WriteMode, original: Option<&str>, content: &str) -> String {
+    match (mode, original) {
+        (WriteMode::Append, Some(original)) if !original.is_empty() => {
+            let separator = if original.ends_with('\n') { "" } else { "\n" };
+            format!("{}{}{}", original, separator, content)
+        }
+        _ => content.to_string(),
+    }
+}
+
+/// Appends `content` on its own line, creating the file when missing.
+/// Returns the resulting file size in bytes.
+async fn append_content(target: &Path, content: &str, existing_len: u64) -> io::Result<u64> {
+    let needs_separator = existing_len > 0 && !ends_with_newline(target).await?;
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(target)
+        .await?;
+    let mut written = 0u64;
+    if needs_separator {
+        file.write_all(b"\n").await?;
+        written += 1;
+    }
+    file.write_all(content.as_bytes()).await?;
+    file.flush().await?;
+    written += content.len() as u64;
+    Ok(existing_len + written)
+}
+
+async fn ends_with_newline(target: &Path) -> io::Result<bool> {
+    let bytes = tokio::fs::read(target).await?;
+    Ok(bytes.last() == Some(&b'\n'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn planned_content_matches_write_modes() {
+        assert_eq!(
+            planned_content(WriteMode::Overwrite, Some("old"), "new"),
+            "new"
+        );
+        assert_eq!(planned_content(WriteMode::Create, None, "new"), "new");
+        assert_eq!(planned_content(WriteMode::Append, Some("a"), "b"), "a\nb");
+        assert_eq!(planned_content(WriteMode::Append, Some("a\n"), "b"), "a\nb");
+        assert_eq!(planned_content(WriteMode::Append, None, "b"), "b");
+    }
+
+    async fn write(target: &Path, mode: WriteMode, content: &str) -> Result<u64, String> {
+        let existing = tokio::fs::metadata(target)
+            .await
+            .ok()
+            .map(|meta| meta.len());
+        check_create(mode, target, existing.is_some())?;
+        write_content(target, mode, content, existing).await
+    }
+
+    fn read(target: &Path) -> String {
+        std::fs::read_to_string(target).unwrap()
+    }
+
+    #[tokio::test]
+    async fn create_refuses_existing_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let note = dir.path().join("notes/new.md");
+        assert_eq!(write(&note, WriteMode::Create, "first").await, Ok(5));
+        assert_eq!(read(&note), "first");
+
+        let err = write(&note, WriteMode::Create, "second").await.unwrap_err();
+        assert!(err.starts_with("File already exists"), "{}", err);
+        assert_eq!(read(&note), "first");
+    }
+
+    #[tokio::test]
+    async fn overwrite_replaces_contents() {
+        let dir = tempfile::tempdir().unwrap();
+        let note = dir.path().join("a.md");
+        std::fs::write(&note, "a much longer original").unwrap();
+        assert_eq!(write(&note, WriteMode::Overwrite, "new").await, Ok(3));
+        assert_eq!(read(&note), "new");
+    }
+
+    #[tokio::test]
+    async fn append_creates_missing_files_and_starts_a_new_line() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = dir.path().join("logs/today.md");
+        assert_eq!(write(&log, WriteMode::Append, "- one").await, Ok(5));
+        assert_eq!(read(&log), "- one");
+
+        // No trailing newline: one is inserted before the new content
+        assert_eq!(write(&log, WriteMode::Append, "- two\n").await, Ok(12));
+        assert_eq!(read(&log), "- one\n- two\n");
+
+        // Already ends with a newline: nothing is added
+        assert_eq!(write(&log, WriteMode::Append, "- three").await, Ok(19));
+        assert_eq!(read(&log), "- one\n- two\n- three");
+
+        let empty = dir.path().join("empty.md");
+        std::fs::write(&empty, "").unwrap();
+        assert_eq!(write(&empty, WriteMode::Append, "x").await, Ok(1));
+        assert_eq!(read(&empty), "x");
+    }
 }
@@ -0,0 +1,358 @@
+//! Replayable trace of agent tool calls.
+//!
+//! Every tool invocation is appended, in call order, to
+//! `.lumina/agent-traces/<session>.jsonl` with its arguments, duration and
+//! result or error, so a misbehaving run can be exported and attached to a bug
+//! report. Arguments that look like credentials are redacted before writing,
+//! and large results are truncated.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Workspace-relative directory holding one trace file per session.
+const TRACE_DIR: &str = ".lumina/agent-traces";
+/// Results larger than this (serialized) are stored as a truncated string.
+const MAX_RESULT_CHARS: usize = 16 * 1024;
+const REDACTED: &str = "[REDACTED]";
+
+/// Argument names whose values are never written, compared case-insensitively
+/// after removing `-` and `_`.
+const SECRET_KEYS: &[&str] = &[
+    "token",
+    "accesstoken",
+    "refreshtoken",
+    "apikey",
+    "secret",
+    "clientsecret",
+    "password",
+    "passwd",
+    "authorization",
+    "cookie",
+    "credential",
+    "credentials",
+    "privatekey",
+];
+/// Prefixes of well-known token formats with the shortest token that follows
+/// them. A prefix only counts at the start of a word, so `task-list.md` keeps
+/// its `sk-`.
+const SECRET_PREFIXES: &[(&str, usize)] = &[
+    ("Bearer ", 1),
+    ("Basic ", 1),
+    ("sk-", MIN_KEY_LEN),
+    ("ghp_", MIN_KEY_LEN),
+    ("github_pat_", MIN_KEY_LEN),
+    ("xoxb-", MIN_KEY_LEN),
+];
+/// Shorter strings after an API key prefix are treated as ordinary text.
+const MIN_KEY_LEN: usize = 16;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TraceOutcome {
+    Success,
+    Error,
+}
+
+/// One traced tool call.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TraceEntry {
+    /// Position of the call within the session, starting at 1.
+    pub seq: u64,
+    /// Unix milliseconds when the call finished.
+    pub timestamp: i64,
+    pub session_id: String,
+    pub call_id: String,
+    pub tool: String,
+    /// Call arguments with secrets redacted.
+    pub args: Value,
+    pub duration_ms: u64,
+    pub outcome: TraceOutcome,
+    #[serde(default)]
+    pub result: Option<Value>,
+    #[serde(default)]
+    pub error: Option<String>,
+    /// Whether `result` was cut to [`MAX_RESULT_CHARS`].
+    #[serde(default)]
+    pub truncated: bool,
+}
+
+/// Appends tool call traces for one agent session.
+pub struct ToolTrace {
+    path: PathBuf,
+    session_id: String,
+    /// Sequence number of the last recorded call.
+    seq: Mutex<u64>,
+}
+
+pub fn trace_path(workspace_root: &Path, session_id: &str) -> PathBuf {
+    let file_name: String = session_id
+        .chars()
+        .map(|ch| {
+            if ch.is_ascii_alphanumeric() || ch == '-' || ch == '_' {
+                ch
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    workspace_root
+        .join(TRACE_DIR)
+        .join(format!("{}.jsonl", file_name))
+}
+
+impl ToolTrace {
+    /// Continues numbering after any calls already traced for the session,
+    /// e.g. when a run resumes after a permission prompt.
+    pub fn new(workspace_root: impl AsRef<Path>, session_id: impl Into<String>) -> Self {
+        let session_id = session_id.into();
+        let path = trace_path(workspace_root.as_ref(), &session_id);
+        let seq = fs::read_to_string(&path)
+            .map(|content| content.lines().filter(|line| !line.is_empty()).count() as u64)
+            .unwrap_or(0);
+        Self {
+            path,
+            session_id,
+            seq: Mutex::new(seq),
+        }
+    }
+
+    /// Records a finished call and returns the written entry.
+    pub fn record(
+        &self,
+        call_id: &str,
+        tool: &str,
+        args: &Value,
+        duration: Duration,
+        result: Result<&Value, &str>,
+    ) -> TraceEntry {
+        let (result, error, truncated) = match result {
+            Ok(value) => {
+                let (value, truncated) = truncate_result(redact(value));
+                (Some(value), None, truncated)
+            }
+            Err(err) => (None, Some(err.to_string()), false),
+        };
+        let mut seq = self.seq.lock().unwrap_or_else(|err| err.into_inner());
+        *seq += 1;
+        let entry = TraceEntry {
+            seq: *seq,
+            timestamp: chrono::Utc::now().timestamp_millis(),
+            session_id: self.session_id.clone(),
+            call_id: call_id.to_string(),
+            tool: tool.to_string(),
+            args: redact(args),
+            duration_ms: duration.as_millis() as u64,
+            outcome: if error.is_some() {
+                TraceOutcome::Error
+            } else {
+                TraceOutcome::Success
+            },
+            result,
+            error,
+            truncated,
+        };
+        // Written while holding the counter so lines stay in `seq` order
+        if let Err(err) = self.append(&entry) {
+            eprintln!("[Trace] Failed to write tool trace: {}", err);
+        }
+        entry
+    }
+
+    fn append(&self, entry: &TraceEntry) -> io::Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let line = serde_json::to_string(entry)?;
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        writeln!(file, "{}", line)
+    }
+}
+
+/// Loads the trace of one session in call order. A missing trace yields no
+/// entries; malformed lines are skipped.
+pub fn load_trace(workspace_root: &Path, session_id: &str) -> Result<Vec<TraceEntry>, String> {
+    let path = trace_path(workspace_root, session_id);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content =
+        fs::read_to_string(&path).map_err(|e| format!("Failed to read tool trace: {}", e))?;
+    let mut entries: Vec<TraceEntry> = content
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect();
+    entries.sort_by_key(|entry| entry.seq);
+    Ok(entries)
+}
+
+/// Copies `value`, replacing secret-looking arguments with a placeholder.
+pub fn redact(value: &Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(key, value)| {
+                    let value = if is_secret_key(key) && !value.is_null() {
+                        Value::String(REDACTED.to_string())
+                    } else {
+                        redact(value)
+                    };
+                    (key.clone(), value)
+                })
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(items.iter().map(redact).collect()),
+        Value::String(text) => Value::String(redact_text(text)),
+        other => other.clone(),
+    }
+}
+
+fn is_secret_key(key: &str) -> bool {
+    let normalized: String = key
+        .chars()
+        .filter(|ch| *ch != '-' && *ch != '_')
+        .collect::<String>()
+        .to_ascii_lowercase();
+    SECRET_KEYS.contains(&normalized.as_str())
+}
+
+/// Masks tokens with a known prefix inside free text, e.g. a `curl` command
+/// passed to the bash tool.
+fn redact_text(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some((start, prefix, min_len)) = SECRET_PREFIXES
+        .iter()
+        .filter_map(|(prefix, min_len)| rest.find(prefix).map(|start| (start, *prefix, *min_len)))
+        .min_by_key(|(start, _, _)| *start)
+    {
+        let before = rest[..start]
+            .chars()
+            .next_back()
+            .or(out.chars().next_back());
+        let at_word_start = before.is_none_or(|ch| !ch.is_alphanumeric() && ch != '_');
+        let token_start = start + prefix.len();
+        let token_len = rest[token_start..]
+            .find(|ch: char| ch.is_whitespace() || matches!(ch, '"' | '\'' | ',' | ';'))
+            .unwrap_or(rest.len() - token_start);
+        out.push_str(&rest[..token_start]);
+        if at_word_start && token_len >= min_len {
+            out.push_str(REDACTED);
+            rest = &rest[token_start + token_len..];
+        } else {
+            rest = &rest[token_start..];
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn truncate_result(value: Value) -> (Value, bool) {
+    let text = match &value {
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    };
+    if text.chars().count() <= MAX_RESULT_CHARS {
+        return (value, false);
+    }
+    let cut: String = text.chars().take(MAX_RESULT_CHARS).collect();
+    (Value::String(cut), true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn records_calls_in_order_and_resumes_numbering() {
+        let workspace = tempfile::tempdir().unwrap();
+        let trace = ToolTrace::new(workspace.path(), "s1");
+        trace.record(
+            "c1",
+            "read",
+            &json!({ "filePath": "a.md" }),
+            Duration::from_millis(12),
+            Ok(&json!("hello")),
+        );
+        trace.record(
+            "c2",
+            "write",
+            &json!({ "filePath": "b.md" }),
+            Duration::from_millis(3),
+            Err("denied"),
+        );
+
+        // A resumed run keeps appending after the existing calls
+        let resumed = ToolTrace::new(workspace.path(), "s1");
+        let entry = resumed.record(
+            "c3",
+            "glob",
+            &json!({ "pattern": "*" }),
+            Duration::ZERO,
+            Ok(&json!(["a.md"])),
+        );
+        assert_eq!(entry.seq, 3);
+
+        let entries = load_trace(workspace.path(), "s1").unwrap();
+        let calls: Vec<_> = entries
+            .iter()
+            .map(|entry| (entry.seq, entry.tool.as_str(), entry.outcome))
+            .collect();
+        assert_eq!(
+            calls,
+            vec![
+                (1, "read", TraceOutcome::Success),
+                (2, "write", TraceOutcome::Error),
+                (3, "glob", TraceOutcome::Success),
+            ]
+        );
+        assert_eq!(entries[0].duration_ms, 12);
+        assert_eq!(entries[0].result, Some(json!("hello")));
+        assert_eq!(entries[1].error.as_deref(), Some("denied"));
+        assert!(load_trace(workspace.path(), "s2").unwrap().is_empty());
+    }
+
+    #[test]
+    fn redacts_secrets_and_truncates_large_results() {
+        let args = json!({
+            "url": "https://api.example.com",
+            "headers": { "Authorization": "Bearer abc.def", "Accept": "text/plain" },
+            "api_key": "k-123",
+            "command": "curl -H 'Authorization: Bearer abc.def' -d token=sk-live1234567890abcdef x",
+            "items": [{ "password": "hunter2" }],
+        });
+        let redacted = redact(&args);
+        assert_eq!(redacted["url"], "https://api.example.com");
+        assert_eq!(redacted["headers"]["Authorization"], REDACTED);
+        assert_eq!(redacted["headers"]["Accept"], "text/plain");
+        assert_eq!(redacted["api_key"], REDACTED);
+        assert_eq!(redacted["items"][0]["password"], REDACTED);
+        assert_eq!(
+            redacted["command"],
+            "curl -H 'Authorization: Bearer [REDACTED]' -d token=sk-[REDACTED] x"
+        );
+        // Prefixes inside words and short strings are not keys
+        assert_eq!(
+            redact_text("see task-list.md and disk-usage, sk-short"),
+            "see task-list.md and disk-usage, sk-short"
+        );
+
+        let workspace = tempfile::tempdir().unwrap();
+        let trace = ToolTrace::new(workspace.path(), "s1");
+        let big = json!("x".repeat(MAX_RESULT_CHARS + 10));
+        let entry = trace.record("c1", "read", &json!({}), Duration::ZERO, Ok(&big));
+        assert!(entry.truncated);
+        assert_eq!(
+            entry.result.unwrap().as_str().unwrap().len(),
+            MAX_RESULT_CHARS
+        );
+    }
+}
@@ -0,0 +1,156 @@
+//! Advisory per-path write locks.
+//!
+//! Several writers can touch the same note at once: the editor (`save_file`),
+//! the agent's `write`/`edit` tools and WebDAV sync downloads. Each of them
+//! holds the lock for the file's canonical path across its read-modify-write,
+//! so concurrent writes to one note serialize instead of losing updates.
+//!
+//! The locks are advisory: code that writes without acquiring them is not
+//! blocked.
+//!
+//! # Lock ordering
+//!
+//! A task that needs several files must acquire them in one call to
+//! [`lock_paths`], which takes the locks in sorted key order. Never call
+//! [`lock_path`] while already holding a file lock: two tasks locking the same
+//! pair of files in opposite order would deadlock.
+
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, Weak};
+use tokio::sync::{Mutex as AsyncMutex, OwnedMutexGuard};
+
+static FILE_LOCKS: Lazy<Mutex<HashMap<PathBuf, Weak<AsyncMutex<()>>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Holds the write lock of one file until dropped.
+#[derive(Debug)]
+pub struct FileLockGuard {
+    key: PathBuf,
+    _guard: OwnedMutexGuard<()>,
+}
+
+impl FileLockGuard {
+    /// The canonical path this guard locks.
+    pub fn path(&self) -> &Path {
+        &self.key
+    }
+}
+
+/// Canonical key for `path`.
+///
+/// The longest existing ancestor is canonicalized and the rest of the path is
+/// appended, so a file that does not exist yet gets the same key it will have
+/// once created.
+pub fn lock_key(path: &Path) -> PathBuf {
+    let absolute = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        std::env::current_dir()
+            .map(|dir| dir.join(path))
+            .unwrap_or_else(|_| path.to_path_buf())
+    };
+    for ancestor in absolute.ancestors() {
+        if let Ok(canonical) = std::fs::canonicalize(ancestor) {
+            let rest = absolute.strip_prefix(ancestor).unwrap_or(Path::new(""));
+            return if rest.as_os_str().is_empty() {
+                canonical
+            } else {
+                canonical.join(rest)
+            };
+        }
+    }
+    absolute
+}
+
+fn mutex_for(key: &Path) -> Arc<AsyncMutex<()>> {
+    let mut locks = FILE_LOCKS.lock().unwrap_or_else(|err| err.into_inner());
+    if let Some(mutex) = locks.get(key).and_then(Weak::upgrade) {
+        return mutex;
+    }
+    // Drop entries whose guards are all gone before adding a new one
+    locks.retain(|_, mutex| mutex.strong_count() > 0);
+    let mutex = Arc::new(AsyncMutex::new(()));
+    locks.insert(key.to_path_buf(), Arc::downgrade(&mutex));
+    mutex
+}
+
+/// Waits for the write lock of `path`.
+pub async fn lock_path(path: &Path) -> FileLockGuard {
+    let key = lock_key(path);
+    let guard = mutex_for(&key).lock_owned().await;
+    FileLockGuard { key, _guard: guard }
+}
+
+/// Waits for the write locks of all `paths`, taken in sorted key order.
+///
+/// Duplicate paths (including different spellings of the same file) are
+/// locked once.
+pub async fn lock_paths<P: AsRef<Path>>(paths: &[P]) -> Vec<FileLockGuard> {
+    let mut keys: Vec<PathBuf> = paths.iter().map(|path| lock_key(path.as_ref())).collect();
+    keys.sort();
+    keys.dedup();
+    let mut guards = Vec::with_capacity(keys.len());
+    for key in keys {
+        let guard = mutex_for(&key).lock_owned().await;
+        guards.push(FileLockGuard { key, _guard: guard });
+    }
+    guards
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tempfile::TempDir;
+
+    #[test]
+    fn key_is_stable_before_and_after_creation() {
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("notes").join("a.md");
+        let before = lock_key(&file);
+
+        std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+        std::fs::write(&file, "a").unwrap();
+        assert_eq!(lock_key(&file), before);
+        assert_eq!(
+            lock_key(&dir.path().join("notes").join("..").join("notes/a.md")),
+            before
+        );
+    }
+
+    #[tokio::test]
+    async fn writers_to_the_same_file_serialize() {
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("a.md");
+
+        let first = lock_path(&file).await;
+        let waiter = {
+            let file = file.clone();
+            tokio::spawn(async move { lock_path(&file).await.path().to_path_buf() })
+        };
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!waiter.is_finished());
+
+        // Another file is not blocked
+        let other = lock_path(&dir.path().join("b.md")).await;
+        drop(other);
+
+        drop(first);
+        assert_eq!(waiter.await.unwrap(), lock_key(&file));
+    }
+
+    #[tokio::test]
+    async fn lock_paths_dedups_and_sorts() {
+        let dir = TempDir::new().unwrap();
+        let a = dir.path().join("a.md");
+        let b = dir.path().join("b.md");
+        let guards = lock_paths(&[b.clone(), a.clone(), dir.path().join("./b.md")]).await;
+        let locked: Vec<_> = guards
+            .iter()
+            .map(|guard| guard.path().to_path_buf())
+            .collect();
+        assert_eq!(locked, vec![lock_key(&a), lock_key(&b)]);
+    }
+}
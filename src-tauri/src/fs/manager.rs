@@ -2,10 +2,14 @@ use once_cell::sync::Lazy;
 use serde::Serialize;
 use std::collections::HashSet;
 use std::env;
+use std::ffi::OsString;
 use std::fs;
-use std::path::{Path, PathBuf};
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::RwLock;
 
+use super::readonly::ensure_writable;
 use crate::error::AppError;
 
 static RUNTIME_ALLOWED_ROOTS: Lazy<RwLock<Vec<PathBuf>>> = Lazy::new(|| RwLock::new(Vec::new()));
@@ -40,19 +44,58 @@ fn absolute_path(path: &Path) -> Result<PathBuf, AppError> {
     }
 }
 
-fn canonicalize_existing_ancestor(path: &Path) -> Result<PathBuf, AppError> {
-    let mut cursor = path.to_path_buf();
-    loop {
-        if cursor.exists() {
-            return fs::canonicalize(&cursor).map_err(AppError::from);
-        }
-        if !cursor.pop() {
-            break;
+/// Same limit as Linux's MAXSYMLINKS
+const MAX_SYMLINK_HOPS: usize = 40;
+
+/// Resolve `path` to where a write would actually land: the longest existing
+/// prefix is canonicalized (following symlinks) and the missing components are
+/// appended. A dangling symlink is followed to its target, since creating the
+/// file would write through it.
+pub(crate) fn resolve_for_write(path: &Path) -> Result<PathBuf, AppError> {
+    let mut current = path.to_path_buf();
+    for _ in 0..=MAX_SYMLINK_HOPS {
+        let mut existing = current.clone();
+        let mut missing: Vec<OsString> = Vec::new();
+        loop {
+            if let Ok(meta) = fs::symlink_metadata(&existing) {
+                if meta.file_type().is_symlink() && !existing.exists() {
+                    let target = fs::read_link(&existing)?;
+                    let mut next = match existing.parent() {
+                        Some(parent) => parent.join(target),
+                        None => target,
+                    };
+                    next.extend(missing.iter().rev());
+                    current = next;
+                    break;
+                }
+                let mut resolved = fs::canonicalize(&existing)?;
+                resolved.extend(missing.iter().rev());
+                return Ok(resolved);
+            }
+            match existing.components().next_back() {
+                Some(Component::Normal(name)) => {
+                    missing.push(name.to_os_string());
+                    existing.pop();
+                }
+                // `missing/..` cannot be resolved safely before `missing` exists
+                Some(Component::ParentDir) => {
+                    return Err(AppError::InvalidPath(format!(
+                        "Path contains '..' after a missing component: {}",
+                        path.display()
+                    )));
+                }
+                _ => {
+                    return Err(AppError::InvalidPath(
+                        "Path has no existing ancestor".to_string(),
+                    ));
+                }
+            }
         }
     }
-    Err(AppError::InvalidPath(
-        "Path has no existing ancestor".to_string(),
-    ))
+    Err(AppError::InvalidPath(format!(
+        "Too many levels of symbolic links: {}",
+        path.display()
+    )))
 }
 
 fn normalize_roots(paths: Vec<PathBuf>) -> Vec<PathBuf> {
@@ -122,7 +165,7 @@ fn default_allowed_roots() -> Vec<PathBuf> {
     normalize_roots(roots)
 }
 
-fn runtime_allowed_roots() -> Vec<PathBuf> {
+pub(super) fn runtime_allowed_roots() -> Vec<PathBuf> {
     match RUNTIME_ALLOWED_ROOTS.read() {
         Ok(guard) => guard.clone(),
         Err(_) => Vec::new(),
@@ -148,12 +191,17 @@ pub fn set_runtime_allowed_roots(roots: Vec<String>) -> Result<(), AppError> {
     Ok(())
 }
 
+/// Check that `path` resolves inside an allowed root.
+///
+/// The check runs on the fully resolved path, so a symlink inside a root that
+/// points outside of it (including a dangling one a write would create through)
+/// is rejected.
 pub fn ensure_allowed_path(path: &Path, must_exist: bool) -> Result<(), AppError> {
     let absolute = absolute_path(path)?;
     let candidate = if must_exist {
         fs::canonicalize(&absolute).map_err(AppError::from)?
     } else {
-        canonicalize_existing_ancestor(&absolute)?
+        resolve_for_write(&absolute)?
     };
 
     let roots = allowed_roots();
@@ -184,13 +232,90 @@ pub fn read_file_content(path: &str) -> Result<String, AppError> {
 }
 
 /// Write content to file, creating parent directories if needed
+///
+/// The write is atomic: a crash mid-write leaves the previous content intact.
 pub fn write_file_content(path: &str, content: &str) -> Result<(), AppError> {
+    write_file_content_with_backup(path, content, false)
+}
+
+/// Atomically write content to file, optionally keeping the previous version
+/// as `<name>.bak` next to it
+pub fn write_file_content_with_backup(
+    path: &str,
+    content: &str,
+    backup: bool,
+) -> Result<(), AppError> {
     let path = Path::new(path);
     ensure_allowed_path(path, false)?;
-    if let Some(parent) = path.parent() {
+    ensure_writable("write files")?;
+    // Write through symlinks instead of replacing the link with a regular file
+    let target = if fs::symlink_metadata(path).is_ok_and(|meta| meta.file_type().is_symlink()) {
+        let resolved = fs::canonicalize(path)?;
+        ensure_allowed_path(&resolved, true)?;
+        resolved
+    } else {
+        path.to_path_buf()
+    };
+    if let Some(parent) = target.parent() {
         fs::create_dir_all(parent)?;
     }
-    fs::write(path, content).map_err(AppError::from)
+
+    if backup && target.is_file() {
+        let backup_path = sibling_path(&target, ".bak")?;
+        ensure_allowed_path(&backup_path, false)?;
+        fs::copy(&target, &backup_path)?;
+    }
+    atomic_write(&target, content.as_bytes())
+}
+
+static TEMP_FILE_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// `<dir>/<name><suffix>` for a file path
+fn sibling_path(path: &Path, suffix: &str) -> Result<PathBuf, AppError> {
+    let name = path
+        .file_name()
+        .ok_or_else(|| AppError::InvalidPath(format!("Invalid file path: {}", path.display())))?;
+    let mut sibling = name.to_os_string();
+    sibling.push(suffix);
+    Ok(path.with_file_name(sibling))
+}
+
+/// Write to a temp file in the same directory, fsync, then rename over `path`
+fn atomic_write(path: &Path, bytes: &[u8]) -> Result<(), AppError> {
+    let suffix = format!(
+        ".{}-{}.tmp",
+        std::process::id(),
+        TEMP_FILE_COUNTER.fetch_add(1, Ordering::Relaxed)
+    );
+    let temp = sibling_path(path, &suffix)?;
+    ensure_allowed_path(&temp, false)?;
+
+    let result = (|| -> std::io::Result<()> {
+        let mut file = fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&temp)?;
+        if let Ok(meta) = fs::metadata(path) {
+            file.set_permissions(meta.permissions())?;
+        }
+        file.write_all(bytes)?;
+        file.sync_all()?;
+        drop(file);
+        fs::rename(&temp, path)
+    })();
+    if let Err(err) = result {
+        let _ = fs::remove_file(&temp);
+        return Err(err.into());
+    }
+
+    // Persist the rename itself (not supported for directories on Windows)
+    #[cfg(unix)]
+    if let Some(parent) = path.parent() {
+        if let Ok(dir) = fs::File::open(parent) {
+            let _ = dir.sync_all();
+        }
+    }
+    Ok(())
 }
 
 /// Check whether a file or directory exists under allowed roots.
@@ -200,6 +325,40 @@ pub fn path_exists_in_allowed_roots(path: &str) -> Result<bool, AppError> {
     Ok(path.exists())
 }
 
+/// Skip hidden files and directories (except .lumina) and common
+/// non-user directories
+pub(super) fn is_skipped_entry(name: &str) -> bool {
+    (name.starts_with('.') && name != ".lumina") || name == "node_modules" || name == "target"
+}
+
+/// Build a file tree entry from a directory entry
+pub(super) fn file_entry(
+    entry: &fs::DirEntry,
+    children: Option<Vec<FileEntry>>,
+) -> Result<FileEntry, AppError> {
+    let path = entry.path();
+    let metadata = entry.metadata()?;
+    let is_dir = path.is_dir();
+    Ok(FileEntry {
+        name: entry.file_name().to_string_lossy().to_string(),
+        path: path.to_string_lossy().to_string(),
+        is_dir,
+        size: (!is_dir).then_some(metadata.len()),
+        modified_at: metadata_timestamp(&metadata, "modified"),
+        created_at: metadata_timestamp(&metadata, "created"),
+        children,
+    })
+}
+
+/// Sort: directories first, then files, alphabetically
+pub(super) fn compare_entries(a: &FileEntry, b: &FileEntry) -> std::cmp::Ordering {
+    match (a.is_dir, b.is_dir) {
+        (true, false) => std::cmp::Ordering::Less,
+        (false, true) => std::cmp::Ordering::Greater,
+        _ => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+    }
+}
+
 /// List directory contents recursively (all files)
 pub fn list_dir_recursive(path: &str) -> Result<Vec<FileEntry>, AppError> {
     let root = Path::new(path);
@@ -215,53 +374,20 @@ pub fn list_dir_recursive(path: &str) -> Result<Vec<FileEntry>, AppError> {
 
     for entry in fs::read_dir(root)? {
         let entry = entry?;
-        let path = entry.path();
-        let name = entry.file_name().to_string_lossy().to_string();
-
-        // Skip hidden files and directories (except .lumina)
-        if name.starts_with('.') && name != ".lumina" {
-            continue;
-        }
-
-        // Skip node_modules and other common non-user directories
-        if name == "node_modules" || name == "target" || name == ".git" {
+        if is_skipped_entry(&entry.file_name().to_string_lossy()) {
             continue;
         }
 
-        if path.is_dir() {
-            let metadata = entry.metadata()?;
-            let children = list_dir_recursive(&path.to_string_lossy())?;
-            // Include all directories (including empty ones)
-            entries.push(FileEntry {
-                name,
-                path: path.to_string_lossy().to_string(),
-                is_dir: true,
-                size: None,
-                modified_at: metadata_timestamp(&metadata, "modified"),
-                created_at: metadata_timestamp(&metadata, "created"),
-                children: Some(children),
-            });
+        // Include all directories (including empty ones) and files
+        let children = if entry.path().is_dir() {
+            Some(list_dir_recursive(&entry.path().to_string_lossy())?)
         } else {
-            let metadata = entry.metadata()?;
-            // Include all files
-            entries.push(FileEntry {
-                name,
-                path: path.to_string_lossy().to_string(),
-                is_dir: false,
-                size: Some(metadata.len()),
-                modified_at: metadata_timestamp(&metadata, "modified"),
-                created_at: metadata_timestamp(&metadata, "created"),
-                children: None,
-            });
-        }
+            None
+        };
+        entries.push(file_entry(&entry, children)?);
     }
 
-    // Sort: directories first, then files, alphabetically
-    entries.sort_by(|a, b| match (a.is_dir, b.is_dir) {
-        (true, false) => std::cmp::Ordering::Less,
-        (false, true) => std::cmp::Ordering::Greater,
-        _ => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
-    });
+    entries.sort_by(compare_entries);
 
     Ok(entries)
 }
@@ -270,6 +396,7 @@ pub fn list_dir_recursive(path: &str) -> Result<Vec<FileEntry>, AppError> {
 pub fn create_new_file(path: &str) -> Result<(), AppError> {
     let path = Path::new(path);
     ensure_allowed_path(path, false)?;
+    ensure_writable("create files")?;
     if path.exists() {
         return Err(AppError::FileExists(path.display().to_string()));
     }
@@ -279,22 +406,11 @@ pub fn create_new_file(path: &str) -> Result<(), AppError> {
     fs::write(path, "").map_err(AppError::from)
 }
 
-/// Delete a file or directory (move to trash/recycle bin)
-pub fn delete_entry(path: &str) -> Result<(), AppError> {
-    let path = Path::new(path);
-    ensure_allowed_path(path, true)?;
-    if !path.exists() {
-        return Err(AppError::FileNotFound(path.display().to_string()));
-    }
-    // 移动到回收站而非永久删除
-    trash::delete(path)?;
-    Ok(())
-}
-
 /// Create a new directory
 pub fn create_new_dir(path: &str) -> Result<(), AppError> {
     let path = Path::new(path);
     ensure_allowed_path(path, false)?;
+    ensure_writable("create folders")?;
     if path.exists() {
         return Err(AppError::FileExists(path.display().to_string()));
     }
@@ -307,6 +423,7 @@ pub fn rename_entry(old_path: &str, new_path: &str) -> Result<(), AppError> {
     let new = Path::new(new_path);
     ensure_allowed_path(old, true)?;
     ensure_allowed_path(new, false)?;
+    ensure_writable("rename entries")?;
     if !old.exists() {
         return Err(AppError::FileNotFound(old_path.to_string()));
     }
@@ -326,6 +443,7 @@ pub fn move_file_to_folder(source: &str, target_folder: &str) -> Result<String,
     let target_folder_path = Path::new(target_folder);
     ensure_allowed_path(source_path, true)?;
     ensure_allowed_path(target_folder_path, true)?;
+    ensure_writable("move entries")?;
 
     // Check source exists and is a file
     if !source_path.exists() {
@@ -371,6 +489,7 @@ pub fn move_folder_to_folder(source: &str, target_folder: &str) -> Result<String
     let target_folder_path = Path::new(target_folder);
     ensure_allowed_path(source_path, true)?;
     ensure_allowed_path(target_folder_path, true)?;
+    ensure_writable("move entries")?;
 
     // Check source exists and is a directory
     if !source_path.exists() {
@@ -456,6 +575,35 @@ mod tests {
         });
     }
 
+    #[test]
+    fn atomic_write_keeps_backup_and_cleans_up_temp_files() {
+        let dir = TempDir::new().expect("temp dir");
+        let file_path = dir.path().join("note.md");
+        let path = file_path.to_string_lossy().to_string();
+        with_allowed_root(dir.path(), || {
+            write_file_content(&path, "v1").expect("first write");
+            write_file_content_with_backup(&path, "v2", true).expect("write with backup");
+            assert_eq!(fs::read_to_string(&file_path).unwrap(), "v2");
+            assert_eq!(
+                fs::read_to_string(dir.path().join("note.md.bak")).unwrap(),
+                "v1"
+            );
+
+            // Renaming over a directory fails; the temp file must not be left behind
+            let blocked = dir.path().join("blocked.md");
+            fs::create_dir(&blocked).unwrap();
+            write_file_content(blocked.to_string_lossy().as_ref(), "x")
+                .expect_err("cannot replace a directory");
+
+            let mut names: Vec<String> = fs::read_dir(dir.path())
+                .unwrap()
+                .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+                .collect();
+            names.sort();
+            assert_eq!(names, vec!["blocked.md", "note.md", "note.md.bak"]);
+        });
+    }
+
     #[test]
     fn rejects_access_outside_allowed_root() {
         let allowed = TempDir::new().expect("allowed temp dir");
@@ -480,6 +628,52 @@ mod tests {
         });
     }
 
+    #[cfg(unix)]
+    #[test]
+    fn rejects_symlink_escaping_allowed_root() {
+        let vault = TempDir::new().expect("vault temp dir");
+        let outside = TempDir::new().expect("outside temp dir");
+        let etc_link = vault.path().join("etc");
+        std::os::unix::fs::symlink("/etc", &etc_link).expect("symlink to /etc");
+        // Dangling link: writing through it would create a file outside the vault
+        let dangling = vault.path().join("new.md");
+        let escaped = outside.path().join("new.md");
+        std::os::unix::fs::symlink(&escaped, &dangling).expect("dangling symlink");
+        let inner = vault.path().join("inner");
+        fs::create_dir(&inner).unwrap();
+        std::os::unix::fs::symlink(&inner, vault.path().join("alias")).unwrap();
+
+        with_allowed_root(vault.path(), || {
+            let passwd = etc_link.join("passwd");
+            let err = read_file_content(passwd.to_string_lossy().as_ref())
+                .expect_err("read through symlink denied");
+            assert!(matches!(err, AppError::InvalidPath(_)));
+            let err = write_file_content(
+                etc_link
+                    .join("lumina-escape.txt")
+                    .to_string_lossy()
+                    .as_ref(),
+                "nope",
+            )
+            .expect_err("write through symlink denied");
+            assert!(matches!(err, AppError::InvalidPath(_)));
+
+            let err = write_file_content(dangling.to_string_lossy().as_ref(), "nope")
+                .expect_err("write through dangling symlink denied");
+            assert!(matches!(err, AppError::InvalidPath(_)));
+            assert!(!escaped.exists());
+
+            let sneaky = vault.path().join("missing/../../escape.md");
+            assert!(ensure_allowed_path(&sneaky, false).is_err());
+
+            // Links that stay inside the root keep working
+            let aliased = vault.path().join("alias").join("note.md");
+            write_file_content(aliased.to_string_lossy().as_ref(), "ok")
+                .expect("write through inner symlink");
+            assert_eq!(fs::read_to_string(inner.join("note.md")).unwrap(), "ok");
+        });
+    }
+
     #[test]
     fn path_exists_rejects_outside_allowed_root() {
         let allowed = TempDir::new().expect("allowed temp dir");
@@ -1,4 +1,14 @@
+mod lock;
 mod manager;
+mod readonly;
+mod recycle;
+mod scan;
+mod thumbnail;
 pub mod watcher;
 
+pub use lock::*;
 pub use manager::*;
+pub use readonly::*;
+pub use recycle::*;
+pub use scan::*;
+pub use thumbnail::*;
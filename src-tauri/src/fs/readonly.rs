@@ -0,0 +1,49 @@
+//! Read-only workspace mode.
+//!
+//! While enabled, every write, delete and move in this module, the agent's
+//! `write`/`edit` tools, agent undo/redo and WebDAV sync refuse with
+//! [`AppError::ReadOnly`].
+//! Reads, listings and searches keep working.
+
+#[cfg(test)]
+use std::cell::Cell;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::error::AppError;
+
+static WORKSPACE_READONLY: AtomicBool = AtomicBool::new(false);
+
+#[cfg(test)]
+thread_local! {
+    /// Read-only mode for tests, scoped to the test's thread so parallel tests
+    /// that write are unaffected.
+    static TEST_READONLY: Cell<bool> = const { Cell::new(false) };
+}
+
+pub fn set_workspace_readonly(readonly: bool) {
+    WORKSPACE_READONLY.store(readonly, Ordering::SeqCst);
+}
+
+pub fn is_workspace_readonly() -> bool {
+    #[cfg(test)]
+    if TEST_READONLY.with(Cell::get) {
+        return true;
+    }
+    WORKSPACE_READONLY.load(Ordering::SeqCst)
+}
+
+/// Enables read-only mode for the current thread only.
+#[cfg(test)]
+pub fn set_thread_readonly(readonly: bool) {
+    TEST_READONLY.with(|flag| flag.set(readonly));
+}
+
+/// Fails with [`AppError::ReadOnly`] when the workspace is read-only;
+/// `action` describes the refused operation.
+pub fn ensure_writable(action: &str) -> Result<(), AppError> {
+    if is_workspace_readonly() {
+        Err(AppError::ReadOnly(format!("cannot {}", action)))
+    } else {
+        Ok(())
+    }
+}
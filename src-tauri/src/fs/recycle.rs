@@ -0,0 +1,252 @@
+//! Trash and restore
+//!
+//! Deleting moves entries to the OS trash by default. When that fails (e.g. on
+//! network drives without a trash can), entries are moved to `.lumina/trash/`
+//! inside the workspace root instead, with a sidecar file recording the
+//! original path so they can be restored.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::manager::{ensure_allowed_path, runtime_allowed_roots};
+use super::readonly::ensure_writable;
+use crate::error::AppError;
+
+/// Workspace-relative folder used when the OS trash is unavailable
+pub const WORKSPACE_TRASH_DIR: &str = ".lumina/trash";
+
+const TRASH_INFO_SUFFIX: &str = ".trashinfo.json";
+
+/// Where a deleted entry went
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TrashLocation {
+    System,
+    Workspace,
+}
+
+/// A deleted entry that can be restored
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrashedEntry {
+    pub original_path: String,
+    pub location: TrashLocation,
+    /// Path inside the workspace trash (`None` for the OS trash)
+    pub trashed_path: Option<String>,
+    /// Deletion time (unix milliseconds)
+    pub deleted_at: i64,
+}
+
+#[derive(Serialize, Deserialize)]
+struct TrashInfo {
+    original_path: String,
+    deleted_at: i64,
+}
+
+/// Delete a file or directory
+///
+/// Moves it to the trash unless `permanent` is set; returns where it went.
+pub fn delete_entry(path: &str, permanent: bool) -> Result<Option<TrashedEntry>, AppError> {
+    let path = Path::new(path);
+    ensure_allowed_path(path, true)?;
+    ensure_writable("delete entries")?;
+    if !path.exists() {
+        return Err(AppError::FileNotFound(path.display().to_string()));
+    }
+
+    if permanent {
+        if path.is_dir() {
+            fs::remove_dir_all(path)?;
+        } else {
+            fs::remove_file(path)?;
+        }
+        return Ok(None);
+    }
+
+    let deleted_at = chrono::Utc::now().timestamp_millis();
+    match trash::delete(path) {
+        Ok(()) => Ok(Some(TrashedEntry {
+            original_path: path.to_string_lossy().to_string(),
+            location: TrashLocation::System,
+            trashed_path: None,
+            deleted_at,
+        })),
+        Err(err) => {
+            let canonical = fs::canonicalize(path)?;
+            match workspace_root_for(&canonical) {
+                Some(root) if !canonical.starts_with(root.join(WORKSPACE_TRASH_DIR)) => {
+                    move_to_workspace_trash(&root, path, deleted_at).map(Some)
+                }
+                _ => Err(err.into()),
+            }
+        }
+    }
+}
+
+/// Restore a trashed entry to its original path; returns the restored path
+pub fn restore_entry(original_path: &str, trashed_path: Option<&str>) -> Result<String, AppError> {
+    let original = Path::new(original_path);
+    ensure_allowed_path(original, false)?;
+    ensure_writable("restore entries")?;
+    if original.exists() {
+        return Err(AppError::FileExists(original.display().to_string()));
+    }
+
+    match trashed_path {
+        Some(trashed) => {
+            let trashed = Path::new(trashed);
+            ensure_allowed_path(trashed, true)?;
+            let in_trash = trashed
+                .parent()
+                .is_some_and(|parent| parent.ends_with(WORKSPACE_TRASH_DIR));
+            if !in_trash {
+                return Err(AppError::InvalidPath(format!(
+                    "Not a workspace trash entry: {}",
+                    trashed.display()
+                )));
+            }
+            restore_from_workspace_trash(original, trashed)?;
+        }
+        None => restore_from_system_trash(original)?,
+    }
+    Ok(original.to_string_lossy().to_string())
+}
+
+/// The most specific workspace root containing `path`
+fn workspace_root_for(path: &Path) -> Option<PathBuf> {
+    runtime_allowed_roots()
+        .into_iter()
+        .filter(|root| path.starts_with(root))
+        .max_by_key(|root| root.components().count())
+}
+
+fn trash_info_path(trashed: &Path) -> PathBuf {
+    let mut name = trashed.file_name().unwrap_or_default().to_os_string();
+    name.push(TRASH_INFO_SUFFIX);
+    trashed.with_file_name(name)
+}
+
+fn move_to_workspace_trash(
+    root: &Path,
+    path: &Path,
+    deleted_at: i64,
+) -> Result<TrashedEntry, AppError> {
+    let trash_dir = root.join(WORKSPACE_TRASH_DIR);
+    fs::create_dir_all(&trash_dir)?;
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .ok_or_else(|| AppError::InvalidPath(format!("Invalid path: {}", path.display())))?;
+    let trashed = (0..)
+        .map(|counter| {
+            let id = if counter == 0 {
+                format!("{}-{}", deleted_at, name)
+            } else {
+                format!("{}-{}-{}", deleted_at, counter, name)
+            };
+            trash_dir.join(id)
+        })
+        .find(|candidate| !candidate.exists() && !trash_info_path(candidate).exists())
+        .expect("unbounded counter always finds a free name");
+
+    let original_path = path.to_string_lossy().to_string();
+    let info = TrashInfo {
+        original_path: original_path.clone(),
+        deleted_at,
+    };
+    let info_json = serde_json::to_string(&info).map_err(|e| AppError::Parse(e.to_string()))?;
+    fs::write(trash_info_path(&trashed), info_json)?;
+    if let Err(err) = fs::rename(path, &trashed) {
+        let _ = fs::remove_file(trash_info_path(&trashed));
+        return Err(err.into());
+    }
+
+    Ok(TrashedEntry {
+        original_path,
+        location: TrashLocation::Workspace,
+        trashed_path: Some(trashed.to_string_lossy().to_string()),
+        deleted_at,
+    })
+}
+
+fn restore_from_workspace_trash(original: &Path, trashed: &Path) -> Result<(), AppError> {
+    if let Some(parent) = original.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::rename(trashed, original)?;
+    let _ = fs::remove_file(trash_info_path(trashed));
+    Ok(())
+}
+
+#[cfg(any(
+    target_os = "windows",
+    all(
+        unix,
+        not(target_os = "macos"),
+        not(target_os = "ios"),
+        not(target_os = "android")
+    )
+))]
+fn restore_from_system_trash(original: &Path) -> Result<(), AppError> {
+    let item = trash::os_limited::list()?
+        .into_iter()
+        .filter(|item| item.original_path() == original)
+        .max_by_key(|item| item.time_deleted)
+        .ok_or_else(|| AppError::FileNotFound(format!("{} (in trash)", original.display())))?;
+    trash::os_limited::restore_all([item])?;
+    Ok(())
+}
+
+#[cfg(not(any(
+    target_os = "windows",
+    all(
+        unix,
+        not(target_os = "macos"),
+        not(target_os = "ios"),
+        not(target_os = "android")
+    )
+)))]
+fn restore_from_system_trash(_original: &Path) -> Result<(), AppError> {
+    Err(AppError::InvalidPath(
+        "Restoring from the system trash is not supported on this platform".to_string(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn workspace_trash_round_trip() {
+        let root = TempDir::new().expect("temp dir");
+        let note = root.path().join("notes").join("a.md");
+        fs::create_dir_all(note.parent().unwrap()).unwrap();
+        fs::write(&note, "hello").unwrap();
+
+        let first = move_to_workspace_trash(root.path(), &note, 42).expect("trash");
+        assert_eq!(first.location, TrashLocation::Workspace);
+        let trashed = PathBuf::from(first.trashed_path.clone().unwrap());
+        assert_eq!(
+            trashed,
+            root.path().join(WORKSPACE_TRASH_DIR).join("42-a.md")
+        );
+        assert!(!note.exists());
+
+        // Same name deleted again in the same millisecond
+        fs::write(&note, "again").unwrap();
+        let second = move_to_workspace_trash(root.path(), &note, 42).expect("trash again");
+        assert!(second.trashed_path.unwrap().ends_with("42-1-a.md"));
+
+        let info: TrashInfo =
+            serde_json::from_str(&fs::read_to_string(trash_info_path(&trashed)).unwrap()).unwrap();
+        assert_eq!(info.original_path, note.to_string_lossy());
+
+        restore_from_workspace_trash(&note.with_file_name("b.md"), &trashed).expect("restore");
+        assert_eq!(
+            fs::read_to_string(note.with_file_name("b.md")).unwrap(),
+            "hello"
+        );
+        assert!(!trash_info_path(&trashed).exists());
+    }
+}
@@ -0,0 +1,208 @@
+//! Streaming directory scan
+//!
+//! `list_dir_recursive` builds the whole tree before returning, which stalls
+//! the UI on very large vaults. A scan walks the tree on a background thread
+//! and emits flat `fs:entry-batch` events (parents before their children),
+//! followed by one `fs:scan-complete` event. Scans can be cancelled by id.
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Emitter};
+
+use super::manager::{
+    compare_entries, ensure_allowed_path, file_entry, is_skipped_entry, FileEntry,
+};
+use crate::error::AppError;
+
+/// Event carrying a chunk of scanned entries
+pub const ENTRY_BATCH_EVENT: &str = "fs:entry-batch";
+
+/// Event emitted once a scan finishes or is cancelled
+pub const SCAN_COMPLETE_EVENT: &str = "fs:scan-complete";
+
+/// Entries per `fs:entry-batch` event
+pub const SCAN_BATCH_SIZE: usize = 500;
+
+static ACTIVE_SCANS: Lazy<Mutex<HashMap<String, Arc<AtomicBool>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EntryBatch {
+    pub scan_id: String,
+    pub root: String,
+    /// Flat entries (`children` is always `None`)
+    pub entries: Vec<FileEntry>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ScanComplete {
+    pub scan_id: String,
+    pub root: String,
+    pub total: usize,
+    pub cancelled: bool,
+    pub error: Option<String>,
+}
+
+/// Walk `root` depth-first, handing entries to `on_batch` in chunks
+///
+/// Returns the number of entries seen and whether the scan was cancelled.
+/// Unreadable subdirectories are skipped.
+pub fn scan_directory(
+    root: &Path,
+    batch_size: usize,
+    cancel: &AtomicBool,
+    mut on_batch: impl FnMut(Vec<FileEntry>),
+) -> Result<(usize, bool), AppError> {
+    let batch_size = batch_size.max(1);
+    let mut batch = Vec::with_capacity(batch_size);
+    let mut total = 0;
+    let mut stack: Vec<PathBuf> = vec![root.to_path_buf()];
+    let mut is_root = true;
+
+    while let Some(dir) = stack.pop() {
+        if cancel.load(Ordering::Relaxed) {
+            return Ok((total, true));
+        }
+        let read_dir = match fs::read_dir(&dir) {
+            Ok(read_dir) => read_dir,
+            Err(err) if is_root => return Err(err.into()),
+            Err(_) => continue,
+        };
+        is_root = false;
+
+        let mut entries: Vec<(FileEntry, bool)> = read_dir
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| !is_skipped_entry(&entry.file_name().to_string_lossy()))
+            .filter_map(|entry| {
+                // Do not descend into symlinked directories (cycles)
+                let descend = entry.file_type().is_ok_and(|kind| kind.is_dir());
+                file_entry(&entry, None).ok().map(|file| (file, descend))
+            })
+            .collect();
+        entries.sort_by(|(a, _), (b, _)| compare_entries(a, b));
+
+        let mut children: Vec<PathBuf> = Vec::new();
+        for (entry, descend) in entries {
+            if descend {
+                children.push(PathBuf::from(&entry.path));
+            }
+            batch.push(entry);
+            total += 1;
+            if batch.len() >= batch_size {
+                on_batch(std::mem::replace(
+                    &mut batch,
+                    Vec::with_capacity(batch_size),
+                ));
+            }
+        }
+        // Pop in name order
+        stack.extend(children.into_iter().rev());
+    }
+
+    if !batch.is_empty() {
+        on_batch(batch);
+    }
+    Ok((total, false))
+}
+
+/// Start a background scan of `path`; returns the scan id
+pub fn start_scan(app: AppHandle, path: &str, scan_id: String) -> Result<String, AppError> {
+    let root = Path::new(path);
+    ensure_allowed_path(root, true)?;
+    if !root.is_dir() {
+        return Err(AppError::InvalidPath("Path is not a directory".to_string()));
+    }
+
+    let cancel = Arc::new(AtomicBool::new(false));
+    if let Ok(mut scans) = ACTIVE_SCANS.lock() {
+        scans.insert(scan_id.clone(), cancel.clone());
+    }
+
+    let root = root.to_path_buf();
+    let root_str = path.to_string();
+    let id = scan_id.clone();
+    std::thread::spawn(move || {
+        let result = scan_directory(&root, SCAN_BATCH_SIZE, &cancel, |entries| {
+            let _ = app.emit(
+                ENTRY_BATCH_EVENT,
+                EntryBatch {
+                    scan_id: id.clone(),
+                    root: root_str.clone(),
+                    entries,
+                },
+            );
+        });
+        if let Ok(mut scans) = ACTIVE_SCANS.lock() {
+            scans.remove(&id);
+        }
+        let (total, cancelled, error) = match result {
+            Ok((total, cancelled)) => (total, cancelled, None),
+            Err(err) => (0, false, Some(err.to_string())),
+        };
+        let _ = app.emit(
+            SCAN_COMPLETE_EVENT,
+            ScanComplete {
+                scan_id: id,
+                root: root_str,
+                total,
+                cancelled,
+                error,
+            },
+        );
+    });
+    Ok(scan_id)
+}
+
+/// Cancel a running scan; returns false when it already finished
+pub fn cancel_scan(scan_id: &str) -> bool {
+    ACTIVE_SCANS
+        .lock()
+        .ok()
+        .and_then(|scans| scans.get(scan_id).cloned())
+        .map(|cancel| cancel.store(true, Ordering::Relaxed))
+        .is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn streams_entries_in_batches_parents_first() {
+        let dir = TempDir::new().expect("temp dir");
+        fs::create_dir_all(dir.path().join("b/nested")).unwrap();
+        fs::create_dir_all(dir.path().join("node_modules/pkg")).unwrap();
+        fs::create_dir_all(dir.path().join(".git")).unwrap();
+        fs::write(dir.path().join("a.md"), "a").unwrap();
+        fs::write(dir.path().join("b/c.md"), "c").unwrap();
+        fs::write(dir.path().join("b/nested/d.md"), "d").unwrap();
+
+        let mut batches: Vec<Vec<String>> = Vec::new();
+        let cancel = AtomicBool::new(false);
+        let (total, cancelled) = scan_directory(dir.path(), 2, &cancel, |entries| {
+            batches.push(entries.into_iter().map(|entry| entry.name).collect());
+        })
+        .expect("scan");
+
+        assert_eq!((total, cancelled), (5, false));
+        assert_eq!(
+            batches,
+            vec![
+                vec!["b".to_string(), "a.md".to_string()],
+                vec!["nested".to_string(), "c.md".to_string()],
+                vec!["d.md".to_string()],
+            ]
+        );
+
+        cancel.store(true, Ordering::Relaxed);
+        let (total, cancelled) = scan_directory(dir.path(), 2, &cancel, |_| {}).unwrap();
+        assert_eq!((total, cancelled), (0, true));
+        assert!(!cancel_scan("missing"));
+    }
+}
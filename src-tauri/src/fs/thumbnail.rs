@@ -0,0 +1,248 @@
+//! Downscaled image previews for attachment browsing
+//!
+//! Images larger than the requested size are decoded, shrunk to fit and
+//! re-encoded as lossless WebP. The result is cached on disk keyed by the
+//! image path, its mtime and size, and the requested dimension, so scrolling a
+//! gallery decodes each image once. Images that already fit are returned as-is.
+
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use image::{ImageFormat, ImageReader};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::Cursor;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+use crate::error::AppError;
+
+pub const MIN_THUMBNAIL_DIM: u32 = 16;
+pub const MAX_THUMBNAIL_DIM: u32 = 4096;
+const THUMBNAIL_MIME: &str = "image/webp";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ImageThumbnail {
+    pub mime_type: String,
+    pub width: u32,
+    pub height: u32,
+    /// Size of the original image
+    pub source_width: u32,
+    pub source_height: u32,
+    /// Base64-encoded image data
+    pub data: String,
+    /// Served from the thumbnail cache
+    pub cached: bool,
+}
+
+/// Preview of the image at `path` that fits within `max_dim` on both sides
+///
+/// `cache_dir` holds one directory per path and size, containing a single
+/// `<mtime>-<len>_<width>x<height>.webp` file named after the image version
+/// and the original dimensions; a stale version is dropped when the image
+/// changes.
+pub fn image_thumbnail(
+    path: &Path,
+    max_dim: u32,
+    cache_dir: &Path,
+) -> Result<ImageThumbnail, AppError> {
+    let max_dim = max_dim.clamp(MIN_THUMBNAIL_DIM, MAX_THUMBNAIL_DIM);
+    let metadata = fs::metadata(path)?;
+    if !metadata.is_file() {
+        return Err(AppError::InvalidPath(path.display().to_string()));
+    }
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map(|duration| duration.as_nanos())
+        .unwrap_or(0);
+
+    let entry_dir = cache_dir.join(cache_key(&format!("{}\n{}", path.display(), max_dim)));
+    let version = format!("{}-{}", modified, metadata.len());
+    if let Some(thumbnail) = read_cached(&entry_dir, &version) {
+        return Ok(thumbnail);
+    }
+
+    let reader = ImageReader::open(path)?.with_guessed_format()?;
+    let format = reader.format();
+    let (width, height) = reader
+        .into_dimensions()
+        .map_err(|err| decode_error(path, err))?;
+    if width <= max_dim && height <= max_dim {
+        if let Some(format) = format {
+            return Ok(ImageThumbnail {
+                mime_type: format.to_mime_type().to_string(),
+                width,
+                height,
+                source_width: width,
+                source_height: height,
+                data: STANDARD.encode(fs::read(path)?),
+                cached: false,
+            });
+        }
+    }
+
+    let image = ImageReader::open(path)?
+        .with_guessed_format()?
+        .decode()
+        .map_err(|err| decode_error(path, err))?
+        .thumbnail(max_dim, max_dim);
+    let mut bytes = Vec::new();
+    image
+        .write_to(&mut Cursor::new(&mut bytes), ImageFormat::WebP)
+        .map_err(|err| AppError::Internal(format!("Failed to encode thumbnail: {}", err)))?;
+
+    // A failed cache write only costs a re-encode next time
+    let cached_path = entry_dir.join(format!("{version}_{width}x{height}.webp"));
+    let _ = write_cached(&entry_dir, &cached_path, &bytes);
+    Ok(ImageThumbnail {
+        mime_type: THUMBNAIL_MIME.to_string(),
+        width: image.width(),
+        height: image.height(),
+        source_width: width,
+        source_height: height,
+        data: STANDARD.encode(&bytes),
+        cached: false,
+    })
+}
+
+fn cache_key(value: &str) -> String {
+    let digest = Sha256::digest(value.as_bytes());
+    digest[..16]
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+fn decode_error(path: &Path, err: image::ImageError) -> AppError {
+    AppError::InvalidInput(format!("Cannot decode image {}: {}", path.display(), err))
+}
+
+fn read_cached(entry_dir: &Path, version: &str) -> Option<ImageThumbnail> {
+    let prefix = format!("{version}_");
+    let (path, source_width, source_height) = fs::read_dir(entry_dir)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .find_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let (width, height) = name
+                .strip_prefix(&prefix)?
+                .strip_suffix(".webp")?
+                .split_once('x')?;
+            Some((entry.path(), width.parse().ok()?, height.parse().ok()?))
+        })?;
+    let bytes = fs::read(path).ok()?;
+    let (width, height) = ImageReader::with_format(Cursor::new(&bytes), ImageFormat::WebP)
+        .into_dimensions()
+        .ok()?;
+    Some(ImageThumbnail {
+        mime_type: THUMBNAIL_MIME.to_string(),
+        width,
+        height,
+        source_width,
+        source_height,
+        data: STANDARD.encode(&bytes),
+        cached: true,
+    })
+}
+
+/// Replace the entry's previous versions with `bytes`, written atomically
+fn write_cached(entry_dir: &Path, cached_path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    if entry_dir.exists() {
+        fs::remove_dir_all(entry_dir)?;
+    }
+    fs::create_dir_all(entry_dir)?;
+    let temp = cached_path.with_extension("webp.tmp");
+    fs::write(&temp, bytes)?;
+    fs::rename(&temp, cached_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, RgbImage};
+
+    fn write_png(path: &Path, width: u32, height: u32) {
+        RgbImage::from_pixel(width, height, Rgb([200, 40, 90]))
+            .save(path)
+            .unwrap();
+    }
+
+    #[test]
+    fn downscales_and_serves_repeat_requests_from_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = dir.path().join("cache");
+        let image = dir.path().join("photo.png");
+        write_png(&image, 400, 200);
+
+        let first = image_thumbnail(&image, 100, &cache).unwrap();
+        assert_eq!(
+            (
+                first.mime_type.as_str(),
+                first.width,
+                first.height,
+                first.cached
+            ),
+            ("image/webp", 100, 50, false)
+        );
+        let second = image_thumbnail(&image, 100, &cache).unwrap();
+        assert!(second.cached);
+        assert_eq!(second.data, first.data);
+        assert_eq!((second.source_width, second.source_height), (400, 200));
+
+        // Another size is cached separately
+        let larger = image_thumbnail(&image, 200, &cache).unwrap();
+        assert_eq!(
+            (larger.width, larger.height, larger.cached),
+            (200, 100, false)
+        );
+    }
+
+    #[test]
+    fn replaces_stale_cache_entries_when_the_image_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = dir.path().join("cache");
+        let image = dir.path().join("photo.png");
+        write_png(&image, 400, 200);
+        image_thumbnail(&image, 100, &cache).unwrap();
+
+        write_png(&image, 300, 300);
+        let later = std::time::SystemTime::now() + std::time::Duration::from_secs(5);
+        fs::File::options()
+            .write(true)
+            .open(&image)
+            .unwrap()
+            .set_modified(later)
+            .unwrap();
+        let updated = image_thumbnail(&image, 100, &cache).unwrap();
+        assert_eq!(
+            (updated.width, updated.height, updated.cached),
+            (100, 100, false)
+        );
+
+        let entries: Vec<_> = fs::read_dir(&cache)
+            .unwrap()
+            .flat_map(|entry| fs::read_dir(entry.unwrap().path()).unwrap())
+            .collect();
+        assert_eq!(entries.len(), 1);
+    }
+
+    #[test]
+    fn returns_small_images_unchanged() {
+        let dir = tempfile::tempdir().unwrap();
+        let image = dir.path().join("icon.png");
+        write_png(&image, 32, 24);
+
+        let thumbnail = image_thumbnail(&image, 256, &dir.path().join("cache")).unwrap();
+        assert_eq!(
+            (
+                thumbnail.mime_type.as_str(),
+                thumbnail.width,
+                thumbnail.height
+            ),
+            ("image/png", 32, 24)
+        );
+        assert_eq!(thumbnail.data, STANDARD.encode(fs::read(&image).unwrap()));
+        assert!(!dir.path().join("cache").exists());
+    }
+}
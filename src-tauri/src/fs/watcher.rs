@@ -1,13 +1,30 @@
 use notify::event::ModifyKind;
 use notify::{Config, Event, RecommendedWatcher, RecursiveMode, Watcher};
-use std::path::Path;
-use std::sync::mpsc::channel;
+use serde::Deserialize;
+use std::path::{Component, Path, PathBuf};
+use std::sync::mpsc::{channel, RecvTimeoutError};
+use std::time::{Duration, Instant};
 use tauri::{AppHandle, Emitter};
 
+/// Event emitted once per coalesced change
+pub const FS_CHANGE_EVENT: &str = "fs:change";
+
+/// Event emitted once per debounce window with all coalesced changes
+pub const FS_CHANGES_EVENT: &str = "fs:changes";
+
+/// Quiet period before a batch of changes is emitted
+const DEFAULT_DEBOUNCE_MS: u64 = 300;
+
+/// Upper bound on how long a continuous event storm can delay a batch
+const MAX_BATCH_DELAY: Duration = Duration::from_secs(2);
+
+/// Directories whose changes are ignored by default (VCS, dependencies and
+/// Lumina's own index, which would otherwise feed back into reindexing)
+const IGNORED_DIRS: [&str; 3] = [".git", "node_modules", ".lumina"];
+
 /// File system event types
-#[derive(Debug, Clone, serde::Serialize)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
 #[serde(tag = "type")]
-#[allow(dead_code)]
 pub enum FsEvent {
     Created { path: String },
     Modified { path: String },
@@ -15,9 +32,161 @@ pub enum FsEvent {
     Renamed { old_path: String, new_path: String },
 }
 
+impl FsEvent {
+    /// The path this event currently applies to
+    fn path(&self) -> &str {
+        match self {
+            FsEvent::Created { path } | FsEvent::Modified { path } | FsEvent::Deleted { path } => {
+                path
+            }
+            FsEvent::Renamed { new_path, .. } => new_path,
+        }
+    }
+}
+
+/// Watcher options
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct WatcherOptions {
+    /// Debounce window in milliseconds (default 300)
+    pub debounce_ms: Option<u64>,
+    /// Ignore `.git`, `node_modules` and `.lumina` (default true)
+    pub ignore_internal: Option<bool>,
+}
+
+/// Merges events for the same path within a debounce window
+///
+/// e.g. create + modify becomes create, create + delete cancels out, and a
+/// rename of a freshly created file becomes a create at the new path.
+#[derive(Debug, Default)]
+struct EventCoalescer {
+    pending: Vec<FsEvent>,
+}
+
+impl EventCoalescer {
+    fn take(&mut self, path: &str) -> Option<FsEvent> {
+        let index = self.pending.iter().position(|event| event.path() == path)?;
+        Some(self.pending.remove(index))
+    }
+
+    fn push(&mut self, event: FsEvent) {
+        let merged = match event {
+            FsEvent::Created { path } => match self.take(&path) {
+                None => Some(FsEvent::Created { path }),
+                Some(FsEvent::Deleted { path }) => Some(FsEvent::Modified { path }),
+                Some(previous) => Some(previous),
+            },
+            FsEvent::Modified { path } => match self.take(&path) {
+                None | Some(FsEvent::Deleted { .. }) => Some(FsEvent::Modified { path }),
+                Some(previous) => Some(previous),
+            },
+            FsEvent::Deleted { path } => match self.take(&path) {
+                Some(FsEvent::Created { .. }) => None,
+                Some(FsEvent::Renamed { old_path, .. }) => {
+                    Some(FsEvent::Deleted { path: old_path })
+                }
+                _ => Some(FsEvent::Deleted { path }),
+            },
+            FsEvent::Renamed { old_path, new_path } => {
+                self.take(&new_path);
+                match self.take(&old_path) {
+                    Some(FsEvent::Created { .. }) => Some(FsEvent::Created { path: new_path }),
+                    Some(FsEvent::Renamed {
+                        old_path: original, ..
+                    }) if original == new_path => Some(FsEvent::Modified { path: new_path }),
+                    Some(FsEvent::Renamed {
+                        old_path: original, ..
+                    }) => Some(FsEvent::Renamed {
+                        old_path: original,
+                        new_path,
+                    }),
+                    _ => Some(FsEvent::Renamed { old_path, new_path }),
+                }
+            }
+        };
+        if let Some(event) = merged {
+            self.pending.push(event);
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    fn drain(&mut self) -> Vec<FsEvent> {
+        std::mem::take(&mut self.pending)
+    }
+}
+
+fn is_relevant(path: &Path) -> bool {
+    let path_str = path.to_string_lossy();
+    path_str.ends_with(".md")
+        || path_str.ends_with(".db.json")
+        || path_str.ends_with(".excalidraw.json")
+        || path_str.ends_with(".diagram.json")
+        || path_str.ends_with(".drawio.json")
+}
+
+/// Whether `path` lies inside an ignored directory below `root`
+fn is_ignored(root: &Path, path: &Path) -> bool {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .components()
+        .any(|component| match component {
+            Component::Normal(name) => IGNORED_DIRS.iter().any(|dir| name == *dir),
+            _ => false,
+        })
+}
+
+/// Classify a raw notify event as create/modify/delete/rename
+fn classify(event: &Event, is_relevant: impl Fn(&Path) -> bool) -> Option<FsEvent> {
+    let first_relevant = event.paths.iter().find(|p| is_relevant(p));
+    let to_string = |p: &PathBuf| p.to_string_lossy().to_string();
+
+    match event.kind {
+        notify::EventKind::Create(_) => {
+            first_relevant.map(|p| FsEvent::Created { path: to_string(p) })
+        }
+        notify::EventKind::Modify(ModifyKind::Name(_)) => {
+            if event.paths.len() >= 2 {
+                let old_path = &event.paths[0];
+                let new_path = &event.paths[event.paths.len() - 1];
+                match (is_relevant(old_path), is_relevant(new_path)) {
+                    (true, true) => Some(FsEvent::Renamed {
+                        old_path: to_string(old_path),
+                        new_path: to_string(new_path),
+                    }),
+                    // Moved out of view (e.g. into an ignored directory)
+                    (true, false) => Some(FsEvent::Deleted {
+                        path: to_string(old_path),
+                    }),
+                    (false, true) => Some(FsEvent::Created {
+                        path: to_string(new_path),
+                    }),
+                    (false, false) => None,
+                }
+            } else {
+                first_relevant.map(|p| FsEvent::Modified { path: to_string(p) })
+            }
+        }
+        notify::EventKind::Modify(_) => {
+            first_relevant.map(|p| FsEvent::Modified { path: to_string(p) })
+        }
+        notify::EventKind::Remove(_) => {
+            first_relevant.map(|p| FsEvent::Deleted { path: to_string(p) })
+        }
+        _ => None,
+    }
+}
+
 /// Start watching a directory for changes
-/// Emits "fs:change" events to the frontend
-pub fn start_watcher(app: AppHandle, watch_path: String) -> Result<(), String> {
+///
+/// Changes are debounced and coalesced per path. Each window emits one
+/// "fs:change" event per change plus a single "fs:changes" batch.
+pub fn start_watcher(
+    app: AppHandle,
+    watch_path: String,
+    options: WatcherOptions,
+) -> Result<(), String> {
     let (tx, rx) = channel();
 
     let mut watcher = RecommendedWatcher::new(
@@ -34,65 +203,152 @@ pub fn start_watcher(app: AppHandle, watch_path: String) -> Result<(), String> {
         .watch(Path::new(&watch_path), RecursiveMode::Recursive)
         .map_err(|e| e.to_string())?;
 
+    let debounce = Duration::from_millis(options.debounce_ms.unwrap_or(DEFAULT_DEBOUNCE_MS));
+    let ignore_internal = options.ignore_internal.unwrap_or(true);
+    let root = PathBuf::from(&watch_path);
+
     // Spawn a thread to handle events
     std::thread::spawn(move || {
         // Keep watcher alive
         let _watcher = watcher;
 
-        while let Ok(event) = rx.recv() {
-            let is_relevant = |p: &std::path::Path| {
-                let path_str = p.to_string_lossy();
-                path_str.ends_with(".md")
-                    || path_str.ends_with(".db.json")
-                    || path_str.ends_with(".excalidraw.json")
-                    || path_str.ends_with(".diagram.json")
-                    || path_str.ends_with(".drawio.json")
-            };
-
-            let first_relevant = event.paths.iter().find(|p| is_relevant(p));
-
-            if first_relevant.is_none()
-                && !matches!(event.kind, notify::EventKind::Modify(ModifyKind::Name(_)))
-            {
-                continue;
+        let mut coalescer = EventCoalescer::default();
+        let mut batch_started: Option<Instant> = None;
+        let flush = |coalescer: &mut EventCoalescer| {
+            let events = coalescer.drain();
+            if events.is_empty() {
+                return;
             }
+            for event in &events {
+                let _ = app.emit(FS_CHANGE_EVENT, event);
+            }
+            let _ = app.emit(FS_CHANGES_EVENT, events);
+        };
+
+        loop {
+            // Block while idle; wait out the debounce window once changes are pending
+            let received = if coalescer.is_empty() {
+                rx.recv().map_err(|_| RecvTimeoutError::Disconnected)
+            } else {
+                rx.recv_timeout(debounce)
+            };
 
-            let fs_event = match event.kind {
-                notify::EventKind::Create(_) => first_relevant.map(|p| FsEvent::Created {
-                    path: p.to_string_lossy().to_string(),
-                }),
-                notify::EventKind::Modify(ModifyKind::Name(_)) => {
-                    if event.paths.len() >= 2 {
-                        let old_path = &event.paths[0];
-                        let new_path = &event.paths[event.paths.len() - 1];
-                        if is_relevant(old_path) || is_relevant(new_path) {
-                            Some(FsEvent::Renamed {
-                                old_path: old_path.to_string_lossy().to_string(),
-                                new_path: new_path.to_string_lossy().to_string(),
+            match received {
+                Ok(event) => {
+                    // Keep the note map parse cache in sync with external edits; paths
+                    // that are no longer files may be removed or renamed folders of notes
+                    crate::note_map::cache::invalidate_paths(
+                        event
+                            .paths
+                            .iter()
+                            .filter(|p| {
+                                p.extension().and_then(|ext| ext.to_str()) == Some("md")
+                                    || !p.is_file()
                             })
-                        } else {
-                            None
-                        }
-                    } else {
-                        first_relevant.map(|p| FsEvent::Modified {
-                            path: p.to_string_lossy().to_string(),
-                        })
+                            .map(|p| p.as_path()),
+                    );
+
+                    let relevant =
+                        |p: &Path| is_relevant(p) && !(ignore_internal && is_ignored(&root, p));
+                    if let Some(fs_event) = classify(&event, relevant) {
+                        coalescer.push(fs_event);
+                        batch_started.get_or_insert_with(Instant::now);
+                    }
+                    if batch_started.is_some_and(|started| started.elapsed() >= MAX_BATCH_DELAY) {
+                        flush(&mut coalescer);
+                        batch_started = None;
                     }
                 }
-                notify::EventKind::Modify(_) => first_relevant.map(|p| FsEvent::Modified {
-                    path: p.to_string_lossy().to_string(),
-                }),
-                notify::EventKind::Remove(_) => first_relevant.map(|p| FsEvent::Deleted {
-                    path: p.to_string_lossy().to_string(),
-                }),
-                _ => None,
-            };
-
-            if let Some(evt) = fs_event {
-                let _ = app.emit("fs:change", evt);
+                Err(RecvTimeoutError::Timeout) => {
+                    flush(&mut coalescer);
+                    batch_started = None;
+                }
+                Err(RecvTimeoutError::Disconnected) => {
+                    flush(&mut coalescer);
+                    break;
+                }
             }
         }
     });
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn created(path: &str) -> FsEvent {
+        FsEvent::Created {
+            path: path.to_string(),
+        }
+    }
+
+    fn modified(path: &str) -> FsEvent {
+        FsEvent::Modified {
+            path: path.to_string(),
+        }
+    }
+
+    fn deleted(path: &str) -> FsEvent {
+        FsEvent::Deleted {
+            path: path.to_string(),
+        }
+    }
+
+    fn renamed(old_path: &str, new_path: &str) -> FsEvent {
+        FsEvent::Renamed {
+            old_path: old_path.to_string(),
+            new_path: new_path.to_string(),
+        }
+    }
+
+    #[test]
+    fn coalesces_events_per_path() {
+        let mut coalescer = EventCoalescer::default();
+        for event in [
+            modified("a.md"),
+            modified("a.md"),
+            created("b.md"),
+            modified("b.md"),
+            created("tmp.md"),
+            deleted("tmp.md"),
+            created("c.md"),
+            renamed("c.md", "d.md"),
+            renamed("e.md", "f.md"),
+            modified("f.md"),
+            deleted("g.md"),
+            created("g.md"),
+        ] {
+            coalescer.push(event);
+        }
+
+        assert_eq!(
+            coalescer.drain(),
+            vec![
+                modified("a.md"),
+                created("b.md"),
+                created("d.md"),
+                renamed("e.md", "f.md"),
+                modified("g.md"),
+            ]
+        );
+        assert!(coalescer.is_empty());
+    }
+
+    #[test]
+    fn ignores_internal_directories_below_root() {
+        let root = Path::new("/vault");
+        assert!(is_ignored(root, Path::new("/vault/.git/HEAD.md")));
+        assert!(is_ignored(root, Path::new("/vault/.lumina/index.db.json")));
+        assert!(is_ignored(
+            root,
+            Path::new("/vault/app/node_modules/x/README.md")
+        ));
+        assert!(!is_ignored(root, Path::new("/vault/notes/a.md")));
+        assert!(!is_ignored(
+            Path::new("/home/.lumina/vault"),
+            Path::new("/home/.lumina/vault/a.md")
+        ));
+    }
+}
@@ -0,0 +1,874 @@
+//! 通用外部 URL 请求
+//!
+//! 前端获取网页内容（链接预览、favicon 等）统一走 `http_fetch`，不再各自新建客户端：
+//! 只允许 http/https，拒绝 localhost、内网、链路本地等地址（URL 和重定向目标检查主机名，
+//! 连接时由自定义 DNS 解析器过滤解析结果，因此 DNS rebinding 和重定向到解析为内网的域名
+//! 都无法绕过；经代理请求时由代理解析域名，改为发送前预先解析每一跳并拒绝内网结果），
+//! 限制重定向次数和响应大小；幂等请求遇到网络错误或 5xx/429 时退避重试。
+//! 成功的 GET 响应可按 URL + 请求头缓存到磁盘。
+
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use reqwest::header::{
+    HeaderName, AUTHORIZATION, CONTENT_TYPE, COOKIE, LOCATION, PROXY_AUTHORIZATION,
+};
+use reqwest::redirect::Policy;
+use reqwest::{Client, ClientBuilder, Method, Response, StatusCode, Url};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+const DEFAULT_TIMEOUT_SECS: u64 = 30;
+const MAX_TIMEOUT_SECS: u64 = 120;
+const DEFAULT_MAX_BYTES: usize = 5 * 1024 * 1024;
+const MAX_BYTES: usize = 20 * 1024 * 1024;
+const MAX_REDIRECTS: usize = 5;
+/// 幂等请求的最大重试次数
+const MAX_RETRIES: u32 = 2;
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(500);
+/// 本机和内网专用的域名后缀
+const BLOCKED_HOST_SUFFIXES: [&str; 4] = [".localhost", ".local", ".internal", ".home.arpa"];
+/// 跳转到其他主机时不转发的请求头
+const SENSITIVE_HEADERS: [HeaderName; 3] = [AUTHORIZATION, COOKIE, PROXY_AUTHORIZATION];
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HttpFetchRequest {
+    pub url: String,
+    /// 默认 GET
+    #[serde(default)]
+    pub method: Option<String>,
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    pub body: Option<String>,
+    pub timeout_secs: Option<u64>,
+    /// 响应体大小上限（字节），超出部分被截断
+    pub max_bytes: Option<usize>,
+    /// 缓存有效期（秒）；为空时不读写缓存
+    pub cache_ttl_secs: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HttpFetchResponse {
+    pub status: u16,
+    /// 重定向后的最终地址
+    pub url: String,
+    /// 响应头（名称小写）
+    pub headers: HashMap<String, String>,
+    pub content_type: Option<String>,
+    /// 文本响应为原文，其余为 base64
+    pub body: String,
+    pub base64: bool,
+    /// 响应体超出大小上限被截断
+    pub truncated: bool,
+    /// 来自磁盘缓存
+    pub cached: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CacheEntry {
+    stored_at: i64,
+    response: HttpFetchResponse,
+}
+
+/// 请求外部 URL
+///
+/// 非 2xx 状态作为正常响应返回；地址被拒绝、请求失败时返回错误。
+#[tauri::command]
+pub async fn http_fetch(
+    app: AppHandle,
+    proxy_state: tauri::State<'_, crate::proxy::ProxyState>,
+    request: HttpFetchRequest,
+) -> Result<HttpFetchResponse, String> {
+    let builder = proxy_state.client_builder().await?;
+    let config = proxy_state.get_config().await;
+    let mut resolver = PublicResolver::new(system_lookup);
+    if config.enabled {
+        resolver.proxy_host = Url::parse(&config.proxy_url)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_ascii_lowercase));
+    }
+    fetch(builder, resolver, cache_dir(&app), request).await
+}
+
+async fn fetch(
+    builder: ClientBuilder,
+    resolver: PublicResolver,
+    cache_dir: Option<PathBuf>,
+    request: HttpFetchRequest,
+) -> Result<HttpFetchResponse, String> {
+    let url = Url::parse(&request.url).map_err(|e| format!("Invalid URL: {}", e))?;
+    check_url(&url)?;
+
+    let method = request.method.as_deref().unwrap_or("GET").to_uppercase();
+    let method = Method::from_bytes(method.as_bytes())
+        .map_err(|_| format!("Unsupported HTTP method: {}", method))?;
+
+    let cache = match (cache_dir, request.cache_ttl_secs) {
+        (Some(dir), Some(ttl)) if method == Method::GET => {
+            Some((dir, cache_key(&url, &request.headers), ttl))
+        }
+        _ => None,
+    };
+    if let Some((dir, key, ttl)) = &cache {
+        if let Some(response) = read_cached(dir, key, *ttl, chrono::Utc::now().timestamp()) {
+            return Ok(response);
+        }
+    }
+
+    let timeout = request
+        .timeout_secs
+        .unwrap_or(DEFAULT_TIMEOUT_SECS)
+        .clamp(1, MAX_TIMEOUT_SECS);
+    let resolver = Arc::new(resolver);
+    // 重定向手动跟随，每一跳都重新检查目标
+    let client = builder
+        .timeout(Duration::from_secs(timeout))
+        .redirect(Policy::none())
+        .dns_resolver(resolver.clone())
+        .build()
+        .map_err(|e| format!("Failed to build HTTP client: {}", e))?;
+
+    let mut hop = Hop {
+        url,
+        method,
+        headers: request.headers.clone(),
+        body: request.body.clone(),
+    };
+    let mut redirects = 0;
+    let response = loop {
+        resolver.check_proxied_target(&hop.url).await?;
+        let response = send_with_retries(&client, &hop).await?;
+        let Some(next) = redirect_target(&response) else {
+            break response;
+        };
+        if redirects >= MAX_REDIRECTS {
+            return Err(format!(
+                "Redirect rejected: more than {} redirects",
+                MAX_REDIRECTS
+            ));
+        }
+        check_url(&next).map_err(|e| format!("Redirect rejected: {}", e))?;
+        hop = hop.redirect(response.status(), next);
+        redirects += 1;
+    };
+
+    let max_bytes = request
+        .max_bytes
+        .unwrap_or(DEFAULT_MAX_BYTES)
+        .clamp(1, MAX_BYTES);
+    let response = read_response(response, max_bytes).await?;
+
+    if let Some((dir, key, _)) = &cache {
+        if (200..300).contains(&response.status) && !response.truncated {
+            if let Err(err) = write_cached(dir, key, &response, chrono::Utc::now().timestamp()) {
+                eprintln!("[HTTP Cache] {}", err);
+            }
+        }
+    }
+    Ok(response)
+}
+
+/// 单次请求的目标，重定向时据此生成下一跳
+struct Hop {
+    url: Url,
+    method: Method,
+    headers: HashMap<String, String>,
+    body: Option<String>,
+}
+
+impl Hop {
+    /// 与浏览器一致：303 以及 POST 的 301/302 改为不带请求体的 GET；
+    /// 跳转到其他主机时去掉凭据类请求头
+    fn redirect(mut self, status: StatusCode, next: Url) -> Self {
+        let to_get = match status {
+            StatusCode::SEE_OTHER => self.method != Method::HEAD,
+            StatusCode::MOVED_PERMANENTLY | StatusCode::FOUND => self.method == Method::POST,
+            _ => false,
+        };
+        if to_get {
+            self.method = Method::GET;
+            self.body = None;
+            self.headers
+                .retain(|name, _| !name.eq_ignore_ascii_case(CONTENT_TYPE.as_str()));
+        }
+        let same_origin = next.host_str() == self.url.host_str()
+            && next.port_or_known_default() == self.url.port_or_known_default();
+        if !same_origin {
+            self.headers.retain(|name, _| {
+                !SENSITIVE_HEADERS
+                    .iter()
+                    .any(|header| name.eq_ignore_ascii_case(header.as_str()))
+            });
+        }
+        self.url = next;
+        self
+    }
+}
+
+/// 发送一跳请求；只有幂等请求才在网络错误或 5xx/429 时重试，避免重复提交
+async fn send_with_retries(client: &Client, hop: &Hop) -> Result<Response, String> {
+    let retries = if hop.method == Method::GET || hop.method == Method::HEAD {
+        MAX_RETRIES
+    } else {
+        0
+    };
+    let mut attempt = 0;
+    loop {
+        let mut builder = client.request(hop.method.clone(), hop.url.clone());
+        for (name, value) in &hop.headers {
+            builder = builder.header(name, value);
+        }
+        if let Some(body) = &hop.body {
+            builder = builder.body(body.clone());
+        }
+
+        let result = builder.send().await;
+        if let Some(blocked) = result.as_ref().err().and_then(blocked_address) {
+            return Err(blocked.to_string());
+        }
+        let error = match result {
+            Ok(response) if attempt < retries && is_retryable_status(response.status()) => {
+                format!("Request failed with status {}", response.status())
+            }
+            Ok(response) => return Ok(response),
+            Err(e) if attempt < retries && !e.is_builder() => e.to_string(),
+            Err(e) => return Err(format!("Request failed: {}", e)),
+        };
+        let delay = RETRY_BASE_DELAY * 2u32.pow(attempt);
+        eprintln!(
+            "[HTTP] Retrying {} in {:?} after error: {}",
+            hop.url, delay, error
+        );
+        tokio::time::sleep(delay).await;
+        attempt += 1;
+    }
+}
+
+/// 重定向响应的下一跳地址；没有合法 Location 时按普通响应返回
+fn redirect_target(response: &Response) -> Option<Url> {
+    let status = response.status();
+    if !matches!(
+        status,
+        StatusCode::MOVED_PERMANENTLY
+            | StatusCode::FOUND
+            | StatusCode::SEE_OTHER
+            | StatusCode::TEMPORARY_REDIRECT
+            | StatusCode::PERMANENT_REDIRECT
+    ) {
+        return None;
+    }
+    let location = response.headers().get(LOCATION)?.to_str().ok()?;
+    response.url().join(location).ok()
+}
+
+/// 清空外部请求缓存，返回删除的条目数
+#[tauri::command]
+pub fn clear_http_cache(app: AppHandle) -> Result<usize, String> {
+    let Some(dir) = cache_dir(&app) else {
+        return Ok(0);
+    };
+    let entries = match fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(format!("Failed to read cache dir: {}", e)),
+    };
+    let mut removed = 0;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().and_then(|ext| ext.to_str()) == Some("json")
+            && fs::remove_file(&path).is_ok()
+        {
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
+
+/// 只允许 http/https 访问公网地址
+fn check_url(url: &Url) -> Result<(), String> {
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(format!("Unsupported URL scheme: {}", url.scheme()));
+    }
+    let host = url
+        .host_str()
+        .ok_or_else(|| "URL has no host".to_string())?
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .trim_end_matches('.')
+        .to_ascii_lowercase();
+    let blocked = match host.parse::<IpAddr>() {
+        Ok(ip) => is_blocked_ip(ip),
+        Err(_) => host == "localhost" || BLOCKED_HOST_SUFFIXES.iter().any(|s| host.ends_with(s)),
+    };
+    if blocked {
+        return Err(format!(
+            "Access to local or private address is denied: {}",
+            host
+        ));
+    }
+    Ok(())
+}
+
+/// 域名只解析到本机或内网地址
+#[derive(Debug)]
+struct BlockedAddress {
+    host: String,
+    ip: IpAddr,
+}
+
+impl fmt::Display for BlockedAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Access to local or private address is denied: {} resolves to {}",
+            self.host, self.ip
+        )
+    }
+}
+
+impl Error for BlockedAddress {}
+
+/// 请求错误是否由 `PublicResolver` 拒绝解析结果引起
+fn blocked_address(err: &reqwest::Error) -> Option<&BlockedAddress> {
+    let mut source = err.source();
+    while let Some(err) = source {
+        if let Some(blocked) = err.downcast_ref::<BlockedAddress>() {
+            return Some(blocked);
+        }
+        source = err.source();
+    }
+    None
+}
+
+/// 过滤掉本机和内网地址的 DNS 解析器
+///
+/// 连接直接使用过滤后的地址，不存在先检查、再由 reqwest 重新解析的时间窗口；
+/// 重定向到的域名同样经过这里。IP 字面量不经过解析，由 `check_url` 检查。
+/// 经代理请求时目标域名由代理解析，不经过这里，见 `check_proxied_target`。
+struct PublicResolver {
+    lookup: fn(&str) -> Resolving,
+    /// 已启用的代理主机，允许解析到本机（例如 localhost 上的代理客户端）
+    proxy_host: Option<String>,
+}
+
+impl PublicResolver {
+    fn new(lookup: fn(&str) -> Resolving) -> Self {
+        Self {
+            lookup,
+            proxy_host: None,
+        }
+    }
+
+    /// 启用代理时预先解析目标域名，任一结果为本机或内网地址即拒绝
+    ///
+    /// 代理会再解析一次，无法完全排除 DNS rebinding，但能挡住直接指向内网的域名。
+    /// 未启用代理时直接返回，由解析器在连接时过滤。
+    async fn check_proxied_target(&self, url: &Url) -> Result<(), String> {
+        if self.proxy_host.is_none() {
+            return Ok(());
+        }
+        let Some(host) = url.host_str() else {
+            return Ok(());
+        };
+        let host = host.trim_end_matches('.').to_ascii_lowercase();
+        // IP 字面量（含 [v6]）已由 check_url 检查
+        if host.starts_with('[') || host.parse::<IpAddr>().is_ok() {
+            return Ok(());
+        }
+        let addrs: Vec<SocketAddr> = (self.lookup)(&host)
+            .await
+            .map_err(|e| format!("Failed to resolve {}: {}", host, e))?
+            .collect();
+        if addrs.is_empty() {
+            return Err(format!("Failed to resolve {}: no addresses", host));
+        }
+        match addrs.iter().find(|addr| is_blocked_ip(addr.ip())) {
+            Some(addr) => Err(BlockedAddress {
+                host,
+                ip: addr.ip(),
+            }
+            .to_string()),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let host = name.as_str().to_ascii_lowercase();
+        let resolving = (self.lookup)(&host);
+        if self.proxy_host.as_deref() == Some(host.as_str()) {
+            return resolving;
+        }
+        Box::pin(async move {
+            let addrs: Vec<SocketAddr> = resolving.await?.collect();
+            let public: Vec<SocketAddr> = addrs
+                .iter()
+                .copied()
+                .filter(|addr| !is_blocked_ip(addr.ip()))
+                .collect();
+            match addrs.first() {
+                Some(addr) if public.is_empty() => Err(Box::new(BlockedAddress {
+                    host,
+                    ip: addr.ip(),
+                }) as _),
+                _ => Ok(Box::new(public.into_iter()) as Addrs),
+            }
+        })
+    }
+}
+
+fn system_lookup(host: &str) -> Resolving {
+    let host = host.to_string();
+    Box::pin(async move {
+        let addrs = tokio::net::lookup_host((host, 0)).await?;
+        Ok(Box::new(addrs) as Addrs)
+    })
+}
+
+fn is_blocked_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_blocked_ipv4(ip),
+        IpAddr::V6(ip) => {
+            if let Some(mapped) = ip.to_ipv4_mapped() {
+                return is_blocked_ipv4(mapped);
+            }
+            let first = ip.segments()[0];
+            ip.is_loopback()
+                || ip.is_unspecified()
+                || ip.is_multicast()
+                // fc00::/7 唯一本地地址、fe80::/10 链路本地地址
+                || (first & 0xfe00) == 0xfc00
+                || (first & 0xffc0) == 0xfe80
+        }
+    }
+}
+
+fn is_blocked_ipv4(ip: Ipv4Addr) -> bool {
+    let [a, b, ..] = ip.octets();
+    ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_unspecified()
+        || ip.is_broadcast()
+        || ip.is_multicast()
+        || a == 0
+        // 100.64.0.0/10 运营商级 NAT
+        || (a == 100 && (64..128).contains(&b))
+}
+
+fn is_retryable_status(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+async fn read_response(
+    mut response: Response,
+    max_bytes: usize,
+) -> Result<HttpFetchResponse, String> {
+    let status = response.status().as_u16();
+    let url = response.url().to_string();
+    let headers: HashMap<String, String> = response
+        .headers()
+        .iter()
+        .filter_map(|(name, value)| {
+            Some((name.as_str().to_string(), value.to_str().ok()?.to_string()))
+        })
+        .collect();
+    let content_type = headers.get("content-type").cloned();
+
+    let (bytes, truncated) = read_body_limited(&mut response, max_bytes)
+        .await
+        .map_err(|e| format!("Failed to read response body: {}", e))?;
+
+    let (body, base64) = if is_textual(content_type.as_deref()) {
+        (String::from_utf8_lossy(&bytes).to_string(), false)
+    } else {
+        (STANDARD.encode(&bytes), true)
+    };
+    Ok(HttpFetchResponse {
+        status,
+        url,
+        headers,
+        content_type,
+        body,
+        base64,
+        truncated,
+        cached: false,
+    })
+}
+
+/// 最多读取 `max_bytes` 字节的响应体，并返回是否被截断
+pub(crate) async fn read_body_limited(
+    response: &mut Response,
+    max_bytes: usize,
+) -> Result<(Vec<u8>, bool), reqwest::Error> {
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        let remaining = max_bytes.saturating_sub(body.len());
+        let end = remaining.min(chunk.len());
+        body.extend_from_slice(&chunk[..end]);
+        if end < chunk.len() {
+            return Ok((body, true));
+        }
+    }
+    Ok((body, false))
+}
+
+/// 响应内容是否为文本；没有 Content-Type 时视为二进制
+pub(crate) fn is_textual(content_type: Option<&str>) -> bool {
+    let Some(content_type) = content_type else {
+        return false;
+    };
+    let mime = content_type
+        .split(';')
+        .next()
+        .unwrap_or("")
+        .trim()
+        .to_ascii_lowercase();
+    mime.starts_with("text/")
+        || mime.ends_with("+json")
+        || mime.ends_with("+xml")
+        || matches!(
+            mime.as_str(),
+            "application/json"
+                | "application/xml"
+                | "application/javascript"
+                | "application/x-javascript"
+                | "application/ecmascript"
+                | "application/x-www-form-urlencoded"
+                | "application/yaml"
+                | "application/x-yaml"
+                | "application/toml"
+        )
+}
+
+/// 缓存目录：<app_cache_dir>/http-cache
+fn cache_dir(app: &AppHandle) -> Option<PathBuf> {
+    app.path()
+        .app_cache_dir()
+        .ok()
+        .map(|dir| dir.join("http-cache"))
+}
+
+/// 以 URL 和请求头（名称不区分大小写、与顺序无关）计算缓存键
+fn cache_key(url: &Url, headers: &HashMap<String, String>) -> String {
+    let headers: BTreeMap<String, &String> = headers
+        .iter()
+        .map(|(name, value)| (name.to_ascii_lowercase(), value))
+        .collect();
+    let material = json!({ "url": url.as_str(), "headers": headers });
+    hex::encode(Sha256::digest(material.to_string().as_bytes()))
+}
+
+fn entry_path(dir: &Path, key: &str) -> PathBuf {
+    dir.join(format!("{}.json", key))
+}
+
+fn read_cached(dir: &Path, key: &str, ttl_secs: u64, now: i64) -> Option<HttpFetchResponse> {
+    let raw = fs::read_to_string(entry_path(dir, key)).ok()?;
+    let entry = serde_json::from_str::<CacheEntry>(&raw).ok()?;
+    if now.saturating_sub(entry.stored_at) > ttl_secs as i64 {
+        return None;
+    }
+    Some(HttpFetchResponse {
+        cached: true,
+        ..entry.response
+    })
+}
+
+fn write_cached(
+    dir: &Path,
+    key: &str,
+    response: &HttpFetchResponse,
+    now: i64,
+) -> Result<(), String> {
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create cache dir: {}", e))?;
+    let entry = CacheEntry {
+        stored_at: now,
+        response: response.clone(),
+    };
+    let raw = serde_json::to_string(&entry).map_err(|e| e.to_string())?;
+    // 先写临时文件再重命名，避免并发读取到半截内容
+    let tmp = dir.join(format!("{}.tmp", key));
+    fs::write(&tmp, raw).map_err(|e| format!("Failed to write cache entry: {}", e))?;
+    fs::rename(&tmp, entry_path(dir, key))
+        .map_err(|e| format!("Failed to write cache entry: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url(raw: &str) -> Url {
+        Url::parse(raw).unwrap()
+    }
+
+    #[test]
+    fn rejects_local_private_and_non_http_urls() {
+        for allowed in [
+            "https://example.com/page",
+            "http://93.184.216.34/",
+            "https://[2606:4700::1111]/",
+        ] {
+            assert!(check_url(&url(allowed)).is_ok(), "{}", allowed);
+        }
+        for denied in [
+            "file:///etc/passwd",
+            "ftp://example.com/",
+            "http://localhost:3000/",
+            "http://LOCALHOST./",
+            "http://printer.local/",
+            "http://api.localhost/",
+            "http://127.0.0.1/",
+            "http://10.0.0.8/",
+            "http://192.168.1.1/",
+            "http://169.254.169.254/latest/meta-data",
+            "http://100.100.1.1/",
+            "http://0.0.0.0/",
+            "http://[::1]/",
+            "http://[fd00::1]/",
+            "http://[fe80::1]/",
+            "http://[::ffff:127.0.0.1]/",
+        ] {
+            assert!(check_url(&url(denied)).is_err(), "{}", denied);
+        }
+    }
+
+    #[test]
+    fn cache_key_ignores_header_order_and_case() {
+        let page = url("https://example.com/a");
+        let a = HashMap::from([
+            ("Accept".to_string(), "text/html".to_string()),
+            ("User-Agent".to_string(), "Lumina".to_string()),
+        ]);
+        let b = HashMap::from([
+            ("user-agent".to_string(), "Lumina".to_string()),
+            ("accept".to_string(), "text/html".to_string()),
+        ]);
+        assert_eq!(cache_key(&page, &a), cache_key(&page, &b));
+        assert_ne!(cache_key(&page, &a), cache_key(&page, &HashMap::new()));
+        assert_ne!(
+            cache_key(&page, &a),
+            cache_key(&url("https://example.com/b"), &a)
+        );
+    }
+
+    #[test]
+    fn cached_responses_expire_after_ttl() {
+        let dir = tempfile::tempdir().unwrap();
+        let response = HttpFetchResponse {
+            status: 200,
+            url: "https://example.com/".to_string(),
+            headers: HashMap::new(),
+            content_type: Some("text/html".to_string()),
+            body: "<title>Example</title>".to_string(),
+            base64: false,
+            truncated: false,
+            cached: false,
+        };
+        write_cached(dir.path(), "key", &response, 1_000).unwrap();
+
+        let hit = read_cached(dir.path(), "key", 60, 1_060).expect("fresh entry");
+        assert!(hit.cached);
+        assert_eq!(hit.body, response.body);
+        assert_eq!(read_cached(dir.path(), "key", 60, 1_061), None);
+        assert_eq!(read_cached(dir.path(), "missing", 60, 1_000), None);
+    }
+
+    #[tokio::test]
+    async fn rejects_hosts_and_redirects_resolving_to_private_addresses() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        // 模拟公网站点：/ 重定向到 rebind.test，/secret 代表内网服务
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                tokio::spawn(async move {
+                    let mut buf = [0u8; 4096];
+                    let n = socket.read(&mut buf).await.unwrap();
+                    let request = String::from_utf8_lossy(&buf[..n]).to_string();
+                    let response = if request.starts_with("GET /secret ") {
+                        "HTTP/1.1 200 OK\r\ncontent-type: text/plain\r\ncontent-length: 6\r\nconnection: close\r\n\r\nsecret"
+                            .to_string()
+                    } else {
+                        format!(
+                            "HTTP/1.1 302 Found\r\nlocation: http://rebind.test:{}/secret\r\ncontent-length: 0\r\nconnection: close\r\n\r\n",
+                            addr.port()
+                        )
+                    };
+                    socket.write_all(response.as_bytes()).await.unwrap();
+                });
+            }
+        });
+
+        // 所有域名都解析到本机；public.test 通过固定解析绕过过滤，模拟真实的公网地址
+        let loopback = |_: &str| -> Resolving {
+            Box::pin(async {
+                Ok(Box::new(std::iter::once(SocketAddr::from(([127, 0, 0, 1], 0)))) as Addrs)
+            })
+        };
+        let request = |url: String| HttpFetchRequest {
+            url,
+            method: None,
+            headers: HashMap::new(),
+            body: None,
+            timeout_secs: Some(5),
+            max_bytes: None,
+            cache_ttl_secs: None,
+        };
+        let builder = || {
+            reqwest::Client::builder()
+                .no_proxy()
+                .resolve("public.test", addr)
+        };
+
+        let redirected = fetch(
+            builder(),
+            PublicResolver::new(loopback),
+            None,
+            request(format!("http://public.test:{}/", addr.port())),
+        )
+        .await
+        .unwrap_err();
+        assert!(
+            redirected.contains("rebind.test resolves to 127.0.0.1"),
+            "{}",
+            redirected
+        );
+
+        let direct = fetch(
+            builder(),
+            PublicResolver::new(loopback),
+            None,
+            request(format!("http://rebind.test:{}/secret", addr.port())),
+        )
+        .await
+        .unwrap_err();
+        assert!(
+            direct.contains("rebind.test resolves to 127.0.0.1"),
+            "{}",
+            direct
+        );
+    }
+
+    #[tokio::test]
+    async fn pre_resolves_targets_sent_through_a_proxy() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        // 模拟会转发任意目标的代理：/redirect 跳转到 intranet.test，其余返回 proxied
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                tokio::spawn(async move {
+                    let mut buf = [0u8; 4096];
+                    let n = socket.read(&mut buf).await.unwrap();
+                    let request = String::from_utf8_lossy(&buf[..n]).to_string();
+                    let response = if request.starts_with("GET http://public.test/redirect ") {
+                        "HTTP/1.1 302 Found\r\nlocation: http://intranet.test/\r\ncontent-length: 0\r\nconnection: close\r\n\r\n"
+                    } else {
+                        "HTTP/1.1 200 OK\r\ncontent-type: text/plain\r\ncontent-length: 7\r\nconnection: close\r\n\r\nproxied"
+                    };
+                    socket.write_all(response.as_bytes()).await.unwrap();
+                });
+            }
+        });
+
+        // public.test 解析为公网地址，其余域名（包括代理主机 proxy.test）解析到本机
+        let lookup = |host: &str| -> Resolving {
+            let ip = if host == "public.test" {
+                [93, 184, 216, 34]
+            } else {
+                [127, 0, 0, 1]
+            };
+            Box::pin(
+                async move { Ok(Box::new(std::iter::once(SocketAddr::from((ip, 0)))) as Addrs) },
+            )
+        };
+        let resolver = || {
+            let mut resolver = PublicResolver::new(lookup);
+            resolver.proxy_host = Some("proxy.test".to_string());
+            resolver
+        };
+        let builder = || {
+            reqwest::Client::builder()
+                .proxy(reqwest::Proxy::http(format!("http://proxy.test:{}", addr.port())).unwrap())
+        };
+        let request = |url: &str| HttpFetchRequest {
+            url: url.to_string(),
+            method: None,
+            headers: HashMap::new(),
+            body: None,
+            timeout_secs: Some(5),
+            max_bytes: None,
+            cache_ttl_secs: None,
+        };
+
+        let response = fetch(builder(), resolver(), None, request("http://public.test/"))
+            .await
+            .unwrap();
+        assert_eq!(response.body, "proxied");
+
+        for url in ["http://intranet.test/", "http://public.test/redirect"] {
+            let err = fetch(builder(), resolver(), None, request(url))
+                .await
+                .unwrap_err();
+            assert!(
+                err.contains("intranet.test resolves to 127.0.0.1"),
+                "{}",
+                err
+            );
+        }
+    }
+
+    #[test]
+    fn redirects_follow_browser_method_and_header_rules() {
+        let hop = || Hop {
+            url: url("https://example.com/form"),
+            method: Method::POST,
+            headers: HashMap::from([
+                ("Authorization".to_string(), "Bearer token".to_string()),
+                ("Content-Type".to_string(), "application/json".to_string()),
+            ]),
+            body: Some("{}".to_string()),
+        };
+
+        let same_host = hop().redirect(StatusCode::FOUND, url("https://example.com/done"));
+        assert_eq!(same_host.method, Method::GET);
+        assert_eq!(same_host.body, None);
+        assert_eq!(same_host.headers.len(), 1);
+        assert!(same_host.headers.contains_key("Authorization"));
+
+        let other_host = hop().redirect(
+            StatusCode::TEMPORARY_REDIRECT,
+            url("https://other.example/form"),
+        );
+        assert_eq!(other_host.method, Method::POST);
+        assert_eq!(other_host.body.as_deref(), Some("{}"));
+        assert!(!other_host.headers.contains_key("Authorization"));
+        assert!(other_host.headers.contains_key("Content-Type"));
+    }
+
+    #[test]
+    fn detects_textual_content_types() {
+        assert!(is_textual(Some("text/html; charset=utf-8")));
+        assert!(is_textual(Some("application/ld+json")));
+        assert!(is_textual(Some("Application/JSON")));
+        assert!(is_textual(Some("application/yaml")));
+        assert!(!is_textual(Some("application/pdf")));
+        assert!(!is_textual(Some("image/x-icon")));
+        assert!(!is_textual(None));
+    }
+}
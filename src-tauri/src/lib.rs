@@ -6,12 +6,20 @@ mod commands;
 mod doc_tools;
 mod error;
 pub mod forge_runtime;
+mod flashcards;
 mod fs;
+mod http_fetch;
 mod llm;
+mod llm_cache;
+mod llm_pricing;
 pub mod mcp;
 pub mod mobile_gateway;
 mod node_runtime;
+mod note_database;
+mod note_templates;
 pub mod proxy;
+mod relay_e2e;
+mod text;
 mod typesetting;
 mod update_manager;
 mod vector_db;
@@ -38,6 +46,6 @@ pub use agent::{
 
 // Re-export MCP commands
 pub use mcp::{
-    mcp_init, mcp_list_servers, mcp_list_tools, mcp_reload, mcp_shutdown, mcp_start_server,
-    mcp_stop_server, mcp_test_tool,
+    mcp_clear_cache, mcp_init, mcp_list_servers, mcp_list_tools, mcp_reload, mcp_shutdown,
+    mcp_start_server, mcp_stop_server, mcp_test_tool,
 };
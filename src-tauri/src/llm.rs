@@ -1,20 +1,207 @@
-use futures_util::StreamExt;
 /**
  * LLM HTTP Client
  * 使用 Rust reqwest 库发送 HTTP 请求，避免 WebView 的 HTTP/2 协议问题
- * 支持流式传输 (SSE)
+ * 支持流式传输 (SSE / NDJSON)
+ *
+ * 对话请求（`chat`）与 Agent 共用 `LlmProvider` 抽象：由 provider 构建原生请求，
+ * 响应归一化为 `ProviderResponse`，流式数据块归一化为 `ProviderDelta`
  */
+use crate::agent::llm_provider::{
+    provider_for, stream_payload, LlmProvider, ProviderRequest, ProviderStreamState,
+};
+use crate::llm_cache::{self, CacheMode};
+use futures_util::StreamExt;
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 use std::collections::HashMap;
 use tauri::{AppHandle, Emitter, Manager};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct LLMRequest {
+    #[serde(default)]
     pub url: String,
+    #[serde(default)]
     pub method: String, // "POST" | "GET"
+    #[serde(default)]
     pub headers: HashMap<String, String>,
     pub body: Option<String>, // JSON string
     pub timeout_secs: Option<u64>,
+    /// 响应缓存模式（仅对 temperature 为 0 的请求生效）
+    #[serde(default)]
+    pub cache: CacheMode,
+    /// 对话请求：设置后忽略 url / method / headers / body，由 provider 构建
+    #[serde(default)]
+    pub chat: Option<ChatRequest>,
+}
+
+/// 对话请求（消息与工具定义均为 OpenAI 格式）
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChatRequest {
+    pub provider: String,
+    pub base_url: String,
+    #[serde(default)]
+    pub api_key: String,
+    pub model: String,
+    pub messages: Vec<Value>,
+    #[serde(default)]
+    pub temperature: Option<f32>,
+    pub max_tokens: usize,
+    #[serde(default)]
+    pub tools: Option<Value>,
+    /// 合并到原生请求体的额外字段（对象字段按一层合并）
+    #[serde(default)]
+    pub extra_body: Map<String, Value>,
+    #[serde(default)]
+    pub extra_headers: HashMap<String, String>,
+}
+
+/// 最终发出的 HTTP 请求
+struct PreparedRequest {
+    url: String,
+    method: String,
+    headers: HashMap<String, String>,
+    body: Option<String>,
+    /// 对话请求使用的 provider，用于归一化响应
+    provider: Option<Box<dyn LlmProvider>>,
+}
+
+impl LLMRequest {
+    fn prepare(&self, stream: bool) -> PreparedRequest {
+        let Some(chat) = &self.chat else {
+            return PreparedRequest {
+                url: self.url.clone(),
+                method: self.method.clone(),
+                headers: self.headers.clone(),
+                body: self.body.clone(),
+                provider: None,
+            };
+        };
+
+        let provider = provider_for(&chat.provider, Some(&chat.base_url));
+        let mut headers = provider.headers(&chat.api_key);
+        headers.extend(chat.extra_headers.clone());
+        let mut body = provider.build_body(&ProviderRequest {
+            model: &chat.model,
+            messages: chat.messages.clone(),
+            temperature: chat.temperature,
+            max_tokens: chat.max_tokens,
+            stream,
+            tools: chat.tools.clone(),
+        });
+        merge_extra_body(&mut body, &chat.extra_body);
+
+        PreparedRequest {
+            url: provider.endpoint(&chat.base_url, &chat.model, stream),
+            method: "POST".to_string(),
+            headers,
+            body: Some(body.to_string()),
+            provider: Some(provider),
+        }
+    }
+}
+
+fn merge_extra_body(body: &mut Value, extra: &Map<String, Value>) {
+    let Some(body) = body.as_object_mut() else {
+        return;
+    };
+    for (key, value) in extra {
+        match (body.get_mut(key), value) {
+            (Some(Value::Object(existing)), Value::Object(fields)) => {
+                existing.extend(fields.clone());
+            }
+            _ => {
+                body.insert(key.clone(), value.clone());
+            }
+        }
+    }
+}
+
+impl PreparedRequest {
+    /// 以请求地址标识 provider（Gemini 的模型名也在地址中）
+    fn cache_key(&self, mode: CacheMode, kind: &str) -> Option<String> {
+        if mode == CacheMode::Off {
+            return None;
+        }
+        let body = serde_json::from_str::<Value>(self.body.as_deref()?).ok()?;
+        llm_cache::cache_key_for_body(kind, &self.url, &body)
+    }
+
+    fn builder(&self, client: &reqwest::Client) -> Result<reqwest::RequestBuilder, String> {
+        let mut req_builder = match self.method.to_uppercase().as_str() {
+            "POST" => client.post(&self.url),
+            "GET" => client.get(&self.url),
+            _ => return Err(format!("Unsupported HTTP method: {}", self.method)),
+        };
+
+        // 添加 headers
+        for (key, value) in &self.headers {
+            req_builder = req_builder.header(key, value);
+        }
+
+        // 添加 body
+        if let Some(ref body) = self.body {
+            req_builder = req_builder.body(body.clone());
+        }
+        Ok(req_builder)
+    }
+
+    /// 对话请求的成功响应归一化为 `ProviderResponse` JSON；无法解析时原样返回
+    fn normalize_body(&self, body: String) -> String {
+        let Some(provider) = &self.provider else {
+            return body;
+        };
+        serde_json::from_str::<Value>(&body)
+            .ok()
+            .and_then(|json| serde_json::to_string(&provider.parse_response(&json)).ok())
+            .unwrap_or(body)
+    }
+
+    /// 命中缓存的对话响应没有实际调用，清零用量，调用方不会再计费
+    fn cached_body(&self, body: String) -> String {
+        if self.provider.is_none() {
+            return body;
+        }
+        let Ok(mut json) = serde_json::from_str::<Value>(&body) else {
+            return body;
+        };
+        for field in ["prompt_tokens", "completion_tokens", "total_tokens"] {
+            if json.get(field).is_some() {
+                json[field] = Value::from(0);
+            }
+        }
+        json.to_string()
+    }
+
+    /// 命中缓存回放流式对话时跳过用量增量
+    fn keeps_cached_chunk(&self, chunk: &str) -> bool {
+        self.provider.is_none()
+            || !serde_json::from_str::<Value>(chunk).is_ok_and(|delta| delta["type"] == "usage")
+    }
+
+    /// 把一条流式负载转换为要发送的数据块；对话请求逐个发送 `ProviderDelta` JSON
+    fn stream_chunks(
+        &self,
+        data: &str,
+        state: &mut ProviderStreamState,
+    ) -> Result<Vec<String>, String> {
+        let Some(provider) = &self.provider else {
+            return Ok(vec![data.to_string()]);
+        };
+        let Ok(event) = serde_json::from_str::<Value>(data) else {
+            return Ok(Vec::new());
+        };
+        if let Some(error) = event.get("error").filter(|e| !e.is_null()) {
+            return Err(error["message"]
+                .as_str()
+                .map(|m| m.to_string())
+                .unwrap_or_else(|| error.to_string()));
+        }
+        Ok(provider
+            .parse_stream_event(&event, state)
+            .iter()
+            .filter_map(|delta| serde_json::to_string(delta).ok())
+            .collect())
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -30,6 +217,18 @@ pub async fn llm_fetch(
     proxy_state: tauri::State<'_, crate::proxy::ProxyState>,
     request: LLMRequest,
 ) -> Result<LLMResponse, String> {
+    let prepared = request.prepare(false);
+    let cache_key = prepared.cache_key(request.cache, "body");
+    if let Some(body) = llm_cache::lookup(request.cache, cache_key.as_deref())
+        .and_then(|payload| payload.as_str().map(|s| s.to_string()))
+    {
+        return Ok(LLMResponse {
+            status: 200,
+            body: prepared.cached_body(body),
+            error: None,
+        });
+    }
+
     let client = proxy_state
         .client_with_timeout(std::time::Duration::from_secs(
             request.timeout_secs.unwrap_or(120),
@@ -49,21 +248,7 @@ pub async fn llm_fetch(
             );
         }
 
-        let mut req_builder = match request.method.to_uppercase().as_str() {
-            "POST" => client.post(&request.url),
-            "GET" => client.get(&request.url),
-            _ => return Err(format!("Unsupported HTTP method: {}", request.method)),
-        };
-
-        // 添加 headers
-        for (key, value) in &request.headers {
-            req_builder = req_builder.header(key, value);
-        }
-
-        // 添加 body
-        if let Some(ref body) = request.body {
-            req_builder = req_builder.body(body.clone());
-        }
+        let req_builder = prepared.builder(&client)?;
 
         // 发送请求
         match req_builder.send().await {
@@ -71,6 +256,19 @@ pub async fn llm_fetch(
                 let status = response.status().as_u16();
                 match response.text().await {
                     Ok(body) => {
+                        let success = (200..300).contains(&status);
+                        let body = if success {
+                            prepared.normalize_body(body)
+                        } else {
+                            body
+                        };
+                        if success {
+                            llm_cache::store(
+                                request.cache,
+                                cache_key.as_deref(),
+                                Value::String(body.clone()),
+                            );
+                        }
                         return Ok(LLMResponse {
                             status,
                             body,
@@ -102,7 +300,7 @@ pub async fn llm_fetch(
 #[derive(Debug, Clone, Serialize)]
 pub struct StreamChunk {
     pub request_id: String,
-    pub chunk: String, // SSE data 内容
+    pub chunk: String, // SSE data 内容（对话请求为 ProviderDelta JSON）
     pub done: bool,    // 是否完成
     pub error: Option<String>,
 }
@@ -115,27 +313,45 @@ pub async fn llm_fetch_stream(
     request_id: String,
     request: LLMRequest,
 ) -> Result<(), String> {
+    // 命中缓存时一次性回放完整的数据块序列
+    let prepared = request.prepare(true);
+    let cache_key = prepared.cache_key(request.cache, "stream");
+    if let Some(chunks) = llm_cache::lookup(request.cache, cache_key.as_deref())
+        .and_then(|payload| serde_json::from_value::<Vec<String>>(payload).ok())
+    {
+        for chunk in chunks
+            .into_iter()
+            .filter(|chunk| prepared.keeps_cached_chunk(chunk))
+        {
+            let _ = app.emit(
+                "llm-stream-chunk",
+                StreamChunk {
+                    request_id: request_id.clone(),
+                    chunk,
+                    done: false,
+                    error: None,
+                },
+            );
+        }
+        let _ = app.emit(
+            "llm-stream-chunk",
+            StreamChunk {
+                request_id,
+                chunk: String::new(),
+                done: true,
+                error: None,
+            },
+        );
+        return Ok(());
+    }
+
     let client = proxy_state
         .client_with_timeout(std::time::Duration::from_secs(
             request.timeout_secs.unwrap_or(300),
         ))
         .await?;
 
-    let mut req_builder = match request.method.to_uppercase().as_str() {
-        "POST" => client.post(&request.url),
-        "GET" => client.get(&request.url),
-        _ => return Err(format!("Unsupported HTTP method: {}", request.method)),
-    };
-
-    // 添加 headers
-    for (key, value) in &request.headers {
-        req_builder = req_builder.header(key, value);
-    }
-
-    // 添加 body
-    if let Some(ref body) = request.body {
-        req_builder = req_builder.body(body.clone());
-    }
+    let req_builder = prepared.builder(&client)?;
 
     // 发送请求
     let response = match req_builder.send().await {
@@ -172,6 +388,10 @@ pub async fn llm_fetch_stream(
     // 流式读取响应体
     let mut stream = response.bytes_stream();
     let mut buffer = String::new();
+    // 完整的数据块序列，收到结束标记后才写入缓存；连接中途断开的响应不缓存
+    let mut chunks: Vec<String> = Vec::new();
+    let mut finished = false;
+    let mut stream_state = ProviderStreamState::default();
 
     while let Some(chunk_result) = stream.next().await {
         match chunk_result {
@@ -179,40 +399,62 @@ pub async fn llm_fetch_stream(
                 let text = String::from_utf8_lossy(&bytes);
                 buffer.push_str(&text);
 
-                // 按行处理 SSE 数据
+                // 按行处理 SSE data / NDJSON 数据，跳过空行、注释与 event 行
                 while let Some(newline_pos) = buffer.find('\n') {
                     let line = buffer[..newline_pos].trim().to_string();
                     buffer = buffer[newline_pos + 1..].to_string();
 
-                    // 跳过空行和 keep-alive 注释
-                    if line.is_empty() || line.starts_with(": keep-alive") || line == ":" {
+                    let Some(data) = stream_payload(&line) else {
                         continue;
+                    };
+
+                    // [DONE] 表示流结束
+                    if data == "[DONE]" {
+                        llm_cache::store(request.cache, cache_key.as_deref(), chunks.into());
+                        let _ = app.emit(
+                            "llm-stream-chunk",
+                            StreamChunk {
+                                request_id: request_id.clone(),
+                                chunk: String::new(),
+                                done: true,
+                                error: None,
+                            },
+                        );
+                        return Ok(());
                     }
 
-                    // 解析 SSE data 行
-                    if line.starts_with("data: ") {
-                        let data = &line[6..];
+                    if serde_json::from_str::<Value>(data).is_ok_and(|event| is_final_event(&event))
+                    {
+                        finished = true;
+                    }
 
-                        // [DONE] 表示流结束
-                        if data == "[DONE]" {
+                    let data_chunks = match prepared.stream_chunks(data, &mut stream_state) {
+                        Ok(data_chunks) => data_chunks,
+                        Err(error) => {
                             let _ = app.emit(
                                 "llm-stream-chunk",
                                 StreamChunk {
-                                    request_id: request_id.clone(),
+                                    request_id,
                                     chunk: String::new(),
                                     done: true,
-                                    error: None,
+                                    error: Some(error),
                                 },
                             );
                             return Ok(());
                         }
+                    };
+
+                    for data in data_chunks {
+                        if request.cache.writes() {
+                            chunks.push(data.clone());
+                        }
 
                         // 发送数据块
                         let _ = app.emit(
                             "llm-stream-chunk",
                             StreamChunk {
                                 request_id: request_id.clone(),
-                                chunk: data.to_string(),
+                                chunk: data,
                                 done: false,
                                 error: None,
                             },
@@ -235,7 +477,10 @@ pub async fn llm_fetch_stream(
         }
     }
 
-    // 流正常结束
+    // 流结束：没有 [DONE] 时只有见到结束事件才视为完整响应
+    if finished {
+        llm_cache::store(request.cache, cache_key.as_deref(), chunks.into());
+    }
     let _ = app.emit(
         "llm-stream-chunk",
         StreamChunk {
@@ -249,6 +494,20 @@ pub async fn llm_fetch_stream(
     Ok(())
 }
 
+/// 原生流事件是否标志响应已完整结束（OpenAI `finish_reason`、Anthropic
+/// `message_stop`、Gemini `finishReason`、Ollama `done`）
+fn is_final_event(event: &Value) -> bool {
+    let any_set = |items: &Value, field: &str| {
+        items
+            .as_array()
+            .is_some_and(|items| items.iter().any(|item| !item[field].is_null()))
+    };
+    any_set(&event["choices"], "finish_reason")
+        || any_set(&event["candidates"], "finishReason")
+        || event["type"] == "message_stop"
+        || event["done"] == true
+}
+
 /// 追加调试日志到文件
 #[tauri::command]
 pub async fn append_debug_log(app: AppHandle, content: String) -> Result<(), String> {
@@ -291,3 +550,103 @@ pub fn get_debug_log_path(app: AppHandle) -> Result<String, String> {
     let log_dir = app_dir.join("debug-logs");
     Ok(log_dir.to_string_lossy().to_string())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn chat_request(provider: &str, base_url: &str, extra_body: Value) -> LLMRequest {
+        serde_json::from_value(json!({
+            "body": null,
+            "timeout_secs": null,
+            "chat": {
+                "provider": provider,
+                "base_url": base_url,
+                "model": "m",
+                "messages": [{ "role": "user", "content": "hi" }],
+                "max_tokens": 64,
+                "extra_body": extra_body,
+                "extra_headers": { "X-Title": "Lumina" },
+            }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn chat_requests_are_built_by_the_provider() {
+        let request = chat_request(
+            "ollama",
+            "http://localhost:11434/v1",
+            json!({ "options": { "num_ctx": 8192 } }),
+        );
+        let prepared = request.prepare(true);
+        assert_eq!(prepared.url, "http://localhost:11434/api/chat");
+        assert_eq!(prepared.headers["X-Title"], "Lumina");
+        assert!(!prepared.headers.contains_key("Authorization"));
+        let body: Value = serde_json::from_str(prepared.body.as_deref().unwrap()).unwrap();
+        assert_eq!(body["options"]["num_predict"], 64);
+        assert_eq!(body["options"]["num_ctx"], 8192);
+
+        let mut state = ProviderStreamState::default();
+        let chunks = prepared
+            .stream_chunks(r#"{"message":{"content":"he"},"done":false}"#, &mut state)
+            .unwrap();
+        assert_eq!(chunks, vec![r#"{"type":"text","data":"he"}"#.to_string()]);
+        assert!(prepared
+            .stream_chunks(r#"{"error":"model not found"}"#, &mut state)
+            .is_err());
+    }
+
+    #[test]
+    fn chat_responses_are_normalized() {
+        let prepared =
+            chat_request("anthropic", "https://api.anthropic.com", json!({})).prepare(false);
+        assert_eq!(prepared.url, "https://api.anthropic.com/v1/messages");
+        let body = prepared.normalize_body(
+            json!({
+                "content": [{ "type": "text", "text": "hello" }],
+                "stop_reason": "end_turn",
+                "usage": { "input_tokens": 3, "output_tokens": 2 }
+            })
+            .to_string(),
+        );
+        let body: Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(body["content"], "hello");
+        assert_eq!(body["finish_reason"], "stop");
+        assert_eq!(body["total_tokens"], 5);
+    }
+
+    #[test]
+    fn only_final_stream_events_complete_the_response() {
+        assert!(is_final_event(
+            &json!({ "choices": [{ "delta": {}, "finish_reason": "stop" }] })
+        ));
+        assert!(is_final_event(&json!({ "type": "message_stop" })));
+        assert!(is_final_event(
+            &json!({ "candidates": [{ "finishReason": "STOP" }] })
+        ));
+        assert!(is_final_event(&json!({ "message": {}, "done": true })));
+
+        assert!(!is_final_event(
+            &json!({ "choices": [{ "delta": { "content": "hi" }, "finish_reason": null }] })
+        ));
+        assert!(!is_final_event(&json!({ "type": "content_block_delta" })));
+        assert!(!is_final_event(&json!({ "message": {}, "done": false })));
+    }
+
+    #[test]
+    fn cache_hits_report_no_usage() {
+        let prepared = chat_request("openai", "https://api.openai.com/v1", json!({})).prepare(true);
+        let body = prepared.cached_body(
+            json!({ "content": "hi", "prompt_tokens": 3, "completion_tokens": 2, "total_tokens": 5 })
+                .to_string(),
+        );
+        let body: Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(body["content"], "hi");
+        assert_eq!(body["total_tokens"], 0);
+
+        assert!(prepared.keeps_cached_chunk(r#"{"type":"text","data":"hi"}"#));
+        assert!(!prepared.keeps_cached_chunk(r#"{"type":"usage","data":{"prompt_tokens":3}}"#));
+    }
+}
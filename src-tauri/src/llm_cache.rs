@@ -0,0 +1,225 @@
+//! LLM 响应缓存
+//!
+//! 以 (provider, model, messages, tools, temperature) 的哈希为键，把最终完成结果落盘。
+//! 只有确定性请求（temperature 为 0）才会命中缓存，相同请求可以立即、离线返回。
+//! 流式请求只缓存收到结束标记的完整结果，从不缓存中途的增量或中途断开的流。
+//! 命中缓存时没有实际调用，用量与费用按 0 计。
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use tauri::{AppHandle, Manager};
+
+/// 缓存目录（应用启动时初始化）
+static CACHE_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// 缓存模式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CacheMode {
+    /// 不读不写
+    #[default]
+    Off,
+    /// 命中则返回，未命中请求后写入
+    ReadWrite,
+    /// 只读取已有缓存，不写入
+    ReadOnly,
+}
+
+impl CacheMode {
+    pub fn reads(self) -> bool {
+        matches!(self, Self::ReadWrite | Self::ReadOnly)
+    }
+
+    pub fn writes(self) -> bool {
+        self == Self::ReadWrite
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CacheEntry {
+    created_at: i64,
+    payload: Value,
+}
+
+/// 初始化缓存目录：<app_data_dir>/llm-cache
+pub fn init(app: &AppHandle) {
+    if let Ok(app_data_dir) = app.path().app_data_dir() {
+        let _ = CACHE_DIR.set(app_data_dir.join("llm-cache"));
+    }
+}
+
+fn cache_dir() -> Option<&'static Path> {
+    CACHE_DIR.get().map(|dir| dir.as_path())
+}
+
+/// 计算缓存键；非确定性请求（temperature 不为 0 或未指定）返回 None
+///
+/// `kind` 区分同一请求的不同结果形态（完整响应体 / 流式数据块 / Agent 响应）
+pub fn cache_key(
+    kind: &str,
+    provider: &str,
+    model: &str,
+    messages: &Value,
+    tools: Option<&Value>,
+    temperature: Option<f64>,
+) -> Option<String> {
+    if temperature != Some(0.0) {
+        return None;
+    }
+    let material = json!({
+        "kind": kind,
+        "provider": provider,
+        "model": model,
+        "messages": messages,
+        "tools": tools,
+    });
+    let mut hasher = Sha256::new();
+    hasher.update(material.to_string().as_bytes());
+    Some(hex::encode(hasher.finalize()))
+}
+
+/// 从原生请求体计算缓存键
+///
+/// 兼容 OpenAI / Anthropic / Gemini / Ollama 的请求体：温度与工具单独取出，
+/// 其余字段（除 stream 外）都视为消息内容参与哈希。
+pub fn cache_key_for_body(kind: &str, provider: &str, body: &Value) -> Option<String> {
+    let temperature = body["temperature"]
+        .as_f64()
+        .or_else(|| body["generationConfig"]["temperature"].as_f64())
+        .or_else(|| body["options"]["temperature"].as_f64());
+    let model = body["model"].as_str().unwrap_or_default();
+    let mut messages = body.as_object()?.clone();
+    for field in ["model", "tools", "temperature", "stream", "stream_options"] {
+        messages.remove(field);
+    }
+    cache_key(
+        kind,
+        provider,
+        model,
+        &Value::Object(messages),
+        body.get("tools"),
+        temperature,
+    )
+}
+
+fn entry_path(dir: &Path, key: &str) -> PathBuf {
+    dir.join(format!("{}.json", key))
+}
+
+fn read_entry(dir: &Path, key: &str) -> Option<Value> {
+    let raw = fs::read_to_string(entry_path(dir, key)).ok()?;
+    serde_json::from_str::<CacheEntry>(&raw)
+        .ok()
+        .map(|entry| entry.payload)
+}
+
+fn write_entry(dir: &Path, key: &str, payload: Value) -> Result<(), String> {
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create cache dir: {}", e))?;
+    let entry = CacheEntry {
+        created_at: chrono::Utc::now().timestamp(),
+        payload,
+    };
+    let raw = serde_json::to_string(&entry).map_err(|e| e.to_string())?;
+    // 先写临时文件再重命名，避免并发读取到半截内容
+    let tmp = dir.join(format!("{}.tmp", key));
+    fs::write(&tmp, raw).map_err(|e| format!("Failed to write cache entry: {}", e))?;
+    fs::rename(&tmp, entry_path(dir, key))
+        .map_err(|e| format!("Failed to write cache entry: {}", e))
+}
+
+fn clear_dir(dir: &Path) -> Result<usize, String> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(format!("Failed to read cache dir: {}", e)),
+    };
+    let mut removed = 0;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().and_then(|ext| ext.to_str()) == Some("json")
+            && fs::remove_file(&path).is_ok()
+        {
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
+
+/// 按模式读取缓存
+pub fn lookup(mode: CacheMode, key: Option<&str>) -> Option<Value> {
+    if !mode.reads() {
+        return None;
+    }
+    read_entry(cache_dir()?, key?)
+}
+
+/// 按模式写入缓存（失败只记录日志，不影响请求结果）
+pub fn store(mode: CacheMode, key: Option<&str>, payload: Value) {
+    if !mode.writes() {
+        return;
+    }
+    let (Some(dir), Some(key)) = (cache_dir(), key) else {
+        return;
+    };
+    if let Err(err) = write_entry(dir, key, payload) {
+        eprintln!("[LLM Cache] {}", err);
+    }
+}
+
+/// 清空 LLM 响应缓存，返回删除的条目数
+#[tauri::command]
+pub fn clear_llm_cache() -> Result<usize, String> {
+    match cache_dir() {
+        Some(dir) => clear_dir(dir),
+        None => Ok(0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn body(temperature: f64) -> Value {
+        json!({
+            "model": "gpt-4o-mini",
+            "messages": [{ "role": "user", "content": "hi" }],
+            "temperature": temperature,
+            "stream": true,
+        })
+    }
+
+    #[test]
+    fn only_deterministic_requests_get_a_key() {
+        let key = cache_key_for_body("body", "openai", &body(0.0)).expect("key");
+        assert_eq!(key.len(), 64);
+        assert!(cache_key_for_body("body", "openai", &body(0.7)).is_none());
+
+        let mut streaming_off = body(0.0);
+        streaming_off["stream"] = json!(false);
+        assert_eq!(
+            cache_key_for_body("body", "openai", &streaming_off),
+            Some(key.clone())
+        );
+        assert_ne!(
+            cache_key_for_body("stream", "openai", &body(0.0)),
+            Some(key)
+        );
+    }
+
+    #[test]
+    fn entries_round_trip_and_clear() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        write_entry(dir.path(), "abc", json!({ "content": "cached" })).expect("write");
+        assert_eq!(
+            read_entry(dir.path(), "abc"),
+            Some(json!({ "content": "cached" }))
+        );
+        assert_eq!(read_entry(dir.path(), "missing"), None);
+        assert_eq!(clear_dir(dir.path()).expect("clear"), 1);
+        assert_eq!(read_entry(dir.path(), "abc"), None);
+    }
+}
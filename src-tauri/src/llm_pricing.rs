@@ -0,0 +1,217 @@
+//! LLM 计费
+//!
+//! 按 provider / 模型的单价表（美元 / 1k tokens）把 token 用量换算为费用，
+//! 并按会话累计。provider 未返回 usage 时（部分流式模式）用字符数近似估算 token。
+
+use crate::text::is_cjk;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// 模型单价（美元 / 1k tokens）
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ModelPrice {
+    pub input_per_1k: f64,
+    pub output_per_1k: f64,
+}
+
+const fn price(input_per_1k: f64, output_per_1k: f64) -> ModelPrice {
+    ModelPrice {
+        input_per_1k,
+        output_per_1k,
+    }
+}
+
+/// 单价表：按模型名前缀匹配，更具体的前缀必须排在前面
+const PRICING_TABLE: &[(&str, ModelPrice)] = &[
+    // OpenAI
+    ("gpt-5-nano", price(0.00005, 0.0004)),
+    ("gpt-5-mini", price(0.00025, 0.002)),
+    ("gpt-5", price(0.00125, 0.01)),
+    ("gpt-4.1-nano", price(0.0001, 0.0004)),
+    ("gpt-4.1-mini", price(0.0004, 0.0016)),
+    ("gpt-4.1", price(0.002, 0.008)),
+    ("gpt-4o-mini", price(0.00015, 0.0006)),
+    ("gpt-4o", price(0.0025, 0.01)),
+    ("o4-mini", price(0.0011, 0.0044)),
+    ("o3-mini", price(0.0011, 0.0044)),
+    ("o3", price(0.002, 0.008)),
+    // Anthropic
+    ("claude-opus-4", price(0.015, 0.075)),
+    ("claude-sonnet-4", price(0.003, 0.015)),
+    ("claude-3-7-sonnet", price(0.003, 0.015)),
+    ("claude-3-5-sonnet", price(0.003, 0.015)),
+    ("claude-haiku-4", price(0.001, 0.005)),
+    ("claude-3-5-haiku", price(0.0008, 0.004)),
+    // Gemini
+    ("gemini-2.5-pro", price(0.00125, 0.01)),
+    ("gemini-2.5-flash-lite", price(0.0001, 0.0004)),
+    ("gemini-2.5-flash", price(0.0003, 0.0025)),
+    ("gemini-2.0-flash", price(0.0001, 0.0004)),
+    // DeepSeek
+    ("deepseek-reasoner", price(0.00055, 0.00219)),
+    ("deepseek-chat", price(0.00027, 0.0011)),
+    // Moonshot / Zhipu
+    ("kimi-k2", price(0.0006, 0.0025)),
+    ("glm-4.5", price(0.0006, 0.0022)),
+];
+
+/// 查询模型单价；本地模型（Ollama）免费，未知模型返回 None
+pub fn model_price(provider: &str, model: &str) -> Option<ModelPrice> {
+    if provider == "ollama" {
+        return Some(price(0.0, 0.0));
+    }
+    let normalized = model.trim().to_ascii_lowercase();
+    // OpenRouter 等聚合平台的模型名形如 "openai/gpt-4o"
+    let name = normalized.rsplit('/').next().unwrap_or(&normalized);
+    PRICING_TABLE
+        .iter()
+        .find(|(prefix, _)| name.starts_with(prefix))
+        .map(|(_, price)| *price)
+}
+
+/// 近似估算文本的 token 数：ASCII 约 4 字符 1 token，CJK 约 1 字 1 token
+pub fn estimate_tokens(text: &str) -> usize {
+    let mut quarter_tokens = 0usize;
+    for ch in text.chars() {
+        quarter_tokens += if ch.is_ascii() {
+            1
+        } else if is_cjk(ch) {
+            4
+        } else {
+            2
+        };
+    }
+    quarter_tokens.div_ceil(4)
+}
+
+/// 会话费用汇总
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct CostSummary {
+    pub prompt_tokens: usize,
+    pub completion_tokens: usize,
+    /// 累计费用（美元）
+    pub total_cost: f64,
+    /// 是否有调用使用了估算的 token 数
+    pub estimated: bool,
+    /// 是否有调用的模型不在单价表中（其费用未计入）
+    pub unpriced: bool,
+}
+
+impl CostSummary {
+    /// 记录一次调用，返回本次费用
+    pub fn record(
+        &mut self,
+        provider: &str,
+        model: &str,
+        prompt_tokens: usize,
+        completion_tokens: usize,
+        estimated: bool,
+    ) -> f64 {
+        self.prompt_tokens += prompt_tokens;
+        self.completion_tokens += completion_tokens;
+        self.estimated |= estimated;
+        let Some(price) = model_price(provider, model) else {
+            self.unpriced = true;
+            return 0.0;
+        };
+        let cost = prompt_tokens as f64 / 1000.0 * price.input_per_1k
+            + completion_tokens as f64 / 1000.0 * price.output_per_1k;
+        self.total_cost += cost;
+        cost
+    }
+}
+
+/// 费用预算达到该比例时提醒
+pub const COST_BUDGET_WARNING_RATIO: f64 = 0.8;
+
+/// 是否接近（或超过）费用预算；预算为 0 表示不限制
+pub fn near_cost_budget(total_cost: f64, budget: f64) -> bool {
+    budget > 0.0 && total_cost >= budget * COST_BUDGET_WARNING_RATIO
+}
+
+/// 按会话累计的费用
+#[derive(Default)]
+struct SessionCosts {
+    by_session: HashMap<String, CostSummary>,
+    /// 最近一次记录费用的会话
+    latest: Option<String>,
+}
+
+static SESSION_COSTS: Lazy<Mutex<SessionCosts>> = Lazy::new(|| Mutex::new(SessionCosts::default()));
+
+/// 记录会话中的一次调用，返回 (本次费用, 会话汇总)
+pub fn record_session_usage(
+    session_id: &str,
+    provider: &str,
+    model: &str,
+    prompt_tokens: usize,
+    completion_tokens: usize,
+    estimated: bool,
+) -> (f64, CostSummary) {
+    let mut costs = SESSION_COSTS.lock().unwrap();
+    costs.latest = Some(session_id.to_string());
+    let summary = costs.by_session.entry(session_id.to_string()).or_default();
+    let cost = summary.record(provider, model, prompt_tokens, completion_tokens, estimated);
+    (cost, summary.clone())
+}
+
+/// 查询会话费用汇总（未指定会话时返回最近一次的会话）
+pub fn session_cost(session_id: Option<&str>) -> Option<CostSummary> {
+    let costs = SESSION_COSTS.lock().unwrap();
+    let session_id = session_id.or(costs.latest.as_deref())?;
+    costs.by_session.get(session_id).cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prices_match_most_specific_prefix() {
+        assert_eq!(
+            model_price("openai", "gpt-4o-mini-2024-07-18"),
+            Some(price(0.00015, 0.0006))
+        );
+        assert_eq!(
+            model_price("openrouter", "openai/gpt-4o"),
+            Some(price(0.0025, 0.01))
+        );
+        assert_eq!(model_price("ollama", "llama3"), Some(price(0.0, 0.0)));
+        assert_eq!(model_price("custom", "my-model"), None);
+    }
+
+    #[test]
+    fn summary_accumulates_cost_and_flags() {
+        let mut summary = CostSummary::default();
+        let cost = summary.record("openai", "gpt-4o", 2000, 1000, false);
+        assert!((cost - 0.015).abs() < 1e-9);
+        summary.record("custom", "unknown", 10, 10, true);
+        assert!((summary.total_cost - 0.015).abs() < 1e-9);
+        assert_eq!(summary.prompt_tokens, 2010);
+        assert!(summary.estimated && summary.unpriced);
+        assert!(near_cost_budget(summary.total_cost, 0.018));
+        assert!(!near_cost_budget(summary.total_cost, 0.0));
+    }
+
+    #[test]
+    fn session_costs_accumulate_per_session() {
+        record_session_usage("session-a", "openai", "gpt-4o", 1000, 0, false);
+        record_session_usage("session-a", "openai", "gpt-4o", 1000, 0, false);
+        let (_, summary) = record_session_usage("session-b", "ollama", "llama3", 5, 5, true);
+        assert_eq!(summary.total_cost, 0.0);
+
+        let a = session_cost(Some("session-a")).expect("session a");
+        assert!((a.total_cost - 0.005).abs() < 1e-9);
+        assert_eq!(a.prompt_tokens, 2000);
+        assert!(session_cost(Some("missing")).is_none());
+    }
+
+    #[test]
+    fn estimates_tokens_for_ascii_and_cjk() {
+        assert_eq!(estimate_tokens(""), 0);
+        assert_eq!(estimate_tokens("abcdefgh"), 2);
+        assert_eq!(estimate_tokens("你好"), 2);
+    }
+}
@@ -13,13 +13,22 @@ mod diagnostics;
 mod doc_tools;
 mod error;
 mod forge_runtime;
+mod flashcards;
 mod fs;
+mod http_fetch;
 mod llm;
+mod llm_cache;
+mod llm_pricing;
 mod mcp;
 mod mobile_gateway;
 mod node_runtime;
+mod note_database;
+mod note_map;
+mod note_templates;
 mod plugins;
 mod proxy;
+mod relay_e2e;
+mod text;
 #[cfg(target_os = "macos")]
 mod traffic_lights;
 mod typesetting;
@@ -44,12 +53,18 @@ fn main() {
             commands::path_exists,
             commands::write_binary_file,
             commands::read_binary_file_base64,
+            commands::read_image_thumbnail_base64,
             commands::list_directory,
+            commands::start_directory_scan,
+            commands::cancel_directory_scan,
             commands::fs_set_allowed_roots,
+            commands::set_workspace_readonly,
+            commands::get_workspace_readonly,
             commands::list_directory_tree,
             commands::create_file,
             commands::create_dir,
             commands::delete_file,
+            commands::restore_from_trash,
             commands::rename_file,
             commands::move_file,
             commands::move_folder,
@@ -58,11 +73,15 @@ fn main() {
             commands::close_video_window,
             commands::get_video_time,
             commands::sync_video_time,
+            commands::report_video_time,
+            commands::report_video_frame,
+            commands::capture_video_frame,
             commands::create_embedded_webview,
             commands::update_webview_bounds,
             commands::close_embedded_webview,
             commands::open_new_window,
             commands::get_bilibili_cid,
+            commands::get_bilibili_pages,
             commands::get_bilibili_danmaku,
             commands::seek_video_time,
             commands::fill_danmaku_prefix,
@@ -73,6 +92,8 @@ fn main() {
             commands::typesetting_export_pdf_base64,
             commands::typesetting_render_docx_pdf_base64,
             commands::typesetting_layout_text,
+            commands::typesetting_build_preview,
+            commands::typeset_markdown_to_pdf_base64,
             // Browser WebView commands
             commands::create_browser_webview,
             commands::update_browser_webview_bounds,
@@ -80,6 +101,12 @@ fn main() {
             commands::navigate_browser_webview,
             commands::browser_webview_go_back,
             commands::browser_webview_go_forward,
+            commands::browser_webview_get_state,
+            commands::report_browser_state,
+            commands::browser_webview_find,
+            commands::browser_webview_find_clear,
+            commands::report_find_result,
+            commands::set_browser_download_dir,
             commands::browser_webview_reload,
             commands::set_browser_webview_visible,
             commands::browser_webview_freeze,
@@ -89,14 +116,41 @@ fn main() {
             vector_db::init_vector_db,
             vector_db::upsert_vector_chunks,
             vector_db::search_vector_chunks,
+            vector_db::search_vector_chunks_in_scope,
+            vector_db::rerank_search_results,
             vector_db::delete_file_vectors,
             vector_db::delete_vectors,
             vector_db::get_vector_index_status,
             vector_db::check_file_needs_reindex,
+            vector_db::compute_reindex_plan,
+            vector_db::chunk_markdown,
+            vector_db::extract_attachment_text,
+            vector_db::chunk_attachment,
+            vector_db::embed_texts,
+            vector_db::embed_texts_batched,
             vector_db::clear_vector_index,
+            // Note map
+            note_map::note_map_export_graph,
+            note_map::note_map_generate,
+            note_map::note_map_clear_cache,
+            note_map::build_link_index,
+            note_map::vault_link_report,
+            note_map::build_tag_index,
+            note_map::rename_tag,
+            note_map::export_bundle,
+            note_map::move_note_with_backlink_fix,
+            // Note templates
+            note_templates::list_note_templates,
+            note_templates::create_note_from_template,
+            flashcards::flashcard_review,
+            flashcards::flashcard_due,
+            note_database::validate_database,
             // LLM HTTP client
             llm::llm_fetch,
             llm::llm_fetch_stream,
+            llm_cache::clear_llm_cache,
+            http_fetch::http_fetch,
+            http_fetch::clear_http_cache,
             // Debug logging
             llm::append_debug_log,
             llm::get_debug_log_path,
@@ -122,15 +176,34 @@ fn main() {
             webdav::commands::webdav_execute_sync,
             webdav::commands::webdav_quick_sync,
             webdav::commands::webdav_scan_local,
+            webdav::commands::webdav_enable_auto_sync,
+            webdav::commands::webdav_disable_auto_sync,
+            webdav::commands::webdav_pause_auto_sync,
+            webdav::commands::webdav_resume_auto_sync,
+            webdav::commands::webdav_get_auto_sync_status,
             // Agent commands
             agent::agent_start_task,
             agent::agent_abort,
             agent::agent_approve_tool,
             agent::agent_get_status,
             agent::agent_get_queue_status,
+            agent::agent_cancel_queued,
+            agent::agent_reorder_queue,
             agent::agent_continue_with_answer,
             agent::agent_list_skills,
             agent::agent_read_skill,
+            agent::agent_list_permission_rules,
+            agent::agent_clear_permission_rules,
+            agent::agent_get_audit_log,
+            agent::agent_export_trace,
+            agent::agent_undo_last,
+            agent::agent_undo_run,
+            agent::agent_redo_last,
+            agent::agent_get_cost,
+            agent::agent_event_schema,
+            agent::agent_list_sessions,
+            agent::agent_load_session,
+            agent::agent_delete_session,
             // Agent debug commands
             agent::agent_enable_debug,
             agent::agent_disable_debug,
@@ -149,6 +222,7 @@ fn main() {
             mcp::mcp_list_tools,
             mcp::mcp_reload,
             mcp::mcp_test_tool,
+            mcp::mcp_clear_cache,
             mcp::mcp_shutdown,
             // VS Code extension host (Codex POC)
             codex_vscode_host::codex_vscode_host_start,
@@ -166,6 +240,7 @@ fn main() {
             // Doc tools pack commands
             doc_tools::doc_tools_get_status,
             doc_tools::doc_tools_install_latest,
+            doc_tools::doc_tools_cancel_install,
             // Mobile Gateway commands
             mobile_gateway::mobile_get_status,
             mobile_gateway::mobile_start_server,
@@ -198,12 +273,17 @@ fn main() {
         .manage(cloud_relay::CloudRelayState::new())
         .manage(update_manager::UpdateManagerState::default())
         .manage(commands::ChildWebviewBoundsState::default())
+        .manage(commands::VideoTimeState::default())
+        .manage(commands::VideoFrameRequests::default())
+        .manage(commands::BrowserNavigationState::default())
+        .manage(commands::BrowserDownloadState::default())
         .manage(proxy::ProxyState::new())
         .setup(|app| {
             if let Err(err) = mobile_gateway::hydrate_state(&app.handle()) {
                 eprintln!("[MobileGateway] Failed to hydrate state: {}", err);
             }
             doc_tools::ensure_doc_tools_env(&app.handle());
+            llm_cache::init(&app.handle());
             if env::var_os("LUMINA_SKILLS_DIR").is_none() {
                 if let Some(root) = agent::skills::builtin_skills_root(&app.handle()) {
                     env::set_var("LUMINA_SKILLS_DIR", root);
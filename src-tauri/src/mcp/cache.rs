@@ -0,0 +1,196 @@
+//! MCP 工具结果缓存
+//!
+//! 只缓存配置中声明为 `cacheable` 的工具（默认不缓存，很多工具有副作用）。
+//! 缓存键为 (server, tool, 参数哈希)，参数哈希前会按键名排序，
+//! 因此 `{"a":1,"b":2}` 与 `{"b":2,"a":1}` 命中同一条目。
+//!
+//! 参数中带时间戳的调用不进入缓存：每次调用的时间戳都不同，
+//! 写入只会占满缓存而永远不会命中。
+
+use super::types::McpToolCallResponse;
+use serde::Serialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// 未配置 `cacheTtlSecs` 时的缓存有效期
+pub const DEFAULT_CACHE_TTL: Duration = Duration::from_secs(300);
+/// 缓存条目上限，超出时先清理过期条目，再淘汰最早写入的条目
+const MAX_CACHE_ENTRIES: usize = 256;
+
+/// 视为时间戳的参数名（小写比较）
+const TIMESTAMP_KEYS: &[&str] = &[
+    "timestamp",
+    "time",
+    "ts",
+    "now",
+    "datetime",
+    "nonce",
+    "request_id",
+    "requestid",
+];
+
+/// 一次工具调用的缓存状态，随工具结果返回
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CacheStatus {
+    /// 工具未开启缓存，或参数含时间戳
+    #[default]
+    Bypass,
+    Hit,
+    Miss,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct CacheKey {
+    server: String,
+    tool: String,
+    args_hash: String,
+}
+
+struct CacheEntry {
+    response: McpToolCallResponse,
+    inserted_at: Instant,
+    expires_at: Instant,
+}
+
+#[derive(Default)]
+pub struct ToolResultCache {
+    entries: HashMap<CacheKey, CacheEntry>,
+}
+
+impl ToolResultCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 查询缓存；参数不可缓存时返回 None
+    pub fn get(&mut self, server: &str, tool: &str, args: &Value) -> Option<McpToolCallResponse> {
+        let key = cache_key(server, tool, args)?;
+        let entry = self.entries.get(&key)?;
+        if entry.expires_at <= Instant::now() {
+            self.entries.remove(&key);
+            return None;
+        }
+        Some(entry.response.clone())
+    }
+
+    /// 写入缓存并返回是否写入；错误结果与含时间戳的参数不缓存
+    pub fn insert(
+        &mut self,
+        server: &str,
+        tool: &str,
+        args: &Value,
+        response: &McpToolCallResponse,
+        ttl: Duration,
+    ) -> bool {
+        if response.is_error {
+            return false;
+        }
+        let Some(key) = cache_key(server, tool, args) else {
+            return false;
+        };
+        if self.entries.len() >= MAX_CACHE_ENTRIES && !self.entries.contains_key(&key) {
+            self.evict();
+        }
+        let now = Instant::now();
+        let mut response = response.clone();
+        response.cache = None;
+        self.entries.insert(
+            key,
+            CacheEntry {
+                response,
+                inserted_at: now,
+                expires_at: now + ttl,
+            },
+        );
+        true
+    }
+
+    /// 清空缓存；指定 server 时只清该 server 的条目。返回清除的条目数
+    pub fn clear(&mut self, server: Option<&str>) -> usize {
+        let before = self.entries.len();
+        match server {
+            Some(server) => self.entries.retain(|key, _| key.server != server),
+            None => self.entries.clear(),
+        }
+        before - self.entries.len()
+    }
+
+    fn evict(&mut self) {
+        let now = Instant::now();
+        self.entries.retain(|_, entry| entry.expires_at > now);
+        if self.entries.len() < MAX_CACHE_ENTRIES {
+            return;
+        }
+        let oldest = self
+            .entries
+            .iter()
+            .min_by_key(|(_, entry)| entry.inserted_at)
+            .map(|(key, _)| key.clone());
+        if let Some(key) = oldest {
+            self.entries.remove(&key);
+        }
+    }
+}
+
+fn cache_key(server: &str, tool: &str, args: &Value) -> Option<CacheKey> {
+    if contains_timestamp(args) {
+        return None;
+    }
+    let mut canonical = String::new();
+    write_canonical(args, &mut canonical);
+    Some(CacheKey {
+        server: server.to_string(),
+        tool: tool.to_string(),
+        args_hash: hex::encode(Sha256::digest(canonical.as_bytes())),
+    })
+}
+
+/// 按键名排序序列化，保证参数顺序不影响哈希
+fn write_canonical(value: &Value, out: &mut String) {
+    match value {
+        Value::Object(map) => {
+            let mut keys: Vec<_> = map.keys().collect();
+            keys.sort();
+            out.push('{');
+            for (index, key) in keys.into_iter().enumerate() {
+                if index > 0 {
+                    out.push(',');
+                }
+                out.push_str(&Value::String(key.clone()).to_string());
+                out.push(':');
+                write_canonical(&map[key], out);
+            }
+            out.push('}');
+        }
+        Value::Array(items) => {
+            out.push('[');
+            for (index, item) in items.iter().enumerate() {
+                if index > 0 {
+                    out.push(',');
+                }
+                write_canonical(item, out);
+            }
+            out.push(']');
+        }
+        other => out.push_str(&other.to_string()),
+    }
+}
+
+/// 参数中是否含时间戳：时间类键名，或形如 RFC 3339 的字符串值
+fn contains_timestamp(value: &Value) -> bool {
+    match value {
+        Value::Object(map) => map.iter().any(|(key, value)| {
+            let key = key.to_ascii_lowercase();
+            TIMESTAMP_KEYS.contains(&key.as_str())
+                || key.ends_with("_at")
+                || key.ends_with("timestamp")
+                || contains_timestamp(value)
+        }),
+        Value::Array(items) => items.iter().any(contains_timestamp),
+        Value::String(text) => chrono::DateTime::parse_from_rfc3339(text.trim()).is_ok(),
+        _ => false,
+    }
+}
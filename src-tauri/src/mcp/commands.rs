@@ -82,7 +82,7 @@ pub async fn mcp_test_tool(
     server_name: String,
     tool_name: String,
     args: serde_json::Value,
-) -> Result<String, String> {
+) -> Result<McpTestToolResult, String> {
     let global = McpManager::global();
     let manager = global.read().await;
     let response = manager.call_tool(&server_name, &tool_name, args).await?;
@@ -98,7 +98,18 @@ pub async fn mcp_test_tool(
         .collect::<Vec<_>>()
         .join("\n");
 
-    Ok(content)
+    Ok(McpTestToolResult {
+        content,
+        cache: response.cache,
+    })
+}
+
+/// 清空工具结果缓存，返回清除的条目数
+#[tauri::command]
+pub async fn mcp_clear_cache(server_name: Option<String>) -> Result<usize, String> {
+    let global = McpManager::global();
+    let manager = global.read().await;
+    Ok(manager.clear_cache(server_name.as_deref()))
 }
 
 /// 关闭所有 MCP 连接
@@ -111,6 +122,14 @@ pub async fn mcp_shutdown() -> Result<(), String> {
 
 // ============ 辅助类型 ============
 
+/// 测试工具调用的结果：文本内容 + 缓存命中情况
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct McpTestToolResult {
+    pub content: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cache: Option<super::cache::CacheStatus>,
+}
+
 /// 工具信息（用于前端展示）
 #[derive(Debug, Clone, serde::Serialize)]
 pub struct McpToolInfo {
@@ -2,12 +2,14 @@
 //!
 //! 注意：Server 名称不应包含双下划线 `__`，因为它用于分隔 server 和 tool 名称。
 
+use super::cache::{CacheStatus, ToolResultCache, DEFAULT_CACHE_TTL};
 use super::client::McpClient;
 use super::config::load_mcp_config;
 use super::types::*;
 use once_cell::sync::Lazy;
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use tokio::sync::RwLock;
 
 /// 全局 MCP Manager 单例
@@ -23,6 +25,10 @@ pub struct McpManager {
     workspace_path: Option<String>,
     /// 缓存的 autoApprove 配置，避免频繁加锁
     auto_approve_cache: HashMap<String, Vec<String>>,
+    /// 每个 server 可缓存的工具及缓存有效期
+    cacheable_tools: HashMap<String, (Vec<String>, Duration)>,
+    /// 工具结果缓存；call_tool 只持有 manager 读锁，因此单独加锁
+    result_cache: Mutex<ToolResultCache>,
 }
 
 impl McpManager {
@@ -32,6 +38,8 @@ impl McpManager {
             clients: HashMap::new(),
             workspace_path: None,
             auto_approve_cache: HashMap::new(),
+            cacheable_tools: HashMap::new(),
+            result_cache: Mutex::new(ToolResultCache::new()),
         }
     }
 
@@ -79,13 +87,22 @@ impl McpManager {
         Ok(())
     }
 
-    /// 重建 autoApprove 缓存
+    /// 重建 autoApprove 与 cacheable 缓存
     fn rebuild_auto_approve_cache(&mut self) {
         self.auto_approve_cache.clear();
+        self.cacheable_tools.clear();
         if let Some(ref config) = self.config {
             for (name, server_config) in &config.mcp_servers {
                 self.auto_approve_cache
                     .insert(name.clone(), server_config.auto_approve.clone());
+                if !server_config.cacheable.is_empty() {
+                    let ttl = server_config
+                        .cache_ttl_secs
+                        .map(Duration::from_secs)
+                        .unwrap_or(DEFAULT_CACHE_TTL);
+                    self.cacheable_tools
+                        .insert(name.clone(), (server_config.cacheable.clone(), ttl));
+                }
             }
         }
     }
@@ -119,6 +136,7 @@ impl McpManager {
 
     /// 停止单个 Server
     pub async fn stop_server(&mut self, name: &str) -> Result<(), String> {
+        self.clear_cache(Some(name));
         if let Some(client) = self.clients.remove(name) {
             client.shutdown().await?;
             println!("[MCP] Server '{}' stopped", name);
@@ -180,7 +198,7 @@ impl McpManager {
         self.clients.get(server_name).cloned()
     }
 
-    /// 调用工具（保持向后兼容）
+    /// 调用工具；配置为 cacheable 的工具会先查结果缓存
     pub async fn call_tool(
         &self,
         server_name: &str,
@@ -192,7 +210,48 @@ impl McpManager {
             .get(server_name)
             .ok_or_else(|| format!("Server '{}' not connected", server_name))?;
 
-        client.call_tool(tool_name, arguments).await
+        let Some(ttl) = self.cache_ttl(server_name, tool_name) else {
+            let mut response = client.call_tool(tool_name, arguments).await?;
+            response.cache = Some(CacheStatus::Bypass);
+            return Ok(response);
+        };
+
+        if let Some(mut cached) = self.lock_cache().get(server_name, tool_name, &arguments) {
+            cached.cache = Some(CacheStatus::Hit);
+            return Ok(cached);
+        }
+
+        let mut response = client.call_tool(tool_name, arguments.clone()).await?;
+        // 含时间戳的参数不会写入缓存，仍标记为 bypass
+        let stored = self
+            .lock_cache()
+            .insert(server_name, tool_name, &arguments, &response, ttl);
+        response.cache = Some(if stored {
+            CacheStatus::Miss
+        } else {
+            CacheStatus::Bypass
+        });
+        Ok(response)
+    }
+
+    /// 工具的缓存有效期；未配置为 cacheable 时返回 None
+    fn cache_ttl(&self, server_name: &str, tool_name: &str) -> Option<Duration> {
+        self.cacheable_tools
+            .get(server_name)
+            .filter(|(tools, _)| tools.iter().any(|tool| tool == tool_name))
+            .map(|(_, ttl)| *ttl)
+    }
+
+    fn lock_cache(&self) -> std::sync::MutexGuard<'_, ToolResultCache> {
+        // 缓存内容可随时丢弃，锁中毒时直接沿用
+        self.result_cache
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// 清空工具结果缓存；指定 server 时只清该 server。返回清除的条目数
+    pub fn clear_cache(&self, server_name: Option<&str>) -> usize {
+        self.lock_cache().clear(server_name)
     }
 
     /// 检查工具是否自动批准（使用缓存，无需锁）
@@ -207,6 +266,8 @@ impl McpManager {
     pub async fn reload(&mut self) -> Result<(), String> {
         println!("[MCP] Reloading configuration...");
 
+        self.clear_cache(None);
+
         // 关闭所有现有连接
         for (name, client) in self.clients.drain() {
             println!("[MCP] Stopping server '{}'", name);
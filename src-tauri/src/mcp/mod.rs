@@ -2,6 +2,7 @@
 //!
 //! 提供与外部 MCP Server 的集成能力
 
+pub mod cache;
 pub mod client;
 pub mod commands;
 pub mod config;
@@ -93,6 +93,8 @@ mod tests {
                 env: HashMap::new(),
                 disabled: false,
                 auto_approve: vec!["tool1".to_string()],
+                cacheable: vec![],
+                cache_ttl_secs: None,
             },
         );
 
@@ -317,4 +319,119 @@ mod tests {
         assert_eq!(tool.name, "simple_tool");
         assert!(tool.description.is_none());
     }
+
+    // ============ 结果缓存测试 ============
+
+    fn text_response(text: &str) -> McpToolCallResponse {
+        serde_json::from_value(serde_json::json!({
+            "content": [{"type": "text", "text": text}]
+        }))
+        .unwrap()
+    }
+
+    /// 用户场景：配置 cacheable 的工具
+    /// 期望：默认不缓存，配置后正确解析
+    #[test]
+    fn test_cacheable_config_defaults_off() {
+        let plain: McpServerConfig =
+            serde_json::from_str(r#"{"command": "npx", "args": []}"#).unwrap();
+        assert!(plain.cacheable.is_empty());
+        assert!(plain.cache_ttl_secs.is_none());
+
+        let cached: McpServerConfig = serde_json::from_str(
+            r#"{"command": "npx", "args": [], "cacheable": ["search_docs"], "cacheTtlSecs": 60}"#,
+        )
+        .unwrap();
+        assert_eq!(cached.cacheable, vec!["search_docs".to_string()]);
+        assert_eq!(cached.cache_ttl_secs, Some(60));
+    }
+
+    /// 用户场景：Agent 以相同参数（键顺序不同）重复调用文档查询
+    /// 期望：第二次命中缓存，清空后失效
+    #[test]
+    fn test_cache_hits_identical_args() {
+        use crate::mcp::cache::ToolResultCache;
+        use std::time::Duration;
+
+        let mut cache = ToolResultCache::new();
+        let args = serde_json::json!({"query": "axum", "limit": 5});
+        let reordered: serde_json::Value =
+            serde_json::from_str(r#"{"limit": 5, "query": "axum"}"#).unwrap();
+
+        assert!(cache.get("docs", "search", &args).is_none());
+        assert!(cache.insert(
+            "docs",
+            "search",
+            &args,
+            &text_response("result"),
+            Duration::from_secs(60)
+        ));
+
+        let hit = cache.get("docs", "search", &reordered).unwrap();
+        assert!(matches!(&hit.content[0], McpContentBlock::Text { text } if text == "result"));
+        assert!(cache
+            .get(
+                "docs",
+                "search",
+                &serde_json::json!({"query": "tokio", "limit": 5})
+            )
+            .is_none());
+        assert!(cache.get("other", "search", &args).is_none());
+
+        assert_eq!(cache.clear(Some("other")), 0);
+        assert_eq!(cache.clear(Some("docs")), 1);
+        assert!(cache.get("docs", "search", &args).is_none());
+    }
+
+    /// 用户场景：参数中带时间戳，或调用出错
+    /// 期望：不写入缓存，过期条目不再返回
+    #[test]
+    fn test_cache_skips_timestamps_errors_and_expired() {
+        use crate::mcp::cache::ToolResultCache;
+        use std::time::Duration;
+
+        let mut cache = ToolResultCache::new();
+        let ttl = Duration::from_secs(60);
+        let ok = text_response("result");
+
+        for args in [
+            serde_json::json!({"query": "axum", "timestamp": 1700000000}),
+            serde_json::json!({"query": "axum", "created_at": "yesterday"}),
+            serde_json::json!({"filter": {"since": "2024-05-01T10:00:00Z"}}),
+        ] {
+            assert!(!cache.insert("docs", "search", &args, &ok, ttl));
+            assert!(cache.get("docs", "search", &args).is_none());
+        }
+
+        let mut failed = text_response("boom");
+        failed.is_error = true;
+        let args = serde_json::json!({"query": "axum"});
+        assert!(!cache.insert("docs", "search", &args, &failed, ttl));
+
+        assert!(cache.insert("docs", "search", &args, &ok, Duration::ZERO));
+        assert!(cache.get("docs", "search", &args).is_none());
+    }
+
+    /// 用户场景：查看工具结果的缓存状态
+    /// 期望：缓存状态随结果序列化，Server 返回的同名字段被忽略
+    #[test]
+    fn test_cache_status_is_serialized_with_response() {
+        use crate::mcp::cache::CacheStatus;
+
+        let mut response: McpToolCallResponse = serde_json::from_value(serde_json::json!({
+            "content": [{"type": "text", "text": "result"}],
+            "cache": "hit"
+        }))
+        .unwrap();
+        assert!(response.cache.is_none());
+        assert!(serde_json::to_value(&response)
+            .unwrap()
+            .get("cache")
+            .is_none());
+
+        response.cache = Some(CacheStatus::Hit);
+        let value = serde_json::to_value(&response).unwrap();
+        assert_eq!(value["cache"], "hit");
+        assert_eq!(value["content"][0]["text"], "result");
+    }
 }
@@ -13,6 +13,7 @@
 //! - ✅ `my_server` → `mcp_my_server__my_tool`
 //! - ❌ `my__server` → 解析错误
 
+use super::cache::CacheStatus;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -38,6 +39,16 @@ pub struct McpServerConfig {
     pub disabled: bool,
     #[serde(default, rename = "autoApprove")]
     pub auto_approve: Vec<String>,
+    /// 结果可缓存的工具（纯查询、无副作用），默认不缓存
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cacheable: Vec<String>,
+    /// 缓存有效期（秒），未设置时为 300
+    #[serde(
+        default,
+        rename = "cacheTtlSecs",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub cache_ttl_secs: Option<u64>,
 }
 
 /// MCP 工具定义（从 tools/list 返回）
@@ -70,14 +81,18 @@ pub enum ServerConnectionStatus {
 }
 
 /// MCP 工具调用结果
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct McpToolCallResponse {
     pub content: Vec<McpContentBlock>,
     #[serde(default, rename = "isError")]
     pub is_error: bool,
+    /// 本次调用是否命中结果缓存（本地元数据，不从 Server 响应中读取）；
+    /// 经 `McpManager::call_tool` 返回时总是 Some
+    #[serde(skip_deserializing, skip_serializing_if = "Option::is_none")]
+    pub cache: Option<CacheStatus>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "lowercase")]
 pub enum McpContentBlock {
     Text {
@@ -93,7 +108,7 @@ pub enum McpContentBlock {
     },
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct McpResource {
     pub uri: String,
     #[serde(default)]
@@ -434,6 +434,7 @@ pub fn emit_agent_event(app: &AppHandle, event: AgentEvent) {
 }
 
 pub fn emit_agent_event_payload(app: &AppHandle, payload: Value) {
+    crate::agent::session_store::record_event(&payload);
     let state = app.state::<MobileGatewayState>();
     let session_id = state.get_current_session_id_snapshot();
     let payload_for_app = match session_id.clone() {
@@ -446,7 +447,10 @@ pub fn emit_agent_event_payload(app: &AppHandle, payload: Value) {
         },
         None => payload.clone(),
     };
-    let _ = app.emit("agent-event", payload_for_app);
+    let _ = app.emit(
+        crate::agent::event_schema::AGENT_EVENT_CHANNEL,
+        payload_for_app,
+    );
     if state.events.receiver_count() == 0 {
         return;
     }
@@ -669,9 +673,15 @@ pub async fn handle_mobile_message(
             tokio::spawn(async move {
                 let context = build_task_context(workspace_path, context, Some(session_id));
                 let agent_state = app_handle.state::<AgentState>();
-                let result =
-                    agent_start_task(app_handle.clone(), agent_state, agent_config, task, context)
-                        .await;
+                let result = agent_start_task(
+                    app_handle.clone(),
+                    agent_state,
+                    agent_config,
+                    task,
+                    context,
+                    None,
+                )
+                .await;
                 if let Err(err) = result {
                     (sender_clone)(MobileServerMessage::Error { message: err });
                 }
@@ -1236,6 +1246,7 @@ fn build_task_context(
         resolved_links: Vec::new(),
         history: Vec::new(),
         skills: Vec::new(),
+        session_id: mobile_session_id.clone(),
         mobile_session_id,
     }
 }
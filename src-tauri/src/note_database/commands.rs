@@ -0,0 +1,17 @@
+//! Tauri commands for note databases
+
+use super::{validate_databases, ValidationReport};
+use crate::error::AppError;
+use std::path::Path;
+
+/// Check database member notes against their column definitions
+///
+/// Validates `database_id`, or every database (and reports orphan notes) when
+/// omitted.
+#[tauri::command]
+pub async fn validate_database(
+    workspace: String,
+    database_id: Option<String>,
+) -> Result<ValidationReport, AppError> {
+    validate_databases(Path::new(&workspace), database_id.as_deref())
+}
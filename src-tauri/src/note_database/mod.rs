@@ -0,0 +1,433 @@
+//! Validation of note databases
+//!
+//! A database is defined by `Databases/<dbId>.db.json` (its columns) and its
+//! rows are the notes whose frontmatter has `db: <dbId>`. Nothing stops a note
+//! from drifting away from the column definitions, so [`validate_databases`]
+//! scans the members and reports what needs fixing: missing required fields,
+//! values that do not fit the column type, and notes referring to a database
+//! that does not exist.
+
+pub mod commands;
+
+pub use commands::*;
+
+use crate::error::AppError;
+use crate::forge_runtime::frontmatter::frontmatter_string_list;
+use crate::forge_runtime::tools::query_database::{collect_rows, parse_iso_date};
+use forge::runtime::cancel::CancellationToken;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Workspace-relative directory holding the database definitions
+pub const DATABASE_DIR: &str = "Databases";
+const SCHEMA_SUFFIX: &str = ".db.json";
+
+/// Column definition, as stored by the frontend (`DatabaseColumn`)
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Column {
+    id: String,
+    name: String,
+    #[serde(rename = "type")]
+    column_type: String,
+    #[serde(default)]
+    options: Vec<SelectOption>,
+    #[serde(default)]
+    required: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct SelectOption {
+    id: String,
+    name: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Schema {
+    #[serde(default)]
+    columns: Vec<Column>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueKind {
+    /// A required column has no value
+    MissingField,
+    /// The value does not fit the column type
+    TypeMismatch,
+    /// A select value that is not one of the column's options
+    UnknownOption,
+    /// The note's `db` names a database without a definition
+    OrphanNote,
+    /// The `.db.json` file could not be read
+    InvalidSchema,
+}
+
+/// One problem to fix
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidationIssue {
+    pub kind: IssueKind,
+    pub database_id: String,
+    /// Workspace-relative path of the note (or of the definition file)
+    pub path: String,
+    /// Column name, for field issues
+    pub column: Option<String>,
+    /// Column type, for field issues
+    pub expected: Option<String>,
+    /// The offending value, for type issues
+    pub value: Option<Value>,
+    pub message: String,
+}
+
+/// Result of a validation run
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidationReport {
+    /// Databases whose members were checked
+    pub databases: Vec<String>,
+    pub notes_checked: usize,
+    /// Sorted by note path
+    pub issues: Vec<ValidationIssue>,
+}
+
+/// Validate one database (`Some(id)`) or every database in the workspace
+///
+/// With `None`, notes referring to any undefined database are reported as
+/// orphans; with `Some(id)` only that database's notes are looked at.
+pub fn validate_databases(
+    workspace: &Path,
+    database_id: Option<&str>,
+) -> Result<ValidationReport, AppError> {
+    let rows = collect_rows(workspace, database_id, &CancellationToken::new())
+        .map_err(|err| AppError::Internal(err.to_string()))?;
+    let mut members: BTreeMap<String, Vec<_>> = BTreeMap::new();
+    for row in rows {
+        let db = row.fields["db"].as_str().unwrap_or_default().to_string();
+        members.entry(db).or_default().push(row);
+    }
+    if let Some(id) = database_id {
+        // Also check a defined database that has no members yet
+        members.entry(id.to_string()).or_default();
+    } else {
+        for id in defined_databases(workspace) {
+            members.entry(id).or_default();
+        }
+    }
+
+    let mut report = ValidationReport::default();
+    for (db, rows) in members {
+        let schema_path = workspace
+            .join(DATABASE_DIR)
+            .join(format!("{}{}", db, SCHEMA_SUFFIX));
+        let schema = match std::fs::read_to_string(&schema_path) {
+            Ok(content) => match serde_json::from_str::<Schema>(&content) {
+                Ok(schema) => schema,
+                Err(err) => {
+                    report.issues.push(ValidationIssue {
+                        kind: IssueKind::InvalidSchema,
+                        database_id: db.clone(),
+                        path: format!("{}/{}{}", DATABASE_DIR, db, SCHEMA_SUFFIX),
+                        column: None,
+                        expected: None,
+                        value: None,
+                        message: format!("Invalid database definition: {}", err),
+                    });
+                    continue;
+                }
+            },
+            Err(_) => {
+                for row in rows {
+                    report.notes_checked += 1;
+                    report.issues.push(ValidationIssue {
+                        kind: IssueKind::OrphanNote,
+                        database_id: db.clone(),
+                        path: row.path,
+                        column: None,
+                        expected: None,
+                        value: None,
+                        message: format!("Database \"{}\" does not exist", db),
+                    });
+                }
+                continue;
+            }
+        };
+
+        report.databases.push(db.clone());
+        for row in rows {
+            report.notes_checked += 1;
+            for column in &schema.columns {
+                if let Some(issue) = check_field(&db, &row.path, &row.fields, column) {
+                    report.issues.push(issue);
+                }
+            }
+        }
+    }
+    report
+        .issues
+        .sort_by(|a, b| a.path.cmp(&b.path).then(a.column.cmp(&b.column)));
+    Ok(report)
+}
+
+/// Ids of the databases defined in `Databases/`
+fn defined_databases(workspace: &Path) -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir(workspace.join(DATABASE_DIR)) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            name.strip_suffix(SCHEMA_SUFFIX).map(str::to_string)
+        })
+        .collect()
+}
+
+/// Look a column up the way the frontend does: by name, then by id, ignoring case
+fn field_value<'a>(fields: &'a Map<String, Value>, column: &Column) -> Option<&'a Value> {
+    fields
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(&column.name))
+        .or_else(|| {
+            fields
+                .iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case(&column.id))
+        })
+        .map(|(_, value)| value)
+}
+
+fn is_blank(value: &Value) -> bool {
+    match value {
+        Value::Null => true,
+        Value::String(text) => text.trim().is_empty(),
+        Value::Array(items) => items.is_empty(),
+        _ => false,
+    }
+}
+
+fn check_field(
+    db: &str,
+    path: &str,
+    fields: &Map<String, Value>,
+    column: &Column,
+) -> Option<ValidationIssue> {
+    let issue = |kind, value: Option<&Value>, message: String| ValidationIssue {
+        kind,
+        database_id: db.to_string(),
+        path: path.to_string(),
+        column: Some(column.name.clone()),
+        expected: Some(column.column_type.clone()),
+        value: value.cloned(),
+        message,
+    };
+    let value = match field_value(fields, column) {
+        Some(value) if !is_blank(value) => value,
+        _ if column.required => {
+            return Some(issue(
+                IssueKind::MissingField,
+                None,
+                format!("Missing required field \"{}\"", column.name),
+            ));
+        }
+        _ => return None,
+    };
+
+    let type_matches = match column.column_type.as_str() {
+        "number" => as_number(value).is_some(),
+        "checkbox" => {
+            matches!(value, Value::Bool(_)) || matches!(value.as_str(), Some("true" | "false"))
+        }
+        "date" => value.as_str().is_some_and(is_date_or_range),
+        "text" | "url" | "select" => is_scalar(value),
+        "multi-select" | "relation" => {
+            is_scalar(value)
+                || value
+                    .as_array()
+                    .is_some_and(|items| items.iter().all(is_scalar))
+        }
+        // Formulas are computed; unknown types come from newer versions
+        _ => true,
+    };
+    if !type_matches {
+        return Some(issue(
+            IssueKind::TypeMismatch,
+            Some(value),
+            format!(
+                "\"{}\" should be a {} value, found {}",
+                column.name,
+                column.column_type,
+                describe(value)
+            ),
+        ));
+    }
+
+    if matches!(column.column_type.as_str(), "select" | "multi-select")
+        && !column.options.is_empty()
+    {
+        let unknown: Vec<String> = frontmatter_string_list(&match value {
+            Value::String(text) if column.column_type == "select" => {
+                Value::Array(vec![Value::String(text.clone())])
+            }
+            other => other.clone(),
+        })
+        .into_iter()
+        .filter(|choice| {
+            !column
+                .options
+                .iter()
+                .any(|option| option.id == *choice || option.name.eq_ignore_ascii_case(choice))
+        })
+        .collect();
+        if !unknown.is_empty() {
+            return Some(issue(
+                IssueKind::UnknownOption,
+                Some(value),
+                format!(
+                    "\"{}\" has values that are not options: {}",
+                    column.name,
+                    unknown.join(", ")
+                ),
+            ));
+        }
+    }
+    None
+}
+
+fn is_scalar(value: &Value) -> bool {
+    matches!(value, Value::String(_) | Value::Number(_) | Value::Bool(_))
+}
+
+fn as_number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(number) => number.as_f64(),
+        Value::String(text) => text.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+/// A date, or a `start - end` range as written by the table editor
+fn is_date_or_range(text: &str) -> bool {
+    parse_iso_date(text).is_some()
+        || text.split_once(" - ").is_some_and(|(start, end)| {
+            parse_iso_date(start).is_some() && parse_iso_date(end).is_some()
+        })
+}
+
+fn describe(value: &Value) -> String {
+    match value {
+        Value::Array(_) => "a list".to_string(),
+        Value::Object(_) => "a map".to_string(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(root: &Path, path: &str, content: &str) {
+        let path = root.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+
+    fn kinds(report: &ValidationReport) -> Vec<(&str, Option<&str>, IssueKind)> {
+        report
+            .issues
+            .iter()
+            .map(|issue| (issue.path.as_str(), issue.column.as_deref(), issue.kind))
+            .collect()
+    }
+
+    #[test]
+    fn reports_missing_fields_type_mismatches_and_orphans() {
+        let workspace = tempfile::tempdir().unwrap();
+        let root = workspace.path();
+        write(
+            root,
+            "Databases/tasks.db.json",
+            r#"{
+                "id": "tasks",
+                "columns": [
+                    { "id": "title", "name": "Title", "type": "text", "required": true },
+                    { "id": "status", "name": "Status", "type": "select",
+                      "options": [{ "id": "todo", "name": "Todo" }, { "id": "done", "name": "Done" }] },
+                    { "id": "estimate", "name": "Estimate", "type": "number" },
+                    { "id": "due", "name": "Due", "type": "date" },
+                    { "id": "tags", "name": "Tags", "type": "multi-select" },
+                    { "id": "score", "name": "Score", "type": "formula" }
+                ]
+            }"#,
+        );
+        write(
+            root,
+            "Databases/tasks/ok.md",
+            "---\ndb: tasks\ntitle: Ship\nstatus: todo\nestimate: \"3\"\ndue: 2024-01-01 - 2024-01-05\ntags: [a, b]\n---\n",
+        );
+        write(
+            root,
+            "Databases/tasks/bad.md",
+            "---\ndb: tasks\nStatus: Later\nestimate: lots\ndue: someday\ntags:\n  nested: map\n---\n",
+        );
+        write(root, "Notes/lost.md", "---\ndb: archive\ntitle: Old\n---\n");
+        write(root, "Notes/plain.md", "# no database\n");
+
+        let report = validate_databases(root, None).unwrap();
+        assert_eq!(report.databases, vec!["tasks"]);
+        assert_eq!(report.notes_checked, 3);
+        assert_eq!(
+            kinds(&report),
+            vec![
+                (
+                    "Databases/tasks/bad.md",
+                    Some("Due"),
+                    IssueKind::TypeMismatch
+                ),
+                (
+                    "Databases/tasks/bad.md",
+                    Some("Estimate"),
+                    IssueKind::TypeMismatch
+                ),
+                (
+                    "Databases/tasks/bad.md",
+                    Some("Status"),
+                    IssueKind::UnknownOption
+                ),
+                (
+                    "Databases/tasks/bad.md",
+                    Some("Tags"),
+                    IssueKind::TypeMismatch
+                ),
+                (
+                    "Databases/tasks/bad.md",
+                    Some("Title"),
+                    IssueKind::MissingField
+                ),
+                ("Notes/lost.md", None, IssueKind::OrphanNote),
+            ]
+        );
+
+        let single = validate_databases(root, Some("tasks")).unwrap();
+        assert_eq!(single.notes_checked, 2);
+        assert!(single
+            .issues
+            .iter()
+            .all(|issue| issue.kind != IssueKind::OrphanNote));
+    }
+
+    #[test]
+    fn reports_unreadable_definitions() {
+        let workspace = tempfile::tempdir().unwrap();
+        write(workspace.path(), "Databases/broken.db.json", "{ not json");
+        write(workspace.path(), "a.md", "---\ndb: broken\n---\n");
+
+        let report = validate_databases(workspace.path(), Some("broken")).unwrap();
+        assert_eq!(
+            kinds(&report),
+            vec![("Databases/broken.db.json", None, IssueKind::InvalidSchema)]
+        );
+        assert!(report.databases.is_empty());
+    }
+}
@@ -0,0 +1,528 @@
+//! Export a set of notes as one markdown document
+//!
+//! The selected notes (folders expand to every note below them) come first,
+//! followed by the notes they link to, up to `max_depth` hops. Each note
+//! becomes a section with its own anchor: links between bundled notes turn
+//! into `#anchor` links, links to notes left out become plain text, and
+//! relative image paths are rewritten to resolve from the output folder. The
+//! result can be fed to `typeset_markdown_to_pdf_base64` for a printable copy.
+
+use super::parser::{ParsedNote, MARKDOWN_LINK_RE, URL_SCHEME_RE};
+use super::report::is_note_target;
+use super::rewrite::{relative_path, relative_to};
+use super::{normalize_rel_path, parent_dir, scan_workspace, LinkResolver, NoteLink};
+use crate::error::AppError;
+use crate::forge_runtime::frontmatter::split_frontmatter;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::ops::Range;
+use std::path::Path;
+use walkdir::WalkDir;
+
+/// Deepest link-following allowed, whatever the options ask for
+const MAX_DEPTH: usize = 10;
+
+/// `[[target#heading^block|alias]]`, with an optional `!` embed marker
+static BUNDLE_WIKI_LINK_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(!?)\[\[([^\]|#^]*)(?:#([^\]|^]*))?(?:\^[^\]|]*)?(?:\|([^\]]*))?\]\]")
+        .expect("valid bundle wikilink regex")
+});
+
+/// Bundle export options
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BundleOptions {
+    /// Link hops to follow from the selected notes (0 = only the selection)
+    pub max_depth: usize,
+    /// Workspace-relative folder the bundle will be saved in; image paths
+    /// are written relative to it (workspace root when empty)
+    pub output_dir: String,
+    /// Optional document title; note sections then start at level 2
+    pub title: Option<String>,
+}
+
+/// A note included in the bundle
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BundledNote {
+    /// Workspace-relative path
+    pub path: String,
+    pub title: String,
+    /// Anchor of the note's section
+    pub anchor: String,
+    /// Link hops from the selection (0 for selected notes)
+    pub depth: usize,
+}
+
+/// A link whose target could not be found
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UnresolvedLink {
+    pub source: String,
+    pub target: String,
+}
+
+/// Merged markdown plus the manifest of what went into it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NoteBundle {
+    pub markdown: String,
+    pub files: Vec<BundledNote>,
+    pub unresolved: Vec<UnresolvedLink>,
+}
+
+/// Bundle the notes and folders at `paths` (absolute or workspace-relative)
+pub fn export_bundle(
+    workspace: &Path,
+    paths: &[String],
+    options: &BundleOptions,
+) -> Result<NoteBundle, AppError> {
+    let workspace = workspace.canonicalize()?;
+    let notes = scan_workspace(&workspace)?;
+    let resolver = LinkResolver::new(&notes);
+    let included = collect_notes(&workspace, &notes, &resolver, paths, options.max_depth)?;
+
+    let mut used = HashSet::new();
+    let files: Vec<BundledNote> = included
+        .iter()
+        .map(|&(index, depth)| BundledNote {
+            path: notes[index].path.clone(),
+            title: notes[index].title.clone(),
+            anchor: unique_slug(&notes[index].title, &mut used),
+            depth,
+        })
+        .collect();
+    let anchors: HashMap<usize, &str> = included
+        .iter()
+        .zip(&files)
+        .map(|(&(index, _), file)| (index, file.anchor.as_str()))
+        .collect();
+
+    let mut markdown = String::new();
+    let section_level = match &options.title {
+        Some(title) => {
+            markdown.push_str(&format!("# {}\n\n", title.trim()));
+            2
+        }
+        None => 1,
+    };
+    let bundler = Bundler {
+        workspace: &workspace,
+        notes: &notes,
+        resolver: &resolver,
+        anchors: &anchors,
+        output_dir: normalize_rel_path(&options.output_dir),
+        section_level,
+    };
+    let mut unresolved = Vec::new();
+    for (&(index, _), file) in included.iter().zip(&files) {
+        let content = std::fs::read_to_string(workspace.join(&file.path))?;
+        markdown.push_str(&format!(
+            "<a id=\"{}\"></a>\n\n{} {}\n\n",
+            file.anchor,
+            "#".repeat(section_level),
+            file.title
+        ));
+        let body = bundler.render_note(index, &content, &mut unresolved);
+        markdown.push_str(body.trim());
+        markdown.push_str("\n\n");
+    }
+
+    Ok(NoteBundle {
+        markdown: markdown.trim_end().to_string() + "\n",
+        files,
+        unresolved,
+    })
+}
+
+/// Selected notes in order, then linked notes breadth-first, as
+/// (note index, depth)
+fn collect_notes(
+    workspace: &Path,
+    notes: &[ParsedNote],
+    resolver: &LinkResolver,
+    paths: &[String],
+    max_depth: usize,
+) -> Result<Vec<(usize, usize)>, AppError> {
+    let by_path: HashMap<String, usize> = notes
+        .iter()
+        .enumerate()
+        .map(|(index, note)| (note.path.to_lowercase(), index))
+        .collect();
+
+    let mut seen = HashSet::new();
+    let mut queue = VecDeque::new();
+    for path in paths {
+        let path = workspace.join(path).canonicalize()?;
+        let rel = relative_to(workspace, &path)?;
+        let selected: Vec<usize> = if path.is_dir() {
+            let prefix = if rel.is_empty() {
+                String::new()
+            } else {
+                format!("{}/", rel.to_lowercase())
+            };
+            (0..notes.len())
+                .filter(|index| notes[*index].path.to_lowercase().starts_with(&prefix))
+                .collect()
+        } else {
+            let index = by_path.get(&rel.to_lowercase()).ok_or_else(|| {
+                AppError::InvalidPath(format!("Not a markdown note: {}", path.display()))
+            })?;
+            vec![*index]
+        };
+        for index in selected {
+            if seen.insert(index) {
+                queue.push_back((index, 0));
+            }
+        }
+    }
+
+    let max_depth = max_depth.min(MAX_DEPTH);
+    let mut included = Vec::new();
+    while let Some((index, depth)) = queue.pop_front() {
+        included.push((index, depth));
+        if depth >= max_depth {
+            continue;
+        }
+        for link in &notes[index].links {
+            if let Some(target) = resolver.resolve(link, &notes[index].path) {
+                if seen.insert(target) {
+                    queue.push_back((target, depth + 1));
+                }
+            }
+        }
+    }
+    Ok(included)
+}
+
+struct Bundler<'a> {
+    workspace: &'a Path,
+    notes: &'a [ParsedNote],
+    resolver: &'a LinkResolver<'a>,
+    anchors: &'a HashMap<usize, &'a str>,
+    output_dir: String,
+    section_level: usize,
+}
+
+impl Bundler<'_> {
+    /// Note body with headings nested under its section and links rewritten
+    fn render_note(
+        &self,
+        index: usize,
+        content: &str,
+        unresolved: &mut Vec<UnresolvedLink>,
+    ) -> String {
+        let note = &self.notes[index];
+        let body = split_frontmatter(content)
+            .map(|(_, body)| body)
+            .unwrap_or(content);
+        let mut output = String::with_capacity(body.len());
+        let mut in_fence = false;
+        // The section heading already shows the title
+        let mut title_skipped = false;
+        for line in body.lines() {
+            let trimmed = line.trim_start();
+            if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+                in_fence = !in_fence;
+            }
+            if in_fence || trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+                output.push_str(line);
+                output.push('\n');
+                continue;
+            }
+            let level = line.chars().take_while(|c| *c == '#').count();
+            if (1..=6).contains(&level) && line[level..].starts_with(' ') {
+                if !title_skipped && level == 1 && line[level..].trim() == note.title {
+                    title_skipped = true;
+                    continue;
+                }
+                // Other top-level headings sit just below the section, like `##`
+                let level = (level.max(2) + self.section_level - 1).min(6);
+                output.push_str(&"#".repeat(level));
+                output.push_str(&self.rewrite_line(
+                    note,
+                    trimmed.trim_start_matches('#'),
+                    unresolved,
+                ));
+            } else {
+                output.push_str(&self.rewrite_line(note, line, unresolved));
+            }
+            output.push('\n');
+        }
+        output
+    }
+
+    fn rewrite_line(
+        &self,
+        note: &ParsedNote,
+        line: &str,
+        unresolved: &mut Vec<UnresolvedLink>,
+    ) -> String {
+        let mut replacements: Vec<(Range<usize>, String)> = Vec::new();
+        let mut missing = |target: &str| {
+            unresolved.push(UnresolvedLink {
+                source: note.path.clone(),
+                target: target.to_string(),
+            })
+        };
+
+        for caps in BUNDLE_WIKI_LINK_RE.captures_iter(line) {
+            let whole = caps.get(0).expect("whole match");
+            let embed = &caps[1] == "!";
+            let target = caps[2].trim();
+            let heading = caps.get(3).map(|heading| heading.as_str().trim());
+            let alias = caps.get(4).map(|alias| alias.as_str().trim());
+
+            if embed && !is_note_target(target) {
+                match self.image_path(note, target) {
+                    Some(path) => replacements.push((
+                        whole.range(),
+                        format!("![{}]({})", alias.unwrap_or_default(), path),
+                    )),
+                    None => missing(target),
+                }
+                continue;
+            }
+            let text = alias
+                .or(heading.filter(|_| target.is_empty()))
+                .unwrap_or(target)
+                .to_string();
+            let anchor = if target.is_empty() {
+                // `[[#heading]]` points into the same note
+                heading.map(slug)
+            } else {
+                let link = NoteLink {
+                    target: target.to_string(),
+                    wiki: true,
+                };
+                match self.resolver.resolve(&link, &note.path) {
+                    Some(index) => self.anchors.get(&index).map(|anchor| match heading {
+                        Some(heading) => slug(heading),
+                        None => anchor.to_string(),
+                    }),
+                    None => {
+                        missing(target);
+                        None
+                    }
+                }
+            };
+            let replacement = match anchor {
+                Some(anchor) => format!("[{}](#{})", text, anchor),
+                None => text,
+            };
+            replacements.push((whole.range(), replacement));
+        }
+
+        for caps in MARKDOWN_LINK_RE.captures_iter(line) {
+            let whole = caps.get(0).expect("whole match");
+            let raw = caps.get(2).expect("markdown link target group");
+            let target = raw
+                .as_str()
+                .trim_start_matches('<')
+                .trim_end_matches('>')
+                .trim();
+            if target.starts_with('#') || target.starts_with('/') || URL_SCHEME_RE.is_match(target)
+            {
+                continue;
+            }
+            let split = target.find(['#', '?']).unwrap_or(target.len());
+            let (path, suffix) = target.split_at(split);
+            let decoded = urlencoding::decode(path)
+                .map(|value| value.into_owned())
+                .unwrap_or_else(|_| path.to_string());
+
+            if &caps[1] == "!" {
+                if let Some(new_path) = self.image_path(note, &decoded) {
+                    replacements.push((raw.range(), new_path));
+                }
+                continue;
+            }
+            if !is_note_target(&decoded) {
+                continue;
+            }
+            let text_range =
+                whole.as_str().find('[').unwrap_or(0) + 1..whole.as_str().find(']').unwrap_or(0);
+            let text = whole.as_str()[text_range].to_string();
+            let link = NoteLink {
+                target: decoded.clone(),
+                wiki: false,
+            };
+            let replacement = match self.resolver.resolve(&link, &note.path) {
+                Some(index) => match self.anchors.get(&index) {
+                    Some(anchor) => match suffix.strip_prefix('#') {
+                        Some(heading) => format!("[{}](#{})", text, slug(heading)),
+                        None => format!("[{}](#{})", text, anchor),
+                    },
+                    None => text,
+                },
+                None => {
+                    missing(&decoded);
+                    text
+                }
+            };
+            replacements.push((whole.range(), replacement));
+        }
+
+        if replacements.is_empty() {
+            return line.to_string();
+        }
+        replacements.sort_by_key(|(range, _)| std::cmp::Reverse(range.start));
+        let mut line = line.to_string();
+        for (range, replacement) in replacements {
+            line.replace_range(range, &replacement);
+        }
+        line
+    }
+
+    /// Path of an image relative to the output folder, `None` if missing
+    fn image_path(&self, note: &ParsedNote, target: &str) -> Option<String> {
+        let target = target.replace('\\', "/");
+        let candidates = [
+            normalize_rel_path(&format!("{}/{}", parent_dir(&note.path), target)),
+            normalize_rel_path(&target),
+        ];
+        let found = candidates
+            .into_iter()
+            .find(|path| self.workspace.join(path).is_file())
+            .or_else(|| self.find_by_name(&target))?;
+        let path = relative_path(&self.output_dir, &found);
+        Some(if path.contains(' ') {
+            format!("<{}>", path)
+        } else {
+            path
+        })
+    }
+}
+
+impl Bundler<'_> {
+    /// Bare `![[name.png]]` embeds may live anywhere in the vault; the
+    /// shortest matching path wins
+    fn find_by_name(&self, name: &str) -> Option<String> {
+        if name.contains('/') {
+            return None;
+        }
+        WalkDir::new(self.workspace)
+            .into_iter()
+            .filter_entry(|entry| {
+                entry.depth() == 0 || !entry.file_name().to_string_lossy().starts_with('.')
+            })
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| {
+                entry.file_type().is_file() && entry.file_name().to_string_lossy() == name
+            })
+            .filter_map(|entry| relative_to(self.workspace, entry.path()).ok())
+            .min_by_key(|path| (path.len(), path.clone()))
+    }
+}
+
+/// Heading anchor in the style of common markdown renderers
+fn slug(text: &str) -> String {
+    text.trim()
+        .to_lowercase()
+        .chars()
+        .filter_map(|c| match c {
+            ' ' | '-' => Some('-'),
+            c if c.is_alphanumeric() || c == '_' => Some(c),
+            _ => None,
+        })
+        .collect()
+}
+
+fn unique_slug(title: &str, used: &mut HashSet<String>) -> String {
+    let base = match slug(title) {
+        base if base.is_empty() => "note".to_string(),
+        base => base,
+    };
+    let mut anchor = base.clone();
+    let mut counter = 1;
+    while !used.insert(anchor.clone()) {
+        anchor = format!("{}-{}", base, counter);
+        counter += 1;
+    }
+    anchor
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bundles_selected_notes_and_linked_notes_up_to_max_depth() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let root = dir.path();
+        for folder in ["Book", "Notes", "assets"] {
+            std::fs::create_dir_all(root.join(folder)).unwrap();
+        }
+        let write = |path: &str, content: &str| std::fs::write(root.join(path), content).unwrap();
+        write(
+            "Book/1 Intro.md",
+            "---\ntags: [x]\n---\n# Intro\nSee [[Ideas#Big One|ideas]], [[Far]] and [[Nowhere]].\n![chart](../assets/chart.png) ![[chart.png]]\n## Part\n```\n# not a heading [[Ideas]]\n```\n",
+        );
+        write("Book/2 Ideas.md", "Back to [intro](1%20Intro.md).");
+        write("Notes/Ideas.md", "# Ideas\n## Big One\n[[Far]]");
+        write("Notes/Far.md", "# Far away");
+        write("assets/chart.png", "png");
+
+        let options = BundleOptions {
+            max_depth: 1,
+            output_dir: "Exports".into(),
+            title: Some("My Book".into()),
+        };
+        let bundle = export_bundle(root, &["Book".to_string()], &options).unwrap();
+
+        let files: Vec<(&str, &str, usize)> = bundle
+            .files
+            .iter()
+            .map(|file| (file.path.as_str(), file.anchor.as_str(), file.depth))
+            .collect();
+        assert_eq!(
+            files,
+            vec![
+                ("Book/1 Intro.md", "intro", 0),
+                ("Book/2 Ideas.md", "2-ideas", 0),
+                ("Notes/Ideas.md", "ideas", 1),
+                ("Notes/Far.md", "far-away", 1),
+            ]
+        );
+        assert_eq!(
+            bundle.unresolved,
+            vec![UnresolvedLink {
+                source: "Book/1 Intro.md".into(),
+                target: "Nowhere".into(),
+            }]
+        );
+        let expected = [
+            "# My Book",
+            "",
+            "<a id=\"intro\"></a>",
+            "",
+            "## Intro",
+            "",
+            "See [ideas](#big-one), [Far](#far-away) and Nowhere.",
+            "![chart](../assets/chart.png) ![](../assets/chart.png)",
+            "### Part",
+            "```",
+            "# not a heading [[Ideas]]",
+            "```",
+            "",
+            "<a id=\"2-ideas\"></a>",
+            "",
+            "## 2 Ideas",
+            "",
+            "Back to [intro](#intro).",
+        ];
+        assert!(bundle.markdown.starts_with(&expected.join("\n")));
+        assert!(bundle
+            .markdown
+            .contains("## Ideas\n\n### Big One\n[Far](#far-away)\n"));
+
+        // Without link following only the selection is bundled
+        let bundle = export_bundle(
+            root,
+            &[root.join("Notes/Ideas.md").to_string_lossy().to_string()],
+            &BundleOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(bundle.files.len(), 1);
+        assert!(bundle.markdown.ends_with("\n## Big One\nFar\n"));
+    }
+}
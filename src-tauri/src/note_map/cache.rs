@@ -0,0 +1,214 @@
+//! Per-workspace parse cache for the note map
+//!
+//! Parsed notes are keyed by workspace-relative path and reused while the
+//! file's modification time and size are unchanged, so repeated scans only
+//! re-read changed files. The file watcher invalidates entries on change.
+//!
+//! The wikilink index built from those notes is cached per workspace as well
+//! and dropped whenever a path inside the workspace is invalidated.
+
+use super::parser::{parse_note, ParsedNote};
+use super::LinkTarget;
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::UNIX_EPOCH;
+
+struct CachedNote {
+    modified_nanos: u128,
+    len: u64,
+    note: ParsedNote,
+}
+
+type WorkspaceCache = HashMap<String, CachedNote>;
+
+static CACHE: Lazy<Mutex<HashMap<PathBuf, WorkspaceCache>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+static LINK_INDEXES: Lazy<Mutex<HashMap<PathBuf, Arc<Vec<LinkTarget>>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Bumped on every invalidation so an index built from a scan that raced
+/// with a change is not stored
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Parse the given markdown files, reusing cached results for unchanged files.
+///
+/// `files` are (workspace-relative path, absolute path) pairs. Entries for
+/// files no longer present are dropped from the cache.
+pub(super) fn parse_files_cached(
+    workspace: &Path,
+    files: Vec<(String, PathBuf)>,
+) -> Vec<ParsedNote> {
+    let mut cache = CACHE.lock().unwrap_or_else(|err| err.into_inner());
+    let mut previous = cache.remove(workspace).unwrap_or_default();
+    let mut next = WorkspaceCache::with_capacity(files.len());
+    let mut notes = Vec::with_capacity(files.len());
+
+    for (rel_path, path) in files {
+        let Ok(metadata) = std::fs::metadata(&path) else {
+            continue;
+        };
+        let modified = metadata.modified().ok();
+        let modified_nanos = modified
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map(|duration| duration.as_nanos())
+            .unwrap_or(0);
+        let len = metadata.len();
+
+        let entry = match previous.remove(&rel_path) {
+            Some(entry) if entry.modified_nanos == modified_nanos && entry.len == len => entry,
+            _ => {
+                let Ok(content) = std::fs::read_to_string(&path) else {
+                    continue;
+                };
+                let mut note = parse_note(&rel_path, &content);
+                note.modified = Some((modified_nanos / 1_000_000_000) as i64);
+                CachedNote {
+                    modified_nanos,
+                    len,
+                    note,
+                }
+            }
+        };
+        notes.push(entry.note.clone());
+        next.insert(rel_path, entry);
+    }
+
+    cache.insert(workspace.to_path_buf(), next);
+    notes
+}
+
+/// Drop cached entries for changed paths (absolute paths from the watcher)
+///
+/// A removed or renamed folder drops every note below it.
+pub fn invalidate_paths<'a>(paths: impl IntoIterator<Item = &'a Path>) {
+    GENERATION.fetch_add(1, Ordering::SeqCst);
+    let mut cache = CACHE.lock().unwrap_or_else(|err| err.into_inner());
+    let mut indexes = LINK_INDEXES.lock().unwrap_or_else(|err| err.into_inner());
+    for path in paths {
+        for (workspace, entries) in cache.iter_mut() {
+            if let Ok(rel) = path.strip_prefix(workspace) {
+                let rel = rel.to_string_lossy().replace('\\', "/");
+                let folder = format!("{}/", rel);
+                entries.retain(|key, _| *key != rel && !key.starts_with(&folder));
+            }
+        }
+        indexes.retain(|workspace, _| !path.starts_with(workspace));
+    }
+}
+
+/// Current invalidation generation, taken before scanning for a link index
+pub(super) fn generation() -> u64 {
+    GENERATION.load(Ordering::SeqCst)
+}
+
+/// Cached wikilink index of a workspace
+pub(super) fn link_index(workspace: &Path) -> Option<Arc<Vec<LinkTarget>>> {
+    let indexes = LINK_INDEXES.lock().unwrap_or_else(|err| err.into_inner());
+    indexes.get(workspace).cloned()
+}
+
+/// Cache a wikilink index unless something was invalidated since `generation`
+pub(super) fn store_link_index(workspace: &Path, generation: u64, index: Arc<Vec<LinkTarget>>) {
+    let mut indexes = LINK_INDEXES.lock().unwrap_or_else(|err| err.into_inner());
+    if GENERATION.load(Ordering::SeqCst) == generation {
+        indexes.insert(workspace.to_path_buf(), index);
+    }
+}
+
+/// Clear the cache for one workspace, or for all workspaces when `None`
+pub fn clear(workspace: Option<&Path>) {
+    let mut cache = CACHE.lock().unwrap_or_else(|err| err.into_inner());
+    let mut indexes = LINK_INDEXES.lock().unwrap_or_else(|err| err.into_inner());
+    match workspace {
+        Some(workspace) => {
+            cache.remove(workspace);
+            indexes.remove(workspace);
+        }
+        None => {
+            cache.clear();
+            indexes.clear();
+        }
+    }
+}
+
+/// Number of cached notes for a workspace
+pub fn cached_count(workspace: &Path) -> usize {
+    let cache = CACHE.lock().unwrap_or_else(|err| err.into_inner());
+    cache
+        .get(workspace)
+        .map(|entries| entries.len())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn files(root: &Path, names: &[&str]) -> Vec<(String, PathBuf)> {
+        names
+            .iter()
+            .map(|name| (name.to_string(), root.join(name)))
+            .collect()
+    }
+
+    #[test]
+    fn reuses_unchanged_entries_and_reparses_changed_files() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let root = dir.path();
+        std::fs::write(root.join("a.md"), "# A\n[[b]]").unwrap();
+        std::fs::write(root.join("b.md"), "# B").unwrap();
+
+        let notes = parse_files_cached(root, files(root, &["a.md", "b.md"]));
+        assert_eq!(notes.len(), 2);
+        assert_eq!(cached_count(root), 2);
+
+        // Different size forces a re-parse even if mtime granularity is coarse.
+        std::fs::write(root.join("b.md"), "# B renamed title").unwrap();
+        let notes = parse_files_cached(root, files(root, &["a.md", "b.md"]));
+        assert_eq!(notes[1].title, "B renamed title");
+
+        std::fs::remove_file(root.join("a.md")).unwrap();
+        let notes = parse_files_cached(root, files(root, &["b.md"]));
+        assert_eq!(notes.len(), 1);
+        assert_eq!(cached_count(root), 1);
+
+        invalidate_paths([root.join("b.md").as_path()]);
+        assert_eq!(cached_count(root), 0);
+
+        parse_files_cached(root, files(root, &["b.md"]));
+        clear(Some(root));
+        assert_eq!(cached_count(root), 0);
+    }
+
+    #[test]
+    fn link_index_is_cached_until_a_path_changes() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("Projects")).unwrap();
+        std::fs::write(root.join("Projects/plan.md"), "# Plan\n## Goals").unwrap();
+
+        let index = crate::note_map::link_index(root).unwrap();
+        assert_eq!(index[0].headings[1].text, "Goals");
+        assert!(Arc::ptr_eq(
+            &index,
+            &crate::note_map::link_index(root).unwrap()
+        ));
+
+        // Removing the folder reaches the watcher as the folder path only
+        std::fs::remove_dir_all(root.join("Projects")).unwrap();
+        invalidate_paths([root.join("Projects").as_path()]);
+        assert!(link_index(root).is_none());
+        assert_eq!(cached_count(root), 0);
+        assert!(crate::note_map::link_index(root).unwrap().is_empty());
+
+        // An index built across an invalidation is returned but not cached
+        let generation = generation();
+        invalidate_paths([root.join("new.md").as_path()]);
+        store_link_index(root, generation, Arc::new(Vec::new()));
+        assert!(link_index(root).is_none());
+    }
+}
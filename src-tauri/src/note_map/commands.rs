@@ -0,0 +1,109 @@
+//! Tauri commands for the note map
+
+use super::bundle::{export_bundle as bundle_notes, BundleOptions, NoteBundle};
+use super::report::{link_report, LinkReportChunk, LinkReportSummary, REPORT_CHUNK_SIZE};
+use super::rewrite::{move_and_fix_links, NoteMoveResult};
+use super::tags::{rename_tag_in_workspace, tag_index, TagEntry, TagRenameResult};
+use super::{
+    build_note_graph, cache, generate_note_map, link_index, LinkTarget, NoteGraph, NoteMapConfig,
+};
+use crate::error::AppError;
+use std::path::Path;
+use tauri::ipc::Channel;
+
+/// Export the workspace note graph (nodes with rank, directed link edges)
+#[tauri::command]
+pub async fn note_map_export_graph(workspace: String) -> Result<NoteGraph, AppError> {
+    build_note_graph(Path::new(&workspace))
+}
+
+/// Generate the ranked note map text, optionally centered on seed notes
+#[tauri::command]
+pub async fn note_map_generate(
+    workspace: String,
+    config: Option<NoteMapConfig>,
+) -> Result<String, AppError> {
+    generate_note_map(Path::new(&workspace), &config.unwrap_or_default())
+}
+
+/// Note paths, titles, aliases and headings for `[[` autocomplete
+///
+/// Served from a cache that the file watcher invalidates.
+#[tauri::command]
+pub async fn build_link_index(workspace: String) -> Result<Vec<LinkTarget>, AppError> {
+    Ok(link_index(Path::new(&workspace))?.as_ref().clone())
+}
+
+/// Clear the note map parse cache (one workspace, or all when omitted)
+#[tauri::command]
+pub async fn note_map_clear_cache(workspace: Option<String>) -> Result<(), AppError> {
+    cache::clear(workspace.as_deref().map(Path::new));
+    Ok(())
+}
+
+/// Move a note or folder into `target_folder` and rewrite links to and from it
+///
+/// Returns the new path and the notes whose links were rewritten; if any
+/// rewrite fails, the move and the rewrites made so far are undone.
+#[tauri::command]
+pub async fn move_note_with_backlink_fix(
+    workspace: String,
+    source: String,
+    target_folder: String,
+) -> Result<NoteMoveResult, AppError> {
+    move_and_fix_links(Path::new(&workspace), &source, &target_folder)
+}
+
+/// Report broken links, orphan notes and ambiguous note names (read-only)
+///
+/// Findings are streamed to `on_chunk` in batches; the totals are returned
+/// once the scan is done.
+#[tauri::command]
+pub async fn vault_link_report(
+    workspace: String,
+    on_chunk: Channel<LinkReportChunk>,
+) -> Result<LinkReportSummary, AppError> {
+    tokio::task::spawn_blocking(move || {
+        link_report(Path::new(&workspace), REPORT_CHUNK_SIZE, |chunk| {
+            let _ = on_chunk.send(chunk);
+        })
+    })
+    .await
+    .map_err(|err| AppError::Internal(format!("link report task failed: {}", err)))?
+}
+
+/// Tags across the workspace with the notes using them, most used first
+#[tauri::command]
+pub async fn build_tag_index(workspace: String) -> Result<Vec<TagEntry>, AppError> {
+    tag_index(Path::new(&workspace))
+}
+
+/// Rename a tag in frontmatter `tags:` and inline `#tags` across the vault
+///
+/// Returns the rewritten notes; nothing is written if any note fails.
+#[tauri::command]
+pub async fn rename_tag(
+    workspace: String,
+    old: String,
+    new: String,
+) -> Result<TagRenameResult, AppError> {
+    crate::fs::ensure_writable("rename tags")?;
+    rename_tag_in_workspace(Path::new(&workspace), &old, &new).await
+}
+
+/// Merge notes and folders into one markdown document (read-only)
+///
+/// Links between bundled notes become section anchors; the manifest lists the
+/// included notes and the links that could not be resolved.
+#[tauri::command]
+pub async fn export_bundle(
+    workspace: String,
+    paths: Vec<String>,
+    options: Option<BundleOptions>,
+) -> Result<NoteBundle, AppError> {
+    tokio::task::spawn_blocking(move || {
+        bundle_notes(Path::new(&workspace), &paths, &options.unwrap_or_default())
+    })
+    .await
+    .map_err(|err| AppError::Internal(format!("bundle export task failed: {}", err)))?
+}
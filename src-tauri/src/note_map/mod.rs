@@ -0,0 +1,546 @@
+//! Note Map Module
+//!
+//! Parses wikilinks and headings across the workspace and ranks notes by
+//! reference relationships.
+
+pub mod bundle;
+pub mod cache;
+pub mod commands;
+pub mod parser;
+pub mod ranking;
+pub mod report;
+pub mod rewrite;
+pub mod tags;
+
+use crate::error::AppError;
+use parser::{note_stem, Heading, NoteLink, ParsedNote};
+use ranking::{NoteFeatures, RankingWeights};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::Arc;
+use walkdir::WalkDir;
+
+pub use commands::*;
+
+const SKIPPED_DIRS: &[&str] = &["node_modules", "target", "dist", "build"];
+const MAX_HEADINGS_PER_NOTE: usize = 6;
+
+/// Note map generation options
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NoteMapConfig {
+    #[serde(flatten)]
+    pub weights: RankingWeights,
+    /// Notes (names or paths) the map should center on, e.g. the active or
+    /// mentioned notes
+    pub seed_notes: Vec<String>,
+    /// Share of PageRank teleport mass given to seed notes (0..=1)
+    pub seed_weight: f64,
+    /// Maximum number of notes listed in the map
+    pub max_notes: usize,
+}
+
+impl Default for NoteMapConfig {
+    fn default() -> Self {
+        Self {
+            weights: RankingWeights::default(),
+            seed_notes: Vec::new(),
+            seed_weight: 0.7,
+            max_notes: 30,
+        }
+    }
+}
+
+/// Graph node (one per note)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GraphNode {
+    /// Workspace-relative note path
+    pub id: String,
+    pub title: String,
+    pub word_count: usize,
+    pub rank: f64,
+}
+
+/// Directed link edge; unresolved links point at the raw link target
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GraphEdge {
+    pub source: String,
+    pub target: String,
+    pub resolved: bool,
+}
+
+/// Full note graph
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NoteGraph {
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>,
+}
+
+/// Parse every markdown note in the workspace, sorted by path
+///
+/// Unchanged files are served from the per-workspace [`cache`].
+pub fn scan_workspace(workspace: &Path) -> Result<Vec<ParsedNote>, AppError> {
+    if !workspace.is_dir() {
+        return Err(AppError::InvalidPath(format!(
+            "Workspace is not a directory: {}",
+            workspace.display()
+        )));
+    }
+
+    let mut notes = cache::parse_files_cached(workspace, markdown_files(workspace));
+    notes.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(notes)
+}
+
+/// List markdown files as (workspace-relative path, absolute path)
+fn markdown_files(workspace: &Path) -> Vec<(String, std::path::PathBuf)> {
+    WalkDir::new(workspace)
+        .into_iter()
+        .filter_entry(|entry| {
+            if entry.depth() == 0 || !entry.file_type().is_dir() {
+                return true;
+            }
+            let name = entry.file_name().to_string_lossy();
+            !name.starts_with('.') && !SKIPPED_DIRS.contains(&name.as_ref())
+        })
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            entry.file_type().is_file()
+                && entry.path().extension().and_then(|ext| ext.to_str()) == Some("md")
+        })
+        .map(|entry| {
+            let rel_path = entry
+                .path()
+                .strip_prefix(workspace)
+                .unwrap_or(entry.path())
+                .to_string_lossy()
+                .replace('\\', "/");
+            (rel_path, entry.into_path())
+        })
+        .collect()
+}
+
+/// A note as offered by `[[` autocomplete
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LinkTarget {
+    /// Workspace-relative path
+    pub path: String,
+    /// File name without `.md`, the shortest wikilink target
+    pub name: String,
+    pub title: String,
+    pub aliases: Vec<String>,
+    /// Headings in document order; `[[name#text]]` links to a section
+    pub headings: Vec<Heading>,
+}
+
+/// Wikilink completion targets of a workspace, sorted by path
+///
+/// The index is cached until the file watcher reports a change inside the
+/// workspace, so repeated lookups do not walk the vault.
+pub fn link_index(workspace: &Path) -> Result<Arc<Vec<LinkTarget>>, AppError> {
+    if let Some(index) = cache::link_index(workspace) {
+        return Ok(index);
+    }
+    let generation = cache::generation();
+    let index = Arc::new(link_targets(&scan_workspace(workspace)?));
+    cache::store_link_index(workspace, generation, index.clone());
+    Ok(index)
+}
+
+/// Completion targets for parsed notes
+pub fn link_targets(notes: &[ParsedNote]) -> Vec<LinkTarget> {
+    notes
+        .iter()
+        .map(|note| LinkTarget {
+            path: note.path.clone(),
+            name: note_stem(&note.path).to_string(),
+            title: note.title.clone(),
+            aliases: note.aliases.clone(),
+            headings: note.headings.clone(),
+        })
+        .collect()
+}
+
+/// Build the note graph for a workspace
+pub fn build_note_graph(workspace: &Path) -> Result<NoteGraph, AppError> {
+    let notes = scan_workspace(workspace)?;
+    Ok(graph_from_notes(&notes))
+}
+
+/// Build the note graph from parsed notes
+pub fn graph_from_notes(notes: &[ParsedNote]) -> NoteGraph {
+    let resolver = LinkResolver::new(notes);
+    let mut edges = Vec::new();
+    let mut resolved_edges = Vec::new();
+    let mut seen = HashSet::new();
+
+    for (source, note) in notes.iter().enumerate() {
+        for link in &note.links {
+            let (target, resolved) = match resolver.resolve(link, &note.path) {
+                Some(target) => {
+                    resolved_edges.push((source, target));
+                    (notes[target].path.clone(), true)
+                }
+                None => (link.target.clone(), false),
+            };
+            if seen.insert((source, target.clone(), resolved)) {
+                edges.push(GraphEdge {
+                    source: note.path.clone(),
+                    target,
+                    resolved,
+                });
+            }
+        }
+    }
+
+    let ranks = ranking::rank_notes(notes.len(), &resolved_edges);
+    let nodes = notes
+        .iter()
+        .zip(ranks)
+        .map(|(note, rank)| GraphNode {
+            id: note.path.clone(),
+            title: note.title.clone(),
+            word_count: note.word_count,
+            rank,
+        })
+        .collect();
+
+    NoteGraph { nodes, edges }
+}
+
+/// Generate a ranked, text note map of the workspace for agent context
+pub fn generate_note_map(workspace: &Path, config: &NoteMapConfig) -> Result<String, AppError> {
+    let notes = scan_workspace(workspace)?;
+    let workspace_prefix = workspace.to_string_lossy().replace('\\', "/");
+    let mut config = config.clone();
+    // Seeds may be absolute paths (e.g. the active note)
+    for seed in &mut config.seed_notes {
+        let normalized = seed.replace('\\', "/");
+        if let Some(rel) = normalized.strip_prefix(&workspace_prefix) {
+            *seed = rel.trim_start_matches('/').to_string();
+        }
+    }
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|duration| duration.as_secs() as i64)
+        .unwrap_or(0);
+    Ok(note_map_from_notes(&notes, &config, now))
+}
+
+/// Render the note map for parsed notes, most relevant first
+pub fn note_map_from_notes(notes: &[ParsedNote], config: &NoteMapConfig, now_secs: i64) -> String {
+    if notes.is_empty() {
+        return "Note map: no notes in workspace".to_string();
+    }
+
+    let resolver = LinkResolver::new(notes);
+    let mut edges = HashSet::new();
+    for (source, note) in notes.iter().enumerate() {
+        for link in &note.links {
+            if let Some(target) = resolver.resolve(link, &note.path) {
+                if target != source {
+                    edges.insert((source, target));
+                }
+            }
+        }
+    }
+    let edges: Vec<(usize, usize)> = edges.into_iter().collect();
+
+    let seeds: HashSet<usize> = config
+        .seed_notes
+        .iter()
+        .filter_map(|seed| {
+            let link = NoteLink {
+                target: seed.clone(),
+                wiki: true,
+            };
+            resolver.resolve(&link, "")
+        })
+        .collect();
+    let personalization = (!seeds.is_empty()).then(|| {
+        let seed_weight = config.seed_weight.clamp(0.0, 1.0);
+        let uniform = (1.0 - seed_weight) / notes.len() as f64;
+        let per_seed = seed_weight / seeds.len() as f64;
+        (0..notes.len())
+            .map(|index| {
+                uniform
+                    + if seeds.contains(&index) {
+                        per_seed
+                    } else {
+                        0.0
+                    }
+            })
+            .collect::<Vec<f64>>()
+    });
+
+    let ranks = ranking::rank_notes_personalized(notes.len(), &edges, personalization.as_deref());
+    let mut in_degree = vec![0usize; notes.len()];
+    let mut out_degree = vec![0usize; notes.len()];
+    for &(source, target) in &edges {
+        out_degree[source] += 1;
+        in_degree[target] += 1;
+    }
+    let features: Vec<NoteFeatures> = notes
+        .iter()
+        .enumerate()
+        .map(|(index, note)| NoteFeatures {
+            out_degree: out_degree[index],
+            word_count: note.word_count,
+            age_days: note
+                .modified
+                .map(|modified| (now_secs - modified).max(0) as f64 / 86_400.0),
+        })
+        .collect();
+    let scores = ranking::score_notes(&ranks, &features, &config.weights);
+
+    let mut order: Vec<usize> = (0..notes.len()).collect();
+    order.sort_by(|a, b| {
+        seeds
+            .contains(b)
+            .cmp(&seeds.contains(a))
+            .then(scores[*b].total_cmp(&scores[*a]))
+            .then(notes[*a].path.cmp(&notes[*b].path))
+    });
+
+    let shown = config.max_notes.max(1).min(notes.len());
+    let mut lines = vec![format!(
+        "Note map (top {} of {} notes, ranked by links):",
+        shown,
+        notes.len()
+    )];
+    for &index in order.iter().take(shown) {
+        let note = &notes[index];
+        let marker = if seeds.contains(&index) {
+            " [focus]"
+        } else {
+            ""
+        };
+        lines.push(format!(
+            "- {} \"{}\"{} ({} words, {} in / {} out)",
+            note.path, note.title, marker, note.word_count, in_degree[index], out_degree[index]
+        ));
+        let headings: Vec<&str> = note
+            .headings
+            .iter()
+            .filter(|heading| heading.level <= 2 && heading.text != note.title)
+            .take(MAX_HEADINGS_PER_NOTE)
+            .map(|heading| heading.text.as_str())
+            .collect();
+        if !headings.is_empty() {
+            lines.push(format!("  {}", headings.join(" · ")));
+        }
+    }
+    lines.join("\n")
+}
+
+/// Resolves link targets to note indices
+///
+/// Relative targets are tried against the source folder first, path-qualified
+/// targets match by workspace path suffix, and bare names fall back to name
+/// lookup (preferring the source folder). `.md` may be omitted.
+pub struct LinkResolver<'a> {
+    notes: &'a [ParsedNote],
+    by_path: HashMap<String, usize>,
+    /// Every trailing run of path segments (`b/c.md`, `c.md`) -> notes
+    by_suffix: HashMap<String, Vec<usize>>,
+    by_name: HashMap<String, Vec<usize>>,
+}
+
+impl<'a> LinkResolver<'a> {
+    pub fn new(notes: &'a [ParsedNote]) -> Self {
+        let mut by_path = HashMap::new();
+        let mut by_suffix: HashMap<String, Vec<usize>> = HashMap::new();
+        let mut by_name: HashMap<String, Vec<usize>> = HashMap::new();
+        for (index, note) in notes.iter().enumerate() {
+            let key = note.path.to_lowercase();
+            let mut suffix_start = Some(0);
+            while let Some(start) = suffix_start {
+                by_suffix
+                    .entry(key[start..].to_string())
+                    .or_default()
+                    .push(index);
+                suffix_start = key[start..].find('/').map(|slash| start + slash + 1);
+            }
+            by_path.insert(key, index);
+            by_name
+                .entry(note_stem(&note.path).to_lowercase())
+                .or_default()
+                .push(index);
+        }
+        Self {
+            notes,
+            by_path,
+            by_suffix,
+            by_name,
+        }
+    }
+
+    pub fn resolve(&self, link: &NoteLink, source_path: &str) -> Option<usize> {
+        let target = link.target.trim().replace('\\', "/");
+        let file_name = target.rsplit('/').next().unwrap_or(&target);
+        let target = match file_name.rfind('.') {
+            Some(dot) if dot > 0 => {
+                if !file_name[dot..].eq_ignore_ascii_case(".md") {
+                    return None;
+                }
+                target.clone()
+            }
+            _ => format!("{}.md", target),
+        };
+
+        let source_dir = parent_dir(source_path);
+        if !target.starts_with('/') {
+            let relative = normalize_rel_path(&format!("{}/{}", source_dir, target));
+            if let Some(index) = self.by_path.get(&relative.to_lowercase()) {
+                return Some(*index);
+            }
+        }
+
+        if target.contains('/') {
+            let wanted = normalize_rel_path(&target).to_lowercase();
+            return self
+                .by_suffix
+                .get(&wanted)?
+                .iter()
+                .copied()
+                .min_by_key(|index| (self.notes[*index].path.len(), *index));
+        }
+
+        let candidates = self.by_name.get(&note_stem(&target).to_lowercase())?;
+        candidates
+            .iter()
+            .copied()
+            .find(|index| parent_dir(&self.notes[*index].path).eq_ignore_ascii_case(source_dir))
+            .or_else(|| {
+                candidates
+                    .iter()
+                    .copied()
+                    .min_by_key(|index| (self.notes[*index].path.len(), *index))
+            })
+    }
+}
+
+fn parent_dir(path: &str) -> &str {
+    path.rfind('/').map(|index| &path[..index]).unwrap_or("")
+}
+
+/// Resolve `.`/`..` segments of a workspace-relative path
+fn normalize_rel_path(path: &str) -> String {
+    let mut segments: Vec<&str> = Vec::new();
+    for segment in path.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop();
+            }
+            other => segments.push(other),
+        }
+    }
+    segments.join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parser::parse_note;
+    use super::*;
+
+    fn note(path: &str, content: &str) -> ParsedNote {
+        parse_note(path, content)
+    }
+
+    #[test]
+    fn exports_resolved_and_dangling_edges() {
+        let notes = vec![
+            note(
+                "Alpha.md",
+                "# Alpha\nSee [[Projects/Beta]] and [[Missing]].",
+            ),
+            note(
+                "Projects/Beta.md",
+                "[[Alpha|a]] [back](../Alpha.md) [[Gamma]]",
+            ),
+            note("Projects/Gamma.md", "links to [[alpha]]"),
+        ];
+        let graph = graph_from_notes(&notes);
+
+        let edges: Vec<(&str, &str, bool)> = graph
+            .edges
+            .iter()
+            .map(|edge| (edge.source.as_str(), edge.target.as_str(), edge.resolved))
+            .collect();
+        assert_eq!(
+            edges,
+            vec![
+                ("Alpha.md", "Projects/Beta.md", true),
+                ("Alpha.md", "Missing", false),
+                ("Projects/Beta.md", "Alpha.md", true),
+                ("Projects/Beta.md", "Projects/Gamma.md", true),
+                ("Projects/Gamma.md", "Alpha.md", true),
+            ]
+        );
+
+        let alpha = &graph.nodes[0];
+        assert_eq!(alpha.title, "Alpha");
+        assert!(graph.nodes.iter().all(|node| node.rank <= alpha.rank));
+    }
+
+    #[test]
+    fn note_map_centers_on_seed_notes() {
+        let notes = vec![
+            note("Hub.md", "# Hub\n## Index\n"),
+            note("A.md", "[[Hub]]"),
+            note("B.md", "[[Hub]]"),
+            note("Projects/Alpha.md", "# Alpha\n## Goals\n[[Projects/Spec]]"),
+            note("Projects/Spec.md", "# Spec"),
+        ];
+
+        let plain = note_map_from_notes(&notes, &NoteMapConfig::default(), 0);
+        assert!(plain.starts_with("Note map (top 5 of 5 notes"));
+        assert!(plain
+            .lines()
+            .nth(1)
+            .unwrap()
+            .starts_with("- Hub.md \"Hub\""));
+
+        let config = NoteMapConfig {
+            seed_notes: vec!["Alpha".to_string()],
+            max_notes: 3,
+            ..NoteMapConfig::default()
+        };
+        let seeded = note_map_from_notes(&notes, &config, 0);
+        let lines: Vec<&str> = seeded.lines().collect();
+        assert_eq!(lines[0], "Note map (top 3 of 5 notes, ranked by links):");
+        assert_eq!(
+            lines[1],
+            "- Projects/Alpha.md \"Alpha\" [focus] (3 words, 0 in / 1 out)"
+        );
+        assert_eq!(lines[2], "  Goals");
+        assert!(lines[3].starts_with("- Projects/Spec.md"));
+    }
+
+    #[test]
+    fn resolver_prefers_source_folder_for_bare_names() {
+        let notes = vec![
+            note("Notes.md", ""),
+            note("Projects/Notes.md", ""),
+            note("Projects/Plan.md", ""),
+        ];
+        let resolver = LinkResolver::new(&notes);
+        let link = |target: &str| NoteLink {
+            target: target.to_string(),
+            wiki: true,
+        };
+
+        assert_eq!(
+            resolver.resolve(&link("Notes"), "Projects/Plan.md"),
+            Some(1)
+        );
+        assert_eq!(resolver.resolve(&link("Notes"), "Other/Day.md"), Some(0));
+        assert_eq!(
+            resolver.resolve(&link("projects/notes.md"), "Alpha.md"),
+            Some(1)
+        );
+        assert_eq!(resolver.resolve(&link("image.png"), "Alpha.md"), None);
+    }
+}
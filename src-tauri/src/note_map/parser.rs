@@ -0,0 +1,282 @@
+//! Markdown note parser for the note map
+//!
+//! Extracts the title, headings, outgoing links and word count of a note.
+
+use crate::forge_runtime::frontmatter::{
+    frontmatter_string_list, parse_frontmatter, split_frontmatter,
+};
+use crate::text::is_cjk;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+pub(crate) static WIKI_LINK_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\[\[([^\]|#^]*)[^\]]*\]\]").expect("valid wikilink regex"));
+pub(crate) static MARKDOWN_LINK_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(!?)\[[^\]]*\]\(\s*(<[^>]+>|[^)\s]+)(?:\s+"[^"]*")?\s*\)"#)
+        .expect("valid markdown link regex")
+});
+pub(crate) static URL_SCHEME_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^[a-zA-Z][a-zA-Z0-9+.-]*:").expect("valid scheme regex"));
+
+/// Markdown heading
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Heading {
+    pub level: u8,
+    pub text: String,
+}
+
+/// Outgoing link as written in the note
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NoteLink {
+    pub target: String,
+    /// true for `[[wikilinks]]`, false for `[text](path.md)` links
+    pub wiki: bool,
+}
+
+/// Parsed note
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParsedNote {
+    /// Workspace-relative path with `/` separators
+    pub path: String,
+    pub title: String,
+    /// Frontmatter `aliases` (or `alias`)
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    /// Lowercase frontmatter and inline tags
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub headings: Vec<Heading>,
+    pub links: Vec<NoteLink>,
+    pub word_count: usize,
+    /// Last modification time (unix seconds), filled in by the workspace scan
+    #[serde(default)]
+    pub modified: Option<i64>,
+}
+
+/// Parse a note's content
+pub fn parse_note(path: &str, content: &str) -> ParsedNote {
+    let body = split_frontmatter(content)
+        .map(|(_, body)| body)
+        .unwrap_or(content);
+
+    let mut headings = Vec::new();
+    let mut links: Vec<NoteLink> = Vec::new();
+    let mut in_fence = false;
+
+    for line in body.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+
+        if let Some(heading) = parse_heading(trimmed) {
+            headings.push(heading);
+        }
+        for link in line_links(line) {
+            push_link(&mut links, link);
+        }
+    }
+
+    let frontmatter = parse_frontmatter(content);
+    let aliases = frontmatter
+        .as_ref()
+        .and_then(|fm| fm.get("aliases").or_else(|| fm.get("alias")))
+        .map(frontmatter_string_list)
+        .unwrap_or_default();
+    let title = frontmatter
+        .as_ref()
+        .and_then(|fm| fm.get("title").and_then(|v| v.as_str()).map(str::to_string))
+        .filter(|title| !title.trim().is_empty())
+        .or_else(|| {
+            headings
+                .iter()
+                .find(|heading| heading.level == 1)
+                .map(|heading| heading.text.clone())
+        })
+        .unwrap_or_else(|| note_stem(path).to_string());
+
+    ParsedNote {
+        path: path.to_string(),
+        title,
+        aliases,
+        tags: super::tags::note_tags(content),
+        headings,
+        links,
+        word_count: count_words(body),
+        modified: None,
+    }
+}
+
+/// File name without directory and `.md` extension
+pub fn note_stem(path: &str) -> &str {
+    let name = path.rsplit(['/', '\\']).next().unwrap_or(path);
+    name.strip_suffix(".md").unwrap_or(name)
+}
+
+/// Every link in a note with its 1-based line number, duplicates included
+///
+/// Line numbers count from the top of the file, frontmatter included.
+pub fn link_occurrences(content: &str) -> Vec<(usize, NoteLink)> {
+    let body = split_frontmatter(content)
+        .map(|(_, body)| body)
+        .unwrap_or(content);
+    let first_line = content[..content.len() - body.len()].lines().count() + 1;
+
+    let mut occurrences = Vec::new();
+    let mut in_fence = false;
+    for (index, line) in body.lines().enumerate() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+        occurrences.extend(
+            line_links(line)
+                .into_iter()
+                .map(|link| (first_line + index, link)),
+        );
+    }
+    occurrences
+}
+
+/// Links on one line, wikilinks first; embeds, anchors and URLs are skipped
+fn line_links(line: &str) -> Vec<NoteLink> {
+    let mut links = Vec::new();
+    for caps in WIKI_LINK_RE.captures_iter(line) {
+        links.push(NoteLink {
+            target: caps[1].trim().to_string(),
+            wiki: true,
+        });
+    }
+    for caps in MARKDOWN_LINK_RE.captures_iter(line) {
+        if &caps[1] == "!" {
+            continue;
+        }
+        let target = caps[2].trim_start_matches('<').trim_end_matches('>').trim();
+        if target.starts_with('#') || URL_SCHEME_RE.is_match(target) {
+            continue;
+        }
+        let target = target.split(['#', '?']).next().unwrap_or_default();
+        let decoded = urlencoding::decode(target)
+            .map(|value| value.into_owned())
+            .unwrap_or_else(|_| target.to_string());
+        links.push(NoteLink {
+            target: decoded,
+            wiki: false,
+        });
+    }
+    links.retain(|link| !link.target.is_empty());
+    links
+}
+
+fn push_link(links: &mut Vec<NoteLink>, link: NoteLink) {
+    if !links.contains(&link) {
+        links.push(link);
+    }
+}
+
+fn parse_heading(line: &str) -> Option<Heading> {
+    let level = line.chars().take_while(|c| *c == '#').count();
+    if level == 0 || level > 6 {
+        return None;
+    }
+    let rest = &line[level..];
+    if !rest.starts_with(' ') {
+        return None;
+    }
+    let text = rest.trim().trim_end_matches('#').trim();
+    if text.is_empty() {
+        return None;
+    }
+    Some(Heading {
+        level: level as u8,
+        text: text.to_string(),
+    })
+}
+
+/// Count words; each CJK character counts as one word
+fn count_words(text: &str) -> usize {
+    text.split_whitespace()
+        .map(|token| {
+            let cjk = token.chars().filter(|c| is_cjk(*c)).count();
+            let has_other = token.chars().any(|c| c.is_alphanumeric() && !is_cjk(c));
+            cjk + usize::from(has_other)
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_title_headings_links_and_words() {
+        let content = "---\ntitle: Alpha Project\n---\n# Alpha\n\nSee [[Beta|b]] and [[Projects/Gamma#Plan]].\n[doc](../Docs/Spec%20One.md) [web](https://example.com) ![img](a.png)\n\n## Next steps ##\n```\n[[InCode]]\n```\n学习笔记 notes\n";
+        let note = parse_note("Projects/Alpha.md", content);
+
+        assert_eq!(note.title, "Alpha Project");
+        assert_eq!(
+            note.headings,
+            vec![
+                Heading {
+                    level: 1,
+                    text: "Alpha".into()
+                },
+                Heading {
+                    level: 2,
+                    text: "Next steps".into()
+                },
+            ]
+        );
+        let targets: Vec<(&str, bool)> = note
+            .links
+            .iter()
+            .map(|link| (link.target.as_str(), link.wiki))
+            .collect();
+        assert_eq!(
+            targets,
+            vec![
+                ("Beta", true),
+                ("Projects/Gamma", true),
+                ("../Docs/Spec One.md", false)
+            ]
+        );
+        assert_eq!(note.word_count, 16);
+    }
+
+    #[test]
+    fn reports_link_lines_below_frontmatter_and_outside_fences() {
+        let content = "---\ntitle: A\n---\n[[B]] and [[B]]\n```\n[[C]]\n```\n[d](D.md)\n";
+        let occurrences = link_occurrences(content);
+        let lines: Vec<(usize, &str)> = occurrences
+            .iter()
+            .map(|(line, link)| (*line, link.target.as_str()))
+            .collect();
+        assert_eq!(lines, vec![(4, "B"), (4, "B"), (8, "D.md")]);
+        assert_eq!(link_occurrences("[[X]]")[0].0, 1);
+    }
+
+    #[test]
+    fn reads_aliases_from_list_or_single_value() {
+        let note = parse_note("A.md", "---\naliases: [Alpha, \" α \"]\n---\n# A\n");
+        assert_eq!(note.aliases, vec!["Alpha", "α"]);
+        let note = parse_note("B.md", "---\nalias: Beta\n---\n");
+        assert_eq!(note.aliases, vec!["Beta"]);
+        assert!(parse_note("C.md", "# C").aliases.is_empty());
+    }
+
+    #[test]
+    fn falls_back_to_file_stem_for_title() {
+        let note = parse_note("Daily/2024-01-15.md", "no heading here");
+        assert_eq!(note.title, "2024-01-15");
+        assert_eq!(note.word_count, 3);
+    }
+}
@@ -0,0 +1,247 @@
+//! Vault link health report
+//!
+//! Finds links to missing notes, notes nothing links to, and file names
+//! shared by several notes (a bare `[[name]]` is then ambiguous). Results are
+//! handed out in chunks so the UI can render a large vault incrementally.
+
+use super::parser::{link_occurrences, note_stem, ParsedNote};
+use super::{scan_workspace, LinkResolver, NoteLink};
+use crate::error::AppError;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+
+/// Default number of items per chunk
+pub const REPORT_CHUNK_SIZE: usize = 200;
+
+/// A link whose target note does not exist
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BrokenLink {
+    /// Workspace-relative path of the note containing the link
+    pub source: String,
+    /// 1-based line number
+    pub line: usize,
+    pub target: String,
+    pub wiki: bool,
+}
+
+/// Notes sharing a file name, so `[[name]]` can mean any of them
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct NameCollision {
+    pub name: String,
+    /// Colliding notes, sorted by path
+    pub notes: Vec<String>,
+    /// Where a bare link from outside their folders resolves
+    pub resolves_to: String,
+}
+
+/// One batch of report items; every batch holds a single kind
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct LinkReportChunk {
+    pub broken_links: Vec<BrokenLink>,
+    /// Notes without inbound links from other notes
+    pub orphans: Vec<String>,
+    pub collisions: Vec<NameCollision>,
+}
+
+/// Totals, returned once every chunk has been delivered
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct LinkReportSummary {
+    pub notes_checked: usize,
+    pub broken_links: usize,
+    pub orphans: usize,
+    pub collisions: usize,
+}
+
+/// Build the link report, passing results to `on_chunk` in batches of at
+/// most `chunk_size` items
+pub fn link_report(
+    workspace: &Path,
+    chunk_size: usize,
+    mut on_chunk: impl FnMut(LinkReportChunk),
+) -> Result<LinkReportSummary, AppError> {
+    let notes = scan_workspace(workspace)?;
+    let chunk_size = chunk_size.max(1);
+    let resolver = LinkResolver::new(&notes);
+    let mut summary = LinkReportSummary {
+        notes_checked: notes.len(),
+        ..Default::default()
+    };
+
+    let mut linked = HashSet::new();
+    let mut broken = Vec::new();
+    for (index, note) in notes.iter().enumerate() {
+        let mut has_broken = false;
+        for link in &note.links {
+            match resolver.resolve(link, &note.path) {
+                Some(target) if target != index => {
+                    linked.insert(target);
+                }
+                Some(_) => {}
+                None => has_broken |= is_note_target(&link.target),
+            }
+        }
+        // Line numbers are not cached, so only notes with broken links are re-read
+        if has_broken {
+            broken.extend(broken_links_in(workspace, note, &resolver));
+        }
+        while broken.len() >= chunk_size {
+            let rest = broken.split_off(chunk_size);
+            summary.broken_links += broken.len();
+            on_chunk(LinkReportChunk {
+                broken_links: std::mem::replace(&mut broken, rest),
+                ..Default::default()
+            });
+        }
+    }
+    if !broken.is_empty() {
+        summary.broken_links += broken.len();
+        on_chunk(LinkReportChunk {
+            broken_links: broken,
+            ..Default::default()
+        });
+    }
+
+    let orphans: Vec<String> = notes
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| !linked.contains(index))
+        .map(|(_, note)| note.path.clone())
+        .collect();
+    summary.orphans = orphans.len();
+    for batch in orphans.chunks(chunk_size) {
+        on_chunk(LinkReportChunk {
+            orphans: batch.to_vec(),
+            ..Default::default()
+        });
+    }
+
+    let collisions = name_collisions(&notes, &resolver);
+    summary.collisions = collisions.len();
+    for batch in collisions.chunks(chunk_size) {
+        on_chunk(LinkReportChunk {
+            collisions: batch.to_vec(),
+            ..Default::default()
+        });
+    }
+
+    Ok(summary)
+}
+
+/// Links to other file types (images, PDFs) are attachments, not notes
+pub(super) fn is_note_target(target: &str) -> bool {
+    let name = target.rsplit(['/', '\\']).next().unwrap_or(target);
+    match name.rfind('.') {
+        Some(dot) if dot > 0 => name[dot..].eq_ignore_ascii_case(".md"),
+        _ => true,
+    }
+}
+
+fn broken_links_in(
+    workspace: &Path,
+    note: &ParsedNote,
+    resolver: &LinkResolver,
+) -> Vec<BrokenLink> {
+    let Ok(content) = std::fs::read_to_string(workspace.join(&note.path)) else {
+        return Vec::new();
+    };
+    link_occurrences(&content)
+        .into_iter()
+        .filter(|(_, link)| {
+            is_note_target(&link.target) && resolver.resolve(link, &note.path).is_none()
+        })
+        .map(|(line, link)| BrokenLink {
+            source: note.path.clone(),
+            line,
+            target: link.target,
+            wiki: link.wiki,
+        })
+        .collect()
+}
+
+fn name_collisions(notes: &[ParsedNote], resolver: &LinkResolver) -> Vec<NameCollision> {
+    let mut by_name: BTreeMap<String, Vec<&ParsedNote>> = BTreeMap::new();
+    for note in notes {
+        by_name
+            .entry(note_stem(&note.path).to_lowercase())
+            .or_default()
+            .push(note);
+    }
+    by_name
+        .into_values()
+        .filter(|group| group.len() > 1)
+        .map(|group| {
+            let name = note_stem(&group[0].path).to_string();
+            let link = NoteLink {
+                target: name.clone(),
+                wiki: true,
+            };
+            let resolves_to = resolver
+                .resolve(&link, "")
+                .map(|index| notes[index].path.clone())
+                .unwrap_or_default();
+            NameCollision {
+                name,
+                notes: group.iter().map(|note| note.path.clone()).collect(),
+                resolves_to,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_broken_links_orphans_and_collisions_in_chunks() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("Archive")).unwrap();
+        let write = |path: &str, content: &str| std::fs::write(root.join(path), content).unwrap();
+        write(
+            "Index.md",
+            "---\ntitle: Home\n---\n[[Plan]] [[Missing]]\n![[photo.png]]\n[gone](Gone.md)\n",
+        );
+        write("Plan.md", "[[Index]] [[Missing]]");
+        write("Archive/Plan.md", "# Old plan");
+        write("Lonely.md", "[[Lonely]]");
+
+        let mut chunks = Vec::new();
+        let summary = link_report(root, 2, |chunk| chunks.push(chunk)).unwrap();
+        assert_eq!(
+            summary,
+            LinkReportSummary {
+                notes_checked: 4,
+                broken_links: 3,
+                orphans: 2,
+                collisions: 1,
+            }
+        );
+
+        let broken: Vec<(&str, usize, &str)> = chunks
+            .iter()
+            .flat_map(|chunk| &chunk.broken_links)
+            .map(|link| (link.source.as_str(), link.line, link.target.as_str()))
+            .collect();
+        assert_eq!(
+            broken,
+            vec![
+                ("Index.md", 4, "Missing"),
+                ("Index.md", 6, "Gone.md"),
+                ("Plan.md", 1, "Missing"),
+            ]
+        );
+        assert!(chunks.iter().all(|chunk| chunk.broken_links.len() <= 2));
+        // Self links do not count as inbound
+        assert_eq!(chunks[2].orphans, vec!["Archive/Plan.md", "Lonely.md"]);
+        assert_eq!(
+            chunks[3].collisions,
+            vec![NameCollision {
+                name: "Plan".into(),
+                notes: vec!["Archive/Plan.md".into(), "Plan.md".into()],
+                resolves_to: "Plan.md".into(),
+            }]
+        );
+    }
+}
@@ -0,0 +1,428 @@
+//! Link rewriting for moved notes
+//!
+//! Moving a note (or a folder of notes) breaks `[[wikilinks]]` and relative
+//! `[text](path.md)` links that point at it, as well as relative links written
+//! inside the moved notes themselves. Links are resolved with the same
+//! [`LinkResolver`] the note map uses, once against the old paths and once
+//! against the new ones. Wikilinks are rewritten when their resolution would
+//! change; markdown links when their literal relative path no longer reaches
+//! the note. Anchors (`#heading`), aliases (`|alias`) and the `.md` extension
+//! style of the original link are kept.
+
+use super::parser::{ParsedNote, MARKDOWN_LINK_RE, URL_SCHEME_RE, WIKI_LINK_RE};
+use super::{normalize_rel_path, parent_dir, scan_workspace, LinkResolver, NoteLink};
+use crate::error::AppError;
+use crate::forge_runtime::frontmatter::split_frontmatter;
+use crate::fs;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// A workspace-relative move of a note or a folder
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NoteMove {
+    pub from: String,
+    pub to: String,
+    pub is_dir: bool,
+}
+
+impl NoteMove {
+    /// New path of `path` after the move (paths compare case-insensitively)
+    pub fn apply(&self, path: &str) -> Option<String> {
+        if path.to_lowercase() == self.from.to_lowercase() {
+            return Some(self.to.clone());
+        }
+        if !self.is_dir {
+            return None;
+        }
+        let prefix = format!("{}/", self.from).to_lowercase();
+        let head = path.get(..prefix.len())?;
+        (head.to_lowercase() == prefix).then(|| format!("{}/{}", self.to, &path[prefix.len()..]))
+    }
+}
+
+/// Result of moving a note with backlink fixing
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NoteMoveResult {
+    /// Absolute path of the moved file or folder
+    pub new_path: String,
+    /// Absolute paths of notes whose links were rewritten
+    pub rewritten: Vec<String>,
+}
+
+/// Rewrite the links of every note affected by `note_move`
+///
+/// `read` loads the content of a note by its old workspace-relative path.
+/// Returns `(new workspace-relative path, rewritten content)` per changed note.
+pub fn plan_link_rewrites(
+    notes: &[ParsedNote],
+    note_move: &NoteMove,
+    read: impl Fn(&str) -> Option<String>,
+) -> Vec<(String, String)> {
+    let moved: Vec<ParsedNote> = notes
+        .iter()
+        .map(|note| {
+            let mut note = note.clone();
+            if let Some(path) = note_move.apply(&note.path) {
+                note.path = path;
+            }
+            note
+        })
+        .collect();
+    let old_resolver = LinkResolver::new(notes);
+    let new_resolver = LinkResolver::new(&moved);
+
+    let mut rewrites = Vec::new();
+    for (index, note) in notes.iter().enumerate() {
+        let new_source = moved[index].path.as_str();
+        let source_moved = note_move.apply(&note.path).is_some();
+        // New target path when the link has to change, `None` when it still works
+        let retarget = |link: &NoteLink| -> Option<&str> {
+            let target = old_resolver.resolve(link, &note.path)?;
+            let new_target = moved[target].path.as_str();
+            let broken = if link.wiki {
+                new_resolver.resolve(link, new_source) != Some(target)
+            } else {
+                // Markdown links are followed literally, not by name lookup
+                (source_moved || note_move.apply(&notes[target].path).is_some())
+                    && !literal_path(new_source, &link.target).eq_ignore_ascii_case(new_target)
+            };
+            broken.then_some(new_target)
+        };
+        if !note.links.iter().any(|link| retarget(link).is_some()) {
+            continue;
+        }
+        let Some(content) = read(&note.path) else {
+            continue;
+        };
+        let rewritten = rewrite_content(&content, new_source, &retarget);
+        if rewritten != content {
+            rewrites.push((new_source.to_string(), rewritten));
+        }
+    }
+    rewrites
+}
+
+/// Rewrite the links of one note, skipping frontmatter and fenced code
+fn rewrite_content<'a>(
+    content: &str,
+    new_source: &str,
+    retarget: &impl Fn(&NoteLink) -> Option<&'a str>,
+) -> String {
+    let body_start = split_frontmatter(content)
+        .map(|(_, body)| content.len() - body.len())
+        .unwrap_or(0);
+    let mut output = String::with_capacity(content.len());
+    output.push_str(&content[..body_start]);
+
+    let mut in_fence = false;
+    for line in content[body_start..].split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+        }
+        if in_fence || trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            output.push_str(line);
+            continue;
+        }
+        output.push_str(&rewrite_line(line, new_source, retarget));
+    }
+    output
+}
+
+fn rewrite_line<'a>(
+    line: &str,
+    new_source: &str,
+    retarget: &impl Fn(&NoteLink) -> Option<&'a str>,
+) -> String {
+    let mut replacements: Vec<(std::ops::Range<usize>, String)> = Vec::new();
+
+    for caps in WIKI_LINK_RE.captures_iter(line) {
+        let raw = caps.get(1).expect("wikilink target group");
+        let link = NoteLink {
+            target: raw.as_str().trim().to_string(),
+            wiki: true,
+        };
+        if let Some(new_target) = retarget(&link) {
+            replacements.push((raw.range(), wiki_target(&link.target, new_target)));
+        }
+    }
+
+    for caps in MARKDOWN_LINK_RE.captures_iter(line) {
+        if &caps[1] == "!" {
+            continue;
+        }
+        let raw = caps.get(2).expect("markdown link target group");
+        let bracketed = raw.as_str().starts_with('<');
+        let target = raw
+            .as_str()
+            .trim_start_matches('<')
+            .trim_end_matches('>')
+            .trim();
+        if target.starts_with('#') || URL_SCHEME_RE.is_match(target) {
+            continue;
+        }
+        let split = target.find(['#', '?']).unwrap_or(target.len());
+        let (path, suffix) = target.split_at(split);
+        let decoded = urlencoding::decode(path)
+            .map(|value| value.into_owned())
+            .unwrap_or_else(|_| path.to_string());
+        let link = NoteLink {
+            target: decoded,
+            wiki: false,
+        };
+        if let Some(new_target) = retarget(&link) {
+            let relative = markdown_target(path, new_source, new_target);
+            let replacement = if bracketed || (relative.contains(' ') && !path.contains('%')) {
+                format!("<{}{}>", relative, suffix)
+            } else if path.contains('%') {
+                format!("{}{}", relative.replace(' ', "%20"), suffix)
+            } else {
+                format!("{}{}", relative, suffix)
+            };
+            replacements.push((raw.range(), replacement));
+        }
+    }
+
+    if replacements.is_empty() {
+        return line.to_string();
+    }
+    replacements.sort_by_key(|(range, _)| std::cmp::Reverse(range.start));
+    let mut line = line.to_string();
+    for (range, replacement) in replacements {
+        line.replace_range(range, &replacement);
+    }
+    line
+}
+
+/// Workspace path for a wikilink, keeping the original `.md` style
+fn wiki_target(original: &str, new_path: &str) -> String {
+    if has_md_extension(original) {
+        new_path.to_string()
+    } else {
+        strip_md(new_path).to_string()
+    }
+}
+
+/// Path relative to the linking note's folder, keeping the original style
+fn markdown_target(original: &str, new_source: &str, new_path: &str) -> String {
+    let path = if original.starts_with('/') {
+        format!("/{}", new_path)
+    } else {
+        relative_path(parent_dir(new_source), new_path)
+    };
+    if has_md_extension(original) {
+        path
+    } else {
+        strip_md(&path).to_string()
+    }
+}
+
+fn has_md_extension(target: &str) -> bool {
+    target.to_lowercase().ends_with(".md")
+}
+
+fn strip_md(path: &str) -> &str {
+    if has_md_extension(path) {
+        &path[..path.len() - 3]
+    } else {
+        path
+    }
+}
+
+/// Workspace path a markdown link points at when followed literally
+fn literal_path(source: &str, target: &str) -> String {
+    let target = target.replace('\\', "/");
+    let path = if target.starts_with('/') {
+        normalize_rel_path(&target)
+    } else {
+        normalize_rel_path(&format!("{}/{}", parent_dir(source), target))
+    };
+    let file_name = path.rsplit('/').next().unwrap_or(&path);
+    if file_name.contains('.') {
+        path
+    } else {
+        format!("{}.md", path)
+    }
+}
+
+/// Relative path from `from_dir` to `to` (both workspace-relative)
+pub(super) fn relative_path(from_dir: &str, to: &str) -> String {
+    let from_dir = normalize_rel_path(from_dir);
+    let from: Vec<&str> = from_dir.split('/').filter(|s| !s.is_empty()).collect();
+    let to: Vec<&str> = to.split('/').collect();
+    let common = from
+        .iter()
+        .zip(&to)
+        .take_while(|(a, b)| a.to_lowercase() == b.to_lowercase())
+        .count();
+    let mut segments = vec![".."; from.len() - common];
+    segments.extend(&to[common..]);
+    segments.join("/")
+}
+
+pub(super) fn relative_to(workspace: &Path, path: &Path) -> Result<String, AppError> {
+    path.strip_prefix(workspace)
+        .map(|rel| rel.to_string_lossy().replace('\\', "/"))
+        .map_err(|_| {
+            AppError::InvalidPath(format!("Path is outside the workspace: {}", path.display()))
+        })
+}
+
+/// Move a note or folder into `target_folder` and fix links to and from it
+///
+/// Each note is rewritten atomically. If a rewrite fails, the notes already
+/// rewritten get their original content back and the move is undone.
+pub fn move_and_fix_links(
+    workspace: &Path,
+    source: &str,
+    target_folder: &str,
+) -> Result<NoteMoveResult, AppError> {
+    // Canonical paths so case-insensitive filesystems compare consistently
+    let workspace = workspace.canonicalize()?;
+    let source_path = Path::new(source).canonicalize()?;
+    let target_path = Path::new(target_folder).canonicalize()?;
+    let is_dir = source_path.is_dir();
+    let from = relative_to(&workspace, &source_path)?;
+    let to_folder = relative_to(&workspace, &target_path)?;
+    let name = source_path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .ok_or_else(|| AppError::InvalidPath("Invalid source name".to_string()))?;
+    let note_move = NoteMove {
+        from,
+        to: normalize_rel_path(&format!("{}/{}", to_folder, name)),
+        is_dir,
+    };
+
+    let notes = scan_workspace(&workspace)?;
+    let rewrites = plan_link_rewrites(&notes, &note_move, |rel| {
+        std::fs::read_to_string(workspace.join(rel)).ok()
+    });
+
+    let new_path = move_entry(&source_path, &target_path, is_dir)?;
+
+    let mut written: Vec<(String, String)> = Vec::new();
+    for (rel, content) in rewrites {
+        let path = workspace.join(&rel).to_string_lossy().to_string();
+        let result = std::fs::read_to_string(&path)
+            .map_err(AppError::from)
+            .and_then(|original| fs::write_file_content(&path, &content).map(|_| original));
+        match result {
+            Ok(original) => written.push((path, original)),
+            Err(err) => {
+                for (path, original) in &written {
+                    let _ = fs::write_file_content(path, original);
+                }
+                if let Some(parent) = source_path.parent() {
+                    let _ = move_entry(Path::new(&new_path), parent, is_dir);
+                }
+                return Err(err);
+            }
+        }
+    }
+    Ok(NoteMoveResult {
+        new_path,
+        rewritten: written.into_iter().map(|(path, _)| path).collect(),
+    })
+}
+
+fn move_entry(source: &Path, target_folder: &Path, is_dir: bool) -> Result<String, AppError> {
+    let source = source.to_string_lossy();
+    let target_folder = target_folder.to_string_lossy();
+    if is_dir {
+        fs::move_folder_to_folder(&source, &target_folder)
+    } else {
+        fs::move_file_to_folder(&source, &target_folder)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::parser::parse_note;
+    use super::*;
+    use std::collections::HashMap;
+
+    fn plan(files: &[(&str, &str)], note_move: NoteMove) -> HashMap<String, String> {
+        let notes: Vec<ParsedNote> = files
+            .iter()
+            .map(|(path, content)| parse_note(path, content))
+            .collect();
+        let contents: HashMap<&str, &str> = files.iter().copied().collect();
+        plan_link_rewrites(&notes, &note_move, |path| {
+            contents.get(path).map(|content| content.to_string())
+        })
+        .into_iter()
+        .collect()
+    }
+
+    #[test]
+    fn rewrites_backlinks_to_a_moved_note() {
+        let rewrites = plan(
+            &[
+                ("Inbox/Plan.md", "# Plan\n[home](../Home.md)\n"),
+                ("Plan.md", "# Root plan"),
+                (
+                    "Inbox/Day.md",
+                    "See [[Plan#Goals|the plan]] and [[plan]].\n```\n[[Plan]]\n```\n",
+                ),
+                (
+                    "Home.md",
+                    "[[Inbox/Plan.md]] [p](Inbox/Plan.md#goals) [[Other]]",
+                ),
+                ("Other.md", "[[Plan]]"),
+            ],
+            NoteMove {
+                from: "inbox/plan.md".to_string(),
+                to: "Archive/2024/Plan.md".to_string(),
+                is_dir: false,
+            },
+        );
+
+        assert_eq!(
+            rewrites["Inbox/Day.md"],
+            "See [[Archive/2024/Plan#Goals|the plan]] and [[Archive/2024/Plan]].\n```\n[[Plan]]\n```\n"
+        );
+        assert_eq!(
+            rewrites["Home.md"],
+            "[[Archive/2024/Plan.md]] [p](Archive/2024/Plan.md#goals) [[Other]]"
+        );
+        // Relative links inside the moved note follow it
+        assert_eq!(
+            rewrites["Archive/2024/Plan.md"],
+            "# Plan\n[home](../../Home.md)\n"
+        );
+        // `[[Plan]]` from the root already resolved to the root note
+        assert!(!rewrites.contains_key("Other.md"));
+        assert_eq!(rewrites.len(), 3);
+    }
+
+    #[test]
+    fn rewrites_links_into_a_moved_folder() {
+        let rewrites = plan(
+            &[
+                ("Projects/Spec.md", "[[Notes/Meeting]] [up](../Index.md)"),
+                ("Projects/Notes/Meeting.md", "[spec](<../Spec.md>)"),
+                ("Index.md", "[s](Projects/My%20Spec.md) [[Projects/Spec]]"),
+                ("Projects/My Spec.md", ""),
+            ],
+            NoteMove {
+                from: "Projects".to_string(),
+                to: "Archive/Projects".to_string(),
+                is_dir: true,
+            },
+        );
+
+        // `[[Projects/Spec]]` still resolves by path suffix after the move
+        assert_eq!(
+            rewrites["Index.md"],
+            "[s](Archive/Projects/My%20Spec.md) [[Projects/Spec]]"
+        );
+        assert_eq!(
+            rewrites["Archive/Projects/Spec.md"],
+            "[[Notes/Meeting]] [up](../../Index.md)"
+        );
+        // Links between notes that moved together stay valid
+        assert!(!rewrites.contains_key("Archive/Projects/Notes/Meeting.md"));
+        assert_eq!(relative_path("a/b", "a/c/d.md"), "../c/d.md");
+    }
+}
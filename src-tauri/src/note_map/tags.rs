@@ -0,0 +1,365 @@
+//! Tag index and vault-wide tag renaming
+//!
+//! Tags come from frontmatter `tags:` and inline `#tag`s in the body. Inline
+//! tags follow the editor's rules (`extractTags` in `useNoteIndexStore.ts`):
+//! they start after whitespace or at the start of a line, so URL fragments and
+//! headings never match, and fenced or inline code is skipped. Tags compare
+//! case-insensitively and are indexed in lowercase.
+
+use super::scan_workspace;
+use crate::error::AppError;
+use crate::forge_runtime::frontmatter::{
+    frontmatter_string_list, parse_frontmatter, set_frontmatter_fields, split_frontmatter,
+};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+static INLINE_TAG_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?:^|\s)#([a-zA-Z\x{4e00}-\x{9fa5}][a-zA-Z0-9\x{4e00}-\x{9fa5}_-]*)")
+        .expect("valid inline tag regex")
+});
+static TAG_NAME_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^[a-zA-Z\x{4e00}-\x{9fa5}][a-zA-Z0-9\x{4e00}-\x{9fa5}_-]*$")
+        .expect("valid tag name regex")
+});
+
+/// A tag and the notes using it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TagEntry {
+    pub tag: String,
+    pub count: usize,
+    /// Workspace-relative paths, sorted
+    pub notes: Vec<String>,
+}
+
+/// Result of renaming a tag
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct TagRenameResult {
+    /// Workspace-relative paths of the rewritten notes
+    pub changed: Vec<String>,
+    /// Tag occurrences replaced across those notes
+    pub replaced: usize,
+}
+
+/// How a tag is compared and indexed: without `#`, in lowercase
+fn tag_key(tag: &str) -> String {
+    tag.trim().trim_start_matches('#').to_lowercase()
+}
+
+/// Lowercase tags of a note, frontmatter first, without duplicates
+pub fn note_tags(content: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    let mut push = |tag: &str| {
+        let tag = tag_key(tag);
+        if !tag.is_empty() && !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    };
+    if let Some(value) = parse_frontmatter(content).and_then(|fm| fm.get("tags").cloned()) {
+        for tag in frontmatter_string_list(&value) {
+            push(&tag);
+        }
+    }
+    for_each_body_line(content, |line| {
+        for (_, tag) in inline_tags(line) {
+            push(tag);
+        }
+    });
+    tags
+}
+
+/// Call `f` with every body line outside fenced code
+fn for_each_body_line<'a>(content: &'a str, mut f: impl FnMut(&'a str)) {
+    let body = split_frontmatter(content)
+        .map(|(_, body)| body)
+        .unwrap_or(content);
+    let mut in_fence = false;
+    for line in body.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if !in_fence {
+            f(line);
+        }
+    }
+}
+
+/// Inline tags of one line as (byte range of the name, name), outside
+/// `inline code`
+fn inline_tags(line: &str) -> Vec<(std::ops::Range<usize>, &str)> {
+    let code = inline_code_ranges(line);
+    INLINE_TAG_RE
+        .captures_iter(line)
+        .filter_map(|caps| caps.get(1))
+        .filter(|name| !code.iter().any(|range| range.contains(&name.start())))
+        .map(|name| (name.range(), name.as_str()))
+        .collect()
+}
+
+/// Byte ranges of backtick code spans, delimiters included
+fn inline_code_ranges(line: &str) -> Vec<std::ops::Range<usize>> {
+    let mut ranges = Vec::new();
+    let mut open: Option<usize> = None;
+    for (index, _) in line.match_indices('`') {
+        match open.take() {
+            Some(start) => ranges.push(start..index + 1),
+            None => open = Some(index),
+        }
+    }
+    ranges
+}
+
+/// Tags across the workspace with the notes using them, most used first
+pub fn tag_index(workspace: &Path) -> Result<Vec<TagEntry>, AppError> {
+    let mut by_tag: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for note in scan_workspace(workspace)? {
+        for tag in note.tags {
+            by_tag.entry(tag).or_default().push(note.path.clone());
+        }
+    }
+    let mut entries: Vec<TagEntry> = by_tag
+        .into_iter()
+        .map(|(tag, notes)| TagEntry {
+            tag,
+            count: notes.len(),
+            notes,
+        })
+        .collect();
+    entries.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.tag.cmp(&b.tag)));
+    Ok(entries)
+}
+
+/// Rewrite `old` to `new` in a note's frontmatter `tags:` and inline tags
+///
+/// Returns the new content and the number of replaced occurrences. A
+/// frontmatter list that already contains `new` (with or without `#`, in any
+/// case) keeps a single entry, the first one.
+pub fn rewrite_tag(content: &str, old: &str, new: &str) -> (String, usize) {
+    let old = old.to_lowercase();
+    let mut replaced = 0;
+    let mut content = content.to_string();
+
+    if let Some(tags) = parse_frontmatter(&content).and_then(|fm| fm.get("tags").cloned()) {
+        let rename = |tag: &str| -> Option<String> {
+            let trimmed = tag.trim();
+            let name = trimmed.trim_start_matches('#');
+            (name.to_lowercase() == old)
+                .then(|| format!("{}{}", &trimmed[..trimmed.len() - name.len()], new))
+        };
+        let renamed = match &tags {
+            Value::Array(items) => {
+                let mut list: Vec<Value> = Vec::with_capacity(items.len());
+                let mut seen: Vec<String> = Vec::new();
+                let mut hits = 0;
+                for item in items {
+                    let item = match item.as_str().and_then(rename) {
+                        Some(tag) => {
+                            hits += 1;
+                            Value::String(tag)
+                        }
+                        None => item.clone(),
+                    };
+                    let duplicate = match item.as_str().map(tag_key) {
+                        Some(key) if seen.contains(&key) => true,
+                        Some(key) => {
+                            seen.push(key);
+                            false
+                        }
+                        None => list.contains(&item),
+                    };
+                    if !duplicate {
+                        list.push(item);
+                    }
+                }
+                (hits > 0).then_some((Value::Array(list), hits))
+            }
+            Value::String(text) => {
+                let mut hits = 0;
+                let mut list: Vec<String> = Vec::new();
+                for tag in text.split(',').filter(|tag| !tag.trim().is_empty()) {
+                    let tag = match rename(tag) {
+                        Some(tag) => {
+                            hits += 1;
+                            tag
+                        }
+                        None => tag.trim().to_string(),
+                    };
+                    if !list.iter().any(|kept| tag_key(kept) == tag_key(&tag)) {
+                        list.push(tag);
+                    }
+                }
+                (hits > 0).then(|| (Value::String(list.join(", ")), hits))
+            }
+            _ => None,
+        };
+        if let Some((value, hits)) = renamed {
+            if let Some(updated) = set_frontmatter_fields(&content, &[("tags", value)]) {
+                content = updated;
+                replaced += hits;
+            }
+        }
+    }
+
+    let body_start = split_frontmatter(&content)
+        .map(|(_, body)| content.len() - body.len())
+        .unwrap_or(0);
+    let mut output = content[..body_start].to_string();
+    let mut in_fence = false;
+    for line in content[body_start..].split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+        }
+        if in_fence || trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            output.push_str(line);
+            continue;
+        }
+        let mut line = line.to_string();
+        let hits: Vec<_> = inline_tags(&line)
+            .into_iter()
+            .filter(|(_, tag)| tag.to_lowercase() == old)
+            .map(|(range, _)| range)
+            .collect();
+        // Replace from the end so earlier ranges stay valid
+        for range in hits.into_iter().rev() {
+            line.replace_range(range, new);
+            replaced += 1;
+        }
+        output.push_str(&line);
+    }
+    (output, replaced)
+}
+
+/// Rename a tag across the vault, reporting the rewritten notes
+///
+/// Every affected note is locked before any is written; if a write fails the
+/// notes already written are restored, so the vault is never left half renamed.
+pub async fn rename_tag_in_workspace(
+    workspace: &Path,
+    old: &str,
+    new: &str,
+) -> Result<TagRenameResult, AppError> {
+    let old = old.trim().trim_start_matches('#');
+    let new = new.trim().trim_start_matches('#');
+    for tag in [old, new] {
+        if !TAG_NAME_RE.is_match(tag) {
+            return Err(AppError::InvalidInput(format!(
+                "Invalid tag name: {:?}",
+                tag
+            )));
+        }
+    }
+    if old == new {
+        return Ok(TagRenameResult::default());
+    }
+
+    let old_key = old.to_lowercase();
+    let candidates: Vec<String> = scan_workspace(workspace)?
+        .into_iter()
+        .filter(|note| note.tags.contains(&old_key))
+        .map(|note| note.path)
+        .collect();
+    let paths: Vec<_> = candidates.iter().map(|rel| workspace.join(rel)).collect();
+    let _locks = crate::fs::lock_paths(&paths).await;
+
+    // Plan every rewrite before writing anything
+    let mut rewrites = Vec::new();
+    let mut result = TagRenameResult::default();
+    for (rel, path) in candidates.into_iter().zip(paths) {
+        let original = std::fs::read_to_string(&path)?;
+        let (updated, replaced) = rewrite_tag(&original, old, new);
+        if replaced > 0 && updated != original {
+            result.changed.push(rel);
+            result.replaced += replaced;
+            rewrites.push((path.to_string_lossy().to_string(), original, updated));
+        }
+    }
+
+    for (index, (path, _, updated)) in rewrites.iter().enumerate() {
+        if let Err(err) = crate::fs::write_file_content(path, updated) {
+            for (written, original, _) in &rewrites[..index] {
+                let _ = crate::fs::write_file_content(written, original);
+            }
+            return Err(err);
+        }
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn collects_frontmatter_and_inline_tags_outside_code() {
+        let content = "---\ntags: [Rust, \"#async\"]\n---\n# Heading\n#rust and #学习 see https://x.dev/#anchor\n`#code` ```\n```\n#fenced\n```\nissue#12 #todo-list\n";
+        assert_eq!(
+            note_tags(content),
+            vec!["rust", "async", "学习", "todo-list"]
+        );
+        assert_eq!(note_tags("---\ntags: a, b\n---\n#c"), vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn rewrites_frontmatter_and_inline_tags() {
+        let content = "---\ntitle: T\ntags: [old, \"#Old\", keep, new]\n---\n#old, #OLD! #older `#old`\n```\n#old\n```\nhttps://a.dev/#old\n";
+        let (rewritten, replaced) = rewrite_tag(content, "old", "new");
+        assert_eq!(replaced, 4);
+        assert_eq!(
+            rewritten,
+            "---\ntitle: T\ntags: [\"new\",\"keep\"]\n---\n#new, #new! #older `#old`\n```\n#old\n```\nhttps://a.dev/#old\n"
+        );
+
+        let (rewritten, _) = rewrite_tag("---\ntags: [\"#New\", old]\n---\n", "old", "new");
+        assert_eq!(rewritten, "---\ntags: [\"#New\"]\n---\n");
+
+        let (rewritten, replaced) =
+            rewrite_tag("---\ntags: old, b, #new\n---\nbody\n", "old", "new");
+        assert_eq!(replaced, 1);
+        assert_eq!(rewritten, "---\ntags: \"new, b\"\n---\nbody\n");
+    }
+
+    #[tokio::test]
+    async fn renames_a_tag_across_the_vault() {
+        // Writes go through the allowed-root check; the working directory is one
+        let dir = tempfile::tempdir_in(std::env::current_dir().unwrap()).expect("tempdir");
+        let root = dir.path();
+        std::fs::write(root.join("a.md"), "---\ntags: [draft]\n---\n#draft #idea\n").unwrap();
+        std::fs::write(root.join("b.md"), "#Draft").unwrap();
+        std::fs::write(root.join("c.md"), "#idea").unwrap();
+
+        let index = tag_index(root).unwrap();
+        assert_eq!(
+            index[0],
+            TagEntry {
+                tag: "draft".into(),
+                count: 2,
+                notes: vec!["a.md".into(), "b.md".into()],
+            }
+        );
+
+        let result = rename_tag_in_workspace(root, "#draft", "wip")
+            .await
+            .unwrap();
+        assert_eq!(result.changed, vec!["a.md", "b.md"]);
+        assert_eq!(result.replaced, 3);
+        assert_eq!(std::fs::read_to_string(root.join("b.md")).unwrap(), "#wip");
+        let tags: Vec<String> = tag_index(root)
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.tag)
+            .collect();
+        assert_eq!(tags, vec!["idea", "wip"]);
+
+        assert!(matches!(
+            rename_tag_in_workspace(root, "wip", "two words").await,
+            Err(AppError::InvalidInput(_))
+        ));
+    }
+}
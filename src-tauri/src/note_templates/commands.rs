@@ -0,0 +1,33 @@
+//! Tauri commands for note templates
+
+use super::{create_from_template, list_templates, resolve_target, CreatedNote, TemplateInfo};
+use crate::error::AppError;
+use serde_json::{Map, Value};
+use std::path::Path;
+
+/// List the workspace templates and the built-in ones they do not override
+#[tauri::command]
+pub async fn list_note_templates(workspace_path: String) -> Result<Vec<TemplateInfo>, AppError> {
+    list_templates(Path::new(&workspace_path))
+}
+
+/// Render `template` into a new note at `target_path` (workspace-relative)
+///
+/// Fails if the note already exists or a required variable is missing.
+#[tauri::command]
+pub async fn create_note_from_template(
+    workspace_path: String,
+    template: String,
+    target_path: String,
+    vars: Option<Map<String, Value>>,
+) -> Result<CreatedNote, AppError> {
+    let workspace = Path::new(&workspace_path);
+    let target = resolve_target(workspace, &target_path)?;
+    create_from_template(
+        workspace,
+        &template,
+        &target,
+        &vars.unwrap_or_default(),
+        chrono::Local::now().fixed_offset(),
+    )
+}
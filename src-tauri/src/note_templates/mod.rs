@@ -0,0 +1,497 @@
+//! Note templates with variable substitution
+//!
+//! Templates are markdown files in `.lumina/templates/<name>.md`; a few
+//! built-in ones (flashcards) are used when the workspace has no template of
+//! that name. Placeholders:
+//!
+//! - `{{name}}`: a required variable
+//! - `{{name|default}}`: an optional variable; the default is parsed as JSON
+//!   when possible (`[]`, `false`, `2.5`) and used as text otherwise
+//! - `{{date}}`, `{{time}}`, `{{datetime}}`, and `{{date:FORMAT}}` /
+//!   `{{time:FORMAT}}` with moment-style tokens (`YYYY-MM-DD`, `HH:mm`)
+//! - `{{title}}`: the `title` variable, or the new note's file name
+//! - `{{cursor}}`: removed from the output; its position is reported so the
+//!   editor can place the caret there
+//!
+//! Inside the frontmatter block values are written as YAML (strings quoted),
+//! so a template says `front: {{front}}`, not `front: "{{front}}"`. In the body
+//! strings are inserted as-is. Anything else in braces (e.g. cloze syntax
+//! `{{c1::answer}}`) is left untouched.
+
+pub mod commands;
+
+pub use commands::*;
+
+use crate::error::AppError;
+use crate::forge_runtime::frontmatter::split_frontmatter;
+use chrono::{DateTime, FixedOffset};
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+/// Workspace-relative directory holding user templates
+pub const TEMPLATE_DIR: &str = ".lumina/templates";
+
+const BUILTIN_TEMPLATES: &[(&str, &str)] = &[
+    (
+        "flashcard-basic",
+        include_str!("../../resources/templates/flashcard-basic.md"),
+    ),
+    (
+        "flashcard-cloze",
+        include_str!("../../resources/templates/flashcard-cloze.md"),
+    ),
+    (
+        "flashcard-mcq",
+        include_str!("../../resources/templates/flashcard-mcq.md"),
+    ),
+    (
+        "flashcard-list",
+        include_str!("../../resources/templates/flashcard-list.md"),
+    ),
+];
+
+static PLACEHOLDER_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"\{\{\s*([A-Za-z_][A-Za-z0-9_-]*)\s*(?::([^|}]*))?(?:\|([^}]*))?\}\}")
+        .expect("valid placeholder regex")
+});
+
+/// Template as listed to the UI and the agent
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TemplateInfo {
+    pub name: String,
+    /// `workspace` or `builtin`
+    pub source: String,
+    /// Variables that must be supplied
+    pub required: Vec<String>,
+    /// Variables with a default
+    pub optional: Vec<String>,
+}
+
+/// Rendered note content
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RenderedNote {
+    pub content: String,
+    /// 1-based (line, column) of `{{cursor}}`, if the template has one
+    pub cursor: Option<(usize, usize)>,
+}
+
+/// Note created from a template
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CreatedNote {
+    pub path: String,
+    pub cursor: Option<(usize, usize)>,
+}
+
+/// Load a template by name: the workspace copy first, then the built-in one
+pub fn load_template(workspace: &Path, name: &str) -> Result<String, AppError> {
+    let name = name.trim().trim_end_matches(".md");
+    if name.is_empty() || name.contains(['/', '\\']) || name.starts_with('.') {
+        return Err(AppError::InvalidInput(format!(
+            "Invalid template name: {}",
+            name
+        )));
+    }
+    let path = workspace.join(TEMPLATE_DIR).join(format!("{}.md", name));
+    if path.is_file() {
+        return Ok(std::fs::read_to_string(&path)?);
+    }
+    BUILTIN_TEMPLATES
+        .iter()
+        .find(|(builtin, _)| *builtin == name)
+        .map(|(_, content)| content.to_string())
+        .ok_or_else(|| AppError::FileNotFound(format!("Template not found: {}", name)))
+}
+
+/// Workspace templates followed by built-ins they do not override
+pub fn list_templates(workspace: &Path) -> Result<Vec<TemplateInfo>, AppError> {
+    let mut templates = Vec::new();
+    if let Ok(entries) = std::fs::read_dir(workspace.join(TEMPLATE_DIR)) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("md") {
+                continue;
+            }
+            let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                continue;
+            };
+            let content = std::fs::read_to_string(&path)?;
+            templates.push(template_info(name, "workspace", &content));
+        }
+    }
+    templates.sort_by(|a, b| a.name.cmp(&b.name));
+    for (name, content) in BUILTIN_TEMPLATES {
+        if !templates.iter().any(|template| template.name == *name) {
+            templates.push(template_info(name, "builtin", content));
+        }
+    }
+    Ok(templates)
+}
+
+fn template_info(name: &str, source: &str, content: &str) -> TemplateInfo {
+    let mut required = BTreeSet::new();
+    let mut optional = BTreeSet::new();
+    for caps in PLACEHOLDER_RE.captures_iter(content) {
+        let name = &caps[1];
+        if caps.get(2).is_some() || is_builtin(name) {
+            continue;
+        }
+        if caps.get(3).is_some() {
+            optional.insert(name.to_string());
+        } else {
+            required.insert(name.to_string());
+        }
+    }
+    // A variable required anywhere is required
+    optional.retain(|name| !required.contains(name));
+    TemplateInfo {
+        name: name.to_string(),
+        source: source.to_string(),
+        required: required.into_iter().collect(),
+        optional: optional.into_iter().collect(),
+    }
+}
+
+fn is_builtin(name: &str) -> bool {
+    matches!(name, "date" | "time" | "datetime" | "cursor" | "title")
+}
+
+/// Render `template` for a note titled `title`
+///
+/// Fails listing every required variable that `vars` does not supply.
+pub fn render_template(
+    template: &str,
+    vars: &Map<String, Value>,
+    title: &str,
+    now: DateTime<FixedOffset>,
+) -> Result<RenderedNote, AppError> {
+    let frontmatter_end = frontmatter_end(template);
+    let mut missing = BTreeSet::new();
+    let rendered = PLACEHOLDER_RE.replace_all(template, |caps: &Captures| {
+        let whole = caps.get(0).expect("whole match");
+        let in_frontmatter = whole.start() < frontmatter_end;
+        let name = &caps[1];
+        let argument = caps.get(2).map(|arg| arg.as_str().trim());
+        let value = match (name, argument) {
+            ("cursor", None) => return CURSOR_MARK.to_string(),
+            ("date", format) => Value::String(format_date(now, format.unwrap_or("YYYY-MM-DD"))),
+            ("time", format) => Value::String(format_date(now, format.unwrap_or("HH:mm"))),
+            ("datetime", None) => Value::String(now.to_rfc3339()),
+            // Not a placeholder, e.g. cloze syntax
+            (_, Some(_)) => return whole.as_str().to_string(),
+            (name, None) => match vars.get(name) {
+                Some(value) => value.clone(),
+                None if name == "title" => Value::String(title.to_string()),
+                None => match caps.get(3) {
+                    Some(default) => parse_default(default.as_str()),
+                    None => {
+                        missing.insert(name.to_string());
+                        return String::new();
+                    }
+                },
+            },
+        };
+        format_value(&value, in_frontmatter)
+    });
+    if !missing.is_empty() {
+        return Err(AppError::InvalidInput(format!(
+            "Missing template variables: {}",
+            missing.into_iter().collect::<Vec<_>>().join(", ")
+        )));
+    }
+
+    let mut content = rendered.into_owned();
+    let cursor = content.find(CURSOR_MARK).map(|offset| {
+        let before = &content[..offset];
+        let line = before.matches('\n').count() + 1;
+        let column = before.rsplit('\n').next().unwrap_or("").chars().count() + 1;
+        (line, column)
+    });
+    content = content.replace(CURSOR_MARK, "");
+    Ok(RenderedNote { content, cursor })
+}
+
+/// Stand-in for `{{cursor}}` until its position is measured
+const CURSOR_MARK: &str = "\u{0}cursor\u{0}";
+
+/// Byte offset where the frontmatter block ends (0 without frontmatter)
+fn frontmatter_end(template: &str) -> usize {
+    split_frontmatter(template)
+        .map(|(yaml, _)| yaml.as_ptr() as usize - template.as_ptr() as usize + yaml.len())
+        .unwrap_or(0)
+}
+
+fn parse_default(text: &str) -> Value {
+    let text = text.trim();
+    serde_json::from_str(text).unwrap_or_else(|_| Value::String(text.to_string()))
+}
+
+fn format_value(value: &Value, in_frontmatter: bool) -> String {
+    match value {
+        // JSON strings and arrays are valid YAML flow scalars / sequences
+        Value::String(text) if !in_frontmatter => text.clone(),
+        Value::Array(items) if !in_frontmatter => items
+            .iter()
+            .map(|item| match item {
+                Value::String(text) => text.clone(),
+                other => other.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join(", "),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+/// Format a date with moment-style tokens; text in `[brackets]` is literal
+fn format_date(now: DateTime<FixedOffset>, format: &str) -> String {
+    const TOKENS: &[(&str, &str)] = &[
+        ("YYYY", "%Y"),
+        ("YY", "%y"),
+        ("MMMM", "%B"),
+        ("MMM", "%b"),
+        ("MM", "%m"),
+        ("M", "%-m"),
+        ("DD", "%d"),
+        ("D", "%-d"),
+        ("dddd", "%A"),
+        ("ddd", "%a"),
+        ("HH", "%H"),
+        ("hh", "%I"),
+        ("mm", "%M"),
+        ("ss", "%S"),
+        ("A", "%p"),
+    ];
+    let mut pattern = String::new();
+    let mut rest = format;
+    'outer: while !rest.is_empty() {
+        if let Some(literal) = rest.strip_prefix('[') {
+            let end = literal.find(']').unwrap_or(literal.len());
+            pattern.push_str(&literal[..end].replace('%', "%%"));
+            rest = literal.get(end + 1..).unwrap_or("");
+            continue;
+        }
+        for (token, specifier) in TOKENS {
+            if let Some(after) = rest.strip_prefix(token) {
+                pattern.push_str(specifier);
+                rest = after;
+                continue 'outer;
+            }
+        }
+        let ch = rest.chars().next().expect("non-empty");
+        if ch == '%' {
+            pattern.push_str("%%");
+        } else {
+            pattern.push(ch);
+        }
+        rest = &rest[ch.len_utf8()..];
+    }
+    now.format(&pattern).to_string()
+}
+
+/// Resolve the new note's path inside the workspace, adding `.md` when the
+/// path has no extension
+pub fn resolve_target(workspace: &Path, target: &str) -> Result<PathBuf, AppError> {
+    let target = target.trim();
+    let relative = Path::new(target);
+    if target.is_empty()
+        || relative.is_absolute()
+        || relative
+            .components()
+            .any(|part| !matches!(part, std::path::Component::Normal(_)))
+    {
+        return Err(AppError::InvalidPath(format!(
+            "Target must be a path inside the workspace: {}",
+            target
+        )));
+    }
+    let mut path = workspace.join(relative);
+    if path.extension().is_none() {
+        path.set_extension("md");
+    }
+    Ok(path)
+}
+
+/// Render a template into a new note; never overwrites an existing file
+///
+/// Like every other write, this respects read-only mode and the allowed roots,
+/// and refuses a target that a symlinked folder redirects out of the workspace.
+pub fn create_from_template(
+    workspace: &Path,
+    template: &str,
+    target: &Path,
+    vars: &Map<String, Value>,
+    now: DateTime<FixedOffset>,
+) -> Result<CreatedNote, AppError> {
+    crate::fs::ensure_writable("create notes")?;
+    crate::fs::ensure_allowed_path(target, false)?;
+    let workspace_root = std::fs::canonicalize(workspace)?;
+    if !crate::fs::resolve_for_write(target)?.starts_with(&workspace_root) {
+        return Err(AppError::InvalidPath(format!(
+            "Target resolves outside the workspace: {}",
+            target.display()
+        )));
+    }
+    if target.exists() {
+        return Err(AppError::FileExists(target.display().to_string()));
+    }
+    let source = load_template(workspace, template)?;
+    let title = target
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let rendered = render_template(&source, vars, &title, now)?;
+    if let Some(parent) = target.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut file = std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(target)
+        .map_err(|err| match err.kind() {
+            std::io::ErrorKind::AlreadyExists => AppError::FileExists(target.display().to_string()),
+            _ => AppError::Io(err),
+        })?;
+    std::io::Write::write_all(&mut file, rendered.content.as_bytes())?;
+    Ok(CreatedNote {
+        path: target.display().to_string(),
+        cursor: rendered.cursor,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn now() -> DateTime<FixedOffset> {
+        DateTime::parse_from_rfc3339("2026-03-05T09:07:00+08:00").unwrap()
+    }
+
+    fn vars(value: Value) -> Map<String, Value> {
+        value.as_object().cloned().unwrap()
+    }
+
+    #[test]
+    fn renders_variables_dates_and_cursor() {
+        let template = "---\ntitle: {{title}}\ntags: {{tags|[]}}\ncreated: {{date}}\n---\n\n# {{title}}\n\n{{date:dddd, MMMM D[th] YYYY}} at {{time}}\n\n{{ summary }}\n{{cursor}}\nSee {{c1::answer}}";
+        let rendered = render_template(
+            template,
+            &vars(json!({ "summary": "Line: \"quoted\"" })),
+            "Weekly Review",
+            now(),
+        )
+        .unwrap();
+        assert_eq!(
+            rendered.content,
+            "---\ntitle: \"Weekly Review\"\ntags: []\ncreated: \"2026-03-05\"\n---\n\n# Weekly Review\n\nThursday, March 5th 2026 at 09:07\n\nLine: \"quoted\"\n\nSee {{c1::answer}}"
+        );
+        assert_eq!(rendered.cursor, Some((12, 1)));
+    }
+
+    #[test]
+    fn reports_every_missing_required_variable() {
+        let err = render_template(
+            "{{front}} / {{back}} / {{deck|Default}}",
+            &Map::new(),
+            "card",
+            now(),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            AppError::InvalidInput("Missing template variables: back, front".into()).to_string()
+        );
+    }
+
+    #[test]
+    fn builtin_flashcard_templates_render_valid_frontmatter() {
+        let info = list_templates(Path::new("/nonexistent")).unwrap();
+        let mcq = info
+            .iter()
+            .find(|template| template.name == "flashcard-mcq")
+            .unwrap();
+        assert_eq!(mcq.source, "builtin");
+        assert_eq!(mcq.required, ["answer", "options", "question"]);
+
+        let rendered = render_template(
+            &load_template(Path::new("/nonexistent"), "flashcard-mcq").unwrap(),
+            &vars(json!({
+                "question": "Capital of France?",
+                "options": ["Paris", "Lyon"],
+                "answer": 0
+            })),
+            "capital",
+            now(),
+        )
+        .unwrap();
+        let frontmatter =
+            crate::forge_runtime::frontmatter::parse_frontmatter(&rendered.content).unwrap();
+        assert_eq!(frontmatter["type"], "mcq");
+        assert_eq!(frontmatter["question"], "Capital of France?");
+        assert_eq!(frontmatter["options"], json!(["Paris", "Lyon"]));
+        assert_eq!(frontmatter["answer"], 0);
+        assert_eq!(frontmatter["due"], "2026-03-05");
+        assert_eq!(frontmatter["deck"], "Default");
+    }
+
+    #[test]
+    fn creates_notes_from_workspace_templates_without_overwriting() {
+        // Inside the working directory, which is an allowed root
+        let workspace = tempfile::tempdir_in(std::env::current_dir().unwrap()).unwrap();
+        let dir = workspace.path().join(TEMPLATE_DIR);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("meeting.md"),
+            "# {{title}}\n\nAttendees: {{who}}\n",
+        )
+        .unwrap();
+
+        let target = resolve_target(workspace.path(), "Meetings/Standup").unwrap();
+        let created = create_from_template(
+            workspace.path(),
+            "meeting",
+            &target,
+            &vars(json!({ "who": ["Ana", "Li"] })),
+            now(),
+        )
+        .unwrap();
+        assert!(created.path.ends_with("Standup.md"));
+        assert_eq!(
+            std::fs::read_to_string(&target).unwrap(),
+            "# Standup\n\nAttendees: Ana, Li\n"
+        );
+
+        let again = create_from_template(workspace.path(), "meeting", &target, &Map::new(), now());
+        assert!(matches!(again, Err(AppError::FileExists(_))));
+        assert!(resolve_target(workspace.path(), "../outside.md").is_err());
+        assert!(load_template(workspace.path(), "../secret").is_err());
+
+        crate::fs::set_thread_readonly(true);
+        let target = resolve_target(workspace.path(), "Meetings/Retro").unwrap();
+        let refused =
+            create_from_template(workspace.path(), "meeting", &target, &Map::new(), now());
+        crate::fs::set_thread_readonly(false);
+        assert!(matches!(refused, Err(AppError::ReadOnly(_))));
+        assert!(!target.exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn refuses_targets_redirected_out_of_the_workspace() {
+        let workspace = tempfile::tempdir_in(std::env::current_dir().unwrap()).unwrap();
+        let outside = tempfile::tempdir_in(std::env::current_dir().unwrap()).unwrap();
+        std::os::unix::fs::symlink(outside.path(), workspace.path().join("Linked")).unwrap();
+        let dir = workspace.path().join(TEMPLATE_DIR);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("plain.md"), "# {{title}}\n").unwrap();
+
+        let target = resolve_target(workspace.path(), "Linked/Note").unwrap();
+        let created = create_from_template(workspace.path(), "plain", &target, &Map::new(), now());
+        assert!(matches!(created, Err(AppError::InvalidPath(_))));
+        assert!(!outside.path().join("Note.md").exists());
+    }
+}
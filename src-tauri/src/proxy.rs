@@ -1,4 +1,4 @@
-use reqwest::Client;
+use reqwest::{Client, ClientBuilder};
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::RwLock;
@@ -50,6 +50,17 @@ impl ProxyState {
         )
     }
 
+    /// Get a client builder configured with the current proxy, for callers
+    /// that need their own timeout or redirect policy.
+    pub async fn client_builder(&self) -> Result<ClientBuilder, String> {
+        let config = self.config.read().await;
+        client_builder(if config.enabled {
+            Some(&config.proxy_url)
+        } else {
+            None
+        })
+    }
+
     /// Update proxy config and rebuild the shared client.
     pub async fn set_config(&self, proxy_url: String, enabled: bool) -> Result<(), String> {
         let proxy = if enabled {
@@ -89,17 +100,24 @@ fn build_client(proxy_url: Option<&str>) -> Result<Client, String> {
 }
 
 fn build_client_with_timeout(proxy_url: Option<&str>, timeout: Duration) -> Result<Client, String> {
-    let mut builder = Client::builder().timeout(timeout);
-    if let Some(url) = proxy_url {
-        if !url.is_empty() {
+    client_builder(proxy_url)?
+        .timeout(timeout)
+        .build()
+        .map_err(|e| format!("Failed to build HTTP client: {}", e))
+}
+
+/// Without an app proxy, environment and system proxies are ignored too, so
+/// requests always connect directly and go through the caller's resolver.
+fn client_builder(proxy_url: Option<&str>) -> Result<ClientBuilder, String> {
+    let builder = Client::builder();
+    match proxy_url.filter(|url| !url.is_empty()) {
+        Some(url) => {
             let proxy =
                 reqwest::Proxy::all(url).map_err(|e| format!("Invalid proxy URL: {}", e))?;
-            builder = builder.proxy(proxy);
+            Ok(builder.proxy(proxy))
         }
+        None => Ok(builder.no_proxy()),
     }
-    builder
-        .build()
-        .map_err(|e| format!("Failed to build HTTP client: {}", e))
 }
 
 // ── Tauri commands ──
@@ -0,0 +1,368 @@
+//! End-to-end encryption of cloud relay traffic
+//!
+//! The desktop puts a random secret into the pairing payload, which reaches the
+//! phone out of band (QR code or copy/paste) and never passes through the relay.
+//! Both peers derive one key per direction from it with HKDF-SHA256 and exchange
+//! ChaCha20-Poly1305 envelopes, so the relay server only forwards ciphertext.
+//!
+//! Every envelope carries the sender's stream id and a counter, both bound in
+//! the AAD together with the direction. The receiver drops frames whose counter
+//! does not increase and frames from a stream it has already moved past, so the
+//! relay can neither replay frames nor reflect them back to their sender. A
+//! new stream id (the peer reconnected) starts a fresh counter.
+//!
+//! IETF ChaCha20-Poly1305 with random 96-bit nonces is used rather than
+//! XChaCha20 because it is what CryptoKit and the Android JCA provide.
+
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use hkdf::Hkdf;
+use rand::RngCore;
+use serde::Deserialize;
+use serde_json::json;
+use sha2::Sha256;
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+const HKDF_SALT: &[u8] = b"lumina-relay-e2e";
+const KEY_ID_INFO: &[u8] = b"v2 key id";
+/// Prefix of every envelope's AAD so ciphertext cannot be replayed in another protocol
+const AAD_PREFIX: &str = "lumina-relay-e2e-v2";
+const NONCE_LEN: usize = 12;
+const SECRET_LEN: usize = 32;
+const STREAM_ID_LEN: usize = 8;
+
+/// Which peer a cipher belongs to; each side seals with its own key
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RelayRole {
+    Desktop,
+    Mobile,
+}
+
+impl RelayRole {
+    fn as_str(self) -> &'static str {
+        match self {
+            RelayRole::Desktop => "desktop",
+            RelayRole::Mobile => "mobile",
+        }
+    }
+
+    fn peer(self) -> Self {
+        match self {
+            RelayRole::Desktop => RelayRole::Mobile,
+            RelayRole::Mobile => RelayRole::Desktop,
+        }
+    }
+
+    /// HKDF info of the key that frames sent by this role are sealed with
+    fn key_info(self) -> String {
+        format!("v2 {} chacha20poly1305 key", self.as_str())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum E2eError {
+    /// The peer encrypts with a different pairing secret
+    KeyMismatch,
+    /// The envelope is malformed or failed authentication
+    Corrupt,
+    /// An unencrypted message arrived while encryption is required
+    Plaintext,
+    /// The frame was already received, or belongs to an earlier stream
+    Replayed,
+}
+
+impl E2eError {
+    /// Machine-readable `code` of the error frame sent back to the peer
+    pub fn code(self) -> &'static str {
+        match self {
+            E2eError::KeyMismatch => "e2e_key_mismatch",
+            E2eError::Corrupt => "e2e_corrupt",
+            E2eError::Plaintext => "e2e_required",
+            E2eError::Replayed => "e2e_replayed",
+        }
+    }
+}
+
+impl std::fmt::Display for E2eError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let message = match self {
+            E2eError::KeyMismatch => {
+                "End-to-end encryption keys do not match; scan the pairing code again"
+            }
+            E2eError::Corrupt => "Received an encrypted message that could not be decrypted",
+            E2eError::Plaintext => {
+                "This desktop requires end-to-end encryption; scan the current pairing code"
+            }
+            E2eError::Replayed => "Dropped a replayed or out-of-order encrypted message",
+        };
+        f.write_str(message)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct SealedData {
+    kid: String,
+    sid: String,
+    seq: u64,
+    nonce: String,
+    ciphertext: String,
+}
+
+/// Counter state of the peer's frame streams
+#[derive(Default)]
+struct ReceivedFrames {
+    stream_id: Option<String>,
+    last_seq: u64,
+    /// Streams the peer has moved past; their frames are never accepted again
+    retired: HashSet<String>,
+}
+
+impl ReceivedFrames {
+    /// Records `seq` of stream `sid`, refusing anything that is not newer
+    fn accept(&mut self, sid: &str, seq: u64) -> Result<(), E2eError> {
+        if self.stream_id.as_deref() == Some(sid) {
+            if seq <= self.last_seq {
+                return Err(E2eError::Replayed);
+            }
+        } else if self.retired.contains(sid) {
+            return Err(E2eError::Replayed);
+        } else if let Some(previous) = self.stream_id.replace(sid.to_string()) {
+            self.retired.insert(previous);
+        }
+        self.last_seq = seq;
+        Ok(())
+    }
+}
+
+/// Encrypts and decrypts relay frames with per-direction keys derived from the
+/// pairing secret
+pub struct RelayCipher {
+    role: RelayRole,
+    /// Seals frames sent by `role`
+    sealer: ChaCha20Poly1305,
+    /// Opens frames sent by the peer
+    opener: ChaCha20Poly1305,
+    /// Short fingerprint of the secret, sent with every envelope so a peer with
+    /// another key is told apart from a corrupted frame
+    key_id: String,
+    /// Random id of the frames this cipher seals
+    stream_id: String,
+    /// Counter of the last sealed frame
+    sent: AtomicU64,
+    received: Mutex<ReceivedFrames>,
+}
+
+impl RelayCipher {
+    /// New random pairing secret, base64 encoded
+    pub fn generate_secret() -> String {
+        let mut secret = [0u8; SECRET_LEN];
+        rand::thread_rng().fill_bytes(&mut secret);
+        STANDARD.encode(secret)
+    }
+
+    /// Cipher for the `role` side of the pairing
+    pub fn from_secret(secret: &str, role: RelayRole) -> Result<Self, String> {
+        let ikm = STANDARD
+            .decode(secret.trim())
+            .map_err(|e| format!("Invalid E2E secret: {}", e))?;
+        if ikm.len() != SECRET_LEN {
+            return Err("Invalid E2E secret length".to_string());
+        }
+        let hkdf = Hkdf::<Sha256>::new(Some(HKDF_SALT), &ikm);
+        let derive = |info: &[u8]| {
+            let mut key = [0u8; 32];
+            hkdf.expand(info, &mut key)
+                .map(|_| ChaCha20Poly1305::new(Key::from_slice(&key)))
+                .map_err(|e| format!("Failed to derive E2E key: {}", e))
+        };
+        let sealer = derive(role.key_info().as_bytes())?;
+        let opener = derive(role.peer().key_info().as_bytes())?;
+        let mut key_id = [0u8; 8];
+        hkdf.expand(KEY_ID_INFO, &mut key_id)
+            .map_err(|e| format!("Failed to derive E2E key: {}", e))?;
+        let mut stream_id = [0u8; STREAM_ID_LEN];
+        rand::thread_rng().fill_bytes(&mut stream_id);
+        Ok(Self {
+            role,
+            sealer,
+            opener,
+            key_id: hex::encode(key_id),
+            stream_id: hex::encode(stream_id),
+            sent: AtomicU64::new(0),
+            received: Mutex::new(ReceivedFrames::default()),
+        })
+    }
+
+    /// Wraps `plaintext` in an `e2e` envelope
+    pub fn seal(&self, plaintext: &str) -> Result<String, String> {
+        let seq = self.sent.fetch_add(1, Ordering::SeqCst) + 1;
+        let aad = envelope_aad(self.role, &self.stream_id, seq);
+        let mut nonce = [0u8; NONCE_LEN];
+        rand::thread_rng().fill_bytes(&mut nonce);
+        let ciphertext = self
+            .sealer
+            .encrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: plaintext.as_bytes(),
+                    aad: aad.as_bytes(),
+                },
+            )
+            .map_err(|_| "Failed to encrypt relay message".to_string())?;
+        Ok(json!({
+            "type": "e2e",
+            "data": {
+                "kid": self.key_id,
+                "sid": self.stream_id,
+                "seq": seq,
+                "nonce": STANDARD.encode(nonce),
+                "ciphertext": STANDARD.encode(ciphertext),
+            }
+        })
+        .to_string())
+    }
+
+    /// Decrypts an `e2e` envelope; any other message is rejected
+    pub fn open(&self, text: &str) -> Result<String, E2eError> {
+        let mut envelope: serde_json::Value =
+            serde_json::from_str(text).map_err(|_| E2eError::Corrupt)?;
+        if envelope.get("type").and_then(|kind| kind.as_str()) != Some("e2e") {
+            return Err(E2eError::Plaintext);
+        }
+        let sealed: SealedData =
+            serde_json::from_value(envelope["data"].take()).map_err(|_| E2eError::Corrupt)?;
+        if sealed.kid != self.key_id {
+            return Err(E2eError::KeyMismatch);
+        }
+        let nonce = STANDARD
+            .decode(&sealed.nonce)
+            .ok()
+            .filter(|nonce| nonce.len() == NONCE_LEN)
+            .ok_or(E2eError::Corrupt)?;
+        let ciphertext = STANDARD
+            .decode(&sealed.ciphertext)
+            .map_err(|_| E2eError::Corrupt)?;
+        let aad = envelope_aad(self.role.peer(), &sealed.sid, sealed.seq);
+        let plaintext = self
+            .opener
+            .decrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: &ciphertext,
+                    aad: aad.as_bytes(),
+                },
+            )
+            .map_err(|_| E2eError::Corrupt)?;
+        let text = String::from_utf8(plaintext).map_err(|_| E2eError::Corrupt)?;
+        self.received
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .accept(&sealed.sid, sealed.seq)?;
+        Ok(text)
+    }
+}
+
+/// AAD of a frame sent by `sender`: binds the direction, stream and counter
+fn envelope_aad(sender: RelayRole, stream_id: &str, seq: u64) -> String {
+    format!("{}|{}|{}|{}", AAD_PREFIX, sender.as_str(), stream_id, seq)
+}
+
+/// Unencrypted error frame, readable by a peer that lacks the key
+pub fn error_frame(error: E2eError) -> String {
+    json!({
+        "type": "error",
+        "data": { "message": error.to_string(), "code": error.code() }
+    })
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_between_peers_with_the_same_secret() {
+        let secret = RelayCipher::generate_secret();
+        let desktop = RelayCipher::from_secret(&secret, RelayRole::Desktop).unwrap();
+        let mobile = RelayCipher::from_secret(&secret, RelayRole::Mobile).unwrap();
+
+        let sealed = desktop.seal(r#"{"type":"pong"}"#).unwrap();
+        assert!(!sealed.contains("pong"));
+        assert_eq!(mobile.open(&sealed).unwrap(), r#"{"type":"pong"}"#);
+        let again = desktop.seal(r#"{"type":"pong"}"#).unwrap();
+        assert_ne!(sealed, again);
+        assert_eq!(mobile.open(&again).unwrap(), r#"{"type":"pong"}"#);
+
+        let reply = mobile.seal(r#"{"type":"ping"}"#).unwrap();
+        assert_eq!(desktop.open(&reply).unwrap(), r#"{"type":"ping"}"#);
+    }
+
+    #[test]
+    fn rejects_replayed_and_reflected_frames() {
+        let secret = RelayCipher::generate_secret();
+        let desktop = RelayCipher::from_secret(&secret, RelayRole::Desktop).unwrap();
+        let mobile = RelayCipher::from_secret(&secret, RelayRole::Mobile).unwrap();
+
+        let first = desktop.seal("first").unwrap();
+        let second = desktop.seal("second").unwrap();
+        assert_eq!(mobile.open(&second).unwrap(), "second");
+        assert_eq!(mobile.open(&second), Err(E2eError::Replayed));
+        assert_eq!(mobile.open(&first), Err(E2eError::Replayed));
+        // Sent back to the desktop, its own frames do not authenticate
+        assert_eq!(desktop.open(&first), Err(E2eError::Corrupt));
+
+        // A reconnected desktop starts a new stream; the old one stays closed
+        let reconnected = RelayCipher::from_secret(&secret, RelayRole::Desktop).unwrap();
+        assert_eq!(
+            mobile.open(&reconnected.seal("hello").unwrap()).unwrap(),
+            "hello"
+        );
+        let stale = desktop.seal("stale").unwrap();
+        assert_eq!(mobile.open(&stale), Err(E2eError::Replayed));
+    }
+
+    #[test]
+    fn rejects_counters_moved_to_another_frame() {
+        let secret = RelayCipher::generate_secret();
+        let desktop = RelayCipher::from_secret(&secret, RelayRole::Desktop).unwrap();
+        let mobile = RelayCipher::from_secret(&secret, RelayRole::Mobile).unwrap();
+
+        let mut envelope: serde_json::Value =
+            serde_json::from_str(&desktop.seal("hello").unwrap()).unwrap();
+        envelope["data"]["seq"] = json!(100);
+        assert_eq!(mobile.open(&envelope.to_string()), Err(E2eError::Corrupt));
+    }
+
+    #[test]
+    fn reports_mismatched_keys_and_plaintext() {
+        let desktop =
+            RelayCipher::from_secret(&RelayCipher::generate_secret(), RelayRole::Desktop).unwrap();
+        let stranger =
+            RelayCipher::from_secret(&RelayCipher::generate_secret(), RelayRole::Mobile).unwrap();
+
+        let sealed = stranger.seal("hello").unwrap();
+        assert_eq!(desktop.open(&sealed), Err(E2eError::KeyMismatch));
+        assert_eq!(
+            desktop.open(r#"{"type":"pair","data":{}}"#),
+            Err(E2eError::Plaintext)
+        );
+    }
+
+    #[test]
+    fn rejects_tampered_ciphertext() {
+        let secret = RelayCipher::generate_secret();
+        let desktop = RelayCipher::from_secret(&secret, RelayRole::Desktop).unwrap();
+        let mobile = RelayCipher::from_secret(&secret, RelayRole::Mobile).unwrap();
+        let mut envelope: serde_json::Value =
+            serde_json::from_str(&desktop.seal("hello").unwrap()).unwrap();
+        let ciphertext = envelope["data"]["ciphertext"].as_str().unwrap();
+        let mut bytes = STANDARD.decode(ciphertext).unwrap();
+        bytes[0] ^= 1;
+        envelope["data"]["ciphertext"] = json!(STANDARD.encode(bytes));
+
+        assert_eq!(mobile.open(&envelope.to_string()), Err(E2eError::Corrupt));
+    }
+}
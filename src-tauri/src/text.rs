@@ -0,0 +1,36 @@
+//! Character classes shared by word counting, search terms, token estimates
+//! and typesetting.
+
+/// CJK ideographs (including compatibility ideographs and extensions B–G),
+/// kana and Hangul syllables: characters that form words without spaces.
+pub(crate) fn is_cjk(ch: char) -> bool {
+    matches!(
+        ch as u32,
+        0x3040..=0x30FF
+            | 0x3400..=0x4DBF
+            | 0x4E00..=0x9FFF
+            | 0xAC00..=0xD7AF
+            | 0xF900..=0xFAFF
+            | 0x20000..=0x2A6DF
+            | 0x2A700..=0x2B73F
+            | 0x2B740..=0x2B81F
+            | 0x2B820..=0x2CEAF
+            | 0x2CEB0..=0x2EBEF
+            | 0x30000..=0x3134F
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_ideographs_kana_and_hangul() {
+        for ch in ['学', 'ひ', 'カ', '한', '\u{F900}', '\u{20000}'] {
+            assert!(is_cjk(ch), "{:?}", ch);
+        }
+        for ch in ['a', '1', '。', '、', ' ', 'é'] {
+            assert!(!is_cjk(ch), "{:?}", ch);
+        }
+    }
+}
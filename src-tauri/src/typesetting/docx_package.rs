@@ -0,0 +1,1153 @@
+use super::pdf_image::detect_image_format;
+use flate2::read::DeflateDecoder;
+use std::collections::HashMap;
+use std::io::Read;
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum DocxPackageError {
+    #[error("not a zip archive")]
+    NotZip,
+    #[error("corrupt zip entry: {0}")]
+    CorruptEntry(String),
+    #[error("unsupported zip compression method {method} for {name}")]
+    UnsupportedCompression { name: String, method: u16 },
+    #[error("missing package part: {0}")]
+    MissingPart(String),
+    #[error("malformed xml in {0}")]
+    MalformedXml(String),
+    #[error("zip entry {0} exceeds the size limit")]
+    EntryTooLarge(String),
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DocxRunStyle {
+    pub bold: bool,
+    pub italic: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DocxRun {
+    pub text: String,
+    pub style: DocxRunStyle,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DocxAlign {
+    #[default]
+    Left,
+    Center,
+    Right,
+    Justify,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DocxParagraph {
+    /// Outline level (1-based) for headings and titles.
+    pub heading: Option<u8>,
+    pub align: DocxAlign,
+    /// Resolved list marker (e.g. "•" or "2.") and nesting level.
+    pub list: Option<(String, u8)>,
+    pub runs: Vec<DocxRun>,
+    /// A page break precedes this paragraph.
+    pub page_break_before: bool,
+    /// Inline pictures, laid out as blocks below the paragraph text.
+    pub images: Vec<DocxImage>,
+}
+
+/// An inline PNG or JPEG picture.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DocxImage {
+    /// Relationship id of the picture part (`r:embed`).
+    pub rel_id: String,
+    /// Display size in EMUs (914400 per inch).
+    pub width_emu: i64,
+    pub height_emu: i64,
+    /// Encoded image file, filled in from the package by [`parse_docx`].
+    pub data: Vec<u8>,
+}
+
+impl DocxParagraph {
+    pub fn text(&self) -> String {
+        self.runs.iter().map(|run| run.text.as_str()).collect()
+    }
+}
+
+/// Body page geometry from the last `w:sectPr`, in twentieths of a point.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DocxPageSetup {
+    pub width: i32,
+    pub height: i32,
+    pub margin_top: i32,
+    pub margin_right: i32,
+    pub margin_bottom: i32,
+    pub margin_left: i32,
+}
+
+impl Default for DocxPageSetup {
+    /// A4 with one-inch margins.
+    fn default() -> Self {
+        Self {
+            width: 11906,
+            height: 16838,
+            margin_top: 1440,
+            margin_right: 1440,
+            margin_bottom: 1440,
+            margin_left: 1440,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DocxDocument {
+    pub paragraphs: Vec<DocxParagraph>,
+    pub page: DocxPageSetup,
+    /// Content the built-in renderer cannot reproduce (tables, images, ...).
+    pub unsupported: Vec<&'static str>,
+}
+
+/// Parses the parts of a .docx package needed for simple text rendering.
+pub fn parse_docx(bytes: &[u8]) -> Result<DocxDocument, DocxPackageError> {
+    let mut parts = PartReader::new(bytes, MAX_PACKAGE_BYTES);
+    let document = parts
+        .read("word/document.xml")?
+        .ok_or_else(|| DocxPackageError::MissingPart("word/document.xml".into()))?;
+    let styles = parts.read("word/styles.xml")?;
+    let numbering = parts.read("word/numbering.xml")?;
+
+    let heading_styles = match styles {
+        Some(xml) => parse_heading_styles(&String::from_utf8_lossy(&xml))?,
+        None => HashMap::new(),
+    };
+    let numbering = match numbering {
+        Some(xml) => parse_numbering(&String::from_utf8_lossy(&xml))?,
+        None => Numbering::default(),
+    };
+    let mut document = parse_document_xml(
+        &String::from_utf8_lossy(&document),
+        &heading_styles,
+        &numbering,
+    )?;
+
+    if document.paragraphs.iter().any(|p| !p.images.is_empty()) {
+        let relationships = match parts.read("word/_rels/document.xml.rels")? {
+            Some(xml) => parse_relationships(&String::from_utf8_lossy(&xml))?,
+            None => HashMap::new(),
+        };
+        let mut missing = false;
+        for paragraph in &mut document.paragraphs {
+            for mut image in std::mem::take(&mut paragraph.images) {
+                let data = match relationships.get(&image.rel_id) {
+                    Some(part) => parts.read(part)?,
+                    None => None,
+                };
+                match data {
+                    Some(data) if detect_image_format(&data).is_some() => {
+                        image.data = data;
+                        paragraph.images.push(image);
+                    }
+                    // Linked pictures, vector formats (EMF, SVG, ...) and broken references
+                    _ => missing = true,
+                }
+            }
+        }
+        if missing && !document.unsupported.contains(&"images") {
+            document.unsupported.push("images");
+        }
+    }
+    Ok(document)
+}
+
+/// Maps internal relationship ids of `word/document.xml` to package part names.
+fn parse_relationships(xml: &str) -> Result<HashMap<String, String>, DocxPackageError> {
+    let mut relationships = HashMap::new();
+    for event in parse_xml(xml, "word/_rels/document.xml.rels")? {
+        let XmlEvent::Start { name, attrs, .. } = event else {
+            continue;
+        };
+        if name != "Relationship" || attr(&attrs, "TargetMode") == Some("External") {
+            continue;
+        }
+        if let (Some(id), Some(target)) = (attr(&attrs, "Id"), attr(&attrs, "Target")) {
+            let part = match target.strip_prefix('/') {
+                Some(absolute) => absolute.to_string(),
+                None => format!("word/{}", target),
+            };
+            relationships.insert(id.to_string(), part);
+        }
+    }
+    Ok(relationships)
+}
+
+/// Largest decompressed size accepted for a single package part.
+const MAX_ENTRY_BYTES: usize = 64 * 1024 * 1024;
+/// Largest decompressed size accepted for all parts read from one package.
+const MAX_PACKAGE_BYTES: usize = 256 * 1024 * 1024;
+/// Highest list level Word defines (`w:ilvl` 0-8).
+const MAX_LIST_LEVEL: u8 = 8;
+
+/// Reads package parts while keeping the total decompressed size under a budget,
+/// so a crafted archive cannot inflate into unbounded memory.
+struct PartReader<'a> {
+    bytes: &'a [u8],
+    remaining: usize,
+}
+
+impl<'a> PartReader<'a> {
+    fn new(bytes: &'a [u8], budget: usize) -> Self {
+        Self {
+            bytes,
+            remaining: budget,
+        }
+    }
+
+    fn read(&mut self, name: &str) -> Result<Option<Vec<u8>>, DocxPackageError> {
+        let limit = self.remaining.min(MAX_ENTRY_BYTES);
+        let data = read_zip_entry_limited(self.bytes, name, limit)?;
+        if let Some(data) = &data {
+            self.remaining -= data.len();
+        }
+        Ok(data)
+    }
+}
+
+const EOCD_SIGNATURE: u32 = 0x0605_4b50;
+const CENTRAL_SIGNATURE: u32 = 0x0201_4b50;
+const LOCAL_SIGNATURE: u32 = 0x0403_4b50;
+
+fn read_u16(bytes: &[u8], offset: usize) -> Option<u16> {
+    let slice = bytes.get(offset..offset + 2)?;
+    Some(u16::from_le_bytes([slice[0], slice[1]]))
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> Option<u32> {
+    let slice = bytes.get(offset..offset + 4)?;
+    Some(u32::from_le_bytes([slice[0], slice[1], slice[2], slice[3]]))
+}
+
+/// Reads one entry of a zip archive. Returns `Ok(None)` when it is absent.
+/// Only stored and deflated entries are supported (no zip64, no encryption).
+pub fn read_zip_entry(bytes: &[u8], name: &str) -> Result<Option<Vec<u8>>, DocxPackageError> {
+    read_zip_entry_limited(bytes, name, MAX_ENTRY_BYTES)
+}
+
+/// Like [`read_zip_entry`], but fails with `EntryTooLarge` once the entry
+/// would decompress to more than `limit` bytes.
+fn read_zip_entry_limited(
+    bytes: &[u8],
+    name: &str,
+    limit: usize,
+) -> Result<Option<Vec<u8>>, DocxPackageError> {
+    // The end-of-central-directory record is followed by at most a 64 KiB comment
+    let search_start = bytes.len().saturating_sub(22 + u16::MAX as usize);
+    let eocd = (search_start..=bytes.len().saturating_sub(22))
+        .rev()
+        .find(|&offset| read_u32(bytes, offset) == Some(EOCD_SIGNATURE))
+        .ok_or(DocxPackageError::NotZip)?;
+    let entry_count = read_u16(bytes, eocd + 10).ok_or(DocxPackageError::NotZip)? as usize;
+    let mut cursor = read_u32(bytes, eocd + 16).ok_or(DocxPackageError::NotZip)? as usize;
+
+    let corrupt = || DocxPackageError::CorruptEntry(name.to_string());
+    for _ in 0..entry_count {
+        if read_u32(bytes, cursor) != Some(CENTRAL_SIGNATURE) {
+            return Err(DocxPackageError::NotZip);
+        }
+        let method = read_u16(bytes, cursor + 10).ok_or(DocxPackageError::NotZip)?;
+        let compressed_size = read_u32(bytes, cursor + 20).ok_or(DocxPackageError::NotZip)?;
+        let name_len = read_u16(bytes, cursor + 28).ok_or(DocxPackageError::NotZip)? as usize;
+        let extra_len = read_u16(bytes, cursor + 30).ok_or(DocxPackageError::NotZip)? as usize;
+        let comment_len = read_u16(bytes, cursor + 32).ok_or(DocxPackageError::NotZip)? as usize;
+        let local_offset = read_u32(bytes, cursor + 42).ok_or(DocxPackageError::NotZip)? as usize;
+        let entry_name = bytes
+            .get(cursor + 46..cursor + 46 + name_len)
+            .ok_or(DocxPackageError::NotZip)?;
+        cursor += 46 + name_len + extra_len + comment_len;
+
+        if entry_name != name.as_bytes() {
+            continue;
+        }
+        if read_u32(bytes, local_offset) != Some(LOCAL_SIGNATURE) {
+            return Err(corrupt());
+        }
+        let local_name_len = read_u16(bytes, local_offset + 26).ok_or_else(corrupt)? as usize;
+        let local_extra_len = read_u16(bytes, local_offset + 28).ok_or_else(corrupt)? as usize;
+        let data_start = local_offset + 30 + local_name_len + local_extra_len;
+        let data = bytes
+            .get(data_start..data_start + compressed_size as usize)
+            .ok_or_else(corrupt)?;
+
+        let too_large = || DocxPackageError::EntryTooLarge(name.to_string());
+        return match method {
+            0 if data.len() > limit => Err(too_large()),
+            0 => Ok(Some(data.to_vec())),
+            8 => {
+                // Read one byte past the limit to tell "exactly at" from "over"
+                let mut output = Vec::new();
+                DeflateDecoder::new(data)
+                    .take(limit as u64 + 1)
+                    .read_to_end(&mut output)
+                    .map_err(|_| corrupt())?;
+                if output.len() > limit {
+                    return Err(too_large());
+                }
+                Ok(Some(output))
+            }
+            method => Err(DocxPackageError::UnsupportedCompression {
+                name: name.to_string(),
+                method,
+            }),
+        };
+    }
+    Ok(None)
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum XmlEvent {
+    Start {
+        name: String,
+        attrs: Vec<(String, String)>,
+        empty: bool,
+    },
+    End(String),
+    Text(String),
+}
+
+fn attr<'a>(attrs: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    attrs
+        .iter()
+        .find(|(key, _)| key == name)
+        .map(|(_, value)| value.as_str())
+}
+
+/// Minimal XML tokenizer for WordprocessingML parts: elements, attributes and
+/// text with entity decoding. Declarations, comments and DTDs are skipped.
+fn parse_xml(xml: &str, part: &str) -> Result<Vec<XmlEvent>, DocxPackageError> {
+    let malformed = || DocxPackageError::MalformedXml(part.to_string());
+    let mut events = Vec::new();
+    let mut rest = xml;
+
+    while !rest.is_empty() {
+        let Some(lt) = rest.find('<') else {
+            events.push(XmlEvent::Text(decode_entities(rest)));
+            break;
+        };
+        if lt > 0 {
+            events.push(XmlEvent::Text(decode_entities(&rest[..lt])));
+        }
+        rest = &rest[lt..];
+
+        if let Some(cdata) = rest.strip_prefix("<![CDATA[") {
+            let end = cdata.find("]]>").ok_or_else(malformed)?;
+            events.push(XmlEvent::Text(cdata[..end].to_string()));
+            rest = &cdata[end + 3..];
+            continue;
+        }
+        let terminator = if rest.starts_with("<!--") {
+            "-->"
+        } else if rest.starts_with("<?") {
+            "?>"
+        } else {
+            ">"
+        };
+        let end = rest.find(terminator).ok_or_else(malformed)?;
+        let tag = &rest[1..end];
+        rest = &rest[end + terminator.len()..];
+
+        if tag.starts_with('!') || tag.starts_with('?') {
+            continue;
+        }
+        if let Some(name) = tag.strip_prefix('/') {
+            events.push(XmlEvent::End(name.trim().to_string()));
+            continue;
+        }
+        let (tag, empty) = match tag.strip_suffix('/') {
+            Some(tag) => (tag, true),
+            None => (tag, false),
+        };
+        let name_end = tag.find(char::is_whitespace).unwrap_or(tag.len());
+        let name = tag[..name_end].to_string();
+        if name.is_empty() {
+            return Err(malformed());
+        }
+        events.push(XmlEvent::Start {
+            name,
+            attrs: parse_attributes(&tag[name_end..]).ok_or_else(malformed)?,
+            empty,
+        });
+    }
+
+    Ok(events)
+}
+
+fn parse_attributes(mut input: &str) -> Option<Vec<(String, String)>> {
+    let mut attrs = Vec::new();
+    loop {
+        input = input.trim_start();
+        if input.is_empty() {
+            return Some(attrs);
+        }
+        let eq = input.find('=')?;
+        let key = input[..eq].trim().to_string();
+        let value_part = input[eq + 1..].trim_start();
+        let quote = value_part
+            .chars()
+            .next()
+            .filter(|c| *c == '"' || *c == '\'')?;
+        let value_end = value_part[1..].find(quote)? + 1;
+        attrs.push((key, decode_entities(&value_part[1..value_end])));
+        input = &value_part[value_end + 1..];
+    }
+}
+
+fn decode_entities(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_string();
+    }
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        output.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let Some(semi) = rest.find(';') else {
+            break;
+        };
+        let entity = &rest[1..semi];
+        let decoded = match entity {
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "amp" => Some('&'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => entity
+                .strip_prefix("#x")
+                .map(|hex| u32::from_str_radix(hex, 16))
+                .or_else(|| entity.strip_prefix('#').map(str::parse::<u32>))
+                .and_then(Result::ok)
+                .and_then(char::from_u32),
+        };
+        match decoded {
+            Some(ch) => {
+                output.push(ch);
+                rest = &rest[semi + 1..];
+            }
+            None => {
+                output.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    output.push_str(rest);
+    output
+}
+
+/// `w:val` toggles such as `<w:b/>`, `<w:b w:val="0"/>`.
+fn toggle_value(attrs: &[(String, String)]) -> bool {
+    !matches!(
+        attr(attrs, "w:val"),
+        Some("0") | Some("false") | Some("off") | Some("none")
+    )
+}
+
+fn heading_level_from_name(name: &str) -> Option<u8> {
+    let name = name.trim().to_ascii_lowercase();
+    if name == "title" {
+        return Some(1);
+    }
+    let level = name.strip_prefix("heading")?.trim().parse::<u8>().ok()?;
+    (1..=9).contains(&level).then_some(level)
+}
+
+/// Maps paragraph style ids to heading levels, from style names
+/// ("heading 1", "Title") or an explicit `w:outlineLvl`.
+fn parse_heading_styles(xml: &str) -> Result<HashMap<String, u8>, DocxPackageError> {
+    let mut headings = HashMap::new();
+    let mut current: Option<(String, Option<u8>)> = None;
+
+    for event in parse_xml(xml, "word/styles.xml")? {
+        match event {
+            XmlEvent::Start { name, attrs, .. } => match name.as_str() {
+                "w:style" => {
+                    current = attr(&attrs, "w:styleId").map(|id| (id.to_string(), None));
+                }
+                "w:name" => {
+                    if let Some((_, level)) = current.as_mut() {
+                        if let Some(found) = attr(&attrs, "w:val").and_then(heading_level_from_name)
+                        {
+                            *level = Some(found);
+                        }
+                    }
+                }
+                "w:outlineLvl" => {
+                    if let Some((_, level)) = current.as_mut() {
+                        if let Some(found) = attr(&attrs, "w:val")
+                            .and_then(|val| val.parse::<u8>().ok())
+                            .filter(|val| *val < 9)
+                        {
+                            level.get_or_insert(found + 1);
+                        }
+                    }
+                }
+                _ => {}
+            },
+            XmlEvent::End(name) if name == "w:style" => {
+                if let Some((id, Some(level))) = current.take() {
+                    headings.insert(id, level);
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(headings)
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct ListLevel {
+    format: String,
+    text: String,
+    start: u32,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+struct Numbering {
+    /// abstractNumId -> level -> definition
+    abstract_levels: HashMap<String, HashMap<u8, ListLevel>>,
+    /// numId -> abstractNumId
+    instances: HashMap<String, String>,
+}
+
+impl Numbering {
+    fn level(&self, num_id: &str, level: u8) -> Option<&ListLevel> {
+        let abstract_id = self.instances.get(num_id)?;
+        self.abstract_levels.get(abstract_id)?.get(&level)
+    }
+}
+
+fn parse_numbering(xml: &str) -> Result<Numbering, DocxPackageError> {
+    let mut numbering = Numbering::default();
+    let mut abstract_id: Option<String> = None;
+    let mut level: Option<(u8, ListLevel)> = None;
+    let mut num_id: Option<String> = None;
+
+    for event in parse_xml(xml, "word/numbering.xml")? {
+        match event {
+            XmlEvent::Start { name, attrs, .. } => {
+                let val = attr(&attrs, "w:val");
+                match name.as_str() {
+                    "w:abstractNum" => {
+                        abstract_id = attr(&attrs, "w:abstractNumId").map(str::to_string);
+                    }
+                    "w:lvl" if abstract_id.is_some() => {
+                        level = attr(&attrs, "w:ilvl")
+                            .and_then(|ilvl| ilvl.parse::<u8>().ok())
+                            .filter(|ilvl| *ilvl <= MAX_LIST_LEVEL)
+                            .map(|ilvl| {
+                                (
+                                    ilvl,
+                                    ListLevel {
+                                        format: "decimal".to_string(),
+                                        text: format!("%{}.", ilvl + 1),
+                                        start: 1,
+                                    },
+                                )
+                            });
+                    }
+                    "w:start" => {
+                        if let (Some((_, level)), Some(start)) =
+                            (level.as_mut(), val.and_then(|v| v.parse().ok()))
+                        {
+                            level.start = start;
+                        }
+                    }
+                    "w:numFmt" => {
+                        if let (Some((_, level)), Some(format)) = (level.as_mut(), val) {
+                            level.format = format.to_string();
+                        }
+                    }
+                    "w:lvlText" => {
+                        if let (Some((_, level)), Some(text)) = (level.as_mut(), val) {
+                            level.text = text.to_string();
+                        }
+                    }
+                    "w:num" => {
+                        num_id = attr(&attrs, "w:numId").map(str::to_string);
+                    }
+                    "w:abstractNumId" => {
+                        if let (Some(num_id), Some(abstract_id)) = (num_id.as_ref(), val) {
+                            numbering
+                                .instances
+                                .insert(num_id.clone(), abstract_id.to_string());
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            XmlEvent::End(name) => match name.as_str() {
+                "w:lvl" => {
+                    if let (Some(abstract_id), Some((ilvl, definition))) =
+                        (abstract_id.as_ref(), level.take())
+                    {
+                        numbering
+                            .abstract_levels
+                            .entry(abstract_id.clone())
+                            .or_default()
+                            .insert(ilvl, definition);
+                    }
+                }
+                "w:abstractNum" => abstract_id = None,
+                "w:num" => num_id = None,
+                _ => {}
+            },
+            XmlEvent::Text(_) => {}
+        }
+    }
+    Ok(numbering)
+}
+
+fn format_list_number(format: &str, value: u32) -> String {
+    fn roman(mut value: u32) -> String {
+        const NUMERALS: [(u32, &str); 13] = [
+            (1000, "m"),
+            (900, "cm"),
+            (500, "d"),
+            (400, "cd"),
+            (100, "c"),
+            (90, "xc"),
+            (50, "l"),
+            (40, "xl"),
+            (10, "x"),
+            (9, "ix"),
+            (5, "v"),
+            (4, "iv"),
+            (1, "i"),
+        ];
+        let mut output = String::new();
+        for (amount, numeral) in NUMERALS {
+            while value >= amount {
+                output.push_str(numeral);
+                value -= amount;
+            }
+        }
+        output
+    }
+    fn letter(value: u32) -> String {
+        let index = (value.max(1) - 1) % 26;
+        let repeat = (value.max(1) - 1) / 26 + 1;
+        char::from(b'a' + index as u8)
+            .to_string()
+            .repeat(repeat as usize)
+    }
+
+    match format {
+        "lowerRoman" => roman(value),
+        "upperRoman" => roman(value).to_uppercase(),
+        "lowerLetter" => letter(value),
+        "upperLetter" => letter(value).to_uppercase(),
+        _ => value.to_string(),
+    }
+}
+
+/// Tracks list counters while walking the document in order.
+#[derive(Default)]
+struct ListCounters {
+    counters: HashMap<String, Vec<Option<u32>>>,
+}
+
+impl ListCounters {
+    fn marker(&mut self, numbering: &Numbering, num_id: &str, level: u8) -> Option<String> {
+        let definition = numbering.level(num_id, level)?;
+        if definition.format == "none" {
+            return Some(String::new());
+        }
+        if definition.format == "bullet" {
+            return Some("•".to_string());
+        }
+
+        let counters = self.counters.entry(num_id.to_string()).or_default();
+        let index = level as usize;
+        if counters.len() <= index {
+            counters.resize(index + 1, None);
+        }
+        let next = counters[index].map_or(definition.start, |value| value + 1);
+        counters[index] = Some(next);
+        counters.truncate(index + 1);
+
+        let mut text = definition.text.clone();
+        for (depth, value) in counters.iter().enumerate() {
+            let placeholder = format!("%{}", depth + 1);
+            if !text.contains(&placeholder) {
+                continue;
+            }
+            let format = numbering
+                .level(num_id, depth as u8)
+                .map_or("decimal", |level| level.format.as_str());
+            let value = value.unwrap_or_else(|| {
+                numbering
+                    .level(num_id, depth as u8)
+                    .map_or(1, |level| level.start)
+            });
+            text = text.replace(&placeholder, &format_list_number(format, value));
+        }
+        Some(text)
+    }
+}
+
+fn unsupported_feature(element: &str) -> Option<&'static str> {
+    match element {
+        "w:tbl" => Some("tables"),
+        "w:pict" | "v:imagedata" => Some("images"),
+        "wp:anchor" => Some("floating images"),
+        "w:object" => Some("embedded objects"),
+        "m:oMath" | "m:oMathPara" => Some("equations"),
+        "w:txbxContent" => Some("text boxes"),
+        "w:footnoteReference" | "w:endnoteReference" => Some("footnotes"),
+        _ => None,
+    }
+}
+
+fn twips(attrs: &[(String, String)], name: &str) -> Option<i32> {
+    attr(attrs, name)?
+        .parse::<f32>()
+        .ok()
+        .map(|value| value as i32)
+}
+
+fn parse_document_xml(
+    xml: &str,
+    heading_styles: &HashMap<String, u8>,
+    numbering: &Numbering,
+) -> Result<DocxDocument, DocxPackageError> {
+    let mut document = DocxDocument::default();
+    let mut counters = ListCounters::default();
+
+    let mut paragraph: Option<DocxParagraph> = None;
+    let mut list_ref: (Option<String>, u8) = (None, 0);
+    let mut run_style = DocxRunStyle::default();
+    let mut in_paragraph_props = false;
+    let mut in_run_props = false;
+    let mut in_text = false;
+    let mut pending_page_break = false;
+    // Inline picture being read inside `w:drawing`
+    let mut drawing: Option<DocxImage> = None;
+
+    for event in parse_xml(xml, "word/document.xml")? {
+        match event {
+            XmlEvent::Start { name, attrs, empty } => {
+                if let Some(feature) = unsupported_feature(&name) {
+                    if !document.unsupported.contains(&feature) {
+                        document.unsupported.push(feature);
+                    }
+                }
+                let val = attr(&attrs, "w:val");
+                match name.as_str() {
+                    "w:p" => {
+                        paragraph = Some(DocxParagraph {
+                            page_break_before: std::mem::take(&mut pending_page_break),
+                            ..DocxParagraph::default()
+                        });
+                        list_ref = (None, 0);
+                        if empty {
+                            document.paragraphs.extend(paragraph.take());
+                        }
+                    }
+                    "w:pStyle" => {
+                        if let (Some(paragraph), Some(style)) = (paragraph.as_mut(), val) {
+                            paragraph.heading = heading_styles
+                                .get(style)
+                                .copied()
+                                .or_else(|| heading_level_from_name(style));
+                        }
+                    }
+                    "w:outlineLvl" => {
+                        if let (Some(paragraph), Some(level)) =
+                            (paragraph.as_mut(), val.and_then(|v| v.parse::<u8>().ok()))
+                        {
+                            if level < 9 {
+                                paragraph.heading = Some(level + 1);
+                            }
+                        }
+                    }
+                    "w:jc" => {
+                        if let Some(paragraph) = paragraph.as_mut() {
+                            paragraph.align = match val {
+                                Some("center") => DocxAlign::Center,
+                                Some("right") | Some("end") => DocxAlign::Right,
+                                Some("both") | Some("distribute") => DocxAlign::Justify,
+                                _ => DocxAlign::Left,
+                            };
+                        }
+                    }
+                    "w:ilvl" => {
+                        list_ref.1 = val
+                            .and_then(|v| v.parse::<u8>().ok())
+                            .map_or(0, |level| level.min(MAX_LIST_LEVEL));
+                    }
+                    "w:numId" => {
+                        list_ref.0 = val.filter(|id| *id != "0").map(str::to_string);
+                    }
+                    "w:pageBreakBefore" if toggle_value(&attrs) => {
+                        if let Some(paragraph) = paragraph.as_mut() {
+                            paragraph.page_break_before = true;
+                        }
+                    }
+                    "w:r" => {
+                        run_style = DocxRunStyle::default();
+                    }
+                    "w:drawing" if empty && !document.unsupported.contains(&"images") => {
+                        document.unsupported.push("images");
+                    }
+                    "wp:inline" => drawing = Some(DocxImage::default()),
+                    "wp:extent" => {
+                        if let Some(image) = drawing.as_mut() {
+                            let emu = |name| attr(&attrs, name).and_then(|v| v.parse().ok());
+                            image.width_emu = emu("cx").unwrap_or(0);
+                            image.height_emu = emu("cy").unwrap_or(0);
+                        }
+                    }
+                    "a:blip" => {
+                        if let (Some(image), Some(id)) = (drawing.as_mut(), attr(&attrs, "r:embed"))
+                        {
+                            image.rel_id = id.to_string();
+                        }
+                    }
+                    "w:pPr" if !empty => in_paragraph_props = true,
+                    "w:rPr" if !empty => in_run_props = true,
+                    "w:b" if in_run_props => run_style.bold = toggle_value(&attrs),
+                    "w:i" if in_run_props => run_style.italic = toggle_value(&attrs),
+                    "w:t" if !empty => in_text = true,
+                    "w:tab" if !in_paragraph_props => {
+                        push_text(paragraph.as_mut(), "\t", run_style)
+                    }
+                    "w:br" | "w:cr" => {
+                        if attr(&attrs, "w:type") == Some("page") {
+                            pending_page_break = true;
+                        } else {
+                            push_text(paragraph.as_mut(), "\n", run_style);
+                        }
+                    }
+                    "w:pgSz" => {
+                        document.page.width = twips(&attrs, "w:w").unwrap_or(document.page.width);
+                        document.page.height = twips(&attrs, "w:h").unwrap_or(document.page.height);
+                    }
+                    "w:pgMar" => {
+                        let page = &mut document.page;
+                        page.margin_top = twips(&attrs, "w:top").unwrap_or(page.margin_top).abs();
+                        page.margin_right = twips(&attrs, "w:right").unwrap_or(page.margin_right);
+                        page.margin_bottom = twips(&attrs, "w:bottom")
+                            .unwrap_or(page.margin_bottom)
+                            .abs();
+                        page.margin_left = twips(&attrs, "w:left").unwrap_or(page.margin_left);
+                    }
+                    _ => {}
+                }
+            }
+            XmlEvent::End(name) => match name.as_str() {
+                "w:p" => {
+                    if let Some(mut finished) = paragraph.take() {
+                        if let Some(num_id) = list_ref.0.take() {
+                            finished.list = counters
+                                .marker(numbering, &num_id, list_ref.1)
+                                .map(|marker| (marker, list_ref.1));
+                        }
+                        // A break in an otherwise empty paragraph starts the page here;
+                        // after text it carries over to the next paragraph
+                        if pending_page_break
+                            && finished.runs.is_empty()
+                            && finished.images.is_empty()
+                        {
+                            finished.page_break_before = true;
+                            pending_page_break = false;
+                        }
+                        document.paragraphs.push(finished);
+                    }
+                }
+                "w:drawing" => {
+                    match drawing.take() {
+                        Some(image)
+                            if !image.rel_id.is_empty()
+                                && image.width_emu > 0
+                                && image.height_emu > 0 =>
+                        {
+                            if let Some(paragraph) = paragraph.as_mut() {
+                                paragraph.images.push(image);
+                            }
+                        }
+                        // Charts and shapes; anchored pictures are flagged on `wp:anchor`
+                        Some(_) if !document.unsupported.contains(&"images") => {
+                            document.unsupported.push("images");
+                        }
+                        _ => {}
+                    }
+                }
+                "w:pPr" => in_paragraph_props = false,
+                "w:rPr" => in_run_props = false,
+                "w:t" => in_text = false,
+                _ => {}
+            },
+            XmlEvent::Text(text) => {
+                if in_text {
+                    push_text(paragraph.as_mut(), &text, run_style);
+                }
+            }
+        }
+    }
+
+    Ok(document)
+}
+
+fn push_text(paragraph: Option<&mut DocxParagraph>, text: &str, style: DocxRunStyle) {
+    let Some(paragraph) = paragraph else {
+        return;
+    };
+    match paragraph.runs.last_mut() {
+        Some(last) if last.style == style => last.text.push_str(text),
+        _ => paragraph.runs.push(DocxRun {
+            text: text.to_string(),
+            style,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    const NS: &str = r#"xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main""#;
+
+    /// Builds a zip archive; `deflate` selects the compression method.
+    fn build_zip(entries: &[(&str, &[u8], bool)]) -> Vec<u8> {
+        let mut output = Vec::new();
+        let mut central = Vec::new();
+        for (name, content, deflate) in entries {
+            let data = if *deflate {
+                let mut encoder =
+                    flate2::write::DeflateEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(content).unwrap();
+                encoder.finish().unwrap()
+            } else {
+                content.to_vec()
+            };
+            let method: u16 = if *deflate { 8 } else { 0 };
+            let offset = output.len() as u32;
+
+            output.extend_from_slice(&LOCAL_SIGNATURE.to_le_bytes());
+            output.extend_from_slice(&[20, 0, 0, 0]);
+            output.extend_from_slice(&method.to_le_bytes());
+            output.extend_from_slice(&[0; 8]);
+            output.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            output.extend_from_slice(&(content.len() as u32).to_le_bytes());
+            output.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            output.extend_from_slice(&[0, 0]);
+            output.extend_from_slice(name.as_bytes());
+            output.extend_from_slice(&data);
+
+            central.extend_from_slice(&CENTRAL_SIGNATURE.to_le_bytes());
+            central.extend_from_slice(&[20, 0, 20, 0, 0, 0]);
+            central.extend_from_slice(&method.to_le_bytes());
+            central.extend_from_slice(&[0; 8]);
+            central.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            central.extend_from_slice(&(content.len() as u32).to_le_bytes());
+            central.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            central.extend_from_slice(&[0; 12]);
+            central.extend_from_slice(&offset.to_le_bytes());
+            central.extend_from_slice(name.as_bytes());
+        }
+        let central_offset = output.len() as u32;
+        output.extend_from_slice(&central);
+        output.extend_from_slice(&EOCD_SIGNATURE.to_le_bytes());
+        output.extend_from_slice(&[0; 4]);
+        output.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+        output.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+        output.extend_from_slice(&(central.len() as u32).to_le_bytes());
+        output.extend_from_slice(&central_offset.to_le_bytes());
+        output.extend_from_slice(&[0, 0]);
+        output
+    }
+
+    fn sample_docx(body: &str) -> Vec<u8> {
+        let document = format!(
+            r#"<?xml version="1.0"?><w:document {NS}><w:body>{body}</w:body></w:document>"#
+        );
+        let styles = format!(
+            r#"<w:styles {NS}><w:style w:type="paragraph" w:styleId="Heading2"><w:name w:val="heading 2"/></w:style></w:styles>"#
+        );
+        let numbering = format!(
+            r#"<w:numbering {NS}>
+<w:abstractNum w:abstractNumId="0"><w:lvl w:ilvl="0"><w:numFmt w:val="bullet"/><w:lvlText w:val="•"/></w:lvl></w:abstractNum>
+<w:abstractNum w:abstractNumId="1"><w:lvl w:ilvl="0"><w:start w:val="1"/><w:numFmt w:val="decimal"/><w:lvlText w:val="%1."/></w:lvl>
+<w:lvl w:ilvl="1"><w:numFmt w:val="lowerLetter"/><w:lvlText w:val="%1.%2)"/></w:lvl></w:abstractNum>
+<w:num w:numId="1"><w:abstractNumId w:val="0"/></w:num><w:num w:numId="2"><w:abstractNumId w:val="1"/></w:num>
+</w:numbering>"#
+        );
+        build_zip(&[
+            ("[Content_Types].xml", b"<Types/>", false),
+            ("word/document.xml", document.as_bytes(), true),
+            ("word/styles.xml", styles.as_bytes(), false),
+            ("word/numbering.xml", numbering.as_bytes(), true),
+        ])
+    }
+
+    fn list_paragraph(num_id: u32, level: u8, text: &str) -> String {
+        format!(
+            r#"<w:p><w:pPr><w:numPr><w:ilvl w:val="{level}"/><w:numId w:val="{num_id}"/></w:numPr></w:pPr><w:r><w:t>{text}</w:t></w:r></w:p>"#
+        )
+    }
+
+    #[test]
+    fn reads_stored_and_deflated_zip_entries() {
+        let zip = build_zip(&[("a.txt", b"stored", false), ("b.txt", b"deflated", true)]);
+
+        assert_eq!(read_zip_entry(&zip, "a.txt").unwrap().unwrap(), b"stored");
+        assert_eq!(read_zip_entry(&zip, "b.txt").unwrap().unwrap(), b"deflated");
+        assert_eq!(read_zip_entry(&zip, "c.txt").unwrap(), None);
+        assert_eq!(
+            read_zip_entry(b"plain text", "a.txt").unwrap_err(),
+            DocxPackageError::NotZip
+        );
+    }
+
+    #[test]
+    fn rejects_entries_that_inflate_past_the_limit() {
+        let zeros = vec![0u8; 4096];
+        let zip = build_zip(&[("bomb.bin", &zeros, true), ("raw.bin", &zeros, false)]);
+
+        assert_eq!(
+            read_zip_entry_limited(&zip, "bomb.bin", 4096)
+                .unwrap()
+                .unwrap()
+                .len(),
+            4096
+        );
+        assert_eq!(
+            read_zip_entry_limited(&zip, "bomb.bin", 1024).unwrap_err(),
+            DocxPackageError::EntryTooLarge("bomb.bin".into())
+        );
+        assert_eq!(
+            read_zip_entry_limited(&zip, "raw.bin", 1024).unwrap_err(),
+            DocxPackageError::EntryTooLarge("raw.bin".into())
+        );
+
+        let mut parts = PartReader::new(&zip, 6000);
+        assert!(parts.read("bomb.bin").unwrap().is_some());
+        assert_eq!(
+            parts.read("raw.bin").unwrap_err(),
+            DocxPackageError::EntryTooLarge("raw.bin".into())
+        );
+    }
+
+    #[test]
+    fn ignores_out_of_range_list_levels() {
+        let numbering = format!(
+            r#"<w:numbering {NS}><w:abstractNum w:abstractNumId="0"><w:lvl w:ilvl="255"><w:numFmt w:val="decimal"/></w:lvl><w:lvl w:ilvl="8"><w:numFmt w:val="decimal"/></w:lvl></w:abstractNum></w:numbering>"#
+        );
+        let numbering = parse_numbering(&numbering).unwrap();
+        let levels = &numbering.abstract_levels["0"];
+
+        assert_eq!(levels.len(), 1);
+        assert_eq!(levels[&8].text, "%9.");
+    }
+
+    #[test]
+    fn parses_headings_runs_and_lists() {
+        let body = [
+            r#"<w:p><w:pPr><w:pStyle w:val="Heading2"/><w:jc w:val="center"/></w:pPr><w:r><w:t>Intro &amp; scope</w:t></w:r></w:p>"#.to_string(),
+            r#"<w:p><w:r><w:t xml:space="preserve">Plain </w:t></w:r><w:r><w:rPr><w:b/><w:i w:val="0"/></w:rPr><w:t>bold</w:t></w:r><w:r><w:rPr><w:i/></w:rPr><w:t>it</w:t></w:r></w:p>"#.to_string(),
+            list_paragraph(1, 0, "bullet"),
+            list_paragraph(2, 0, "first"),
+            list_paragraph(2, 1, "nested"),
+            list_paragraph(2, 0, "second"),
+            r#"<w:p><w:r><w:br w:type="page"/></w:r></w:p><w:p><w:r><w:t>after</w:t></w:r></w:p>"#.to_string(),
+            r#"<w:sectPr><w:pgSz w:w="12240" w:h="15840"/><w:pgMar w:top="720" w:right="1080" w:bottom="720" w:left="1080"/></w:sectPr>"#.to_string(),
+        ]
+        .concat();
+
+        let document = parse_docx(&sample_docx(&body)).unwrap();
+        let paragraphs = &document.paragraphs;
+
+        assert!(document.unsupported.is_empty());
+        assert_eq!(paragraphs[0].heading, Some(2));
+        assert_eq!(paragraphs[0].align, DocxAlign::Center);
+        assert_eq!(paragraphs[0].text(), "Intro & scope");
+        assert_eq!(
+            paragraphs[1].runs,
+            vec![
+                DocxRun {
+                    text: "Plain ".into(),
+                    style: DocxRunStyle::default()
+                },
+                DocxRun {
+                    text: "bold".into(),
+                    style: DocxRunStyle {
+                        bold: true,
+                        italic: false
+                    }
+                },
+                DocxRun {
+                    text: "it".into(),
+                    style: DocxRunStyle {
+                        bold: false,
+                        italic: true
+                    }
+                },
+            ]
+        );
+        let markers: Vec<_> = paragraphs[2..6]
+            .iter()
+            .map(|p| p.list.clone().unwrap())
+            .collect();
+        assert_eq!(
+            markers,
+            vec![
+                ("•".to_string(), 0),
+                ("1.".to_string(), 0),
+                ("1.a)".to_string(), 1),
+                ("2.".to_string(), 0),
+            ]
+        );
+        assert!(paragraphs[6].page_break_before);
+        assert_eq!(paragraphs[7].text(), "after");
+        assert_eq!(document.page.width, 12240);
+        assert_eq!(document.page.margin_left, 1080);
+    }
+
+    #[test]
+    fn flags_tables_and_images_as_unsupported() {
+        let body = r#"<w:tbl><w:tr><w:tc><w:p><w:r><w:t>cell</w:t></w:r></w:p></w:tc></w:tr></w:tbl><w:p><w:r><w:drawing/></w:r></w:p>"#;
+
+        let document = parse_docx(&sample_docx(body)).unwrap();
+
+        assert_eq!(document.unsupported, vec!["tables", "images"]);
+        assert_eq!(document.paragraphs[0].text(), "cell");
+    }
+
+    #[test]
+    fn resolves_inline_pictures_from_relationships() {
+        let png = crate::typesetting::pdf_image::tests::build_png(1, 1, 0, &[0, 128]);
+        let picture = |id: &str| {
+            format!(
+                r#"<w:p><w:r><w:drawing><wp:inline><wp:extent cx="914400" cy="457200"/><a:graphic><a:graphicData><pic:pic><pic:blipFill><a:blip r:embed="{id}"/></pic:blipFill></pic:pic></a:graphicData></a:graphic></wp:inline></w:drawing></w:r></w:p>"#
+            )
+        };
+        let document = format!(
+            r#"<w:document {NS}><w:body>{}{}<w:p><w:r><w:drawing><wp:anchor><a:blip r:embed="rId1"/></wp:anchor></w:drawing></w:r></w:p></w:body></w:document>"#,
+            picture("rId1"),
+            picture("rId2")
+        );
+        let rels = r#"<Relationships><Relationship Id="rId1" Target="media/image1.png"/><Relationship Id="rId2" Target="media/image2.emf"/></Relationships>"#;
+        let zip = build_zip(&[
+            ("word/document.xml", document.as_bytes(), true),
+            ("word/_rels/document.xml.rels", rels.as_bytes(), false),
+            ("word/media/image1.png", &png, false),
+            ("word/media/image2.emf", b"\x01\x00\x00\x00", false),
+        ]);
+
+        let document = parse_docx(&zip).unwrap();
+
+        let image = &document.paragraphs[0].images[0];
+        assert_eq!(image.rel_id, "rId1");
+        assert_eq!((image.width_emu, image.height_emu), (914400, 457200));
+        assert_eq!(image.data, png);
+        assert!(document.paragraphs[1].images.is_empty());
+        assert_eq!(document.unsupported, vec!["floating images", "images"]);
+    }
+}
@@ -0,0 +1,566 @@
+use super::docx_package::{
+    parse_docx, DocxAlign, DocxDocument, DocxPackageError, DocxPageSetup, DocxParagraph,
+    DocxRunStyle,
+};
+use super::font_manager::find_installed_fonts;
+use super::{
+    layout_text_paragraph, paginate_lines_with_widows_orphans, shape_mixed_text, write_text_pdf,
+    FontData, FontManager, PageMargins, PageSize, PageStyle, ParagraphAlign, PdfExportError,
+    PdfGlyph, PdfImage, PdfPage, PdfTextRun, ShapingError, TextLayoutOptions,
+};
+use thiserror::Error;
+
+// Shared with the markdown flow so both built-in renderers typeset alike.
+/// Layout works in integer units of 1/100 pt.
+pub(super) const UNITS_PER_PT: f32 = 100.0;
+pub(super) const BODY_FONT_SIZE_PT: f32 = 11.0;
+pub(super) const HEADING_FONT_SIZES_PT: [f32; 3] = [20.0, 16.0, 13.0];
+pub(super) const LINE_HEIGHT_RATIO: f32 = 1.3;
+pub(super) const PARAGRAPH_SPACE_AFTER_PT: f32 = 6.0;
+pub(super) const HEADING_SPACE_BEFORE_PT: f32 = 12.0;
+pub(super) const LIST_INDENT_PT: f32 = 18.0;
+const EMU_PER_PT: f32 = 12700.0;
+pub(super) const TAB_SPACES: &str = "    ";
+pub(super) const ORPHANS: usize = 2;
+pub(super) const WIDOWS: usize = 2;
+
+/// TrueType fonts probed for the built-in renderer, in order of preference.
+/// The first one that covers every character of the document is used.
+const FONT_CANDIDATES: [&str; 16] = [
+    "arial.ttf",
+    "Arial.ttf",
+    "calibri.ttf",
+    "times.ttf",
+    "Times New Roman.ttf",
+    "LiberationSans-Regular.ttf",
+    "NotoSans-Regular.ttf",
+    "DejaVuSans.ttf",
+    "simhei.ttf",
+    "simsun.ttf",
+    "msyh.ttf",
+    "Arial Unicode.ttf",
+    "NotoSansSC-Regular.ttf",
+    "NotoSansCJKsc-Regular.ttf",
+    "DroidSansFallbackFull.ttf",
+    "wqy-microhei.ttf",
+];
+
+#[derive(Debug, Error)]
+pub enum DocxRenderError {
+    #[error(transparent)]
+    Package(#[from] DocxPackageError),
+    #[error("document uses features the built-in renderer does not support: {}", .0.join(", "))]
+    Unsupported(Vec<&'static str>),
+    #[error("no installed TrueType font covers the document text")]
+    NoFont,
+    #[error(transparent)]
+    Shaping(#[from] ShapingError),
+    #[error(transparent)]
+    Pdf(#[from] PdfExportError),
+}
+
+/// Renders a .docx to PDF without LibreOffice. Handles paragraphs, headings,
+/// bold/italic runs, lists and inline PNG/JPEG pictures; documents with
+/// tables, floating or vector images and similar content are rejected unless `allow_unsupported` is set, in which case that
+/// content is dropped (table cell text is kept as plain paragraphs).
+pub fn render_docx_builtin(
+    bytes: &[u8],
+    allow_unsupported: bool,
+) -> Result<Vec<u8>, DocxRenderError> {
+    let document = parse_docx(bytes)?;
+    if !allow_unsupported && !document.unsupported.is_empty() {
+        return Err(DocxRenderError::Unsupported(document.unsupported));
+    }
+    let text: String = document
+        .paragraphs
+        .iter()
+        .flat_map(|paragraph| {
+            let marker = paragraph.list.as_ref().map(|(marker, _)| marker.clone());
+            marker.into_iter().chain(std::iter::once(paragraph.text()))
+        })
+        .collect();
+    let (font_name, font) = find_system_font(&text).ok_or(DocxRenderError::NoFont)?;
+    render_docx_document(&document, &font_name, &font)
+}
+
+pub fn render_docx_document(
+    document: &DocxDocument,
+    font_name: &str,
+    font: &FontData,
+) -> Result<Vec<u8>, DocxRenderError> {
+    let page_style = page_style_from_setup(document.page);
+    let body = page_style.body_box();
+    let body_left_pt = mm_to_pt(body.x_mm);
+    let body_top = (mm_to_pt(body.y_mm) * UNITS_PER_PT) as i32;
+    let body_width = (mm_to_pt(body.width_mm) * UNITS_PER_PT) as i32;
+    let body_height = (mm_to_pt(body.height_mm) * UNITS_PER_PT) as i32;
+
+    let mut lines = Vec::new();
+    for (index, paragraph) in document.paragraphs.iter().enumerate() {
+        let is_first = index == 0;
+        let mut laid = layout_docx_paragraph(
+            paragraph,
+            font,
+            body_left_pt,
+            body_width,
+            body_height,
+            is_first,
+        )?;
+        if let Some(first) = laid.first_mut() {
+            first.page_break_before = paragraph.page_break_before && !is_first;
+        }
+        lines.append(&mut laid);
+    }
+
+    let mut pages = Vec::new();
+    let mut section_start = 0;
+    while section_start < lines.len() {
+        let section_end = lines[section_start + 1..]
+            .iter()
+            .position(|line| line.page_break_before)
+            .map_or(lines.len(), |offset| section_start + 1 + offset);
+        let section = &lines[section_start..section_end];
+        let heights: Vec<i32> = section.iter().map(|line| line.height).collect();
+        let paragraph_end: Vec<bool> = section.iter().map(|line| line.paragraph_end).collect();
+
+        for slice in paginate_lines_with_widows_orphans(
+            &heights,
+            body_height,
+            &paragraph_end,
+            ORPHANS,
+            WIDOWS,
+        ) {
+            let mut page = PdfPage::default();
+            let mut cursor = body_top;
+            for line in &section[slice.start..slice.end] {
+                let baseline_pt = (cursor + line.baseline) as f32 / UNITS_PER_PT;
+                page.runs.extend(line.runs.iter().cloned().map(|mut run| {
+                    run.y_pt = baseline_pt;
+                    run
+                }));
+                let top_pt = cursor as f32 / UNITS_PER_PT;
+                page.images
+                    .extend(line.images.iter().cloned().map(|mut image| {
+                        image.y_pt += top_pt;
+                        image
+                    }));
+                cursor = cursor.saturating_add(line.height);
+            }
+            pages.push(page);
+        }
+        section_start = section_end;
+    }
+
+    Ok(write_text_pdf(page_style, font_name, font.bytes(), &pages)?)
+}
+
+/// One laid-out line with its paragraph spacing folded into `height`.
+#[derive(Clone, Debug)]
+struct LaidLine {
+    height: i32,
+    /// Baseline offset from the top of the line box.
+    baseline: i32,
+    /// Runs with absolute x positions; y is filled in during pagination.
+    runs: Vec<PdfTextRun>,
+    /// Block images with absolute x positions; y is relative to the line top.
+    images: Vec<PdfImage>,
+    paragraph_end: bool,
+    page_break_before: bool,
+}
+
+fn layout_docx_paragraph(
+    paragraph: &DocxParagraph,
+    font: &FontData,
+    body_left_pt: f32,
+    body_width: i32,
+    body_height: i32,
+    is_first: bool,
+) -> Result<Vec<LaidLine>, ShapingError> {
+    let font_size_pt = paragraph.heading.map_or(BODY_FONT_SIZE_PT, |level| {
+        HEADING_FONT_SIZES_PT[(level.max(1) as usize - 1).min(HEADING_FONT_SIZES_PT.len() - 1)]
+    });
+    let font_size = (font_size_pt * UNITS_PER_PT) as i32;
+    let line_height = (font_size_pt * LINE_HEIGHT_RATIO * UNITS_PER_PT) as i32;
+    let space_before = if paragraph.heading.is_some() && !is_first {
+        (HEADING_SPACE_BEFORE_PT * UNITS_PER_PT) as i32
+    } else {
+        0
+    };
+    let space_after = (PARAGRAPH_SPACE_AFTER_PT * UNITS_PER_PT) as i32;
+    let indent = paragraph.list.as_ref().map_or(0, |(_, level)| {
+        ((*level as f32 + 1.0) * LIST_INDENT_PT * UNITS_PER_PT) as i32
+    });
+    let align = match paragraph.align {
+        DocxAlign::Left => ParagraphAlign::Left,
+        DocxAlign::Center => ParagraphAlign::Center,
+        DocxAlign::Right => ParagraphAlign::Right,
+        DocxAlign::Justify => ParagraphAlign::Justify,
+    };
+
+    // Flatten runs into one string with a style per byte range
+    let mut text = String::new();
+    let mut spans: Vec<(usize, DocxRunStyle)> = Vec::new();
+    let heading_style = DocxRunStyle {
+        bold: paragraph.heading.is_some(),
+        italic: false,
+    };
+    if let Some((marker, _)) = paragraph
+        .list
+        .as_ref()
+        .filter(|(marker, _)| !marker.is_empty())
+    {
+        text.push_str(marker);
+        text.push(' ');
+        spans.push((text.len(), heading_style));
+    }
+    for run in &paragraph.runs {
+        text.push_str(&run.text.replace('\t', TAB_SPACES));
+        let style = DocxRunStyle {
+            bold: run.style.bold || heading_style.bold,
+            italic: run.style.italic,
+        };
+        spans.push((text.len(), style));
+    }
+    let style_at = |byte: usize| {
+        spans
+            .iter()
+            .find(|(end, _)| byte < *end)
+            .map_or(heading_style, |(_, style)| *style)
+    };
+
+    let metrics = font.metrics();
+    let scale = font_size_pt / metrics.units_per_em.max(1) as f32;
+    let ascent = metrics.ascent as f32 * scale * UNITS_PER_PT;
+    let descent = metrics.descent as f32 * scale * UNITS_PER_PT;
+    let baseline_in_line = ((line_height as f32 - (ascent - descent)) / 2.0 + ascent) as i32;
+
+    // Manual line breaks split the paragraph into segments laid out separately.
+    // A paragraph holding only pictures has no text lines at all.
+    let has_images = !paragraph.images.is_empty();
+    let mut segments = Vec::new();
+    if !(has_images && text.trim().is_empty()) {
+        let mut offset = 0;
+        for segment in text.split('\n') {
+            segments.push((offset, segment));
+            offset += segment.len() + 1;
+        }
+    }
+    let segment_count = segments.len();
+
+    let mut laid = Vec::new();
+    for (segment_index, (segment_offset, segment)) in segments.into_iter().enumerate() {
+        let first_segment = segment_index == 0;
+        // Pictures follow the text, so they carry the paragraph's space after
+        let last_segment = segment_index + 1 == segment_count && !has_images;
+        let segment_space_before = if first_segment { space_before } else { 0 };
+        let segment_space_after = if last_segment { space_after } else { 0 };
+
+        let positioned = layout_text_paragraph(
+            font,
+            segment,
+            TextLayoutOptions {
+                max_width: (body_width - indent).max(1),
+                line_height,
+                font_size: Some(font_size),
+                align,
+                first_line_indent: 0,
+                space_before: segment_space_before,
+                space_after: segment_space_after,
+            },
+        )?;
+        if positioned.is_empty() {
+            laid.push(LaidLine {
+                height: line_height + segment_space_before + segment_space_after,
+                baseline: segment_space_before + baseline_in_line,
+                runs: Vec::new(),
+                images: Vec::new(),
+                paragraph_end: last_segment,
+                page_break_before: false,
+            });
+            continue;
+        }
+
+        let glyphs = shape_mixed_text(font, segment)?.glyphs;
+        let cluster_ends = cluster_end_bytes(segment, &glyphs);
+        let line_count = positioned.len();
+        for (line_index, line) in positioned.into_iter().enumerate() {
+            let mut runs: Vec<PdfTextRun> = Vec::new();
+            let mut x_pt = body_left_pt + (indent + line.x_offset) as f32 / UNITS_PER_PT;
+            let mut gaps_seen = 0;
+            for index in line.start..line.end.min(glyphs.len()) {
+                let glyph = glyphs[index];
+                let cluster = glyph.cluster as usize;
+                let first_in_cluster = index == 0 || glyphs[index - 1].cluster != glyph.cluster;
+                let cluster_text = if first_in_cluster {
+                    segment
+                        .get(cluster..cluster_ends[index])
+                        .unwrap_or_default()
+                        .to_string()
+                } else {
+                    String::new()
+                };
+                let mut advance_pt = glyph.x_advance as f32 * scale;
+                if line.justify_gap > 0 && cluster_text.chars().any(char::is_whitespace) {
+                    let extra = line.justify_gap + i32::from(gaps_seen < line.justify_remainder);
+                    advance_pt += extra as f32 / UNITS_PER_PT;
+                    gaps_seen += 1;
+                }
+
+                let style = style_at(segment_offset + cluster);
+                let pdf_glyph = PdfGlyph {
+                    id: glyph.id as u16,
+                    advance_pt,
+                    text: cluster_text,
+                };
+                match runs.last_mut() {
+                    Some(run) if run.bold == style.bold && run.italic == style.italic => {
+                        run.glyphs.push(pdf_glyph)
+                    }
+                    _ => runs.push(PdfTextRun {
+                        x_pt,
+                        y_pt: 0.0,
+                        font_size_pt,
+                        font: 0,
+                        bold: style.bold,
+                        italic: style.italic,
+                        glyphs: vec![pdf_glyph],
+                    }),
+                }
+                x_pt += advance_pt;
+            }
+
+            laid.push(LaidLine {
+                height: line_height + line.space_before + line.space_after,
+                baseline: line.space_before + baseline_in_line,
+                runs,
+                images: Vec::new(),
+                paragraph_end: last_segment && line_index + 1 == line_count,
+                page_break_before: false,
+            });
+        }
+    }
+
+    // Each picture is its own line box, scaled down to fit the body
+    let available_width = (body_width - indent).max(1) as f32;
+    let image_count = paragraph.images.len();
+    for (image_index, image) in paragraph.images.iter().enumerate() {
+        let natural_width = image.width_emu as f32 / EMU_PER_PT * UNITS_PER_PT;
+        let natural_height = image.height_emu as f32 / EMU_PER_PT * UNITS_PER_PT;
+        let fit = (available_width / natural_width)
+            .min(body_height as f32 / natural_height)
+            .min(1.0);
+        let width = natural_width * fit;
+        let height = natural_height * fit;
+        let x_offset = match paragraph.align {
+            DocxAlign::Center => (available_width - width) / 2.0,
+            DocxAlign::Right => available_width - width,
+            DocxAlign::Left | DocxAlign::Justify => 0.0,
+        };
+        let image_space_before = if laid.is_empty() { space_before } else { 0 };
+        let last_image = image_index + 1 == image_count;
+        let image_space_after = if last_image { space_after } else { 0 };
+
+        laid.push(LaidLine {
+            height: height.ceil() as i32 + image_space_before + image_space_after,
+            baseline: 0,
+            runs: Vec::new(),
+            images: vec![PdfImage {
+                bytes: image.data.clone(),
+                x_pt: body_left_pt + (indent as f32 + x_offset) / UNITS_PER_PT,
+                y_pt: image_space_before as f32 / UNITS_PER_PT,
+                width_pt: width / UNITS_PER_PT,
+                height_pt: height / UNITS_PER_PT,
+            }],
+            paragraph_end: last_image,
+            page_break_before: false,
+        });
+    }
+    Ok(laid)
+}
+
+pub(super) fn cluster_end_bytes(text: &str, glyphs: &[super::Glyph]) -> Vec<usize> {
+    let mut ends = vec![text.len(); glyphs.len()];
+    for index in (0..glyphs.len()).rev() {
+        let cluster = glyphs[index].cluster;
+        ends[index] = glyphs[index + 1..]
+            .iter()
+            .map(|glyph| glyph.cluster)
+            .find(|next| *next > cluster)
+            .map_or(text.len(), |next| next as usize);
+    }
+    ends
+}
+
+pub(super) fn mm_to_pt(mm: f32) -> f32 {
+    mm * 72.0 / 25.4
+}
+
+fn twips_to_mm(twips: i32) -> f32 {
+    twips.max(0) as f32 / 1440.0 * 25.4
+}
+
+fn page_style_from_setup(setup: DocxPageSetup) -> PageStyle {
+    PageStyle {
+        size: PageSize::Custom {
+            width_mm: twips_to_mm(setup.width),
+            height_mm: twips_to_mm(setup.height),
+        },
+        margins: PageMargins {
+            top_mm: twips_to_mm(setup.margin_top),
+            right_mm: twips_to_mm(setup.margin_right),
+            bottom_mm: twips_to_mm(setup.margin_bottom),
+            left_mm: twips_to_mm(setup.margin_left),
+        },
+        header_height_mm: 0.0,
+        footer_height_mm: 0.0,
+    }
+}
+
+/// Finds an installed TrueType font (glyf outlines, not a collection) with a
+/// glyph for every visible character of `text`.
+fn find_system_font(text: &str) -> Option<(String, FontData)> {
+    let chain = find_installed_fonts(&FONT_CANDIDATES);
+    let (path, font) = FontManager::new().load_fallback(&chain, text)?;
+    let name = path.file_stem()?.to_string_lossy().to_string();
+    Some((name, font))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::typesetting::docx_package::{DocxImage, DocxRun};
+    use std::path::PathBuf;
+
+    fn fixture_font() -> FontData {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests")
+            .join("fixtures")
+            .join("katex-main-regular.ttf");
+        FontManager::new()
+            .load_from_path(path)
+            .expect("expected valid font load")
+    }
+
+    fn paragraph(text: &str, style: DocxRunStyle) -> DocxParagraph {
+        DocxParagraph {
+            runs: vec![DocxRun {
+                text: text.to_string(),
+                style,
+            }],
+            ..DocxParagraph::default()
+        }
+    }
+
+    fn page_count(pdf: &[u8]) -> usize {
+        let text = String::from_utf8_lossy(pdf);
+        let count = text
+            .split("/Type /Pages /Kids [")
+            .nth(1)
+            .and_then(|rest| rest.split("/Count ").nth(1))
+            .and_then(|rest| rest.split_whitespace().next())
+            .expect("page tree");
+        count.parse().unwrap()
+    }
+
+    #[test]
+    fn lays_out_styled_runs_and_list_markers() {
+        let font = fixture_font();
+        let mut item = DocxParagraph {
+            list: Some(("1.".to_string(), 1)),
+            ..paragraph("item", DocxRunStyle::default())
+        };
+        item.runs.push(DocxRun {
+            text: " bold".to_string(),
+            style: DocxRunStyle {
+                bold: true,
+                italic: false,
+            },
+        });
+
+        let lines = layout_docx_paragraph(&item, &font, 72.0, 40_000, 60_000, true).unwrap();
+
+        assert_eq!(lines.len(), 1);
+        let runs = &lines[0].runs;
+        assert_eq!(runs.len(), 2);
+        assert!(!runs[0].bold && runs[1].bold);
+        // Nested list items are indented two steps from the body edge
+        assert!((runs[0].x_pt - (72.0 + 2.0 * LIST_INDENT_PT)).abs() < 0.01);
+        let marker_text: String = runs[0].glyphs.iter().map(|g| g.text.as_str()).collect();
+        assert_eq!(marker_text, "1. item");
+        assert!(runs[1].x_pt > runs[0].x_pt);
+    }
+
+    #[test]
+    fn renders_headings_page_breaks_and_overflow_to_pages() {
+        let font = fixture_font();
+        let mut document = DocxDocument::default();
+        document.paragraphs.push(DocxParagraph {
+            heading: Some(1),
+            ..paragraph("Title", DocxRunStyle::default())
+        });
+        document
+            .paragraphs
+            .push(paragraph("Short intro.", DocxRunStyle::default()));
+        document.paragraphs.push(DocxParagraph {
+            page_break_before: true,
+            ..paragraph("Second page.", DocxRunStyle::default())
+        });
+        let single = render_docx_document(&document, "KaTeX Main", &font).unwrap();
+        assert_eq!(page_count(&single), 2);
+        let text = String::from_utf8_lossy(&single);
+        assert!(text.contains("/Encoding /Identity-H"));
+        assert!(text.contains("2 Tr"));
+
+        let long = "lorem ipsum dolor sit amet ".repeat(400);
+        document
+            .paragraphs
+            .push(paragraph(&long, DocxRunStyle::default()));
+        let overflow = render_docx_document(&document, "KaTeX Main", &font).unwrap();
+        assert!(page_count(&overflow) > 3);
+    }
+
+    #[test]
+    fn reserves_space_for_block_images_in_the_flow() {
+        let font = fixture_font();
+        let png = crate::typesetting::pdf_image::tests::build_png(1, 1, 0, &[0, 0]);
+        // 200x100pt picture centered on a 400pt body
+        let picture = DocxImage {
+            rel_id: "rId1".to_string(),
+            width_emu: 200 * 12700,
+            height_emu: 100 * 12700,
+            data: png,
+        };
+        let figure = DocxParagraph {
+            align: DocxAlign::Center,
+            images: vec![picture.clone()],
+            ..DocxParagraph::default()
+        };
+
+        let lines = layout_docx_paragraph(&figure, &font, 72.0, 40_000, 60_000, false).unwrap();
+
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0].height, 10_000 + 600);
+        let image = &lines[0].images[0];
+        assert!((image.x_pt - 172.0).abs() < 0.01);
+        assert!((image.width_pt - 200.0).abs() < 0.01 && (image.height_pt - 100.0).abs() < 0.01);
+
+        // An oversized picture shrinks to the body; text comes before it
+        let mut captioned = paragraph("Caption", DocxRunStyle::default());
+        captioned.images.push(DocxImage {
+            width_emu: 800 * 12700,
+            ..picture
+        });
+        let lines = layout_docx_paragraph(&captioned, &font, 72.0, 40_000, 60_000, false).unwrap();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].images.is_empty() && !lines[0].paragraph_end);
+        assert!((lines[1].images[0].width_pt - 400.0).abs() < 0.01);
+        assert_eq!(lines[1].height, 5_000 + 600);
+
+        let mut document = DocxDocument::default();
+        document.paragraphs.push(figure);
+        let pdf = render_docx_document(&document, "KaTeX Main", &font).unwrap();
+        let text = String::from_utf8_lossy(&pdf);
+        assert!(text.contains("/XObject << /Im0 "));
+        assert!(text.contains("200.00 0 0 100.00 "));
+    }
+}
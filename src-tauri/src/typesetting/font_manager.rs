@@ -3,10 +3,24 @@ use std::{
     path::{Path, PathBuf},
     sync::Arc,
 };
+use walkdir::WalkDir;
 
 const DEFAULT_ZH_FONT_FAMILY: &str = "SimSun";
 const DEFAULT_EN_FONT_FAMILY: &str = "Times New Roman";
 
+/// Monospace TrueType fonts probed for code, in order of preference.
+pub const MONOSPACE_FONT_CANDIDATES: [&str; 9] = [
+    "consola.ttf",
+    "cour.ttf",
+    "Courier New.ttf",
+    "Menlo-Regular.ttf",
+    "DejaVuSansMono.ttf",
+    "LiberationMono-Regular.ttf",
+    "NotoSansMono-Regular.ttf",
+    "SourceCodePro-Regular.ttf",
+    "UbuntuMono-R.ttf",
+];
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum ScriptKind {
     Zh,
@@ -127,6 +141,26 @@ impl FontManager {
         Ok(data)
     }
 
+    /// Loads the first font of a fallback `chain` that can be embedded
+    /// (TrueType outlines) and has a glyph for every visible character of
+    /// `text`. Fonts that fail to load are skipped.
+    pub fn load_fallback(&mut self, chain: &[PathBuf], text: &str) -> Option<(PathBuf, FontData)> {
+        let mut chars: Vec<char> = text
+            .chars()
+            .filter(|ch| !ch.is_whitespace() && !ch.is_control())
+            .collect();
+        chars.sort_unstable();
+        chars.dedup();
+
+        chain.iter().find_map(|path| {
+            let font = self.load_from_path(path).ok()?;
+            let face = ttf_parser::Face::parse(font.bytes(), 0).ok()?;
+            let covers = face.tables().glyf.is_some()
+                && chars.iter().all(|ch| face.glyph_index(*ch).is_some());
+            covers.then(|| (path.clone(), font))
+        })
+    }
+
     pub fn cached(&self, path: &Path) -> Option<&FontData> {
         self.cache.get(path)
     }
@@ -136,6 +170,54 @@ impl FontManager {
     }
 }
 
+/// Directories holding installed fonts on this platform.
+pub fn system_font_dirs() -> Vec<PathBuf> {
+    let home = std::env::var_os("HOME").map(PathBuf::from);
+    let mut dirs = Vec::new();
+    if cfg!(target_os = "windows") {
+        let windir = std::env::var_os("WINDIR").map_or(PathBuf::from("C:\\Windows"), PathBuf::from);
+        dirs.push(windir.join("Fonts"));
+        if let Some(local) = std::env::var_os("LOCALAPPDATA") {
+            dirs.push(
+                PathBuf::from(local)
+                    .join("Microsoft")
+                    .join("Windows")
+                    .join("Fonts"),
+            );
+        }
+    } else if cfg!(target_os = "macos") {
+        dirs.push(PathBuf::from("/System/Library/Fonts"));
+        dirs.push(PathBuf::from("/Library/Fonts"));
+        dirs.extend(home.map(|home| home.join("Library").join("Fonts")));
+    } else {
+        dirs.push(PathBuf::from("/usr/share/fonts"));
+        dirs.push(PathBuf::from("/usr/local/share/fonts"));
+        dirs.extend(home.map(|home| home.join(".local").join("share").join("fonts")));
+    }
+    dirs
+}
+
+/// Resolves font file names (case-insensitively) to installed fonts, keeping
+/// the order of `file_names` so the result can be used as a fallback chain.
+pub fn find_installed_fonts(file_names: &[&str]) -> Vec<PathBuf> {
+    let mut installed: HashMap<String, PathBuf> = HashMap::new();
+    for dir in system_font_dirs() {
+        for entry in WalkDir::new(dir)
+            .max_depth(4)
+            .into_iter()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_type().is_file())
+        {
+            let name = entry.file_name().to_string_lossy().to_ascii_lowercase();
+            installed.entry(name).or_insert_with(|| entry.into_path());
+        }
+    }
+    file_names
+        .iter()
+        .filter_map(|name| installed.get(&name.to_ascii_lowercase()).cloned())
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -195,6 +277,18 @@ mod tests {
         assert!(matches!(err, FontError::Parse(_)));
     }
 
+    #[test]
+    fn load_fallback_skips_fonts_missing_glyphs() {
+        let mut manager = FontManager::new();
+        let chain = vec![PathBuf::from("/nonexistent/font.ttf"), fixture_font_path()];
+
+        let (path, _) = manager
+            .load_fallback(&chain, "abc 123")
+            .expect("fixture covers latin text");
+        assert_eq!(path, fixture_font_path());
+        assert!(manager.load_fallback(&chain, "汉字").is_none());
+    }
+
     #[test]
     fn default_font_mapping_uses_expected_families() {
         let mapping = FontMapping::default_system();
@@ -0,0 +1,1006 @@
+use super::docx_render::{
+    cluster_end_bytes, mm_to_pt, BODY_FONT_SIZE_PT, HEADING_FONT_SIZES_PT as DOCX_HEADING_SIZES_PT,
+    HEADING_SPACE_BEFORE_PT, LINE_HEIGHT_RATIO, LIST_INDENT_PT, ORPHANS, PARAGRAPH_SPACE_AFTER_PT,
+    TAB_SPACES, UNITS_PER_PT, WIDOWS,
+};
+use super::text_layout::scale_glyphs_for_font_size;
+use super::{
+    layout_shaped_paragraph, paginate_lines_with_widows_orphans, shape_mixed_text,
+    write_multi_font_pdf, FontData, Glyph, PageStyle, ParagraphAlign, PdfExportError, PdfFont,
+    PdfGlyph, PdfPage, PdfTextRun, ShapingError, TextLayoutOptions,
+};
+use thiserror::Error;
+
+const CODE_BLOCK_FONT_SIZE_PT: f32 = 10.0;
+/// Markdown has six heading levels; the first three match the DOCX renderer
+/// and the deeper ones step down to body size.
+const HEADING_FONT_SIZES_PT: [f32; 6] = [
+    DOCX_HEADING_SIZES_PT[0],
+    DOCX_HEADING_SIZES_PT[1],
+    DOCX_HEADING_SIZES_PT[2],
+    12.0,
+    BODY_FONT_SIZE_PT,
+    BODY_FONT_SIZE_PT,
+];
+const BULLET: &str = "•";
+
+/// Font index of body text in the typeset pages' runs.
+pub const BODY_FONT: usize = 0;
+/// Font index of code when a separate code font is used.
+pub const CODE_FONT: usize = 1;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MarkdownSpanStyle {
+    pub bold: bool,
+    pub italic: bool,
+    pub code: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MarkdownSpan {
+    pub text: String,
+    pub style: MarkdownSpanStyle,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MarkdownBlockKind {
+    Paragraph,
+    /// ATX heading level (1-6).
+    Heading(u8),
+    /// A list item; continuation paragraphs of an item have no marker.
+    ListItem,
+    /// Fenced code; lines are separated by `\n`.
+    CodeBlock,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MarkdownBlock {
+    pub kind: MarkdownBlockKind,
+    /// Resolved list marker ("•" or "3.").
+    pub marker: Option<String>,
+    /// List nesting depth, 0 for top-level items.
+    pub list_level: u8,
+    /// Number of enclosing blockquotes.
+    pub quote_depth: u8,
+    pub spans: Vec<MarkdownSpan>,
+}
+
+impl MarkdownBlock {
+    pub fn text(&self) -> String {
+        self.spans.iter().map(|span| span.text.as_str()).collect()
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum MarkdownTypesetError {
+    #[error(transparent)]
+    Shaping(#[from] ShapingError),
+    #[error(transparent)]
+    Pdf(#[from] PdfExportError),
+}
+
+/// A font used for typesetting together with the name it is embedded under.
+#[derive(Clone, Copy, Debug)]
+pub struct NamedFont<'a> {
+    pub name: &'a str,
+    pub font: &'a FontData,
+}
+
+/// Fonts for [`typeset_markdown`]; code falls back to the body font.
+#[derive(Clone, Copy, Debug)]
+pub struct MarkdownFonts<'a> {
+    pub body: NamedFont<'a>,
+    pub code: Option<NamedFont<'a>>,
+}
+
+/// Parses the Markdown subset used in notes into blocks: ATX headings,
+/// paragraphs, bullet and numbered lists, blockquotes and fenced code, with
+/// bold, italic and code spans. Links keep their text; images, HTML and YAML
+/// front matter are dropped.
+pub fn parse_markdown(markdown: &str) -> Vec<MarkdownBlock> {
+    let mut parser = BlockParser::default();
+    let mut lines = markdown.lines().peekable();
+
+    // Front matter is only recognized when it is closed
+    if lines.peek().map(|line| line.trim_end()) == Some("---") {
+        let rest: Vec<&str> = markdown.lines().skip(1).collect();
+        if let Some(end) = rest
+            .iter()
+            .position(|line| matches!(line.trim_end(), "---" | "..."))
+        {
+            lines.nth(end + 1);
+        }
+    }
+
+    for line in lines {
+        parser.line(line);
+    }
+    parser.finish()
+}
+
+#[derive(Default)]
+struct PendingBlock {
+    kind: Option<MarkdownBlockKind>,
+    marker: Option<String>,
+    list_level: u8,
+    quote_depth: u8,
+    source: String,
+}
+
+struct Fence {
+    ch: char,
+    len: usize,
+    indent: usize,
+    quote_depth: u8,
+    lines: Vec<String>,
+}
+
+struct ListLevel {
+    indent: usize,
+    /// Number of the last ordered item; `None` for bullet lists.
+    number: Option<u32>,
+}
+
+#[derive(Default)]
+struct BlockParser {
+    blocks: Vec<MarkdownBlock>,
+    pending: PendingBlock,
+    fence: Option<Fence>,
+    lists: Vec<ListLevel>,
+}
+
+impl BlockParser {
+    fn line(&mut self, line: &str) {
+        let (quote_depth, content) = strip_quote_markers(line);
+
+        if let Some(fence) = self.fence.as_mut() {
+            let trimmed = content.trim();
+            let closes = trimmed.len() >= fence.len
+                && trimmed.chars().all(|ch| ch == fence.ch)
+                && quote_depth >= fence.quote_depth;
+            if closes {
+                self.close_fence();
+            } else {
+                let (indent, _) = leading_indent(content);
+                let strip = indent.min(fence.indent);
+                fence.lines.push(content.chars().skip(strip).collect());
+            }
+            return;
+        }
+
+        let (indent, trimmed) = leading_indent(content);
+        if trimmed.trim().is_empty() {
+            self.flush();
+            return;
+        }
+
+        if let Some((ch, len)) = fence_opening(trimmed) {
+            self.flush();
+            self.fence = Some(Fence {
+                ch,
+                len,
+                indent,
+                quote_depth,
+                lines: Vec::new(),
+            });
+            return;
+        }
+
+        if let Some((level, text)) = atx_heading(trimmed) {
+            self.flush();
+            self.lists.clear();
+            self.pending = PendingBlock {
+                kind: Some(MarkdownBlockKind::Heading(level)),
+                quote_depth,
+                source: text.to_string(),
+                ..PendingBlock::default()
+            };
+            self.flush();
+            return;
+        }
+
+        if is_thematic_break(trimmed) {
+            self.flush();
+            self.lists.clear();
+            return;
+        }
+
+        if let Some((number, text)) = list_item(trimmed) {
+            self.flush();
+            let level = self.enter_list(indent, number);
+            let marker = self.lists[level]
+                .number
+                .map_or_else(|| BULLET.to_string(), |number| format!("{number}."));
+            self.pending = PendingBlock {
+                kind: Some(MarkdownBlockKind::ListItem),
+                marker: Some(marker),
+                list_level: level as u8,
+                quote_depth,
+                source: text.to_string(),
+            };
+            return;
+        }
+
+        // Lazy continuation: unquoted lines continue a quoted paragraph
+        if self.pending.kind.is_some()
+            && (quote_depth == self.pending.quote_depth || quote_depth == 0)
+        {
+            let source = &mut self.pending.source;
+            let hard_break = source.ends_with("  ") || source.ends_with('\\');
+            let kept = source.trim_end_matches([' ', '\\']).len();
+            source.truncate(kept);
+            source.push(if hard_break { '\n' } else { ' ' });
+            source.push_str(trimmed.trim_end_matches(['\r']));
+            return;
+        }
+
+        self.flush();
+        // Indented text after a list item belongs to that item
+        let in_list = indent >= 2 && !self.lists.is_empty();
+        if !in_list {
+            self.lists.clear();
+        }
+        self.pending = PendingBlock {
+            kind: Some(if in_list {
+                MarkdownBlockKind::ListItem
+            } else {
+                MarkdownBlockKind::Paragraph
+            }),
+            list_level: self.lists.len().saturating_sub(1) as u8,
+            quote_depth,
+            source: trimmed.to_string(),
+            ..PendingBlock::default()
+        };
+    }
+
+    /// Records a list item at `indent` and returns its nesting level.
+    fn enter_list(&mut self, indent: usize, number: Option<u32>) -> usize {
+        while self.lists.last().is_some_and(|level| level.indent > indent) {
+            self.lists.pop();
+        }
+        match self.lists.last_mut() {
+            Some(level) if indent < level.indent + 2 => {
+                level.number = match (level.number, number) {
+                    (Some(previous), Some(_)) => Some(previous + 1),
+                    (_, number) => number,
+                };
+            }
+            _ => self.lists.push(ListLevel { indent, number }),
+        }
+        self.lists.len() - 1
+    }
+
+    fn close_fence(&mut self) {
+        let Some(fence) = self.fence.take() else {
+            return;
+        };
+        let text = fence.lines.join("\n").replace('\t', TAB_SPACES);
+        self.blocks.push(MarkdownBlock {
+            kind: MarkdownBlockKind::CodeBlock,
+            marker: None,
+            list_level: 0,
+            quote_depth: fence.quote_depth,
+            spans: vec![MarkdownSpan {
+                text,
+                style: MarkdownSpanStyle {
+                    code: true,
+                    ..MarkdownSpanStyle::default()
+                },
+            }],
+        });
+    }
+
+    fn flush(&mut self) {
+        let pending = std::mem::take(&mut self.pending);
+        let Some(kind) = pending.kind else {
+            return;
+        };
+        self.blocks.push(MarkdownBlock {
+            kind,
+            marker: pending.marker,
+            list_level: pending.list_level,
+            quote_depth: pending.quote_depth,
+            spans: parse_inline(pending.source.trim_end()),
+        });
+    }
+
+    fn finish(mut self) -> Vec<MarkdownBlock> {
+        self.flush();
+        // An unclosed fence runs to the end of the document
+        self.close_fence();
+        self.blocks
+    }
+}
+
+/// Strips up to three spaces and `>` markers; returns the quote depth.
+fn strip_quote_markers(line: &str) -> (u8, &str) {
+    let mut depth = 0u8;
+    let mut rest = line;
+    loop {
+        let (indent, trimmed) = leading_indent(rest);
+        match trimmed.strip_prefix('>') {
+            Some(after) if indent < 4 => {
+                depth = depth.saturating_add(1);
+                rest = after.strip_prefix(' ').unwrap_or(after);
+            }
+            _ => return (depth, rest),
+        }
+    }
+}
+
+/// Width of the leading whitespace (tabs count as four) and the rest.
+fn leading_indent(line: &str) -> (usize, &str) {
+    let mut width = 0;
+    for (index, ch) in line.char_indices() {
+        match ch {
+            ' ' => width += 1,
+            '\t' => width += 4,
+            _ => return (width, &line[index..]),
+        }
+    }
+    (width, "")
+}
+
+fn fence_opening(trimmed: &str) -> Option<(char, usize)> {
+    let ch = trimmed
+        .chars()
+        .next()
+        .filter(|ch| matches!(ch, '`' | '~'))?;
+    let len = trimmed.chars().take_while(|next| *next == ch).count();
+    // Backtick fences cannot have backticks in their info string
+    let info = &trimmed[len..];
+    (len >= 3 && !(ch == '`' && info.contains('`'))).then_some((ch, len))
+}
+
+fn atx_heading(trimmed: &str) -> Option<(u8, &str)> {
+    let level = trimmed.chars().take_while(|ch| *ch == '#').count();
+    if !(1..=6).contains(&level) {
+        return None;
+    }
+    let rest = &trimmed[level..];
+    if !rest.is_empty() && !rest.starts_with([' ', '\t']) {
+        return None;
+    }
+    // Optional closing sequence: "## Title ##"
+    let text = rest.trim();
+    let text = match text.trim_end_matches('#') {
+        stripped if stripped.is_empty() || stripped.ends_with([' ', '\t']) => stripped.trim_end(),
+        _ => text,
+    };
+    Some((level as u8, text))
+}
+
+fn is_thematic_break(trimmed: &str) -> bool {
+    let marks: Vec<char> = trimmed.chars().filter(|ch| !ch.is_whitespace()).collect();
+    marks.len() >= 3
+        && matches!(marks[0], '-' | '*' | '_')
+        && marks.iter().all(|ch| *ch == marks[0])
+}
+
+/// Returns the ordered number (`None` for bullets) and the item text.
+fn list_item(trimmed: &str) -> Option<(Option<u32>, &str)> {
+    let separated = |rest: &str| rest.is_empty() || rest.starts_with([' ', '\t']);
+    if let Some(rest) = trimmed.strip_prefix(['-', '*', '+']) {
+        return separated(rest).then(|| (None, rest.trim_start()));
+    }
+    let digits = trimmed.chars().take_while(char::is_ascii_digit).count();
+    if !(1..=9).contains(&digits) {
+        return None;
+    }
+    let rest = trimmed[digits..].strip_prefix(['.', ')'])?;
+    let number = trimmed[..digits].parse().ok()?;
+    separated(rest).then(|| (Some(number), rest.trim_start()))
+}
+
+/// Parses inline markup into styled spans.
+pub fn parse_inline(source: &str) -> Vec<MarkdownSpan> {
+    let mut spans = Vec::new();
+    parse_inline_into(source, MarkdownSpanStyle::default(), &mut spans);
+    spans
+}
+
+fn push_span(spans: &mut Vec<MarkdownSpan>, text: &str, style: MarkdownSpanStyle) {
+    if text.is_empty() {
+        return;
+    }
+    match spans.last_mut() {
+        Some(last) if last.style == style => last.text.push_str(text),
+        _ => spans.push(MarkdownSpan {
+            text: text.to_string(),
+            style,
+        }),
+    }
+}
+
+fn parse_inline_into(source: &str, base: MarkdownSpanStyle, spans: &mut Vec<MarkdownSpan>) {
+    let mut style = base;
+    let mut index = 0;
+    let mut literal_start = 0;
+    let flush = |spans: &mut Vec<MarkdownSpan>, end: usize, start: usize, style| {
+        push_span(spans, &source[start..end], style);
+    };
+
+    while index < source.len() {
+        let rest = &source[index..];
+        let ch = rest.chars().next().expect("index is a char boundary");
+
+        if ch == '\\' {
+            if let Some(next) = rest[1..].chars().next().filter(char::is_ascii_punctuation) {
+                flush(spans, index, literal_start, style);
+                push_span(spans, &next.to_string(), style);
+                index += 2;
+                literal_start = index;
+                continue;
+            }
+        }
+
+        if ch == '`' {
+            let run = rest.chars().take_while(|next| *next == '`').count();
+            if let Some(content_len) = find_backtick_run(&rest[run..], run) {
+                flush(spans, index, literal_start, style);
+                let content = rest[run..run + content_len].replace('\n', " ");
+                let content = match content.strip_prefix(' ').and_then(|c| c.strip_suffix(' ')) {
+                    Some(inner) if !inner.trim().is_empty() => inner.to_string(),
+                    _ => content,
+                };
+                push_span(
+                    spans,
+                    &content,
+                    MarkdownSpanStyle {
+                        code: true,
+                        ..style
+                    },
+                );
+                index += run * 2 + content_len;
+            } else {
+                // Unmatched backticks are literal text
+                index += run;
+                continue;
+            }
+            literal_start = index;
+            continue;
+        }
+
+        if rest.starts_with("![") {
+            if let Some((_, consumed)) = link_parts(&rest[1..]) {
+                flush(spans, index, literal_start, style);
+                index += 1 + consumed;
+                literal_start = index;
+                continue;
+            }
+        }
+
+        if rest.starts_with("[[") {
+            if let Some(end) = rest.find("]]") {
+                flush(spans, index, literal_start, style);
+                let target = &rest[2..end];
+                let label = target.rsplit('|').next().unwrap_or(target);
+                push_span(spans, label, style);
+                index += end + 2;
+                literal_start = index;
+                continue;
+            }
+        }
+
+        if ch == '[' {
+            if let Some((label, consumed)) = link_parts(rest) {
+                flush(spans, index, literal_start, style);
+                parse_inline_into(label, style, spans);
+                index += consumed;
+                literal_start = index;
+                continue;
+            }
+        }
+
+        if ch == '*' || ch == '_' {
+            let run = rest.chars().take_while(|next| *next == ch).count();
+            let before = source[..index].chars().next_back();
+            let after = rest[run..].chars().next();
+            let intraword = ch == '_'
+                && before.is_some_and(char::is_alphanumeric)
+                && after.is_some_and(char::is_alphanumeric);
+            let can_open = after.is_some_and(|c| !c.is_whitespace()) && !intraword;
+            let can_close = before.is_some_and(|c| !c.is_whitespace()) && !intraword;
+
+            let mut remaining = run;
+            let mut next_style = style;
+            let toggle = |active: &mut bool, width: usize, remaining: &mut usize| {
+                if *remaining < width {
+                    return;
+                }
+                let delimiter = ch.to_string().repeat(width);
+                let closes = *active && can_close;
+                let opens =
+                    !*active && can_open && source[index + run..].contains(delimiter.as_str());
+                if closes || opens {
+                    *active = !*active;
+                    *remaining -= width;
+                }
+            };
+            toggle(&mut next_style.bold, 2, &mut remaining);
+            toggle(&mut next_style.italic, 1, &mut remaining);
+
+            if next_style != style {
+                flush(spans, index, literal_start, style);
+                push_span(spans, &ch.to_string().repeat(remaining), style);
+                style = next_style;
+                index += run;
+                literal_start = index;
+            } else {
+                index += run;
+            }
+            continue;
+        }
+
+        index += ch.len_utf8();
+    }
+    flush(spans, source.len(), literal_start, style);
+}
+
+/// Finds a closing run of exactly `run` backticks; returns the content length.
+fn find_backtick_run(rest: &str, run: usize) -> Option<usize> {
+    let bytes = rest.as_bytes();
+    let mut index = 0;
+    while index < bytes.len() {
+        if bytes[index] == b'`' {
+            let len = bytes[index..].iter().take_while(|b| **b == b'`').count();
+            if len == run {
+                return Some(index);
+            }
+            index += len;
+        } else {
+            index += 1;
+        }
+    }
+    None
+}
+
+/// Parses `[label](destination)`; returns the label and the consumed length.
+fn link_parts(rest: &str) -> Option<(&str, usize)> {
+    let mut depth = 0;
+    let mut label_end = None;
+    for (index, ch) in rest.char_indices() {
+        match ch {
+            '[' => depth += 1,
+            ']' => {
+                depth -= 1;
+                if depth == 0 {
+                    label_end = Some(index);
+                    break;
+                }
+            }
+            _ => {}
+        }
+    }
+    let label_end = label_end?;
+    let destination = rest[label_end + 1..].strip_prefix('(')?;
+    let close = destination.find(')')?;
+    Some((&rest[1..label_end], label_end + 2 + close + 1))
+}
+
+/// Lays out parsed blocks into pages. Runs use [`BODY_FONT`], or [`CODE_FONT`]
+/// for code when `fonts.code` is set.
+pub fn typeset_markdown(
+    markdown: &str,
+    fonts: MarkdownFonts<'_>,
+    page_style: PageStyle,
+) -> Result<Vec<PdfPage>, ShapingError> {
+    let body = page_style.body_box();
+    let body_left_pt = mm_to_pt(body.x_mm);
+    let body_top = (mm_to_pt(body.y_mm) * UNITS_PER_PT) as i32;
+    let body_width = (mm_to_pt(body.width_mm) * UNITS_PER_PT) as i32;
+    let body_height = (mm_to_pt(body.height_mm) * UNITS_PER_PT) as i32;
+
+    let mut lines = Vec::new();
+    for (index, block) in parse_markdown(markdown).iter().enumerate() {
+        lines.extend(layout_block(
+            block,
+            fonts,
+            body_left_pt,
+            body_width,
+            index == 0,
+        )?);
+    }
+
+    let heights: Vec<i32> = lines.iter().map(|line| line.height).collect();
+    let paragraph_end: Vec<bool> = lines.iter().map(|line| line.paragraph_end).collect();
+    let mut pages = Vec::new();
+    for slice in
+        paginate_lines_with_widows_orphans(&heights, body_height, &paragraph_end, ORPHANS, WIDOWS)
+    {
+        let mut page = PdfPage::default();
+        let mut cursor = body_top;
+        for line in &lines[slice.start..slice.end] {
+            let baseline_pt = (cursor + line.baseline) as f32 / UNITS_PER_PT;
+            page.runs.extend(line.runs.iter().cloned().map(|mut run| {
+                run.y_pt = baseline_pt;
+                run
+            }));
+            cursor = cursor.saturating_add(line.height);
+        }
+        pages.push(page);
+    }
+    Ok(pages)
+}
+
+/// Typesets `markdown` and writes it as a PDF embedding the body font and, if
+/// given, the code font.
+pub fn typeset_markdown_to_pdf(
+    markdown: &str,
+    fonts: MarkdownFonts<'_>,
+    page_style: PageStyle,
+) -> Result<Vec<u8>, MarkdownTypesetError> {
+    let pages = typeset_markdown(markdown, fonts, page_style)?;
+    let mut pdf_fonts = vec![PdfFont {
+        name: fonts.body.name,
+        bytes: fonts.body.font.bytes(),
+    }];
+    if let Some(code) = fonts.code {
+        pdf_fonts.push(PdfFont {
+            name: code.name,
+            bytes: code.font.bytes(),
+        });
+    }
+    Ok(write_multi_font_pdf(page_style, &pdf_fonts, &pages)?)
+}
+
+/// One laid-out line with its paragraph spacing folded into `height`.
+#[derive(Clone, Debug)]
+struct FlowLine {
+    height: i32,
+    /// Baseline offset from the top of the line box.
+    baseline: i32,
+    /// Runs with absolute x positions; y is filled in during pagination.
+    runs: Vec<PdfTextRun>,
+    paragraph_end: bool,
+}
+
+fn layout_block(
+    block: &MarkdownBlock,
+    fonts: MarkdownFonts<'_>,
+    body_left_pt: f32,
+    body_width: i32,
+    is_first: bool,
+) -> Result<Vec<FlowLine>, ShapingError> {
+    let (font_size_pt, bold) = match block.kind {
+        MarkdownBlockKind::Heading(level) => (
+            HEADING_FONT_SIZES_PT[(level.max(1) as usize - 1).min(HEADING_FONT_SIZES_PT.len() - 1)],
+            true,
+        ),
+        MarkdownBlockKind::CodeBlock => (CODE_BLOCK_FONT_SIZE_PT, false),
+        MarkdownBlockKind::Paragraph | MarkdownBlockKind::ListItem => (BODY_FONT_SIZE_PT, false),
+    };
+    let font_size = (font_size_pt * UNITS_PER_PT) as i32;
+    let line_height = (font_size_pt * LINE_HEIGHT_RATIO * UNITS_PER_PT) as i32;
+    let space_before = if matches!(block.kind, MarkdownBlockKind::Heading(_)) && !is_first {
+        (HEADING_SPACE_BEFORE_PT * UNITS_PER_PT) as i32
+    } else {
+        0
+    };
+    let space_after = (PARAGRAPH_SPACE_AFTER_PT * UNITS_PER_PT) as i32;
+    let list_depth = match block.kind {
+        MarkdownBlockKind::ListItem => block.list_level as i32 + 1,
+        _ => 0,
+    };
+    let indent =
+        ((list_depth + block.quote_depth as i32) as f32 * LIST_INDENT_PT * UNITS_PER_PT) as i32;
+
+    // Flatten spans into one string with a style per byte range
+    let mut text = String::new();
+    let mut ranges: Vec<(usize, MarkdownSpanStyle)> = Vec::new();
+    if let Some(marker) = &block.marker {
+        text.push_str(marker);
+        text.push(' ');
+        ranges.push((text.len(), MarkdownSpanStyle::default()));
+    }
+    for span in &block.spans {
+        text.push_str(&span.text.replace('\t', TAB_SPACES));
+        ranges.push((text.len(), span.style));
+    }
+    let style_at = |byte: usize| {
+        ranges
+            .iter()
+            .find(|(end, _)| byte < *end)
+            .map_or(MarkdownSpanStyle::default(), |(_, style)| *style)
+    };
+    let code_font = fonts.code.map_or(BODY_FONT, |_| CODE_FONT);
+    let font_for = |style: MarkdownSpanStyle| {
+        if style.code {
+            code_font
+        } else {
+            BODY_FONT
+        }
+    };
+
+    let metrics = fonts.body.font.metrics();
+    let scale = font_size_pt / metrics.units_per_em.max(1) as f32;
+    let ascent = metrics.ascent as f32 * scale * UNITS_PER_PT;
+    let descent = metrics.descent as f32 * scale * UNITS_PER_PT;
+    let baseline_in_line = ((line_height as f32 - (ascent - descent)) / 2.0 + ascent) as i32;
+
+    let mut segments = Vec::new();
+    let mut offset = 0;
+    for segment in text.split('\n') {
+        segments.push((offset, segment));
+        offset += segment.len() + 1;
+    }
+    let segment_count = segments.len();
+
+    let mut laid = Vec::new();
+    for (segment_index, (segment_offset, segment)) in segments.into_iter().enumerate() {
+        let first_segment = segment_index == 0;
+        let last_segment = segment_index + 1 == segment_count;
+        let segment_space_before = if first_segment { space_before } else { 0 };
+        let segment_space_after = if last_segment { space_after } else { 0 };
+
+        // Shape each same-font stretch separately; clusters stay segment offsets
+        let mut glyphs: Vec<Glyph> = Vec::new();
+        let mut glyph_fonts: Vec<usize> = Vec::new();
+        let mut stretch_start = 0;
+        while stretch_start < segment.len() {
+            let font_index = font_for(style_at(segment_offset + stretch_start));
+            let mut stretch_end = stretch_start;
+            while stretch_end < segment.len()
+                && font_for(style_at(segment_offset + stretch_end)) == font_index
+            {
+                stretch_end += segment[stretch_end..]
+                    .chars()
+                    .next()
+                    .map_or(1, char::len_utf8);
+            }
+            let font = match (font_index, fonts.code) {
+                (CODE_FONT, Some(code)) => code.font,
+                _ => fonts.body.font,
+            };
+            let run = shape_mixed_text(font, &segment[stretch_start..stretch_end])?;
+            for mut glyph in scale_glyphs_for_font_size(&run, Some(font_size)) {
+                glyph.cluster += stretch_start as u32;
+                glyphs.push(glyph);
+                glyph_fonts.push(font_index);
+            }
+            stretch_start = stretch_end;
+        }
+
+        let positioned = layout_shaped_paragraph(
+            segment,
+            &glyphs,
+            TextLayoutOptions {
+                max_width: (body_width - indent).max(1),
+                line_height,
+                font_size: None,
+                align: ParagraphAlign::Left,
+                first_line_indent: 0,
+                space_before: segment_space_before,
+                space_after: segment_space_after,
+            },
+        );
+        if positioned.is_empty() {
+            laid.push(FlowLine {
+                height: line_height + segment_space_before + segment_space_after,
+                baseline: segment_space_before + baseline_in_line,
+                runs: Vec::new(),
+                paragraph_end: last_segment,
+            });
+            continue;
+        }
+
+        let cluster_ends = cluster_end_bytes(segment, &glyphs);
+        let line_count = positioned.len();
+        for (line_index, line) in positioned.into_iter().enumerate() {
+            let mut runs: Vec<PdfTextRun> = Vec::new();
+            let mut x_pt = body_left_pt + (indent + line.x_offset) as f32 / UNITS_PER_PT;
+            for index in line.start..line.end.min(glyphs.len()) {
+                let glyph = glyphs[index];
+                let cluster = glyph.cluster as usize;
+                let first_in_cluster = index == 0 || glyphs[index - 1].cluster != glyph.cluster;
+                let cluster_text = if first_in_cluster {
+                    segment
+                        .get(cluster..cluster_ends[index])
+                        .unwrap_or_default()
+                        .to_string()
+                } else {
+                    String::new()
+                };
+                let advance_pt = glyph.x_advance as f32 / UNITS_PER_PT;
+                let style = style_at(segment_offset + cluster);
+                let font = glyph_fonts[index];
+                let bold = bold || style.bold;
+                let pdf_glyph = PdfGlyph {
+                    id: glyph.id as u16,
+                    advance_pt,
+                    text: cluster_text,
+                };
+                match runs.last_mut() {
+                    Some(run)
+                        if run.font == font && run.bold == bold && run.italic == style.italic =>
+                    {
+                        run.glyphs.push(pdf_glyph)
+                    }
+                    _ => runs.push(PdfTextRun {
+                        x_pt,
+                        y_pt: 0.0,
+                        font_size_pt,
+                        font,
+                        bold,
+                        italic: style.italic,
+                        glyphs: vec![pdf_glyph],
+                    }),
+                }
+                x_pt += advance_pt;
+            }
+
+            laid.push(FlowLine {
+                height: line_height + line.space_before + line.space_after,
+                baseline: line.space_before + baseline_in_line,
+                runs,
+                paragraph_end: last_segment && line_index + 1 == line_count,
+            });
+        }
+    }
+    Ok(laid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::typesetting::{FontManager, PageMargins, PageSize};
+    use std::path::PathBuf;
+
+    fn fixture_font() -> FontData {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests")
+            .join("fixtures")
+            .join("katex-main-regular.ttf");
+        FontManager::new()
+            .load_from_path(path)
+            .expect("expected valid font load")
+    }
+
+    fn span(text: &str, bold: bool, italic: bool, code: bool) -> MarkdownSpan {
+        MarkdownSpan {
+            text: text.to_string(),
+            style: MarkdownSpanStyle { bold, italic, code },
+        }
+    }
+
+    fn a4() -> PageStyle {
+        PageStyle {
+            size: PageSize::A4,
+            margins: PageMargins {
+                top_mm: 25.0,
+                bottom_mm: 25.0,
+                left_mm: 25.0,
+                right_mm: 25.0,
+            },
+            header_height_mm: 0.0,
+            footer_height_mm: 0.0,
+        }
+    }
+
+    #[test]
+    fn parses_inline_emphasis_code_and_links() {
+        assert_eq!(
+            parse_inline(
+                "a **bold** and *it* with `co*de` [link](http://x) [[Note|alias]] snake_case"
+            ),
+            vec![
+                span("a ", false, false, false),
+                span("bold", true, false, false),
+                span(" and ", false, false, false),
+                span("it", false, true, false),
+                span(" with ", false, false, false),
+                span("co*de", false, false, true),
+                span(" link alias snake_case", false, false, false),
+            ]
+        );
+        assert_eq!(
+            parse_inline("***both*** \\*lit\\* 2 * 3"),
+            vec![
+                span("both", true, true, false),
+                span(" *lit* 2 * 3", false, false, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_block_structure() {
+        let markdown = "---\ntitle: x\n---\n# Title #\n\nFirst line\nsecond line  \nbroken\n\n- one\n- two\n  - nested\n\n3. three\n1. four\n\n> quoted\nlazy\n\n```rust\nlet x = 1;\n\n  indented\n```\n***\nafter";
+        let blocks = parse_markdown(markdown);
+        let summary: Vec<_> = blocks
+            .iter()
+            .map(|block| {
+                (
+                    block.kind,
+                    block.marker.clone(),
+                    block.list_level,
+                    block.quote_depth,
+                    block.text(),
+                )
+            })
+            .collect();
+
+        use MarkdownBlockKind::*;
+        assert_eq!(
+            summary,
+            vec![
+                (Heading(1), None, 0, 0, "Title".to_string()),
+                (
+                    Paragraph,
+                    None,
+                    0,
+                    0,
+                    "First line second line\nbroken".to_string()
+                ),
+                (ListItem, Some("•".to_string()), 0, 0, "one".to_string()),
+                (ListItem, Some("•".to_string()), 0, 0, "two".to_string()),
+                (ListItem, Some("•".to_string()), 1, 0, "nested".to_string()),
+                (ListItem, Some("3.".to_string()), 0, 0, "three".to_string()),
+                (ListItem, Some("4.".to_string()), 0, 0, "four".to_string()),
+                (Paragraph, None, 0, 1, "quoted lazy".to_string()),
+                (
+                    CodeBlock,
+                    None,
+                    0,
+                    0,
+                    "let x = 1;\n\n  indented".to_string()
+                ),
+                (Paragraph, None, 0, 0, "after".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn typesets_headings_lists_and_code_with_their_fonts() {
+        let body = fixture_font();
+        let code = fixture_font();
+        let fonts = MarkdownFonts {
+            body: NamedFont {
+                name: "Body",
+                font: &body,
+            },
+            code: Some(NamedFont {
+                name: "Mono",
+                font: &code,
+            }),
+        };
+        let markdown = "# Title\n\nSome `code` here\n\n- item\n  - nested\n\n> quote";
+
+        let pages = typeset_markdown(markdown, fonts, a4()).unwrap();
+
+        assert_eq!(pages.len(), 1);
+        let runs = &pages[0].runs;
+        let left = mm_to_pt(25.0);
+        let summary: Vec<_> = runs
+            .iter()
+            .map(|run| {
+                let text: String = run.glyphs.iter().map(|g| g.text.as_str()).collect();
+                (text, run.font, run.font_size_pt, run.bold)
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("Title".to_string(), BODY_FONT, 20.0, true),
+                ("Some ".to_string(), BODY_FONT, 11.0, false),
+                ("code".to_string(), CODE_FONT, 11.0, false),
+                (" here".to_string(), BODY_FONT, 11.0, false),
+                ("• item".to_string(), BODY_FONT, 11.0, false),
+                ("• nested".to_string(), BODY_FONT, 11.0, false),
+                ("quote".to_string(), BODY_FONT, 11.0, false),
+            ]
+        );
+        assert!((runs[0].x_pt - left).abs() < 0.01);
+        assert!((runs[4].x_pt - (left + LIST_INDENT_PT)).abs() < 0.01);
+        assert!((runs[5].x_pt - (left + 2.0 * LIST_INDENT_PT)).abs() < 0.01);
+        assert!((runs[6].x_pt - (left + LIST_INDENT_PT)).abs() < 0.01);
+        assert!(runs.windows(2).all(|pair| pair[0].y_pt <= pair[1].y_pt));
+
+        // Same input, same bytes
+        let first = typeset_markdown_to_pdf(markdown, fonts, a4()).unwrap();
+        let second = typeset_markdown_to_pdf(markdown, fonts, a4()).unwrap();
+        assert_eq!(first, second);
+        let text = String::from_utf8_lossy(&first);
+        assert!(text.contains("/Font << /F1 3 0 R /F2 8 0 R >>"));
+        assert!(text.contains("/F2 11.00 Tf"));
+    }
+}
@@ -1,26 +1,45 @@
+pub mod docx_package;
+pub mod docx_render;
 pub mod font_manager;
 pub mod line_break;
+pub mod markdown_flow;
 pub mod page_model;
 pub mod pagination;
 pub mod paragraph_layout;
 pub mod pdf_export;
+pub mod pdf_image;
 pub mod preview_pipeline;
 pub mod preview_viewport;
 pub mod shaping;
 pub mod text_layout;
 
 #[allow(unused_imports)]
-pub use font_manager::{FontData, FontError, FontManager, FontMapping, FontMetrics, ScriptKind};
+pub use docx_package::{parse_docx, DocxDocument, DocxPackageError};
+#[allow(unused_imports)]
+pub use docx_render::{render_docx_builtin, render_docx_document, DocxRenderError};
+#[allow(unused_imports)]
+pub use font_manager::{
+    find_installed_fonts, system_font_dirs, FontData, FontError, FontManager, FontMapping,
+    FontMetrics, ScriptKind, MONOSPACE_FONT_CANDIDATES,
+};
 #[allow(unused_imports)]
 pub use line_break::{break_glyph_run, BreakKind, LineBreak};
 #[allow(unused_imports)]
+pub use markdown_flow::{
+    parse_markdown, typeset_markdown, typeset_markdown_to_pdf, MarkdownBlock, MarkdownFonts,
+    MarkdownTypesetError, NamedFont,
+};
+#[allow(unused_imports)]
 pub use page_model::{PageBox, PageMargins, PageSize, PageStyle};
 #[allow(unused_imports)]
 pub use pagination::{paginate_flow, paginate_lines_with_widows_orphans, PageSlice};
 #[allow(unused_imports)]
 pub use paragraph_layout::{layout_paragraph, ParagraphAlign, PositionedLine};
 #[allow(unused_imports)]
-pub use pdf_export::{write_empty_pdf, write_pdf_with_embedded_font, PdfExportError};
+pub use pdf_export::{
+    write_empty_pdf, write_multi_font_pdf, write_pdf_with_embedded_font, write_text_pdf,
+    PdfExportError, PdfFont, PdfGlyph, PdfImage, PdfPage, PdfTextRun,
+};
 #[allow(unused_imports)]
 pub use preview_pipeline::{
     build_preview_pages, build_preview_pages_for_style, preview_page_metrics, PreviewLine,
@@ -31,4 +50,4 @@ pub use preview_viewport::{PreviewPageSize, PreviewViewport};
 #[allow(unused_imports)]
 pub use shaping::{shape_mixed_text, shape_text, Glyph, GlyphRun, ShapingError};
 #[allow(unused_imports)]
-pub use text_layout::{layout_text_paragraph, TextLayoutOptions};
+pub use text_layout::{layout_shaped_paragraph, layout_text_paragraph, TextLayoutOptions};
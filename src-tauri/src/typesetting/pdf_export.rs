@@ -1,4 +1,7 @@
+use super::pdf_image::encode_image;
 use super::PageStyle;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
 use thiserror::Error;
 
 #[derive(Debug, Error, PartialEq)]
@@ -7,6 +10,10 @@ pub enum PdfExportError {
     InvalidPageSize,
     #[error("invalid font data")]
     InvalidFontData,
+    #[error("invalid image data")]
+    InvalidImageData,
+    #[error("unsupported image: {0}")]
+    UnsupportedImage(String),
 }
 
 pub fn write_empty_pdf(page_style: PageStyle) -> Result<Vec<u8>, PdfExportError> {
@@ -29,18 +36,7 @@ pub fn write_empty_pdf(page_style: PageStyle) -> Result<Vec<u8>, PdfExportError>
     let contents = "<< /Length 0 >>\nstream\n\nendstream";
     offsets[4] = write_object(&mut output, 4, contents);
 
-    let xref_offset = output.len();
-    output.push_str("xref\n");
-    output.push_str(&format!("0 {}\n", offsets.len()));
-    output.push_str("0000000000 65535 f \n");
-    for offset in offsets.iter().skip(1) {
-        output.push_str(&format!("{:010} 00000 n \n", offset));
-    }
-    output.push_str("trailer\n");
-    output.push_str(&format!("<< /Size {} /Root 1 0 R >>\n", offsets.len()));
-    output.push_str("startxref\n");
-    output.push_str(&format!("{}\n", xref_offset));
-    output.push_str("%%EOF\n");
+    write_xref_and_trailer(&mut output, &offsets);
 
     Ok(output.into_bytes())
 }
@@ -114,6 +110,392 @@ pub fn write_pdf_with_embedded_font(
     );
     offsets[7] = write_object(&mut output, 7, &font_body);
 
+    write_xref_and_trailer(&mut output, &offsets);
+
+    Ok(output.into_bytes())
+}
+
+/// A shaped glyph placed in a [`PdfTextRun`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct PdfGlyph {
+    pub id: u16,
+    /// Horizontal advance in points, including kerning and justification.
+    pub advance_pt: f32,
+    /// Source text of the glyph's cluster (empty for continuation glyphs).
+    pub text: String,
+}
+
+/// Glyphs sharing one baseline and style.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PdfTextRun {
+    /// Baseline origin in points, measured from the top-left page corner.
+    pub x_pt: f32,
+    pub y_pt: f32,
+    pub font_size_pt: f32,
+    /// Index into the fonts passed to [`write_multi_font_pdf`].
+    pub font: usize,
+    pub bold: bool,
+    pub italic: bool,
+    pub glyphs: Vec<PdfGlyph>,
+}
+
+/// A PNG or JPEG image drawn into a box on the page.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PdfImage {
+    /// Encoded PNG or JPEG file contents.
+    pub bytes: Vec<u8>,
+    /// Top-left corner of the image box in points, measured from the top-left
+    /// page corner.
+    pub x_pt: f32,
+    pub y_pt: f32,
+    pub width_pt: f32,
+    pub height_pt: f32,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PdfPage {
+    pub runs: Vec<PdfTextRun>,
+    pub images: Vec<PdfImage>,
+}
+
+/// Horizontal skew applied to italic runs (synthetic oblique).
+const ITALIC_SKEW: f32 = 0.21;
+/// Stroke width applied to bold runs, relative to the font size (synthetic bold).
+const BOLD_STROKE_RATIO: f32 = 0.025;
+const TO_UNICODE_CHUNK: usize = 100;
+/// Object id of the first font; catalog and page tree come before it.
+const FIRST_FONT_ID: usize = 3;
+const OBJECTS_PER_FONT: usize = 5;
+
+/// A TrueType font embedded by [`write_multi_font_pdf`].
+#[derive(Clone, Copy, Debug)]
+pub struct PdfFont<'a> {
+    pub name: &'a str,
+    pub bytes: &'a [u8],
+}
+
+/// Writes a multi-page PDF whose text is drawn with a single embedded TrueType
+/// font. Glyphs are addressed by glyph id (Identity-H), so shaped output from
+/// `shape_mixed_text` can be placed directly. Bold and italic are synthesized.
+/// Page images are embedded as image XObjects and drawn beneath the text.
+pub fn write_text_pdf(
+    page_style: PageStyle,
+    font_name: &str,
+    font_bytes: &[u8],
+    pages: &[PdfPage],
+) -> Result<Vec<u8>, PdfExportError> {
+    write_multi_font_pdf(
+        page_style,
+        &[PdfFont {
+            name: font_name,
+            bytes: font_bytes,
+        }],
+        pages,
+    )
+}
+
+/// Like [`write_text_pdf`], with each run drawn in `fonts[run.font]`.
+pub fn write_multi_font_pdf(
+    page_style: PageStyle,
+    fonts: &[PdfFont<'_>],
+    pages: &[PdfPage],
+) -> Result<Vec<u8>, PdfExportError> {
+    let (width_pt, height_pt) = pdf_page_size_points(page_style)?;
+
+    if fonts.is_empty() || fonts.iter().any(|font| font.bytes.is_empty()) {
+        return Err(PdfExportError::InvalidFontData);
+    }
+
+    let faces = fonts
+        .iter()
+        .map(|font| ttf_parser::Face::parse(font.bytes, 0))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| PdfExportError::InvalidFontData)?;
+
+    let mut widths: Vec<BTreeMap<u16, i32>> = vec![BTreeMap::new(); fonts.len()];
+    let mut unicode: Vec<BTreeMap<u16, String>> = vec![BTreeMap::new(); fonts.len()];
+    for run in pages.iter().flat_map(|page| page.runs.iter()) {
+        let face = faces.get(run.font).ok_or(PdfExportError::InvalidFontData)?;
+        for glyph in &run.glyphs {
+            widths[run.font].entry(glyph.id).or_insert_with(|| {
+                let advance = face
+                    .glyph_hor_advance(ttf_parser::GlyphId(glyph.id))
+                    .unwrap_or(0);
+                to_pdf_units(face, advance as i32)
+            });
+            if !glyph.text.is_empty() {
+                unicode[run.font]
+                    .entry(glyph.id)
+                    .or_insert_with(|| glyph.text.clone());
+            }
+        }
+    }
+
+    let images = pages
+        .iter()
+        .flat_map(|page| page.images.iter())
+        .map(|image| encode_image(&image.bytes))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let empty_page = [PdfPage::default()];
+    let pages = if pages.is_empty() {
+        &empty_page[..]
+    } else {
+        pages
+    };
+
+    let mut output = String::new();
+    output.push_str("%PDF-1.7\n");
+
+    let first_page_id = FIRST_FONT_ID + fonts.len() * OBJECTS_PER_FONT;
+    let first_image_id = first_page_id + pages.len() * 2;
+    let mut offsets = vec![0usize; first_image_id + images.len()];
+
+    offsets[1] = write_object(&mut output, 1, "<< /Type /Catalog /Pages 2 0 R >>");
+    let kids = (0..pages.len())
+        .map(|index| format!("{} 0 R", first_page_id + index * 2))
+        .collect::<Vec<_>>()
+        .join(" ");
+    offsets[2] = write_object(
+        &mut output,
+        2,
+        &format!("<< /Type /Pages /Kids [{}] /Count {} >>", kids, pages.len()),
+    );
+
+    let mut font_resources = String::new();
+    for (index, font) in fonts.iter().enumerate() {
+        let base_id = FIRST_FONT_ID + index * OBJECTS_PER_FONT;
+        write_font_objects(
+            &mut output,
+            &mut offsets,
+            base_id,
+            font,
+            &faces[index],
+            &widths[index],
+            &unicode[index],
+        );
+        write!(&mut font_resources, "/F{} {} 0 R ", index + 1, base_id).expect("resource write");
+    }
+
+    let mut next_image = 0;
+    for (index, page) in pages.iter().enumerate() {
+        let page_id = first_page_id + index * 2;
+        let contents_id = page_id + 1;
+        let mut xobjects = String::new();
+        for image_index in next_image..next_image + page.images.len() {
+            write!(
+                &mut xobjects,
+                "/Im{} {} 0 R ",
+                image_index,
+                first_image_id + image_index
+            )
+            .expect("resource write");
+        }
+        let xobject_resources = if xobjects.is_empty() {
+            String::new()
+        } else {
+            format!(" /XObject << {}>>", xobjects)
+        };
+        let page_body = format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {:.2} {:.2}] \
+/Resources << /Font << {}>>{} >> /Contents {} 0 R >>",
+            width_pt, height_pt, font_resources, xobject_resources, contents_id
+        );
+        offsets[page_id] = write_object(&mut output, page_id, &page_body);
+
+        let contents = page_content_stream(page, next_image, height_pt, &widths);
+        next_image += page.images.len();
+        offsets[contents_id] = write_object(
+            &mut output,
+            contents_id,
+            &format!(
+                "<< /Length {} >>\nstream\n{}endstream",
+                contents.len(),
+                contents
+            ),
+        );
+    }
+
+    for (index, image) in images.iter().enumerate() {
+        let image_id = first_image_id + index;
+        let data = font_file_stream_data(&image.data);
+        let body = format!(
+            "<< /Type /XObject /Subtype /Image /Width {} /Height {} /ColorSpace /{} \
+/BitsPerComponent 8 /Filter [/ASCIIHexDecode /{}] /Length {} >>\nstream\n{}endstream",
+            image.width,
+            image.height,
+            image.color_space,
+            image.filter,
+            data.len(),
+            data
+        );
+        offsets[image_id] = write_object(&mut output, image_id, &body);
+    }
+
+    write_xref_and_trailer(&mut output, &offsets);
+
+    Ok(output.into_bytes())
+}
+
+fn to_pdf_units(face: &ttf_parser::Face<'_>, value: i32) -> i32 {
+    let units_per_em = face.units_per_em().max(1) as f32;
+    (value as f32 * 1000.0 / units_per_em).round() as i32
+}
+
+/// Writes the Type0 font, CIDFont, descriptor, font file and ToUnicode
+/// objects of one font as `base_id..base_id + OBJECTS_PER_FONT`.
+fn write_font_objects(
+    output: &mut String,
+    offsets: &mut [usize],
+    base_id: usize,
+    font: &PdfFont<'_>,
+    face: &ttf_parser::Face<'_>,
+    widths: &BTreeMap<u16, i32>,
+    unicode: &BTreeMap<u16, String>,
+) {
+    let subset_name = subset_font_name(font.name, font.bytes);
+    let bbox = face.global_bounding_box();
+    let (cid_id, descriptor_id, file_id, to_unicode_id) =
+        (base_id + 1, base_id + 2, base_id + 3, base_id + 4);
+
+    let type0 = format!(
+        "<< /Type /Font /Subtype /Type0 /BaseFont /{name} /Encoding /Identity-H \
+/DescendantFonts [{cid_id} 0 R] /ToUnicode {to_unicode_id} 0 R >>",
+        name = subset_name
+    );
+    offsets[base_id] = write_object(output, base_id, &type0);
+
+    let mut width_array = String::new();
+    for (id, width) in widths {
+        write!(&mut width_array, "{} [{}] ", id, width).expect("width write");
+    }
+    let cid_font = format!(
+        "<< /Type /Font /Subtype /CIDFontType2 /BaseFont /{name} \
+/CIDSystemInfo << /Registry (Adobe) /Ordering (Identity) /Supplement 0 >> \
+/FontDescriptor {descriptor_id} 0 R /DW 1000 /W [{widths}] /CIDToGIDMap /Identity >>",
+        name = subset_name,
+        widths = width_array.trim_end()
+    );
+    offsets[cid_id] = write_object(output, cid_id, &cid_font);
+
+    let font_descriptor = format!(
+        "<< /Type /FontDescriptor /FontName /{name} /Flags 32 \
+/FontBBox [{x_min} {y_min} {x_max} {y_max}] /ItalicAngle 0 /Ascent {ascent} \
+/Descent {descent} /CapHeight {ascent} /StemV 80 /FontFile2 {file_id} 0 R >>",
+        name = subset_name,
+        x_min = to_pdf_units(face, bbox.x_min as i32),
+        y_min = to_pdf_units(face, bbox.y_min as i32),
+        x_max = to_pdf_units(face, bbox.x_max as i32),
+        y_max = to_pdf_units(face, bbox.y_max as i32),
+        ascent = to_pdf_units(face, face.ascender() as i32),
+        descent = to_pdf_units(face, face.descender() as i32)
+    );
+    offsets[descriptor_id] = write_object(output, descriptor_id, &font_descriptor);
+
+    let font_stream_data = font_file_stream_data(font.bytes);
+    let font_file = format!(
+        "<< /Length {} /Filter /ASCIIHexDecode /Length1 {} >>\nstream\n{}endstream",
+        font_stream_data.len(),
+        font.bytes.len(),
+        font_stream_data
+    );
+    offsets[file_id] = write_object(output, file_id, &font_file);
+
+    let to_unicode = to_unicode_cmap(unicode);
+    offsets[to_unicode_id] = write_object(
+        output,
+        to_unicode_id,
+        &format!(
+            "<< /Length {} >>\nstream\n{}endstream",
+            to_unicode.len(),
+            to_unicode
+        ),
+    );
+}
+
+fn page_content_stream(
+    page: &PdfPage,
+    first_image: usize,
+    height_pt: f32,
+    widths: &[BTreeMap<u16, i32>],
+) -> String {
+    let mut stream = String::new();
+    for (offset, image) in page.images.iter().enumerate() {
+        // The unit square is scaled to the image box; PDF y grows upwards
+        writeln!(
+            &mut stream,
+            "q {:.2} 0 0 {:.2} {:.2} {:.2} cm /Im{} Do Q",
+            image.width_pt,
+            image.height_pt,
+            image.x_pt,
+            height_pt - image.y_pt - image.height_pt,
+            first_image + offset
+        )
+        .expect("content write");
+    }
+    for run in &page.runs {
+        if run.glyphs.is_empty() || !run.font_size_pt.is_finite() || run.font_size_pt <= 0.0 {
+            continue;
+        }
+        let size = run.font_size_pt;
+        let skew = if run.italic { ITALIC_SKEW } else { 0.0 };
+        stream.push_str("BT\n");
+        writeln!(&mut stream, "/F{} {:.2} Tf", run.font + 1, size).expect("content write");
+        if run.bold {
+            writeln!(&mut stream, "2 Tr {:.3} w", size * BOLD_STROKE_RATIO).expect("content write");
+        } else {
+            stream.push_str("0 Tr\n");
+        }
+        writeln!(
+            &mut stream,
+            "1 0 {:.2} 1 {:.2} {:.2} Tm",
+            skew,
+            run.x_pt,
+            height_pt - run.y_pt
+        )
+        .expect("content write");
+
+        // TJ offsets are subtracted from the nominal advance, in 1/1000 em
+        let mut array = String::from("[<");
+        for glyph in &run.glyphs {
+            write!(&mut array, "{:04X}", glyph.id).expect("content write");
+            let nominal = widths[run.font].get(&glyph.id).copied().unwrap_or(0);
+            let adjust = nominal - (glyph.advance_pt * 1000.0 / size).round() as i32;
+            if adjust != 0 {
+                write!(&mut array, "> {} <", adjust).expect("content write");
+            }
+        }
+        array.push_str(">] TJ\n");
+        stream.push_str(&array.replace("<>", ""));
+        stream.push_str("ET\n");
+    }
+    stream
+}
+
+fn to_unicode_cmap(unicode: &BTreeMap<u16, String>) -> String {
+    let mut cmap = String::from(
+        "/CIDInit /ProcSet findresource begin\n12 dict begin\nbegincmap\n\
+/CIDSystemInfo << /Registry (Adobe) /Ordering (UCS) /Supplement 0 >> def\n\
+/CMapName /Adobe-Identity-UCS def\n/CMapType 2 def\n\
+1 begincodespacerange\n<0000> <FFFF>\nendcodespacerange\n",
+    );
+    let entries: Vec<_> = unicode.iter().collect();
+    for chunk in entries.chunks(TO_UNICODE_CHUNK) {
+        writeln!(&mut cmap, "{} beginbfchar", chunk.len()).expect("cmap write");
+        for (id, text) in chunk {
+            let utf16: String = text
+                .encode_utf16()
+                .map(|unit| format!("{:04X}", unit))
+                .collect();
+            writeln!(&mut cmap, "<{:04X}> <{}>", id, utf16).expect("cmap write");
+        }
+        cmap.push_str("endbfchar\n");
+    }
+    cmap.push_str("endcmap\nCMapName currentdict /CMap defineresource pop\nend\nend\n");
+    cmap
+}
+
+fn write_xref_and_trailer(output: &mut String, offsets: &[usize]) {
     let xref_offset = output.len();
     output.push_str("xref\n");
     output.push_str(&format!("0 {}\n", offsets.len()));
@@ -126,8 +508,6 @@ pub fn write_pdf_with_embedded_font(
     output.push_str("startxref\n");
     output.push_str(&format!("{}\n", xref_offset));
     output.push_str("%%EOF\n");
-
-    Ok(output.into_bytes())
 }
 
 fn write_object(output: &mut String, id: usize, body: &str) -> usize {
@@ -214,7 +594,6 @@ fn font_file_stream_data(font_bytes: &[u8]) -> String {
 fn encode_hex(bytes: &[u8]) -> String {
     let mut output = String::with_capacity(bytes.len() * 2);
     for byte in bytes {
-        use std::fmt::Write;
         write!(&mut output, "{:02X}", byte).expect("hex write");
     }
     output
@@ -323,6 +702,89 @@ mod tests {
         assert!(text.contains("+KaTeX-Main-Regular"));
     }
 
+    #[test]
+    fn text_pdf_writes_one_page_object_per_page() {
+        let font_bytes = fixture_font_bytes();
+        let glyph = |id: u16, advance_pt: f32, text: &str| PdfGlyph {
+            id,
+            advance_pt,
+            text: text.to_string(),
+        };
+        let run = PdfTextRun {
+            x_pt: 72.0,
+            y_pt: 100.0,
+            font_size_pt: 10.0,
+            font: 0,
+            bold: false,
+            italic: true,
+            glyphs: vec![glyph(36, 7.5, "A"), glyph(57, 1.0, "V")],
+        };
+        let pages = vec![
+            PdfPage {
+                runs: vec![run],
+                ..PdfPage::default()
+            },
+            PdfPage::default(),
+        ];
+
+        let pdf = write_text_pdf(
+            sample_style(PageSize::A4),
+            "KaTeX Main",
+            &font_bytes,
+            &pages,
+        )
+        .unwrap();
+        let text = String::from_utf8(pdf).unwrap();
+
+        assert!(text.contains("/Kids [8 0 R 10 0 R] /Count 2"));
+        assert!(text.contains("/Encoding /Identity-H"));
+        assert!(text.contains("/CIDToGIDMap /Identity"));
+        assert!(text.contains("<0024> <0041>"));
+        assert!(text.contains("1 0 0.21 1 72.00 741.89 Tm"));
+        // The second glyph's shaped advance differs from its nominal width
+        assert!(text.contains("[<00240039> "));
+        assert!(text.ends_with("%%EOF\n"));
+    }
+
+    #[test]
+    fn text_pdf_embeds_page_images_at_their_box_size() {
+        let font_bytes = fixture_font_bytes();
+        let png =
+            crate::typesetting::pdf_image::tests::build_png(2, 1, 2, &[0, 255, 0, 0, 0, 0, 255]);
+        let pages = vec![PdfPage {
+            images: vec![PdfImage {
+                bytes: png,
+                x_pt: 72.0,
+                y_pt: 100.0,
+                width_pt: 144.0,
+                height_pt: 72.0,
+            }],
+            ..PdfPage::default()
+        }];
+
+        let pdf = write_text_pdf(
+            sample_style(PageSize::A4),
+            "KaTeX Main",
+            &font_bytes,
+            &pages,
+        )
+        .unwrap();
+        let text = String::from_utf8(pdf).unwrap();
+
+        assert!(text.contains("/Resources << /Font << /F1 3 0 R >> /XObject << /Im0 10 0 R >> >>"));
+        assert!(text.contains("10 0 obj\n<< /Type /XObject /Subtype /Image /Width 2 /Height 1"));
+        assert!(text.contains("/Filter [/ASCIIHexDecode /FlateDecode]"));
+        // 144x72pt box whose top edge sits 100pt below the top of an A4 page
+        assert!(text.contains("q 144.00 0 0 72.00 72.00 669.89 cm /Im0 Do Q"));
+
+        // Every xref entry points at its object header
+        let xref = text.rfind("xref\n").unwrap();
+        for (id, line) in text[xref..].lines().skip(3).take(10).enumerate() {
+            let offset: usize = line[..10].parse().unwrap();
+            assert!(text[offset..].starts_with(&format!("{} 0 obj", id + 1)));
+        }
+    }
+
     #[test]
     fn embedded_font_errors_on_invalid_font_data() {
         let err =
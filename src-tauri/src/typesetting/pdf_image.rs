@@ -0,0 +1,447 @@
+use super::PdfExportError;
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use std::io::{Read, Write};
+
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+
+/// An image ready to be written as a PDF image XObject.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EncodedImage {
+    pub width: u32,
+    pub height: u32,
+    pub color_space: &'static str,
+    /// PDF filter the data is encoded with (before the ASCII hex wrapper).
+    pub filter: &'static str,
+    pub data: Vec<u8>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ImageFormat {
+    Png,
+    Jpeg,
+}
+
+pub fn detect_image_format(bytes: &[u8]) -> Option<ImageFormat> {
+    if bytes.starts_with(&PNG_SIGNATURE) {
+        Some(ImageFormat::Png)
+    } else if bytes.starts_with(&[0xFF, 0xD8]) {
+        Some(ImageFormat::Jpeg)
+    } else {
+        None
+    }
+}
+
+/// Converts PNG or JPEG bytes into PDF image data. JPEG is passed through
+/// (DCTDecode); PNG is decoded, alpha is flattened onto white, and the pixels
+/// are re-compressed (FlateDecode).
+pub fn encode_image(bytes: &[u8]) -> Result<EncodedImage, PdfExportError> {
+    match detect_image_format(bytes) {
+        Some(ImageFormat::Png) => encode_png(bytes),
+        Some(ImageFormat::Jpeg) => encode_jpeg(bytes),
+        None => Err(PdfExportError::UnsupportedImage(
+            "only PNG and JPEG images are supported".into(),
+        )),
+    }
+}
+
+fn encode_jpeg(bytes: &[u8]) -> Result<EncodedImage, PdfExportError> {
+    let mut offset = 2;
+    while offset + 4 <= bytes.len() {
+        if bytes[offset] != 0xFF {
+            return Err(PdfExportError::InvalidImageData);
+        }
+        let marker = bytes[offset + 1];
+        // Fill bytes and standalone markers carry no length
+        if marker == 0xFF {
+            offset += 1;
+            continue;
+        }
+        if marker == 0x01 || (0xD0..=0xD7).contains(&marker) {
+            offset += 2;
+            continue;
+        }
+        let length = u16::from_be_bytes([bytes[offset + 2], bytes[offset + 3]]) as usize;
+        let segment = bytes
+            .get(offset + 4..offset + 2 + length)
+            .ok_or(PdfExportError::InvalidImageData)?;
+        match marker {
+            // Baseline, extended sequential and progressive Huffman frames
+            0xC0..=0xC2 => {
+                if segment.len() < 6 {
+                    return Err(PdfExportError::InvalidImageData);
+                }
+                let height = u16::from_be_bytes([segment[1], segment[2]]) as u32;
+                let width = u16::from_be_bytes([segment[3], segment[4]]) as u32;
+                let color_space = match segment[5] {
+                    1 => "DeviceGray",
+                    3 => "DeviceRGB",
+                    4 => "DeviceCMYK",
+                    _ => return Err(PdfExportError::InvalidImageData),
+                };
+                if width == 0 || height == 0 {
+                    return Err(PdfExportError::InvalidImageData);
+                }
+                return Ok(EncodedImage {
+                    width,
+                    height,
+                    color_space,
+                    filter: "DCTDecode",
+                    data: bytes.to_vec(),
+                });
+            }
+            0xC3 | 0xC5..=0xC7 | 0xC9..=0xCB | 0xCD..=0xCF => {
+                return Err(PdfExportError::UnsupportedImage(
+                    "lossless and arithmetic-coded JPEG".into(),
+                ));
+            }
+            0xDA => break,
+            _ => {}
+        }
+        offset += 2 + length;
+    }
+    Err(PdfExportError::InvalidImageData)
+}
+
+/// Largest PNG accepted for embedding; decoding works on the full pixel buffer.
+const MAX_PNG_PIXELS: usize = 40_000_000;
+
+struct PngHeader {
+    width: u32,
+    height: u32,
+    bit_depth: u8,
+    color_type: u8,
+}
+
+impl PngHeader {
+    fn channels(&self) -> Option<usize> {
+        match self.color_type {
+            0 | 3 => Some(1),
+            2 => Some(3),
+            4 => Some(2),
+            6 => Some(4),
+            _ => None,
+        }
+    }
+}
+
+fn encode_png(bytes: &[u8]) -> Result<EncodedImage, PdfExportError> {
+    let invalid = || PdfExportError::InvalidImageData;
+    let mut header: Option<PngHeader> = None;
+    let mut palette: &[u8] = &[];
+    let mut transparency: &[u8] = &[];
+    let mut compressed = Vec::new();
+
+    let mut offset = PNG_SIGNATURE.len();
+    while offset + 8 <= bytes.len() {
+        let length = u32::from_be_bytes(
+            bytes[offset..offset + 4]
+                .try_into()
+                .map_err(|_| invalid())?,
+        ) as usize;
+        let kind = &bytes[offset + 4..offset + 8];
+        let data = bytes
+            .get(offset + 8..offset + 8 + length)
+            .ok_or_else(invalid)?;
+        match kind {
+            b"IHDR" => {
+                if data.len() < 13 {
+                    return Err(invalid());
+                }
+                if data[12] != 0 {
+                    return Err(PdfExportError::UnsupportedImage("interlaced PNG".into()));
+                }
+                header = Some(PngHeader {
+                    width: u32::from_be_bytes([data[0], data[1], data[2], data[3]]),
+                    height: u32::from_be_bytes([data[4], data[5], data[6], data[7]]),
+                    bit_depth: data[8],
+                    color_type: data[9],
+                });
+            }
+            b"PLTE" => palette = data,
+            b"tRNS" => transparency = data,
+            b"IDAT" => compressed.extend_from_slice(data),
+            b"IEND" => break,
+            _ => {}
+        }
+        // Chunk length, type, data and CRC
+        offset += 12 + length;
+    }
+
+    let header = header.ok_or_else(invalid)?;
+    let channels = header.channels().ok_or_else(invalid)?;
+    let depth = header.bit_depth as usize;
+    let valid_depth = match header.color_type {
+        0 => matches!(depth, 1 | 2 | 4 | 8 | 16),
+        3 => matches!(depth, 1 | 2 | 4 | 8),
+        _ => matches!(depth, 8 | 16),
+    };
+    if !valid_depth || header.width == 0 || header.height == 0 {
+        return Err(invalid());
+    }
+    if header.color_type == 3 && palette.is_empty() {
+        return Err(invalid());
+    }
+
+    let width = header.width as usize;
+    let height = header.height as usize;
+    let too_large = || PdfExportError::UnsupportedImage("PNG larger than 40 megapixels".into());
+    if width.checked_mul(height).ok_or_else(too_large)? > MAX_PNG_PIXELS {
+        return Err(too_large());
+    }
+    let bits_per_pixel = channels * depth;
+    let stride = width
+        .checked_mul(bits_per_pixel)
+        .ok_or_else(too_large)?
+        .div_ceil(8);
+    let filter_bpp = bits_per_pixel.div_ceil(8).max(1);
+    let expected = (stride + 1).checked_mul(height).ok_or_else(too_large)?;
+
+    // Scanlines past the declared size are never used, so stop inflating there
+    let mut raw = Vec::new();
+    ZlibDecoder::new(compressed.as_slice())
+        .take(expected as u64)
+        .read_to_end(&mut raw)
+        .map_err(|_| invalid())?;
+    if raw.len() < expected {
+        return Err(invalid());
+    }
+    let rows = unfilter_scanlines(&raw, stride, height, filter_bpp).ok_or_else(invalid)?;
+
+    let gray_output = matches!(header.color_type, 0 | 4);
+    let out_channels = if gray_output { 1 } else { 3 };
+    let mut pixels = Vec::with_capacity(width * height * out_channels);
+    let max_sample = (1u32 << depth) - 1;
+    for row in rows.chunks(stride) {
+        for x in 0..width {
+            let sample = |channel: usize| read_sample(row, x * channels + channel, depth);
+            let scale = |value: u32| (value * 255 / max_sample) as u8;
+            let (rgb, alpha) = match header.color_type {
+                0 => {
+                    let value = sample(0);
+                    let keyed = transparency.len() >= 2
+                        && value == u16::from_be_bytes([transparency[0], transparency[1]]) as u32;
+                    ([scale(value); 3], if keyed { 0 } else { 255 })
+                }
+                2 => {
+                    let values = [sample(0), sample(1), sample(2)];
+                    let keyed = transparency.len() >= 6
+                        && (0..3).all(|index| {
+                            values[index]
+                                == u16::from_be_bytes([
+                                    transparency[index * 2],
+                                    transparency[index * 2 + 1],
+                                ]) as u32
+                        });
+                    (values.map(scale), if keyed { 0 } else { 255 })
+                }
+                3 => {
+                    let index = sample(0) as usize;
+                    let entry = palette.get(index * 3..index * 3 + 3).ok_or_else(invalid)?;
+                    (
+                        [entry[0], entry[1], entry[2]],
+                        transparency.get(index).copied().unwrap_or(255),
+                    )
+                }
+                4 => ([scale(sample(0)); 3], scale(sample(1))),
+                _ => (
+                    [scale(sample(0)), scale(sample(1)), scale(sample(2))],
+                    scale(sample(3)),
+                ),
+            };
+            let flattened = rgb.map(|channel| flatten_on_white(channel, alpha));
+            pixels.extend_from_slice(&flattened[..out_channels]);
+        }
+    }
+
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&pixels).map_err(|_| invalid())?;
+    let data = encoder.finish().map_err(|_| invalid())?;
+
+    Ok(EncodedImage {
+        width: header.width,
+        height: header.height,
+        color_space: if gray_output {
+            "DeviceGray"
+        } else {
+            "DeviceRGB"
+        },
+        filter: "FlateDecode",
+        data,
+    })
+}
+
+fn flatten_on_white(channel: u8, alpha: u8) -> u8 {
+    let channel = channel as u32;
+    let alpha = alpha as u32;
+    ((channel * alpha + 255 * (255 - alpha) + 127) / 255) as u8
+}
+
+/// Reads sample `index` of a scanline with the given bit depth.
+/// 16-bit samples are reduced to their high byte.
+fn read_sample(row: &[u8], index: usize, depth: usize) -> u32 {
+    match depth {
+        16 => row.get(index * 2).copied().unwrap_or(0) as u32 * 257,
+        8 => row.get(index).copied().unwrap_or(0) as u32,
+        _ => {
+            let bit = index * depth;
+            let byte = row.get(bit / 8).copied().unwrap_or(0) as u32;
+            let shift = 8 - depth - bit % 8;
+            (byte >> shift) & ((1 << depth) - 1)
+        }
+    }
+}
+
+/// Reverses PNG scanline filters; returns the unfiltered rows back to back.
+fn unfilter_scanlines(raw: &[u8], stride: usize, height: usize, bpp: usize) -> Option<Vec<u8>> {
+    let mut output = vec![0u8; stride * height];
+    for y in 0..height {
+        let line = &raw[y * (stride + 1)..(y + 1) * (stride + 1)];
+        let filter = line[0];
+        let (previous, current) = output.split_at_mut(y * stride);
+        let prior = if y == 0 {
+            None
+        } else {
+            Some(&previous[(y - 1) * stride..])
+        };
+        let current = &mut current[..stride];
+        for x in 0..stride {
+            let left = if x >= bpp { current[x - bpp] as i32 } else { 0 };
+            let up = prior.map_or(0, |row| row[x] as i32);
+            let up_left = match prior {
+                Some(row) if x >= bpp => row[x - bpp] as i32,
+                _ => 0,
+            };
+            let predictor = match filter {
+                0 => 0,
+                1 => left,
+                2 => up,
+                3 => (left + up) / 2,
+                4 => paeth(left, up, up_left),
+                _ => return None,
+            };
+            current[x] = (line[x + 1] as i32 + predictor) as u8;
+        }
+    }
+    Some(output)
+}
+
+fn paeth(left: i32, up: i32, up_left: i32) -> i32 {
+    let estimate = left + up - up_left;
+    let to_left = (estimate - left).abs();
+    let to_up = (estimate - up).abs();
+    let to_up_left = (estimate - up_left).abs();
+    if to_left <= to_up && to_left <= to_up_left {
+        left
+    } else if to_up <= to_up_left {
+        up
+    } else {
+        up_left
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    fn crc32(bytes: &[u8]) -> u32 {
+        let mut crc = 0xFFFF_FFFFu32;
+        for byte in bytes {
+            crc ^= *byte as u32;
+            for _ in 0..8 {
+                let mask = (crc & 1).wrapping_neg();
+                crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+            }
+        }
+        !crc
+    }
+
+    /// Builds an 8-bit PNG from raw scanlines (each prefixed with its filter byte).
+    pub(crate) fn build_png(width: u32, height: u32, color_type: u8, scanlines: &[u8]) -> Vec<u8> {
+        let mut ihdr = Vec::new();
+        ihdr.extend_from_slice(&width.to_be_bytes());
+        ihdr.extend_from_slice(&height.to_be_bytes());
+        ihdr.extend_from_slice(&[8, color_type, 0, 0, 0]);
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(scanlines).unwrap();
+        let idat = encoder.finish().unwrap();
+
+        let mut png = PNG_SIGNATURE.to_vec();
+        for (kind, data) in [(b"IHDR", ihdr), (b"IDAT", idat), (b"IEND", Vec::new())] {
+            png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+            let mut chunk = kind.to_vec();
+            chunk.extend_from_slice(&data);
+            png.extend_from_slice(&chunk);
+            png.extend_from_slice(&crc32(&chunk).to_be_bytes());
+        }
+        png
+    }
+
+    fn decode_flate(data: &[u8]) -> Vec<u8> {
+        let mut output = Vec::new();
+        ZlibDecoder::new(data).read_to_end(&mut output).unwrap();
+        output
+    }
+
+    #[test]
+    fn png_alpha_is_flattened_onto_white() {
+        // 2x2 RGBA: opaque red, transparent, half-transparent black, opaque blue;
+        // the second row uses the Up filter
+        let scanlines = [
+            0, 255, 0, 0, 255, 0, 0, 0, 0, //
+            2, 1, 0, 0, 129, 0, 0, 255, 255,
+        ];
+        let image = encode_image(&build_png(2, 2, 6, &scanlines)).unwrap();
+
+        assert_eq!((image.width, image.height), (2, 2));
+        assert_eq!(image.color_space, "DeviceRGB");
+        assert_eq!(image.filter, "FlateDecode");
+        assert_eq!(
+            decode_flate(&image.data),
+            vec![255, 0, 0, 255, 255, 255, 127, 127, 127, 0, 0, 255]
+        );
+    }
+
+    #[test]
+    fn png_dimensions_are_bounded() {
+        let huge = build_png(u32::MAX, u32::MAX, 6, &[0; 4]);
+        assert!(matches!(
+            encode_image(&huge),
+            Err(PdfExportError::UnsupportedImage(_))
+        ));
+
+        let over = build_png(10_000, 5_000, 0, &[0; 4]);
+        assert!(matches!(
+            encode_image(&over),
+            Err(PdfExportError::UnsupportedImage(_))
+        ));
+
+        // Extra inflated data beyond the declared scanlines is ignored
+        let mut scanlines = vec![0, 7];
+        scanlines.extend_from_slice(&[0; 1024]);
+        let image = encode_image(&build_png(1, 1, 0, &scanlines)).unwrap();
+        assert_eq!(decode_flate(&image.data), vec![7]);
+    }
+
+    #[test]
+    fn jpeg_frame_header_provides_size_and_color_space() {
+        let mut jpeg = vec![0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x04, 0x4A, 0x46];
+        jpeg.extend_from_slice(&[
+            0xFF, 0xC0, 0x00, 0x0B, 8, 0x00, 0x20, 0x00, 0x40, 3, 0, 0, 0,
+        ]);
+        jpeg.extend_from_slice(&[0xFF, 0xDA, 0x00, 0x02, 0xFF, 0xD9]);
+
+        let image = encode_image(&jpeg).unwrap();
+
+        assert_eq!((image.width, image.height), (64, 32));
+        assert_eq!(image.color_space, "DeviceRGB");
+        assert_eq!(image.filter, "DCTDecode");
+        assert_eq!(image.data, jpeg);
+
+        assert_eq!(
+            encode_image(b"GIF89a").unwrap_err(),
+            PdfExportError::UnsupportedImage("only PNG and JPEG images are supported".into())
+        );
+    }
+}
@@ -18,6 +18,7 @@ pub struct PreviewPage {
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct PreviewPageMetrics {
     pub page_size_px: PreviewPageSize,
+    pub body_width_px: i32,
     pub body_height_px: i32,
 }
 
@@ -69,10 +70,12 @@ pub fn preview_page_metrics(page_style: PageStyle, dpi: f32) -> PreviewPageMetri
         width_px: mm_to_px(page_box.width_mm, dpi),
         height_px: mm_to_px(page_box.height_mm, dpi),
     };
+    let body_width_px = mm_to_px(body_box.width_mm, dpi);
     let body_height_px = mm_to_px(body_box.height_mm, dpi);
 
     PreviewPageMetrics {
         page_size_px,
+        body_width_px,
         body_height_px,
     }
 }
@@ -88,22 +91,18 @@ pub fn build_preview_pages_for_style(
     (metrics, pages)
 }
 
+/// Paragraph spacing is carried by the first and last line of each paragraph,
+/// so lines from several consecutive paragraphs can be paginated together.
 fn line_heights_for_preview(lines: &[PositionedLine], line_height: i32) -> Vec<i32> {
     let line_height = line_height.max(0);
-    let mut heights = Vec::with_capacity(lines.len());
-
-    for (index, line) in lines.iter().enumerate() {
-        let mut height = line_height;
-        if index == 0 {
-            height = height.saturating_add(line.space_before.max(0));
-        }
-        if index + 1 == lines.len() {
-            height = height.saturating_add(line.space_after.max(0));
-        }
-        heights.push(height);
-    }
-
-    heights
+    lines
+        .iter()
+        .map(|line| {
+            line_height
+                .saturating_add(line.space_before.max(0))
+                .saturating_add(line.space_after.max(0))
+        })
+        .collect()
 }
 
 const DEFAULT_DPI: f32 = 96.0;
@@ -181,6 +180,16 @@ mod tests {
         assert_eq!(pages[1].lines[1].y_offset, 10);
     }
 
+    #[test]
+    fn counts_spacing_between_consecutive_paragraphs() {
+        let lines = vec![line(0, 1, 0, 0, 5), line(0, 1, 15, 5, 0)];
+
+        let pages = build_preview_pages(&lines, 10, 25);
+
+        assert_eq!(pages.len(), 2);
+        assert_eq!(pages[0].used_height, 15);
+    }
+
     #[test]
     fn metrics_convert_page_and_body_boxes_to_px() {
         let style = PageStyle {
@@ -207,6 +216,7 @@ mod tests {
             metrics.page_size_px.height_px,
             mm_to_px(page_box.height_mm, 96.0)
         );
+        assert_eq!(metrics.body_width_px, mm_to_px(body_box.width_mm, 96.0));
         assert_eq!(metrics.body_height_px, mm_to_px(body_box.height_mm, 96.0));
     }
 
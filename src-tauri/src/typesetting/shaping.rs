@@ -1,3 +1,4 @@
+use crate::text::is_cjk;
 use crate::typesetting::{FontData, ScriptKind};
 use rustybuzz::{script, Direction, Face, Language, UnicodeBuffer};
 use std::str::FromStr;
@@ -33,20 +34,9 @@ struct ScriptRun {
     end: usize,
 }
 
+/// CJK text plus CJK symbols and punctuation, which need the CJK font too
 fn is_cjk_char(ch: char) -> bool {
-    matches!(
-        ch as u32,
-        0x3400..=0x4DBF
-            | 0x4E00..=0x9FFF
-            | 0xF900..=0xFAFF
-            | 0x20000..=0x2A6DF
-            | 0x2A700..=0x2B73F
-            | 0x2B740..=0x2B81F
-            | 0x2B820..=0x2CEAF
-            | 0x2CEB0..=0x2EBEF
-            | 0x30000..=0x3134F
-            | 0x3000..=0x303F
-    )
+    is_cjk(ch) || matches!(ch as u32, 0x3000..=0x303F)
 }
 
 fn script_kind_for_char(ch: char) -> ScriptKind {
@@ -20,15 +20,26 @@ pub fn layout_text_paragraph(
     options: TextLayoutOptions,
 ) -> Result<Vec<PositionedLine>, ShapingError> {
     let glyph_run = shape_mixed_text(font, text)?;
-    if glyph_run.glyphs.is_empty() {
-        return Ok(Vec::new());
+    let glyphs = scale_glyphs_for_font_size(&glyph_run, options.font_size);
+    Ok(layout_shaped_paragraph(text, &glyphs, options))
+}
+
+/// Lays out glyphs that were already shaped and scaled to layout units, e.g.
+/// when a paragraph mixes several fonts. Glyph clusters are byte offsets into
+/// `text`; `options.font_size` is ignored.
+pub fn layout_shaped_paragraph(
+    text: &str,
+    glyphs: &[Glyph],
+    options: TextLayoutOptions,
+) -> Vec<PositionedLine> {
+    if glyphs.is_empty() {
+        return Vec::new();
     }
 
-    let glyphs = scale_glyphs_for_font_size(&glyph_run, options.font_size);
-    let break_after = break_after_for_text(text, &glyphs);
-    let lines = break_glyph_run(&glyphs, options.max_width, &break_after);
+    let break_after = break_after_for_text(text, glyphs);
+    let lines = break_glyph_run(glyphs, options.max_width, &break_after);
 
-    Ok(layout_paragraph(
+    layout_paragraph(
         &lines,
         options.max_width,
         options.line_height,
@@ -37,10 +48,13 @@ pub fn layout_text_paragraph(
         options.first_line_indent,
         options.space_before,
         options.space_after,
-    ))
+    )
 }
 
-fn scale_glyphs_for_font_size(glyph_run: &GlyphRun, font_size: Option<i32>) -> Vec<Glyph> {
+pub(crate) fn scale_glyphs_for_font_size(
+    glyph_run: &GlyphRun,
+    font_size: Option<i32>,
+) -> Vec<Glyph> {
     let mut glyphs = glyph_run.glyphs.clone();
     let Some(font_size) = font_size else {
         return glyphs;
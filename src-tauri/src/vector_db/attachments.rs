@@ -0,0 +1,356 @@
+//! Text extraction from note attachments for the vector index
+//!
+//! PDFs are read through their text layer with `pdftotext`; scanned PDFs
+//! (no text layer) and images go through OCR with `tesseract`, PDFs being
+//! rendered to images with `pdftoppm` first. The binaries come from the Doc
+//! Tools Pack or `PATH`. When a tool is missing the attachment is reported as
+//! skipped instead of failing, so indexing carries on with the notes.
+
+use super::chunking::{chunk_markdown, ChunkOptions, MarkdownChunk};
+use crate::doc_tools::{doc_tools_get_status, ToolIntegrity};
+use crate::error::AppError;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::process::Output;
+use std::time::Duration;
+use tauri::AppHandle;
+use uuid::Uuid;
+
+/// Longest a single tool invocation may run
+const TOOL_TIMEOUT: Duration = Duration::from_secs(120);
+/// Pages of a scanned PDF that are OCRed; the rest are skipped
+const MAX_OCR_PAGES: usize = 30;
+const OCR_DPI: &str = "200";
+const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "webp", "gif", "bmp", "tif", "tiff"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AttachmentKind {
+    Pdf,
+    Image,
+}
+
+impl AttachmentKind {
+    /// Kind of an indexable attachment, by extension
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_lowercase();
+        if ext == "pdf" {
+            Some(Self::Pdf)
+        } else if IMAGE_EXTENSIONS.contains(&ext.as_str()) {
+            Some(Self::Image)
+        } else {
+            None
+        }
+    }
+}
+
+/// Whether `path` is an attachment whose text can be indexed
+pub fn is_attachment(path: &Path) -> bool {
+    AttachmentKind::from_path(path).is_some()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExtractionMethod {
+    TextLayer,
+    Ocr,
+}
+
+/// Text extracted from an attachment
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AttachmentText {
+    pub path: String,
+    pub kind: AttachmentKind,
+    /// Text per page; images have a single page
+    pub pages: Vec<String>,
+    pub method: Option<ExtractionMethod>,
+    /// Why nothing (or not everything) was extracted, e.g. a missing tool
+    pub skipped: Option<String>,
+}
+
+impl AttachmentText {
+    fn skipped(path: &Path, kind: AttachmentKind, reason: impl Into<String>) -> Self {
+        Self {
+            path: path.to_string_lossy().to_string(),
+            kind,
+            pages: Vec::new(),
+            method: None,
+            skipped: Some(reason.into()),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pages.iter().all(|page| page.trim().is_empty())
+    }
+}
+
+/// Extraction binaries that are available
+#[derive(Debug, Clone, Default)]
+pub struct AttachmentTools {
+    pub pdftotext: Option<PathBuf>,
+    pub pdftoppm: Option<PathBuf>,
+    pub tesseract: Option<PathBuf>,
+}
+
+impl AttachmentTools {
+    /// Look the tools up in the Doc Tools Pack (or `PATH`); tools that are
+    /// missing or fail their checksum are left out
+    pub async fn detect(app: AppHandle) -> Result<Self, AppError> {
+        let status = doc_tools_get_status(app).await?;
+        let tool = |name: &str| {
+            status
+                .tools
+                .get(name)
+                .filter(|tool| tool.available && tool.integrity != ToolIntegrity::Mismatch)
+                .and_then(|tool| tool.path.as_ref())
+                .map(PathBuf::from)
+        };
+        Ok(Self {
+            pdftotext: tool("pdftotext"),
+            pdftoppm: tool("pdftoppm"),
+            tesseract: tool("tesseract"),
+        })
+    }
+}
+
+/// Extract the text of a PDF or image attachment
+pub async fn extract_attachment(
+    path: &Path,
+    tools: &AttachmentTools,
+) -> Result<AttachmentText, AppError> {
+    let kind = AttachmentKind::from_path(path)
+        .ok_or_else(|| AppError::InvalidInput(format!("Not a PDF or image: {}", path.display())))?;
+    if !path.is_file() {
+        return Err(AppError::FileNotFound(path.display().to_string()));
+    }
+
+    match kind {
+        AttachmentKind::Image => {
+            let Some(tesseract) = &tools.tesseract else {
+                return Ok(AttachmentText::skipped(
+                    path,
+                    kind,
+                    "OCR needs tesseract; install the Doc Tools Pack in Settings",
+                ));
+            };
+            let text = ocr_image(tesseract, path).await?;
+            Ok(AttachmentText {
+                path: path.to_string_lossy().to_string(),
+                kind,
+                pages: vec![text],
+                method: Some(ExtractionMethod::Ocr),
+                skipped: None,
+            })
+        }
+        AttachmentKind::Pdf => {
+            let Some(pdftotext) = &tools.pdftotext else {
+                return Ok(AttachmentText::skipped(
+                    path,
+                    kind,
+                    "PDF text extraction needs pdftotext; install the Doc Tools Pack in Settings",
+                ));
+            };
+            let output = run_tool(
+                tokio::process::Command::new(pdftotext)
+                    .args(["-layout", "-enc", "UTF-8"])
+                    .arg(path)
+                    .arg("-"),
+                "pdftotext",
+            )
+            .await?;
+            let pages = pdf_pages(&String::from_utf8_lossy(&output.stdout));
+            let text = AttachmentText {
+                path: path.to_string_lossy().to_string(),
+                kind,
+                pages,
+                method: Some(ExtractionMethod::TextLayer),
+                skipped: None,
+            };
+            if !text.is_empty() {
+                return Ok(text);
+            }
+            // No text layer: a scanned document
+            match (&tools.pdftoppm, &tools.tesseract) {
+                (Some(pdftoppm), Some(tesseract)) => ocr_pdf(pdftoppm, tesseract, path).await,
+                _ => Ok(AttachmentText::skipped(
+                    path,
+                    kind,
+                    "The PDF has no text layer; OCR needs pdftoppm and tesseract",
+                )),
+            }
+        }
+    }
+}
+
+/// Pages of `pdftotext` output, which separates pages with form feeds
+fn pdf_pages(output: &str) -> Vec<String> {
+    let mut pages: Vec<String> = output
+        .split('\u{c}')
+        .map(|page| page.trim_end().to_string())
+        .collect();
+    // Output ends with a form feed after the last page
+    if pages.last().is_some_and(|page| page.trim().is_empty()) {
+        pages.pop();
+    }
+    pages
+}
+
+async fn ocr_image(tesseract: &Path, image: &Path) -> Result<String, AppError> {
+    let output = run_tool(
+        tokio::process::Command::new(tesseract)
+            .arg(image)
+            .arg("stdout"),
+        "tesseract",
+    )
+    .await?;
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .trim_end()
+        .to_string())
+}
+
+async fn ocr_pdf(
+    pdftoppm: &Path,
+    tesseract: &Path,
+    pdf: &Path,
+) -> Result<AttachmentText, AppError> {
+    let out_dir = std::env::temp_dir()
+        .join("lumina-attachments")
+        .join(Uuid::new_v4().to_string());
+    tokio::fs::create_dir_all(&out_dir).await?;
+    let result = async {
+        run_tool(
+            tokio::process::Command::new(pdftoppm)
+                .args(["-r", OCR_DPI, "-png", "-f", "1", "-l"])
+                .arg(MAX_OCR_PAGES.to_string())
+                .arg(pdf)
+                .arg(out_dir.join("page")),
+            "pdftoppm",
+        )
+        .await?;
+        // `page-01.png`, `page-02.png`, ... sort in page order
+        let mut images: Vec<PathBuf> = std::fs::read_dir(&out_dir)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "png"))
+            .collect();
+        images.sort();
+        let mut pages = Vec::with_capacity(images.len());
+        for image in &images {
+            pages.push(ocr_image(tesseract, image).await?);
+        }
+        Ok::<_, AppError>(pages)
+    }
+    .await;
+    let _ = tokio::fs::remove_dir_all(&out_dir).await;
+
+    let pages = result?;
+    let skipped = (pages.len() >= MAX_OCR_PAGES)
+        .then(|| format!("Only the first {} pages were OCRed", MAX_OCR_PAGES));
+    Ok(AttachmentText {
+        path: pdf.to_string_lossy().to_string(),
+        kind: AttachmentKind::Pdf,
+        pages,
+        method: Some(ExtractionMethod::Ocr),
+        skipped,
+    })
+}
+
+async fn run_tool(command: &mut tokio::process::Command, name: &str) -> Result<Output, AppError> {
+    command.kill_on_drop(true);
+    let output = tokio::time::timeout(TOOL_TIMEOUT, command.output())
+        .await
+        .map_err(|_| AppError::ExternalTool(format!("{name} timed out")))??;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(AppError::ExternalTool(format!(
+            "{name} failed ({}): {}",
+            output.status,
+            stderr.trim()
+        )));
+    }
+    Ok(output)
+}
+
+/// Chunks of an attachment's text, tied to the attachment path
+///
+/// PDF pages become `Page N` sections so search results point at a page.
+pub fn attachment_chunks(
+    text: &AttachmentText,
+    options: &ChunkOptions,
+    file_modified: Option<i64>,
+) -> Vec<MarkdownChunk> {
+    let mut markdown = String::new();
+    for (index, page) in text.pages.iter().enumerate() {
+        if page.trim().is_empty() {
+            continue;
+        }
+        if text.kind == AttachmentKind::Pdf {
+            markdown.push_str(&format!("# Page {}\n\n", index + 1));
+        }
+        for line in page.lines() {
+            // Extracted text is plain text: keep `#` and fences from acting as markdown
+            let trimmed = line.trim_start();
+            if trimmed.starts_with('#') || trimmed.starts_with("```") || trimmed.starts_with("~~~")
+            {
+                markdown.push('\\');
+            }
+            markdown.push_str(line);
+            markdown.push('\n');
+        }
+        markdown.push('\n');
+    }
+    chunk_markdown(&text.path, &markdown, options, file_modified)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pdf_text(pages: &[&str]) -> AttachmentText {
+        AttachmentText {
+            path: "/v/scans/report.pdf".into(),
+            kind: AttachmentKind::Pdf,
+            pages: pages.iter().map(|page| page.to_string()).collect(),
+            method: Some(ExtractionMethod::TextLayer),
+            skipped: None,
+        }
+    }
+
+    #[test]
+    fn detects_attachment_kinds_and_splits_pdf_pages() {
+        assert_eq!(
+            AttachmentKind::from_path(Path::new("a/Scan.PDF")),
+            Some(AttachmentKind::Pdf)
+        );
+        assert_eq!(
+            AttachmentKind::from_path(Path::new("shot.jpeg")),
+            Some(AttachmentKind::Image)
+        );
+        assert!(!is_attachment(Path::new("note.md")));
+
+        assert_eq!(
+            pdf_pages("First page\n\u{c}\n\u{c}Third  \n\u{c}"),
+            vec!["First page", "", "Third"]
+        );
+        assert!(pdf_text(&["", "  "]).is_empty());
+    }
+
+    #[test]
+    fn chunks_pdf_pages_under_page_headings() {
+        let text = pdf_text(&["Revenue grew.\n# not a heading", "", "Costs fell."]);
+        let chunks = attachment_chunks(&text, &ChunkOptions::default(), Some(7));
+        let summary: Vec<(&str, &str)> = chunks
+            .iter()
+            .map(|chunk| (chunk.heading.as_str(), chunk.content.as_str()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("Page 1", "# Page 1\n\nRevenue grew.\n\\# not a heading"),
+                ("Page 3", "# Page 3\n\nCosts fell."),
+            ]
+        );
+        assert!(chunks.iter().all(
+            |chunk| chunk.file_path == "/v/scans/report.pdf" && chunk.file_modified == Some(7)
+        ));
+    }
+}
@@ -0,0 +1,336 @@
+//! Markdown chunking for the vector index
+//!
+//! Splits a note into [`MarkdownChunk`]s (a [`VectorChunk`] without the
+//! embedding). Fenced code blocks are never split across chunks, and each
+//! chunk records the heading path it starts under, e.g. `Setup > Linux`.
+
+use super::VectorChunk;
+use crate::llm_pricing::estimate_tokens;
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+use std::path::Path;
+
+/// How chunk boundaries are chosen
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChunkStrategy {
+    /// Every heading starts a new chunk; long sections are split further
+    #[default]
+    Heading,
+    /// Fixed-size sliding window that ignores headings as boundaries
+    Overlap,
+}
+
+/// Chunking options
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChunkOptions {
+    pub strategy: ChunkStrategy,
+    /// Upper bound on the (estimated) tokens of one chunk. A single code
+    /// block or line larger than this becomes a chunk of its own.
+    pub max_tokens: usize,
+    /// Tokens repeated from the end of the previous chunk, capped at half of
+    /// `max_tokens`
+    pub overlap_tokens: usize,
+}
+
+impl Default for ChunkOptions {
+    fn default() -> Self {
+        Self {
+            strategy: ChunkStrategy::Heading,
+            max_tokens: 400,
+            overlap_tokens: 50,
+        }
+    }
+}
+
+/// One chunk of a note, ready to be embedded
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MarkdownChunk {
+    pub id: String,
+    pub content: String,
+    pub file_path: String,
+    pub heading: String,
+    /// 1-based, inclusive
+    pub start_line: i32,
+    pub end_line: i32,
+    pub file_modified: Option<i64>,
+}
+
+impl MarkdownChunk {
+    /// Attach the embedding for storage
+    pub fn into_vector_chunk(self, vector: Vec<f32>) -> VectorChunk {
+        VectorChunk {
+            id: self.id,
+            vector,
+            content: self.content,
+            file_path: self.file_path,
+            heading: self.heading,
+            start_line: self.start_line,
+            end_line: self.end_line,
+            file_modified: self.file_modified,
+        }
+    }
+}
+
+/// Lines that must stay together: a fenced code block or a single line
+#[derive(Debug)]
+struct Block {
+    lines: Range<usize>,
+    tokens: usize,
+    blank: bool,
+    heading: Option<(usize, String)>,
+}
+
+/// `# Title` -> (1, "Title"); `#tag` and `#######` are not headings
+fn parse_heading(line: &str) -> Option<(usize, String)> {
+    let level = line.chars().take_while(|c| *c == '#').count();
+    if !(1..=6).contains(&level) {
+        return None;
+    }
+    let rest = &line[level..];
+    if !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+    let text = rest.trim().trim_end_matches('#').trim();
+    (!text.is_empty()).then(|| (level, text.to_string()))
+}
+
+/// Opening fence marker (char and length) of a line, if any
+fn fence_marker(line: &str) -> Option<(char, usize)> {
+    let trimmed = line.trim_start();
+    let marker = trimmed.chars().next().filter(|c| *c == '`' || *c == '~')?;
+    let len = trimmed.chars().take_while(|c| *c == marker).count();
+    (len >= 3).then_some((marker, len))
+}
+
+fn closes_fence(line: &str, (marker, len): (char, usize)) -> bool {
+    let trimmed = line.trim();
+    let run = trimmed.chars().take_while(|c| *c == marker).count();
+    run >= len && run == trimmed.chars().count()
+}
+
+fn split_blocks(lines: &[&str]) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        let start = i;
+        let mut heading = None;
+        if let Some(fence) = fence_marker(lines[i]) {
+            // An unclosed fence runs to the end of the file
+            i += 1;
+            while i < lines.len() && !closes_fence(lines[i], fence) {
+                i += 1;
+            }
+            i = (i + 1).min(lines.len());
+        } else {
+            heading = parse_heading(lines[i]);
+            i += 1;
+        }
+        let text = lines[start..i].join("\n");
+        blocks.push(Block {
+            lines: start..i,
+            tokens: estimate_tokens(&text) + 1,
+            blank: text.trim().is_empty(),
+            heading,
+        });
+    }
+    blocks
+}
+
+/// Heading path in effect at each block; `fallback` before the first heading
+fn heading_paths(blocks: &[Block], fallback: &str) -> Vec<String> {
+    let mut stack: Vec<(usize, String)> = Vec::new();
+    blocks
+        .iter()
+        .map(|block| {
+            if let Some((level, text)) = &block.heading {
+                while stack.last().is_some_and(|(top, _)| top >= level) {
+                    stack.pop();
+                }
+                stack.push((*level, text.clone()));
+            }
+            if stack.is_empty() {
+                fallback.to_string()
+            } else {
+                stack
+                    .iter()
+                    .map(|(_, text)| text.as_str())
+                    .collect::<Vec<_>>()
+                    .join(" > ")
+            }
+        })
+        .collect()
+}
+
+/// Greedily packs `blocks` into ranges of at most `max` tokens, preferring to
+/// cut at a blank line in the second half of a chunk
+fn pack(blocks: &[Block], max: usize, overlap: usize) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    let mut start = 0;
+    while start < blocks.len() {
+        let mut end = start;
+        let mut tokens = 0;
+        while end < blocks.len() && (end == start || tokens + blocks[end].tokens <= max) {
+            tokens += blocks[end].tokens;
+            end += 1;
+        }
+        if end < blocks.len() {
+            let half = start + (end - start) / 2;
+            if let Some(cut) = (half + 1..end).rev().find(|&i| blocks[i].blank) {
+                end = cut;
+            }
+        }
+        ranges.push(start..end);
+        if end >= blocks.len() {
+            break;
+        }
+
+        // Repeat trailing blocks of this chunk, always moving forward
+        let mut next = end;
+        let mut repeated = 0;
+        while next > start + 1 && repeated + blocks[next - 1].tokens <= overlap {
+            next -= 1;
+            repeated += blocks[next].tokens;
+        }
+        start = next;
+    }
+    ranges
+}
+
+/// Split a Markdown note into chunks
+pub fn chunk_markdown(
+    file_path: &str,
+    content: &str,
+    options: &ChunkOptions,
+    file_modified: Option<i64>,
+) -> Vec<MarkdownChunk> {
+    let lines: Vec<&str> = content.lines().collect();
+    let blocks = split_blocks(&lines);
+    let title = Path::new(file_path)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_else(|| file_path.to_string());
+    let paths = heading_paths(&blocks, &title);
+
+    let max = options.max_tokens.max(1);
+    let overlap = options.overlap_tokens.min(max / 2);
+    let sections: Vec<Range<usize>> = match options.strategy {
+        ChunkStrategy::Overlap => std::iter::once(0..blocks.len()).collect(),
+        ChunkStrategy::Heading => {
+            let mut sections = Vec::new();
+            let mut start = 0;
+            for (i, block) in blocks.iter().enumerate() {
+                if block.heading.is_some() && i > start {
+                    sections.push(start..i);
+                    start = i;
+                }
+            }
+            sections.push(start..blocks.len());
+            sections
+        }
+    };
+
+    let mut chunks = Vec::new();
+    for section in sections {
+        let section_blocks = &blocks[section.clone()];
+        for range in pack(section_blocks, max, overlap) {
+            let used: Vec<&Block> = section_blocks[range.clone()]
+                .iter()
+                .filter(|block| !block.blank)
+                .collect();
+            let (Some(first), Some(last)) = (used.first(), used.last()) else {
+                continue;
+            };
+            let start_line = first.lines.start + 1;
+            let end_line = last.lines.end;
+            chunks.push(MarkdownChunk {
+                id: format!("{}:{}-{}", file_path, start_line, end_line),
+                content: lines[first.lines.start..last.lines.end].join("\n"),
+                file_path: file_path.to_string(),
+                heading: paths[section.start + range.start].clone(),
+                start_line: start_line as i32,
+                end_line: end_line as i32,
+                file_modified,
+            });
+        }
+    }
+    chunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(strategy: ChunkStrategy, max_tokens: usize, overlap_tokens: usize) -> ChunkOptions {
+        ChunkOptions {
+            strategy,
+            max_tokens,
+            overlap_tokens,
+        }
+    }
+
+    #[test]
+    fn splits_by_heading_hierarchy() {
+        let content = "intro\n\n# Setup\nsteps\n## Linux\napt install\n# Usage\nrun it";
+        let chunks = chunk_markdown("/v/guide.md", content, &ChunkOptions::default(), Some(7));
+
+        let summary: Vec<_> = chunks
+            .iter()
+            .map(|c| (c.heading.as_str(), c.start_line, c.end_line))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("guide", 1, 1),
+                ("Setup", 3, 4),
+                ("Setup > Linux", 5, 6),
+                ("Usage", 7, 8),
+            ]
+        );
+        assert_eq!(chunks[1].content, "# Setup\nsteps");
+        assert_eq!(chunks[1].id, "/v/guide.md:3-4");
+        assert_eq!(chunks[1].file_modified, Some(7));
+    }
+
+    #[test]
+    fn never_splits_code_fences() {
+        let code: Vec<String> = (0..40).map(|i| format!("let x{} = {};", i, i)).collect();
+        let content = format!(
+            "# Code\ntext before\n```rust\n# not a heading\n{}\n```\ntext after",
+            code.join("\n")
+        );
+        let chunks = chunk_markdown(
+            "/v/a.md",
+            &content,
+            &options(ChunkStrategy::Heading, 40, 0),
+            None,
+        );
+
+        let fence = chunks
+            .iter()
+            .find(|c| c.content.contains("```rust"))
+            .unwrap();
+        assert!(fence.content.contains("let x39 = 39;"));
+        assert!(fence.content.trim_end().ends_with("```"));
+        assert!(chunks.iter().all(|c| c.heading == "Code"));
+    }
+
+    #[test]
+    fn overlap_strategy_repeats_trailing_lines() {
+        let content: String = (1..=12).map(|i| format!("line number {}\n", i)).collect();
+        let chunks = chunk_markdown(
+            "/v/a.md",
+            &content,
+            &options(ChunkStrategy::Overlap, 20, 6),
+            None,
+        );
+
+        assert!(chunks.len() > 1);
+        for pair in chunks.windows(2) {
+            assert!(pair[1].start_line <= pair[0].end_line);
+            assert!(pair[1].start_line > pair[0].start_line);
+        }
+        assert_eq!(chunks.last().unwrap().end_line, 12);
+    }
+}
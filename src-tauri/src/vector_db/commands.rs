@@ -1,11 +1,25 @@
 //! Tauri commands for vector database operations
 
+use super::attachments::{attachment_chunks, extract_attachment, AttachmentText, AttachmentTools};
+use super::chunking::{chunk_markdown as split_markdown, ChunkOptions, MarkdownChunk};
+use super::embedding::{
+    embed_texts as embed_batch, embed_texts_batched as embed_concurrently, BatchOptions,
+    EmbeddingConfig, Embeddings,
+};
+use super::reindex::{compute_plan, ReindexPlan};
+use super::rerank::{rerank, RerankConfig};
 use super::{
     clear_all_vectors, delete_vectors_by_file, delete_vectors_by_ids, file_needs_reindex,
-    get_index_status, init_db, search_vectors, upsert_vectors, IndexStatus, SearchResult,
-    VectorChunk,
+    get_index_status, init_db, search_vectors, search_vectors_in_scope, upsert_vectors,
+    IndexStatus, ScopedSearchResult, SearchResult, VectorChunk,
 };
 use crate::error::AppError;
+use std::path::Path;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+/// Timeout per embedding request
+const EMBED_TIMEOUT: Duration = Duration::from_secs(60);
 
 /// Initialize vector database
 #[tauri::command]
@@ -14,9 +28,30 @@ pub async fn init_vector_db(db_path: String) -> Result<(), AppError> {
 }
 
 /// Insert or update vectors
+///
+/// `model` is the embedding model that produced the vectors; a mismatch with
+/// the model the index was built with is an error.
+#[tauri::command]
+pub async fn upsert_vector_chunks(
+    chunks: Vec<VectorChunk>,
+    model: Option<String>,
+) -> Result<(), AppError> {
+    upsert_vectors(chunks, model.as_deref())
+}
+
+/// Embed texts with the configured provider (or its fallback), through the
+/// app's proxy settings; vectors are normalized
 #[tauri::command]
-pub async fn upsert_vector_chunks(chunks: Vec<VectorChunk>) -> Result<(), AppError> {
-    upsert_vectors(chunks)
+pub async fn embed_texts(
+    proxy_state: tauri::State<'_, crate::proxy::ProxyState>,
+    texts: Vec<String>,
+    config: EmbeddingConfig,
+) -> Result<Embeddings, AppError> {
+    let client = proxy_state
+        .client_with_timeout(EMBED_TIMEOUT)
+        .await
+        .map_err(AppError::Network)?;
+    embed_batch(&client, &texts, &config).await
 }
 
 /// Search vectors by similarity
@@ -30,6 +65,28 @@ pub async fn search_vector_chunks(
     search_vectors(query_vector, limit, min_score, directory_filter)
 }
 
+/// Search vectors within a workspace sub-folder
+#[tauri::command]
+pub async fn search_vector_chunks_in_scope(
+    query_vector: Vec<f32>,
+    limit: usize,
+    min_score: f32,
+    workspace_path: String,
+    scope: Option<String>,
+) -> Result<ScopedSearchResult, AppError> {
+    search_vectors_in_scope(query_vector, limit, min_score, &workspace_path, scope)
+}
+
+/// Rerank search results with a cross-encoder (unchanged when disabled)
+#[tauri::command]
+pub async fn rerank_search_results(
+    query: String,
+    candidates: Vec<SearchResult>,
+    config: RerankConfig,
+) -> Result<Vec<SearchResult>, AppError> {
+    rerank(&query, candidates, &config).await
+}
+
 /// Delete vectors by file path
 #[tauri::command]
 pub async fn delete_file_vectors(file_path: String) -> Result<(), AppError> {
@@ -57,6 +114,88 @@ pub async fn check_file_needs_reindex(
     file_needs_reindex(&file_path, modified_time)
 }
 
+/// Diff the workspace against the index: files to add, update and remove
+#[tauri::command]
+pub async fn compute_reindex_plan(workspace_path: String) -> Result<ReindexPlan, AppError> {
+    compute_plan(&workspace_path)
+}
+
+/// Split a Markdown note into chunks ready for embedding
+#[tauri::command]
+pub async fn chunk_markdown(
+    path: String,
+    content: String,
+    options: Option<ChunkOptions>,
+    file_modified: Option<i64>,
+) -> Result<Vec<MarkdownChunk>, AppError> {
+    Ok(split_markdown(
+        &path,
+        &content,
+        &options.unwrap_or_default(),
+        file_modified,
+    ))
+}
+
+/// Extract the text of a PDF (text layer, OCR for scans) or image (OCR)
+///
+/// Missing extraction tools are reported in `skipped` rather than failing.
+#[tauri::command]
+pub async fn extract_attachment_text(
+    app: AppHandle,
+    path: String,
+) -> Result<AttachmentText, AppError> {
+    crate::fs::ensure_allowed_path(Path::new(&path), true)?;
+    let tools = AttachmentTools::detect(app).await?;
+    extract_attachment(Path::new(&path), &tools).await
+}
+
+/// Extract an attachment's text and split it into chunks ready for embedding
+///
+/// Returns no chunks when the attachment has no extractable text.
+#[tauri::command]
+pub async fn chunk_attachment(
+    app: AppHandle,
+    path: String,
+    options: Option<ChunkOptions>,
+    file_modified: Option<i64>,
+) -> Result<Vec<MarkdownChunk>, AppError> {
+    crate::fs::ensure_allowed_path(Path::new(&path), true)?;
+    let tools = AttachmentTools::detect(app).await?;
+    let text = extract_attachment(Path::new(&path), &tools).await?;
+    Ok(attachment_chunks(
+        &text,
+        &options.unwrap_or_default(),
+        file_modified,
+    ))
+}
+
+/// Embed many texts (e.g. a whole vault during reindex) with concurrent
+/// batched requests, emitting `embedding:progress` events; vectors are
+/// returned in input order
+#[tauri::command]
+pub async fn embed_texts_batched(
+    app: AppHandle,
+    proxy_state: tauri::State<'_, crate::proxy::ProxyState>,
+    texts: Vec<String>,
+    config: EmbeddingConfig,
+    batch_size: Option<usize>,
+    concurrency: Option<usize>,
+) -> Result<Embeddings, AppError> {
+    let client = proxy_state
+        .client_with_timeout(EMBED_TIMEOUT)
+        .await
+        .map_err(AppError::Network)?;
+    let defaults = BatchOptions::default();
+    let options = BatchOptions {
+        batch_size: batch_size.unwrap_or(defaults.batch_size),
+        concurrency: concurrency.unwrap_or(defaults.concurrency),
+    };
+    embed_concurrently(&client, texts, &config, options, move |progress| {
+        let _ = app.emit("embedding:progress", progress);
+    })
+    .await
+}
+
 /// Clear all vectors (for full reindex)
 #[tauri::command]
 pub async fn clear_vector_index() -> Result<(), AppError> {
@@ -0,0 +1,607 @@
+//! Text embedding for backend-side indexing and vector lookups
+//!
+//! Queries (e.g. the agent's `find_note` tool) and note chunks can be embedded
+//! here, so the index can be rebuilt without the frontend embedder. Supports an
+//! OpenAI-compatible `/embeddings` endpoint and Ollama, mirroring the frontend
+//! embedder. For offline use, point `fallback` at a local Ollama model: it is
+//! tried when the primary provider fails. A fallback model builds its own index;
+//! its query vectors are not compared with vectors from another model. Vectors
+//! are L2-normalized.
+
+use crate::error::AppError;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+/// Texts sent per request
+const BATCH_SIZE: usize = 64;
+/// Tries per batch in [`embed_texts_batched`]
+const MAX_ATTEMPTS: u32 = 4;
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(500);
+
+/// Embedding settings, matching the frontend RAG configuration
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EmbeddingConfig {
+    pub enabled: bool,
+    /// `openai` (any OpenAI-compatible API) or `ollama`
+    pub provider: String,
+    pub model: String,
+    /// API base; empty uses the provider default
+    pub base_url: String,
+    /// May be empty for local servers
+    pub api_key: String,
+    /// Requested vector size, for models that support shortening
+    pub dimensions: Option<usize>,
+    /// Provider tried when this one fails, e.g. a local Ollama model
+    pub fallback: Option<Box<EmbeddingConfig>>,
+}
+
+impl Default for EmbeddingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            provider: "openai".to_string(),
+            model: "text-embedding-3-small".to_string(),
+            base_url: String::new(),
+            api_key: String::new(),
+            dimensions: None,
+            fallback: None,
+        }
+    }
+}
+
+impl EmbeddingConfig {
+    /// Enabled and complete enough to send a request
+    pub fn is_active(&self) -> bool {
+        self.enabled && !self.model.trim().is_empty()
+    }
+
+    fn base_url(&self) -> &str {
+        match self.base_url.trim().trim_end_matches('/') {
+            "" if self.provider == "ollama" => "http://localhost:11434",
+            "" => "https://api.openai.com/v1",
+            base => base,
+        }
+    }
+}
+
+/// Vectors for a batch of texts, with the model that produced them
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Embeddings {
+    pub provider: String,
+    pub model: String,
+    pub dimensions: usize,
+    /// One normalized vector per input text, in input order
+    pub vectors: Vec<Vec<f32>>,
+}
+
+/// Embed a single query; `vectors` holds exactly one vector
+///
+/// The model may be a fallback, so check it against the index (see
+/// [`super::ensure_index_model`]) before comparing the vector with stored ones.
+pub async fn embed_query(text: &str, config: &EmbeddingConfig) -> Result<Embeddings, AppError> {
+    let client = reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .map_err(|e| AppError::Network(format!("Failed to build HTTP client: {}", e)))?;
+    let embeddings = embed_texts(&client, &[text.to_string()], config).await?;
+    if embeddings.vectors.len() != 1 {
+        return Err(AppError::Parse("Embedding response has no vector".into()));
+    }
+    Ok(embeddings)
+}
+
+/// Embed `texts`, falling back to `config.fallback` (and its fallback) when
+/// a provider fails
+pub async fn embed_texts(
+    client: &reqwest::Client,
+    texts: &[String],
+    config: &EmbeddingConfig,
+) -> Result<Embeddings, AppError> {
+    let mut provider = Some(config);
+    let mut errors = Vec::new();
+    while let Some(current) = provider {
+        match embed_with(client, texts, current).await {
+            Ok(vectors) => return Ok(embeddings(current, vectors)),
+            Err(err) => errors.push(format!("{}/{}: {}", current.provider, current.model, err)),
+        }
+        provider = current.fallback.as_deref();
+    }
+    Err(all_failed(errors))
+}
+
+/// Batching and concurrency for [`embed_texts_batched`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BatchOptions {
+    /// Texts per request
+    pub batch_size: usize,
+    /// Requests in flight at once
+    pub concurrency: usize,
+}
+
+impl Default for BatchOptions {
+    fn default() -> Self {
+        Self {
+            batch_size: BATCH_SIZE,
+            concurrency: 4,
+        }
+    }
+}
+
+/// Progress of a batched embedding run, in texts
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct EmbedProgress {
+    pub done: usize,
+    pub total: usize,
+    /// Model currently embedding; changes when falling back
+    pub model: String,
+}
+
+/// Embed many texts with concurrent batched requests
+///
+/// At most `options.concurrency` requests run at once; a batch failing with a
+/// network error or a 429/5xx response is retried with exponential backoff.
+/// Vectors come back in input order. When a batch still fails the whole run
+/// moves on to the fallback provider, so all vectors come from one model.
+pub async fn embed_texts_batched(
+    client: &reqwest::Client,
+    texts: Vec<String>,
+    config: &EmbeddingConfig,
+    options: BatchOptions,
+    on_progress: impl Fn(EmbedProgress) + Send + Sync + 'static,
+) -> Result<Embeddings, AppError> {
+    let texts = Arc::new(texts);
+    let on_progress: Arc<dyn Fn(EmbedProgress) + Send + Sync> = Arc::new(on_progress);
+    let mut provider = Some(config);
+    let mut errors = Vec::new();
+    while let Some(current) = provider {
+        match embed_batched_with(client, &texts, current, options, &on_progress).await {
+            Ok(vectors) => return Ok(embeddings(current, vectors)),
+            Err(err) => errors.push(format!("{}/{}: {}", current.provider, current.model, err)),
+        }
+        provider = current.fallback.as_deref();
+    }
+    Err(all_failed(errors))
+}
+
+fn embeddings(config: &EmbeddingConfig, vectors: Vec<Vec<f32>>) -> Embeddings {
+    Embeddings {
+        provider: config.provider.clone(),
+        model: config.model.clone(),
+        dimensions: vectors.first().map_or(0, Vec::len),
+        vectors,
+    }
+}
+
+fn all_failed(errors: Vec<String>) -> AppError {
+    AppError::Network(format!(
+        "All embedding providers failed: {}",
+        errors.join("; ")
+    ))
+}
+
+/// Embed `texts` with one provider, one batch after another
+async fn embed_with(
+    client: &reqwest::Client,
+    texts: &[String],
+    config: &EmbeddingConfig,
+) -> Result<Vec<Vec<f32>>, AppError> {
+    check_model(config)?;
+    let mut vectors = Vec::with_capacity(texts.len());
+    for batch in texts.chunks(BATCH_SIZE) {
+        vectors.extend(request_batch(client, batch, config).await?);
+    }
+    finish(vectors)
+}
+
+/// Embed `texts` with one provider, batches running concurrently
+async fn embed_batched_with(
+    client: &reqwest::Client,
+    texts: &Arc<Vec<String>>,
+    config: &EmbeddingConfig,
+    options: BatchOptions,
+    on_progress: &Arc<dyn Fn(EmbedProgress) + Send + Sync>,
+) -> Result<Vec<Vec<f32>>, AppError> {
+    check_model(config)?;
+    let total = texts.len();
+    let batch_size = options.batch_size.max(1);
+    let batches = total.div_ceil(batch_size);
+    let semaphore = Arc::new(Semaphore::new(options.concurrency.max(1)));
+    let done = Arc::new(AtomicUsize::new(0));
+    let config = Arc::new(config.clone());
+    on_progress(EmbedProgress {
+        done: 0,
+        total,
+        model: config.model.clone(),
+    });
+
+    // Dropping the set on an early return aborts the remaining batches
+    let mut tasks = JoinSet::new();
+    for index in 0..batches {
+        let range = index * batch_size..((index + 1) * batch_size).min(total);
+        let (client, texts, config) = (client.clone(), texts.clone(), config.clone());
+        let (semaphore, done, on_progress) = (semaphore.clone(), done.clone(), on_progress.clone());
+        tasks.spawn(async move {
+            let _permit = semaphore
+                .acquire_owned()
+                .await
+                .map_err(|_| AppError::Internal("Embedding queue closed".into()))?;
+            let batch = &texts[range];
+            let vectors = request_with_retry(&client, batch, &config).await?;
+            let done = done.fetch_add(batch.len(), Ordering::SeqCst) + batch.len();
+            on_progress(EmbedProgress {
+                done,
+                total,
+                model: config.model.clone(),
+            });
+            Ok::<_, AppError>((index, vectors))
+        });
+    }
+
+    let mut slots: Vec<Vec<Vec<f32>>> = vec![Vec::new(); batches];
+    while let Some(joined) = tasks.join_next().await {
+        let (index, vectors) =
+            joined.map_err(|e| AppError::Internal(format!("Embedding task failed: {}", e)))??;
+        slots[index] = vectors;
+    }
+    finish(slots.into_iter().flatten().collect())
+}
+
+fn check_model(config: &EmbeddingConfig) -> Result<(), AppError> {
+    if config.model.trim().is_empty() {
+        return Err(AppError::InvalidInput(
+            "No embedding model configured".into(),
+        ));
+    }
+    Ok(())
+}
+
+/// Check that all vectors share one size and normalize them
+fn finish(vectors: Vec<Vec<f32>>) -> Result<Vec<Vec<f32>>, AppError> {
+    if let Some(first) = vectors.first() {
+        let dimensions = first.len();
+        if vectors.iter().any(|vector| vector.len() != dimensions) {
+            return Err(AppError::Parse(
+                "Embedding vectors have different sizes".into(),
+            ));
+        }
+    }
+    Ok(vectors.into_iter().map(normalize).collect())
+}
+
+/// [`request_batch`], retrying network errors, rate limits and server errors
+async fn request_with_retry(
+    client: &reqwest::Client,
+    batch: &[String],
+    config: &EmbeddingConfig,
+) -> Result<Vec<Vec<f32>>, AppError> {
+    let mut attempt = 0;
+    loop {
+        match request_batch(client, batch, config).await {
+            Err(AppError::Network(_)) if attempt + 1 < MAX_ATTEMPTS => {
+                tokio::time::sleep(retry_delay(attempt)).await;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Exponential backoff: 0.5s, 1s, 2s, ...
+fn retry_delay(attempt: u32) -> Duration {
+    RETRY_BASE_DELAY * 2u32.pow(attempt.min(6))
+}
+
+/// Embed one batch with a single request (one per text on legacy Ollama)
+async fn request_batch(
+    client: &reqwest::Client,
+    batch: &[String],
+    config: &EmbeddingConfig,
+) -> Result<Vec<Vec<f32>>, AppError> {
+    let base = config.base_url();
+    let vectors = if config.provider == "ollama" {
+        let response = post(
+            client,
+            &format!("{}/api/embed", base),
+            config,
+            json!({ "model": config.model, "input": batch }),
+        )
+        .await?;
+        match response {
+            Some(body) => parse_embeddings(&body)?,
+            // Ollama before 0.2 only has the legacy endpoint, one text per request
+            None => {
+                let mut legacy = Vec::with_capacity(batch.len());
+                for text in batch {
+                    let body = post(
+                        client,
+                        &format!("{}/api/embeddings", base),
+                        config,
+                        json!({ "model": config.model, "prompt": text }),
+                    )
+                    .await?
+                    .ok_or_else(endpoint_not_found)?;
+                    legacy.extend(parse_embeddings(&body)?);
+                }
+                legacy
+            }
+        }
+    } else {
+        let mut request = json!({ "model": config.model, "input": batch });
+        if let Some(dimensions) = config.dimensions {
+            request["dimensions"] = json!(dimensions);
+        }
+        let body = post(client, &format!("{}/embeddings", base), config, request)
+            .await?
+            .ok_or_else(endpoint_not_found)?;
+        parse_embeddings(&body)?
+    };
+    if vectors.len() != batch.len() {
+        return Err(AppError::Parse(format!(
+            "Expected {} embeddings, got {}",
+            batch.len(),
+            vectors.len()
+        )));
+    }
+    Ok(vectors)
+}
+
+fn endpoint_not_found() -> AppError {
+    AppError::InvalidInput("Embedding endpoint not found; check the API base URL".into())
+}
+
+/// POST a JSON request; `None` when the endpoint does not exist
+async fn post(
+    client: &reqwest::Client,
+    url: &str,
+    config: &EmbeddingConfig,
+    body: Value,
+) -> Result<Option<Value>, AppError> {
+    let mut request = client.post(url).json(&body);
+    if !config.api_key.is_empty() {
+        request = request.bearer_auth(&config.api_key);
+    }
+    let response = request
+        .send()
+        .await
+        .map_err(|e| AppError::Network(format!("Embedding request failed: {}", e)))?;
+    let status = response.status();
+    if status == reqwest::StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        let message = format!("Embedding request failed ({}): {}", status, body);
+        // Rate limits and server errors are worth retrying; other statuses
+        // (bad key, unknown model) are not
+        return Err(
+            if status == reqwest::StatusCode::TOO_MANY_REQUESTS || status.is_server_error() {
+                AppError::Network(message)
+            } else {
+                AppError::InvalidInput(message)
+            },
+        );
+    }
+    response
+        .json()
+        .await
+        .map(Some)
+        .map_err(|e| AppError::Parse(format!("Invalid embedding response: {}", e)))
+}
+
+/// Read the vectors from any of the supported response shapes:
+/// OpenAI `data[].embedding` (ordered by `index`), Ollama `embeddings[]` or
+/// legacy `embedding`
+fn parse_embeddings(body: &Value) -> Result<Vec<Vec<f32>>, AppError> {
+    let vectors: Vec<&Value> = if let Some(data) = body.get("data").and_then(Value::as_array) {
+        let mut data: Vec<&Value> = data.iter().collect();
+        data.sort_by_key(|item| item.get("index").and_then(Value::as_u64));
+        data.into_iter()
+            .filter_map(|item| item.get("embedding"))
+            .collect()
+    } else if let Some(embeddings) = body.get("embeddings").and_then(Value::as_array) {
+        embeddings.iter().collect()
+    } else {
+        body.get("embedding").into_iter().collect()
+    };
+    if vectors.is_empty() {
+        return Err(AppError::Parse("Embedding response has no vector".into()));
+    }
+    vectors.into_iter().map(parse_vector).collect()
+}
+
+fn parse_vector(vector: &Value) -> Result<Vec<f32>, AppError> {
+    vector
+        .as_array()
+        .and_then(|values| {
+            values
+                .iter()
+                .map(|value| value.as_f64().map(|value| value as f32))
+                .collect::<Option<Vec<f32>>>()
+        })
+        .filter(|vector| !vector.is_empty())
+        .ok_or_else(|| AppError::Parse("Embedding vector is empty or not numeric".into()))
+}
+
+/// Scale to unit length, so dot products are cosine similarities
+fn normalize(mut vector: Vec<f32>) -> Vec<f32> {
+    let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        for x in &mut vector {
+            *x /= norm;
+        }
+    }
+    vector
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_openai_and_ollama_responses() {
+        let openai = json!({ "data": [
+            { "index": 1, "embedding": [2.0] },
+            { "index": 0, "embedding": [0.5, -1.0] }
+        ] });
+        let ollama = json!({ "embeddings": [[0.25, 0.75], [1.0, 0.0]] });
+        let legacy = json!({ "embedding": [1.0] });
+        assert_eq!(
+            parse_embeddings(&openai).unwrap(),
+            vec![vec![0.5, -1.0], vec![2.0]]
+        );
+        assert_eq!(
+            parse_embeddings(&ollama).unwrap(),
+            vec![vec![0.25, 0.75], vec![1.0, 0.0]]
+        );
+        assert_eq!(parse_embeddings(&legacy).unwrap(), vec![vec![1.0]]);
+        assert!(parse_embeddings(&json!({ "data": [] })).is_err());
+        assert!(parse_embeddings(&json!({ "embedding": [] })).is_err());
+    }
+
+    #[test]
+    fn backs_off_exponentially() {
+        assert_eq!(retry_delay(0), Duration::from_millis(500));
+        assert_eq!(retry_delay(2), Duration::from_secs(2));
+    }
+
+    #[tokio::test]
+    async fn batched_embedding_keeps_input_order() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        // Minimal OpenAI-compatible server: each text "n" embeds to [n, 1]
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                tokio::spawn(async move {
+                    let mut request = Vec::new();
+                    let mut buf = [0u8; 4096];
+                    let body = loop {
+                        let n = socket.read(&mut buf).await.unwrap();
+                        request.extend_from_slice(&buf[..n]);
+                        let text = String::from_utf8_lossy(&request).to_string();
+                        if let Some((head, body)) = text.split_once("\r\n\r\n") {
+                            let length = head
+                                .lines()
+                                .find_map(|line| {
+                                    let (name, value) = line.split_once(':')?;
+                                    name.eq_ignore_ascii_case("content-length")
+                                        .then(|| value.trim().parse::<usize>().ok())?
+                                })
+                                .unwrap_or(0);
+                            if body.len() >= length {
+                                break body.to_string();
+                            }
+                        }
+                    };
+                    let input: Value = serde_json::from_str(&body).unwrap();
+                    let data: Vec<Value> = input["input"]
+                        .as_array()
+                        .unwrap()
+                        .iter()
+                        .enumerate()
+                        .map(|(index, text)| {
+                            let n: f32 = text.as_str().unwrap().parse().unwrap();
+                            json!({ "index": index, "embedding": [n, 1.0] })
+                        })
+                        .collect();
+                    // Later batches answer first
+                    let first: f32 = input["input"][0].as_str().unwrap().parse().unwrap();
+                    tokio::time::sleep(Duration::from_millis(50 - first as u64 * 5)).await;
+                    let body = json!({ "data": data }).to_string();
+                    let response = format!(
+                        "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                        body.len(),
+                        body
+                    );
+                    socket.write_all(response.as_bytes()).await.unwrap();
+                });
+            }
+        });
+
+        let config = EmbeddingConfig {
+            enabled: true,
+            base_url,
+            ..EmbeddingConfig::default()
+        };
+        let texts: Vec<String> = (0..7).map(|n| n.to_string()).collect();
+        let progress = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let seen = progress.clone();
+        let result = embed_texts_batched(
+            &reqwest::Client::new(),
+            texts,
+            &config,
+            BatchOptions {
+                batch_size: 2,
+                concurrency: 3,
+            },
+            move |update| seen.lock().unwrap().push(update.done),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(result.dimensions, 2);
+        let firsts: Vec<f32> = result
+            .vectors
+            .iter()
+            .map(|vector| (vector[0] / vector[1] * 100.0).round() / 100.0)
+            .collect();
+        assert_eq!(firsts, vec![0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        let progress = progress.lock().unwrap();
+        assert_eq!(progress.first(), Some(&0));
+        assert_eq!(progress.iter().max(), Some(&7));
+        assert_eq!(progress.len(), 5);
+    }
+
+    #[test]
+    fn normalizes_to_unit_length() {
+        assert_eq!(normalize(vec![3.0, 4.0]), vec![0.6, 0.8]);
+        assert_eq!(normalize(vec![0.0, 0.0]), vec![0.0, 0.0]);
+    }
+
+    #[tokio::test]
+    async fn reports_every_failed_provider() {
+        let config = EmbeddingConfig {
+            model: " ".to_string(),
+            fallback: Some(Box::new(EmbeddingConfig {
+                provider: "ollama".to_string(),
+                model: String::new(),
+                ..EmbeddingConfig::default()
+            })),
+            ..EmbeddingConfig::default()
+        };
+        let err = embed_texts(&reqwest::Client::new(), &["a".to_string()], &config)
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.contains("openai/ :") && err.contains("ollama/:"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn defaults_base_url_per_provider() {
+        let openai = EmbeddingConfig::default();
+        assert_eq!(openai.base_url(), "https://api.openai.com/v1");
+        let ollama = EmbeddingConfig {
+            provider: "ollama".to_string(),
+            ..EmbeddingConfig::default()
+        };
+        assert_eq!(ollama.base_url(), "http://localhost:11434");
+        let custom = EmbeddingConfig {
+            base_url: "http://host/v1/".to_string(),
+            ..EmbeddingConfig::default()
+        };
+        assert_eq!(custom.base_url(), "http://host/v1");
+    }
+}
@@ -1,9 +1,16 @@
 //! Vector Database Module
 //!
 //! SQLite-based vector storage for RAG system.
-//! Uses bincode for efficient vector serialization.
+//! Uses bincode for efficient vector serialization. The embedding model and
+//! vector size are recorded with the index, so vectors from a different model
+//! are rejected instead of silently scoring zero.
 
+pub mod attachments;
+pub mod chunking;
 pub mod commands;
+pub mod embedding;
+pub mod reindex;
+pub mod rerank;
 
 use crate::error::AppError;
 use rusqlite::{params, Connection};
@@ -37,6 +44,18 @@ pub struct SearchResult {
     pub end_line: i32,
 }
 
+/// Search result restricted to a workspace sub-folder
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ScopedSearchResult {
+    /// Scope as given by the caller (None = whole workspace)
+    pub scope: Option<String>,
+    pub results: Vec<SearchResult>,
+    /// Number of indexed chunks inside the scope
+    pub indexed_chunks: i64,
+    /// Explanation when the result is empty because the scope is not indexed
+    pub message: Option<String>,
+}
+
 /// Index status information
 #[derive(Debug, Serialize, Deserialize)]
 pub struct IndexStatus {
@@ -44,6 +63,16 @@ pub struct IndexStatus {
     pub total_chunks: i64,
     pub total_files: i64,
     pub last_indexed: Option<i64>,
+    pub embedding_model: Option<String>,
+    pub embedding_dimensions: Option<usize>,
+}
+
+/// Embedding model the index was built with
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EmbeddingMeta {
+    /// `None` when the vectors were stored without naming their model
+    pub model: Option<String>,
+    pub dimensions: usize,
 }
 
 /// Global database connection (lazily initialized per workspace)
@@ -78,6 +107,15 @@ pub fn init_db(db_path: &str) -> Result<(), AppError> {
     )
     .map_err(|e| AppError::Database(format!("Failed to create index: {}", e)))?;
 
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS index_meta (
+            key TEXT PRIMARY KEY,
+            value TEXT NOT NULL
+        )",
+        [],
+    )
+    .map_err(|e| AppError::Database(format!("Failed to create index_meta table: {}", e)))?;
+
     // Store connection
     let mut db = DB_CONNECTION
         .lock()
@@ -88,7 +126,10 @@ pub fn init_db(db_path: &str) -> Result<(), AppError> {
 }
 
 /// Insert or update vectors
-pub fn upsert_vectors(chunks: Vec<VectorChunk>) -> Result<(), AppError> {
+///
+/// `model` names the embedding model; vectors whose model or size differ from
+/// what the (non-empty) index was built with are rejected.
+pub fn upsert_vectors(chunks: Vec<VectorChunk>, model: Option<&str>) -> Result<(), AppError> {
     let db = DB_CONNECTION
         .lock()
         .map_err(|_| AppError::Database("Lock poisoned".into()))?;
@@ -96,6 +137,32 @@ pub fn upsert_vectors(chunks: Vec<VectorChunk>) -> Result<(), AppError> {
         .as_ref()
         .ok_or_else(|| AppError::Database("Database not initialized".into()))?;
 
+    let Some(dimensions) = chunks.first().map(|chunk| chunk.vector.len()) else {
+        return Ok(());
+    };
+    if chunks.iter().any(|chunk| chunk.vector.len() != dimensions) {
+        return Err(AppError::InvalidInput(
+            "Vectors in one batch have different sizes".into(),
+        ));
+    }
+    let incoming = EmbeddingMeta {
+        model: model.map(str::to_string),
+        dimensions,
+    };
+    let is_empty = conn
+        .query_row("SELECT COUNT(*) FROM vectors", [], |row| {
+            row.get::<_, i64>(0)
+        })
+        .map(|count| count == 0)
+        .unwrap_or(true);
+    let stored = if is_empty {
+        None
+    } else {
+        read_embedding_meta(conn)
+    };
+    let meta = merge_embedding_meta(stored, incoming)?;
+    write_embedding_meta(conn, &meta)?;
+
     for chunk in chunks {
         let vector_blob = bincode::serialize(&chunk.vector)
             .map_err(|e| AppError::Database(format!("Failed to serialize vector: {}", e)))?;
@@ -121,6 +188,76 @@ pub fn upsert_vectors(chunks: Vec<VectorChunk>) -> Result<(), AppError> {
     Ok(())
 }
 
+/// Check that a query vector comes from the model the index was built with
+///
+/// Vectors from another model are not comparable even when their sizes match,
+/// e.g. after the primary embedding provider failed over to its fallback.
+pub fn ensure_index_model(model: &str, dimensions: usize) -> Result<(), AppError> {
+    let db = DB_CONNECTION
+        .lock()
+        .map_err(|_| AppError::Database("Lock poisoned".into()))?;
+    let conn = db
+        .as_ref()
+        .ok_or_else(|| AppError::Database("Database not initialized".into()))?;
+    let incoming = EmbeddingMeta {
+        model: Some(model.to_string()),
+        dimensions,
+    };
+    merge_embedding_meta(read_embedding_meta(conn), incoming).map(|_| ())
+}
+
+/// Check incoming vectors against the stored model; the merged metadata
+/// keeps a known model name when the incoming vectors do not name one
+fn merge_embedding_meta(
+    stored: Option<EmbeddingMeta>,
+    incoming: EmbeddingMeta,
+) -> Result<EmbeddingMeta, AppError> {
+    let Some(stored) = stored else {
+        return Ok(incoming);
+    };
+    let model_differs = matches!(
+        (&stored.model, &incoming.model),
+        (Some(stored), Some(incoming)) if stored != incoming
+    );
+    if stored.dimensions != incoming.dimensions || model_differs {
+        let describe = |meta: &EmbeddingMeta| match &meta.model {
+            Some(model) => format!("{} ({} dimensions)", model, meta.dimensions),
+            None => format!("{} dimensions", meta.dimensions),
+        };
+        return Err(AppError::InvalidInput(format!(
+            "Embedding model mismatch: the index was built with {}, got {}. Rebuild the index after changing the embedding model.",
+            describe(&stored),
+            describe(&incoming)
+        )));
+    }
+    Ok(EmbeddingMeta {
+        model: incoming.model.or(stored.model),
+        dimensions: stored.dimensions,
+    })
+}
+
+fn read_embedding_meta(conn: &Connection) -> Option<EmbeddingMeta> {
+    let value: String = conn
+        .query_row(
+            "SELECT value FROM index_meta WHERE key = 'embedding'",
+            [],
+            |row| row.get(0),
+        )
+        .ok()?;
+    serde_json::from_str(&value).ok()
+}
+
+fn write_embedding_meta(conn: &Connection, meta: &EmbeddingMeta) -> Result<(), AppError> {
+    let value = serde_json::to_string(meta)
+        .map_err(|e| AppError::Database(format!("Failed to serialize index metadata: {}", e)))?;
+    conn.execute(
+        "INSERT OR REPLACE INTO index_meta (key, value) VALUES ('embedding', ?1)",
+        params![value],
+    )
+    .map_err(|e| AppError::Database(format!("Failed to write index metadata: {}", e)))?;
+    Ok(())
+}
+
 /// Row data from query
 type VectorRow = (String, Vec<u8>, String, String, String, i32, i32);
 
@@ -167,8 +304,8 @@ pub fn search_vectors(
 
     // Collect all matching rows
     let all_rows = if let Some(ref dir) = directory_filter {
-        let sql = "SELECT id, vector, content, file_path, heading, start_line, end_line FROM vectors WHERE file_path LIKE ?1";
-        let pattern = format!("{}%", dir);
+        let sql = "SELECT id, vector, content, file_path, heading, start_line, end_line FROM vectors WHERE file_path LIKE ?1 ESCAPE '\\'";
+        let pattern = like_prefix_pattern(dir);
         collect_rows(conn, sql, &[&pattern])?
     } else {
         let sql =
@@ -211,6 +348,136 @@ pub fn search_vectors(
     Ok(results.into_iter().take(limit).map(|(_, r)| r).collect())
 }
 
+/// Search vectors inside a workspace sub-folder
+///
+/// `scope` may be relative to the workspace or absolute, but must stay inside
+/// it. A scope without indexed chunks yields an empty, labelled result rather
+/// than an error.
+pub fn search_vectors_in_scope(
+    query_vector: Vec<f32>,
+    limit: usize,
+    min_score: f32,
+    workspace_root: &str,
+    scope: Option<String>,
+) -> Result<ScopedSearchResult, AppError> {
+    let scope = scope
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty());
+    let directory_filter = match scope.as_deref() {
+        Some(scope) => resolve_scope_filter(workspace_root, scope)?,
+        None => resolve_scope_filter(workspace_root, ".")?,
+    };
+
+    let indexed_chunks = count_chunks_with_prefix(&directory_filter)?;
+    if indexed_chunks == 0 {
+        let label = scope.as_deref().unwrap_or("workspace");
+        return Ok(ScopedSearchResult {
+            message: Some(format!(
+                "No indexed notes under '{}'. The folder may be empty or not indexed yet.",
+                label
+            )),
+            scope,
+            results: Vec::new(),
+            indexed_chunks,
+        });
+    }
+
+    let results = search_vectors(query_vector, limit, min_score, Some(directory_filter))?;
+    Ok(ScopedSearchResult {
+        scope,
+        results,
+        indexed_chunks,
+        message: None,
+    })
+}
+
+/// Resolve a search scope into the `directory_filter` prefix used by
+/// [`search_vectors`]. The prefix ends with a separator so `Projects/Alpha`
+/// does not also match `Projects/AlphaBeta`.
+pub fn resolve_scope_filter(workspace_root: &str, scope: &str) -> Result<String, AppError> {
+    let root = workspace_root.trim_end_matches(['/', '\\']);
+    if root.is_empty() {
+        return Err(AppError::InvalidPath("Workspace path is empty".into()));
+    }
+    let separator = if root.contains('\\') && !root.contains('/') {
+        '\\'
+    } else {
+        '/'
+    };
+
+    let scope = scope.trim();
+    let relative = match scope.strip_prefix(root) {
+        Some(rest) if rest.is_empty() || rest.starts_with(['/', '\\']) => rest,
+        _ if is_absolute_path(scope) => {
+            return Err(AppError::InvalidPath(format!(
+                "Scope is outside the workspace: {}",
+                scope
+            )))
+        }
+        _ => scope,
+    };
+
+    let mut segments: Vec<&str> = Vec::new();
+    for segment in relative.split(['/', '\\']) {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                if segments.pop().is_none() {
+                    return Err(AppError::InvalidPath(format!(
+                        "Scope is outside the workspace: {}",
+                        scope
+                    )));
+                }
+            }
+            other => segments.push(other),
+        }
+    }
+
+    let mut filter = root.to_string();
+    for segment in segments {
+        filter.push(separator);
+        filter.push_str(segment);
+    }
+    filter.push(separator);
+    Ok(filter)
+}
+
+fn is_absolute_path(path: &str) -> bool {
+    let bytes = path.as_bytes();
+    path.starts_with(['/', '\\'])
+        || (bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':')
+}
+
+/// Build a LIKE pattern matching paths that start with `prefix`
+fn like_prefix_pattern(prefix: &str) -> String {
+    let mut pattern = String::with_capacity(prefix.len() + 1);
+    for ch in prefix.chars() {
+        if matches!(ch, '\\' | '%' | '_') {
+            pattern.push('\\');
+        }
+        pattern.push(ch);
+    }
+    pattern.push('%');
+    pattern
+}
+
+/// Count indexed chunks whose file path starts with `prefix`
+fn count_chunks_with_prefix(prefix: &str) -> Result<i64, AppError> {
+    let db = DB_CONNECTION
+        .lock()
+        .map_err(|_| AppError::Database("Lock poisoned".into()))?;
+    let conn = db
+        .as_ref()
+        .ok_or_else(|| AppError::Database("Database not initialized".into()))?;
+
+    conn.query_row(
+        "SELECT COUNT(*) FROM vectors WHERE file_path LIKE ?1 ESCAPE '\\'",
+        params![like_prefix_pattern(prefix)],
+        |row| row.get(0),
+    )
+    .map_err(|e| AppError::Database(format!("Failed to count vectors: {}", e)))
+}
+
 /// Delete vectors by file path
 pub fn delete_vectors_by_file(file_path: &str) -> Result<(), AppError> {
     let db = DB_CONNECTION
@@ -271,6 +538,8 @@ pub fn get_index_status() -> Result<IndexStatus, AppError> {
             total_chunks: 0,
             total_files: 0,
             last_indexed: None,
+            embedding_model: None,
+            embedding_dimensions: None,
         });
     }
 
@@ -290,11 +559,15 @@ pub fn get_index_status() -> Result<IndexStatus, AppError> {
         .query_row("SELECT MAX(created_at) FROM vectors", [], |row| row.get(0))
         .ok();
 
+    let meta = read_embedding_meta(conn).filter(|_| total_chunks > 0);
+
     Ok(IndexStatus {
         initialized: true,
         total_chunks,
         total_files,
         last_indexed,
+        embedding_model: meta.as_ref().and_then(|meta| meta.model.clone()),
+        embedding_dimensions: meta.map(|meta| meta.dimensions),
     })
 }
 
@@ -331,6 +604,8 @@ pub fn clear_all_vectors() -> Result<(), AppError> {
 
     conn.execute("DELETE FROM vectors", [])
         .map_err(|e| AppError::Database(format!("Failed to clear vectors: {}", e)))?;
+    conn.execute("DELETE FROM index_meta WHERE key = 'embedding'", [])
+        .map_err(|e| AppError::Database(format!("Failed to clear index metadata: {}", e)))?;
 
     Ok(())
 }
@@ -351,3 +626,64 @@ fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
         dot / (norm_a * norm_b)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_relative_and_absolute_scopes() {
+        assert_eq!(
+            resolve_scope_filter("/home/me/notes", "Projects/Alpha").unwrap(),
+            "/home/me/notes/Projects/Alpha/"
+        );
+        assert_eq!(
+            resolve_scope_filter("/home/me/notes/", "/home/me/notes/Projects/./Alpha/").unwrap(),
+            "/home/me/notes/Projects/Alpha/"
+        );
+        assert_eq!(
+            resolve_scope_filter("C:\\Notes", "Projects/Alpha").unwrap(),
+            "C:\\Notes\\Projects\\Alpha\\"
+        );
+        assert_eq!(
+            resolve_scope_filter("/home/me/notes", ".").unwrap(),
+            "/home/me/notes/"
+        );
+    }
+
+    #[test]
+    fn rejects_scopes_outside_workspace() {
+        assert!(resolve_scope_filter("/home/me/notes", "../other").is_err());
+        assert!(resolve_scope_filter("/home/me/notes", "Projects/../../other").is_err());
+        assert!(resolve_scope_filter("/home/me/notes", "/home/me/notes-old/a").is_err());
+        assert!(resolve_scope_filter("/home/me/notes", "D:\\elsewhere").is_err());
+    }
+
+    #[test]
+    fn rejects_vectors_from_a_different_model() {
+        let meta = |model: Option<&str>, dimensions| EmbeddingMeta {
+            model: model.map(str::to_string),
+            dimensions,
+        };
+        let stored = meta(Some("text-embedding-3-small"), 1536);
+
+        assert_eq!(
+            merge_embedding_meta(Some(stored.clone()), meta(None, 1536)).unwrap(),
+            stored
+        );
+        assert_eq!(
+            merge_embedding_meta(None, meta(Some("bge-m3"), 1024)).unwrap(),
+            meta(Some("bge-m3"), 1024)
+        );
+        assert!(merge_embedding_meta(Some(stored.clone()), meta(None, 1024)).is_err());
+        let err = merge_embedding_meta(Some(stored), meta(Some("bge-m3"), 1536)).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("text-embedding-3-small (1536 dimensions)"));
+    }
+
+    #[test]
+    fn like_pattern_escapes_wildcards() {
+        assert_eq!(like_prefix_pattern("/a_b/100%/"), "/a\\_b/100\\%/%");
+    }
+}
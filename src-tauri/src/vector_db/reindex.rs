@@ -0,0 +1,195 @@
+//! Incremental reindex planning
+//!
+//! Diffs the Markdown files in a workspace against the `file_modified`
+//! column of the vector index, so only new, changed and deleted notes need
+//! to be processed after a sync.
+//!
+//! Attachments (PDFs, images) are indexed when a note embeds them, so they
+//! never show up in `add`; once indexed they are updated and removed like
+//! notes.
+
+use super::attachments::is_attachment;
+use super::{like_prefix_pattern, resolve_scope_filter, DB_CONNECTION};
+use crate::error::AppError;
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+use walkdir::WalkDir;
+
+/// A file to (re)index together with its modification time (unix ms)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReindexFile {
+    pub path: String,
+    pub modified: i64,
+}
+
+/// Work needed to bring the vector index in line with the workspace
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReindexPlan {
+    /// On disk but not indexed
+    pub add: Vec<ReindexFile>,
+    /// Indexed, but modified since (or indexed without a modification time)
+    pub update: Vec<ReindexFile>,
+    /// Indexed but no longer on disk; their vectors should be deleted
+    pub remove: Vec<String>,
+    /// Indexed and up to date
+    pub unchanged: usize,
+}
+
+/// Compute the reindex plan for `workspace_root`
+///
+/// The index is read in a single query while holding the database lock and
+/// the workspace is walked afterwards, so the plan never blocks concurrent
+/// searches or upserts for long. A file written after the plan was computed
+/// keeps a newer mtime than its stored one and is picked up by the next plan.
+pub fn compute_plan(workspace_root: &str) -> Result<ReindexPlan, AppError> {
+    let prefix = resolve_scope_filter(workspace_root, ".")?;
+    let indexed = indexed_files(&prefix)?;
+    let mut on_disk = markdown_files(Path::new(workspace_root));
+    on_disk.extend(indexed_attachments(&indexed));
+    Ok(diff_index(on_disk, indexed))
+}
+
+/// Indexed attachments that still exist, with their modification times
+fn indexed_attachments(indexed: &HashMap<String, Option<i64>>) -> HashMap<String, i64> {
+    indexed
+        .keys()
+        .filter(|path| is_attachment(Path::new(path)))
+        .filter_map(|path| {
+            let modified = std::fs::metadata(path).ok()?.modified().ok()?;
+            let millis = modified.duration_since(UNIX_EPOCH).ok()?.as_millis() as i64;
+            Some((path.clone(), millis))
+        })
+        .collect()
+}
+
+/// Latest stored modification time per indexed file under `prefix`
+fn indexed_files(prefix: &str) -> Result<HashMap<String, Option<i64>>, AppError> {
+    let db = DB_CONNECTION
+        .lock()
+        .map_err(|_| AppError::Database("Lock poisoned".into()))?;
+    let conn = db
+        .as_ref()
+        .ok_or_else(|| AppError::Database("Database not initialized".into()))?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT file_path, MIN(file_modified) FROM vectors
+             WHERE file_path LIKE ?1 ESCAPE '\\' GROUP BY file_path",
+        )
+        .map_err(|e| AppError::Database(format!("Failed to prepare query: {}", e)))?;
+    let rows = stmt
+        .query_map(params![like_prefix_pattern(prefix)], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, Option<i64>>(1)?))
+        })
+        .map_err(|e| AppError::Database(format!("Failed to query vectors: {}", e)))?;
+
+    let mut indexed = HashMap::new();
+    for row in rows {
+        let (path, modified) =
+            row.map_err(|e| AppError::Database(format!("Failed to read row: {}", e)))?;
+        indexed.insert(path, modified);
+    }
+    Ok(indexed)
+}
+
+/// Markdown files under `root` with their modification times (unix ms)
+///
+/// Hidden directories (including `.lumina`), `node_modules` and `target` are
+/// skipped.
+fn markdown_files(root: &Path) -> HashMap<String, i64> {
+    WalkDir::new(root)
+        .into_iter()
+        .filter_entry(|entry| {
+            let name = entry.file_name().to_string_lossy();
+            entry.depth() == 0
+                || !(name.starts_with('.') || name == "node_modules" || name == "target")
+        })
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            entry.file_type().is_file()
+                && entry.path().extension().and_then(|ext| ext.to_str()) == Some("md")
+        })
+        .filter_map(|entry| {
+            let modified = entry.metadata().ok()?.modified().ok()?;
+            let millis = modified.duration_since(UNIX_EPOCH).ok()?.as_millis() as i64;
+            Some((entry.path().to_string_lossy().to_string(), millis))
+        })
+        .collect()
+}
+
+fn diff_index(
+    on_disk: HashMap<String, i64>,
+    mut indexed: HashMap<String, Option<i64>>,
+) -> ReindexPlan {
+    let mut plan = ReindexPlan::default();
+    for (path, modified) in on_disk {
+        match indexed.remove(&path) {
+            None => plan.add.push(ReindexFile { path, modified }),
+            Some(Some(stored)) if stored >= modified => plan.unchanged += 1,
+            Some(_) => plan.update.push(ReindexFile { path, modified }),
+        }
+    }
+    plan.remove = indexed.into_keys().collect();
+
+    plan.add.sort_by(|a, b| a.path.cmp(&b.path));
+    plan.update.sort_by(|a, b| a.path.cmp(&b.path));
+    plan.remove.sort();
+    plan
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diffs_disk_against_index() {
+        let on_disk = HashMap::from([
+            ("/v/new.md".to_string(), 10),
+            ("/v/changed.md".to_string(), 20),
+            ("/v/same.md".to_string(), 30),
+            ("/v/legacy.md".to_string(), 40),
+        ]);
+        let indexed = HashMap::from([
+            ("/v/changed.md".to_string(), Some(15)),
+            ("/v/same.md".to_string(), Some(30)),
+            ("/v/legacy.md".to_string(), None),
+            ("/v/gone.md".to_string(), Some(5)),
+        ]);
+
+        let plan = diff_index(on_disk, indexed);
+        assert_eq!(
+            plan.add,
+            vec![ReindexFile {
+                path: "/v/new.md".to_string(),
+                modified: 10
+            }]
+        );
+        assert_eq!(
+            plan.update
+                .iter()
+                .map(|file| file.path.as_str())
+                .collect::<Vec<_>>(),
+            vec!["/v/changed.md", "/v/legacy.md"]
+        );
+        assert_eq!(plan.remove, vec!["/v/gone.md".to_string()]);
+        assert_eq!(plan.unchanged, 1);
+    }
+
+    #[test]
+    fn walks_markdown_files_skipping_hidden_dirs() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir_all(dir.path().join("notes")).unwrap();
+        std::fs::create_dir_all(dir.path().join(".lumina/trash")).unwrap();
+        std::fs::write(dir.path().join("notes/a.md"), "a").unwrap();
+        std::fs::write(dir.path().join("notes/b.txt"), "b").unwrap();
+        std::fs::write(dir.path().join(".lumina/trash/c.md"), "c").unwrap();
+
+        let files = markdown_files(dir.path());
+        assert_eq!(files.len(), 1);
+        let a = dir.path().join("notes/a.md").to_string_lossy().to_string();
+        assert!(files[&a] > 0);
+    }
+}
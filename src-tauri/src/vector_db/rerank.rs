@@ -0,0 +1,215 @@
+//! Cross-encoder re-ranking of vector search results
+//!
+//! Embedding similarity scores the query and each chunk independently; a
+//! cross-encoder reads every (query, chunk) pair together and ranks far more
+//! precisely. The reranker calls an OpenAI-compatible `/rerank` endpoint
+//! (SiliconFlow, Jina, or a local TEI / Xinference server) and sends at most
+//! `max_candidates` pairs per query to bound cost and latency.
+
+use super::SearchResult;
+use crate::error::AppError;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Reranker settings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RerankConfig {
+    pub enabled: bool,
+    pub model: String,
+    /// API base, `/rerank` is appended
+    pub base_url: String,
+    /// May be empty for local servers
+    pub api_key: String,
+    /// Results kept after reranking
+    pub top_n: usize,
+    /// Candidates sent to the reranker; the rest are dropped
+    pub max_candidates: usize,
+}
+
+impl Default for RerankConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            model: "BAAI/bge-reranker-v2-m3".to_string(),
+            base_url: "https://api.siliconflow.cn/v1".to_string(),
+            api_key: String::new(),
+            top_n: 5,
+            max_candidates: 20,
+        }
+    }
+}
+
+impl RerankConfig {
+    /// Enabled and complete enough to send a request
+    pub fn is_active(&self) -> bool {
+        self.enabled && !self.model.trim().is_empty() && !self.base_url.trim().is_empty()
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct RerankRequest<'a> {
+    model: &'a str,
+    query: &'a str,
+    documents: &'a [&'a str],
+    top_n: usize,
+    return_documents: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct RerankResponse {
+    results: Vec<RerankHit>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RerankHit {
+    index: usize,
+    #[serde(alias = "relevanceScore", alias = "score")]
+    relevance_score: f32,
+}
+
+/// Score `documents` against `query`
+///
+/// Returns `(index, score)` pairs, best first, at most `top_n` of them. Only
+/// the first `max_candidates` documents are sent.
+pub async fn rerank_documents(
+    query: &str,
+    documents: &[&str],
+    config: &RerankConfig,
+) -> Result<Vec<(usize, f32)>, AppError> {
+    let documents = &documents[..documents.len().min(config.max_candidates.max(1))];
+    if documents.is_empty() {
+        return Ok(Vec::new());
+    }
+    let top_n = config.top_n.clamp(1, documents.len());
+
+    let client = reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .map_err(|e| AppError::Network(format!("Failed to build HTTP client: {}", e)))?;
+    let mut request = client
+        .post(format!("{}/rerank", config.base_url.trim_end_matches('/')))
+        .json(&RerankRequest {
+            model: &config.model,
+            query,
+            documents,
+            top_n,
+            return_documents: false,
+        });
+    if !config.api_key.is_empty() {
+        request = request.bearer_auth(&config.api_key);
+    }
+
+    let response = request
+        .send()
+        .await
+        .map_err(|e| AppError::Network(format!("Rerank request failed: {}", e)))?;
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(AppError::Network(format!(
+            "Rerank request failed ({}): {}",
+            status, body
+        )));
+    }
+    let body: RerankResponse = response
+        .json()
+        .await
+        .map_err(|e| AppError::Parse(format!("Invalid rerank response: {}", e)))?;
+    Ok(normalize_ranking(body.results, documents.len(), top_n))
+}
+
+/// Drop out-of-range and duplicate indices, sort best first, keep `top_n`
+fn normalize_ranking(hits: Vec<RerankHit>, count: usize, top_n: usize) -> Vec<(usize, f32)> {
+    let mut ranking: Vec<(usize, f32)> = hits
+        .into_iter()
+        .filter(|hit| hit.index < count)
+        .map(|hit| (hit.index, hit.relevance_score))
+        .collect();
+    ranking.sort_by(|a, b| b.1.total_cmp(&a.1));
+    let mut seen = vec![false; count];
+    ranking.retain(|(index, _)| !std::mem::replace(&mut seen[*index], true));
+    ranking.truncate(top_n);
+    ranking
+}
+
+/// Reorder `candidates` by `ranking`, replacing each score with the
+/// reranker's; candidates missing from the ranking are dropped
+pub fn apply_ranking<T>(
+    candidates: Vec<T>,
+    ranking: &[(usize, f32)],
+    mut set_score: impl FnMut(&mut T, f32),
+) -> Vec<T> {
+    let mut slots: Vec<Option<T>> = candidates.into_iter().map(Some).collect();
+    ranking
+        .iter()
+        .filter_map(|&(index, score)| {
+            let mut item = slots.get_mut(index)?.take()?;
+            set_score(&mut item, score);
+            Some(item)
+        })
+        .collect()
+}
+
+/// Rerank vector search results with a cross-encoder
+pub async fn rerank(
+    query: &str,
+    candidates: Vec<SearchResult>,
+    config: &RerankConfig,
+) -> Result<Vec<SearchResult>, AppError> {
+    if !config.is_active() || candidates.is_empty() {
+        return Ok(candidates);
+    }
+    let documents: Vec<&str> = candidates.iter().map(|c| c.content.as_str()).collect();
+    let ranking = rerank_documents(query, &documents, config).await?;
+    Ok(apply_ranking(candidates, &ranking, |result, score| {
+        result.score = score
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hit(index: usize, relevance_score: f32) -> RerankHit {
+        RerankHit {
+            index,
+            relevance_score,
+        }
+    }
+
+    #[test]
+    fn ranking_is_sorted_deduplicated_and_capped() {
+        let ranking = normalize_ranking(
+            vec![
+                hit(0, 0.2),
+                hit(2, 0.9),
+                hit(7, 1.0),
+                hit(1, 0.5),
+                hit(2, 0.1),
+            ],
+            3,
+            2,
+        );
+        assert_eq!(ranking, vec![(2, 0.9), (1, 0.5)]);
+    }
+
+    #[test]
+    fn applies_ranking_to_candidates() {
+        let reordered = apply_ranking(
+            vec![("a", 0.0), ("b", 0.0), ("c", 0.0)],
+            &[(2, 0.8), (0, 0.3)],
+            |item, score| item.1 = score,
+        );
+        assert_eq!(reordered, vec![("c", 0.8), ("a", 0.3)]);
+    }
+
+    #[test]
+    fn accepts_camel_case_scores() {
+        let response: RerankResponse =
+            serde_json::from_str(r#"{"results":[{"index":1,"relevanceScore":0.7}]}"#).unwrap();
+        assert_eq!(response.results[0].relevance_score, 0.7);
+    }
+}
@@ -0,0 +1,449 @@
+//! 自动同步
+//!
+//! 订阅文件监听器的 `fs:changes` 批量事件，去抖后只同步发生变更的路径；
+//! 另按 `sync_interval_secs` 定期做一次快速同步，拉取远程的变更。
+//! 同步自身写入的文件（下载）会被记录，监听器再报告它们时直接忽略，
+//! 避免下载触发上传的回环。服务器不可达时按指数退避重试，支持手动暂停/恢复。
+//! 需要前端已通过 `start_file_watcher` 启动监听。
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+use tauri::{AppHandle, Emitter, EventId, Listener};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use tokio::time::Instant;
+
+use super::sync::SyncEngine;
+use super::types::{SyncResult, WebDAVConfig};
+use crate::fs::watcher::FS_CHANGES_EVENT;
+
+/// 自动同步状态事件
+pub const AUTO_SYNC_EVENT: &str = "webdav:auto-sync";
+
+/// 默认去抖时间：连续保存时只在停顿后同步一次
+pub const DEFAULT_DEBOUNCE_MS: u64 = 2000;
+/// 持续有变更时，最多推迟这么久也要同步一次
+const MAX_PENDING_DELAY: Duration = Duration::from_secs(30);
+/// 定期快速同步的最短间隔
+const MIN_FULL_SYNC_INTERVAL: Duration = Duration::from_secs(30);
+const MIN_BACKOFF: Duration = Duration::from_secs(5);
+const MAX_BACKOFF: Duration = Duration::from_secs(300);
+
+/// 同步写入的本地文件及写入后的 mtime
+static SYNC_WRITES: Lazy<Mutex<HashMap<PathBuf, SystemTime>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// 记录一次由同步写入的本地文件（下载）
+pub fn record_sync_write(path: &Path) {
+    let Some(modified) = path.metadata().ok().and_then(|m| m.modified().ok()) else {
+        return;
+    };
+    if let Ok(mut writes) = SYNC_WRITES.lock() {
+        writes.insert(path.to_path_buf(), modified);
+    }
+}
+
+/// 文件当前内容是否就是同步写入的版本；之后用户再修改时 mtime 会变化
+fn is_sync_write(path: &Path) -> bool {
+    let Ok(mut writes) = SYNC_WRITES.lock() else {
+        return false;
+    };
+    let Some(recorded) = writes.get(path) else {
+        return false;
+    };
+    let current = path.metadata().ok().and_then(|m| m.modified().ok());
+    if current == Some(*recorded) {
+        return true;
+    }
+    writes.remove(path);
+    false
+}
+
+/// 自动同步阶段
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AutoSyncPhase {
+    /// 没有待同步的变更
+    Idle,
+    /// 有变更，等待去抖结束
+    Pending,
+    Syncing,
+    Paused,
+    /// 服务器不可达，等待退避结束后重试
+    Offline,
+}
+
+/// 自动同步状态，随 `webdav:auto-sync` 事件发送
+#[derive(Debug, Clone, Serialize)]
+pub struct AutoSyncStatus {
+    pub workspace: String,
+    pub phase: AutoSyncPhase,
+    /// 待同步的路径数
+    pub pending: usize,
+    pub last_result: Option<SyncResult>,
+    pub last_error: Option<String>,
+    /// 离线时距下次重试的毫秒数
+    pub retry_in_ms: Option<u64>,
+}
+
+enum Control {
+    Changes(Vec<String>),
+    Config(WebDAVConfig),
+    Pause,
+    Resume,
+    Stop,
+}
+
+/// 运行中的自动同步
+pub struct AutoSyncHandle {
+    sender: UnboundedSender<Control>,
+    listener: EventId,
+    status: Arc<Mutex<AutoSyncStatus>>,
+}
+
+impl AutoSyncHandle {
+    /// 订阅文件变更并启动同步循环
+    pub fn start(
+        app: AppHandle,
+        config: WebDAVConfig,
+        http_client: reqwest::Client,
+        workspace: String,
+        debounce: Duration,
+    ) -> Self {
+        let (sender, receiver) = unbounded_channel();
+        let status = Arc::new(Mutex::new(AutoSyncStatus {
+            workspace: workspace.clone(),
+            phase: AutoSyncPhase::Idle,
+            pending: 0,
+            last_result: None,
+            last_error: None,
+            retry_in_ms: None,
+        }));
+
+        let root = PathBuf::from(&workspace);
+        let changes = sender.clone();
+        let listener = app.listen(FS_CHANGES_EVENT, move |event| {
+            let paths = changed_paths(&root, event.payload());
+            if !paths.is_empty() {
+                let _ = changes.send(Control::Changes(paths));
+            }
+        });
+
+        let sync_loop = SyncLoop {
+            app,
+            config,
+            http_client,
+            workspace,
+            debounce,
+            status: status.clone(),
+        };
+        tauri::async_runtime::spawn(sync_loop.run(receiver));
+
+        Self {
+            sender,
+            listener,
+            status,
+        }
+    }
+
+    pub fn status(&self) -> AutoSyncStatus {
+        self.status
+            .lock()
+            .map(|status| status.clone())
+            .unwrap_or_else(|poisoned| poisoned.into_inner().clone())
+    }
+
+    pub fn pause(&self) {
+        let _ = self.sender.send(Control::Pause);
+    }
+
+    pub fn resume(&self) {
+        let _ = self.sender.send(Control::Resume);
+    }
+
+    /// 之后的同步使用新的配置
+    pub fn update_config(&self, config: WebDAVConfig) {
+        let _ = self.sender.send(Control::Config(config));
+    }
+
+    /// 取消订阅并结束同步循环（进行中的同步会先完成）
+    pub fn stop(self, app: &AppHandle) {
+        app.unlisten(self.listener);
+        let _ = self.sender.send(Control::Stop);
+    }
+}
+
+/// 从 `fs:changes` 负载中取出工作区内需要同步的相对路径
+///
+/// 重命名同时包含旧路径（远程删除）和新路径（上传）；跳过同步引擎忽略的
+/// 文件以及同步自己写入的文件。
+fn changed_paths(root: &Path, payload: &str) -> Vec<String> {
+    let Ok(events) = serde_json::from_str::<Vec<Value>>(payload) else {
+        return Vec::new();
+    };
+    let mut paths = BTreeSet::new();
+    for event in &events {
+        for key in ["path", "old_path", "new_path"] {
+            let Some(path) = event.get(key).and_then(Value::as_str) else {
+                continue;
+            };
+            let path = Path::new(path);
+            let Ok(relative) = path.strip_prefix(root) else {
+                continue;
+            };
+            let skipped = relative
+                .components()
+                .any(|c| SyncEngine::should_skip(&c.as_os_str().to_string_lossy()));
+            if skipped || relative.as_os_str().is_empty() || is_sync_write(path) {
+                continue;
+            }
+            paths.insert(relative.to_string_lossy().replace('\\', "/"));
+        }
+    }
+    paths.into_iter().collect()
+}
+
+/// 下一次退避时长：5 秒起，每次翻倍，最长 5 分钟
+fn next_backoff(current: Option<Duration>) -> Duration {
+    current
+        .map(|delay| (delay * 2).min(MAX_BACKOFF))
+        .unwrap_or(MIN_BACKOFF)
+}
+
+struct SyncLoop {
+    app: AppHandle,
+    config: WebDAVConfig,
+    http_client: reqwest::Client,
+    workspace: String,
+    debounce: Duration,
+    status: Arc<Mutex<AutoSyncStatus>>,
+}
+
+impl SyncLoop {
+    async fn run(mut self, mut receiver: UnboundedReceiver<Control>) {
+        let mut pending: BTreeSet<String> = BTreeSet::new();
+        let mut pending_since: Option<Instant> = None;
+        let mut due: Option<Instant> = None;
+        let mut backoff: Option<Duration> = None;
+        let mut paused = false;
+        // 启用后先做一次快速同步
+        let mut next_full = Instant::now();
+
+        loop {
+            let deadline = if paused {
+                None
+            } else {
+                Some(due.map_or(next_full, |due| due.min(next_full)))
+            };
+
+            tokio::select! {
+                control = receiver.recv() => match control {
+                    Some(Control::Changes(paths)) => {
+                        pending.extend(paths);
+                        let now = Instant::now();
+                        let since = *pending_since.get_or_insert(now);
+                        due = Some(match (backoff, due) {
+                            // 离线时不提前重试
+                            (Some(_), Some(due)) => due,
+                            (Some(delay), None) => now + delay,
+                            (None, _) => (now + self.debounce).min(since + MAX_PENDING_DELAY),
+                        });
+                        let phase = if paused {
+                            AutoSyncPhase::Paused
+                        } else if backoff.is_some() {
+                            AutoSyncPhase::Offline
+                        } else {
+                            AutoSyncPhase::Pending
+                        };
+                        self.publish(|status| {
+                            status.phase = phase;
+                            status.pending = pending.len();
+                        });
+                    }
+                    Some(Control::Config(config)) => self.config = config,
+                    Some(Control::Pause) => {
+                        paused = true;
+                        self.publish(|status| status.phase = AutoSyncPhase::Paused);
+                    }
+                    Some(Control::Resume) => {
+                        paused = false;
+                        backoff = None;
+                        due = (!pending.is_empty()).then(Instant::now);
+                        let phase = if pending.is_empty() {
+                            AutoSyncPhase::Idle
+                        } else {
+                            AutoSyncPhase::Pending
+                        };
+                        self.publish(|status| {
+                            status.phase = phase;
+                            status.retry_in_ms = None;
+                        });
+                    }
+                    Some(Control::Stop) | None => break,
+                },
+                _ = sleep_until(deadline) => {
+                    let now = Instant::now();
+                    let full = now >= next_full;
+                    let paths: Vec<String> = std::mem::take(&mut pending).into_iter().collect();
+                    pending_since = None;
+                    due = None;
+                    self.publish(|status| {
+                        status.phase = AutoSyncPhase::Syncing;
+                        status.pending = 0;
+                    });
+
+                    match self.sync(&paths, full).await {
+                        Ok(result) => {
+                            if full {
+                                next_full = now + self.full_sync_interval();
+                            }
+                            // 失败的条目留待下次重试
+                            pending.extend(result.errors.iter().map(|e| e.path.clone()));
+                            if pending.is_empty() {
+                                backoff = None;
+                            } else {
+                                let delay = next_backoff(backoff);
+                                backoff = Some(delay);
+                                pending_since = Some(now);
+                                due = Some(Instant::now() + delay);
+                            }
+                            let phase = if paused {
+                                AutoSyncPhase::Paused
+                            } else if pending.is_empty() {
+                                AutoSyncPhase::Idle
+                            } else {
+                                AutoSyncPhase::Pending
+                            };
+                            self.publish(|status| {
+                                status.phase = phase;
+                                status.pending = pending.len();
+                                status.last_error = None;
+                                status.retry_in_ms = None;
+                                status.last_result = Some(result);
+                            });
+                        }
+                        Err(err) => {
+                            let delay = next_backoff(backoff);
+                            backoff = Some(delay);
+                            if full {
+                                next_full = now + delay;
+                            }
+                            pending.extend(paths);
+                            if !pending.is_empty() {
+                                pending_since = Some(now);
+                                due = Some(Instant::now() + delay);
+                            }
+                            eprintln!("[WebDAV] Auto sync failed, retrying in {:?}: {}", delay, err);
+                            let phase = if paused {
+                                AutoSyncPhase::Paused
+                            } else {
+                                AutoSyncPhase::Offline
+                            };
+                            self.publish(|status| {
+                                status.phase = phase;
+                                status.pending = pending.len();
+                                status.last_error = Some(err.to_string());
+                                status.retry_in_ms = Some(delay.as_millis() as u64);
+                            });
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// 定期快速同步，或只同步变更的路径
+    async fn sync(
+        &self,
+        paths: &[String],
+        full: bool,
+    ) -> Result<SyncResult, crate::error::AppError> {
+        let mut engine = SyncEngine::with_client(
+            self.config.clone(),
+            self.workspace.clone(),
+            self.http_client.clone(),
+        )?;
+        if full {
+            engine.quick_sync().await
+        } else {
+            engine.sync_paths(paths).await
+        }
+    }
+
+    fn full_sync_interval(&self) -> Duration {
+        Duration::from_secs(self.config.sync_interval_secs).max(MIN_FULL_SYNC_INTERVAL)
+    }
+
+    /// 更新状态并发送事件
+    fn publish(&self, update: impl FnOnce(&mut AutoSyncStatus)) {
+        let snapshot = {
+            let mut status = match self.status.lock() {
+                Ok(status) => status,
+                Err(poisoned) => poisoned.into_inner(),
+            };
+            update(&mut status);
+            status.clone()
+        };
+        let _ = self.app.emit(AUTO_SYNC_EVENT, snapshot);
+    }
+}
+
+async fn sleep_until(deadline: Option<Instant>) {
+    match deadline {
+        Some(deadline) => tokio::time::sleep_until(deadline).await,
+        None => std::future::pending().await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn collects_relative_paths_and_skips_sync_writes() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let downloaded = root.join("pulled.md");
+        std::fs::write(&downloaded, "from remote").unwrap();
+        record_sync_write(&downloaded);
+
+        let payload = serde_json::json!([
+            { "type": "Modified", "path": root.join("notes/a.md") },
+            { "type": "Renamed", "old_path": root.join("b.md"), "new_path": root.join("c.md") },
+            { "type": "Modified", "path": root.join(".obsidian/x.md") },
+            { "type": "Modified", "path": "/elsewhere/d.md" },
+            { "type": "Modified", "path": downloaded },
+        ])
+        .to_string();
+        assert_eq!(
+            changed_paths(root, &payload),
+            vec!["b.md", "c.md", "notes/a.md"]
+        );
+
+        // 用户之后再修改，就要同步
+        std::fs::write(&downloaded, "edited locally, a bit longer").unwrap();
+        let later = SystemTime::now() + Duration::from_secs(5);
+        std::fs::File::options()
+            .write(true)
+            .open(&downloaded)
+            .unwrap()
+            .set_modified(later)
+            .unwrap();
+        let payload = serde_json::json!([{ "type": "Modified", "path": downloaded }]).to_string();
+        assert_eq!(changed_paths(root, &payload), vec!["pulled.md"]);
+    }
+
+    #[test]
+    fn backs_off_exponentially_up_to_a_cap() {
+        assert_eq!(next_backoff(None), MIN_BACKOFF);
+        assert_eq!(
+            next_backoff(Some(Duration::from_secs(5))),
+            Duration::from_secs(10)
+        );
+        assert_eq!(next_backoff(Some(Duration::from_secs(200))), MAX_BACKOFF);
+    }
+}
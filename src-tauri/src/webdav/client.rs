@@ -10,6 +10,18 @@ use std::time::Duration;
 use super::types::{RemoteEntry, WebDAVConfig};
 use crate::error::AppError;
 
+/// PROPFIND 请求体
+const PROPFIND_BODY: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<D:propfind xmlns:D="DAV:">
+  <D:prop>
+    <D:resourcetype/>
+    <D:getcontentlength/>
+    <D:getlastmodified/>
+    <D:getetag/>
+    <D:getcontenttype/>
+  </D:prop>
+</D:propfind>"#;
+
 /// WebDAV 客户端
 pub struct WebDAVClient {
     client: Client,
@@ -77,25 +89,13 @@ impl WebDAVClient {
     pub async fn list_dir(&self, path: &str) -> Result<Vec<RemoteEntry>, AppError> {
         let url = self.build_url(path);
 
-        // PROPFIND 请求体
-        let body = r#"<?xml version="1.0" encoding="utf-8"?>
-<D:propfind xmlns:D="DAV:">
-  <D:prop>
-    <D:resourcetype/>
-    <D:getcontentlength/>
-    <D:getlastmodified/>
-    <D:getetag/>
-    <D:getcontenttype/>
-  </D:prop>
-</D:propfind>"#;
-
         let response = self
             .client
             .request(Method::from_bytes(b"PROPFIND").unwrap(), &url)
             .header(AUTHORIZATION, self.auth_header())
             .header(CONTENT_TYPE, "application/xml")
             .header("Depth", "1")
-            .body(body)
+            .body(PROPFIND_BODY)
             .send()
             .await
             .map_err(|e| AppError::WebDAV(format!("PROPFIND failed: {}", e)))?;
@@ -115,6 +115,45 @@ impl WebDAVClient {
         self.parse_propfind_response(&body, path)
     }
 
+    /// 查询单个文件/目录的信息 (PROPFIND Depth 0)，不存在时返回 None
+    pub async fn stat(&self, path: &str) -> Result<Option<RemoteEntry>, AppError> {
+        let url = self.build_url(path);
+
+        let response = self
+            .client
+            .request(Method::from_bytes(b"PROPFIND").unwrap(), &url)
+            .header(AUTHORIZATION, self.auth_header())
+            .header(CONTENT_TYPE, "application/xml")
+            .header("Depth", "0")
+            .body(PROPFIND_BODY)
+            .send()
+            .await
+            .map_err(|e| AppError::WebDAV(format!("PROPFIND failed: {}", e)))?;
+
+        match response.status() {
+            StatusCode::NOT_FOUND => return Ok(None),
+            StatusCode::MULTI_STATUS => {}
+            status => {
+                return Err(AppError::WebDAV(format!(
+                    "PROPFIND failed with status: {}",
+                    status
+                )))
+            }
+        }
+
+        let body = response
+            .text()
+            .await
+            .map_err(|e| AppError::WebDAV(format!("Failed to read response: {}", e)))?;
+
+        // 以根目录为基准解析，目标自身不会被当作“根目录本身”跳过
+        let target = path.trim_matches('/');
+        Ok(self
+            .parse_propfind_response(&body, "")?
+            .into_iter()
+            .find(|entry| entry.path.trim_matches('/') == target))
+    }
+
     /// 递归列出所有文件
     pub async fn list_all_recursive(&self, path: &str) -> Result<Vec<RemoteEntry>, AppError> {
         let mut all_entries = Vec::new();
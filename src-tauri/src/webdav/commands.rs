@@ -3,8 +3,10 @@
 //! 暴露给前端的命令接口
 
 use std::sync::Mutex;
-use tauri::State;
+use std::time::Duration;
+use tauri::{AppHandle, State};
 
+use super::auto_sync::{AutoSyncHandle, AutoSyncStatus, DEFAULT_DEBOUNCE_MS};
 use super::client::WebDAVClient;
 use super::sync::SyncEngine;
 use super::types::*;
@@ -13,12 +15,14 @@ use crate::error::AppError;
 /// WebDAV 状态管理
 pub struct WebDAVState {
     config: Mutex<Option<WebDAVConfig>>,
+    auto_sync: Mutex<Option<AutoSyncHandle>>,
 }
 
 impl WebDAVState {
     pub fn new() -> Self {
         Self {
             config: Mutex::new(None),
+            auto_sync: Mutex::new(None),
         }
     }
 }
@@ -39,7 +43,17 @@ pub async fn webdav_set_config(
         .config
         .lock()
         .map_err(|_| AppError::WebDAV("Failed to acquire lock".to_string()))?;
-    *guard = Some(config);
+    *guard = Some(config.clone());
+    drop(guard);
+
+    // 运行中的自动同步改用新配置
+    let auto_sync = state
+        .auto_sync
+        .lock()
+        .map_err(|_| AppError::WebDAV("Failed to acquire lock".to_string()))?;
+    if let Some(handle) = auto_sync.as_ref() {
+        handle.update_config(config);
+    }
     Ok(())
 }
 
@@ -186,3 +200,93 @@ pub async fn webdav_scan_local(
     let engine = SyncEngine::with_client(config, vault_path, http_client)?;
     engine.scan_local_files()
 }
+
+/// 开启自动同步：监听文件变更，去抖后增量同步变更的路径
+///
+/// 状态通过 `webdav:auto-sync` 事件推送；之后 `webdav_set_config` 保存的配置
+/// 会应用到运行中的自动同步。已开启时会先停止旧的自动同步。
+#[tauri::command]
+pub async fn webdav_enable_auto_sync(
+    app: AppHandle,
+    state: State<'_, WebDAVState>,
+    proxy_state: State<'_, crate::proxy::ProxyState>,
+    config: WebDAVConfig,
+    vault_path: String,
+    debounce_ms: Option<u64>,
+) -> Result<AutoSyncStatus, AppError> {
+    let http_client = proxy_state.client().await;
+
+    let mut auto_sync = state
+        .auto_sync
+        .lock()
+        .map_err(|_| AppError::WebDAV("Failed to acquire lock".to_string()))?;
+    if let Some(previous) = auto_sync.take() {
+        previous.stop(&app);
+    }
+    let handle = AutoSyncHandle::start(
+        app,
+        config,
+        http_client,
+        vault_path,
+        Duration::from_millis(debounce_ms.unwrap_or(DEFAULT_DEBOUNCE_MS)),
+    );
+    let status = handle.status();
+    *auto_sync = Some(handle);
+    Ok(status)
+}
+
+/// 关闭自动同步
+#[tauri::command]
+pub async fn webdav_disable_auto_sync(
+    app: AppHandle,
+    state: State<'_, WebDAVState>,
+) -> Result<(), AppError> {
+    let handle = state
+        .auto_sync
+        .lock()
+        .map_err(|_| AppError::WebDAV("Failed to acquire lock".to_string()))?
+        .take();
+    if let Some(handle) = handle {
+        handle.stop(&app);
+    }
+    Ok(())
+}
+
+/// 暂停自动同步（变更仍会被记录，恢复后一并同步）
+#[tauri::command]
+pub async fn webdav_pause_auto_sync(state: State<'_, WebDAVState>) -> Result<(), AppError> {
+    with_auto_sync(&state, AutoSyncHandle::pause)
+}
+
+/// 恢复自动同步，并立即重试（不再等待离线退避）
+#[tauri::command]
+pub async fn webdav_resume_auto_sync(state: State<'_, WebDAVState>) -> Result<(), AppError> {
+    with_auto_sync(&state, AutoSyncHandle::resume)
+}
+
+/// 获取自动同步状态，未开启时返回 None
+#[tauri::command]
+pub async fn webdav_get_auto_sync_status(
+    state: State<'_, WebDAVState>,
+) -> Result<Option<AutoSyncStatus>, AppError> {
+    let guard = state
+        .auto_sync
+        .lock()
+        .map_err(|_| AppError::WebDAV("Failed to acquire lock".to_string()))?;
+    Ok(guard.as_ref().map(AutoSyncHandle::status))
+}
+
+fn with_auto_sync(
+    state: &WebDAVState,
+    action: impl FnOnce(&AutoSyncHandle),
+) -> Result<(), AppError> {
+    let guard = state
+        .auto_sync
+        .lock()
+        .map_err(|_| AppError::WebDAV("Failed to acquire lock".to_string()))?;
+    let handle = guard
+        .as_ref()
+        .ok_or_else(|| AppError::WebDAV("Auto sync is not enabled".to_string()))?;
+    action(handle);
+    Ok(())
+}
@@ -3,8 +3,10 @@
 //! 提供 WebDAV 同步功能，包括：
 //! - 客户端：HTTP 请求封装
 //! - 同步：本地优先的双向同步逻辑
+//! - 自动同步：监听文件变更，去抖后增量同步
 //! - 命令：Tauri 命令接口
 
+pub mod auto_sync;
 pub mod client;
 pub mod commands;
 pub mod sync;
@@ -17,16 +17,19 @@ pub struct SyncEngine {
     client: WebDAVClient,
     vault_path: String,
     state: Option<SyncState>,
+    conflict_strategy: ConflictStrategy,
 }
 
 impl SyncEngine {
     /// 创建新的同步引擎
     pub fn new(config: WebDAVConfig, vault_path: String) -> Result<Self, AppError> {
+        let conflict_strategy = config.conflict_strategy;
         let client = WebDAVClient::new(config)?;
         Ok(Self {
             client,
             vault_path,
             state: None,
+            conflict_strategy,
         })
     }
 
@@ -36,11 +39,13 @@ impl SyncEngine {
         vault_path: String,
         http_client: reqwest::Client,
     ) -> Result<Self, AppError> {
+        let conflict_strategy = config.conflict_strategy;
         let client = WebDAVClient::with_client(config, http_client);
         Ok(Self {
             client,
             vault_path,
             state: None,
+            conflict_strategy,
         })
     }
 
@@ -130,8 +135,31 @@ impl SyncEngine {
         Ok(files)
     }
 
+    /// 单个本地文件的元信息，不存在时返回 None
+    fn local_file_info(&self, relative_path: &str) -> Option<LocalFileInfo> {
+        let path = Path::new(&self.vault_path).join(relative_path);
+        let metadata = fs::metadata(&path).ok()?;
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        Some(LocalFileInfo {
+            relative_path: relative_path.to_string(),
+            absolute_path: path.to_string_lossy().to_string(),
+            is_dir: metadata.is_dir(),
+            size: if metadata.is_file() {
+                metadata.len()
+            } else {
+                0
+            },
+            modified,
+        })
+    }
+
     /// 判断是否应该跳过的文件/目录
-    fn should_skip(name: &str) -> bool {
+    pub(super) fn should_skip(name: &str) -> bool {
         name.starts_with('.')
             || name == "node_modules"
             || name == "target"
@@ -176,7 +204,11 @@ impl SyncEngine {
             let remote = remote_map.get(path).copied();
             let last_record = last_sync_map.get(path).copied();
 
-            let (action, reason) = self.determine_action(Some(local), remote, last_record);
+            let (action, reason) = self.apply_conflict_strategy(self.determine_action(
+                Some(local),
+                remote,
+                last_record,
+            ));
 
             if action != SyncAction::Skip {
                 items.push(SyncPlanItem {
@@ -303,8 +335,30 @@ impl SyncEngine {
         }
     }
 
+    /// 按冲突策略改写冲突条目；Skip 和 KeepBoth 保留为冲突
+    fn apply_conflict_strategy(
+        &self,
+        (action, reason): (SyncAction, String),
+    ) -> (SyncAction, String) {
+        if action != SyncAction::Conflict {
+            return (action, reason);
+        }
+        match self.conflict_strategy {
+            ConflictStrategy::PreferLocal => (
+                SyncAction::Upload,
+                format!("{}, keeping local version", reason),
+            ),
+            ConflictStrategy::PreferRemote => (
+                SyncAction::Download,
+                format!("{}, keeping remote version", reason),
+            ),
+            ConflictStrategy::Skip | ConflictStrategy::KeepBoth => (action, reason),
+        }
+    }
+
     /// 执行同步
     pub async fn execute_sync(&mut self, plan: &SyncPlan) -> Result<SyncResult, AppError> {
+        crate::fs::ensure_writable("sync the workspace")?;
         let start = Instant::now();
         let mut uploaded = 0;
         let mut downloaded = 0;
@@ -312,6 +366,7 @@ impl SyncEngine {
         let mut conflicts = 0;
         let mut errors = Vec::new();
         let mut new_records = Vec::new();
+        let mut conflict_copies = Vec::new();
 
         for item in &plan.items {
             let result = match item.action {
@@ -326,7 +381,7 @@ impl SyncEngine {
                     );
                     continue;
                 }
-                SyncAction::Conflict => self.handle_conflict(item).await,
+                SyncAction::Conflict => self.handle_conflict(item, &mut conflict_copies).await,
                 SyncAction::Skip => continue,
             };
 
@@ -396,6 +451,7 @@ impl SyncEngine {
             deleted,
             conflicts,
             errors,
+            conflict_copies,
             duration_ms: start.elapsed().as_millis() as u64,
         })
     }
@@ -460,8 +516,12 @@ impl SyncEngine {
             }
 
             let content = self.client.download(&item.path).await?;
+            // 与编辑器保存、Agent 写入串行，避免覆盖同时发生的本地修改
+            let _lock = crate::fs::lock_path(local_path).await;
             fs::write(local_path, &content)
                 .map_err(|e| AppError::WebDAV(format!("Failed to write local file: {}", e)))?;
+            // 自动同步据此忽略这次写入产生的文件变更事件
+            super::auto_sync::record_sync_write(local_path);
         }
 
         let local_mtime = local_path
@@ -502,43 +562,190 @@ impl SyncEngine {
     }
 
     /// 处理冲突 - 保留两个版本
-    async fn handle_conflict(&self, item: &SyncPlanItem) -> Result<Option<FileRecord>, AppError> {
+    ///
+    /// 远程版本另存为 `note (conflicted copy from <设备> <日期>).md`，本地文件
+    /// 保持不变并上传覆盖远程，之后两端都有两个版本，副本随下次同步上传。
+    async fn handle_conflict(
+        &self,
+        item: &SyncPlanItem,
+        conflict_copies: &mut Vec<String>,
+    ) -> Result<Option<FileRecord>, AppError> {
         let remote = item.remote.as_ref().ok_or_else(|| {
             AppError::WebDAV("No remote file for conflict resolution".to_string())
         })?;
 
-        // 下载远程版本为 .conflict 文件
-        let conflict_path = format!("{}/{}.conflict", self.vault_path, item.path);
-        let conflict_path = Path::new(&conflict_path);
+        let device = device_name();
+        let date = chrono::Local::now().format("%Y-%m-%d").to_string();
+        let (copy_relative, copy_path) = (1..)
+            .map(|n| conflict_copy_path(&item.path, &device, &date, n))
+            .map(|relative| {
+                let absolute = format!("{}/{}", self.vault_path, relative);
+                (relative, absolute)
+            })
+            .find(|(_, absolute)| !Path::new(absolute).exists())
+            .expect("unbounded range always yields a free name");
+        let copy_path = Path::new(&copy_path);
 
-        if let Some(parent) = conflict_path.parent() {
+        if let Some(parent) = copy_path.parent() {
             fs::create_dir_all(parent)?;
         }
 
         let content = self.client.download(&item.path).await?;
-        fs::write(conflict_path, &content)?;
+        {
+            let _lock = crate::fs::lock_path(copy_path).await;
+            fs::write(copy_path, &content)?;
+        }
+        conflict_copies.push(copy_relative);
+
+        match item.local.as_ref() {
+            // 远程版本已保存，本地版本成为该路径的当前版本
+            Some(local) if !local.is_dir => self.execute_upload(item).await,
+            local => Ok(Some(FileRecord {
+                path: item.path.clone(),
+                local_mtime: local.map(|l| l.modified).unwrap_or(0),
+                remote_mtime: remote.modified,
+                etag: remote.etag.clone(),
+            })),
+        }
+    }
 
-        // 记录本地版本的信息
-        let local = item.local.as_ref();
-        let local_mtime = local.map(|l| l.modified).unwrap_or(0);
+    /// 增量同步：只处理给定的相对路径，冲突按冲突策略处理
+    pub async fn sync_paths(&mut self, paths: &[String]) -> Result<SyncResult, AppError> {
+        self.load_state()?;
 
-        Ok(Some(FileRecord {
-            path: item.path.clone(),
-            local_mtime,
-            remote_mtime: remote.modified,
-            etag: remote.etag.clone(),
-        }))
+        let mut items = Vec::new();
+        for path in paths {
+            let local = self.local_file_info(path);
+            let remote = self.client.stat(path).await?;
+            let last_record = self
+                .state
+                .as_ref()
+                .and_then(|s| s.file_records.iter().find(|r| &r.path == path));
+
+            let (action, reason) = match (&local, &remote, last_record) {
+                (None, None, _) => continue,
+                // 目录本身不同步；本地删除/重命名目录时不应在本地重建它
+                (None, Some(r), _) if r.is_dir => continue,
+                // 与 compute_sync_plan 一致：同步过的文件在本地被删除 -> 删除远程
+                (None, Some(_), Some(_)) => (
+                    SyncAction::DeleteRemote,
+                    "Local file was deleted".to_string(),
+                ),
+                (None, Some(_), None) => (SyncAction::Download, "New file on remote".to_string()),
+                _ => self.apply_conflict_strategy(self.determine_action(
+                    local.as_ref(),
+                    remote.as_ref(),
+                    last_record,
+                )),
+            };
+            if action == SyncAction::Skip || self.skips_conflict(&action) {
+                continue;
+            }
+            items.push(SyncPlanItem {
+                path: path.clone(),
+                action,
+                local,
+                remote,
+                reason,
+            });
+        }
+
+        let upload_count = items
+            .iter()
+            .filter(|i| i.action == SyncAction::Upload)
+            .count();
+        let download_count = items
+            .iter()
+            .filter(|i| i.action == SyncAction::Download)
+            .count();
+        let conflict_count = items
+            .iter()
+            .filter(|i| i.action == SyncAction::Conflict)
+            .count();
+        let plan = SyncPlan {
+            items,
+            upload_count,
+            download_count,
+            conflict_count,
+        };
+        self.execute_sync(&plan).await
+    }
+
+    /// 冲突策略为 Skip 时，自动同步不处理冲突
+    fn skips_conflict(&self, action: &SyncAction) -> bool {
+        *action == SyncAction::Conflict && self.conflict_strategy == ConflictStrategy::Skip
     }
 
-    /// 快速同步：仅同步非冲突文件
+    /// 快速同步：冲突按冲突策略处理，策略为 Skip 时只同步非冲突文件
     pub async fn quick_sync(&mut self) -> Result<SyncResult, AppError> {
         let mut plan = self.compute_sync_plan().await?;
 
-        // 过滤掉冲突，只处理确定性的操作
-        plan.items
-            .retain(|item| item.action != SyncAction::Conflict);
-        plan.conflict_count = 0;
+        // 过滤掉跳过的冲突，只处理确定性的操作
+        plan.items.retain(|item| !self.skips_conflict(&item.action));
+        plan.conflict_count = plan
+            .items
+            .iter()
+            .filter(|i| i.action == SyncAction::Conflict)
+            .count();
 
         self.execute_sync(&plan).await
     }
 }
+
+/// 本机名称，用于冲突副本的文件名
+fn device_name() -> String {
+    ["COMPUTERNAME", "HOSTNAME"]
+        .iter()
+        .filter_map(|key| std::env::var(key).ok())
+        .chain(fs::read_to_string("/etc/hostname").ok())
+        .map(|name| name.trim().to_string())
+        .find(|name| !name.is_empty())
+        .unwrap_or_else(|| "another device".to_string())
+}
+
+/// 冲突副本的相对路径：`dir/note (conflicted copy from <设备> <日期>).md`，
+/// 重名时 n > 1 追加序号
+fn conflict_copy_path(path: &str, device: &str, date: &str, n: usize) -> String {
+    let (dir, name) = match path.rsplit_once('/') {
+        Some((dir, name)) => (format!("{}/", dir), name),
+        None => (String::new(), path),
+    };
+    let (stem, ext) = match name.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() => (stem, format!(".{}", ext)),
+        _ => (name, String::new()),
+    };
+    let device: String = device
+        .chars()
+        .filter(|c| !matches!(c, '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|'))
+        .collect();
+    let suffix = if n > 1 {
+        format!(" {}", n)
+    } else {
+        String::new()
+    };
+    format!(
+        "{}{} (conflicted copy from {} {}{}){}",
+        dir, stem, device, date, suffix, ext
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_conflict_copies_next_to_the_note() {
+        assert_eq!(
+            conflict_copy_path("daily/2024-05-01.md", "laptop", "2024-05-02", 1),
+            "daily/2024-05-01 (conflicted copy from laptop 2024-05-02).md"
+        );
+        assert_eq!(
+            conflict_copy_path("todo.md", "my/pc", "2024-05-02", 2),
+            "todo (conflicted copy from mypc 2024-05-02 2).md"
+        );
+        assert_eq!(
+            conflict_copy_path(".env", "pc", "2024-05-02", 1),
+            ".env (conflicted copy from pc 2024-05-02)"
+        );
+    }
+}
@@ -19,6 +19,9 @@ pub struct WebDAVConfig {
     pub auto_sync: bool,
     /// 自动同步间隔 (秒)
     pub sync_interval_secs: u64,
+    /// 两端都修改时的处理方式
+    #[serde(default)]
+    pub conflict_strategy: ConflictStrategy,
 }
 
 impl Default for WebDAVConfig {
@@ -30,10 +33,25 @@ impl Default for WebDAVConfig {
             remote_base_path: "/".to_string(),
             auto_sync: false,
             sync_interval_secs: 300, // 5 分钟
+            conflict_strategy: ConflictStrategy::default(),
         }
     }
 }
 
+/// 冲突处理策略
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum ConflictStrategy {
+    /// 跳过冲突，留给用户在同步计划中处理
+    #[default]
+    Skip,
+    /// 以本地为准，覆盖远程
+    PreferLocal,
+    /// 以远程为准，覆盖本地
+    PreferRemote,
+    /// 远程版本另存为冲突副本，本地保持不变并上传
+    KeepBoth,
+}
+
 /// 远程文件/目录信息
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RemoteEntry {
@@ -164,6 +182,9 @@ pub struct SyncResult {
     pub conflicts: usize,
     /// 错误列表
     pub errors: Vec<SyncError>,
+    /// 本次创建的冲突副本 (相对路径)
+    #[serde(default)]
+    pub conflict_copies: Vec<String>,
     /// 同步耗时 (毫秒)
     pub duration_ms: u64,
 }
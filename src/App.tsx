@@ -1017,6 +1017,7 @@ function App() {
               <BrowserView
                 tabId={activeTab.id}
                 initialUrl={activeTab.webpageUrl}
+                partition={activeTab.webpagePartition}
                 isActive={true}
               />
             </div>
@@ -13,6 +13,7 @@ import { getRecommendedTemperature } from "@/services/llm/temperature";
 import { Settings, Tag, Loader2, Check, X, Zap } from "lucide-react";
 import { useLocaleStore } from "@/stores/useLocaleStore";
 import { ThinkingModelIcon } from "@/components/ai/ThinkingModelIcon";
+import { errorToString } from "@/lib/appError";
 
 // 测试连接状态类型
 type TestStatus = "idle" | "testing" | "success" | "error";
@@ -39,7 +40,14 @@ function getModelMeta(provider: LLMProviderType, modelId?: string) {
 
 export function AISettingsModal({ isOpen, onClose }: AISettingsModalProps) {
   const { config, setConfig } = useAIStore();
-  const { autoApprove, setAutoApprove, autoCompactEnabled, setAutoCompactEnabled } = useRustAgentStore();
+  const {
+    autoApprove,
+    setAutoApprove,
+    autoCompactEnabled,
+    setAutoCompactEnabled,
+    dryRun,
+    setDryRun,
+  } = useRustAgentStore();
   const {
     config: ragConfig,
     setConfig: setRAGConfig,
@@ -48,6 +56,9 @@ export function AISettingsModal({ isOpen, onClose }: AISettingsModalProps) {
     rebuildIndex,
     cancelIndex,
     lastError: ragError,
+    autoReindex,
+    setAutoReindex,
+    autoReindexProgress,
   } = useRAGStore();
   const { hideAllWebViews, showAllWebViews } = useBrowserStore();
   const { t } = useLocaleStore();
@@ -66,7 +77,7 @@ export function AISettingsModal({ isOpen, onClose }: AISettingsModalProps) {
 
   // 解析错误信息
   const parseError = useCallback((error: unknown): string => {
-    const errorStr = String(error);
+    const errorStr = errorToString(error);
     const errorLower = errorStr.toLowerCase();
     
     // 精确匹配 HTTP 状态码（避免误匹配）
@@ -384,6 +395,18 @@ export function AISettingsModal({ isOpen, onClose }: AISettingsModalProps) {
                 <span className="text-[10px] text-muted-foreground">{t.aiSettings.autoCompactHint}</span>
               </div>
             </label>
+            <label className="flex items-start gap-2 text-xs text-foreground cursor-pointer">
+              <input
+                type="checkbox"
+                checked={dryRun}
+                onChange={(e) => setDryRun(e.target.checked)}
+                className="w-3 h-3 rounded border-border/60 mt-0.5"
+              />
+              <div className="flex flex-col gap-0.5">
+                <span>{t.aiSettings.dryRunMode}</span>
+                <span className="text-[10px] text-muted-foreground">{t.aiSettings.dryRunHint}</span>
+              </div>
+            </label>
           </div>
 
           {/* RAG 设置（完整，与 RightPanel 同步） */}
@@ -658,6 +681,25 @@ export function AISettingsModal({ isOpen, onClose }: AISettingsModalProps) {
                     </div>
                   )}
 
+                  <label className="flex items-start gap-2 text-xs text-foreground cursor-pointer">
+                    <input
+                      type="checkbox"
+                      checked={autoReindex}
+                      onChange={(e) => void setAutoReindex(e.target.checked)}
+                      className="w-3 h-3 rounded border-border/60 mt-0.5"
+                    />
+                    <div className="flex flex-col gap-0.5">
+                      <span>{t.aiSettings.autoReindex}</span>
+                      <span className="text-[10px] text-muted-foreground">
+                        {autoReindexProgress
+                          ? t.aiSettings.filesProgress
+                              .replace('{current}', String(autoReindexProgress.current))
+                              .replace('{total}', String(autoReindexProgress.total))
+                          : t.aiSettings.autoReindexHint}
+                      </span>
+                    </div>
+                  </label>
+
                   <button
                     onClick={() => rebuildIndex()}
                     disabled={ragIsIndexing || (ragConfig.embeddingProvider === 'openai' && !ragConfig.embeddingApiKey)}
@@ -120,7 +120,9 @@ export function SkillManagerModal({ isOpen, onClose }: SkillManagerModalProps) {
                   {items.map((skill) => (
                     <div
                       key={`${skill.source ?? "skill"}:${skill.name}`}
-                      className="border border-border/60 rounded-lg p-3 bg-background/60"
+                      className={`border rounded-lg p-3 bg-background/60 ${
+                        skill.errors?.length ? "border-destructive/40" : "border-border/60"
+                      }`}
                     >
                       <div className="flex items-center justify-between">
                         <div className="text-sm font-medium text-foreground">{skill.title}</div>
@@ -131,6 +133,20 @@ export function SkillManagerModal({ isOpen, onClose }: SkillManagerModalProps) {
                           {skill.description}
                         </div>
                       )}
+                      {(skill.triggers?.length || skill.globs?.length) ? (
+                        <div className="text-[10px] text-muted-foreground mt-1">
+                          {t.ai.skillsManagerAutoActivate}:{" "}
+                          {[...(skill.triggers ?? []), ...(skill.globs ?? [])].join(", ")}
+                        </div>
+                      ) : null}
+                      {skill.errors && skill.errors.length > 0 && (
+                        <div className="mt-2 text-[10px] text-destructive bg-destructive/10 border border-destructive/20 rounded-md p-2 space-y-0.5">
+                          <div className="font-medium">{t.ai.skillsManagerInvalid}</div>
+                          {skill.errors.map((message) => (
+                            <div key={message}>{message}</div>
+                          ))}
+                        </div>
+                      )}
                       {skill.tags && skill.tags.length > 0 && (
                         <div className="mt-2 flex flex-wrap gap-1">
                           {skill.tags.map((tag) => (
@@ -16,19 +16,51 @@
  */
 
 import { useState, useCallback, useRef, useEffect } from 'react';
-import { Globe, Bookmark, Share2, AlertCircle, PanelLeftOpen, PanelRightOpen } from 'lucide-react';
+import { Globe, Bookmark, Share2, AlertCircle, PanelLeftOpen, PanelRightOpen, FolderDown, Search } from 'lucide-react';
 import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
+import { open } from '@tauri-apps/plugin-dialog';
 import { useLocaleStore } from '@/stores/useLocaleStore';
 import { AddressBar } from './AddressBar';
+import { FindBar } from './FindBar';
 import { useFileStore } from '@/stores/useFileStore';
 import { useBrowserStore } from '@/stores/useBrowserStore';
 import { useUIStore } from '@/stores/useUIStore';
 import { cn } from '@/lib/utils';
 import { reportOperationError } from '@/lib/reportError';
+import { errorToString } from '@/lib/appError';
+
+/** 后端 browser_webview_get_state 返回的导航状态 */
+interface BrowserWebviewState {
+  url: string;
+  title: string;
+  can_go_back: boolean;
+  can_go_forward: boolean;
+  loading: boolean;
+}
+
+/** 后端下载事件（browser:download-progress / browser:download-complete） */
+interface BrowserDownloadEvent {
+  tab_id: string;
+  file_name: string;
+  path: string;
+  received_bytes?: number;
+  bytes?: number;
+  success?: boolean;
+  error?: string | null;
+}
+
+const formatBytes = (bytes: number) => {
+  if (bytes < 1024) return `${bytes} B`;
+  if (bytes < 1024 * 1024) return `${(bytes / 1024).toFixed(1)} KB`;
+  return `${(bytes / 1024 / 1024).toFixed(1)} MB`;
+};
 
 interface BrowserViewProps {
   tabId: string;
   initialUrl?: string;
+  /** 存储分区："incognito" 为无痕，其他名称为持久化的独立容器 */
+  partition?: string;
   isActive?: boolean;
   onTitleChange?: (title: string) => void;
 }
@@ -39,6 +71,7 @@ const DEFAULT_HOME_URL = 'https://www.google.com';
 export function BrowserView({
   tabId,
   initialUrl = '',
+  partition,
   isActive = true,
   onTitleChange,
 }: BrowserViewProps) {
@@ -58,6 +91,11 @@ export function BrowserView({
   const [isLoading, setIsLoading] = useState(false);
   const [webviewCreated, setWebviewCreated] = useState(false);
   const [error, setError] = useState<string | null>(null);
+  const [navState, setNavState] = useState<BrowserWebviewState | null>(null);
+  const [downloadStatus, setDownloadStatus] = useState('');
+  const [showFind, setShowFind] = useState(false);
+  const vaultPath = useFileStore((state) => state.vaultPath);
+  const downloadDir = useBrowserStore((state) => state.downloadDir);
   
   // 跟踪上一个 tabId，用于在切换时隐藏旧的 WebView
   const prevTabIdRef = useRef<string | null>(null);
@@ -192,6 +230,7 @@ export function BrowserView({
           y: rect.top,
           width: rect.width,
           height: rect.height,
+          partition,
         });
       } else {
         // WebView 已存在，显示并更新位置
@@ -240,11 +279,11 @@ export function BrowserView({
         error: err,
         context: { tabId, url },
       });
-      setError(String(err));
+      setError(errorToString(err));
     } finally {
       setIsLoading(false);
     }
-  }, [tabId, registerWebView, updateWebpageTab, updateTitle, onTitleChange]);
+  }, [tabId, partition, registerWebView, updateWebpageTab, updateTitle, onTitleChange]);
   
   // 更新 WebView 浏览器位置大小
   const updateWebviewBounds = useCallback(async () => {
@@ -341,7 +380,7 @@ export function BrowserView({
         error: err,
         context: { tabId, url },
       });
-      setError(String(err));
+      setError(errorToString(err));
     } finally {
       setIsLoading(false);
     }
@@ -439,6 +478,104 @@ export function BrowserView({
     }
   }, [tabId, initialUrl, webviewCreated, isActive, createWebview, updateWebviewBounds]);
   
+  // 轮询导航状态，用于后退 / 前进按钮与地址栏（页面内跳转不会经过 handleNavigate）
+  useEffect(() => {
+    if (!webviewCreated || !isActive) return;
+
+    let cancelled = false;
+    const refresh = () => {
+      void invoke<BrowserWebviewState | null>('browser_webview_get_state', { tabId })
+        .then((state) => {
+          if (cancelled || !state) return;
+          setNavState(state);
+          if (state.url) setCurrentUrl(state.url);
+        })
+        .catch(() => {});
+    };
+    refresh();
+    const timer = window.setInterval(refresh, 1000);
+    return () => {
+      cancelled = true;
+      window.clearInterval(timer);
+    };
+  }, [tabId, webviewCreated, isActive]);
+
+  // Ctrl/Cmd+F 打开页面内查找（焦点在应用界面时生效）
+  useEffect(() => {
+    if (!isActive || !webviewCreated) return;
+    const handleKeyDown = (e: KeyboardEvent) => {
+      if ((e.ctrlKey || e.metaKey) && !e.shiftKey && !e.altKey && e.key.toLowerCase() === 'f') {
+        e.preventDefault();
+        setShowFind(true);
+      }
+    };
+    window.addEventListener('keydown', handleKeyDown);
+    return () => window.removeEventListener('keydown', handleKeyDown);
+  }, [isActive, webviewCreated]);
+
+  // 默认下载到工作区的 Downloads 目录
+  useEffect(() => {
+    if (!vaultPath || downloadDir) return;
+    void useBrowserStore.getState().setDownloadDir(`${vaultPath}/Downloads`);
+  }, [vaultPath, downloadDir]);
+
+  // 选择下载目录
+  const handleChooseDownloadDir = useCallback(async () => {
+    const selected = await open({
+      directory: true,
+      multiple: false,
+      defaultPath: downloadDir ?? vaultPath ?? undefined,
+    });
+    if (typeof selected === 'string') {
+      await useBrowserStore.getState().setDownloadDir(selected);
+    }
+  }, [downloadDir, vaultPath]);
+
+  // 当前标签页的下载进度
+  useEffect(() => {
+    const unlisteners: Array<() => void> = [];
+    let disposed = false;
+
+    const setup = async () => {
+      try {
+        const progress = await listen<BrowserDownloadEvent>('browser:download-progress', (event) => {
+          const payload = event.payload;
+          if (payload.tab_id !== tabId) return;
+          setDownloadStatus(
+            t.browser.downloading
+              .replace('{name}', payload.file_name)
+              .replace('{size}', formatBytes(payload.received_bytes ?? 0))
+          );
+        });
+        const complete = await listen<BrowserDownloadEvent>('browser:download-complete', (event) => {
+          const payload = event.payload;
+          if (payload.tab_id !== tabId) return;
+          setDownloadStatus(
+            payload.success
+              ? t.browser.downloadSaved.replace('{name}', payload.file_name)
+              : t.browser.downloadFailed.replace('{error}', payload.error || payload.file_name)
+          );
+        });
+        unlisteners.push(progress, complete);
+        if (disposed) unlisteners.forEach((unlisten) => unlisten());
+      } catch (error) {
+        reportOperationError({
+          source: "BrowserView.setupDownloadListener",
+          action: "Register browser download listeners",
+          error,
+          level: "warning",
+          context: { tabId },
+        });
+      }
+    };
+
+    void setup();
+    return () => {
+      disposed = true;
+      unlisteners.forEach((unlisten) => unlisten());
+    };
+  }, [tabId, t]);
+
   // 监听窗口大小变化
   useEffect(() => {
     if (!webviewCreated) return;
@@ -467,10 +604,13 @@ export function BrowserView({
         onForward={handleForward}
         onRefresh={handleRefresh}
         onHome={handleHome}
-        canGoBack={webviewCreated}
-        canGoForward={webviewCreated}
-        isLoading={isLoading}
+        canGoBack={webviewCreated && (navState?.can_go_back ?? true)}
+        canGoForward={webviewCreated && (navState?.can_go_forward ?? true)}
+        isLoading={isLoading || !!navState?.loading}
       />
+      {showFind && webviewCreated && (
+        <FindBar tabId={tabId} onClose={() => setShowFind(false)} />
+      )}
       
       {/* 工具栏 - 快捷网址 */}
       <div className="flex items-center gap-1 px-2 py-0.5 border-b border-border/60 bg-muted/30 overflow-x-auto scrollbar-none">
@@ -524,9 +664,24 @@ export function BrowserView({
           </button>
         ))}
         <div className="flex-1" />
-        <span className="text-xs text-muted-foreground shrink-0">
-          {isLoading ? t.common.loading : ''}
+        <span className="text-xs text-muted-foreground shrink-0 truncate max-w-[240px]">
+          {isLoading ? t.common.loading : downloadStatus}
         </span>
+        <button
+          onClick={() => setShowFind((value) => !value)}
+          disabled={!webviewCreated}
+          className="p-1 rounded hover:bg-accent transition-colors text-muted-foreground hover:text-foreground disabled:opacity-40 shrink-0"
+          title={t.browser.findPlaceholder}
+        >
+          <Search size={12} />
+        </button>
+        <button
+          onClick={() => void handleChooseDownloadDir()}
+          className="p-1 rounded hover:bg-accent transition-colors text-muted-foreground hover:text-foreground shrink-0"
+          title={downloadDir ? `${t.browser.downloadFolder}: ${downloadDir}` : t.browser.downloadFolder}
+        >
+          <FolderDown size={12} />
+        </button>
         {/* 切换右侧侧边栏按钮 */}
         <button
           onClick={() => useUIStore.getState().toggleRightSidebar()}
@@ -0,0 +1,144 @@
+/**
+ * 网页内查找栏
+ * 在浏览器标签页或视频 WebView（tabId 为 "video"）中高亮匹配项并循环切换
+ */
+
+import { useState, useEffect, useRef, useCallback } from 'react';
+import { ChevronUp, ChevronDown, X, CaseSensitive } from 'lucide-react';
+import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
+import { cn } from '@/lib/utils';
+import { useLocaleStore } from '@/stores/useLocaleStore';
+import { reportOperationError } from '@/lib/reportError';
+
+interface FindResultPayload {
+  tabId: string;
+  query: string;
+  total: number;
+  current: number;
+}
+
+interface FindBarProps {
+  tabId: string;
+  onClose: () => void;
+  className?: string;
+}
+
+export function FindBar({ tabId, onClose, className }: FindBarProps) {
+  const { t } = useLocaleStore();
+  const [query, setQuery] = useState('');
+  const [matchCase, setMatchCase] = useState(false);
+  const [result, setResult] = useState<FindResultPayload | null>(null);
+  const inputRef = useRef<HTMLInputElement>(null);
+
+  useEffect(() => {
+    inputRef.current?.focus();
+  }, []);
+
+  // 页面返回的匹配数量
+  useEffect(() => {
+    let unlisten: (() => void) | null = null;
+    let disposed = false;
+    void listen<FindResultPayload>('browser:find-result', (event) => {
+      if (event.payload.tabId === tabId) setResult(event.payload);
+    }).then((fn) => {
+      if (disposed) fn();
+      else unlisten = fn;
+    });
+    return () => {
+      disposed = true;
+      if (unlisten) unlisten();
+    };
+  }, [tabId]);
+
+  const find = useCallback(async (text: string, forward: boolean, caseSensitive: boolean) => {
+    try {
+      await invoke('browser_webview_find', { tabId, query: text, forward, matchCase: caseSensitive });
+    } catch (error) {
+      reportOperationError({
+        source: 'FindBar.find',
+        action: 'Find in page',
+        error,
+        level: 'warning',
+        context: { tabId },
+      });
+    }
+  }, [tabId]);
+
+  // 输入变化后重新查找
+  useEffect(() => {
+    const timer = setTimeout(() => void find(query, true, matchCase), 200);
+    return () => clearTimeout(timer);
+  }, [query, matchCase, find]);
+
+  const close = useCallback(() => {
+    void invoke('browser_webview_find_clear', { tabId }).catch(() => {});
+    onClose();
+  }, [tabId, onClose]);
+
+  const handleKeyDown = (e: React.KeyboardEvent<HTMLInputElement>) => {
+    if (e.key === 'Enter') {
+      e.preventDefault();
+      void find(query, !e.shiftKey, matchCase);
+    } else if (e.key === 'Escape') {
+      e.preventDefault();
+      close();
+    }
+  };
+
+  const hasResult = result !== null && result.query === query && query !== '';
+
+  return (
+    <div className={cn('flex items-center gap-1 px-2 py-1 border-b border-border/60 bg-background', className)}>
+      <input
+        ref={inputRef}
+        type="text"
+        value={query}
+        onChange={(e) => setQuery(e.target.value)}
+        onKeyDown={handleKeyDown}
+        placeholder={t.browser.findPlaceholder}
+        className="flex-1 min-w-0 px-2 py-0.5 text-xs bg-muted border border-border/60 rounded outline-none focus:border-primary"
+      />
+      <span className="text-[11px] text-muted-foreground tabular-nums shrink-0 min-w-[48px] text-right">
+        {hasResult
+          ? result.total > 0
+            ? `${result.current}/${result.total}`
+            : t.browser.findNoResults
+          : ''}
+      </span>
+      <button
+        onClick={() => setMatchCase((value) => !value)}
+        className={cn(
+          'p-1 rounded transition-colors shrink-0',
+          matchCase ? 'bg-accent text-foreground' : 'text-muted-foreground hover:bg-accent hover:text-foreground'
+        )}
+        title={t.browser.findMatchCase}
+      >
+        <CaseSensitive size={12} />
+      </button>
+      <button
+        onClick={() => void find(query, false, matchCase)}
+        disabled={!hasResult || result.total === 0}
+        className="p-1 rounded hover:bg-accent text-muted-foreground hover:text-foreground disabled:opacity-40 shrink-0"
+        title={t.browser.findPrevious}
+      >
+        <ChevronUp size={12} />
+      </button>
+      <button
+        onClick={() => void find(query, true, matchCase)}
+        disabled={!hasResult || result.total === 0}
+        className="p-1 rounded hover:bg-accent text-muted-foreground hover:text-foreground disabled:opacity-40 shrink-0"
+        title={t.browser.findNext}
+      >
+        <ChevronDown size={12} />
+      </button>
+      <button
+        onClick={close}
+        className="p-1 rounded hover:bg-accent text-muted-foreground hover:text-foreground shrink-0"
+        title={t.browser.findClose}
+      >
+        <X size={12} />
+      </button>
+    </div>
+  );
+}
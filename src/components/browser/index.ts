@@ -1,2 +1,3 @@
 export { BrowserView } from './BrowserView';
 export { AddressBar } from './AddressBar';
+export { FindBar } from './FindBar';
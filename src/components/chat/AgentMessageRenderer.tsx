@@ -34,6 +34,7 @@ import {
   AlertTriangle,
   RefreshCw,
 } from "lucide-react";
+import { errorToString } from "@/lib/appError";
 
 // ============ 类型定义 ============
 
@@ -563,7 +564,7 @@ export const AgentMessageRenderer = memo(function AgentMessageRenderer({
       }
     } catch (error) {
       console.error("Failed to apply edit:", error);
-      alert(t.ai.applyEditFailed.replace('{error}', String(error)));
+      alert(t.ai.applyEditFailed.replace('{error}', errorToString(error)));
     }
   }, [pendingDiff, clearPendingEdits, openFile, diffResolver, t]);
 
@@ -29,6 +29,7 @@ import {
   RefreshCw,
   Bug,
   FileText,
+  ArrowUp,
 } from "lucide-react";
 
 export function AgentPanel() {
@@ -214,8 +215,28 @@ export function AgentPanel() {
               </p>
             )}
             {queuedTasks.slice(0, 3).map((item) => (
-              <div key={item.id} className="mt-1 text-xs text-muted-foreground truncate">
-                #{item.position} {item.task}
+              <div key={item.id} className="mt-1 flex items-center gap-1 text-xs text-muted-foreground">
+                <span className="flex-1 truncate">
+                  #{item.position} {item.task}
+                </span>
+                {item.position > 1 && (
+                  <button
+                    onClick={() => rustStore.moveQueuedTask(item.id, item.position - 1)}
+                    className="p-0.5 rounded hover:bg-muted hover:text-foreground transition-colors"
+                    title={t.ai.agentQueueMoveUp}
+                    aria-label={t.ai.agentQueueMoveUp}
+                  >
+                    <ArrowUp size={12} />
+                  </button>
+                )}
+                <button
+                  onClick={() => rustStore.cancelQueuedTask(item.id)}
+                  className="p-0.5 rounded hover:bg-muted hover:text-destructive transition-colors"
+                  title={t.ai.agentQueueCancel}
+                  aria-label={t.ai.agentQueueCancel}
+                >
+                  <X size={12} />
+                </button>
               </div>
             ))}
             {isWaitingApproval && (
@@ -226,7 +247,7 @@ export function AgentPanel() {
             {llmRetryState && status === "running" && (
               <div className="mt-2 rounded-md border border-warning/40 bg-warning/10 px-2 py-1.5 text-xs text-warning">
                 <p className="font-medium">
-                  {t.ai.agentRetryTitle}
+                  {llmRetryState.rateLimited ? t.ai.agentRateLimitedTitle : t.ai.agentRetryTitle}
                   {" "}
                   {t.ai.agentRetryAttempt
                     .replace('{attempt}', String(llmRetryState.attempt))
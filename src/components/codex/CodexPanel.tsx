@@ -7,6 +7,7 @@ import { CodexEmbeddedWebview } from "@/components/codex/CodexEmbeddedWebview";
 import { useUIStore } from "@/stores/useUIStore";
 import { useFileStore } from "@/stores/useFileStore";
 import { normalizeErrorMessage, reportOperationError } from "@/lib/reportError";
+import { errorToString } from "@/lib/appError";
 
 type HostInfo = {
   origin: string;
@@ -208,7 +209,7 @@ export async function waitForCodexViewReady(
       }
     } catch (error) {
       if (isAbortError(error)) throw error;
-      lastError = error instanceof Error ? error.message : String(error);
+      lastError = error instanceof Error ? error.message : errorToString(error);
     }
 
     await new Promise<void>((resolve, reject) => {
@@ -1,4 +1,5 @@
 import type { Translations } from "@/i18n";
+import { errorToString } from "@/lib/appError";
 
 function normalizePathForDisplay(path: string): string {
   return path.replace(/\\/g, "/");
@@ -16,7 +17,7 @@ function toErrorDetail(error: unknown): string {
   if (error instanceof Error) return error.message || "";
   if (typeof error === "string") return error;
   if (error === null || error === undefined) return "";
-  return String(error);
+  return errorToString(error);
 }
 
 export function formatDatabaseActionError(
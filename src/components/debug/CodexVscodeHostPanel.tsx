@@ -4,6 +4,7 @@ import { useEffect, useMemo, useState } from "react";
 import { CodexEmbeddedWebview } from "./CodexEmbeddedWebview";
 import { useLocaleStore } from "@/stores/useLocaleStore";
 import { reportOperationError } from "@/lib/reportError";
+import { errorToString } from "@/lib/appError";
 
 type HostInfo = {
   origin: string;
@@ -98,7 +99,7 @@ export function CodexVscodeHostPanel({ onClose }: Props) {
         const h = await fetch(`${host.origin}/health`).then((r) => r.json());
         if (!canceled) setHealth(h);
       } catch (e) {
-        if (!canceled) setHealth({ ok: false, error: e instanceof Error ? e.message : String(e) });
+        if (!canceled) setHealth({ ok: false, error: e instanceof Error ? e.message : errorToString(e) });
       }
     };
 
@@ -37,6 +37,7 @@ import {
   RESEARCH_PHASES,
   getPhaseLabel,
   getPhaseProgress,
+  formatEta,
   NoteReference,
   WebSearchResult,
 } from "@/stores/useDeepResearchStore";
@@ -591,7 +592,11 @@ export function DeepResearchCard({ className, chatId }: DeepResearchCardProps) {
   const { topic, phase, phaseMessage, keywords, foundNotes, webSearchResults, crawlingProgress, readingProgress, tokenUsage, error } =
     currentSession;
 
-  const progress = getPhaseProgress(phase);
+  // 优先使用后端推送的整体进度，旧会话回退到按阶段估算
+  const progress = currentSession.progress
+    ? Math.round(currentSession.progress.fraction * 100)
+    : getPhaseProgress(phase);
+  const etaSeconds = isRunning ? currentSession.progress?.eta_seconds : undefined;
 
   // 复制报告
   const handleCopy = () => {
@@ -672,6 +677,11 @@ ${reportContent}`;
           </div>
           <div className="text-xs text-muted-foreground">
             {phaseMessage}
+            {etaSeconds !== undefined && etaSeconds > 0 && (
+              <span className="ml-2 opacity-70">
+                {t.deepResearch.etaRemaining.replace('{time}', formatEta(etaSeconds))}
+              </span>
+            )}
           </div>
         </div>
 
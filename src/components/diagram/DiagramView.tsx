@@ -11,6 +11,7 @@ import { readFile, saveFile } from "@/lib/tauri";
 import { useAIStore } from "@/stores/useAIStore";
 import { useLocaleStore } from "@/stores/useLocaleStore";
 import { useUIStore } from "@/stores/useUIStore";
+import { errorToString } from "@/lib/appError";
 
 interface DiagramViewProps {
   filePath: string;
@@ -249,7 +250,7 @@ export function DiagramView({
         pendingSerializedRef.current = null;
         if (pending == null) return;
         void saveNow(pending).catch((err) => {
-          const message = err instanceof Error ? err.message : String(err);
+          const message = err instanceof Error ? err.message : errorToString(err);
           setError(t.diagramView.saveFailed.replace("{message}", message));
         });
       }, SAVE_DEBOUNCE_MS);
@@ -268,7 +269,7 @@ export function DiagramView({
       await saveNow(serialized);
       setHasUnsavedChanges(false);
     } catch (err) {
-      const message = err instanceof Error ? err.message : String(err);
+      const message = err instanceof Error ? err.message : errorToString(err);
       setError(t.diagramView.saveFailed.replace("{message}", message));
     } finally {
       setIsSaving(false);
@@ -295,7 +296,7 @@ export function DiagramView({
         if (!isMountedRef.current) {
           return false;
         }
-        const message = err instanceof Error ? err.message : String(err);
+        const message = err instanceof Error ? err.message : errorToString(err);
         if (resetOnError) {
           setInitialData(createInitialScene());
           latestElementsRef.current = [];
@@ -413,7 +414,7 @@ export function DiagramView({
       setLastSavedAt(Date.now());
       setError(null);
     } catch (err) {
-      const message = err instanceof Error ? err.message : String(err);
+      const message = err instanceof Error ? err.message : errorToString(err);
       setError(t.diagramView.loadFailed.replace("{message}", message));
     }
   }, [applySceneSnapshot, externalContent, hasUnsavedChanges, isManualSave, loading, t]);
@@ -69,6 +69,15 @@ vi.mock("@/stores/useFileStore", () => ({
 vi.mock("@/lib/tauri", () => ({
   readFile: vi.fn(async () => "![Hero](../assets/hero.png)"),
   readBinaryFileBase64: vi.fn(async () => "AAAA"),
+  readImageThumbnailBase64: vi.fn(async () => ({
+    mime_type: "image/webp",
+    width: 512,
+    height: 288,
+    source_width: 1280,
+    source_height: 720,
+    data: "AAAA",
+    cached: false,
+  })),
   showInExplorer: async () => undefined,
 }));
 
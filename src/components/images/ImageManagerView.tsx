@@ -1181,6 +1181,7 @@ function ImageDetailPanel({
           path={image.path}
           alt={image.name}
           className="aspect-[4/3] w-full rounded-2xl border border-border/60"
+          maxDim={1024}
           onDimensions={({ width, height }) => onDimension(width, height)}
         />
 
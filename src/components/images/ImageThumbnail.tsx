@@ -1,6 +1,6 @@
 import { useEffect, useMemo, useState } from "react";
 
-import { readBinaryFileBase64 } from "@/lib/tauri";
+import { readBinaryFileBase64, readImageThumbnailBase64 } from "@/lib/tauri";
 import { cn } from "@/lib/utils";
 
 type CachedPreview = {
@@ -11,6 +11,8 @@ type CachedPreview = {
 
 const previewCache = new Map<string, CachedPreview>();
 
+const DEFAULT_MAX_DIM = 512;
+
 const mimeTypeForPath = (path: string): string => {
   const extension = path.split(".").pop()?.toLowerCase();
   switch (extension) {
@@ -32,10 +34,26 @@ const mimeTypeForPath = (path: string): string => {
   }
 };
 
-async function loadImagePreview(path: string): Promise<CachedPreview> {
-  const cached = previewCache.get(path);
+const previewKey = (path: string, maxDim: number) => `${maxDim}:${path}`;
+
+async function loadImagePreview(path: string, maxDim: number): Promise<CachedPreview> {
+  const key = previewKey(path, maxDim);
+  const cached = previewCache.get(key);
   if (cached) return cached;
 
+  try {
+    const thumbnail = await readImageThumbnailBase64(path, maxDim);
+    const preview = {
+      src: `data:${thumbnail.mime_type};base64,${thumbnail.data}`,
+      width: thumbnail.source_width,
+      height: thumbnail.source_height,
+    };
+    previewCache.set(key, preview);
+    return preview;
+  } catch {
+    // Formats the backend cannot decode (SVG, AVIF) load at full resolution
+  }
+
   const base64 = await readBinaryFileBase64(path);
   const src = `data:${mimeTypeForPath(path)};base64,${base64}`;
   const dimensions = await new Promise<{ width: number; height: number }>((resolve) => {
@@ -57,7 +75,7 @@ async function loadImagePreview(path: string): Promise<CachedPreview> {
     width: dimensions.width,
     height: dimensions.height,
   };
-  previewCache.set(path, preview);
+  previewCache.set(key, preview);
   return preview;
 }
 
@@ -66,6 +84,8 @@ interface ImageThumbnailProps {
   alt: string;
   className?: string;
   imgClassName?: string;
+  /** Longest side of the preview in pixels */
+  maxDim?: number;
   onDimensions?: (dimensions: { width: number; height: number }) => void;
 }
 
@@ -74,9 +94,10 @@ export function ImageThumbnail({
   alt,
   className,
   imgClassName,
+  maxDim = DEFAULT_MAX_DIM,
   onDimensions,
 }: ImageThumbnailProps) {
-  const cached = useMemo(() => previewCache.get(path) ?? null, [path]);
+  const cached = useMemo(() => previewCache.get(previewKey(path, maxDim)) ?? null, [maxDim, path]);
   const [preview, setPreview] = useState<CachedPreview | null>(cached);
   const [status, setStatus] = useState<"idle" | "loading" | "error">(
     cached ? "idle" : "loading",
@@ -90,7 +111,7 @@ export function ImageThumbnail({
     }
 
     setStatus("loading");
-    loadImagePreview(path)
+    loadImagePreview(path, maxDim)
       .then((nextPreview) => {
         if (cancelled) return;
         setPreview(nextPreview);
@@ -105,7 +126,7 @@ export function ImageThumbnail({
     return () => {
       cancelled = true;
     };
-  }, [cached, onDimensions, path]);
+  }, [cached, maxDim, onDimensions, path]);
 
   if (preview) {
     return (
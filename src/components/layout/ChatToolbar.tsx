@@ -10,6 +10,7 @@ import {
   Microscope,
 } from "lucide-react";
 import type { ExportMessage } from "@/features/conversation-export/exportUtils";
+import type { TaskCost } from "@/stores/useRustAgentStore";
 
 interface ChatToolbarProps {
   showHistory: boolean;
@@ -22,6 +23,7 @@ interface ChatToolbarProps {
   onCancelExportSelection: () => void;
   onNewChat: () => void;
   agentTokens: number;
+  agentCost?: TaskCost | null;
   chatTokens: number;
   renderModeToggle: (className?: string) => React.ReactNode;
 }
@@ -37,6 +39,7 @@ export function ChatToolbar({
   onCancelExportSelection,
   onNewChat,
   agentTokens,
+  agentCost,
   chatTokens,
   renderModeToggle,
 }: ChatToolbarProps) {
@@ -73,6 +76,16 @@ export function ChatToolbar({
         <span className="ml-3 text-[11px] text-muted-foreground select-none whitespace-nowrap ui-compact-text ui-compact-hide-md">
           {t.ai.sessionTokens}: {chatMode === "agent" ? agentTokens : chatTokens}
         </span>
+        {chatMode === "agent" && agentCost && (
+          <span
+            className={`text-[11px] select-none whitespace-nowrap ui-compact-text ui-compact-hide-md ${
+              agentCost.nearBudget ? "text-amber-500" : "text-muted-foreground"
+            }`}
+          >
+            {agentCost.estimated ? "~" : ""}${agentCost.totalCost.toFixed(4)}
+            {agentCost.budget > 0 ? ` / $${agentCost.budget.toFixed(2)}` : ""}
+          </span>
+        )}
       </div>
       <div className="flex items-center gap-2">
         {isConversationMode && (
@@ -74,6 +74,7 @@ import {
   type LLMProviderType,
   type ThinkingMode,
 } from "@/services/llm";
+import { errorToString } from "@/lib/appError";
 
 type ChatAssistantPart =
   | { type: "text"; content: string }
@@ -165,6 +166,7 @@ export function MainAIChatShell() {
     error: _rustError,
     lastIntent: rustLastIntent,
     totalTokensUsed: rustTotalTokens,
+    taskCost: rustTaskCost,
     currentPlan: rustCurrentPlan,
     startTask: rustStartTask,
     abort: agentAbort,
@@ -488,7 +490,7 @@ export function MainAIChatShell() {
       setSelectedExportIds([]);
     } catch (error) {
       console.error("[ConversationExport] failed:", error);
-      alert(t.ai.exportFailed.replace("{error}", String(error)));
+      alert(t.ai.exportFailed.replace("{error}", errorToString(error)));
     } finally {
       setIsExportingConversation(false);
     }
@@ -1066,6 +1068,7 @@ export function MainAIChatShell() {
         onCancelExportSelection={handleCancelExportSelection}
         onNewChat={handleNewChat}
         agentTokens={rustTotalTokens}
+        agentCost={rustTaskCost}
         chatTokens={chatTotalTokens}
         renderModeToggle={(className) => <ModeToggle className={className} />}
       />
@@ -86,9 +86,9 @@ function BacklinksView() {
   }, [currentFile]);
   
   const backlinks = useMemo(() => {
-    if (!currentFileName) return [];
-    return getBacklinks(currentFileName);
-  }, [currentFileName, getBacklinks]);
+    if (!currentFile) return [];
+    return getBacklinks(currentFile);
+  }, [currentFile, getBacklinks]);
   
   if (!currentFile) {
     return (
@@ -14,6 +14,7 @@ import { cn } from "@/lib/utils";
 import { ChevronLeft, ChevronRight, ListTree, Loader2, FileText } from 'lucide-react';
 import { useLocaleStore } from '@/stores/useLocaleStore';
 import { readFile, stat } from "@tauri-apps/plugin-fs";
+import { errorToString } from "@/lib/appError";
 
 interface PDFViewerProps {
   filePath: string;
@@ -85,7 +86,7 @@ export function PDFViewer({ filePath, className }: PDFViewerProps) {
       } catch (err) {
         console.error("Failed to read PDF file:", err);
         if (!cancelled) {
-          const errorMessage = t.pdfViewer.readFailed.replace("{error}", String(err));
+          const errorMessage = t.pdfViewer.readFailed.replace("{error}", errorToString(err));
           setError(errorMessage);
           setLoading(false);
         }
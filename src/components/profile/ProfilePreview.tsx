@@ -5,6 +5,7 @@ import { buildProfileData } from "@/services/profile/profileData";
 import type { ProfilePageData } from "@/types/profile";
 import { cn } from "@/lib/utils";
 import { useLocaleStore } from "@/stores/useLocaleStore";
+import { errorToString } from "@/lib/appError";
 
 const emptyData: ProfilePageData = {
   profile: {
@@ -42,7 +43,7 @@ export function ProfilePreview() {
         const result = await buildProfileData(fileTree, profileConfig);
         if (alive) setData(result);
       } catch (err) {
-        if (alive) setError(err instanceof Error ? err.message : String(err));
+        if (alive) setError(err instanceof Error ? err.message : errorToString(err));
       } finally {
         if (alive) setIsLoading(false);
       }
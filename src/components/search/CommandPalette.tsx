@@ -23,6 +23,7 @@ import {
   User,
   UploadCloud,
 } from "lucide-react";
+import { errorToString } from "@/lib/appError";
 
 export type PaletteMode = "command" | "file" | "search";
 
@@ -228,7 +229,7 @@ export function CommandPalette({ isOpen, mode, onClose, onModeChange }: CommandP
           });
           alert(t.settingsModal.publishSuccess.replace("{path}", result.outputDir));
         } catch (error) {
-          const message = error instanceof Error ? error.message : String(error);
+          const message = error instanceof Error ? error.message : errorToString(error);
           alert(`${t.settingsModal.publishFailed}: ${message}`);
         }
       },
@@ -4,10 +4,12 @@ import { Cloud, Copy, Power } from "lucide-react";
 import { QRCodeSVG } from "qrcode.react";
 import { useLocaleStore } from "@/stores/useLocaleStore";
 import { reportOperationError } from "@/lib/reportError";
+import { errorToString } from "@/lib/appError";
 
 interface CloudRelayStatus {
   running: boolean;
   connected: boolean;
+  e2e?: boolean;
   relay_url?: string | null;
   pairing_payload?: string | null;
   error?: string | null;
@@ -17,6 +19,7 @@ interface CloudRelayConfig {
   relay_url: string;
   email: string;
   password: string;
+  e2e: boolean;
 }
 
 export function CloudRelaySection() {
@@ -29,6 +32,7 @@ export function CloudRelaySection() {
     relay_url: "",
     email: "",
     password: "",
+    e2e: false,
   });
 
   const loadStatus = async () => {
@@ -43,14 +47,14 @@ export function CloudRelaySection() {
         error: err,
         level: "warning",
       });
-      setError(String(err));
+      setError(errorToString(err));
     }
   };
 
   useEffect(() => {
     loadStatus();
     invoke<CloudRelayConfig>("cloud_relay_get_config")
-      .then((config) => setFormData(config))
+      .then((config) => setFormData({ ...config, e2e: Boolean(config.e2e) }))
       .catch((err) => {
         reportOperationError({
           source: "CloudRelaySection",
@@ -74,7 +78,7 @@ export function CloudRelaySection() {
         action: "Start cloud relay",
         error: err,
       });
-      setError(String(err));
+      setError(errorToString(err));
     } finally {
       setLoading(false);
     }
@@ -91,7 +95,7 @@ export function CloudRelaySection() {
         action: "Stop cloud relay",
         error: err,
       });
-      setError(String(err));
+      setError(errorToString(err));
     } finally {
       setLoading(false);
     }
@@ -143,6 +147,7 @@ export function CloudRelaySection() {
       <div className="space-y-2 text-xs text-muted-foreground">
         <div>
           {t.settingsModal.cloudRelayStatus}: {isConnected ? t.settingsModal.cloudRelayConnected : t.settingsModal.cloudRelayDisconnected}
+          {isConnected && status?.e2e && ` · ${t.settingsModal.cloudRelayE2eActive}`}
         </div>
         {statusError && <div className="text-destructive">{statusError}</div>}
         {error && !statusError && <div className="text-destructive">{error}</div>}
@@ -187,6 +192,19 @@ export function CloudRelaySection() {
             />
           </div>
         </div>
+        <label className="flex items-start gap-2 text-xs text-muted-foreground">
+          <input
+            type="checkbox"
+            className="mt-0.5"
+            checked={formData.e2e}
+            disabled={isRunning}
+            onChange={(e) => setFormData({ ...formData, e2e: e.target.checked })}
+          />
+          <span>
+            <span className="text-foreground/90">{t.settingsModal.cloudRelayE2e}</span>
+            <span className="block text-[10px]">{t.settingsModal.cloudRelayE2eHint}</span>
+          </span>
+        </label>
       </div>
 
       {status?.pairing_payload && (
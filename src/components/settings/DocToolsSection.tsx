@@ -1,8 +1,16 @@
 import { useEffect, useState } from "react";
-import { Download, RefreshCw, PackageCheck, PackageX } from "lucide-react";
+import { listen } from "@tauri-apps/api/event";
+import { Download, RefreshCw, PackageCheck, PackageX, X } from "lucide-react";
 import { useLocaleStore } from "@/stores/useLocaleStore";
-import { getDocToolsStatus, installDocTools, type DocToolsStatus } from "@/lib/tauri";
+import {
+  cancelDocToolsInstall,
+  getDocToolsStatus,
+  installDocTools,
+  type DocToolsInstallProgress,
+  type DocToolsStatus,
+} from "@/lib/tauri";
 import { reportOperationError } from "@/lib/reportError";
+import { errorToString } from "@/lib/appError";
 
 export function DocToolsSection() {
   const { t } = useLocaleStore();
@@ -10,6 +18,7 @@ export function DocToolsSection() {
   const [loading, setLoading] = useState(false);
   const [installing, setInstalling] = useState(false);
   const [error, setError] = useState<string | null>(null);
+  const [progress, setProgress] = useState<DocToolsInstallProgress | null>(null);
 
   const loadStatus = async () => {
     setLoading(true);
@@ -24,7 +33,7 @@ export function DocToolsSection() {
         error: err,
         level: "warning",
       });
-      setError(String(err));
+      setError(errorToString(err));
     } finally {
       setLoading(false);
     }
@@ -34,8 +43,24 @@ export function DocToolsSection() {
     loadStatus();
   }, []);
 
+  useEffect(() => {
+    let disposed = false;
+    let unlisten: (() => void) | undefined;
+    void listen<DocToolsInstallProgress>("doc-tools:install-progress", (event) => {
+      setProgress(event.payload);
+    }).then((dispose) => {
+      if (disposed) dispose();
+      else unlisten = dispose;
+    });
+    return () => {
+      disposed = true;
+      unlisten?.();
+    };
+  }, []);
+
   const handleInstall = async () => {
     setInstalling(true);
+    setProgress(null);
     try {
       const data = await installDocTools();
       setStatus(data);
@@ -46,12 +71,49 @@ export function DocToolsSection() {
         action: "Install doc tools",
         error: err,
       });
-      setError(String(err));
+      setError(errorToString(err));
     } finally {
       setInstalling(false);
+      setProgress(null);
     }
   };
 
+  const handleCancel = async () => {
+    try {
+      await cancelDocToolsInstall();
+    } catch (err) {
+      reportOperationError({
+        source: "DocToolsSection.handleCancel",
+        action: "Cancel doc tools install",
+        error: err,
+        level: "warning",
+      });
+    }
+  };
+
+  const progressLabel = (() => {
+    if (!progress) return t.settingsModal.docToolsInstalling;
+    switch (progress.phase) {
+      case "verifying":
+        return t.settingsModal.docToolsVerifying;
+      case "extracting":
+        return t.settingsModal.docToolsExtracting;
+      case "downloading": {
+        const mb = (bytes: number) => (bytes / 1024 / 1024).toFixed(1);
+        const amount = progress.totalBytes
+          ? `${mb(progress.downloadedBytes)} / ${mb(progress.totalBytes)} MB`
+          : `${mb(progress.downloadedBytes)} MB`;
+        return `${t.settingsModal.docToolsDownloading} ${amount}`;
+      }
+      default:
+        return t.settingsModal.docToolsInstalling;
+    }
+  })();
+  const progressPercent =
+    progress?.phase === "downloading" && progress.totalBytes
+      ? Math.min(100, (progress.downloadedBytes / progress.totalBytes) * 100)
+      : null;
+
   const isInstalled = Boolean(status?.installed);
 
   return (
@@ -76,6 +138,16 @@ export function DocToolsSection() {
             <RefreshCw size={12} />
             {t.settingsModal.docToolsRefresh}
           </button>
+          {installing && (
+            <button
+              type="button"
+              onClick={handleCancel}
+              className="inline-flex items-center gap-1.5 rounded-lg border border-border px-3 py-1.5 text-xs font-medium text-foreground hover:bg-muted"
+            >
+              <X size={12} />
+              {t.settingsModal.docToolsCancel}
+            </button>
+          )}
           {!isInstalled && (
             <button
               type="button"
@@ -90,6 +162,20 @@ export function DocToolsSection() {
         </div>
       </div>
 
+      {installing && (
+        <div className="space-y-1">
+          <div className="text-xs text-muted-foreground">{progressLabel}</div>
+          {progressPercent !== null && (
+            <div className="h-1.5 w-full overflow-hidden rounded-full bg-muted">
+              <div
+                className="h-full bg-primary transition-[width]"
+                style={{ width: `${progressPercent}%` }}
+              />
+            </div>
+          )}
+        </div>
+      )}
+
       {error && <div className="text-xs text-destructive">{error}</div>}
 
       {status && (
@@ -125,12 +211,17 @@ export function DocToolsSection() {
             {Object.entries(status.tools).map(([name, tool]) => (
               <div
                 key={name}
+                title={[tool.version, tool.checksum].filter(Boolean).join("\n") || undefined}
                 className="flex items-center justify-between rounded-lg border border-border bg-background/70 px-2 py-1"
               >
                 <span className="text-[11px]">{name}</span>
-                <span className={tool.available ? "text-success" : "text-muted-foreground"}>
-                  {tool.available ? (tool.source ?? t.settingsModal.docToolsAvailable) : t.settingsModal.docToolsUnavailable}
-                </span>
+                {tool.available && tool.integrity === "mismatch" ? (
+                  <span className="text-destructive">{t.settingsModal.docToolsCorrupt}</span>
+                ) : (
+                  <span className={tool.available ? "text-success" : "text-muted-foreground"}>
+                    {tool.available ? (tool.source ?? t.settingsModal.docToolsAvailable) : t.settingsModal.docToolsUnavailable}
+                  </span>
+                )}
               </div>
             ))}
           </div>
@@ -5,6 +5,7 @@ import { QRCodeSVG } from "qrcode.react";
 import { useLocaleStore } from "@/stores/useLocaleStore";
 import { useFileStore } from "@/stores/useFileStore";
 import { reportOperationError } from "@/lib/reportError";
+import { errorToString } from "@/lib/appError";
 
 interface MobileGatewayStatus {
   running: boolean;
@@ -44,7 +45,7 @@ export function MobileGatewaySection() {
         error: err,
         level: "warning",
       });
-      setError(String(err));
+      setError(errorToString(err));
     }
   };
 
@@ -80,7 +81,7 @@ export function MobileGatewaySection() {
         action: "Start mobile gateway",
         error: err,
       });
-      setError(String(err));
+      setError(errorToString(err));
     } finally {
       setLoading(false);
     }
@@ -97,7 +98,7 @@ export function MobileGatewaySection() {
         action: "Stop mobile gateway",
         error: err,
       });
-      setError(String(err));
+      setError(errorToString(err));
     } finally {
       setLoading(false);
     }
@@ -132,7 +133,7 @@ export function MobileGatewaySection() {
         action: "Sync mobile workspace",
         error: err,
       });
-      setError(String(err));
+      setError(errorToString(err));
     }
   };
 
@@ -16,6 +16,7 @@ import { join } from "@/lib/path";
 import { exists } from "@/lib/tauri";
 import { reportOperationError } from "@/lib/reportError";
 import { resolveMountedOpenClawWorkspacePath } from "./openClawWorkspaceSectionModel";
+import { errorToString } from "@/lib/appError";
 
 function formatCheckedAt(timestamp: number): string {
   return new Date(timestamp).toLocaleString();
@@ -98,7 +99,7 @@ export function OpenClawWorkspaceSection() {
       setActionError(null);
       await openIfExists(snapshot, join(snapshot.workspacePath, "AGENTS.md"), openFile);
     } catch (error) {
-      const message = error instanceof Error ? error.message : String(error);
+      const message = error instanceof Error ? error.message : errorToString(error);
       setActionError(message);
       reportOperationError({
         source: "OpenClawWorkspaceSection.handleOpenAgents",
@@ -118,7 +119,7 @@ export function OpenClawWorkspaceSection() {
       await openFile(path);
       await refreshWorkspace(vaultPath, { workspacePath: snapshot.workspacePath });
     } catch (error) {
-      const message = error instanceof Error ? error.message : String(error);
+      const message = error instanceof Error ? error.message : errorToString(error);
       setActionError(message);
       reportOperationError({
         source: "OpenClawWorkspaceSection.handleOpenTodayMemory",
@@ -144,7 +145,7 @@ export function OpenClawWorkspaceSection() {
       });
       await refreshWorkspace(vaultPath, { workspacePath: targetWorkspacePath });
     } catch (error) {
-      const message = error instanceof Error ? error.message : String(error);
+      const message = error instanceof Error ? error.message : errorToString(error);
       setActionError(message);
       reportOperationError({
         source: "OpenClawWorkspaceSection.handleAttach",
@@ -185,7 +186,7 @@ export function OpenClawWorkspaceSection() {
         }
       }
     } catch (error) {
-      const message = error instanceof Error ? error.message : String(error);
+      const message = error instanceof Error ? error.message : errorToString(error);
       setActionError(message);
       reportOperationError({
         source: "OpenClawWorkspaceSection.handlePickWorkspace",
@@ -206,7 +207,7 @@ export function OpenClawWorkspaceSection() {
         workspacePath: scanPath,
       });
     } catch (error) {
-      const message = error instanceof Error ? error.message : String(error);
+      const message = error instanceof Error ? error.message : errorToString(error);
       setActionError(message);
       reportOperationError({
         source: "OpenClawWorkspaceSection.handleRescan",
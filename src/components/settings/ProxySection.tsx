@@ -4,6 +4,7 @@ import { Globe, Loader2 } from "lucide-react";
 import { useLocaleStore } from "@/stores/useLocaleStore";
 import { useUIStore } from "@/stores/useUIStore";
 import { reportOperationError } from "@/lib/reportError";
+import { errorToString } from "@/lib/appError";
 
 export function ProxySection() {
   const { t } = useLocaleStore();
@@ -53,7 +54,7 @@ export function ProxySection() {
     } catch (err) {
       setTestResult({
         ok: false,
-        msg: t.settingsModal.proxyTestFailed.replace("{error}", String(err)),
+        msg: t.settingsModal.proxyTestFailed.replace("{error}", errorToString(err)),
       });
     } finally {
       setTesting(false);
@@ -7,6 +7,7 @@ import { useProfileStore } from "@/stores/useProfileStore";
 import { usePublishStore } from "@/stores/usePublishStore";
 import { publishSite } from "@/services/publish/exporter";
 import { getDefaultPublishOutputDir } from "@/services/publish/config";
+import { errorToString } from "@/lib/appError";
 
 interface PublishSettingsSectionProps {
   vaultPath: string | null;
@@ -63,7 +64,7 @@ export function PublishSettingsSection({ vaultPath, fileTree }: PublishSettingsS
       });
       setResult(t.settingsModal.publishSuccess.replace("{path}", response.outputDir));
     } catch (err) {
-      const message = err instanceof Error ? err.message : String(err);
+      const message = err instanceof Error ? err.message : errorToString(err);
       setError(`${t.settingsModal.publishFailed}: ${message}`);
     } finally {
       setPublishing(false);
@@ -13,6 +13,7 @@ import {
 } from "@/stores/useUpdateStore";
 import { normalizeErrorMessage, reportOperationError } from "@/lib/reportError";
 import { retryWithExponentialBackoff } from "@/lib/retry";
+import { errorToString } from "@/lib/appError";
 
 type DownloadEvent = {
     event: string;
@@ -104,7 +105,7 @@ export function UpdateChecker() {
                 error: err,
                 level: "warning",
             });
-            setCheckError(err instanceof Error ? err.message : String(err));
+            setCheckError(err instanceof Error ? err.message : errorToString(err));
             setCheckStatus("error");
         }
     };
@@ -219,7 +220,7 @@ export function UpdateChecker() {
                 action: "Relaunch app after update",
                 error: err,
             });
-            setCheckError(err instanceof Error ? err.message : String(err));
+            setCheckError(err instanceof Error ? err.message : errorToString(err));
         }
     };
 
@@ -495,7 +495,7 @@ export function WebDAVSettings({ compact = false }: WebDAVSettingsProps) {
 
           {pendingSyncPlan.conflict_count > 0 && (
             <div className="rounded-lg border border-warning/20 bg-warning/10 p-3 text-xs text-warning">
-              Conflicts stay pending and are skipped during execution. Review the highlighted entries before trusting the sync result.
+              Executing keeps both versions of each conflict: the remote version is saved as a conflicted copy next to the note and the local version is uploaded.
             </div>
           )}
 
@@ -521,6 +521,9 @@ export function WebDAVSettings({ compact = false }: WebDAVSettingsProps) {
             {lastSyncResult.uploaded} uploaded, {lastSyncResult.downloaded} downloaded
             {lastSyncResult.conflicts > 0 && `, ${lastSyncResult.conflicts} conflicts`}
           </p>
+          {lastSyncResult.conflict_copies?.map((path) => (
+            <p key={path} className="text-warning truncate" title={path}>Conflicted copy: {path}</p>
+          ))}
           {lastSyncResult.errors.length > 0 && <p className="text-destructive">{lastSyncResult.errors.length} errors occurred</p>}
         </div>
       )}
@@ -44,6 +44,7 @@ import { TypesettingToolbar } from "./TypesettingToolbar";
 import { TypesettingPreviewPage } from "./TypesettingPreviewPage";
 import { useTypesettingInit } from "./hooks/useTypesettingInit";
 import { useTypesettingExport } from "./hooks/useTypesettingExport";
+import { errorToString } from "@/lib/appError";
 
 declare global {
   interface Window {
@@ -182,7 +183,7 @@ export function TypesettingDocumentPane({ path, onExportReady, autoOpen = true }
       try {
         fontPath = await getTypesettingFixtureFontPath();
       } catch (err) {
-        const reason = String(err);
+        const reason = errorToString(err);
         setLayoutError(reason);
         updateLayoutSummary(path, `Layout unavailable: ${reason}`);
         setBodyLayout(null);
@@ -399,7 +400,7 @@ export function TypesettingDocumentPane({ path, onExportReady, autoOpen = true }
         setFooterLayout(nextFooterLayout);
       } catch (err) {
         if (layoutRunRef.current !== runId) return;
-        const reason = String(err);
+        const reason = errorToString(err);
         setLayoutError(reason);
         updateLayoutSummary(path, `Layout unavailable: ${reason}`);
         setBodyLayout(null);
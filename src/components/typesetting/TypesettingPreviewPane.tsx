@@ -10,6 +10,7 @@ import {
   TypesettingPreviewPageMm,
   TypesettingTextLayout,
 } from "@/lib/tauri";
+import { errorToString } from "@/lib/appError";
 
 const DEFAULT_DPI = 96;
 const MIN_ZOOM = 0.5;
@@ -78,7 +79,7 @@ export function TypesettingPreviewPane() {
       })
       .catch((err) => {
         if (active) {
-          setError(String(err));
+          setError(errorToString(err));
         }
       });
 
@@ -117,7 +118,7 @@ export function TypesettingPreviewPane() {
 
     loadLayout().catch((err) => {
       if (active) {
-        setLayoutError(String(err));
+        setLayoutError(errorToString(err));
       }
     });
 
@@ -5,9 +5,9 @@ import { join, tempDir } from "@tauri-apps/api/path";
 import { open as openExternal } from "@tauri-apps/plugin-shell";
 import {
   getTypesettingExportPdfBase64,
-  getTypesettingRenderDocxPdfBase64,
-  getDocToolsStatus,
+  getTypesettingRenderDocxPdf,
   installDocTools,
+  type TypesettingDocxRenderer,
   type TypesettingPreviewBoxMm,
   type TypesettingPreviewPageMm,
 } from "@/lib/tauri";
@@ -22,6 +22,7 @@ import {
   pxToMm,
   pxToPt,
 } from "../typesettingUtils";
+import { errorToString } from "@/lib/appError";
 
 interface UseTypesettingExportOptions {
   path: string;
@@ -87,6 +88,7 @@ export function useTypesettingExport({
   const [openOfficeTotalPages, setOpenOfficeTotalPages] = useState(0);
   const [openOfficeStale, setOpenOfficeStale] = useState(false);
   const [openOfficeAutoRefresh, setOpenOfficeAutoRefresh] = useState(false);
+  const [openOfficeRenderer, setOpenOfficeRenderer] = useState<TypesettingDocxRenderer | null>(null);
   const openOfficeRefreshRef = useRef<number | null>(null);
   const [docToolsInstalling, setDocToolsInstalling] = useState(false);
 
@@ -233,28 +235,11 @@ export function useTypesettingExport({
     totalPages,
   ]);
 
-  const ensureOpenOfficeAvailable = useCallback(async (): Promise<boolean> => {
+  const renderOpenOfficePdfBytes = useCallback(async (): Promise<Uint8Array | null> => {
     if (!tauriAvailable) {
       setOpenOfficeError("OpenOffice preview requires desktop app.");
-      return false;
-    }
-    try {
-      const status = await getDocToolsStatus();
-      const soffice = status.tools?.soffice;
-      if (!soffice?.available) {
-        setOpenOfficeError("soffice not available. Install doc tools.");
-        return false;
-      }
-      return true;
-    } catch (err) {
-      setOpenOfficeError(String(err));
-      return false;
+      return null;
     }
-  }, [tauriAvailable]);
-
-  const renderOpenOfficePdfBytes = useCallback(async (): Promise<Uint8Array | null> => {
-    const available = await ensureOpenOfficeAvailable();
-    if (!available) return null;
     if (!doc) {
       setOpenOfficeError("OpenOffice preview requires a document.");
       return null;
@@ -270,13 +255,15 @@ export function useTypesettingExport({
       );
       tempDocxPath = docxPath;
       await exportDocx(path, docxPath);
-      const payload = await getTypesettingRenderDocxPdfBase64(docxPath);
-      const bytes = decodeBase64ToBytes(payload);
+      // Simple documents render in-process; soffice is only needed as a fallback
+      const rendered = await getTypesettingRenderDocxPdf(docxPath);
+      const bytes = decodeBase64ToBytes(rendered.pdf_base64);
+      setOpenOfficeRenderer(rendered.renderer);
       setOpenOfficePdf(bytes);
       setOpenOfficeStale(false);
       return bytes;
     } catch (err) {
-      const reason = String(err);
+      const reason = errorToString(err);
       setOpenOfficeError(reason);
       return null;
     } finally {
@@ -289,7 +276,7 @@ export function useTypesettingExport({
       }
       setOpenOfficeLoading(false);
     }
-  }, [doc, ensureOpenOfficeAvailable, exportDocx, path]);
+  }, [doc, exportDocx, path, tauriAvailable]);
 
   const getExportPdfBytes = useCallback(async (): Promise<Uint8Array> => {
     if (openOfficePreview) {
@@ -331,7 +318,7 @@ export function useTypesettingExport({
         await renderOpenOfficePdfBytes();
       }
     } catch (err) {
-      setOpenOfficeError(String(err));
+      setOpenOfficeError(errorToString(err));
     } finally {
       setDocToolsInstalling(false);
     }
@@ -340,6 +327,7 @@ export function useTypesettingExport({
   // Reset OpenOffice state on path/settings change
   useEffect(() => {
     setOpenOfficePdf(null);
+    setOpenOfficeRenderer(null);
     setOpenOfficeTotalPages(0);
     setOpenOfficeError(null);
     setOpenOfficeStale(false);
@@ -479,6 +467,7 @@ export function useTypesettingExport({
     openOfficeStale,
     openOfficeAutoRefresh,
     setOpenOfficeAutoRefresh,
+    openOfficeRenderer,
     docToolsInstalling,
     // Handlers
     handleExport,
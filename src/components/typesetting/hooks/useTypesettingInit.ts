@@ -18,6 +18,7 @@ import {
 } from "@/typesetting/fontPaths";
 import { findFirstExistingFontPath } from "../typesettingUtils";
 import type { TypesettingDoc } from "@/stores/useTypesettingDocStore";
+import { errorToString } from "@/lib/appError";
 
 export function useTypesettingInit(
   path: string,
@@ -34,7 +35,7 @@ export function useTypesettingInit(
   useEffect(() => {
     if (!autoOpen) return;
     if (doc) return;
-    openDoc(path).catch((err) => setError(String(err)));
+    openDoc(path).catch((err) => setError(errorToString(err)));
   }, [autoOpen, doc, openDoc, path]);
 
   useEffect(() => {
@@ -16,7 +16,8 @@ import {
   X,
   Edit3,
   Check,
-  Minus
+  Minus,
+  Search
 } from 'lucide-react';
 import {
   VideoNoteFile,
@@ -29,6 +30,7 @@ import {
   createVideoNoteFile,
   exportToMarkdown,
   getVideoCid,
+  extractPage,
   getDanmakuList,
   filterNoteDanmakus,
   getVideoNoteFilePath,
@@ -41,6 +43,7 @@ import { useLocaleStore } from '@/stores/useLocaleStore';
 import { saveFile } from '@/lib/tauri';
 import { invoke } from '@tauri-apps/api/core';
 import { reportOperationError } from '@/lib/reportError';
+import { FindBar } from '@/components/browser/FindBar';
 
 interface VideoNoteViewProps {
   onClose?: () => void;
@@ -91,6 +94,7 @@ export function VideoNoteView({
   
   // 内嵌 WebView 状态
   const [webviewCreated, setWebviewCreated] = useState(false);
+  const [showFind, setShowFind] = useState(false);
 
   // 记录是否已自动加载
   const autoLoadedRef = useRef(false);
@@ -257,7 +261,7 @@ export function VideoNoteView({
     setIsSyncingDanmaku(true);
     try {
       // 1. 获取视频 CID
-      const cid = await getVideoCid(noteFile.video.bvid);
+      const cid = await getVideoCid(noteFile.video.bvid, extractPage(noteFile.video.url));
       if (!cid) {
         reportOperationError({
           source: "VideoNoteView.handleSyncDanmaku",
@@ -601,6 +605,14 @@ export function VideoNoteView({
           >
             <ExternalLink className="w-4 h-4" />
           </button>
+          <button
+            onClick={() => setShowFind((value) => !value)}
+            disabled={!webviewCreated}
+            className="p-1.5 hover:bg-accent rounded transition-colors disabled:opacity-40"
+            title={t.browser.findPlaceholder}
+          >
+            <Search className="w-4 h-4" />
+          </button>
           <button
             onClick={handleExport}
             className="p-1.5 hover:bg-accent rounded transition-colors"
@@ -629,6 +641,9 @@ export function VideoNoteView({
       <div className="flex-1 flex overflow-hidden">
         {/* 视频播放器区域 */}
         <div className="flex-1 flex flex-col min-w-0">
+          {showFind && webviewCreated && (
+            <FindBar tabId="video" onClose={() => setShowFind(false)} />
+          )}
           {/* 内嵌 WebView 区域 */}
           <div 
             ref={videoContainerRef}
@@ -259,8 +259,11 @@ export default {
     agentQueueTitle: 'Task queue',
     agentQueueCurrent: 'Current',
     agentQueuePending: '{count} queued',
+    agentQueueCancel: 'Remove from queue',
+    agentQueueMoveUp: 'Move up',
     agentQueueWaitingApprovalHint: 'Approve or reject the pending tool call first.',
     agentRetryTitle: 'Retrying request',
+    agentRateLimitedTitle: 'Rate limited, waiting to retry',
     agentRetryAttempt: 'Attempt {attempt}/{max}',
     agentRetryReason: 'Reason',
     agentRetryIn: 'Retrying in {seconds}s',
@@ -277,6 +280,8 @@ export default {
     skillsManagerClose: 'Close',
     skillsManagerEmpty: 'No skills available',
     skillsManagerError: 'Failed to load skills',
+    skillsManagerInvalid: 'Invalid skill metadata',
+    skillsManagerAutoActivate: 'Auto-activates on',
     skillsManagerSourceWorkspace: 'Workspace',
     skillsManagerSourceUser: 'User',
     skillsManagerSourceBuiltin: 'Built-in',
@@ -1188,6 +1193,9 @@ export default {
     cloudRelayQrHint: 'Scan with your phone to connect via cloud.',
     cloudRelayCopy: 'Copy',
     cloudRelayCopied: 'Copied',
+    cloudRelayE2e: 'End-to-end encryption',
+    cloudRelayE2eHint: 'The relay server only sees ciphertext. Phones must scan the new pairing code.',
+    cloudRelayE2eActive: 'End-to-end encrypted',
     docToolsTitle: 'Doc Tools Pack',
     docToolsDesc: 'Optional Python + document conversion tools for docx skills.',
     docToolsInstall: 'Download and install',
@@ -1202,6 +1210,11 @@ export default {
     docToolsAvailable: 'Available',
     docToolsUnavailable: 'Unavailable',
     docToolsHint: 'When installed, Lumina prefers the pack binaries over system tools.',
+    docToolsDownloading: 'Downloading',
+    docToolsVerifying: 'Verifying checksum...',
+    docToolsExtracting: 'Extracting...',
+    docToolsCancel: 'Cancel',
+    docToolsCorrupt: 'Corrupt',
     docToolsPrompt: 'Doc Tools Pack is not installed. Download and install now?',
     softwareUpdateDescription: 'Check the current version and open the updater window.',
     softwareUpdateOpen: 'Open updater',
@@ -1320,6 +1333,16 @@ export default {
     home: 'Home',
     addressPlaceholder: 'Search or enter address',
     go: 'Go',
+    downloadFolder: 'Download folder',
+    downloading: 'Downloading {name} ({size})',
+    downloadSaved: 'Saved {name}',
+    downloadFailed: 'Download failed: {error}',
+    findPlaceholder: 'Find in page',
+    findNoResults: 'No results',
+    findMatchCase: 'Match case',
+    findPrevious: 'Previous match',
+    findNext: 'Next match',
+    findClose: 'Close find',
   },
 
   // Knowledge Graph
@@ -1541,11 +1564,15 @@ export default {
     noManualConfirm: 'No manual confirmation needed',
     autoCompactContext: 'Auto-compact context',
     autoCompactHint: 'Auto-generate a summary near the context limit and keep the session (95%)',
+    dryRunMode: 'Dry run mode',
+    dryRunHint: 'Write tools only preview changes; files are not modified',
     semanticSearch: 'Semantic Search (RAG)',
     indexing: 'Indexing',
     indexed: 'Indexed {count} chunks',
     notIndexed: 'Index not built yet',
     rebuildIndex: 'Rebuild Index',
+    autoReindex: 'Auto-update index',
+    autoReindexHint: 'Re-embed notes in the background when they change',
     cancelIndex: 'Cancel Index',
     embeddingService: 'Embedding Service',
     embeddingApiKey: 'Embedding API Key',
@@ -1614,6 +1641,7 @@ export default {
     moreResults: 'More results',
     crawlingProgress: 'Crawling web pages',
     crawledPages: '{count} pages crawled',
+    etaRemaining: '~{time} left',
     clarifyTitle: 'Need clarification',
     clarifyPlaceholder: 'Add details to refine your research goal...',
     clarifySkip: 'Skip',
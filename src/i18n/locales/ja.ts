@@ -259,8 +259,11 @@ export default {
     agentQueueTitle: 'タスクキュー',
     agentQueueCurrent: '現在実行',
     agentQueuePending: '{count} 件待機中',
+    agentQueueCancel: 'キューから削除',
+    agentQueueMoveUp: '上へ移動',
     agentQueueWaitingApprovalHint: '先に保留中のツール承認を処理してください。',
     agentRetryTitle: '再試行中',
+    agentRateLimitedTitle: 'レート制限中、再試行を待機',
     agentRetryAttempt: '{attempt}/{max} 回目',
     agentRetryReason: '理由',
     agentRetryIn: '{seconds} 秒後に再試行',
@@ -277,6 +280,8 @@ export default {
     skillsManagerClose: '閉じる',
     skillsManagerEmpty: 'スキルがありません',
     skillsManagerError: 'スキルの読み込みに失敗しました',
+    skillsManagerInvalid: 'スキルのメタデータが不正です',
+    skillsManagerAutoActivate: '自動有効化の条件',
     skillsManagerSourceWorkspace: 'ワークスペース',
     skillsManagerSourceUser: 'ユーザー',
     skillsManagerSourceBuiltin: '内蔵',
@@ -1083,6 +1088,9 @@ export default {
     cloudRelayQrHint: 'QRを読み取ってクラウド接続します。',
     cloudRelayCopy: 'コピー',
     cloudRelayCopied: 'コピー済み',
+    cloudRelayE2e: 'エンドツーエンド暗号化',
+    cloudRelayE2eHint: 'リレーサーバーには暗号文のみが届きます。スマートフォンで新しいペアリングコードを読み取ってください。',
+    cloudRelayE2eActive: 'エンドツーエンド暗号化済み',
     docToolsTitle: 'Doc ツールパック',
     docToolsDesc: 'docx スキル向けの Python と文書変換ツール（任意）。',
     docToolsInstall: 'ダウンロードしてインストール',
@@ -1097,6 +1105,11 @@ export default {
     docToolsAvailable: '利用可能',
     docToolsUnavailable: '利用不可',
     docToolsHint: 'インストール後はパック内の実行ファイルを優先します。',
+    docToolsDownloading: 'ダウンロード中',
+    docToolsVerifying: 'チェックサムを検証中...',
+    docToolsExtracting: '展開中...',
+    docToolsCancel: 'キャンセル',
+    docToolsCorrupt: '破損',
     docToolsPrompt: 'Doc ツールパックが未インストールです。今すぐダウンロードしますか？',
     softwareUpdateDescription: '現在のバージョンを確認して更新ウィンドウを開きます。',
     softwareUpdateOpen: '更新ウィンドウを開く',
@@ -1321,6 +1334,16 @@ export default {
     home: 'ホーム',
     addressPlaceholder: '検索またはURLを入力',
     go: '移動',
+    downloadFolder: 'ダウンロード先',
+    downloading: '{name} をダウンロード中（{size}）',
+    downloadSaved: '{name} を保存しました',
+    downloadFailed: 'ダウンロード失敗：{error}',
+    findPlaceholder: 'ページ内を検索',
+    findNoResults: '一致なし',
+    findMatchCase: '大文字と小文字を区別',
+    findPrevious: '前の一致',
+    findNext: '次の一致',
+    findClose: '検索を閉じる',
   },
 
   // ナレッジグラフ
@@ -1542,11 +1565,15 @@ export default {
     noManualConfirm: '手動確認不要',
     autoCompactContext: 'コンテキストを自動圧縮',
     autoCompactHint: 'コンテキスト上限に近づくと自動で要約し、同じセッションに保持します (95%)',
+    dryRunMode: 'ドライランモード',
+    dryRunHint: '書き込み系ツールは変更をプレビューするだけで、ファイルは変更されません',
     semanticSearch: 'セマンティック検索 (RAG)',
     indexing: 'インデックス中',
     indexed: '{count} 個のチャンクをインデックス済み',
     notIndexed: 'インデックス未作成',
     rebuildIndex: 'インデックス再構築',
+    autoReindex: 'インデックスを自動更新',
+    autoReindexHint: 'ノートが変更されたらバックグラウンドで再インデックスします',
     cancelIndex: 'インデックスキャンセル',
     embeddingService: 'Embedding サービス',
     embeddingApiKey: 'Embedding API Key',
@@ -1615,6 +1642,7 @@ export default {
     moreResults: 'さらに表示',
     crawlingProgress: 'ページをクロール中',
     crawledPages: '{count} ページをクロール',
+    etaRemaining: '残り約 {time}',
     clarifyTitle: '追加情報が必要',
     clarifyPlaceholder: '調査の目的を補足してください...',
     clarifySkip: 'スキップ',
@@ -259,8 +259,11 @@ export default {
     agentQueueTitle: '任务队列',
     agentQueueCurrent: '当前执行',
     agentQueuePending: '排队中 {count} 项',
+    agentQueueCancel: '移出队列',
+    agentQueueMoveUp: '上移',
     agentQueueWaitingApprovalHint: '请先处理待审批工具调用。',
     agentRetryTitle: '网络重试中',
+    agentRateLimitedTitle: '触发限流，等待重试',
     agentRetryAttempt: '第 {attempt}/{max} 次',
     agentRetryReason: '原因',
     agentRetryIn: '{seconds} 秒后重试',
@@ -277,6 +280,8 @@ export default {
     skillsManagerClose: '关闭',
     skillsManagerEmpty: '暂无技能',
     skillsManagerError: '技能加载失败',
+    skillsManagerInvalid: '技能元数据无效',
+    skillsManagerAutoActivate: '自动启用条件',
     skillsManagerSourceWorkspace: '工作空间',
     skillsManagerSourceUser: '用户',
     skillsManagerSourceBuiltin: '内置',
@@ -1189,6 +1194,9 @@ export default {
     cloudRelayQrHint: '手机扫码通过云端连接。',
     cloudRelayCopy: '复制',
     cloudRelayCopied: '已复制',
+    cloudRelayE2e: '端到端加密',
+    cloudRelayE2eHint: '中继服务器只能看到密文。手机需要重新扫描新的配对码。',
+    cloudRelayE2eActive: '已端到端加密',
     docToolsTitle: 'Doc 工具包',
     docToolsDesc: '为 docx 技能提供 Python 与文档转换工具支持（可选安装）。',
     docToolsInstall: '下载并安装',
@@ -1203,6 +1211,11 @@ export default {
     docToolsAvailable: '可用',
     docToolsUnavailable: '不可用',
     docToolsHint: '安装后会优先使用工具包中的可执行文件。',
+    docToolsDownloading: '正在下载',
+    docToolsVerifying: '正在校验...',
+    docToolsExtracting: '正在解压...',
+    docToolsCancel: '取消',
+    docToolsCorrupt: '已损坏',
     docToolsPrompt: '未检测到 Doc 工具包，是否现在下载并安装？',
     softwareUpdateDescription: '查看当前版本并打开更新窗口。',
     softwareUpdateOpen: '打开更新窗口',
@@ -1321,6 +1334,16 @@ export default {
     home: '主页',
     addressPlaceholder: '搜索或输入网址',
     go: '前往',
+    downloadFolder: '下载目录',
+    downloading: '正在下载 {name}（{size}）',
+    downloadSaved: '已保存 {name}',
+    downloadFailed: '下载失败：{error}',
+    findPlaceholder: '在页面中查找',
+    findNoResults: '无结果',
+    findMatchCase: '区分大小写',
+    findPrevious: '上一个',
+    findNext: '下一个',
+    findClose: '关闭查找',
   },
 
   // 知识图谱
@@ -1542,11 +1565,15 @@ export default {
     noManualConfirm: '无需手动确认',
     autoCompactContext: '自动压缩上下文',
     autoCompactHint: '接近上下文上限时自动生成摘要并保留当前会话 (95%)',
+    dryRunMode: '试运行模式',
+    dryRunHint: '写入类工具只预览变更，不会修改文件',
     semanticSearch: '语义搜索 (RAG)',
     indexing: '正在索引',
     indexed: '已索引 {count} 个片段',
     notIndexed: '尚未建立索引',
     rebuildIndex: '重新索引',
+    autoReindex: '自动更新索引',
+    autoReindexHint: '笔记变更后在后台重新生成向量',
     cancelIndex: '取消索引',
     embeddingService: 'Embedding 服务',
     embeddingApiKey: 'Embedding API Key',
@@ -1615,6 +1642,7 @@ export default {
     moreResults: '查看更多结果',
     crawlingProgress: '正在抓取网页',
     crawledPages: '已抓取 {count} 页',
+    etaRemaining: '预计剩余 {time}',
     clarifyTitle: '需要澄清',
     clarifyPlaceholder: '补充说明你的研究目标...',
     clarifySkip: '跳过',
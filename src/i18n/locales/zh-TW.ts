@@ -259,8 +259,11 @@ export default {
     agentQueueTitle: '任務佇列',
     agentQueueCurrent: '目前執行',
     agentQueuePending: '佇列中 {count} 項',
+    agentQueueCancel: '移出佇列',
+    agentQueueMoveUp: '上移',
     agentQueueWaitingApprovalHint: '請先處理待審批工具呼叫。',
     agentRetryTitle: '網路重試中',
+    agentRateLimitedTitle: '觸發限流，等待重試',
     agentRetryAttempt: '第 {attempt}/{max} 次',
     agentRetryReason: '原因',
     agentRetryIn: '{seconds} 秒後重試',
@@ -277,6 +280,8 @@ export default {
     skillsManagerClose: '關閉',
     skillsManagerEmpty: '暫無技能',
     skillsManagerError: '技能載入失敗',
+    skillsManagerInvalid: '技能中繼資料無效',
+    skillsManagerAutoActivate: '自動啟用條件',
     skillsManagerSourceWorkspace: '工作區',
     skillsManagerSourceUser: '使用者',
     skillsManagerSourceBuiltin: '內建',
@@ -1083,6 +1088,9 @@ export default {
     cloudRelayQrHint: '手機掃碼透過雲端連線。',
     cloudRelayCopy: '複製',
     cloudRelayCopied: '已複製',
+    cloudRelayE2e: '端對端加密',
+    cloudRelayE2eHint: '中繼伺服器只能看到密文。手機需要重新掃描新的配對碼。',
+    cloudRelayE2eActive: '已端對端加密',
     docToolsTitle: 'Doc 工具包',
     docToolsDesc: '為 docx 技能提供 Python 與文件轉換工具（可選安裝）。',
     docToolsInstall: '下載並安裝',
@@ -1097,6 +1105,11 @@ export default {
     docToolsAvailable: '可用',
     docToolsUnavailable: '不可用',
     docToolsHint: '安裝後會優先使用工具包中的執行檔。',
+    docToolsDownloading: '正在下載',
+    docToolsVerifying: '正在校驗...',
+    docToolsExtracting: '正在解壓縮...',
+    docToolsCancel: '取消',
+    docToolsCorrupt: '已損毀',
     docToolsPrompt: '尚未安裝 Doc 工具包，是否立即下載並安裝？',
     softwareUpdateDescription: '查看目前版本並開啟更新視窗。',
     softwareUpdateOpen: '開啟更新視窗',
@@ -1321,6 +1334,16 @@ export default {
     home: '首頁',
     addressPlaceholder: '搜尋或輸入網址',
     go: '前往',
+    downloadFolder: '下載目錄',
+    downloading: '正在下載 {name}（{size}）',
+    downloadSaved: '已儲存 {name}',
+    downloadFailed: '下載失敗：{error}',
+    findPlaceholder: '在頁面中尋找',
+    findNoResults: '無結果',
+    findMatchCase: '區分大小寫',
+    findPrevious: '上一個',
+    findNext: '下一個',
+    findClose: '關閉尋找',
   },
 
   // 知識圖譜
@@ -1542,11 +1565,15 @@ export default {
     noManualConfirm: '無需手動確認',
     autoCompactContext: '自動壓縮上下文',
     autoCompactHint: '接近上下文上限時自動生成摘要並保留目前會話 (95%)',
+    dryRunMode: '試運行模式',
+    dryRunHint: '寫入類工具只預覽變更，不會修改檔案',
     semanticSearch: '語義搜尋 (RAG)',
     indexing: '正在索引',
     indexed: '已索引 {count} 個片段',
     notIndexed: '尚未建立索引',
     rebuildIndex: '重新索引',
+    autoReindex: '自動更新索引',
+    autoReindexHint: '筆記變更後在背景重新產生向量',
     cancelIndex: '取消索引',
     embeddingService: 'Embedding 服務',
     embeddingApiKey: 'Embedding API Key',
@@ -1615,6 +1642,7 @@ export default {
     moreResults: '查看更多結果',
     crawlingProgress: '正在抓取網頁',
     crawledPages: '已抓取 {count} 頁',
+    etaRemaining: '預計剩餘 {time}',
     clarifyTitle: '需要澄清',
     clarifyPlaceholder: '補充說明你的研究目標...',
     clarifySkip: '略過',
@@ -0,0 +1,35 @@
+import { describe, expect, it } from "vitest";
+import { validateEventPayload, type EventChannelSchema } from "./agentEventSchema";
+
+const channel: EventChannelSchema = {
+  channel: "agent-event",
+  tag_field: "type",
+  content_field: "data",
+  variants: [
+    {
+      tag: "queue_updated",
+      fields: [
+        { name: "running", type: "boolean", optional: false },
+        { name: "active_task", type: "string", optional: true },
+        { name: "queued", type: "QueuedTaskSummary[]", optional: false },
+      ],
+    },
+  ],
+};
+
+describe("validateEventPayload", () => {
+  it("accepts payloads that match the schema", () => {
+    expect(
+      validateEventPayload(channel, { type: "queue_updated", data: { running: true, queued: [] } }),
+    ).toEqual([]);
+  });
+
+  it("reports unknown tags and field drift", () => {
+    expect(validateEventPayload(channel, { type: "queue_changed", data: {} })).toEqual([
+      'unknown event type "queue_changed"',
+    ]);
+    expect(
+      validateEventPayload(channel, { type: "queue_updated", data: { running: true, pending: [] } }),
+    ).toEqual(["queue_updated.queued is missing", "queue_updated.pending is not in the schema"]);
+  });
+});
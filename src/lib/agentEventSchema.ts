@@ -0,0 +1,74 @@
+import { invoke } from "@tauri-apps/api/core";
+
+/** Version of the agent / deep-research event contract this frontend was written against */
+export const AGENT_EVENT_SCHEMA_VERSION = 1;
+
+export interface EventFieldSchema {
+  name: string;
+  type: string;
+  optional: boolean;
+}
+
+export interface EventVariantSchema {
+  tag: string;
+  fields: EventFieldSchema[];
+}
+
+export interface EventChannelSchema {
+  channel: string;
+  tag_field: string;
+  content_field: string;
+  variants: EventVariantSchema[];
+}
+
+export interface AgentEventSchema {
+  version: number;
+  agent: EventChannelSchema;
+  deep_research: EventChannelSchema;
+}
+
+export const fetchAgentEventSchema = () => invoke<AgentEventSchema>("agent_event_schema");
+
+/**
+ * Payload problems for one event: unknown tag, missing required fields or
+ * undescribed fields. Empty when the payload matches the schema.
+ */
+export function validateEventPayload(
+  channel: EventChannelSchema,
+  payload: { type: string; data: unknown },
+): string[] {
+  const variant = channel.variants.find((item) => item.tag === payload.type);
+  if (!variant) return [`unknown event type "${payload.type}"`];
+  const data =
+    payload.data && typeof payload.data === "object"
+      ? (payload.data as Record<string, unknown>)
+      : {};
+  const problems: string[] = [];
+  for (const field of variant.fields) {
+    if (!field.optional && !(field.name in data)) {
+      problems.push(`${payload.type}.${field.name} is missing`);
+    }
+  }
+  for (const key of Object.keys(data)) {
+    if (!variant.fields.some((field) => field.name === key)) {
+      problems.push(`${payload.type}.${key} is not in the schema`);
+    }
+  }
+  return problems;
+}
+
+/** Fetches the backend schema and warns when its version differs from ours */
+export async function checkAgentEventSchema(): Promise<AgentEventSchema | null> {
+  try {
+    const schema = await fetchAgentEventSchema();
+    if (schema.version !== AGENT_EVENT_SCHEMA_VERSION) {
+      console.warn(
+        `[AgentEvents] Backend event schema v${schema.version} differs from frontend v${AGENT_EVENT_SCHEMA_VERSION}; some events may be ignored`,
+      );
+    }
+    return schema;
+  } catch (error) {
+    console.warn("[AgentEvents] Failed to load event schema:", error);
+    return null;
+  }
+}
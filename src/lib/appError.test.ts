@@ -0,0 +1,19 @@
+import { describe, expect, it } from "vitest";
+import { errorToString, getAppErrorCode, isAppError } from "./appError";
+
+describe("appError", () => {
+  const payload = { code: "webview", message: "Webview error: Main window not found" };
+
+  it("recognizes serialized Rust errors", () => {
+    expect(isAppError(payload)).toBe(true);
+    expect(getAppErrorCode(payload)).toBe("webview");
+    expect(errorToString(payload)).toBe("Webview error: Main window not found");
+  });
+
+  it("falls back to String() for other values", () => {
+    expect(isAppError("Invalid path: x")).toBe(false);
+    expect(getAppErrorCode(new Error("boom"))).toBeNull();
+    expect(errorToString("Invalid path: x")).toBe("Invalid path: x");
+    expect(errorToString(new Error("boom"))).toBe("Error: boom");
+  });
+});
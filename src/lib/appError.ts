@@ -0,0 +1,47 @@
+/**
+ * Errors returned by Rust commands (`AppError`) arrive as `{ code, message }`.
+ * `code` is a stable error class the UI can branch on; `message` is the
+ * human-readable text.
+ */
+
+export type AppErrorCode =
+  | "io"
+  | "file_not_found"
+  | "invalid_path"
+  | "file_exists"
+  | "database"
+  | "trash"
+  | "webdav"
+  | "network"
+  | "update"
+  | "update_network"
+  | "update_integrity"
+  | "update_install"
+  | "update_state"
+  | "invalid_input"
+  | "parse"
+  | "webview"
+  | "external_tool"
+  | "typesetting"
+  | "read_only"
+  | "internal";
+
+export interface AppErrorPayload {
+  code: AppErrorCode;
+  message: string;
+}
+
+export const isAppError = (error: unknown): error is AppErrorPayload =>
+  typeof error === "object" &&
+  error !== null &&
+  typeof (error as { code?: unknown }).code === "string" &&
+  typeof (error as { message?: unknown }).message === "string" &&
+  !(error instanceof Error);
+
+/** Error class of a Rust command failure, or null for any other error */
+export const getAppErrorCode = (error: unknown): AppErrorCode | null =>
+  isAppError(error) ? error.code : null;
+
+/** Drop-in replacement for `String(error)` that also unwraps `AppError` payloads */
+export const errorToString = (error: unknown): string =>
+  isAppError(error) ? error.message : String(error);
@@ -3,6 +3,9 @@ export type FsChangePayload =
   | { type: "Renamed"; old_path?: string; new_path?: string }
   | { type: string; [key: string]: unknown };
 
+/** Payload of the "fs:changes" event: all coalesced changes of one debounce window */
+export type FsChangeBatchPayload = FsChangePayload[];
+
 function isNonEmptyString(value: unknown): value is string {
   return typeof value === "string" && value.trim().length > 0;
 }
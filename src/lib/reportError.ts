@@ -1,4 +1,5 @@
 import { useErrorStore, type AppErrorLevel } from "@/stores/useErrorStore";
+import { isAppError } from "@/lib/appError";
 
 export type ReportOperationErrorInput = {
   source: string;
@@ -21,6 +22,7 @@ const stringifyContext = (context?: Record<string, unknown>): string | undefined
 export const normalizeErrorMessage = (error: unknown): string => {
   if (error instanceof Error && error.message) return error.message;
   if (typeof error === "string") return error;
+  if (isAppError(error)) return error.message;
   if (error === null || error === undefined) return "Unknown error";
   try {
     return JSON.stringify(error);
@@ -1,3 +1,5 @@
+import { errorToString } from "@/lib/appError";
+
 export type RetryContext = {
   attempt: number;
   maxAttempts: number;
@@ -48,5 +50,5 @@ export async function retryWithExponentialBackoff<T>(
     }
   }
 
-  throw lastError instanceof Error ? lastError : new Error(String(lastError ?? "Unknown retry error"));
+  throw lastError instanceof Error ? lastError : new Error(errorToString(lastError ?? "Unknown retry error"));
 }
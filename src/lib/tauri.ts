@@ -1,10 +1,11 @@
-import { invoke } from "@tauri-apps/api/core";
+import { Channel, invoke } from "@tauri-apps/api/core";
 import type { SkillDetail, SkillInfo } from "@/types/skills";
 import type { PluginEntry, PluginInfo } from "@/types/plugins";
 import {
   readDir as tauriReadDir,
   rename as tauriRename,
 } from "@tauri-apps/plugin-fs";
+import { errorToString } from "@/lib/appError";
 
 export interface FileEntry {
   name: string;
@@ -26,9 +27,10 @@ export async function readFile(path: string): Promise<string> {
 
 /**
  * Save file content to disk
+ * Writes atomically; with `backup` the previous version is kept as `<name>.bak`
  */
-export async function saveFile(path: string, content: string): Promise<void> {
-  return invoke("save_file", { path, content });
+export async function saveFile(path: string, content: string, backup?: boolean): Promise<void> {
+  return invoke("save_file", backup === undefined ? { path, content } : { path, content, backup });
 }
 
 /**
@@ -45,6 +47,30 @@ export async function readBinaryFileBase64(path: string): Promise<string> {
   return invoke<string>("read_binary_file_base64", { path });
 }
 
+export interface ImageThumbnailData {
+  mime_type: string;
+  width: number;
+  height: number;
+  /** Size of the original image */
+  source_width: number;
+  source_height: number;
+  /** Base64-encoded image data */
+  data: string;
+  /** Served from the on-disk thumbnail cache */
+  cached: boolean;
+}
+
+/**
+ * Read a downscaled preview of an image that fits within maxDim pixels.
+ * Previews are cached on disk; use readBinaryFileBase64 for the full image.
+ */
+export async function readImageThumbnailBase64(
+  path: string,
+  maxDim: number,
+): Promise<ImageThumbnailData> {
+  return invoke<ImageThumbnailData>("read_image_thumbnail_base64", { path, maxDim });
+}
+
 export type TypesettingPreviewBoxMm = {
   x_mm: number;
   y_mm: number;
@@ -75,6 +101,57 @@ export type TypesettingTextLayout = {
 
 export type TypesettingParagraphAlign = "left" | "right" | "center" | "justify";
 
+/** Page geometry overrides (mm); unset fields use the default A4 layout. */
+export type TypesettingPageStyleInput = {
+  width_mm?: number;
+  height_mm?: number;
+  margin_top_mm?: number;
+  margin_right_mm?: number;
+  margin_bottom_mm?: number;
+  margin_left_mm?: number;
+  header_height_mm?: number;
+  footer_height_mm?: number;
+};
+
+/** Preview layout options; sizes are px at `dpi` (default 96). */
+export type TypesettingPreviewOptions = {
+  font_size?: number;
+  line_height?: number;
+  align?: TypesettingParagraphAlign;
+  first_line_indent?: number;
+  space_before?: number;
+  space_after?: number;
+  dpi?: number;
+  zoom?: number;
+  page_gap_px?: number;
+};
+
+export type TypesettingPreviewLine = {
+  line_index: number;
+  start_byte: number;
+  end_byte: number;
+  x_mm: number;
+  y_mm: number;
+  width_mm: number;
+  height_mm: number;
+};
+
+export type TypesettingPreviewPage = {
+  page_index: number;
+  top_px: number;
+  used_height_mm: number;
+  lines: TypesettingPreviewLine[];
+};
+
+export type TypesettingPreview = {
+  page: TypesettingPreviewPageMm;
+  page_width_px: number;
+  page_height_px: number;
+  page_count: number;
+  total_height_px: number;
+  pages: TypesettingPreviewPage[];
+};
+
 export const isTauriAvailable = (): boolean => {
   if (typeof window === "undefined") return false;
   const tauriInvoke = (window as typeof window & {
@@ -93,7 +170,7 @@ const invokeTypesetting = async <T>(
   try {
     return await invoke<T>(command, args);
   } catch (err) {
-    const message = err instanceof Error ? err.message : String(err);
+    const message = err instanceof Error ? err.message : errorToString(err);
     throw new Error(`Typesetting command ${command} failed: ${message}`);
   }
 };
@@ -182,12 +259,65 @@ export async function getTypesettingLayoutText(params: {
   return invokeTypesetting<TypesettingTextLayout>("typesetting_layout_text", args);
 }
 
+/**
+ * Lay out and paginate `text` (one paragraph per line) for a scrolling
+ * multi-page preview. Line positions are mm from the page origin.
+ */
+export async function buildTypesettingPreview(
+  text: string,
+  fontPath: string,
+  style?: TypesettingPageStyleInput,
+  options?: TypesettingPreviewOptions,
+): Promise<TypesettingPreview> {
+  return invokeTypesetting<TypesettingPreview>("typesetting_build_preview", {
+    text,
+    fontPath,
+    style,
+    options,
+  });
+}
+
+/**
+ * Typeset markdown (headings, paragraphs, lists, quotes, code) into a
+ * paginated PDF. Returns base64 PDF bytes.
+ */
+export async function typesetMarkdownToPdfBase64(
+  markdown: string,
+  fontPath: string,
+  style?: TypesettingPageStyleInput,
+): Promise<string> {
+  return invokeTypesetting<string>("typeset_markdown_to_pdf_base64", {
+    markdown,
+    fontPath,
+    style,
+  });
+}
+
 export async function getTypesettingExportPdfBase64(): Promise<string> {
   return invokeTypesetting<string>("typesetting_export_pdf_base64");
 }
 
-export async function getTypesettingRenderDocxPdfBase64(docxPath: string): Promise<string> {
-  return invokeTypesetting<string>("typesetting_render_docx_pdf_base64", { docxPath });
+export type TypesettingDocxRenderer = "builtin" | "soffice";
+
+export interface TypesettingDocxPdf {
+  pdf_base64: string;
+  renderer: TypesettingDocxRenderer;
+  /** Why the built-in renderer was skipped when "auto" fell back to soffice. */
+  fallback_reason: string | null;
+}
+
+/**
+ * Render a docx to PDF. "auto" (default) uses the built-in renderer for simple
+ * documents and falls back to soffice for tables, images and the like.
+ */
+export async function getTypesettingRenderDocxPdf(
+  docxPath: string,
+  renderer: "auto" | TypesettingDocxRenderer = "auto",
+): Promise<TypesettingDocxPdf> {
+  return invokeTypesetting<TypesettingDocxPdf>("typesetting_render_docx_pdf_base64", {
+    docxPath,
+    renderer,
+  });
 }
 
 const buildTokens = (text: string): string[] => {
@@ -322,6 +452,35 @@ export async function listDirectory(path: string): Promise<FileEntry[]> {
   return invoke<FileEntry[]>("list_directory", { path });
 }
 
+export interface DirectoryScanBatch {
+  scan_id: string;
+  root: string;
+  entries: FileEntry[];
+}
+
+export interface DirectoryScanComplete {
+  scan_id: string;
+  root: string;
+  total: number;
+  cancelled: boolean;
+  error: string | null;
+}
+
+/**
+ * Start a streaming directory scan (listen to "fs:entry-batch" / "fs:scan-complete")
+ * Returns the scan id
+ */
+export async function startDirectoryScan(path: string): Promise<string> {
+  return invoke<string>("start_directory_scan", { path });
+}
+
+/**
+ * Cancel a running directory scan
+ */
+export async function cancelDirectoryScan(scanId: string): Promise<boolean> {
+  return invoke<boolean>("cancel_directory_scan", { scanId });
+}
+
 /**
  * Create a new file
  */
@@ -329,11 +488,33 @@ export async function createFile(path: string): Promise<void> {
   return invoke("create_file", { path });
 }
 
+export interface TrashedEntry {
+  original_path: string;
+  location: "system" | "workspace";
+  /** Path inside the workspace `.lumina/trash/` (null for the OS trash) */
+  trashed_path: string | null;
+  deleted_at: number;
+}
+
 /**
  * Delete a file or directory
+ * Moves it to the trash unless `permanent`; resolves to the trash entry for undo
  */
-export async function deleteFile(path: string): Promise<void> {
-  return invoke("delete_file", { path });
+export async function deleteFile(path: string, permanent?: boolean): Promise<TrashedEntry | null> {
+  return invoke<TrashedEntry | null>(
+    "delete_file",
+    permanent === undefined ? { path } : { path, permanent }
+  );
+}
+
+/**
+ * Restore a trashed file or directory to its original path
+ */
+export async function restoreFromTrash(entry: TrashedEntry): Promise<string> {
+  return invoke<string>("restore_from_trash", {
+    originalPath: entry.original_path,
+    trashedPath: entry.trashed_path,
+  });
 }
 
 /**
@@ -428,6 +609,242 @@ export async function moveFolder(sourcePath: string, targetFolder: string): Prom
   return invoke<string>("move_folder", { source: sourcePath, targetFolder });
 }
 
+export interface NoteMoveResult {
+  new_path: string;
+  /** Notes whose links were rewritten to follow the move */
+  rewritten: string[];
+}
+
+/**
+ * Move a note or folder to a target folder and rewrite wikilinks / markdown
+ * links that pointed at the old location
+ */
+export async function moveNoteWithBacklinkFix(
+  workspace: string,
+  sourcePath: string,
+  targetFolder: string
+): Promise<NoteMoveResult> {
+  return invoke<NoteMoveResult>("move_note_with_backlink_fix", {
+    workspace,
+    source: sourcePath,
+    targetFolder,
+  });
+}
+
+export interface LinkTarget {
+  path: string;
+  /** File name without .md */
+  name: string;
+  title: string;
+  aliases: string[];
+  /** `[[name#text]]` links to a heading */
+  headings: { level: number; text: string }[];
+}
+
+/**
+ * Notes, aliases and headings for `[[` autocomplete. The backend caches the
+ * index and rebuilds it after file watcher changes.
+ */
+export async function buildLinkIndex(workspace: string): Promise<LinkTarget[]> {
+  return invoke<LinkTarget[]>("build_link_index", { workspace });
+}
+
+export interface BrokenLink {
+  source: string;
+  /** 1-based line number */
+  line: number;
+  target: string;
+  wiki: boolean;
+}
+
+export interface NameCollision {
+  name: string;
+  notes: string[];
+  /** Where a bare `[[name]]` resolves */
+  resolves_to: string;
+}
+
+export interface LinkReportChunk {
+  broken_links: BrokenLink[];
+  orphans: string[];
+  collisions: NameCollision[];
+}
+
+export interface LinkReportSummary {
+  notes_checked: number;
+  broken_links: number;
+  orphans: number;
+  collisions: number;
+}
+
+/**
+ * Find broken links, orphan notes and ambiguous note names. Findings arrive
+ * in batches through `onChunk`; the promise resolves with the totals.
+ */
+export async function vaultLinkReport(
+  workspace: string,
+  onChunk: (chunk: LinkReportChunk) => void
+): Promise<LinkReportSummary> {
+  const channel = new Channel<LinkReportChunk>();
+  channel.onmessage = onChunk;
+  return invoke<LinkReportSummary>("vault_link_report", { workspace, onChunk: channel });
+}
+
+export interface TagEntry {
+  /** Lowercase tag without `#` */
+  tag: string;
+  count: number;
+  notes: string[];
+}
+
+export interface TagRenameResult {
+  /** Workspace-relative paths of the rewritten notes */
+  changed: string[];
+  replaced: number;
+}
+
+/**
+ * Frontmatter and inline tags across the workspace, most used first
+ */
+export async function buildTagIndex(workspace: string): Promise<TagEntry[]> {
+  return invoke<TagEntry[]>("build_tag_index", { workspace });
+}
+
+/**
+ * Rename a tag in frontmatter `tags:` and inline `#tags` across the vault.
+ * Code blocks and URLs are left alone.
+ */
+export async function renameTag(
+  workspace: string,
+  oldTag: string,
+  newTag: string
+): Promise<TagRenameResult> {
+  return invoke<TagRenameResult>("rename_tag", { workspace, old: oldTag, new: newTag });
+}
+
+export interface BundleOptions {
+  /** Link hops to follow from the selected notes (0 = selection only) */
+  max_depth?: number;
+  /** Workspace-relative folder the bundle will be saved in */
+  output_dir?: string;
+  title?: string;
+}
+
+export interface NoteBundle {
+  markdown: string;
+  files: { path: string; title: string; anchor: string; depth: number }[];
+  unresolved: { source: string; target: string }[];
+}
+
+/**
+ * Merge notes and folders into one markdown document, e.g. as input for
+ * `typeset_markdown_to_pdf_base64`
+ */
+export async function exportBundle(
+  workspace: string,
+  paths: string[],
+  options?: BundleOptions
+): Promise<NoteBundle> {
+  return invoke<NoteBundle>("export_bundle", { workspace, paths, options });
+}
+
+export interface EmbeddingProviderConfig {
+  enabled?: boolean;
+  /** "openai" (any OpenAI-compatible API) or "ollama" */
+  provider: string;
+  model: string;
+  base_url?: string;
+  api_key?: string;
+  dimensions?: number | null;
+  /** Tried when this provider fails, e.g. a local Ollama model */
+  fallback?: EmbeddingProviderConfig | null;
+}
+
+export interface Embeddings {
+  provider: string;
+  model: string;
+  dimensions: number;
+  /** Normalized vectors, one per input text */
+  vectors: number[][];
+}
+
+/**
+ * Embed texts in the backend (remote endpoint, falling back to the
+ * configured fallback provider)
+ */
+export async function embedTexts(
+  texts: string[],
+  config: EmbeddingProviderConfig
+): Promise<Embeddings> {
+  return invoke<Embeddings>("embed_texts", { texts, config });
+}
+
+/** Payload of the "embedding:progress" event */
+export interface EmbedProgress {
+  done: number;
+  total: number;
+  model: string;
+}
+
+/**
+ * Embed many texts with concurrent batched requests, retrying rate-limited
+ * batches (listen to "embedding:progress" for progress)
+ */
+export async function embedTextsBatched(
+  texts: string[],
+  config: EmbeddingProviderConfig,
+  options?: { batchSize?: number; concurrency?: number }
+): Promise<Embeddings> {
+  return invoke<Embeddings>("embed_texts_batched", {
+    texts,
+    config,
+    batchSize: options?.batchSize ?? null,
+    concurrency: options?.concurrency ?? null,
+  });
+}
+
+export interface HttpFetchRequest {
+  url: string;
+  /** Defaults to GET */
+  method?: string;
+  headers?: Record<string, string>;
+  body?: string;
+  timeout_secs?: number;
+  /** Response bodies beyond this many bytes are truncated */
+  max_bytes?: number;
+  /** Serve and store successful GET responses from the disk cache for this long */
+  cache_ttl_secs?: number;
+}
+
+export interface HttpFetchResponse {
+  status: number;
+  /** Final URL after redirects */
+  url: string;
+  /** Response headers, lowercase names */
+  headers: Record<string, string>;
+  content_type: string | null;
+  /** Text for textual content types, base64 otherwise */
+  body: string;
+  base64: boolean;
+  truncated: boolean;
+  cached: boolean;
+}
+
+/**
+ * Fetch an external http(s) URL through the backend (link previews, favicons).
+ * Local and private addresses are rejected; non-2xx statuses resolve normally.
+ */
+export async function httpFetch(request: HttpFetchRequest): Promise<HttpFetchResponse> {
+  return invoke<HttpFetchResponse>("http_fetch", { request });
+}
+
+/**
+ * Remove all cached external fetch responses, returning how many were deleted.
+ */
+export async function clearHttpCache(): Promise<number> {
+  return invoke<number>("clear_http_cache");
+}
+
 /**
  * Show file/folder in the system file explorer.
  */
@@ -435,6 +852,21 @@ export async function showInExplorer(path: string): Promise<void> {
   return invoke("show_in_explorer", { path });
 }
 
+/**
+ * Toggle read-only workspace mode. While enabled, every write path
+ * (saves, renames, deletes, sync, agent edits) is rejected.
+ */
+export async function setWorkspaceReadonly(readonly: boolean): Promise<void> {
+  return invoke("set_workspace_readonly", { readonly });
+}
+
+/**
+ * Whether the workspace is currently read-only.
+ */
+export async function getWorkspaceReadonly(): Promise<boolean> {
+  return invoke<boolean>("get_workspace_readonly");
+}
+
 /**
  * Open a new window
  */
@@ -488,15 +920,34 @@ export async function scaffoldWorkspaceUiOverhaulPlugin(): Promise<string> {
 
 // ============ Doc tools ============
 
+export interface DocToolStatus {
+  available: boolean;
+  path?: string;
+  source?: string;
+  /** First line of `<tool> --version` */
+  version?: string;
+  /** SHA-256 of the binary (pack tools only) */
+  checksum?: string;
+  integrity: "verified" | "mismatch" | "unverified";
+}
+
 export interface DocToolsStatus {
   installed: boolean;
   version?: string;
   rootDir?: string;
   binDir?: string;
-  tools: Record<string, { available: boolean; path?: string; source?: string }>;
+  tools: Record<string, DocToolStatus>;
   missing: string[];
 }
 
+/** Payload of the "doc-tools:install-progress" event */
+export interface DocToolsInstallProgress {
+  phase: "downloading" | "verifying" | "extracting" | "done" | "cancelled";
+  version: string;
+  downloadedBytes: number;
+  totalBytes?: number;
+}
+
 export async function getDocToolsStatus(): Promise<DocToolsStatus> {
   return invoke("doc_tools_get_status");
 }
@@ -505,10 +956,229 @@ export async function installDocTools(): Promise<DocToolsStatus> {
   return invoke("doc_tools_install_latest");
 }
 
+/**
+ * Cancel a running doc tools install; resolves false when none is running
+ */
+export async function cancelDocToolsInstall(): Promise<boolean> {
+  return invoke("doc_tools_cancel_install");
+}
+
+export interface AgentAuditEntry {
+  /** Unix milliseconds */
+  timestamp: number;
+  session_id: string;
+  tool: string;
+  /** "read", "edit", "command" (bash), or the write mode ("create" | "overwrite" | "append") */
+  operation: string;
+  path: string;
+  outcome: "success" | "error";
+  error: string | null;
+  /** Bytes written (write) or resulting file size (other file tools) */
+  bytes: number | null;
+  /** SHA-256 before a write or edit (null when the file did not exist) */
+  before_hash: string | null;
+  after_hash: string | null;
+  /** Shell command run by bash; `path` is its working directory */
+  command: string | null;
+}
+
+/**
+ * Get the agent file-operation audit log (defaults to the current session)
+ */
+export async function getAgentAuditLog(
+  workspacePath: string,
+  sessionId?: string
+): Promise<AgentAuditEntry[]> {
+  return invoke<AgentAuditEntry[]>("agent_get_audit_log", { workspacePath, sessionId });
+}
+
+export interface AgentTraceEntry {
+  /** Position of the call within the session, starting at 1 */
+  seq: number;
+  /** Unix milliseconds */
+  timestamp: number;
+  session_id: string;
+  call_id: string;
+  tool: string;
+  /** Call arguments with secrets redacted */
+  args: unknown;
+  duration_ms: number;
+  outcome: "success" | "error";
+  result: unknown | null;
+  error: string | null;
+  /** Whether `result` was cut to keep the trace small */
+  truncated: boolean;
+}
+
+/**
+ * Export the ordered tool-call trace of an agent session for bug reports
+ */
+export async function exportAgentTrace(
+  workspacePath: string,
+  sessionId: string
+): Promise<AgentTraceEntry[]> {
+  return invoke<AgentTraceEntry[]>("agent_export_trace", { workspacePath, sessionId });
+}
+
+export interface AgentUndoReport {
+  /** Journal entries reverted (or re-applied), newest first */
+  entries: number[];
+  /** Files written or removed */
+  files: string[];
+  /** Files edited outside the agent since; when non-empty nothing was changed */
+  conflicts: string[];
+}
+
+/**
+ * Revert the agent's most recent file change (defaults to the current session)
+ */
+export async function undoAgentLast(
+  workspacePath: string,
+  sessionId?: string
+): Promise<AgentUndoReport> {
+  return invoke<AgentUndoReport>("agent_undo_last", { workspacePath, sessionId });
+}
+
+/**
+ * Revert every file change of an agent run, newest first
+ */
+export async function undoAgentRun(
+  workspacePath: string,
+  sessionId?: string
+): Promise<AgentUndoReport> {
+  return invoke<AgentUndoReport>("agent_undo_run", { workspacePath, sessionId });
+}
+
+/**
+ * Re-apply the most recently undone agent file change
+ */
+export async function redoAgentLast(
+  workspacePath: string,
+  sessionId?: string
+): Promise<AgentUndoReport> {
+  return invoke<AgentUndoReport>("agent_redo_last", { workspacePath, sessionId });
+}
+
+export interface NoteTemplateInfo {
+  name: string;
+  source: "workspace" | "builtin";
+  /** Variables that must be supplied */
+  required: string[];
+  /** Variables with a default */
+  optional: string[];
+}
+
+export interface CreatedNote {
+  path: string;
+  /** 1-based [line, column] of the template's {{cursor}} placeholder */
+  cursor: [number, number] | null;
+}
+
+/**
+ * List templates in .lumina/templates plus the built-in ones they do not override
+ */
+export async function listNoteTemplates(workspacePath: string): Promise<NoteTemplateInfo[]> {
+  return invoke<NoteTemplateInfo[]>("list_note_templates", { workspacePath });
+}
+
+/**
+ * Render a template into a new note. Fails if the note exists or a required
+ * variable is missing.
+ */
+export async function createNoteFromTemplate(
+  workspacePath: string,
+  template: string,
+  targetPath: string,
+  vars: Record<string, unknown> = {}
+): Promise<CreatedNote> {
+  return invoke<CreatedNote>("create_note_from_template", {
+    workspacePath,
+    template,
+    targetPath,
+    vars,
+  });
+}
+
+export interface FlashcardSchedule {
+  ease: number;
+  /** Days until the next review */
+  interval: number;
+  repetitions: number;
+  /** YYYY-MM-DD */
+  due: string;
+  lastReview: string | null;
+}
+
+export interface DueFlashcard {
+  /** Workspace-relative path */
+  path: string;
+  deck: string;
+  state: FlashcardSchedule;
+  /** All frontmatter fields of the card */
+  fields: Record<string, unknown>;
+}
+
+/**
+ * Grade a flashcard note (0 again, 1 hard, 2 good, 3 easy) and save its next
+ * review date (SM-2)
+ */
+export async function reviewFlashcard(path: string, grade: 0 | 1 | 2 | 3): Promise<FlashcardSchedule> {
+  return invoke<FlashcardSchedule>("flashcard_review", { path, grade });
+}
+
+/**
+ * Flashcards due today, optionally from one deck, most overdue first
+ */
+export async function getDueFlashcards(workspace: string, deck?: string): Promise<DueFlashcard[]> {
+  return invoke<DueFlashcard[]>("flashcard_due", { workspace, deck });
+}
+
+export interface DatabaseValidationIssue {
+  kind: "missing_field" | "type_mismatch" | "unknown_option" | "orphan_note" | "invalid_schema";
+  databaseId: string;
+  /** Workspace-relative path of the note (or of the .db.json file) */
+  path: string;
+  column: string | null;
+  /** Column type */
+  expected: string | null;
+  value: unknown | null;
+  message: string;
+}
+
+export interface DatabaseValidationReport {
+  /** Databases whose members were checked */
+  databases: string[];
+  notesChecked: number;
+  /** Sorted by note path */
+  issues: DatabaseValidationIssue[];
+}
+
+/**
+ * Check database notes against their column definitions. Validates every
+ * database and reports orphan notes when databaseId is omitted.
+ */
+export async function validateDatabase(
+  workspace: string,
+  databaseId?: string
+): Promise<DatabaseValidationReport> {
+  return invoke<DatabaseValidationReport>("validate_database", { workspace, databaseId });
+}
+
+export interface FileWatcherOptions {
+  /** Debounce window in milliseconds (default 300) */
+  debounce_ms?: number;
+  /** Ignore .git, node_modules and .lumina (default true) */
+  ignore_internal?: boolean;
+}
+
 /**
  * Start file system watcher for a directory
- * Emits "fs:change" events when files are created, modified, or deleted
+ * Emits debounced "fs:change" events when files are created, modified, deleted or renamed,
+ * plus one "fs:changes" event per debounce window with the coalesced batch
  */
-export async function startFileWatcher(watchPath: string): Promise<void> {
-  return invoke("start_file_watcher", { watchPath });
+export async function startFileWatcher(
+  watchPath: string,
+  options?: FileWatcherOptions
+): Promise<void> {
+  return invoke("start_file_watcher", { watchPath, options });
 }
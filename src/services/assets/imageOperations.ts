@@ -12,6 +12,7 @@ import {
   listMarkdownNotePaths,
   planAssetReferenceUpdates,
 } from "./imageManager";
+import { errorToString } from "@/lib/appError";
 
 export interface ImageAssetPreview {
   changes: AssetPathChange[];
@@ -164,7 +165,7 @@ export const executeImageAssetChanges = async ({
       );
     }
 
-    const baseMessage = error instanceof Error ? error.message : String(error);
+    const baseMessage = error instanceof Error ? error.message : errorToString(error);
     if (rollbackErrors.length > 0) {
       throw new Error(`${baseMessage} (${rollbackErrors.join("; ")})`);
     }
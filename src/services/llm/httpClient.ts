@@ -6,17 +6,68 @@
 import { invoke } from "@tauri-apps/api/core";
 import { listen, UnlistenFn } from "@tauri-apps/api/event";
 import type { StreamChunk } from "./types";
+import { errorToString } from "@/lib/appError";
 
 // ============ 类型定义 ============
 
+// 响应缓存模式：仅对 temperature 为 0 的请求生效
+export type LlmCacheMode = "off" | "read_write" | "read_only";
+
 export interface HttpRequest {
   url: string;
   method: string;
   headers: Record<string, string>;
   body?: string;
   timeout_secs?: number;
+  cache?: LlmCacheMode;
+  // 对话请求：设置后由后端 provider 构建原生请求，忽略 url / method / headers / body
+  chat?: ChatRequest;
+}
+
+// 对话请求：消息与工具定义均为 OpenAI 格式，后端与 Agent 共用同一套 provider 转换
+export interface ChatRequest {
+  provider: string;
+  base_url: string;
+  api_key?: string;
+  model: string;
+  messages: unknown[];
+  temperature?: number;
+  max_tokens: number;
+  tools?: unknown[];
+  // 合并到原生请求体的额外字段（对象字段按一层合并）
+  extra_body?: Record<string, unknown>;
+  extra_headers?: Record<string, string>;
+}
+
+// 后端归一化后的非流式响应
+export interface ChatResponse {
+  content: string;
+  reasoning?: string | null;
+  tool_calls: Array<{ id: string; name: string; params: Record<string, unknown> }>;
+  finish_reason?: string | null;
+  prompt_tokens: number;
+  completion_tokens: number;
+  total_tokens: number;
 }
 
+// 后端归一化后的流式增量
+export type ChatDelta =
+  | { type: "text"; data: string }
+  | { type: "reasoning"; data: string }
+  | {
+      type: "tool_call";
+      data: { index: number; id?: string | null; name: string; arguments: string };
+    }
+  | { type: "finish"; data: string }
+  | {
+      type: "usage";
+      data: {
+        prompt_tokens?: number | null;
+        completion_tokens?: number | null;
+        total_tokens?: number | null;
+      };
+    };
+
 export interface HttpResponse {
   status: number;
   body: string;
@@ -49,26 +100,18 @@ export async function llmFetch(request: HttpRequest): Promise<HttpResponse> {
 }
 
 /**
- * 发送 JSON 请求并解析响应
+ * 发送对话请求，返回后端归一化后的响应
  */
-export async function llmFetchJson<T>(
-  url: string,
-  options: {
-    method?: string;
-    headers?: Record<string, string>;
-    body?: unknown;
-    timeout?: number;
-  } = {}
-): Promise<{ ok: boolean; status: number; data?: T; error?: string }> {
+export async function llmChat(
+  chat: ChatRequest,
+  timeout = 120
+): Promise<{ ok: boolean; status: number; data?: ChatResponse; error?: string }> {
   const response = await llmFetch({
-    url,
-    method: options.method || "POST",
-    headers: {
-      "Content-Type": "application/json",
-      ...options.headers,
-    },
-    body: options.body ? JSON.stringify(options.body) : undefined,
-    timeout_secs: options.timeout || 120,
+    url: "",
+    method: "POST",
+    headers: {},
+    chat,
+    timeout_secs: timeout,
   });
 
   if (response.error) {
@@ -77,7 +120,7 @@ export async function llmFetchJson<T>(
 
   if (response.status >= 200 && response.status < 300) {
     try {
-      const data = JSON.parse(response.body) as T;
+      const data = JSON.parse(response.body) as ChatResponse;
       return { ok: true, status: response.status, data };
     } catch {
       return { ok: false, status: response.status, error: "Failed to parse JSON response" };
@@ -87,8 +130,67 @@ export async function llmFetchJson<T>(
   }
 }
 
+/**
+ * 清空 LLM 响应缓存，返回删除的条目数
+ */
+export async function clearLlmCache(): Promise<number> {
+  return invoke<number>("clear_llm_cache");
+}
+
 // ============ 流式请求 ============
 
+/**
+ * 解析对话流的数据块（后端归一化后的 ChatDelta JSON）
+ */
+export function parseChatDelta(chunk: string, includeReasoning = true): StreamChunk[] {
+  let delta: ChatDelta;
+  try {
+    delta = JSON.parse(chunk) as ChatDelta;
+  } catch {
+    return [];
+  }
+
+  switch (delta.type) {
+    case "text":
+      return [{ type: "text", text: delta.data }];
+    case "reasoning":
+      return includeReasoning ? [{ type: "reasoning", text: delta.data }] : [];
+    case "usage": {
+      const inputTokens = delta.data.prompt_tokens || 0;
+      const outputTokens = delta.data.completion_tokens || 0;
+      return [
+        {
+          type: "usage",
+          inputTokens,
+          outputTokens,
+          totalTokens: delta.data.total_tokens || inputTokens + outputTokens,
+        },
+      ];
+    }
+    default:
+      return [];
+  }
+}
+
+/**
+ * 发送流式对话请求（通过 Tauri 后端的 provider 抽象）
+ */
+export function llmChatStream(
+  chat: ChatRequest,
+  options: { includeReasoning?: boolean; timeout?: number } = {}
+): AsyncGenerator<StreamChunk> {
+  return llmFetchStream(
+    {
+      url: "",
+      method: "POST",
+      headers: {},
+      chat,
+      timeout_secs: options.timeout || 300,
+    },
+    (chunk) => parseChatDelta(chunk, options.includeReasoning ?? true)
+  );
+}
+
 /**
  * 发送流式 HTTP 请求（通过 Tauri 后端）
  * 返回 AsyncGenerator，可以用 for await 遍历
@@ -172,7 +274,7 @@ export async function* llmFetchStream(
 
     // 启动流式请求
     invoke("llm_fetch_stream", { requestId, request }).catch((e) => {
-      streamError = String(e);
+      streamError = errorToString(e);
       streamDone = true;
       if (resolveNext) resolveNext();
     });
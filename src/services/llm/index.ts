@@ -52,6 +52,7 @@ import type { Message, LLMOptions, LLMResponse, LLMStream, LLMConfig } from "./t
 import { getLLMConfig } from "./config";
 import { createProvider } from "./factory";
 import { getCurrentTranslations } from "@/stores/useLocaleStore";
+import { errorToString } from "@/lib/appError";
 
 export { createProvider } from "./factory";
 export {
@@ -88,7 +89,7 @@ export async function callLLM(
       const response = await provider.call(messages, finalOptions);
       return response;
     } catch (error) {
-      lastError = error instanceof Error ? error : new Error(String(error));
+      lastError = error instanceof Error ? error : new Error(errorToString(error));
 
       const isRetryable =
         lastError.message.includes("Failed to fetch") ||
@@ -1,48 +1,13 @@
 /**
  * Anthropic (Claude) Provider
  * 支持多模态输入（图片）
- * 通过 Tauri 后端发送请求
+ * 通过 Tauri 后端的 provider 抽象发送请求（与 Agent 共用 Messages 协议转换）
  */
 
-import type { Message, MessageContent, LLMConfig, LLMOptions, LLMResponse, LLMProvider } from "../types";
-import { llmFetchJson } from "../httpClient";
+import type { Message, LLMConfig, LLMOptions, LLMResponse, LLMProvider } from "../types";
+import { llmChat } from "../httpClient";
 import { getCurrentTranslations } from "@/stores/useLocaleStore";
-
-// 转换消息内容为 Anthropic 格式
-function convertContent(
-  content: MessageContent
-): string | Array<{ type: string; text?: string; source?: { type: string; media_type: string; data: string } }> {
-  if (typeof content === "string") {
-    return content;
-  }
-  
-  return content.map(item => {
-    if (item.type === "text") {
-      return { type: "text", text: item.text };
-    } else if (item.type === "image") {
-      return {
-        type: "image",
-        source: {
-          type: "base64",
-          media_type: item.source.mediaType,
-          data: item.source.data
-        }
-      };
-    }
-    return { type: "text", text: "" };
-  });
-}
-
-// 提取 system 消息的纯文本内容
-function getSystemText(content: MessageContent): string {
-  if (typeof content === "string") {
-    return content;
-  }
-  return content
-    .filter(item => item.type === "text")
-    .map(item => (item as { type: "text"; text: string }).text)
-    .join("\n");
-}
+import { toChatMessages } from "./openaiCompatible";
 
 export class AnthropicProvider implements LLMProvider {
   private config: LLMConfig;
@@ -52,32 +17,18 @@ export class AnthropicProvider implements LLMProvider {
   }
 
   async call(messages: Message[], options?: LLMOptions): Promise<LLMResponse> {
-    const baseUrl = this.config.baseUrl || "https://api.anthropic.com";
-    
-    // 分离 system 消息 (Anthropic 要求 system 单独传)
-    const systemMessage = messages.find(m => m.role === "system");
-    const chatMessages = messages.filter(m => m.role !== "system");
-
-    const result = await llmFetchJson<{
-      content: Array<{ text?: string }>;
-      usage?: { input_tokens?: number; output_tokens?: number };
-    }>(`${baseUrl}/v1/messages`, {
-      method: "POST",
-      headers: {
-        "x-api-key": this.config.apiKey,
-        "anthropic-version": "2023-06-01",
-      },
-      body: {
+    // system 消息的拆分与图片内容块的转换由后端完成
+    const result = await llmChat(
+      {
+        provider: "anthropic",
+        base_url: this.config.baseUrl || "https://api.anthropic.com",
+        api_key: this.config.apiKey,
         model: this.config.model,
+        messages: toChatMessages(messages),
         max_tokens: options?.maxTokens || 4096,
-        system: systemMessage ? getSystemText(systemMessage.content) : "",
-        messages: chatMessages.map(m => ({
-          role: m.role,
-          content: convertContent(m.content),
-        })),
       },
-      timeout: 120,
-    });
+      120
+    );
 
     if (!result.ok || !result.data) {
       const t = getCurrentTranslations();
@@ -86,11 +37,11 @@ export class AnthropicProvider implements LLMProvider {
 
     const data = result.data;
     return {
-      content: data.content[0]?.text || "",
-      usage: data.usage ? {
-        promptTokens: data.usage.input_tokens || 0,
-        completionTokens: data.usage.output_tokens || 0,
-        totalTokens: (data.usage.input_tokens || 0) + (data.usage.output_tokens || 0),
+      content: data.content,
+      usage: data.total_tokens ? {
+        promptTokens: data.prompt_tokens,
+        completionTokens: data.completion_tokens,
+        totalTokens: data.total_tokens,
       } : undefined,
     };
   }
@@ -11,7 +11,6 @@ export class DeepSeekProvider extends OpenAICompatibleProvider {
     super(config, {
       defaultBaseUrl: "https://api.deepseek.com/v1",
       supportsReasoning: true,
-      reasoningField: "reasoning_content",
     });
   }
 
@@ -21,7 +21,6 @@ export class MoonshotProvider extends OpenAICompatibleProvider {
     super(config, {
       defaultBaseUrl: "https://api.moonshot.cn/v1",
       supportsReasoning: true,
-      reasoningField: "reasoning_content",
     });
   }
 
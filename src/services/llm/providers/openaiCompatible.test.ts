@@ -1,15 +1,24 @@
 import { describe, expect, it } from "vitest";
 
-import type { LLMConfig } from "../types";
-import { OpenAICompatibleProvider } from "./openaiCompatible";
+import type { ChatResponse } from "../httpClient";
+import { parseChatDelta } from "../httpClient";
+import type { LLMConfig, LLMOptions, Message } from "../types";
+import { OpenAICompatibleProvider, type OpenAICompatibleConfig } from "./openaiCompatible";
 
 class TestOpenAICompatibleProvider extends OpenAICompatibleProvider {
-  parse(data: Record<string, unknown>) {
+  parse(data: ChatResponse) {
     return this.parseResponse(data);
   }
+
+  chatRequest(messages: Message[], options?: LLMOptions, stream = false) {
+    return this.buildChatRequest(messages, options, stream);
+  }
 }
 
-function createProvider(config: Partial<LLMConfig> = {}) {
+function createProvider(
+  config: Partial<LLMConfig> = {},
+  providerConfig: Partial<OpenAICompatibleConfig> = {},
+) {
   return new TestOpenAICompatibleProvider(
     {
       provider: "openai",
@@ -19,31 +28,32 @@ function createProvider(config: Partial<LLMConfig> = {}) {
     },
     {
       defaultBaseUrl: "https://api.openai.com/v1",
+      ...providerConfig,
     },
   );
 }
 
+function response(overrides: Partial<ChatResponse> = {}): ChatResponse {
+  return {
+    content: "ok",
+    reasoning: null,
+    tool_calls: [],
+    finish_reason: "stop",
+    prompt_tokens: 3,
+    completion_tokens: 2,
+    total_tokens: 5,
+    ...overrides,
+  };
+}
+
 describe("OpenAICompatibleProvider.parseResponse", () => {
-  it("parses valid tool call JSON arguments", () => {
+  it("maps normalized tool calls and usage", () => {
     const provider = createProvider();
-    const result = provider.parse({
-      choices: [
-        {
-          message: {
-            content: "ok",
-            tool_calls: [
-              {
-                id: "call_1",
-                function: {
-                  name: "searchNotes",
-                  arguments: '{"query":"rag","limit":5}',
-                },
-              },
-            ],
-          },
-        },
-      ],
-    });
+    const result = provider.parse(
+      response({
+        tool_calls: [{ id: "call_1", name: "searchNotes", params: { query: "rag", limit: 5 } }],
+      }),
+    );
 
     expect(result.toolCalls).toEqual([
       {
@@ -52,51 +62,47 @@ describe("OpenAICompatibleProvider.parseResponse", () => {
         arguments: { query: "rag", limit: 5 },
       },
     ]);
+    expect(result.usage).toEqual({ promptTokens: 3, completionTokens: 2, totalTokens: 5 });
   });
 
-  it("returns empty args for malformed tool call JSON instead of throwing", () => {
-    const provider = createProvider();
+  it("only surfaces reasoning for providers that support it", () => {
+    const data = response({ reasoning: "think first" });
 
-    expect(() =>
-      provider.parse({
-        choices: [
-          {
-            message: {
-              content: "ok",
-              tool_calls: [
-                {
-                  id: "call_2",
-                  function: {
-                    name: "searchNotes",
-                    arguments: "{bad-json",
-                  },
-                },
-              ],
-            },
-          },
-        ],
-      }),
-    ).not.toThrow();
+    expect(createProvider().parse(data).content).toBe("ok");
+    expect(createProvider({}, { supportsReasoning: true }).parse(data).content).toBe(
+      "<thinking>\nthink first\n</thinking>\n\nok",
+    );
+  });
+});
 
-    const result = provider.parse({
-      choices: [
-        {
-          message: {
-            content: "ok",
-            tool_calls: [
-              {
-                id: "call_2",
-                function: {
-                  name: "searchNotes",
-                  arguments: "{bad-json",
-                },
-              },
-            ],
-          },
-        },
-      ],
+describe("OpenAICompatibleProvider.buildChatRequest", () => {
+  it("hands common fields to the backend provider and keeps the rest as extra body", () => {
+    const provider = createProvider(
+      { provider: "openrouter", baseUrl: "https://openrouter.ai/api/v1" },
+      { extraHeaders: { "X-Title": "Lumina Note" } },
+    );
+    const tools = [{ type: "function", function: { name: "searchNotes", parameters: {} } }];
+    const request = provider.chatRequest([{ role: "user", content: "hi" }], { tools }, true);
+
+    expect(request.provider).toBe("openrouter");
+    expect(request.base_url).toBe("https://openrouter.ai/api/v1");
+    expect(request.messages).toEqual([{ role: "user", content: "hi" }]);
+    expect(request.tools).toEqual(tools);
+    expect(request.extra_headers).toEqual({ "X-Title": "Lumina Note" });
+    expect(request.extra_body).toEqual({
+      stream_options: { include_usage: true },
+      tool_choice: "auto",
     });
+  });
+});
 
-    expect(result.toolCalls?.[0]?.arguments).toEqual({});
+describe("parseChatDelta", () => {
+  it("maps normalized deltas to stream chunks", () => {
+    expect(parseChatDelta('{"type":"text","data":"he"}')).toEqual([{ type: "text", text: "he" }]);
+    expect(parseChatDelta('{"type":"reasoning","data":"hm"}', false)).toEqual([]);
+    expect(
+      parseChatDelta('{"type":"usage","data":{"prompt_tokens":4,"completion_tokens":1,"total_tokens":null}}'),
+    ).toEqual([{ type: "usage", inputTokens: 4, outputTokens: 1, totalTokens: 5 }]);
+    expect(parseChatDelta("not json")).toEqual([]);
   });
 });
@@ -13,9 +13,8 @@ import type {
   LLMResponse, 
   LLMProvider, 
   LLMStream,
-  StreamChunk
 } from "../types";
-import { llmFetchJson, llmFetchStream, HttpRequest } from "../httpClient";
+import { llmChat, llmChatStream, type ChatRequest, type ChatResponse } from "../httpClient";
 import { getCurrentTranslations } from "@/stores/useLocaleStore";
 import { resolveTemperature } from "../temperature";
 import { getThinkingRequestBodyPatch } from "../thinking";
@@ -47,6 +46,16 @@ function convertContent(
   });
 }
 
+/**
+ * 转换消息为对话请求使用的 OpenAI 格式
+ */
+export function toChatMessages(messages: Message[]) {
+  return messages.map(m => ({
+    role: m.role,
+    content: convertContent(m.content),
+  }));
+}
+
 // ============ Provider 配置 ============
 
 export interface OpenAICompatibleConfig {
@@ -54,10 +63,8 @@ export interface OpenAICompatibleConfig {
   defaultBaseUrl: string;
   /** 额外请求头 */
   extraHeaders?: Record<string, string>;
-  /** 是否支持 reasoning_content（DeepSeek R1, Moonshot Thinking） */
+  /** 是否输出推理内容（DeepSeek R1, Moonshot Thinking；字段差异由后端归一化） */
   supportsReasoning?: boolean;
-  /** reasoning 字段名（默认 reasoning_content，OpenRouter 用 reasoning） */
-  reasoningField?: string;
   /** 自定义请求体字段 */
   customBodyFields?: Record<string, unknown>;
 }
@@ -73,35 +80,6 @@ export class OpenAICompatibleProvider implements LLMProvider {
     this.providerConfig = providerConfig;
   }
 
-  /**
-   * 获取 API URL
-   */
-  protected getUrl(): string {
-    const baseUrl = this.config.baseUrl || this.providerConfig.defaultBaseUrl;
-    return `${baseUrl}/chat/completions`;
-  }
-
-  /**
-   * 获取请求头
-   */
-  protected getHeaders(): Record<string, string> {
-    const headers: Record<string, string> = {
-      "Content-Type": "application/json",
-    };
-
-    // API Key（Ollama 可选）
-    if (this.config.apiKey) {
-      headers["Authorization"] = `Bearer ${this.config.apiKey}`;
-    }
-
-    // 额外请求头
-    if (this.providerConfig.extraHeaders) {
-      Object.assign(headers, this.providerConfig.extraHeaders);
-    }
-
-    return headers;
-  }
-
   /**
    * 获取 temperature（用户配置优先；未配置时使用模型推荐默认值）
    */
@@ -120,10 +98,7 @@ export class OpenAICompatibleProvider implements LLMProvider {
   protected buildRequestBody(messages: Message[], options?: LLMOptions, stream = false): Record<string, unknown> {
     const body: Record<string, unknown> = {
       model: this.config.model,
-      messages: messages.map(m => ({
-        role: m.role,
-        content: convertContent(m.content),
-      })),
+      messages: toChatMessages(messages),
       max_tokens: options?.maxTokens || 4096,
       stream,
     };
@@ -163,76 +138,65 @@ export class OpenAICompatibleProvider implements LLMProvider {
   }
 
   /**
-   * 解析响应内容
+   * 拆分请求体为对话请求：通用字段交给后端 provider，其余字段原样合并
    */
-  protected parseResponse(data: Record<string, unknown>): LLMResponse {
-    const message = (data.choices as Array<{ message?: Record<string, unknown> }>)?.[0]?.message;
-    
+  protected buildChatRequest(messages: Message[], options?: LLMOptions, stream = false): ChatRequest {
+    const {
+      model,
+      messages: chatMessages,
+      temperature,
+      max_tokens,
+      tools,
+      ...extraBody
+    } = this.buildRequestBody(messages, options, stream);
+    delete extraBody.stream;
+
+    return {
+      provider: this.config.provider,
+      base_url: this.config.baseUrl || this.providerConfig.defaultBaseUrl,
+      api_key: this.config.apiKey,
+      model: model as string,
+      messages: chatMessages as unknown[],
+      temperature: temperature as number | undefined,
+      max_tokens: max_tokens as number,
+      tools: tools as unknown[] | undefined,
+      extra_body: extraBody,
+      extra_headers: this.providerConfig.extraHeaders,
+    };
+  }
+
+  /**
+   * 解析后端归一化后的响应
+   */
+  protected parseResponse(data: ChatResponse): LLMResponse {
     let content = "";
-    if (message) {
-      // 处理 reasoning content
-      const reasoningField = this.providerConfig.reasoningField || "reasoning_content";
-      const reasoning = message[reasoningField] as string | undefined;
-      if (reasoning && this.providerConfig.supportsReasoning) {
-        content += `<thinking>\n${reasoning}\n</thinking>\n\n`;
-      }
-      content += (message.content as string) || "";
+    if (data.reasoning && this.providerConfig.supportsReasoning) {
+      content += `<thinking>\n${data.reasoning}\n</thinking>\n\n`;
     }
+    content += data.content || "";
 
-    // 解析 tool_calls
-    const toolCalls = (message?.tool_calls as Array<{
-      id: string;
-      function: { name: string; arguments: string };
-    }>)?.map(tc => ({
+    const toolCalls = data.tool_calls?.map(tc => ({
       id: tc.id,
-      name: tc.function.name,
-      arguments: this.parseToolArguments(tc.function.arguments),
+      name: tc.name,
+      arguments: tc.params ?? {},
     }));
 
-    // 解析 usage
-    const usage = data.usage as { prompt_tokens?: number; completion_tokens?: number; total_tokens?: number } | undefined;
-
     return {
       content,
       toolCalls: toolCalls?.length ? toolCalls : undefined,
-      usage: usage ? {
-        promptTokens: usage.prompt_tokens || 0,
-        completionTokens: usage.completion_tokens || 0,
-        totalTokens: usage.total_tokens || 0,
+      usage: data.total_tokens ? {
+        promptTokens: data.prompt_tokens || 0,
+        completionTokens: data.completion_tokens || 0,
+        totalTokens: data.total_tokens,
       } : undefined,
     };
   }
 
-  private parseToolArguments(rawArgs: unknown): Record<string, unknown> {
-    if (typeof rawArgs !== "string" || rawArgs.trim() === "") {
-      return {};
-    }
-
-    try {
-      const parsed = JSON.parse(rawArgs) as unknown;
-      if (parsed && typeof parsed === "object" && !Array.isArray(parsed)) {
-        return parsed as Record<string, unknown>;
-      }
-      return {};
-    } catch {
-      return {};
-    }
-  }
-
   /**
    * 非流式调用
    */
   async call(messages: Message[], options?: LLMOptions): Promise<LLMResponse> {
-    const url = this.getUrl();
-    const headers = this.getHeaders();
-    const body = this.buildRequestBody(messages, options, false);
-
-    const result = await llmFetchJson<Record<string, unknown>>(url, {
-      method: "POST",
-      headers,
-      body,
-      timeout: 120,
-    });
+    const result = await llmChat(this.buildChatRequest(messages, options, false), 120);
 
     if (!result.ok || !result.data) {
       const t = getCurrentTranslations();
@@ -249,53 +213,9 @@ export class OpenAICompatibleProvider implements LLMProvider {
    * 流式调用
    */
   async *stream(messages: Message[], options?: LLMOptions): LLMStream {
-    const url = this.getUrl();
-    const headers = this.getHeaders();
-    const body = this.buildRequestBody(messages, options, true);
-
-    const request: HttpRequest = {
-      url,
-      method: "POST",
-      headers,
-      body: JSON.stringify(body),
-      timeout_secs: 300,
-    };
-
-    // 使用自定义解析器处理 reasoning_content
-    const reasoningField = this.providerConfig.reasoningField || "reasoning_content";
-    const supportsReasoning = this.providerConfig.supportsReasoning;
-
-    const parseChunk = (chunk: string): StreamChunk[] => {
-      const results: StreamChunk[] = [];
-      try {
-        const data = JSON.parse(chunk);
-        const delta = data.choices?.[0]?.delta;
-
-        // reasoning content
-        if (supportsReasoning && delta?.[reasoningField]) {
-          results.push({ type: "reasoning", text: delta[reasoningField] });
-        }
-
-        // 正常文本内容
-        if (delta?.content) {
-          results.push({ type: "text", text: delta.content });
-        }
-
-        // usage
-        if (data.usage) {
-          results.push({
-            type: "usage",
-            inputTokens: data.usage.prompt_tokens || 0,
-            outputTokens: data.usage.completion_tokens || 0,
-            totalTokens: data.usage.total_tokens || 0,
-          });
-        }
-      } catch {
-        // JSON 解析失败，跳过
-      }
-      return results;
-    };
-
-    yield* llmFetchStream(request, parseChunk);
+    yield* llmChatStream(this.buildChatRequest(messages, options, true), {
+      includeReasoning: !!this.providerConfig.supportsReasoning,
+      timeout: 300,
+    });
   }
 }
@@ -14,9 +14,8 @@ export class OpenRouterProvider extends OpenAICompatibleProvider {
         "HTTP-Referer": "https://lumina-note.app",
         "X-Title": "Lumina Note",
       },
-      // OpenRouter 的某些模型（如 DeepSeek R1）用 reasoning 字段
+      // OpenRouter 的某些模型（如 DeepSeek R1）会返回推理内容
       supportsReasoning: true,
-      reasoningField: "reasoning",
     });
   }
 }
@@ -11,7 +11,6 @@ export class ZAIProvider extends OpenAICompatibleProvider {
     super(config, {
       defaultBaseUrl: "https://open.bigmodel.cn/api/paas/v4",
       supportsReasoning: true,
-      reasoningField: "reasoning_content",
     });
   }
 }
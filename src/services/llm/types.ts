@@ -103,6 +103,14 @@ export interface LLMConfig {
   baseUrl?: string;
   temperature?: number;
   thinkingMode?: ThinkingMode;
+  toolCallMode?: ToolCallMode;
+  reasoningMode?: ReasoningMode;
+  /** Agent 费用预算（美元），0 或未设置表示不限制 */
+  costBudget?: number;
+  /** 未选择 skills 时 Agent 自动注入的数量上限（默认 2，0 关闭） */
+  skillTopK?: number;
+  /** 自动选择 skill 的最低相关度（0-1，默认 0.4） */
+  skillMinScore?: number;
 
   // Deep Research 网络搜索
   tavilyApiKey?: string;
@@ -120,6 +128,12 @@ export interface LLMOptions {
 
 export type ThinkingMode = "auto" | "thinking" | "instant";
 
+// 工具调用模式：auto 按 provider 默认能力，native 为原生 Function Calling，xml 为提示词内 XML 协议
+export type ToolCallMode = "auto" | "native" | "xml";
+
+// 推理内容展示方式：surface 流式展示并保留在最终消息中，collapse 仅在生成时展示，discard 不展示也不保留
+export type ReasoningMode = "surface" | "collapse" | "discard";
+
 // ============ LLM 响应 ============
 
 export interface LLMToolCall {
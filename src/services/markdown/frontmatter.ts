@@ -3,6 +3,7 @@
  * 用于 Dataview 风格数据库：笔记即数据
  */
 import { parse as parseYaml, stringify as stringifyYaml } from "yaml";
+import { errorToString } from "@/lib/appError";
 
 export interface FrontmatterData {
   [key: string]: unknown;
@@ -50,7 +51,7 @@ export function parseFrontmatter(markdown: string): ParsedNote {
       frontmatter: {},
       content: markdown,
       hasFrontmatter: false,
-      parseError: error instanceof Error ? error.message : String(error),
+      parseError: error instanceof Error ? error.message : errorToString(error),
     };
   }
 }
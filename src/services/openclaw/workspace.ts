@@ -2,6 +2,7 @@ import { homeDir } from "@tauri-apps/api/path";
 import { join } from "@/lib/path";
 import { createDir, exists, saveFile } from "@/lib/tauri";
 import type { FileEntry } from "@/lib/tauri";
+import { errorToString } from "@/lib/appError";
 
 export const OPENCLAW_REQUIRED_ROOT_FILES = ["AGENTS.md", "SOUL.md", "USER.md"] as const;
 export const OPENCLAW_OPTIONAL_ROOT_FILES = [
@@ -242,7 +243,7 @@ export async function inspectOpenClawWorkspace(
       editablePriorityFiles: [],
       indexingScope: "shared-workspace",
       gatewayEnabled: false,
-      error: error instanceof Error ? error.message : String(error),
+      error: error instanceof Error ? error.message : errorToString(error),
     };
   }
 }
@@ -28,6 +28,7 @@ import { pluginRenderRuntime } from "@/services/plugins/renderRuntime";
 import { pluginEditorRuntime } from "@/services/plugins/editorRuntime";
 import type { PluginInfo, PluginPermission, PluginRuntimeStatus } from "@/types/plugins";
 import type { OpenClawConflictState, OpenClawWorkspaceAttachment } from "@/types/openclaw";
+import { errorToString } from "@/lib/appError";
 
 type PluginHostEvent = "app:ready" | "workspace:changed" | "active-file:changed";
 
@@ -538,7 +539,7 @@ class PluginRuntime {
         });
         statuses[plugin.id] = { enabled: true, loaded: true };
       } catch (err) {
-        const message = err instanceof Error ? err.message : String(err);
+        const message = err instanceof Error ? err.message : errorToString(err);
         const structured = parseStructuredManifestError(message);
         statuses[plugin.id] = structured
           ? {
@@ -1214,7 +1215,7 @@ return exported(api, plugin);
         },
         deleteFile: async (path: string) => {
           requirePermission("vault:delete");
-          return deleteFile(resolvePluginPath(path));
+          await deleteFile(resolvePluginPath(path));
         },
         renameFile: async (oldPath: string, newPath: string) => {
           requirePermission("vault:move");
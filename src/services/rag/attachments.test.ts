@@ -0,0 +1,44 @@
+import { describe, expect, it } from "vitest";
+
+import { attachmentCandidates, extractAttachmentRefs, isAttachmentPath } from "./attachments";
+
+describe("extractAttachmentRefs", () => {
+  it("collects embedded and linked PDFs and images outside code fences", () => {
+    const content = [
+      "![[scan.pdf]] and ![[shot.PNG|300]]",
+      "[report](<Reports/Q1 report.pdf>) ![chart](../assets/chart%201.png \"Chart\")",
+      "[[Other note]] [site](https://example.com/a.pdf) [doc](notes.md)",
+      "```",
+      "![[ignored.pdf]]",
+      "```",
+    ].join("\n");
+
+    expect(extractAttachmentRefs(content)).toEqual([
+      "scan.pdf",
+      "shot.PNG",
+      "Reports/Q1 report.pdf",
+      "../assets/chart 1.png",
+    ]);
+  });
+
+  it("recognizes attachment extensions", () => {
+    expect(isAttachmentPath("a/b.JPEG")).toBe(true);
+    expect(isAttachmentPath("note.md")).toBe(false);
+    expect(isAttachmentPath("pdf")).toBe(false);
+  });
+});
+
+describe("attachmentCandidates", () => {
+  it("resolves against the note folder first, then the workspace root", () => {
+    expect(attachmentCandidates("assets/a.png", "/vault/notes/x.md", "/vault")).toEqual([
+      "/vault/notes/assets/a.png",
+      "/vault/assets/a.png",
+    ]);
+    expect(attachmentCandidates("../assets/a.png", "/vault/notes/x.md", "/vault/")).toEqual([
+      "/vault/assets/a.png",
+    ]);
+    expect(attachmentCandidates("/scans/b.pdf", "/vault/x.md", "/vault")).toEqual([
+      "/vault/scans/b.pdf",
+    ]);
+  });
+});
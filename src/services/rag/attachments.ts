@@ -0,0 +1,77 @@
+/**
+ * 笔记附件（PDF、图片）引用解析
+ * 索引笔记时顺带索引它引用的附件，附件文本由后端提取（PDF 文本层 / OCR）
+ */
+
+const ATTACHMENT_EXTENSIONS = ["pdf", "png", "jpg", "jpeg", "webp", "gif", "bmp", "tif", "tiff"];
+
+/** ![[file.pdf|alias]] / [[file.pdf]] */
+const WIKI_ATTACHMENT_RE = /!?\[\[([^\]|#^]+)(?:[#^|][^\]]*)?\]\]/g;
+/** ![alt](file.png) / [text](<file name.pdf>) */
+const MARKDOWN_ATTACHMENT_RE = /!?\[[^\]]*\]\(\s*(<[^>]+>|[^)\s]+)(?:\s+"[^"]*")?\s*\)/g;
+
+/** 是否为可提取文本的附件（按扩展名） */
+export function isAttachmentPath(path: string): boolean {
+  const ext = path.split(".").pop()?.toLowerCase() ?? "";
+  return path.includes(".") && ATTACHMENT_EXTENSIONS.includes(ext);
+}
+
+/**
+ * 提取笔记中引用的附件路径（原样返回，未解析为绝对路径），跳过代码块和外链
+ */
+export function extractAttachmentRefs(content: string): string[] {
+  const refs = new Set<string>();
+  let inFence = false;
+  for (const line of content.split("\n")) {
+    const trimmed = line.trimStart();
+    if (trimmed.startsWith("```") || trimmed.startsWith("~~~")) {
+      inFence = !inFence;
+      continue;
+    }
+    if (inFence) continue;
+
+    for (const match of line.matchAll(WIKI_ATTACHMENT_RE)) {
+      const target = match[1].trim();
+      if (isAttachmentPath(target)) refs.add(target);
+    }
+    for (const match of line.matchAll(MARKDOWN_ATTACHMENT_RE)) {
+      const raw = match[1].replace(/^<|>$/g, "").trim();
+      if (/^[a-z][a-z0-9+.-]*:/i.test(raw) || raw.startsWith("#")) continue;
+      let target = raw.split(/[?#]/)[0];
+      try {
+        target = decodeURIComponent(target);
+      } catch {
+        // 保留原始路径
+      }
+      if (isAttachmentPath(target)) refs.add(target);
+    }
+  }
+  return [...refs];
+}
+
+/**
+ * 附件可能的绝对路径，按优先级排列：相对笔记所在目录，其次相对工作区根目录；
+ * 落在工作区之外的路径被丢弃
+ */
+export function attachmentCandidates(ref: string, notePath: string, workspacePath: string): string[] {
+  const normalized = ref.replace(/\\/g, "/");
+  const noteDir = notePath.replace(/\\/g, "/").replace(/\/[^/]*$/, "");
+  const root = workspacePath.replace(/\\/g, "/").replace(/\/$/, "");
+  const candidates = normalized.startsWith("/")
+    ? [joinPath(root, normalized)]
+    : [joinPath(noteDir, normalized), joinPath(root, normalized)];
+  return [...new Set(candidates)].filter((path) => path.startsWith(`${root}/`));
+}
+
+function joinPath(base: string, rel: string): string {
+  const parts = `${base}/${rel}`.split("/");
+  const out: string[] = [];
+  for (const [i, part] of parts.entries()) {
+    if (part === "..") {
+      if (out.length > 1) out.pop();
+    } else if (part !== "." && (part !== "" || i === 0)) {
+      out.push(part);
+    }
+  }
+  return out.join("/");
+}
@@ -0,0 +1,81 @@
+import { afterEach, beforeEach, describe, expect, it, vi } from "vitest";
+
+import { AutoReindexQueue, isAutoReindexPath } from "./autoReindex";
+
+function createTarget() {
+  return {
+    reindexFile: vi.fn().mockResolvedValue(undefined),
+    removeFile: vi.fn().mockResolvedValue(undefined),
+    isBusy: vi.fn().mockReturnValue(false),
+  };
+}
+
+describe("AutoReindexQueue", () => {
+  beforeEach(() => {
+    vi.useFakeTimers();
+  });
+
+  afterEach(() => {
+    vi.useRealTimers();
+  });
+
+  it("debounces repeated changes into one reindex per file", async () => {
+    const target = createTarget();
+    const queue = new AutoReindexQueue(target, undefined, 100);
+
+    queue.enqueue([{ type: "Modified", path: "/vault/a.md" }]);
+    await vi.advanceTimersByTimeAsync(50);
+    queue.enqueue([{ type: "Modified", path: "/vault/a.md" }]);
+    await vi.advanceTimersByTimeAsync(50);
+    expect(target.reindexFile).not.toHaveBeenCalled();
+
+    await vi.advanceTimersByTimeAsync(100);
+    expect(target.reindexFile).toHaveBeenCalledTimes(1);
+    expect(target.reindexFile).toHaveBeenCalledWith("/vault/a.md");
+  });
+
+  it("removes deleted and renamed-away files", async () => {
+    const target = createTarget();
+    const queue = new AutoReindexQueue(target);
+
+    queue.enqueue([
+      { type: "Deleted", path: "/vault/gone.md" },
+      { type: "Renamed", old_path: "/vault/old.md", new_path: "/vault/new.md" },
+    ]);
+    await queue.flush();
+
+    expect(target.removeFile.mock.calls).toEqual([["/vault/gone.md"], ["/vault/old.md"]]);
+    expect(target.reindexFile.mock.calls).toEqual([["/vault/new.md"]]);
+  });
+
+  it("ignores index files and non-markdown changes", async () => {
+    const target = createTarget();
+    const queue = new AutoReindexQueue(target);
+
+    queue.enqueue([
+      { type: "Modified", path: "/vault/.lumina/vectors.db" },
+      { type: "Created", path: "/vault/.lumina/notes.md" },
+      { type: "Modified", path: "/vault/board.excalidraw.json" },
+    ]);
+    await queue.flush();
+
+    expect(target.reindexFile).not.toHaveBeenCalled();
+    expect(isAutoReindexPath("C:\\vault\\notes\\a.md")).toBe(true);
+  });
+
+  it("waits for a running full index and reports progress", async () => {
+    const target = createTarget();
+    target.isBusy.mockReturnValue(true);
+    const onProgress = vi.fn();
+    const queue = new AutoReindexQueue(target, onProgress, 100);
+
+    queue.enqueue([{ type: "Created", path: "/vault/a.md" }]);
+    await vi.advanceTimersByTimeAsync(150);
+    expect(target.reindexFile).not.toHaveBeenCalled();
+
+    target.isBusy.mockReturnValue(false);
+    await vi.advanceTimersByTimeAsync(100);
+    expect(target.reindexFile).toHaveBeenCalledTimes(1);
+    expect(onProgress).toHaveBeenLastCalledWith({ current: 1, total: 1 });
+  });
+});
@@ -0,0 +1,128 @@
+/**
+ * 自动增量索引队列
+ * 监听 "fs:changes" 批量事件，去抖后只对变更的 Markdown 文件重新生成 embedding
+ */
+
+import type { FsChangePayload } from "@/lib/fsChange";
+import type { IndexProgressCallback } from "./manager";
+
+/** 去抖窗口：连续保存时只在停顿后重建一次 */
+export const AUTO_REINDEX_DEBOUNCE_MS = 1500;
+
+type PendingAction = "index" | "remove";
+
+export interface AutoReindexTarget {
+  /** 重新分块、生成 embedding 并写入单个文件 */
+  reindexFile(path: string): Promise<void>;
+  /** 删除单个文件的向量 */
+  removeFile(path: string): Promise<void>;
+  /** 全量/增量索引进行中时返回 true，队列会延后处理 */
+  isBusy(): boolean;
+}
+
+/**
+ * 只处理工作区内的 .md 文件；.lumina 下是索引数据库等内部文件，
+ * 处理它们会让索引写入反过来触发索引
+ */
+export function isAutoReindexPath(path: string): boolean {
+  if (!path.toLowerCase().endsWith(".md")) return false;
+  return !path.split(/[/\\]/).some((part) => part === ".lumina" || part === ".git");
+}
+
+export class AutoReindexQueue {
+  private pending = new Map<string, PendingAction>();
+  private timer: ReturnType<typeof setTimeout> | null = null;
+  private running: Promise<void> | null = null;
+
+  constructor(
+    private target: AutoReindexTarget,
+    private onProgress?: IndexProgressCallback,
+    private debounceMs = AUTO_REINDEX_DEBOUNCE_MS,
+  ) {}
+
+  /** 记录一批文件变更，重命名视为删除旧路径 + 索引新路径 */
+  enqueue(events: FsChangePayload[]): void {
+    for (const event of events) {
+      switch (event.type) {
+        case "Created":
+        case "Modified":
+          this.mark(event.path, "index");
+          break;
+        case "Deleted":
+          this.mark(event.path, "remove");
+          break;
+        case "Renamed":
+          this.mark(event.old_path, "remove");
+          this.mark(event.new_path, "index");
+          break;
+      }
+    }
+    if (this.pending.size > 0) this.schedule();
+  }
+
+  /** 等待队列中的变更全部处理完（测试与关闭时使用） */
+  async flush(): Promise<void> {
+    if (this.timer) {
+      clearTimeout(this.timer);
+      this.timer = null;
+    }
+    while (this.running || (this.pending.size > 0 && !this.target.isBusy())) {
+      await (this.running ?? this.run());
+    }
+  }
+
+  dispose(): void {
+    if (this.timer) clearTimeout(this.timer);
+    this.timer = null;
+    this.pending.clear();
+  }
+
+  private mark(path: unknown, action: PendingAction): void {
+    if (typeof path !== "string" || !isAutoReindexPath(path)) return;
+    this.pending.set(path, action);
+  }
+
+  private schedule(): void {
+    if (this.timer) clearTimeout(this.timer);
+    this.timer = setTimeout(() => {
+      this.timer = null;
+      void this.run();
+    }, this.debounceMs);
+  }
+
+  private run(): Promise<void> {
+    if (this.running) return this.running;
+    if (this.pending.size === 0) return Promise.resolve();
+    // 全量索引进行中：稍后再试，避免与其清空/重建交错
+    if (this.target.isBusy()) {
+      this.schedule();
+      return Promise.resolve();
+    }
+    this.running = this.process().finally(() => {
+      this.running = null;
+      if (this.pending.size > 0) this.schedule();
+    });
+    return this.running;
+  }
+
+  private async process(): Promise<void> {
+    const batch = [...this.pending.entries()];
+    this.pending.clear();
+    const total = batch.length;
+
+    for (let i = 0; i < total; i++) {
+      const [path, action] = batch[i];
+      this.onProgress?.({ current: i, total, currentFile: path });
+      try {
+        if (action === "remove") {
+          await this.target.removeFile(path);
+        } else {
+          await this.target.reindexFile(path);
+        }
+      } catch (e) {
+        console.warn(`[RAG] Auto reindex failed: ${path}`, e);
+      }
+    }
+    this.onProgress?.({ current: total, total });
+  }
+}
@@ -3,8 +3,79 @@
  * 将 Markdown 文档分割为语义块，针对笔记场景优化
  */
 
+import { invoke } from "@tauri-apps/api/core";
 import type { Chunk, ChunkMetadata, RAGConfig } from "./types";
 
+/** 后端 chunk_markdown 返回的分块（与 VectorChunk 相同，但没有向量） */
+interface MarkdownChunk {
+  id: string;
+  content: string;
+  file_path: string;
+  heading: string;
+  start_line: number;
+  end_line: number;
+  file_modified?: number | null;
+}
+
+/** 配置按字符计，后端按 token 计；英文约 4 字符 1 token */
+const CHARS_PER_TOKEN = 4;
+
+/**
+ * 调用后端分块（按标题层级，不拆分代码块），手动索引与自动索引共用
+ */
+export async function chunkMarkdown(
+  config: RAGConfig,
+  content: string,
+  filePath: string,
+  fileModified?: number,
+): Promise<Chunk[]> {
+  const chunks = await invoke<MarkdownChunk[]>("chunk_markdown", {
+    path: filePath,
+    content,
+    options: chunkOptions(config),
+    fileModified: fileModified === undefined ? null : Math.round(fileModified),
+  });
+  return toChunks(chunks);
+}
+
+/**
+ * 提取附件（PDF / 图片）文本并分块；缺少提取工具或没有文字时返回空数组
+ */
+export async function chunkAttachment(
+  config: RAGConfig,
+  filePath: string,
+  fileModified?: number,
+): Promise<Chunk[]> {
+  const chunks = await invoke<MarkdownChunk[]>("chunk_attachment", {
+    path: filePath,
+    options: chunkOptions(config),
+    fileModified: fileModified === undefined ? null : Math.round(fileModified),
+  });
+  return toChunks(chunks ?? []);
+}
+
+function chunkOptions(config: RAGConfig) {
+  return {
+    strategy: config.chunkStrategy ?? "heading",
+    max_tokens: Math.max(1, Math.round(config.chunkSize / CHARS_PER_TOKEN)),
+    overlap_tokens: Math.max(0, Math.round(config.chunkOverlap / CHARS_PER_TOKEN)),
+  };
+}
+
+function toChunks(chunks: MarkdownChunk[]): Chunk[] {
+  return chunks.map((chunk) => ({
+    id: chunk.id,
+    content: chunk.content,
+    metadata: {
+      filePath: chunk.file_path,
+      heading: chunk.heading,
+      startLine: chunk.start_line,
+      endLine: chunk.end_line,
+      fileModified: chunk.file_modified ?? undefined,
+    },
+  }));
+}
+
 export class MarkdownChunker {
   private chunkSize: number;
   private chunkOverlap: number;
@@ -5,7 +5,11 @@
 export * from "./types";
 export { Embedder } from "./embedder";
 export { Reranker } from "./reranker";
-export { MarkdownChunker } from "./chunker";
+export { MarkdownChunker, chunkMarkdown, chunkAttachment } from "./chunker";
+export { extractAttachmentRefs, isAttachmentPath } from "./attachments";
 export { VectorStore } from "./vectorStore";
+export type { ReindexPlan } from "./vectorStore";
 export { RAGManager } from "./manager";
 export type { IndexProgress, IndexProgressCallback } from "./manager";
+export { AutoReindexQueue, isAutoReindexPath } from "./autoReindex";
+export type { AutoReindexTarget } from "./autoReindex";
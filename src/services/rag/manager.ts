@@ -7,10 +7,12 @@ import { invoke } from "@tauri-apps/api/core";
 import { stat } from "@tauri-apps/plugin-fs";
 import { Embedder } from "./embedder";
 import { Reranker } from "./reranker";
-import { MarkdownChunker } from "./chunker";
+import { chunkAttachment, chunkMarkdown } from "./chunker";
+import { attachmentCandidates, extractAttachmentRefs, isAttachmentPath } from "./attachments";
 import { VectorStore } from "./vectorStore";
 import type {
   RAGConfig,
+  Chunk,
   ChunkWithVector,
   SearchOptions,
   SearchResult,
@@ -28,7 +30,6 @@ export type IndexProgressCallback = (progress: IndexProgress) => void;
 export class RAGManager {
   private embedder: Embedder;
   private reranker: Reranker;
-  private chunker: MarkdownChunker;
   private vectorStore: VectorStore;
   private config: RAGConfig;
   private workspacePath: string | null = null;
@@ -39,7 +40,6 @@ export class RAGManager {
     this.config = config;
     this.embedder = new Embedder(config);
     this.reranker = new Reranker(config);
-    this.chunker = new MarkdownChunker(config);
     // VectorStore will be initialized with actual path later
     this.vectorStore = null as unknown as VectorStore;
   }
@@ -51,7 +51,6 @@ export class RAGManager {
     this.config = { ...this.config, ...config };
     this.embedder.updateConfig(this.config);
     this.reranker.updateConfig(this.config);
-    this.chunker.updateConfig(this.config);
   }
 
   /**
@@ -178,12 +177,111 @@ export class RAGManager {
   }
 
   /**
-   * 索引单个文件
+   * 按索引差异重建 - 由后端对比文件修改时间，只处理新增、修改和已删除的文件
+   * （适合同步之后调用，无需读取未变化的文件）
+   */
+  async reindexChanged(onProgress?: IndexProgressCallback): Promise<void> {
+    if (!this.workspacePath) {
+      throw new Error("RAG Manager not initialized");
+    }
+
+    if (this.isIndexing) {
+      throw new Error("Indexing already in progress");
+    }
+
+    this.isIndexing = true;
+
+    try {
+      const plan = await this.vectorStore.computeReindexPlan(this.workspacePath);
+
+      // 删除磁盘上已不存在的文件
+      for (const path of plan.remove) {
+        await this.vectorStore.deleteByFile(path);
+        this.fileStateCache.delete(path);
+      }
+
+      const filesToIndex = [...plan.add, ...plan.update];
+      const total = filesToIndex.length;
+      let processed = 0;
+
+      for (const file of filesToIndex) {
+        onProgress?.({
+          current: processed,
+          total,
+          currentFile: file.path,
+        });
+
+        try {
+          if (isAttachmentPath(file.path)) {
+            // 已索引的附件：重新提取文本
+            await this.indexAttachment(file.path, file.modified);
+          } else {
+            const content = await invoke<string>("read_file", { path: file.path });
+            await this.vectorStore.deleteByFile(file.path);
+            await this.indexFile(file.path, content, file.modified);
+          }
+        } catch (e) {
+          console.warn(`[RAG] Failed to reindex file: ${file.path}`, e);
+        }
+        processed++;
+      }
+
+      onProgress?.({
+        current: total,
+        total,
+      });
+    } finally {
+      this.isIndexing = false;
+    }
+  }
+
+  /**
+   * 索引单个文件（连同它引用的 PDF / 图片附件）
    */
   async indexFile(filePath: string, content: string, modified?: number): Promise<void> {
     // 分块
-    const chunks = this.chunker.chunk(content, filePath, modified);
-    
+    const chunks = await chunkMarkdown(this.config, content, filePath, modified);
+    await this.embedAndStore(chunks);
+    await this.indexAttachments(filePath, content);
+  }
+
+  /**
+   * 索引单个附件：后端提取文本（PDF 文本层，扫描件和图片走 OCR），
+   * 未安装文档工具包时没有分块，附件被跳过
+   */
+  async indexAttachment(filePath: string, modified?: number): Promise<void> {
+    const chunks = await chunkAttachment(this.config, filePath, modified);
+    await this.vectorStore.deleteByFile(filePath);
+    await this.embedAndStore(chunks);
+  }
+
+  /**
+   * 索引笔记引用的附件，未变化的附件跳过
+   */
+  private async indexAttachments(notePath: string, content: string): Promise<void> {
+    if (!this.workspacePath) return;
+
+    for (const ref of extractAttachmentRefs(content)) {
+      for (const path of attachmentCandidates(ref, notePath, this.workspacePath)) {
+        let modified: number | null;
+        try {
+          modified = this.normalizeTimestamp((await stat(path)).mtime);
+        } catch {
+          continue; // 该候选路径不存在
+        }
+        try {
+          if (modified === null || await this.vectorStore.needsReindex(path, modified)) {
+            await this.indexAttachment(path, modified ?? undefined);
+          }
+        } catch (e) {
+          console.warn(`[RAG] Failed to index attachment: ${path}`, e);
+        }
+        break;
+      }
+    }
+  }
+
+  private async embedAndStore(chunks: Chunk[]): Promise<void> {
     if (chunks.length === 0) {
       return;
     }
@@ -199,7 +297,32 @@ export class RAGManager {
     }));
 
     // 存储
-    await this.vectorStore.upsert(chunksWithVectors);
+    await this.vectorStore.upsert(chunksWithVectors, this.config.embeddingModel);
+  }
+
+  /**
+   * 重新索引单个文件（文件监听触发的自动索引使用）
+   */
+  async reindexFile(filePath: string): Promise<void> {
+    if (!this.vectorStore?.isInitialized()) {
+      throw new Error("RAG Manager not initialized");
+    }
+    if (isAttachmentPath(filePath)) {
+      const modified = this.normalizeTimestamp((await stat(filePath)).mtime);
+      await this.indexAttachment(filePath, modified ?? undefined);
+      return;
+    }
+    const content = await invoke<string>("read_file", { path: filePath });
+    const modified = await this.resolveFileModifiedTime(filePath, content);
+    await this.vectorStore.deleteByFile(filePath);
+    await this.indexFile(filePath, content, modified);
+  }
+
+  /**
+   * 是否有全量/增量索引在进行
+   */
+  isBusy(): boolean {
+    return this.isIndexing;
   }
 
   /**
@@ -207,6 +330,7 @@ export class RAGManager {
    */
   async removeFile(filePath: string): Promise<void> {
     await this.vectorStore.deleteByFile(filePath);
+    this.fileStateCache.delete(filePath);
   }
 
   /**
@@ -221,7 +345,8 @@ export class RAGManager {
     const { embedding } = await this.embedder.embed(query);
 
     // 向量搜索（如果启用重排序，获取更多结果以供重排）
-    const searchLimit = this.reranker.isEnabled() 
+    const rerank = this.reranker.isEnabled() && !options?.skipRerank;
+    const searchLimit = rerank
       ? Math.max((options?.limit ?? this.config.maxResults) * 3, 20)
       : (options?.limit ?? this.config.maxResults);
 
@@ -232,7 +357,7 @@ export class RAGManager {
     });
 
     // 如果启用了重排序，进行 rerank
-    if (this.reranker.isEnabled() && results.length > 0) {
+    if (rerank && results.length > 0) {
       results = await this.reranker.rerank(query, results);
       // rerank 后截取用户要求的数量
       results = results.slice(0, options?.limit ?? this.config.maxResults);
@@ -28,6 +28,8 @@ export interface SearchOptions {
   limit?: number;
   minScore?: number;
   directory?: string;
+  /** 只返回向量检索结果（重排序由调用方自行处理） */
+  skipRerank?: boolean;
 }
 
 export interface SearchResult {
@@ -47,6 +49,9 @@ export interface IndexStatus {
   totalChunks: number;
   totalFiles: number;
   lastIndexed?: number;
+  /** 建索引所用的 embedding 模型及向量维度 */
+  embeddingModel?: string;
+  embeddingDimensions?: number;
   isIndexing: boolean;
   progress?: {
     current: number;
@@ -74,6 +79,7 @@ export interface RAGConfig {
   // 通用配置
   chunkSize: number;      // 分块大小 (字符)
   chunkOverlap: number;   // 重叠字符数
+  chunkStrategy?: "heading" | "overlap"; // 按标题层级分块 / 滑动窗口
   minScore: number;       // 最低相似度
   maxResults: number;     // 最大返回数
 }
@@ -91,6 +97,7 @@ export const DEFAULT_RAG_CONFIG: RAGConfig = {
   // 通用
   chunkSize: 1500,
   chunkOverlap: 200,
+  chunkStrategy: "heading",
   minScore: 0.5,
   maxResults: 10,
 };
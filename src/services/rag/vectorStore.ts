@@ -16,6 +16,14 @@ export interface VectorChunk {
   file_modified?: number;
 }
 
+/** Result of diffing the workspace against the index (mtimes in ms) */
+export interface ReindexPlan {
+  add: { path: string; modified: number }[];
+  update: { path: string; modified: number }[];
+  remove: string[];
+  unchanged: number;
+}
+
 export class VectorStore {
   private dbPath: string;
   private initialized = false;
@@ -42,9 +50,9 @@ export class VectorStore {
   }
 
   /**
-   * 插入或更新向量
+   * 插入或更新向量；model 为生成向量的 embedding 模型，与索引记录的模型不一致时后端拒绝写入
    */
-  async upsert(chunks: ChunkWithVector[]): Promise<void> {
+  async upsert(chunks: ChunkWithVector[], model?: string): Promise<void> {
     if (!this.initialized) {
       throw new Error("VectorStore not initialized");
     }
@@ -60,7 +68,7 @@ export class VectorStore {
       file_modified: c.metadata.fileModified,
     }));
 
-    await invoke("upsert_vector_chunks", { chunks: vectorChunks });
+    await invoke("upsert_vector_chunks", { chunks: vectorChunks, model: model ?? null });
   }
 
   /**
@@ -115,6 +123,8 @@ export class VectorStore {
       total_chunks: number;
       total_files: number;
       last_indexed?: number;
+      embedding_model?: string | null;
+      embedding_dimensions?: number | null;
     }>("get_vector_index_status");
 
     return {
@@ -122,6 +132,8 @@ export class VectorStore {
       totalChunks: status.total_chunks,
       totalFiles: status.total_files,
       lastIndexed: status.last_indexed,
+      embeddingModel: status.embedding_model ?? undefined,
+      embeddingDimensions: status.embedding_dimensions ?? undefined,
       isIndexing: false,
     };
   }
@@ -140,6 +152,17 @@ export class VectorStore {
     });
   }
 
+  /**
+   * 对比工作区与索引，得到需要新增/更新/删除的文件
+   */
+  async computeReindexPlan(workspacePath: string): Promise<ReindexPlan> {
+    if (!this.initialized) {
+      throw new Error("VectorStore not initialized");
+    }
+
+    return await invoke<ReindexPlan>("compute_reindex_plan", { workspacePath });
+  }
+
   /**
    * 清空所有向量
    */
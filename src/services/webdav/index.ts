@@ -10,6 +10,7 @@ import type {
   LocalFileInfo,
   SyncPlan,
   SyncResult,
+  AutoSyncStatus,
 } from './types';
 
 export * from './types';
@@ -183,6 +184,48 @@ export class WebDAVService {
       vaultPath,
     });
   }
+
+  /**
+   * 开启自动同步（需已启动文件监听），状态通过 webdav:auto-sync 事件推送
+   */
+  async enableAutoSync(vaultPath: string, debounceMs?: number): Promise<AutoSyncStatus> {
+    if (!this.config) {
+      throw new Error('WebDAV not configured');
+    }
+    return invoke<AutoSyncStatus>('webdav_enable_auto_sync', {
+      config: this.config,
+      vaultPath,
+      debounceMs,
+    });
+  }
+
+  /**
+   * 关闭自动同步
+   */
+  async disableAutoSync(): Promise<void> {
+    return invoke('webdav_disable_auto_sync');
+  }
+
+  /**
+   * 暂停自动同步
+   */
+  async pauseAutoSync(): Promise<void> {
+    return invoke('webdav_pause_auto_sync');
+  }
+
+  /**
+   * 恢复自动同步，并立即重试
+   */
+  async resumeAutoSync(): Promise<void> {
+    return invoke('webdav_resume_auto_sync');
+  }
+
+  /**
+   * 获取自动同步状态，未开启时为 null
+   */
+  async getAutoSyncStatus(): Promise<AutoSyncStatus | null> {
+    return invoke<AutoSyncStatus | null>('webdav_get_auto_sync_status');
+  }
 }
 
 // 导出单例实例
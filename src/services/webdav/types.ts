@@ -17,8 +17,18 @@ export interface WebDAVConfig {
   auto_sync: boolean;
   /** 自动同步间隔 (秒) */
   sync_interval_secs: number;
+  /** 两端都修改时的处理方式，缺省为 Skip */
+  conflict_strategy?: ConflictStrategy;
 }
 
+/**
+ * 冲突处理策略
+ * - Skip: 跳过，留给用户在同步计划中处理
+ * - PreferLocal / PreferRemote: 以一端为准覆盖另一端
+ * - KeepBoth: 远程版本另存为冲突副本，本地保持不变
+ */
+export type ConflictStrategy = 'Skip' | 'PreferLocal' | 'PreferRemote' | 'KeepBoth';
+
 /** 创建默认配置 */
 export function createDefaultConfig(): WebDAVConfig {
   return {
@@ -147,6 +157,23 @@ export interface SyncResult {
   conflicts: number;
   /** 错误列表 */
   errors: SyncError[];
+  /** 本次创建的冲突副本 (相对路径) */
+  conflict_copies: string[];
   /** 同步耗时 (毫秒) */
   duration_ms: number;
 }
+
+/** 自动同步阶段 */
+export type AutoSyncPhase = 'idle' | 'pending' | 'syncing' | 'paused' | 'offline';
+
+/** 自动同步状态（随 webdav:auto-sync 事件推送） */
+export interface AutoSyncStatus {
+  workspace: string;
+  phase: AutoSyncPhase;
+  /** 待同步的路径数 */
+  pending: number;
+  last_result: SyncResult | null;
+  last_error: string | null;
+  /** 离线时距下次重试的毫秒数 */
+  retry_in_ms: number | null;
+}
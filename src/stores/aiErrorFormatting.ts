@@ -1,7 +1,8 @@
 import { getCurrentTranslations } from "@/stores/useLocaleStore";
+import { errorToString } from "@/lib/appError";
 
 function extractProviderErrorMessage(error: unknown): string {
-  const errorStr = error instanceof Error ? error.message : String(error);
+  const errorStr = error instanceof Error ? error.message : errorToString(error);
 
   try {
     const jsonMatch = errorStr.match(/\{[\s\S]*\}/);
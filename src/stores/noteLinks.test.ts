@@ -0,0 +1,97 @@
+import { describe, expect, it } from "vitest";
+import {
+  buildNoteLinkIndex,
+  collectBacklinks,
+  extractNoteLinks,
+  noteLinkKey,
+  resolveNoteLink,
+} from "./noteLinks";
+
+const files = [
+  { path: "/vault/Alpha.md", name: "Alpha" },
+  { path: "/vault/Projects/Alpha.md", name: "Alpha" },
+  { path: "/vault/Projects/Beta Plan.md", name: "Beta Plan" },
+  { path: "/vault/Daily/2024-01-15.md", name: "2024-01-15" },
+];
+const index = buildNoteLinkIndex(files);
+
+describe("extractNoteLinks", () => {
+  it("extracts wikilinks with aliases and headings", () => {
+    const links = extractNoteLinks("See [[Alpha|the alpha note]] and [[Projects/Beta Plan#Goals]].");
+    expect(links.map((link) => [link.target, link.kind])).toEqual([
+      ["Alpha", "wiki"],
+      ["Projects/Beta Plan", "wiki"],
+    ]);
+    expect(links[0].line).toBe(1);
+  });
+
+  it("extracts local markdown links and skips urls, images and code fences", () => {
+    const content = [
+      "[plan](<Projects/Beta Plan.md>) [web](https://example.com/a.md)",
+      "![img](assets/pic.png) [encoded](../Alpha%20Notes.md#top)",
+      "```",
+      "[[Ignored]]",
+      "```",
+    ].join("\n");
+    expect(extractNoteLinks(content).map((link) => link.target)).toEqual([
+      "Projects/Beta Plan.md",
+      "../Alpha Notes.md",
+    ]);
+  });
+});
+
+describe("resolveNoteLink", () => {
+  it("resolves path-qualified wikilinks by vault path", () => {
+    expect(resolveNoteLink({ target: "Projects/Alpha", kind: "wiki" }, "/vault/Daily/2024-01-15.md", index)).toBe(
+      "/vault/Projects/Alpha.md"
+    );
+  });
+
+  it("prefers the note in the source folder for bare names", () => {
+    expect(resolveNoteLink({ target: "alpha", kind: "wiki" }, "/vault/Projects/Beta Plan.md", index)).toBe(
+      "/vault/Projects/Alpha.md"
+    );
+    expect(resolveNoteLink({ target: "Alpha", kind: "wiki" }, "/vault/Daily/2024-01-15.md", index)).toBe(
+      "/vault/Alpha.md"
+    );
+  });
+
+  it("resolves relative markdown links with or without .md", () => {
+    expect(resolveNoteLink({ target: "../Projects/Beta Plan.md", kind: "markdown" }, "/vault/Daily/2024-01-15.md", index)).toBe(
+      "/vault/Projects/Beta Plan.md"
+    );
+    expect(resolveNoteLink({ target: "./Alpha", kind: "markdown" }, "/vault/Projects/Beta Plan.md", index)).toBe(
+      "/vault/Projects/Alpha.md"
+    );
+    expect(resolveNoteLink({ target: "assets/pic.png", kind: "markdown" }, "/vault/Alpha.md", index)).toBeNull();
+  });
+});
+
+describe("collectBacklinks", () => {
+  it("merges all link forms into one backlink per source file", () => {
+    const backlinks = collectBacklinks(
+      [
+        {
+          path: "/vault/Daily/2024-01-15.md",
+          name: "2024-01-15",
+          content: "Worked on [[Projects/Alpha|alpha]].\nAgain: [alpha](../Projects/Alpha.md)",
+        },
+        {
+          path: "/vault/Projects/Beta Plan.md",
+          name: "Beta Plan",
+          content: "Depends on [[Alpha]] and [[Missing Note]].",
+        },
+      ],
+      index
+    );
+
+    const alpha = backlinks.get(noteLinkKey("/vault/Projects/Alpha.md")) ?? [];
+    expect(alpha.map((link) => [link.path, link.line])).toEqual([
+      ["/vault/Daily/2024-01-15.md", 1],
+      ["/vault/Projects/Beta Plan.md", 1],
+    ]);
+    expect(alpha[0].context).toBe("Worked on [[Projects/Alpha|alpha]].");
+    expect(backlinks.get("missing note")?.map((link) => link.name)).toEqual(["Beta Plan"]);
+    expect(backlinks.has(noteLinkKey("/vault/Alpha.md"))).toBe(false);
+  });
+});
@@ -0,0 +1,188 @@
+import type { Backlink } from "./useNoteIndexStore";
+
+// A link found in a note: [[wikilink]] or [text](markdown-link)
+export interface NoteLinkRef {
+  target: string;
+  kind: "wiki" | "markdown";
+  line: number; // 1-based
+  context: string;
+}
+
+// Lookup tables for resolving link targets to note paths
+export interface NoteLinkIndex {
+  byPath: Map<string, string>; // noteLinkKey(path) -> path
+  byName: Map<string, string[]>; // lowercase note name -> paths
+}
+
+export interface LinkedNote {
+  path: string;
+  name: string;
+  content: string;
+}
+
+const WIKI_LINK_RE = /\[\[([^\]|#^]*)(?:[#^][^\]|]*)?(?:\|[^\]]*)?\]\]/g;
+const MARKDOWN_LINK_RE = /(!?)\[[^\]]*\]\(\s*(<[^>]+>|[^)\s]+)(?:\s+"[^"]*")?\s*\)/g;
+const URL_SCHEME_RE = /^[a-z][a-z0-9+.-]*:/i;
+
+// Normalize separators and "."/".." segments, keeping the root ("/" or "C:/")
+function normalizeNotePath(path: string): string {
+  const normalized = path.replace(/\\/g, "/");
+  const root = normalized.match(/^([a-zA-Z]:\/|\/)/)?.[0] ?? "";
+  const segments: string[] = [];
+  for (const segment of normalized.slice(root.length).split("/")) {
+    if (!segment || segment === ".") continue;
+    if (segment === "..") {
+      segments.pop();
+      continue;
+    }
+    segments.push(segment);
+  }
+  return root + segments.join("/");
+}
+
+function dirnameOf(path: string): string {
+  const normalized = normalizeNotePath(path);
+  const lastSlash = normalized.lastIndexOf("/");
+  return lastSlash === -1 ? "" : normalized.slice(0, lastSlash);
+}
+
+function noteNameOf(path: string): string {
+  const normalized = normalizeNotePath(path);
+  return normalized.slice(normalized.lastIndexOf("/") + 1).replace(/\.md$/i, "");
+}
+
+// Case-insensitive key used for backlinks of a note path
+export function noteLinkKey(path: string): string {
+  return normalizeNotePath(path).toLowerCase();
+}
+
+// Extract wikilinks and local markdown links, skipping fenced code blocks
+export function extractNoteLinks(content: string): NoteLinkRef[] {
+  const links: NoteLinkRef[] = [];
+  let inFence = false;
+
+  content.split("\n").forEach((rawLine, index) => {
+    const line = rawLine.replace(/\r$/, "");
+    if (/^\s*(```|~~~)/.test(line)) {
+      inFence = !inFence;
+      return;
+    }
+    if (inFence) return;
+
+    const context = line.trim();
+    for (const match of line.matchAll(WIKI_LINK_RE)) {
+      const target = match[1].trim();
+      if (target) {
+        links.push({ target, kind: "wiki", line: index + 1, context });
+      }
+    }
+    for (const match of line.matchAll(MARKDOWN_LINK_RE)) {
+      if (match[1] === "!") continue; // images
+      let target = match[2].replace(/^<|>$/g, "").trim();
+      if (!target || target.startsWith("#") || URL_SCHEME_RE.test(target)) continue;
+      target = target.replace(/[#?].*$/, "");
+      try {
+        target = decodeURIComponent(target);
+      } catch {
+        // Keep the raw target when it is not valid percent-encoding
+      }
+      if (target) {
+        links.push({ target, kind: "markdown", line: index + 1, context });
+      }
+    }
+  });
+
+  return links;
+}
+
+export function buildNoteLinkIndex(files: { path: string; name: string }[]): NoteLinkIndex {
+  const byPath = new Map<string, string>();
+  const byName = new Map<string, string[]>();
+  for (const file of files) {
+    byPath.set(noteLinkKey(file.path), file.path);
+    const name = file.name.toLowerCase();
+    byName.set(name, [...(byName.get(name) ?? []), file.path]);
+  }
+  return { byPath, byName };
+}
+
+/**
+ * Resolve a link target to the canonical note path.
+ * Relative targets are tried against the source folder first, path-qualified
+ * targets ([[folder/note]], [x](/folder/note.md)) match by vault path suffix,
+ * and bare names fall back to name lookup (preferring the source folder).
+ * The ".md" extension may be omitted. Returns null for unresolved links.
+ */
+export function resolveNoteLink(
+  ref: Pick<NoteLinkRef, "target" | "kind">,
+  sourcePath: string,
+  index: NoteLinkIndex
+): string | null {
+  const target = ref.target.trim().replace(/\\/g, "/");
+  const fileName = target.slice(target.lastIndexOf("/") + 1);
+  const extMatch = fileName.match(/\.[^.]+$/);
+  if (extMatch && extMatch[0].toLowerCase() !== ".md") return null;
+  const withExt = extMatch ? target : `${target}.md`;
+
+  const sourceDir = dirnameOf(sourcePath);
+  if (!withExt.startsWith("/")) {
+    const relative = index.byPath.get(noteLinkKey(sourceDir ? `${sourceDir}/${withExt}` : withExt));
+    if (relative) return relative;
+  }
+
+  if (withExt.includes("/")) {
+    const suffix = `/${noteLinkKey(withExt).replace(/^\/+/, "")}`;
+    let best: string | null = null;
+    for (const [key, path] of index.byPath) {
+      if (key.endsWith(suffix) && (best === null || path.length < best.length)) {
+        best = path;
+      }
+    }
+    return best;
+  }
+
+  const matches = index.byName.get(noteNameOf(withExt).toLowerCase()) ?? [];
+  if (matches.length <= 1) return matches[0] ?? null;
+  const sourceKey = noteLinkKey(sourceDir);
+  return (
+    matches.find((path) => noteLinkKey(dirnameOf(path)) === sourceKey) ??
+    [...matches].sort((a, b) => a.length - b.length)[0]
+  );
+}
+
+/**
+ * Build backlinks for all notes, keyed by noteLinkKey(targetPath).
+ * Unresolved wikilinks are keyed by their lowercase note name so links to
+ * notes that do not exist yet still show up. Each source file contributes
+ * at most one backlink per target (its first occurrence).
+ */
+export function collectBacklinks(notes: LinkedNote[], index: NoteLinkIndex): Map<string, Backlink[]> {
+  const backlinks = new Map<string, Backlink[]>();
+
+  for (const note of notes) {
+    const seen = new Set<string>();
+    for (const ref of extractNoteLinks(note.content)) {
+      const resolved = resolveNoteLink(ref, note.path, index);
+      let key: string;
+      if (resolved) {
+        key = noteLinkKey(resolved);
+      } else if (ref.kind === "wiki") {
+        key = noteNameOf(ref.target).toLowerCase();
+      } else {
+        continue;
+      }
+      if (seen.has(key)) continue;
+      seen.add(key);
+
+      const entry: Backlink = {
+        path: note.path,
+        name: note.name,
+        context: ref.context,
+        line: ref.line,
+      };
+      backlinks.set(key, [...(backlinks.get(key) ?? []), entry]);
+    }
+  }
+
+  return backlinks;
+}
@@ -42,6 +42,8 @@ interface BrowserState {
   // 全局隐藏状态（用于模态框打开时）
   globalHidden: boolean;
   hiddenRequestCount: number;
+  // 网页下载保存目录（需位于工作区内）
+  downloadDir: string | null;
   
   // Actions
   registerWebView: (tabId: string, url: string, title?: string) => void;
@@ -49,6 +51,7 @@ interface BrowserState {
   setActiveTab: (tabId: string | null) => void;
   updateUrl: (tabId: string, url: string) => void;
   updateTitle: (tabId: string, title: string) => void;
+  setDownloadDir: (dir: string) => Promise<void>;
   
   // 状态管理
   freezeTab: (tabId: string) => Promise<void>;
@@ -93,6 +96,7 @@ export const useBrowserStore = create<BrowserState>((set, get) => ({
   activeTabId: null,
   globalHidden: false,
   hiddenRequestCount: 0,
+  downloadDir: null,
   
   // 注册新的 WebView
   registerWebView: (tabId: string, url: string, title?: string) => {
@@ -177,6 +181,22 @@ export const useBrowserStore = create<BrowserState>((set, get) => ({
     set({ instances: newInstances });
   },
   
+  // 设置下载目录（后端校验路径是否在允许范围内）
+  setDownloadDir: async (dir: string) => {
+    try {
+      await invoke('set_browser_download_dir', { dir });
+      set({ downloadDir: dir });
+    } catch (error) {
+      reportOperationError({
+        source: 'BrowserStore.setDownloadDir',
+        action: 'Set browser download folder',
+        error,
+        level: 'warning',
+        context: { dir },
+      });
+    }
+  },
+  
   // 冻结标签页（暂停 JS，但保留 WebView）
   freezeTab: async (tabId: string) => {
     const { instances } = get();
@@ -186,14 +206,17 @@ export const useBrowserStore = create<BrowserState>((set, get) => ({
     if (instance.state === 'frozen' || instance.state === 'discarded') return;
     
     try {
-      // 通过注入 JS 暂停页面活动
-      await invoke('browser_webview_freeze', { tabId });
+      // 原生隐藏 WebView（引擎挂起渲染并节流定时器），并注入 JS 暂停媒体
+      const result = await invoke<{ suspended: boolean; method: string } | null>(
+        'browser_webview_freeze',
+        { tabId },
+      );
       
       const newInstances = new Map(instances);
       newInstances.set(tabId, { ...instance, state: 'frozen' });
       set({ instances: newInstances });
       
-      console.log('[BrowserStore] 冻结标签页:', tabId);
+      console.log('[BrowserStore] 冻结标签页:', tabId, result);
     } catch (err) {
       reportOperationError({
         source: "BrowserStore.freezeTab",
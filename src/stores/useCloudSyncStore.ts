@@ -12,6 +12,7 @@ import {
   refreshCloudToken,
   registerCloudAccount,
 } from '@/services/cloudSync/client';
+import { errorToString } from '@/lib/appError';
 
 export type CloudAuthStatus = 'anonymous' | 'authenticating' | 'authenticated';
 
@@ -87,7 +88,7 @@ async function authenticate(
     syncDerivedWebDav({ ...get(), session });
     return session;
   } catch (error) {
-    const message = error instanceof Error ? error.message : String(error);
+    const message = error instanceof Error ? error.message : errorToString(error);
     set({ isLoading: false, authStatus: 'anonymous', error: message, session: null });
     useWebDAVStore.getState().resetConfig();
     return null;
@@ -129,7 +130,7 @@ export const useCloudSyncStore = create<CloudSyncState>()(
           set({ session: nextSession, authStatus: 'authenticated', error: null });
           return response.token;
         } catch (error) {
-          const message = error instanceof Error ? error.message : String(error);
+          const message = error instanceof Error ? error.message : errorToString(error);
           set({ error: message, authStatus: 'anonymous', session: null });
           return null;
         }
@@ -144,7 +145,7 @@ export const useCloudSyncStore = create<CloudSyncState>()(
           syncDerivedWebDav({ ...get(), session: nextSession });
           return workspaces;
         } catch (error) {
-          const message = error instanceof Error ? error.message : String(error);
+          const message = error instanceof Error ? error.message : errorToString(error);
           set({ error: message });
           return null;
         }
@@ -166,7 +167,7 @@ export const useCloudSyncStore = create<CloudSyncState>()(
           syncDerivedWebDav({ ...get(), session: nextSession });
           return workspace;
         } catch (error) {
-          const message = error instanceof Error ? error.message : String(error);
+          const message = error instanceof Error ? error.message : errorToString(error);
           set({ error: message });
           return null;
         }
@@ -23,6 +23,7 @@ import { applyFilters } from "./databaseFilter";
 import { applyFormulaColumns } from "./databaseFormula";
 import { normalizeDatabaseDefinition } from "@/features/database/selectOptions";
 import { reportOperationError } from "@/lib/reportError";
+import { errorToString } from "@/lib/appError";
 
 // ==================== 工具函数 ====================
 
@@ -177,7 +178,7 @@ function isBlankCellValue(value: unknown): boolean {
 
 function normalizeError(error: unknown): Error {
   if (error instanceof Error) return error;
-  return new Error(String(error));
+  return new Error(errorToString(error));
 }
 
 // ==================== Store Interface ====================
@@ -10,6 +10,7 @@ import { invoke } from "@tauri-apps/api/core";
 import { listen, UnlistenFn } from "@tauri-apps/api/event";
 import { getCurrentTranslations } from "@/stores/useLocaleStore";
 import { reportOperationError } from "@/lib/reportError";
+import { errorToString } from "@/lib/appError";
 
 // ============ 类型定义 ============
 
@@ -31,6 +32,9 @@ export type ResearchPhase =
 /** 报告风格 */
 export type ReportStyle = "detailed" | "summary" | "outline";
 
+/** 报告篇幅预设 */
+export type ReportPreset = "brief" | "standard" | "comprehensive";
+
 /** 笔记引用 */
 export interface NoteReference {
   path: string;
@@ -70,12 +74,31 @@ export interface DeepResearchConfig {
   max_search_results?: number;
   max_notes_to_read?: number;
   report_style?: ReportStyle;
+  report_preset?: ReportPreset;
   include_citations?: boolean;
   locale?: string;
   // 网络搜索配置
   enable_web_search?: boolean;
+  /** 网络搜索提供方："tavily"（默认）或 "searxng" */
+  web_search_provider?: string;
   tavily_api_key?: string;
+  /** SearXNG 实例地址 */
+  searxng_url?: string;
   max_web_search_results?: number;
+  /** 仅搜索这些域名（如文档站） */
+  include_domains?: string[];
+  /** 排除这些域名（如内容农场） */
+  exclude_domains?: string[];
+  /** 每个域名最多保留的结果数 */
+  max_results_per_domain?: number;
+  // 网页爬取配置
+  /** 从搜索结果跟进链接的最大深度（0 表示不跟进） */
+  crawl_max_depth?: number;
+  /** 最多爬取的网页数 */
+  crawl_max_pages?: number;
+  respect_robots_txt?: boolean;
+  /** 同一主机两次请求的最小间隔（毫秒） */
+  crawl_host_interval_ms?: number;
 }
 
 /** 研究请求 */
@@ -84,6 +107,7 @@ export interface DeepResearchRequest {
   workspace_path: string;
   search_scope?: string;
   report_style: ReportStyle;
+  report_preset?: ReportPreset;
   include_citations: boolean;
   pre_searched_notes: NoteReference[];
 }
@@ -102,6 +126,16 @@ export interface TokenUsage {
   totalTokens: number;
 }
 
+/** 后端随事件推送的整体进度 */
+export interface ResearchProgress {
+  /** 0-1 */
+  fraction: number;
+  /** 预计剩余秒数，等待澄清或样本不足时缺省 */
+  eta_seconds?: number;
+  /** 等待用户澄清时为 true */
+  paused: boolean;
+}
+
 /** 研究会话 */
 export interface ResearchSession {
   id: string;
@@ -111,17 +145,23 @@ export interface ResearchSession {
   completedAt?: Date;  // 完成时间
   phase: ResearchPhase;
   phaseMessage: string;
+  /** 后端确认的篇幅预设（开始时的 phase_change 事件携带） */
+  reportPreset?: ReportPreset;
   keywords: string[];
   foundNotes: NoteReference[];
   webSearchResults: WebSearchResult[];  // 网络搜索结果
   crawlingProgress: { current: number; total: number };  // 爬取进度
   readingProgress: { current: number; total: number };
+  /** 整体进度（旧版本保存的会话没有该字段） */
+  progress?: ResearchProgress;
   outline: ReportOutline | null;
   reportChunks: string[];
   finalReport: string | null;
   error: string | null;
   // 澄清相关
   clarification: ClarificationInfo | null;
+  /** 已提交的澄清回答（按轮次顺序） */
+  clarifications?: string[];
   // Token 使用统计
   tokenUsage: TokenUsage;
 }
@@ -149,6 +189,7 @@ interface DeepResearchState {
       chatId?: string;  // 关联的聊天对话 ID
       searchScope?: string;
       reportStyle?: ReportStyle;
+      reportPreset?: ReportPreset;
       includeCitations?: boolean;
       preSearchedNotes?: NoteReference[];
     }
@@ -164,14 +205,14 @@ interface DeepResearchState {
   getSelectedSession: () => ResearchSession | null;
 
   // 内部方法
-  _handleEvent: (event: DeepResearchEvent) => void;
+  _handleEvent: (event: DeepResearchEventPayload) => void;
   _setupListener: () => Promise<UnlistenFn>;
   _saveToHistory: () => void;
 }
 
 /** 后端事件类型 */
 type DeepResearchEvent =
-  | { type: "phase_change"; data: { phase: ResearchPhase; message: string } }
+  | { type: "phase_change"; data: { phase: ResearchPhase; message: string; preset?: ReportPreset } }
   | { type: "keywords_extracted"; data: { keywords: string[] } }
   | { type: "notes_found"; data: { notes: NoteReference[] } }
   | { type: "web_search_complete"; data: { results: WebSearchResult[] } }
@@ -198,6 +239,9 @@ type DeepResearchEvent =
   | { type: "complete"; data: { report: string } }
   | { type: "error"; data: { message: string } };
 
+/** 事件载荷：事件本身附带整体进度 */
+type DeepResearchEventPayload = DeepResearchEvent & { progress?: ResearchProgress };
+
 // ============ Store 实现 ============
 
 const STORAGE_KEY = "deep-research-sessions";
@@ -217,6 +261,7 @@ export const useDeepResearchStore = create<DeepResearchState>()(
       chatId = crypto.randomUUID(),  // 默认生成一个 ID
       searchScope,
       reportStyle = "detailed",
+      reportPreset,
       includeCitations = true,
       preSearchedNotes = [],
     } = options;
@@ -250,6 +295,7 @@ export const useDeepResearchStore = create<DeepResearchState>()(
       workspace_path: workspacePath,
       search_scope: searchScope,
       report_style: reportStyle,
+      report_preset: reportPreset,
       include_citations: includeCitations,
       pre_searched_notes: preSearchedNotes,
     };
@@ -270,7 +316,7 @@ export const useDeepResearchStore = create<DeepResearchState>()(
           ? {
               ...state.currentSession,
               phase: "error",
-              error: String(error),
+              error: errorToString(error),
             }
           : null,
         isRunning: false,
@@ -304,20 +350,21 @@ export const useDeepResearchStore = create<DeepResearchState>()(
       return;
     }
 
+    // 先更新状态：恢复后后端可能很快再次请求澄清，不能在 invoke 之后用旧快照覆盖
+    set({
+      isWaitingForClarification: false,
+      currentSession: {
+        ...currentSession,
+        phase: "analyzing_topic",
+        phaseMessage: getCurrentTranslations().deepResearch.phaseMessages.resumed,
+        clarification: null,
+        clarifications: [...(currentSession.clarifications ?? []), clarification],
+      },
+    });
+
     try {
       console.log("[DeepResearch] Submitting clarification:", clarification);
       await invoke("deep_research_resume", { clarification });
-      
-      // 更新状态
-      set({
-        isWaitingForClarification: false,
-        currentSession: {
-          ...currentSession,
-          phase: "analyzing_topic",
-          phaseMessage: getCurrentTranslations().deepResearch.phaseMessages.resumed,
-          clarification: null,
-        },
-      });
     } catch (error) {
       reportOperationError({
         source: "DeepResearchStore.submitClarification",
@@ -325,15 +372,17 @@ export const useDeepResearchStore = create<DeepResearchState>()(
         error,
         context: { sessionId: currentSession.id },
       });
-      set({
+      set((state) => ({
         isWaitingForClarification: false,
-        currentSession: {
-          ...currentSession,
-          phase: "error",
-          error: String(error),
-        },
+        currentSession: state.currentSession
+          ? {
+              ...state.currentSession,
+              phase: "error",
+              error: errorToString(error),
+            }
+          : null,
         isRunning: false,
-      });
+      }));
     }
   },
 
@@ -407,6 +456,15 @@ export const useDeepResearchStore = create<DeepResearchState>()(
   },
 
   _handleEvent: (event) => {
+    if (event.progress) {
+      const progress = event.progress;
+      set((state) => ({
+        currentSession: state.currentSession
+          ? { ...state.currentSession, progress }
+          : null,
+      }));
+    }
+
     const { currentSession } = get();
     if (!currentSession) return;
 
@@ -417,6 +475,7 @@ export const useDeepResearchStore = create<DeepResearchState>()(
             ...currentSession,
             phase: event.data.phase,
             phaseMessage: event.data.message,
+            reportPreset: event.data.preset ?? currentSession.reportPreset,
           },
         });
         break;
@@ -559,7 +618,7 @@ export const useDeepResearchStore = create<DeepResearchState>()(
   },
 
   _setupListener: async () => {
-    const unlisten = await listen<DeepResearchEvent>(
+    const unlisten = await listen<DeepResearchEventPayload>(
       "deep-research-event",
       (event) => {
         get()._handleEvent(event.payload);
@@ -637,6 +696,14 @@ export function getPhaseProgress(phase: ResearchPhase): number {
   return progress[phase] || 0;
 }
 
+/** 格式化预计剩余时间 */
+export function formatEta(seconds: number): string {
+  if (seconds < 60) return `${seconds}s`;
+  const minutes = Math.round(seconds / 60);
+  if (minutes < 60) return `${minutes}m`;
+  return `${Math.floor(minutes / 60)}h ${minutes % 60}m`;
+}
+
 /** 阶段列表（用于进度展示） */
 export const RESEARCH_PHASES: ResearchPhase[] = [
   "analyzing_topic",
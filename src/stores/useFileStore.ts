@@ -10,6 +10,7 @@ import { useOpenClawWorkspaceStore } from "@/stores/useOpenClawWorkspaceStore";
 import { getCurrentTranslations } from "@/stores/useLocaleStore";
 import { parseFrontmatter } from "@/services/markdown/frontmatter";
 import { reportOperationError } from "@/lib/reportError";
+import { errorToString } from "@/lib/appError";
 
 // 历史记录条目
 interface HistoryEntry {
@@ -64,6 +65,7 @@ export interface Tab {
   databaseId?: string; // 数据库 ID
   webpageUrl?: string; // 网页 URL
   webpageTitle?: string; // 网页标题
+  webpagePartition?: string; // 网页存储分区（"incognito" 为无痕，其他名称为持久化容器）
   flashcardDeckId?: string; // 闪卡牌组 ID
   pluginViewType?: string; // 插件视图类型
   pluginViewHtml?: string; // 插件视图 HTML
@@ -183,7 +185,7 @@ interface FileState {
   openPDFTab: (pdfPath: string) => void;
   openDiagramTab: (diagramPath: string) => void;
   openAIMainTab: () => void;
-  openWebpageTab: (url: string, title?: string) => void;
+  openWebpageTab: (url: string, title?: string, partition?: string) => void;
   updateWebpageTab: (tabId: string, url?: string, title?: string) => void;
   openFlashcardTab: (deckId?: string) => void;
   openCardFlowTab: () => void;
@@ -1763,14 +1765,17 @@ export const useFileStore = create<FileState>()(
       },
 
       // 打开网页标签页
-      openWebpageTab: (url: string, title?: string) => {
+      openWebpageTab: (url: string, title?: string, partition?: string) => {
         const t = getCurrentTranslations();
         const { tabs, activeTabIndex, currentContent, isDirty, undoStack, redoStack, switchTab } = get();
 
         // 如果已有相同 URL 的网页标签，直接切换过去，避免重复创建
         if (url) {
           const existingIndex = tabs.findIndex(
-            (t) => t.type === "webpage" && t.webpageUrl === url
+            (t) =>
+              t.type === "webpage" &&
+              t.webpageUrl === url &&
+              t.webpagePartition === partition
           );
           if (existingIndex !== -1) {
             // 在切换前仍然保存当前标签页状态
@@ -1828,6 +1833,7 @@ export const useFileStore = create<FileState>()(
           redoStack: [],
           webpageUrl: url,
           webpageTitle: defaultTitle,
+          webpagePartition: partition,
         };
 
         updatedTabs.push(webpageTab);
@@ -2269,7 +2275,7 @@ export const useFileStore = create<FileState>()(
           get().setMobileWorkspaceSync({
             status: "error",
             path,
-            error: error instanceof Error ? error.message : String(error),
+            error: error instanceof Error ? error.message : errorToString(error),
           });
         }
       },
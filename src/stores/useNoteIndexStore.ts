@@ -1,5 +1,6 @@
 import { create } from "zustand";
 import { FileEntry, readFile } from "@/lib/tauri";
+import { buildNoteLinkIndex, collectBacklinks, LinkedNote, noteLinkKey } from "./noteLinks";
 
 // Extract [[wikilinks]] from content
 export function extractWikiLinks(content: string): string[] {
@@ -52,7 +53,8 @@ interface NoteIndexState {
   // Index of all notes
   noteIndex: Map<string, NoteIndex>;
 
-  // Backlinks cache: targetName -> backlinks[]
+  // Backlinks cache: noteLinkKey(targetPath) -> backlinks[]
+  // (unresolved wikilinks are keyed by lowercase note name)
   backlinksCache: Map<string, Backlink[]>;
 
   // All tags
@@ -64,7 +66,7 @@ interface NoteIndexState {
 
   // Actions
   buildIndex: (fileTree: FileEntry[]) => Promise<void>;
-  getBacklinks: (noteName: string) => Backlink[];
+  getBacklinks: (notePathOrName: string) => Backlink[];
   getTagFiles: (tag: string) => string[];
   searchContent: (query: string, files: FileEntry[]) => Promise<SearchResult[]>;
 }
@@ -94,7 +96,7 @@ export const useNoteIndexStore = create<NoteIndexState>((set, get) => ({
     set({ isIndexing: true });
 
     const noteIndex = new Map<string, NoteIndex>();
-    const backlinksMap = new Map<string, Backlink[]>();
+    const linkedNotes: LinkedNote[] = [];
     const tagsMap = new Map<string, { count: number; files: string[] }>();
 
     // Flatten file tree
@@ -113,12 +115,6 @@ export const useNoteIndexStore = create<NoteIndexState>((set, get) => ({
     };
     flattenTree(fileTree);
 
-    // Build note name to path map for resolving links
-    const nameToPath = new Map<string, string>();
-    allFiles.forEach(f => {
-      nameToPath.set(f.name.toLowerCase(), f.path);
-    });
-
     // Index each file
     for (const file of allFiles) {
       try {
@@ -135,34 +131,7 @@ export const useNoteIndexStore = create<NoteIndexState>((set, get) => ({
           lastModified: Date.now(),
         });
 
-        // Build backlinks
-        const lines = content.split("\n");
-        for (const linkName of outgoingLinks) {
-          // Find the line containing this link
-          let contextLine = "";
-          let lineNum = 0;
-          for (let i = 0; i < lines.length; i++) {
-            if (lines[i].includes(`[[${linkName}`) ||
-              lines[i].toLowerCase().includes(`[[${linkName.toLowerCase()}`)) {
-              contextLine = lines[i].trim();
-              lineNum = i + 1;
-              break;
-            }
-          }
-
-          const backlink: Backlink = {
-            path: file.path,
-            name: file.name,
-            context: contextLine,
-            line: lineNum,
-          };
-
-          const normalizedLinkName = linkName.toLowerCase();
-          if (!backlinksMap.has(normalizedLinkName)) {
-            backlinksMap.set(normalizedLinkName, []);
-          }
-          backlinksMap.get(normalizedLinkName)!.push(backlink);
-        }
+        linkedNotes.push({ path: file.path, name: file.name, content });
 
         // Build tags index
         for (const tag of tags) {
@@ -178,6 +147,9 @@ export const useNoteIndexStore = create<NoteIndexState>((set, get) => ({
       }
     }
 
+    // Build backlinks (wikilinks and markdown links resolved to note paths)
+    const backlinksMap = collectBacklinks(linkedNotes, buildNoteLinkIndex(allFiles));
+
     // Convert tags map to sorted array
     const allTags: TagInfo[] = Array.from(tagsMap.entries())
       .map(([tag, info]) => ({ tag, count: info.count, files: info.files }))
@@ -197,9 +169,27 @@ export const useNoteIndexStore = create<NoteIndexState>((set, get) => ({
     }
   },
 
-  getBacklinks: (noteName: string) => {
-    const { backlinksCache } = get();
-    return backlinksCache.get(noteName.toLowerCase()) || [];
+  getBacklinks: (notePathOrName: string) => {
+    const { backlinksCache, noteIndex } = get();
+    const noteName = notePathOrName.replace(/^.*[\\/]/, "").replace(/\.md$/i, "").toLowerCase();
+    // A path looks up that note only; a bare name merges every note with that name.
+    // Unresolved [[links]] to the name are included in both cases.
+    const keys = /[\\/]/.test(notePathOrName)
+      ? [noteLinkKey(notePathOrName)]
+      : [...noteIndex.values()]
+        .filter((note) => note.name.toLowerCase() === noteName)
+        .map((note) => noteLinkKey(note.path));
+
+    const results: Backlink[] = [];
+    const seen = new Set<string>();
+    for (const key of [...keys, noteName]) {
+      for (const backlink of backlinksCache.get(key) || []) {
+        if (seen.has(backlink.path)) continue;
+        seen.add(backlink.path);
+        results.push(backlink);
+      }
+    }
+    return results;
   },
 
   getTagFiles: (tag: string) => {
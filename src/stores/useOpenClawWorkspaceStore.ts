@@ -10,6 +10,7 @@ import {
 import { listDirectory, type FileEntry } from "@/lib/tauri";
 import { useWorkspaceStore } from "@/stores/useWorkspaceStore";
 import type { OpenClawConflictState, OpenClawWorkspaceAttachment } from "@/types/openclaw";
+import { errorToString } from "@/lib/appError";
 
 export const OPENCLAW_WORKSPACE_RELEASE_ENABLED =
   (import.meta.env.VITE_ENABLE_OPENCLAW_WORKSPACE ?? "1") !== "0";
@@ -206,7 +207,7 @@ export const useOpenClawWorkspaceStore = create<OpenClawWorkspaceState>()(
           }));
           return snapshot;
         } catch (error) {
-          set({ isRefreshing: false, lastError: error instanceof Error ? error.message : String(error) });
+          set({ isRefreshing: false, lastError: error instanceof Error ? error.message : errorToString(error) });
           return null;
         }
       },
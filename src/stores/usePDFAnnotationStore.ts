@@ -20,6 +20,7 @@ import {
   generateAnnotationId,
 } from '@/services/pdf/annotations';
 import { getCurrentTranslations } from '@/stores/useLocaleStore';
+import { errorToString } from '@/lib/appError';
 
 interface PDFAnnotationState {
   // 当前加载的批注文件
@@ -104,14 +105,14 @@ export const usePDFAnnotationStore = create<PDFAnnotationState>((set, get) => ({
       set({ currentFile: file, loading: false });
     } catch (err) {
       // 文件不存在，创建空文件
-      if (String(err).includes('not found') || String(err).includes('No such file')) {
+      if (errorToString(err).includes('not found') || errorToString(err).includes('No such file')) {
         const emptyFile = createEmptyAnnotationFile(pdfPath);
         set({ currentFile: emptyFile, loading: false });
       } else {
         console.error('Failed to load annotations:', err);
         const t = getCurrentTranslations();
         set({ 
-          error: t.pdfViewer.annotation.loadFailed.replace("{error}", String(err)),
+          error: t.pdfViewer.annotation.loadFailed.replace("{error}", errorToString(err)),
           loading: false,
           currentFile: createEmptyAnnotationFile(pdfPath),
         });
@@ -150,7 +151,7 @@ export const usePDFAnnotationStore = create<PDFAnnotationState>((set, get) => ({
     } catch (err) {
       console.error('Failed to save annotations:', err);
       const t = getCurrentTranslations();
-      set({ error: t.pdfViewer.annotation.saveFailed.replace("{error}", String(err)) });
+      set({ error: t.pdfViewer.annotation.saveFailed.replace("{error}", errorToString(err)) });
     }
   },
   
@@ -10,6 +10,7 @@ import {
 import type { PluginInfo, PluginRuntimeStatus } from "@/types/plugins";
 import { pluginRuntime } from "@/services/plugins/runtime";
 import { pluginStyleRuntime } from "@/services/plugins/styleRuntime";
+import { errorToString } from "@/lib/appError";
 
 interface PluginStoreState {
   plugins: PluginInfo[];
@@ -126,7 +127,7 @@ export const usePluginStore = create<PluginStoreState>()(
         } catch (err) {
           set({
             loading: false,
-            error: err instanceof Error ? err.message : String(err),
+            error: err instanceof Error ? err.message : errorToString(err),
           });
         }
       },
@@ -4,7 +4,17 @@
 
 import { create } from "zustand";
 import { persist } from "zustand/middleware";
-import { RAGManager, RAGConfig, DEFAULT_RAG_CONFIG, IndexStatus, SearchResult } from "@/services/rag";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+import {
+  RAGManager,
+  RAGConfig,
+  DEFAULT_RAG_CONFIG,
+  IndexStatus,
+  SearchResult,
+  AutoReindexQueue,
+  type IndexProgress,
+} from "@/services/rag";
+import type { FsChangeBatchPayload } from "@/lib/fsChange";
 import { encryptApiKey, decryptApiKey } from "@/lib/crypto";
 import { useFileStore } from "./useFileStore";
 import { getCurrentTranslations } from "@/stores/useLocaleStore";
@@ -24,6 +34,11 @@ interface RAGState {
   indexStatus: IndexStatus | null;
   lastError: string | null;
 
+  // 文件变更后自动增量索引
+  autoReindex: boolean;
+  autoReindexProgress: IndexProgress | null;
+  setAutoReindex: (enabled: boolean) => Promise<void>;
+
   // 操作
   initialize: (workspacePath: string) => Promise<void>;
   rebuildIndex: () => Promise<void>;
@@ -32,6 +47,17 @@ interface RAGState {
   getStatus: () => Promise<IndexStatus | null>;
 }
 
+// 自动索引的监听与队列（不进入 store，避免被持久化）
+let autoReindexQueue: AutoReindexQueue | null = null;
+let autoReindexUnlisten: UnlistenFn | null = null;
+
+function stopAutoReindex(): void {
+  autoReindexUnlisten?.();
+  autoReindexUnlisten = null;
+  autoReindexQueue?.dispose();
+  autoReindexQueue = null;
+}
+
 export const useRAGStore = create<RAGState>()(
   persist(
     (set, get) => ({
@@ -73,6 +99,43 @@ export const useRAGStore = create<RAGState>()(
       indexStatus: null,
       lastError: null,
 
+      autoReindex: true,
+      autoReindexProgress: null,
+      setAutoReindex: async (enabled) => {
+        set({ autoReindex: enabled });
+        stopAutoReindex();
+        const { ragManager } = get();
+        if (!enabled || !ragManager?.isInitialized()) {
+          set({ autoReindexProgress: null });
+          return;
+        }
+
+        const queue = new AutoReindexQueue(ragManager, (progress) => {
+          const done = progress.current >= progress.total;
+          set({ autoReindexProgress: done ? null : progress });
+          if (done) void get().getStatus();
+        });
+        autoReindexQueue = queue;
+        try {
+          const unlisten = await listen<FsChangeBatchPayload>("fs:changes", (event) => {
+            queue.enqueue(event.payload ?? []);
+          });
+          // 等待注册期间可能已被关闭或替换
+          if (autoReindexQueue !== queue) {
+            unlisten();
+            return;
+          }
+          autoReindexUnlisten = unlisten;
+        } catch (error) {
+          reportOperationError({
+            source: "RAGStore.setAutoReindex",
+            action: "Subscribe fs:changes for auto reindex",
+            error,
+            level: "warning",
+          });
+        }
+      },
+
       // 初始化 RAG 系统
       initialize: async (workspacePath: string) => {
         const { config, ragManager: existing } = get();
@@ -93,6 +156,7 @@ export const useRAGStore = create<RAGState>()(
             ragManager, 
             isInitialized: true,
           });
+          await get().setAutoReindex(get().autoReindex);
 
           // 检查是否需要构建索引
           const status = await ragManager.getStatus();
@@ -221,6 +285,7 @@ export const useRAGStore = create<RAGState>()(
       name: "neurone-rag",
       partialize: (state) => ({
         config: state.config,
+        autoReindex: state.autoReindex,
       }),
       // 恢复数据后解密 API Keys（复用 useAIStore 模式）
       onRehydrateStorage: () => async (state) => {
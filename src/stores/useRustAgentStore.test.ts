@@ -51,6 +51,7 @@ vi.mock('@/stores/useLocaleStore', () => ({
 }));
 
 // Import after mocks
+import { invoke } from '@tauri-apps/api/core';
 import { useRustAgentStore, type Message } from './useRustAgentStore';
 
 describe('useRustAgentStore', () => {
@@ -76,6 +77,7 @@ describe('useRustAgentStore', () => {
       pendingCompaction: false,
       isCompacting: false,
       lastTokenUsage: null,
+      taskCost: null,
     });
   });
 
@@ -249,6 +251,38 @@ describe('useRustAgentStore', () => {
     });
   });
 
+  describe('task queue', () => {
+    it('cancels and reorders queued tasks by id', async () => {
+      const invokeMock = vi.mocked(invoke);
+      invokeMock.mockReset();
+      invokeMock.mockResolvedValue(undefined);
+
+      await useRustAgentStore.getState().cancelQueuedTask('task-2');
+      await useRustAgentStore.getState().moveQueuedTask('task-3', 1);
+
+      expect(invokeMock).toHaveBeenCalledWith('agent_cancel_queued', { id: 'task-2' });
+      expect(invokeMock).toHaveBeenCalledWith('agent_reorder_queue', {
+        id: 'task-3',
+        newPosition: 1,
+      });
+    });
+
+    it('resyncs the queue when the task already started running', async () => {
+      const invokeMock = vi.mocked(invoke);
+      invokeMock.mockReset();
+      invokeMock.mockImplementation(async (command) => {
+        if (command === 'agent_cancel_queued') throw new Error('Task task-1 is not queued');
+        return { running: true, active_task: 'task 1', queued: [] };
+      });
+
+      await useRustAgentStore.getState().cancelQueuedTask('task-1');
+
+      expect(invokeMock).toHaveBeenCalledWith('agent_get_queue_status');
+      expect(useRustAgentStore.getState().activeTaskPreview).toBe('task 1');
+      expect(useRustAgentStore.getState().queuedTasks).toEqual([]);
+    });
+  });
+
   describe('_handleEvent', () => {
     it('should handle status_change event', () => {
       const store = useRustAgentStore.getState();
@@ -338,6 +372,30 @@ describe('useRustAgentStore', () => {
       });
     });
 
+    it('should mark llmRetryState as rate limited on rate_limited event', () => {
+      const store = useRustAgentStore.getState();
+
+      act(() => {
+        store._handleEvent({
+          type: 'rate_limited',
+          data: {
+            request_id: 'req-456',
+            attempt: 1,
+            max_retries: 3,
+            delay_ms: 7000,
+            reason: 'HTTP 429 Too Many Requests',
+            next_retry_at: 1700000007000,
+          },
+        });
+      });
+
+      expect(useRustAgentStore.getState().llmRetryState).toMatchObject({
+        requestId: 'req-456',
+        delayMs: 7000,
+        rateLimited: true,
+      });
+    });
+
     it('should clear llmRetryState on llm_request_end', () => {
       useRustAgentStore.setState({
         llmRetryState: {
@@ -14,6 +14,10 @@ import { getAIConfig, type AIConfig } from "@/services/ai/ai";
 import { useFileStore } from "@/stores/useFileStore";
 import { useWorkspaceStore } from "@/stores/useWorkspaceStore";
 import { useAgentProfileStore } from "@/stores/useAgentProfileStore";
+import { useAIStore } from "@/stores/useAIStore";
+import { useRAGStore } from "@/stores/useRAGStore";
+import type { RAGConfig } from "@/services/rag";
+import { checkAgentEventSchema } from "@/lib/agentEventSchema";
 import {
   callLLM,
   normalizeThinkingMode,
@@ -153,12 +157,16 @@ export interface PendingToolApproval {
   requestId: string;
 }
 
+/** 排队任务优先级：高优先级先运行，同优先级按入队顺序 */
+export type TaskPriority = "low" | "normal" | "high";
+
 export interface AgentQueuedTask {
   id: string;
   task: string;
   workspace_path: string;
   enqueued_at: number;
   position: number;
+  priority?: TaskPriority;
 }
 
 export interface DebugPromptStack {
@@ -178,6 +186,8 @@ export interface LlmRetryState {
   delayMs: number;
   reason: string;
   nextRetryAt: number;
+  /** 是否因 provider 限流（429）而等待 */
+  rateLimited?: boolean;
 }
 
 export type StreamingReasoningStatus = "idle" | "streaming" | "done";
@@ -233,6 +243,33 @@ export interface Plan {
   explanation?: string;
 }
 
+export interface RagResult {
+  file_path: string;
+  content: string;
+  score: number;
+  heading?: string;
+}
+
+/** 交叉编码器重排序配置（对应 Rust RerankConfig） */
+export interface RerankConfig {
+  enabled: boolean;
+  model: string;
+  base_url: string;
+  api_key: string;
+  top_n: number;
+  max_candidates: number;
+}
+
+/** 查询向量化配置（对应 Rust EmbeddingConfig），与 RAG 索引使用同一模型 */
+export interface EmbeddingConfig {
+  enabled: boolean;
+  provider: string;
+  model: string;
+  base_url: string;
+  api_key: string;
+  dimensions?: number;
+}
+
 export interface TaskContext {
   workspace_path: string;
   active_note_path?: string;
@@ -241,8 +278,14 @@ export interface TaskContext {
   history?: Message[];  // 历史对话消息（多轮对话支持）
   skills?: SelectedSkill[];
   mobile_session_id?: string;
+  /** 持久化会话 id（.lumina/sessions/<id>.jsonl）；history 为空时后端从该会话恢复 */
+  session_id?: string;
   display_message?: string;
   attachments?: MessageAttachment[];
+  /** 与任务相关的笔记片段（向量检索候选，后端按 rerank 配置重排） */
+  rag_results?: RagResult[];
+  /** 已有任务运行时的排队优先级（不随 context 发送） */
+  priority?: TaskPriority;
 }
 
 export interface AgentConfig {
@@ -252,13 +295,55 @@ export interface AgentConfig {
   base_url?: string;
   temperature?: number;
   thinking_mode?: "auto" | "thinking" | "instant";
+  tool_call_mode?: "auto" | "native" | "xml";
+  reasoning_mode?: "surface" | "collapse" | "discard";
+  cache?: "off" | "read_write" | "read_only";
+  /** 费用预算（美元），0 表示不限制 */
+  cost_budget?: number;
+  /** 模型上下文窗口（tokens），0 表示未知 */
+  context_window?: number;
+  /** 运行中 prompt 超过上下文窗口的该比例时压缩较早的消息，0 表示不压缩 */
+  compact_threshold?: number;
+  /** 单个工具结果最多占可用上下文的比例，超出时截断，0 表示不限制 */
+  tool_output_ratio?: number;
+  /** 按工具名覆盖的输出上限（tokens），0 表示该工具不限制 */
+  tool_output_limits?: Record<string, number>;
+  /** 未选择 skills 时自动注入的数量上限，0 表示不自动选择 */
+  skill_top_k?: number;
+  /** 自动选择 skill 的最低相关度（0-1） */
+  skill_min_score?: number;
   max_tokens?: number;
   max_plan_iterations?: number;
   max_steps?: number;
   auto_approve?: boolean;
+  /** 试运行：写入类工具只返回模拟结果，不修改文件 */
+  dry_run?: boolean;
+  rerank?: RerankConfig;
+  embedding?: EmbeddingConfig;
   locale?: string;
 }
 
+/** 试运行中写入类工具的模拟变更（来自 dry_run_change 事件） */
+interface SimulatedChange {
+  tool: string;
+  path: string | null;
+  original: string | null;
+  modified: string | null;
+  description: string;
+}
+
+/** 当前任务的累计费用（来自 cost_update 事件） */
+export interface TaskCost {
+  stepCost: number;
+  totalCost: number;
+  promptTokens: number;
+  completionTokens: number;
+  estimated: boolean;
+  unpriced: boolean;
+  budget: number;
+  nearBudget: boolean;
+}
+
 // ============ Context Compaction ============
 
 const SUMMARY_MESSAGE_ID = "rust-session-summary";
@@ -538,9 +623,11 @@ interface RustAgentState {
   // 配置
   autoApprove: boolean;
   autoCompactEnabled: boolean;
+  dryRun: boolean;
   pendingCompaction: boolean;
   isCompacting: boolean;
   lastTokenUsage: { input: number; output: number; total: number } | null;
+  taskCost: TaskCost | null;
   
   // 调试模式
   debugEnabled: boolean;
@@ -567,11 +654,14 @@ interface RustAgentState {
   clearChat: () => void;
   setAutoApprove: (value: boolean) => void;
   setAutoCompactEnabled: (value: boolean) => void;
+  setDryRun: (value: boolean) => void;
   
   // 工具审批操作（新增）
   approveTool: () => Promise<void>;
   rejectTool: () => Promise<void>;
   syncQueueStatus: () => Promise<void>;
+  cancelQueuedTask: (id: string) => Promise<void>;
+  moveQueuedTask: (id: string, position: number) => Promise<void>;
   
   // 超时重试（新增）
   retryTimeout: () => Promise<void>;
@@ -619,7 +709,61 @@ const resolveVaultPath = (): string | null => {
   }
 };
 
-const buildAgentConfig = (aiConfig: AIConfig, autoApprove: boolean): AgentConfig => {
+/** 送去重排的候选上限，限制成本与延迟 */
+const RERANK_MAX_CANDIDATES = 20;
+
+/** 本地重排服务通常不需要 API Key，留空时不发送鉴权头 */
+const buildRerankConfig = (ragConfig: RAGConfig): RerankConfig => ({
+  enabled: ragConfig.rerankerEnabled,
+  model: ragConfig.rerankerModel || "BAAI/bge-reranker-v2-m3",
+  base_url: ragConfig.rerankerBaseUrl || "https://api.siliconflow.cn/v1",
+  api_key: ragConfig.rerankerApiKey || "",
+  top_n: ragConfig.rerankerTopN || 5,
+  max_candidates: RERANK_MAX_CANDIDATES,
+});
+
+/** find_note 工具的语义检索需要与索引相同的 embedding 模型 */
+const buildEmbeddingConfig = (ragConfig: RAGConfig): EmbeddingConfig => ({
+  enabled:
+    ragConfig.enabled &&
+    (ragConfig.embeddingProvider === "ollama" || !!ragConfig.embeddingApiKey),
+  provider: ragConfig.embeddingProvider,
+  model: ragConfig.embeddingModel,
+  base_url: ragConfig.embeddingBaseUrl || "",
+  api_key: ragConfig.embeddingApiKey || "",
+  dimensions: ragConfig.embeddingDimensions,
+});
+
+/**
+ * 为任务检索相关笔记。这里只取向量候选，重排序由后端完成，
+ * 启用重排时多取一些候选供其挑选
+ */
+const collectRagResults = async (task: string): Promise<RagResult[]> => {
+  const { ragManager, config } = useRAGStore.getState();
+  if (!config.enabled || !ragManager?.isInitialized()) return [];
+  try {
+    const rerank = buildRerankConfig(config);
+    const results = await ragManager.search(task, {
+      limit: rerank.enabled ? rerank.max_candidates : config.maxResults,
+      skipRerank: true,
+    });
+    return results.map((result) => ({
+      file_path: result.filePath,
+      content: result.content,
+      score: result.score,
+      heading: result.heading || undefined,
+    }));
+  } catch (e) {
+    console.warn("[RustAgent] RAG retrieval failed:", e);
+    return [];
+  }
+};
+
+const buildAgentConfig = (
+  aiConfig: AIConfig,
+  autoApprove: boolean,
+  dryRun = false,
+): AgentConfig => {
   const actualModel = aiConfig.model === "custom" && aiConfig.customModelId
     ? aiConfig.customModelId
     : aiConfig.model;
@@ -633,11 +777,20 @@ const buildAgentConfig = (aiConfig: AIConfig, autoApprove: boolean): AgentConfig
       aiConfig.temperature ??
       getRecommendedTemperature(aiConfig.provider, actualModel),
     thinking_mode: aiConfig.thinkingMode ?? "auto",
+    tool_call_mode: aiConfig.toolCallMode ?? "auto",
+    reasoning_mode: aiConfig.reasoningMode ?? "collapse",
+    cost_budget: aiConfig.costBudget ?? 0,
+    context_window: resolveModelContextWindow(aiConfig) ?? 0,
+    skill_top_k: aiConfig.skillTopK,
+    skill_min_score: aiConfig.skillMinScore,
     max_tokens: 4096,
     // 0 means unlimited
     max_plan_iterations: 0,
     max_steps: 0,
     auto_approve: autoApprove,
+    dry_run: dryRun,
+    rerank: buildRerankConfig(useRAGStore.getState().config),
+    embedding: buildEmbeddingConfig(useRAGStore.getState().config),
     locale: "zh-CN",
   };
 };
@@ -674,9 +827,11 @@ export const useRustAgentStore = create<RustAgentState>()(
       totalTokensUsed: 0,
       autoApprove: false,
       autoCompactEnabled: true,
+      dryRun: false,
       pendingCompaction: false,
       isCompacting: false,
       lastTokenUsage: null,
+      taskCost: null,
       
       // 任务统计初始状态
       taskStats: {
@@ -788,6 +943,7 @@ export const useRustAgentStore = create<RustAgentState>()(
                 }),
             totalTasks: stats.totalTasks + 1,
           },
+          ...(isBusy ? {} : { taskCost: null }),
         });
         
         // 将历史消息转换为后端格式并传入
@@ -798,7 +954,7 @@ export const useRustAgentStore = create<RustAgentState>()(
             content: m.role === "user" ? (m.rawContent || m.content) : m.content,
           }));
 
-        const config = buildAgentConfig(aiConfig, get().autoApprove);
+        const config = buildAgentConfig(aiConfig, get().autoApprove, get().dryRun);
         
         console.log("[RustAgent] 发送配置到 Rust:", {
           ...config,
@@ -815,13 +971,21 @@ export const useRustAgentStore = create<RustAgentState>()(
           const {
             display_message: _displayMessage,
             attachments: _displayAttachments,
+            priority,
             ...contextForBackend
           } = context;
           const contextWithHistory = {
             ...contextForBackend,
+            rag_results: contextForBackend.rag_results ?? (await collectRagResults(task)),
+            session_id: contextForBackend.session_id ?? get().currentSessionId ?? undefined,
             history: historyForBackend,
           };
-          await invoke("agent_start_task", { config, task, context: contextWithHistory });
+          await invoke("agent_start_task", {
+            config,
+            task,
+            context: contextWithHistory,
+            priority: priority ?? "normal",
+          });
           await get().syncQueueStatus();
         } catch (e) {
           console.error("[RustAgent] agent_start_task failed:", e);
@@ -862,6 +1026,7 @@ export const useRustAgentStore = create<RustAgentState>()(
           pendingCompaction: false,
           isCompacting: false,
           lastTokenUsage: null,
+          taskCost: null,
           queuedTasks: [],
           activeTaskPreview: null,
           debugPromptStack: null,
@@ -883,6 +1048,11 @@ export const useRustAgentStore = create<RustAgentState>()(
           pendingCompaction: value ? get().pendingCompaction : false,
         });
       },
+
+      // 设置试运行模式
+      setDryRun: (value: boolean) => {
+        set({ dryRun: value });
+      },
       
       // 审批工具调用（新增）
       approveTool: async () => {
@@ -948,6 +1118,26 @@ export const useRustAgentStore = create<RustAgentState>()(
         }
       },
       
+      // 取消排队中的任务（队列变化通过 queue_updated 事件同步）
+      cancelQueuedTask: async (id: string) => {
+        try {
+          await invoke("agent_cancel_queued", { id });
+        } catch (e) {
+          // 任务可能已开始运行，重新同步队列
+          console.warn("[RustAgent] Failed to cancel queued task:", e);
+          await get().syncQueueStatus();
+        }
+      },
+
+      moveQueuedTask: async (id: string, position: number) => {
+        try {
+          await invoke("agent_reorder_queue", { id, newPosition: position });
+        } catch (e) {
+          console.warn("[RustAgent] Failed to reorder queued task:", e);
+          await get().syncQueueStatus();
+        }
+      },
+      
       // 超时重试（新增）
       retryTimeout: async () => {
         // TODO: 实现超时重试逻辑
@@ -1010,6 +1200,7 @@ export const useRustAgentStore = create<RustAgentState>()(
           pendingCompaction: false,
           isCompacting: false,
           lastTokenUsage: null,
+          taskCost: null,
         });
         void get().syncMobileSessions();
       },
@@ -1037,6 +1228,7 @@ export const useRustAgentStore = create<RustAgentState>()(
           pendingCompaction: false,
           isCompacting: false,
           lastTokenUsage: null,
+          taskCost: null,
         });
       },
 
@@ -1059,6 +1251,7 @@ export const useRustAgentStore = create<RustAgentState>()(
               pendingCompaction: false,
               isCompacting: false,
               lastTokenUsage: null,
+              taskCost: null,
             });
           } else {
             // 没有会话了，创建一个新的
@@ -1080,6 +1273,7 @@ export const useRustAgentStore = create<RustAgentState>()(
               pendingCompaction: false,
               isCompacting: false,
               lastTokenUsage: null,
+              taskCost: null,
             });
           }
         } else {
@@ -1779,6 +1973,63 @@ export const useRustAgentStore = create<RustAgentState>()(
             break;
           }
 
+          case "dry_run_change": {
+            const { change } = event.data as { change: SimulatedChange };
+            const { messages: baseMessages, flushed } = flushStreamingToMessages();
+            set({
+              messages: [
+                ...baseMessages,
+                {
+                  role: "tool",
+                  content: `🧪 ${change.tool}: ${change.description}`,
+                },
+              ],
+              streamingContent: flushed ? "" : state.streamingContent,
+              streamingReasoning: flushed ? "" : state.streamingReasoning,
+              streamingReasoningStatus: flushed ? "idle" : state.streamingReasoningStatus,
+            });
+            // 文件变更复用 diff 预览，确认后才会写入
+            if (change.path && change.modified !== null) {
+              useAIStore.getState().setPendingDiff({
+                fileName: change.path.split(/[\\/]/).pop() || change.path,
+                filePath: change.path,
+                original: change.original ?? "",
+                modified: change.modified,
+                description: change.description,
+              });
+            }
+            break;
+          }
+
+          case "cost_update": {
+            const data = event.data as {
+              step_cost: number;
+              total_cost: number;
+              prompt_tokens: number;
+              completion_tokens: number;
+              estimated: boolean;
+              unpriced: boolean;
+              budget: number;
+              near_budget: boolean;
+            };
+            if (data.near_budget && !state.taskCost?.nearBudget) {
+              console.warn("[RustAgent] 费用接近预算:", data.total_cost, "/", data.budget);
+            }
+            set({
+              taskCost: {
+                stepCost: data.step_cost,
+                totalCost: data.total_cost,
+                promptTokens: data.prompt_tokens,
+                completionTokens: data.completion_tokens,
+                estimated: data.estimated,
+                unpriced: data.unpriced,
+                budget: data.budget,
+                nearBudget: data.near_budget,
+              },
+            });
+            break;
+          }
+
           case "complete": {
             const { result } = event.data as { result: string };
             const stats = state.taskStats;
@@ -1895,7 +2146,8 @@ export const useRustAgentStore = create<RustAgentState>()(
             break;
           }
 
-          case "llm_retry_scheduled": {
+          case "llm_retry_scheduled":
+          case "rate_limited": {
             const { request_id, attempt, max_retries, delay_ms, reason, next_retry_at } = event.data as {
               request_id: string;
               attempt: number;
@@ -1912,6 +2164,7 @@ export const useRustAgentStore = create<RustAgentState>()(
                 delayMs: delay_ms,
                 reason,
                 nextRetryAt: next_retry_at,
+                ...(event.type === "rate_limited" ? { rateLimited: true } : {}),
               },
             });
             break;
@@ -2073,6 +2326,7 @@ export const useRustAgentStore = create<RustAgentState>()(
       partialize: (state) => ({
         autoApprove: state.autoApprove,
         autoCompactEnabled: state.autoCompactEnabled,
+        dryRun: state.dryRun,
         sessions: state.sessions,
         currentSessionId: state.currentSessionId,
         // 持久化累计统计
@@ -2116,6 +2370,7 @@ export async function initRustAgentListeners() {
     unlistenFn = await useRustAgentStore.getState()._setupListeners();
     await useRustAgentStore.getState().syncQueueStatus();
     await useRustAgentStore.getState().syncMobileSessions();
+    void checkAgentEventSchema();
     console.log("[RustAgent] Listener initialized");
   } finally {
     isInitializing = false;
@@ -14,6 +14,7 @@ import {
   testWebDAVConnection,
   webdavService,
 } from '@/services/webdav';
+import { errorToString } from '@/lib/appError';
 
 interface WebDAVState {
   // 配置
@@ -121,7 +122,7 @@ export const useWebDAVStore = create<WebDAVState>()(
           });
           return success;
         } catch (error) {
-          const message = error instanceof Error ? error.message : String(error);
+          const message = error instanceof Error ? error.message : errorToString(error);
           set({
             isConnected: false,
             connectionError: message,
@@ -161,7 +162,7 @@ export const useWebDAVStore = create<WebDAVState>()(
           
           return plan;
         } catch (error) {
-          const message = error instanceof Error ? error.message : String(error);
+          const message = error instanceof Error ? error.message : errorToString(error);
           set({
             syncProgress: { ...get().syncProgress, stage: 'Error', error: message },
             connectionError: message,
@@ -214,7 +215,7 @@ export const useWebDAVStore = create<WebDAVState>()(
           
           return result;
         } catch (error) {
-          const message = error instanceof Error ? error.message : String(error);
+          const message = error instanceof Error ? error.message : errorToString(error);
           set({
             syncProgress: { ...get().syncProgress, stage: 'Error', error: message },
             connectionError: message,
@@ -260,7 +261,7 @@ export const useWebDAVStore = create<WebDAVState>()(
           
           return result;
         } catch (error) {
-          const message = error instanceof Error ? error.message : String(error);
+          const message = error instanceof Error ? error.message : errorToString(error);
           set({
             syncProgress: { ...get().syncProgress, stage: 'Error', error: message },
             connectionError: message,
@@ -37,6 +37,7 @@ export interface DatabaseColumn {
   name: string;
   type: ColumnType;
   width?: number; // 列宽度，可选
+  required?: boolean; // 必填列，validate_database 会报告缺失的值
   
   // Select/Multi-select 选项
   options?: SelectOption[];
@@ -5,6 +5,12 @@ export interface SkillInfo {
   version?: string;
   tags?: string[];
   source?: string;
+  /** Keywords in the task that auto-activate the skill */
+  triggers?: string[];
+  /** Workspace-relative globs matched against the active note */
+  globs?: string[];
+  /** Metadata validation errors; absent when the skill is well-formed */
+  errors?: string[];
 }
 
 export interface SkillDetail {
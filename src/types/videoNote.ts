@@ -38,6 +38,16 @@ export function extractBvid(url: string): string | null {
   return match ? match[0] : null;
 }
 
+/**
+ * 从 B站链接提取分P序号（`?p=2`），未指定时返回 undefined
+ */
+export function extractPage(url: string): number | undefined {
+  const match = url.match(/[?&]p=(\d+)/);
+  if (!match) return undefined;
+  const page = Number(match[1]);
+  return page >= 1 ? page : undefined;
+}
+
 /**
  * 生成 B站嵌入播放器 URL
  */
@@ -71,13 +81,25 @@ export interface DanmakuItem {
   timestamp: number; // 发送时间戳
 }
 
+/**
+ * 视频分P信息
+ */
+export interface BilibiliPage {
+  page: number;      // 分P序号（从 1 开始）
+  cid: number;
+  part: string;      // 分P标题
+  duration: number;  // 时长（秒）
+}
+
 /**
  * 获取视频 CID（通过 Rust 后端）
+ *
+ * page 为分P序号（从 1 开始），默认第 1 P
  */
-export async function getVideoCid(bvid: string): Promise<number | null> {
+export async function getVideoCid(bvid: string, page?: number): Promise<number | null> {
   try {
     const { invoke } = await import('@tauri-apps/api/core');
-    const cid = await invoke<number | null>('get_bilibili_cid', { bvid });
+    const cid = await invoke<number | null>('get_bilibili_cid', { bvid, page });
     return cid;
   } catch (error) {
     console.error('[Danmaku] Failed to get cid:', error);
@@ -85,6 +107,50 @@ export async function getVideoCid(bvid: string): Promise<number | null> {
   }
 }
 
+/**
+ * 截取的视频帧
+ */
+export interface VideoFrame {
+  pngBase64: string;   // PNG 图片 base64（不含 data: 前缀）
+  width: number;
+  height: number;
+  currentTime: number; // 截图时的播放时间（秒）
+  label: string;       // 如 "frame @ 12:34"
+}
+
+/**
+ * 截取内嵌视频当前帧（通过 Rust 后端）
+ *
+ * 跨域视频无法读取画面，会抛出错误
+ */
+export async function captureVideoFrame(maxWidth?: number): Promise<VideoFrame> {
+  const { invoke } = await import('@tauri-apps/api/core');
+  const frame = await invoke<{ png_base64: string; width: number; height: number; current_time: number }>(
+    'capture_video_frame',
+    { maxWidth },
+  );
+  return {
+    pngBase64: frame.png_base64,
+    width: frame.width,
+    height: frame.height,
+    currentTime: frame.current_time,
+    label: `frame @ ${formatTimestamp(frame.current_time)}`,
+  };
+}
+
+/**
+ * 获取视频的全部分P（通过 Rust 后端）
+ */
+export async function getVideoPages(bvid: string): Promise<BilibiliPage[]> {
+  try {
+    const { invoke } = await import('@tauri-apps/api/core');
+    return await invoke<BilibiliPage[]>('get_bilibili_pages', { bvid });
+  } catch (error) {
+    console.error('[Danmaku] Failed to get pages:', error);
+    return [];
+  }
+}
+
 /**
  * 获取视频弹幕列表（通过 Rust 后端）
  */